@@ -0,0 +1,67 @@
+//! Benchmarks for copying large collected files.
+//!
+//! Compares `std::fs::copy`, which the collectors used before, with the
+//! buffered, preallocating copy in `fast_copy`. The source is 1 GB by default;
+//! set `COPY_BENCH_SIZE_MB` to use a different size.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_collector::collectors::platforms::fast_copy::copy_file_with_progress;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Write a file of `size_mb` MiB with non-repeating block contents
+fn create_source(path: &Path, size_mb: usize) {
+    let mut file = File::create(path).unwrap();
+    let mut block: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    for i in 0..size_mb {
+        block[0] = i as u8;
+        file.write_all(&block).unwrap();
+    }
+    file.sync_all().unwrap();
+}
+
+/// Benchmark old (`fs::copy`) versus new copy path on a large file
+fn bench_large_file_copy(c: &mut Criterion) {
+    let size_mb: usize = std::env::var("COPY_BENCH_SIZE_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024);
+
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("large.evtx");
+    let dest = temp_dir.path().join("large_copy.evtx");
+    create_source(&source, size_mb);
+
+    let mut group = c.benchmark_group("large_file_copy");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes((size_mb * 1024 * 1024) as u64));
+
+    group.bench_function("std_fs_copy", |b| {
+        b.iter(|| fs::copy(black_box(&source), black_box(&dest)).unwrap());
+    });
+
+    for buffer_kb in [64, 1024, 8192] {
+        group.bench_with_input(
+            BenchmarkId::new("fast_copy", format!("{}KB", buffer_kb)),
+            &buffer_kb,
+            |b, &buffer_kb| {
+                b.iter(|| {
+                    copy_file_with_progress(
+                        black_box(&source),
+                        black_box(&dest),
+                        buffer_kb * 1024,
+                        &mut |_, _| {},
+                    )
+                    .unwrap()
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_large_file_copy);
+criterion_main!(benches);
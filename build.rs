@@ -1,10 +1,19 @@
 use std::env;
 use std::fs;
 use std::path::Path;
+use std::process::Command;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=config/");
     println!("cargo:rerun-if-env-changed=RS_COLLECTOR_CONFIG");
+    if Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+    }
+
+    // Record the commit for the collection summary, when building from git
+    if let Some(git_hash) = git_commit_hash() {
+        println!("cargo:rustc-env=RS_COLLECTOR_GIT_HASH={git_hash}");
+    }
 
     // Only run config embedding when feature is enabled
     if env::var("CARGO_FEATURE_EMBED_CONFIG").is_ok() {
@@ -14,6 +23,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn git_commit_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!hash.is_empty()).then_some(hash)
+}
+
 fn embed_appropriate_config() -> Result<(), Box<dyn std::error::Error>> {
     // Determine target OS - use CARGO_CFG_TARGET_OS for cross-compilation
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_else(|_| env::consts::OS.to_string());
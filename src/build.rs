@@ -3,21 +3,52 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{anyhow, Context, Result};
-use log::{info, warn};
+use log::{error, info, warn};
+
+use crate::config::validator::validate_config;
+use crate::config::CollectionConfig;
+
+/// Check that a config will load in the binary it is embedded in
+///
+/// The config goes through the same migration and deserialization as the
+/// embedded config does at runtime, then through [`validate_config`].
+pub fn verify_embedded_config(config_path: &Path) -> Result<()> {
+    let config = CollectionConfig::from_yaml_file(config_path)?;
+    validate_config(&config)?;
+    info!(
+        "Verified embedded config {} ({} artifacts)",
+        config_path.display(),
+        config.artifacts.len()
+    );
+    Ok(())
+}
 
 /// Build a binary with embedded configuration
 ///
 /// This function handles the entire build process:
-/// 1. Determines the target OS and architecture
-/// 2. Copies the config file to the appropriate location
-/// 3. Builds the binary with embedded configuration
-/// 4. Copies the binary to the specified output location
+/// 1. Verifies the config with [`verify_embedded_config`]
+/// 2. Determines the target OS and architecture
+/// 3. Copies the config file to the appropriate location
+/// 4. Builds the binary with embedded configuration
+/// 5. Copies the binary to the specified output location
 pub fn build_binary_with_config(
     config_path: &Path,
     output_path: Option<&Path>,
     binary_name: Option<&str>,
     target_os: Option<&str>,
 ) -> Result<PathBuf> {
+    // A config that does not load would only fail once the binary runs
+    if let Err(e) = verify_embedded_config(config_path) {
+        error!(
+            "Refusing to build: {} is not a valid embedded config",
+            config_path.display()
+        );
+        return Err(e.context(format!(
+            "Embedded config {} failed verification",
+            config_path.display()
+        )));
+    }
+
     // Determine target OS and triple
     let target_os_normalized = match target_os.map(|s| s.to_lowercase()).as_deref() {
         Some("windows") | Some("win") => "windows".to_string(),
@@ -155,3 +186,58 @@ pub fn execute_build_script(script_path: &Path) -> Result<()> {
     // Just return success since the binary should already be built
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_embedded_config() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let valid = dir.path().join("valid.yaml");
+        fs::write(
+            &valid,
+            r#"version: "1.1"
+description: "Linux triage"
+artifacts:
+  - name: "syslog"
+    artifact_type: Logs
+    source_path: "/var/log/syslog"
+    destination_name: "syslog"
+    required: true
+"#,
+        )
+        .unwrap();
+        assert!(verify_embedded_config(&valid).is_ok());
+
+        let malformed = dir.path().join("malformed.yaml");
+        fs::write(&malformed, "version: \"1.1\"\nartifacts: [unclosed").unwrap();
+        assert!(verify_embedded_config(&malformed).is_err());
+
+        let invalid = dir.path().join("invalid.yaml");
+        fs::write(
+            &invalid,
+            "version: \"1.1\"\ndescription: empty\nartifacts: []\n",
+        )
+        .unwrap();
+        let err = verify_embedded_config(&invalid).unwrap_err();
+        assert!(err.to_string().contains("no artifacts are defined"));
+    }
+
+    #[test]
+    fn test_build_refuses_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let invalid = dir.path().join("invalid.yaml");
+        fs::write(
+            &invalid,
+            "version: \"1.1\"\ndescription: empty\nartifacts: []\n",
+        )
+        .unwrap();
+
+        // Fails before any config is copied or cargo is run
+        let err =
+            build_binary_with_config(&invalid, Some(dir.path()), None, Some("linux")).unwrap_err();
+        assert!(format!("{:#}", err).contains("failed verification"));
+    }
+}
@@ -1,5 +1,9 @@
 use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::collectors::memory::minidump_export::MiniDumpType;
+use crate::utils::deadline::parse_duration;
 
 /// Command-line arguments for the rust-dfir-triage tool.
 ///
@@ -69,6 +73,10 @@ pub struct Args {
     #[clap(short = 'c', long)]
     pub config: Option<PathBuf>,
 
+    /// Print a configuration template to stdout and exit
+    #[clap(long, value_name = "NAME")]
+    pub config_template: Option<ConfigTemplate>,
+
     /// Override default artifact types to collect (comma-separated)
     #[clap(short = 't', long)]
     pub artifact_types: Option<String>,
@@ -81,6 +89,72 @@ pub struct Args {
     #[clap(long)]
     pub force: bool,
 
+    /// Check a signed update manifest and exit
+    #[clap(
+        long,
+        value_name = "MANIFEST_URL",
+        help = "Fetch the signed update manifest at this URL, report whether this version is current, and exit without collecting or installing anything"
+    )]
+    pub check_update: Option<String>,
+
+    /// Re-launch with elevated privileges when not elevated
+    #[clap(
+        long,
+        help = "When not elevated and --force is not given, re-launch with the same arguments through sudo (Linux, macOS) or the UAC prompt (Windows) instead of exiting"
+    )]
+    pub auto_elevate: bool,
+
+    /// Proxy for all outbound connections
+    #[clap(
+        long,
+        value_name = "URL",
+        help = "Send S3, SFTP, SIEM export and update traffic through this proxy (http://, https:// or socks5://host:port); SFTP needs an http:// or socks5:// proxy"
+    )]
+    pub proxy_url: Option<String>,
+
+    /// Environment variable with the proxy credentials
+    #[clap(
+        long,
+        value_name = "VAR",
+        requires = "proxy_url",
+        help = "Read the proxy user:password from this environment variable instead of the URL"
+    )]
+    pub proxy_auth_env: Option<String>,
+
+    /// Hosts that bypass the proxy
+    #[clap(
+        long,
+        value_name = "HOSTS",
+        requires = "proxy_url",
+        help = "Comma-separated hosts and domain suffixes reached without the proxy (default: NO_PROXY)"
+    )]
+    pub no_proxy: Option<String>,
+
+    /// Collect from read-only filesystem snapshots (Linux)
+    #[clap(
+        long,
+        help = "Snapshot each LVM, btrfs or ZFS filesystem holding artifacts and collect from the snapshot, removing it afterwards; other filesystems are collected live (Linux)"
+    )]
+    pub use_snapshots: bool,
+
+    /// Size of LVM snapshots
+    #[clap(
+        long,
+        value_name = "SIZE",
+        requires = "use_snapshots",
+        help = "Space reserved for each LVM snapshot, as an lvcreate size (2G) or extents (20%ORIGIN) [default: 1G]"
+    )]
+    pub snapshot_size: Option<String>,
+
+    /// Mount points collected live even with --use-snapshots
+    #[clap(
+        long,
+        value_name = "MOUNT_POINT",
+        requires = "use_snapshots",
+        help = "Collect the filesystem mounted here live instead of from a snapshot (repeatable)"
+    )]
+    pub no_snapshot: Vec<PathBuf>,
+
     /// Stream artifacts directly to cloud storage (S3 or SFTP) without local storage
     #[clap(
         long,
@@ -100,6 +174,13 @@ pub struct Args {
     #[clap(long, help = "Skip volatile data collection")]
     pub no_volatile_data: bool,
 
+    /// Skip adding artifacts for the server roles detected on the host
+    #[clap(
+        long,
+        help = "Do not add catalog artifacts for detected server roles (IIS, SQL Server, Jenkins, ...)"
+    )]
+    pub no_dynamic_artifacts: bool,
+
     /// Dump process memory for forensic analysis
     #[clap(long, help = "Dump process memory for forensic analysis")]
     pub dump_process_memory: bool,
@@ -138,6 +219,23 @@ pub struct Args {
     )]
     pub memory_regions: String,
 
+    /// Also write each dumped process as a minidump (.dmp) of this type
+    #[clap(
+        long,
+        value_enum,
+        requires = "dump_process_memory",
+        help = "Also write each dumped process as a minidump (.dmp) of this type"
+    )]
+    pub minidump_type: Option<MiniDumpType>,
+
+    /// Write thread, fd and mapping context of the selected processes without dumping memory (Linux)
+    #[clap(
+        long,
+        conflicts_with = "dump_process_memory",
+        help = "Write thread, fd and mapping context (context.json) of the selected processes without dumping memory (Linux)"
+    )]
+    pub process_context_only: bool,
+
     /// Search for a pattern in process memory (hex format, e.g. "4D5A90")
     #[clap(
         long,
@@ -152,13 +250,300 @@ pub struct Args {
     )]
     pub memory_yara: Option<String>,
 
-    /// Dump specific memory region (format: pid:address:size, e.g. "1234:0x400000:4096")
+    /// Dump specific memory region (format: pid:start-end in hex, e.g. "1234:0x400000-0x401000")
     #[clap(
         long,
-        help = "Dump specific memory region (format: pid:address:size, e.g. \"1234:0x400000:4096\")"
+        alias = "memory-region-dump",
+        help = "Dump specific memory region (format: pid:start-end in hex, e.g. \"1234:0x400000-0x401000\")"
     )]
     pub dump_memory_region: Option<String>,
 
+    /// List executable memory regions with no backing file (injected code)
+    #[clap(
+        long,
+        help = "List executable memory regions with no backing file, a sign of injected code"
+    )]
+    pub detect_injected_code: bool,
+
+    /// Hash and check the signature of the binary of every running process
+    #[clap(
+        long,
+        help = "Hash every distinct running process binary and check its code signature (volatile/process_binaries.json)"
+    )]
+    pub hash_process_binaries: bool,
+
+    /// Dump LSASS memory for offline credential analysis (Windows, memory_collection builds)
+    #[clap(
+        long,
+        requires = "force",
+        help = "Dump LSASS memory for offline credential analysis (Windows builds with memory_collection); the dump holds credentials, so --force is required"
+    )]
+    pub dump_lsass: bool,
+
+    /// Copy raw cloud credential files as well as inventorying them
+    #[clap(
+        long,
+        help = "Copy raw AWS, Azure, gcloud, kubeconfig and Docker credential files into the output instead of only a scrubbed inventory; recorded in the audit trail"
+    )]
+    pub collect_credential_files: bool,
+
+    /// Never read locked files from a Volume Shadow Copy
+    #[clap(
+        long,
+        help = "Do not create a Volume Shadow Copy to read files locked by other processes (Windows); use where VSS activity raises security alerts"
+    )]
+    pub disable_vss: bool,
+
+    /// Map a remote path prefix to a local mount point (repeatable, format: remote=local)
+    #[clap(
+        long = "network-mount-point",
+        value_name = "REMOTE=LOCAL",
+        help = "Map a remote path prefix to a local mount point (repeatable, format: remote=local)"
+    )]
+    pub network_mount_point: Vec<String>,
+
+    /// Collect in RFC 3227 order of volatility (volatile snapshot, process memory, files, bodyfile)
+    #[clap(
+        long,
+        help = "Collect in order of volatility: volatile snapshot, process memory, files, bodyfile"
+    )]
+    pub volatility_order: bool,
+
+    /// Memory cap for buffering the volatile snapshot before it is flushed (in MB)
+    #[clap(
+        long,
+        default_value = "64",
+        help = "Memory cap for buffering the volatile snapshot before it is flushed (in MB)"
+    )]
+    pub volatility_buffer_mb: usize,
+
+    /// Cap the collector's CPU use at this percent of all cores
+    #[clap(
+        long,
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u32).range(1..=100),
+        help = "Cap the collector's CPU use at this percent of total CPU capacity (cgroup, Job Object or lowered priority, plus internal throttling)"
+    )]
+    pub max_cpu_percent: Option<u32>,
+
+    /// Cap the collector's memory use (in MB)
+    #[clap(
+        long,
+        value_name = "MB",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Cap the collector's memory in MB; near the cap it reduces concurrency and flushes buffers"
+    )]
+    pub max_memory_mb: Option<u64>,
+
+    /// Finish the whole run, compression and upload included, within this time
+    #[clap(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Finish the whole run, compression and upload included, within this time (e.g. 30m, 1h30m); artifacts that would not fit are skipped"
+    )]
+    pub max_runtime: Option<Duration>,
+
+    /// Time kept back from --max-runtime on top of the compression and upload estimate
+    #[clap(
+        long,
+        value_name = "DURATION",
+        default_value = "60s",
+        value_parser = parse_duration,
+        requires = "max_runtime",
+        help = "Time kept back from --max-runtime on top of the compression and upload estimate"
+    )]
+    pub max_runtime_margin: Duration,
+
+    /// Write local performance metrics to metrics.json (nothing is transmitted)
+    #[clap(
+        long,
+        help = "Write local performance metrics (phase, artifact, compression and upload timings) to metrics.json"
+    )]
+    pub metrics: bool,
+
+    /// Also rewrite metrics.json every N seconds while collecting
+    #[clap(
+        long,
+        value_name = "SECONDS",
+        requires = "metrics",
+        help = "Also rewrite metrics.json every N seconds while collecting"
+    )]
+    pub metrics_interval: Option<u64>,
+
+    /// NTP server to probe for documenting host clock skew (e.g. pool.ntp.org)
+    #[clap(
+        long,
+        value_name = "SERVER",
+        help = "NTP server to probe for documenting host clock skew (e.g. pool.ntp.org)"
+    )]
+    pub ntp_check: Option<String>,
+
+    /// Directory for scratch space and the archive (defaults to the system temp directory)
+    #[clap(
+        long,
+        help = "Directory for scratch space and the archive, e.g. on the output volume (defaults to the system temp directory)"
+    )]
+    pub temp_dir: Option<String>,
+
+    /// Stage the collection in `.inprogress-<id>` and rename it once complete
+    #[clap(
+        long,
+        help = "Write the collection to <output>/.inprogress-<id>/ and rename it to its final name only once the summary and archive are complete; interrupted runs are quarantined as .failed-<id>"
+    )]
+    pub atomic_output: bool,
+
+    /// Write a SHA-256 manifest of the output before it is finalized
+    #[clap(
+        long,
+        requires = "atomic_output",
+        help = "With --atomic-output, write output_manifest.sha256 covering every output file before the rename"
+    )]
+    pub output_manifest: bool,
+
+    /// Keep running after collection and re-collect artifacts whose source paths change
+    #[clap(
+        long,
+        help = "After collection, watch source paths and re-collect changed artifacts until killed"
+    )]
+    pub watch: bool,
+
+    /// NSRL RDS SQLite database used to flag known-good collected files
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "NSRL RDS SQLite database; collected files are hashed and flagged as known-good or for review"
+    )]
+    pub nsrl_db: Option<String>,
+
+    /// SSDEEP-hash collected executables (fuzzy_hash feature)
+    #[clap(
+        long,
+        help = "Record an SSDEEP fuzzy hash for collected .exe, .dll, .sys, .so and .dylib files; needs the fuzzy_hash feature"
+    )]
+    pub fuzzy_hash: bool,
+
+    /// Re-read and re-hash this percentage of collected files after collection
+    #[clap(
+        long,
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u32).range(1..=100),
+        conflicts_with = "stream",
+        help = "After collection, re-read PERCENT of the collected files from their sources, compare hashes and write verification_report.json; mismatched copies of unchanged sources are collected again"
+    )]
+    pub verify_sample: Option<u32>,
+
+    /// Seed choosing the --verify-sample files
+    #[clap(
+        long,
+        value_name = "SEED",
+        requires = "verify_sample",
+        help = "Seed choosing the --verify-sample files, to repeat a sample (default: taken from the clock and recorded in the report)"
+    )]
+    pub verify_seed: Option<u64>,
+
+    /// Record each source's timestamps and size before and after reading it
+    #[clap(
+        long,
+        help = "Record the modification time, change time and size of each source before and after it is read and write no_writes_report.json listing any that changed"
+    )]
+    pub verify_no_writes: bool,
+
+    /// Record the extended attributes of collected files
+    #[clap(
+        long,
+        help = "Write the extended attributes of each file collected from the live system (quarantine and provenance data on macOS, SELinux labels and capabilities on Linux) to a <filename>.xattr.json sidecar next to the copy"
+    )]
+    pub collect_xattrs: bool,
+
+    /// Compute the entropy of collected files
+    #[clap(
+        long,
+        help = "Compute the Shannon entropy of each collected file and list those above entropy_threshold (default 7.2) in high_entropy_files.json; reads every collected byte again"
+    )]
+    pub compute_entropy: bool,
+
+    /// Skip files unchanged since an earlier collection
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Compare file artifacts with the file_manifest.json or collection_summary.json of an earlier collection and skip files whose size, modification time and hash are unchanged; volatile data and memory are always collected"
+    )]
+    pub baseline: Option<PathBuf>,
+
+    /// Collect every file even when --baseline is given
+    #[clap(long, help = "Ignore --baseline and collect every file")]
+    pub force_full: bool,
+
+    /// Archive from an earlier run to update instead of recompressing
+    #[clap(
+        long,
+        value_name = "ZIP",
+        help = "Build the archive from the ZIP of an earlier run, copying entries for unchanged files without recompressing them and adding only new or modified files"
+    )]
+    pub previous_archive: Option<PathBuf>,
+
+    /// Schema of collection_summary.json
+    #[clap(
+        long,
+        value_enum,
+        default_value = "v2",
+        help = "Schema of collection_summary.json; v1 omits host, config hash and rollup fields"
+    )]
+    pub summary_format: SummaryFormat,
+
+    /// Run the `check` environment checks before collecting and stop if a required check fails
+    #[clap(
+        long,
+        help = "Run the environment checks from the check subcommand first and stop if a required check fails"
+    )]
+    pub preflight: bool,
+
+    /// Snapshot processes, listeners, sessions and key directories before and after collection
+    #[clap(
+        long,
+        help = "Compare system state before and after collection and write state_diff.json"
+    )]
+    pub state_diff: bool,
+
+    /// Hours of macOS Unified Log exported to unified_logs.jsonl
+    #[clap(
+        long,
+        value_name = "HOURS",
+        default_value = "24",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Hours of macOS Unified Log to export to unified_logs.jsonl with log show"
+    )]
+    pub unified_log_hours: u64,
+
+    /// Check S3 and SFTP access without writing and deleting a probe object
+    #[clap(
+        long,
+        global = true,
+        help = "Check S3 and SFTP access without writing and deleting a 1-byte probe"
+    )]
+    pub no_write_probe: bool,
+
+    /// Serve the HTTP control API instead of collecting once (control_api feature)
+    #[clap(
+        long,
+        value_name = "ADDR",
+        help = "Serve the HTTP control API on ADDR (e.g. 127.0.0.1:8443) and run collections on request; needs the control_api feature"
+    )]
+    pub serve: Option<String>,
+
+    /// File holding the bearer token control API clients must send
+    #[clap(long, value_name = "PATH", requires = "serve")]
+    pub serve_token_file: Option<PathBuf>,
+
+    /// PEM certificate chain for serving the control API over TLS
+    #[clap(long, value_name = "PATH", requires_all = ["serve", "serve_tls_key"])]
+    pub serve_tls_cert: Option<PathBuf>,
+
+    /// PEM private key for serving the control API over TLS
+    #[clap(long, value_name = "PATH", requires_all = ["serve", "serve_tls_cert"])]
+    pub serve_tls_key: Option<PathBuf>,
+
     /// Subcommands
     #[clap(subcommand)]
     pub command: Option<Commands>,
@@ -188,6 +573,56 @@ impl std::fmt::Display for TargetOS {
     }
 }
 
+/// Configuration templates printed by `--config-template`.
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+pub enum ConfigTemplate {
+    /// Windows triage artifacts
+    Windows,
+    /// Linux triage artifacts
+    Linux,
+    /// macOS triage artifacts
+    Macos,
+    /// Docker, containerd and Kubernetes node artifacts
+    Container,
+    /// Hostname and system logs only
+    Minimal,
+}
+
+impl std::fmt::Display for ConfigTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigTemplate::Windows => write!(f, "windows"),
+            ConfigTemplate::Linux => write!(f, "linux"),
+            ConfigTemplate::Macos => write!(f, "macos"),
+            ConfigTemplate::Container => write!(f, "container"),
+            ConfigTemplate::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+/// Collection summary schema.
+///
+/// v2 adds host information, build and config hashes, per-type rollups,
+/// phase timings and upload destinations on top of the v1 fields.
+#[derive(Clone, Copy, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// Original layout without `summary_version`
+    V1,
+    /// Versioned layout (`summary_version: 2`)
+    #[default]
+    V2,
+}
+
+/// Document format of the `export` subcommand
+#[derive(Clone, Copy, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum SiemFormat {
+    /// OpenSearch/Elasticsearch `_bulk` action and document lines
+    #[default]
+    Opensearch,
+    /// Splunk HTTP Event Collector events
+    Splunk,
+}
+
 /// Available subcommands for the collector.
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -205,6 +640,158 @@ pub enum Commands {
     /// Build a standalone binary with embedded configuration
     #[clap(name = "build")]
     Build(BuildOpts),
+
+    /// Validate privileges, output directory, upload targets and tools without collecting
+    Check {
+        /// Print the results as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Compare two collection summaries (baseline, then incident)
+    Diff {
+        /// collection_summary.json from the baseline collection
+        baseline: PathBuf,
+
+        /// collection_summary.json from the later collection
+        incident: PathBuf,
+
+        /// Print the differences as JSON instead of one line per change
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Print a presigned S3 URL for one uploaded object (uses --bucket, --region and --profile)
+    #[clap(name = "generate-url")]
+    GenerateUrl {
+        /// Object key, including any --prefix used for the upload
+        #[clap(long)]
+        key: String,
+
+        /// Hours until the URL expires (at most 168)
+        #[clap(long, default_value = "24")]
+        expiry_hours: u64,
+    },
+
+    /// Install the release listed in a signed update manifest over this executable
+    SelfUpdate {
+        /// URL of the signed JSON manifest
+        manifest_url: String,
+
+        /// Install the manifest's version even when it is older than this one
+        #[clap(long)]
+        allow_downgrade: bool,
+    },
+
+    /// Download one object of an earlier upload from S3
+    Download {
+        /// S3 bucket holding the object
+        #[clap(long)]
+        bucket: String,
+
+        /// Object key, including any --prefix used for the upload
+        #[clap(long)]
+        key: String,
+
+        /// File to write, or an existing directory to write it into under the key's file name
+        #[clap(long)]
+        output_path: PathBuf,
+
+        /// AWS region of the bucket
+        #[clap(long)]
+        region: Option<String>,
+
+        /// AWS profile to use for credentials
+        #[clap(long)]
+        profile: Option<String>,
+    },
+
+    /// Deploy the collector to a remote Windows host over SMB, run it and retrieve the archive (remote_windows feature)
+    #[clap(name = "remote-windows")]
+    RemoteWindows(RemoteWindowsOpts),
+
+    /// Export a finished collection as OpenSearch bulk or Splunk HEC documents
+    Export(ExportOpts),
+}
+
+/// Options for the export subcommand.
+///
+/// The API key or HEC token is read from `--token-file`, never from
+/// arguments.
+#[derive(ClapArgs, Debug)]
+pub struct ExportOpts {
+    /// Collection directory holding collection_summary.json (<output>/<hostname>)
+    pub collection_dir: PathBuf,
+
+    /// Document format
+    #[clap(long, value_enum, default_value_t)]
+    pub format: SiemFormat,
+
+    /// Directory for the .ndjson files (default: <collection_dir>/siem_export)
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// OpenSearch/Elasticsearch URL or Splunk HEC URL to POST the documents to instead of writing files
+    #[clap(long)]
+    pub url: Option<String>,
+
+    /// File holding the API key or HEC token sent with --url
+    #[clap(long)]
+    pub token_file: Option<PathBuf>,
+
+    /// Authorization scheme sent before the token (default: ApiKey for OpenSearch, Splunk for Splunk)
+    #[clap(long)]
+    pub auth_scheme: Option<String>,
+
+    /// Index name template; {host}, {type}, {date} and {collection_id} are replaced
+    #[clap(long)]
+    pub index: Option<String>,
+
+    /// Splunk sourcetype template, with the same placeholders as --index
+    #[clap(long, default_value = crate::utils::siem_export::DEFAULT_SOURCETYPE_TEMPLATE)]
+    pub sourcetype: String,
+
+    /// Documents per request with --url
+    #[clap(long, default_value = "500")]
+    pub batch_size: usize,
+
+    /// Retries of a request that failed to connect or got a 429 or 5xx response
+    #[clap(long, default_value = "3")]
+    pub retries: u32,
+}
+
+/// Options for the remote-windows subcommand.
+///
+/// Credentials are read from `RS_COLLECTOR_REMOTE_USER` and
+/// `RS_COLLECTOR_REMOTE_PASSWORD` (or a prompt), never from arguments.
+#[derive(ClapArgs, Debug)]
+pub struct RemoteWindowsOpts {
+    /// Remote host name or address
+    pub host: String,
+
+    /// Collector binary to deploy (default: this executable)
+    #[clap(long)]
+    pub binary: Option<PathBuf>,
+
+    /// Configuration file to deploy with the collector
+    #[clap(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Local directory for the retrieved archive and collector log
+    #[clap(short, long, default_value = ".")]
+    pub output: PathBuf,
+
+    /// Seconds between checks for the remote collection finishing
+    #[clap(long, default_value = "10")]
+    pub poll_seconds: u64,
+
+    /// Minutes to wait for the remote collection before giving up
+    #[clap(long, default_value = "120")]
+    pub timeout_minutes: u64,
+
+    /// Arguments passed to the remote collector (after --)
+    #[clap(last = true)]
+    pub collector_args: Vec<String>,
 }
 
 /// Options for the build subcommand.
@@ -312,10 +899,308 @@ mod tests {
         assert_eq!(args.memory_regions, "all");
         assert!(!args.verbose);
         assert!(!args.force);
+        assert!(!args.auto_elevate);
+        assert!(args.check_update.is_none());
+        assert!(!args.use_snapshots);
+        assert!(args.snapshot_size.is_none());
+        assert!(args.no_snapshot.is_empty());
+        assert!(args.proxy_url.is_none());
+        assert!(args.proxy_auth_env.is_none());
+        assert!(args.no_proxy.is_none());
         assert!(!args.stream);
         assert!(!args.no_volatile_data);
+        assert!(!args.no_dynamic_artifacts);
+        assert!(!args.verify_no_writes);
+        assert!(!args.collect_xattrs);
+        assert!(!args.compute_entropy);
+        assert!(args.baseline.is_none());
+        assert!(!args.force_full);
+        assert!(args.previous_archive.is_none());
         assert!(!args.dump_process_memory);
+        assert!(!args.detect_injected_code);
         assert!(!args.include_system_processes);
+        assert!(!args.volatility_order);
+        assert_eq!(args.volatility_buffer_mb, 64);
+        assert!(!args.metrics);
+        assert_eq!(args.metrics_interval, None);
+        assert!(!args.watch);
+        assert!(!args.state_diff);
+        assert_eq!(args.unified_log_hours, 24);
+    }
+
+    #[test]
+    fn test_watch_with_stream() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--watch",
+            "--stream",
+            "--bucket",
+            "watch-bucket",
+        ]);
+
+        assert!(args.watch);
+        assert!(args.stream);
+    }
+
+    #[test]
+    fn test_unified_log_hours_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--unified-log-hours", "72"]);
+        assert_eq!(args.unified_log_hours, 72);
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--unified-log-hours", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_ntp_check_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--ntp-check", "pool.ntp.org"]);
+        assert_eq!(args.ntp_check, Some("pool.ntp.org".to_string()));
+    }
+
+    #[test]
+    fn test_nsrl_db_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--nsrl-db", "/data/RDS_modern.db"]);
+        assert_eq!(args.nsrl_db, Some("/data/RDS_modern.db".to_string()));
+    }
+
+    #[test]
+    fn test_fuzzy_hash_arg() {
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).fuzzy_hash);
+        assert!(Args::parse_from(&["rust-dfir-triage", "--fuzzy-hash"]).fuzzy_hash);
+    }
+
+    #[test]
+    fn test_baseline_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--baseline",
+            "previous/file_manifest.json",
+            "--force-full",
+        ]);
+        assert_eq!(
+            args.baseline,
+            Some(PathBuf::from("previous/file_manifest.json"))
+        );
+        assert!(args.force_full);
+    }
+
+    #[test]
+    fn test_previous_archive_arg() {
+        let args = Args::parse_from([
+            "rust-dfir-triage",
+            "--previous-archive",
+            "previous/host-20260101.zip",
+        ]);
+        assert_eq!(
+            args.previous_archive,
+            Some(PathBuf::from("previous/host-20260101.zip"))
+        );
+    }
+
+    #[test]
+    fn test_verify_sample_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--verify-sample",
+            "5",
+            "--verify-seed",
+            "1234",
+        ]);
+        assert_eq!(args.verify_sample, Some(5));
+        assert_eq!(args.verify_seed, Some(1234));
+        assert!(!args.verify_no_writes);
+        assert!(Args::parse_from(&["rust-dfir-triage", "--verify-no-writes"]).verify_no_writes);
+        assert!(Args::parse_from(&["rust-dfir-triage", "--collect-xattrs"]).collect_xattrs);
+        assert!(Args::parse_from(&["rust-dfir-triage", "--compute-entropy"]).compute_entropy);
+
+        for bad in ["0", "101"] {
+            assert!(Args::try_parse_from(&["rust-dfir-triage", "--verify-sample", bad]).is_err());
+        }
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--verify-seed", "1"]).is_err());
+        assert!(
+            Args::try_parse_from(&["rust-dfir-triage", "--verify-sample", "5", "--stream"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_temp_dir_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--temp-dir", "/mnt/evidence/tmp"]);
+        assert_eq!(args.temp_dir, Some("/mnt/evidence/tmp".to_string()));
+
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert_eq!(args.temp_dir, None);
+    }
+
+    #[test]
+    fn test_atomic_output_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--output",
+            "/mnt/usb",
+            "--atomic-output",
+            "--output-manifest",
+        ]);
+        assert!(args.atomic_output);
+        assert!(args.output_manifest);
+
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert!(!args.atomic_output);
+        assert!(!args.output_manifest);
+
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--output-manifest"]).is_err());
+    }
+
+    #[test]
+    fn test_check_subcommand() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--bucket",
+            "evidence",
+            "check",
+            "--json",
+            "--no-write-probe",
+        ]);
+
+        assert!(matches!(args.command, Some(Commands::Check { json: true })));
+        assert!(args.no_write_probe);
+        assert_eq!(args.bucket, Some("evidence".to_string()));
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "diff",
+            "before/collection_summary.json",
+            "after/collection_summary.json",
+        ]);
+        match args.command {
+            Some(Commands::Diff {
+                baseline,
+                incident,
+                json,
+            }) => {
+                assert_eq!(baseline, PathBuf::from("before/collection_summary.json"));
+                assert_eq!(incident, PathBuf::from("after/collection_summary.json"));
+                assert!(!json);
+            }
+            other => panic!("expected diff subcommand, got {:?}", other),
+        }
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--bucket",
+            "evidence",
+            "generate-url",
+            "--key",
+            "host1/archive.zip",
+        ]);
+        match args.command {
+            Some(Commands::GenerateUrl { key, expiry_hours }) => {
+                assert_eq!(key, "host1/archive.zip");
+                assert_eq!(expiry_hours, 24);
+            }
+            other => panic!("expected generate-url subcommand, got {:?}", other),
+        }
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "download",
+            "--bucket",
+            "evidence",
+            "--key",
+            "host1/archive.zip",
+            "--output-path",
+            "retrieved",
+            "--region",
+            "eu-west-1",
+        ]);
+        match args.command {
+            Some(Commands::Download {
+                bucket,
+                key,
+                output_path,
+                region,
+                profile,
+            }) => {
+                assert_eq!(bucket, "evidence");
+                assert_eq!(key, "host1/archive.zip");
+                assert_eq!(output_path, PathBuf::from("retrieved"));
+                assert_eq!(region.as_deref(), Some("eu-west-1"));
+                assert_eq!(profile, None);
+            }
+            other => panic!("expected download subcommand, got {:?}", other),
+        }
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "download", "--key", "k"]).is_err());
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--serve",
+            "127.0.0.1:8443",
+            "--serve-token-file",
+            "token",
+        ]);
+        assert_eq!(args.serve.as_deref(), Some("127.0.0.1:8443"));
+        assert_eq!(args.serve_token_file, Some(PathBuf::from("token")));
+        assert!(
+            Args::try_parse_from(&["rust-dfir-triage", "--serve-token-file", "token"]).is_err()
+        );
+        assert!(Args::try_parse_from(&[
+            "rust-dfir-triage",
+            "--serve",
+            "127.0.0.1:8443",
+            "--serve-tls-cert",
+            "cert.pem",
+        ])
+        .is_err());
+
+        let args = Args::parse_from(&["rust-dfir-triage", "--preflight"]);
+        assert!(args.preflight);
+        assert!(!args.no_write_probe);
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn test_summary_format_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert_eq!(args.summary_format, SummaryFormat::V2);
+
+        let args = Args::parse_from(&["rust-dfir-triage", "--summary-format", "v1"]);
+        assert_eq!(args.summary_format, SummaryFormat::V1);
+    }
+
+    #[test]
+    fn test_network_mount_point_repeatable() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--network-mount-point",
+            "//fileserver/logs=/mnt/logs",
+            "--network-mount-point",
+            "nfs01:/export/home=/mnt/home",
+        ]);
+
+        assert_eq!(
+            args.network_mount_point,
+            vec![
+                "//fileserver/logs=/mnt/logs".to_string(),
+                "nfs01:/export/home=/mnt/home".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_volatility_order_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--volatility-order",
+            "--volatility-buffer-mb",
+            "16",
+        ]);
+
+        assert!(args.volatility_order);
+        assert_eq!(args.volatility_buffer_mb, 16);
+    }
+
+    #[test]
+    fn test_state_diff_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--state-diff"]);
+        assert!(args.state_diff);
     }
 
     #[test]
@@ -342,6 +1227,263 @@ mod tests {
         assert_eq!(args.memory_regions, "heap,stack");
     }
 
+    #[test]
+    fn test_minidump_type_arg() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--dump-process-memory",
+            "--minidump-type",
+            "full",
+        ]);
+        assert_eq!(
+            args.minidump_type,
+            Some(MiniDumpType::MiniDumpWithFullMemory)
+        );
+
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert_eq!(args.minidump_type, None);
+
+        // Only meaningful alongside a process memory dump
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--minidump-type", "normal"]).is_err());
+        assert!(Args::try_parse_from(&[
+            "rust-dfir-triage",
+            "--dump-process-memory",
+            "--minidump-type",
+            "kernel",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_dump_lsass_requires_force() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--dump-lsass", "--force"]);
+        assert!(args.dump_lsass);
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).dump_lsass);
+
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--dump-lsass"]).is_err());
+    }
+
+    #[test]
+    fn test_update_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--check-update",
+            "https://updates.corp/manifest.json",
+        ]);
+        assert_eq!(
+            args.check_update.as_deref(),
+            Some("https://updates.corp/manifest.json")
+        );
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "self-update",
+            "https://updates.corp/manifest.json",
+            "--allow-downgrade",
+        ]);
+        match args.command {
+            Some(Commands::SelfUpdate {
+                manifest_url,
+                allow_downgrade,
+            }) => {
+                assert_eq!(manifest_url, "https://updates.corp/manifest.json");
+                assert!(allow_downgrade);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "self-update"]).is_err());
+    }
+
+    #[test]
+    fn test_proxy_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--proxy-url",
+            "socks5://proxy.corp:1080",
+            "--proxy-auth-env",
+            "PROXY_CREDENTIALS",
+            "--no-proxy",
+            "internal,.corp",
+        ]);
+        assert_eq!(args.proxy_url.as_deref(), Some("socks5://proxy.corp:1080"));
+        assert_eq!(args.proxy_auth_env.as_deref(), Some("PROXY_CREDENTIALS"));
+        assert_eq!(args.no_proxy.as_deref(), Some("internal,.corp"));
+        assert!(Args::try_parse_from(&[
+            "rust-dfir-triage",
+            "--proxy-auth-env",
+            "PROXY_CREDENTIALS"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_snapshot_args() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--use-snapshots",
+            "--snapshot-size",
+            "20%ORIGIN",
+            "--no-snapshot",
+            "/var",
+            "--no-snapshot",
+            "/home",
+        ]);
+        assert!(args.use_snapshots);
+        assert_eq!(args.snapshot_size.as_deref(), Some("20%ORIGIN"));
+        assert_eq!(
+            args.no_snapshot,
+            vec![PathBuf::from("/var"), PathBuf::from("/home")]
+        );
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--snapshot-size", "2G"]).is_err());
+    }
+
+    #[test]
+    fn test_auto_elevate() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--auto-elevate", "-o", "out"]);
+        assert!(args.auto_elevate);
+        assert!(!args.force);
+    }
+
+    #[test]
+    fn test_hash_process_binaries() {
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).hash_process_binaries);
+        let args = Args::parse_from(&["rust-dfir-triage", "--hash-process-binaries"]);
+        assert!(args.hash_process_binaries);
+    }
+
+    #[test]
+    fn test_process_context_only() {
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).process_context_only);
+        let args = Args::parse_from(&["rust-dfir-triage", "--process-context-only", "--pid", "42"]);
+        assert!(args.process_context_only);
+        assert!(Args::try_parse_from(&[
+            "rust-dfir-triage",
+            "--process-context-only",
+            "--dump-process-memory",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_max_runtime() {
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert_eq!(args.max_runtime, None);
+        assert_eq!(args.max_runtime_margin, Duration::from_secs(60));
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--max-runtime",
+            "30m",
+            "--max-runtime-margin",
+            "2m",
+        ]);
+        assert_eq!(args.max_runtime, Some(Duration::from_secs(1800)));
+        assert_eq!(args.max_runtime_margin, Duration::from_secs(120));
+
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--max-runtime", "soon"]).is_err());
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "--max-runtime-margin", "2m"]).is_err());
+    }
+
+    #[test]
+    fn test_disable_vss() {
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).disable_vss);
+        assert!(Args::parse_from(&["rust-dfir-triage", "--disable-vss"]).disable_vss);
+    }
+
+    #[test]
+    fn test_collect_credential_files() {
+        assert!(!Args::parse_from(&["rust-dfir-triage"]).collect_credential_files);
+        let args = Args::parse_from(&["rust-dfir-triage", "--collect-credential-files"]);
+        assert!(args.collect_credential_files);
+    }
+
+    #[test]
+    fn test_resource_limits() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--max-cpu-percent",
+            "25",
+            "--max-memory-mb",
+            "512",
+        ]);
+        assert_eq!(args.max_cpu_percent, Some(25));
+        assert_eq!(args.max_memory_mb, Some(512));
+
+        for bad in ["0", "101"] {
+            assert!(Args::try_parse_from(&["rust-dfir-triage", "--max-cpu-percent", bad]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_remote_windows_subcommand() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "remote-windows",
+            "ws01.corp.example",
+            "-c",
+            "triage.yaml",
+            "--timeout-minutes",
+            "30",
+            "--",
+            "--artifact-types",
+            "Registry",
+        ]);
+
+        match args.command {
+            Some(Commands::RemoteWindows(opts)) => {
+                assert_eq!(opts.host, "ws01.corp.example");
+                assert_eq!(opts.config, Some(PathBuf::from("triage.yaml")));
+                assert_eq!(opts.output, PathBuf::from("."));
+                assert_eq!(opts.poll_seconds, 10);
+                assert_eq!(opts.timeout_minutes, 30);
+                assert_eq!(opts.collector_args, vec!["--artifact-types", "Registry"]);
+            }
+            _ => panic!("Expected RemoteWindows command"),
+        }
+
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "remote-windows"]).is_err());
+    }
+
+    #[test]
+    fn test_export_subcommand() {
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "export",
+            "out/WS01",
+            "--format",
+            "splunk",
+            "--url",
+            "https://splunk.corp:8088",
+            "--token-file",
+            "hec.token",
+            "--index",
+            "dfir",
+        ]);
+
+        match args.command {
+            Some(Commands::Export(opts)) => {
+                assert_eq!(opts.collection_dir, PathBuf::from("out/WS01"));
+                assert_eq!(opts.format, SiemFormat::Splunk);
+                assert_eq!(opts.url.as_deref(), Some("https://splunk.corp:8088"));
+                assert_eq!(opts.token_file, Some(PathBuf::from("hec.token")));
+                assert_eq!(opts.index.as_deref(), Some("dfir"));
+                assert_eq!(opts.sourcetype, "rs_collector:{type}");
+                assert_eq!(opts.batch_size, 500);
+                assert_eq!(opts.retries, 3);
+            }
+            _ => panic!("Expected Export command"),
+        }
+
+        match Args::parse_from(&["rust-dfir-triage", "export", "out/WS01"]).command {
+            Some(Commands::Export(opts)) => {
+                assert_eq!(opts.format, SiemFormat::Opensearch);
+                assert!(opts.output.is_none() && opts.url.is_none());
+            }
+            _ => panic!("Expected Export command"),
+        }
+        assert!(Args::try_parse_from(&["rust-dfir-triage", "export"]).is_err());
+    }
+
     #[test]
     fn test_init_config_subcommand() {
         let args = Args::parse_from(&[
@@ -361,6 +1503,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_config_template_arg() {
+        let args = Args::parse_from(&["rust-dfir-triage", "--config-template", "container"]);
+        assert_eq!(args.config_template, Some(ConfigTemplate::Container));
+        assert_eq!(ConfigTemplate::Macos.to_string(), "macos");
+
+        let args = Args::parse_from(&["rust-dfir-triage"]);
+        assert_eq!(args.config_template, None);
+
+        assert!(
+            Args::try_parse_from(&["rust-dfir-triage", "--config-template", "solaris"]).is_err()
+        );
+    }
+
     #[test]
     fn test_build_subcommand() {
         let args = Args::parse_from(&[
@@ -434,15 +1590,22 @@ mod tests {
             "--memory-yara",
             "/path/to/rules.yar",
             "--dump-memory-region",
-            "1234:0x400000:4096",
+            "1234:0x400000-0x401000",
         ]);
 
         assert_eq!(args.memory_search, Some("4D5A9000".to_string()));
         assert_eq!(args.memory_yara, Some("/path/to/rules.yar".to_string()));
         assert_eq!(
             args.dump_memory_region,
-            Some("1234:0x400000:4096".to_string())
+            Some("1234:0x400000-0x401000".to_string())
         );
+
+        let args = Args::parse_from(&[
+            "rust-dfir-triage",
+            "--memory-region-dump",
+            "1234:0x400000-0x401000",
+        ]);
+        assert!(args.dump_memory_region.is_some());
     }
 
     #[test]
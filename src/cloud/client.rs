@@ -1,10 +1,105 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
 use log::warn;
-use rusoto_core::Region;
+use rusoto_core::{HttpClient, Region};
+use rusoto_credential::{DefaultCredentialsProvider, ProvideAwsCredentials};
 use rusoto_s3::S3Client;
 
+use crate::utils::{http, proxy};
+
+/// Connect timeout for S3 connections opened through a proxy
+const PROXY_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// hyper connector that opens S3 connections through the `--proxy-url`
+/// proxy, or directly for hosts that bypass it; TLS to S3 is layered on top
+/// by [`HttpsConnector`], inside any TLS to an `https://` proxy
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProxyConnector;
+
+impl Service<Uri> for ProxyConnector {
+    type Response = MaybeHttpsStream<tokio::net::TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        Box::pin(async move {
+            let host = uri
+                .host()
+                .map(|host| {
+                    host.trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .to_string()
+                })
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URI has no host"))?;
+            let port = uri
+                .port_u16()
+                .unwrap_or(if uri.scheme_str() == Some("http") {
+                    80
+                } else {
+                    443
+                });
+            if let Some(proxy) = proxy::explicit_proxy_for(&host) {
+                return http::tunnel(&proxy, &host, port, PROXY_CONNECT_TIMEOUT)
+                    .await
+                    .map_err(|e| io::Error::other(format!("{:#}", e)));
+            }
+            let stream = tokio::task::spawn_blocking(move || {
+                proxy::connect_direct(&host, port, PROXY_CONNECT_TIMEOUT)
+            })
+            .await
+            .map_err(io::Error::other)?
+            .map_err(|e| io::Error::other(format!("{:#}", e)))?;
+            stream.set_read_timeout(None)?;
+            stream.set_write_timeout(None)?;
+            stream.set_nonblocking(true)?;
+            Ok(MaybeHttpsStream::Http(tokio::net::TcpStream::from_std(
+                stream,
+            )?))
+        })
+    }
+}
+
+/// S3 client using `provider`, through the explicit proxy when one is
+/// configured
+pub fn s3_client_with<P>(provider: P, region: Region) -> Result<S3Client>
+where
+    P: ProvideAwsCredentials + Send + Sync + 'static,
+{
+    if proxy::configured().is_some() {
+        let http_client =
+            HttpClient::from_connector(HttpsConnector::new_with_connector(ProxyConnector));
+        Ok(S3Client::new_with(http_client, provider, region))
+    } else {
+        let http_client =
+            HttpClient::new().map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+        Ok(S3Client::new_with(http_client, provider, region))
+    }
+}
+
+/// S3 client using the default credential chain, through the explicit
+/// proxy when one is configured
+pub fn default_s3_client(region: Region) -> Result<S3Client> {
+    if proxy::configured().is_none() {
+        return Ok(S3Client::new(region));
+    }
+    let provider = DefaultCredentialsProvider::new()
+        .map_err(|e| anyhow!("Failed to create AWS credentials provider: {}", e))?;
+    s3_client_with(provider, region)
+}
+
 /// Create an S3 client with the specified region and profile
 pub fn create_s3_client(region_name: Option<&str>, profile: Option<&str>) -> Result<Arc<S3Client>> {
     let region = match region_name {
@@ -23,23 +118,18 @@ pub fn create_s3_client(region_name: Option<&str>, profile: Option<&str>) -> Res
         match rusoto_credential::ProfileProvider::new() {
             Ok(mut provider) => {
                 provider.set_profile(profile_name);
-                Arc::new(S3Client::new_with(
-                    rusoto_core::HttpClient::new()
-                        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?,
-                    provider,
-                    region.clone(),
-                ))
+                Arc::new(s3_client_with(provider, region.clone())?)
             }
             Err(e) => {
                 warn!(
                     "Failed to create AWS profile provider: {}, using default",
                     e
                 );
-                Arc::new(S3Client::new(region.clone()))
+                Arc::new(default_s3_client(region.clone())?)
             }
         }
     } else {
-        Arc::new(S3Client::new(region.clone()))
+        Arc::new(default_s3_client(region.clone())?)
     };
 
     Ok(s3_client)
@@ -11,17 +11,29 @@ use anyhow::{anyhow, Context, Result};
 use futures::future::{self};
 use log::{debug, info, warn};
 use rusoto_core::{ByteStream, Region};
+use rusoto_credential::{
+    AwsCredentials, DefaultCredentialsProvider, ProfileProvider, ProvideAwsCredentials,
+};
+use rusoto_s3::util::{PreSignedRequest, PreSignedRequestOption};
 use rusoto_s3::{
     AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
-    CompletedPart, CreateMultipartUploadRequest, PutObjectRequest, S3Client, UploadPartRequest, S3,
+    CompletedPart, CreateMultipartUploadRequest, GetObjectRequest, PutObjectRequest, S3Client,
+    UploadPartRequest, S3,
 };
 use tokio::fs::File as AsyncFile;
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::time::sleep;
 
+use crate::cloud::client;
 use crate::constants::{
     LARGE_FILE_THRESHOLD, MAX_UPLOAD_RETRIES, S3_UPLOAD_CHUNK_SIZE as UPLOAD_CHUNK_SIZE,
 };
+use crate::errors::CollectorError;
+use crate::security::audit_log::{self, AuditAction};
+use crate::utils::metrics::{self, Measurement};
+
+/// Longest expiry SigV4 allows for a presigned URL (7 days)
+pub const MAX_PRESIGNED_URL_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
 
 /// Async file queue for concurrent uploads to Amazon S3.
 ///
@@ -73,29 +85,26 @@ impl UploadQueue {
         region_name: Option<&str>,
         profile: Option<&str>,
     ) -> Self {
-        let region = match region_name {
-            Some(name) => match name.parse::<Region>() {
-                Ok(r) => r,
-                Err(_) => {
-                    warn!("Invalid region '{}', using default", name);
-                    Region::default()
-                }
-            },
-            None => Region::default(),
+        let region = parse_region(region_name);
+
+        let default_client = || match client::default_s3_client(region.clone()) {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("{:#}, using default", e);
+                S3Client::new(region.clone())
+            }
         };
 
         // Create S3 client with profile if specified
         let s3_client = if let Some(profile_name) = profile {
-            match rusoto_credential::ProfileProvider::new() {
+            match ProfileProvider::new() {
                 Ok(mut provider) => {
                     provider.set_profile(profile_name);
-                    match rusoto_core::HttpClient::new() {
-                        Ok(http_client) => {
-                            Arc::new(S3Client::new_with(http_client, provider, region.clone()))
-                        }
+                    match client::s3_client_with(provider, region.clone()) {
+                        Ok(client) => Arc::new(client),
                         Err(e) => {
-                            warn!("Failed to create HTTP client: {}, using default", e);
-                            Arc::new(S3Client::new(region.clone()))
+                            warn!("{:#}, using default", e);
+                            Arc::new(default_client())
                         }
                     }
                 }
@@ -104,11 +113,11 @@ impl UploadQueue {
                         "Failed to create AWS profile provider: {}, using default",
                         e
                     );
-                    Arc::new(S3Client::new(region.clone()))
+                    Arc::new(default_client())
                 }
             }
         } else {
-            Arc::new(S3Client::new(region.clone()))
+            Arc::new(default_client())
         };
 
         UploadQueue {
@@ -144,6 +153,10 @@ impl UploadQueue {
             .to_string_lossy();
         let key = format!("{}/{}", self.prefix, filename);
 
+        audit_log::record(
+            AuditAction::UploadAttempt,
+            &format!("{} -> s3://{}/{}", file_path.display(), self.bucket, key),
+        );
         debug!(
             "Starting upload of {} ({} bytes) to s3://{}/{}",
             file_path.display(),
@@ -268,11 +281,11 @@ impl UploadQueue {
                 }
                 Err(e) => {
                     if attempt >= max_attempts {
-                        return Err(anyhow!(
-                            "Failed to upload to S3 after {} attempts: {}",
-                            max_attempts,
-                            e
-                        ));
+                        return Err(CollectorError::UploadFailed {
+                            target: format!("s3://{}/{}", self.bucket, key),
+                            cause: format!("{} attempts failed: {}", max_attempts, e),
+                        }
+                        .into());
                     }
 
                     // Exponential backoff
@@ -481,6 +494,13 @@ pub async fn upload_files_concurrently(
     let bytes_uploaded = Arc::clone(&queue.bytes_uploaded);
     let total_bytes = Arc::clone(&queue.total_bytes);
 
+    // Sampled again at every progress report, for throughput over time
+    metrics::record_with(|| Measurement::UploadProgress {
+        target: "s3".into(),
+        bytes_uploaded: 0,
+        at: Instant::now(),
+    });
+
     // Start a separate tokio task for progress reporting
     let _progress_task = tokio::spawn(async move {
         let mut last_reported = 0;
@@ -491,6 +511,11 @@ pub async fn upload_files_concurrently(
 
             let uploaded = bytes_uploaded.load(Ordering::SeqCst);
             let total = total_bytes.load(Ordering::SeqCst);
+            metrics::record_with(|| Measurement::UploadProgress {
+                target: "s3".into(),
+                bytes_uploaded: uploaded,
+                at: Instant::now(),
+            });
 
             if total > 0 && (uploaded != last_reported) {
                 let percentage = (uploaded as f64 / total as f64) * 100.0;
@@ -519,6 +544,11 @@ pub async fn upload_files_concurrently(
     future::join_all(tasks).await;
 
     let (uploaded, total) = queue.get_progress();
+    metrics::record_with(|| Measurement::UploadProgress {
+        target: "s3".into(),
+        bytes_uploaded: uploaded,
+        at: Instant::now(),
+    });
     let region_name = queue.get_region().name();
 
     if uploaded < total {
@@ -536,7 +566,240 @@ pub async fn upload_files_concurrently(
     Ok(())
 }
 
-/// Legacy upload function for backward compatibility
+/// Parse `region_name`, falling back to the default region when it is
+/// missing or invalid
+fn parse_region(region_name: Option<&str>) -> Region {
+    match region_name {
+        Some(name) => match name.parse::<Region>() {
+            Ok(r) => r,
+            Err(_) => {
+                warn!("Invalid region '{}', using default", name);
+                Region::default()
+            }
+        },
+        None => Region::default(),
+    }
+}
+
+/// Generate a presigned GET URL for `key` in `bucket`, valid for `expiry_secs`.
+///
+/// Anyone holding the URL can download the object until it expires, without
+/// AWS credentials of their own. Signing happens locally with the credentials
+/// of `profile` (or the default provider chain); nothing is sent to S3.
+/// Temporary credentials cap the URL's lifetime at their own expiry.
+///
+/// # Example
+///
+/// ```no_run
+/// # use rust_collector::cloud::s3::generate_presigned_url;
+/// # async fn example() -> anyhow::Result<()> {
+/// let url = generate_presigned_url(
+///     "my-forensics-bucket",
+///     "collections/host1/20240115-120000-host1.zip",
+///     Some("us-west-2"),
+///     None,
+///     3600,
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn generate_presigned_url(
+    bucket: &str,
+    key: &str,
+    region_name: Option<&str>,
+    profile: Option<&str>,
+    expiry_secs: u64,
+) -> Result<String> {
+    validate_presigned_expiry(expiry_secs)?;
+    let region = parse_region(region_name);
+
+    let credentials = match profile {
+        Some(profile_name) => {
+            let mut provider =
+                ProfileProvider::new().context("Failed to create AWS profile provider")?;
+            provider.set_profile(profile_name);
+            provider.credentials().await
+        }
+        None => {
+            DefaultCredentialsProvider::new()
+                .context("Failed to create AWS credentials provider")?
+                .credentials()
+                .await
+        }
+    }
+    .context("Failed to load AWS credentials")?;
+
+    Ok(presign_get(
+        bucket,
+        key,
+        &region,
+        &credentials,
+        Duration::from_secs(expiry_secs),
+    ))
+}
+
+fn validate_presigned_expiry(expiry_secs: u64) -> Result<()> {
+    if expiry_secs == 0 || expiry_secs > MAX_PRESIGNED_URL_EXPIRY_SECS {
+        return Err(anyhow!(
+            "Presigned URL expiry must be between 1 second and 7 days, got {} seconds",
+            expiry_secs
+        ));
+    }
+    Ok(())
+}
+
+fn presign_get(
+    bucket: &str,
+    key: &str,
+    region: &Region,
+    credentials: &AwsCredentials,
+    expires_in: Duration,
+) -> String {
+    GetObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        ..Default::default()
+    }
+    .get_presigned_url(region, credentials, &PreSignedRequestOption { expires_in })
+}
+
+/// Download the object `key` from `bucket` to `output_path` with `GetObject`.
+///
+/// Lets a responder pull one file from an earlier upload without fetching
+/// the whole archive. When `output_path` is an existing directory the object
+/// is written into it under the last component of its key. The body is
+/// streamed to `<output_path>.part`, which is renamed once the size matches
+/// the object's `Content-Length`, so an interrupted download never leaves a
+/// file that looks complete.
+///
+/// # Example
+///
+/// ```no_run
+/// # use rust_collector::cloud::s3::download_artifact;
+/// # use std::path::Path;
+/// # async fn example() -> anyhow::Result<()> {
+/// download_artifact(
+///     "my-forensics-bucket",
+///     "collections/host1/20240115-120000-host1.zip",
+///     Path::new("evidence/"),
+///     Some("us-west-2"),
+///     None,
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn download_artifact(
+    bucket: &str,
+    key: &str,
+    output_path: &Path,
+    region_name: Option<&str>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let output_path = download_destination(output_path, key)?;
+    let client = client_for(region_name, profile)?;
+    let started = Instant::now();
+
+    info!("Downloading s3://{}/{}...", bucket, key);
+    let object = client
+        .get_object(GetObjectRequest {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow!("GetObject failed for s3://{}/{}: {}", bucket, key, e))?;
+    let body = object
+        .body
+        .ok_or_else(|| anyhow!("s3://{}/{} has no body", bucket, key))?;
+
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let mut partial = output_path.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let mut file = AsyncFile::create(&partial)
+        .await
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    let written = tokio::io::copy(&mut body.into_async_read(), &mut file)
+        .await
+        .with_context(|| format!("Failed to download s3://{}/{}", bucket, key));
+    let written = match written {
+        Ok(written) => written,
+        Err(e) => {
+            drop(file);
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    file.sync_all().await?;
+    drop(file);
+
+    if let Some(expected) = object.content_length.filter(|len| *len as u64 != written) {
+        let _ = fs::remove_file(&partial);
+        return Err(anyhow!(
+            "Download of s3://{}/{} was truncated: {} of {} bytes",
+            bucket,
+            key,
+            written,
+            expected
+        ));
+    }
+    fs::rename(&partial, &output_path)
+        .with_context(|| format!("Failed to move download to {}", output_path.display()))?;
+
+    info!(
+        "Downloaded {} bytes to {} in {:.1}s",
+        written,
+        output_path.display(),
+        started.elapsed().as_secs_f64()
+    );
+    audit_log::record(
+        AuditAction::Download,
+        &format!(
+            "s3://{}/{} -> {} ({} bytes)",
+            bucket,
+            key,
+            output_path.display(),
+            written
+        ),
+    );
+    Ok(())
+}
+
+/// Where a download of `key` to `output_path` is written: into the
+/// directory when `output_path` is one, otherwise to `output_path` itself
+fn download_destination(output_path: &Path, key: &str) -> Result<PathBuf> {
+    if !output_path.is_dir() {
+        return Ok(output_path.to_path_buf());
+    }
+    let name = key
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+        .ok_or_else(|| anyhow!("Cannot derive a file name from key '{}'", key))?;
+    Ok(output_path.join(name))
+}
+
+/// S3 client for `region_name`, using the credentials of `profile` or the
+/// default provider chain
+fn client_for(region_name: Option<&str>, profile: Option<&str>) -> Result<S3Client> {
+    let region = parse_region(region_name);
+    match profile {
+        Some(profile_name) => {
+            let mut provider =
+                ProfileProvider::new().context("Failed to create AWS profile provider")?;
+            provider.set_profile(profile_name);
+            client::s3_client_with(provider, region)
+        }
+        None => client::default_s3_client(region),
+    }
+}
+
 #[allow(dead_code)]
 pub async fn upload_to_s3(
     file_path: &Path,
@@ -564,7 +827,11 @@ pub async fn upload_to_s3(
             );
             Ok(())
         }
-        Err(e) => Err(anyhow!("Failed to upload to S3: {}", e)),
+        Err(e) => Err(CollectorError::UploadFailed {
+            target: format!("S3 bucket {}", bucket),
+            cause: format!("{:#}", e),
+        }
+        .into()),
     }
 }
 
@@ -575,6 +842,21 @@ mod tests {
     use std::io::Write;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_download_destination() {
+        let dir = TempDir::new().unwrap();
+        let key = "collections/host1/20240115-120000-host1.zip";
+
+        assert_eq!(
+            download_destination(dir.path(), key).unwrap(),
+            dir.path().join("20240115-120000-host1.zip")
+        );
+        let file = dir.path().join("evidence.zip");
+        assert_eq!(download_destination(&file, key).unwrap(), file);
+        assert!(download_destination(dir.path(), "collections/").is_err());
+        assert!(download_destination(dir.path(), "collections/..").is_err());
+    }
+
     #[test]
     fn test_upload_queue_new() {
         let queue = UploadQueue::new("test-bucket", "test-prefix", None, None);
@@ -643,6 +925,28 @@ mod tests {
         assert_eq!(queue.get_region().name(), "eu-west-1");
     }
 
+    #[test]
+    fn test_presign_get() {
+        let credentials = AwsCredentials::new("AKIDEXAMPLE", "secret", None, None);
+        let url = presign_get(
+            "evidence",
+            "host1/archive.zip",
+            &Region::UsWest2,
+            &credentials,
+            Duration::from_secs(3600),
+        );
+
+        assert!(url.starts_with("https://s3.us-west-2.amazonaws.com/evidence/host1/archive.zip?"));
+        assert!(url.contains("X-Amz-Expires=3600"));
+        assert!(url.contains("X-Amz-Credential=AKIDEXAMPLE%2F"));
+        assert!(url.contains("X-Amz-Signature="));
+        assert!(!url.contains("secret"));
+
+        assert!(validate_presigned_expiry(MAX_PRESIGNED_URL_EXPIRY_SECS).is_ok());
+        assert!(validate_presigned_expiry(0).is_err());
+        assert!(validate_presigned_expiry(MAX_PRESIGNED_URL_EXPIRY_SECS + 1).is_err());
+    }
+
     #[tokio::test]
     async fn test_add_file_nonexistent() {
         let queue = UploadQueue::new("test-bucket", "test-prefix", None, None);
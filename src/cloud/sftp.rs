@@ -2,7 +2,7 @@ use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc, Mutex,
 };
 use std::time::{Duration, Instant};
@@ -19,6 +19,10 @@ use crate::constants::{
     SFTP_DEFAULT_CONCURRENT_CONNECTIONS as DEFAULT_CONCURRENT_CONNECTIONS,
     SFTP_DEFAULT_PORT as DEFAULT_PORT,
 };
+use crate::errors::CollectorError;
+use crate::security::audit_log::{self, AuditAction};
+use crate::utils::metrics::{self, Measurement};
+use crate::utils::proxy;
 
 /// Configuration for SFTP uploads.
 ///
@@ -99,6 +103,8 @@ pub struct SFTPClient {
     retry_config: RetryConfig,
     total_bytes: Arc<AtomicU64>,
     bytes_uploaded: Arc<AtomicU64>,
+    /// Set once `remote_path` is known to exist on the server
+    remote_path_ready: AtomicBool,
 }
 
 impl SFTPClient {
@@ -122,28 +128,34 @@ impl SFTPClient {
             retry_config,
             total_bytes: Arc::new(AtomicU64::new(0)),
             bytes_uploaded: Arc::new(AtomicU64::new(0)),
+            remote_path_ready: AtomicBool::new(false),
         }
     }
 
-    /// Create a new SSH session
+    /// Create a new SSH session for uploads.
+    ///
+    /// The first session also creates `remote_path` on the server if it is
+    /// missing, so uploads do not fail on a nonexistent directory.
     fn create_session(&self) -> Result<Session> {
-        // Create TCP connection
-        let tcp =
-            std::net::TcpStream::connect(format!("{}:{}", self.config.host, self.config.port))
-                .context(format!(
-                    "Failed to connect to {}:{}",
-                    self.config.host, self.config.port
-                ))?;
+        let session = self.connect()?;
+
+        if !self.remote_path_ready.load(Ordering::SeqCst) {
+            let sftp = Self::create_sftp(&session)?;
+            Self::mkdir_recursive(&sftp, &self.config.remote_path)?;
+            self.remote_path_ready.store(true, Ordering::SeqCst);
+        }
+
+        Ok(session)
+    }
 
-        // Set connection timeout
-        tcp.set_read_timeout(Some(Duration::from_secs(
-            self.config.connection_timeout_sec,
-        )))
-        .context("Failed to set read timeout")?;
-        tcp.set_write_timeout(Some(Duration::from_secs(
-            self.config.connection_timeout_sec,
-        )))
-        .context("Failed to set write timeout")?;
+    /// Connect and authenticate a new SSH session
+    fn connect(&self) -> Result<Session> {
+        // Create TCP connection, through the proxy if one is configured
+        let tcp = proxy::connect(
+            &self.config.host,
+            self.config.port,
+            Duration::from_secs(self.config.connection_timeout_sec),
+        )?;
 
         // Create SSH session
         let mut session = Session::new().context("Failed to create SSH session")?;
@@ -179,6 +191,31 @@ impl SFTPClient {
         session.sftp().context("Failed to create SFTP subsystem")
     }
 
+    /// Create `path` and any missing parent directories on the server.
+    ///
+    /// Each component is checked with `stat` and created with mode 0755 if
+    /// it does not exist. A component that exists but is not a directory is
+    /// an error. A directory created concurrently by another connection is
+    /// accepted.
+    pub fn mkdir_recursive(sftp: &Sftp, path: &str) -> Result<()> {
+        for dir in remote_dir_prefixes(path) {
+            let dir_path = Path::new(&dir);
+            match sftp.stat(dir_path) {
+                Ok(stat) if stat.is_dir() => continue,
+                Ok(_) => return Err(anyhow!("Remote path {} exists and is not a directory", dir)),
+                Err(_) => {}
+            }
+
+            if let Err(e) = sftp.mkdir(dir_path, 0o755) {
+                if !sftp.stat(dir_path).map(|s| s.is_dir()).unwrap_or(false) {
+                    return Err(e).context(format!("Failed to create remote directory {}", dir));
+                }
+            }
+            debug!("Created remote directory {}", dir);
+        }
+        Ok(())
+    }
+
     /// Upload a file to the SFTP server
     pub async fn upload_file(&self, local_path: &Path) -> Result<()> {
         // Get file metadata
@@ -202,6 +239,17 @@ impl SFTPClient {
             filename
         );
 
+        audit_log::record(
+            AuditAction::UploadAttempt,
+            &format!(
+                "{} -> sftp://{}@{}:{}{}",
+                local_path.display(),
+                self.config.username,
+                self.config.host,
+                self.config.port,
+                remote_path
+            ),
+        );
         debug!(
             "Starting upload of {} ({} bytes) to sftp://{}@{}:{}{}",
             local_path.display(),
@@ -434,6 +482,67 @@ impl SFTPClient {
             self.total_bytes.load(Ordering::SeqCst),
         )
     }
+
+    /// Check connectivity, authentication and the remote path.
+    ///
+    /// With `write_probe`, a 1-byte file is created in the remote path and
+    /// removed again; otherwise the path is only checked to be a directory.
+    pub fn check_access(&self, write_probe: bool) -> Result<()> {
+        let session = self.connect()?;
+        let sftp = Self::create_sftp(&session)?;
+
+        let remote_dir = match self.config.remote_path.trim_end_matches('/') {
+            "" if self.config.remote_path.starts_with('/') => "/",
+            "" => ".",
+            dir => dir,
+        };
+        let stat = sftp
+            .stat(Path::new(remote_dir))
+            .context(format!("Remote path {} is not accessible", remote_dir))?;
+        if !stat.is_dir() {
+            return Err(anyhow!("Remote path {} is not a directory", remote_dir));
+        }
+
+        if write_probe {
+            let probe_path = format!(
+                "{}/.rs-collector-preflight-{}",
+                remote_dir.trim_end_matches('/'),
+                uuid::Uuid::new_v4()
+            );
+            let mut probe = sftp
+                .create(Path::new(&probe_path))
+                .context(format!("Remote path {} is not writable", remote_dir))?;
+            probe
+                .write_all(&[0])
+                .context(format!("Failed to write probe file {}", probe_path))?;
+            drop(probe);
+            sftp.unlink(Path::new(&probe_path))
+                .context(format!("Failed to remove probe file {}", probe_path))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Directories to check, outermost first, to create `path` on the server.
+///
+/// `/data/triage/` gives `/data` and `/data/triage`; relative paths are
+/// relative to the login directory, and `.` components are skipped.
+fn remote_dir_prefixes(path: &str) -> Vec<String> {
+    let mut prefix = if path.starts_with('/') {
+        "/".to_string()
+    } else {
+        String::new()
+    };
+    let mut prefixes = Vec::new();
+    for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        prefix.push_str(component);
+        prefixes.push(prefix.clone());
+    }
+    prefixes
 }
 
 /// Upload multiple files to SFTP server concurrently.
@@ -462,6 +571,13 @@ pub async fn upload_files_concurrently(files: Vec<PathBuf>, config: SFTPConfig)
     let bytes_uploaded = Arc::clone(&client.bytes_uploaded);
     let total_bytes = Arc::clone(&client.total_bytes);
 
+    // Sampled again at every progress report, for throughput over time
+    metrics::record_with(|| Measurement::UploadProgress {
+        target: "sftp".into(),
+        bytes_uploaded: 0,
+        at: Instant::now(),
+    });
+
     // Start a separate tokio task for progress reporting
     let _progress_task = tokio::spawn(async move {
         let mut last_reported = 0;
@@ -472,6 +588,11 @@ pub async fn upload_files_concurrently(files: Vec<PathBuf>, config: SFTPConfig)
 
             let uploaded = bytes_uploaded.load(Ordering::SeqCst);
             let total = total_bytes.load(Ordering::SeqCst);
+            metrics::record_with(|| Measurement::UploadProgress {
+                target: "sftp".into(),
+                bytes_uploaded: uploaded,
+                at: Instant::now(),
+            });
 
             if total > 0 && (uploaded != last_reported) {
                 let percentage = (uploaded as f64 / total as f64) * 100.0;
@@ -495,6 +616,11 @@ pub async fn upload_files_concurrently(files: Vec<PathBuf>, config: SFTPConfig)
     }
 
     let (uploaded, total) = client.get_progress();
+    metrics::record_with(|| Measurement::UploadProgress {
+        target: "sftp".into(),
+        bytes_uploaded: uploaded,
+        at: Instant::now(),
+    });
 
     if uploaded < total {
         warn!(
@@ -527,7 +653,11 @@ pub async fn upload_to_sftp(file_path: &Path, config: SFTPConfig) -> Result<()>
             );
             Ok(())
         }
-        Err(e) => Err(anyhow!("Failed to upload to SFTP: {}", e)),
+        Err(e) => Err(CollectorError::UploadFailed {
+            target: format!("SFTP server {}", config.host),
+            cause: format!("{:#}", e),
+        }
+        .into()),
     }
 }
 
@@ -674,6 +804,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_remote_dir_prefixes() {
+        assert_eq!(
+            remote_dir_prefixes("/data/triage/host1/"),
+            vec!["/data", "/data/triage", "/data/triage/host1"]
+        );
+        assert_eq!(
+            remote_dir_prefixes("uploads//./today"),
+            vec!["uploads", "uploads/today"]
+        );
+        assert!(remote_dir_prefixes("/").is_empty());
+        assert!(remote_dir_prefixes("").is_empty());
+    }
+
     #[tokio::test]
     async fn test_upload_file_nonexistent() {
         let config = SFTPConfig {
@@ -17,6 +17,8 @@ use tokio::sync::mpsc;
 use tokio::time::Duration;
 
 use crate::cloud::sftp::SFTPConfig;
+use crate::security::audit_log::{self, AuditAction};
+use crate::utils::proxy;
 
 // Constants
 const MAX_RETRIES: usize = 3;
@@ -61,17 +63,20 @@ impl SFTPUploadStream {
     ///
     /// A new SFTPUploadStream instance or an error if the connection couldn't be established
     pub async fn new(config: SFTPConfig, remote_path: &str, buffer_size_mb: usize) -> Result<Self> {
-        // Create TCP connection
-        let tcp =
-            std::net::TcpStream::connect(format!("{}:{}", config.host, config.port)).context(
-                format!("Failed to connect to {}:{}", config.host, config.port),
-            )?;
-
-        // Set connection timeout
-        tcp.set_read_timeout(Some(Duration::from_secs(config.connection_timeout_sec)))
-            .context("Failed to set read timeout")?;
-        tcp.set_write_timeout(Some(Duration::from_secs(config.connection_timeout_sec)))
-            .context("Failed to set write timeout")?;
+        audit_log::record(
+            AuditAction::UploadAttempt,
+            &format!(
+                "stream -> sftp://{}@{}:{}{}",
+                config.username, config.host, config.port, remote_path
+            ),
+        );
+
+        // Create TCP connection, through the proxy if one is configured
+        let tcp = proxy::connect(
+            &config.host,
+            config.port,
+            Duration::from_secs(config.connection_timeout_sec),
+        )?;
 
         // Create SSH session
         let mut session = Session::new().context("Failed to create SSH session")?;
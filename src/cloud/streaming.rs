@@ -6,6 +6,7 @@ use std::task::{Context, Poll};
 
 use crate::cloud::streaming_target::StreamingTarget;
 use crate::constants::{MAX_UPLOAD_RETRIES as MAX_RETRIES, S3_MIN_PART_SIZE as MIN_PART_SIZE};
+use crate::security::audit_log::{self, AuditAction};
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 use bytes::{Bytes, BytesMut};
 use log::{debug, warn};
@@ -70,6 +71,11 @@ impl S3UploadStream {
         key: &str,
         buffer_size_mb: usize,
     ) -> Result<Self> {
+        audit_log::record(
+            AuditAction::UploadAttempt,
+            &format!("stream -> s3://{}/{}", bucket, key),
+        );
+
         // Ensure buffer size is at least the minimum part size
         let buffer_size = buffer_size_mb.max(5) * 1024 * 1024;
 
@@ -0,0 +1,117 @@
+//! Cooperative cancellation of timed-out artifact copies.
+//!
+//! A collection that exceeds its timeout is abandoned by the async side, but
+//! the copy itself usually runs inside `spawn_blocking`, which cannot be
+//! killed. When an artifact times out its source path is marked cancelled
+//! here, and the copy loops check [`is_cancelled`] between chunks or files
+//! and stop early. Like the footprint tracker, the registry is
+//! process-global so copy loops don't need the flag threaded through every
+//! collector.
+//!
+//! A whole collection can be cancelled too (the control API does this): every
+//! copy then reports cancelled and the next phase boundary fails.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Normalized source paths whose copies should stop
+static CANCELLED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Number of cancelled sources, checked before taking the lock
+static CANCELLED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Set while the running collection is being cancelled
+static COLLECTION_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Normalize a path for prefix comparison across `\\?\` and separator styles
+fn normalize(path: &str) -> String {
+    path.trim_start_matches(r"\\?\")
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+fn update_count(cancelled: &[String]) {
+    CANCELLED_COUNT.store(cancelled.len(), Ordering::Relaxed);
+}
+
+/// Ask copies of `source` (and anything below it) to stop
+pub fn cancel(source: &str) {
+    let source = normalize(source);
+    let mut cancelled = CANCELLED.lock().unwrap_or_else(|e| e.into_inner());
+    if !cancelled.contains(&source) {
+        cancelled.push(source);
+    }
+    update_count(&cancelled);
+}
+
+/// Clear a cancellation before `source` is collected again
+pub fn resume(source: &str) {
+    if CANCELLED_COUNT.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let source = normalize(source);
+    let mut cancelled = CANCELLED.lock().unwrap_or_else(|e| e.into_inner());
+    cancelled.retain(|c| *c != source);
+    update_count(&cancelled);
+}
+
+/// Ask the running collection to stop: all copies and any later phases
+pub fn cancel_collection() {
+    COLLECTION_CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Clear a collection cancellation before the next collection starts
+pub fn reset_collection() {
+    COLLECTION_CANCELLED.store(false, Ordering::Relaxed);
+}
+
+/// Whether the running collection has been cancelled
+pub fn collection_cancelled() -> bool {
+    COLLECTION_CANCELLED.load(Ordering::Relaxed)
+}
+
+/// Whether a copy of `path` should stop because its artifact timed out or
+/// the collection was cancelled
+pub fn is_cancelled(path: &Path) -> bool {
+    if collection_cancelled() {
+        return true;
+    }
+    if CANCELLED_COUNT.load(Ordering::Relaxed) == 0 {
+        return false;
+    }
+    let path = normalize(&path.to_string_lossy());
+    let cancelled = CANCELLED.lock().unwrap_or_else(|e| e.into_inner());
+    cancelled.iter().any(|source| {
+        path == *source
+            || path
+                .strip_prefix(source.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_covers_children_only() {
+        cancel("/mnt/hung-nfs/share");
+        assert!(is_cancelled(Path::new("/mnt/hung-nfs/share")));
+        assert!(is_cancelled(Path::new("/mnt/hung-nfs/share/a/b.txt")));
+        assert!(!is_cancelled(Path::new("/mnt/hung-nfs/shared")));
+        assert!(!is_cancelled(Path::new("/mnt/other")));
+
+        resume("/mnt/hung-nfs/share");
+        assert!(!is_cancelled(Path::new("/mnt/hung-nfs/share/a/b.txt")));
+    }
+
+    #[test]
+    fn test_windows_path_forms_match() {
+        cancel(r"\\?\C:\Windows\System32\Tasks");
+        assert!(is_cancelled(Path::new(r"C:\Windows\System32\tasks\Job")));
+        resume(r"C:\Windows\System32\Tasks");
+        assert!(!is_cancelled(Path::new(r"C:\Windows\System32\tasks\Job")));
+    }
+}
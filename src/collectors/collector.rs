@@ -1,35 +1,57 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use futures::future::{self, FutureExt};
 use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, Semaphore};
 
+use crate::collectors::cancellation;
+use crate::collectors::cloud_credentials;
+use crate::collectors::incremental;
+use crate::collectors::mail_stores::{self, MailStoreOptions};
 use crate::collectors::permission_tracker::PermissionTracker;
 use crate::collectors::platforms;
+use crate::collectors::platforms::common::{rfc3339, FallbackCollector, WalkOptions};
+use crate::collectors::platforms::encryption;
 use crate::collectors::regex::RegexCollector;
-use crate::config::{Artifact, ArtifactType, WindowsArtifactType};
+use crate::collectors::source::{self, SourceMetadata};
+use crate::config::{
+    resolve_all_variables, Artifact, ArtifactType, CollectionConfig, ContainerType,
+    MacOSArtifactType, WindowsArtifactType,
+};
+use crate::constants::DEFAULT_ARTIFACT_TIMEOUT_SECS;
+use crate::errors::CollectorError;
 use crate::models::ArtifactMetadata;
+use crate::security::config::{log_security_event, SecurityEvent};
+use crate::security::path_validator::validate_destination;
+use crate::utils::metrics::{self, Measurement};
+use crate::utils::path_encoding::escaped_path;
+use crate::utils::resource_limits;
+use crate::windows::efs;
 
 /// Trait for artifact collectors.
 ///
 /// This trait defines the interface that all artifact collectors must implement.
 /// Collectors are responsible for gathering specific types of forensic artifacts
-/// from a system and storing them in a specified output directory.
+/// from a system and storing them at a destination chosen by the caller.
 ///
 /// # Thread Safety
 ///
 /// Implementors must be `Send + Sync` to support concurrent collection operations.
 #[async_trait::async_trait]
 pub trait ArtifactCollector: Send + Sync {
-    /// Collect a specific artifact and save it to the output directory.
+    /// Collect a specific artifact and save it to `output_path`.
     ///
     /// # Arguments
     ///
     /// * `artifact` - The artifact configuration specifying what to collect
-    /// * `output_dir` - Directory where the collected artifact should be saved
+    /// * `output_path` - Destination claimed for the artifact: the file to
+    ///   write, or the directory to copy a directory source into
     ///
     /// # Returns
     ///
@@ -41,9 +63,10 @@ pub trait ArtifactCollector: Send + Sync {
     /// Implementors should:
     /// - Preserve the original file metadata when possible
     /// - Handle platform-specific paths appropriately
-    /// - Create necessary subdirectories in the output directory
+    /// - Write to `output_path` itself; the caller has already made the name
+    ///   unique within the run
     /// - Return detailed error messages for troubleshooting
-    async fn collect(&self, artifact: &Artifact, output_dir: &Path) -> Result<ArtifactMetadata>;
+    async fn collect(&self, artifact: &Artifact, output_path: &Path) -> Result<ArtifactMetadata>;
 
     /// Check if this collector supports a given artifact type.
     ///
@@ -59,11 +82,28 @@ pub trait ArtifactCollector: Send + Sync {
 }
 
 /// Check if an artifact is a special case that doesn't have a standard file path
-fn is_special_artifact(artifact_type: &ArtifactType) -> bool {
+pub(crate) fn is_special_artifact(artifact_type: &ArtifactType) -> bool {
     match artifact_type {
         // Windows special artifacts
         ArtifactType::Windows(WindowsArtifactType::MFT) => true,
         ArtifactType::Windows(WindowsArtifactType::USNJournal) => true,
+        ArtifactType::Windows(WindowsArtifactType::ETWSessions) => true,
+        ArtifactType::Windows(WindowsArtifactType::NetworkShares) => true,
+        ArtifactType::Windows(WindowsArtifactType::PowerShellHistory) => true,
+
+        // Container special artifacts
+        ArtifactType::Container(ContainerType::DockerContainerLogs) => true,
+        ArtifactType::Container(ContainerType::PodmanContainerLogs) => true,
+
+        // macOS special artifacts
+        ArtifactType::MacOS(MacOSArtifactType::Keychains) => true,
+        ArtifactType::MacOS(MacOSArtifactType::AppFirewall) => true,
+        ArtifactType::MacOS(MacOSArtifactType::LoginRecords) => true,
+        ArtifactType::MacOS(MacOSArtifactType::WiFiHistory) => true,
+
+        // Inventoried across user homes rather than copied from one path
+        ArtifactType::CloudCredentials => true,
+        ArtifactType::MailStores => true,
 
         // Other special artifacts that might not have standard paths
         _ => false,
@@ -78,7 +118,7 @@ fn get_destination_path(fs_dir: &Path, artifact: &Artifact) -> PathBuf {
     }
 
     // For regular files, preserve the original path structure
-    let source_path = Path::new(&artifact.source_path);
+    let source_path = Path::new(source::strip_scheme(&artifact.source_path));
 
     // Handle absolute paths by removing the leading separator
     let rel_path = if source_path.is_absolute() {
@@ -102,37 +142,173 @@ fn get_destination_path(fs_dir: &Path, artifact: &Artifact) -> PathBuf {
     fs_dir.join(rel_path)
 }
 
-/// Handle potential duplicate filenames by adding a numeric suffix
-fn handle_duplicate_filename(dest_path: &Path) -> PathBuf {
-    if dest_path.exists() {
-        // Add a numeric suffix to the filename
-        let file_stem = dest_path
-            .file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| "file".to_string());
+/// Destinations handed out during one collection run.
+///
+/// Artifacts are collected concurrently, so checking whether a destination
+/// exists and creating it later lets two artifacts that resolve to the same
+/// path (common after multi-user or multi-drive expansion) both pick it and
+/// overwrite each other. Every destination is claimed here, under one lock,
+/// before any artifact is dispatched.
+#[derive(Debug, Default)]
+struct DestinationRegistry {
+    claimed: std::sync::Mutex<HashSet<String>>,
+}
 
-        let extension = dest_path
-            .extension()
-            .map(|s| format!(".{}", s.to_string_lossy()))
-            .unwrap_or_else(|| "".to_string());
+impl DestinationRegistry {
+    /// Claim `dest_path`, or the first `<stem>_<n><ext>` variant that is
+    /// neither claimed in this run nor already on disk
+    fn claim(&self, dest_path: &Path) -> PathBuf {
+        let mut claimed = self
+            .claimed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
 
+        let mut candidate = dest_path.to_path_buf();
         let mut counter = 1;
-        loop {
-            let new_name = format!("{}_{}{}", file_stem, counter, extension);
-            let new_path = dest_path.with_file_name(new_name);
-            if !new_path.exists() {
-                return new_path;
-            }
+        while claimed.contains(&destination_key(&candidate)) || candidate.exists() {
+            candidate = suffixed_path(dest_path, counter);
             counter += 1;
         }
+        claimed.insert(destination_key(&candidate));
+        candidate
+    }
+}
+
+/// Registry key for a destination; Windows and macOS file systems are
+/// case-insensitive, so `SYSTEM` and `system` are the same destination there
+fn destination_key(path: &Path) -> String {
+    let normalized = normalize_path_for_storage(path);
+    if cfg!(any(windows, target_os = "macos")) {
+        normalized.to_lowercase()
     } else {
-        dest_path.to_path_buf()
+        normalized
     }
 }
 
-/// Normalize path for storage (convert backslashes to forward slashes)
+/// `dest_path` with a numeric suffix added to its file name
+fn suffixed_path(dest_path: &Path, counter: usize) -> PathBuf {
+    let file_stem = dest_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+
+    let extension = dest_path
+        .extension()
+        .map(|s| format!(".{}", s.to_string_lossy()))
+        .unwrap_or_else(|| "".to_string());
+
+    dest_path.with_file_name(format!("{}_{}{}", file_stem, counter, extension))
+}
+
+/// Normalize path for storage (convert backslashes to forward slashes, and
+/// escape bytes that are not valid Unicode)
 fn normalize_path_for_storage(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
+    escaped_path(path).replace('\\', "/")
+}
+
+/// Why an artifact could not be collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// Collection exceeded the artifact's timeout
+    Timeout,
+    /// The source could not be read with the current privileges
+    PermissionDenied,
+    /// The destination would have been written outside the output directory
+    UnsafePath,
+    /// Any other collection error
+    Error,
+}
+
+impl FailureReason {
+    /// Classify a collection error
+    pub fn of(error: &anyhow::Error) -> Self {
+        match CollectorError::find(error) {
+            Some(CollectorError::Timeout { .. }) => FailureReason::Timeout,
+            _ if PermissionTracker::is_permission_failure(error) => FailureReason::PermissionDenied,
+            _ => FailureReason::Error,
+        }
+    }
+}
+
+/// An artifact that failed to collect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactFailure {
+    pub artifact: String,
+    pub source_path: String,
+    pub required: bool,
+    pub reason: FailureReason,
+    pub message: String,
+}
+
+/// Options applied to every artifact in a collection run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionOptions {
+    /// Default per-artifact timeout; `None` waits indefinitely
+    pub artifact_timeout: Option<Duration>,
+    /// Time by which every artifact must finish (`--max-runtime`); those
+    /// still running are aborted and reported in `deadline_aborted`
+    pub cutoff: Option<Instant>,
+}
+
+impl Default for CollectionOptions {
+    fn default() -> Self {
+        Self {
+            artifact_timeout: Some(Duration::from_secs(DEFAULT_ARTIFACT_TIMEOUT_SECS)),
+            cutoff: None,
+        }
+    }
+}
+
+impl CollectionOptions {
+    /// Read options from `global_options` (`artifact_timeout_sec`, 0 disables)
+    pub fn from_config(config: &CollectionConfig) -> Self {
+        let mut options = Self::default();
+        if let Some(value) = config.global_options.get("artifact_timeout_sec") {
+            match parse_timeout(value) {
+                Some(timeout) => options.artifact_timeout = timeout,
+                None => warn!(
+                    "Invalid artifact_timeout_sec '{}'; using {} seconds",
+                    value, DEFAULT_ARTIFACT_TIMEOUT_SECS
+                ),
+            }
+        }
+        options
+    }
+
+    /// Timeout for one artifact, honouring its `timeout_sec` metadata
+    pub fn timeout_for(&self, artifact: &Artifact) -> Option<Duration> {
+        match artifact.metadata.get("timeout_sec") {
+            Some(value) => parse_timeout(value).unwrap_or_else(|| {
+                warn!(
+                    "Invalid timeout_sec '{}' for artifact {}; using the default",
+                    value, artifact.name
+                );
+                self.artifact_timeout
+            }),
+            None => self.artifact_timeout,
+        }
+    }
+}
+
+/// Parse a timeout in seconds; `Some(None)` means no timeout
+fn parse_timeout(value: &str) -> Option<Option<Duration>> {
+    match value.trim().parse::<u64>().ok()? {
+        0 => Some(None),
+        seconds => Some(Some(Duration::from_secs(seconds))),
+    }
+}
+
+/// Artifacts collected in a run, and those that failed
+#[derive(Debug, Clone, Default)]
+pub struct CollectionOutcome {
+    /// Metadata keyed by path relative to the base directory
+    pub collected: HashMap<String, ArtifactMetadata>,
+    pub failures: Vec<ArtifactFailure>,
+    /// Names of artifacts not attempted because the platform does not support them
+    pub skipped: Vec<String>,
+    /// Names of artifacts aborted at the `cutoff`
+    pub deadline_aborted: Vec<String>,
 }
 
 /// Collect artifacts based on configuration with parallel execution
@@ -140,6 +316,197 @@ pub async fn collect_artifacts_parallel(
     artifacts: &[Artifact],
     base_dir: &Path,
 ) -> Result<HashMap<String, ArtifactMetadata>> {
+    let outcome =
+        collect_artifacts_parallel_with_options(artifacts, base_dir, &CollectionOptions::default())
+            .await?;
+    Ok(outcome.collected)
+}
+
+/// Collect artifacts in parallel, reporting failures and applying timeouts
+pub async fn collect_artifacts_parallel_with_options(
+    artifacts: &[Artifact],
+    base_dir: &Path,
+    options: &CollectionOptions,
+) -> Result<CollectionOutcome> {
+    // Create a rate limiter to control concurrent artifact collection
+    // This prevents overwhelming the system with too many concurrent I/O operations
+    let max_concurrent = std::cmp::min(num_cpus::get() * 2, 32); // Limit concurrency
+    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    // With --max-cpu-percent or --max-memory-mb, permits are withheld while
+    // the collector is over its limits
+    resource_limits::govern(&semaphore, max_concurrent);
+
+    // Get the platform-specific collector
+    let collector: Arc<dyn ArtifactCollector> = Arc::from(platforms::get_platform_collector());
+
+    collect_with_collector(artifacts, base_dir, options, collector, semaphore).await
+}
+
+/// Collect one artifact, returning metadata keyed by relative path
+async fn collect_one(
+    collector: &dyn ArtifactCollector,
+    artifact: &Artifact,
+    output_dir: &Path,
+    final_output_path: &Path,
+    base_dir: &Path,
+) -> Result<Vec<(String, ArtifactMetadata)>> {
+    // Check if this is a regex-based artifact
+    if artifact.regex.as_ref().is_some_and(|r| r.enabled) {
+        // Use regex collector for this artifact
+        let (source, source_path) = source::resolve(&artifact.source_path)?;
+        let regex_collector = RegexCollector::with_source(source);
+        let source_path = PathBuf::from(source_path);
+        let collected_items = regex_collector
+            .collect_with_regex(artifact, &source_path, output_dir)
+            .await?;
+
+        return Ok(collected_items
+            .into_iter()
+            .map(|(path, metadata)| {
+                let relative_path =
+                    normalize_path_for_storage(path.strip_prefix(base_dir).unwrap_or(&path));
+                (relative_path, metadata)
+            })
+            .collect());
+    }
+
+    // Cloud credential files are inventoried rather than copied, the same
+    // way on every platform
+    if artifact.artifact_type == ArtifactType::CloudCredentials {
+        let source_path = PathBuf::from(source::strip_scheme(&artifact.source_path));
+        let output_path = final_output_path.to_path_buf();
+        let metadata = tokio::task::spawn_blocking(move || {
+            cloud_credentials::collect_cloud_credentials(&source_path, &output_path)
+        })
+        .await
+        .context("Task join error")??;
+
+        let relative_path = normalize_path_for_storage(
+            final_output_path
+                .strip_prefix(base_dir)
+                .unwrap_or(final_output_path),
+        );
+        return Ok(vec![(relative_path, metadata)]);
+    }
+
+    // Mail stores are located per user and listed unless the artifact
+    // opts into copying them
+    if artifact.artifact_type == ArtifactType::MailStores {
+        let options = MailStoreOptions::from_artifact(artifact)?;
+        let source_path = PathBuf::from(source::strip_scheme(&artifact.source_path));
+        let output_path = final_output_path.to_path_buf();
+        let metadata = tokio::task::spawn_blocking(move || {
+            mail_stores::collect_mail_stores(&source_path, &output_path, &options)
+        })
+        .await
+        .context("Task join error")??;
+
+        let relative_path = normalize_path_for_storage(
+            final_output_path
+                .strip_prefix(base_dir)
+                .unwrap_or(final_output_path),
+        );
+        return Ok(vec![(relative_path, metadata)]);
+    }
+
+    // Standard collection writes to the claimed destination itself.
+    // Exclusions, time filters and metadata-only listings are applied by
+    // the generic directory walker rather than type-specific collection.
+    let walk = WalkOptions::from_artifact(artifact)?;
+
+    // A single file unchanged since the --baseline collection is left there
+    let baseline_file = if walk.metadata_only {
+        None
+    } else {
+        compare_with_baseline(artifact).await
+    };
+    if let Some((key, _, true)) = &baseline_file {
+        debug!("{} unchanged since the baseline collection", key);
+        return Ok(Vec::new());
+    }
+
+    let result = if walk.is_default() {
+        collector.collect(artifact, final_output_path).await
+    } else {
+        FallbackCollector::new()
+            .with_walk_options(walk)
+            .collect(artifact, final_output_path)
+            .await
+    };
+
+    // Encryption is only detectable on the live system's own files
+    let resolved = source::resolve(&artifact.source_path).ok();
+    let live = resolved
+        .as_ref()
+        .is_some_and(|(source, _)| source.is_live_system());
+    let mut metadata = match result {
+        Ok(metadata) => metadata,
+        Err(e) if live => {
+            let source_path = Path::new(source::strip_scheme(&artifact.source_path));
+            return Err(encryption::explain_failure(e, source_path));
+        }
+        Err(e) => return Err(e),
+    };
+    if live {
+        encryption::mark_encrypted(&mut metadata);
+    }
+    if let Some((key, source_metadata, _)) = &baseline_file {
+        incremental::record_collected(key, source_metadata.len, rfc3339(source_metadata.modified));
+    }
+    if let Some((source, path)) = &resolved {
+        source.annotate(Path::new(path), &mut metadata);
+    }
+
+    // Raw EFS captures are written next to the claimed destination
+    let output_path = if metadata.fallback_method.as_deref() == Some(efs::FALLBACK_METHOD) {
+        efs::raw_path(final_output_path)
+    } else {
+        final_output_path.to_path_buf()
+    };
+
+    // Create a relative path for the result that preserves the original structure
+    let relative_path =
+        normalize_path_for_storage(output_path.strip_prefix(base_dir).unwrap_or(&output_path));
+    Ok(vec![(relative_path, metadata)])
+}
+
+/// Path and metadata of an artifact that is a single file on the live
+/// system, and whether it is unchanged since the `--baseline` collection.
+/// Memory artifacts and those without a plain source path are always
+/// collected.
+async fn compare_with_baseline(artifact: &Artifact) -> Option<(String, SourceMetadata, bool)> {
+    if is_special_artifact(&artifact.artifact_type)
+        || artifact.artifact_type == ArtifactType::Memory
+    {
+        return None;
+    }
+    let (source, path) = source::resolve(&artifact.source_path).ok()?;
+    if !source.is_live_system() {
+        return None;
+    }
+    let path = PathBuf::from(resolve_all_variables(path));
+    tokio::task::spawn_blocking(move || {
+        let metadata = source::block_on(source.stat(&path))
+            .ok()
+            .filter(|metadata| !metadata.is_dir)?;
+        let key = path.to_string_lossy().to_string();
+        let unchanged = incremental::is_unchanged(&key, metadata.len, metadata.modified, || {
+            incremental::source_sha256(source.as_ref(), &path)
+        });
+        Some((key, metadata, unchanged))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+async fn collect_with_collector(
+    artifacts: &[Artifact],
+    base_dir: &Path,
+    options: &CollectionOptions,
+    collector: Arc<dyn ArtifactCollector>,
+    semaphore: Arc<Semaphore>,
+) -> Result<CollectionOutcome> {
     // Make sure base directory exists
     tokio::fs::create_dir_all(base_dir)
         .await
@@ -151,33 +518,50 @@ pub async fn collect_artifacts_parallel(
         .await
         .context("Failed to create fs directory")?;
 
-    // Create a rate limiter to control concurrent artifact collection
-    // This prevents overwhelming the system with too many concurrent I/O operations
-    let max_concurrent = std::cmp::min(num_cpus::get() * 2, 32); // Limit concurrency
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
-
     // Create permission tracker to monitor permission-related failures
     let permission_tracker = Arc::new(PermissionTracker::new());
 
-    // Get the platform-specific collector
-    let collector = Arc::new(platforms::get_platform_collector());
-
     // Filter artifacts for the current platform
-    let platform_artifacts = platforms::filter_artifacts_for_platform(artifacts);
-
-    // Shared results map protected by a mutex
-    let results = Arc::new(Mutex::new(HashMap::new()));
+    let platform_artifacts: Vec<Artifact> = artifacts
+        .iter()
+        .filter(|artifact| collector.supports_artifact_type(&artifact.artifact_type))
+        .cloned()
+        .collect();
+
+    let skipped: Vec<String> = artifacts
+        .iter()
+        .filter(|artifact| !collector.supports_artifact_type(&artifact.artifact_type))
+        .map(|artifact| artifact.name.clone())
+        .collect();
+
+    // Shared results protected by a mutex
+    let outcome = Arc::new(Mutex::new(CollectionOutcome {
+        skipped,
+        ..Default::default()
+    }));
+
+    let destinations = DestinationRegistry::default();
 
     // Process all artifacts in parallel with controlled concurrency
     let futures = platform_artifacts.iter().map(|artifact| {
+        // Configs may come from less-trusted sources, so refuse destinations
+        // that leave the fs directory before anything is created. Claim the
+        // rest now, in config order, so concurrent artifacts resolving to
+        // the same path get distinct names
+        let destination = validate_destination(&get_destination_path(&fs_dir, artifact), &fs_dir)
+            .map(|path| destinations.claim(&path));
+
         // Clone references for the async block
         let collector = Arc::clone(&collector);
-        let results = Arc::clone(&results);
+        let outcome = Arc::clone(&outcome);
         let semaphore = Arc::clone(&semaphore);
         let permission_tracker = Arc::clone(&permission_tracker);
         let artifact = artifact.clone(); // Clone the artifact for the async move block
         let fs_dir = fs_dir.clone();
         let base_dir = base_dir.to_path_buf();
+        let timeout = options.timeout_for(&artifact);
+        let cutoff = options.cutoff;
+        let dispatched = Instant::now();
 
         async move {
             // Acquire a permit from the semaphore, limiting concurrency
@@ -188,189 +572,140 @@ pub async fn collect_artifacts_parallel(
                         "Failed to acquire semaphore permit for artifact: {}",
                         artifact.name
                     );
-                    return (
-                        artifact,
-                        Err(anyhow::anyhow!("Failed to acquire semaphore permit")),
-                    );
+                    return;
+                }
+            };
+            let queue_wait = dispatched.elapsed();
+
+            let final_output_path = match destination {
+                Ok(path) => path,
+                Err(e) => {
+                    log_security_event(SecurityEvent::PathValidationFailed {
+                        path: artifact.source_path.clone(),
+                        reason: e.to_string(),
+                    });
+                    outcome.lock().await.failures.push(ArtifactFailure {
+                        artifact: artifact.name.clone(),
+                        source_path: artifact.source_path.clone(),
+                        required: artifact.required,
+                        reason: FailureReason::UnsafePath,
+                        message: e.to_string(),
+                    });
+                    return;
                 }
             };
 
             info!("Collecting artifact: {}", artifact.name);
 
-            // Determine output path based on original file path
-            let output_path = get_destination_path(&fs_dir, &artifact);
-
-            // Handle potential duplicate filenames
-            let final_output_path = handle_duplicate_filename(&output_path);
+            let output_dir = final_output_path.parent().unwrap_or(&fs_dir);
 
             // Create parent directories if they don't exist
-            if let Some(parent) = final_output_path.parent() {
-                if let Err(e) = tokio::fs::create_dir_all(parent).await {
-                    warn!("Failed to create directory {}: {}", parent.display(), e);
-                }
+            if let Err(e) = tokio::fs::create_dir_all(output_dir).await {
+                warn!("Failed to create directory {}: {}", output_dir.display(), e);
             }
 
-            // Check if this is a regex-based artifact
-            if let Some(regex_config) = &artifact.regex {
-                if regex_config.enabled {
-                    // Use regex collector for this artifact
-                    let regex_collector = RegexCollector::new();
-                    let source_path = PathBuf::from(&artifact.source_path);
-
-                    match regex_collector
-                        .collect_with_regex(
-                            &artifact,
-                            &source_path,
-                            &final_output_path.parent().unwrap_or(&fs_dir),
-                        )
-                        .await
-                    {
-                        Ok(collected_items) => {
-                            let mut map = results.lock().await;
-                            for (path, metadata) in collected_items {
-                                let relative_path = normalize_path_for_storage(
-                                    &path.strip_prefix(&base_dir).unwrap_or(&path),
-                                );
-                                map.insert(relative_path, metadata);
-                            }
-                            info!("Successfully collected regex artifact: {}", artifact.name);
-                        }
-                        Err(e) => {
-                            // If the artifact is required, report the error but continue
-                            let error_msg = e.to_string();
-
-                            // Track permission failures
-                            if PermissionTracker::is_permission_error(&error_msg) {
-                                permission_tracker
-                                    .record_permission_failure(&artifact.name)
-                                    .await;
-                            }
-
-                            if artifact.required {
-                                if error_msg.contains("Permission denied") {
-                                    warn!(
-                                        "⚠️  Failed to collect required regex artifact '{}': {}",
-                                        artifact.name, error_msg
-                                    );
-                                } else {
-                                    warn!(
-                                        "Failed to collect required regex artifact '{}': {}",
-                                        artifact.name, error_msg
-                                    );
-                                }
-                            } else {
-                                debug!(
-                                    "Failed to collect optional regex artifact '{}': {}",
-                                    artifact.name, error_msg
-                                );
-                            }
-                        }
-                    }
-                } else {
-                    // Standard collection for non-regex artifacts
-                    match collector
-                        .collect(&artifact, &final_output_path.parent().unwrap_or(&fs_dir))
-                        .await
-                    {
-                        Ok(metadata) => {
-                            // Create a relative path for the result that preserves the original structure
-                            let relative_path = normalize_path_for_storage(
-                                &final_output_path
-                                    .strip_prefix(&base_dir)
-                                    .unwrap_or(&final_output_path),
-                            );
+            // A previous run may have cancelled this source after a timeout
+            cancellation::resume(&artifact.source_path);
 
-                            // Add result to the shared map
-                            let mut map = results.lock().await;
-                            map.insert(relative_path, metadata);
-                            info!("Successfully collected: {}", artifact.name);
-                        }
-                        Err(e) => {
-                            // If the artifact is required, report the error but continue
-                            let error_msg = e.to_string();
-
-                            // Track permission failures
-                            if PermissionTracker::is_permission_error(&error_msg) {
-                                permission_tracker
-                                    .record_permission_failure(&artifact.name)
-                                    .await;
-                            }
-
-                            if artifact.required {
-                                if error_msg.contains("Permission denied") {
-                                    warn!(
-                                        "⚠️  Failed to collect required artifact '{}': {}",
-                                        artifact.name, error_msg
-                                    );
-                                } else {
-                                    warn!(
-                                        "Failed to collect required artifact '{}': {}",
-                                        artifact.name, error_msg
-                                    );
-                                }
-                            } else {
-                                debug!(
-                                    "Failed to collect optional artifact '{}': {}",
-                                    artifact.name, error_msg
-                                );
-                            }
+            // The cutoff applies when it comes before the artifact's own timeout
+            let started = Instant::now();
+            let budget = cutoff.map(|cutoff| cutoff.saturating_duration_since(started));
+            let (timeout, by_deadline) = match (timeout, budget) {
+                (Some(limit), Some(budget)) if budget < limit => (Some(budget), true),
+                (None, Some(budget)) => (Some(budget), true),
+                (timeout, _) => (timeout, false),
+            };
+            let collection = collect_one(
+                collector.as_ref(),
+                &artifact,
+                output_dir,
+                &final_output_path,
+                &base_dir,
+            );
+            let result = match timeout {
+                Some(limit) => match tokio::time::timeout(limit, collection).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        // spawn_blocking work can't be killed; ask copy loops to stop
+                        cancellation::cancel(&artifact.source_path);
+                        Err(CollectorError::Timeout {
+                            artifact_name: artifact.name.clone(),
+                            seconds: limit.as_secs(),
                         }
+                        .into())
                     }
+                },
+                None => collection.await,
+            };
+            metrics::record_with(|| Measurement::Artifact {
+                name: artifact.name.clone(),
+                elapsed: started.elapsed(),
+                queue_wait,
+                bytes: result
+                    .as_ref()
+                    .map(|items| items.iter().map(|(_, m)| m.file_size).sum())
+                    .unwrap_or(0),
+                succeeded: result.is_ok(),
+            });
+
+            if let (true, Err(e)) = (by_deadline, &result) {
+                if FailureReason::of(e) == FailureReason::Timeout {
+                    warn!("Aborted {} at the --max-runtime cutoff", artifact.name);
+                    outcome
+                        .lock()
+                        .await
+                        .deadline_aborted
+                        .push(artifact.name.clone());
+                    return;
                 }
-            } else {
-                // Standard collection for non-regex artifacts
-                match collector
-                    .collect(&artifact, &final_output_path.parent().unwrap_or(&fs_dir))
-                    .await
-                {
-                    Ok(metadata) => {
-                        // Create a relative path for the result that preserves the original structure
-                        let relative_path = normalize_path_for_storage(
-                            &final_output_path
-                                .strip_prefix(&base_dir)
-                                .unwrap_or(&final_output_path),
-                        );
+            }
 
-                        // Add result to the shared map
-                        let mut map = results.lock().await;
-                        map.insert(relative_path, metadata);
-                        info!("Successfully collected: {}", artifact.name);
+            match result {
+                Ok(collected_items) => {
+                    let mut outcome = outcome.lock().await;
+                    outcome.collected.extend(collected_items);
+                    info!("Successfully collected: {}", artifact.name);
+                }
+                Err(e) => {
+                    // If the artifact is required, report the error but continue
+                    let error_msg = e.to_string();
+                    let reason = FailureReason::of(&e);
+
+                    // Track permission failures
+                    if reason == FailureReason::PermissionDenied {
+                        permission_tracker
+                            .record_permission_failure(&artifact.name)
+                            .await;
                     }
-                    Err(e) => {
-                        // If the artifact is required, report the error but continue
-                        let error_msg = e.to_string();
-
-                        // Track permission failures
-                        if PermissionTracker::is_permission_error(&error_msg) {
-                            permission_tracker
-                                .record_permission_failure(&artifact.name)
-                                .await;
-                        }
 
-                        if artifact.required {
-                            if error_msg.contains("Permission denied") {
-                                warn!(
-                                    "⚠️  Failed to collect required artifact '{}': {}",
-                                    artifact.name, error_msg
-                                );
-                            } else {
-                                warn!(
-                                    "Failed to collect required artifact '{}': {}",
-                                    artifact.name, error_msg
-                                );
-                            }
+                    if artifact.required {
+                        if reason == FailureReason::Error {
+                            warn!(
+                                "Failed to collect required artifact '{}': {}",
+                                artifact.name, error_msg
+                            );
                         } else {
-                            debug!(
-                                "Failed to collect optional artifact '{}': {}",
+                            warn!(
+                                "⚠️  Failed to collect required artifact '{}': {}",
                                 artifact.name, error_msg
                             );
                         }
+                    } else {
+                        debug!(
+                            "Failed to collect optional artifact '{}': {}",
+                            artifact.name, error_msg
+                        );
                     }
+
+                    outcome.lock().await.failures.push(ArtifactFailure {
+                        artifact: artifact.name.clone(),
+                        source_path: artifact.source_path.clone(),
+                        required: artifact.required,
+                        reason,
+                        message: error_msg,
+                    });
                 }
             }
-
-            // Return the expected tuple
-            (artifact, Ok(()))
         }
         .boxed()
     });
@@ -382,8 +717,8 @@ pub async fn collect_artifacts_parallel(
     permission_tracker.report_failures().await;
 
     // Extract results from the mutex
-    let final_results = results.lock().await.clone();
-    Ok(final_results)
+    let final_outcome = outcome.lock().await.clone();
+    Ok(final_outcome)
 }
 
 /// Legacy synchronous collection function that calls the async implementation.
@@ -423,6 +758,22 @@ pub fn collect_artifacts(
     artifacts: &[Artifact],
     base_dir: &Path,
 ) -> Result<HashMap<String, ArtifactMetadata>> {
+    run_collection(collect_artifacts_parallel(artifacts, base_dir))
+}
+
+/// Synchronous form of [`collect_artifacts_parallel_with_options`].
+pub fn collect_artifacts_with_options(
+    artifacts: &[Artifact],
+    base_dir: &Path,
+    options: &CollectionOptions,
+) -> Result<CollectionOutcome> {
+    run_collection(collect_artifacts_parallel_with_options(
+        artifacts, base_dir, options,
+    ))
+}
+
+/// Run a collection on a new runtime
+fn run_collection<T>(collection: impl Future<Output = Result<T>>) -> Result<T> {
     // Create a new runtime for running the async function
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(num_cpus::get())
@@ -431,7 +782,12 @@ pub fn collect_artifacts(
         .context("Failed to create Tokio runtime")?;
 
     // Run the async function in the runtime
-    runtime.block_on(collect_artifacts_parallel(artifacts, base_dir))
+    let result = runtime.block_on(collection);
+
+    // Copies abandoned after a timeout may still be blocked in the kernel;
+    // dropping the runtime normally would wait for them
+    runtime.shutdown_background();
+    result
 }
 
 #[cfg(test)]
@@ -439,6 +795,7 @@ mod tests {
     use super::*;
     use crate::config::{LinuxArtifactType, MacOSArtifactType};
     use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use tempfile::TempDir;
 
     // Mock collector for testing
@@ -452,18 +809,17 @@ mod tests {
         async fn collect(
             &self,
             artifact: &Artifact,
-            output_dir: &Path,
+            output_path: &Path,
         ) -> Result<ArtifactMetadata> {
             if self.should_fail {
                 return Err(anyhow::anyhow!("Mock failure"));
             }
 
             // Create a dummy file
-            let dest_path = output_dir.join(&artifact.destination_name);
-            if let Some(parent) = dest_path.parent() {
+            if let Some(parent) = output_path.parent() {
                 fs::create_dir_all(parent)?;
             }
-            fs::write(&dest_path, "mock content")?;
+            fs::write(output_path, "mock content")?;
 
             Ok(ArtifactMetadata {
                 original_path: artifact.source_path.clone(),
@@ -473,6 +829,8 @@ mod tests {
                 accessed_time: None,
                 modified_time: None,
                 is_locked: false,
+                sha256: None,
+                ..Default::default()
             })
         }
 
@@ -588,40 +946,36 @@ mod tests {
     }
 
     #[test]
-    fn test_handle_duplicate_filename() {
+    fn test_destination_registry_claims_unique_names() {
         let temp_dir = TempDir::new().unwrap();
         let base_path = temp_dir.path().join("test.txt");
+        let registry = DestinationRegistry::default();
 
-        // First call should return the original path
-        let path1 = handle_duplicate_filename(&base_path);
-        assert_eq!(path1, base_path);
-
-        // Create the file
-        fs::write(&path1, "content1").unwrap();
-
-        // Second call should add _1
-        let path2 = handle_duplicate_filename(&base_path);
-        assert_eq!(path2, temp_dir.path().join("test_1.txt"));
-
-        // Create the second file
-        fs::write(&path2, "content2").unwrap();
+        // First claim returns the original path
+        assert_eq!(registry.claim(&base_path), base_path);
 
-        // Third call should add _2
-        let path3 = handle_duplicate_filename(&base_path);
-        assert_eq!(path3, temp_dir.path().join("test_2.txt"));
+        // Later claims add a suffix even before anything is written
+        assert_eq!(
+            registry.claim(&base_path),
+            temp_dir.path().join("test_1.txt")
+        );
+        assert_eq!(
+            registry.claim(&base_path),
+            temp_dir.path().join("test_2.txt")
+        );
     }
 
     #[test]
-    fn test_handle_duplicate_filename_no_extension() {
+    fn test_destination_registry_skips_existing_files() {
         let temp_dir = TempDir::new().unwrap();
         let base_path = temp_dir.path().join("test");
 
-        // Create the file
+        // Create the file, as a previous run would have
         fs::write(&base_path, "content").unwrap();
 
         // Should add _1 without extension
-        let path2 = handle_duplicate_filename(&base_path);
-        assert_eq!(path2, temp_dir.path().join("test_1"));
+        let registry = DestinationRegistry::default();
+        assert_eq!(registry.claim(&base_path), temp_dir.path().join("test_1"));
     }
 
     #[test]
@@ -682,7 +1036,9 @@ mod tests {
         let fs_dir = temp_dir.path().join("fs");
         fs::create_dir_all(&fs_dir).unwrap();
 
-        let metadata = collector.collect(&artifact, &fs_dir).await.unwrap();
+        let output_path = fs_dir.join("file.txt");
+        let metadata = collector.collect(&artifact, &output_path).await.unwrap();
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), "mock content");
         assert_eq!(metadata.file_size, 12);
         assert_eq!(metadata.original_path, "/test/file.txt");
     }
@@ -712,6 +1068,31 @@ mod tests {
         assert_eq!(result.unwrap_err().to_string(), "Mock failure");
     }
 
+    #[tokio::test]
+    async fn test_unsupported_artifacts_reported_as_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        let collector: Arc<dyn ArtifactCollector> = Arc::new(MockCollector {
+            supported_types: vec![ArtifactType::FileSystem],
+            should_fail: false,
+        });
+        let mut syslog = file_artifact("syslog", "/var/log/syslog");
+        syslog.artifact_type = ArtifactType::Linux(LinuxArtifactType::SysLogs);
+        let artifacts = [file_artifact("file", "/test/file.txt"), syslog];
+
+        let outcome = collect_with_collector(
+            &artifacts,
+            temp_dir.path(),
+            &CollectionOptions::default(),
+            collector,
+            Arc::new(Semaphore::new(2)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.collected.len(), 1);
+        assert_eq!(outcome.skipped, vec!["syslog".to_string()]);
+    }
+
     #[test]
     fn test_legacy_collect_artifacts() {
         let temp_dir = TempDir::new().unwrap();
@@ -798,21 +1179,22 @@ mod tests {
     }
 
     #[test]
-    fn test_handle_duplicate_filename_complex_extensions() {
+    fn test_destination_registry_complex_extensions() {
         let temp_dir = TempDir::new().unwrap();
+        let registry = DestinationRegistry::default();
 
         // Test file with multiple dots
         let base_path = temp_dir.path().join("file.tar.gz");
         fs::write(&base_path, "content").unwrap();
 
-        let path2 = handle_duplicate_filename(&base_path);
+        let path2 = registry.claim(&base_path);
         assert_eq!(path2, temp_dir.path().join("file.tar_1.gz"));
 
         // Test file with no stem
         let hidden_path = temp_dir.path().join(".hidden");
         fs::write(&hidden_path, "content").unwrap();
 
-        let path3 = handle_duplicate_filename(&hidden_path);
+        let path3 = registry.claim(&hidden_path);
         assert_eq!(path3, temp_dir.path().join(".hidden_1"));
     }
 
@@ -916,6 +1298,11 @@ mod tests {
         let special_types = vec![
             ArtifactType::Windows(MFT),
             ArtifactType::Windows(USNJournal),
+            ArtifactType::Windows(ETWSessions),
+            ArtifactType::Windows(NetworkShares),
+            ArtifactType::MacOS(MacOSArtifactType::Keychains),
+            ArtifactType::CloudCredentials,
+            ArtifactType::MailStores,
         ];
 
         let normal_types = vec![
@@ -968,4 +1355,287 @@ mod tests {
             assert_eq!(max_concurrent, 32);
         }
     }
+
+    /// Collector whose "slow" artifact blocks until its source is cancelled
+    struct SlowCollector {
+        stopped: Arc<AtomicBool>,
+    }
+
+    #[async_trait::async_trait]
+    impl ArtifactCollector for SlowCollector {
+        async fn collect(
+            &self,
+            artifact: &Artifact,
+            output_dir: &Path,
+        ) -> Result<ArtifactMetadata> {
+            if artifact.name != "slow" {
+                let mock = MockCollector {
+                    supported_types: vec![],
+                    should_fail: false,
+                };
+                return mock.collect(artifact, output_dir).await;
+            }
+
+            let source = PathBuf::from(&artifact.source_path);
+            let stopped = Arc::clone(&self.stopped);
+            tokio::task::spawn_blocking(move || {
+                while !cancellation::is_cancelled(&source) {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                stopped.store(true, Ordering::SeqCst);
+            })
+            .await?;
+            Err(anyhow::anyhow!("Copy cancelled"))
+        }
+
+        fn supports_artifact_type(&self, _artifact_type: &ArtifactType) -> bool {
+            true
+        }
+    }
+
+    fn file_artifact(name: &str, source_path: &str) -> Artifact {
+        Artifact {
+            name: name.to_string(),
+            artifact_type: ArtifactType::FileSystem,
+            source_path: source_path.to_string(),
+            destination_name: format!("{}.txt", name),
+            description: None,
+            required: true,
+            metadata: HashMap::new(),
+            regex: None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_timeout_releases_permit() {
+        let temp_dir = TempDir::new().unwrap();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let collector: Arc<dyn ArtifactCollector> = Arc::new(SlowCollector {
+            stopped: Arc::clone(&stopped),
+        });
+        // With one permit the fast artifact only runs if the slow one frees it
+        let semaphore = Arc::new(Semaphore::new(1));
+        let options = CollectionOptions {
+            artifact_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let artifacts = [
+            file_artifact("slow", "/mnt/hung-nfs/timeout-test"),
+            file_artifact("fast", "/test/fast.txt"),
+        ];
+
+        let outcome = collect_with_collector(
+            &artifacts,
+            temp_dir.path(),
+            &options,
+            collector,
+            Arc::clone(&semaphore),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.collected.len(), 1);
+        assert_eq!(outcome.failures.len(), 1);
+        assert_eq!(outcome.failures[0].artifact, "slow");
+        assert_eq!(outcome.failures[0].reason, FailureReason::Timeout);
+        assert_eq!(semaphore.available_permits(), 1);
+
+        // The abandoned blocking copy sees the cancellation and stops
+        for _ in 0..100 {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cutoff_aborts_in_flight_artifacts() {
+        let temp_dir = TempDir::new().unwrap();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let collector: Arc<dyn ArtifactCollector> = Arc::new(SlowCollector {
+            stopped: Arc::clone(&stopped),
+        });
+        // The cutoff comes well before the artifact timeout
+        let options = CollectionOptions {
+            artifact_timeout: Some(Duration::from_secs(300)),
+            cutoff: Some(Instant::now() + Duration::from_millis(200)),
+        };
+        let artifacts = [
+            file_artifact("slow", "/mnt/hung-nfs/deadline-test"),
+            file_artifact("fast", "/test/fast.txt"),
+        ];
+
+        let started = Instant::now();
+        let outcome = collect_with_collector(
+            &artifacts,
+            temp_dir.path(),
+            &options,
+            collector,
+            Arc::new(Semaphore::new(2)),
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(outcome.collected.len(), 1);
+        // Aborted by the deadline, which is not a failure
+        assert_eq!(outcome.deadline_aborted, vec!["slow".to_string()]);
+        assert!(outcome.failures.is_empty());
+
+        for _ in 0..100 {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    /// Collector that writes the artifact's name, so overwrites are visible
+    struct NameWritingCollector;
+
+    #[async_trait::async_trait]
+    impl ArtifactCollector for NameWritingCollector {
+        async fn collect(
+            &self,
+            artifact: &Artifact,
+            output_path: &Path,
+        ) -> Result<ArtifactMetadata> {
+            // Interleave with the other artifacts before writing
+            tokio::task::yield_now().await;
+            tokio::fs::write(output_path, &artifact.name).await?;
+
+            Ok(ArtifactMetadata {
+                original_path: artifact.source_path.clone(),
+                collection_time: chrono::Utc::now().to_rfc3339(),
+                file_size: artifact.name.len() as u64,
+                created_time: None,
+                accessed_time: None,
+                modified_time: None,
+                is_locked: false,
+                sha256: None,
+                ..Default::default()
+            })
+        }
+
+        fn supports_artifact_type(&self, _artifact_type: &ArtifactType) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_same_destination_keeps_every_artifact() {
+        let temp_dir = TempDir::new().unwrap();
+        let artifacts: Vec<Artifact> = (0..64)
+            .map(|i| file_artifact(&format!("user{}", i), "/home/shared/.bash_history"))
+            .collect();
+
+        let outcome = collect_with_collector(
+            &artifacts,
+            temp_dir.path(),
+            &CollectionOptions::default(),
+            Arc::new(NameWritingCollector),
+            Arc::new(Semaphore::new(16)),
+        )
+        .await
+        .unwrap();
+
+        assert!(outcome.failures.is_empty());
+        assert_eq!(outcome.collected.len(), artifacts.len());
+
+        // Every recorded path holds a different artifact's content
+        let mut contents: Vec<String> = outcome
+            .collected
+            .keys()
+            .map(|relative| fs::read_to_string(temp_dir.path().join(relative)).unwrap())
+            .collect();
+        contents.sort();
+        let mut names: Vec<String> = artifacts.iter().map(|a| a.name.clone()).collect();
+        names.sort();
+        assert_eq!(contents, names);
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_destinations_are_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+
+        let special = |name: &str, destination_name: &str| Artifact {
+            artifact_type: ArtifactType::Windows(WindowsArtifactType::MFT),
+            destination_name: destination_name.to_string(),
+            ..file_artifact(name, "$MFT")
+        };
+        let mut artifacts = vec![
+            special("dotdot", "../../../../etc/cron.d/evil"),
+            special("absolute", &outside.path().join("evil").to_string_lossy()),
+            special("drive", "C:evil"),
+            file_artifact("source-dotdot", "/var/../../../evil"),
+            file_artifact("ok", "/var/log/syslog"),
+        ];
+
+        // A directory inside fs/ that points outside the output tree
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(temp_dir.path().join("fs")).unwrap();
+            std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("fs/linked")).unwrap();
+            artifacts.push(file_artifact("symlink", "/linked/nested/evil"));
+        }
+
+        let outcome = collect_with_collector(
+            &artifacts,
+            temp_dir.path(),
+            &CollectionOptions::default(),
+            Arc::new(NameWritingCollector),
+            Arc::new(Semaphore::new(4)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.collected.len(), 1);
+        assert!(outcome.collected.contains_key("fs/var/log/syslog"));
+        assert_eq!(outcome.failures.len(), artifacts.len() - 1);
+        assert!(outcome
+            .failures
+            .iter()
+            .all(|f| f.reason == FailureReason::UnsafePath));
+        assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_artifact_timeout_options() {
+        let mut config = CollectionConfig::default();
+        assert_eq!(
+            CollectionOptions::from_config(&config).artifact_timeout,
+            Some(Duration::from_secs(DEFAULT_ARTIFACT_TIMEOUT_SECS))
+        );
+
+        config
+            .global_options
+            .insert("artifact_timeout_sec".to_string(), "30".to_string());
+        let options = CollectionOptions::from_config(&config);
+        assert_eq!(options.artifact_timeout, Some(Duration::from_secs(30)));
+
+        let mut artifact = file_artifact("nfs", "/mnt/nfs");
+        assert_eq!(
+            options.timeout_for(&artifact),
+            Some(Duration::from_secs(30))
+        );
+        artifact
+            .metadata
+            .insert("timeout_sec".to_string(), "5".to_string());
+        assert_eq!(options.timeout_for(&artifact), Some(Duration::from_secs(5)));
+        artifact
+            .metadata
+            .insert("timeout_sec".to_string(), "0".to_string());
+        assert_eq!(options.timeout_for(&artifact), None);
+        artifact
+            .metadata
+            .insert("timeout_sec".to_string(), "soon".to_string());
+        assert_eq!(
+            options.timeout_for(&artifact),
+            Some(Duration::from_secs(30))
+        );
+    }
 }
@@ -0,0 +1,348 @@
+//! Collector footprint tracking.
+//!
+//! Records what the collector itself changed or perturbed on the target
+//! system so the answer to "what did your tool touch?" is part of the
+//! evidence rather than a recollection:
+//!
+//! - files and directories created (output directory, scratch space, archives)
+//! - registry keys or services touched
+//! - external processes spawned, with their arguments
+//! - filesystem snapshots created and removed (`--use-snapshots`)
+//! - bytes read per volume, as an approximation of page-cache perturbation
+//!
+//! The tracker is process-global so collectors can record into it without
+//! threading state through every call. External commands should be started
+//! through [`run_command`], which records them automatically.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use sysinfo::{DiskExt, System, SystemExt};
+
+/// Name of the footprint report written into the output directory
+pub const FOOTPRINT_FILE: &str = "collector_footprint.json";
+
+lazy_static! {
+    static ref FOOTPRINT: Mutex<CollectorFootprint> = Mutex::new(CollectorFootprint::default());
+    static ref MOUNT_POINTS: Vec<PathBuf> = load_mount_points();
+}
+
+/// A file or directory created by the collector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedPath {
+    pub path: String,
+    /// "file" or "directory"
+    pub kind: String,
+    pub timestamp: String,
+}
+
+/// A registry key or service the collector created, modified or deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryTouch {
+    pub key: String,
+    pub action: String,
+    pub timestamp: String,
+}
+
+/// An external process started by the collector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnedProcess {
+    pub program: String,
+    pub args: Vec<String>,
+    pub started: String,
+    /// Exit code, if the process ran to completion
+    pub exit_code: Option<i32>,
+    /// Spawn error, if the process could not be started
+    pub error: Option<String>,
+}
+
+/// A filesystem snapshot the collector created, removed or failed to create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEvent {
+    /// Mount point of the snapshotted filesystem
+    pub filesystem: String,
+    /// `lvm`, `btrfs` or `zfs`, or the filesystem type when none applies
+    pub kind: String,
+    /// `created`, `removed`, `failed`, `skipped` or `orphan_removed`
+    pub action: String,
+    /// Snapshot name and where it was read from, or the error
+    pub detail: String,
+    pub timestamp: String,
+}
+
+/// Everything the collector changed or perturbed on the system
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorFootprint {
+    pub created_paths: Vec<CreatedPath>,
+    pub registry_keys: Vec<RegistryTouch>,
+    pub processes: Vec<SpawnedProcess>,
+    #[serde(default)]
+    pub snapshots: Vec<SnapshotEvent>,
+    /// Bytes read from each volume, keyed by mount point
+    pub bytes_read_per_volume: BTreeMap<String, u64>,
+}
+
+impl CollectorFootprint {
+    /// Total bytes read across all volumes
+    pub fn total_bytes_read(&self) -> u64 {
+        self.bytes_read_per_volume.values().sum()
+    }
+
+    /// One-line summary suitable for the end of the run
+    pub fn summary_line(&self) -> String {
+        format!(
+            "Collector footprint: {} paths created, {} registry keys touched, {} processes spawned, {} bytes read across {} volume(s)",
+            self.created_paths.len(),
+            self.registry_keys.len(),
+            self.processes.len(),
+            self.total_bytes_read(),
+            self.bytes_read_per_volume.len()
+        )
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+fn with_footprint(f: impl FnOnce(&mut CollectorFootprint)) {
+    if let Ok(mut footprint) = FOOTPRINT.lock() {
+        f(&mut footprint);
+    }
+}
+
+/// Record a file or directory created by the collector
+pub fn record_created_path(path: &Path) {
+    let kind = if path.is_dir() { "directory" } else { "file" };
+    debug!("Footprint: created {} {}", kind, path.display());
+    with_footprint(|fp| {
+        let path = path.to_string_lossy().to_string();
+        if !fp.created_paths.iter().any(|p| p.path == path) {
+            fp.created_paths.push(CreatedPath {
+                path,
+                kind: kind.to_string(),
+                timestamp: now(),
+            });
+        }
+    });
+}
+
+/// Create `path` (and parents) and record it if it did not exist before
+pub fn create_dir_all_tracked(path: &Path) -> io::Result<()> {
+    let existed = path.exists();
+    fs::create_dir_all(path)?;
+    if !existed {
+        record_created_path(path);
+    }
+    Ok(())
+}
+
+/// Record a registry key or service the collector created, modified or deleted.
+///
+/// Privilege enablement does not touch the registry; this is for features
+/// that install services or write keys.
+#[allow(dead_code)]
+pub fn record_registry_key(key: &str, action: &str) {
+    debug!("Footprint: registry {} {}", action, key);
+    with_footprint(|fp| {
+        fp.registry_keys.push(RegistryTouch {
+            key: key.to_string(),
+            action: action.to_string(),
+            timestamp: now(),
+        })
+    });
+}
+
+/// Record `bytes` read from the volume holding `path`
+pub fn record_bytes_read(path: &Path, bytes: u64) {
+    let volume = volume_for(path, &MOUNT_POINTS);
+    with_footprint(|fp| {
+        *fp.bytes_read_per_volume.entry(volume).or_insert(0) += bytes;
+    });
+}
+
+/// Record a snapshot created, removed or not created on `filesystem`
+pub fn record_snapshot(filesystem: &Path, kind: &str, action: &str, detail: &str) {
+    debug!(
+        "Footprint: snapshot {} of {} ({}): {}",
+        action,
+        filesystem.display(),
+        kind,
+        detail
+    );
+    with_footprint(|fp| {
+        fp.snapshots.push(SnapshotEvent {
+            filesystem: filesystem.to_string_lossy().to_string(),
+            kind: kind.to_string(),
+            action: action.to_string(),
+            detail: detail.to_string(),
+            timestamp: now(),
+        })
+    });
+}
+
+/// Run an external command to completion, recording it in the footprint
+pub fn run_command(command: &mut Command) -> io::Result<Output> {
+    let started = now();
+    let result = command.output();
+
+    match &result {
+        Ok(output) => record_process(command, started, output.status.code(), None),
+        Err(e) => record_process(command, started, None, Some(e.to_string())),
+    }
+    result
+}
+
+/// Record an external command started at `started` that was not run
+/// through [`run_command`], such as one whose output is streamed
+pub fn record_process(
+    command: &Command,
+    started: String,
+    exit_code: Option<i32>,
+    error: Option<String>,
+) {
+    let program = command.get_program().to_string_lossy().to_string();
+    let args: Vec<String> = command
+        .get_args()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    debug!("Footprint: spawned {} {}", program, args.join(" "));
+
+    with_footprint(|fp| {
+        fp.processes.push(SpawnedProcess {
+            program,
+            args,
+            started,
+            exit_code,
+            error,
+        })
+    });
+}
+
+/// Drop everything recorded so far and start a new footprint
+pub fn reset() {
+    with_footprint(|fp| *fp = CollectorFootprint::default());
+}
+
+/// Snapshot of the footprint recorded so far
+pub fn snapshot() -> CollectorFootprint {
+    FOOTPRINT.lock().map(|fp| fp.clone()).unwrap_or_default()
+}
+
+/// Write the footprint report to `output_dir`
+pub fn write_footprint(output_dir: &Path) -> Result<PathBuf> {
+    let path = output_dir.join(FOOTPRINT_FILE);
+    record_created_path(&path);
+
+    let json = serde_json::to_string_pretty(&snapshot())
+        .context("Failed to serialize collector footprint")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+    info!("Collector footprint written to {}", path.display());
+    Ok(path)
+}
+
+fn load_mount_points() -> Vec<PathBuf> {
+    let mut system = System::new();
+    system.refresh_disks_list();
+    let mut mounts: Vec<PathBuf> = system
+        .disks()
+        .iter()
+        .map(|d| d.mount_point().to_path_buf())
+        .collect();
+    // Longest first so nested mounts win over their parents
+    mounts.sort_by_key(|m| std::cmp::Reverse(m.as_os_str().len()));
+    mounts
+}
+
+/// Mount point holding `path`, or its root component if none matches
+fn volume_for(path: &Path, mount_points: &[PathBuf]) -> String {
+    if let Some(mount) = mount_points.iter().find(|m| path.starts_with(m)) {
+        return mount.to_string_lossy().to_string();
+    }
+
+    let root: PathBuf = path
+        .components()
+        .take_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        .collect();
+    if root.as_os_str().is_empty() {
+        "unknown".to_string()
+    } else {
+        root.to_string_lossy().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_volume_for_prefers_longest_mount() {
+        let mounts = vec![PathBuf::from("/var/log"), PathBuf::from("/")];
+
+        assert_eq!(
+            volume_for(Path::new("/var/log/syslog"), &mounts),
+            "/var/log"
+        );
+        assert_eq!(volume_for(Path::new("/etc/passwd"), &mounts), "/");
+        assert_eq!(volume_for(Path::new("relative/file"), &mounts), "unknown");
+    }
+
+    #[test]
+    fn test_run_command_records_arguments() {
+        let marker = format!("footprint-{}", uuid::Uuid::new_v4());
+        let _ = run_command(Command::new("rust-collector-missing-binary").arg(&marker));
+
+        let footprint = snapshot();
+        let spawned = footprint
+            .processes
+            .iter()
+            .find(|p| p.args == vec![marker.clone()])
+            .expect("spawn should be recorded");
+        assert_eq!(spawned.program, "rust-collector-missing-binary");
+        assert!(spawned.error.is_some());
+        assert_eq!(spawned.exit_code, None);
+    }
+
+    #[test]
+    fn test_created_dir_recorded_once() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("scratch");
+
+        create_dir_all_tracked(&dir).unwrap();
+        create_dir_all_tracked(&dir).unwrap();
+        record_created_path(&dir);
+
+        let path = dir.to_string_lossy().to_string();
+        let footprint = snapshot();
+        let matches: Vec<_> = footprint
+            .created_paths
+            .iter()
+            .filter(|p| p.path == path)
+            .collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].kind, "directory");
+    }
+
+    #[test]
+    fn test_summary_line_counts() {
+        let mut footprint = CollectorFootprint::default();
+        footprint.bytes_read_per_volume.insert("/".to_string(), 100);
+        footprint
+            .bytes_read_per_volume
+            .insert("/home".to_string(), 50);
+
+        let line = footprint.summary_line();
+        assert!(line.contains("0 processes spawned"));
+        assert!(line.contains("150 bytes read across 2 volume(s)"));
+    }
+}
@@ -0,0 +1,676 @@
+//! Incremental collection against an earlier collection (`--baseline`).
+//!
+//! Repeat sweeps of the same host mostly find the same files. With a
+//! baseline, each source file is compared with its entry in the earlier
+//! collection before it is copied:
+//!
+//! - size or modification time differ: collected (`changed`)
+//! - both equal and the baseline has a SHA-256: the source is hashed and
+//!   collected only if the hash differs, so a rewrite that kept the size
+//!   and timestamp is still caught
+//! - both equal and no hash is known: skipped as `unchanged_from_baseline`,
+//!   pointing at the collection that holds the copy
+//! - no entry: collected (`new`)
+//!
+//! Baseline entries whose file no longer exists are noted as deleted.
+//!
+//! Every run writes [`MANIFEST_FILE`] listing each source file it handled
+//! with its size, modification time and the collection holding its copy,
+//! so any collection can be the next one's baseline; skipped files keep
+//! pointing at the collection they were last copied in. A
+//! `collection_summary.json` also works as a baseline, covering the file
+//! artifacts it lists but not files below collected directories.
+//!
+//! Only file artifacts read from the live system take part; volatile data,
+//! process memory and the other live queries are always collected. Like
+//! the footprint tracker, the state is process-global so collectors can
+//! consult it without threading it through.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::collectors::footprint::record_created_path;
+use crate::collectors::source::{block_on, ArtifactSource};
+use crate::models::ArtifactMetadata;
+use crate::utils::hash::sha256_reader;
+use crate::utils::summary::CollectionSummary;
+
+/// File manifest written into every collection, usable as a later baseline
+pub const MANIFEST_FILE: &str = "file_manifest.json";
+
+lazy_static! {
+    static ref STATE: Mutex<IncrementalState> = Mutex::new(IncrementalState::default());
+}
+
+/// How a file compared with the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    /// Copied in a run without a baseline
+    Collected,
+    /// Not in the baseline
+    New,
+    /// Size, modification time or hash differ from the baseline
+    Changed,
+    /// Not copied; the copy is in the collection named by `collection_id`
+    UnchangedFromBaseline,
+}
+
+/// A source file handled by a collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    pub status: FileStatus,
+    /// Collection holding the copy of the file
+    pub collection_id: String,
+}
+
+/// The earlier collection a run was compared with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineInfo {
+    /// Baseline document as given on the command line
+    pub path: String,
+    /// SHA-256 of the baseline document
+    pub sha256: String,
+    pub collection_id: String,
+    pub hostname: String,
+    pub collection_time: String,
+}
+
+/// `file_manifest.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileManifest {
+    pub collection_id: String,
+    pub hostname: String,
+    pub collection_time: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineInfo>,
+    pub files: Vec<ManifestEntry>,
+    /// Baseline files that no longer exist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deleted: Vec<String>,
+}
+
+/// Known files of an earlier collection, keyed by source path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Baseline {
+    pub info: BaselineInfo,
+    pub entries: HashMap<String, ManifestEntry>,
+}
+
+impl Baseline {
+    /// Read a `file_manifest.json` or `collection_summary.json`
+    pub fn load(path: &Path) -> Result<Self> {
+        let document = fs::read_to_string(path)
+            .context(format!("Failed to read baseline {}", path.display()))?;
+        Self::parse(&path.to_string_lossy(), &document)
+    }
+
+    /// Parse a baseline document; `path` is recorded as its name
+    pub fn parse(path: &str, document: &str) -> Result<Self> {
+        let value: Value =
+            serde_json::from_str(document).context(format!("Baseline {} is not JSON", path))?;
+        let sha256 = format!("{:x}", Sha256::digest(document.as_bytes()));
+
+        if value.get("files").is_some() {
+            let manifest: FileManifest = serde_json::from_value(value)
+                .context(format!("Failed to parse file manifest {}", path))?;
+            return Ok(Self {
+                info: BaselineInfo {
+                    path: path.to_string(),
+                    sha256,
+                    collection_id: manifest.collection_id,
+                    hostname: manifest.hostname,
+                    collection_time: manifest.collection_time,
+                },
+                entries: manifest
+                    .files
+                    .into_iter()
+                    .map(|entry| (entry.path.clone(), entry))
+                    .collect(),
+            });
+        }
+        if value.get("artifacts").is_none() {
+            bail!(
+                "Baseline {} is neither a {} nor a collection summary",
+                path,
+                MANIFEST_FILE
+            );
+        }
+
+        let summary = CollectionSummary::from_json(document)?;
+        let entries = summary
+            .artifacts
+            .iter()
+            .map(|artifact| &artifact.metadata)
+            // Listings and memory dumps are not copies of a source file
+            .filter(|m| {
+                m.listed_files.is_none() && m.memory_region.is_none() && m.dump_method.is_none()
+            })
+            .map(|m| {
+                let entry = ManifestEntry {
+                    path: m.original_path.clone(),
+                    size: m.file_size,
+                    modified: m.modified_time.clone(),
+                    sha256: m.sha256.clone(),
+                    status: FileStatus::Collected,
+                    collection_id: summary.collection_id.clone(),
+                };
+                (entry.path.clone(), entry)
+            })
+            .collect();
+        Ok(Self {
+            info: BaselineInfo {
+                path: path.to_string(),
+                sha256,
+                collection_id: summary.collection_id.clone(),
+                hostname: summary.hostname.clone(),
+                collection_time: summary.collection_time.clone(),
+            },
+            entries,
+        })
+    }
+}
+
+/// How a source compares with its baseline `entry`. `hash` is only called
+/// when size and modification time match and the baseline has a hash to
+/// compare.
+fn compare_entry(
+    entry: Option<&ManifestEntry>,
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: impl FnOnce() -> Option<String>,
+) -> FileStatus {
+    let Some(entry) = entry else {
+        return FileStatus::New;
+    };
+    if entry.size != size || !same_time(entry.modified.as_deref(), modified) {
+        return FileStatus::Changed;
+    }
+    match &entry.sha256 {
+        Some(expected) => match hash() {
+            Some(actual) if actual.eq_ignore_ascii_case(expected) => {
+                FileStatus::UnchangedFromBaseline
+            }
+            _ => FileStatus::Changed,
+        },
+        None => FileStatus::UnchangedFromBaseline,
+    }
+}
+
+/// Whether a recorded modification time and a live one are the same
+/// instant, at the precision the recorded one was written with
+fn same_time(recorded: Option<&str>, live: Option<SystemTime>) -> bool {
+    let (Some(recorded), Some(live)) = (recorded, live) else {
+        return recorded.is_none() && live.is_none();
+    };
+    let Ok(recorded_time) = DateTime::parse_from_rfc3339(recorded) else {
+        return false;
+    };
+    let recorded_time = recorded_time.with_timezone(&Utc);
+    let live = DateTime::<Utc>::from(live);
+    // Some collectors record whole seconds only
+    if !recorded.contains('.') {
+        return recorded_time.timestamp() == live.timestamp();
+    }
+    (recorded_time - live).num_microseconds() == Some(0)
+}
+
+#[derive(Default)]
+struct IncrementalState {
+    baseline: Option<Baseline>,
+    /// Status of files compared but not yet copied
+    pending: HashMap<String, FileStatus>,
+    /// Files handled in this run
+    files: BTreeMap<String, ManifestEntry>,
+}
+
+/// Collect every file, dropping any baseline and the files recorded by an
+/// earlier run in this process
+pub fn clear_baseline() {
+    if let Ok(mut state) = STATE.lock() {
+        *state = IncrementalState::default();
+    }
+}
+
+/// Compare file artifacts with `baseline` until [`clear_baseline`] is called
+pub fn set_baseline(baseline: Baseline) {
+    if let Ok(mut state) = STATE.lock() {
+        info!(
+            "Collecting incrementally against {} (collection {}, {} files)",
+            baseline.info.path,
+            baseline.info.collection_id,
+            baseline.entries.len()
+        );
+        state.baseline = Some(baseline);
+    }
+}
+
+/// Whether the source file at `path` is unchanged since the baseline, so
+/// it need not be copied. Without a baseline every file is copied.
+///
+/// A file that must be copied is recorded once [`record_collected`] is
+/// called for it; an unchanged one is recorded here.
+pub fn is_unchanged(
+    path: &str,
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: impl FnOnce() -> Option<String>,
+) -> bool {
+    // Compare outside the lock, since hashing reads the whole file
+    let entry = match STATE.lock() {
+        Ok(state) => match &state.baseline {
+            Some(baseline) => baseline.entries.get(path).cloned(),
+            None => return false,
+        },
+        Err(_) => return false,
+    };
+    let status = compare_entry(entry.as_ref(), size, modified, hash);
+
+    let Ok(mut state) = STATE.lock() else {
+        return false;
+    };
+    match (status, entry) {
+        (FileStatus::UnchangedFromBaseline, Some(entry)) => {
+            state.files.insert(
+                path.to_string(),
+                ManifestEntry {
+                    status: FileStatus::UnchangedFromBaseline,
+                    ..entry
+                },
+            );
+            true
+        }
+        (status, _) => {
+            state.pending.insert(path.to_string(), status);
+            false
+        }
+    }
+}
+
+/// SHA-256 of `path` read from `source`, for [`is_unchanged`]; `None` if
+/// it cannot be read. Call from a blocking task.
+pub fn source_sha256(source: &dyn ArtifactSource, path: &Path) -> Option<String> {
+    let reader = block_on(source.open(path)).ok()?;
+    sha256_reader(reader).ok()
+}
+
+/// Record a source file copied in this run
+pub fn record_collected(path: &str, size: u64, modified: Option<String>) {
+    let Ok(mut state) = STATE.lock() else {
+        return;
+    };
+    let status = state.pending.remove(path).unwrap_or(FileStatus::Collected);
+    state.files.insert(
+        path.to_string(),
+        ManifestEntry {
+            path: path.to_string(),
+            size,
+            modified,
+            sha256: None,
+            status,
+            // Filled in with this run's ID when the manifest is written
+            collection_id: String::new(),
+        },
+    );
+}
+
+/// Build the manifest of this run. Hashes computed for collected files
+/// (NSRL lookups) are taken from `collected`.
+pub fn manifest(
+    collection_id: &str,
+    hostname: &str,
+    collection_time: &str,
+    collected: &[(String, ArtifactMetadata)],
+) -> FileManifest {
+    let state = STATE.lock().ok();
+    let (baseline, mut files) = match &state {
+        Some(state) => (state.baseline.clone(), state.files.clone()),
+        None => (None, BTreeMap::new()),
+    };
+    drop(state);
+
+    let hashes: HashMap<&str, &str> = collected
+        .iter()
+        .filter_map(|(_, m)| Some((m.original_path.as_str(), m.sha256.as_deref()?)))
+        .collect();
+    for entry in files.values_mut() {
+        if entry.collection_id.is_empty() {
+            entry.collection_id = collection_id.to_string();
+            entry.sha256 = hashes.get(entry.path.as_str()).map(|h| h.to_string());
+        }
+    }
+
+    let deleted = baseline
+        .as_ref()
+        .map(|baseline| {
+            baseline
+                .entries
+                .keys()
+                .filter(|path| !files.contains_key(*path))
+                .filter(|path| {
+                    matches!(fs::symlink_metadata(path), Err(e) if e.kind() == io::ErrorKind::NotFound)
+                })
+                .cloned()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    FileManifest {
+        collection_id: collection_id.to_string(),
+        hostname: hostname.to_string(),
+        collection_time: collection_time.to_string(),
+        baseline: baseline.map(|b| b.info),
+        files: files.into_values().collect(),
+        deleted,
+    }
+}
+
+impl FileManifest {
+    /// `incremental` section of the collection summary: the baseline and
+    /// how many files compared each way. `None` without a baseline.
+    pub fn summary_section(&self) -> Option<Value> {
+        let baseline = self.baseline.as_ref()?;
+        let count = |status: FileStatus| self.files.iter().filter(|f| f.status == status).count();
+        Some(json!({
+            "baseline": baseline,
+            "manifest": MANIFEST_FILE,
+            "unchanged_from_baseline": count(FileStatus::UnchangedFromBaseline),
+            "changed": count(FileStatus::Changed),
+            "new": count(FileStatus::New),
+            "deleted": self.deleted.len(),
+        }))
+    }
+
+    /// Write the manifest to `output_dir`
+    pub fn write(&self, output_dir: &Path) -> Result<()> {
+        let path = output_dir.join(MANIFEST_FILE);
+        record_created_path(&path);
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize file manifest")?;
+        fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+        if let Some(baseline) = &self.baseline {
+            let unchanged = self
+                .files
+                .iter()
+                .filter(|f| f.status == FileStatus::UnchangedFromBaseline)
+                .count();
+            info!(
+                "{} of {} files unchanged since collection {}; {} deleted",
+                unchanged,
+                self.files.len(),
+                baseline.collection_id,
+                self.deleted.len()
+            );
+            if baseline.hostname != self.hostname {
+                warn!(
+                    "Baseline {} was collected from {}, not {}",
+                    baseline.path, baseline.hostname, self.hostname
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const MTIME: &str = "2024-03-01T08:00:00.123456789+00:00";
+
+    fn mtime() -> SystemTime {
+        DateTime::parse_from_rfc3339(MTIME).unwrap().into()
+    }
+
+    fn manifest_json(files: &[(&str, u64, Option<&str>)]) -> String {
+        serde_json::to_string(&FileManifest {
+            collection_id: "previous".to_string(),
+            hostname: "host1".to_string(),
+            collection_time: "2024-03-02T00:00:00Z".to_string(),
+            baseline: None,
+            files: files
+                .iter()
+                .map(|(path, size, sha256)| ManifestEntry {
+                    path: path.to_string(),
+                    size: *size,
+                    modified: Some(MTIME.to_string()),
+                    sha256: sha256.map(str::to_string),
+                    status: FileStatus::Collected,
+                    collection_id: "previous".to_string(),
+                })
+                .collect(),
+            deleted: Vec::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_size_and_mtime() {
+        let baseline = Baseline::parse(
+            "file_manifest.json",
+            &manifest_json(&[("/etc/hosts", 120, None)]),
+        )
+        .unwrap();
+        assert_eq!(baseline.info.collection_id, "previous");
+        let no_hash = || -> Option<String> { panic!("hashed without a baseline hash") };
+
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/etc/hosts"),
+                120,
+                Some(mtime()),
+                no_hash
+            ),
+            FileStatus::UnchangedFromBaseline
+        );
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/etc/hosts"),
+                121,
+                Some(mtime()),
+                no_hash
+            ),
+            FileStatus::Changed
+        );
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/etc/hosts"),
+                120,
+                Some(mtime() + Duration::from_secs(1)),
+                no_hash
+            ),
+            FileStatus::Changed
+        );
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/etc/passwd"),
+                120,
+                Some(mtime()),
+                no_hash
+            ),
+            FileStatus::New
+        );
+    }
+
+    #[test]
+    fn test_same_size_and_mtime_with_different_hash_is_collected() {
+        let baseline = Baseline::parse(
+            "file_manifest.json",
+            &manifest_json(&[("/var/log/auth.log", 4096, Some("AB12"))]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/var/log/auth.log"),
+                4096,
+                Some(mtime()),
+                || Some("ffff".to_string())
+            ),
+            FileStatus::Changed
+        );
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/var/log/auth.log"),
+                4096,
+                Some(mtime()),
+                || Some("ab12".to_string())
+            ),
+            FileStatus::UnchangedFromBaseline
+        );
+        // An unreadable source cannot be shown to be unchanged
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/var/log/auth.log"),
+                4096,
+                Some(mtime()),
+                || None
+            ),
+            FileStatus::Changed
+        );
+    }
+
+    #[test]
+    fn test_summary_baseline() {
+        let summary = json!({
+            "collection_id": "summary-id",
+            "hostname": "host1",
+            "collection_time": "2024-03-02T00:00:00Z",
+            "os_version": "Linux",
+            "collector_version": "1.0",
+            "organization": "",
+            "artifacts": [
+                {
+                    "path": "fs/etc/hosts",
+                    "original_path": "/etc/hosts",
+                    "collection_time": "2024-03-02T00:00:00Z",
+                    "file_size": 120,
+                    "modified_time": "2024-03-01T08:00:00Z",
+                    "is_locked": false,
+                    "sha256": "abcd"
+                },
+                {
+                    "path": "fs/var/log/listing.jsonl",
+                    "original_path": "/var/log",
+                    "collection_time": "2024-03-02T00:00:00Z",
+                    "file_size": 0,
+                    "is_locked": false,
+                    "listed_files": 12
+                }
+            ]
+        })
+        .to_string();
+        let baseline = Baseline::parse("collection_summary.json", &summary).unwrap();
+
+        assert_eq!(baseline.info.collection_id, "summary-id");
+        assert_eq!(baseline.entries.len(), 1);
+        let entry = &baseline.entries["/etc/hosts"];
+        assert_eq!(entry.sha256.as_deref(), Some("abcd"));
+        // Whole-second timestamps match at second precision
+        let live = mtime();
+        assert_eq!(
+            compare_entry(
+                baseline.entries.get("/etc/hosts"),
+                120,
+                Some(live),
+                || Some("abcd".to_string())
+            ),
+            FileStatus::UnchangedFromBaseline
+        );
+        assert!(Baseline::parse("other.json", "{\"name\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_manifest_points_unchanged_files_at_baseline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let gone = dir.path().join("gone.log").to_string_lossy().to_string();
+        let baseline = Baseline::parse(
+            "file_manifest.json",
+            &manifest_json(&[
+                ("/incremental-test/same", 10, None),
+                ("/incremental-test/edited", 10, None),
+                (&gone, 5, None),
+            ]),
+        )
+        .unwrap();
+        set_baseline(baseline);
+
+        assert!(is_unchanged(
+            "/incremental-test/same",
+            10,
+            Some(mtime()),
+            || None
+        ));
+        assert!(!is_unchanged(
+            "/incremental-test/edited",
+            11,
+            Some(mtime()),
+            || None
+        ));
+        assert!(!is_unchanged(
+            "/incremental-test/added",
+            3,
+            Some(mtime()),
+            || None
+        ));
+        record_collected("/incremental-test/edited", 11, Some(MTIME.to_string()));
+        record_collected("/incremental-test/added", 3, Some(MTIME.to_string()));
+
+        let manifest = manifest("current", "host1", "2024-03-09T00:00:00Z", &[]);
+        let status = |path: &str| {
+            manifest
+                .files
+                .iter()
+                .find(|f| f.path == path)
+                .map(|f| (f.status, f.collection_id.clone()))
+                .unwrap()
+        };
+        assert_eq!(
+            status("/incremental-test/same"),
+            (FileStatus::UnchangedFromBaseline, "previous".to_string())
+        );
+        assert_eq!(
+            status("/incremental-test/edited"),
+            (FileStatus::Changed, "current".to_string())
+        );
+        assert_eq!(
+            status("/incremental-test/added"),
+            (FileStatus::New, "current".to_string())
+        );
+        assert!(manifest.deleted.contains(&gone));
+
+        let section = manifest.summary_section().unwrap();
+        assert_eq!(section["baseline"]["collection_id"], "previous");
+        assert_eq!(section["unchanged_from_baseline"], 1);
+
+        // The manifest is itself a baseline for the next run
+        manifest.write(dir.path()).unwrap();
+        let next = Baseline::load(&dir.path().join(MANIFEST_FILE)).unwrap();
+        assert_eq!(
+            next.entries["/incremental-test/same"].collection_id,
+            "previous"
+        );
+        assert_eq!(next.info.collection_id, "current");
+    }
+}
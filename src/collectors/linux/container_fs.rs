@@ -0,0 +1,798 @@
+//! Container filesystems as an artifact source.
+//!
+//! `container://<id-or-name>/<path>` reads `<path>` as the container sees
+//! it, so a webshell dropped into an nginx container or a crontab inside a
+//! long-running pod can be collected like any host file. The container is
+//! looked up with `docker inspect`, then `podman inspect`, which give its
+//! image and storage layers:
+//!
+//! - **overlay** (`overlay2` for Docker, `overlay` for Podman): the writable
+//!   `UpperDir` over each `LowerDir`. The layers are merged here rather than
+//!   read through `MergedDir`, which only exists while the container runs,
+//!   honouring whiteouts (`.wh.<name>` files and 0/0 character devices) and
+//!   opaque directories (`.wh..wh..opq`, or the `overlay.opaque` xattr).
+//! - **vfs**: the container's single directory, found through Docker's layer
+//!   database or Podman's `vfs-containers/containers.json`.
+//!
+//! Symbolic links are resolved inside the container root, so an absolute
+//! link such as `/etc/passwd` never reaches the host's file. Each collected
+//! artifact records the container, its image and the layers holding the
+//! path in `container` metadata; layers are named by their diff digest
+//! where the runtime's layer database has one.
+//!
+//! A container no runtime knows fails only the artifacts that name it.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::debug;
+use serde_json::Value;
+
+use crate::collectors::linux::containers::{
+    run_inspect, string_at, summary_from_json, ContainerRuntime,
+};
+use crate::collectors::read_only;
+use crate::collectors::source::{ArtifactSource, SourceMetadata};
+use crate::models::{ArtifactMetadata, ContainerProvenance};
+
+/// Scheme of container filesystem paths
+pub const CONTAINER_SCHEME: &str = "container";
+
+/// Label of a container's own writable layer
+pub const CONTAINER_LAYER: &str = "container";
+
+/// Symbolic links followed while resolving one path, as in Linux
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Default storage roots, used to find vfs container directories
+const DOCKER_ROOT: &str = "/var/lib/docker";
+const PODMAN_ROOT: &str = "/var/lib/containers/storage";
+
+/// Looks a container up, returning its runtime and `inspect` output
+type Inspector = dyn Fn(&str) -> Result<(ContainerRuntime, String)> + Send + Sync;
+
+/// Files inside containers, addressed as `<id-or-name>/<path>`
+pub struct ContainerSource {
+    inspect: Box<Inspector>,
+    storage_roots: Vec<PathBuf>,
+    roots: Mutex<HashMap<String, Arc<ContainerRoot>>>,
+}
+
+impl ContainerSource {
+    /// Source inspecting containers with the Docker, then the Podman client
+    pub fn new() -> Self {
+        Self::with_inspector(
+            |container| {
+                let docker = match run_inspect(ContainerRuntime::Docker, container) {
+                    Ok(output) => return Ok((ContainerRuntime::Docker, output)),
+                    Err(e) => e,
+                };
+                match run_inspect(ContainerRuntime::Podman, container) {
+                    Ok(output) => Ok((ContainerRuntime::Podman, output)),
+                    Err(podman) => Err(anyhow!(
+                        "No container runtime knows container {} ({:#}; {:#})",
+                        container,
+                        docker,
+                        podman
+                    )),
+                }
+            },
+            vec![PathBuf::from(DOCKER_ROOT), PathBuf::from(PODMAN_ROOT)],
+        )
+    }
+
+    /// Source looking containers up with `inspect` and finding vfs
+    /// containers under `storage_roots`
+    fn with_inspector<F>(inspect: F, storage_roots: Vec<PathBuf>) -> Self
+    where
+        F: Fn(&str) -> Result<(ContainerRuntime, String)> + Send + Sync + 'static,
+    {
+        Self {
+            inspect: Box::new(inspect),
+            storage_roots,
+            roots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Container root and in-container path of a source path
+    fn resolve(&self, path: &Path) -> io::Result<(Arc<ContainerRoot>, PathBuf)> {
+        let mut parts = path_parts(path).into_iter();
+        let container = parts.next().filter(|part| part != "..").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} does not start with a container", path.display()),
+            )
+        })?;
+        let container = container.to_string_lossy().to_string();
+        let inner: PathBuf = parts.collect();
+
+        let mut roots = self.roots.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(root) = roots.get(&container) {
+            return Ok((Arc::clone(root), inner));
+        }
+        let root = self
+            .load_root(&container)
+            .map(Arc::new)
+            .map_err(|e| io::Error::other(format!("{:#}", e)))?;
+        roots.insert(container, Arc::clone(&root));
+        Ok((root, inner))
+    }
+
+    fn load_root(&self, container: &str) -> Result<ContainerRoot> {
+        let (runtime, output) = (self.inspect)(container)?;
+        let value: Value =
+            serde_json::from_str(&output).context("Failed to parse inspect output")?;
+        let value = match value {
+            Value::Array(mut items) if !items.is_empty() => items.swap_remove(0),
+            Value::Object(_) => value,
+            _ => return Err(anyhow!("inspect returned no container for {}", container)),
+        };
+        let root = ContainerRoot::from_inspect(&value, runtime, &self.storage_roots)?;
+        debug!("Container {} has {} layers", container, root.layers.len());
+        Ok(root)
+    }
+}
+
+impl Default for ContainerSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ArtifactSource for ContainerSource {
+    fn scheme(&self) -> &str {
+        CONTAINER_SCHEME
+    }
+
+    async fn stat(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let (root, inner) = self.resolve(path)?;
+        let (host, _) = root.canonicalize(&inner)?;
+        Ok(SourceMetadata::from(fs::symlink_metadata(host)?))
+    }
+
+    async fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let (root, inner) = self.resolve(path)?;
+        let (_, resolved) = root.canonicalize(&inner)?;
+        Ok(root
+            .list(&resolved)?
+            .into_iter()
+            .map(|name| path.join(name))
+            .collect())
+    }
+
+    async fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let (root, inner) = self.resolve(path)?;
+        let (host, _) = root.canonicalize(&inner)?;
+        Ok(Box::new(read_only::open(&host)?))
+    }
+
+    fn annotate(&self, path: &Path, metadata: &mut ArtifactMetadata) {
+        let Ok((root, inner)) = self.resolve(path) else {
+            return;
+        };
+        let mut provenance = root.provenance.clone();
+        if let Ok((_, resolved)) = root.canonicalize(&inner) {
+            provenance.layers = root.layers_holding(&resolved);
+        }
+        metadata.container = Some(provenance);
+    }
+}
+
+/// One storage layer of a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Layer {
+    dir: PathBuf,
+    label: String,
+}
+
+/// A container's layers, topmost first, merged as overlayfs does
+#[derive(Debug)]
+struct ContainerRoot {
+    /// Container and image, with `layers` left empty
+    provenance: ContainerProvenance,
+    layers: Vec<Layer>,
+}
+
+impl ContainerRoot {
+    /// Root of a container from its `inspect` output
+    fn from_inspect(
+        value: &Value,
+        runtime: ContainerRuntime,
+        storage_roots: &[PathBuf],
+    ) -> Result<Self> {
+        let summary = summary_from_json(value, runtime, "");
+        let id = summary.id.clone();
+        let driver = value
+            .get("GraphDriver")
+            .and_then(|driver| string_at(driver, &["Name"]))
+            .unwrap_or_default();
+        let data = value
+            .get("GraphDriver")
+            .and_then(|driver| driver.get("Data"));
+
+        let layers = if let Some(upper) = data.and_then(|d| string_at(d, &["UpperDir"])) {
+            let lower: Vec<PathBuf> = data
+                .and_then(|d| string_at(d, &["LowerDir"]))
+                .map(|dirs| {
+                    dirs.split(':')
+                        .filter(|dir| !dir.is_empty())
+                        .map(PathBuf::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let digests = lower
+                .first()
+                .and_then(|dir| dir.ancestors().nth(3))
+                .map(layer_digests)
+                .unwrap_or_default();
+            std::iter::once(Layer {
+                dir: PathBuf::from(upper),
+                label: CONTAINER_LAYER.to_string(),
+            })
+            .chain(lower.into_iter().map(|dir| {
+                let label = layer_label(&dir, &digests);
+                Layer { dir, label }
+            }))
+            .collect()
+        } else if let Some(merged) = data.and_then(|d| string_at(d, &["MergedDir"])) {
+            vec![Layer {
+                dir: PathBuf::from(merged),
+                label: CONTAINER_LAYER.to_string(),
+            }]
+        } else if driver == "vfs" {
+            let dir = storage_roots
+                .iter()
+                .find_map(|root| vfs_dir(root, runtime, &id))
+                .ok_or_else(|| anyhow!("No vfs directory found for container {}", id))?;
+            vec![Layer {
+                dir,
+                label: CONTAINER_LAYER.to_string(),
+            }]
+        } else {
+            return Err(anyhow!(
+                "Container {} uses the unsupported storage driver '{}'",
+                id,
+                driver
+            ));
+        };
+
+        Ok(Self {
+            provenance: ContainerProvenance {
+                runtime: summary.runtime,
+                container_id: id,
+                container_name: Some(summary.name).filter(|name| !name.is_empty()),
+                image: summary.image,
+                image_digest: summary.image_digest,
+                layers: Vec::new(),
+            },
+            layers,
+        })
+    }
+
+    /// Host path and layer of in-container path `path`, whose components
+    /// must not be symbolic links
+    fn lookup(&self, path: &Path) -> io::Result<(PathBuf, usize)> {
+        let ancestors: Vec<&Path> = proper_ancestors(path);
+        for (index, layer) in self.layers.iter().enumerate() {
+            if whited_out(&layer.dir, path) {
+                break;
+            }
+            let mut complete = true;
+            let mut hides_lower = false;
+            for ancestor in &ancestors {
+                match fs::symlink_metadata(layer.dir.join(ancestor)) {
+                    Ok(meta) if meta.is_dir() => {
+                        hides_lower |= is_opaque(&layer.dir.join(ancestor));
+                    }
+                    // A file here replaces the directory of lower layers
+                    Ok(_) => return Err(not_found(path)),
+                    Err(_) => {
+                        complete = false;
+                        break;
+                    }
+                }
+            }
+            if complete {
+                let host = layer.dir.join(path);
+                if let Ok(meta) = fs::symlink_metadata(&host) {
+                    if is_whiteout_device(&meta) {
+                        break;
+                    }
+                    return Ok((host, index));
+                }
+            }
+            if hides_lower {
+                break;
+            }
+        }
+        Err(not_found(path))
+    }
+
+    /// Resolve symbolic links in `path` within the container, returning
+    /// the host path and the in-container path without links
+    fn canonicalize(&self, path: &Path) -> io::Result<(PathBuf, PathBuf)> {
+        let mut queue: VecDeque<OsString> = path_parts(path).into();
+        let mut resolved = PathBuf::new();
+        let mut hops = 0;
+        while let Some(part) = queue.pop_front() {
+            if part == ".." {
+                resolved.pop();
+                continue;
+            }
+            let next = resolved.join(&part);
+            let (host, _) = self.lookup(&next)?;
+            let is_link = fs::symlink_metadata(&host)?.file_type().is_symlink();
+            if is_link {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(io::Error::other(format!(
+                        "Too many levels of symbolic links in {}",
+                        path.display()
+                    )));
+                }
+                let target = fs::read_link(&host)?;
+                if target.has_root() {
+                    resolved = PathBuf::new();
+                }
+                for target_part in path_parts(&target).into_iter().rev() {
+                    queue.push_front(target_part);
+                }
+            } else {
+                resolved = next;
+            }
+        }
+        let (host, _) = self.lookup(&resolved)?;
+        Ok((host, resolved))
+    }
+
+    /// Names in directory `path`, merged across layers
+    fn list(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let (_, top) = self.lookup(path)?;
+        let mut names = Vec::new();
+        let mut seen: HashSet<OsString> = HashSet::new();
+        for layer in &self.layers[top..] {
+            let dir = layer.dir.join(path);
+            match fs::symlink_metadata(&dir) {
+                Ok(meta) if meta.is_dir() => {}
+                Ok(_) => break,
+                Err(_) if whited_out(&layer.dir, path) => break,
+                Err(_) => continue,
+            }
+            let mut entries: Vec<(OsString, fs::Metadata)> = fs::read_dir(&dir)?
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| Some((entry.file_name(), entry.metadata().ok()?)))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, meta) in entries {
+                let name_str = name.to_string_lossy();
+                if let Some(hidden) = name_str.strip_prefix(".wh.") {
+                    if !hidden.starts_with(".wh.") {
+                        seen.insert(OsString::from(hidden));
+                    }
+                    continue;
+                }
+                if seen.insert(name.clone()) && !is_whiteout_device(&meta) {
+                    names.push(name);
+                }
+            }
+            if is_opaque(&dir) {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    /// Labels of the layers holding `path`: the one it is read from, and
+    /// for a directory every lower layer merged into it
+    fn layers_holding(&self, path: &Path) -> Vec<String> {
+        let Ok((host, top)) = self.lookup(path) else {
+            return Vec::new();
+        };
+        if !host.is_dir() {
+            return vec![self.layers[top].label.clone()];
+        }
+        let mut labels = Vec::new();
+        for layer in &self.layers[top..] {
+            let dir = layer.dir.join(path);
+            match fs::symlink_metadata(&dir) {
+                Ok(meta) if meta.is_dir() => labels.push(layer.label.clone()),
+                Ok(_) => break,
+                Err(_) if whited_out(&layer.dir, path) => break,
+                Err(_) => continue,
+            }
+            if is_opaque(&dir) {
+                break;
+            }
+        }
+        labels
+    }
+}
+
+/// Normal components of `path`, with `..` kept and roots dropped
+fn path_parts(path: &Path) -> Vec<OsString> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Ancestors of a relative `path`, shortest first, excluding the root and
+/// `path` itself
+fn proper_ancestors(path: &Path) -> Vec<&Path> {
+    let mut ancestors: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .collect();
+    ancestors.reverse();
+    ancestors
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("/{} does not exist in the container", path.display()),
+    )
+}
+
+/// Whether `path` or one of its ancestors is deleted in the layer at
+/// `layer_dir` by a `.wh.<name>` file or a whiteout device
+fn whited_out(layer_dir: &Path, path: &Path) -> bool {
+    let mut prefix = PathBuf::new();
+    for part in path_parts(path) {
+        let mut marker = OsString::from(".wh.");
+        marker.push(&part);
+        if fs::symlink_metadata(layer_dir.join(&prefix).join(marker)).is_ok() {
+            return true;
+        }
+        prefix.push(&part);
+        if fs::symlink_metadata(layer_dir.join(&prefix)).is_ok_and(|meta| is_whiteout_device(&meta))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// overlayfs marks a deleted lower file with a 0/0 character device
+fn is_whiteout_device(meta: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+        meta.file_type().is_char_device() && meta.rdev() == 0
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        false
+    }
+}
+
+/// Whether directory `dir` hides the same directory in lower layers
+fn is_opaque(dir: &Path) -> bool {
+    if fs::symlink_metadata(dir.join(".wh..wh..opq")).is_ok() {
+        return true;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        ["trusted.overlay.opaque", "user.overlay.opaque"]
+            .iter()
+            .any(|name| xattr_is_y(dir, name))
+    }
+    #[cfg(not(target_os = "linux"))]
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn xattr_is_y(path: &Path, name: &str) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let (Ok(path), Ok(name)) = (
+        CString::new(path.as_os_str().as_bytes()),
+        CString::new(name),
+    ) else {
+        return false;
+    };
+    let mut value = [0u8; 1];
+    // SAFETY: both strings are NUL-terminated and the buffer length is passed
+    let len = unsafe {
+        libc::lgetxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+        )
+    };
+    len == 1 && value[0] == b'y'
+}
+
+/// Diff digests of overlay layers under the storage root `root`, by layer
+/// directory name: Docker's `image/overlay2/layerdb` and Podman's
+/// `overlay-layers/layers.json`
+fn layer_digests(root: &Path) -> HashMap<String, String> {
+    let mut digests = HashMap::new();
+
+    if let Ok(entries) = fs::read_dir(root.join("image/overlay2/layerdb/sha256")) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let read = |name: &str| {
+                fs::read_to_string(entry.path().join(name))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+            };
+            if let (Some(cache_id), Some(diff)) = (read("cache-id"), read("diff")) {
+                digests.insert(cache_id, diff);
+            }
+        }
+    }
+
+    if let Ok(data) = fs::read_to_string(root.join("overlay-layers/layers.json")) {
+        if let Ok(Value::Array(layers)) = serde_json::from_str::<Value>(&data) {
+            for layer in layers {
+                if let (Some(id), Some(diff)) = (
+                    string_at(&layer, &["id"]),
+                    string_at(&layer, &["diff-digest"]),
+                ) {
+                    digests.insert(id, diff);
+                }
+            }
+        }
+    }
+
+    digests
+}
+
+/// Label of the lower layer at `dir` (`<storage>/<layer>/diff`)
+fn layer_label(dir: &Path, digests: &HashMap<String, String>) -> String {
+    let layer_dir = if dir.file_name() == Some(OsStr::new("diff")) {
+        dir.parent().unwrap_or(dir)
+    } else {
+        dir
+    };
+    let id = layer_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    digests
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| layer_dir.to_string_lossy().to_string())
+}
+
+/// Directory of a vfs container under the storage root `root`
+fn vfs_dir(root: &Path, runtime: ContainerRuntime, id: &str) -> Option<PathBuf> {
+    let layer = match runtime {
+        ContainerRuntime::Docker => fs::read_to_string(
+            root.join("image/vfs/layerdb/mounts")
+                .join(id)
+                .join("mount-id"),
+        )
+        .ok()?
+        .trim()
+        .to_string(),
+        ContainerRuntime::Podman => {
+            let data = fs::read_to_string(root.join("vfs-containers/containers.json")).ok()?;
+            let containers: Value = serde_json::from_str(&data).ok()?;
+            containers
+                .as_array()?
+                .iter()
+                .find(|container| string_at(container, &["id"]).as_deref() == Some(id))
+                .and_then(|container| string_at(container, &["layer"]))?
+        }
+    };
+    let dir = root.join("vfs/dir").join(layer);
+    dir.is_dir().then_some(dir)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::collectors::source::block_on;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Docker-style overlay2 storage: the container's upper layer over an
+    /// application layer over a base layer
+    fn overlay_storage(root: &Path) -> String {
+        let overlay = root.join("overlay2");
+        let upper = overlay.join("c0ntainer/diff");
+        let app = overlay.join("app111/diff");
+        let base = overlay.join("base000/diff");
+
+        write(&base.join("usr/bin/sh"), "base shell");
+        write(&base.join("etc/passwd"), "root:x:0:0::/root:/bin/sh\n");
+        write(&base.join("etc/motd"), "welcome");
+        write(&base.join("var/www/html/index.html"), "base index");
+        write(&base.join("var/www/html/old.php"), "old");
+        std::os::unix::fs::symlink("usr/bin", base.join("bin")).unwrap();
+
+        write(&app.join("var/www/html/index.html"), "app index");
+        write(&app.join("var/www/html/app.php"), "app");
+        write(&app.join("var/www/html/.wh.old.php"), "");
+        write(&app.join("var/cache/.wh..wh..opq"), "");
+        write(&base.join("var/cache/stale"), "stale");
+
+        write(
+            &upper.join("var/www/html/shell.php"),
+            "<?php system($_GET['c']);",
+        );
+        write(&upper.join("etc/.wh.motd"), "");
+        // Absolute links resolve inside the container
+        std::os::unix::fs::symlink("/etc/passwd", upper.join("var/www/html/leak")).unwrap();
+        std::os::unix::fs::symlink("../../../../../../etc/hostname", upper.join("escape")).unwrap();
+
+        let layerdb = root.join("image/overlay2/layerdb/sha256");
+        write(&layerdb.join("aaaa/cache-id"), "app111");
+        write(&layerdb.join("aaaa/diff"), "sha256:appdiff");
+        write(&layerdb.join("bbbb/cache-id"), "base000");
+        write(&layerdb.join("bbbb/diff"), "sha256:basediff");
+
+        serde_json::json!([{
+            "Id": "4f2a9c",
+            "Name": "/web",
+            "Image": "sha256:0d8a1b",
+            "Config": {"Image": "nginx:1.25"},
+            "GraphDriver": {
+                "Name": "overlay2",
+                "Data": {
+                    "LowerDir": format!("{}:{}", app.display(), base.display()),
+                    "UpperDir": upper.display().to_string(),
+                    "MergedDir": overlay.join("c0ntainer/merged").display().to_string()
+                }
+            }
+        }])
+        .to_string()
+    }
+
+    fn source(root: &Path) -> ContainerSource {
+        let inspect = overlay_storage(root);
+        ContainerSource::with_inspector(
+            move |container| match container {
+                "web" | "4f2a9c" => Ok((ContainerRuntime::Docker, inspect.clone())),
+                _ => Err(anyhow!("No such container: {}", container)),
+            },
+            Vec::new(),
+        )
+    }
+
+    fn read(source: &ContainerSource, path: &str) -> io::Result<String> {
+        block_on(source.read(Path::new(path))).map(|data| String::from_utf8(data).unwrap())
+    }
+
+    #[test]
+    fn test_overlay_path_resolution() {
+        let temp = TempDir::new().unwrap();
+        let source = source(temp.path());
+
+        assert_eq!(
+            read(&source, "web/var/www/html/index.html").unwrap(),
+            "app index"
+        );
+        assert!(read(&source, "web/var/www/html/shell.php")
+            .unwrap()
+            .contains("system"));
+        assert_eq!(read(&source, "web/bin/sh").unwrap(), "base shell");
+        assert_eq!(
+            read(&source, "web/var/www/html/leak").unwrap(),
+            "root:x:0:0::/root:/bin/sh\n"
+        );
+
+        for deleted in [
+            "web/var/www/html/old.php",
+            "web/etc/motd",
+            "web/var/cache/stale",
+            "web/escape",
+        ] {
+            let error = read(&source, deleted).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::NotFound, "{}", deleted);
+        }
+    }
+
+    #[test]
+    fn test_list_merges_layers() {
+        let temp = TempDir::new().unwrap();
+        let source = source(temp.path());
+
+        let entries = block_on(source.list_dir(Path::new("web/var/www/html"))).unwrap();
+        let names: Vec<String> = entries
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["leak", "shell.php", "app.php", "index.html"]);
+        assert_eq!(entries[1], Path::new("web/var/www/html/shell.php"));
+
+        let etc = block_on(source.list_dir(Path::new("web/etc"))).unwrap();
+        assert_eq!(etc, vec![PathBuf::from("web/etc/passwd")]);
+        assert!(block_on(source.list_dir(Path::new("web/var/cache")))
+            .unwrap()
+            .is_empty());
+        assert!(
+            block_on(source.stat(Path::new("web/var/www")))
+                .unwrap()
+                .is_dir
+        );
+    }
+
+    #[test]
+    fn test_annotate_records_layer_provenance() {
+        let temp = TempDir::new().unwrap();
+        let source = source(temp.path());
+        let mut metadata: ArtifactMetadata = serde_json::from_value(serde_json::json!({
+            "original_path": "web/var/www/html/index.html",
+            "collection_time": "2024-03-05T14:30:10Z",
+            "file_size": 9,
+            "is_locked": false
+        }))
+        .unwrap();
+
+        source.annotate(Path::new("web/var/www/html/index.html"), &mut metadata);
+        let provenance = metadata.container.clone().unwrap();
+        assert_eq!(provenance.runtime, "docker");
+        assert_eq!(provenance.container_id, "4f2a9c");
+        assert_eq!(provenance.container_name.as_deref(), Some("web"));
+        assert_eq!(provenance.image.as_deref(), Some("nginx:1.25"));
+        assert_eq!(provenance.image_digest.as_deref(), Some("sha256:0d8a1b"));
+        assert_eq!(provenance.layers, vec!["sha256:appdiff"]);
+
+        source.annotate(Path::new("4f2a9c/var/www/html"), &mut metadata);
+        assert_eq!(
+            metadata.container.unwrap().layers,
+            vec![CONTAINER_LAYER, "sha256:appdiff", "sha256:basediff"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_container_fails_cleanly() {
+        let temp = TempDir::new().unwrap();
+        let source = source(temp.path());
+        let error = block_on(source.stat(Path::new("db/etc/passwd"))).unwrap_err();
+        assert!(error.to_string().contains("No such container: db"));
+        assert!(block_on(source.stat(Path::new(""))).is_err());
+    }
+
+    #[test]
+    fn test_vfs_container_directories() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            &root.join("image/vfs/layerdb/mounts/d0c/mount-id"),
+            "d0c-init\n",
+        );
+        write(&root.join("vfs/dir/d0c-init/etc/hostname"), "docker-vfs");
+        write(
+            &root.join("vfs-containers/containers.json"),
+            r#"[{"id": "p0d", "layer": "p0d-layer"}]"#,
+        );
+        write(&root.join("vfs/dir/p0d-layer/etc/hostname"), "podman-vfs");
+
+        for (runtime, id, expected) in [
+            (ContainerRuntime::Docker, "d0c", "d0c-init"),
+            (ContainerRuntime::Podman, "p0d", "p0d-layer"),
+        ] {
+            let inspect =
+                serde_json::json!({"Id": id, "GraphDriver": {"Name": "vfs", "Data": null}});
+            let container =
+                ContainerRoot::from_inspect(&inspect, runtime, &[root.to_path_buf()]).unwrap();
+            assert_eq!(container.layers[0].dir, root.join("vfs/dir").join(expected));
+            let (host, _) = container.canonicalize(Path::new("etc/hostname")).unwrap();
+            assert!(host.starts_with(root.join("vfs/dir")));
+        }
+
+        let btrfs = serde_json::json!({"Id": "b7", "GraphDriver": {"Name": "btrfs", "Data": null}});
+        assert!(ContainerRoot::from_inspect(&btrfs, ContainerRuntime::Docker, &[]).is_err());
+    }
+}
@@ -0,0 +1,704 @@
+//! Docker and Podman container state.
+//!
+//! Each container directory under the runtime's storage
+//! (`/var/lib/docker/containers/<id>/`, or
+//! `/var/lib/containers/storage/overlay-containers/<id>/userdata/` for
+//! Podman) is copied: the container's configuration and its `json-file` /
+//! `k8s-file` logs. `container_summary.json` then lists every container with
+//! its image and image digest, creation time, environment and mounted
+//! volumes, taken from `docker inspect` / `podman inspect`. When the runtime
+//! is not running, as on a mounted disk image, the configuration file in the
+//! container directory is read instead.
+//!
+//! `container_images.json` lists the runtime's local images with their
+//! digests, creation times and layer diff IDs, from `images` and
+//! `image inspect`; it is only written when the client works.
+//!
+//! Environment variables often carry credentials, so they pass through the
+//! volatile data scrubber before being written.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+use crate::security::credential_scrubber::scrub_volatile_text;
+
+/// File name used for the container summary
+pub const CONTAINER_SUMMARY_FILE: &str = "container_summary.json";
+
+/// File name used for the local image listing
+pub const CONTAINER_IMAGES_FILE: &str = "container_images.json";
+
+/// Container runtime whose storage is being collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    /// Command-line client, also used as the runtime name in the summary
+    pub fn command(&self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+
+    /// Directory holding the configuration and logs of one container
+    fn files_dir(&self, container_dir: &Path) -> PathBuf {
+        match self {
+            ContainerRuntime::Docker => container_dir.to_path_buf(),
+            ContainerRuntime::Podman => container_dir.join("userdata"),
+        }
+    }
+
+    /// On-disk configuration read when `inspect` is unavailable
+    fn config_file(&self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "config.v2.json",
+            ContainerRuntime::Podman => "config.json",
+        }
+    }
+}
+
+/// A volume or bind mount of a container
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerMount {
+    /// `bind`, `volume`, `tmpfs`, ...
+    pub mount_type: Option<String>,
+    /// Host path (or volume path) mounted into the container
+    pub source: String,
+    pub destination: String,
+    pub read_write: bool,
+}
+
+/// One container in `container_summary.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerSummary {
+    pub id: String,
+    pub name: String,
+    pub runtime: String,
+    /// Image reference the container was created from, e.g. `nginx:1.25`
+    pub image: Option<String>,
+    /// Image ID (`sha256:` digest of the image configuration)
+    pub image_digest: Option<String>,
+    pub created: Option<String>,
+    pub state: Option<String>,
+    /// `NAME=value` entries, with secret values scrubbed
+    pub environment: Vec<String>,
+    pub mounts: Vec<ContainerMount>,
+    /// `docker inspect`, `podman inspect` or the configuration file read
+    pub metadata_source: String,
+}
+
+/// Contents of `container_summary.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerSummaryReport {
+    pub runtime: String,
+    pub storage_path: String,
+    pub collection_time: String,
+    pub containers: Vec<ContainerSummary>,
+}
+
+/// A local image in `container_images.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerImage {
+    /// Image ID (`sha256:` digest of the image configuration)
+    pub id: String,
+    pub repository: Option<String>,
+    pub tag: Option<String>,
+    /// Registry manifest digest, for pulled images
+    pub digest: Option<String>,
+    pub created: Option<String>,
+    pub size: Option<String>,
+    /// Diff IDs of the image layers, base first
+    pub layers: Vec<String>,
+}
+
+/// Copy every container under `storage_dir` into `output_dir` and write
+/// `container_summary.json`, inspecting each container with the runtime's
+/// client, then list the local images in `container_images.json`
+pub fn collect_containers(
+    runtime: ContainerRuntime,
+    storage_dir: &Path,
+    output_dir: &Path,
+) -> Result<Vec<ArtifactMetadata>> {
+    let collected = collect_from(runtime, storage_dir, output_dir, |id| {
+        run_inspect(runtime, id)
+    })?;
+
+    match list_images(runtime) {
+        Ok(images) => {
+            let path = output_dir.join(CONTAINER_IMAGES_FILE);
+            let json =
+                serde_json::to_string_pretty(&images).context("Failed to serialize images")?;
+            fs::write(&path, json)
+                .context(format!("Failed to write images to {}", path.display()))?;
+        }
+        Err(e) => warn!("Failed to list {} images: {:#}", runtime.command(), e),
+    }
+    Ok(collected)
+}
+
+/// Local images of `runtime`, with layers from `image inspect`
+fn list_images(runtime: ContainerRuntime) -> Result<Vec<ContainerImage>> {
+    let listing = run_client(
+        runtime,
+        &[
+            "images",
+            "--no-trunc",
+            "--digests",
+            "--format",
+            "{{json .}}",
+        ],
+    )?;
+    let mut images = parse_image_list(&listing);
+
+    let mut ids: Vec<&str> = images.iter().map(|image| image.id.as_str()).collect();
+    ids.sort();
+    ids.dedup();
+    if !ids.is_empty() {
+        let mut args = vec!["image", "inspect"];
+        args.extend(ids);
+        match run_client(runtime, &args) {
+            Ok(output) => add_image_details(&mut images, &output),
+            Err(e) => debug!("{} image inspect failed: {:#}", runtime.command(), e),
+        }
+    }
+    Ok(images)
+}
+
+/// Parse `images --format '{{json .}}'`, one object per line; Docker and
+/// Podman name the fields alike but differ in case and value types
+fn parse_image_list(output: &str) -> Vec<ContainerImage> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .filter_map(|value| {
+            let id = string_at(&value, &["ID", "Id"])?;
+            let present =
+                |keys: &[&str]| string_at(&value, keys).filter(|v| !v.is_empty() && v != "<none>");
+            Some(ContainerImage {
+                id: if id.contains(':') {
+                    id
+                } else {
+                    format!("sha256:{}", id)
+                },
+                repository: present(&["Repository"]),
+                tag: present(&["Tag"]),
+                digest: present(&["Digest"]),
+                created: present(&["CreatedAt"]),
+                size: present(&["Size"]).or_else(|| {
+                    value
+                        .get("Size")
+                        .and_then(Value::as_u64)
+                        .map(|size| size.to_string())
+                }),
+                layers: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Fill in RFC 3339 creation times and layer diff IDs from `image inspect`
+fn add_image_details(images: &mut [ContainerImage], inspect_output: &str) {
+    let Ok(Value::Array(inspected)) = serde_json::from_str::<Value>(inspect_output) else {
+        return;
+    };
+    for details in &inspected {
+        let Some(id) = string_at(details, &["Id", "ID"]) else {
+            continue;
+        };
+        let layers: Vec<String> = details
+            .get("RootFS")
+            .and_then(|rootfs| rootfs.get("Layers"))
+            .and_then(Value::as_array)
+            .map(|layers| {
+                layers
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let created = string_at(details, &["Created"]);
+        for image in images.iter_mut().filter(|image| {
+            image.id.trim_start_matches("sha256:") == id.trim_start_matches("sha256:")
+        }) {
+            image.layers = layers.clone();
+            if created.is_some() {
+                image.created = created.clone();
+            }
+        }
+    }
+}
+
+fn collect_from<F>(
+    runtime: ContainerRuntime,
+    storage_dir: &Path,
+    output_dir: &Path,
+    inspect: F,
+) -> Result<Vec<ArtifactMetadata>>
+where
+    F: Fn(&str) -> Result<String>,
+{
+    info!(
+        "Collecting {} containers from {}",
+        runtime.command(),
+        storage_dir.display()
+    );
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut container_dirs: Vec<PathBuf> = fs::read_dir(storage_dir)
+        .context(format!("Failed to read {}", storage_dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    container_dirs.sort();
+
+    let fallback = FallbackCollector::new();
+    let mut collected = Vec::new();
+    let mut report = ContainerSummaryReport {
+        runtime: runtime.command().to_string(),
+        storage_path: storage_dir.to_string_lossy().to_string(),
+        collection_time: Utc::now().to_rfc3339(),
+        containers: Vec::new(),
+    };
+
+    for container_dir in container_dirs {
+        let id = file_name(&container_dir);
+        let files_dir = runtime.files_dir(&container_dir);
+
+        for file in regular_files(&files_dir) {
+            let dest = output_dir.join(&id).join(file_name(&file));
+            match fallback.collect_standard_file(&file, &dest) {
+                Ok(metadata) => collected.push(metadata),
+                Err(e) => warn!("Failed to copy {}: {:#}", file.display(), e),
+            }
+        }
+
+        match container_summary(runtime, &id, &files_dir, &inspect) {
+            Ok(summary) => report.containers.push(summary),
+            Err(e) => warn!("No metadata for container {}: {:#}", id, e),
+        }
+    }
+
+    let path = output_dir.join(CONTAINER_SUMMARY_FILE);
+    let json =
+        serde_json::to_string_pretty(&report).context("Failed to serialize container summary")?;
+    fs::write(&path, json).context(format!(
+        "Failed to write container summary to {}",
+        path.display()
+    ))?;
+
+    info!(
+        "Collected {} {} containers",
+        report.containers.len(),
+        runtime.command()
+    );
+    Ok(collected)
+}
+
+/// Summary of one container from `inspect`, else from its configuration file
+fn container_summary<F>(
+    runtime: ContainerRuntime,
+    id: &str,
+    files_dir: &Path,
+    inspect: &F,
+) -> Result<ContainerSummary>
+where
+    F: Fn(&str) -> Result<String>,
+{
+    let inspected = inspect(id).and_then(|output| {
+        let value: Value =
+            serde_json::from_str(&output).context("Failed to parse inspect output")?;
+        // inspect prints an array with one object per container
+        match value {
+            Value::Array(mut items) if !items.is_empty() => Ok(items.swap_remove(0)),
+            Value::Object(_) => Ok(value),
+            _ => Err(anyhow!("inspect returned no container")),
+        }
+    });
+
+    let (value, source) = match inspected {
+        Ok(value) => (value, format!("{} inspect", runtime.command())),
+        Err(e) => {
+            debug!(
+                "{} inspect {} failed, reading configuration: {:#}",
+                runtime.command(),
+                id,
+                e
+            );
+            let config = files_dir.join(runtime.config_file());
+            let data = fs::read_to_string(&config)
+                .context(format!("Failed to read {}", config.display()))?;
+            let value = serde_json::from_str(&data)
+                .context(format!("Failed to parse {}", config.display()))?;
+            (value, config.to_string_lossy().to_string())
+        }
+    };
+
+    let mut summary = summary_from_json(&value, runtime, &source);
+    if summary.id.is_empty() {
+        summary.id = id.to_string();
+    }
+    Ok(summary)
+}
+
+/// Build a summary from `docker inspect`, `podman inspect`, Docker's
+/// `config.v2.json` or an OCI runtime `config.json`; the layouts share
+/// most keys and differ in case and in how mounts are listed
+pub(crate) fn summary_from_json(
+    value: &Value,
+    runtime: ContainerRuntime,
+    source: &str,
+) -> ContainerSummary {
+    let config = value.get("Config");
+
+    let image_digest = string_at(value, &["Image"])
+        .filter(|image| !image.is_empty())
+        .map(|image| {
+            // Podman reports the bare hex image ID
+            if image.contains(':') {
+                image
+            } else {
+                format!("sha256:{}", image)
+            }
+        });
+
+    let state = value.get("State").and_then(|state| {
+        string_at(state, &["Status"]).or_else(|| {
+            state
+                .get("Running")
+                .and_then(Value::as_bool)
+                .map(|running| if running { "running" } else { "exited" }.to_string())
+        })
+    });
+
+    let environment = config
+        .and_then(|c| c.get("Env"))
+        .or_else(|| value.get("process").and_then(|p| p.get("env")))
+        .and_then(Value::as_array)
+        .map(|env| {
+            env.iter()
+                .filter_map(Value::as_str)
+                .map(scrub_volatile_text)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ContainerSummary {
+        id: string_at(value, &["Id", "ID"]).unwrap_or_default(),
+        name: string_at(value, &["Name"])
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default(),
+        runtime: runtime.command().to_string(),
+        image: config
+            .and_then(|c| string_at(c, &["Image"]))
+            .or_else(|| string_at(value, &["ImageName"])),
+        image_digest,
+        created: string_at(value, &["Created"]),
+        state,
+        environment,
+        mounts: container_mounts(value),
+        metadata_source: source.to_string(),
+    }
+}
+
+/// Mounts from `Mounts` (inspect), `MountPoints` (`config.v2.json`) or
+/// `mounts` (OCI spec)
+fn container_mounts(value: &Value) -> Vec<ContainerMount> {
+    let entries: Vec<&Value> = if let Some(mounts) = value.get("Mounts").and_then(Value::as_array) {
+        mounts.iter().collect()
+    } else if let Some(points) = value.get("MountPoints").and_then(Value::as_object) {
+        points.values().collect()
+    } else if let Some(mounts) = value.get("mounts").and_then(Value::as_array) {
+        mounts.iter().collect()
+    } else {
+        Vec::new()
+    };
+
+    entries
+        .into_iter()
+        .map(|mount| {
+            let read_write = match mount.get("RW").and_then(Value::as_bool) {
+                Some(rw) => rw,
+                None => !mount
+                    .get("options")
+                    .and_then(Value::as_array)
+                    .is_some_and(|options| options.iter().any(|o| o.as_str() == Some("ro"))),
+            };
+            ContainerMount {
+                mount_type: string_at(mount, &["Type", "type"]).filter(|t| !t.is_empty()),
+                source: string_at(mount, &["Source", "source"]).unwrap_or_default(),
+                destination: string_at(mount, &["Destination", "destination"]).unwrap_or_default(),
+                read_write,
+            }
+        })
+        .collect()
+}
+
+/// First of `keys` present in `value` as a string
+pub(crate) fn string_at(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+/// Run `<runtime> inspect <id>` and return its stdout
+pub(crate) fn run_inspect(runtime: ContainerRuntime, id: &str) -> Result<String> {
+    run_client(runtime, &["inspect", id])
+}
+
+/// Run the runtime's client with `args` and return its stdout
+fn run_client(runtime: ContainerRuntime, args: &[&str]) -> Result<String> {
+    let output = run_command(Command::new(runtime.command()).args(args)).context(format!(
+        "Failed to execute {} {}",
+        runtime.command(),
+        args[0]
+    ))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(anyhow!(
+            "{} {} failed: {}",
+            runtime.command(),
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Regular files directly in `dir`; sub-directories (`mounts`,
+/// `checkpoints`, `shm`) are not copied
+fn regular_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::credential_scrubber::SCRUBBED;
+    use tempfile::TempDir;
+
+    const INSPECTED_ID: &str = "4f2a9c";
+    const OFFLINE_ID: &str = "9b1e07";
+
+    const DOCKER_INSPECT: &str = r#"[{
+        "Id": "4f2a9c",
+        "Created": "2024-03-05T14:30:10.123456789Z",
+        "Name": "/web",
+        "Image": "sha256:0d8a1b",
+        "State": {"Status": "running", "Running": true},
+        "Config": {
+            "Image": "nginx:1.25",
+            "Env": ["PATH=/usr/bin", "DB_PASSWORD=hunter2"]
+        },
+        "Mounts": [
+            {"Type": "bind", "Source": "/srv/www", "Destination": "/usr/share/nginx/html", "RW": false}
+        ]
+    }]"#;
+
+    const CONFIG_V2: &str = r#"{
+        "ID": "9b1e07",
+        "Created": "2024-03-01T08:00:00Z",
+        "Name": "/backup",
+        "Image": "sha256:77aa01",
+        "State": {"Running": false},
+        "Config": {"Image": "alpine:3.19", "Env": ["API_TOKEN=abc123"]},
+        "MountPoints": {
+            "/data": {"Type": "volume", "Source": "/var/lib/docker/volumes/data/_data", "Destination": "/data", "RW": true}
+        }
+    }"#;
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_collect_docker_containers() {
+        let temp = TempDir::new().unwrap();
+        let storage = temp.path().join("containers");
+        let web = storage.join(INSPECTED_ID);
+        write_file(&web.join("config.v2.json"), "{}");
+        write_file(
+            &web.join(format!("{}-json.log", INSPECTED_ID)),
+            "{\"log\":\"GET /\\n\"}\n",
+        );
+        fs::create_dir_all(web.join("mounts").join("shm")).unwrap();
+        write_file(&storage.join(OFFLINE_ID).join("config.v2.json"), CONFIG_V2);
+
+        let output = temp.path().join("out");
+        let collected = collect_from(ContainerRuntime::Docker, &storage, &output, |id| {
+            if id == INSPECTED_ID {
+                Ok(DOCKER_INSPECT.to_string())
+            } else {
+                Err(anyhow!("Cannot connect to the Docker daemon"))
+            }
+        })
+        .unwrap();
+
+        assert_eq!(collected.len(), 3);
+        assert!(output
+            .join(INSPECTED_ID)
+            .join(format!("{}-json.log", INSPECTED_ID))
+            .is_file());
+        assert!(!output.join(INSPECTED_ID).join("mounts").exists());
+
+        let report: ContainerSummaryReport =
+            serde_json::from_str(&fs::read_to_string(output.join(CONTAINER_SUMMARY_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(report.runtime, "docker");
+        assert_eq!(report.containers.len(), 2);
+
+        let web = &report.containers[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.image.as_deref(), Some("nginx:1.25"));
+        assert_eq!(web.image_digest.as_deref(), Some("sha256:0d8a1b"));
+        assert_eq!(
+            web.created.as_deref(),
+            Some("2024-03-05T14:30:10.123456789Z")
+        );
+        assert_eq!(web.state.as_deref(), Some("running"));
+        assert_eq!(
+            web.environment,
+            vec![
+                "PATH=/usr/bin".to_string(),
+                format!("DB_PASSWORD={}", SCRUBBED)
+            ]
+        );
+        assert_eq!(
+            web.mounts,
+            vec![ContainerMount {
+                mount_type: Some("bind".to_string()),
+                source: "/srv/www".to_string(),
+                destination: "/usr/share/nginx/html".to_string(),
+                read_write: false,
+            }]
+        );
+        assert_eq!(web.metadata_source, "docker inspect");
+
+        let backup = &report.containers[1];
+        assert_eq!(backup.id, OFFLINE_ID);
+        assert_eq!(backup.state.as_deref(), Some("exited"));
+        assert_eq!(backup.environment, vec![format!("API_TOKEN={}", SCRUBBED)]);
+        assert_eq!(backup.mounts[0].destination, "/data");
+        assert!(backup.mounts[0].read_write);
+        assert!(backup.metadata_source.ends_with("config.v2.json"));
+    }
+
+    #[test]
+    fn test_podman_oci_config_fallback() {
+        let temp = TempDir::new().unwrap();
+        let storage = temp.path().join("overlay-containers");
+        let userdata = storage.join("c0ffee").join("userdata");
+        write_file(
+            &userdata.join("config.json"),
+            r#"{
+                "process": {"env": ["HOME=/root"]},
+                "mounts": [
+                    {"destination": "/proc", "type": "proc", "source": "proc"},
+                    {"destination": "/etc/app", "type": "bind", "source": "/opt/app", "options": ["rbind", "ro"]}
+                ]
+            }"#,
+        );
+        write_file(
+            &userdata.join("ctr.log"),
+            "2024-03-05T14:30:10Z stdout F ready\n",
+        );
+
+        let output = temp.path().join("out");
+        let collected = collect_from(ContainerRuntime::Podman, &storage, &output, |_| {
+            Err(anyhow!("podman not found"))
+        })
+        .unwrap();
+        assert_eq!(collected.len(), 2);
+        assert!(output.join("c0ffee").join("ctr.log").is_file());
+
+        let report: ContainerSummaryReport =
+            serde_json::from_str(&fs::read_to_string(output.join(CONTAINER_SUMMARY_FILE)).unwrap())
+                .unwrap();
+        let container = &report.containers[0];
+        assert_eq!(container.id, "c0ffee");
+        assert_eq!(container.runtime, "podman");
+        assert_eq!(container.environment, vec!["HOME=/root"]);
+        assert_eq!(container.mounts.len(), 2);
+        assert!(container.mounts[0].read_write);
+        assert!(!container.mounts[1].read_write);
+
+        // podman inspect reports the bare image ID
+        let inspected = summary_from_json(
+            &serde_json::json!({"Id": "c0ffee", "Image": "77aa01", "ImageName": "docker.io/library/redis:7"}),
+            ContainerRuntime::Podman,
+            "podman inspect",
+        );
+        assert_eq!(inspected.image_digest.as_deref(), Some("sha256:77aa01"));
+        assert_eq!(
+            inspected.image.as_deref(),
+            Some("docker.io/library/redis:7")
+        );
+    }
+
+    #[test]
+    fn test_parse_image_list() {
+        let docker = concat!(
+            r#"{"Containers":"N/A","CreatedAt":"2024-02-14 09:12:01 +0000 UTC","Digest":"sha256:56b388","ID":"sha256:0d8a1b","Repository":"nginx","Tag":"1.25","Size":"187MB"}"#,
+            "\n",
+            r#"{"CreatedAt":"2024-03-01 08:00:00 +0000 UTC","Digest":"<none>","ID":"sha256:77aa01","Repository":"<none>","Tag":"<none>","Size":"7.38MB"}"#,
+            "\nnot json\n"
+        );
+        let mut images = parse_image_list(docker);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].repository.as_deref(), Some("nginx"));
+        assert_eq!(images[0].digest.as_deref(), Some("sha256:56b388"));
+        assert_eq!(images[1].repository, None);
+        assert_eq!(images[1].digest, None);
+
+        add_image_details(
+            &mut images,
+            r#"[{"Id": "sha256:0d8a1b", "Created": "2024-02-14T09:12:01.5Z",
+                 "RootFS": {"Type": "layers", "Layers": ["sha256:basediff", "sha256:appdiff"]}}]"#,
+        );
+        assert_eq!(images[0].created.as_deref(), Some("2024-02-14T09:12:01.5Z"));
+        assert_eq!(images[0].layers, vec!["sha256:basediff", "sha256:appdiff"]);
+        assert!(images[1].layers.is_empty());
+
+        // Podman reports bare hex IDs and numeric sizes
+        let podman = parse_image_list(
+            r#"{"ID":"77aa01","Repository":"docker.io/library/redis","Tag":"7","Size":41234567}"#,
+        );
+        assert_eq!(podman[0].id, "sha256:77aa01");
+        assert_eq!(podman[0].size.as_deref(), Some("41234567"));
+    }
+}
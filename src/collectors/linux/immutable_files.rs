@@ -0,0 +1,252 @@
+//! Files with the immutable or append-only attribute.
+//!
+//! `chattr +i` makes a file impossible to modify, rename or delete, even
+//! for root, until the flag is cleared again; attackers set it on their
+//! tools, cron entries and `authorized_keys` so cleanup fails. `chattr +a`
+//! (append-only) is used the same way on logs and configuration.
+//!
+//! The flags are read with the `FS_IOC_GETFLAGS` ioctl, as `lsattr` does,
+//! for every regular file and directory below the roots. Symbolic links
+//! are not followed and `/proc`, `/sys`, `/dev` and `/run` are skipped.
+//! Filesystems without the attributes (tmpfs, network shares) report
+//! nothing. Flagged regular files are hashed.
+//!
+//! Immutable and append-only files are expected under `/boot` and kernel
+//! module directories, where some distributions protect the kernel;
+//! anywhere else they are marked `suspicious`. Results are written to
+//! `immutable_files.json`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::collectors::footprint::create_dir_all_tracked;
+use crate::models::ArtifactMetadata;
+use crate::utils::hash::calculate_sha256;
+
+/// File name used for the results
+pub const IMMUTABLE_FILES_FILE: &str = "immutable_files.json";
+
+/// `FS_IMMUTABLE_FL` from `linux/fs.h`
+pub const FS_IMMUTABLE_FL: u32 = 0x0000_0010;
+
+/// `FS_APPEND_FL` from `linux/fs.h`
+pub const FS_APPEND_FL: u32 = 0x0000_0020;
+
+/// Files larger than this are not hashed
+const MAX_HASH_SIZE_MB: u64 = 256;
+
+/// Virtual filesystems that are never walked
+const SKIPPED_DIRS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+
+/// Where immutable files are normal
+const EXPECTED_LOCATIONS: &[&str] = &["/boot", "/lib/modules", "/usr/lib/modules"];
+
+/// A file or directory with the immutable or append-only flag
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImmutableFileInfo {
+    pub path: PathBuf,
+    /// All inode flags, as `FS_IOC_GETFLAGS` reports them
+    pub flags: u32,
+    pub is_immutable: bool,
+    pub is_append_only: bool,
+    pub owner_uid: Option<u32>,
+    /// SHA-256 of regular files up to 256 MB
+    pub sha256: Option<String>,
+    /// Outside `/boot` and the kernel module directories
+    pub suspicious: bool,
+}
+
+impl ImmutableFileInfo {
+    /// Entry for `path` with inode `flags`
+    fn new(path: &Path, flags: u32, owner_uid: Option<u32>, sha256: Option<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            flags,
+            is_immutable: flags & FS_IMMUTABLE_FL != 0,
+            is_append_only: flags & FS_APPEND_FL != 0,
+            owner_uid,
+            sha256,
+            suspicious: !is_expected_location(path),
+        }
+    }
+}
+
+/// Whether immutable files are normal at `path`
+pub fn is_expected_location(path: &Path) -> bool {
+    EXPECTED_LOCATIONS
+        .iter()
+        .any(|location| path.starts_with(location))
+}
+
+/// Every immutable or append-only file and directory below `roots`.
+///
+/// Entries that cannot be read are skipped; only a root that does not
+/// exist is an error.
+pub fn find_immutable_files(roots: &[PathBuf]) -> Result<Vec<ImmutableFileInfo>> {
+    let mut found = Vec::new();
+    for root in roots {
+        fs::symlink_metadata(root).context(format!("Cannot read {}", root.display()))?;
+        let walker = WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| {
+                !SKIPPED_DIRS
+                    .iter()
+                    .any(|dir| entry.path() == Path::new(dir))
+            });
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("Skipping unreadable entry: {}", e);
+                    continue;
+                }
+            };
+            let file_type = entry.file_type();
+            // Opening devices or FIFOs can block or have side effects
+            if !file_type.is_file() && !file_type.is_dir() {
+                continue;
+            }
+            let Some(flags) = inode_flags(entry.path()) else {
+                continue;
+            };
+            if flags & (FS_IMMUTABLE_FL | FS_APPEND_FL) == 0 {
+                continue;
+            }
+            let sha256 = if file_type.is_file() {
+                calculate_sha256(entry.path(), MAX_HASH_SIZE_MB)
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            };
+            found.push(ImmutableFileInfo::new(
+                entry.path(),
+                flags,
+                owner_uid(entry.path()),
+                sha256,
+            ));
+        }
+    }
+    Ok(found)
+}
+
+/// Inode flags of `path`, or `None` when its filesystem has none
+#[cfg(target_os = "linux")]
+fn inode_flags(path: &Path) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = crate::collectors::read_only::open(path).ok()?;
+    let mut flags: libc::c_int = 0;
+    // SAFETY: FS_IOC_GETFLAGS writes one int (despite the ioctl number's
+    // long size) through the pointer, on an open descriptor
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) };
+    (result == 0).then_some(flags as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn inode_flags(_path: &Path) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn owner_uid(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::symlink_metadata(path)
+        .ok()
+        .map(|metadata| metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn owner_uid(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Write the immutable files below `root` to
+/// `output_dir/immutable_files.json`
+pub fn collect_immutable_files(root: &Path, output_dir: &Path) -> Result<ArtifactMetadata> {
+    info!("Looking for immutable files below {}", root.display());
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let files = find_immutable_files(&[root.to_path_buf()])?;
+    let suspicious = files.iter().filter(|f| f.suspicious).count();
+    let json =
+        serde_json::to_string_pretty(&files).context("Failed to serialize immutable files")?;
+    let path = output_dir.join(IMMUTABLE_FILES_FILE);
+    fs::write(&path, &json).context(format!("Failed to write {}", path.display()))?;
+    info!(
+        "Found {} immutable or append-only files, {} outside expected locations",
+        files.len(),
+        suspicious
+    );
+
+    Ok(ArtifactMetadata {
+        original_path: root.to_string_lossy().to_string(),
+        collection_time: Utc::now().to_rfc3339(),
+        file_size: json.len() as u64,
+        created_time: None,
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flags_and_locations() {
+        let module = ImmutableFileInfo::new(
+            Path::new("/lib/modules/6.1.0/kernel/fs/ext4.ko"),
+            FS_IMMUTABLE_FL | 0x80000,
+            Some(0),
+            None,
+        );
+        assert!(module.is_immutable);
+        assert!(!module.is_append_only);
+        assert!(!module.suspicious);
+
+        let tool = ImmutableFileInfo::new(
+            Path::new("/usr/bin/.x/kworker"),
+            FS_APPEND_FL,
+            Some(0),
+            None,
+        );
+        assert!(!tool.is_immutable);
+        assert!(tool.is_append_only);
+        assert!(tool.suspicious);
+
+        assert!(is_expected_location(Path::new("/boot/vmlinuz")));
+        assert!(!is_expected_location(Path::new("/bootstrap/tool")));
+        assert!(!is_expected_location(Path::new("/etc/cron.d/job")));
+    }
+
+    #[test]
+    fn test_unflagged_tree_has_no_results() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/b")).unwrap();
+        fs::write(temp.path().join("a/b/file"), b"data").unwrap();
+
+        let output = temp.path().join("out");
+        collect_immutable_files(&temp.path().join("a"), &output).unwrap();
+        let files: Vec<ImmutableFileInfo> =
+            serde_json::from_str(&fs::read_to_string(output.join(IMMUTABLE_FILES_FILE)).unwrap())
+                .unwrap();
+        assert!(files.is_empty());
+        assert!(find_immutable_files(&[temp.path().join("missing")]).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! Kernel ring buffer messages.
+//!
+//! The ring buffer holds hardware and driver errors alongside
+//! security-relevant kernel events: OOM kills, segfaults, Yama ptrace
+//! denials, module loads and taint warnings. It is lost on reboot and
+//! overwritten as it fills, so it is read with `dmesg` rather than copied.
+//!
+//! `dmesg --time-format=iso --json` (util-linux 2.38+, usually with kernel
+//! 5.15+ distributions) is tried first. Older `dmesg` builds get
+//! `dmesg --raw`, whose `<pri>[seconds]` prefixes are parsed instead and
+//! whose output is also kept as `dmesg.txt`. Either way every message is
+//! written as one [`DmesgEntry`] per line to `dmesg.jsonl`.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+use crate::models::ArtifactMetadata;
+use crate::security::credential_scrubber::{scrub_volatile_text, volatile_scrubbing_enabled};
+
+/// File name used for the parsed messages
+pub const DMESG_FILE: &str = "dmesg.jsonl";
+
+/// File name used for the unparsed `dmesg --raw` output
+pub const DMESG_RAW_FILE: &str = "dmesg.txt";
+
+/// Syslog facility names, indexed by facility number
+const FACILITIES: &[&str] = &[
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+/// Syslog level names, indexed by level number
+const LEVELS: &[&str] = &[
+    "emerg", "alert", "crit", "err", "warn", "notice", "info", "debug",
+];
+
+/// One kernel message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DmesgEntry {
+    /// Syslog facility, `kern` for messages from the kernel itself
+    pub facility: Option<String>,
+    /// Syslog level, from `emerg` to `debug`
+    pub level: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Run `dmesg` and write the parsed ring buffer to `output_dir/dmesg.jsonl`.
+///
+/// Fails if neither invocation of `dmesg` succeeds, which is usually
+/// `kernel.dmesg_restrict` refusing a non-root user.
+pub fn collect_dmesg(output_dir: &Path) -> Result<ArtifactMetadata> {
+    info!("Collecting kernel ring buffer");
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let (command, entries) = match run_json() {
+        Ok(entries) => ("dmesg --time-format=iso --json", entries),
+        Err(e) => {
+            debug!("dmesg --json unavailable, falling back to --raw: {:#}", e);
+            let output = run_command(Command::new("dmesg").arg("--raw"))
+                .context("Failed to execute dmesg")?;
+            if !output.status.success() {
+                bail!(
+                    "dmesg failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            let raw = String::from_utf8_lossy(&output.stdout);
+            let raw_path = output_dir.join(DMESG_RAW_FILE);
+            fs::write(&raw_path, scrub(raw.to_string()))
+                .context(format!("Failed to write {}", raw_path.display()))?;
+            ("dmesg --raw", parse_raw(&raw, boot_time()))
+        }
+    };
+
+    let mut jsonl = String::new();
+    for mut entry in entries.iter().cloned() {
+        entry.message = scrub(entry.message);
+        jsonl.push_str(
+            &serde_json::to_string(&entry).context("Failed to serialize kernel message")?,
+        );
+        jsonl.push('\n');
+    }
+    let path = output_dir.join(DMESG_FILE);
+    fs::write(&path, &jsonl).context(format!("Failed to write {}", path.display()))?;
+    info!(
+        "Collected {} kernel messages with {}",
+        entries.len(),
+        command
+    );
+
+    Ok(ArtifactMetadata {
+        original_path: command.to_string(),
+        collection_time: Utc::now().to_rfc3339(),
+        file_size: jsonl.len() as u64,
+        created_time: None,
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        ..Default::default()
+    })
+}
+
+fn scrub(text: String) -> String {
+    if volatile_scrubbing_enabled() {
+        scrub_volatile_text(&text)
+    } else {
+        text
+    }
+}
+
+fn run_json() -> Result<Vec<DmesgEntry>> {
+    let output = run_command(Command::new("dmesg").arg("--time-format=iso").arg("--json"))
+        .context("Failed to execute dmesg")?;
+    if !output.status.success() {
+        bail!(
+            "dmesg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_json(&output.stdout, boot_time())
+}
+
+/// Boot time from `btime` in `/proc/stat`
+fn boot_time() -> Option<DateTime<Utc>> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    let btime = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()?;
+    Utc.timestamp_opt(btime, 0).single()
+}
+
+/// Split a syslog priority into facility and level names
+fn decode_priority(pri: u64) -> (Option<String>, Option<String>) {
+    let name = |names: &[&str], index: u64| {
+        names
+            .get(index as usize)
+            .map(|n| n.to_string())
+            .or_else(|| Some(index.to_string()))
+    };
+    (name(FACILITIES, pri >> 3), name(LEVELS, pri & 7))
+}
+
+/// Timestamp from seconds since boot
+fn since_boot(seconds: f64, boot: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    let offset = chrono::Duration::microseconds((seconds * 1_000_000.0) as i64);
+    boot.map(|boot| boot + offset)
+}
+
+/// Parse `dmesg --json` output.
+///
+/// `time` is the ISO string asked for, or seconds since boot from builds
+/// that ignore `--time-format` in JSON mode. `pri` is the raw priority, or
+/// `fac` and `pri` are names when `dmesg` decoded them.
+fn parse_json(output: &[u8], boot: Option<DateTime<Utc>>) -> Result<Vec<DmesgEntry>> {
+    let document: serde_json::Value =
+        serde_json::from_slice(output).context("dmesg output is not JSON")?;
+    let records = document
+        .get("dmesg")
+        .and_then(|records| records.as_array())
+        .context("dmesg JSON has no \"dmesg\" array")?;
+
+    let mut entries = Vec::with_capacity(records.len());
+    for record in records {
+        let message = record
+            .get("msg")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let timestamp = match record.get("time") {
+            Some(serde_json::Value::String(time)) => parse_iso(time),
+            Some(serde_json::Value::Number(seconds)) => {
+                seconds.as_f64().and_then(|s| since_boot(s, boot))
+            }
+            _ => None,
+        };
+        let Some(timestamp) = timestamp else {
+            warn!("Kernel message without a usable timestamp: {}", message);
+            continue;
+        };
+        let (facility, level) = match record.get("pri") {
+            Some(serde_json::Value::Number(pri)) => {
+                pri.as_u64().map(decode_priority).unwrap_or_default()
+            }
+            Some(serde_json::Value::String(level)) => (
+                record
+                    .get("fac")
+                    .and_then(|f| f.as_str())
+                    .map(str::to_string),
+                Some(level.clone()),
+            ),
+            _ => (None, None),
+        };
+        entries.push(DmesgEntry {
+            facility,
+            level,
+            timestamp,
+            message,
+        });
+    }
+    Ok(entries)
+}
+
+/// Parse dmesg's ISO format, which uses a comma before the fraction
+fn parse_iso(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&time.replacen(',', ".", 1))
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Parse `dmesg --raw` output: `<pri>[seconds] message` lines.
+///
+/// Lines without the prefix continue the previous message. Messages logged
+/// with `printk.time=0` have no seconds and are stamped with the boot time.
+fn parse_raw(output: &str, boot: Option<DateTime<Utc>>) -> Vec<DmesgEntry> {
+    let mut entries: Vec<DmesgEntry> = Vec::new();
+    for line in output.lines() {
+        let prefixed = line.strip_prefix('<').and_then(|rest| {
+            let (pri, rest) = rest.split_once('>')?;
+            Some((pri.parse::<u64>().ok()?, rest))
+        });
+        let Some((pri, rest)) = prefixed else {
+            if let Some(previous) = entries.last_mut() {
+                previous.message.push('\n');
+                previous.message.push_str(line);
+            }
+            continue;
+        };
+
+        let (seconds, message) = match rest
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(seconds, message)| Some((seconds.trim().parse::<f64>().ok()?, message)))
+        {
+            Some((seconds, message)) => (seconds, message.strip_prefix(' ').unwrap_or(message)),
+            None => (0.0, rest),
+        };
+        let Some(timestamp) = since_boot(seconds, boot) else {
+            continue;
+        };
+        let (facility, level) = decode_priority(pri);
+        entries.push(DmesgEntry {
+            facility,
+            level,
+            timestamp,
+            message: message.to_string(),
+        });
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let output = br#"{
+           "dmesg": [
+              {"pri": 6, "time": "2024-03-01T10:15:30,123456+00:00", "msg": "Linux version 6.1.0"},
+              {"fac": "kern", "pri": "err", "time": "2024-03-01T12:15:30,000000+02:00", "msg": "Out of memory: Killed process 4242 (java)"},
+              {"pri": 28, "time": 12.5, "msg": "systemd[1]: started"}
+           ]
+        }"#;
+        let boot = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).single();
+
+        let entries = parse_json(output, boot).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].facility.as_deref(), Some("kern"));
+        assert_eq!(entries[0].level.as_deref(), Some("info"));
+        assert_eq!(
+            entries[0].timestamp.to_rfc3339(),
+            "2024-03-01T10:15:30.123456+00:00"
+        );
+        assert_eq!(entries[1].level.as_deref(), Some("err"));
+        assert_eq!(
+            entries[1].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 1, 10, 15, 30).unwrap()
+        );
+        assert_eq!(entries[2].facility.as_deref(), Some("daemon"));
+        assert_eq!(entries[2].level.as_deref(), Some("warn"));
+        assert_eq!(
+            entries[2].timestamp,
+            boot.unwrap() + chrono::Duration::milliseconds(12_500)
+        );
+
+        assert!(parse_json(b"not json", boot).is_err());
+    }
+
+    #[test]
+    fn test_parse_raw() {
+        let output = "<6>[    0.000000] Linux version 6.1.0\n\
+                      <4>[   42.250000] ptrace attach of \"gdb\"[100] was attempted\n\
+                      continued line\n\
+                      <3>no timestamp\n";
+        let boot = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).single();
+
+        let entries = parse_raw(output, boot);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].message, "Linux version 6.1.0");
+        assert_eq!(entries[0].timestamp, boot.unwrap());
+        assert_eq!(entries[1].level.as_deref(), Some("warn"));
+        assert_eq!(
+            entries[1].message,
+            "ptrace attach of \"gdb\"[100] was attempted\ncontinued line"
+        );
+        assert_eq!(
+            entries[1].timestamp,
+            boot.unwrap() + chrono::Duration::milliseconds(42_250)
+        );
+        assert_eq!(entries[2].level.as_deref(), Some("err"));
+        assert_eq!(entries[2].message, "no timestamp");
+
+        assert!(parse_raw(output, None).is_empty());
+    }
+}
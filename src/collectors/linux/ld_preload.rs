@@ -0,0 +1,397 @@
+//! `LD_PRELOAD` hijacking audit.
+//!
+//! The dynamic loader maps every library listed in `/etc/ld.so.preload`, and
+//! in a process's `LD_PRELOAD`, into each program it starts, ahead of libc.
+//! Userland rootkits (Azazel, Jynx, libprocesshider) use this to hook
+//! `readdir` and friends and hide their files and processes. This module
+//! records:
+//!
+//! - the libraries in `/etc/ld.so.preload`
+//! - `LD_PRELOAD` and `LD_LIBRARY_PATH` of every process, from
+//!   `/proc/<pid>/environ` (other users' processes need root)
+//!
+//! Each preloaded library is hashed and flagged as unexpected unless its
+//! path or SHA-256 is listed in `expected_preload_libraries` in
+//! `global_options` (comma-separated). With no baseline every preload is
+//! unexpected, since most hosts have none. `LD_LIBRARY_PATH` is recorded
+//! but not flagged.
+//!
+//! Results are written to `volatile/ld_preload_audit.json`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::volatile::models::ProcessInfo;
+use crate::utils::hash::calculate_sha256;
+
+/// File name used for the audit
+pub const LD_PRELOAD_AUDIT_FILE: &str = "ld_preload_audit.json";
+
+/// Whether the audit runs on this platform
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Libraries larger than this are not hashed
+const MAX_LIBRARY_SIZE_MB: u64 = 256;
+
+lazy_static! {
+    /// Paths and lowercase SHA-256 hashes from `expected_preload_libraries`
+    static ref EXPECTED_PRELOADS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+/// Apply `expected_preload_libraries` from `global_options`, clearing the
+/// baseline when it is missing
+pub fn configure(global_options: &HashMap<String, String>) {
+    let baseline = global_options
+        .get("expected_preload_libraries")
+        .map(|value| parse_baseline(value))
+        .unwrap_or_default();
+    if let Ok(mut expected) = EXPECTED_PRELOADS.write() {
+        *expected = baseline;
+    }
+}
+
+/// Split a comma-separated baseline into paths and lowercase hashes
+fn parse_baseline(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            if entry.len() == 64 && entry.chars().all(|c| c.is_ascii_hexdigit()) {
+                entry.to_ascii_lowercase()
+            } else {
+                entry.to_string()
+            }
+        })
+        .collect()
+}
+
+/// A preloaded library
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PreloadLibrary {
+    /// As listed; may be relative or contain `$LIB`-style tokens
+    pub path: String,
+    /// Whether the library exists on disk
+    pub exists: bool,
+    pub sha256: Option<String>,
+    /// Listed in `expected_preload_libraries` by path or hash
+    pub expected: bool,
+}
+
+/// Loader variables of one process
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessPreload {
+    pub pid: u32,
+    pub process_name: String,
+    pub ld_preload: Vec<PreloadLibrary>,
+    pub ld_library_path: Vec<String>,
+}
+
+/// Result of the audit
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LdPreloadReport {
+    /// Libraries in `/etc/ld.so.preload`
+    pub system_preload: Vec<PreloadLibrary>,
+    /// Processes with `LD_PRELOAD` or `LD_LIBRARY_PATH` set
+    pub processes: Vec<ProcessPreload>,
+    /// Processes whose environment could not be read
+    pub unreadable_processes: usize,
+    /// Distinct unexpected library paths, system-wide and per process
+    pub unexpected: Vec<String>,
+}
+
+/// Audit `/etc/ld.so.preload` and the loader variables of `processes`,
+/// warning about each unexpected library
+pub fn audit(processes: &[ProcessInfo]) -> Result<LdPreloadReport> {
+    let expected = EXPECTED_PRELOADS
+        .read()
+        .map(|expected| expected.clone())
+        .unwrap_or_default();
+    let report = audit_root(Path::new("/"), processes, &expected)?;
+    for path in &report.unexpected {
+        warn!("Unexpected preloaded library {}", path);
+    }
+    Ok(report)
+}
+
+/// Audit the system mounted at `root`
+fn audit_root(
+    root: &Path,
+    processes: &[ProcessInfo],
+    expected: &HashSet<String>,
+) -> Result<LdPreloadReport> {
+    let mut hasher = LibraryHasher::new(root, expected);
+    let mut report = LdPreloadReport::default();
+
+    let preload_file = root.join("etc/ld.so.preload");
+    match fs::read_to_string(&preload_file) {
+        Ok(contents) => {
+            report.system_preload = parse_preload_file(&contents)
+                .into_iter()
+                .map(|path| hasher.describe(path))
+                .collect();
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", preload_file.display()))
+        }
+    }
+
+    for process in processes {
+        let environ = root.join(format!("proc/{}/environ", process.pid));
+        let contents = match fs::read(&environ) {
+            Ok(contents) => contents,
+            // Exited since the process listing
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(_) => {
+                report.unreadable_processes += 1;
+                continue;
+            }
+        };
+        let (ld_preload, ld_library_path) = loader_variables(&contents);
+        if ld_preload.is_none() && ld_library_path.is_none() {
+            continue;
+        }
+        report.processes.push(ProcessPreload {
+            pid: process.pid,
+            process_name: process.name.clone(),
+            ld_preload: ld_preload
+                .map(|value| split_ld_preload(&value))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|path| hasher.describe(path))
+                .collect(),
+            ld_library_path: ld_library_path
+                .map(|value| split_search_path(&value))
+                .unwrap_or_default(),
+        });
+    }
+
+    let mut unexpected: Vec<String> = report
+        .system_preload
+        .iter()
+        .chain(report.processes.iter().flat_map(|p| &p.ld_preload))
+        .filter(|library| !library.expected)
+        .map(|library| library.path.clone())
+        .collect();
+    unexpected.sort();
+    unexpected.dedup();
+    report.unexpected = unexpected;
+    Ok(report)
+}
+
+/// Hashes libraries once however many processes preload them
+struct LibraryHasher<'a> {
+    root: &'a Path,
+    expected: &'a HashSet<String>,
+    hashes: HashMap<String, (bool, Option<String>)>,
+}
+
+impl<'a> LibraryHasher<'a> {
+    fn new(root: &'a Path, expected: &'a HashSet<String>) -> Self {
+        Self {
+            root,
+            expected,
+            hashes: HashMap::new(),
+        }
+    }
+
+    fn describe(&mut self, path: String) -> PreloadLibrary {
+        let root = self.root;
+        let (exists, sha256) = self
+            .hashes
+            .entry(path.clone())
+            .or_insert_with(|| {
+                let on_disk = root_path(root, &path);
+                match calculate_sha256(&on_disk, MAX_LIBRARY_SIZE_MB) {
+                    Ok(sha256) => (true, sha256),
+                    Err(_) => (on_disk.exists(), None),
+                }
+            })
+            .clone();
+        let expected = self.expected.contains(&path)
+            || sha256
+                .as_ref()
+                .is_some_and(|hash| self.expected.contains(hash));
+        PreloadLibrary {
+            path,
+            exists,
+            sha256,
+            expected,
+        }
+    }
+}
+
+/// `path` under `root`; relative paths are left as they are
+fn root_path(root: &Path, path: &str) -> PathBuf {
+    match path.strip_prefix('/') {
+        Some(relative) => root.join(relative),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Library paths in `/etc/ld.so.preload`: separated by whitespace or `:`,
+/// with `#` starting a comment
+fn parse_preload_file(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(split_ld_preload)
+        .collect()
+}
+
+/// Library paths in an `LD_PRELOAD` value, separated by spaces or colons
+fn split_ld_preload(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Directories of an `LD_LIBRARY_PATH` value; `;` is accepted like `:`
+fn split_search_path(value: &str) -> Vec<String> {
+    value
+        .split([':', ';'])
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `LD_PRELOAD` and `LD_LIBRARY_PATH` from NUL-separated `environ` contents
+fn loader_variables(environ: &[u8]) -> (Option<String>, Option<String>) {
+    let mut ld_preload = None;
+    let mut ld_library_path = None;
+    for entry in environ.split(|&b| b == 0) {
+        let entry = String::from_utf8_lossy(entry);
+        if let Some(value) = entry.strip_prefix("LD_PRELOAD=") {
+            ld_preload = Some(value.to_string());
+        } else if let Some(value) = entry.strip_prefix("LD_LIBRARY_PATH=") {
+            ld_library_path = Some(value.to_string());
+        }
+    }
+    (ld_preload, ld_library_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::sha256_reader;
+    use tempfile::TempDir;
+
+    fn process(pid: u32, name: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cmd: vec![name.to_string()],
+            exe: None,
+            status: "Running".to_string(),
+            start_time: 0,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid: Some(1),
+        }
+    }
+
+    fn write(root: &Path, path: &str, contents: &[u8]) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_parse_preload_values() {
+        assert_eq!(
+            parse_preload_file("# hide\n/lib/libhide.so /usr/lib/a.so:/b.so\n\n"),
+            vec!["/lib/libhide.so", "/usr/lib/a.so", "/b.so"]
+        );
+        assert_eq!(
+            split_search_path("/opt/app/lib::/tmp/.x;/usr/lib"),
+            vec!["/opt/app/lib", "/tmp/.x", "/usr/lib"]
+        );
+        let (preload, library_path) =
+            loader_variables(b"HOME=/root\0LD_PRELOAD=/dev/shm/x.so\0PATH=/bin\0");
+        assert_eq!(preload.as_deref(), Some("/dev/shm/x.so"));
+        assert_eq!(library_path, None);
+
+        let baseline = parse_baseline(" /usr/lib/libjemalloc.so.2, ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789 ,");
+        assert!(baseline.contains("/usr/lib/libjemalloc.so.2"));
+        assert!(baseline.contains(&"abcdef0123456789".repeat(4)));
+        assert_eq!(baseline.len(), 2);
+    }
+
+    #[test]
+    fn test_audit_flags_unexpected_preloads() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "etc/ld.so.preload", b"/lib/libprocesshider.so\n");
+        write(root, "lib/libprocesshider.so", b"hooks readdir");
+        write(root, "usr/lib/libjemalloc.so.2", b"allocator");
+        write(
+            root,
+            "proc/100/environ",
+            b"LD_PRELOAD=/usr/lib/libjemalloc.so.2 /tmp/.hidden.so\0LD_LIBRARY_PATH=/opt/app/lib\0",
+        );
+        write(root, "proc/200/environ", b"LD_LIBRARY_PATH=/opt/db/lib\0");
+        write(root, "proc/300/environ", b"HOME=/root\0");
+        let processes = [
+            process(100, "redis-server"),
+            process(200, "postgres"),
+            process(300, "bash"),
+            // Exited before the audit
+            process(400, "cron"),
+        ];
+        let jemalloc = sha256_reader(&b"allocator"[..]).unwrap();
+        let expected: HashSet<String> = [jemalloc.clone()].into_iter().collect();
+
+        let report = audit_root(root, &processes, &expected).unwrap();
+
+        assert_eq!(report.system_preload.len(), 1);
+        assert!(report.system_preload[0].exists);
+        assert!(!report.system_preload[0].expected);
+        assert_eq!(report.processes.len(), 2);
+
+        let redis = &report.processes[0];
+        assert_eq!(redis.pid, 100);
+        assert_eq!(redis.ld_library_path, vec!["/opt/app/lib"]);
+        assert_eq!(
+            redis.ld_preload[0].sha256.as_deref(),
+            Some(jemalloc.as_str())
+        );
+        assert!(redis.ld_preload[0].expected);
+        assert!(!redis.ld_preload[1].exists);
+        assert!(!redis.ld_preload[1].expected);
+
+        assert!(report.processes[1].ld_preload.is_empty());
+        assert_eq!(report.unreadable_processes, 0);
+        assert_eq!(
+            report.unexpected,
+            vec!["/lib/libprocesshider.so", "/tmp/.hidden.so"]
+        );
+    }
+
+    #[test]
+    fn test_configure_clears_baseline_when_option_missing() {
+        let mut options = HashMap::new();
+        options.insert(
+            "expected_preload_libraries".to_string(),
+            "/usr/lib/libjemalloc.so.2".to_string(),
+        );
+        configure(&options);
+        assert!(EXPECTED_PRELOADS
+            .read()
+            .unwrap()
+            .contains("/usr/lib/libjemalloc.so.2"));
+
+        configure(&HashMap::new());
+        assert!(EXPECTED_PRELOADS.read().unwrap().is_empty());
+    }
+}
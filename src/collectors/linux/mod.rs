@@ -0,0 +1,34 @@
+//! Linux-specific collectors that read live kernel state from `/proc` or
+//! combine file copies with parsing.
+
+/// Files inside Docker and Podman containers (`container://`)
+pub mod container_fs;
+
+/// Docker and Podman container configuration, logs and summary
+pub mod containers;
+
+/// Kernel ring buffer messages from `dmesg`
+pub mod kernel_messages;
+
+/// Files with the immutable or append-only attribute (`chattr +i`, `+a`)
+pub mod immutable_files;
+
+/// `/etc/ld.so.preload` and per-process `LD_PRELOAD` audit
+pub mod ld_preload;
+
+/// Alias of [`ld_preload`], after the audit it runs
+#[allow(unused_imports)]
+pub use ld_preload as ld_preload_audit;
+
+/// Packet socket enumeration from `/proc/net/packet`
+pub mod proc_net;
+
+/// Alias of [`proc_net`], which only reads packet sockets so far
+#[allow(unused_imports)]
+pub use proc_net as proc_net_packet_sockets;
+
+/// Read-only LVM, btrfs and ZFS snapshots to collect from (`--use-snapshots`)
+pub mod snapshots;
+
+/// sshd configuration and authorized keys of every account
+pub mod sshd_config;
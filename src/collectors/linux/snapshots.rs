@@ -0,0 +1,963 @@
+//! Collection from read-only filesystem snapshots (`--use-snapshots`).
+//!
+//! Files on a busy system change while they are copied, so a collection of
+//! databases, logs and configuration can be internally inconsistent. With
+//! `--use-snapshots`, every filesystem holding a collected artifact is
+//! snapshotted before collection starts and artifacts are read from the
+//! snapshot instead:
+//!
+//! - **LVM**: `lvcreate --snapshot` of the logical volume (sized by
+//!   `--snapshot-size`), mounted read-only without journal replay under the
+//!   temporary directory.
+//! - **btrfs**: a read-only `btrfs subvolume snapshot` inside the mounted
+//!   subvolume, under `.rs_collector_snapshots`.
+//! - **ZFS**: `zfs snapshot` of the dataset, read through its
+//!   `.zfs/snapshot` directory.
+//!
+//! [`SnapshotSession`] makes a [`SnapshotSource`] the default artifact
+//! source, which maps each path onto the snapshot of its filesystem and
+//! leaves paths on other filesystems alone. Snapshots are removed when the
+//! session is dropped, including on error paths; snapshots left by a
+//! collector that was killed are named `rscol-<pid>-<n>` and removed by
+//! [`remove_orphans`] at the next start once that process is gone.
+//!
+//! A filesystem that cannot be snapshotted, or is excluded with
+//! `--no-snapshot`, is collected live. Every snapshot created, removed,
+//! skipped or failed is recorded in the footprint and the audit trail.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+
+use crate::collectors::footprint::{self, record_snapshot};
+use crate::collectors::source::{
+    self, split_scheme, ArtifactSource, LocalSource, SourceMetadata, LOCAL_SCHEME,
+};
+use crate::config::resolve_all_variables;
+use crate::security::audit_log::{self, AuditAction};
+
+/// Scheme of the snapshot source
+pub const SNAPSHOT_SCHEME: &str = "snapshot";
+
+/// LVM snapshot size when `--snapshot-size` is not given
+pub const DEFAULT_SNAPSHOT_SIZE: &str = "1G";
+
+/// Prefix of every snapshot name, followed by the collector's pid
+const NAME_PREFIX: &str = "rscol-";
+
+/// Directory holding btrfs snapshots inside the snapshotted subvolume
+const BTRFS_SNAPSHOT_DIR: &str = ".rs_collector_snapshots";
+
+/// Snapshots taken by this process, to keep names unique
+static SNAPSHOT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Snapshot mechanism of a filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Lvm,
+    Btrfs,
+    Zfs,
+}
+
+impl SnapshotKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SnapshotKind::Lvm => "lvm",
+            SnapshotKind::Btrfs => "btrfs",
+            SnapshotKind::Zfs => "zfs",
+        }
+    }
+}
+
+/// One line of `/proc/self/mountinfo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountEntry {
+    /// Where the filesystem is mounted
+    pub mount_point: PathBuf,
+    /// Filesystem type (`ext4`, `btrfs`, `zfs`, ...)
+    pub fs_type: String,
+    /// Device or dataset mounted
+    pub source: String,
+    /// Directory of the filesystem mounted at `mount_point`
+    pub root: PathBuf,
+}
+
+/// Parse `/proc/self/mountinfo`, skipping malformed lines
+pub fn parse_mountinfo(text: &str) -> Vec<MountEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let (before, after) = line.split_once(" - ")?;
+            let before: Vec<&str> = before.split(' ').collect();
+            let mut after = after.split(' ');
+            if before.len() < 5 {
+                return None;
+            }
+            Some(MountEntry {
+                root: PathBuf::from(unescape_mount_field(before[3])),
+                mount_point: PathBuf::from(unescape_mount_field(before[4])),
+                fs_type: after.next()?.to_string(),
+                source: unescape_mount_field(after.next()?),
+            })
+        })
+        .collect()
+}
+
+/// Undo the octal escaping of spaces, tabs, newlines and backslashes in
+/// mountinfo fields
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(digits, 8) {
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// The mount holding `path`: the longest mount point it is under, the
+/// last-mounted one when a mount point is shadowed
+pub fn mount_for<'a>(path: &Path, mounts: &'a [MountEntry]) -> Option<&'a MountEntry> {
+    mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| path.starts_with(&mount.mount_point))
+        .max_by_key(|(index, mount)| (mount.mount_point.components().count(), *index))
+        .map(|(_, mount)| mount)
+}
+
+/// Runs the snapshot tools, returning their standard output
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &str, args: &[String]) -> Result<String>;
+}
+
+/// Runs the real commands, recording them in the footprint
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[String]) -> Result<String> {
+        let output = footprint::run_command(Command::new(program).args(args))
+            .with_context(|| format!("Failed to run {}", program))?;
+        if !output.status.success() {
+            bail!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// How snapshots are taken
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// LVM snapshot size: `lvcreate --size` (`2G`), or `--extents` when it
+    /// contains `%` (`20%ORIGIN`)
+    pub lvm_size: String,
+    /// Mount points that are always collected live
+    pub exclude: Vec<PathBuf>,
+    /// Where LVM snapshots are mounted
+    pub mount_dir: PathBuf,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            lvm_size: DEFAULT_SNAPSHOT_SIZE.to_string(),
+            exclude: Vec::new(),
+            mount_dir: std::env::temp_dir().join("rs_collector_snapshots"),
+        }
+    }
+}
+
+/// A snapshot the session created and must remove
+#[derive(Debug)]
+struct ActiveSnapshot {
+    kind: SnapshotKind,
+    /// Mount point of the snapshotted filesystem
+    filesystem: PathBuf,
+    /// What was created: `vg/lv`, the btrfs snapshot path or `dataset@name`
+    name: String,
+    /// Where the filesystem's content is read from
+    root: PathBuf,
+    /// Mount point of an LVM snapshot
+    mounted_at: Option<PathBuf>,
+}
+
+/// Snapshots of the filesystems being collected, removed when dropped
+pub struct SnapshotSession {
+    snapshots: Vec<ActiveSnapshot>,
+    previous_source: Option<Arc<dyn ArtifactSource>>,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl SnapshotSession {
+    /// Snapshot the filesystems holding `source_paths` and read artifacts
+    /// from the snapshots until the session is dropped
+    pub fn start(source_paths: &[String], options: &SnapshotOptions) -> Result<Self> {
+        let mountinfo = fs::read_to_string("/proc/self/mountinfo")
+            .context("Failed to read /proc/self/mountinfo")?;
+        let mounts = parse_mountinfo(&mountinfo);
+        remove_orphans(&mounts, options, &SystemRunner);
+        Ok(Self::start_with(
+            source_paths,
+            &mounts,
+            options,
+            Arc::new(SystemRunner),
+        ))
+    }
+
+    /// [`start`](Self::start) with the mount table and the commands given
+    pub fn start_with(
+        source_paths: &[String],
+        mounts: &[MountEntry],
+        options: &SnapshotOptions,
+        runner: Arc<dyn CommandRunner>,
+    ) -> Self {
+        let mut session = SnapshotSession {
+            snapshots: Vec::new(),
+            previous_source: None,
+            runner,
+        };
+
+        let mut seen = HashSet::new();
+        for path in source_paths.iter().filter_map(|p| snapshot_path(p)) {
+            let Some(mount) = mount_for(&path, mounts) else {
+                continue;
+            };
+            if !seen.insert(mount.mount_point.clone()) {
+                continue;
+            }
+            if options.exclude.contains(&mount.mount_point) {
+                info!(
+                    "Collecting {} live (--no-snapshot)",
+                    mount.mount_point.display()
+                );
+                record_snapshot(
+                    &mount.mount_point,
+                    &mount.fs_type,
+                    "skipped",
+                    "excluded with --no-snapshot",
+                );
+                continue;
+            }
+            session.snapshot(mount, options);
+        }
+
+        if !session.snapshots.is_empty() {
+            session.previous_source = Some(source::default_source());
+            source::set_default_source(Arc::new(SnapshotSource {
+                mappings: session
+                    .snapshots
+                    .iter()
+                    .map(|s| (s.filesystem.clone(), s.root.clone()))
+                    .collect(),
+                mount_points: mounts.iter().map(|m| m.mount_point.clone()).collect(),
+            }));
+        }
+        session
+    }
+
+    /// Mount points read from a snapshot, with the snapshot root
+    pub fn mappings(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.snapshots
+            .iter()
+            .map(|s| (s.filesystem.clone(), s.root.clone()))
+            .collect()
+    }
+
+    /// Snapshot `mount`, or note why it is collected live
+    fn snapshot(&mut self, mount: &MountEntry, options: &SnapshotOptions) {
+        let Some(kind) = self.kind_of(mount) else {
+            debug!(
+                "{} ({}) cannot be snapshotted, collecting live",
+                mount.mount_point.display(),
+                mount.fs_type
+            );
+            record_snapshot(
+                &mount.mount_point,
+                &mount.fs_type,
+                "skipped",
+                "filesystem does not support snapshots",
+            );
+            return;
+        };
+
+        let name = format!(
+            "{}{}-{}",
+            NAME_PREFIX,
+            std::process::id(),
+            SNAPSHOT_COUNTER.fetch_add(1, Ordering::SeqCst)
+        );
+        let result = match kind {
+            SnapshotKind::Lvm => self.create_lvm(mount, &name, options),
+            SnapshotKind::Btrfs => self.create_btrfs(mount, &name),
+            SnapshotKind::Zfs => self.create_zfs(mount, &name),
+        };
+        match result {
+            Ok(snapshot) => {
+                let detail = format!("{} read from {}", snapshot.name, snapshot.root.display());
+                info!(
+                    "Collecting {} from {} snapshot {}",
+                    mount.mount_point.display(),
+                    kind.as_str(),
+                    detail
+                );
+                record_snapshot(&mount.mount_point, kind.as_str(), "created", &detail);
+                audit_log::record(
+                    AuditAction::Snapshot,
+                    &format!(
+                        "created {} snapshot of {}: {}",
+                        kind.as_str(),
+                        mount.mount_point.display(),
+                        detail
+                    ),
+                );
+                self.snapshots.push(snapshot);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to snapshot {}, collecting live: {:#}",
+                    mount.mount_point.display(),
+                    e
+                );
+                record_snapshot(
+                    &mount.mount_point,
+                    kind.as_str(),
+                    "failed",
+                    &format!("{:#}", e),
+                );
+            }
+        }
+    }
+
+    /// How `mount` can be snapshotted, if at all; device-mapper devices are
+    /// LVM volumes when `lvs` knows them
+    fn kind_of(&self, mount: &MountEntry) -> Option<SnapshotKind> {
+        match mount.fs_type.as_str() {
+            "btrfs" => Some(SnapshotKind::Btrfs),
+            "zfs" => Some(SnapshotKind::Zfs),
+            _ if mount.source.starts_with("/dev/mapper/")
+                || mount.source.starts_with("/dev/dm-") =>
+            {
+                Some(SnapshotKind::Lvm)
+            }
+            _ if mount.source.starts_with("/dev/") => {
+                let device = Path::new(&mount.source);
+                let under_vg = device
+                    .parent()
+                    .and_then(|vg| vg.file_name())
+                    .is_some_and(|vg| vg != "dev");
+                under_vg.then_some(SnapshotKind::Lvm)
+            }
+            _ => None,
+        }
+    }
+
+    fn create_lvm(
+        &self,
+        mount: &MountEntry,
+        name: &str,
+        options: &SnapshotOptions,
+    ) -> Result<ActiveSnapshot> {
+        let output = self.runner.run(
+            "lvs",
+            &args(&[
+                "--noheadings",
+                "--separator",
+                ",",
+                "-o",
+                "vg_name,lv_name",
+                &mount.source,
+            ]),
+        )?;
+        let (vg, lv) = output
+            .lines()
+            .find_map(|line| line.trim().split_once(','))
+            .map(|(vg, lv)| (vg.trim().to_string(), lv.trim().to_string()))
+            .ok_or_else(|| anyhow!("{} is not an LVM logical volume", mount.source))?;
+
+        let size_flag = if options.lvm_size.contains('%') {
+            "--extents"
+        } else {
+            "--size"
+        };
+        self.runner.run(
+            "lvcreate",
+            &args(&[
+                "--snapshot",
+                "--permission",
+                "r",
+                size_flag,
+                &options.lvm_size,
+                "--name",
+                name,
+                &format!("{}/{}", vg, lv),
+            ]),
+        )?;
+        let snapshot_lv = format!("{}/{}", vg, name);
+
+        let mounted_at = options.mount_dir.join(name);
+        let mounted = footprint::create_dir_all_tracked(&mounted_at)
+            .with_context(|| format!("Failed to create {}", mounted_at.display()))
+            .and_then(|_| {
+                let mount_options = match mount.fs_type.as_str() {
+                    "ext3" | "ext4" => "ro,noload",
+                    "xfs" => "ro,nouuid,norecovery",
+                    _ => "ro",
+                };
+                self.runner.run(
+                    "mount",
+                    &args(&[
+                        "-t",
+                        &mount.fs_type,
+                        "-o",
+                        mount_options,
+                        &format!("/dev/{}", snapshot_lv),
+                        &mounted_at.to_string_lossy(),
+                    ]),
+                )
+            });
+        if let Err(e) = mounted {
+            let _ = fs::remove_dir(&mounted_at);
+            if let Err(remove) = self.runner.run("lvremove", &args(&["-f", &snapshot_lv])) {
+                warn!("Failed to remove snapshot {}: {:#}", snapshot_lv, remove);
+            }
+            return Err(e);
+        }
+
+        Ok(ActiveSnapshot {
+            kind: SnapshotKind::Lvm,
+            filesystem: mount.mount_point.clone(),
+            name: snapshot_lv,
+            root: join_relative(&mounted_at, &mount.root),
+            mounted_at: Some(mounted_at),
+        })
+    }
+
+    fn create_btrfs(&self, mount: &MountEntry, name: &str) -> Result<ActiveSnapshot> {
+        let snapshot_dir = mount.mount_point.join(BTRFS_SNAPSHOT_DIR);
+        footprint::create_dir_all_tracked(&snapshot_dir)
+            .with_context(|| format!("Failed to create {}", snapshot_dir.display()))?;
+        let snapshot = snapshot_dir.join(name);
+        let created = self.runner.run(
+            "btrfs",
+            &args(&[
+                "subvolume",
+                "snapshot",
+                "-r",
+                &mount.mount_point.to_string_lossy(),
+                &snapshot.to_string_lossy(),
+            ]),
+        );
+        if let Err(e) = created {
+            let _ = fs::remove_dir(&snapshot_dir);
+            return Err(e);
+        }
+
+        Ok(ActiveSnapshot {
+            kind: SnapshotKind::Btrfs,
+            filesystem: mount.mount_point.clone(),
+            name: snapshot.to_string_lossy().to_string(),
+            root: snapshot,
+            mounted_at: None,
+        })
+    }
+
+    fn create_zfs(&self, mount: &MountEntry, name: &str) -> Result<ActiveSnapshot> {
+        let snapshot = format!("{}@{}", mount.source, name);
+        self.runner.run("zfs", &args(&["snapshot", &snapshot]))?;
+
+        Ok(ActiveSnapshot {
+            kind: SnapshotKind::Zfs,
+            filesystem: mount.mount_point.clone(),
+            name: snapshot,
+            root: mount.mount_point.join(".zfs").join("snapshot").join(name),
+            mounted_at: None,
+        })
+    }
+
+    /// Unmount and remove one snapshot
+    fn remove(&self, snapshot: &ActiveSnapshot) -> Result<()> {
+        match snapshot.kind {
+            SnapshotKind::Lvm => {
+                if let Some(mounted_at) = &snapshot.mounted_at {
+                    self.runner
+                        .run("umount", &args(&[&mounted_at.to_string_lossy()]))?;
+                    let _ = fs::remove_dir(mounted_at);
+                }
+                self.runner
+                    .run("lvremove", &args(&["-f", &snapshot.name]))?;
+            }
+            SnapshotKind::Btrfs => {
+                self.runner
+                    .run("btrfs", &args(&["subvolume", "delete", &snapshot.name]))?;
+                // Only removed when no other collector's snapshot is in it
+                let _ = fs::remove_dir(snapshot.filesystem.join(BTRFS_SNAPSHOT_DIR));
+            }
+            SnapshotKind::Zfs => {
+                self.runner
+                    .run("zfs", &args(&["destroy", &snapshot.name]))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SnapshotSession {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous_source.take() {
+            source::set_default_source(previous);
+        }
+        for snapshot in self.snapshots.iter().rev() {
+            let kind = snapshot.kind.as_str();
+            match self.remove(snapshot) {
+                Ok(()) => {
+                    info!("Removed {} snapshot {}", kind, snapshot.name);
+                    record_snapshot(&snapshot.filesystem, kind, "removed", &snapshot.name);
+                    audit_log::record(
+                        AuditAction::Snapshot,
+                        &format!("removed {} snapshot {}", kind, snapshot.name),
+                    );
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to remove {} snapshot {}, remove it by hand: {:#}",
+                        kind, snapshot.name, e
+                    );
+                    record_snapshot(
+                        &snapshot.filesystem,
+                        kind,
+                        "failed",
+                        &format!("removing {}: {:#}", snapshot.name, e),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Remove snapshots and mounts left by collectors that are no longer
+/// running; tools that are not installed are skipped
+pub fn remove_orphans(
+    mounts: &[MountEntry],
+    options: &SnapshotOptions,
+    runner: &dyn CommandRunner,
+) {
+    let removed = |filesystem: &Path, kind: &str, name: &str, result: Result<String>| match result {
+        Ok(_) => {
+            info!("Removed orphaned {} snapshot {}", kind, name);
+            record_snapshot(filesystem, kind, "orphan_removed", name);
+            audit_log::record(
+                AuditAction::Snapshot,
+                &format!("removed orphaned {} snapshot {}", kind, name),
+            );
+        }
+        Err(e) => warn!("Failed to remove orphaned snapshot {}: {:#}", name, e),
+    };
+
+    // LVM snapshot mounts first, so the volumes can be removed
+    for mount in mounts {
+        let orphaned = mount
+            .mount_point
+            .strip_prefix(&options.mount_dir)
+            .ok()
+            .and_then(|rest| rest.to_str())
+            .is_some_and(is_orphan_name);
+        if orphaned {
+            let result = runner.run("umount", &args(&[&mount.mount_point.to_string_lossy()]));
+            if result.is_ok() {
+                let _ = fs::remove_dir(&mount.mount_point);
+            }
+            removed(&mount.mount_point, "lvm", &mount.source, result);
+        }
+    }
+    if let Ok(output) = runner.run(
+        "lvs",
+        &args(&["--noheadings", "--separator", ",", "-o", "vg_name,lv_name"]),
+    ) {
+        for (vg, lv) in output
+            .lines()
+            .filter_map(|line| line.trim().split_once(','))
+        {
+            if is_orphan_name(lv.trim()) {
+                let name = format!("{}/{}", vg.trim(), lv.trim());
+                let result = runner.run("lvremove", &args(&["-f", &name]));
+                removed(Path::new(&name), "lvm", &name, result);
+            }
+        }
+    }
+
+    for mount in mounts.iter().filter(|m| m.fs_type == "btrfs") {
+        let Ok(entries) = fs::read_dir(mount.mount_point.join(BTRFS_SNAPSHOT_DIR)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_str().is_some_and(is_orphan_name) {
+                let path = entry.path().to_string_lossy().to_string();
+                let result = runner.run("btrfs", &args(&["subvolume", "delete", &path]));
+                removed(&mount.mount_point, "btrfs", &path, result);
+            }
+        }
+        let _ = fs::remove_dir(mount.mount_point.join(BTRFS_SNAPSHOT_DIR));
+    }
+
+    if let Ok(output) = runner.run(
+        "zfs",
+        &args(&["list", "-H", "-t", "snapshot", "-o", "name"]),
+    ) {
+        for name in output.lines().map(str::trim) {
+            let orphaned = name
+                .split_once('@')
+                .is_some_and(|(_, snapshot)| is_orphan_name(snapshot));
+            if orphaned {
+                let dataset = name.split('@').next().unwrap_or(name);
+                let result = runner.run("zfs", &args(&["destroy", name]));
+                removed(Path::new(dataset), "zfs", name, result);
+            }
+        }
+    }
+}
+
+/// Whether `name` is a snapshot of a collector process that has exited
+fn is_orphan_name(name: &str) -> bool {
+    snapshot_owner(name).is_some_and(|pid| {
+        pid != std::process::id() && !Path::new("/proc").join(pid.to_string()).exists()
+    })
+}
+
+/// Pid of the collector that named a snapshot `rscol-<pid>-<n>`
+fn snapshot_owner(name: &str) -> Option<u32> {
+    let (pid, counter) = name.strip_prefix(NAME_PREFIX)?.split_once('-')?;
+    counter.parse::<usize>().ok()?;
+    pid.parse().ok()
+}
+
+/// The local path an artifact `source_path` reads from, up to its first
+/// wildcard; `None` for other sources and relative paths
+fn snapshot_path(source_path: &str) -> Option<PathBuf> {
+    let path = match split_scheme(source_path) {
+        (None, path) => path,
+        (Some(scheme), path) if scheme.eq_ignore_ascii_case(LOCAL_SCHEME) => path,
+        (Some(_), _) => return None,
+    };
+    let resolved = resolve_all_variables(path);
+    let fixed = resolved
+        .find(['*', '?', '['])
+        .map_or(resolved.as_str(), |wildcard| &resolved[..wildcard]);
+    let path = PathBuf::from(fixed);
+    path.is_absolute().then_some(path)
+}
+
+/// `base` joined with the absolute `path`
+fn join_relative(base: &Path, path: &Path) -> PathBuf {
+    base.join(
+        path.components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect::<PathBuf>(),
+    )
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// The local filesystem with snapshotted filesystems read from their
+/// snapshot. Paths keep their original form, so collected artifacts are
+/// named as if read live.
+pub struct SnapshotSource {
+    /// Snapshotted mount points and their snapshot roots
+    mappings: Vec<(PathBuf, PathBuf)>,
+    /// Every mount point, so paths on filesystems mounted below a
+    /// snapshotted one are not remapped
+    mount_points: Vec<PathBuf>,
+}
+
+impl SnapshotSource {
+    /// Where `path` is read from
+    pub fn remap(&self, path: &Path) -> PathBuf {
+        let mount = self
+            .mount_points
+            .iter()
+            .filter(|mount| path.starts_with(mount))
+            .max_by_key(|mount| mount.components().count());
+        let Some(mount) = mount else {
+            return path.to_path_buf();
+        };
+        match self.mappings.iter().find(|(fs, _)| fs == mount) {
+            Some((fs, root)) => match path.strip_prefix(fs) {
+                Ok(relative) if relative.as_os_str().is_empty() => root.clone(),
+                Ok(relative) => root.join(relative),
+                Err(_) => path.to_path_buf(),
+            },
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl ArtifactSource for SnapshotSource {
+    fn scheme(&self) -> &str {
+        SNAPSHOT_SCHEME
+    }
+
+    fn is_live_system(&self) -> bool {
+        true
+    }
+
+    async fn stat(&self, path: &Path) -> io::Result<SourceMetadata> {
+        LocalSource.stat(&self.remap(path)).await
+    }
+
+    async fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(self.remap(path))?
+            .map(|entry| entry.map(|e| path.join(e.file_name())))
+            .collect()
+    }
+
+    async fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        LocalSource.open(&self.remap(path)).await
+    }
+
+    async fn copy_to(&self, path: &Path, dest: &Path) -> io::Result<u64> {
+        LocalSource.copy_to(&self.remap(path), dest).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    /// Records commands; `btrfs subvolume snapshot` copies the tree so
+    /// reads can be told apart from the live files
+    #[derive(Default)]
+    struct MockRunner {
+        commands: Mutex<Vec<String>>,
+        fail: Option<&'static str>,
+    }
+
+    impl MockRunner {
+        fn commands(&self) -> Vec<String> {
+            self.commands.lock().unwrap().clone()
+        }
+    }
+
+    impl CommandRunner for MockRunner {
+        fn run(&self, program: &str, args: &[String]) -> Result<String> {
+            self.commands
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", program, args.join(" ")));
+            if self.fail == Some(program) {
+                bail!("{} failed", program);
+            }
+            if program == "btrfs" && args[1] == "snapshot" {
+                copy_tree(Path::new(&args[3]), Path::new(&args[4]));
+            }
+            if program == "lvs" {
+                return Ok("  vg0,root\n".to_string());
+            }
+            Ok(String::new())
+        }
+    }
+
+    fn copy_tree(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap().flatten() {
+            if entry.file_name() == BTRFS_SNAPSHOT_DIR {
+                continue;
+            }
+            if entry.path().is_dir() {
+                copy_tree(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    fn mount(mount_point: &Path, fs_type: &str, source: &str) -> MountEntry {
+        MountEntry {
+            mount_point: mount_point.to_path_buf(),
+            fs_type: fs_type.to_string(),
+            source: source.to_string(),
+            root: PathBuf::from("/"),
+        }
+    }
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let mounts = parse_mountinfo(
+            "22 1 253:0 / / rw,relatime shared:1 - ext4 /dev/mapper/vg0-root rw\n\
+             40 22 0:35 /@home /home rw shared:20 - btrfs /dev/sda2 rw,subvol=/@home\n\
+             41 22 0:36 / /mnt/my\\040data rw - zfs tank/data rw\n\
+             garbage\n",
+        );
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(mounts[0].source, "/dev/mapper/vg0-root");
+        assert_eq!(mounts[1].root, PathBuf::from("/@home"));
+        assert_eq!(mounts[1].fs_type, "btrfs");
+        assert_eq!(mounts[2].mount_point, PathBuf::from("/mnt/my data"));
+        assert_eq!(
+            mount_for(Path::new("/home/alice/.bashrc"), &mounts).map(|m| &m.source),
+            Some(&"/dev/sda2".to_string())
+        );
+        assert_eq!(
+            mount_for(Path::new("/etc/passwd"), &mounts).map(|m| &m.fs_type),
+            Some(&"ext4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_snapshot_names_and_paths() {
+        assert_eq!(snapshot_owner("rscol-4242-3"), Some(4242));
+        assert_eq!(snapshot_owner("rscol-x-3"), None);
+        assert_eq!(snapshot_owner("root"), None);
+        assert!(!is_orphan_name(&format!("rscol-{}-0", std::process::id())));
+        assert!(is_orphan_name("rscol-4294967290-0"));
+
+        assert_eq!(
+            snapshot_path("/var/log/*.log"),
+            Some(PathBuf::from("/var/log/"))
+        );
+        assert_eq!(
+            snapshot_path("file:///etc/passwd"),
+            Some(PathBuf::from("/etc/passwd"))
+        );
+        assert_eq!(snapshot_path("container://web/etc/passwd"), None);
+        assert_eq!(snapshot_path("relative/path"), None);
+    }
+
+    #[test]
+    fn test_btrfs_snapshot_is_read_and_removed() {
+        let live = TempDir::new().unwrap();
+        let nested = TempDir::new_in(live.path()).unwrap();
+        fs::create_dir_all(live.path().join("etc")).unwrap();
+        fs::write(live.path().join("etc/hosts"), b"snapshot").unwrap();
+        fs::write(nested.path().join("other"), b"nested").unwrap();
+        let mounts = vec![
+            mount(live.path(), "btrfs", "/dev/sda2"),
+            mount(nested.path(), "tmpfs", "tmpfs"),
+        ];
+        let runner = Arc::new(MockRunner::default());
+
+        let session = SnapshotSession::start_with(
+            &[live.path().join("etc/hosts").to_string_lossy().to_string()],
+            &mounts,
+            &SnapshotOptions::default(),
+            runner.clone(),
+        );
+        // The live file changes after the snapshot was taken
+        fs::write(live.path().join("etc/hosts"), b"changed").unwrap();
+
+        let mappings = session.mappings();
+        assert_eq!(mappings.len(), 1);
+        let source = SnapshotSource {
+            mappings,
+            mount_points: mounts.iter().map(|m| m.mount_point.clone()).collect(),
+        };
+        let hosts = live.path().join("etc/hosts");
+        assert!(source
+            .remap(&hosts)
+            .starts_with(live.path().join(BTRFS_SNAPSHOT_DIR)));
+        assert_eq!(source::block_on(source.read(&hosts)).unwrap(), b"snapshot");
+        assert_eq!(
+            source::block_on(source.list_dir(&live.path().join("etc"))).unwrap(),
+            vec![hosts.clone()]
+        );
+        // Paths on a filesystem mounted inside are read live
+        let other = nested.path().join("other");
+        assert_eq!(source.remap(&other), other);
+
+        drop(session);
+        let commands = runner.commands();
+        assert!(commands[0].starts_with("btrfs subvolume snapshot -r"));
+        assert!(commands[1].starts_with("btrfs subvolume delete"));
+    }
+
+    #[test]
+    fn test_lvm_snapshot_commands() {
+        let root = TempDir::new().unwrap();
+        let mount_dir = TempDir::new().unwrap();
+        let mut mounts = vec![mount(root.path(), "xfs", "/dev/mapper/vg0-root")];
+        mounts[0].root = PathBuf::from("/srv");
+        let runner = Arc::new(MockRunner::default());
+        let options = SnapshotOptions {
+            lvm_size: "20%ORIGIN".to_string(),
+            exclude: Vec::new(),
+            mount_dir: mount_dir.path().to_path_buf(),
+        };
+
+        let session = SnapshotSession::start_with(
+            &[root.path().join("data").to_string_lossy().to_string()],
+            &mounts,
+            &options,
+            runner.clone(),
+        );
+        let mappings = session.mappings();
+        assert_eq!(mappings.len(), 1);
+        assert!(mappings[0].1.starts_with(mount_dir.path()));
+        assert!(mappings[0].1.ends_with("srv"));
+        drop(session);
+
+        let commands = runner.commands();
+        assert!(commands[1].starts_with("lvcreate --snapshot --permission r --extents 20%ORIGIN"));
+        assert!(commands[1].ends_with(" vg0/root"));
+        assert!(commands[2].starts_with("mount -t xfs -o ro,nouuid,norecovery /dev/vg0/rscol-"));
+        assert!(commands[3].starts_with("umount "));
+        assert!(commands[4].starts_with("lvremove -f vg0/rscol-"));
+    }
+
+    #[test]
+    fn test_failed_and_excluded_filesystems_are_collected_live() {
+        let zfs = TempDir::new().unwrap();
+        let excluded = TempDir::new().unwrap();
+        let mounts = vec![
+            mount(zfs.path(), "zfs", "tank/data"),
+            mount(excluded.path(), "btrfs", "/dev/sdb1"),
+        ];
+        let runner = Arc::new(MockRunner {
+            fail: Some("zfs"),
+            ..Default::default()
+        });
+        let options = SnapshotOptions {
+            exclude: vec![excluded.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let session = SnapshotSession::start_with(
+            &[
+                zfs.path().join("a").to_string_lossy().to_string(),
+                excluded.path().join("b").to_string_lossy().to_string(),
+            ],
+            &mounts,
+            &options,
+            runner.clone(),
+        );
+        assert!(session.mappings().is_empty());
+        assert!(session.previous_source.is_none());
+        drop(session);
+        assert_eq!(runner.commands().len(), 1);
+        assert!(runner.commands()[0].starts_with("zfs snapshot tank/data@rscol-"));
+    }
+}
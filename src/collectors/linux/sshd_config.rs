@@ -0,0 +1,861 @@
+//! OpenSSH server configuration and authorized keys.
+//!
+//! Attackers who gain root often keep access through sshd rather than a
+//! separate implant: enabling root or password logins, pointing
+//! `AuthorizedKeysFile` at a file they control, or appending a key to some
+//! user's `~/.ssh/authorized_keys`. This collector copies `sshd_config`
+//! (with the files it `Include`s) and writes the security-relevant settings
+//! to `sshd_config.json`, including the directives of every `Match` block.
+//!
+//! It then copies the authorized keys files of every account in
+//! `/etc/passwd`: the default `~/.ssh/authorized_keys` and
+//! `~/.ssh/authorized_keys2`, plus any other location the configuration
+//! names. `authorized_keys.json` lists each file found, and locations other
+//! than the two defaults are marked as non-standard.
+//!
+//! Host private keys in `/etc/ssh` are never copied.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::create_dir_all_tracked;
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+use crate::security::path_validator::sanitize_filename;
+
+/// File name used for the parsed server configuration
+pub const SSHD_CONFIG_FILE: &str = "sshd_config.json";
+
+/// File name used for the authorized keys inventory
+pub const AUTHORIZED_KEYS_FILE: &str = "authorized_keys.json";
+
+/// Account database read to find home directories
+pub const PASSWD_PATH: &str = "/etc/passwd";
+
+/// `AuthorizedKeysFile` when the configuration does not set it
+const DEFAULT_AUTHORIZED_KEYS_FILES: &[&str] = &[".ssh/authorized_keys", ".ssh/authorized_keys2"];
+
+/// Nesting limit for `Include`, as in sshd
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A directive inside a `Match` block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SshdDirective {
+    pub keyword: String,
+    pub value: String,
+    /// Configuration file and line the directive was read from
+    pub file: String,
+    pub line: usize,
+}
+
+/// A `Match` block and the directives that apply when it matches
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatchBlock {
+    /// Match criteria, e.g. `User backup Address 10.0.0.0/8`
+    pub criteria: String,
+    pub file: String,
+    pub line: usize,
+    pub directives: Vec<SshdDirective>,
+}
+
+/// Security-relevant settings of an `sshd_config`.
+///
+/// Single-valued settings keep the first value read, as sshd does; `None`
+/// means the directive is absent and sshd's built-in default applies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SshdConfig {
+    pub path: String,
+    /// Files pulled in by `Include`, in the order they were read
+    pub included_files: Vec<String>,
+    pub permit_root_login: Option<String>,
+    pub password_authentication: Option<String>,
+    pub permit_empty_passwords: Option<String>,
+    /// Global `AuthorizedKeysFile` patterns; empty means the default
+    pub authorized_keys_files: Vec<String>,
+    pub authorized_keys_command: Option<String>,
+    pub listen_addresses: Vec<String>,
+    pub ports: Vec<u16>,
+    pub allow_users: Vec<String>,
+    pub deny_users: Vec<String>,
+    pub match_blocks: Vec<MatchBlock>,
+    /// Settings that weaken authentication or move authorized keys
+    pub findings: Vec<String>,
+}
+
+impl SshdConfig {
+    /// Every `AuthorizedKeysFile` pattern in effect, globally or in a `Match`
+    /// block, with where it was configured
+    pub fn authorized_keys_patterns(&self) -> Vec<(String, String)> {
+        let mut patterns: Vec<(String, String)> = if self.authorized_keys_files.is_empty() {
+            DEFAULT_AUTHORIZED_KEYS_FILES
+                .iter()
+                .map(|p| (p.to_string(), "default".to_string()))
+                .collect()
+        } else {
+            self.authorized_keys_files
+                .iter()
+                .map(|p| (p.clone(), "AuthorizedKeysFile".to_string()))
+                .collect()
+        };
+        for block in &self.match_blocks {
+            for directive in &block.directives {
+                if directive.keyword.eq_ignore_ascii_case("AuthorizedKeysFile") {
+                    patterns.extend(
+                        split_values(&directive.value)
+                            .into_iter()
+                            .map(|p| (p, format!("Match {}", block.criteria))),
+                    );
+                }
+            }
+        }
+        patterns
+    }
+}
+
+/// An authorized keys file found for an account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuthorizedKeysLocation {
+    pub user: String,
+    pub uid: u32,
+    pub path: String,
+    /// `AuthorizedKeysFile` pattern the path was expanded from
+    pub pattern: String,
+    /// `default`, `AuthorizedKeysFile` or the `Match` block that set it
+    pub configured_by: String,
+    /// One of the default `~/.ssh/authorized_keys` locations
+    pub standard: bool,
+    /// Non-empty, non-comment lines
+    pub key_count: usize,
+    /// Copy relative to the collector's output directory
+    pub collected_as: Option<String>,
+}
+
+/// Contents of `authorized_keys.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthorizedKeysInventory {
+    pub collection_time: String,
+    pub locations: Vec<AuthorizedKeysLocation>,
+    /// Accounts or files that could not be read
+    pub notes: Vec<String>,
+}
+
+/// A `/etc/passwd` entry
+#[derive(Debug, Clone, PartialEq)]
+struct PasswdEntry {
+    user: String,
+    uid: u32,
+    home: PathBuf,
+}
+
+/// Parse `sshd_config` at `config_path`, following `Include` directives.
+///
+/// Relative `Include` paths are resolved against the directory of
+/// `config_path`, like sshd resolves them against `/etc/ssh`.
+pub fn parse_sshd_config(config_path: &Path) -> Result<SshdConfig> {
+    let mut config = SshdConfig {
+        path: config_path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let base_dir = config_path.parent().unwrap_or(Path::new("/"));
+    let mut current_match = None;
+    parse_file(config_path, base_dir, &mut config, &mut current_match, 0)?;
+    config.findings = findings(&config);
+    Ok(config)
+}
+
+fn parse_file(
+    path: &Path,
+    base_dir: &Path,
+    config: &mut SshdConfig,
+    current_match: &mut Option<usize>,
+    depth: usize,
+) -> Result<()> {
+    if depth > MAX_INCLUDE_DEPTH {
+        bail!("Include nested too deeply at {}", path.display());
+    }
+    let contents =
+        fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let file = path.to_string_lossy().to_string();
+
+    for (index, raw) in contents.lines().enumerate() {
+        let Some((keyword, value)) = split_directive(raw) else {
+            continue;
+        };
+        let line = index + 1;
+
+        if keyword.eq_ignore_ascii_case("Match") {
+            config.match_blocks.push(MatchBlock {
+                criteria: value.to_string(),
+                file: file.clone(),
+                line,
+                directives: Vec::new(),
+            });
+            *current_match = Some(config.match_blocks.len() - 1);
+            continue;
+        }
+
+        if keyword.eq_ignore_ascii_case("Include") {
+            for pattern in split_values(value) {
+                for included in resolve_include(&pattern, base_dir) {
+                    config
+                        .included_files
+                        .push(included.to_string_lossy().to_string());
+                    if let Err(e) =
+                        parse_file(&included, base_dir, config, current_match, depth + 1)
+                    {
+                        warn!("Skipping included sshd configuration: {:#}", e);
+                    }
+                }
+            }
+            continue;
+        }
+
+        match *current_match {
+            Some(block) => config.match_blocks[block].directives.push(SshdDirective {
+                keyword: keyword.to_string(),
+                value: value.to_string(),
+                file: file.clone(),
+                line,
+            }),
+            None => apply_global(config, keyword, value),
+        }
+    }
+    Ok(())
+}
+
+/// Split a configuration line into keyword and value; `None` for blank lines
+/// and comments. Both `Keyword value` and `Keyword=value` are accepted.
+fn split_directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((keyword, value))
+}
+
+/// Whitespace-separated arguments, with double quotes removed
+fn split_values(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    values.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        values.push(current);
+    }
+    values
+}
+
+fn apply_global(config: &mut SshdConfig, keyword: &str, value: &str) {
+    let first = |slot: &mut Option<String>| {
+        if slot.is_none() {
+            *slot = Some(value.to_string());
+        }
+    };
+    match keyword.to_ascii_lowercase().as_str() {
+        "permitrootlogin" => first(&mut config.permit_root_login),
+        "passwordauthentication" => first(&mut config.password_authentication),
+        "permitemptypasswords" => first(&mut config.permit_empty_passwords),
+        "authorizedkeyscommand" => first(&mut config.authorized_keys_command),
+        "authorizedkeysfile" if config.authorized_keys_files.is_empty() => {
+            config.authorized_keys_files = split_values(value);
+        }
+        "listenaddress" => config.listen_addresses.push(value.to_string()),
+        "port" => match value.parse() {
+            Ok(port) => config.ports.push(port),
+            Err(_) => debug!("Ignoring invalid sshd Port {}", value),
+        },
+        "allowusers" => config.allow_users.extend(split_values(value)),
+        "denyusers" => config.deny_users.extend(split_values(value)),
+        _ => {}
+    }
+}
+
+/// Expand an `Include` argument, which may use `*` and `?` in its last
+/// component, into the files it names in lexical order
+fn resolve_include(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let path = if Path::new(pattern).is_absolute() {
+        PathBuf::from(pattern)
+    } else {
+        base_dir.join(pattern)
+    };
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| wildcard_match(&name, &e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+/// Shell-style match of `*` and `?`
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Weakened authentication or relocated keys, globally or per `Match` block
+fn findings(config: &SshdConfig) -> Vec<String> {
+    let mut findings = Vec::new();
+    let mut check = |keyword: &str, value: &str, scope: &str| {
+        let value_lower = value.to_ascii_lowercase();
+        match keyword.to_ascii_lowercase().as_str() {
+            "permitrootlogin" if value_lower == "yes" => findings.push(format!(
+                "{}: PermitRootLogin yes allows root password logins",
+                scope
+            )),
+            "passwordauthentication" if value_lower == "yes" => {
+                findings.push(format!("{}: PasswordAuthentication yes", scope))
+            }
+            "permitemptypasswords" if value_lower == "yes" => findings.push(format!(
+                "{}: PermitEmptyPasswords yes allows accounts without a password",
+                scope
+            )),
+            "authorizedkeysfile" => {
+                for pattern in split_values(value) {
+                    if !is_standard_pattern(&pattern) {
+                        findings.push(format!(
+                            "{}: non-standard AuthorizedKeysFile {}",
+                            scope, pattern
+                        ));
+                    }
+                }
+            }
+            "authorizedkeyscommand" if value_lower != "none" => findings.push(format!(
+                "{}: AuthorizedKeysCommand {} supplies keys",
+                scope, value
+            )),
+            _ => {}
+        }
+    };
+
+    let globals = [
+        ("PermitRootLogin", config.permit_root_login.clone()),
+        (
+            "PasswordAuthentication",
+            config.password_authentication.clone(),
+        ),
+        (
+            "PermitEmptyPasswords",
+            config.permit_empty_passwords.clone(),
+        ),
+        (
+            "AuthorizedKeysCommand",
+            config.authorized_keys_command.clone(),
+        ),
+    ];
+    for (keyword, value) in globals {
+        if let Some(value) = value {
+            check(keyword, &value, "global");
+        }
+    }
+    if !config.authorized_keys_files.is_empty() {
+        check(
+            "AuthorizedKeysFile",
+            &config.authorized_keys_files.join(" "),
+            "global",
+        );
+    }
+    for block in &config.match_blocks {
+        let scope = format!("Match {}", block.criteria);
+        for directive in &block.directives {
+            check(&directive.keyword, &directive.value, &scope);
+        }
+    }
+    findings
+}
+
+/// Whether `pattern` names one of the default locations
+fn is_standard_pattern(pattern: &str) -> bool {
+    let relative = pattern
+        .strip_prefix("%h/")
+        .or_else(|| pattern.strip_prefix("~/"))
+        .unwrap_or(pattern);
+    DEFAULT_AUTHORIZED_KEYS_FILES.contains(&relative)
+}
+
+/// Expand the `%%`, `%h`, `%u` and `%U` tokens of an `AuthorizedKeysFile`
+/// pattern; relative results are under the home directory
+fn expand_pattern(pattern: &str, entry: &PasswdEntry) -> PathBuf {
+    let mut expanded = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('h') => expanded.push_str(&entry.home.to_string_lossy()),
+            Some('u') => expanded.push_str(&entry.user),
+            Some('U') => expanded.push_str(&entry.uid.to_string()),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    let expanded = expanded
+        .strip_prefix("~/")
+        .map(|rest| entry.home.join(rest).to_string_lossy().to_string())
+        .unwrap_or(expanded);
+
+    let path = PathBuf::from(&expanded);
+    if path.is_absolute() {
+        path
+    } else {
+        entry.home.join(path)
+    }
+}
+
+/// Accounts with a home directory from `/etc/passwd` contents
+fn parse_passwd(contents: &str) -> Vec<PasswdEntry> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 6 || fields[5].is_empty() {
+                return None;
+            }
+            Some(PasswdEntry {
+                user: fields[0].to_string(),
+                uid: fields[2].parse().ok()?,
+                home: PathBuf::from(fields[5]),
+            })
+        })
+        .collect()
+}
+
+/// Count the keys in an authorized keys file
+fn count_keys(contents: &str) -> usize {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .count()
+}
+
+/// Copy the sshd configuration in `ssh_dir` and the authorized keys files of
+/// every account in `/etc/passwd` into `output_dir`
+///
+/// `sshd_config` and its includes are copied under `config/`, authorized
+/// keys files under `authorized_keys/<user>/`. A missing or unreadable
+/// configuration is noted in the inventory and the default key locations
+/// are still searched.
+pub fn collect_ssh_configuration(
+    ssh_dir: &Path,
+    output_dir: &Path,
+) -> Result<Vec<ArtifactMetadata>> {
+    collect_from(ssh_dir, Path::new(PASSWD_PATH), output_dir)
+}
+
+fn collect_from(ssh_dir: &Path, passwd: &Path, output_dir: &Path) -> Result<Vec<ArtifactMetadata>> {
+    info!("Collecting sshd configuration from {}", ssh_dir.display());
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let fallback = FallbackCollector::new();
+    let mut collected = Vec::new();
+    let mut inventory = AuthorizedKeysInventory {
+        collection_time: Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    let config_path = ssh_dir.join("sshd_config");
+    let config = match parse_sshd_config(&config_path) {
+        Ok(config) => {
+            let config_dir = output_dir.join("config");
+            let sources = std::iter::once(config_path.clone())
+                .chain(config.included_files.iter().map(PathBuf::from));
+            for source in sources {
+                let relative = source
+                    .strip_prefix(ssh_dir)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|_| {
+                        PathBuf::from(sanitize_filename(&source.to_string_lossy()))
+                    });
+                match fallback.collect_standard_file(&source, &config_dir.join(relative)) {
+                    Ok(metadata) => collected.push(metadata),
+                    Err(e) => warn!("Failed to copy {}: {:#}", source.display(), e),
+                }
+            }
+
+            let path = output_dir.join(SSHD_CONFIG_FILE);
+            let json = serde_json::to_string_pretty(&config)
+                .context("Failed to serialize sshd configuration")?;
+            fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+            for finding in &config.findings {
+                info!("sshd_config: {}", finding);
+            }
+            config
+        }
+        Err(e) => {
+            warn!("Could not parse {}: {:#}", config_path.display(), e);
+            inventory.notes.push(format!(
+                "Could not parse {}, default AuthorizedKeysFile assumed: {:#}",
+                config_path.display(),
+                e
+            ));
+            SshdConfig::default()
+        }
+    };
+
+    let accounts = match fs::read_to_string(passwd) {
+        Ok(contents) => parse_passwd(&contents),
+        Err(e) => {
+            inventory
+                .notes
+                .push(format!("Failed to read {}: {}", passwd.display(), e));
+            Vec::new()
+        }
+    };
+
+    // The defaults are always searched so a key planted there is found even
+    // when the configuration points elsewhere
+    let mut patterns = config.authorized_keys_patterns();
+    for default in DEFAULT_AUTHORIZED_KEYS_FILES {
+        if !patterns.iter().any(|(p, _)| p == default) {
+            patterns.push((default.to_string(), "default".to_string()));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for account in &accounts {
+        for (pattern, configured_by) in &patterns {
+            let path = expand_pattern(pattern, account);
+            if !path.is_file() || !seen.insert((account.user.clone(), path.clone())) {
+                continue;
+            }
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    inventory
+                        .notes
+                        .push(format!("Failed to read {}: {}", path.display(), e));
+                    continue;
+                }
+            };
+
+            let relative = PathBuf::from("authorized_keys")
+                .join(sanitize_filename(&account.user))
+                .join(sanitize_filename(
+                    path.strip_prefix(&account.home)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .trim_start_matches('/'),
+                ));
+            let collected_as =
+                match fallback.collect_standard_file(&path, &output_dir.join(&relative)) {
+                    Ok(metadata) => {
+                        collected.push(metadata);
+                        Some(relative.to_string_lossy().to_string())
+                    }
+                    Err(e) => {
+                        warn!("Failed to copy {}: {:#}", path.display(), e);
+                        None
+                    }
+                };
+
+            inventory.locations.push(AuthorizedKeysLocation {
+                user: account.user.clone(),
+                uid: account.uid,
+                path: path.to_string_lossy().to_string(),
+                pattern: pattern.clone(),
+                configured_by: configured_by.clone(),
+                standard: is_standard_pattern(pattern),
+                key_count: count_keys(&contents),
+                collected_as,
+            });
+        }
+    }
+
+    let inventory_path = output_dir.join(AUTHORIZED_KEYS_FILE);
+    let json = serde_json::to_string_pretty(&inventory)
+        .context("Failed to serialize authorized keys inventory")?;
+    fs::write(&inventory_path, json).context(format!(
+        "Failed to write authorized keys inventory to {}",
+        inventory_path.display()
+    ))?;
+
+    info!(
+        "Collected {} authorized keys files for {} accounts",
+        inventory.locations.len(),
+        accounts.len()
+    );
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_parse_sshd_config() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("sshd_config");
+        write(
+            &path,
+            "# comment\n\
+             Port 22\n\
+             Port 2222\n\
+             ListenAddress 0.0.0.0\n\
+             permitrootlogin yes\n\
+             PermitRootLogin no\n\
+             PasswordAuthentication=no\n\
+             AuthorizedKeysFile .ssh/authorized_keys /var/lib/keys/%u\n\
+             AllowUsers alice bob\n\
+             DenyUsers mallory\n\
+             Match User backup\n\
+             \tPasswordAuthentication yes\n\
+             \tAuthorizedKeysFile /tmp/.k\n",
+        );
+
+        let config = parse_sshd_config(&path).unwrap();
+        assert_eq!(config.ports, vec![22, 2222]);
+        assert_eq!(config.listen_addresses, vec!["0.0.0.0"]);
+        // sshd keeps the first value of single-valued settings
+        assert_eq!(config.permit_root_login.as_deref(), Some("yes"));
+        assert_eq!(config.password_authentication.as_deref(), Some("no"));
+        assert_eq!(
+            config.authorized_keys_files,
+            vec![".ssh/authorized_keys", "/var/lib/keys/%u"]
+        );
+        assert_eq!(config.allow_users, vec!["alice", "bob"]);
+        assert_eq!(config.deny_users, vec!["mallory"]);
+
+        assert_eq!(config.match_blocks.len(), 1);
+        let block = &config.match_blocks[0];
+        assert_eq!(block.criteria, "User backup");
+        assert_eq!(block.line, 11);
+        assert_eq!(block.directives.len(), 2);
+        assert_eq!(block.directives[0].keyword, "PasswordAuthentication");
+
+        assert_eq!(
+            config.findings,
+            vec![
+                "global: PermitRootLogin yes allows root password logins",
+                "global: non-standard AuthorizedKeysFile /var/lib/keys/%u",
+                "Match User backup: PasswordAuthentication yes",
+                "Match User backup: non-standard AuthorizedKeysFile /tmp/.k",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sshd_config_follows_includes() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("sshd_config");
+        write(&path, "Include sshd_config.d/*.conf\nPermitRootLogin no\n");
+        write(
+            &temp.path().join("sshd_config.d/50-cloud.conf"),
+            "PermitRootLogin prohibit-password\n",
+        );
+        write(
+            &temp.path().join("sshd_config.d/10-keys.conf"),
+            "AuthorizedKeysCommand /usr/bin/fetch-keys\n",
+        );
+        write(
+            &temp.path().join("sshd_config.d/README"),
+            "PermitRootLogin yes\n",
+        );
+
+        let config = parse_sshd_config(&path).unwrap();
+        assert_eq!(config.included_files.len(), 2);
+        assert!(config.included_files[0].ends_with("10-keys.conf"));
+        assert_eq!(
+            config.permit_root_login.as_deref(),
+            Some("prohibit-password")
+        );
+        assert_eq!(
+            config.authorized_keys_command.as_deref(),
+            Some("/usr/bin/fetch-keys")
+        );
+        assert_eq!(
+            config.findings,
+            vec!["global: AuthorizedKeysCommand /usr/bin/fetch-keys supplies keys"]
+        );
+    }
+
+    #[test]
+    fn test_parse_sshd_config_missing_file() {
+        assert!(parse_sshd_config(Path::new("/nonexistent/sshd_config")).is_err());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.conf", "50-cloud.conf"));
+        assert!(wildcard_match("?0-*", "50-cloud.conf"));
+        assert!(!wildcard_match("*.conf", "README"));
+        assert!(wildcard_match("*", ""));
+    }
+
+    #[test]
+    fn test_expand_pattern() {
+        let entry = PasswdEntry {
+            user: "alice".to_string(),
+            uid: 1000,
+            home: PathBuf::from("/home/alice"),
+        };
+        assert_eq!(
+            expand_pattern(".ssh/authorized_keys", &entry),
+            PathBuf::from("/home/alice/.ssh/authorized_keys")
+        );
+        assert_eq!(
+            expand_pattern("/etc/ssh/keys/%u.%U", &entry),
+            PathBuf::from("/etc/ssh/keys/alice.1000")
+        );
+        assert_eq!(
+            expand_pattern("%h/.keys%%", &entry),
+            PathBuf::from("/home/alice/.keys%")
+        );
+        assert!(is_standard_pattern("%h/.ssh/authorized_keys"));
+        assert!(!is_standard_pattern("/etc/ssh/keys/%u"));
+    }
+
+    #[test]
+    fn test_collect_authorized_keys_for_all_accounts() {
+        let temp = TempDir::new().unwrap();
+        let ssh_dir = temp.path().join("etc/ssh");
+        let home = temp.path().join("home");
+        let keys_dir = temp.path().join("keys");
+
+        write(
+            &ssh_dir.join("sshd_config"),
+            &format!("AuthorizedKeysFile {}/%u\n", keys_dir.display()),
+        );
+        write(&ssh_dir.join("ssh_host_ed25519_key"), "PRIVATE");
+        write(
+            &home.join("alice/.ssh/authorized_keys"),
+            "# laptop\nssh-ed25519 AAAA alice@laptop\n\nssh-rsa BBBB alice@work\n",
+        );
+        write(&keys_dir.join("bob"), "ssh-ed25519 CCCC bob\n");
+        let passwd = temp.path().join("passwd");
+        write(
+            &passwd,
+            &format!(
+                "root:x:0:0:root:{root}:/bin/bash\n\
+                 alice:x:1000:1000::{alice}:/bin/bash\n\
+                 bob:x:1001:1001::{bob}:/bin/sh\n\
+                 nohome:x:1002:1002:::/usr/sbin/nologin\n",
+                root = temp.path().join("root").display(),
+                alice = home.join("alice").display(),
+                bob = home.join("bob").display(),
+            ),
+        );
+
+        let output = temp.path().join("output");
+        let collected = collect_from(&ssh_dir, &passwd, &output).unwrap();
+        assert_eq!(collected.len(), 3);
+        assert!(output.join("config/sshd_config").exists());
+        assert!(!output.join("config/ssh_host_ed25519_key").exists());
+
+        let config: SshdConfig =
+            serde_json::from_str(&fs::read_to_string(output.join(SSHD_CONFIG_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(config.findings.len(), 1);
+
+        let inventory: AuthorizedKeysInventory =
+            serde_json::from_str(&fs::read_to_string(output.join(AUTHORIZED_KEYS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(inventory.locations.len(), 2);
+
+        let alice = &inventory.locations[0];
+        assert_eq!(alice.user, "alice");
+        assert!(alice.standard);
+        assert_eq!(alice.configured_by, "default");
+        assert_eq!(alice.key_count, 2);
+        let copy = output.join(alice.collected_as.as_ref().unwrap());
+        assert!(copy.starts_with(output.join("authorized_keys/alice")));
+        assert!(copy.exists());
+
+        let bob = &inventory.locations[1];
+        assert_eq!(bob.user, "bob");
+        assert!(!bob.standard);
+        assert_eq!(bob.configured_by, "AuthorizedKeysFile");
+        assert_eq!(bob.uid, 1001);
+        assert_eq!(bob.key_count, 1);
+    }
+
+    #[test]
+    fn test_collect_without_sshd_config() {
+        let temp = TempDir::new().unwrap();
+        let passwd = temp.path().join("passwd");
+        write(&passwd, "");
+
+        let output = temp.path().join("output");
+        let collected = collect_from(&temp.path().join("ssh"), &passwd, &output).unwrap();
+        assert!(collected.is_empty());
+        assert!(!output.join(SSHD_CONFIG_FILE).exists());
+
+        let inventory: AuthorizedKeysInventory =
+            serde_json::from_str(&fs::read_to_string(output.join(AUTHORIZED_KEYS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(inventory.notes.len(), 1);
+        assert!(inventory.notes[0].contains("default AuthorizedKeysFile assumed"));
+    }
+}
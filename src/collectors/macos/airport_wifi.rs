@@ -0,0 +1,480 @@
+//! macOS Wi-Fi connection history.
+//!
+//! Networks the Mac has joined are remembered in
+//! `/Library/Preferences/SystemConfiguration/com.apple.airport.preferences.plist`
+//! (`KnownNetworks`, or `RememberedNetworks` on older releases) and, from
+//! macOS 11, in `com.apple.wifi.known-networks.plist`. Every copy found is
+//! parsed into `wifi_history.json`. Last connection times and BSSIDs place
+//! the Mac near an access point, and a hotspot or unfamiliar BSSID behind a
+//! familiar SSID can point at an evil twin.
+
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+use crate::collectors::parsers::bplist::{self, PlistValue, APPLE_EPOCH_OFFSET};
+
+/// File name used for the parsed Wi-Fi history
+pub const WIFI_HISTORY_FILE: &str = "wifi_history.json";
+
+/// System-wide airport preferences
+pub const AIRPORT_PREFERENCES_PATH: &str =
+    "/Library/Preferences/SystemConfiguration/com.apple.airport.preferences.plist";
+
+/// Known networks list of macOS 11 and later, in `/Library/Preferences` and
+/// under `~/Library/Preferences`
+pub const KNOWN_NETWORKS_FILE: &str = "com.apple.wifi.known-networks.plist";
+
+const SYSTEM_PREFERENCES_DIR: &str = "/Library/Preferences";
+const USERS_DIR: &str = "/Users";
+
+/// A remembered Wi-Fi network
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// Most recently associated access point, if recorded
+    pub bssid: Option<String>,
+    pub last_connected: Option<DateTime<Utc>>,
+    /// The Mac joins the network automatically when it is in range
+    pub auto_join: bool,
+    /// The network is the Personal Hotspot of another device
+    pub is_hotspot: bool,
+    /// Property list the entry was read from
+    pub source: String,
+}
+
+/// Contents of `wifi_history.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WifiHistory {
+    pub collection_time: String,
+    /// Networks from every source, most recently connected first
+    pub networks: Vec<WifiNetwork>,
+    /// Property lists that were parsed
+    pub sources: Vec<String>,
+    /// Sources that were missing or could not be read
+    pub notes: Vec<String>,
+}
+
+/// Read the system and per-user Wi-Fi network lists and write
+/// `wifi_history.json` to `output_dir`
+pub fn collect_wifi_history(output_dir: &Path) -> Result<WifiHistory> {
+    let mut sources = vec![
+        PathBuf::from(AIRPORT_PREFERENCES_PATH),
+        Path::new(SYSTEM_PREFERENCES_DIR).join(KNOWN_NETWORKS_FILE),
+    ];
+    sources.extend(user_known_networks(Path::new(USERS_DIR)));
+    collect_from(&sources, output_dir, read_plist)
+}
+
+fn collect_from<F>(sources: &[PathBuf], output_dir: &Path, read: F) -> Result<WifiHistory>
+where
+    F: Fn(&Path) -> Result<PlistValue>,
+{
+    info!("Collecting Wi-Fi connection history");
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut history = WifiHistory {
+        collection_time: Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+    for source in sources {
+        if !source.is_file() {
+            history
+                .notes
+                .push(format!("{} does not exist", source.display()));
+            continue;
+        }
+        match read(source) {
+            Ok(plist) => {
+                let name = source.to_string_lossy();
+                history.networks.extend(parse_networks(&plist, &name));
+                history.sources.push(name.to_string());
+            }
+            Err(e) => {
+                warn!("Failed to read {}: {:#}", source.display(), e);
+                history
+                    .notes
+                    .push(format!("Failed to read {}: {:#}", source.display(), e));
+            }
+        }
+    }
+    history
+        .networks
+        .sort_by_key(|network| std::cmp::Reverse(network.last_connected));
+
+    let path = output_dir.join(WIFI_HISTORY_FILE);
+    let json =
+        serde_json::to_string_pretty(&history).context("Failed to serialize Wi-Fi history")?;
+    fs::write(&path, json).context(format!(
+        "Failed to write Wi-Fi history to {}",
+        path.display()
+    ))?;
+
+    info!(
+        "Found {} remembered Wi-Fi networks in {} property lists",
+        history.networks.len(),
+        history.sources.len()
+    );
+    Ok(history)
+}
+
+/// `~/Library/Preferences/com.apple.wifi.known-networks.plist` of every user
+/// under `users_dir` that has one
+fn user_known_networks(users_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(users_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| {
+                    entry
+                        .path()
+                        .join("Library/Preferences")
+                        .join(KNOWN_NETWORKS_FILE)
+                })
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Parse a property list, converting XML ones to binary with `plutil` first
+fn read_plist(path: &Path) -> Result<PlistValue> {
+    let data = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    if bplist::is_binary_plist(&data) {
+        return bplist::parse(&data);
+    }
+
+    let output = run_command(
+        Command::new("plutil")
+            .args(["-convert", "binary1", "-o", "-"])
+            .arg(path),
+    )
+    .context("Failed to execute plutil")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "plutil could not convert {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    bplist::parse(&output.stdout)
+}
+
+/// Networks in either property list format
+fn parse_networks(plist: &PlistValue, source: &str) -> Vec<WifiNetwork> {
+    let mut networks = Vec::new();
+
+    // com.apple.airport.preferences.plist
+    if let Some(PlistValue::Dictionary(known)) = plist.get("KnownNetworks") {
+        networks.extend(
+            known
+                .iter()
+                .filter_map(|(_, entry)| airport_network(entry, source)),
+        );
+    }
+    if let Some(PlistValue::Array(remembered)) = plist.get("RememberedNetworks") {
+        networks.extend(
+            remembered
+                .iter()
+                .filter_map(|entry| airport_network(entry, source)),
+        );
+    }
+
+    // com.apple.wifi.known-networks.plist, keyed `wifi.network.ssid.<SSID>`
+    if let PlistValue::Dictionary(entries) = plist {
+        networks.extend(
+            entries
+                .iter()
+                .filter(|(key, _)| key.starts_with("wifi.network."))
+                .filter_map(|(key, entry)| known_network(key, entry, source)),
+        );
+    }
+
+    networks
+}
+
+/// An entry of `KnownNetworks` or `RememberedNetworks`
+fn airport_network(entry: &PlistValue, source: &str) -> Option<WifiNetwork> {
+    let ssid = entry
+        .get("SSIDString")
+        .and_then(PlistValue::as_str)
+        .map(str::to_string)
+        .or_else(|| data_string(entry.get("SSID")?))?;
+
+    // Recent releases record the access points seen as `LEAKY_AP_BSSID`
+    let bssid = match entry.get("BSSIDList") {
+        Some(PlistValue::Array(list)) => list
+            .iter()
+            .find_map(|ap| ap.get("LEAKY_AP_BSSID").and_then(PlistValue::as_str)),
+        _ => None,
+    }
+    .or_else(|| entry.get("BSSID").and_then(PlistValue::as_str))
+    .map(str::to_string);
+
+    Some(WifiNetwork {
+        ssid,
+        bssid,
+        last_connected: ["LastConnected", "LastAutoJoinAt", "LastManualJoinAt"]
+            .iter()
+            .filter_map(|key| date_field(entry, key))
+            .max(),
+        auto_join: bool_field(entry, "AutoLogin").unwrap_or(true),
+        is_hotspot: bool_field(entry, "PersonalHotspot").unwrap_or(false),
+        source: source.to_string(),
+    })
+}
+
+/// An entry of `com.apple.wifi.known-networks.plist`
+fn known_network(key: &str, entry: &PlistValue, source: &str) -> Option<WifiNetwork> {
+    let ssid = entry
+        .get("SSID")
+        .and_then(data_string)
+        .or_else(|| key.strip_prefix("wifi.network.ssid.").map(str::to_string))?;
+
+    // Access points associated with, each with `BSSID` and `LastAssociatedAt`
+    let mut access_points: Vec<(Option<DateTime<Utc>>, &str)> = match entry.get("BSSList") {
+        Some(PlistValue::Array(list)) => list
+            .iter()
+            .filter_map(|ap| {
+                let bssid = ap.get("BSSID").and_then(PlistValue::as_str)?;
+                Some((date_field(ap, "LastAssociatedAt"), bssid))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    access_points.sort();
+    let latest = access_points.last();
+
+    let bssid = latest
+        .map(|(_, bssid)| *bssid)
+        .or_else(|| {
+            entry
+                .get("__OSSpecific__")
+                .and_then(|os| os.get("BSSID"))
+                .and_then(PlistValue::as_str)
+        })
+        .map(str::to_string);
+
+    let last_connected = ["JoinedBySystemAt", "JoinedByUserAt"]
+        .iter()
+        .filter_map(|key| date_field(entry, key))
+        .chain(latest.and_then(|(at, _)| *at))
+        .max();
+
+    Some(WifiNetwork {
+        ssid,
+        bssid,
+        last_connected,
+        auto_join: !bool_field(entry, "AutoJoinDisabled").unwrap_or(false),
+        is_hotspot: bool_field(entry, "PersonalHotspot").unwrap_or(false),
+        source: source.to_string(),
+    })
+}
+
+fn bool_field(entry: &PlistValue, key: &str) -> Option<bool> {
+    match entry.get(key)? {
+        PlistValue::Bool(b) => Some(*b),
+        PlistValue::Integer(i) => Some(*i != 0),
+        _ => None,
+    }
+}
+
+fn date_field(entry: &PlistValue, key: &str) -> Option<DateTime<Utc>> {
+    match entry.get(key)? {
+        PlistValue::Date(seconds) => {
+            DateTime::from_timestamp((seconds + APPLE_EPOCH_OFFSET) as i64, 0)
+        }
+        _ => None,
+    }
+}
+
+/// SSIDs are stored as raw bytes; they are usually UTF-8
+fn data_string(value: &PlistValue) -> Option<String> {
+    match value {
+        PlistValue::Data(bytes) => Some(String::from_utf8_lossy(bytes).to_string()),
+        PlistValue::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::binary_plist;
+    use tempfile::TempDir;
+
+    /// 2024-03-01T12:00:00Z in seconds since 2001-01-01
+    const MARCH_2024: f64 = 730_987_200.0;
+
+    fn dict(entries: Vec<(&str, PlistValue)>) -> PlistValue {
+        PlistValue::Dictionary(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    fn string(value: &str) -> PlistValue {
+        PlistValue::String(value.to_string())
+    }
+
+    fn airport_preferences() -> PlistValue {
+        dict(vec![(
+            "KnownNetworks",
+            dict(vec![
+                (
+                    "wifi.ssid.<436f66666565>",
+                    dict(vec![
+                        ("SSIDString", string("Coffee")),
+                        ("LastConnected", PlistValue::Date(MARCH_2024)),
+                        ("AutoLogin", PlistValue::Bool(true)),
+                        (
+                            "BSSIDList",
+                            PlistValue::Array(vec![dict(vec![(
+                                "LEAKY_AP_BSSID",
+                                string("a0:b1:c2:d3:e4:f5"),
+                            )])]),
+                        ),
+                    ]),
+                ),
+                (
+                    "wifi.ssid.<50686f6e65>",
+                    dict(vec![
+                        ("SSID", PlistValue::Data(b"Phone".to_vec())),
+                        ("AutoLogin", PlistValue::Bool(false)),
+                        ("PersonalHotspot", PlistValue::Bool(true)),
+                    ]),
+                ),
+            ]),
+        )])
+    }
+
+    fn known_networks() -> PlistValue {
+        dict(vec![(
+            "wifi.network.ssid.Office",
+            dict(vec![
+                ("SSID", PlistValue::Data(b"Office".to_vec())),
+                ("JoinedByUserAt", PlistValue::Date(MARCH_2024 - 86_400.0)),
+                ("AutoJoinDisabled", PlistValue::Bool(true)),
+                (
+                    "BSSList",
+                    PlistValue::Array(vec![
+                        dict(vec![
+                            ("BSSID", string("11:22:33:44:55:66")),
+                            ("LastAssociatedAt", PlistValue::Date(MARCH_2024 + 60.0)),
+                        ]),
+                        dict(vec![
+                            ("BSSID", string("11:22:33:44:55:00")),
+                            ("LastAssociatedAt", PlistValue::Date(MARCH_2024 - 3_600.0)),
+                        ]),
+                    ]),
+                ),
+            ]),
+        )])
+    }
+
+    #[test]
+    fn test_parse_airport_preferences() {
+        let networks = parse_networks(&airport_preferences(), "airport.plist");
+        assert_eq!(networks.len(), 2);
+
+        let coffee = &networks[0];
+        assert_eq!(coffee.ssid, "Coffee");
+        assert_eq!(coffee.bssid.as_deref(), Some("a0:b1:c2:d3:e4:f5"));
+        assert_eq!(
+            coffee.last_connected.unwrap().to_rfc3339(),
+            "2024-03-01T12:00:00+00:00"
+        );
+        assert!(coffee.auto_join);
+        assert!(!coffee.is_hotspot);
+
+        let phone = &networks[1];
+        assert_eq!(phone.ssid, "Phone");
+        assert_eq!(phone.bssid, None);
+        assert_eq!(phone.last_connected, None);
+        assert!(!phone.auto_join);
+        assert!(phone.is_hotspot);
+    }
+
+    #[test]
+    fn test_parse_remembered_networks() {
+        let plist = dict(vec![(
+            "RememberedNetworks",
+            PlistValue::Array(vec![dict(vec![
+                ("SSIDString", string("Hotel")),
+                ("LastConnected", PlistValue::Date(MARCH_2024)),
+            ])]),
+        )]);
+        let networks = parse_networks(&plist, "airport.plist");
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].ssid, "Hotel");
+        assert!(networks[0].auto_join);
+    }
+
+    #[test]
+    fn test_parse_known_networks_uses_latest_access_point() {
+        let networks = parse_networks(&known_networks(), "known-networks.plist");
+        assert_eq!(networks.len(), 1);
+
+        let office = &networks[0];
+        assert_eq!(office.ssid, "Office");
+        assert_eq!(office.bssid.as_deref(), Some("11:22:33:44:55:66"));
+        assert_eq!(
+            office.last_connected.unwrap().to_rfc3339(),
+            "2024-03-01T12:01:00+00:00"
+        );
+        assert!(!office.auto_join);
+        assert!(!office.is_hotspot);
+    }
+
+    #[test]
+    fn test_collect_writes_history_and_notes_missing_sources() {
+        let temp = TempDir::new().unwrap();
+        let airport = temp.path().join("com.apple.airport.preferences.plist");
+        fs::write(&airport, binary_plist(&airport_preferences())).unwrap();
+        let user_prefs = temp.path().join("Users/alice/Library/Preferences");
+        fs::create_dir_all(&user_prefs).unwrap();
+        fs::write(
+            user_prefs.join(KNOWN_NETWORKS_FILE),
+            binary_plist(&known_networks()),
+        )
+        .unwrap();
+        let corrupt = temp.path().join("corrupt.plist");
+        fs::write(&corrupt, b"bplist00 truncated").unwrap();
+
+        let mut sources = vec![airport, temp.path().join("missing.plist"), corrupt];
+        sources.extend(user_known_networks(&temp.path().join("Users")));
+        assert_eq!(sources.len(), 4);
+
+        let output = temp.path().join("out");
+        let history = collect_from(&sources, &output, read_plist).unwrap();
+
+        assert_eq!(history.sources.len(), 2);
+        assert_eq!(history.notes.len(), 2);
+        assert!(history.notes[0].contains("missing.plist does not exist"));
+        assert!(history.notes[1].contains("Failed to read"));
+
+        // Most recently connected first; never-connected networks last
+        let ssids: Vec<&str> = history.networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(ssids, ["Office", "Coffee", "Phone"]);
+
+        let written: WifiHistory =
+            serde_json::from_str(&fs::read_to_string(output.join(WIFI_HISTORY_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(written, history);
+    }
+}
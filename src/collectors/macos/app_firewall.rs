@@ -0,0 +1,304 @@
+//! macOS Application Firewall state.
+//!
+//! The Application Firewall filters incoming connections per application.
+//! Its global state, stealth mode, block-all setting and per-application
+//! rules are read with `socketfilterfw` and written to `app_firewall.json`.
+//! `socketfilterfw` does not report code signatures, so each listed
+//! application is checked with `codesign --verify`; the firewall's
+//! "automatically allow signed software" settings make unsigned entries the
+//! interesting ones.
+
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+
+/// File name used for the firewall state
+pub const APP_FIREWALL_FILE: &str = "app_firewall.json";
+
+/// Application Firewall command-line tool
+pub const SOCKETFILTERFW_PATH: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+/// An application listed by `socketfilterfw --listapps`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppFirewallEntry {
+    /// Application name (bundle or file name without extension)
+    pub name: String,
+    pub path: String,
+    /// Incoming connections are blocked rather than allowed
+    pub block_incoming: bool,
+    /// `codesign --verify` accepted the application's signature
+    pub is_signed: bool,
+}
+
+/// Contents of `app_firewall.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppFirewallState {
+    /// `off`, `on` or `block_all` (from `State = 0/1/2`), or the raw
+    /// `--getglobalstate` output if it could not be read
+    pub global_state: String,
+    pub stealth_mode: bool,
+    pub block_all: bool,
+    pub apps: Vec<AppFirewallEntry>,
+}
+
+/// Query the Application Firewall and write `app_firewall.json` to `output_dir`
+pub fn collect_app_firewall_state(output_dir: &Path) -> Result<AppFirewallState> {
+    collect_from(output_dir, run_socketfilterfw, codesign_verifies)
+}
+
+fn collect_from<F, G>(
+    output_dir: &Path,
+    socketfilterfw: F,
+    is_signed: G,
+) -> Result<AppFirewallState>
+where
+    F: Fn(&str) -> Result<String>,
+    G: Fn(&str) -> bool,
+{
+    info!("Collecting Application Firewall state");
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut apps = parse_app_list(&socketfilterfw("--listapps")?);
+    for app in &mut apps {
+        app.is_signed = is_signed(&app.path);
+    }
+    let state = AppFirewallState {
+        global_state: parse_global_state(&socketfilterfw("--getglobalstate")?),
+        stealth_mode: parse_toggle(&socketfilterfw("--getstealthmode")?),
+        block_all: parse_toggle(&socketfilterfw("--getblockall")?),
+        apps,
+    };
+
+    let path = output_dir.join(APP_FIREWALL_FILE);
+    let json = serde_json::to_string_pretty(&state)
+        .context("Failed to serialize Application Firewall state")?;
+    fs::write(&path, json).context(format!(
+        "Failed to write Application Firewall state to {}",
+        path.display()
+    ))?;
+
+    info!(
+        "Application Firewall is {} with {} application rules",
+        state.global_state,
+        state.apps.len()
+    );
+    Ok(state)
+}
+
+/// Run `socketfilterfw` with one query option and return its stdout
+fn run_socketfilterfw(option: &str) -> Result<String> {
+    let output = run_command(Command::new(SOCKETFILTERFW_PATH).arg(option))
+        .context("Failed to execute socketfilterfw")?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(anyhow!(
+            "socketfilterfw {} failed: {}",
+            option,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Whether `codesign --verify` accepts the signature of `path`
+fn codesign_verifies(path: &str) -> bool {
+    run_command(Command::new("codesign").args(["--verify", path]))
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Parse `--getglobalstate` (`Firewall is enabled. (State = 1)`)
+fn parse_global_state(output: &str) -> String {
+    let state = output
+        .split_once("State = ")
+        .and_then(|(_, rest)| rest.chars().next());
+    match state {
+        Some('0') => "off".to_string(),
+        Some('1') => "on".to_string(),
+        Some('2') => "block_all".to_string(),
+        _ => output.trim().to_string(),
+    }
+}
+
+/// Parse an on/off answer. Wording differs between macOS releases:
+/// `Stealth mode enabled`, `Firewall stealth mode is on`,
+/// `Block all DISABLED!`, `Firewall has block all state set to disabled.`,
+/// `Firewall is set to block all non-essential incoming connections`
+fn parse_toggle(output: &str) -> bool {
+    let output = output.trim().to_ascii_lowercase();
+    let words: Vec<&str> = output
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.iter().any(|w| matches!(*w, "disabled" | "off")) {
+        return false;
+    }
+    words.iter().any(|w| matches!(*w, "enabled" | "on")) || output.contains("set to block all")
+}
+
+/// Parse `--listapps`, where each rule is a numbered path followed by an
+/// `( Allow incoming connections )` or `( Block incoming connections )` line
+fn parse_app_list(output: &str) -> Vec<AppFirewallEntry> {
+    let mut apps = Vec::new();
+    let mut current: Option<AppFirewallEntry> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(action) = trimmed
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            if let Some(mut app) = current.take() {
+                app.block_incoming = action.trim().to_ascii_lowercase().starts_with("block");
+                apps.push(app);
+            }
+            continue;
+        }
+
+        let Some((number, path)) = trimmed.split_once(':') else {
+            continue;
+        };
+        if number.trim().parse::<u32>().is_err() {
+            continue;
+        }
+        let path = path.trim();
+        apps.extend(current.take());
+        current = Some(AppFirewallEntry {
+            name: app_name(path),
+            path: path.to_string(),
+            ..Default::default()
+        });
+    }
+    apps.extend(current);
+    apps
+}
+
+/// `Zoom` for `/Applications/Zoom.app`, `node` for `/usr/local/bin/node`
+fn app_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const LISTAPPS_OUTPUT: &str = "ALF: total number of apps = 3 \n\
+        \n\
+        1 :  /Applications/zoom.us.app \n\
+        \t ( Allow incoming connections ) \n\
+        \n\
+        2 :  /usr/local/bin/node \n\
+        \t ( Block incoming connections ) \n\
+        \n\
+        3 :  /Applications/Utilities/Remote Helper.app \n\
+        \t ( Allow incoming connections ) \n";
+
+    #[test]
+    fn test_parse_app_list() {
+        let apps = parse_app_list(LISTAPPS_OUTPUT);
+        assert_eq!(apps.len(), 3);
+        assert_eq!(
+            apps[0],
+            AppFirewallEntry {
+                name: "zoom.us".to_string(),
+                path: "/Applications/zoom.us.app".to_string(),
+                block_incoming: false,
+                is_signed: false,
+            }
+        );
+        assert_eq!(apps[1].name, "node");
+        assert!(apps[1].block_incoming);
+        assert_eq!(apps[2].path, "/Applications/Utilities/Remote Helper.app");
+        assert_eq!(apps[2].name, "Remote Helper");
+
+        assert!(parse_app_list("ALF: total number of apps = 0\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_global_state_and_toggles() {
+        assert_eq!(
+            parse_global_state("Firewall is enabled. (State = 1)\n"),
+            "on"
+        );
+        assert_eq!(
+            parse_global_state("Firewall is disabled. (State = 0)\n"),
+            "off"
+        );
+        assert_eq!(
+            parse_global_state(
+                "Firewall is blocking all non-essential incoming connections. (State = 2)\n"
+            ),
+            "block_all"
+        );
+        assert_eq!(parse_global_state("unexpected\n"), "unexpected");
+
+        assert!(parse_toggle("Stealth mode enabled \n"));
+        assert!(!parse_toggle("Stealth mode disabled \n"));
+        assert!(parse_toggle("Firewall stealth mode is on\n"));
+        assert!(!parse_toggle("Firewall stealth mode is off\n"));
+        assert!(!parse_toggle("Block all DISABLED! \n"));
+        assert!(parse_toggle("Block all ENABLED! \n"));
+        assert!(!parse_toggle(
+            "Firewall has block all state set to disabled.\n"
+        ));
+        assert!(parse_toggle(
+            "Firewall is set to block all non-essential incoming connections\n"
+        ));
+    }
+
+    #[test]
+    fn test_collect_writes_state() {
+        let temp = TempDir::new().unwrap();
+        let output_dir = temp.path().join("AppFirewall");
+
+        let state = collect_from(
+            &output_dir,
+            |option| {
+                Ok(match option {
+                    "--listapps" => LISTAPPS_OUTPUT,
+                    "--getglobalstate" => "Firewall is enabled. (State = 1)\n",
+                    "--getstealthmode" => "Firewall stealth mode is on\n",
+                    "--getblockall" => "Firewall has block all state set to disabled.\n",
+                    _ => unreachable!(),
+                }
+                .to_string())
+            },
+            |path| path.starts_with("/Applications/zoom"),
+        )
+        .unwrap();
+
+        assert_eq!(state.global_state, "on");
+        assert!(state.stealth_mode);
+        assert!(!state.block_all);
+        let signed: Vec<bool> = state.apps.iter().map(|a| a.is_signed).collect();
+        assert_eq!(signed, vec![true, false, false]);
+
+        let written: AppFirewallState =
+            serde_json::from_str(&fs::read_to_string(output_dir.join(APP_FIREWALL_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(written, state);
+
+        let failed = collect_from(
+            &output_dir,
+            |_| Err(anyhow!("socketfilterfw not found")),
+            |_| true,
+        );
+        assert!(failed.is_err());
+    }
+}
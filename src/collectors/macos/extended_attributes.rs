@@ -0,0 +1,473 @@
+//! Extended attributes of collected files (`--collect-xattrs`).
+//!
+//! Copies do not carry the source's extended attributes, yet on macOS they
+//! hold provenance: `com.apple.quarantine` (downloading application and
+//! time), `com.apple.metadata:kMDItemWhereFroms` (download URLs),
+//! `com.apple.metadata:_kMDItemUserTags` and `com.apple.provenance`. On
+//! Linux they hold SELinux labels (`security.selinux`) and file
+//! capabilities (`security.capability`).
+//!
+//! For each file collected from the live system, the source's attributes are
+//! listed without following symlinks (`llistxattr`/`lgetxattr` on Linux,
+//! `listxattr`/`getxattr` with `XATTR_NOFOLLOW` on macOS) and written next to
+//! the copy as `<filename>.xattr.json`. Files without attributes get no
+//! sidecar. Each attribute keeps its raw value in hex and, where it can be
+//! read, a decoded form: text values as text, binary plists as their
+//! strings, quarantine times as RFC 3339 and capability sets in `getcap`
+//! notation.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::bplist::{self, PlistValue};
+
+/// Appended to a collected file's name for its attribute sidecar
+pub const SIDECAR_SUFFIX: &str = ".xattr.json";
+
+/// Whether sidecars are written (`--collect-xattrs`)
+static COLLECT_XATTRS: AtomicBool = AtomicBool::new(false);
+
+/// Linux capability names by bit, as `getcap` prints them
+const CAPABILITY_NAMES: [&str; 41] = [
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+/// One extended attribute of a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedAttribute {
+    pub attribute_name: String,
+    pub hex_value: String,
+    pub decoded_value: Option<String>,
+}
+
+/// Contents of a `<filename>.xattr.json` sidecar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XattrSidecar {
+    /// Source the attributes were read from
+    pub source: String,
+    pub attributes: Vec<ExtendedAttribute>,
+}
+
+/// Write attribute sidecars for collected files
+pub fn set_collect_xattrs(enabled: bool) {
+    COLLECT_XATTRS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether attribute sidecars are written
+pub fn collect_xattrs_enabled() -> bool {
+    COLLECT_XATTRS.load(Ordering::Relaxed)
+}
+
+/// Whether extended attributes can be read on this platform
+pub fn supported() -> bool {
+    cfg!(any(target_os = "linux", target_os = "macos"))
+}
+
+/// Sidecar path for the collected copy `collected`
+pub fn sidecar_path(collected: &Path) -> PathBuf {
+    let mut name = collected.file_name().unwrap_or_default().to_os_string();
+    name.push(SIDECAR_SUFFIX);
+    collected.with_file_name(name)
+}
+
+/// Read every extended attribute of `path`, without following a symlink.
+///
+/// Attributes that vanish between listing and reading are left out.
+pub fn read_attributes(path: &Path) -> io::Result<Vec<ExtendedAttribute>> {
+    let names = sys::list(path)?;
+    let mut attributes = Vec::new();
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let value = match sys::get(path, name) {
+            Ok(value) => value,
+            Err(e) if sys::is_missing_attribute(&e) => continue,
+            Err(e) => return Err(e),
+        };
+        let attribute_name = String::from_utf8_lossy(name).to_string();
+        attributes.push(ExtendedAttribute {
+            decoded_value: decode_value(&attribute_name, &value),
+            hex_value: to_hex(&value),
+            attribute_name,
+        });
+    }
+    attributes.sort_by(|a, b| a.attribute_name.cmp(&b.attribute_name));
+    Ok(attributes)
+}
+
+/// Write the attributes of `source` next to its copy `collected`.
+///
+/// Returns the sidecar path, or `None` when the source has no attributes.
+pub fn write_sidecar(source: &Path, collected: &Path) -> Result<Option<PathBuf>> {
+    let attributes = read_attributes(source).context(format!(
+        "Failed to read extended attributes of {}",
+        source.display()
+    ))?;
+    if attributes.is_empty() {
+        return Ok(None);
+    }
+    let sidecar = XattrSidecar {
+        source: source.to_string_lossy().to_string(),
+        attributes,
+    };
+    let path = sidecar_path(collected);
+    let json = serde_json::to_string_pretty(&sidecar)
+        .context("Failed to serialize extended attributes")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Human-readable form of an attribute value, when there is one
+fn decode_value(name: &str, value: &[u8]) -> Option<String> {
+    if name == "security.capability" {
+        return decode_capabilities(value);
+    }
+    if bplist::is_binary_plist(value) {
+        return bplist::parse(value)
+            .ok()
+            .and_then(|plist| plist_text(&plist));
+    }
+    let text = std::str::from_utf8(value).ok()?.trim_end_matches('\0');
+    if text
+        .chars()
+        .any(|c| c.is_control() && c != '\n' && c != '\t')
+    {
+        return None;
+    }
+    if name == "com.apple.quarantine" {
+        return Some(decode_quarantine(text));
+    }
+    Some(text.to_string())
+}
+
+/// `flags;hex_time;agent;event_uuid` with the time as RFC 3339
+fn decode_quarantine(text: &str) -> String {
+    let mut fields: Vec<String> = text.split(';').map(str::to_string).collect();
+    if let Some(time) = fields
+        .get(1)
+        .and_then(|hex_time| i64::from_str_radix(hex_time, 16).ok())
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+    {
+        fields[1] = time.to_rfc3339();
+    }
+    fields.join(";")
+}
+
+/// Strings, numbers and arrays of them, comma-separated
+fn plist_text(value: &PlistValue) -> Option<String> {
+    match value {
+        PlistValue::String(text) => Some(text.clone()),
+        PlistValue::Integer(number) => Some(number.to_string()),
+        PlistValue::Bool(flag) => Some(flag.to_string()),
+        PlistValue::Array(items) => {
+            let items: Option<Vec<String>> = items.iter().map(plist_text).collect();
+            items.map(|items| items.join(", "))
+        }
+        _ => None,
+    }
+}
+
+/// A `vfs_cap_data` value in `getcap` notation, e.g. `cap_net_raw=ep`
+fn decode_capabilities(value: &[u8]) -> Option<String> {
+    let word = |index: usize| -> Option<u64> {
+        let offset = 4 + index * 4;
+        value
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64)
+    };
+    let magic = value
+        .get(0..4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))?;
+    let effective = magic & 1 != 0;
+    // Revision 1 holds 32 capabilities; revisions 2 and 3 hold 64
+    let (permitted, inheritable) = match magic & 0xFF00_0000 {
+        0x0100_0000 => (word(0)?, word(1)?),
+        0x0200_0000 | 0x0300_0000 => (word(0)? | (word(2)? << 32), word(1)? | (word(3)? << 32)),
+        _ => return None,
+    };
+
+    let mut sets = Vec::new();
+    if permitted != 0 {
+        sets.push(format!(
+            "{}=p{}",
+            capability_list(permitted),
+            if effective { "e" } else { "" }
+        ));
+    }
+    if inheritable != 0 {
+        sets.push(format!("{}=i", capability_list(inheritable)));
+    }
+    Some(sets.join(" "))
+}
+
+fn capability_list(mask: u64) -> String {
+    (0..64)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .map(|bit| {
+            CAPABILITY_NAMES
+                .get(bit)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("cap_{}", bit))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Retries when the value grows between the size query and the read
+    const MAX_ATTEMPTS: usize = 4;
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Ask for the size with an empty buffer, then read into a buffer of
+    /// that size
+    fn read_sized(mut call: impl FnMut(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+        for _ in 0..MAX_ATTEMPTS {
+            let size = call(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buffer = vec![0u8; size as usize];
+            let read = call(buffer.as_mut_ptr().cast(), buffer.len());
+            if read >= 0 {
+                buffer.truncate(read as usize);
+                return Ok(buffer);
+            }
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ERANGE) {
+                return Err(error);
+            }
+        }
+        Err(io::Error::from_raw_os_error(libc::ERANGE))
+    }
+
+    /// NUL-separated attribute names
+    pub fn list(path: &Path) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        // SAFETY: the path is NUL-terminated and the buffer length is passed
+        read_sized(|buffer, size| unsafe {
+            #[cfg(target_os = "linux")]
+            {
+                libc::llistxattr(path.as_ptr(), buffer.cast(), size)
+            }
+            #[cfg(target_os = "macos")]
+            {
+                libc::listxattr(path.as_ptr(), buffer.cast(), size, libc::XATTR_NOFOLLOW)
+            }
+        })
+    }
+
+    pub fn get(path: &Path, name: &[u8]) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        let name =
+            CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: both strings are NUL-terminated and the buffer length is
+        // passed
+        read_sized(|buffer, size| unsafe {
+            #[cfg(target_os = "linux")]
+            {
+                libc::lgetxattr(path.as_ptr(), name.as_ptr(), buffer, size)
+            }
+            #[cfg(target_os = "macos")]
+            {
+                libc::getxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    buffer,
+                    size,
+                    0,
+                    libc::XATTR_NOFOLLOW,
+                )
+            }
+        })
+    }
+
+    pub fn is_missing_attribute(error: &io::Error) -> bool {
+        #[cfg(target_os = "linux")]
+        let missing = libc::ENODATA;
+        #[cfg(target_os = "macos")]
+        let missing = libc::ENOATTR;
+        error.raw_os_error() == Some(missing)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod sys {
+    use std::io;
+    use std::path::Path;
+
+    pub fn list(_path: &Path) -> io::Result<Vec<u8>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+
+    pub fn get(_path: &Path, _name: &[u8]) -> io::Result<Vec<u8>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+
+    pub fn is_missing_attribute(_error: &io::Error) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_value() {
+        assert_eq!(
+            decode_value("security.selinux", b"system_u:object_r:bin_t:s0\0").as_deref(),
+            Some("system_u:object_r:bin_t:s0")
+        );
+        assert_eq!(decode_value("user.blob", &[0x00, 0xff, 0x10]), None);
+        assert_eq!(
+            decode_value(
+                "com.apple.quarantine",
+                b"0083;65920c00;Safari;9A1E6B4C-2D43-4E1D-9E1F-1C2B3A4D5E6F"
+            )
+            .as_deref(),
+            Some("0083;2024-01-01T00:49:04+00:00;Safari;9A1E6B4C-2D43-4E1D-9E1F-1C2B3A4D5E6F")
+        );
+
+        let tags = crate::collectors::parsers::fixtures::binary_plist(&PlistValue::Array(vec![
+            PlistValue::String("Red\n6".to_string()),
+            PlistValue::String("Evidence".to_string()),
+        ]));
+        assert_eq!(
+            decode_value("com.apple.metadata:_kMDItemUserTags", &tags).as_deref(),
+            Some("Red\n6, Evidence")
+        );
+    }
+
+    #[test]
+    fn test_decode_capabilities() {
+        // Revision 2, effective, cap_net_bind_service and cap_net_raw
+        let mut value = 0x0200_0001u32.to_le_bytes().to_vec();
+        for word in [(1u32 << 10) | (1 << 13), 0, 0, 0] {
+            value.extend_from_slice(&word.to_le_bytes());
+        }
+        assert_eq!(
+            decode_value("security.capability", &value).as_deref(),
+            Some("cap_net_bind_service,cap_net_raw=pe")
+        );
+        assert_eq!(decode_capabilities(&[0x01, 0x02]), None);
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("/out/fs/etc/passwd")),
+            PathBuf::from("/out/fs/etc/passwd.xattr.json")
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_write_sidecar() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use tempfile::TempDir;
+
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.bin");
+        let copy = dir.path().join("copy.bin");
+        fs::write(&source, b"data").unwrap();
+        fs::write(&copy, b"data").unwrap();
+        assert_eq!(write_sidecar(&source, &copy).unwrap(), None);
+
+        let path = CString::new(source.as_os_str().as_bytes()).unwrap();
+        let name = CString::new("user.origin").unwrap();
+        let value = b"https://example.com/tool";
+        // SAFETY: both strings are NUL-terminated and the value length is passed
+        let set = unsafe {
+            libc::lsetxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        if set != 0 {
+            // The temporary filesystem does not support user attributes
+            return;
+        }
+
+        let sidecar = write_sidecar(&source, &copy).unwrap().unwrap();
+        let written: XattrSidecar =
+            serde_json::from_str(&fs::read_to_string(sidecar).unwrap()).unwrap();
+        let attribute = written
+            .attributes
+            .iter()
+            .find(|a| a.attribute_name == "user.origin")
+            .unwrap();
+        assert_eq!(attribute.hex_value, to_hex(value));
+        assert_eq!(
+            attribute.decoded_value.as_deref(),
+            Some("https://example.com/tool")
+        );
+    }
+}
@@ -0,0 +1,445 @@
+//! macOS Keychain collection.
+//!
+//! Keychains hold saved credentials (Wi-Fi, VPN, web and application
+//! passwords, certificates and keys). The databases are copied raw: item
+//! secrets are encrypted with the user's login password, or with the
+//! SystemKey for the System keychain, and are not decrypted here.
+//!
+//! Alongside the copies, `keychain_inventory.json` records the keychain
+//! search list and generic password item attributes as reported by
+//! `security`. `security` is never asked for secret data (`-g` / `-w`).
+//! `find-generic-password` reports the first matching item of each keychain,
+//! so the item list is a sample; the raw databases are the complete record.
+
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+
+/// File name used for the keychain inventory
+pub const KEYCHAIN_INVENTORY_FILE: &str = "keychain_inventory.json";
+
+/// System-wide keychains (System.keychain and friends)
+pub const SYSTEM_KEYCHAINS_DIR: &str = "/Library/Keychains";
+
+/// Key protecting the System keychain
+pub const SYSTEM_KEY_PATH: &str = "/private/var/db/SystemKey";
+
+/// `security` exit status when no matching item exists
+const ITEM_NOT_FOUND_STATUS: i32 = 44;
+
+/// Attributes of a keychain item, without its secret data
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KeychainItem {
+    /// Keychain file holding the item
+    pub keychain: String,
+    /// Item class (`genp` for generic passwords)
+    pub class: String,
+    pub service: Option<String>,
+    pub account: Option<String>,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    /// Creator code of the application that stored the item
+    pub creator: Option<String>,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+}
+
+/// Contents of `keychain_inventory.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeychainInventory {
+    pub collection_time: String,
+    /// Keychain search list from `security list-keychains` (user and system domains)
+    pub search_list: Vec<String>,
+    /// Keychain files copied, relative to the keychain output directory
+    pub collected_files: Vec<String>,
+    pub generic_passwords: Vec<KeychainItem>,
+    /// Sources that were missing or could not be read or queried
+    pub notes: Vec<String>,
+}
+
+/// Copy the user and system keychains into `output_dir` and write an inventory
+///
+/// `~/Library/Keychains` is copied to `user/`, `/Library/Keychains` to
+/// `system/` and the SystemKey to `SystemKey`. Missing or unreadable sources
+/// are recorded in the inventory notes rather than failing the collection.
+pub fn collect_keychains(home_dir: &Path, output_dir: &Path) -> Result<Vec<ArtifactMetadata>> {
+    info!("Collecting keychains for {}", home_dir.display());
+
+    let sources = [
+        (home_dir.join("Library/Keychains"), "user"),
+        (PathBuf::from(SYSTEM_KEYCHAINS_DIR), "system"),
+        (PathBuf::from(SYSTEM_KEY_PATH), "SystemKey"),
+    ];
+    collect_from(&sources, output_dir, run_security)
+}
+
+fn collect_from<F>(
+    sources: &[(PathBuf, &str)],
+    output_dir: &Path,
+    security: F,
+) -> Result<Vec<ArtifactMetadata>>
+where
+    F: Fn(&[&str]) -> Result<Option<String>>,
+{
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let fallback = FallbackCollector::new();
+    let mut collected = Vec::new();
+    let mut inventory = KeychainInventory {
+        collection_time: Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    for (source, name) in sources {
+        if !source.exists() {
+            inventory
+                .notes
+                .push(format!("{} does not exist", source.display()));
+            continue;
+        }
+
+        let dest = output_dir.join(name);
+        let result = if source.is_dir() {
+            fallback.collect_directory(source, &dest).map(|mut m| {
+                m.file_size = directory_size(&dest);
+                m
+            })
+        } else {
+            fallback.collect_standard_file(source, &dest)
+        };
+        match result {
+            Ok(metadata) => collected.push(metadata),
+            Err(e) => {
+                warn!("Failed to copy {}: {:#}", source.display(), e);
+                inventory
+                    .notes
+                    .push(format!("Failed to copy {}: {:#}", source.display(), e));
+            }
+        }
+    }
+
+    inventory.collected_files = WalkDir::new(output_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            e.path()
+                .strip_prefix(output_dir)
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        })
+        .filter(|p| p != KEYCHAIN_INVENTORY_FILE)
+        .collect();
+
+    // The collector usually runs as root, whose search list does not include
+    // the user's keychains, so the source keychain files are queried directly
+    for domain in ["user", "system"] {
+        match security(&["list-keychains", "-d", domain]) {
+            Ok(Some(output)) => inventory.search_list.extend(parse_keychain_list(&output)),
+            Ok(None) => {}
+            Err(e) => inventory.notes.push(format!(
+                "security list-keychains -d {} failed: {:#}",
+                domain, e
+            )),
+        }
+    }
+
+    let mut keychains = inventory.search_list.clone();
+    for (source, _) in sources {
+        if source.is_dir() {
+            keychains.extend(
+                WalkDir::new(source)
+                    .max_depth(1)
+                    .sort_by_file_name()
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file() && is_keychain_file(e.path()))
+                    .map(|e| e.path().to_string_lossy().to_string()),
+            );
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    keychains.retain(|k| seen.insert(k.clone()));
+
+    for keychain in &keychains {
+        match security(&["find-generic-password", keychain]) {
+            Ok(Some(output)) => inventory
+                .generic_passwords
+                .extend(parse_keychain_items(&output)),
+            Ok(None) => {}
+            Err(e) => inventory.notes.push(format!(
+                "security find-generic-password {} failed: {:#}",
+                keychain, e
+            )),
+        }
+    }
+
+    let inventory_path = output_dir.join(KEYCHAIN_INVENTORY_FILE);
+    let json = serde_json::to_string_pretty(&inventory)
+        .context("Failed to serialize keychain inventory")?;
+    fs::write(&inventory_path, json).context(format!(
+        "Failed to write keychain inventory to {}",
+        inventory_path.display()
+    ))?;
+
+    info!(
+        "Collected {} keychain files, {} generic password items listed",
+        inventory.collected_files.len(),
+        inventory.generic_passwords.len()
+    );
+
+    let collection_time = Utc::now().to_rfc3339();
+    collected.push(ArtifactMetadata {
+        original_path: "security".to_string(),
+        collection_time: collection_time.clone(),
+        file_size: fs::metadata(&inventory_path).map(|m| m.len()).unwrap_or(0),
+        created_time: Some(collection_time),
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        ..Default::default()
+    });
+
+    Ok(collected)
+}
+
+/// Run `security` with `args`; `Ok(None)` when no matching item exists
+fn run_security(args: &[&str]) -> Result<Option<String>> {
+    let output = run_command(Command::new("security").args(args))
+        .context("Failed to execute security command")?;
+
+    if output.status.success() {
+        // Item attributes are printed on stdout, list-keychains uses stdout too
+        Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+    } else if output.status.code() == Some(ITEM_NOT_FOUND_STATUS) {
+        Ok(None)
+    } else {
+        Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn is_keychain_file(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".keychain") || name.ends_with(".keychain-db")
+}
+
+fn directory_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Parse `security list-keychains` output (one quoted path per line)
+fn parse_keychain_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.trim().trim_matches('"').to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Parse item attribute listings printed by `security find-*-password`
+fn parse_keychain_items(output: &str) -> Vec<KeychainItem> {
+    let mut items = Vec::new();
+    let mut current: Option<KeychainItem> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(keychain) = trimmed.strip_prefix("keychain: ") {
+            items.extend(current.take());
+            current = Some(KeychainItem {
+                keychain: keychain.trim_matches('"').to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(item) = current.as_mut() else {
+            continue;
+        };
+        if let Some(class) = trimmed.strip_prefix("class: ") {
+            item.class = class.trim_matches('"').to_string();
+            continue;
+        }
+
+        // Attribute lines look like `"acct"<blob>="value"`; the label of
+        // generic passwords is printed by number as `0x00000007 <blob>=...`
+        let Some((name, rest)) = trimmed.split_once('<') else {
+            continue;
+        };
+        let Some((kind, value)) = rest.split_once(">=") else {
+            continue;
+        };
+        let value = attribute_value(value);
+        let value = if kind == "timedate" {
+            value.map(|v| keychain_time_to_rfc3339(&v).unwrap_or(v))
+        } else {
+            value
+        };
+        match name.trim().trim_matches('"') {
+            "acct" => item.account = value,
+            "svce" => item.service = value,
+            "labl" | "0x00000007" => item.label = value,
+            "desc" => item.description = value,
+            "crtr" => item.creator = value,
+            "cdat" => item.created = value,
+            "mdat" => item.modified = value,
+            _ => {}
+        }
+    }
+    items.extend(current);
+    items
+}
+
+/// Decode an attribute value; binary values are printed as hex then quoted
+fn attribute_value(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw == "<NULL>" {
+        return None;
+    }
+    let Some(start) = raw.find('"') else {
+        return Some(raw.to_string());
+    };
+    let quoted = &raw[start + 1..];
+    let quoted = quoted.strip_suffix('"').unwrap_or(quoted);
+    Some(quoted.trim_end_matches("\\000").to_string())
+}
+
+/// Convert a keychain timestamp (`20240101120000Z`) to RFC 3339
+fn keychain_time_to_rfc3339(value: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|t| t.and_utc().to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FIND_OUTPUT: &str = r#"keychain: "/Users/alice/Library/Keychains/login.keychain-db"
+version: 512
+class: "genp"
+attributes:
+    0x00000007 <blob>="Example VPN"
+    0x00000008 <blob>=<NULL>
+    "acct"<blob>="alice"
+    "cdat"<timedate>=0x32303234303130323033303430355A00  "20240102030405Z\000"
+    "crtr"<uint32>="aapl"
+    "desc"<blob>=<NULL>
+    "mdat"<timedate>=0x32303234303230323033303430355A00  "20240202030405Z\000"
+    "svce"<blob>="com.example.vpn"
+"#;
+
+    #[test]
+    fn test_parse_keychain_list() {
+        let output = "    \"/Users/alice/Library/Keychains/login.keychain-db\"\n    \"/Library/Keychains/System.keychain\"\n";
+        assert_eq!(
+            parse_keychain_list(output),
+            vec![
+                "/Users/alice/Library/Keychains/login.keychain-db",
+                "/Library/Keychains/System.keychain"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_keychain_items() {
+        let items = parse_keychain_items(FIND_OUTPUT);
+        assert_eq!(items.len(), 1);
+
+        let item = &items[0];
+        assert_eq!(
+            item.keychain,
+            "/Users/alice/Library/Keychains/login.keychain-db"
+        );
+        assert_eq!(item.class, "genp");
+        assert_eq!(item.label.as_deref(), Some("Example VPN"));
+        assert_eq!(item.account.as_deref(), Some("alice"));
+        assert_eq!(item.service.as_deref(), Some("com.example.vpn"));
+        assert_eq!(item.creator.as_deref(), Some("aapl"));
+        assert_eq!(item.description, None);
+        assert_eq!(item.created.as_deref(), Some("2024-01-02T03:04:05+00:00"));
+        assert_eq!(item.modified.as_deref(), Some("2024-02-02T03:04:05+00:00"));
+    }
+
+    #[test]
+    fn test_collect_copies_sources_and_writes_inventory() {
+        let temp = TempDir::new().unwrap();
+        let user_dir = temp.path().join("home/Library/Keychains");
+        fs::create_dir_all(user_dir.join("UUID")).unwrap();
+        fs::write(user_dir.join("login.keychain-db"), b"kych").unwrap();
+        fs::write(user_dir.join("UUID/keychain-2.db"), b"sqlite").unwrap();
+        let output_dir = temp.path().join("out");
+
+        let sources = [
+            (user_dir.clone(), "user"),
+            (temp.path().join("missing"), "system"),
+        ];
+        let queried = std::cell::RefCell::new(Vec::new());
+        let collected = collect_from(&sources, &output_dir, |args| {
+            queried.borrow_mut().push(args.join(" "));
+            match args[0] {
+                "list-keychains" if args[2] == "user" => Ok(Some(format!(
+                    "    \"{}\"\n",
+                    user_dir.join("login.keychain-db").display()
+                ))),
+                "list-keychains" => Err(anyhow!("not permitted")),
+                _ => Ok(Some(FIND_OUTPUT.to_string())),
+            }
+        })
+        .unwrap();
+
+        // One copied directory plus the inventory
+        assert_eq!(collected.len(), 2);
+        assert_eq!(collected[0].file_size, 10);
+        assert!(output_dir.join("user/login.keychain-db").exists());
+        assert!(output_dir.join("user/UUID/keychain-2.db").exists());
+
+        // The search list entry and the source file are the same keychain
+        let finds: Vec<_> = queried
+            .borrow()
+            .iter()
+            .filter(|q| q.starts_with("find-generic-password"))
+            .cloned()
+            .collect();
+        assert_eq!(finds.len(), 1);
+
+        let inventory: KeychainInventory = serde_json::from_str(
+            &fs::read_to_string(output_dir.join(KEYCHAIN_INVENTORY_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            inventory.collected_files,
+            vec!["user/UUID/keychain-2.db", "user/login.keychain-db"]
+        );
+        assert_eq!(inventory.search_list.len(), 1);
+        assert_eq!(inventory.generic_passwords.len(), 1);
+        assert_eq!(inventory.notes.len(), 2);
+        assert!(inventory.notes[0].contains("does not exist"));
+        assert!(inventory.notes[1].contains("not permitted"));
+    }
+}
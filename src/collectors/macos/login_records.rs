@@ -0,0 +1,147 @@
+//! macOS login records.
+//!
+//! macOS keeps `utmpx` and last-login records in the Apple System Log store
+//! under `/private/var/log/asl` rather than in `wtmp`. The `.asl` files are
+//! copied raw, and `last` (which reads them) is captured to `last.txt` for
+//! the authentication report. Login window activity (logins, logouts, screen
+//! unlocks) is only in the Unified Log, so the last
+//! [`unified_log_hours`](crate::collectors::macos::unified_log_parser::unified_log_hours)
+//! hours of `loginwindow` events are exported to `loginwindow.jsonl`.
+
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use log::{info, warn};
+
+use crate::collectors::footprint::{create_dir_all_tracked, run_command};
+use crate::collectors::macos::unified_log_parser::{convert_ndjson, unified_log_hours};
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+
+/// File name of the captured `last` output
+pub const LAST_OUTPUT_FILE: &str = "last.txt";
+
+/// File name of the exported `loginwindow` Unified Log events
+pub const LOGINWINDOW_FILE: &str = "loginwindow.jsonl";
+
+/// Unified Log predicate selecting login window events
+pub const LOGINWINDOW_PREDICATE: &str = "process == \"loginwindow\"";
+
+/// Copy the ASL store under `asl_dir` and capture `last` and the
+/// `loginwindow` log excerpt into `output_dir`
+pub fn collect_login_records(asl_dir: &Path, output_dir: &Path) -> Result<Vec<ArtifactMetadata>> {
+    info!("Collecting login records from {}", asl_dir.display());
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut collected = copy_asl_store(asl_dir, &output_dir.join("asl"));
+
+    match run_last() {
+        Ok(output) => {
+            let path = output_dir.join(LAST_OUTPUT_FILE);
+            fs::write(&path, output).context(format!("Failed to write {}", path.display()))?;
+            collected.push(command_metadata("last", &path));
+        }
+        Err(e) => warn!("Failed to capture last: {:#}", e),
+    }
+
+    let path = output_dir.join(LOGINWINDOW_FILE);
+    match export_loginwindow(&path) {
+        Ok(count) => {
+            info!("Exported {} loginwindow events", count);
+            collected.push(command_metadata("log show", &path));
+        }
+        Err(e) => warn!("Failed to export loginwindow events: {:#}", e),
+    }
+
+    Ok(collected)
+}
+
+/// Copy the `.asl` files of the ASL store; a missing store (removed in
+/// recent macOS releases on some systems) is not an error
+fn copy_asl_store(asl_dir: &Path, dest: &Path) -> Vec<ArtifactMetadata> {
+    let Ok(entries) = fs::read_dir(asl_dir) else {
+        warn!("ASL store {} is not readable", asl_dir.display());
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "asl"))
+        .collect();
+    files.sort();
+
+    let fallback = FallbackCollector::new();
+    files
+        .iter()
+        .filter_map(|file| {
+            let name = file.file_name()?;
+            fallback
+                .collect_standard_file(file, &dest.join(name))
+                .map_err(|e| warn!("Failed to copy {}: {:#}", file.display(), e))
+                .ok()
+        })
+        .collect()
+}
+
+fn run_last() -> Result<Vec<u8>> {
+    let output = run_command(&mut Command::new("last")).context("Failed to execute last")?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(anyhow!(
+            "last failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Export `loginwindow` events with `log show` to `path`; returns the
+/// number of events written
+fn export_loginwindow(path: &Path) -> Result<u64> {
+    let output = run_command(
+        Command::new("log")
+            .arg("show")
+            .arg("--last")
+            .arg(format!("{}h", unified_log_hours()))
+            .arg("--style")
+            .arg("ndjson")
+            .arg("--predicate")
+            .arg(LOGINWINDOW_PREDICATE),
+    )
+    .context("Failed to execute log command")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "log show failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut writer = BufWriter::new(
+        fs::File::create(path).context(format!("Failed to create {}", path.display()))?,
+    );
+    convert_ndjson(output.stdout.as_slice(), &mut writer)
+}
+
+fn command_metadata(command: &str, path: &Path) -> ArtifactMetadata {
+    let collection_time = Utc::now().to_rfc3339();
+    ArtifactMetadata {
+        original_path: command.to_string(),
+        collection_time: collection_time.clone(),
+        file_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        created_time: Some(collection_time),
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,23 @@
+//! macOS-specific collectors that need more than a single file copy.
+
+/// Wi-Fi connection history from the airport and known-networks plists
+pub mod airport_wifi;
+
+/// Application Firewall state through `socketfilterfw`
+pub mod app_firewall;
+
+/// Extended attributes of collected files, written as sidecars
+pub mod extended_attributes;
+
+/// Keychain database collection and inventory
+pub mod keychains;
+
+/// Login records from the ASL store, `last` and `loginwindow` events
+pub mod login_records;
+
+/// Unified Log export to JSON lines through `log show`
+pub mod unified_log_parser;
+
+/// The Unified Log parser under the name the collection request uses
+#[allow(unused_imports)]
+pub use unified_log_parser as asl_unified_log_parser;
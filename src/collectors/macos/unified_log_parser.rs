@@ -0,0 +1,238 @@
+//! macOS Unified Log conversion to JSON lines.
+//!
+//! The `.tracev3` files under `/private/var/db/diagnostics` only make sense
+//! together with the `uuidtext` format strings, so they are decoded with
+//! `log show` rather than parsed here. `log show --style ndjson` prints one
+//! JSON object per event; each is reduced to the fields needed for a
+//! timeline and written to a JSONL file as it is read, so a busy system's
+//! log never has to fit in memory.
+//!
+//! The raw trace files are still copied by the collector; the JSONL file is
+//! a convenience view of the last [`unified_log_hours`] hours.
+
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::collectors::footprint::run_command;
+use crate::security::credential_scrubber::scrub_volatile_text;
+
+/// File name of the converted log inside the unified log output directory
+pub const UNIFIED_LOG_FILE: &str = "unified_logs.jsonl";
+
+/// Default time window exported from the Unified Log, in hours
+pub const DEFAULT_UNIFIED_LOG_HOURS: u64 = 24;
+
+/// Live log store; `log show` reads it when no archive is given
+pub const LIVE_LOG_STORE: &str = "/private/var/db/diagnostics";
+
+static UNIFIED_LOG_HOURS: AtomicU64 = AtomicU64::new(DEFAULT_UNIFIED_LOG_HOURS);
+
+/// Set the time window exported from the Unified Log (`--unified-log-hours`)
+pub fn set_unified_log_hours(hours: u64) {
+    UNIFIED_LOG_HOURS.store(hours.max(1), Ordering::Relaxed);
+}
+
+/// Time window exported from the Unified Log, in hours
+pub fn unified_log_hours() -> u64 {
+    UNIFIED_LOG_HOURS.load(Ordering::Relaxed)
+}
+
+/// A Unified Log event
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnifiedLogEvent {
+    /// Local time as printed by `log show`, with UTC offset
+    pub timestamp: String,
+    pub subsystem: Option<String>,
+    pub category: Option<String>,
+    /// Message type (`Default`, `Info`, `Debug`, `Error`, `Fault`), or the
+    /// event type for activity and signpost events
+    pub level: Option<String>,
+    /// Executable name of the logging process
+    pub process: Option<String>,
+    pub message: Option<String>,
+}
+
+fn non_empty_str(event: &Value, key: &str) -> Option<String> {
+    event
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Reduce one `log show --style ndjson` line to an event; `None` for lines
+/// that are not events (such as the trailing status object)
+pub fn parse_event(line: &str) -> Option<UnifiedLogEvent> {
+    let event: Value = serde_json::from_str(line.trim()).ok()?;
+    let timestamp = non_empty_str(&event, "timestamp")?;
+    let process = non_empty_str(&event, "processImagePath")
+        .map(|path| path.rsplit('/').next().unwrap_or_default().to_string());
+    Some(UnifiedLogEvent {
+        timestamp,
+        subsystem: non_empty_str(&event, "subsystem"),
+        category: non_empty_str(&event, "category"),
+        level: non_empty_str(&event, "messageType").or_else(|| non_empty_str(&event, "eventType")),
+        process,
+        message: non_empty_str(&event, "eventMessage"),
+    })
+}
+
+/// Convert ndjson from `reader` to events written to `writer`, scrubbing
+/// secrets from messages; returns the number of events written
+pub fn convert_ndjson<R: BufRead, W: Write>(reader: R, writer: &mut W) -> Result<u64> {
+    let mut count = 0;
+    for line in reader.lines() {
+        let line = line.context("Failed to read log show output")?;
+        let Some(mut event) = parse_event(&line) else {
+            continue;
+        };
+        event.message = event.message.as_deref().map(scrub_volatile_text);
+        serde_json::to_writer(&mut *writer, &event)
+            .context("Failed to serialize unified log event")?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Export the last `duration_hours` hours of the Unified Log to JSONL.
+///
+/// `tracev3_dir` is the live store ([`LIVE_LOG_STORE`]) or a `.logarchive`
+/// directory. `log show` output is spooled to a scratch file next to
+/// `output_path` and converted line by line. Returns the number of events
+/// written.
+pub fn parse_unified_logs(
+    tracev3_dir: &Path,
+    output_path: &Path,
+    duration_hours: u64,
+) -> Result<u64> {
+    info!(
+        "Converting the last {}h of unified logs from {}",
+        duration_hours,
+        tracev3_dir.display()
+    );
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)
+            .context(format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let raw_path = output_path.with_extension("ndjson.tmp");
+    let raw =
+        File::create(&raw_path).context(format!("Failed to create {}", raw_path.display()))?;
+
+    let mut command = Command::new("log");
+    command
+        .arg("show")
+        .arg("--last")
+        .arg(format!("{}h", duration_hours))
+        .arg("--style")
+        .arg("ndjson");
+    if tracev3_dir != Path::new(LIVE_LOG_STORE) {
+        command.arg(tracev3_dir);
+    }
+    command.stdout(Stdio::from(raw));
+
+    let result = run_command(&mut command)
+        .context("Failed to execute log command")
+        .and_then(|output| {
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "log show failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        })
+        .and_then(|_| {
+            let reader = BufReader::new(
+                File::open(&raw_path).context(format!("Failed to open {}", raw_path.display()))?,
+            );
+            let mut writer = BufWriter::new(
+                File::create(output_path)
+                    .context(format!("Failed to create {}", output_path.display()))?,
+            );
+            convert_ndjson(reader, &mut writer)
+        });
+
+    if let Err(e) = fs::remove_file(&raw_path) {
+        warn!("Failed to remove {}: {}", raw_path.display(), e);
+    }
+
+    let count = result?;
+    info!(
+        "Wrote {} unified log events to {}",
+        count,
+        output_path.display()
+    );
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NDJSON: &str = r#"{"traceID":1,"eventMessage":"Accepted publickey for alice","eventType":"logEvent","subsystem":"com.openssh.sshd","category":"auth","timestamp":"2024-01-02 03:04:05.123456-0800","messageType":"Default","processImagePath":"\/usr\/sbin\/sshd","processID":412}
+{"traceID":2,"eventMessage":"","eventType":"activityCreateEvent","subsystem":"","category":"","timestamp":"2024-01-02 03:04:06.000000-0800","messageType":"","processImagePath":"\/usr\/libexec\/trustd"}
+not json
+{"count":2,"finished":1}
+"#;
+
+    #[test]
+    fn test_parse_event() {
+        let event = parse_event(NDJSON.lines().next().unwrap()).unwrap();
+        assert_eq!(event.timestamp, "2024-01-02 03:04:05.123456-0800");
+        assert_eq!(event.subsystem.as_deref(), Some("com.openssh.sshd"));
+        assert_eq!(event.category.as_deref(), Some("auth"));
+        assert_eq!(event.level.as_deref(), Some("Default"));
+        assert_eq!(event.process.as_deref(), Some("sshd"));
+        assert_eq!(
+            event.message.as_deref(),
+            Some("Accepted publickey for alice")
+        );
+
+        let activity = parse_event(NDJSON.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(activity.subsystem, None);
+        assert_eq!(activity.level.as_deref(), Some("activityCreateEvent"));
+        assert_eq!(activity.message, None);
+
+        assert_eq!(parse_event("not json"), None);
+        assert_eq!(parse_event(r#"{"count":2,"finished":1}"#), None);
+    }
+
+    #[test]
+    fn test_convert_ndjson() {
+        let mut output = Vec::new();
+        let count = convert_ndjson(NDJSON.as_bytes(), &mut output).unwrap();
+        assert_eq!(count, 2);
+
+        let lines: Vec<UnifiedLogEvent> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].process.as_deref(), Some("sshd"));
+        assert_eq!(lines[1].process.as_deref(), Some("trustd"));
+    }
+
+    #[test]
+    fn test_unified_log_hours() {
+        assert_eq!(DEFAULT_UNIFIED_LOG_HOURS, 24);
+        set_unified_log_hours(0);
+        assert_eq!(unified_log_hours(), 1);
+        set_unified_log_hours(DEFAULT_UNIFIED_LOG_HOURS);
+        assert_eq!(unified_log_hours(), DEFAULT_UNIFIED_LOG_HOURS);
+    }
+}
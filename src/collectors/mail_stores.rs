@@ -0,0 +1,737 @@
+//! Mail client stores of every user, inventoried and optionally copied.
+//!
+//! Business email compromise cases need the mail itself, but mailbox caches
+//! run to tens of gigabytes. For each user home below the artifact's source
+//! path (or the source path itself, if it is a home), this module locates:
+//!
+//! - Outlook data files (`*.ost`, `*.pst`, `*.nst`) in
+//!   `AppData\Local\Microsoft\Outlook` and `Documents\Outlook Files`, plus
+//!   every PST and OST named by the Outlook profiles in the user's
+//!   `NTUSER.DAT`, wherever they are
+//! - Outlook's `RoamCache` (autocomplete and recent recipients)
+//! - the new Outlook (`AppData\Local\Microsoft\Olk`) and Windows Mail
+//!   (`Comms\Unistore\data`, the Mail and Calendar app package) local stores
+//! - Apple Mail's `Envelope Index` database (with its `-wal` and `-shm`) and
+//!   the account mailboxes in `~/Library/Mail/V*/`
+//!
+//! `mail_stores_inventory.json` lists each store with its size and
+//! modification time. Mailbox stores are only listed unless the artifact
+//! sets `metadata_only: "false"`; the `RoamCache` and `Envelope Index`
+//! indexes are small and always copied. Nothing larger than
+//! `max_store_size_mb` (2048 by default) is copied. Copies go under
+//! `files/<user>/`, and each uncollected store records why, so the analyst
+//! can request a targeted follow-up collection.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::regf::{Hive, Key};
+use crate::collectors::platforms::common::{rfc3339, FallbackCollector};
+use crate::config::Artifact;
+use crate::errors::CollectorError;
+use crate::models::ArtifactMetadata;
+
+/// Inventory written to the artifact destination
+pub const MAIL_STORES_INVENTORY_FILE: &str = "mail_stores_inventory.json";
+
+/// Directory under the destination receiving copies
+pub const COLLECTED_DIR: &str = "files";
+
+/// Size above which nothing is copied, unless `max_store_size_mb` is set
+pub const DEFAULT_MAX_STORE_SIZE_MB: u64 = 2048;
+
+/// Outlook profile roots in `NTUSER.DAT`: Office 2013 and later, then
+/// Outlook 2010 and earlier
+const OUTLOOK_PROFILE_KEYS: [&str; 2] = [
+    r"Software\Microsoft\Office",
+    r"Software\Microsoft\Windows NT\CurrentVersion\Windows Messaging Subsystem\Profiles",
+];
+
+/// Profile values holding a PST or OST path: Unicode, then ANSI properties
+const STORE_PATH_VALUES: [&str; 4] = ["001f6700", "001f6610", "001e6700", "001e6610"];
+
+/// Kind of a located mail store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailStoreKind {
+    OutlookOst,
+    OutlookPst,
+    /// Outlook group and shared folder store
+    OutlookNst,
+    OutlookRoamCache,
+    /// New Outlook for Windows (`Olk`) local store
+    NewOutlook,
+    /// Windows Mail and Calendar app store
+    WindowsMail,
+    AppleMailEnvelopeIndex,
+    /// An Apple Mail account directory under `~/Library/Mail/V*/`
+    AppleMailMailbox,
+}
+
+impl MailStoreKind {
+    /// Whether the kind holds message contents, copied only when
+    /// `metadata_only` is `false`
+    pub fn is_mailbox(self) -> bool {
+        !matches!(
+            self,
+            MailStoreKind::OutlookRoamCache | MailStoreKind::AppleMailEnvelopeIndex
+        )
+    }
+
+    /// Outlook data file kind from a file name's extension
+    fn from_outlook_file(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "ost" => Some(MailStoreKind::OutlookOst),
+            "pst" => Some(MailStoreKind::OutlookPst),
+            "nst" => Some(MailStoreKind::OutlookNst),
+            _ => None,
+        }
+    }
+}
+
+/// How much of the stores is copied, from the artifact's `metadata`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailStoreOptions {
+    /// Only list mailbox stores (`metadata_only`, `true` unless set to
+    /// `false`)
+    pub metadata_only: bool,
+    /// Stores larger than this are never copied (`max_store_size_mb`)
+    pub max_store_size_mb: u64,
+}
+
+impl Default for MailStoreOptions {
+    fn default() -> Self {
+        Self {
+            metadata_only: true,
+            max_store_size_mb: DEFAULT_MAX_STORE_SIZE_MB,
+        }
+    }
+}
+
+impl MailStoreOptions {
+    /// Options from `artifact.metadata`; invalid values are a config error
+    pub fn from_artifact(artifact: &Artifact) -> Result<Self> {
+        let invalid = |key: &str, value: &str, reason: &str| -> anyhow::Error {
+            CollectorError::ConfigError {
+                message: format!(
+                    "Invalid {} '{}' for artifact {}: {}",
+                    key, value, artifact.name, reason
+                ),
+            }
+            .into()
+        };
+
+        let mut options = Self::default();
+        if let Some(value) = artifact.metadata.get("metadata_only").map(|v| v.trim()) {
+            options.metadata_only = if value.eq_ignore_ascii_case("true") {
+                true
+            } else if value.eq_ignore_ascii_case("false") {
+                false
+            } else {
+                return Err(invalid("metadata_only", value, "expected true or false"));
+            };
+        }
+        if let Some(value) = artifact.metadata.get("max_store_size_mb").map(|v| v.trim()) {
+            options.max_store_size_mb = value
+                .parse()
+                .map_err(|_| invalid("max_store_size_mb", value, "expected a number of MB"))?;
+        }
+        Ok(options)
+    }
+}
+
+/// One located mail store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MailStore {
+    pub kind: MailStoreKind,
+    pub path: String,
+    /// User profile folder name
+    pub user: String,
+    /// `default_location`, or `outlook_profile:<name>` for paths read from
+    /// `NTUSER.DAT`
+    pub found_in: String,
+    pub exists: bool,
+    /// Bytes, summed over the files of a directory store
+    pub size: u64,
+    /// Files in a directory store; 1 for a file
+    pub file_count: u64,
+    /// Latest modification time of the store's files
+    pub modified_time: Option<String>,
+    pub collected: bool,
+    /// Copy under `files/`
+    pub collected_copy: Option<String>,
+    /// Why an existing store was not copied
+    pub not_collected_reason: Option<String>,
+}
+
+/// Contents of `mail_stores_inventory.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MailStoresInventory {
+    pub collection_time: String,
+    pub source: String,
+    pub metadata_only: bool,
+    pub max_store_size_mb: u64,
+    pub stores: Vec<MailStore>,
+    /// Hives that could not be read for Outlook profiles
+    pub notes: Vec<String>,
+}
+
+/// Directories below a home that mark it as one
+const HOME_MARKERS: [&str; 2] = ["AppData", "Library"];
+
+/// `root` if it is a user home, else its subdirectories
+fn user_homes(root: &Path) -> Vec<PathBuf> {
+    if HOME_MARKERS.iter().any(|dir| root.join(dir).is_dir()) {
+        return vec![root.to_path_buf()];
+    }
+    let mut homes: Vec<PathBuf> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    homes.sort();
+    homes
+}
+
+/// Entries directly in `dir`, sorted
+fn entries_in(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+/// Stores in the default locations below `home`
+fn locate_in_home(home: &Path) -> Vec<(PathBuf, MailStoreKind)> {
+    let mut found = Vec::new();
+
+    let outlook = home.join("AppData/Local/Microsoft/Outlook");
+    for dir in [outlook.clone(), home.join("Documents/Outlook Files")] {
+        for path in entries_in(&dir).into_iter().filter(|p| p.is_file()) {
+            if let Some(kind) = MailStoreKind::from_outlook_file(&path) {
+                found.push((path, kind));
+            }
+        }
+    }
+    for path in entries_in(&outlook.join("RoamCache"))
+        .into_iter()
+        .filter(|p| p.is_file())
+    {
+        found.push((path, MailStoreKind::OutlookRoamCache));
+    }
+
+    let local = home.join("AppData/Local");
+    for (relative, kind) in [
+        ("Microsoft/Olk", MailStoreKind::NewOutlook),
+        ("Comms/Unistore/data", MailStoreKind::WindowsMail),
+        (
+            "Packages/microsoft.windowscommunicationsapps_8wekyb3d8bbwe/LocalState",
+            MailStoreKind::WindowsMail,
+        ),
+    ] {
+        let path = local.join(relative);
+        if path.is_dir() {
+            found.push((path, kind));
+        }
+    }
+
+    // Apple Mail keeps one V<n> directory per storage format version
+    for version in entries_in(&home.join("Library/Mail")) {
+        let is_version = version
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .and_then(|n| {
+                n.strip_prefix('V')
+                    .map(|digits| digits.parse::<u32>().is_ok())
+            })
+            .unwrap_or(false);
+        if !is_version || !version.is_dir() {
+            continue;
+        }
+        for entry in entries_in(&version) {
+            if entry.file_name().is_some_and(|n| n == "MailData") {
+                for index in entries_in(&entry) {
+                    let is_index = index
+                        .file_name()
+                        .is_some_and(|n| n.to_string_lossy().starts_with("Envelope Index"));
+                    if is_index && index.is_file() {
+                        found.push((index, MailStoreKind::AppleMailEnvelopeIndex));
+                    }
+                }
+            } else if entry.is_dir() {
+                found.push((entry, MailStoreKind::AppleMailMailbox));
+            }
+        }
+    }
+    found
+}
+
+/// UTF-16LE (Unicode properties) or ANSI path data of a profile value
+fn profile_path(name: &str, data: &[u8]) -> Option<String> {
+    let text = if name.starts_with("001f") {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        data.iter().map(|&b| b as char).collect()
+    };
+    let text = text.trim_end_matches('\0').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// PST and OST paths in the values of a profile's subkeys
+fn profile_stores(profile: &Key, found: &mut Vec<(String, String)>) -> Result<()> {
+    let name = profile.name();
+    for service in profile.subkeys()? {
+        for value in service.values()? {
+            if !STORE_PATH_VALUES.contains(&value.name.to_lowercase().as_str()) {
+                continue;
+            }
+            if let Some(path) = profile_path(&value.name.to_lowercase(), &value.data) {
+                found.push((path, name.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// PST and OST paths named by the Outlook profiles of a user hive, with the
+/// profile each is configured in
+pub fn outlook_profile_stores(hive: &Hive) -> Result<Vec<(String, String)>> {
+    let mut found = Vec::new();
+
+    if let Some(office) = hive.open_key(OUTLOOK_PROFILE_KEYS[0])? {
+        for version in office.subkeys()? {
+            let Some(profiles) = version
+                .subkey("Outlook")?
+                .map(|outlook| outlook.subkey("Profiles"))
+                .transpose()?
+                .flatten()
+            else {
+                continue;
+            };
+            for profile in profiles.subkeys()? {
+                profile_stores(&profile, &mut found)?;
+            }
+        }
+    }
+    if let Some(profiles) = hive.open_key(OUTLOOK_PROFILE_KEYS[1])? {
+        for profile in profiles.subkeys()? {
+            profile_stores(&profile, &mut found)?;
+        }
+    }
+
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
+
+/// The user's `NTUSER.DAT`, read through a raw handle on Windows while the
+/// user is logged on and the hive is locked
+fn open_user_hive(home: &Path) -> Result<Option<Hive>> {
+    let path = home.join("NTUSER.DAT");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    match Hive::open(&path) {
+        Ok(hive) => Ok(Some(hive)),
+        Err(e) if cfg!(target_os = "windows") => {
+            debug!("{} not readable directly: {:#}", path.display(), e);
+            let copy = std::env::temp_dir().join(format!(
+                "rs_collector_ntuser_{}_{}.dat",
+                std::process::id(),
+                file_name(home)
+            ));
+            let result = crate::windows::collect_with_raw_handle(&path.to_string_lossy(), &copy)
+                .and_then(|_| fs::read(&copy).context("Failed to read hive copy"))
+                .and_then(Hive::from_bytes);
+            let _ = fs::remove_file(&copy);
+            result.map(Some)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Size, file count and latest modification time of a file or directory
+fn measure(path: &Path) -> (u64, u64, Option<SystemTime>) {
+    if path.is_file() {
+        let metadata = fs::metadata(path).ok();
+        return (
+            metadata.as_ref().map_or(0, |m| m.len()),
+            1,
+            metadata.and_then(|m| m.modified().ok()),
+        );
+    }
+    let mut size = 0;
+    let mut count = 0;
+    let mut latest: Option<SystemTime> = None;
+    for entry in walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+    {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        size += metadata.len();
+        count += 1;
+        if let Ok(modified) = metadata.modified() {
+            latest = Some(latest.map_or(modified, |l| l.max(modified)));
+        }
+    }
+    (size, count, latest)
+}
+
+fn store_entry(path: &Path, kind: MailStoreKind, user: &str, found_in: &str) -> MailStore {
+    let exists = path.exists();
+    let (size, file_count, modified) = if exists { measure(path) } else { (0, 0, None) };
+    MailStore {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        user: user.to_string(),
+        found_in: found_in.to_string(),
+        exists,
+        size,
+        file_count,
+        modified_time: rfc3339(modified),
+        collected: false,
+        collected_copy: None,
+        not_collected_reason: None,
+    }
+}
+
+/// Locate the mail stores of the homes below `source_root`
+pub fn build_inventory(source_root: &Path, options: &MailStoreOptions) -> MailStoresInventory {
+    let mut inventory = MailStoresInventory {
+        collection_time: chrono::Utc::now().to_rfc3339(),
+        source: source_root.to_string_lossy().to_string(),
+        metadata_only: options.metadata_only,
+        max_store_size_mb: options.max_store_size_mb,
+        ..Default::default()
+    };
+    let mut seen = BTreeSet::new();
+
+    for home in user_homes(source_root) {
+        let user = file_name(&home);
+        for (path, kind) in locate_in_home(&home) {
+            seen.insert(path.to_string_lossy().to_lowercase());
+            inventory
+                .stores
+                .push(store_entry(&path, kind, &user, "default_location"));
+        }
+
+        let hive = match open_user_hive(&home) {
+            Ok(Some(hive)) => hive,
+            Ok(None) => continue,
+            Err(e) => {
+                inventory
+                    .notes
+                    .push(format!("NTUSER.DAT of {} not read: {:#}", user, e));
+                continue;
+            }
+        };
+        match outlook_profile_stores(&hive) {
+            Ok(stores) => {
+                for (path, profile) in stores {
+                    let path = PathBuf::from(path);
+                    let Some(kind) = MailStoreKind::from_outlook_file(&path) else {
+                        continue;
+                    };
+                    if seen.insert(path.to_string_lossy().to_lowercase()) {
+                        inventory.stores.push(store_entry(
+                            &path,
+                            kind,
+                            &user,
+                            &format!("outlook_profile:{}", profile),
+                        ));
+                    }
+                }
+            }
+            Err(e) => inventory
+                .notes
+                .push(format!("Outlook profiles of {} not read: {:#}", user, e)),
+        }
+    }
+    inventory
+}
+
+/// Copy `store` under `dest/files/<user>/`, keeping its path below the home
+fn copy_store(store: &mut MailStore, source_root: &Path, dest: &Path) -> Result<u64> {
+    let source = Path::new(&store.path);
+    let home = if source_root.ends_with(&store.user) {
+        source_root.to_path_buf()
+    } else {
+        source_root.join(&store.user)
+    };
+    let relative = match source.strip_prefix(&home) {
+        Ok(relative) => PathBuf::from(&store.user).join(relative),
+        // Profile paths on other drives or shares
+        Err(_) => PathBuf::from(&store.user)
+            .join("other")
+            .join(source.file_name().unwrap_or_default()),
+    };
+    let target = dest.join(COLLECTED_DIR).join(&relative);
+
+    let copied = match FallbackCollector::new().collect_path(source, &target) {
+        Ok(metadata) => metadata.file_size,
+        // Outlook keeps its open data files locked
+        Err(_) if cfg!(target_os = "windows") && source.is_file() => {
+            crate::windows::collect_with_raw_handle(&store.path, &target)
+                .context(format!("Failed to copy {}", store.path))?
+                .file_size
+        }
+        Err(e) => return Err(e.context(format!("Failed to copy {}", store.path))),
+    };
+    store.collected = true;
+    store.collected_copy = Some(
+        Path::new(COLLECTED_DIR)
+            .join(relative)
+            .to_string_lossy()
+            .replace('\\', "/"),
+    );
+    Ok(copied)
+}
+
+/// Write `mail_stores_inventory.json` for the homes below `source_root`
+/// into the directory `dest`, copying the stores `options` allow
+pub fn collect_mail_stores(
+    source_root: &Path,
+    dest: &Path,
+    options: &MailStoreOptions,
+) -> Result<ArtifactMetadata> {
+    info!("Inventorying mail stores under {}", source_root.display());
+    fs::create_dir_all(dest).context(format!("Failed to create {}", dest.display()))?;
+
+    let mut inventory = build_inventory(source_root, options);
+    let max_size = options.max_store_size_mb * 1024 * 1024;
+    let mut total_size = 0;
+    for store in inventory.stores.iter_mut().filter(|s| s.exists) {
+        if store.kind.is_mailbox() && options.metadata_only {
+            store.not_collected_reason = Some("metadata_only".to_string());
+        } else if store.size > max_size {
+            store.not_collected_reason = Some(format!(
+                "larger than max_store_size_mb ({})",
+                options.max_store_size_mb
+            ));
+        } else {
+            match copy_store(store, source_root, dest) {
+                Ok(copied) => total_size += copied,
+                Err(e) => {
+                    warn!("Mail store not copied: {:#}", e);
+                    store.not_collected_reason = Some(format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    let listed = inventory.stores.iter().filter(|s| !s.collected).count();
+    info!(
+        "Found {} mail stores under {}, {} listed without copying",
+        inventory.stores.len(),
+        source_root.display(),
+        listed
+    );
+
+    let path = dest.join(MAIL_STORES_INVENTORY_FILE);
+    let json = serde_json::to_string_pretty(&inventory)
+        .context("Failed to serialize mail store inventory")?;
+    fs::write(&path, &json).context(format!("Failed to write {}", path.display()))?;
+    total_size += json.len() as u64;
+
+    Ok(ArtifactMetadata {
+        original_path: source_root.to_string_lossy().to_string(),
+        collection_time: chrono::Utc::now().to_rfc3339(),
+        file_size: total_size,
+        created_time: None,
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        listed_files: Some(listed as u64),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{HiveBuilder, HiveKey};
+    use crate::config::ArtifactType;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain([0])
+            .flat_map(|u| u.to_le_bytes())
+            .collect()
+    }
+
+    fn store<'a>(inventory: &'a MailStoresInventory, suffix: &str) -> &'a MailStore {
+        inventory
+            .stores
+            .iter()
+            .find(|s| s.path.replace('\\', "/").ends_with(suffix))
+            .unwrap_or_else(|| panic!("{} not inventoried", suffix))
+    }
+
+    #[test]
+    fn test_outlook_profile_stores() {
+        let mut root = HiveKey::new("ROOT");
+        let profile = root
+            .subkey("Software")
+            .subkey("Microsoft")
+            .subkey("Office")
+            .subkey("16.0")
+            .subkey("Outlook")
+            .subkey("Profiles")
+            .subkey("Outlook");
+        profile
+            .subkey("0a0d020000000000c000000000000046")
+            .binary_value(
+                "001f6610",
+                &utf16(r"C:\Users\bob\AppData\Local\Microsoft\Outlook\bob@example.com.ost"),
+            );
+        profile
+            .subkey("9207f3e0a3b11019908b08002b2a56c2")
+            .binary_value("001f6700", &utf16(r"D:\Archive\2019.pst"))
+            .dword_value("00036601", 1);
+        let hive = Hive::from_bytes(HiveBuilder::build(&root)).unwrap();
+
+        let stores = outlook_profile_stores(&hive).unwrap();
+        assert_eq!(
+            stores,
+            vec![
+                (
+                    r"C:\Users\bob\AppData\Local\Microsoft\Outlook\bob@example.com.ost".to_string(),
+                    "Outlook".to_string()
+                ),
+                (r"D:\Archive\2019.pst".to_string(), "Outlook".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mailboxes_are_listed_and_indexes_copied() {
+        let temp = TempDir::new().unwrap();
+        let users = temp.path().join("Users");
+        let bob = users.join("bob");
+        write(
+            &bob.join("AppData/Local/Microsoft/Outlook/bob@example.com.ost"),
+            &[0u8; 4096],
+        );
+        write(
+            &bob.join("AppData/Local/Microsoft/Outlook/RoamCache/Stream_Autocomplete_0_1.dat"),
+            b"autocomplete",
+        );
+        write(&bob.join("Documents/Outlook Files/old.pst"), b"pst");
+        let alice = users.join("alice");
+        write(
+            &alice.join("Library/Mail/V10/MailData/Envelope Index"),
+            b"SQLite format 3\0",
+        );
+        write(
+            &alice.join("Library/Mail/V10/1A2B3C/INBOX.mbox/Messages/1.emlx"),
+            b"From: ceo@example.com",
+        );
+
+        let dest = temp.path().join("out");
+        let options = MailStoreOptions::default();
+        let metadata = collect_mail_stores(&users, &dest, &options).unwrap();
+        let inventory: MailStoresInventory = serde_json::from_str(
+            &fs::read_to_string(dest.join(MAIL_STORES_INVENTORY_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(inventory.stores.len(), 5);
+        assert_eq!(metadata.listed_files, Some(3));
+
+        let ost = store(&inventory, "bob@example.com.ost");
+        assert_eq!(ost.kind, MailStoreKind::OutlookOst);
+        assert_eq!(ost.size, 4096);
+        assert!(!ost.collected);
+        assert_eq!(ost.not_collected_reason.as_deref(), Some("metadata_only"));
+
+        let roamcache = store(&inventory, "Stream_Autocomplete_0_1.dat");
+        assert!(roamcache.collected);
+        let copy = dest.join(roamcache.collected_copy.as_ref().unwrap());
+        assert_eq!(fs::read(copy).unwrap(), b"autocomplete");
+
+        assert!(store(&inventory, "MailData/Envelope Index").collected);
+        let mailbox = store(&inventory, "V10/1A2B3C");
+        assert_eq!(mailbox.kind, MailStoreKind::AppleMailMailbox);
+        assert_eq!(mailbox.file_count, 1);
+        assert!(!mailbox.collected);
+        assert_eq!(store(&inventory, "old.pst").user, "bob");
+    }
+
+    #[test]
+    fn test_full_collection_is_opt_in_and_capped() {
+        let artifact = Artifact {
+            name: "mail".into(),
+            artifact_type: ArtifactType::MailStores,
+            source_path: "/Users".into(),
+            destination_name: "MailStores".into(),
+            description: None,
+            required: false,
+            metadata: HashMap::from([
+                ("metadata_only".to_string(), "false".to_string()),
+                ("max_store_size_mb".to_string(), "1".to_string()),
+            ]),
+            regex: None,
+        };
+        let options = MailStoreOptions::from_artifact(&artifact).unwrap();
+        assert!(!options.metadata_only);
+        assert_eq!(options.max_store_size_mb, 1);
+
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("carol");
+        let outlook = home.join("AppData/Local/Microsoft/Outlook");
+        write(&outlook.join("small.pst"), b"pst");
+        write(&outlook.join("huge.ost"), &vec![0u8; 2 * 1024 * 1024]);
+        let dest = temp.path().join("out");
+        collect_mail_stores(&home, &dest, &options).unwrap();
+        let inventory: MailStoresInventory = serde_json::from_str(
+            &fs::read_to_string(dest.join(MAIL_STORES_INVENTORY_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert!(store(&inventory, "small.pst").collected);
+        let huge = store(&inventory, "huge.ost");
+        assert!(!huge.collected);
+        assert!(huge
+            .not_collected_reason
+            .as_deref()
+            .unwrap()
+            .contains("max_store_size_mb"));
+
+        let mut invalid = artifact.clone();
+        invalid
+            .metadata
+            .insert("metadata_only".to_string(), "sometimes".to_string());
+        assert!(MailStoreOptions::from_artifact(&invalid).is_err());
+    }
+}
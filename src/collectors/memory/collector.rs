@@ -14,6 +14,9 @@ use crate::collectors::memory::models::{
     MemoryCollectionOptions, MemoryCollectionSummary, ProcessMemoryInfo,
 };
 use crate::collectors::memory::platforms::{self, MemoryCollectorImpl};
+#[cfg(target_os = "linux")]
+use crate::collectors::memory::process_context::read_process_context;
+use crate::collectors::memory::region_dump::read_range;
 use crate::collectors::volatile::models::ProcessInfo;
 use crate::constants::{DEFAULT_MAX_PROCESS_MEMORY, DEFAULT_MAX_TOTAL_MEMORY};
 
@@ -172,6 +175,7 @@ impl MemoryCollector {
                         collection_time: Utc::now().to_rfc3339(),
                         status: "Failed".to_string(),
                         error: Some(e.to_string()),
+                        read_method: None,
                     };
 
                     process_infos.push(failed_process);
@@ -261,6 +265,7 @@ impl MemoryCollector {
                 collection_time: Utc::now().to_rfc3339(),
                 status: "Skipped".to_string(),
                 error: Some(format!("Process memory size exceeds maximum")),
+                read_method: None,
             });
         }
 
@@ -280,6 +285,7 @@ impl MemoryCollector {
             collection_time: Utc::now().to_rfc3339(),
             status: "Success".to_string(),
             error: None,
+            read_method: None,
         };
 
         // Export process info to create the directory
@@ -287,13 +293,23 @@ impl MemoryCollector {
             .export_process_info(&process_info)
             .context(format!("Failed to export process info for process {}", pid))?;
 
+        // Thread and fd context is cheap next to the dump, so always take it
+        #[cfg(target_os = "linux")]
+        {
+            let context = read_process_context(Path::new("/proc"), pid);
+            if let Err(e) = exporter.export_process_context(&process_dir, &context) {
+                warn!("Failed to export context for process {}: {}", pid, e);
+            }
+        }
+
         // Create memory map
         exporter
             .create_memory_map(&process_dir, &process_info.regions)
             .context(format!("Failed to create memory map for process {}", pid))?;
 
-        // Dump memory regions
+        // Dump memory regions; unreadable ranges are zero-filled and recorded
         let mut dumped_memory_size = 0u64;
+        let mut read_error = None;
 
         for region in &mut process_info.regions {
             // Skip regions that are too small
@@ -301,47 +317,44 @@ impl MemoryCollector {
                 continue;
             }
 
-            // Read memory
-            match self
-                .platform_impl
-                .read_memory(pid, region.base_address, region.size as usize)
-            {
-                Ok(data) => {
-                    if data.is_empty() {
-                        debug!(
-                            "Skipping empty memory region at {:x} for process {}",
-                            region.base_address, pid
-                        );
-                        continue;
-                    }
-
-                    // Export memory region
-                    match exporter.export_memory_region(&process_dir, region, &data) {
-                        Ok(dump_path) => {
-                            // Update region info
-                            region.dumped = true;
-                            region.dump_path = Some(
-                                dump_path
-                                    .strip_prefix(&process_dir)
-                                    .unwrap_or(&dump_path)
-                                    .to_string_lossy()
-                                    .to_string(),
-                            );
-
-                            // Update dumped memory size
-                            dumped_memory_size += data.len() as u64;
-                        }
-                        Err(e) => {
-                            warn!(
-                                "Failed to export memory region at {:x} for process {}: {}",
-                                region.base_address, pid, e
-                            );
-                        }
-                    }
+            let (data, gaps) = read_range(
+                self.platform_impl.as_ref(),
+                pid,
+                region.base_address,
+                region.size as usize,
+            );
+            let unreadable: u64 = gaps.iter().map(|g| g.size).sum();
+            if let Some(gap) = gaps.first() {
+                debug!(
+                    "{} of {} bytes at {:x} for process {} unreadable: {}",
+                    unreadable, region.size, region.base_address, pid, gap.reason
+                );
+                read_error.get_or_insert_with(|| gap.reason.clone());
+            }
+            region.gaps = gaps;
+            if unreadable >= region.size {
+                continue;
+            }
+
+            // Export memory region
+            match exporter.export_memory_region(&process_dir, region, &data) {
+                Ok(dump_path) => {
+                    // Update region info
+                    region.dumped = true;
+                    region.dump_path = Some(
+                        dump_path
+                            .strip_prefix(&process_dir)
+                            .unwrap_or(&dump_path)
+                            .to_string_lossy()
+                            .to_string(),
+                    );
+
+                    // Update dumped memory size
+                    dumped_memory_size += region.size - unreadable;
                 }
                 Err(e) => {
-                    debug!(
-                        "Failed to read memory at {:x} for process {}: {}",
+                    warn!(
+                        "Failed to export memory region at {:x} for process {}: {}",
                         region.base_address, pid, e
                     );
                 }
@@ -350,6 +363,14 @@ impl MemoryCollector {
 
         // Update process info
         process_info.dumped_memory_size = dumped_memory_size;
+        process_info.read_method = self.platform_impl.read_method(pid);
+        if let Some(error) = read_error {
+            if dumped_memory_size == 0 {
+                warn!("No memory could be read from process {}: {}", pid, error);
+                process_info.status = "Failed".to_string();
+            }
+            process_info.error = Some(error);
+        }
 
         // Re-export process info with updated region info
         exporter.export_process_info(&process_info)?;
@@ -573,6 +594,7 @@ mod tests {
             mapped_file: None,
             dumped: false,
             dump_path: None,
+            gaps: Vec::new(),
         };
 
         let region2 = MemoryRegionInfo {
@@ -588,6 +610,7 @@ mod tests {
             mapped_file: None,
             dumped: false,
             dump_path: None,
+            gaps: Vec::new(),
         };
 
         let region3 = MemoryRegionInfo {
@@ -603,6 +626,7 @@ mod tests {
             mapped_file: None,
             dumped: false,
             dump_path: None,
+            gaps: Vec::new(),
         };
 
         assert!(filter.matches(&region1));
@@ -679,6 +703,7 @@ mod tests {
             collection_time: Utc::now().to_rfc3339(),
             status: "Success".to_string(),
             error: None,
+            read_method: None,
         };
 
         let processes = vec![process_info];
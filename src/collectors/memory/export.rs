@@ -11,8 +11,9 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use crate::collectors::memory::models::{
-    MemoryCollectionSummary, MemoryRegionInfo, ProcessMemoryInfo, ProcessSummary,
+    MemoryCollectionSummary, MemoryRegionInfo, ProcessContext, ProcessMemoryInfo, ProcessSummary,
 };
+use crate::collectors::memory::process_context::PROCESS_CONTEXT_FILE;
 
 /// Memory export handler
 pub struct MemoryExporter {
@@ -28,12 +29,14 @@ impl MemoryExporter {
         }
     }
 
+    /// Directory of a process: `[base_dir]/[process_name]_[pid]`
+    pub fn process_dir(&self, name: &str, pid: u32) -> PathBuf {
+        self.base_dir.join(format!("{}_{}", name, pid))
+    }
+
     /// Export process memory information to a directory
     pub fn export_process_info(&self, process_info: &ProcessMemoryInfo) -> Result<PathBuf> {
-        // Create process directory path: [base_dir]/[process_name]_[pid]
-        let process_dir = self
-            .base_dir
-            .join(format!("{}_{}", process_info.name, process_info.pid));
+        let process_dir = self.process_dir(&process_info.name, process_info.pid);
 
         // Create the directory if it doesn't exist
         fs::create_dir_all(&process_dir).context(format!(
@@ -57,6 +60,31 @@ impl MemoryExporter {
         Ok(process_dir)
     }
 
+    /// Write a process's thread and file-descriptor context as `context.json`
+    pub fn export_process_context(
+        &self,
+        process_dir: impl AsRef<Path>,
+        context: &ProcessContext,
+    ) -> Result<PathBuf> {
+        let process_dir = process_dir.as_ref();
+        fs::create_dir_all(process_dir).context(format!(
+            "Failed to create process directory: {}",
+            process_dir.display()
+        ))?;
+
+        let context_path = process_dir.join(PROCESS_CONTEXT_FILE);
+        let context_json = serde_json::to_string_pretty(context)
+            .context("Failed to serialize process context to JSON")?;
+        fs::write(&context_path, context_json).context(format!(
+            "Failed to write process context to file: {}",
+            context_path.display()
+        ))?;
+
+        debug!("Exported process context to {}", context_path.display());
+
+        Ok(context_path)
+    }
+
     /// Export memory region data to a file
     pub fn export_memory_region(
         &self,
@@ -193,6 +221,8 @@ impl MemoryExporter {
             total_memory_size: process_info.total_memory_size,
             dumped_memory_size: process_info.dumped_memory_size,
             status: process_info.status.clone(),
+            error: process_info.error.clone(),
+            read_method: process_info.read_method.clone(),
         }
     }
 
@@ -233,6 +233,7 @@ mod tests {
             mapped_file: None,
             dumped: false,
             dump_path: None,
+            gaps: Vec::new(),
         };
 
         // Test with matching type and size
@@ -25,6 +25,8 @@ use crate::collectors::memory::platforms::MemoryCollectorImpl;
 use crate::collectors::volatile::models::ProcessInfo;
 #[cfg(feature = "memory_collection")]
 use crate::constants::MEMORY_CHUNK_SIZE;
+#[cfg(feature = "memory_collection")]
+use crate::errors::CollectorError;
 
 /// MemProcFS-based memory collector implementation
 pub struct MemProcFSCollector {
@@ -59,9 +61,12 @@ impl MemoryCollectorImpl for MemProcFSCollector {
                 .vmm
                 .lock()
                 .map_err(|e| anyhow!("Failed to acquire VMM lock: {}", e))?;
-            let proc = vmm
-                .process_from_pid(pid)
-                .map_err(|e| anyhow!("Failed to get process {}: {}", pid, e))?;
+            let proc =
+                vmm.process_from_pid(pid)
+                    .map_err(|e| CollectorError::MemoryCollectionFailed {
+                        pid,
+                        cause: format!("Failed to get process: {}", e),
+                    })?;
 
             // Get memory regions using VAD map
             let vad_map = proc.map_vad(true).context("Failed to retrieve VAD map")?;
@@ -91,6 +96,7 @@ impl MemoryCollectorImpl for MemProcFSCollector {
                     },
                     dumped: false,
                     dump_path: None,
+                    gaps: Vec::new(),
                 };
 
                 regions.push(region);
@@ -116,9 +122,12 @@ impl MemoryCollectorImpl for MemProcFSCollector {
                 .vmm
                 .lock()
                 .map_err(|e| anyhow!("Failed to acquire VMM lock: {}", e))?;
-            let proc = vmm
-                .process_from_pid(pid)
-                .map_err(|e| anyhow!("Failed to get process {}: {}", pid, e))?;
+            let proc =
+                vmm.process_from_pid(pid)
+                    .map_err(|e| CollectorError::MemoryCollectionFailed {
+                        pid,
+                        cause: format!("Failed to get process: {}", e),
+                    })?;
 
             // Handle large memory regions with chunking
             if size > 1024 * 1024 * 10 {
@@ -216,9 +225,12 @@ impl MemoryCollectorImpl for MemProcFSCollector {
                 .vmm
                 .lock()
                 .map_err(|e| anyhow!("Failed to acquire VMM lock: {}", e))?;
-            let proc = vmm
-                .process_from_pid(pid)
-                .map_err(|e| anyhow!("Failed to get process {}: {}", pid, e))?;
+            let proc =
+                vmm.process_from_pid(pid)
+                    .map_err(|e| CollectorError::MemoryCollectionFailed {
+                        pid,
+                        cause: format!("Failed to get process: {}", e),
+                    })?;
 
             // Get modules using module map
             let module_map = proc
@@ -266,9 +278,12 @@ impl MemoryCollectorImpl for MemProcFSCollector {
                 .vmm
                 .lock()
                 .map_err(|e| anyhow!("Failed to acquire VMM lock: {}", e))?;
-            let proc = vmm
-                .process_from_pid(pid)
-                .map_err(|e| anyhow!("Failed to get process {}: {}", pid, e))?;
+            let proc =
+                vmm.process_from_pid(pid)
+                    .map_err(|e| CollectorError::MemoryCollectionFailed {
+                        pid,
+                        cause: format!("Failed to get process: {}", e),
+                    })?;
             let end_addr = end_addr.unwrap_or(u64::MAX);
 
             // Create a memory search object
@@ -0,0 +1,774 @@
+//! Process memory as a Windows Minidump (`.dmp`).
+//!
+//! On Windows the dump is written by `MiniDumpWriteDump` from dbghelp. Other
+//! platforms get a synthetic minidump assembled from the platform memory
+//! collector: system information, the thread list from `/proc/<pid>/task`,
+//! the loaded modules and the memory ranges the dump type asks for. Linux
+//! and macOS threads carry no register context (that would need the
+//! process stopped under ptrace), so debuggers can inspect memory and
+//! modules but not unwind stacks.
+//!
+//! Synthetic dumps follow the Breakpad conventions for non-Windows hosts
+//! (platform ids `0x8201` for Linux and `0x8101` for macOS). Unreadable
+//! pages inside dumped ranges are zero-filled.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+
+use crate::collectors::memory::models::{MemoryRegionInfo, MemoryRegionType};
+use crate::collectors::memory::platforms::MemoryCollectorImpl;
+use crate::collectors::memory::region_dump::read_range;
+use crate::collectors::volatile::models::ProcessInfo;
+
+/// Minidump content, named after the `MINIDUMP_TYPE` flags they map to
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum MiniDumpType {
+    /// Threads, modules and thread stacks
+    #[value(name = "normal")]
+    MiniDumpNormal,
+    /// Everything in a normal dump plus all readable memory
+    #[value(name = "full")]
+    MiniDumpWithFullMemory,
+    /// A normal dump plus per-thread times
+    #[value(name = "thread-info")]
+    MiniDumpWithThreadInfo,
+}
+
+impl MiniDumpType {
+    /// `MINIDUMP_TYPE` flags
+    pub fn flags(self) -> u32 {
+        match self {
+            MiniDumpType::MiniDumpNormal => 0x0000,
+            MiniDumpType::MiniDumpWithFullMemory => 0x0002,
+            MiniDumpType::MiniDumpWithThreadInfo => 0x1000,
+        }
+    }
+}
+
+const MINIDUMP_SIGNATURE: u32 = 0x504D_444D; // "MDMP"
+const MINIDUMP_VERSION: u32 = 0xA793;
+const HEADER_SIZE: usize = 32;
+const DIRECTORY_ENTRY_SIZE: usize = 12;
+
+// Stream types
+const THREAD_LIST_STREAM: u32 = 3;
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const SYSTEM_INFO_STREAM: u32 = 7;
+const MEMORY64_LIST_STREAM: u32 = 9;
+const THREAD_INFO_LIST_STREAM: u32 = 17;
+
+const THREAD_INFO_ENTRY_SIZE: u32 = 64;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+/// FILETIME ticks (100 ns) per second
+const FILETIME_TICKS: u64 = 10_000_000;
+/// `/proc` reports times in USER_HZ, fixed at 100 for the userspace ABI
+const USER_HZ: u64 = 100;
+
+/// Write a minidump of `process` to `output_path`
+pub fn write_minidump(
+    collector: &dyn MemoryCollectorImpl,
+    process: &ProcessInfo,
+    output_path: &Path,
+    dump_type: MiniDumpType,
+) -> Result<()> {
+    info!(
+        "Writing {:?} minidump of process {} ({}) to {}",
+        dump_type,
+        process.pid,
+        process.name,
+        output_path.display()
+    );
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = collector;
+        write_windows_minidump(process.pid, output_path, dump_type)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        write_synthetic_minidump(collector, process, output_path, dump_type)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn write_windows_minidump(
+    pid: u32,
+    output_path: &Path,
+    dump_type: MiniDumpType,
+) -> Result<()> {
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr::null_mut;
+    use winapi::um::dbghelp::MiniDumpWriteDump;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::OpenProcess;
+    use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+    let file = File::create(output_path).context(format!(
+        "Failed to create minidump file: {}",
+        output_path.display()
+    ))?;
+
+    // SAFETY: OpenProcess has no pointer arguments; the handle is closed below
+    let process = unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, 0, pid) };
+    if process.is_null() {
+        anyhow::bail!(
+            "Failed to open process {}: {}",
+            pid,
+            io::Error::last_os_error()
+        );
+    }
+
+    // SAFETY: both handles are valid for the call; the optional exception,
+    // user stream and callback parameters may be null
+    let written = unsafe {
+        MiniDumpWriteDump(
+            process,
+            pid,
+            file.as_raw_handle() as _,
+            dump_type.flags(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+        )
+    };
+    let error = io::Error::last_os_error();
+    // SAFETY: `process` came from OpenProcess and is not used afterwards
+    unsafe { CloseHandle(process) };
+
+    if written == 0 {
+        anyhow::bail!("MiniDumpWriteDump failed for process {}: {}", pid, error);
+    }
+    Ok(())
+}
+
+/// A thread of the dumped process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ThreadRecord {
+    id: u32,
+    /// FILETIME of thread creation, 0 if unknown
+    create_time: u64,
+    /// Kernel and user CPU time in 100 ns units
+    kernel_time: u64,
+    user_time: u64,
+}
+
+/// Threads from `/proc/<pid>/task`; just the main thread where that is missing
+fn process_threads(pid: u32) -> Vec<ThreadRecord> {
+    let boot_time = fs::read_to_string("/proc/stat")
+        .ok()
+        .and_then(|stat| parse_boot_time(&stat));
+
+    let mut threads: Vec<ThreadRecord> = fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+                .map(|tid| {
+                    fs::read_to_string(format!("/proc/{}/task/{}/stat", pid, tid))
+                        .ok()
+                        .and_then(|stat| parse_thread_stat(tid, &stat, boot_time))
+                        .unwrap_or(ThreadRecord {
+                            id: tid,
+                            ..Default::default()
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if threads.is_empty() {
+        threads.push(ThreadRecord {
+            id: pid,
+            ..Default::default()
+        });
+    }
+    threads.sort_by_key(|t| t.id);
+    threads
+}
+
+/// `btime` (boot time in Unix seconds) from `/proc/stat`
+fn parse_boot_time(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Times from a `/proc/<pid>/task/<tid>/stat` line
+fn parse_thread_stat(tid: u32, stat: &str, boot_time: Option<u64>) -> Option<ThreadRecord> {
+    // The command name may contain spaces and parentheses; fields follow the last ')'
+    let fields: Vec<&str> = stat
+        .get(stat.rfind(')')? + 1..)?
+        .split_whitespace()
+        .collect();
+    // Field numbers from proc(5), counted from `state` (field 3)
+    let field = |number: usize| -> Option<u64> { fields.get(number - 3)?.parse().ok() };
+    let ticks = |value: u64| value * FILETIME_TICKS / USER_HZ;
+
+    let create_time = match (boot_time, field(22)) {
+        (Some(boot), Some(start)) => (boot + FILETIME_UNIX_OFFSET) * FILETIME_TICKS + ticks(start),
+        _ => 0,
+    };
+    Some(ThreadRecord {
+        id: tid,
+        create_time,
+        kernel_time: ticks(field(15)?),
+        user_time: ticks(field(14)?),
+    })
+}
+
+/// Minidump under construction; everything but Memory64 data is buffered
+struct DumpBuilder {
+    buf: Vec<u8>,
+    streams: Vec<(u32, u32, u32)>,
+}
+
+impl DumpBuilder {
+    fn new(stream_count: usize) -> Self {
+        Self {
+            buf: vec![0; HEADER_SIZE + stream_count * DIRECTORY_ENTRY_SIZE],
+            streams: Vec::new(),
+        }
+    }
+
+    fn rva(&self) -> u32 {
+        self.buf.len() as u32
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn patch_u32(&mut self, offset: usize, value: u32) {
+        self.buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn align(&mut self) {
+        while !self.buf.len().is_multiple_of(8) {
+            self.buf.push(0);
+        }
+    }
+
+    /// Append a MINIDUMP_STRING and return its RVA
+    fn string(&mut self, value: &str) -> u32 {
+        self.align();
+        let rva = self.rva();
+        let units: Vec<u16> = value.encode_utf16().collect();
+        self.u32((units.len() * 2) as u32);
+        for unit in units {
+            self.u16(unit);
+        }
+        self.u16(0);
+        rva
+    }
+
+    /// Start a stream at the next aligned offset
+    fn begin_stream(&mut self) -> u32 {
+        self.align();
+        self.rva()
+    }
+
+    fn end_stream(&mut self, stream_type: u32, rva: u32) {
+        self.streams.push((stream_type, self.rva() - rva, rva));
+    }
+
+    /// Fill in the header and stream directory
+    fn finish_header(&mut self, flags: u32) {
+        let count = self.streams.len() as u32;
+        let header = [
+            MINIDUMP_SIGNATURE,
+            MINIDUMP_VERSION,
+            count,
+            HEADER_SIZE as u32,
+            0, // checksum
+            Utc::now().timestamp() as u32,
+        ];
+        for (index, value) in header.into_iter().enumerate() {
+            self.patch_u32(index * 4, value);
+        }
+        self.buf[24..32].copy_from_slice(&u64::from(flags).to_le_bytes());
+
+        for (index, (stream_type, size, rva)) in self.streams.clone().into_iter().enumerate() {
+            let offset = HEADER_SIZE + index * DIRECTORY_ENTRY_SIZE;
+            self.patch_u32(offset, stream_type);
+            self.patch_u32(offset + 4, size);
+            self.patch_u32(offset + 8, rva);
+        }
+    }
+}
+
+/// Breakpad platform id of the host
+fn platform_id() -> u32 {
+    if cfg!(target_os = "macos") {
+        0x8101
+    } else {
+        0x8201
+    }
+}
+
+/// `PROCESSOR_ARCHITECTURE_*` of the host
+fn processor_architecture() -> u16 {
+    match std::env::consts::ARCH {
+        "x86" => 0,
+        "arm" => 5,
+        "x86_64" => 9,
+        "aarch64" => 12,
+        _ => 0xFFFF,
+    }
+}
+
+fn write_system_info(dump: &mut DumpBuilder) {
+    let csd_version = dump.string("");
+    let processors = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    let rva = dump.begin_stream();
+    dump.u16(processor_architecture());
+    dump.u16(0); // level
+    dump.u16(0); // revision
+    dump.buf.push(processors.min(u8::MAX as usize) as u8);
+    dump.buf.push(1); // VER_NT_WORKSTATION
+    dump.u32(0); // major version
+    dump.u32(0); // minor version
+    dump.u32(0); // build number
+    dump.u32(platform_id());
+    dump.u32(csd_version);
+    dump.u16(0); // suite mask
+    dump.u16(0);
+    dump.buf.extend_from_slice(&[0; 24]); // CPU_INFORMATION
+    dump.end_stream(SYSTEM_INFO_STREAM, rva);
+}
+
+fn write_module_list(
+    dump: &mut DumpBuilder,
+    collector: &dyn MemoryCollectorImpl,
+    process: &ProcessInfo,
+) {
+    let mut modules = collector.get_modules(process).unwrap_or_else(|e| {
+        warn!("No module list for process {}: {}", process.pid, e);
+        Vec::new()
+    });
+    modules.sort_by_key(|m| m.base_address);
+    let names: Vec<u32> = modules.iter().map(|m| dump.string(&m.path)).collect();
+
+    let rva = dump.begin_stream();
+    dump.u32(modules.len() as u32);
+    for (module, name) in modules.iter().zip(names) {
+        dump.u64(module.base_address);
+        dump.u32(module.size.min(u64::from(u32::MAX)) as u32);
+        dump.u32(0); // checksum
+        dump.u32(0); // timestamp
+        dump.u32(name);
+        dump.buf.extend_from_slice(&[0; 52]); // VS_FIXEDFILEINFO
+        dump.buf.extend_from_slice(&[0; 16]); // CodeView and misc records
+        dump.buf.extend_from_slice(&[0; 16]); // reserved
+    }
+    dump.end_stream(MODULE_LIST_STREAM, rva);
+}
+
+/// Write the thread list; returns the offsets of stack locations to patch
+/// with the RVA of the stack memory, and which region each refers to
+fn write_thread_list(
+    dump: &mut DumpBuilder,
+    threads: &[ThreadRecord],
+    pid: u32,
+    stack: Option<&MemoryRegionInfo>,
+) -> Vec<usize> {
+    let rva = dump.begin_stream();
+    let mut stack_locations = Vec::new();
+    dump.u32(threads.len() as u32);
+    for thread in threads {
+        dump.u32(thread.id);
+        dump.u32(0); // suspend count
+        dump.u32(0); // priority class
+        dump.u32(0); // priority
+        dump.u64(0); // TEB
+                     // Only the main thread's stack is known from the maps
+        match stack.filter(|_| thread.id == pid) {
+            Some(region) => {
+                dump.u64(region.base_address);
+                stack_locations.push(dump.buf.len());
+                dump.u32(0);
+                dump.u32(0);
+            }
+            None => {
+                dump.u64(0);
+                dump.u32(0);
+                dump.u32(0);
+            }
+        }
+        dump.u32(0); // no thread context
+        dump.u32(0);
+    }
+    dump.end_stream(THREAD_LIST_STREAM, rva);
+    stack_locations
+}
+
+fn write_thread_info_list(dump: &mut DumpBuilder, threads: &[ThreadRecord]) {
+    let rva = dump.begin_stream();
+    dump.u32(12); // header size
+    dump.u32(THREAD_INFO_ENTRY_SIZE);
+    dump.u32(threads.len() as u32);
+    for thread in threads {
+        dump.u32(thread.id);
+        dump.u32(0); // dump flags
+        dump.u32(0); // dump error
+        dump.u32(0); // exit status
+        dump.u64(thread.create_time);
+        dump.u64(0); // exit time
+        dump.u64(thread.kernel_time);
+        dump.u64(thread.user_time);
+        dump.u64(0); // start address
+        dump.u64(0); // affinity
+    }
+    dump.end_stream(THREAD_INFO_LIST_STREAM, rva);
+}
+
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn write_synthetic_minidump(
+    collector: &dyn MemoryCollectorImpl,
+    process: &ProcessInfo,
+    output_path: &Path,
+    dump_type: MiniDumpType,
+) -> Result<()> {
+    let pid = process.pid;
+    let regions: Vec<MemoryRegionInfo> = collector
+        .get_memory_regions(process)
+        .context(format!("Failed to get memory regions for process {}", pid))?
+        .into_iter()
+        .filter(|r| r.protection.read && r.size > 0)
+        .collect();
+    let stack = regions
+        .iter()
+        .find(|r| r.region_type == MemoryRegionType::Stack);
+    let threads = process_threads(pid);
+    let full_memory = dump_type == MiniDumpType::MiniDumpWithFullMemory;
+    let thread_info = dump_type == MiniDumpType::MiniDumpWithThreadInfo;
+
+    let mut dump = DumpBuilder::new(if thread_info { 5 } else { 4 });
+    write_system_info(&mut dump);
+    write_module_list(&mut dump, collector, process);
+    let stack_locations = write_thread_list(&mut dump, &threads, pid, stack);
+    if thread_info {
+        write_thread_info_list(&mut dump, &threads);
+    }
+
+    if !full_memory {
+        // Stack memory only, stored inline
+        let ranges: Vec<&MemoryRegionInfo> = stack.into_iter().collect();
+        let rva = dump.begin_stream();
+        dump.u32(ranges.len() as u32);
+        let descriptors = dump.buf.len();
+        for region in &ranges {
+            dump.u64(region.base_address);
+            dump.u64(0); // location, filled in below
+        }
+        dump.end_stream(MEMORY_LIST_STREAM, rva);
+
+        for (index, region) in ranges.iter().enumerate() {
+            let (data, gaps) =
+                read_range(collector, pid, region.base_address, region.size as usize);
+            if !gaps.is_empty() {
+                debug!(
+                    "{} unreadable ranges in stack of process {}",
+                    gaps.len(),
+                    pid
+                );
+            }
+            dump.align();
+            let data_rva = dump.rva();
+            dump.buf.extend_from_slice(&data);
+            let location = descriptors + index * 16 + 8;
+            dump.patch_u32(location, data.len() as u32);
+            dump.patch_u32(location + 4, data_rva);
+            for &offset in &stack_locations {
+                dump.patch_u32(offset, data.len() as u32);
+                dump.patch_u32(offset + 4, data_rva);
+            }
+        }
+        dump.finish_header(dump_type.flags());
+        fs::write(output_path, &dump.buf).context(format!(
+            "Failed to write minidump: {}",
+            output_path.display()
+        ))?;
+        return Ok(());
+    }
+
+    // Full memory: descriptors now, region data streamed after the buffer
+    let rva = dump.begin_stream();
+    dump.u64(regions.len() as u64);
+    let base_rva = dump.buf.len() as u64 + 8 + regions.len() as u64 * 16;
+    dump.u64(base_rva);
+    let mut offset = base_rva;
+    for region in &regions {
+        dump.u64(region.base_address);
+        dump.u64(region.size);
+        // The main thread's stack points into the Memory64 data when addressable
+        if stack.is_some_and(|s| s.base_address == region.base_address) {
+            if let Ok(stack_rva) = u32::try_from(offset) {
+                for &location in &stack_locations {
+                    dump.patch_u32(location, region.size.min(u64::from(u32::MAX)) as u32);
+                    dump.patch_u32(location + 4, stack_rva);
+                }
+            }
+        }
+        offset += region.size;
+    }
+    dump.end_stream(MEMORY64_LIST_STREAM, rva);
+    dump.finish_header(dump_type.flags());
+
+    let mut writer = BufWriter::new(File::create(output_path).context(format!(
+        "Failed to create minidump file: {}",
+        output_path.display()
+    ))?);
+    writer.write_all(&dump.buf)?;
+    let mut unreadable = 0u64;
+    for region in &regions {
+        let (data, gaps) = read_range(collector, pid, region.base_address, region.size as usize);
+        unreadable += gaps.iter().map(|g| g.size).sum::<u64>();
+        writer.write_all(&data).context(format!(
+            "Failed to write minidump: {}",
+            output_path.display()
+        ))?;
+    }
+    writer.flush()?;
+
+    if unreadable > 0 {
+        warn!(
+            "{} bytes of process {} were unreadable and zero-filled in the minidump",
+            unreadable, pid
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::memory::models::{MemoryProtection, ModuleInfo};
+    use crate::collectors::parsers::bytes::{u32_at, u64_at};
+    use anyhow::bail;
+    use tempfile::TempDir;
+
+    const STACK: u64 = 0x7000_0000;
+    const HEAP: u64 = 0x1000_0000;
+
+    /// Serves `address & 0xff` from a heap and a stack region, with one module
+    struct FakeProcess;
+
+    fn region(base_address: u64, size: u64, region_type: MemoryRegionType) -> MemoryRegionInfo {
+        MemoryRegionInfo {
+            base_address,
+            size,
+            region_type,
+            protection: MemoryProtection {
+                read: true,
+                write: true,
+                execute: false,
+            },
+            name: None,
+            mapped_file: None,
+            dumped: false,
+            dump_path: None,
+            gaps: Vec::new(),
+        }
+    }
+
+    impl MemoryCollectorImpl for FakeProcess {
+        fn new() -> Result<Self> {
+            Ok(Self)
+        }
+
+        fn get_memory_regions(&self, _process: &ProcessInfo) -> Result<Vec<MemoryRegionInfo>> {
+            Ok(vec![
+                region(HEAP, 0x2000, MemoryRegionType::Heap),
+                region(STACK, 0x1000, MemoryRegionType::Stack),
+            ])
+        }
+
+        fn read_memory(&self, _pid: u32, address: u64, size: usize) -> Result<Vec<u8>> {
+            // The second heap page is unmapped
+            if address < HEAP + 0x2000 && address + size as u64 > HEAP + 0x1000 {
+                bail!("unreadable");
+            }
+            Ok((address..address + size as u64)
+                .map(|a| (a & 0xff) as u8)
+                .collect())
+        }
+
+        fn get_modules(&self, _process: &ProcessInfo) -> Result<Vec<ModuleInfo>> {
+            Ok(vec![ModuleInfo {
+                base_address: 0x40_0000,
+                size: 0x1000,
+                path: "/usr/bin/target".into(),
+                name: "target".into(),
+                version: None,
+            }])
+        }
+    }
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            pid: u32::MAX,
+            name: "target".into(),
+            cmd: vec![],
+            exe: None,
+            status: "Running".into(),
+            start_time: 0,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid: None,
+        }
+    }
+
+    /// Stream types mapped to (size, rva)
+    fn streams(data: &[u8]) -> Vec<(u32, u32, usize)> {
+        assert_eq!(u32_at(data, 0).unwrap(), MINIDUMP_SIGNATURE);
+        let count = u32_at(data, 8).unwrap() as usize;
+        let directory = u32_at(data, 12).unwrap() as usize;
+        (0..count)
+            .map(|i| {
+                let entry = directory + i * DIRECTORY_ENTRY_SIZE;
+                (
+                    u32_at(data, entry).unwrap(),
+                    u32_at(data, entry + 4).unwrap(),
+                    u32_at(data, entry + 8).unwrap() as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn stream(data: &[u8], stream_type: u32) -> usize {
+        streams(data)
+            .into_iter()
+            .find(|(t, _, _)| *t == stream_type)
+            .map(|(_, _, rva)| rva)
+            .unwrap()
+    }
+
+    fn write(dump_type: MiniDumpType) -> Vec<u8> {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("target.dmp");
+        write_synthetic_minidump(&FakeProcess, &process(), &path, dump_type).unwrap();
+        fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn test_normal_minidump() {
+        let data = write(MiniDumpType::MiniDumpNormal);
+        let types: Vec<u32> = streams(&data).iter().map(|s| s.0).collect();
+        assert_eq!(
+            types,
+            vec![
+                SYSTEM_INFO_STREAM,
+                MODULE_LIST_STREAM,
+                THREAD_LIST_STREAM,
+                MEMORY_LIST_STREAM
+            ]
+        );
+        assert_eq!(u64_at(&data, 24).unwrap(), 0);
+
+        let modules = stream(&data, MODULE_LIST_STREAM);
+        assert_eq!(u32_at(&data, modules).unwrap(), 1);
+        assert_eq!(u64_at(&data, modules + 4).unwrap(), 0x40_0000);
+        let name = u32_at(&data, modules + 4 + 20).unwrap() as usize;
+        let length = u32_at(&data, name).unwrap() as usize;
+        let units: Vec<u16> = data[name + 4..name + 4 + length]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&units).unwrap(), "/usr/bin/target");
+
+        // Only the stack is dumped
+        let memory = stream(&data, MEMORY_LIST_STREAM);
+        assert_eq!(u32_at(&data, memory).unwrap(), 1);
+        assert_eq!(u64_at(&data, memory + 4).unwrap(), STACK);
+        assert_eq!(u32_at(&data, memory + 12).unwrap(), 0x1000);
+        let rva = u32_at(&data, memory + 16).unwrap() as usize;
+        assert_eq!(data[rva + 0x42], 0x42);
+        assert!(streams(&data).iter().all(|s| s.0 != MEMORY64_LIST_STREAM));
+    }
+
+    #[test]
+    fn test_full_memory_minidump() {
+        let data = write(MiniDumpType::MiniDumpWithFullMemory);
+        assert_eq!(u64_at(&data, 24).unwrap(), 0x2);
+
+        let memory = stream(&data, MEMORY64_LIST_STREAM);
+        assert_eq!(u64_at(&data, memory).unwrap(), 2);
+        let base = u64_at(&data, memory + 8).unwrap() as usize;
+        assert_eq!(
+            (
+                u64_at(&data, memory + 16).unwrap(),
+                u64_at(&data, memory + 24).unwrap()
+            ),
+            (HEAP, 0x2000)
+        );
+        assert_eq!(
+            (
+                u64_at(&data, memory + 32).unwrap(),
+                u64_at(&data, memory + 40).unwrap()
+            ),
+            (STACK, 0x1000)
+        );
+        assert_eq!(data.len(), base + 0x3000);
+
+        // Heap: readable page, then a zero-filled unreadable page
+        assert_eq!(data[base + 0x10], 0x10);
+        assert!(data[base + 0x1000..base + 0x2000].iter().all(|&b| b == 0));
+        assert_eq!(data[base + 0x2000 + 0x7f], 0x7f);
+    }
+
+    #[test]
+    fn test_thread_info_minidump() {
+        let data = write(MiniDumpType::MiniDumpWithThreadInfo);
+        assert_eq!(u64_at(&data, 24).unwrap(), 0x1000);
+
+        let threads = stream(&data, THREAD_LIST_STREAM);
+        let info = stream(&data, THREAD_INFO_LIST_STREAM);
+        assert_eq!(u32_at(&data, info + 4).unwrap(), THREAD_INFO_ENTRY_SIZE);
+        assert_eq!(
+            u32_at(&data, info + 8).unwrap(),
+            u32_at(&data, threads).unwrap()
+        );
+        // No /proc entry for the fake PID: only the main thread is listed
+        assert_eq!(u32_at(&data, info + 12).unwrap(), u32::MAX);
+    }
+
+    #[test]
+    fn test_parse_thread_stat() {
+        let stat =
+            "4242 (worker (1)) S 1 4242 4242 0 -1 4194560 100 0 0 0 250 50 0 0 20 0 3 0 1000 0 0";
+        let thread = parse_thread_stat(4242, stat, Some(1_700_000_000)).unwrap();
+        assert_eq!(thread.user_time, 250 * FILETIME_TICKS / USER_HZ);
+        assert_eq!(thread.kernel_time, 50 * FILETIME_TICKS / USER_HZ);
+        assert_eq!(
+            thread.create_time,
+            (1_700_000_000 + FILETIME_UNIX_OFFSET) * FILETIME_TICKS + 10 * FILETIME_TICKS
+        );
+        assert_eq!(
+            parse_boot_time("cpu 1 2\nbtime 1700000000\n"),
+            Some(1_700_000_000)
+        );
+        assert!(parse_thread_stat(1, "garbage", None).is_none());
+    }
+}
@@ -19,8 +19,11 @@
 pub mod collector;
 pub mod export;
 pub mod filters;
+pub mod minidump_export;
 pub mod models;
 pub mod platforms;
+pub mod process_context;
+pub mod region_dump;
 
 // New memprocfs implementation
 #[cfg(feature = "memory_collection")]
@@ -33,6 +36,9 @@ use log::warn;
 use std::path::Path;
 
 use crate::collectors::memory::collector::MemoryCollector;
+use crate::collectors::memory::export::MemoryExporter;
+use crate::collectors::memory::filters::ProcessFilter;
+use crate::collectors::memory::minidump_export::{write_minidump, MiniDumpType};
 use crate::collectors::memory::models::MemoryCollectionSummary;
 #[cfg(feature = "memory_collection")]
 use crate::collectors::memory::platforms::MemoryCollectorImpl;
@@ -82,6 +88,64 @@ pub fn collect_process_memory(
     collector.collect_all(processes, memory_dir)
 }
 
+/// Write a minidump of each selected process to `process_memory/minidumps`
+///
+/// Failures are logged per process; returns the paths of the dumps written.
+pub fn write_process_minidumps(
+    processes: &[ProcessInfo],
+    output_dir: impl AsRef<Path>,
+    process_names: Option<&str>,
+    process_ids: Option<&str>,
+    include_system_processes: bool,
+    dump_type: MiniDumpType,
+) -> Result<Vec<std::path::PathBuf>> {
+    let filter = ProcessFilter::from_args(process_names, process_ids, include_system_processes);
+    let collector = platforms::get_memory_collector()?;
+    let dump_dir = output_dir.as_ref().join("process_memory").join("minidumps");
+    std::fs::create_dir_all(&dump_dir)?;
+
+    let mut written = Vec::new();
+    for process in processes.iter().filter(|p| filter.matches(p)) {
+        let path = dump_dir.join(format!("{}_{}.dmp", process.name, process.pid));
+        match write_minidump(collector.as_ref(), process, &path, dump_type) {
+            Ok(()) => written.push(path),
+            Err(e) => warn!("Failed to write minidump of process {}: {}", process.pid, e),
+        }
+    }
+    Ok(written)
+}
+
+/// Write `context.json` for each selected process without dumping memory
+///
+/// Each context goes in the same `process_memory/<name>_<pid>` directory a
+/// dump would use. Only Linux exposes the context through `/proc`; other
+/// platforms return an error. Returns the paths of the files written.
+pub fn collect_process_context(
+    processes: &[ProcessInfo],
+    output_dir: impl AsRef<Path>,
+    process_names: Option<&str>,
+    process_ids: Option<&str>,
+    include_system_processes: bool,
+) -> Result<Vec<std::path::PathBuf>> {
+    if !cfg!(target_os = "linux") {
+        anyhow::bail!("Process context is only collected on Linux");
+    }
+
+    let filter = ProcessFilter::from_args(process_names, process_ids, include_system_processes);
+    let exporter = MemoryExporter::new(output_dir.as_ref().join("process_memory"));
+
+    let mut written = Vec::new();
+    for process in processes.iter().filter(|p| filter.matches(p)) {
+        let context = process_context::read_process_context(Path::new("/proc"), process.pid);
+        let process_dir = exporter.process_dir(&process.name, process.pid);
+        match exporter.export_process_context(&process_dir, &context) {
+            Ok(path) => written.push(path),
+            Err(e) => warn!("Failed to write context of process {}: {}", process.pid, e),
+        }
+    }
+    Ok(written)
+}
+
 /// Check if memory collection is available on this platform
 pub fn is_memory_collection_available() -> bool {
     #[cfg(feature = "memory_collection")]
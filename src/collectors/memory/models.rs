@@ -4,7 +4,7 @@
 //! including memory regions, process information, and collection summaries.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Memory region type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -63,6 +63,20 @@ pub struct MemoryRegionInfo {
     pub dumped: bool,
     /// Path to the dump file (relative to process directory)
     pub dump_path: Option<String>,
+    /// Ranges that could not be read and are zero-filled in the dump
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<MemoryGap>,
+}
+
+/// A range of a memory region that could not be read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryGap {
+    /// Offset of the range from the region base address
+    pub offset: u64,
+    /// Size of the range in bytes
+    pub size: u64,
+    /// Why the range could not be read
+    pub reason: String,
 }
 
 /// Module information
@@ -111,6 +125,9 @@ pub struct ProcessMemoryInfo {
     pub status: String,
     /// Error message if collection failed
     pub error: Option<String>,
+    /// How memory was read, where the platform reports it (e.g. `ptrace`)
+    #[serde(default)]
+    pub read_method: Option<String>,
 }
 
 /// Memory collection options
@@ -187,4 +204,89 @@ pub struct ProcessSummary {
     pub dumped_memory_size: u64,
     /// Collection status
     pub status: String,
+    /// Why the process could not be (fully) read
+    #[serde(default)]
+    pub error: Option<String>,
+    /// How memory was read, where the platform reports it (e.g. `ptrace`)
+    #[serde(default)]
+    pub read_method: Option<String>,
+}
+
+/// Thread, file-descriptor and mapping context of a process, written as
+/// `context.json` next to its memory dump (Linux)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessContext {
+    /// Process ID
+    pub pid: u32,
+    /// Command line arguments (`/proc/<pid>/cmdline`)
+    pub cmdline: Vec<String>,
+    /// Environment as `NAME=value` entries (`/proc/<pid>/environ`)
+    pub environ: Vec<String>,
+    /// Threads (`/proc/<pid>/task/*`)
+    pub threads: Vec<ThreadContext>,
+    /// Open file descriptors (`/proc/<pid>/fd`)
+    pub file_descriptors: Vec<FileDescriptor>,
+    /// Memory mappings (`/proc/<pid>/maps`)
+    pub maps: Vec<MemoryMapping>,
+    /// Memory totals in kB (`/proc/<pid>/smaps_rollup`)
+    pub smaps_rollup: BTreeMap<String, u64>,
+    /// Control group membership (`/proc/<pid>/cgroup`)
+    pub cgroups: Vec<CgroupMembership>,
+    /// Sources that could not be read, e.g. kernel stacks without root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// One thread of a process
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreadContext {
+    /// Thread ID
+    pub tid: u32,
+    /// Thread name (`Name`)
+    pub name: Option<String>,
+    /// Scheduler state (`State`, e.g. `S (sleeping)`)
+    pub state: Option<String>,
+    /// Every field of `/proc/<pid>/task/<tid>/status`
+    pub status: BTreeMap<String, String>,
+    /// Kernel stack frames (`/proc/<pid>/task/<tid>/stack`); needs root
+    pub kernel_stack: Option<Vec<String>>,
+}
+
+/// An open file descriptor and what it refers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDescriptor {
+    /// Descriptor number
+    pub fd: u32,
+    /// Link target, e.g. a path, `socket:[12345]` or `pipe:[678]`
+    pub target: String,
+}
+
+/// One line of `/proc/<pid>/maps`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryMapping {
+    /// Start address
+    pub start: u64,
+    /// End address (exclusive)
+    pub end: u64,
+    /// Permissions, e.g. `r-xp`
+    pub permissions: String,
+    /// Offset into the mapped file
+    pub offset: u64,
+    /// Device as `major:minor`
+    pub device: String,
+    /// Inode of the mapped file, 0 for anonymous mappings
+    pub inode: u64,
+    /// Mapped file or pseudo-name such as `[heap]`
+    pub pathname: Option<String>,
+}
+
+/// One line of `/proc/<pid>/cgroup`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupMembership {
+    /// Hierarchy ID, 0 for the cgroup v2 unified hierarchy
+    pub hierarchy_id: u32,
+    /// Controllers bound to the hierarchy (empty for cgroup v2)
+    pub controllers: Vec<String>,
+    /// Path of the cgroup within the hierarchy
+    pub path: String,
 }
@@ -1,7 +1,14 @@
 //! Linux-specific memory collection implementation
 //!
-//! This module provides Linux-specific implementation for memory collection
-//! using the /proc filesystem.
+//! Memory is read with `process_vm_readv`, which copies straight from the
+//! target's address space without seeking. When the kernel refuses it (no
+//! cross-memory attach support, or an LSM denying it) the collector attaches
+//! with `PTRACE_ATTACH`, reads `/proc/<pid>/mem` and detaches again after
+//! every read. Region maps and modules come from `/proc/<pid>/maps`.
+//!
+//! When both methods are denied, the error explains why from the Yama
+//! `ptrace_scope` setting and the target's user namespace, and later reads
+//! of that process fail immediately.
 
 use anyhow::{anyhow, Context, Result};
 use log::{debug, info, warn};
@@ -9,140 +16,183 @@ use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{self, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::Mutex;
 
 use crate::collectors::memory::models::{
     MemoryProtection, MemoryRegionInfo, MemoryRegionType, ModuleInfo,
 };
 use crate::collectors::memory::platforms::MemoryCollectorImpl;
 use crate::collectors::volatile::models::ProcessInfo;
-use crate::constants::MEMORY_CHUNK_SIZE;
+use crate::errors::CollectorError;
+
+/// Yama LSM ptrace restriction level
+const YAMA_PTRACE_SCOPE: &str = "/proc/sys/kernel/yama/ptrace_scope";
+
+/// How the memory of a process was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadMethod {
+    VmReadv,
+    Ptrace,
+}
+
+impl ReadMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            ReadMethod::VmReadv => "process_vm_readv",
+            ReadMethod::Ptrace => "ptrace",
+        }
+    }
+}
 
 /// Linux memory collector implementation
 pub struct LinuxMemoryCollector {
-    // No state needed for Linux implementation
+    /// Method that last succeeded for each process
+    methods: Mutex<HashMap<u32, ReadMethod>>,
+    /// Processes whose memory cannot be read, with the reason
+    denied: Mutex<HashMap<u32, String>>,
 }
 
-// Regular methods outside of the trait implementation
-impl LinuxMemoryCollector {
-    /// Internal implementation of memory reading
-    fn read_memory_internal(&self, pid: u32, address: u64, size: usize) -> Result<Vec<u8>> {
-        let mem_path = format!("/proc/{}/mem", pid);
-
-        // Open the process memory file with improved error handling
-        let mut file = match File::open(&mem_path) {
-            Ok(f) => f,
-            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                return Err(anyhow!("Permission denied when accessing process memory for pid {}. Run as root or adjust ptrace_scope.", pid));
-            }
-            Err(e) if e.kind() == io::ErrorKind::NotFound => {
-                return Err(anyhow!(
-                    "Process {} no longer exists or is not accessible",
-                    pid
-                ));
-            }
-            Err(e) => {
-                return Err(anyhow!(
-                    "Failed to open memory file for process {}: {}",
-                    pid,
-                    e
-                ));
-            }
-        };
-
-        // Seek to the specified address
-        if let Err(e) = file.seek(SeekFrom::Start(address)) {
-            return Err(anyhow!(
-                "Failed to seek to address {:x} for process {}: {}",
-                address,
-                pid,
-                e
-            ));
+/// Detaches from a ptrace-stopped process when dropped
+struct PtraceAttachment(libc::pid_t);
+
+impl Drop for PtraceAttachment {
+    fn drop(&mut self) {
+        // SAFETY: PTRACE_DETACH takes no address, and data 0 delivers no signal
+        unsafe {
+            libc::ptrace(
+                libc::PTRACE_DETACH,
+                self.0,
+                std::ptr::null_mut::<libc::c_void>(),
+                std::ptr::null_mut::<libc::c_void>(),
+            );
         }
+    }
+}
 
-        // Read the memory
-        let mut buffer = vec![0u8; size];
-        let bytes_read = match file.read(&mut buffer) {
-            Ok(n) => n,
-            Err(e) if e.kind() == io::ErrorKind::InvalidInput => {
-                // This can happen for special memory regions like vsyscall
-                debug!(
-                    "Invalid memory region at {:x} for process {}: {}",
-                    address, pid, e
-                );
-                return Ok(Vec::new());
-            }
-            Err(e) => {
-                return Err(anyhow!(
-                    "Failed to read memory at address {:x} for process {}: {}",
-                    address,
-                    pid,
-                    e
-                ));
-            }
-        };
-
-        // Resize buffer to actual bytes read
-        buffer.truncate(bytes_read);
+/// Read `size` bytes at `address` of `pid` with `process_vm_readv`
+///
+/// Returns fewer bytes than requested when the range runs into unmapped memory.
+fn vm_readv(pid: u32, address: u64, size: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![0u8; size];
+    let local = libc::iovec {
+        iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+        iov_len: size,
+    };
+    let remote = libc::iovec {
+        iov_base: address as *mut libc::c_void,
+        iov_len: size,
+    };
+    // SAFETY: `local` covers `size` writable bytes of `buffer`; the remote
+    // range is only read, and validated by the kernel
+    let read = unsafe { libc::process_vm_readv(pid as libc::pid_t, &local, 1, &remote, 1, 0) };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buffer.truncate(read as usize);
+    Ok(buffer)
+}
 
-        debug!(
-            "Read {} bytes from address {:x} for process {}",
-            bytes_read, address, pid
-        );
+/// Read `size` bytes at `address` from `/proc/<pid>/mem`
+fn read_proc_mem(pid: u32, address: u64, size: usize) -> io::Result<Vec<u8>> {
+    let mut file = File::open(format!("/proc/{}/mem", pid))?;
+    file.seek(SeekFrom::Start(address))?;
+    let mut buffer = vec![0u8; size];
+    let bytes_read = file.read(&mut buffer)?;
+    buffer.truncate(bytes_read);
+    Ok(buffer)
+}
 
-        Ok(buffer)
+/// Explain a denied read from the Yama scope and whether the target is in
+/// another user namespace
+fn explain_denial(ptrace_scope: Option<u8>, other_user_namespace: bool) -> String {
+    let mut reasons = Vec::new();
+    match ptrace_scope {
+        Some(1) => reasons.push(
+            "Yama ptrace_scope is 1: only descendants, or processes that allowed this \
+             collector with prctl(PR_SET_PTRACER), can be read without CAP_SYS_PTRACE",
+        ),
+        Some(2) => reasons.push("Yama ptrace_scope is 2: reading requires CAP_SYS_PTRACE"),
+        Some(3) => reasons.push("Yama ptrace_scope is 3: ptrace is disabled until reboot"),
+        _ => {}
+    }
+    if other_user_namespace {
+        reasons.push("the process runs in a different user namespace");
+    }
+    if reasons.is_empty() {
+        reasons.push("run as root or with CAP_SYS_PTRACE");
     }
+    format!(
+        "Permission denied reading process memory: {}",
+        reasons.join("; ")
+    )
+}
 
-    /// Read large memory regions in chunks to avoid allocation issues
-    fn read_large_memory(&self, pid: u32, address: u64, size: usize) -> Result<Vec<u8>> {
-        const CHUNK_SIZE: usize = MEMORY_CHUNK_SIZE;
-        let mut result = Vec::with_capacity(size);
-        let mut failures = 0;
+/// Explain why the memory of `pid` cannot be read on this system
+fn denial_reason(pid: u32) -> String {
+    let ptrace_scope = fs::read_to_string(YAMA_PTRACE_SCOPE)
+        .ok()
+        .and_then(|scope| scope.trim().parse().ok());
+    let namespace = |pid: &str| fs::read_link(format!("/proc/{}/ns/user", pid)).ok();
+    let other_user_namespace = match (namespace("self"), namespace(&pid.to_string())) {
+        (Some(own), Some(target)) => own != target,
+        _ => false,
+    };
+    explain_denial(ptrace_scope, other_user_namespace)
+}
 
-        debug!(
-            "Reading large memory region of {} bytes in chunks for process {}",
-            size, pid
-        );
+fn is_permission_error(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::PermissionDenied || e.raw_os_error() == Some(libc::EPERM)
+}
 
-        for chunk_offset in (0..size).step_by(CHUNK_SIZE) {
-            let chunk_size = std::cmp::min(CHUNK_SIZE, size - chunk_offset);
-            let chunk_addr = address + chunk_offset as u64;
+impl LinuxMemoryCollector {
+    /// Read through `PTRACE_ATTACH` and `/proc/<pid>/mem`, detaching before returning
+    fn read_with_ptrace(&self, pid: u32, address: u64, size: usize) -> io::Result<Vec<u8>> {
+        let target = pid as libc::pid_t;
+        // SAFETY: PTRACE_ATTACH takes no address or data
+        let attached = unsafe {
+            libc::ptrace(
+                libc::PTRACE_ATTACH,
+                target,
+                std::ptr::null_mut::<libc::c_void>(),
+                std::ptr::null_mut::<libc::c_void>(),
+            )
+        };
+        if attached < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let _attachment = PtraceAttachment(target);
 
-            match self.read_memory_internal(pid, chunk_addr, chunk_size) {
-                Ok(data) => {
-                    result.extend(data);
-                }
-                Err(e) => {
-                    // Log partial failure but continue
-                    debug!("Failed to read memory chunk at {:x}: {}", chunk_addr, e);
-                    failures += 1;
-
-                    // If too many failures, abort
-                    if failures > 5 {
-                        warn!(
-                            "Too many failures reading memory for process {}, aborting",
-                            pid
-                        );
-                        break;
-                    }
-                }
-            }
+        // Wait for the attach stop before touching memory
+        let mut status = 0;
+        // SAFETY: `status` is a valid out pointer
+        if unsafe { libc::waitpid(target, &mut status, libc::__WALL) } < 0 {
+            return Err(io::Error::last_os_error());
         }
+        read_proc_mem(pid, address, size)
+    }
 
-        if result.is_empty() {
-            return Err(anyhow!(
-                "Failed to read any memory from address {:x} for process {}",
-                address,
+    fn record_method(&self, pid: u32, method: ReadMethod) {
+        let mut methods = self.methods.lock().unwrap();
+        if methods.insert(pid, method) != Some(method) && method == ReadMethod::Ptrace {
+            info!(
+                "process_vm_readv is blocked for process {}, reading through ptrace",
                 pid
-            ));
+            );
         }
+    }
 
-        debug!(
-            "Read {} bytes total from large memory region for process {}",
-            result.len(),
-            pid
-        );
-
-        Ok(result)
+    fn read_failed(&self, pid: u32, address: u64, e: io::Error) -> anyhow::Error {
+        let cause = if is_permission_error(&e) {
+            let reason = denial_reason(pid);
+            warn!("Cannot read memory of process {}: {}", pid, reason);
+            self.denied.lock().unwrap().insert(pid, reason.clone());
+            reason
+        } else if e.raw_os_error() == Some(libc::ESRCH) {
+            "Process no longer exists or is not accessible".to_string()
+        } else {
+            format!("Failed to read memory at address {:x}: {}", address, e)
+        };
+        CollectorError::MemoryCollectionFailed { pid, cause }.into()
     }
 }
 
@@ -157,7 +207,10 @@ impl MemoryCollectorImpl for LinuxMemoryCollector {
 
         info!("Initialized Linux memory collector");
 
-        Ok(Self {})
+        Ok(Self {
+            methods: Mutex::new(HashMap::new()),
+            denied: Mutex::new(HashMap::new()),
+        })
     }
 
     fn get_memory_regions(&self, process: &ProcessInfo) -> Result<Vec<MemoryRegionInfo>> {
@@ -244,6 +297,7 @@ impl MemoryCollectorImpl for LinuxMemoryCollector {
                 mapped_file,
                 dumped: false,
                 dump_path: None,
+                gaps: Vec::new(),
             };
 
             regions.push(region);
@@ -255,13 +309,51 @@ impl MemoryCollectorImpl for LinuxMemoryCollector {
     }
 
     fn read_memory(&self, pid: u32, address: u64, size: usize) -> Result<Vec<u8>> {
-        // For large memory regions, use chunked reading
-        if size > 1024 * 1024 * 10 {
-            // 10MB threshold
-            return self.read_large_memory(pid, address, size);
+        if let Some(reason) = self.denied.lock().unwrap().get(&pid) {
+            return Err(CollectorError::MemoryCollectionFailed {
+                pid,
+                cause: reason.clone(),
+            }
+            .into());
+        }
+
+        let method = self.methods.lock().unwrap().get(&pid).copied();
+        if method != Some(ReadMethod::Ptrace) {
+            match vm_readv(pid, address, size) {
+                Ok(data) => {
+                    self.record_method(pid, ReadMethod::VmReadv);
+                    return Ok(data);
+                }
+                Err(e) if is_permission_error(&e) || e.raw_os_error() == Some(libc::ENOSYS) => {
+                    debug!("process_vm_readv failed for process {}: {}", pid, e);
+                }
+                Err(e) => return Err(self.read_failed(pid, address, e)),
+            }
         }
 
-        self.read_memory_internal(pid, address, size)
+        match self.read_with_ptrace(pid, address, size) {
+            Ok(data) => {
+                self.record_method(pid, ReadMethod::Ptrace);
+                debug!(
+                    "Read {} bytes from address {:x} for process {} through ptrace",
+                    data.len(),
+                    address,
+                    pid
+                );
+                Ok(data)
+            }
+            // Special regions like vsyscall can't be read through /proc/<pid>/mem
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => Ok(Vec::new()),
+            Err(e) => Err(self.read_failed(pid, address, e)),
+        }
+    }
+
+    fn read_method(&self, pid: u32) -> Option<String> {
+        self.methods
+            .lock()
+            .unwrap()
+            .get(&pid)
+            .map(|method| method.as_str().to_string())
     }
 
     fn get_modules(&self, process: &ProcessInfo) -> Result<Vec<ModuleInfo>> {
@@ -335,3 +427,96 @@ impl MemoryCollectorImpl for LinuxMemoryCollector {
         Ok(module_list)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERN: &[u8] = b"rs-collector memory pattern \x00\x01\x02\x03\xfe\xff";
+
+    /// Forked child holding `PATTERN` at `address` until dropped
+    struct PatternChild {
+        pid: libc::pid_t,
+        address: u64,
+    }
+
+    impl PatternChild {
+        fn spawn() -> Self {
+            // Allocated before forking, so it sits at the same address in the child
+            let pattern = PATTERN.repeat(512);
+            let address = pattern.as_ptr() as u64;
+            // SAFETY: the child only calls pause(), which is async-signal-safe
+            match unsafe { libc::fork() } {
+                0 => loop {
+                    unsafe { libc::pause() };
+                },
+                -1 => panic!("fork failed: {}", io::Error::last_os_error()),
+                pid => Self { pid, address },
+            }
+        }
+    }
+
+    impl Drop for PatternChild {
+        fn drop(&mut self) {
+            // SAFETY: `pid` is our own child
+            unsafe {
+                libc::kill(self.pid, libc::SIGKILL);
+                libc::waitpid(self.pid, std::ptr::null_mut(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_vm_readv_reads_child_memory() {
+        let child = PatternChild::spawn();
+        let collector = LinuxMemoryCollector::new().unwrap();
+
+        let data = collector
+            .read_memory(child.pid as u32, child.address, PATTERN.len() * 512)
+            .unwrap();
+        assert_eq!(data, PATTERN.repeat(512));
+        assert_eq!(
+            collector.read_method(child.pid as u32).as_deref(),
+            Some("process_vm_readv")
+        );
+    }
+
+    #[test]
+    fn test_ptrace_reads_child_memory() {
+        let child = PatternChild::spawn();
+        let collector = LinuxMemoryCollector::new().unwrap();
+
+        match collector.read_with_ptrace(child.pid as u32, child.address, PATTERN.len()) {
+            Ok(data) => assert_eq!(data, PATTERN),
+            // Sandboxes without ptrace (seccomp, ptrace_scope 3) can't exercise this path
+            Err(e) if is_permission_error(&e) => eprintln!("ptrace unavailable: {}", e),
+            Err(e) => panic!("ptrace read failed: {}", e),
+        }
+
+        // Detached again: the child can still be read normally
+        let data = collector
+            .read_memory(child.pid as u32, child.address, PATTERN.len())
+            .unwrap();
+        assert_eq!(data, PATTERN);
+    }
+
+    #[test]
+    fn test_unmapped_read_fails_without_denying_process() {
+        let child = PatternChild::spawn();
+        let collector = LinuxMemoryCollector::new().unwrap();
+        let pid = child.pid as u32;
+
+        assert!(collector.read_memory(pid, 0, 4096).is_err());
+        assert!(collector.denied.lock().unwrap().is_empty());
+        assert!(collector.read_memory(pid, child.address, 16).is_ok());
+    }
+
+    #[test]
+    fn test_explain_denial() {
+        assert!(explain_denial(Some(1), false).contains("prctl(PR_SET_PTRACER)"));
+        assert!(explain_denial(Some(2), false).contains("requires CAP_SYS_PTRACE"));
+        assert!(explain_denial(Some(3), true).contains("different user namespace"));
+        assert!(explain_denial(Some(0), false).contains("run as root"));
+        assert!(explain_denial(None, false).starts_with("Permission denied"));
+    }
+}
@@ -49,6 +49,8 @@ use crate::collectors::memory::models::{
 };
 use crate::collectors::memory::platforms::MemoryCollectorImpl;
 use crate::collectors::volatile::models::ProcessInfo;
+#[cfg(feature = "memory_collection")]
+use crate::errors::CollectorError;
 
 /// macOS memory collector implementation
 pub struct MacOSMemoryCollector {
@@ -148,6 +150,7 @@ impl MemoryCollectorImpl for MacOSMemoryCollector {
                     mapped_file: None, // We'll try to fill this in from modules later
                     dumped: false,
                     dump_path: None,
+                    gaps: Vec::new(),
                 };
 
                 regions.push(region);
@@ -208,12 +211,14 @@ impl MemoryCollectorImpl for MacOSMemoryCollector {
             };
 
             if kr != KERN_SUCCESS {
-                return Err(anyhow!(
-                    "Failed to read memory at address {:x} for process {}: {}",
-                    address,
+                return Err(CollectorError::MemoryCollectionFailed {
                     pid,
-                    kr
-                ));
+                    cause: format!(
+                        "Failed to read memory at address {:x}: kern_return {}",
+                        address, kr
+                    ),
+                }
+                .into());
             }
 
             // Resize buffer to actual bytes read
@@ -35,6 +35,11 @@ pub trait MemoryCollectorImpl: Send + Sync {
         process: &ProcessInfo,
     ) -> Result<Vec<crate::collectors::memory::models::ModuleInfo>>;
 
+    /// How memory of `pid` was read, for platforms with more than one method
+    fn read_method(&self, _pid: u32) -> Option<String> {
+        None
+    }
+
     /// Search for a pattern in process memory (default implementation)
     fn search_memory(
         &self,
@@ -43,7 +48,11 @@ pub trait MemoryCollectorImpl: Send + Sync {
         _start_addr: u64,
         _end_addr: Option<u64>,
     ) -> Result<Vec<u64>> {
-        anyhow::bail!("Memory searching not implemented for this platform")
+        Err(crate::errors::CollectorError::PlatformNotSupported {
+            artifact_type: "Memory searching".to_string(),
+            platform: std::env::consts::OS.to_string(),
+        }
+        .into())
     }
 
     /// YARA scan of process memory (default implementation)
@@ -55,7 +64,11 @@ pub trait MemoryCollectorImpl: Send + Sync {
         _start_addr: u64,
         _end_addr: Option<u64>,
     ) -> Result<Vec<String>> {
-        anyhow::bail!("YARA scanning not implemented for this platform")
+        Err(crate::errors::CollectorError::PlatformNotSupported {
+            artifact_type: "YARA scanning".to_string(),
+            platform: std::env::consts::OS.to_string(),
+        }
+        .into())
     }
 }
 
@@ -100,6 +100,7 @@ impl MemoryCollectorImpl for WindowsMemoryCollector {
                     },
                     dumped: false,
                     dump_path: None,
+                    gaps: Vec::new(),
                 };
 
                 regions.push(region);
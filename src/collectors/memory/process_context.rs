@@ -0,0 +1,382 @@
+//! Thread and file-descriptor context of a process (Linux)
+//!
+//! A raw memory dump says little about what a process was doing. This module
+//! reads the `/proc/<pid>` entries that describe it - per-thread status and
+//! kernel stacks, the fd table, mappings, environment, command line and
+//! cgroups - into a [`ProcessContext`] that is written as `context.json` in
+//! the process's output directory.
+//!
+//! The readers take the proc root as a parameter so they can be pointed at a
+//! fixture tree in tests. A source that cannot be read (kernel stacks need
+//! root, and processes may exit mid-read) is noted in `errors` rather than
+//! failing the whole context.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::collectors::memory::models::{
+    CgroupMembership, FileDescriptor, MemoryMapping, ProcessContext, ThreadContext,
+};
+
+/// File name of the context written in each process directory
+pub const PROCESS_CONTEXT_FILE: &str = "context.json";
+
+/// Read the context of `pid` from the proc filesystem at `proc_root`
+pub fn read_process_context(proc_root: &Path, pid: u32) -> ProcessContext {
+    let process_dir = proc_root.join(pid.to_string());
+    let mut context = ProcessContext {
+        pid,
+        ..Default::default()
+    };
+    let mut errors = Vec::new();
+
+    if let Some(data) = read_bytes(&process_dir.join("cmdline"), &mut errors) {
+        context.cmdline = parse_nul_separated(&data);
+    }
+    if let Some(data) = read_bytes(&process_dir.join("environ"), &mut errors) {
+        context.environ = parse_nul_separated(&data);
+    }
+    context.threads = read_threads(&process_dir, &mut errors);
+    context.file_descriptors = read_file_descriptors(&process_dir, &mut errors);
+    if let Some(text) = read_text(&process_dir.join("maps"), &mut errors) {
+        context.maps = parse_maps(&text);
+    }
+    if let Some(text) = read_text(&process_dir.join("smaps_rollup"), &mut errors) {
+        context.smaps_rollup = parse_smaps_rollup(&text);
+    }
+    if let Some(text) = read_text(&process_dir.join("cgroup"), &mut errors) {
+        context.cgroups = parse_cgroup(&text);
+    }
+
+    context.errors = errors;
+    context
+}
+
+/// The value of a read of `path`, noting the error when it failed
+fn read_source<T>(path: &Path, result: io::Result<T>, errors: &mut Vec<String>) -> Option<T> {
+    match result {
+        Ok(data) => Some(data),
+        Err(e) => {
+            errors.push(format!("{}: {}", path.display(), e));
+            None
+        }
+    }
+}
+
+fn read_bytes(path: &Path, errors: &mut Vec<String>) -> Option<Vec<u8>> {
+    read_source(path, fs::read(path), errors)
+}
+
+fn read_text(path: &Path, errors: &mut Vec<String>) -> Option<String> {
+    read_bytes(path, errors).map(|data| String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Numeric entries of a `/proc` directory such as `task` or `fd`, sorted
+fn numeric_entries(dir: &Path, errors: &mut Vec<String>) -> Vec<u32> {
+    let Some(entries) = read_source(dir, fs::read_dir(dir), errors) else {
+        return Vec::new();
+    };
+    let mut numbers: Vec<u32> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    numbers.sort_unstable();
+    numbers
+}
+
+fn read_threads(process_dir: &Path, errors: &mut Vec<String>) -> Vec<ThreadContext> {
+    let task_dir = process_dir.join("task");
+    numeric_entries(&task_dir, errors)
+        .into_iter()
+        .map(|tid| {
+            let thread_dir = task_dir.join(tid.to_string());
+            let status = read_text(&thread_dir.join("status"), errors)
+                .map(|text| parse_status(&text))
+                .unwrap_or_default();
+            let kernel_stack =
+                read_text(&thread_dir.join("stack"), errors).map(|text| parse_kernel_stack(&text));
+            ThreadContext {
+                tid,
+                name: status.get("Name").cloned(),
+                state: status.get("State").cloned(),
+                status,
+                kernel_stack,
+            }
+        })
+        .collect()
+}
+
+fn read_file_descriptors(process_dir: &Path, errors: &mut Vec<String>) -> Vec<FileDescriptor> {
+    let fd_dir = process_dir.join("fd");
+    numeric_entries(&fd_dir, errors)
+        .into_iter()
+        .filter_map(|fd| {
+            // A descriptor closed since the listing has no link to read
+            let link = fd_dir.join(fd.to_string());
+            let target = read_source(&link, fs::read_link(&link), errors)?;
+            Some(FileDescriptor {
+                fd,
+                target: target.to_string_lossy().into_owned(),
+            })
+        })
+        .collect()
+}
+
+/// `Key:\tvalue` lines of a `status` file
+pub fn parse_status(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Frames of a kernel `stack` file, without the `[<0>]` address prefix
+pub fn parse_kernel_stack(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| match line.split_once("] ") {
+            Some((prefix, frame)) if prefix.starts_with("[<") => frame.trim(),
+            _ => line.trim(),
+        })
+        .filter(|frame| !frame.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Entries of a NUL-separated file such as `cmdline` or `environ`
+pub fn parse_nul_separated(data: &[u8]) -> Vec<String> {
+    data.split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect()
+}
+
+/// Lines of a `maps` file; malformed lines are skipped
+pub fn parse_maps(text: &str) -> Vec<MemoryMapping> {
+    text.lines().filter_map(parse_maps_line).collect()
+}
+
+fn parse_maps_line(line: &str) -> Option<MemoryMapping> {
+    let mut rest = line.trim_start();
+    let mut fields = Vec::with_capacity(5);
+    for _ in 0..5 {
+        let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if field.is_empty() {
+            return None;
+        }
+        fields.push(field);
+        rest = tail.trim_start();
+    }
+
+    let (start, end) = fields[0].split_once('-')?;
+    // The pathname keeps its inner spaces; only the padding before it goes
+    let pathname = rest.trim_end();
+    Some(MemoryMapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        permissions: fields[1].to_string(),
+        offset: u64::from_str_radix(fields[2], 16).ok()?,
+        device: fields[3].to_string(),
+        inode: fields[4].parse().ok()?,
+        pathname: (!pathname.is_empty()).then(|| pathname.to_string()),
+    })
+}
+
+/// `Key:   value kB` lines of `smaps_rollup`, skipping the mapping header
+pub fn parse_smaps_rollup(text: &str) -> BTreeMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim().trim_end_matches("kB").trim();
+            Some((key.trim().to_string(), value.parse().ok()?))
+        })
+        .collect()
+}
+
+/// `id:controllers:path` lines of a `cgroup` file
+pub fn parse_cgroup(text: &str) -> Vec<CgroupMembership> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let hierarchy_id = parts.next()?.parse().ok()?;
+            let controllers = parts
+                .next()?
+                .split(',')
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+                .collect();
+            Some(CgroupMembership {
+                hierarchy_id,
+                controllers,
+                path: parts.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_proc() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/process_context/proc")
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = parse_status(include_str!(
+            "../../../tests/fixtures/process_context/proc/4242/task/4242/status"
+        ));
+        assert_eq!(status.get("Name").map(String::as_str), Some("nginx"));
+        assert_eq!(
+            status.get("State").map(String::as_str),
+            Some("S (sleeping)")
+        );
+        assert_eq!(
+            status.get("Uid").map(String::as_str),
+            Some("33\t33\t33\t33")
+        );
+        assert_eq!(
+            status.get("Cpus_allowed_list").map(String::as_str),
+            Some("0-3")
+        );
+    }
+
+    #[test]
+    fn test_parse_kernel_stack() {
+        let stack = parse_kernel_stack(include_str!(
+            "../../../tests/fixtures/process_context/proc/4242/task/4242/stack"
+        ));
+        assert_eq!(
+            stack,
+            vec![
+                "ep_poll+0x2ba/0x2f0",
+                "do_epoll_wait+0xb8/0xd0",
+                "__x64_sys_epoll_wait+0x5d/0xa0",
+                "do_syscall_64+0x5b/0x80",
+                "entry_SYSCALL_64_after_hwframe+0x6e/0xd8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_maps() {
+        let maps = parse_maps(include_str!(
+            "../../../tests/fixtures/process_context/proc/4242/maps"
+        ));
+        assert_eq!(maps.len(), 5);
+        assert_eq!(
+            maps[0],
+            MemoryMapping {
+                start: 0x55d4c8a00000,
+                end: 0x55d4c8a2e000,
+                permissions: "r--p".to_string(),
+                offset: 0,
+                device: "08:01".to_string(),
+                inode: 1837402,
+                pathname: Some("/usr/sbin/nginx".to_string()),
+            }
+        );
+        assert_eq!(maps[2].pathname.as_deref(), Some("[heap]"));
+        assert_eq!(maps[3].pathname, None);
+        assert_eq!(
+            maps[4].pathname.as_deref(),
+            Some("/tmp/upload cache (deleted)")
+        );
+    }
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let rollup = parse_smaps_rollup(include_str!(
+            "../../../tests/fixtures/process_context/proc/4242/smaps_rollup"
+        ));
+        assert_eq!(rollup.get("Rss"), Some(&11204));
+        assert_eq!(rollup.get("Swap"), Some(&0));
+        assert!(!rollup.keys().any(|k| k.contains('-')));
+    }
+
+    #[test]
+    fn test_parse_cgroup() {
+        let cgroups = parse_cgroup(include_str!(
+            "../../../tests/fixtures/process_context/proc/4242/cgroup"
+        ));
+        assert_eq!(
+            cgroups,
+            vec![
+                CgroupMembership {
+                    hierarchy_id: 4,
+                    controllers: vec!["cpu".to_string(), "cpuacct".to_string()],
+                    path: "/system.slice/nginx.service".to_string(),
+                },
+                CgroupMembership {
+                    hierarchy_id: 0,
+                    controllers: Vec::new(),
+                    path: "/system.slice/nginx.service".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_process_context_from_fixture() {
+        let context = read_process_context(&fixture_proc(), 4242);
+
+        assert_eq!(context.pid, 4242);
+        assert_eq!(
+            context.cmdline,
+            vec!["nginx: worker process", "-g", "daemon off;"]
+        );
+        assert_eq!(
+            context.environ,
+            vec!["PATH=/usr/sbin:/usr/bin", "LANG=C.UTF-8"]
+        );
+        assert_eq!(context.threads.len(), 2);
+        assert_eq!(context.threads[0].tid, 4242);
+        assert_eq!(context.threads[1].name.as_deref(), Some("nginx-aio"));
+        assert_eq!(
+            context.threads[0].kernel_stack.as_ref().map(Vec::len),
+            Some(5)
+        );
+        // The second thread has no readable stack, as without root
+        assert_eq!(context.threads[1].kernel_stack, None);
+        assert!(context.errors.iter().any(|e| e.contains("task/4243/stack")));
+        assert_eq!(context.maps.len(), 5);
+        assert_eq!(context.cgroups.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_file_descriptors() {
+        use std::os::unix::fs::symlink;
+
+        let root = tempfile::tempdir().unwrap();
+        let fd_dir = root.path().join("7").join("fd");
+        fs::create_dir_all(&fd_dir).unwrap();
+        symlink("/dev/null", fd_dir.join("0")).unwrap();
+        symlink("socket:[31337]", fd_dir.join("10")).unwrap();
+        symlink("/var/log/app.log", fd_dir.join("2")).unwrap();
+
+        let context = read_process_context(root.path(), 7);
+        assert_eq!(
+            context.file_descriptors,
+            vec![
+                FileDescriptor {
+                    fd: 0,
+                    target: "/dev/null".to_string(),
+                },
+                FileDescriptor {
+                    fd: 2,
+                    target: "/var/log/app.log".to_string(),
+                },
+                FileDescriptor {
+                    fd: 10,
+                    target: "socket:[31337]".to_string(),
+                },
+            ]
+        );
+        // Everything else is missing from this tree and reported as such
+        assert!(context.errors.iter().any(|e| e.contains("cmdline")));
+    }
+}
@@ -0,0 +1,405 @@
+//! Dump of a single address range from a process (`--dump-memory-region`).
+//!
+//! The range is read through [`MemoryCollectorImpl::read_memory`] in chunks
+//! and written to a raw dump whose offsets map directly to addresses: pages
+//! that cannot be read (guard pages, unmapped gaps) are zero-filled rather
+//! than skipped. A sidecar `.hex` file holds a canonical hex dump of the same
+//! bytes, addressed by virtual address, for quick review without a hex editor.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+
+use crate::collectors::memory::models::MemoryGap;
+use crate::collectors::memory::platforms::MemoryCollectorImpl;
+use crate::models::{ArtifactMetadata, MemoryRegionSource};
+
+/// Bytes requested from the platform reader at a time
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Granularity used to retry a chunk that could not be read in one go
+const PAGE_SIZE: usize = 4096;
+
+/// Bytes shown per hex dump line
+const HEX_LINE_WIDTH: usize = 16;
+
+/// Parse a `<pid>:<start_hex>-<end_hex>` region spec.
+///
+/// Addresses are hexadecimal with an optional `0x` prefix; the end address
+/// is exclusive.
+pub fn parse_region_spec(spec: &str) -> Result<(u32, u64, u64)> {
+    let (pid, range) = spec.split_once(':').context(format!(
+        "Invalid memory region '{}': expected <pid>:<start_hex>-<end_hex>",
+        spec
+    ))?;
+    let (start, end) = range.split_once('-').context(format!(
+        "Invalid memory region '{}': expected <pid>:<start_hex>-<end_hex>",
+        spec
+    ))?;
+
+    let pid = pid
+        .trim()
+        .parse::<u32>()
+        .context(format!("Invalid PID in memory region '{}'", spec))?;
+    let start = parse_hex_address(start).context(format!("Invalid start address in '{}'", spec))?;
+    let end = parse_hex_address(end).context(format!("Invalid end address in '{}'", spec))?;
+
+    if end <= start {
+        bail!(
+            "Invalid memory region '{}': end address must be greater than start address",
+            spec
+        );
+    }
+    Ok((pid, start, end))
+}
+
+fn parse_hex_address(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    Ok(u64::from_str_radix(digits, 16)?)
+}
+
+/// Dump `start_addr..end_addr` of process `pid` to `output_path`.
+///
+/// Writes the raw bytes to `output_path` and a hex dump next to it with a
+/// `.hex` extension. The returned metadata records the range in
+/// `memory_region`; its `dump_size` counts the bytes actually read, so it is
+/// smaller than `file_size` when pages had to be zero-filled.
+pub fn dump_memory_region(
+    collector: &dyn MemoryCollectorImpl,
+    pid: u32,
+    start_addr: u64,
+    end_addr: u64,
+    output_path: &Path,
+) -> Result<ArtifactMetadata> {
+    if end_addr <= start_addr {
+        bail!(
+            "Invalid memory region {:#x}-{:#x}: end address must be greater than start address",
+            start_addr,
+            end_addr
+        );
+    }
+
+    info!(
+        "Dumping memory region {:#x}-{:#x} of process {} to {}",
+        start_addr,
+        end_addr,
+        pid,
+        output_path.display()
+    );
+
+    let hex_path = output_path.with_extension("hex");
+    let mut raw = BufWriter::new(File::create(output_path).context(format!(
+        "Failed to create memory dump file: {}",
+        output_path.display()
+    ))?);
+    let mut hex = BufWriter::new(File::create(&hex_path).context(format!(
+        "Failed to create hex dump file: {}",
+        hex_path.display()
+    ))?);
+
+    let mut address = start_addr;
+    let mut bytes_read = 0u64;
+    while address < end_addr {
+        let size = (end_addr - address).min(CHUNK_SIZE as u64) as usize;
+        let (chunk, gaps) = read_chunk(collector, pid, address, size);
+        bytes_read += (size as u64) - gaps.iter().map(|g| g.size).sum::<u64>();
+
+        raw.write_all(&chunk).context(format!(
+            "Failed to write memory data to file: {}",
+            output_path.display()
+        ))?;
+        write_hex_dump(&mut hex, address, &chunk)
+            .context(format!("Failed to write hex dump: {}", hex_path.display()))?;
+
+        address += size as u64;
+    }
+    raw.flush()?;
+    hex.flush()?;
+
+    let total = end_addr - start_addr;
+    if bytes_read == 0 {
+        bail!(
+            "No memory could be read from process {} in {:#x}-{:#x}",
+            pid,
+            start_addr,
+            end_addr
+        );
+    }
+    if bytes_read < total {
+        warn!(
+            "{} of {} bytes in {:#x}-{:#x} of process {} were unreadable and zero-filled",
+            total - bytes_read,
+            total,
+            start_addr,
+            end_addr,
+            pid
+        );
+    }
+
+    Ok(ArtifactMetadata {
+        original_path: format!("memory://{}/{:#x}-{:#x}", pid, start_addr, end_addr),
+        collection_time: Utc::now().to_rfc3339(),
+        file_size: total,
+        created_time: None,
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        memory_region: Some(MemoryRegionSource {
+            pid,
+            start_address: start_addr,
+            end_address: end_addr,
+            dump_size: bytes_read,
+        }),
+        ..Default::default()
+    })
+}
+
+/// Read `size` bytes at `address` in chunks, zero-filling what cannot be
+/// read. Returns the bytes and the unreadable ranges, with offsets relative
+/// to `address`.
+pub(crate) fn read_range(
+    collector: &dyn MemoryCollectorImpl,
+    pid: u32,
+    address: u64,
+    size: usize,
+) -> (Vec<u8>, Vec<MemoryGap>) {
+    let mut data = Vec::with_capacity(size);
+    let mut gaps: Vec<MemoryGap> = Vec::new();
+    for offset in (0..size).step_by(CHUNK_SIZE) {
+        let len = CHUNK_SIZE.min(size - offset);
+        let (chunk, chunk_gaps) = read_chunk(collector, pid, address + offset as u64, len);
+        data.extend_from_slice(&chunk);
+        for gap in chunk_gaps {
+            push_gap(&mut gaps, offset as u64 + gap.offset, gap.size, gap.reason);
+        }
+    }
+    (data, gaps)
+}
+
+/// Read `size` bytes at `address`, falling back to page-sized reads when the
+/// whole chunk fails. Returns the bytes (unreadable pages zeroed) and the
+/// unreadable ranges relative to `address`.
+fn read_chunk(
+    collector: &dyn MemoryCollectorImpl,
+    pid: u32,
+    address: u64,
+    size: usize,
+) -> (Vec<u8>, Vec<MemoryGap>) {
+    match collector.read_memory(pid, address, size) {
+        Ok(data) if data.len() == size => return (data, Vec::new()),
+        Ok(data) => debug!(
+            "Short read at {:#x} ({} of {} bytes), retrying by page",
+            address,
+            data.len(),
+            size
+        ),
+        Err(e) => debug!("Read at {:#x} failed ({}), retrying by page", address, e),
+    }
+
+    let mut chunk = vec![0u8; size];
+    let mut gaps = Vec::new();
+    for offset in (0..size).step_by(PAGE_SIZE) {
+        let len = PAGE_SIZE.min(size - offset);
+        match collector.read_memory(pid, address + offset as u64, len) {
+            Ok(data) => {
+                let read = len.min(data.len());
+                chunk[offset..offset + read].copy_from_slice(&data[..read]);
+                if read < len {
+                    push_gap(
+                        &mut gaps,
+                        (offset + read) as u64,
+                        (len - read) as u64,
+                        "short read".to_string(),
+                    );
+                }
+            }
+            Err(e) => push_gap(&mut gaps, offset as u64, len as u64, format!("{:#}", e)),
+        }
+    }
+    (chunk, gaps)
+}
+
+/// Append a gap, merging it into the previous one when they touch and
+/// share a reason
+fn push_gap(gaps: &mut Vec<MemoryGap>, offset: u64, size: u64, reason: String) {
+    if let Some(last) = gaps.last_mut() {
+        if last.offset + last.size == offset && last.reason == reason {
+            last.size += size;
+            return;
+        }
+    }
+    gaps.push(MemoryGap {
+        offset,
+        size,
+        reason,
+    });
+}
+
+/// Write `data` as `address  hex bytes  |ascii|` lines
+fn write_hex_dump(writer: &mut impl Write, address: u64, data: &[u8]) -> std::io::Result<()> {
+    for (index, line) in data.chunks(HEX_LINE_WIDTH).enumerate() {
+        write!(
+            writer,
+            "{:016x}  ",
+            address + (index * HEX_LINE_WIDTH) as u64
+        )?;
+        for column in 0..HEX_LINE_WIDTH {
+            match line.get(column) {
+                Some(byte) => write!(writer, "{:02x} ", byte)?,
+                None => write!(writer, "   ")?,
+            }
+            if column == HEX_LINE_WIDTH / 2 - 1 {
+                write!(writer, " ")?;
+            }
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(writer, " |{}|", ascii)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::memory::models::{MemoryRegionInfo, ModuleInfo};
+    use crate::collectors::volatile::models::ProcessInfo;
+    use tempfile::TempDir;
+
+    /// Serves `address & 0xff` for every byte, except inside `hole`
+    struct PatternReader {
+        hole: std::ops::Range<u64>,
+    }
+
+    impl MemoryCollectorImpl for PatternReader {
+        fn new() -> Result<Self> {
+            Ok(Self { hole: 0..0 })
+        }
+
+        fn get_memory_regions(&self, _process: &ProcessInfo) -> Result<Vec<MemoryRegionInfo>> {
+            Ok(Vec::new())
+        }
+
+        fn read_memory(&self, _pid: u32, address: u64, size: usize) -> Result<Vec<u8>> {
+            let end = address + size as u64;
+            if address < self.hole.end && self.hole.start < end {
+                bail!("unreadable");
+            }
+            Ok((address..end).map(|a| (a & 0xff) as u8).collect())
+        }
+
+        fn get_modules(&self, _process: &ProcessInfo) -> Result<Vec<ModuleInfo>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_parse_region_spec() {
+        assert_eq!(
+            parse_region_spec("1234:0x400000-0x401000").unwrap(),
+            (1234, 0x400000, 0x401000)
+        );
+        assert_eq!(
+            parse_region_spec("1:7ffe0000-7FFE1000").unwrap(),
+            (1, 0x7ffe0000, 0x7ffe1000)
+        );
+        assert!(parse_region_spec("1234:0x400000:4096").is_err());
+        assert!(parse_region_spec("abc:0x1000-0x2000").is_err());
+        assert!(parse_region_spec("1234:0x2000-0x1000").is_err());
+    }
+
+    #[test]
+    fn test_dump_memory_region() {
+        let temp = TempDir::new().unwrap();
+        let output = temp.path().join("region.dmp");
+        let reader = PatternReader::new().unwrap();
+
+        let metadata = dump_memory_region(&reader, 42, 0x1000, 0x1020, &output).unwrap();
+
+        let raw = std::fs::read(&output).unwrap();
+        assert_eq!(raw.len(), 0x20);
+        assert_eq!(raw[0], 0x00);
+        assert_eq!(raw[0x1f], 0x1f);
+        assert_eq!(metadata.file_size, 0x20);
+        let region = metadata.memory_region.unwrap();
+        assert_eq!(
+            (
+                region.pid,
+                region.start_address,
+                region.end_address,
+                region.dump_size
+            ),
+            (42, 0x1000, 0x1020, 0x20)
+        );
+
+        let hex = std::fs::read_to_string(temp.path().join("region.hex")).unwrap();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("0000000000001010  10 11 12 13 14 15 16 17  18 19"));
+        assert!(lines[1].ends_with("|................|"));
+    }
+
+    #[test]
+    fn test_unreadable_pages_zero_filled() {
+        let temp = TempDir::new().unwrap();
+        let output = temp.path().join("region.dmp");
+        let reader = PatternReader {
+            hole: 0x11000..0x12000,
+        };
+
+        let metadata = dump_memory_region(&reader, 42, 0x10000, 0x13000, &output).unwrap();
+
+        let raw = std::fs::read(&output).unwrap();
+        assert_eq!(raw.len(), 0x3000);
+        assert_eq!(raw[0x0fff], 0xff);
+        assert!(raw[0x1000..0x2000].iter().all(|&b| b == 0));
+        assert_eq!(raw[0x2001], 0x01);
+        assert_eq!(metadata.memory_region.unwrap().dump_size, 0x2000);
+
+        let unreadable = PatternReader { hole: 0..u64::MAX };
+        assert!(dump_memory_region(&unreadable, 42, 0x1000, 0x2000, &output).is_err());
+    }
+
+    #[test]
+    fn test_read_range_records_gaps() {
+        let reader = PatternReader {
+            hole: 0x102000..0x104000,
+        };
+
+        // Spans two chunks; the hole is two pages in the second one
+        let (data, gaps) = read_range(&reader, 42, 0x1000, CHUNK_SIZE + 0x4000);
+        assert_eq!(data.len(), CHUNK_SIZE + 0x4000);
+        assert_eq!(data[0x10], 0x10);
+        assert!(data[0x101000..0x103000].iter().all(|&b| b == 0));
+        assert_eq!(data[0x103001], 0x01);
+        assert_eq!(
+            gaps,
+            vec![MemoryGap {
+                offset: 0x101000,
+                size: 0x2000,
+                reason: "unreadable".to_string(),
+            }]
+        );
+
+        let (data, gaps) = read_range(&reader, 42, 0x200000, 0x3000);
+        assert_eq!(data.len(), 0x3000);
+        assert!(gaps.is_empty());
+    }
+}
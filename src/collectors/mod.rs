@@ -62,6 +62,12 @@ pub mod collector;
 /// Platform-specific collector implementations
 pub mod platforms;
 
+/// Sources artifact files are read from (local filesystem, in-memory, ...)
+pub mod source;
+
+/// Read-only opens of source files and `--verify-no-writes`
+pub mod read_only;
+
 /// Streaming upload collectors for cloud storage
 pub mod streaming;
 
@@ -79,3 +85,37 @@ pub mod regex;
 
 /// Permission error tracking and reporting
 pub mod permission_tracker;
+
+/// Cancellation of copies whose artifact timed out
+pub mod cancellation;
+
+/// Collection phase tracking for order-of-volatility mode
+pub mod phases;
+
+/// Tracking of what the collector itself changed on the target system
+pub mod footprint;
+
+/// Windows live-state collectors (ETW sessions, SMB shares), ShellBags and IIS log parsing
+pub mod windows;
+
+/// Linux live-state collectors (packet sockets) and sshd configuration
+pub mod linux;
+
+/// macOS collectors that combine file copies with live queries (keychains,
+/// Unified Log export)
+pub mod macos;
+
+/// Continuous re-collection of changed artifacts (`--watch`)
+pub mod watch;
+
+/// Skipping files unchanged since an earlier collection (`--baseline`)
+pub mod incremental;
+
+/// Offline parsers for collected artifacts (SRUM, ...)
+pub mod parsers;
+
+/// Scrubbed inventory of cloud CLI, kubeconfig and Docker credential files
+pub mod cloud_credentials;
+
+/// Inventory of Outlook, Windows Mail and Apple Mail stores across user homes
+pub mod mail_stores;
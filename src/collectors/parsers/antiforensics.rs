@@ -0,0 +1,1223 @@
+//! Anti-forensics indicators in the collected artifacts.
+//!
+//! Each check can be turned off with `antiforensics_<check>: "false"` in
+//! `global_options`:
+//!
+//! - `cleared_event_logs`: the log-cleared events 1102 (Security) and 104
+//!   (System), and Security, System or Application logs holding fewer than
+//!   [`MIN_EVENT_LOG_RECORDS`] records
+//! - `usn_journal`: a `$UsnJrnl:$J` copy that is empty or smaller than
+//!   [`MIN_USN_JOURNAL_BYTES`]; its logical size only grows, so a small
+//!   journal was deleted and recreated recently
+//! - `prefetch`: `EnablePrefetcher` set to 0, or the SysMain service
+//!   disabled, in the collected `SYSTEM` hive
+//! - `auditd`: rule files that load no rules or disable auditing, rule
+//!   removals and `audit_enabled=0` in `audit.log`, and no `auditd` in the
+//!   collected process list although auditd is installed
+//! - `shell_history`: empty history files of accounts with a login shell,
+//!   and history files that are symlinks to `/dev/null`
+//! - `timestomping`: `$MFT` records whose `$STANDARD_INFORMATION` creation
+//!   time is more than [`TIMESTOMP_TOLERANCE_HOURS`] before the `$FILE_NAME`
+//!   creation time, which ordinary APIs cannot change
+//! - `syslog_gaps`: no `syslog` or `messages` lines for longer than
+//!   `antiforensics_syslog_gap_hours` (6 by default)
+//!
+//! Everything is read from the collected copies. The one live-system read is
+//! the symlink check of history files, because a collected copy of a link
+//! to `/dev/null` is just an empty file.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::auth_unix::{split_syslog_line, TimeContext};
+use super::evtx::{self, EventRecord, XmlElement};
+use super::mft::{self, DirectoryIndex};
+use super::regf::Hive;
+use super::{find_collected_file, find_collected_files_matching, ParsedReport};
+use crate::collectors::volatile::models::ProcessInfo;
+
+/// Report file name
+pub const ANTIFORENSICS_FINDINGS_FILE: &str = "antiforensics_findings.json";
+
+/// Security, System and Application logs with fewer records than this are
+/// reported as probably cleared
+pub const MIN_EVENT_LOG_RECORDS: usize = 50;
+
+/// USN journals smaller than this (1 MiB) are reported as recently recreated
+pub const MIN_USN_JOURNAL_BYTES: u64 = 1024 * 1024;
+
+/// How far the `$STANDARD_INFORMATION` creation time may precede the
+/// `$FILE_NAME` one before the record is reported
+pub const TIMESTOMP_TOLERANCE_HOURS: i64 = 24;
+
+/// Default `antiforensics_syslog_gap_hours`
+const DEFAULT_SYSLOG_GAP_HOURS: i64 = 6;
+
+/// Timestomping findings reported per `$MFT`; the rest are only counted
+const MAX_TIMESTOMP_FINDINGS: usize = 1000;
+
+/// Logs that are written to constantly on any Windows system
+const BUSY_EVENT_LOGS: &[&str] = &["Security.evtx", "System.evtx", "Application.evtx"];
+
+/// Event log clear events and the log that records them
+const LOG_CLEARED_EVENTS: &[(&str, u32)] = &[("Security.evtx", 1102), ("System.evtx", 104)];
+
+/// Values under `ControlSetXXX`
+const PREFETCH_PARAMETERS: &str = r"Control\Session Manager\Memory Management\PrefetchParameters";
+const SYSMAIN_SERVICE: &str = r"Services\SysMain";
+const SERVICE_DISABLED: u32 = 4;
+
+/// Names of collected shell history files
+const HISTORY_FILES: &[&str] = &[".bash_history", ".zsh_history", ".sh_history"];
+
+/// Shells of accounts that cannot log in
+const NOLOGIN_SHELLS: &[&str] = &["nologin", "false", "sync", "shutdown", "halt"];
+
+/// Log files whose gaps are looked for, besides their rotations
+const SYSLOG_FILES: &[&str] = &["syslog", "messages"];
+
+lazy_static! {
+    static ref OPTIONS: RwLock<AntiForensicsOptions> =
+        RwLock::new(AntiForensicsOptions::default());
+    /// `msg=audit(1700000000.123:456)`
+    static ref AUDIT_TIME: Regex = Regex::new(r"msg=audit\((\d+)(?:\.\d+)?:\d+\)").unwrap();
+}
+
+/// An anti-forensics check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AntiForensicsCheck {
+    ClearedEventLogs,
+    UsnJournal,
+    Prefetch,
+    Auditd,
+    ShellHistory,
+    Timestomping,
+    SyslogGaps,
+}
+
+impl AntiForensicsCheck {
+    pub const ALL: [Self; 7] = [
+        Self::ClearedEventLogs,
+        Self::UsnJournal,
+        Self::Prefetch,
+        Self::Auditd,
+        Self::ShellHistory,
+        Self::Timestomping,
+        Self::SyslogGaps,
+    ];
+
+    /// Name used in reports and in the `antiforensics_<name>` option
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ClearedEventLogs => "cleared_event_logs",
+            Self::UsnJournal => "usn_journal",
+            Self::Prefetch => "prefetch",
+            Self::Auditd => "auditd",
+            Self::ShellHistory => "shell_history",
+            Self::Timestomping => "timestomping",
+            Self::SyslogGaps => "syslog_gaps",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// A sign that evidence was removed or disguised
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AntiForensicsFinding {
+    pub check: AntiForensicsCheck,
+    pub severity: Severity,
+    /// Collected file the finding is based on
+    pub evidence: String,
+    pub description: String,
+    /// When the indicated action happened, if known
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Which checks run and their thresholds
+#[derive(Debug, Clone)]
+pub struct AntiForensicsOptions {
+    pub disabled: HashSet<AntiForensicsCheck>,
+    pub syslog_gap: Duration,
+}
+
+impl Default for AntiForensicsOptions {
+    fn default() -> Self {
+        Self {
+            disabled: HashSet::new(),
+            syslog_gap: Duration::hours(DEFAULT_SYSLOG_GAP_HOURS),
+        }
+    }
+}
+
+impl AntiForensicsOptions {
+    pub fn enabled(&self, check: AntiForensicsCheck) -> bool {
+        !self.disabled.contains(&check)
+    }
+}
+
+/// Apply `antiforensics_<check>` and `antiforensics_syslog_gap_hours` from
+/// `global_options`
+pub fn configure(global_options: &HashMap<String, String>) {
+    let mut options = AntiForensicsOptions::default();
+    for check in AntiForensicsCheck::ALL {
+        let key = format!("antiforensics_{}", check.name());
+        if let Some(value) = global_options.get(&key) {
+            match value.trim().parse::<bool>() {
+                Ok(true) => {}
+                Ok(false) => {
+                    options.disabled.insert(check);
+                }
+                Err(_) => warn!("Invalid {} '{}'; check enabled", key, value),
+            }
+        }
+    }
+    if let Some(value) = global_options.get("antiforensics_syslog_gap_hours") {
+        match value.trim().parse::<i64>() {
+            Ok(hours) if hours > 0 => options.syslog_gap = Duration::hours(hours),
+            _ => warn!(
+                "Invalid antiforensics_syslog_gap_hours '{}'; using {}",
+                value, DEFAULT_SYSLOG_GAP_HOURS
+            ),
+        }
+    }
+    if let Ok(mut current) = OPTIONS.write() {
+        *current = options;
+    }
+}
+
+/// The options set by [`configure`]
+pub fn options() -> AntiForensicsOptions {
+    OPTIONS
+        .read()
+        .map(|options| options.clone())
+        .unwrap_or_default()
+}
+
+/// Collected files the checks read
+#[derive(Debug, Clone, Default)]
+pub struct AntiForensicsSources {
+    /// Directory collected files are stored under by their original path
+    pub fs_dir: PathBuf,
+    pub event_logs: Vec<PathBuf>,
+    pub usn_journals: Vec<PathBuf>,
+    pub system_hive: Option<PathBuf>,
+    pub audit_logs: Vec<PathBuf>,
+    pub audit_rules: Vec<PathBuf>,
+    pub process_list: Option<PathBuf>,
+    /// Maps history files to their accounts
+    pub passwd: Option<PathBuf>,
+    pub shell_histories: Vec<PathBuf>,
+    pub mft: Vec<PathBuf>,
+    pub syslogs: Vec<PathBuf>,
+}
+
+impl AntiForensicsSources {
+    /// Whether any check has input; `passwd` and the process list alone are
+    /// not checked
+    pub fn is_empty(&self) -> bool {
+        self.event_logs.is_empty()
+            && self.usn_journals.is_empty()
+            && self.system_hive.is_none()
+            && self.audit_logs.is_empty()
+            && self.audit_rules.is_empty()
+            && self.shell_histories.is_empty()
+            && self.mft.is_empty()
+            && self.syslogs.is_empty()
+    }
+
+    pub fn all(&self) -> Vec<PathBuf> {
+        self.event_logs
+            .iter()
+            .chain(&self.usn_journals)
+            .chain(&self.system_hive)
+            .chain(&self.audit_logs)
+            .chain(&self.audit_rules)
+            .chain(&self.process_list)
+            .chain(&self.passwd)
+            .chain(&self.shell_histories)
+            .chain(&self.mft)
+            .chain(&self.syslogs)
+            .cloned()
+            .collect()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `name`, `name.1` or `name-20240301`
+fn is_log_or_rotation(name: &str, base: &str) -> bool {
+    name.strip_prefix(base).is_some_and(|rest| {
+        rest.is_empty()
+            || rest
+                .strip_prefix('.')
+                .or_else(|| rest.strip_prefix('-'))
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Find the collected inputs of every check under `artifact_dir`
+pub fn find_sources(artifact_dir: &Path) -> AntiForensicsSources {
+    let named = |names: &'static [&'static str]| {
+        move |path: &Path| {
+            let name = file_name(path);
+            names.iter().any(|n| name.eq_ignore_ascii_case(n))
+        }
+    };
+    AntiForensicsSources {
+        fs_dir: artifact_dir.join("fs"),
+        event_logs: find_collected_files_matching(artifact_dir, |path| {
+            file_name(path).to_ascii_lowercase().ends_with(".evtx")
+        }),
+        usn_journals: find_collected_files_matching(artifact_dir, named(&["UsnJrnl", "$J"])),
+        system_hive: find_collected_file(artifact_dir, "SYSTEM"),
+        audit_logs: find_collected_files_matching(artifact_dir, |path| {
+            is_log_or_rotation(&file_name(path), "audit.log")
+        }),
+        audit_rules: find_collected_files_matching(artifact_dir, |path| {
+            let name = file_name(path);
+            name == "audit.rules"
+                || (name.ends_with(".rules")
+                    && path.parent().is_some_and(|p| file_name(p) == "rules.d")
+                    && path.components().any(|c| c.as_os_str() == "audit"))
+        }),
+        process_list: find_collected_file(artifact_dir, "processes.json"),
+        passwd: find_collected_file(artifact_dir, "passwd"),
+        shell_histories: find_collected_files_matching(artifact_dir, named(HISTORY_FILES)),
+        mft: find_collected_files_matching(artifact_dir, named(&["MFT", "$MFT"])),
+        syslogs: find_collected_files_matching(artifact_dir, |path| {
+            let name = file_name(path);
+            SYSLOG_FILES
+                .iter()
+                .any(|base| is_log_or_rotation(&name, base))
+        }),
+    }
+}
+
+fn finding(
+    check: AntiForensicsCheck,
+    severity: Severity,
+    evidence: &Path,
+    description: String,
+    timestamp: Option<DateTime<Utc>>,
+) -> AntiForensicsFinding {
+    AntiForensicsFinding {
+        check,
+        severity,
+        evidence: evidence.to_string_lossy().to_string(),
+        description,
+        timestamp,
+    }
+}
+
+/// Run every enabled check that has input; `live_root` is where the
+/// examined system is mounted, if it is available for the history symlink
+/// check
+pub fn detect_antiforensics(
+    sources: &AntiForensicsSources,
+    options: &AntiForensicsOptions,
+    live_root: Option<&Path>,
+    time: TimeContext,
+) -> Result<ParsedReport<AntiForensicsFinding>> {
+    let mut report = ParsedReport::new(&sources.all());
+
+    for check in AntiForensicsCheck::ALL {
+        if !options.enabled(check) {
+            report
+                .notes
+                .push(format!("{} check disabled in global_options", check.name()));
+            continue;
+        }
+        match check {
+            AntiForensicsCheck::ClearedEventLogs => {
+                check_event_logs(&sources.event_logs, &mut report)
+            }
+            AntiForensicsCheck::UsnJournal => {
+                check_usn_journals(&sources.usn_journals, &mut report)
+            }
+            AntiForensicsCheck::Prefetch => {
+                if let Some(hive) = &sources.system_hive {
+                    if let Err(e) = check_prefetch(hive, &mut report) {
+                        report
+                            .notes
+                            .push(format!("Skipped {}: {:#}", hive.display(), e));
+                    }
+                }
+            }
+            AntiForensicsCheck::Auditd => check_auditd(sources, &mut report),
+            AntiForensicsCheck::ShellHistory => {
+                check_shell_history(sources, live_root, &mut report)
+            }
+            AntiForensicsCheck::Timestomping => {
+                for path in &sources.mft {
+                    if let Err(e) = check_timestomping(path, &mut report) {
+                        report
+                            .notes
+                            .push(format!("Skipped {}: {:#}", path.display(), e));
+                    }
+                }
+            }
+            AntiForensicsCheck::SyslogGaps => {
+                check_syslog_gaps(&sources.syslogs, options.syslog_gap, time, &mut report)
+            }
+        }
+    }
+
+    report.records.sort_by_key(|f| Reverse(f.severity));
+    Ok(report)
+}
+
+/// Text of the first element named `name` below `element`
+fn descendant_text(element: &XmlElement, name: &str) -> Option<String> {
+    element.children.iter().find_map(|child| {
+        if child.name == name && !child.text.trim().is_empty() {
+            Some(child.text.trim().to_string())
+        } else {
+            descendant_text(child, name)
+        }
+    })
+}
+
+/// Whether the record comes from the event log service itself; records
+/// without a provider are accepted
+fn from_eventlog_provider(record: &EventRecord) -> bool {
+    record
+        .event
+        .child("System")
+        .and_then(|system| system.child("Provider"))
+        .and_then(|provider| provider.attribute("Name"))
+        .is_none_or(|name| name.to_ascii_lowercase().contains("eventlog"))
+}
+
+fn check_event_logs(paths: &[PathBuf], report: &mut ParsedReport<AntiForensicsFinding>) {
+    for path in paths {
+        let log = match fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| evtx::parse_evtx(&data))
+        {
+            Ok(log) => log,
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {:#}", path.display(), e));
+                continue;
+            }
+        };
+        let name = file_name(path);
+
+        let clear_event = LOG_CLEARED_EVENTS
+            .iter()
+            .find(|(log_name, _)| name.eq_ignore_ascii_case(log_name))
+            .map(|(_, id)| *id);
+        if let Some(clear_event) = clear_event {
+            for record in log.records.iter().filter(|record| {
+                record.event_id() == Some(clear_event) && from_eventlog_provider(record)
+            }) {
+                // UserData/LogFileCleared on Windows; EventData in forwarded logs
+                let by = descendant_text(&record.event, "SubjectUserName")
+                    .or_else(|| record.event_data().remove("SubjectUserName"))
+                    .map(|user| format!(" by {}", user))
+                    .unwrap_or_default();
+                report.records.push(finding(
+                    AntiForensicsCheck::ClearedEventLogs,
+                    Severity::High,
+                    path,
+                    format!(
+                        "Event log cleared{} (event {} in {})",
+                        by, clear_event, name
+                    ),
+                    record.written_time,
+                ));
+            }
+        }
+
+        let busy = BUSY_EVENT_LOGS
+            .iter()
+            .any(|busy| name.eq_ignore_ascii_case(busy));
+        if busy && log.records.len() < MIN_EVENT_LOG_RECORDS {
+            let first = log.records.iter().min_by_key(|r| r.record_id);
+            report.records.push(finding(
+                AntiForensicsCheck::ClearedEventLogs,
+                Severity::Medium,
+                path,
+                format!(
+                    "{} holds only {} records{}; a log this busy is rarely this short unless it was cleared",
+                    name,
+                    log.records.len(),
+                    first
+                        .map(|r| format!(", starting at record {}", r.record_id))
+                        .unwrap_or_default()
+                ),
+                first.and_then(|r| r.written_time),
+            ));
+        }
+    }
+}
+
+fn check_usn_journals(paths: &[PathBuf], report: &mut ParsedReport<AntiForensicsFinding>) {
+    for path in paths {
+        let len = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let (severity, description) = if len == 0 {
+            (
+                Severity::High,
+                "USN journal is empty; it was deleted or journaling is disabled".to_string(),
+            )
+        } else if len < MIN_USN_JOURNAL_BYTES {
+            (
+                Severity::Medium,
+                format!(
+                    "USN journal is only {} bytes; its size only grows, so it was deleted and recreated recently",
+                    len
+                ),
+            )
+        } else {
+            continue;
+        };
+        report.records.push(finding(
+            AntiForensicsCheck::UsnJournal,
+            severity,
+            path,
+            description,
+            None,
+        ));
+    }
+}
+
+fn check_prefetch(path: &Path, report: &mut ParsedReport<AntiForensicsFinding>) -> Result<()> {
+    let hive = Hive::open(path)?;
+    let current = match hive.open_key("Select")? {
+        Some(select) => select
+            .value("Current")?
+            .and_then(|v| v.as_dword())
+            .unwrap_or(1),
+        None => 1,
+    };
+    let control_set = format!("ControlSet{:03}", current);
+
+    let parameters = format!(r"{}\{}", control_set, PREFETCH_PARAMETERS);
+    if let Some(key) = hive.open_key(&parameters)? {
+        if key.value("EnablePrefetcher")?.and_then(|v| v.as_dword()) == Some(0) {
+            report.records.push(finding(
+                AntiForensicsCheck::Prefetch,
+                Severity::Medium,
+                path,
+                format!(
+                    r"Prefetch is disabled (EnablePrefetcher=0 in {}); Windows Server disables it by default",
+                    parameters
+                ),
+                key.last_written(),
+            ));
+        }
+    }
+
+    let sysmain = format!(r"{}\{}", control_set, SYSMAIN_SERVICE);
+    if let Some(key) = hive.open_key(&sysmain)? {
+        if key.value("Start")?.and_then(|v| v.as_dword()) == Some(SERVICE_DISABLED) {
+            report.records.push(finding(
+                AntiForensicsCheck::Prefetch,
+                Severity::Medium,
+                path,
+                "The SysMain service, which writes Prefetch files, is disabled".to_string(),
+                key.last_written(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Time of an audit record, from its `msg=audit(<epoch>...)` field
+fn audit_record_time(line: &str) -> Option<DateTime<Utc>> {
+    let secs = AUDIT_TIME.captures(line)?.get(1)?.as_str().parse().ok()?;
+    Utc.timestamp_opt(secs, 0).single()
+}
+
+fn check_auditd(sources: &AntiForensicsSources, report: &mut ParsedReport<AntiForensicsFinding>) {
+    for path in &sources.audit_rules {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let rules: Vec<Vec<&str>> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        let description = if rules.iter().any(|rule| rule[..] == ["-e", "0"]) {
+            "Auditing is disabled at boot (-e 0)"
+        } else if !rules
+            .iter()
+            .any(|rule| matches!(rule[0], "-a" | "-A" | "-w"))
+        {
+            "No audit rules are loaded at boot; the rules file only flushes or configures auditd"
+        } else {
+            continue;
+        };
+        report.records.push(finding(
+            AntiForensicsCheck::Auditd,
+            Severity::Medium,
+            path,
+            description.to_string(),
+            None,
+        ));
+    }
+
+    for path in &sources.audit_logs {
+        let text = match fs::read(path) {
+            Ok(data) => String::from_utf8_lossy(&data).to_string(),
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let mut removed_rules = 0usize;
+        let mut first_removal = None;
+        for line in text.lines().filter(|l| l.contains("type=CONFIG_CHANGE")) {
+            if line.contains("op=remove_rule") {
+                removed_rules += 1;
+                first_removal = first_removal.or_else(|| audit_record_time(line));
+            } else if line.contains("audit_enabled=0") {
+                report.records.push(finding(
+                    AntiForensicsCheck::Auditd,
+                    Severity::High,
+                    path,
+                    "Auditing was disabled (audit_enabled=0)".to_string(),
+                    audit_record_time(line),
+                ));
+            }
+        }
+        if removed_rules > 0 {
+            report.records.push(finding(
+                AntiForensicsCheck::Auditd,
+                Severity::High,
+                path,
+                format!("{} audit rule(s) were removed", removed_rules),
+                first_removal,
+            ));
+        }
+    }
+
+    let installed = !sources.audit_logs.is_empty() || !sources.audit_rules.is_empty();
+    if let (true, Some(path)) = (installed, &sources.process_list) {
+        let processes = fs::read(path)
+            .context("Failed to read process list")
+            .and_then(|data| {
+                serde_json::from_slice::<Vec<ProcessInfo>>(&data)
+                    .context("Failed to parse process list")
+            });
+        match processes {
+            Ok(processes) if !processes.iter().any(|p| p.name == "auditd") => {
+                report.records.push(finding(
+                    AntiForensicsCheck::Auditd,
+                    Severity::Medium,
+                    path,
+                    "auditd was not running at collection time although its log or rules were collected"
+                        .to_string(),
+                    None,
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => report
+                .notes
+                .push(format!("Skipped {}: {:#}", path.display(), e)),
+        }
+    }
+}
+
+/// Home directory to (user, shell) from a collected `passwd`
+fn accounts_by_home(passwd: &Path) -> HashMap<PathBuf, (String, String)> {
+    fs::read_to_string(passwd)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            let home = PathBuf::from(fields.get(5)?);
+            Some((
+                home,
+                (fields.first()?.to_string(), fields.get(6)?.to_string()),
+            ))
+        })
+        .collect()
+}
+
+fn check_shell_history(
+    sources: &AntiForensicsSources,
+    live_root: Option<&Path>,
+    report: &mut ParsedReport<AntiForensicsFinding>,
+) {
+    let accounts = sources
+        .passwd
+        .as_deref()
+        .map(accounts_by_home)
+        .unwrap_or_default();
+    if !sources.shell_histories.is_empty() && accounts.is_empty() {
+        report
+            .notes
+            .push("No passwd collected; every empty history file is reported".to_string());
+    }
+
+    for path in &sources.shell_histories {
+        // The collected copy sits under the fs directory at its original path
+        let original = path
+            .strip_prefix(&sources.fs_dir)
+            .ok()
+            .map(|relative| Path::new("/").join(relative));
+        let account = original
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(|home| accounts.get(home));
+        let user = account
+            .map(|(user, _)| user.clone())
+            .unwrap_or_else(|| "an unknown user".to_string());
+
+        let link_target = live_root
+            .zip(original.as_deref())
+            .and_then(|(root, original)| {
+                fs::read_link(root.join(original.strip_prefix("/").unwrap_or(original))).ok()
+            });
+        if link_target.as_deref() == Some(Path::new("/dev/null")) {
+            report.records.push(finding(
+                AntiForensicsCheck::ShellHistory,
+                Severity::High,
+                path,
+                format!(
+                    "History file of {} is a symlink to /dev/null; commands are discarded",
+                    user
+                ),
+                None,
+            ));
+            continue;
+        }
+
+        let empty = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(false);
+        let can_log_in = account.is_none_or(|(_, shell)| {
+            !NOLOGIN_SHELLS
+                .iter()
+                .any(|nologin| shell.ends_with(&format!("/{}", nologin)) || shell == nologin)
+        });
+        if empty && can_log_in {
+            report.records.push(finding(
+                AntiForensicsCheck::ShellHistory,
+                Severity::Medium,
+                path,
+                format!(
+                    "History file of {} is empty although the account has a login shell",
+                    user
+                ),
+                fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .map(DateTime::<Utc>::from),
+            ));
+        }
+    }
+}
+
+fn check_timestomping(path: &Path, report: &mut ParsedReport<AntiForensicsFinding>) -> Result<()> {
+    let tolerance = (TIMESTOMP_TOLERANCE_HOURS * 3600 * 10_000_000) as u64;
+    let mut directories = DirectoryIndex::default();
+    let mut suspects = Vec::new();
+
+    let skipped = mft::for_each_record(path, |record| {
+        directories.add(&record);
+        if let (true, Some(si), Some(name)) = (
+            record.in_use,
+            record.standard_information,
+            record.file_name.as_ref(),
+        ) {
+            if si.created != 0 && name.times.created > si.created.saturating_add(tolerance) {
+                suspects.push((name.parent_record, name.name.clone(), si, name.times));
+            }
+        }
+    })?;
+    if skipped > 0 {
+        report.notes.push(format!(
+            "{}: {} MFT records could not be read",
+            path.display(),
+            skipped
+        ));
+    }
+    if suspects.len() > MAX_TIMESTOMP_FINDINGS {
+        report.notes.push(format!(
+            "{}: {} records look timestomped; only the first {} are reported",
+            path.display(),
+            suspects.len(),
+            MAX_TIMESTOMP_FINDINGS
+        ));
+    }
+
+    for (parent, name, si, file_name) in suspects.into_iter().take(MAX_TIMESTOMP_FINDINGS) {
+        let (Some(si_created), Some(fn_created)) = (si.created_utc(), file_name.created_utc())
+        else {
+            continue;
+        };
+        let whole_seconds = si.whole_seconds();
+        report.records.push(finding(
+            AntiForensicsCheck::Timestomping,
+            if whole_seconds {
+                Severity::High
+            } else {
+                Severity::Medium
+            },
+            path,
+            format!(
+                "{}: $STANDARD_INFORMATION creation time {} is {} days before the $FILE_NAME creation time{}",
+                directories.path(parent, &name),
+                si_created.to_rfc3339(),
+                (fn_created - si_created).num_days(),
+                if whole_seconds {
+                    ", and every $STANDARD_INFORMATION time is a whole second"
+                } else {
+                    ""
+                }
+            ),
+            Some(fn_created),
+        ));
+    }
+    Ok(())
+}
+
+fn check_syslog_gaps(
+    paths: &[PathBuf],
+    gap: Duration,
+    time: TimeContext,
+    report: &mut ParsedReport<AntiForensicsFinding>,
+) {
+    for path in paths {
+        let text = match fs::read(path) {
+            Ok(data) => String::from_utf8_lossy(&data).to_string(),
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let mut previous: Option<DateTime<Utc>> = None;
+        for line in text.lines() {
+            let Some((timestamp, _, _)) = split_syslog_line(line, time) else {
+                continue;
+            };
+            if let Some(previous) = previous.filter(|p| timestamp - *p > gap) {
+                report.records.push(finding(
+                    AntiForensicsCheck::SyslogGaps,
+                    Severity::Low,
+                    path,
+                    format!(
+                        "No messages for {:.1} hours between {} and {}; logging was stopped, the host was down, or lines were removed",
+                        (timestamp - previous).num_minutes() as f64 / 60.0,
+                        previous.to_rfc3339(),
+                        timestamp.to_rfc3339()
+                    ),
+                    Some(previous),
+                ));
+            }
+            if previous.is_none_or(|p| timestamp > p) {
+                previous = Some(timestamp);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{
+        evtx_file, mft_record, EvtxEvent, EvtxValue, HiveBuilder, HiveKey, MftRecordFixture,
+    };
+    use crate::collectors::parsers::mft::{NtfsTimes, ROOT_RECORD};
+    use tempfile::TempDir;
+
+    const FILETIME: u64 = 133_485_408_000_000_000;
+
+    fn time() -> TimeContext {
+        TimeContext {
+            utc_offset_minutes: 0,
+            reference: Utc.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap(),
+        }
+    }
+
+    fn write(path: &Path, data: impl AsRef<[u8]>) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+        path.to_path_buf()
+    }
+
+    fn detect(artifact_dir: &Path, live_root: Option<&Path>) -> Vec<AntiForensicsFinding> {
+        let sources = find_sources(artifact_dir);
+        detect_antiforensics(
+            &sources,
+            &AntiForensicsOptions::default(),
+            live_root,
+            time(),
+        )
+        .unwrap()
+        .records
+    }
+
+    fn checks(findings: &[AntiForensicsFinding]) -> Vec<(AntiForensicsCheck, Severity)> {
+        findings.iter().map(|f| (f.check, f.severity)).collect()
+    }
+
+    #[test]
+    fn test_cleared_event_logs() {
+        let temp = TempDir::new().unwrap();
+        let logs = temp.path().join("fs/Windows/System32/winevt/Logs");
+        let cleared = EvtxEvent {
+            record_id: 1,
+            written: FILETIME,
+            event_id: 1102,
+            data: vec![("SubjectUserName", EvtxValue::Text("mallory"))],
+        };
+        write(&logs.join("Security.evtx"), evtx_file(&[cleared]));
+        let busy: Vec<EvtxEvent> = (1..=MIN_EVENT_LOG_RECORDS as u64)
+            .map(|record_id| EvtxEvent {
+                record_id,
+                written: FILETIME,
+                event_id: 7036,
+                data: vec![],
+            })
+            .collect();
+        write(&logs.join("System.evtx"), evtx_file(&busy));
+
+        let findings = detect(temp.path(), None);
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (AntiForensicsCheck::ClearedEventLogs, Severity::High),
+                (AntiForensicsCheck::ClearedEventLogs, Severity::Medium),
+            ]
+        );
+        assert!(findings[0].description.contains("by mallory"));
+        assert!(findings[0].timestamp.is_some());
+        assert!(findings[1].description.contains("only 1 records"));
+        assert!(findings[1].evidence.ends_with("Security.evtx"));
+    }
+
+    #[test]
+    fn test_usn_journal() {
+        let temp = TempDir::new().unwrap();
+        write(&temp.path().join("fs/UsnJrnl"), vec![0u8; 4096]);
+        let other = TempDir::new().unwrap();
+        write(&other.path().join("fs/UsnJrnl"), b"");
+        let healthy = TempDir::new().unwrap();
+        let journal = write(&healthy.path().join("fs/UsnJrnl"), b"");
+        fs::File::options()
+            .write(true)
+            .open(journal)
+            .unwrap()
+            .set_len(MIN_USN_JOURNAL_BYTES)
+            .unwrap();
+
+        let small = detect(temp.path(), None);
+        assert_eq!(
+            checks(&small),
+            vec![(AntiForensicsCheck::UsnJournal, Severity::Medium)]
+        );
+        assert!(small[0].description.contains("4096 bytes"));
+        assert_eq!(
+            checks(&detect(other.path(), None)),
+            vec![(AntiForensicsCheck::UsnJournal, Severity::High)]
+        );
+        assert!(detect(healthy.path(), None).is_empty());
+    }
+
+    #[test]
+    fn test_prefetch_disabled() {
+        let temp = TempDir::new().unwrap();
+        let mut root = HiveKey::new("ROOT");
+        root.subkey("Select").dword_value("Current", 2);
+        let control_set = root.subkey("ControlSet002");
+        control_set
+            .subkey("Control")
+            .subkey("Session Manager")
+            .subkey("Memory Management")
+            .subkey("PrefetchParameters")
+            .dword_value("EnablePrefetcher", 0);
+        control_set
+            .subkey("Services")
+            .subkey("SysMain")
+            .dword_value("Start", SERVICE_DISABLED);
+        // The inactive control set still has Prefetch on
+        root.subkey("ControlSet001")
+            .subkey("Control")
+            .subkey("Session Manager")
+            .subkey("Memory Management")
+            .subkey("PrefetchParameters")
+            .dword_value("EnablePrefetcher", 3);
+        write(
+            &temp.path().join("fs/Windows/System32/config/SYSTEM"),
+            HiveBuilder::build(&root),
+        );
+
+        let findings = detect(temp.path(), None);
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (AntiForensicsCheck::Prefetch, Severity::Medium),
+                (AntiForensicsCheck::Prefetch, Severity::Medium),
+            ]
+        );
+        assert!(findings[0].description.contains(r"ControlSet002\Control"));
+        assert!(findings[1].description.contains("SysMain"));
+    }
+
+    #[test]
+    fn test_auditd() {
+        let temp = TempDir::new().unwrap();
+        write(
+            &temp.path().join("fs/etc/audit/audit.rules"),
+            "## flush, then nothing\n-D\n-b 8192\n",
+        );
+        write(
+            &temp.path().join("fs/var/log/audit/audit.log"),
+            "type=DAEMON_START msg=audit(1709942400.000:1): op=start\n\
+             type=CONFIG_CHANGE msg=audit(1709942500.100:7): auid=0 ses=1 op=remove_rule key=(null) list=4 res=1\n\
+             type=CONFIG_CHANGE msg=audit(1709942500.100:8): auid=0 ses=1 op=remove_rule key=(null) list=4 res=1\n\
+             type=CONFIG_CHANGE msg=audit(1709942600.000:9): audit_enabled=0 old=1 auid=0 ses=1 res=1\n",
+        );
+        let process = |name: &str| ProcessInfo {
+            pid: 1,
+            name: name.to_string(),
+            cmd: vec![],
+            exe: None,
+            status: "Run".to_string(),
+            start_time: 0,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid: None,
+        };
+        write(
+            &temp.path().join("volatile/processes.json"),
+            serde_json::to_vec(&vec![process("systemd"), process("sshd")]).unwrap(),
+        );
+
+        let findings = detect(temp.path(), None);
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (AntiForensicsCheck::Auditd, Severity::High),
+                (AntiForensicsCheck::Auditd, Severity::High),
+                (AntiForensicsCheck::Auditd, Severity::Medium),
+                (AntiForensicsCheck::Auditd, Severity::Medium),
+            ]
+        );
+        assert_eq!(
+            findings[0].timestamp,
+            Utc.timestamp_opt(1_709_942_600, 0).single()
+        );
+        assert_eq!(findings[1].description, "2 audit rule(s) were removed");
+        assert!(findings[2].description.contains("No audit rules"));
+        assert!(findings[3].description.contains("not running"));
+
+        // Loading rules with auditd running is clean
+        write(
+            &temp.path().join("fs/etc/audit/audit.rules"),
+            "-D\n-w /etc/shadow -p wa -k shadow\n",
+        );
+        fs::remove_file(temp.path().join("fs/var/log/audit/audit.log")).unwrap();
+        write(
+            &temp.path().join("volatile/processes.json"),
+            serde_json::to_vec(&vec![process("auditd")]).unwrap(),
+        );
+        assert!(detect(temp.path(), None).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_history() {
+        let temp = TempDir::new().unwrap();
+        let live = TempDir::new().unwrap();
+        write(
+            &temp.path().join("fs/etc/passwd"),
+            "root:x:0:0:root:/root:/bin/bash\n\
+             alice:x:1000:1000::/home/alice:/bin/zsh\n\
+             www-data:x:33:33::/var/www:/usr/sbin/nologin\n",
+        );
+        write(&temp.path().join("fs/root/.bash_history"), b"");
+        write(&temp.path().join("fs/home/alice/.zsh_history"), b"");
+        write(&temp.path().join("fs/var/www/.bash_history"), b"");
+        fs::create_dir_all(live.path().join("home/alice")).unwrap();
+        std::os::unix::fs::symlink("/dev/null", live.path().join("home/alice/.zsh_history"))
+            .unwrap();
+
+        let findings = detect(temp.path(), Some(live.path()));
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (AntiForensicsCheck::ShellHistory, Severity::High),
+                (AntiForensicsCheck::ShellHistory, Severity::Medium),
+            ]
+        );
+        assert!(findings[0].description.contains("alice"));
+        assert!(findings[0].description.contains("/dev/null"));
+        assert!(findings[1].description.contains("root"));
+    }
+
+    #[test]
+    fn test_timestomping() {
+        let temp = TempDir::new().unwrap();
+        let day = 864_000_000_000u64;
+        let real = NtfsTimes {
+            created: FILETIME + 1234,
+            modified: FILETIME + 1234,
+            mft_modified: FILETIME + 1234,
+            accessed: FILETIME + 1234,
+        };
+        let stomped_whole = NtfsTimes {
+            created: FILETIME - 400 * day,
+            modified: FILETIME - 400 * day,
+            mft_modified: FILETIME - 400 * day,
+            accessed: FILETIME - 400 * day,
+        };
+        let stomped = NtfsTimes {
+            created: FILETIME - 30 * day + 5,
+            ..real
+        };
+        let records = [
+            MftRecordFixture {
+                record_number: 64,
+                directory: true,
+                file_name: Some((ROOT_RECORD, "Windows")),
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 65,
+                standard_information: Some(stomped_whole),
+                file_name: Some((64, "evil.dll")),
+                file_name_times: real,
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 66,
+                standard_information: Some(stomped),
+                file_name: Some((64, "tool.exe")),
+                file_name_times: real,
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 67,
+                standard_information: Some(real),
+                file_name: Some((64, "clean.txt")),
+                file_name_times: real,
+                ..Default::default()
+            },
+        ];
+        write(
+            &temp.path().join("fs/MFT"),
+            records.iter().flat_map(mft_record).collect::<Vec<u8>>(),
+        );
+
+        let findings = detect(temp.path(), None);
+        assert_eq!(
+            checks(&findings),
+            vec![
+                (AntiForensicsCheck::Timestomping, Severity::High),
+                (AntiForensicsCheck::Timestomping, Severity::Medium),
+            ]
+        );
+        assert!(findings[0].description.starts_with(r"\Windows\evil.dll:"));
+        assert!(findings[0].description.contains("400 days"));
+        assert!(findings[1].description.starts_with(r"\Windows\tool.exe:"));
+    }
+
+    #[test]
+    fn test_syslog_gaps() {
+        let temp = TempDir::new().unwrap();
+        write(
+            &temp.path().join("fs/var/log/syslog"),
+            "Mar  8 09:00:00 host systemd[1]: Started session.\n\
+             Mar  8 09:30:00 host CRON[20]: (root) CMD (run-parts)\n\
+             not a syslog line\n\
+             Mar  8 18:42:00 host kernel: usb 1-1: new device\n\
+             Mar  8 19:00:00 host systemd[1]: Stopped session.\n",
+        );
+        write(&temp.path().join("fs/var/log/syslog.2.gz"), b"ignored");
+
+        let findings = detect(temp.path(), None);
+        assert_eq!(
+            checks(&findings),
+            vec![(AntiForensicsCheck::SyslogGaps, Severity::Low)]
+        );
+        assert!(findings[0].description.contains("9.2 hours"));
+        assert_eq!(
+            findings[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 8, 9, 30, 0).single()
+        );
+
+        let options = AntiForensicsOptions {
+            syslog_gap: Duration::hours(12),
+            ..Default::default()
+        };
+        let report =
+            detect_antiforensics(&find_sources(temp.path()), &options, None, time()).unwrap();
+        assert!(report.records.is_empty());
+    }
+
+    #[test]
+    fn test_configure_disables_checks() {
+        let temp = TempDir::new().unwrap();
+        write(&temp.path().join("fs/UsnJrnl"), b"");
+
+        let global_options: HashMap<String, String> = [
+            ("antiforensics_usn_journal", "false"),
+            ("antiforensics_prefetch", "true"),
+            ("antiforensics_syslog_gap_hours", "12"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        configure(&global_options);
+        let options = options();
+        configure(&HashMap::new());
+
+        assert!(!options.enabled(AntiForensicsCheck::UsnJournal));
+        assert!(options.enabled(AntiForensicsCheck::Prefetch));
+        assert_eq!(options.syslog_gap, Duration::hours(12));
+
+        let report =
+            detect_antiforensics(&find_sources(temp.path()), &options, None, time()).unwrap();
+        assert!(report.records.is_empty());
+        assert_eq!(
+            report.notes,
+            vec!["usn_journal check disabled in global_options"]
+        );
+    }
+}
@@ -0,0 +1,1049 @@
+//! Unix authentication history: login records, sudo and sshd logs.
+//!
+//! Sources merged into one sorted timeline:
+//!
+//! - `wtmp` / `btmp`: utmp records of logins, logouts, boots and failed
+//!   logins. The record layout depends on the C library, so it is detected
+//!   per file from the record size and the plausibility of the decoded
+//!   records:
+//!
+//!   ```text
+//!   glibc (384 bytes): i16 type | i32 pid @4 | line[32] @8 | id[4] @40
+//!                      | user[32] @44 | host[256] @76 | exit @332 | i32 session @336
+//!                      | i32 tv_sec @340 | i32 tv_usec @344 | addr_v6[16] @348
+//!   musl  (400 bytes): same up to session, then i32 pad | i64 tv_sec @344
+//!                      | i64 tv_usec @352 | addr_v6[16] @360
+//!   ```
+//!
+//! - `lastlog`: one 292-byte record per UID (`i32 time | line[32] | host[256]`)
+//! - `auth.log` / `secure`: `sudo` command lines and sshd accepted/failed logins
+//! - `sudo-io` session `log` files written when sudo I/O logging is enabled;
+//!   only the session metadata is read, the recorded terminal I/O is not
+//!   replayed
+//! - macOS `last` output and `loginwindow` Unified Log events captured by the
+//!   login records collector
+//!
+//! Syslog and `last` timestamps carry neither year nor zone. They are read
+//! in the host's current UTC offset, in the year that puts them at or before
+//! the reference time (the parse time, right after collection).
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{find_collected_file, find_collected_files_matching, ParsedReport};
+use crate::collectors::macos::login_records::{LAST_OUTPUT_FILE, LOGINWINDOW_FILE};
+use crate::collectors::macos::unified_log_parser::UnifiedLogEvent;
+use crate::utils::timezone::local_to_utc;
+
+/// Report file name
+pub const AUTH_REPORT_FILE: &str = "auth_report.json";
+
+const UT_RUN_LVL: i16 = 1;
+const UT_BOOT_TIME: i16 = 2;
+const UT_LOGIN_PROCESS: i16 = 6;
+const UT_USER_PROCESS: i16 = 7;
+const UT_DEAD_PROCESS: i16 = 8;
+const UT_ACCOUNTING: i16 = 9;
+
+const UT_LINE: (usize, usize) = (8, 32);
+const UT_USER: (usize, usize) = (44, 32);
+const UT_HOST: (usize, usize) = (76, 256);
+
+const LASTLOG_RECORD_SIZE: usize = 292;
+
+/// UIDs read from `lastlog` when no `passwd` was collected to name them;
+/// the file is sparse and indexed by UID, so it can be terabytes long
+const MAX_LASTLOG_SCAN_UIDS: u32 = 65_536;
+
+/// Earliest timestamp accepted when detecting the utmp layout (1980-01-01)
+const MIN_PLAUSIBLE_TIME: i64 = 315_532_800;
+
+/// Latest timestamp accepted when detecting the utmp layout (2100-01-01)
+const MAX_PLAUSIBLE_TIME: i64 = 4_102_444_800;
+
+/// A utmp record layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UtmpLayout {
+    name: &'static str,
+    record_size: usize,
+    tv_sec: usize,
+    tv_usec: usize,
+    /// Width of `tv_sec` and `tv_usec`
+    time_width: usize,
+    addr_v6: usize,
+}
+
+const GLIBC_UTMP: UtmpLayout = UtmpLayout {
+    name: "glibc",
+    record_size: 384,
+    tv_sec: 340,
+    tv_usec: 344,
+    time_width: 4,
+    addr_v6: 348,
+};
+
+const MUSL_UTMP: UtmpLayout = UtmpLayout {
+    name: "musl",
+    record_size: 400,
+    tv_sec: 344,
+    tv_usec: 352,
+    time_width: 8,
+    addr_v6: 360,
+};
+
+const UTMP_LAYOUTS: &[UtmpLayout] = &[GLIBC_UTMP, MUSL_UTMP];
+
+lazy_static! {
+    static ref SSHD_ACCEPTED: Regex =
+        Regex::new(r"^Accepted (\S+) for (\S+) from (\S+) port \d+").unwrap();
+    static ref SSHD_FAILED: Regex =
+        Regex::new(r"^Failed (\S+) for (?:invalid user )?(\S+) from (\S+) port \d+").unwrap();
+}
+
+/// A normalized authentication event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthEvent {
+    pub timestamp: DateTime<Utc>,
+    /// `login`, `logout`, `failed_login`, `last_login`, `boot`, `shutdown`,
+    /// `sudo`, `sudo_session`, `unlock`
+    pub event: String,
+    pub user: Option<String>,
+    pub source_ip: Option<String>,
+    /// Remote host name, when the record holds a name rather than an address
+    pub host: Option<String>,
+    pub tty: Option<String>,
+    pub success: bool,
+    /// How the user authenticated (`password`, `publickey`, ...) or the
+    /// record type (`wtmp`, `btmp`, `lastlog`, `sudo`, `sudo-io`, `last`,
+    /// `loginwindow`)
+    pub method: String,
+    /// Command run with sudo, or the log message
+    pub detail: Option<String>,
+    pub source: String,
+}
+
+/// Collected files the authentication report is built from
+#[derive(Debug, Clone, Default)]
+pub struct AuthSources {
+    pub utmp_files: Vec<PathBuf>,
+    pub lastlog_files: Vec<PathBuf>,
+    /// Names the UIDs in `lastlog`
+    pub passwd: Option<PathBuf>,
+    pub auth_logs: Vec<PathBuf>,
+    pub sudo_io_logs: Vec<PathBuf>,
+    pub last_outputs: Vec<PathBuf>,
+    pub loginwindow_logs: Vec<PathBuf>,
+}
+
+impl AuthSources {
+    /// Whether there is anything to parse; `passwd` alone is not a source
+    pub fn is_empty(&self) -> bool {
+        self.utmp_files.is_empty()
+            && self.lastlog_files.is_empty()
+            && self.auth_logs.is_empty()
+            && self.sudo_io_logs.is_empty()
+            && self.last_outputs.is_empty()
+            && self.loginwindow_logs.is_empty()
+    }
+
+    pub fn all(&self) -> Vec<PathBuf> {
+        self.utmp_files
+            .iter()
+            .chain(&self.lastlog_files)
+            .chain(&self.passwd)
+            .chain(&self.auth_logs)
+            .chain(&self.sudo_io_logs)
+            .chain(&self.last_outputs)
+            .chain(&self.loginwindow_logs)
+            .cloned()
+            .collect()
+    }
+}
+
+/// How year-less local timestamps are placed
+#[derive(Debug, Clone, Copy)]
+pub struct TimeContext {
+    /// Host UTC offset in minutes, east positive
+    pub utc_offset_minutes: i32,
+    /// Timestamps are placed in the latest year that keeps them at or
+    /// before this time
+    pub reference: DateTime<Utc>,
+}
+
+/// Find the collected authentication sources under `artifact_dir`
+pub fn find_auth_sources(artifact_dir: &Path) -> AuthSources {
+    let name_is = |matches: fn(&str) -> bool| {
+        move |path: &Path| {
+            path.file_name()
+                .is_some_and(|n| matches(&n.to_string_lossy()))
+        }
+    };
+    AuthSources {
+        utmp_files: find_collected_files_matching(artifact_dir, name_is(is_utmp_name)),
+        lastlog_files: find_collected_files_matching(artifact_dir, name_is(|n| n == "lastlog")),
+        passwd: find_collected_file(artifact_dir, "passwd"),
+        auth_logs: find_collected_files_matching(artifact_dir, name_is(is_auth_log_name)),
+        sudo_io_logs: find_collected_files_matching(artifact_dir, is_sudo_io_log),
+        last_outputs: find_collected_files_matching(
+            artifact_dir,
+            name_is(|n| n == LAST_OUTPUT_FILE),
+        ),
+        loginwindow_logs: find_collected_files_matching(
+            artifact_dir,
+            name_is(|n| n == LOGINWINDOW_FILE),
+        ),
+    }
+}
+
+/// `wtmp`, `btmp` and their rotations (`wtmp.1`, `btmp-20240301`)
+fn is_utmp_name(name: &str) -> bool {
+    ["wtmp", "btmp"].iter().any(|base| {
+        name.strip_prefix(base)
+            .is_some_and(|rest| rest.is_empty() || is_rotation_suffix(rest))
+    })
+}
+
+/// `auth.log` (Debian) and `secure` (Red Hat) and their uncompressed rotations
+fn is_auth_log_name(name: &str) -> bool {
+    ["auth.log", "secure"].iter().any(|base| {
+        name.strip_prefix(base)
+            .is_some_and(|rest| rest.is_empty() || is_rotation_suffix(rest))
+    })
+}
+
+fn is_rotation_suffix(rest: &str) -> bool {
+    rest.strip_prefix('.')
+        .or_else(|| rest.strip_prefix('-'))
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// A sudo I/O log session `log` file (`sudo-io/00/00/01/log`)
+fn is_sudo_io_log(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == "log")
+        && path.components().any(|c| c.as_os_str() == "sudo-io")
+}
+
+/// Parse every source into one report sorted by time
+pub fn parse_auth_sources(
+    sources: &AuthSources,
+    time: TimeContext,
+) -> Result<ParsedReport<AuthEvent>> {
+    let mut report = ParsedReport::new(&sources.all());
+    let mut events = Vec::new();
+
+    for path in &sources.utmp_files {
+        match parse_utmp_file(path) {
+            Ok((layout, mut parsed)) => {
+                report.notes.push(format!(
+                    "{}: {} utmp layout, {} events",
+                    path.display(),
+                    layout.name,
+                    parsed.len()
+                ));
+                events.append(&mut parsed);
+            }
+            Err(e) => report
+                .notes
+                .push(format!("Skipped {}: {:#}", path.display(), e)),
+        }
+    }
+
+    let users = match &sources.passwd {
+        Some(passwd) => fs::read_to_string(passwd)
+            .map(|text| parse_passwd(&text))
+            .unwrap_or_default(),
+        None => HashMap::new(),
+    };
+    for path in &sources.lastlog_files {
+        match parse_lastlog(path, &users) {
+            Ok(mut parsed) => events.append(&mut parsed),
+            Err(e) => report
+                .notes
+                .push(format!("Skipped {}: {:#}", path.display(), e)),
+        }
+    }
+    if !sources.lastlog_files.is_empty() && users.is_empty() {
+        report.notes.push(format!(
+            "No passwd collected; lastlog users are UIDs and only UIDs below {} were read",
+            MAX_LASTLOG_SCAN_UIDS
+        ));
+    }
+
+    let text_sources: [(&[PathBuf], TextParser); 4] = [
+        (&sources.auth_logs, parse_auth_log),
+        (&sources.sudo_io_logs, parse_sudo_io_log),
+        (&sources.last_outputs, parse_last_output),
+        (&sources.loginwindow_logs, parse_loginwindow_log),
+    ];
+    for (paths, parse) in text_sources {
+        for path in paths {
+            match fs::read(path) {
+                Ok(data) => events.extend(parse(
+                    &String::from_utf8_lossy(&data),
+                    &path.to_string_lossy(),
+                    time,
+                )),
+                Err(e) => report
+                    .notes
+                    .push(format!("Skipped {}: {}", path.display(), e)),
+            }
+        }
+    }
+    if !sources.auth_logs.is_empty() || !sources.last_outputs.is_empty() {
+        report.notes.push(format!(
+            "Year-less syslog and last timestamps were read as UTC{:+} minutes",
+            time.utc_offset_minutes
+        ));
+    }
+
+    events.sort_by_key(|e| e.timestamp);
+    report.records = events;
+    Ok(report)
+}
+
+type TextParser = fn(&str, &str, TimeContext) -> Vec<AuthEvent>;
+
+fn event(timestamp: DateTime<Utc>, event: &str, method: &str, source: &str) -> AuthEvent {
+    AuthEvent {
+        timestamp,
+        event: event.to_string(),
+        user: None,
+        source_ip: None,
+        host: None,
+        tty: None,
+        success: true,
+        method: method.to_string(),
+        detail: None,
+        source: source.to_string(),
+    }
+}
+
+fn nul_terminated(data: &[u8], (offset, len): (usize, usize)) -> Option<String> {
+    let field = data.get(offset..offset + len)?;
+    let end = field.iter().position(|&b| b == 0).unwrap_or(len);
+    let text = String::from_utf8_lossy(&field[..end]).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn int_at(data: &[u8], offset: usize, width: usize) -> Option<i64> {
+    let bytes = data.get(offset..offset + width)?;
+    Some(match width {
+        4 => i32::from_le_bytes(bytes.try_into().ok()?) as i64,
+        8 => i64::from_le_bytes(bytes.try_into().ok()?),
+        _ => return None,
+    })
+}
+
+fn utmp_type(record: &[u8]) -> i16 {
+    i16::from_le_bytes([record[0], record[1]])
+}
+
+fn utmp_time(record: &[u8], layout: &UtmpLayout) -> Option<DateTime<Utc>> {
+    let secs = int_at(record, layout.tv_sec, layout.time_width)?;
+    let usecs = int_at(record, layout.tv_usec, layout.time_width)?;
+    if !(MIN_PLAUSIBLE_TIME..MAX_PLAUSIBLE_TIME).contains(&secs) || !(0..1_000_000).contains(&usecs)
+    {
+        return None;
+    }
+    Utc.timestamp_opt(secs, (usecs * 1000) as u32).single()
+}
+
+/// Share of records in `data` that decode sensibly with `layout`
+fn layout_score(data: &[u8], layout: &UtmpLayout) -> f64 {
+    let records: Vec<&[u8]> = data.chunks_exact(layout.record_size).collect();
+    if records.is_empty() {
+        return 0.0;
+    }
+    let plausible = records
+        .iter()
+        .filter(|record| match utmp_type(record) {
+            0 => true,
+            UT_RUN_LVL..=UT_ACCOUNTING => utmp_time(record, layout).is_some(),
+            _ => false,
+        })
+        .count();
+    plausible as f64 / records.len() as f64
+}
+
+/// Pick the layout whose records decode best; exact multiples of the
+/// record size win ties
+fn detect_utmp_layout(data: &[u8]) -> Option<UtmpLayout> {
+    UTMP_LAYOUTS
+        .iter()
+        .map(|layout| {
+            let exact = data.len().is_multiple_of(layout.record_size);
+            (layout, layout_score(data, layout), exact)
+        })
+        .filter(|(_, score, _)| *score > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(a.2.cmp(&b.2)))
+        .map(|(layout, _, _)| *layout)
+}
+
+fn utmp_address(record: &[u8], layout: &UtmpLayout) -> Option<IpAddr> {
+    let bytes: [u8; 16] = record
+        .get(layout.addr_v6..layout.addr_v6 + 16)?
+        .try_into()
+        .ok()?;
+    if bytes.iter().all(|&b| b == 0) {
+        None
+    } else if bytes[4..].iter().all(|&b| b == 0) {
+        Some(IpAddr::V4(Ipv4Addr::new(
+            bytes[0], bytes[1], bytes[2], bytes[3],
+        )))
+    } else {
+        Some(IpAddr::V6(Ipv6Addr::from(bytes)))
+    }
+}
+
+/// Parse a `wtmp` or `btmp` file; every record of a `btmp` file is a
+/// failed login
+fn parse_utmp_file(path: &Path) -> Result<(UtmpLayout, Vec<AuthEvent>)> {
+    let data = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    let failed = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with("btmp"));
+    let layout = detect_utmp_layout(&data)
+        .ok_or_else(|| anyhow::anyhow!("No utmp records ({} bytes)", data.len()))?;
+    Ok((
+        layout,
+        parse_utmp(&data, &layout, failed, &path.to_string_lossy()),
+    ))
+}
+
+/// Login, logout, boot and shutdown events of a `wtmp` file's contents;
+/// `None` if no utmp layout fits
+pub(crate) fn parse_wtmp_data(data: &[u8], source: &str) -> Option<Vec<AuthEvent>> {
+    let layout = detect_utmp_layout(data)?;
+    Some(parse_utmp(data, &layout, false, source))
+}
+
+fn parse_utmp(data: &[u8], layout: &UtmpLayout, failed: bool, source: &str) -> Vec<AuthEvent> {
+    let mut events = Vec::new();
+    // Logout records usually have no user; it is taken from the login on the same line
+    let mut users_by_line: HashMap<String, String> = HashMap::new();
+
+    for record in data.chunks_exact(layout.record_size) {
+        let record_type = utmp_type(record);
+        let Some(timestamp) = utmp_time(record, layout) else {
+            continue;
+        };
+        let user = nul_terminated(record, UT_USER);
+        let line = nul_terminated(record, UT_LINE);
+        let host = nul_terminated(record, UT_HOST);
+
+        let (name, method) = match (failed, record_type) {
+            (true, UT_LOGIN_PROCESS | UT_USER_PROCESS) => ("failed_login", "btmp"),
+            (false, UT_USER_PROCESS) => ("login", "wtmp"),
+            (false, UT_DEAD_PROCESS) => ("logout", "wtmp"),
+            (false, UT_BOOT_TIME) => ("boot", "wtmp"),
+            (false, UT_RUN_LVL) if user.as_deref() == Some("shutdown") => ("shutdown", "wtmp"),
+            _ => continue,
+        };
+
+        let mut entry = event(timestamp, name, method, source);
+        entry.success = !failed;
+        entry.user = match (record_type, &line) {
+            (UT_USER_PROCESS, Some(line)) if !failed => {
+                if let Some(user) = &user {
+                    users_by_line.insert(line.clone(), user.clone());
+                }
+                user
+            }
+            (UT_DEAD_PROCESS, Some(line)) => user.or_else(|| users_by_line.remove(line)),
+            _ => user,
+        };
+        entry.source_ip = utmp_address(record, layout)
+            .or_else(|| host.as_deref().and_then(|h| h.parse().ok()))
+            .map(|ip| ip.to_string());
+        entry.host =
+            host.filter(|h| h.parse::<IpAddr>().is_err() && name != "boot" && name != "shutdown");
+        entry.tty = line.filter(|l| l != "~");
+        events.push(entry);
+    }
+    events
+}
+
+/// UID to user name from a `passwd` file
+pub(crate) fn parse_passwd(text: &str) -> HashMap<u32, String> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some((fields.get(2)?.parse().ok()?, fields.first()?.to_string()))
+        })
+        .collect()
+}
+
+/// Parse `lastlog`, reading only the records of `users` when known
+pub(crate) fn parse_lastlog(path: &Path, users: &HashMap<u32, String>) -> Result<Vec<AuthEvent>> {
+    let mut file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let length = file.metadata()?.len();
+    let record_count = (length / LASTLOG_RECORD_SIZE as u64).min(u32::MAX as u64) as u32;
+
+    let mut uids: Vec<u32> = if users.is_empty() {
+        (0..record_count.min(MAX_LASTLOG_SCAN_UIDS)).collect()
+    } else {
+        users
+            .keys()
+            .copied()
+            .filter(|uid| *uid < record_count)
+            .collect()
+    };
+    uids.sort_unstable();
+    uids.dedup();
+
+    let source = path.to_string_lossy();
+    let mut events = Vec::new();
+    let mut record = [0u8; LASTLOG_RECORD_SIZE];
+    for uid in uids {
+        file.seek(SeekFrom::Start(uid as u64 * LASTLOG_RECORD_SIZE as u64))?;
+        file.read_exact(&mut record)?;
+        let Some(secs) = int_at(&record, 0, 4).filter(|t| *t > 0) else {
+            continue;
+        };
+        let Some(timestamp) = Utc.timestamp_opt(secs, 0).single() else {
+            continue;
+        };
+        let host = nul_terminated(&record, (36, 256));
+        let mut entry = event(timestamp, "last_login", "lastlog", &source);
+        entry.user = Some(
+            users
+                .get(&uid)
+                .cloned()
+                .unwrap_or_else(|| format!("uid {}", uid)),
+        );
+        entry.tty = nul_terminated(&record, (4, 32));
+        entry.source_ip = host
+            .as_deref()
+            .and_then(|h| h.parse::<IpAddr>().ok())
+            .map(|ip| ip.to_string());
+        entry.host = host.filter(|h| h.parse::<IpAddr>().is_err());
+        events.push(entry);
+    }
+    Ok(events)
+}
+
+/// Place a year-less local time (`Mar  5 14:30:10`) in the latest year that
+/// keeps it at or before the reference time
+fn yearless_time(month_day_time: &str, time: TimeContext) -> Option<DateTime<Utc>> {
+    let slack = Duration::days(1);
+    let year = time.reference.year();
+    [year, year - 1].into_iter().find_map(|year| {
+        let local = NaiveDateTime::parse_from_str(
+            &format!("{} {}", year, month_day_time),
+            "%Y %b %d %H:%M:%S",
+        )
+        .ok()?;
+        let utc = local_to_utc(local, time.utc_offset_minutes)?;
+        (utc <= time.reference + slack).then_some(utc)
+    })
+}
+
+/// Split a syslog line into its UTC time, program name and message
+pub(crate) fn split_syslog_line(
+    line: &str,
+    time: TimeContext,
+) -> Option<(DateTime<Utc>, String, String)> {
+    let mut tokens = line.split_whitespace();
+    let first = tokens.next()?;
+    let (timestamp, rest) = match DateTime::parse_from_rfc3339(first) {
+        Ok(timestamp) => (
+            timestamp.with_timezone(&Utc),
+            line.trim_start()[first.len()..].trim_start(),
+        ),
+        Err(_) => {
+            let day = tokens.next()?;
+            let clock = tokens.next()?;
+            let timestamp = yearless_time(&format!("{} {} {}", first, day, clock), time)?;
+            let offset = line.find(clock)? + clock.len();
+            (timestamp, line[offset..].trim_start())
+        }
+    };
+    // Host name, then `program[pid]: message`
+    let (_, rest) = rest.split_once(char::is_whitespace)?;
+    let (tag, message) = rest.split_once(": ")?;
+    let program = tag.split('[').next().unwrap_or(tag).to_string();
+    Some((timestamp, program, message.trim().to_string()))
+}
+
+/// `sudo` and `sshd` events from an `auth.log` or `secure` file
+fn parse_auth_log(text: &str, source: &str, time: TimeContext) -> Vec<AuthEvent> {
+    text.lines()
+        .filter_map(|line| {
+            let (timestamp, program, message) = split_syslog_line(line, time)?;
+            match program.as_str() {
+                "sudo" => sudo_event(timestamp, &message, source),
+                "sshd" => sshd_event(timestamp, &message, source),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// `alice : TTY=pts/0 ; PWD=/home/alice ; USER=root ; COMMAND=/usr/bin/id`,
+/// with a leading reason (`3 incorrect password attempts ;`,
+/// `user NOT in sudoers ;`) when sudo refused
+fn sudo_event(timestamp: DateTime<Utc>, message: &str, source: &str) -> Option<AuthEvent> {
+    let (user, rest) = message.split_once(" : ")?;
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut reason = None;
+    for part in rest.split(" ; ") {
+        match part.split_once('=') {
+            Some((key, value)) if key.chars().all(|c| c.is_ascii_uppercase()) => {
+                fields.insert(key, value.trim());
+            }
+            _ => reason = Some(part.trim()),
+        }
+    }
+    let command = fields.get("COMMAND")?;
+
+    let mut entry = event(timestamp, "sudo", "sudo", source);
+    entry.user = Some(user.trim().to_string());
+    entry.tty = fields
+        .get("TTY")
+        .filter(|tty| **tty != "unknown")
+        .map(|tty| tty.to_string());
+    entry.success = reason.is_none();
+    entry.detail = Some(match (reason, fields.get("USER")) {
+        (Some(reason), _) => format!("{}: {}", reason, command),
+        (None, Some(runas)) => format!("as {}: {}", runas, command),
+        (None, None) => command.to_string(),
+    });
+    Some(entry)
+}
+
+/// `Accepted publickey for alice from 10.0.0.5 port 52144 ssh2` and
+/// `Failed password for invalid user admin from 10.0.0.9 port 40022 ssh2`
+fn sshd_event(timestamp: DateTime<Utc>, message: &str, source: &str) -> Option<AuthEvent> {
+    let (captures, success) = match SSHD_ACCEPTED.captures(message) {
+        Some(captures) => (captures, true),
+        None => (SSHD_FAILED.captures(message)?, false),
+    };
+    let name = if success { "login" } else { "failed_login" };
+    let mut entry = event(timestamp, name, &captures[1], source);
+    entry.user = Some(captures[2].to_string());
+    entry.source_ip = captures[3].parse::<IpAddr>().ok().map(|ip| ip.to_string());
+    entry.host = entry.source_ip.is_none().then(|| captures[3].to_string());
+    entry.tty = Some("ssh".to_string());
+    entry.success = success;
+    Some(entry)
+}
+
+/// A sudo I/O log `log` file: `time:user:runas_user:runas_group:tty[:lines:cols]`,
+/// then the working directory and the command
+fn parse_sudo_io_log(text: &str, source: &str, _time: TimeContext) -> Vec<AuthEvent> {
+    let mut lines = text.lines();
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let fields: Vec<&str> = header.split(':').collect();
+    let Some(timestamp) = fields
+        .first()
+        .and_then(|t| t.parse::<i64>().ok())
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+    else {
+        return Vec::new();
+    };
+    let _cwd = lines.next();
+    let command = lines.next().map(str::to_string);
+
+    let mut entry = event(timestamp, "sudo_session", "sudo-io", source);
+    entry.user = fields
+        .get(1)
+        .filter(|u| !u.is_empty())
+        .map(|u| u.to_string());
+    entry.tty = fields
+        .get(4)
+        .filter(|t| !t.is_empty() && **t != "unknown")
+        .map(|t| t.trim_start_matches("/dev/").to_string());
+    entry.detail = match (fields.get(2).filter(|r| !r.is_empty()), command) {
+        (Some(runas), Some(command)) => Some(format!("as {}: {}", runas, command)),
+        (_, command) => command,
+    };
+    vec![entry]
+}
+
+const WEEKDAYS: &[&str] = &["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// macOS `last` output: `user tty [host] Www Mmm dd HH:MM ...`
+fn parse_last_output(text: &str, source: &str, time: TimeContext) -> Vec<AuthEvent> {
+    text.lines()
+        .filter(|line| !line.starts_with("wtmp begins"))
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let day_index = tokens
+                .iter()
+                .position(|t| WEEKDAYS.contains(t))
+                .filter(|i| *i >= 2)?;
+            let date = tokens.get(day_index + 1..day_index + 4)?;
+            let timestamp =
+                yearless_time(&format!("{} {} {}:00", date[0], date[1], date[2]), time)?;
+
+            let user = tokens[0];
+            let name = match user {
+                "reboot" => "boot",
+                "shutdown" => "shutdown",
+                _ => "login",
+            };
+            let mut entry = event(timestamp, name, "last", source);
+            if name == "login" {
+                entry.user = Some(user.to_string());
+                entry.tty = Some(tokens[1].to_string());
+                let host = (day_index > 2).then(|| tokens[2..day_index].join(" "));
+                entry.source_ip = host
+                    .as_deref()
+                    .and_then(|h| h.parse::<IpAddr>().ok())
+                    .map(|ip| ip.to_string());
+                entry.host = host.filter(|h| h.parse::<IpAddr>().is_err());
+            }
+            Some(entry)
+        })
+        .collect()
+}
+
+/// Login, logout and unlock events from the exported `loginwindow` log
+fn parse_loginwindow_log(text: &str, source: &str, _time: TimeContext) -> Vec<AuthEvent> {
+    text.lines()
+        .filter_map(|line| {
+            let log_event: UnifiedLogEvent = serde_json::from_str(line).ok()?;
+            let message = log_event.message?;
+            // Most messages name the process itself; that is not a login
+            let lower = message.to_lowercase().replace("loginwindow", "");
+            let name = if lower.contains("logout") {
+                "logout"
+            } else if lower.contains("unlock") {
+                "unlock"
+            } else if lower.contains("login") || lower.contains("authenticat") {
+                "login"
+            } else {
+                return None;
+            };
+            let timestamp =
+                DateTime::parse_from_str(&log_event.timestamp, "%Y-%m-%d %H:%M:%S%.f%z").ok()?;
+            let mut entry = event(timestamp.with_timezone(&Utc), name, "loginwindow", source);
+            entry.success = !lower.contains("fail");
+            entry.detail = Some(message);
+            Some(entry)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 2024-03-05 14:30:10 UTC
+    const LOGIN_TIME: i64 = 1_709_649_010;
+
+    fn time_context() -> TimeContext {
+        TimeContext {
+            utc_offset_minutes: 0,
+            reference: Utc.with_ymd_and_hms(2024, 3, 6, 0, 0, 0).unwrap(),
+        }
+    }
+
+    fn put(record: &mut [u8], offset: usize, bytes: &[u8]) {
+        record[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// A utmp record in `layout`
+    fn utmp_record(
+        layout: &UtmpLayout,
+        record_type: i16,
+        line: &str,
+        user: &str,
+        host: &str,
+        secs: i64,
+        addr: [u8; 4],
+    ) -> Vec<u8> {
+        let mut record = vec![0u8; layout.record_size];
+        put(&mut record, 0, &record_type.to_le_bytes());
+        put(&mut record, 4, &1234i32.to_le_bytes());
+        put(&mut record, UT_LINE.0, line.as_bytes());
+        put(&mut record, UT_USER.0, user.as_bytes());
+        put(&mut record, UT_HOST.0, host.as_bytes());
+        if layout.time_width == 4 {
+            put(&mut record, layout.tv_sec, &(secs as i32).to_le_bytes());
+            put(&mut record, layout.tv_usec, &500i32.to_le_bytes());
+        } else {
+            put(&mut record, layout.tv_sec, &secs.to_le_bytes());
+            put(&mut record, layout.tv_usec, &500i64.to_le_bytes());
+        }
+        put(&mut record, layout.addr_v6, &addr);
+        record
+    }
+
+    fn wtmp(layout: &UtmpLayout) -> Vec<u8> {
+        [
+            utmp_record(
+                layout,
+                UT_BOOT_TIME,
+                "~",
+                "reboot",
+                "6.1.0",
+                LOGIN_TIME - 600,
+                [0; 4],
+            ),
+            utmp_record(
+                layout,
+                UT_USER_PROCESS,
+                "pts/0",
+                "alice",
+                "10.0.0.5",
+                LOGIN_TIME,
+                [10, 0, 0, 5],
+            ),
+            utmp_record(
+                layout,
+                UT_USER_PROCESS,
+                "pts/1",
+                "bob",
+                "jump.example.com",
+                LOGIN_TIME + 60,
+                [0; 4],
+            ),
+            utmp_record(
+                layout,
+                UT_DEAD_PROCESS,
+                "pts/0",
+                "",
+                "",
+                LOGIN_TIME + 300,
+                [0; 4],
+            ),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_utmp_layout_detection() {
+        for layout in UTMP_LAYOUTS {
+            let data = wtmp(layout);
+            assert_eq!(detect_utmp_layout(&data), Some(*layout), "{}", layout.name);
+        }
+        // 4800 bytes divides both record sizes; the decoded records decide
+        let glibc: Vec<u8> = (0..25).flat_map(|_| wtmp(&GLIBC_UTMP)).collect();
+        assert_eq!(glibc.len() % MUSL_UTMP.record_size, 0);
+        assert_eq!(detect_utmp_layout(&glibc), Some(GLIBC_UTMP));
+        assert_eq!(detect_utmp_layout(&[0xff; 100]), None);
+    }
+
+    #[test]
+    fn test_parse_wtmp_and_btmp() {
+        for layout in UTMP_LAYOUTS {
+            let events = parse_utmp(&wtmp(layout), layout, false, "wtmp");
+            let summary: Vec<(&str, Option<&str>)> = events
+                .iter()
+                .map(|e| (e.event.as_str(), e.user.as_deref()))
+                .collect();
+            assert_eq!(
+                summary,
+                vec![
+                    ("boot", Some("reboot")),
+                    ("login", Some("alice")),
+                    ("login", Some("bob")),
+                    ("logout", Some("alice")),
+                ],
+                "{}",
+                layout.name
+            );
+            let alice = &events[1];
+            assert_eq!(
+                alice.timestamp,
+                Utc.timestamp_opt(LOGIN_TIME, 500_000).unwrap()
+            );
+            assert_eq!(alice.source_ip.as_deref(), Some("10.0.0.5"));
+            assert_eq!(alice.host, None);
+            assert_eq!(alice.tty.as_deref(), Some("pts/0"));
+            assert!(alice.success);
+            assert_eq!(events[2].host.as_deref(), Some("jump.example.com"));
+            assert_eq!(events[2].source_ip, None);
+            assert_eq!(events[0].tty, None);
+        }
+
+        let btmp = utmp_record(
+            &MUSL_UTMP,
+            UT_LOGIN_PROCESS,
+            "ssh:notty",
+            "admin",
+            "203.0.113.7",
+            LOGIN_TIME,
+            [203, 0, 113, 7],
+        );
+        let events = parse_utmp(&btmp, &MUSL_UTMP, true, "btmp");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "failed_login");
+        assert!(!events[0].success);
+        assert_eq!(events[0].method, "btmp");
+        assert_eq!(events[0].source_ip.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_parse_auth_log() {
+        let log = "\
+Mar  5 14:30:10 web01 sshd[812]: Accepted publickey for alice from 10.0.0.5 port 52144 ssh2: ED25519 SHA256:abc
+Mar  5 14:31:00 web01 sudo:    alice : TTY=pts/0 ; PWD=/home/alice ; USER=root ; COMMAND=/usr/bin/id
+Mar  5 14:32:00 web01 sudo:      bob : 3 incorrect password attempts ; TTY=pts/1 ; PWD=/home/bob ; USER=root ; COMMAND=/bin/bash
+Mar  5 14:33:00 web01 sudo: pam_unix(sudo:session): session opened for user root(uid=0) by alice(uid=1000)
+2024-03-05T14:34:00.123456+01:00 web01 sshd[900]: Failed password for invalid user admin from 203.0.113.7 port 40022 ssh2
+Dec 31 23:59:59 web01 CRON[1]: pam_unix(cron:session): session closed for user root
+";
+        let events = parse_auth_log(log, "auth.log", time_context());
+        assert_eq!(events.len(), 4);
+
+        assert_eq!(events[0].event, "login");
+        assert_eq!(events[0].method, "publickey");
+        assert_eq!(events[0].user.as_deref(), Some("alice"));
+        assert_eq!(events[0].source_ip.as_deref(), Some("10.0.0.5"));
+        assert_eq!(
+            events[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 5, 14, 30, 10).unwrap()
+        );
+
+        assert_eq!(events[1].event, "sudo");
+        assert!(events[1].success);
+        assert_eq!(events[1].tty.as_deref(), Some("pts/0"));
+        assert_eq!(events[1].detail.as_deref(), Some("as root: /usr/bin/id"));
+
+        assert!(!events[2].success);
+        assert_eq!(events[2].user.as_deref(), Some("bob"));
+        assert_eq!(
+            events[2].detail.as_deref(),
+            Some("3 incorrect password attempts: /bin/bash")
+        );
+
+        assert_eq!(events[3].event, "failed_login");
+        assert_eq!(events[3].user.as_deref(), Some("admin"));
+        assert_eq!(
+            events[3].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 5, 13, 34, 0).unwrap() + Duration::microseconds(123_456)
+        );
+
+        // A December line read in March belongs to the previous year
+        assert_eq!(
+            yearless_time("Dec 31 23:59:59", time_context()),
+            Some(Utc.with_ymd_and_hms(2023, 12, 31, 23, 59, 59).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_macos_sources() {
+        let last = "\
+alice     ttys000                   Tue Mar  5 14:30   still logged in
+bob       ttys001  192.168.1.20     Tue Mar  5 10:00 - 10:05  (00:05)
+reboot    ~                         Mon Mar  4 09:10
+
+wtmp begins Mon Mar  4 09:10
+";
+        let events = parse_last_output(last, "last.txt", time_context());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].user.as_deref(), Some("alice"));
+        assert_eq!(events[0].tty.as_deref(), Some("ttys000"));
+        assert_eq!(events[1].source_ip.as_deref(), Some("192.168.1.20"));
+        assert_eq!(events[2].event, "boot");
+        assert_eq!(
+            events[2].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 4, 9, 10, 0).unwrap()
+        );
+
+        let loginwindow = r#"{"timestamp":"2024-03-05 14:30:10.000000-0800","process":"loginwindow","message":"-[SessionAgentNotificationCenter sendBSDNotification:] | sending com.apple.sessionagent.screenIsUnlocked"}
+{"timestamp":"2024-03-05 14:31:00.000000-0800","process":"loginwindow","message":"Drawing window"}
+"#;
+        let events = parse_loginwindow_log(loginwindow, "loginwindow.jsonl", time_context());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "unlock");
+        assert_eq!(
+            events[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 5, 22, 30, 10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_auth_sources_merges_and_sorts() {
+        let temp = TempDir::new().unwrap();
+        let log_dir = temp.path().join("var").join("log");
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(log_dir.join("wtmp"), wtmp(&GLIBC_UTMP)).unwrap();
+        fs::write(
+            log_dir.join("auth.log"),
+            "Mar  5 14:31:00 web01 sudo:    alice : TTY=pts/0 ; PWD=/ ; USER=root ; COMMAND=/usr/bin/id\n",
+        )
+        .unwrap();
+
+        let mut lastlog = vec![0u8; LASTLOG_RECORD_SIZE * 1001];
+        let offset = 1000 * LASTLOG_RECORD_SIZE;
+        put(
+            &mut lastlog,
+            offset,
+            &((LOGIN_TIME - 86_400) as i32).to_le_bytes(),
+        );
+        put(&mut lastlog, offset + 4, b"pts/3");
+        put(&mut lastlog, offset + 36, b"10.0.0.9");
+        fs::write(log_dir.join("lastlog"), lastlog).unwrap();
+
+        let passwd_dir = temp.path().join("etc");
+        fs::create_dir_all(&passwd_dir).unwrap();
+        fs::write(
+            passwd_dir.join("passwd"),
+            "root:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/bash\n",
+        )
+        .unwrap();
+
+        let session = temp.path().join("var/log/sudo-io/00/00/01");
+        fs::create_dir_all(&session).unwrap();
+        fs::write(
+            session.join("log"),
+            format!(
+                "{}:alice:root::/dev/pts/0:24:80\n/home/alice\n/bin/bash\n",
+                LOGIN_TIME + 120
+            ),
+        )
+        .unwrap();
+        fs::write(session.join("timing"), "0 0.1 5\n").unwrap();
+
+        let sources = find_auth_sources(temp.path());
+        assert_eq!(sources.utmp_files.len(), 1);
+        assert_eq!(sources.sudo_io_logs.len(), 1);
+        assert!(sources.passwd.is_some());
+
+        let report = parse_auth_sources(&sources, time_context()).unwrap();
+        let timeline: Vec<&str> = report.records.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(
+            timeline,
+            vec![
+                "last_login",
+                "boot",
+                "login",
+                "sudo",
+                "login",
+                "sudo_session",
+                "logout"
+            ]
+        );
+        assert!(report
+            .records
+            .windows(2)
+            .all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let last_login = &report.records[0];
+        assert_eq!(last_login.user.as_deref(), Some("alice"));
+        assert_eq!(last_login.tty.as_deref(), Some("pts/3"));
+        assert_eq!(last_login.source_ip.as_deref(), Some("10.0.0.9"));
+
+        let session = &report.records[5];
+        assert_eq!(session.method, "sudo-io");
+        assert_eq!(session.tty.as_deref(), Some("pts/0"));
+        assert_eq!(session.detail.as_deref(), Some("as root: /bin/bash"));
+        assert!(report.notes[0].contains("glibc utmp layout, 4 events"));
+    }
+}
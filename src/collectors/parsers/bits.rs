@@ -0,0 +1,353 @@
+//! BITS (Background Intelligent Transfer Service) job database parser.
+//!
+//! BITS jobs are a persistence and download mechanism: a job can fetch a
+//! payload and run a notify command when the transfer completes. Jobs are
+//! stored in `qmgr.db` (ESE, Windows 10 and later) or in the alternating
+//! `qmgr0.dat` / `qmgr1.dat` files on older systems.
+//!
+//! Both formats keep each job as a serialized record, so records are carved
+//! from the raw file rather than read through the ESE catalog. A job record
+//! starts with:
+//!
+//! ```text
+//! u32 type | u32 priority | u32 state | u32 (unused) | GUID job id
+//! wstring name | wstring description | wstring command | wstring arguments
+//! wstring owner SID
+//! ```
+//!
+//! where a wstring is a `u32` count of UTF-16 units including the
+//! terminating NUL. The job's file list follows; remote names (URLs, UNC
+//! paths) and local file names are taken from the wstrings between one job
+//! and the next. Deleted jobs whose records are still in the file are
+//! reported too, and the same job found in several files is listed once.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::bytes::u32_at;
+use super::ParsedReport;
+
+/// Report file name
+pub const BITS_JOBS_FILE: &str = "bits_jobs.json";
+
+/// Job database file names, newest format first
+pub const BITS_DATABASE_FILES: [&str; 3] = ["qmgr.db", "qmgr0.dat", "qmgr1.dat"];
+
+/// Longest string accepted in a job record, in UTF-16 units
+const MAX_STRING_UNITS: usize = 0x1000;
+
+/// How far past a job header file names are searched for
+const MAX_JOB_SPAN: usize = 0x10000;
+
+/// Size of the fixed job header before the name
+const JOB_HEADER_SIZE: usize = 32;
+
+/// A BITS job carved from a job database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BitsJob {
+    pub job_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// download, upload or upload_reply
+    pub job_type: String,
+    pub priority: String,
+    pub state: String,
+    pub owner_sid: String,
+    /// Program run when the job completes or fails
+    pub notify_command: Option<String>,
+    pub notify_arguments: Option<String>,
+    /// Remote names of the job's files
+    pub urls: Vec<String>,
+    /// Local names of the job's files
+    pub local_files: Vec<String>,
+    /// Collected file the job was carved from
+    pub source: String,
+    pub offset: u64,
+}
+
+/// Read a counted, NUL-terminated UTF-16 string; returns it and the next offset
+fn wstring_at(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let count = u32_at(data, offset).ok()? as usize;
+    let start = offset + 4;
+    if count == 0 {
+        return Some((String::new(), start));
+    }
+    if count > MAX_STRING_UNITS {
+        return None;
+    }
+    let bytes = data.get(start..start + count * 2)?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let (last, text) = units.split_last()?;
+    if *last != 0 || text.iter().any(|&u| u < 0x20) {
+        return None;
+    }
+    Some((String::from_utf16(text).ok()?, start + count * 2))
+}
+
+fn job_type_name(value: u32) -> Option<&'static str> {
+    match value {
+        0 => Some("download"),
+        1 => Some("upload"),
+        2 => Some("upload_reply"),
+        _ => None,
+    }
+}
+
+fn priority_name(value: u32) -> Option<&'static str> {
+    match value {
+        0 => Some("foreground"),
+        1 => Some("high"),
+        2 => Some("normal"),
+        3 => Some("low"),
+        _ => None,
+    }
+}
+
+fn state_name(value: u32) -> Option<&'static str> {
+    match value {
+        0 => Some("queued"),
+        1 => Some("connecting"),
+        2 => Some("transferring"),
+        3 => Some("suspended"),
+        4 => Some("error"),
+        5 => Some("transient_error"),
+        6 => Some("transferred"),
+        7 => Some("acknowledged"),
+        8 => Some("cancelled"),
+        _ => None,
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+/// Try to read a job header at `offset`; returns the job and where its strings end
+fn job_at(data: &[u8], offset: usize) -> Option<(BitsJob, usize)> {
+    let job_type = job_type_name(u32_at(data, offset).ok()?)?;
+    let priority = priority_name(u32_at(data, offset + 4).ok()?)?;
+    let state = state_name(u32_at(data, offset + 8).ok()?)?;
+    let guid: [u8; 16] = data
+        .get(offset + 16..offset + JOB_HEADER_SIZE)?
+        .try_into()
+        .ok()?;
+
+    let (name, next) = wstring_at(data, offset + JOB_HEADER_SIZE)?;
+    if name.is_empty() {
+        return None;
+    }
+    let (description, next) = wstring_at(data, next)?;
+    let (command, next) = wstring_at(data, next)?;
+    let (arguments, next) = wstring_at(data, next)?;
+    let (owner_sid, end) = wstring_at(data, next)?;
+    // The SID anchors the match; random data rarely gets this far
+    if !owner_sid.starts_with("S-1-") {
+        return None;
+    }
+
+    let job = BitsJob {
+        job_id: format!(
+            "{{{}}}",
+            Uuid::from_bytes_le(guid)
+                .hyphenated()
+                .to_string()
+                .to_uppercase()
+        ),
+        name,
+        description: non_empty(description),
+        job_type: job_type.to_string(),
+        priority: priority.to_string(),
+        state: state.to_string(),
+        owner_sid,
+        notify_command: non_empty(command),
+        notify_arguments: non_empty(arguments),
+        urls: Vec::new(),
+        local_files: Vec::new(),
+        source: String::new(),
+        offset: offset as u64,
+    };
+    Some((job, end))
+}
+
+fn is_remote_name(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    ["http://", "https://", "ftp://", "smb://", "\\\\"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+}
+
+fn is_local_name(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() > 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\"
+}
+
+/// Collect the file names stored in `data[start..end]`
+fn file_names(data: &[u8], start: usize, end: usize, job: &mut BitsJob) {
+    let mut offset = start;
+    while offset + 4 <= end {
+        match wstring_at(&data[..end], offset) {
+            Some((value, next)) if !value.is_empty() => {
+                if is_remote_name(&value) {
+                    if !job.urls.contains(&value) {
+                        job.urls.push(value);
+                    }
+                } else if is_local_name(&value) && !job.local_files.contains(&value) {
+                    job.local_files.push(value);
+                }
+                offset = next;
+            }
+            _ => offset += 1,
+        }
+    }
+}
+
+/// Carve all job records from the contents of a job database
+pub fn carve_jobs(data: &[u8]) -> Vec<BitsJob> {
+    let mut headers = Vec::new();
+    let mut offset = 0;
+    while offset + JOB_HEADER_SIZE < data.len() {
+        match job_at(data, offset) {
+            Some((job, end)) => {
+                headers.push((job, end));
+                offset = end;
+            }
+            None => offset += 1,
+        }
+    }
+
+    let starts: Vec<usize> = headers.iter().map(|(job, _)| job.offset as usize).collect();
+    headers
+        .into_iter()
+        .enumerate()
+        .map(|(i, (mut job, end))| {
+            let limit = starts
+                .get(i + 1)
+                .copied()
+                .unwrap_or(data.len())
+                .min(end + MAX_JOB_SPAN);
+            file_names(data, end, limit, &mut job);
+            job
+        })
+        .collect()
+}
+
+/// Carve jobs from each collected job database, listing each job once
+pub fn parse_bits(files: &[PathBuf]) -> Result<ParsedReport<BitsJob>> {
+    let mut report = ParsedReport::new(files);
+    let mut seen = HashSet::new();
+
+    for file in files {
+        let data = fs::read(file).context(format!("Failed to read {}", file.display()))?;
+        let jobs = carve_jobs(&data);
+        if jobs.is_empty() && !data.iter().all(|&b| b == 0) {
+            report.notes.push(format!(
+                "No job records recognised in {}",
+                display_name(file)
+            ));
+        }
+        for mut job in jobs {
+            if seen.insert(job.job_id.clone()) {
+                job.source = file.to_string_lossy().to_string();
+                report.records.push(job);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{bits_job, BitsJobFixture};
+    use tempfile::TempDir;
+
+    fn sample_job() -> BitsJobFixture<'static> {
+        BitsJobFixture {
+            id: [
+                0x78, 0x56, 0x34, 0x12, 0x34, 0x12, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc,
+                0xde, 0xf0,
+            ],
+            name: "updater",
+            command: r"C:\Users\Public\run.exe",
+            arguments: "run.exe -silent",
+            sid: "S-1-5-21-1-2-3-1001",
+            files: &[(
+                r"C:\Users\Public\payload.bin",
+                "https://evil.example/payload.bin",
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_carve_job_with_files() {
+        let mut data = vec![0xAAu8; 37];
+        data.extend(bits_job(&sample_job()));
+        data.extend([0u8; 64]);
+
+        let jobs = carve_jobs(&data);
+        assert_eq!(jobs.len(), 1);
+        let job = &jobs[0];
+        assert_eq!(job.job_id, "{12345678-1234-1234-1234-56789ABCDEF0}");
+        assert_eq!(job.name, "updater");
+        assert_eq!(job.job_type, "download");
+        assert_eq!(job.state, "transferred");
+        assert_eq!(job.offset, 37);
+        assert_eq!(
+            job.notify_command.as_deref(),
+            Some(r"C:\Users\Public\run.exe")
+        );
+        assert_eq!(job.notify_arguments.as_deref(), Some("run.exe -silent"));
+        assert_eq!(job.description, None);
+        assert_eq!(job.urls, vec!["https://evil.example/payload.bin"]);
+        assert_eq!(job.local_files, vec![r"C:\Users\Public\payload.bin"]);
+    }
+
+    #[test]
+    fn test_file_names_stop_at_next_job() {
+        let mut second = sample_job();
+        second.id[0] = 0x99;
+        second.name = "second";
+        second.files = &[(r"C:\temp\b.txt", r"\\fileserver\share\b.txt")];
+
+        let mut data = bits_job(&sample_job());
+        data.extend(bits_job(&second));
+
+        let jobs = carve_jobs(&data);
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].urls, vec!["https://evil.example/payload.bin"]);
+        assert_eq!(jobs[1].urls, vec![r"\\fileserver\share\b.txt"]);
+    }
+
+    #[test]
+    fn test_parse_deduplicates_across_files() {
+        let temp = TempDir::new().unwrap();
+        let qmgr0 = temp.path().join("qmgr0.dat");
+        let qmgr1 = temp.path().join("qmgr1.dat");
+        let garbage = temp.path().join("qmgr.db");
+        fs::write(&qmgr0, bits_job(&sample_job())).unwrap();
+        fs::write(&qmgr1, bits_job(&sample_job())).unwrap();
+        fs::write(&garbage, b"not a job database at all").unwrap();
+
+        let report = parse_bits(&[garbage, qmgr0.clone(), qmgr1]).unwrap();
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].source, qmgr0.to_string_lossy());
+        assert_eq!(report.sources.len(), 3);
+        assert_eq!(report.notes, vec!["No job records recognised in qmgr.db"]);
+    }
+}
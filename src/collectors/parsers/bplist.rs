@@ -0,0 +1,454 @@
+//! Minimal binary property list (`bplist00`) reader.
+//!
+//! Reads the object table into a [`PlistValue`] tree and decodes
+//! `NSKeyedArchiver` archives, which is how macOS stores the background task
+//! management database, into JSON. Only the object types found in archives
+//! are supported; XML plists are not handled.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+
+const MAGIC: &[u8] = b"bplist00";
+const TRAILER_SIZE: usize = 32;
+
+/// Objects nested deeper than this are treated as corrupt (or cyclic)
+const MAX_DEPTH: usize = 64;
+
+/// Seconds from 1970-01-01 to 2001-01-01, the epoch of plist dates
+pub const APPLE_EPOCH_OFFSET: f64 = 978_307_200.0;
+
+/// A decoded property list object
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlistValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    /// Seconds since 2001-01-01 UTC
+    Date(f64),
+    Data(Vec<u8>),
+    String(String),
+    /// Reference into an `NSKeyedArchiver` object table
+    Uid(u64),
+    Array(Vec<PlistValue>),
+    Dictionary(Vec<(String, PlistValue)>),
+}
+
+impl PlistValue {
+    /// Value for `key` if this is a dictionary
+    pub fn get(&self, key: &str) -> Option<&PlistValue> {
+        match self {
+            PlistValue::Dictionary(entries) => {
+                entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PlistValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    ref_size: usize,
+    offsets: Vec<usize>,
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+impl Reader<'_> {
+    fn slice(&self, start: usize, len: usize) -> Result<&[u8]> {
+        start
+            .checked_add(len)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| anyhow!("Property list truncated at offset {:#x}", start))
+    }
+
+    /// Object count following a marker whose low nibble is `nibble`;
+    /// returns the count and the offset of the first content byte
+    fn count(&self, nibble: u8, offset: usize) -> Result<(usize, usize)> {
+        if nibble != 0x0F {
+            return Ok((usize::from(nibble), offset + 1));
+        }
+        let marker = self.slice(offset + 1, 1)?[0];
+        if marker >> 4 != 0x1 {
+            bail!("Bad object count marker {:#x} at {:#x}", marker, offset + 1);
+        }
+        let width = 1usize << (marker & 0x0F);
+        let count = be_uint(self.slice(offset + 2, width)?);
+        let count = usize::try_from(count)
+            .ok()
+            .filter(|&c| c <= self.data.len())
+            .ok_or_else(|| anyhow!("Implausible object count {} at {:#x}", count, offset))?;
+        Ok((count, offset + 2 + width))
+    }
+
+    fn object_ref(&self, offset: usize) -> Result<usize> {
+        let index = be_uint(self.slice(offset, self.ref_size)?);
+        usize::try_from(index).map_err(|_| anyhow!("Object reference {} out of range", index))
+    }
+
+    fn object(&self, index: usize, depth: usize) -> Result<PlistValue> {
+        if depth > MAX_DEPTH {
+            bail!("Property list nested too deeply");
+        }
+        let offset = *self
+            .offsets
+            .get(index)
+            .ok_or_else(|| anyhow!("Object {} is not in the offset table", index))?;
+        let marker = self.slice(offset, 1)?[0];
+        let nibble = marker & 0x0F;
+
+        Ok(match marker >> 4 {
+            0x0 => match marker {
+                0x08 => PlistValue::Bool(false),
+                0x09 => PlistValue::Bool(true),
+                _ => PlistValue::Null,
+            },
+            0x1 => {
+                let width = 1usize << nibble;
+                let bytes = self.slice(offset + 1, width)?;
+                // 1, 2 and 4 byte integers are unsigned; 8 bytes are signed
+                let value = be_uint(bytes);
+                PlistValue::Integer(if width >= 8 {
+                    i64::from_be_bytes(value.to_be_bytes())
+                } else {
+                    i64::try_from(value).unwrap_or(i64::MAX)
+                })
+            }
+            0x2 => PlistValue::Real(self.real(offset, nibble)?),
+            0x3 => PlistValue::Date(self.real(offset, 3)?),
+            0x4 => {
+                let (len, start) = self.count(nibble, offset)?;
+                PlistValue::Data(self.slice(start, len)?.to_vec())
+            }
+            0x5 => {
+                let (len, start) = self.count(nibble, offset)?;
+                PlistValue::String(String::from_utf8_lossy(self.slice(start, len)?).to_string())
+            }
+            0x6 => {
+                let (len, start) = self.count(nibble, offset)?;
+                let units: Vec<u16> = self
+                    .slice(start, len * 2)?
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                PlistValue::String(String::from_utf16_lossy(&units))
+            }
+            0x8 => PlistValue::Uid(be_uint(self.slice(offset + 1, usize::from(nibble) + 1)?)),
+            0xA | 0xC => {
+                let (len, start) = self.count(nibble, offset)?;
+                let mut items = Vec::with_capacity(len);
+                for i in 0..len {
+                    let item = self.object_ref(start + i * self.ref_size)?;
+                    items.push(self.object(item, depth + 1)?);
+                }
+                PlistValue::Array(items)
+            }
+            0xD => {
+                let (len, start) = self.count(nibble, offset)?;
+                let mut entries = Vec::with_capacity(len);
+                for i in 0..len {
+                    let key = self.object_ref(start + i * self.ref_size)?;
+                    let value = self.object_ref(start + (len + i) * self.ref_size)?;
+                    let key = match self.object(key, depth + 1)? {
+                        PlistValue::String(s) => s,
+                        other => bail!("Dictionary key is not a string: {:?}", other),
+                    };
+                    entries.push((key, self.object(value, depth + 1)?));
+                }
+                PlistValue::Dictionary(entries)
+            }
+            other => bail!("Unsupported object type {:#x} at {:#x}", other, offset),
+        })
+    }
+
+    fn real(&self, offset: usize, nibble: u8) -> Result<f64> {
+        Ok(match nibble {
+            2 => {
+                let b = self.slice(offset + 1, 4)?;
+                f64::from(f32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            3 => {
+                let b = self.slice(offset + 1, 8)?;
+                f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+            }
+            other => bail!("Unsupported real width {} at {:#x}", other, offset),
+        })
+    }
+}
+
+/// Check for the binary plist signature
+pub fn is_binary_plist(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Parse a binary property list
+pub fn parse(data: &[u8]) -> Result<PlistValue> {
+    if !is_binary_plist(data) {
+        bail!("Not a binary property list");
+    }
+    if data.len() < MAGIC.len() + TRAILER_SIZE {
+        bail!("Binary property list is truncated");
+    }
+    let trailer = &data[data.len() - TRAILER_SIZE..];
+    let offset_size = usize::from(trailer[6]);
+    let ref_size = usize::from(trailer[7]);
+    let count = be_uint(&trailer[8..16]);
+    let top = be_uint(&trailer[16..24]);
+    let table = be_uint(&trailer[24..32]);
+    if !(1..=8).contains(&offset_size) || !(1..=8).contains(&ref_size) {
+        bail!("Bad binary property list trailer");
+    }
+
+    let count = usize::try_from(count)
+        .ok()
+        .filter(|&c| c <= data.len())
+        .ok_or_else(|| anyhow!("Implausible object count {}", count))?;
+    let table = usize::try_from(table).map_err(|_| anyhow!("Bad offset table offset"))?;
+    let table_bytes = count
+        .checked_mul(offset_size)
+        .and_then(|len| data.get(table..table.checked_add(len)?))
+        .ok_or_else(|| anyhow!("Offset table is truncated"))?;
+    let offsets = table_bytes
+        .chunks_exact(offset_size)
+        .map(|c| usize::try_from(be_uint(c)).unwrap_or(usize::MAX))
+        .collect();
+
+    let reader = Reader {
+        data,
+        ref_size,
+        offsets,
+    };
+    let top = usize::try_from(top).map_err(|_| anyhow!("Bad top object {}", top))?;
+    reader.object(top, 0)
+}
+
+/// Decode an `NSKeyedArchiver` archive into JSON.
+///
+/// Collections become JSON arrays and objects, `NSString`, `NSURL`, `NSUUID`
+/// and `NSDate` become strings or numbers, and other archived objects become
+/// objects of their encoded keys plus `"$class"` with their class name.
+pub fn decode_keyed_archive(plist: &PlistValue) -> Result<Value> {
+    let objects = match plist.get("$objects") {
+        Some(PlistValue::Array(objects)) => objects,
+        _ => bail!("Not a keyed archive (no $objects)"),
+    };
+    let root = match plist.get("$top") {
+        Some(PlistValue::Dictionary(top)) => top
+            .iter()
+            .find(|(k, _)| k == "root")
+            .or_else(|| top.first())
+            .map(|(_, v)| v)
+            .ok_or_else(|| anyhow!("Keyed archive has an empty $top"))?,
+        _ => bail!("Not a keyed archive (no $top)"),
+    };
+    Ok(KeyedArchive { objects }.value(root, 0))
+}
+
+struct KeyedArchive<'a> {
+    objects: &'a [PlistValue],
+}
+
+impl KeyedArchive<'_> {
+    fn value(&self, value: &PlistValue, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        match value {
+            PlistValue::Uid(uid) => usize::try_from(*uid)
+                .ok()
+                .and_then(|i| self.objects.get(i))
+                .map(|object| self.object(object, depth + 1))
+                .unwrap_or(Value::Null),
+            PlistValue::Null => Value::Null,
+            PlistValue::Bool(b) => Value::Bool(*b),
+            PlistValue::Integer(i) => Value::from(*i),
+            PlistValue::Real(r) => Value::from(*r),
+            PlistValue::Date(d) => Value::from(*d + APPLE_EPOCH_OFFSET),
+            PlistValue::Data(bytes) => {
+                Value::String(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+            }
+            PlistValue::String(s) if s == "$null" => Value::Null,
+            PlistValue::String(s) => Value::String(s.clone()),
+            PlistValue::Array(items) => {
+                Value::Array(items.iter().map(|v| self.value(v, depth + 1)).collect())
+            }
+            PlistValue::Dictionary(_) => self.object(value, depth),
+        }
+    }
+
+    fn class_name(&self, object: &PlistValue, depth: usize) -> Option<String> {
+        match self.value(object.get("$class")?, depth + 1) {
+            Value::Object(class) => class
+                .get("$classname")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    fn object(&self, object: &PlistValue, depth: usize) -> Value {
+        let PlistValue::Dictionary(entries) = object else {
+            return self.value(object, depth);
+        };
+        let Some(class) = self.class_name(object, depth) else {
+            return Value::Object(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.value(v, depth + 1)))
+                    .collect(),
+            );
+        };
+
+        if let Some(PlistValue::Array(values)) = object.get("NS.objects") {
+            let values: Vec<Value> = values.iter().map(|v| self.value(v, depth + 1)).collect();
+            if let Some(PlistValue::Array(keys)) = object.get("NS.keys") {
+                let mut map = Map::new();
+                for (key, value) in keys.iter().zip(values) {
+                    let key = match self.value(key, depth + 1) {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    map.insert(key, value);
+                }
+                return Value::Object(map);
+            }
+            return Value::Array(values);
+        }
+        if let Some(string) = object.get("NS.string") {
+            return self.value(string, depth + 1);
+        }
+        if let Some(relative) = object.get("NS.relative") {
+            return self.value(relative, depth + 1);
+        }
+        if let Some(PlistValue::Data(bytes)) = object.get("NS.uuidbytes") {
+            return Value::String(format_uuid(bytes));
+        }
+        if let Some(PlistValue::Real(time) | PlistValue::Date(time)) = object.get("NS.time") {
+            return Value::from(time + APPLE_EPOCH_OFFSET);
+        }
+
+        let mut map = Map::new();
+        for (key, value) in entries {
+            if key != "$class" {
+                map.insert(key.clone(), self.value(value, depth + 1));
+            }
+        }
+        map.insert("$class".into(), Value::String(class));
+        Value::Object(map)
+    }
+}
+
+fn format_uuid(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    if hex.len() != 32 {
+        return hex;
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::binary_plist;
+
+    fn dict(entries: &[(&str, PlistValue)]) -> PlistValue {
+        PlistValue::Dictionary(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    fn string(s: &str) -> PlistValue {
+        PlistValue::String(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let value = dict(&[
+            ("name", string("Example")),
+            ("unicode", string("caf\u{e9} \u{2603}")),
+            ("count", PlistValue::Integer(300)),
+            ("negative", PlistValue::Integer(-5)),
+            ("enabled", PlistValue::Bool(true)),
+            ("blob", PlistValue::Data(vec![1, 2, 3])),
+            (
+                "list",
+                PlistValue::Array((0..20).map(PlistValue::Integer).collect()),
+            ),
+        ]);
+        assert_eq!(parse(&binary_plist(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(parse(b"not a plist").is_err());
+        let data = binary_plist(&dict(&[("name", string("Example"))]));
+        assert!(parse(&data[..data.len() - 8]).is_err());
+        assert!(parse(&data[..20]).is_err());
+    }
+
+    #[test]
+    fn test_decode_keyed_archive() {
+        let archive = dict(&[
+            ("$archiver", string("NSKeyedArchiver")),
+            ("$top", dict(&[("root", PlistValue::Uid(1))])),
+            (
+                "$objects",
+                PlistValue::Array(vec![
+                    string("$null"),
+                    dict(&[
+                        ("$class", PlistValue::Uid(5)),
+                        ("name", PlistValue::Uid(2)),
+                        ("url", PlistValue::Uid(3)),
+                        ("missing", PlistValue::Uid(0)),
+                        ("items", PlistValue::Uid(6)),
+                    ]),
+                    string("Example"),
+                    dict(&[
+                        ("$class", PlistValue::Uid(4)),
+                        ("NS.base", PlistValue::Uid(0)),
+                        ("NS.relative", PlistValue::Uid(8)),
+                    ]),
+                    dict(&[("$classname", string("NSURL"))]),
+                    dict(&[("$classname", string("ItemRecord"))]),
+                    dict(&[
+                        ("$class", PlistValue::Uid(7)),
+                        (
+                            "NS.objects",
+                            PlistValue::Array(vec![PlistValue::Uid(2), PlistValue::Uid(2)]),
+                        ),
+                    ]),
+                    dict(&[("$classname", string("NSArray"))]),
+                    string("file:///Applications/Example.app/"),
+                ]),
+            ),
+        ]);
+
+        let decoded = decode_keyed_archive(&archive).unwrap();
+        assert_eq!(decoded["$class"], "ItemRecord");
+        assert_eq!(decoded["name"], "Example");
+        assert_eq!(decoded["url"], "file:///Applications/Example.app/");
+        assert_eq!(decoded["missing"], Value::Null);
+        assert_eq!(decoded["items"], serde_json::json!(["Example", "Example"]));
+
+        assert!(decode_keyed_archive(&dict(&[("name", string("x"))])).is_err());
+    }
+}
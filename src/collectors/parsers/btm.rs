@@ -0,0 +1,323 @@
+//! Background Task Management (BTM) database parser.
+//!
+//! Since macOS 13, login items, launch agents and daemons, and the apps that
+//! install them are tracked in
+//! `/private/var/db/com.apple.backgroundtaskmanagement/BackgroundItems-v<N>.btm`,
+//! an `NSKeyedArchiver` binary plist. Items are listed per user UUID under
+//! `itemsByUserIdentifier`; each `ItemRecord` names the item, its executable
+//! or URL, and the app that manages it, either through
+//! `associatedBundleIdentifiers` or by listing the item among its own `items`.
+//!
+//! `type` and `disposition` are reported as the raw flag values; `sfltool
+//! dumpbtm` (captured under `volatile/macos_persistence/`) decodes them on a
+//! live system.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{bplist, ParsedReport};
+
+/// Report file name
+pub const BTM_ITEMS_FILE: &str = "btm_items.json";
+
+/// Prefix of BTM database file names (`BackgroundItems-v4.btm`, ...)
+pub const BTM_FILE_PREFIX: &str = "BackgroundItems-v";
+
+/// A background item from the BTM database
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BtmItem {
+    pub name: Option<String>,
+    pub identifier: Option<String>,
+    /// Executable run by the item (launch agents and daemons)
+    pub executable_path: Option<String>,
+    /// Bundle or plist URL of the item
+    pub url: Option<String>,
+    pub bundle_identifier: Option<String>,
+    pub team_identifier: Option<String>,
+    pub developer_name: Option<String>,
+    /// App that installed or manages the item
+    pub managing_app: Option<String>,
+    /// Identifier of the item listing this one among its `items`
+    pub parent_identifier: Option<String>,
+    /// Raw `type` flags
+    pub item_type: Option<i64>,
+    /// Raw `disposition` flags (enabled, allowed, notified)
+    pub disposition: Option<i64>,
+    /// UUID of the user the item is registered for
+    pub user_uuid: Option<String>,
+    /// Collected database file
+    pub source: String,
+}
+
+/// Whether `path` is named like a BTM database
+pub fn is_btm_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .is_some_and(|name| name.starts_with(BTM_FILE_PREFIX) && name.ends_with(".btm"))
+}
+
+fn string_field(item: &Value, key: &str) -> Option<String> {
+    item.get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn is_item_record(value: &Value) -> bool {
+    let class_matches = value
+        .get("$class")
+        .and_then(Value::as_str)
+        .is_some_and(|class| class.ends_with("ItemRecord"));
+    class_matches
+        || (value.get("identifier").is_some()
+            && (value.get("executablePath").is_some() || value.get("url").is_some()))
+}
+
+/// Collect item records below `value`, with the user UUID they are listed under
+fn find_items<'a>(
+    value: &'a Value,
+    user: Option<&'a str>,
+    out: &mut Vec<(&'a Value, Option<&'a str>)>,
+) {
+    match value {
+        Value::Object(_) if is_item_record(value) => out.push((value, user)),
+        Value::Object(map) => {
+            for (key, child) in map {
+                if key == "itemsByUserIdentifier" {
+                    if let Value::Object(users) = child {
+                        for (uuid, items) in users {
+                            find_items(items, Some(uuid), out);
+                        }
+                        continue;
+                    }
+                }
+                find_items(child, user, out);
+            }
+        }
+        Value::Array(items) => {
+            for child in items {
+                find_items(child, user, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parse the contents of a BTM database
+pub fn parse_btm(data: &[u8], source: &str) -> Result<Vec<BtmItem>> {
+    let archive = bplist::decode_keyed_archive(&bplist::parse(data)?)?;
+    let mut records = Vec::new();
+    find_items(&archive, None, &mut records);
+
+    // Parents list their children's identifiers in `items`
+    let mut parents: HashMap<&str, &Value> = HashMap::new();
+    for (record, _) in &records {
+        if let Some(Value::Array(children)) = record.get("items") {
+            for child in children.iter().filter_map(Value::as_str) {
+                parents.insert(child, *record);
+            }
+        }
+    }
+
+    Ok(records
+        .iter()
+        .map(|(record, user)| {
+            let identifier = string_field(record, "identifier");
+            let parent = identifier
+                .as_deref()
+                .and_then(|id| parents.get(id))
+                .copied();
+            let associated = record
+                .get("associatedBundleIdentifiers")
+                .and_then(Value::as_array)
+                .and_then(|ids| ids.iter().find_map(Value::as_str))
+                .map(str::to_string);
+            let managing_app = associated.or_else(|| {
+                parent.and_then(|p| {
+                    string_field(p, "bundleIdentifier").or_else(|| string_field(p, "name"))
+                })
+            });
+            BtmItem {
+                name: string_field(record, "name"),
+                executable_path: string_field(record, "executablePath"),
+                url: string_field(record, "url"),
+                bundle_identifier: string_field(record, "bundleIdentifier"),
+                team_identifier: string_field(record, "teamIdentifier"),
+                developer_name: string_field(record, "developerName"),
+                managing_app,
+                parent_identifier: parent.and_then(|p| string_field(p, "identifier")),
+                item_type: record.get("type").and_then(Value::as_i64),
+                disposition: record.get("disposition").and_then(Value::as_i64),
+                user_uuid: user.map(str::to_string),
+                source: source.to_string(),
+                identifier,
+            }
+        })
+        .collect())
+}
+
+/// Parse each collected BTM database; files that cannot be parsed are noted
+/// and skipped
+pub fn parse_btm_files(files: &[PathBuf]) -> Result<ParsedReport<BtmItem>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files {
+        let source = file.to_string_lossy().to_string();
+        let parsed = fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| parse_btm(&data, &source));
+        match parsed {
+            Ok(items) => report.records.extend(items),
+            Err(e) => report.notes.push(format!(
+                "Skipped {}: {}",
+                file.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::bplist::PlistValue;
+    use crate::collectors::parsers::fixtures::KeyedArchiveBuilder;
+    use tempfile::TempDir;
+
+    const USER: &str = "A1B2C3D4-0000-1111-2222-333344445555";
+
+    fn sample_btm() -> Vec<u8> {
+        let mut archive = KeyedArchiveBuilder::new();
+
+        let agent_id = archive.string("16.com.example.updater");
+        let agent_name = archive.string("com.example.updater");
+        let agent_exe = archive.string("/Library/Application Support/Example/updater");
+        let agent_url = archive.string("file:///Library/LaunchAgents/com.example.updater.plist");
+        let agent_url = archive.object(
+            "NSURL",
+            vec![("NS.base", archive.null()), ("NS.relative", agent_url)],
+        );
+        let agent = archive.object(
+            "ItemRecord",
+            vec![
+                ("identifier", agent_id),
+                ("name", agent_name),
+                ("executablePath", agent_exe),
+                ("url", agent_url),
+                ("type", PlistValue::Integer(0x10008)),
+                ("disposition", PlistValue::Integer(3)),
+            ],
+        );
+
+        let app_id = archive.string("2.com.example.app");
+        let app_name = archive.string("Example");
+        let app_bundle = archive.string("com.example.app");
+        let team = archive.string("ABCDE12345");
+        let child_id = archive.string("16.com.example.updater");
+        let children = archive.array(vec![child_id]);
+        let app = archive.object(
+            "ItemRecord",
+            vec![
+                ("identifier", app_id),
+                ("name", app_name),
+                ("bundleIdentifier", app_bundle),
+                ("teamIdentifier", team),
+                ("items", children),
+                ("type", PlistValue::Integer(2)),
+            ],
+        );
+
+        let login_id = archive.string("4.com.other.helper");
+        let login_name = archive.string("Other Helper");
+        let login_url = archive
+            .string("file:///Applications/Other.app/Contents/Library/LoginItems/Helper.app/");
+        let associated = archive.string("com.other.app");
+        let associated = archive.array(vec![associated]);
+        let login = archive.object(
+            "ItemRecord",
+            vec![
+                ("identifier", login_id),
+                ("name", login_name),
+                ("url", login_url),
+                ("associatedBundleIdentifiers", associated),
+                ("type", PlistValue::Integer(4)),
+            ],
+        );
+
+        let items = archive.array(vec![app, agent, login]);
+        let user = archive.string(USER);
+        let by_user = archive.dictionary(vec![(user, items)]);
+        let store = archive.object("Storage", vec![("itemsByUserIdentifier", by_user)]);
+        let root = archive.object(
+            "Container",
+            vec![("store", store), ("version", PlistValue::Integer(4))],
+        );
+        archive.finish(root)
+    }
+
+    #[test]
+    fn test_parse_btm() {
+        let items = parse_btm(&sample_btm(), "BackgroundItems-v4.btm").unwrap();
+        assert_eq!(items.len(), 3);
+
+        let agent = items
+            .iter()
+            .find(|i| i.name.as_deref() == Some("com.example.updater"))
+            .unwrap();
+        assert_eq!(
+            agent.executable_path.as_deref(),
+            Some("/Library/Application Support/Example/updater")
+        );
+        assert_eq!(
+            agent.url.as_deref(),
+            Some("file:///Library/LaunchAgents/com.example.updater.plist")
+        );
+        assert_eq!(agent.managing_app.as_deref(), Some("com.example.app"));
+        assert_eq!(
+            agent.parent_identifier.as_deref(),
+            Some("2.com.example.app")
+        );
+        assert_eq!(agent.item_type, Some(0x10008));
+        assert_eq!(agent.disposition, Some(3));
+        assert_eq!(agent.user_uuid.as_deref(), Some(USER));
+
+        let app = items
+            .iter()
+            .find(|i| i.name.as_deref() == Some("Example"))
+            .unwrap();
+        assert_eq!(app.team_identifier.as_deref(), Some("ABCDE12345"));
+        assert_eq!(app.managing_app, None);
+
+        let login = items
+            .iter()
+            .find(|i| i.name.as_deref() == Some("Other Helper"))
+            .unwrap();
+        assert_eq!(login.managing_app.as_deref(), Some("com.other.app"));
+    }
+
+    #[test]
+    fn test_parse_btm_files_notes_bad_files() {
+        let temp = TempDir::new().unwrap();
+        let good = temp.path().join("BackgroundItems-v4.btm");
+        let bad = temp.path().join("BackgroundItems-v9.btm");
+        fs::write(&good, sample_btm()).unwrap();
+        fs::write(&bad, b"bplist00 but not really").unwrap();
+
+        assert!(is_btm_file(&good));
+        assert!(!is_btm_file(
+            &temp.path().join("BackgroundItems-v4.btm.bak")
+        ));
+
+        let report = parse_btm_files(&[good, bad]).unwrap();
+        assert_eq!(report.records.len(), 3);
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].starts_with("Skipped BackgroundItems-v9.btm:"));
+    }
+}
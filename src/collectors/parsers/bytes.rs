@@ -0,0 +1,98 @@
+//! Bounds-checked integer readers for the binary parsers.
+//!
+//! Every reader takes the buffer and a byte offset and fails with
+//! [`OutOfBounds`] instead of panicking when the value does not fit. The error
+//! converts into `anyhow::Error`, so parsers returning `anyhow::Result` use
+//! `?` directly; parsers that skip malformed structures use `.ok()?`.
+
+use std::fmt;
+
+/// A read that runs past the end of its buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub offset: usize,
+    /// Length of the buffer that was read
+    pub len: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Read past end of {} byte buffer at offset {:#x}",
+            self.len, self.offset
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+fn array_at<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], OutOfBounds> {
+    offset
+        .checked_add(N)
+        .and_then(|end| data.get(offset..end))
+        .map(|b| b.try_into().expect("slice has the array length"))
+        .ok_or(OutOfBounds {
+            offset,
+            len: data.len(),
+        })
+}
+
+/// Little-endian `u16` at `offset`
+pub fn u16_at(data: &[u8], offset: usize) -> Result<u16, OutOfBounds> {
+    array_at(data, offset).map(u16::from_le_bytes)
+}
+
+/// Little-endian `u32` at `offset`
+pub fn u32_at(data: &[u8], offset: usize) -> Result<u32, OutOfBounds> {
+    array_at(data, offset).map(u32::from_le_bytes)
+}
+
+/// Little-endian `u64` at `offset`
+pub fn u64_at(data: &[u8], offset: usize) -> Result<u64, OutOfBounds> {
+    array_at(data, offset).map(u64::from_le_bytes)
+}
+
+/// Big-endian `u16` at `offset`
+pub fn u16_be_at(data: &[u8], offset: usize) -> Result<u16, OutOfBounds> {
+    array_at(data, offset).map(u16::from_be_bytes)
+}
+
+/// Big-endian `u32` at `offset`
+pub fn u32_be_at(data: &[u8], offset: usize) -> Result<u32, OutOfBounds> {
+    array_at(data, offset).map(u32::from_be_bytes)
+}
+
+/// Big-endian `u64` at `offset`
+pub fn u64_be_at(data: &[u8], offset: usize) -> Result<u64, OutOfBounds> {
+    array_at(data, offset).map(u64::from_be_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_both_byte_orders() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        assert_eq!(u16_at(&data, 1), Ok(0x0302));
+        assert_eq!(u32_at(&data, 0), Ok(0x0403_0201));
+        assert_eq!(u64_at(&data, 1), Ok(0x0908_0706_0504_0302));
+        assert_eq!(u16_be_at(&data, 1), Ok(0x0203));
+        assert_eq!(u32_be_at(&data, 0), Ok(0x0102_0304));
+        assert_eq!(u64_be_at(&data, 1), Ok(0x0203_0405_0607_0809));
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let data = [0u8; 4];
+        assert_eq!(u32_at(&data, 0), Ok(0));
+        assert_eq!(u32_at(&data, 1), Err(OutOfBounds { offset: 1, len: 4 }));
+        assert!(u64_at(&data, 0).is_err());
+        assert!(u16_at(&data, usize::MAX).is_err());
+        assert_eq!(
+            u16_at(&data, 4).unwrap_err().to_string(),
+            "Read past end of 4 byte buffer at offset 0x4"
+        );
+    }
+}
@@ -0,0 +1,859 @@
+//! Minimal read-only ESE (Extensible Storage Engine) database reader.
+//!
+//! Supports what is needed to pull rows out of collected Windows databases
+//! such as SRUDB.dat: the file header (including the shutdown state), the
+//! MSysObjects catalog, B-tree traversal of table data, and decoding of
+//! fixed, variable and tagged columns. Tagged values compressed with the
+//! 7-bit schemes are decoded; values stored in the long-value tree or
+//! compressed with Xpress are reported as [`EseValue::Unsupported`].
+//!
+//! Pages are read on demand and every offset is bounds checked, so a damaged
+//! or dirty database yields errors and skipped pages rather than panics.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+
+use super::bytes::{u16_at, u32_at};
+
+/// File header signature
+const ESE_SIGNATURE: u32 = 0x89ab_cdef;
+
+/// Root page of the MSysObjects catalog
+const CATALOG_FDP: u32 = 4;
+
+/// Pages larger than this use the extended page header and tag layout
+const LARGE_PAGE_THRESHOLD: usize = 8192;
+
+const PAGE_FLAG_LEAF: u32 = 0x0002;
+const PAGE_FLAG_PARENT: u32 = 0x0004;
+const PAGE_FLAG_EMPTY: u32 = 0x0008;
+const PAGE_FLAG_SPACE_TREE: u32 = 0x0020;
+
+const TAG_FLAG_DELETED: u16 = 0x2;
+const TAG_FLAG_COMMON_KEY: u16 = 0x4;
+
+/// Catalog object types
+const CATALOG_TYPE_TABLE: i64 = 1;
+const CATALOG_TYPE_COLUMN: i64 = 2;
+
+/// Sizes of the MSysObjects fixed columns, in column id order
+const CATALOG_FIXED_SIZES: [usize; 11] = [4, 2, 4, 4, 4, 4, 4, 1, 2, 4, 2];
+
+/// Column type identifiers (JET_coltyp)
+pub mod coltyp {
+    pub const BIT: u32 = 1;
+    pub const UNSIGNED_BYTE: u32 = 2;
+    pub const SHORT: u32 = 3;
+    pub const LONG: u32 = 4;
+    pub const CURRENCY: u32 = 5;
+    pub const IEEE_SINGLE: u32 = 6;
+    pub const IEEE_DOUBLE: u32 = 7;
+    pub const DATE_TIME: u32 = 8;
+    pub const TEXT: u32 = 10;
+    pub const LONG_BINARY: u32 = 11;
+    pub const LONG_TEXT: u32 = 12;
+    pub const UNSIGNED_LONG: u32 = 14;
+    pub const LONG_LONG: u32 = 15;
+    pub const GUID: u32 = 16;
+    pub const UNSIGNED_SHORT: u32 = 17;
+}
+
+/// Code page marking UTF-16 text columns
+const CODEPAGE_UNICODE: u32 = 1200;
+
+/// Database shutdown state from the file header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseState {
+    JustCreated,
+    DirtyShutdown,
+    CleanShutdown,
+    BeingConverted,
+    ForceDetach,
+    Unknown,
+}
+
+impl DatabaseState {
+    fn from_raw(raw: u32) -> Self {
+        match raw {
+            1 => Self::JustCreated,
+            2 => Self::DirtyShutdown,
+            3 => Self::CleanShutdown,
+            4 => Self::BeingConverted,
+            5 => Self::ForceDetach,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Whether transaction logs still need to be replayed into the database
+    pub fn is_dirty(&self) -> bool {
+        !matches!(self, Self::CleanShutdown)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::JustCreated => "just_created",
+            Self::DirtyShutdown => "dirty_shutdown",
+            Self::CleanShutdown => "clean_shutdown",
+            Self::BeingConverted => "being_converted",
+            Self::ForceDetach => "force_detach",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Fields of the database file header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EseHeader {
+    pub format_version: u32,
+    pub format_revision: u32,
+    pub page_size: usize,
+    pub state: DatabaseState,
+}
+
+/// A column definition from the catalog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDef {
+    pub id: u32,
+    pub name: String,
+    pub column_type: u32,
+    pub size: u32,
+    pub codepage: u32,
+}
+
+/// A table definition from the catalog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableDef {
+    pub name: String,
+    pub fdp: u32,
+    pub columns: Vec<ColumnDef>,
+}
+
+/// A decoded column value
+#[derive(Debug, Clone, PartialEq)]
+pub enum EseValue {
+    Int(i64),
+    /// OLE automation date (days since 1899-12-30)
+    DateTime(f64),
+    Float(f64),
+    Binary(Vec<u8>),
+    Text(String),
+    Guid([u8; 16]),
+    /// Value present but stored in a form this reader does not decode
+    Unsupported(&'static str),
+}
+
+impl EseValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// A table row keyed by column name; null columns are absent
+pub type Row = HashMap<String, EseValue>;
+
+/// Rows read from a table, plus the number of pages that could not be read
+#[derive(Debug, Default)]
+pub struct TableScan {
+    pub rows: Vec<Row>,
+    pub skipped_pages: usize,
+}
+
+/// An open ESE database
+pub struct EseDatabase {
+    file: File,
+    header: EseHeader,
+    tables: Vec<TableDef>,
+}
+
+fn slice_at(buf: &[u8], start: usize, end: usize) -> Result<&[u8]> {
+    buf.get(start..end).ok_or_else(|| {
+        anyhow!(
+            "Invalid range {}..{} in {} byte buffer",
+            start,
+            end,
+            buf.len()
+        )
+    })
+}
+
+/// Parse the database file header from the first page
+pub fn parse_header(buf: &[u8]) -> Result<EseHeader> {
+    let signature = u32_at(buf, 4)?;
+    if signature != ESE_SIGNATURE {
+        return Err(anyhow!("Not an ESE database (signature {:#x})", signature));
+    }
+
+    let page_size = u32_at(buf, 236)? as usize;
+    if !matches!(page_size, 2048 | 4096 | 8192 | 16384 | 32768) {
+        return Err(anyhow!("Unsupported ESE page size {}", page_size));
+    }
+
+    Ok(EseHeader {
+        format_version: u32_at(buf, 8)?,
+        format_revision: u32_at(buf, 232)?,
+        page_size,
+        state: DatabaseState::from_raw(u32_at(buf, 52)?),
+    })
+}
+
+/// Decode a 7-bit compressed value (ASCII or UTF-16 with zero high bytes)
+pub fn decompress_7bit(data: &[u8]) -> Option<String> {
+    let (&first, packed) = data.split_first()?;
+    let unicode = match first >> 3 {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+
+    let count = packed.len() * 8 / 7;
+    let mut units = Vec::with_capacity(count);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for &byte in packed {
+        bits |= (byte as u32) << bit_count;
+        bit_count += 8;
+        while bit_count >= 7 && units.len() < count {
+            units.push((bits & 0x7f) as u16);
+            bits >>= 7;
+            bit_count -= 7;
+        }
+    }
+
+    let text: String = if unicode {
+        String::from_utf16_lossy(&units)
+    } else {
+        units.iter().map(|&u| u as u8 as char).collect()
+    };
+    // Trailing bits that do not fill a character decode as NUL
+    Some(text.trim_end_matches('\0').to_string())
+}
+
+fn decode_text(data: &[u8], codepage: u32) -> String {
+    if codepage == CODEPAGE_UNICODE {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+            .trim_end_matches('\0')
+            .to_string()
+    } else {
+        String::from_utf8_lossy(data)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+}
+
+fn signed(data: &[u8]) -> i64 {
+    match data.len() {
+        1 => data[0] as i8 as i64,
+        2 => i16::from_le_bytes([data[0], data[1]]) as i64,
+        4 => i32::from_le_bytes([data[0], data[1], data[2], data[3]]) as i64,
+        8 => i64::from_le_bytes(data.try_into().unwrap_or_default()),
+        _ => 0,
+    }
+}
+
+fn unsigned(data: &[u8]) -> i64 {
+    match data.len() {
+        1 => data[0] as i64,
+        2 => u16::from_le_bytes([data[0], data[1]]) as i64,
+        4 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as i64,
+        _ => signed(data),
+    }
+}
+
+/// Decode raw column bytes according to the column type
+fn decode_value(column: &ColumnDef, data: &[u8]) -> EseValue {
+    match column.column_type {
+        coltyp::BIT | coltyp::UNSIGNED_BYTE | coltyp::UNSIGNED_SHORT | coltyp::UNSIGNED_LONG => {
+            EseValue::Int(unsigned(data))
+        }
+        coltyp::SHORT | coltyp::LONG | coltyp::CURRENCY | coltyp::LONG_LONG => {
+            EseValue::Int(signed(data))
+        }
+        coltyp::IEEE_SINGLE if data.len() == 4 => {
+            EseValue::Float(f32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64)
+        }
+        coltyp::IEEE_DOUBLE if data.len() == 8 => {
+            EseValue::Float(f64::from_le_bytes(data.try_into().unwrap_or_default()))
+        }
+        coltyp::DATE_TIME if data.len() == 8 => {
+            EseValue::DateTime(f64::from_le_bytes(data.try_into().unwrap_or_default()))
+        }
+        coltyp::GUID if data.len() == 16 => EseValue::Guid(data.try_into().unwrap_or_default()),
+        coltyp::TEXT | coltyp::LONG_TEXT => EseValue::Text(decode_text(data, column.codepage)),
+        _ => EseValue::Binary(data.to_vec()),
+    }
+}
+
+/// Size of a fixed column of the given type, if it is fixed size
+fn fixed_size(column_type: u32) -> Option<usize> {
+    match column_type {
+        coltyp::BIT | coltyp::UNSIGNED_BYTE => Some(1),
+        coltyp::SHORT | coltyp::UNSIGNED_SHORT => Some(2),
+        coltyp::LONG | coltyp::IEEE_SINGLE | coltyp::UNSIGNED_LONG => Some(4),
+        coltyp::CURRENCY | coltyp::IEEE_DOUBLE | coltyp::DATE_TIME | coltyp::LONG_LONG => Some(8),
+        coltyp::GUID => Some(16),
+        _ => None,
+    }
+}
+
+/// Raw column data of one record, before type decoding
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RawRecord {
+    /// Fixed column data by column id (1..=last fixed); `None` when null
+    fixed: Vec<Option<Vec<u8>>>,
+    /// Variable column data by column id (128..); `None` when null
+    variable: Vec<Option<Vec<u8>>>,
+    /// Tagged column data keyed by column id
+    tagged: Vec<(u32, TaggedData)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TaggedData {
+    Plain(Vec<u8>),
+    Compressed(Vec<u8>),
+    LongValue,
+}
+
+/// Split a data definition record into fixed, variable and tagged parts
+fn parse_record(data: &[u8], fixed_sizes: &[usize], large_pages: bool) -> Result<RawRecord> {
+    let last_fixed = *data.first().ok_or_else(|| anyhow!("Empty record"))? as usize;
+    let last_variable = *data.get(1).ok_or_else(|| anyhow!("Truncated record"))? as usize;
+    let variable_offset = u16_at(data, 2)? as usize;
+
+    let mut record = RawRecord::default();
+
+    // Fixed columns, then a null bitmap with one bit per fixed column
+    let mut offset = 4;
+    let mut spans = Vec::with_capacity(last_fixed);
+    for id in 1..=last_fixed {
+        let size = *fixed_sizes
+            .get(id - 1)
+            .ok_or_else(|| anyhow!("Record has unknown fixed column {}", id))?;
+        spans.push((offset, size));
+        offset += size;
+    }
+    let bitmap = slice_at(data, offset, offset + last_fixed.div_ceil(8))?;
+    for (index, (start, size)) in spans.into_iter().enumerate() {
+        let is_null = bitmap[index / 8] & (1 << (index % 8)) != 0;
+        record.fixed.push(if is_null {
+            None
+        } else {
+            Some(slice_at(data, start, start + size)?.to_vec())
+        });
+    }
+
+    // Variable columns: an array of end offsets (bit 15 marks null)
+    let variable_count = last_variable.saturating_sub(127);
+    let variable_data = variable_offset + variable_count * 2;
+    let mut previous_end = 0;
+    for index in 0..variable_count {
+        let raw_end = u16_at(data, variable_offset + index * 2)?;
+        let end = (raw_end & 0x7fff) as usize;
+        if raw_end & 0x8000 != 0 {
+            record.variable.push(None);
+        } else {
+            let value = slice_at(data, variable_data + previous_end, variable_data + end)?;
+            record.variable.push(Some(value.to_vec()));
+        }
+        previous_end = end;
+    }
+
+    // Tagged columns: (id, offset) pairs; the first offset is the array size
+    let tagged_start = variable_data + previous_end;
+    if tagged_start < data.len() {
+        let tagged = &data[tagged_start..];
+        let offset_mask = if large_pages { 0x7fff } else { 0x3fff };
+        let array_size = (u16_at(tagged, 2)? & offset_mask) as usize;
+        let count = array_size / 4;
+
+        let mut entries = Vec::with_capacity(count);
+        for index in 0..count {
+            let id = u16_at(tagged, index * 4)? as u32;
+            let raw_offset = u16_at(tagged, index * 4 + 2)?;
+            entries.push((id, raw_offset));
+        }
+
+        for (index, &(id, raw_offset)) in entries.iter().enumerate() {
+            let start = (raw_offset & offset_mask) as usize;
+            let end = entries
+                .get(index + 1)
+                .map(|&(_, next)| (next & offset_mask) as usize)
+                .unwrap_or(tagged.len());
+            let value = slice_at(tagged, start, end)?;
+
+            // Large pages always carry a flags byte; small pages flag it in bit 14
+            let has_flags = large_pages || raw_offset & 0x4000 != 0;
+            let entry = if has_flags && !value.is_empty() {
+                let flags = value[0];
+                let payload = value[1..].to_vec();
+                if flags & 0x04 != 0 {
+                    TaggedData::LongValue
+                } else if flags & 0x02 != 0 {
+                    TaggedData::Compressed(payload)
+                } else {
+                    TaggedData::Plain(payload)
+                }
+            } else {
+                TaggedData::Plain(value.to_vec())
+            };
+            record.tagged.push((id, entry));
+        }
+    }
+
+    Ok(record)
+}
+
+/// A page tag: value offset within the page, value size and tag flags
+struct PageTag {
+    offset: usize,
+    size: usize,
+    flags: u16,
+}
+
+struct Page {
+    data: Vec<u8>,
+    flags: u32,
+    header_size: usize,
+    tags: Vec<PageTag>,
+}
+
+impl Page {
+    fn parse(data: Vec<u8>, page_size: usize) -> Result<Self> {
+        let large = page_size > LARGE_PAGE_THRESHOLD;
+        let header_size = if large { 80 } else { 40 };
+        let tag_count = u16_at(&data, 34)? as usize;
+        let flags = u32_at(&data, 36)?;
+
+        if tag_count * 4 + header_size > page_size {
+            return Err(anyhow!("Page has implausible tag count {}", tag_count));
+        }
+
+        let mut tags = Vec::with_capacity(tag_count);
+        for index in 0..tag_count {
+            let position = page_size - 4 * (index + 1);
+            let raw_size = u16_at(&data, position)?;
+            let raw_offset = u16_at(&data, position + 2)?;
+            let (size, offset, tag_flags) = if large {
+                let offset = (raw_offset & 0x7fff) as usize;
+                // Flags live in the top bits of the value's first word
+                let value_flags = u16_at(&data, header_size + offset)
+                    .map(|v| v >> 13)
+                    .unwrap_or(0);
+                ((raw_size & 0x7fff) as usize, offset, value_flags)
+            } else {
+                (
+                    (raw_size & 0x1fff) as usize,
+                    (raw_offset & 0x1fff) as usize,
+                    raw_offset >> 13,
+                )
+            };
+            tags.push(PageTag {
+                offset,
+                size,
+                flags: tag_flags,
+            });
+        }
+
+        Ok(Self {
+            data,
+            flags,
+            header_size,
+            tags,
+        })
+    }
+
+    fn value(&self, tag: &PageTag) -> Result<&[u8]> {
+        let start = self.header_size + tag.offset;
+        slice_at(&self.data, start, start + tag.size)
+    }
+
+    /// Entry values (tag 0 is the page's external header), split into key and data
+    fn entries(&self, large_pages: bool) -> Vec<Result<&[u8]>> {
+        self.tags
+            .iter()
+            .skip(1)
+            .filter(|tag| tag.flags & TAG_FLAG_DELETED == 0)
+            .map(|tag| {
+                let value = self.value(tag)?;
+                let mut offset = 0;
+                if tag.flags & TAG_FLAG_COMMON_KEY != 0 {
+                    offset += 2;
+                }
+                let key_mask = if large_pages { 0x1fff } else { 0xffff };
+                let local_key = (u16_at(value, offset)? & key_mask) as usize;
+                offset += 2 + local_key;
+                slice_at(value, offset, value.len())
+            })
+            .collect()
+    }
+}
+
+/// Read only the file header, e.g. to check the shutdown state
+pub fn read_header(path: &Path) -> Result<EseHeader> {
+    let mut file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    read_header_from(&mut file)
+}
+
+fn read_header_from(file: &mut File) -> Result<EseHeader> {
+    let mut first_page = vec![0u8; 4096];
+    file.read_exact(&mut first_page)
+        .context("Failed to read ESE file header")?;
+    parse_header(&first_page)
+}
+
+impl EseDatabase {
+    /// Open a database and load its catalog
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+        let header = read_header_from(&mut file)?;
+        debug!(
+            "ESE database {}: version {:#x} revision {:#x}, {} byte pages, state {}",
+            path.display(),
+            header.format_version,
+            header.format_revision,
+            header.page_size,
+            header.state.as_str()
+        );
+
+        let mut database = Self {
+            file,
+            header,
+            tables: Vec::new(),
+        };
+        database.tables = database.load_catalog()?;
+        Ok(database)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableDef> {
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    fn large_pages(&self) -> bool {
+        self.header.page_size > LARGE_PAGE_THRESHOLD
+    }
+
+    fn read_page(&self, number: u32) -> Result<Page> {
+        let page_size = self.header.page_size;
+        let offset = (number as u64 + 1) * page_size as u64;
+        let mut data = vec![0u8; page_size];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut data)
+            .context(format!("Failed to read page {}", number))?;
+        Page::parse(data, page_size).context(format!("Invalid page {}", number))
+    }
+
+    /// Collect leaf record data of the B-tree rooted at `root`
+    fn walk_tree(&self, root: u32, records: &mut Vec<Vec<u8>>) -> usize {
+        let mut skipped = 0;
+        let mut visited = HashSet::new();
+        let mut stack = vec![root];
+
+        while let Some(number) = stack.pop() {
+            if !visited.insert(number) {
+                continue;
+            }
+            let page = match self.read_page(number) {
+                Ok(page) => page,
+                Err(e) => {
+                    warn!("Skipping unreadable ESE page {}: {:#}", number, e);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            if page.flags & (PAGE_FLAG_EMPTY | PAGE_FLAG_SPACE_TREE) != 0 {
+                continue;
+            }
+
+            let mut children = Vec::new();
+            for entry in page.entries(self.large_pages()) {
+                match entry {
+                    Ok(data) if page.flags & PAGE_FLAG_LEAF != 0 => records.push(data.to_vec()),
+                    Ok(data) if page.flags & PAGE_FLAG_PARENT != 0 => match u32_at(data, 0) {
+                        Ok(child) => children.push(child),
+                        Err(_) => skipped += 1,
+                    },
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("Skipping damaged entry on page {}: {:#}", number, e);
+                    }
+                }
+            }
+            // Visit children left to right
+            stack.extend(children.into_iter().rev());
+        }
+
+        skipped
+    }
+
+    fn load_catalog(&self) -> Result<Vec<TableDef>> {
+        let mut records = Vec::new();
+        let skipped = self.walk_tree(CATALOG_FDP, &mut records);
+        if records.is_empty() {
+            return Err(anyhow!(
+                "ESE catalog is empty or unreadable ({} pages skipped)",
+                skipped
+            ));
+        }
+
+        let mut tables: Vec<(i64, TableDef)> = Vec::new();
+        let mut columns: Vec<(i64, ColumnDef)> = Vec::new();
+
+        for data in records {
+            let record = match parse_record(&data, &CATALOG_FIXED_SIZES, self.large_pages()) {
+                Ok(record) => record,
+                Err(e) => {
+                    debug!("Skipping unreadable catalog record: {:#}", e);
+                    continue;
+                }
+            };
+            let fixed = |id: usize| -> Option<i64> {
+                record
+                    .fixed
+                    .get(id - 1)
+                    .and_then(|v| v.as_deref())
+                    .map(signed)
+            };
+            let name = record
+                .variable
+                .first()
+                .and_then(|v| v.as_deref())
+                .map(|n| decode_text(n, 0))
+                .unwrap_or_default();
+
+            let (object_id, object_type, id, coltyp_or_fdp) =
+                match (fixed(1), fixed(2), fixed(3), fixed(4)) {
+                    (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+                    _ => continue,
+                };
+
+            match object_type {
+                CATALOG_TYPE_TABLE => tables.push((
+                    object_id,
+                    TableDef {
+                        name,
+                        fdp: coltyp_or_fdp as u32,
+                        columns: Vec::new(),
+                    },
+                )),
+                CATALOG_TYPE_COLUMN => columns.push((
+                    object_id,
+                    ColumnDef {
+                        id: id as u32,
+                        name,
+                        column_type: coltyp_or_fdp as u32,
+                        size: fixed(5).unwrap_or(0) as u32,
+                        codepage: fixed(7).unwrap_or(0) as u32,
+                    },
+                )),
+                _ => {}
+            }
+        }
+
+        for (object_id, column) in columns {
+            if let Some((_, table)) = tables.iter_mut().find(|(id, _)| *id == object_id) {
+                table.columns.push(column);
+            }
+        }
+        for (_, table) in tables.iter_mut() {
+            table.columns.sort_by_key(|c| c.id);
+        }
+
+        Ok(tables.into_iter().map(|(_, t)| t).collect())
+    }
+
+    /// Read every row of `table_name`
+    pub fn read_table(&self, table_name: &str) -> Result<TableScan> {
+        let table = self
+            .table(table_name)
+            .ok_or_else(|| anyhow!("Table {} not found", table_name))?;
+
+        // Fixed column sizes by id; ids are dense from 1
+        let fixed_columns: Vec<&ColumnDef> = table.columns.iter().filter(|c| c.id < 128).collect();
+        let mut fixed_sizes = Vec::new();
+        for id in 1..=fixed_columns.iter().map(|c| c.id).max().unwrap_or(0) {
+            let size = fixed_columns
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| fixed_size(c.column_type).unwrap_or(c.size as usize))
+                .ok_or_else(|| anyhow!("Table {} is missing fixed column {}", table.name, id))?;
+            fixed_sizes.push(size);
+        }
+
+        let mut records = Vec::new();
+        let skipped_pages = self.walk_tree(table.fdp, &mut records);
+
+        let mut scan = TableScan {
+            rows: Vec::with_capacity(records.len()),
+            skipped_pages,
+        };
+        for data in records {
+            match parse_record(&data, &fixed_sizes, self.large_pages()) {
+                Ok(raw) => scan.rows.push(self.decode_row(table, raw)),
+                Err(e) => debug!("Skipping unreadable record in {}: {:#}", table.name, e),
+            }
+        }
+        Ok(scan)
+    }
+
+    fn decode_row(&self, table: &TableDef, raw: RawRecord) -> Row {
+        let mut row = Row::new();
+        let column = |id: u32| table.columns.iter().find(|c| c.id == id);
+
+        for (index, value) in raw.fixed.iter().enumerate() {
+            if let (Some(def), Some(data)) = (column(index as u32 + 1), value) {
+                row.insert(def.name.clone(), decode_value(def, data));
+            }
+        }
+        for (index, value) in raw.variable.iter().enumerate() {
+            if let (Some(def), Some(data)) = (column(index as u32 + 128), value) {
+                row.insert(def.name.clone(), decode_value(def, data));
+            }
+        }
+        for (id, value) in raw.tagged {
+            let def = match column(id) {
+                Some(def) => def,
+                None => continue,
+            };
+            let decoded = match value {
+                TaggedData::Plain(data) => decode_value(def, &data),
+                TaggedData::Compressed(data) => match decompress_7bit(&data) {
+                    Some(text)
+                        if def.column_type == coltyp::TEXT
+                            || def.column_type == coltyp::LONG_TEXT =>
+                    {
+                        EseValue::Text(text)
+                    }
+                    Some(text) => EseValue::Binary(
+                        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect(),
+                    ),
+                    None => EseValue::Unsupported("compressed"),
+                },
+                TaggedData::LongValue => EseValue::Unsupported("long value"),
+            };
+            row.insert(def.name.clone(), decoded);
+        }
+
+        row
+    }
+}
+
+/// Convert an OLE automation date to RFC3339 UTC
+pub fn ole_date_to_rfc3339(days: f64) -> Option<String> {
+    if !days.is_finite() {
+        return None;
+    }
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let millis = (days * 86_400_000.0).round() as i64;
+    let datetime = epoch.checked_add_signed(chrono::Duration::milliseconds(millis))?;
+    Some(
+        chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(datetime, chrono::Utc)
+            .to_rfc3339(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{ese_record, EseBuilder, FixtureColumn};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_header_rejects_other_files() {
+        let mut page = vec![0u8; 4096];
+        page[..4].copy_from_slice(b"regf");
+        assert!(parse_header(&page).is_err());
+    }
+
+    #[test]
+    fn test_database_state_dirty() {
+        assert!(DatabaseState::from_raw(2).is_dirty());
+        assert!(!DatabaseState::from_raw(3).is_dirty());
+        assert_eq!(DatabaseState::from_raw(2).as_str(), "dirty_shutdown");
+    }
+
+    #[test]
+    fn test_parse_record_fixed_variable_tagged() {
+        let data = ese_record(
+            &[vec![7, 0, 0, 0], vec![1, 0]],
+            &[b"Name".to_vec()],
+            &[(256, b"blob".to_vec())],
+        );
+        let record = parse_record(&data, &[4, 2], false).unwrap();
+
+        assert_eq!(record.fixed, vec![Some(vec![7, 0, 0, 0]), Some(vec![1, 0])]);
+        assert_eq!(record.variable, vec![Some(b"Name".to_vec())]);
+        assert_eq!(
+            record.tagged,
+            vec![(256, TaggedData::Plain(b"blob".to_vec()))]
+        );
+    }
+
+    #[test]
+    fn test_decompress_7bit_ascii() {
+        // "AB" packed 7 bits per character, LSB first
+        let data = [0x08, 0x41 | (0x42 << 7) as u8, 0x42 >> 1];
+        assert_eq!(decompress_7bit(&data).unwrap(), "AB");
+        assert_eq!(decompress_7bit(&[0x18, 0x00]), None);
+    }
+
+    #[test]
+    fn test_ole_date_conversion() {
+        assert_eq!(
+            ole_date_to_rfc3339(45292.5).unwrap(),
+            "2024-01-01T12:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_read_table_across_branch_pages() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("test.edb");
+
+        let columns = [
+            FixtureColumn::new(1, "Id", coltyp::LONG),
+            FixtureColumn::new(128, "Label", coltyp::TEXT),
+        ];
+        let rows: Vec<Vec<u8>> = (0..6i32)
+            .map(|i| {
+                ese_record(
+                    &[i.to_le_bytes().to_vec()],
+                    &[format!("row{}", i).into_bytes()],
+                    &[],
+                )
+            })
+            .collect();
+
+        let mut builder = EseBuilder::new();
+        builder.add_table_split("Items", &columns, &rows, 3);
+        builder.write(&path);
+
+        assert!(!read_header(&path).unwrap().state.is_dirty());
+        let db = EseDatabase::open(&path).unwrap();
+        let scan = db.read_table("Items").unwrap();
+        assert_eq!(scan.skipped_pages, 0);
+        let labels: Vec<String> = scan
+            .rows
+            .iter()
+            .map(|r| match &r["Label"] {
+                EseValue::Text(t) => t.clone(),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(labels, vec!["row0", "row1", "row2", "row3", "row4", "row5"]);
+        assert_eq!(scan.rows[4]["Id"], EseValue::Int(4));
+    }
+}
@@ -0,0 +1,673 @@
+//! Minimal read-only reader for Windows XML event logs (EVTX).
+//!
+//! An EVTX file is a 4 KiB file header followed by 64 KiB chunks, each
+//! holding event records whose body is Binary XML: a token stream in which
+//! element and attribute names are offsets to strings in the chunk, and the
+//! layout of an event is a template whose substitutions are filled from the
+//! values stored with each record. A chunk writes a name or template inline
+//! the first time it is used and refers to it by offset afterwards, so every
+//! offset here is relative to the chunk. Records are rendered into a small
+//! element tree. Checksums are not verified, so records in a log copied while
+//! in use are still read as long as their structure is intact.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+
+use super::bytes::{u16_at, u32_at, u64_at};
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+const FILE_SIGNATURE: &[u8] = b"ElfFile\0";
+const CHUNK_SIGNATURE: &[u8] = b"ElfChnk\0";
+const RECORD_SIGNATURE: [u8; 4] = [0x2a, 0x2a, 0x00, 0x00];
+
+const FILE_HEADER_SIZE: usize = 4096;
+const CHUNK_SIZE: usize = 65536;
+/// Chunk header including its string and template hash tables
+const CHUNK_HEADER_SIZE: usize = 512;
+const CHUNK_FREE_SPACE_OFFSET: usize = 48;
+/// Signature, size, record id and written time
+const RECORD_HEADER_SIZE: usize = 24;
+
+/// Deepest nesting of templates and embedded Binary XML followed
+const MAX_NESTING: usize = 16;
+
+/// Set on a token when more data of the same kind follows
+const MORE_DATA_FLAG: u8 = 0x40;
+
+const TOKEN_EOF: u8 = 0x00;
+const TOKEN_OPEN_START_ELEMENT: u8 = 0x01;
+const TOKEN_CLOSE_START_ELEMENT: u8 = 0x02;
+const TOKEN_CLOSE_EMPTY_ELEMENT: u8 = 0x03;
+const TOKEN_END_ELEMENT: u8 = 0x04;
+const TOKEN_VALUE: u8 = 0x05;
+const TOKEN_ATTRIBUTE: u8 = 0x06;
+const TOKEN_CDATA: u8 = 0x07;
+const TOKEN_CHAR_REF: u8 = 0x08;
+const TOKEN_ENTITY_REF: u8 = 0x09;
+const TOKEN_PI_TARGET: u8 = 0x0a;
+const TOKEN_PI_DATA: u8 = 0x0b;
+const TOKEN_TEMPLATE_INSTANCE: u8 = 0x0c;
+const TOKEN_NORMAL_SUBSTITUTION: u8 = 0x0d;
+const TOKEN_OPTIONAL_SUBSTITUTION: u8 = 0x0e;
+const TOKEN_FRAGMENT_HEADER: u8 = 0x0f;
+
+const TYPE_NULL: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_ANSI_STRING: u8 = 0x02;
+const TYPE_INT8: u8 = 0x03;
+const TYPE_UINT8: u8 = 0x04;
+const TYPE_INT16: u8 = 0x05;
+const TYPE_UINT16: u8 = 0x06;
+const TYPE_INT32: u8 = 0x07;
+const TYPE_UINT32: u8 = 0x08;
+const TYPE_INT64: u8 = 0x09;
+const TYPE_UINT64: u8 = 0x0a;
+const TYPE_REAL32: u8 = 0x0b;
+const TYPE_REAL64: u8 = 0x0c;
+const TYPE_BOOL: u8 = 0x0d;
+const TYPE_GUID: u8 = 0x0f;
+const TYPE_SIZE_T: u8 = 0x10;
+const TYPE_FILETIME: u8 = 0x11;
+const TYPE_SYSTEMTIME: u8 = 0x12;
+const TYPE_SID: u8 = 0x13;
+const TYPE_HEX_INT32: u8 = 0x14;
+const TYPE_HEX_INT64: u8 = 0x15;
+const TYPE_BINXML: u8 = 0x21;
+const TYPE_ARRAY_FLAG: u8 = 0x80;
+
+/// An element of a rendered event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub text: String,
+    pub children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    /// First child element named `name`
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Value of the attribute named `name`
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An event record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRecord {
+    pub record_id: u64,
+    /// When the record was written, from the record header
+    pub written_time: Option<DateTime<Utc>>,
+    /// The rendered `Event` element
+    pub event: XmlElement,
+}
+
+impl EventRecord {
+    /// `System/EventID`
+    pub fn event_id(&self) -> Option<u32> {
+        self.event
+            .child("System")?
+            .child("EventID")?
+            .text
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// `EventData/Data` values keyed by their `Name` attribute
+    pub fn event_data(&self) -> BTreeMap<String, String> {
+        self.event
+            .child("EventData")
+            .map(|data| {
+                data.children
+                    .iter()
+                    .filter(|child| child.name == "Data")
+                    .filter_map(|child| {
+                        let name = child.attribute("Name")?;
+                        Some((name.to_string(), child.text.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Values of the element under `UserData` keyed by element name, as
+    /// logged by providers that don't use `EventData`
+    pub fn user_data(&self) -> BTreeMap<String, String> {
+        self.event
+            .child("UserData")
+            .and_then(|data| data.children.first())
+            .map(|fields| {
+                fields
+                    .children
+                    .iter()
+                    .map(|child| (child.name.clone(), child.text.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The records of an event log
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pub records: Vec<EventRecord>,
+    /// Records whose Binary XML could not be rendered
+    pub skipped: usize,
+}
+
+/// Check for the EVTX file signature
+pub fn is_evtx(data: &[u8]) -> bool {
+    data.starts_with(FILE_SIGNATURE)
+}
+
+/// Read every record of every chunk in `data`.
+///
+/// Chunks without a valid signature (unused space at the end of the file)
+/// are skipped, as is a record that cannot be rendered.
+pub fn parse_evtx(data: &[u8]) -> Result<EventLog> {
+    if !is_evtx(data) {
+        bail!("Not an EVTX file (bad signature)");
+    }
+    if data.len() < FILE_HEADER_SIZE {
+        bail!("EVTX file header is truncated");
+    }
+
+    let mut log = EventLog::default();
+    let mut offset = FILE_HEADER_SIZE;
+    while offset < data.len() {
+        let chunk = &data[offset..data.len().min(offset + CHUNK_SIZE)];
+        if chunk.starts_with(CHUNK_SIGNATURE) {
+            read_chunk(chunk, &mut log)?;
+        }
+        offset += CHUNK_SIZE;
+    }
+    Ok(log)
+}
+
+fn byte_at(buf: &[u8], offset: usize) -> Result<u8> {
+    buf.get(offset)
+        .copied()
+        .ok_or_else(|| anyhow!("EVTX read past end at offset {:#x}", offset))
+}
+
+fn utf16_at(buf: &[u8], offset: usize, count: usize) -> Result<String> {
+    let bytes = buf
+        .get(offset..offset + count * 2)
+        .ok_or_else(|| anyhow!("EVTX read past end at offset {:#x}", offset))?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// Read the records of one chunk into `log`
+fn read_chunk(chunk: &[u8], log: &mut EventLog) -> Result<()> {
+    let free_space = u32_at(chunk, CHUNK_FREE_SPACE_OFFSET)? as usize;
+    let end = free_space.min(chunk.len());
+
+    let mut offset = CHUNK_HEADER_SIZE;
+    while offset + RECORD_HEADER_SIZE <= end {
+        if chunk[offset..offset + 4] != RECORD_SIGNATURE {
+            break;
+        }
+        let size = u32_at(chunk, offset + 4)? as usize;
+        if size < RECORD_HEADER_SIZE + 4 || offset + size > chunk.len() {
+            log.skipped += 1;
+            break;
+        }
+
+        let body = offset + RECORD_HEADER_SIZE..offset + size - 4;
+        match render(chunk, body.start, body.end) {
+            Ok(event) => log.records.push(EventRecord {
+                record_id: u64_at(chunk, offset + 8)?,
+                written_time: filetime_ticks_to_utc(u64_at(chunk, offset + 16)?),
+                event,
+            }),
+            Err(_) => log.skipped += 1,
+        }
+        offset += size;
+    }
+    Ok(())
+}
+
+/// Render the Binary XML between `start` and `end` to its root element
+fn render(chunk: &[u8], start: usize, end: usize) -> Result<XmlElement> {
+    let mut tree = TreeBuilder::default();
+    Renderer { chunk }.read_tokens(start, end, &[], &mut tree, 0)?;
+    tree.roots
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Event record has no root element"))
+}
+
+/// A substitution value stored with a template instance
+struct Value<'a> {
+    value_type: u8,
+    offset: usize,
+    data: &'a [u8],
+}
+
+/// Elements being built while a token stream is read
+#[derive(Default)]
+struct TreeBuilder {
+    stack: Vec<XmlElement>,
+    roots: Vec<XmlElement>,
+    /// Text goes to the last attribute until the start tag is closed
+    in_attribute: bool,
+}
+
+impl TreeBuilder {
+    fn open(&mut self, name: String) {
+        self.stack.push(XmlElement {
+            name,
+            ..Default::default()
+        });
+        self.in_attribute = false;
+    }
+
+    fn close(&mut self) -> Result<()> {
+        let element = self
+            .stack
+            .pop()
+            .ok_or_else(|| anyhow!("Binary XML closes an element that was not opened"))?;
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(element),
+            None => self.roots.push(element),
+        }
+        self.in_attribute = false;
+        Ok(())
+    }
+
+    fn attribute(&mut self, name: String) -> Result<()> {
+        let element = self
+            .stack
+            .last_mut()
+            .ok_or_else(|| anyhow!("Binary XML attribute outside an element"))?;
+        element.attributes.push((name, String::new()));
+        self.in_attribute = true;
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        let Some(element) = self.stack.last_mut() else {
+            return;
+        };
+        match element.attributes.last_mut() {
+            Some((_, value)) if self.in_attribute => value.push_str(text),
+            _ => element.text.push_str(text),
+        }
+    }
+}
+
+struct Renderer<'a> {
+    chunk: &'a [u8],
+}
+
+impl<'a> Renderer<'a> {
+    /// Read tokens from `pos` until end of fragment or `end`, returning the
+    /// position after the last token read
+    fn read_tokens(
+        &self,
+        mut pos: usize,
+        end: usize,
+        values: &[Value<'a>],
+        tree: &mut TreeBuilder,
+        nesting: usize,
+    ) -> Result<usize> {
+        if nesting > MAX_NESTING {
+            bail!("Binary XML is nested too deeply");
+        }
+
+        while pos < end {
+            let token = byte_at(self.chunk, pos)?;
+            match token & !MORE_DATA_FLAG {
+                TOKEN_EOF => return Ok(pos + 1),
+                TOKEN_FRAGMENT_HEADER => pos += 4,
+                TOKEN_OPEN_START_ELEMENT => {
+                    // Token, dependency id, element size, name offset, then
+                    // the attribute list size when attributes follow
+                    let name_offset = u32_at(self.chunk, pos + 7)? as usize;
+                    pos += 11;
+                    if token & MORE_DATA_FLAG != 0 {
+                        pos += 4;
+                    }
+                    let (name, next) = self.name(name_offset, pos)?;
+                    tree.open(name);
+                    pos = next;
+                }
+                TOKEN_CLOSE_START_ELEMENT => {
+                    tree.in_attribute = false;
+                    pos += 1;
+                }
+                TOKEN_CLOSE_EMPTY_ELEMENT | TOKEN_END_ELEMENT => {
+                    tree.close()?;
+                    pos += 1;
+                }
+                TOKEN_VALUE => {
+                    let value_type = byte_at(self.chunk, pos + 1)?;
+                    if value_type != TYPE_STRING {
+                        bail!("Unsupported Binary XML value type {:#04x}", value_type);
+                    }
+                    let count = u16_at(self.chunk, pos + 2)? as usize;
+                    tree.text(&utf16_at(self.chunk, pos + 4, count)?);
+                    pos += 4 + count * 2;
+                }
+                TOKEN_ATTRIBUTE => {
+                    let name_offset = u32_at(self.chunk, pos + 1)? as usize;
+                    let (name, next) = self.name(name_offset, pos + 5)?;
+                    tree.attribute(name)?;
+                    pos = next;
+                }
+                TOKEN_CDATA | TOKEN_PI_DATA => {
+                    let count = u16_at(self.chunk, pos + 1)? as usize;
+                    if token & !MORE_DATA_FLAG == TOKEN_CDATA {
+                        tree.text(&utf16_at(self.chunk, pos + 3, count)?);
+                    }
+                    pos += 3 + count * 2;
+                }
+                TOKEN_CHAR_REF => {
+                    let code = u16_at(self.chunk, pos + 1)?;
+                    if let Some(c) = char::from_u32(u32::from(code)) {
+                        tree.text(&c.to_string());
+                    }
+                    pos += 3;
+                }
+                TOKEN_ENTITY_REF => {
+                    let name_offset = u32_at(self.chunk, pos + 1)? as usize;
+                    let (name, next) = self.name(name_offset, pos + 5)?;
+                    tree.text(match name.as_str() {
+                        "amp" => "&",
+                        "lt" => "<",
+                        "gt" => ">",
+                        "quot" => "\"",
+                        "apos" => "'",
+                        _ => "",
+                    });
+                    pos = next;
+                }
+                TOKEN_PI_TARGET => {
+                    let name_offset = u32_at(self.chunk, pos + 1)? as usize;
+                    pos = self.name(name_offset, pos + 5)?.1;
+                }
+                TOKEN_TEMPLATE_INSTANCE => {
+                    pos = self.read_template_instance(pos, tree, nesting)?;
+                }
+                TOKEN_NORMAL_SUBSTITUTION | TOKEN_OPTIONAL_SUBSTITUTION => {
+                    let index = u16_at(self.chunk, pos + 1)? as usize;
+                    if let Some(value) = values.get(index) {
+                        self.substitute(value, tree, nesting)?;
+                    }
+                    pos += 4;
+                }
+                _ => bail!(
+                    "Unknown Binary XML token {:#04x} at chunk offset {:#x}",
+                    token,
+                    pos
+                ),
+            }
+        }
+        Ok(pos)
+    }
+
+    /// Read the name string at `offset`; returns the name and the position
+    /// after it when it is stored inline at `pos`, else `pos` unchanged
+    fn name(&self, offset: usize, pos: usize) -> Result<(String, usize)> {
+        // Next string offset, hash, character count, characters, terminator
+        let count = u16_at(self.chunk, offset + 6)? as usize;
+        let name = utf16_at(self.chunk, offset + 8, count)?;
+        let next = if offset == pos {
+            pos + 8 + count * 2 + 2
+        } else {
+            pos
+        };
+        Ok((name, next))
+    }
+
+    /// Render a template instance at `pos`; returns the position after its
+    /// substitution values
+    fn read_template_instance(
+        &self,
+        pos: usize,
+        tree: &mut TreeBuilder,
+        nesting: usize,
+    ) -> Result<usize> {
+        // Token, unknown byte, template id, definition offset
+        let definition = u32_at(self.chunk, pos + 6)? as usize;
+        let mut pos = pos + 10;
+
+        // Definition: next template offset, GUID, body size, body
+        let body_size = u32_at(self.chunk, definition + 20)? as usize;
+        let body = definition + 24;
+        if definition == pos {
+            pos = body + body_size;
+        }
+
+        let count = u32_at(self.chunk, pos)? as usize;
+        pos += 4;
+        let mut data_pos = pos + count * 4;
+        if data_pos > self.chunk.len() {
+            bail!(
+                "Template instance has {} substitutions past chunk end",
+                count
+            );
+        }
+        let mut values = Vec::with_capacity(count);
+        for index in 0..count {
+            let size = u16_at(self.chunk, pos + index * 4)? as usize;
+            let value_type = byte_at(self.chunk, pos + index * 4 + 2)?;
+            let data = self
+                .chunk
+                .get(data_pos..data_pos + size)
+                .ok_or_else(|| anyhow!("Substitution value {} runs past chunk end", index))?;
+            values.push(Value {
+                value_type,
+                offset: data_pos,
+                data,
+            });
+            data_pos += size;
+        }
+
+        self.read_tokens(body, body + body_size, &values, tree, nesting + 1)?;
+        Ok(data_pos)
+    }
+
+    fn substitute(&self, value: &Value<'a>, tree: &mut TreeBuilder, nesting: usize) -> Result<()> {
+        match value.value_type {
+            TYPE_NULL => {}
+            TYPE_BINXML => {
+                let end = value.offset + value.data.len();
+                self.read_tokens(value.offset, end, &[], tree, nesting + 1)?;
+            }
+            value_type => tree.text(&render_value(value_type, value.data)),
+        }
+        Ok(())
+    }
+}
+
+/// Render a substitution value as text
+fn render_value(value_type: u8, data: &[u8]) -> String {
+    let fixed = |n: usize| data.get(..n);
+
+    let rendered = match value_type {
+        TYPE_STRING => Some(utf16_text(data)),
+        TYPE_ANSI_STRING => Some(
+            String::from_utf8_lossy(data)
+                .trim_end_matches('\0')
+                .to_string(),
+        ),
+        TYPE_INT8 => fixed(1).map(|b| (b[0] as i8).to_string()),
+        TYPE_UINT8 => fixed(1).map(|b| b[0].to_string()),
+        TYPE_INT16 => fixed(2).map(|b| i16::from_le_bytes([b[0], b[1]]).to_string()),
+        TYPE_UINT16 => fixed(2).map(|b| u16::from_le_bytes([b[0], b[1]]).to_string()),
+        TYPE_INT32 => u32_at(data, 0).ok().map(|v| (v as i32).to_string()),
+        TYPE_UINT32 => u32_at(data, 0).ok().map(|v| v.to_string()),
+        TYPE_INT64 => u64_at(data, 0).ok().map(|v| (v as i64).to_string()),
+        TYPE_UINT64 => u64_at(data, 0).ok().map(|v| v.to_string()),
+        TYPE_REAL32 => u32_at(data, 0).ok().map(|v| f32::from_bits(v).to_string()),
+        TYPE_REAL64 => u64_at(data, 0).ok().map(|v| f64::from_bits(v).to_string()),
+        TYPE_BOOL => u32_at(data, 0).ok().map(|v| (v != 0).to_string()),
+        TYPE_GUID => fixed(16).map(|b| {
+            format!(
+                "{{{:08X}-{:04X}-{:04X}-{}-{}}}",
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                u16::from_le_bytes([b[4], b[5]]),
+                u16::from_le_bytes([b[6], b[7]]),
+                hex_bytes(&b[8..10]),
+                hex_bytes(&b[10..16])
+            )
+        }),
+        TYPE_SIZE_T | TYPE_HEX_INT32 | TYPE_HEX_INT64 => match data.len() {
+            4 => u32_at(data, 0).ok().map(|v| format!("{:#x}", v)),
+            8 => u64_at(data, 0).ok().map(|v| format!("{:#x}", v)),
+            _ => None,
+        },
+        TYPE_FILETIME => u64_at(data, 0)
+            .ok()
+            .and_then(filetime_ticks_to_utc)
+            .map(|time| time.to_rfc3339()),
+        TYPE_SYSTEMTIME => fixed(16).map(|b| {
+            let field = |i: usize| u16::from_le_bytes([b[i * 2], b[i * 2 + 1]]);
+            // Year, month, day of week, day, hour, minute, second, millisecond
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                field(0),
+                field(1),
+                field(3),
+                field(4),
+                field(5),
+                field(6),
+                field(7)
+            )
+        }),
+        TYPE_SID => render_sid(data),
+        t if t == TYPE_STRING | TYPE_ARRAY_FLAG => Some(
+            utf16_text(data)
+                .split('\0')
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => None,
+    };
+    rendered.unwrap_or_else(|| hex_bytes(data))
+}
+
+fn utf16_text(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn hex_bytes(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// `S-<revision>-<authority>-<sub authorities>`
+fn render_sid(data: &[u8]) -> Option<String> {
+    let revision = *data.first()?;
+    let count = *data.get(1)? as usize;
+    let authority = data
+        .get(2..8)?
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+    let mut sid = format!("S-{}-{}", revision, authority);
+    for index in 0..count {
+        sid.push_str(&format!("-{}", u32_at(data, 8 + index * 4).ok()?));
+    }
+    Some(sid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{evtx_file, EvtxEvent, EvtxValue};
+
+    #[test]
+    fn test_parse_evtx_templates_and_values() {
+        let data = evtx_file(&[
+            EvtxEvent {
+                record_id: 7,
+                written: 133_485_408_000_000_000,
+                event_id: 4688,
+                data: vec![
+                    ("NewProcessId", EvtxValue::HexInt64(0x1a2c)),
+                    (
+                        "NewProcessName",
+                        EvtxValue::Text(r"C:\Windows\System32\cmd.exe"),
+                    ),
+                ],
+            },
+            // Same layout: the template is referenced, not repeated
+            EvtxEvent {
+                record_id: 8,
+                written: 133_485_408_100_000_000,
+                event_id: 4688,
+                data: vec![
+                    ("NewProcessId", EvtxValue::HexInt64(0x2b3c)),
+                    ("NewProcessName", EvtxValue::Text("x & y")),
+                ],
+            },
+            EvtxEvent {
+                record_id: 9,
+                written: 133_485_408_200_000_000,
+                event_id: 4624,
+                data: vec![("LogonType", EvtxValue::UInt32(3))],
+            },
+        ]);
+        assert!(is_evtx(&data));
+
+        let log = parse_evtx(&data).unwrap();
+        assert_eq!(log.skipped, 0);
+        assert_eq!(log.records.len(), 3);
+
+        let first = &log.records[0];
+        assert_eq!(first.record_id, 7);
+        assert_eq!(
+            first.written_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+        assert_eq!(first.event_id(), Some(4688));
+        let data = first.event_data();
+        assert_eq!(data["NewProcessId"], "0x1a2c");
+        assert_eq!(data["NewProcessName"], r"C:\Windows\System32\cmd.exe");
+
+        assert_eq!(log.records[1].event_data()["NewProcessName"], "x & y");
+        assert_eq!(log.records[2].event_id(), Some(4624));
+        assert_eq!(log.records[2].event_data()["LogonType"], "3");
+    }
+
+    #[test]
+    fn test_parse_evtx_rejects_other_files() {
+        assert!(parse_evtx(b"not an event log").is_err());
+        assert!(parse_evtx(b"ElfFile\0short").is_err());
+    }
+
+    #[test]
+    fn test_render_value_types() {
+        assert_eq!(render_value(TYPE_HEX_INT32, &0x10u32.to_le_bytes()), "0x10");
+        assert_eq!(render_value(TYPE_BOOL, &1u32.to_le_bytes()), "true");
+        assert_eq!(
+            render_value(
+                TYPE_SID,
+                &[1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 32, 2, 0, 0]
+            ),
+            "S-1-5-32-544"
+        );
+        assert_eq!(render_value(0x0e, &[0xde, 0xad]), "DEAD");
+    }
+}
@@ -0,0 +1,1274 @@
+//! Builders for small synthetic ESE databases, registry hives and other
+//! binary artifact formats.
+//!
+//! Real SRUDB.dat and SOFTWARE files are large and contain host data, so
+//! parser tests generate minimal, structurally valid samples instead.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use super::bplist::PlistValue;
+use super::mft::NtfsTimes;
+
+const PAGE_SIZE: usize = 4096;
+const PAGE_HEADER_SIZE: usize = 40;
+const CATALOG_PAGE: u32 = 4;
+
+const PAGE_ROOT: u32 = 0x1;
+const PAGE_LEAF: u32 = 0x2;
+const PAGE_PARENT: u32 = 0x4;
+
+/// A column of a fixture table
+#[derive(Debug, Clone)]
+pub struct FixtureColumn {
+    pub id: u32,
+    pub name: &'static str,
+    pub column_type: u32,
+    pub codepage: u32,
+}
+
+impl FixtureColumn {
+    pub fn new(id: u32, name: &'static str, column_type: u32) -> Self {
+        Self {
+            id,
+            name,
+            column_type,
+            codepage: 0,
+        }
+    }
+}
+
+/// Encode a data definition record
+pub fn ese_record(fixed: &[Vec<u8>], variable: &[Vec<u8>], tagged: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let fixed_len: usize = fixed.iter().map(Vec::len).sum();
+    let bitmap_len = fixed.len().div_ceil(8);
+    let variable_offset = 4 + fixed_len + bitmap_len;
+
+    let mut record = vec![fixed.len() as u8, (127 + variable.len()) as u8];
+    record.extend_from_slice(&(variable_offset as u16).to_le_bytes());
+    for value in fixed {
+        record.extend_from_slice(value);
+    }
+    record.extend(std::iter::repeat_n(0u8, bitmap_len));
+
+    let mut end = 0u16;
+    for value in variable {
+        end += value.len() as u16;
+        record.extend_from_slice(&end.to_le_bytes());
+    }
+    for value in variable {
+        record.extend_from_slice(value);
+    }
+
+    let mut offset = (tagged.len() * 4) as u16;
+    for (id, value) in tagged {
+        record.extend_from_slice(&id.to_le_bytes());
+        record.extend_from_slice(&offset.to_le_bytes());
+        offset += value.len() as u16;
+    }
+    for (_, value) in tagged {
+        record.extend_from_slice(value);
+    }
+
+    record
+}
+
+/// Encode a page from its entry values (tag 0 is left empty)
+fn page(flags: u32, values: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = vec![0u8; PAGE_SIZE];
+    let mut tags = vec![(0u16, 0u16)];
+    let mut offset = 0usize;
+    for value in values {
+        let start = PAGE_HEADER_SIZE + offset;
+        data[start..start + value.len()].copy_from_slice(value);
+        tags.push((value.len() as u16, offset as u16));
+        offset += value.len();
+    }
+    assert!(
+        PAGE_HEADER_SIZE + offset + tags.len() * 4 <= PAGE_SIZE,
+        "fixture page overflow"
+    );
+
+    data[34..36].copy_from_slice(&(tags.len() as u16).to_le_bytes());
+    data[36..40].copy_from_slice(&flags.to_le_bytes());
+    for (index, (size, offset)) in tags.into_iter().enumerate() {
+        let position = PAGE_SIZE - 4 * (index + 1);
+        data[position..position + 2].copy_from_slice(&size.to_le_bytes());
+        data[position + 2..position + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+    data
+}
+
+/// Prefix `data` with a 4-byte local key
+fn keyed(key: u32, data: &[u8]) -> Vec<u8> {
+    let mut value = 4u16.to_le_bytes().to_vec();
+    value.extend_from_slice(&key.to_be_bytes());
+    value.extend_from_slice(data);
+    value
+}
+
+fn catalog_record(
+    object_id: i32,
+    object_type: i16,
+    id: i32,
+    coltyp_or_fdp: i32,
+    codepage: i32,
+    name: &str,
+) -> Vec<u8> {
+    ese_record(
+        &[
+            object_id.to_le_bytes().to_vec(),
+            object_type.to_le_bytes().to_vec(),
+            id.to_le_bytes().to_vec(),
+            coltyp_or_fdp.to_le_bytes().to_vec(),
+            0i32.to_le_bytes().to_vec(),
+            0i32.to_le_bytes().to_vec(),
+            codepage.to_le_bytes().to_vec(),
+        ],
+        &[name.as_bytes().to_vec()],
+        &[],
+    )
+}
+
+/// Builds a minimal ESE database with 4 KiB pages
+pub struct EseBuilder {
+    state: u32,
+    pages: BTreeMap<u32, Vec<u8>>,
+    catalog: Vec<Vec<u8>>,
+    next_page: u32,
+    next_object_id: i32,
+}
+
+impl Default for EseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EseBuilder {
+    pub fn new() -> Self {
+        Self {
+            state: 3,
+            pages: BTreeMap::new(),
+            catalog: Vec::new(),
+            next_page: CATALOG_PAGE + 1,
+            next_object_id: 2,
+        }
+    }
+
+    /// Mark the database as not cleanly shut down
+    pub fn dirty(&mut self) -> &mut Self {
+        self.state = 2;
+        self
+    }
+
+    fn register_table(&mut self, name: &str, columns: &[FixtureColumn], fdp: u32) {
+        let object_id = self.next_object_id;
+        self.next_object_id += 1;
+        self.catalog
+            .push(catalog_record(object_id, 1, object_id, fdp as i32, 0, name));
+        for column in columns {
+            self.catalog.push(catalog_record(
+                object_id,
+                2,
+                column.id as i32,
+                column.column_type as i32,
+                column.codepage as i32,
+                column.name,
+            ));
+        }
+    }
+
+    /// Add a table whose rows fit on a single root leaf page
+    pub fn add_table(
+        &mut self,
+        name: &str,
+        columns: &[FixtureColumn],
+        rows: &[Vec<u8>],
+    ) -> &mut Self {
+        let fdp = self.next_page;
+        self.next_page += 1;
+        let values: Vec<Vec<u8>> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, r)| keyed(i as u32, r))
+            .collect();
+        self.pages.insert(fdp, page(PAGE_ROOT | PAGE_LEAF, &values));
+        self.register_table(name, columns, fdp);
+        self
+    }
+
+    /// Add a table with a root branch page over leaves of `per_page` rows
+    pub fn add_table_split(
+        &mut self,
+        name: &str,
+        columns: &[FixtureColumn],
+        rows: &[Vec<u8>],
+        per_page: usize,
+    ) -> &mut Self {
+        let fdp = self.next_page;
+        self.next_page += 1;
+
+        let mut branch_entries = Vec::new();
+        for (chunk_index, chunk) in rows.chunks(per_page).enumerate() {
+            let leaf = self.next_page;
+            self.next_page += 1;
+            let values: Vec<Vec<u8>> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, r)| keyed((chunk_index * per_page + i) as u32, r))
+                .collect();
+            self.pages.insert(leaf, page(PAGE_LEAF, &values));
+            branch_entries.push(keyed(chunk_index as u32, &leaf.to_le_bytes()));
+        }
+        self.pages
+            .insert(fdp, page(PAGE_ROOT | PAGE_PARENT, &branch_entries));
+        self.register_table(name, columns, fdp);
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut header = vec![0u8; PAGE_SIZE];
+        header[4..8].copy_from_slice(&0x89ab_cdefu32.to_le_bytes());
+        header[8..12].copy_from_slice(&0x620u32.to_le_bytes());
+        header[52..56].copy_from_slice(&self.state.to_le_bytes());
+        header[232..236].copy_from_slice(&0x14u32.to_le_bytes());
+        header[236..240].copy_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+
+        let catalog_values: Vec<Vec<u8>> = self
+            .catalog
+            .iter()
+            .enumerate()
+            .map(|(i, r)| keyed(i as u32, r))
+            .collect();
+        let mut pages = self.pages.clone();
+        pages.insert(CATALOG_PAGE, page(PAGE_ROOT | PAGE_LEAF, &catalog_values));
+
+        // Page n lives at offset (n + 1) * page size; page 0 is the shadow header
+        let mut file = header.clone();
+        file.extend_from_slice(&header);
+        for number in 1..self.next_page {
+            match pages.get(&number) {
+                Some(data) => file.extend_from_slice(data),
+                None => file.extend(std::iter::repeat_n(0u8, PAGE_SIZE)),
+            }
+        }
+        file
+    }
+
+    pub fn write(&self, path: &Path) {
+        std::fs::write(path, self.build()).unwrap();
+    }
+}
+
+/// Fixed column helper for the common signed types
+pub fn fixed_i32(value: i32) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+pub fn fixed_i64(value: i64) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+pub fn fixed_ole_date(days: f64) -> Vec<u8> {
+    days.to_le_bytes().to_vec()
+}
+
+/// A key in a fixture hive
+#[derive(Debug, Clone, Default)]
+pub struct HiveKey {
+    name: String,
+    subkeys: Vec<HiveKey>,
+    values: Vec<(String, u32, Vec<u8>)>,
+}
+
+impl HiveKey {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add (or reuse) a subkey and return it
+    pub fn subkey(&mut self, name: &str) -> &mut HiveKey {
+        let index = match self.subkeys.iter().position(|k| k.name == name) {
+            Some(index) => index,
+            None => {
+                self.subkeys.push(HiveKey::new(name));
+                self.subkeys.len() - 1
+            }
+        };
+        &mut self.subkeys[index]
+    }
+
+    pub fn string_value(&mut self, name: &str, value: &str) -> &mut Self {
+        let mut data: Vec<u8> = value.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        data.extend_from_slice(&[0, 0]);
+        self.values.push((name.to_string(), 1, data));
+        self
+    }
+
+    pub fn dword_value(&mut self, name: &str, value: u32) -> &mut Self {
+        self.values
+            .push((name.to_string(), 4, value.to_le_bytes().to_vec()));
+        self
+    }
+
+    pub fn binary_value(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        self.values.push((name.to_string(), 3, value.to_vec()));
+        self
+    }
+
+    pub fn qword_value(&mut self, name: &str, value: u64) -> &mut Self {
+        self.values
+            .push((name.to_string(), 11, value.to_le_bytes().to_vec()));
+        self
+    }
+}
+
+/// Serializes a [`HiveKey`] tree into a regf file
+pub struct HiveBuilder {
+    bins: Vec<u8>,
+}
+
+impl HiveBuilder {
+    pub fn build(root: &HiveKey) -> Vec<u8> {
+        let mut builder = Self {
+            bins: vec![0u8; 32],
+        };
+        let root_offset = builder.write_key(root);
+
+        // Pad the single hive bin to a 4 KiB multiple
+        let size = builder.bins.len().next_multiple_of(4096);
+        builder.bins.resize(size, 0);
+        builder.bins[0..4].copy_from_slice(b"hbin");
+        builder.bins[8..12].copy_from_slice(&(size as u32).to_le_bytes());
+
+        let mut file = vec![0u8; 4096];
+        file[0..4].copy_from_slice(b"regf");
+        file[0x24..0x28].copy_from_slice(&(root_offset as u32).to_le_bytes());
+        file[0x28..0x2c].copy_from_slice(&(size as u32).to_le_bytes());
+        file.extend_from_slice(&builder.bins);
+        file
+    }
+
+    fn alloc(&mut self, data: &[u8]) -> usize {
+        let offset = self.bins.len();
+        let size = (4 + data.len()).next_multiple_of(8);
+        self.bins.extend_from_slice(&(-(size as i32)).to_le_bytes());
+        self.bins.extend_from_slice(data);
+        self.bins.resize(offset + size, 0);
+        offset
+    }
+
+    fn write_value(&mut self, name: &str, data_type: u32, data: &[u8]) -> usize {
+        let (size_field, offset_field) = if data.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..data.len()].copy_from_slice(data);
+            (data.len() as u32 | 0x8000_0000, u32::from_le_bytes(inline))
+        } else {
+            (data.len() as u32, self.alloc(data) as u32)
+        };
+
+        let mut cell = b"vk".to_vec();
+        cell.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        cell.extend_from_slice(&size_field.to_le_bytes());
+        cell.extend_from_slice(&offset_field.to_le_bytes());
+        cell.extend_from_slice(&data_type.to_le_bytes());
+        cell.extend_from_slice(&1u16.to_le_bytes());
+        cell.extend_from_slice(&0u16.to_le_bytes());
+        cell.extend_from_slice(name.as_bytes());
+        self.alloc(&cell)
+    }
+
+    fn write_key(&mut self, key: &HiveKey) -> usize {
+        let children: Vec<usize> = key.subkeys.iter().map(|k| self.write_key(k)).collect();
+        let values: Vec<usize> = key
+            .values
+            .iter()
+            .map(|(name, data_type, data)| self.write_value(name, *data_type, data))
+            .collect();
+
+        let subkey_list = if children.is_empty() {
+            u32::MAX
+        } else {
+            let mut list = b"lf".to_vec();
+            list.extend_from_slice(&(children.len() as u16).to_le_bytes());
+            for child in &children {
+                list.extend_from_slice(&(*child as u32).to_le_bytes());
+                list.extend_from_slice(&0u32.to_le_bytes());
+            }
+            self.alloc(&list) as u32
+        };
+        let value_list = if values.is_empty() {
+            u32::MAX
+        } else {
+            let list: Vec<u8> = values
+                .iter()
+                .flat_map(|v| (*v as u32).to_le_bytes())
+                .collect();
+            self.alloc(&list) as u32
+        };
+
+        let mut cell = vec![0u8; 76];
+        cell[0..2].copy_from_slice(b"nk");
+        cell[2..4].copy_from_slice(&0x20u16.to_le_bytes());
+        cell[4..12].copy_from_slice(&133_500_000_000_000_000u64.to_le_bytes());
+        cell[20..24].copy_from_slice(&(children.len() as u32).to_le_bytes());
+        cell[28..32].copy_from_slice(&subkey_list.to_le_bytes());
+        cell[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        cell[36..40].copy_from_slice(&(values.len() as u32).to_le_bytes());
+        cell[40..44].copy_from_slice(&value_list.to_le_bytes());
+        cell[44..48].copy_from_slice(&u32::MAX.to_le_bytes());
+        cell[48..52].copy_from_slice(&u32::MAX.to_le_bytes());
+        cell[72..74].copy_from_slice(&(key.name.len() as u16).to_le_bytes());
+        cell.extend_from_slice(key.name.as_bytes());
+        self.alloc(&cell)
+    }
+}
+
+/// A transaction log entry for [`transaction_log`]
+#[derive(Debug, Clone)]
+pub struct LogEntryFixture {
+    pub sequence: u32,
+    pub hive_bins_size: u32,
+    /// Dirty pages as (offset into the hive bins, data)
+    pub pages: Vec<(u32, Vec<u8>)>,
+}
+
+/// Serialize a Windows 8.1+ transaction log: the base block of `hive`
+/// followed by `HvLE` entries with valid Marvin32 hashes
+pub fn transaction_log(hive: &[u8], entries: &[LogEntryFixture]) -> Vec<u8> {
+    use super::regf::marvin32;
+    const SEED: u64 = 0x82EF_4D88_7A4E_55C5;
+
+    let mut out = hive[..512].to_vec();
+    for entry in entries {
+        let mut body = Vec::new();
+        for (offset, page) in &entry.pages {
+            body.extend_from_slice(&offset.to_le_bytes());
+            body.extend_from_slice(&(page.len() as u32).to_le_bytes());
+        }
+        for (_, page) in &entry.pages {
+            body.extend_from_slice(page);
+        }
+        body.resize((40 + body.len()).next_multiple_of(512) - 40, 0);
+
+        let mut header = vec![0u8; 40];
+        header[0..4].copy_from_slice(b"HvLE");
+        header[4..8].copy_from_slice(&((40 + body.len()) as u32).to_le_bytes());
+        header[12..16].copy_from_slice(&entry.sequence.to_le_bytes());
+        header[16..20].copy_from_slice(&entry.hive_bins_size.to_le_bytes());
+        header[20..24].copy_from_slice(&(entry.pages.len() as u32).to_le_bytes());
+        header[24..32].copy_from_slice(&marvin32(&body, SEED).to_le_bytes());
+        let hash_2 = marvin32(&header[..32], SEED);
+        header[32..40].copy_from_slice(&hash_2.to_le_bytes());
+
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&body);
+    }
+    out
+}
+
+/// A BITS job record for [`bits_job`]
+#[derive(Debug, Clone)]
+pub struct BitsJobFixture<'a> {
+    pub id: [u8; 16],
+    pub job_type: u32,
+    pub priority: u32,
+    pub state: u32,
+    pub name: &'a str,
+    pub description: &'a str,
+    pub command: &'a str,
+    pub arguments: &'a str,
+    pub sid: &'a str,
+    /// (local name, remote name) pairs
+    pub files: &'a [(&'a str, &'a str)],
+}
+
+impl Default for BitsJobFixture<'_> {
+    fn default() -> Self {
+        Self {
+            id: [0; 16],
+            job_type: 0,
+            priority: 2,
+            state: 6,
+            name: "job",
+            description: "",
+            command: "",
+            arguments: "",
+            sid: "S-1-5-18",
+            files: &[],
+        }
+    }
+}
+
+/// Counted UTF-16 string as stored in BITS job records
+fn bits_wstring(value: &str) -> Vec<u8> {
+    let mut units: Vec<u16> = value.encode_utf16().collect();
+    units.push(0);
+    let mut out = (units.len() as u32).to_le_bytes().to_vec();
+    out.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+    out
+}
+
+/// Serialize a BITS job record followed by its file list
+pub fn bits_job(job: &BitsJobFixture) -> Vec<u8> {
+    let mut out = Vec::new();
+    for value in [job.job_type, job.priority, job.state, 0] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&job.id);
+    for value in [
+        job.name,
+        job.description,
+        job.command,
+        job.arguments,
+        job.sid,
+    ] {
+        out.extend(bits_wstring(value));
+    }
+
+    out.extend_from_slice(&(job.files.len() as u32).to_le_bytes());
+    for (local, remote) in job.files {
+        out.extend(bits_wstring(local));
+        out.extend(bits_wstring(remote));
+        // Temporary file name, then download and transfer sizes
+        out.extend(bits_wstring(""));
+        out.extend_from_slice(&4096u64.to_le_bytes());
+        out.extend_from_slice(&4096u64.to_le_bytes());
+    }
+    out
+}
+
+/// UTF-16LE text with a byte order mark, as task XML files are stored
+pub fn utf16_with_bom(text: &str) -> Vec<u8> {
+    let mut out = vec![0xFF, 0xFE];
+    out.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    out
+}
+
+/// 2024-01-01T00:00:00Z as a FILETIME
+const FIXTURE_FILETIME: u64 = 133_485_408_000_000_000;
+
+/// Version 1 object id whose node field is MAC 00:0c:29:ab:cd:ef
+const FIXTURE_DROID: [u8; 16] = [
+    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x11, 0x80, 0x01, 0x00, 0x0c, 0x29, 0xab, 0xcd, 0xef,
+];
+
+fn utf16_bytes(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+}
+
+/// A Recycle Bin `$I` file in format `version` (1 or 2)
+pub fn recycle_bin_index(version: u64, size: u64, deleted: u64, path: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&version.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&deleted.to_le_bytes());
+    let mut units = utf16_bytes(path);
+    units.extend_from_slice(&[0, 0]);
+    if version == 1 {
+        units.resize(520, 0);
+    } else {
+        out.extend_from_slice(&((units.len() / 2) as u32).to_le_bytes());
+    }
+    out.extend(units);
+    out
+}
+
+/// A shell link for [`lnk_file`]
+#[derive(Debug, Clone)]
+pub struct LnkFixture<'a> {
+    /// Local base path; empty for a target on a network share
+    pub local_base_path: &'a str,
+    pub network_share: &'a str,
+    pub path_suffix: &'a str,
+    pub arguments: &'a str,
+    pub working_directory: &'a str,
+    pub modified: u64,
+    pub size: u32,
+    pub volume_serial: u32,
+    pub volume_label: &'a str,
+    /// Machine id for a tracker block; empty for none
+    pub machine_id: &'a str,
+    pub file_droid: [u8; 16],
+}
+
+impl Default for LnkFixture<'_> {
+    fn default() -> Self {
+        Self {
+            local_base_path: r"C:\",
+            network_share: "",
+            path_suffix: "",
+            arguments: "",
+            working_directory: "",
+            modified: FIXTURE_FILETIME,
+            size: 4096,
+            volume_serial: 0,
+            volume_label: "",
+            machine_id: "",
+            file_droid: FIXTURE_DROID,
+        }
+    }
+}
+
+fn nul_terminated(value: &str) -> Vec<u8> {
+    let mut out = value.as_bytes().to_vec();
+    out.push(0);
+    out
+}
+
+/// Serialize a shell link with an empty ID list, `LinkInfo`, Unicode string
+/// data and, if `machine_id` is set, a tracker block
+pub fn lnk_file(link: &LnkFixture) -> Vec<u8> {
+    // HasLinkTargetIDList | HasLinkInfo | IsUnicode
+    let mut flags = 0x1 | 0x2 | 0x80u32;
+    if !link.working_directory.is_empty() {
+        flags |= 0x10;
+    }
+    if !link.arguments.is_empty() {
+        flags |= 0x20;
+    }
+
+    let mut out = vec![0u8; 0x4C];
+    out[0..4].copy_from_slice(&0x4Cu32.to_le_bytes());
+    out[4..20].copy_from_slice(&[
+        0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x46,
+    ]);
+    out[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+    out[0x18..0x1C].copy_from_slice(&0x20u32.to_le_bytes());
+    for offset in [0x1C, 0x24, 0x2C] {
+        out[offset..offset + 8].copy_from_slice(&link.modified.to_le_bytes());
+    }
+    out[0x34..0x38].copy_from_slice(&link.size.to_le_bytes());
+    out[0x3C..0x40].copy_from_slice(&1u32.to_le_bytes());
+
+    // ID list holding only its terminator
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+
+    // LinkInfo with a 0x1C byte header and ANSI paths
+    let mut body = Vec::new();
+    let mut info_flags = 0u32;
+    let (mut volume_offset, mut base_offset, mut network_offset) = (0u32, 0u32, 0u32);
+    if !link.local_base_path.is_empty() {
+        info_flags |= 0x1;
+        volume_offset = 0x1C + body.len() as u32;
+        let label = nul_terminated(link.volume_label);
+        body.extend_from_slice(&(0x10 + label.len() as u32).to_le_bytes());
+        body.extend_from_slice(&3u32.to_le_bytes());
+        body.extend_from_slice(&link.volume_serial.to_le_bytes());
+        body.extend_from_slice(&0x10u32.to_le_bytes());
+        body.extend(label);
+        base_offset = 0x1C + body.len() as u32;
+        body.extend(nul_terminated(link.local_base_path));
+    }
+    if !link.network_share.is_empty() {
+        info_flags |= 0x2;
+        network_offset = 0x1C + body.len() as u32;
+        let share = nul_terminated(link.network_share);
+        body.extend_from_slice(&(0x14 + share.len() as u32).to_le_bytes());
+        body.extend_from_slice(&0x2u32.to_le_bytes());
+        body.extend_from_slice(&0x14u32.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&0x0002_0000u32.to_le_bytes());
+        body.extend(share);
+    }
+    let suffix_offset = 0x1C + body.len() as u32;
+    body.extend(nul_terminated(link.path_suffix));
+
+    let info_size = 0x1C + body.len() as u32;
+    for value in [
+        info_size,
+        0x1C,
+        info_flags,
+        volume_offset,
+        base_offset,
+        network_offset,
+        suffix_offset,
+    ] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend(body);
+
+    for value in [link.working_directory, link.arguments] {
+        if !value.is_empty() {
+            out.extend_from_slice(&(value.encode_utf16().count() as u16).to_le_bytes());
+            out.extend(utf16_bytes(value));
+        }
+    }
+
+    if !link.machine_id.is_empty() {
+        let mut block = vec![0u8; 0x60];
+        block[0..4].copy_from_slice(&0x60u32.to_le_bytes());
+        block[4..8].copy_from_slice(&0xA000_0003u32.to_le_bytes());
+        block[8..12].copy_from_slice(&0x58u32.to_le_bytes());
+        block[16..16 + link.machine_id.len()].copy_from_slice(link.machine_id.as_bytes());
+        block[48..64].copy_from_slice(&link.file_droid);
+        block[80..96].copy_from_slice(&link.file_droid);
+        out.extend(block);
+    }
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out
+}
+
+/// A `DestList` entry for [`dest_list`]
+#[derive(Debug, Clone)]
+pub struct DestListFixture<'a> {
+    pub entry_number: u32,
+    pub path: &'a str,
+    pub hostname: &'a str,
+    pub last_accessed: u64,
+    pub access_count: u32,
+    pub pinned: bool,
+}
+
+impl Default for DestListFixture<'_> {
+    fn default() -> Self {
+        Self {
+            entry_number: 1,
+            path: "",
+            hostname: "",
+            last_accessed: FIXTURE_FILETIME,
+            access_count: 1,
+            pinned: false,
+        }
+    }
+}
+
+/// Serialize a `DestList` stream in format `version` (1 for Windows 7/8,
+/// 3 or 4 for Windows 10 and later)
+pub fn dest_list(version: u32, entries: &[DestListFixture]) -> Vec<u8> {
+    let mut out = vec![0u8; 32];
+    out[0..4].copy_from_slice(&version.to_le_bytes());
+    out[4..8].copy_from_slice(&(entries.len() as u32).to_le_bytes());
+    let pinned = entries.iter().filter(|e| e.pinned).count() as u32;
+    out[8..12].copy_from_slice(&pinned.to_le_bytes());
+
+    for (position, entry) in entries.iter().enumerate() {
+        let fixed = if version == 1 { 0x70 } else { 0x80 };
+        let mut record = vec![0u8; fixed];
+        record[0x18..0x28].copy_from_slice(&FIXTURE_DROID);
+        record[0x48..0x48 + entry.hostname.len()].copy_from_slice(entry.hostname.as_bytes());
+        record[0x58..0x5C].copy_from_slice(&entry.entry_number.to_le_bytes());
+        record[0x64..0x6C].copy_from_slice(&entry.last_accessed.to_le_bytes());
+        let pin = if entry.pinned { position as i32 } else { -1 };
+        record[0x6C..0x70].copy_from_slice(&pin.to_le_bytes());
+        if version == 1 {
+            record[0x60..0x64].copy_from_slice(&(entry.access_count as f32).to_le_bytes());
+        } else {
+            record[0x74..0x78].copy_from_slice(&entry.access_count.to_le_bytes());
+        }
+        record.extend_from_slice(&(entry.path.encode_utf16().count() as u16).to_le_bytes());
+        record.extend(utf16_bytes(entry.path));
+        if version != 1 {
+            record.extend_from_slice(&[0u8; 4]);
+        }
+        out.extend(record);
+    }
+    out
+}
+
+/// Serialize a compound file holding `streams` in its root storage
+pub fn compound_file(streams: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    for (name, data) in streams {
+        file.create_stream(format!("/{}", name))
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+    file.flush().unwrap();
+    file.into_inner().into_inner()
+}
+
+fn bplist_count(out: &mut Vec<u8>, kind: u8, count: usize) {
+    if count < 15 {
+        out.push(kind << 4 | count as u8);
+    } else {
+        out.push(kind << 4 | 0x0F);
+        out.push(0x13);
+        out.extend_from_slice(&(count as u64).to_be_bytes());
+    }
+}
+
+/// Append `value` and its children to `objects`; returns its index
+fn bplist_object(value: &PlistValue, objects: &mut Vec<Vec<u8>>) -> u16 {
+    let index = objects.len();
+    objects.push(Vec::new());
+    let mut out = Vec::new();
+    match value {
+        PlistValue::Null => out.push(0x00),
+        PlistValue::Bool(b) => out.push(if *b { 0x09 } else { 0x08 }),
+        PlistValue::Integer(i) => match u32::try_from(*i) {
+            Ok(small) if small <= 0xFF => out.extend([0x10, small as u8]),
+            Ok(small) if small <= 0xFFFF => {
+                out.push(0x11);
+                out.extend_from_slice(&(small as u16).to_be_bytes());
+            }
+            _ => {
+                out.push(0x13);
+                out.extend_from_slice(&i.to_be_bytes());
+            }
+        },
+        PlistValue::Real(r) => {
+            out.push(0x23);
+            out.extend_from_slice(&r.to_be_bytes());
+        }
+        PlistValue::Date(d) => {
+            out.push(0x33);
+            out.extend_from_slice(&d.to_be_bytes());
+        }
+        PlistValue::Data(bytes) => {
+            bplist_count(&mut out, 0x4, bytes.len());
+            out.extend_from_slice(bytes);
+        }
+        PlistValue::String(s) if s.is_ascii() => {
+            bplist_count(&mut out, 0x5, s.len());
+            out.extend_from_slice(s.as_bytes());
+        }
+        PlistValue::String(s) => {
+            let units: Vec<u16> = s.encode_utf16().collect();
+            bplist_count(&mut out, 0x6, units.len());
+            out.extend(units.iter().flat_map(|u| u.to_be_bytes()));
+        }
+        PlistValue::Uid(uid) => {
+            out.push(0x83);
+            out.extend_from_slice(&(*uid as u32).to_be_bytes());
+        }
+        PlistValue::Array(items) => {
+            bplist_count(&mut out, 0xA, items.len());
+            for item in items {
+                let child = bplist_object(item, objects);
+                out.extend_from_slice(&child.to_be_bytes());
+            }
+        }
+        PlistValue::Dictionary(entries) => {
+            bplist_count(&mut out, 0xD, entries.len());
+            let keys: Vec<u16> = entries
+                .iter()
+                .map(|(k, _)| bplist_object(&PlistValue::String(k.clone()), objects))
+                .collect();
+            let values: Vec<u16> = entries
+                .iter()
+                .map(|(_, v)| bplist_object(v, objects))
+                .collect();
+            for child in keys.iter().chain(&values) {
+                out.extend_from_slice(&child.to_be_bytes());
+            }
+        }
+    }
+    objects[index] = out;
+    index as u16
+}
+
+/// Serialize `value` as a `bplist00` binary property list with 2-byte
+/// object references
+pub fn binary_plist(value: &PlistValue) -> Vec<u8> {
+    let mut objects = Vec::new();
+    let top = bplist_object(value, &mut objects);
+
+    let mut out = b"bplist00".to_vec();
+    let mut offsets = Vec::new();
+    for object in &objects {
+        offsets.push(out.len() as u32);
+        out.extend_from_slice(object);
+    }
+    let table = out.len() as u64;
+    for offset in offsets {
+        out.extend_from_slice(&offset.to_be_bytes());
+    }
+    out.extend_from_slice(&[0u8; 6]);
+    out.extend([4, 2]);
+    out.extend_from_slice(&(objects.len() as u64).to_be_bytes());
+    out.extend_from_slice(&u64::from(top).to_be_bytes());
+    out.extend_from_slice(&table.to_be_bytes());
+    out
+}
+
+/// Builder for `NSKeyedArchiver` archives
+pub struct KeyedArchiveBuilder {
+    objects: Vec<PlistValue>,
+    classes: BTreeMap<String, u64>,
+}
+
+impl KeyedArchiveBuilder {
+    pub fn new() -> Self {
+        Self {
+            objects: vec![PlistValue::String("$null".into())],
+            classes: BTreeMap::new(),
+        }
+    }
+
+    fn push(&mut self, value: PlistValue) -> PlistValue {
+        self.objects.push(value);
+        PlistValue::Uid(self.objects.len() as u64 - 1)
+    }
+
+    fn class(&mut self, name: &str) -> PlistValue {
+        if let Some(uid) = self.classes.get(name) {
+            return PlistValue::Uid(*uid);
+        }
+        let uid = self.push(PlistValue::Dictionary(vec![
+            ("$classname".into(), PlistValue::String(name.into())),
+            (
+                "$classes".into(),
+                PlistValue::Array(vec![
+                    PlistValue::String(name.into()),
+                    PlistValue::String("NSObject".into()),
+                ]),
+            ),
+        ]));
+        if let PlistValue::Uid(id) = uid {
+            self.classes.insert(name.into(), id);
+        }
+        uid
+    }
+
+    /// Reference to the `$null` object
+    pub fn null(&self) -> PlistValue {
+        PlistValue::Uid(0)
+    }
+
+    pub fn string(&mut self, value: &str) -> PlistValue {
+        self.push(PlistValue::String(value.into()))
+    }
+
+    /// An archived object of class `class` with the given encoded keys
+    pub fn object(&mut self, class: &str, fields: Vec<(&str, PlistValue)>) -> PlistValue {
+        let mut entries = vec![("$class".to_string(), self.class(class))];
+        entries.extend(fields.into_iter().map(|(k, v)| (k.to_string(), v)));
+        self.push(PlistValue::Dictionary(entries))
+    }
+
+    pub fn array(&mut self, items: Vec<PlistValue>) -> PlistValue {
+        self.object("NSArray", vec![("NS.objects", PlistValue::Array(items))])
+    }
+
+    pub fn dictionary(&mut self, entries: Vec<(PlistValue, PlistValue)>) -> PlistValue {
+        let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+        self.object(
+            "NSDictionary",
+            vec![
+                ("NS.keys", PlistValue::Array(keys)),
+                ("NS.objects", PlistValue::Array(values)),
+            ],
+        )
+    }
+
+    /// Serialize the archive with `root` as its root object
+    pub fn finish(self, root: PlistValue) -> Vec<u8> {
+        binary_plist(&PlistValue::Dictionary(vec![
+            (
+                "$archiver".into(),
+                PlistValue::String("NSKeyedArchiver".into()),
+            ),
+            ("$version".into(), PlistValue::Integer(100_000)),
+            (
+                "$top".into(),
+                PlistValue::Dictionary(vec![("root".into(), root)]),
+            ),
+            ("$objects".into(), PlistValue::Array(self.objects)),
+        ]))
+    }
+}
+
+/// A substitution value of an [`EvtxEvent`]
+pub enum EvtxValue<'a> {
+    Text(&'a str),
+    UInt32(u32),
+    HexInt64(u64),
+}
+
+impl EvtxValue<'_> {
+    /// Value type and bytes
+    fn encode(&self) -> (u8, Vec<u8>) {
+        match self {
+            EvtxValue::Text(text) => (0x01, utf16_bytes(text)),
+            EvtxValue::UInt32(value) => (0x08, value.to_le_bytes().to_vec()),
+            EvtxValue::HexInt64(value) => (0x15, value.to_le_bytes().to_vec()),
+        }
+    }
+}
+
+/// An event record: `System/EventID` and named `EventData/Data` values
+pub struct EvtxEvent<'a> {
+    pub record_id: u64,
+    /// FILETIME the record was written
+    pub written: u64,
+    pub event_id: u16,
+    pub data: Vec<(&'a str, EvtxValue<'a>)>,
+}
+
+/// Write the offset of name `name` followed by `gap`, then the name itself
+/// if this is its first use in the chunk
+fn evtx_name(chunk: &mut Vec<u8>, names: &mut BTreeMap<String, u32>, name: &str, gap: &[u8]) {
+    match names.get(name) {
+        Some(offset) => {
+            chunk.extend_from_slice(&offset.to_le_bytes());
+            chunk.extend_from_slice(gap);
+        }
+        None => {
+            let offset = (chunk.len() + 4 + gap.len()) as u32;
+            names.insert(name.to_string(), offset);
+            chunk.extend_from_slice(&offset.to_le_bytes());
+            chunk.extend_from_slice(gap);
+            // Next string offset, hash, character count, characters, terminator
+            chunk.extend_from_slice(&[0; 6]);
+            chunk.extend_from_slice(&(name.encode_utf16().count() as u16).to_le_bytes());
+            chunk.extend(utf16_bytes(name));
+            chunk.extend_from_slice(&[0; 2]);
+        }
+    }
+}
+
+/// Open a start tag; an element with attributes carries their list size
+fn evtx_open_element(
+    chunk: &mut Vec<u8>,
+    names: &mut BTreeMap<String, u32>,
+    name: &str,
+    has_attributes: bool,
+) {
+    chunk.push(if has_attributes { 0x41 } else { 0x01 });
+    chunk.extend_from_slice(&[0xff, 0xff, 0, 0, 0, 0]);
+    let gap: &[u8] = if has_attributes { &[0; 4] } else { &[] };
+    evtx_name(chunk, names, name, gap);
+}
+
+/// Element `name` holding substitution `index`
+fn evtx_substituted_element(
+    chunk: &mut Vec<u8>,
+    names: &mut BTreeMap<String, u32>,
+    name: &str,
+    attribute: Option<(&str, &str)>,
+    index: u16,
+    value_type: u8,
+) {
+    evtx_open_element(chunk, names, name, attribute.is_some());
+    if let Some((attribute, value)) = attribute {
+        chunk.push(0x06);
+        evtx_name(chunk, names, attribute, &[]);
+        chunk.extend_from_slice(&[0x05, 0x01]);
+        chunk.extend_from_slice(&(value.encode_utf16().count() as u16).to_le_bytes());
+        chunk.extend(utf16_bytes(value));
+    }
+    chunk.push(0x02);
+    chunk.push(0x0d);
+    chunk.extend_from_slice(&index.to_le_bytes());
+    chunk.push(value_type);
+    chunk.push(0x04);
+}
+
+/// An EVTX file with one chunk holding `events`.
+///
+/// Each distinct `EventData` layout gets one template, written inline by the
+/// first event that uses it and referenced by the later ones; names are
+/// likewise written once and then referenced, as Windows does.
+pub fn evtx_file(events: &[EvtxEvent]) -> Vec<u8> {
+    let mut chunk = vec![0u8; 512];
+    let mut names = BTreeMap::new();
+    let mut templates: BTreeMap<Vec<(String, u8)>, u32> = BTreeMap::new();
+    let mut last_record = 0usize;
+
+    for event in events {
+        let values: Vec<(u8, Vec<u8>)> =
+            std::iter::once((0x06, event.event_id.to_le_bytes().to_vec()))
+                .chain(event.data.iter().map(|(_, value)| value.encode()))
+                .collect();
+        let layout: Vec<(String, u8)> = event
+            .data
+            .iter()
+            .zip(&values[1..])
+            .map(|((name, _), (value_type, _))| (name.to_string(), *value_type))
+            .collect();
+
+        last_record = chunk.len();
+        chunk.extend_from_slice(&[0x2a, 0x2a, 0, 0, 0, 0, 0, 0]);
+        chunk.extend_from_slice(&event.record_id.to_le_bytes());
+        chunk.extend_from_slice(&event.written.to_le_bytes());
+
+        // Fragment header, then the template instance
+        chunk.extend_from_slice(&[0x0f, 0x01, 0x01, 0x00, 0x0c, 0x01, 0, 0, 0, 0]);
+        match templates.get(&layout) {
+            Some(definition) => chunk.extend_from_slice(&definition.to_le_bytes()),
+            None => {
+                let definition = (chunk.len() + 4) as u32;
+                templates.insert(layout.clone(), definition);
+                chunk.extend_from_slice(&definition.to_le_bytes());
+                // Next template offset, GUID, body size
+                chunk.extend_from_slice(&[0; 24]);
+                let body = chunk.len();
+
+                chunk.extend_from_slice(&[0x0f, 0x01, 0x01, 0x00]);
+                evtx_open_element(&mut chunk, &mut names, "Event", false);
+                chunk.push(0x02);
+                evtx_open_element(&mut chunk, &mut names, "System", false);
+                chunk.push(0x02);
+                evtx_substituted_element(&mut chunk, &mut names, "EventID", None, 0, 0x06);
+                chunk.push(0x04);
+                evtx_open_element(&mut chunk, &mut names, "EventData", false);
+                chunk.push(0x02);
+                for (index, (name, value_type)) in layout.iter().enumerate() {
+                    evtx_substituted_element(
+                        &mut chunk,
+                        &mut names,
+                        "Data",
+                        Some(("Name", name)),
+                        index as u16 + 1,
+                        *value_type,
+                    );
+                }
+                chunk.extend_from_slice(&[0x04, 0x04, 0x00]);
+
+                let size = (chunk.len() - body) as u32;
+                chunk[body - 4..body].copy_from_slice(&size.to_le_bytes());
+            }
+        }
+
+        chunk.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        for (value_type, bytes) in &values {
+            chunk.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+            chunk.extend_from_slice(&[*value_type, 0]);
+        }
+        for (_, bytes) in &values {
+            chunk.extend_from_slice(bytes);
+        }
+        chunk.push(0x00);
+
+        let size = (chunk.len() + 4 - last_record) as u32;
+        chunk.extend_from_slice(&size.to_le_bytes());
+        chunk[last_record + 4..last_record + 8].copy_from_slice(&size.to_le_bytes());
+    }
+
+    let first_id = events.first().map_or(0, |e| e.record_id);
+    let last_id = events.last().map_or(0, |e| e.record_id);
+    chunk[..8].copy_from_slice(b"ElfChnk\0");
+    chunk[8..16].copy_from_slice(&first_id.to_le_bytes());
+    chunk[16..24].copy_from_slice(&last_id.to_le_bytes());
+    chunk[24..32].copy_from_slice(&first_id.to_le_bytes());
+    chunk[32..40].copy_from_slice(&last_id.to_le_bytes());
+    chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+    chunk[44..48].copy_from_slice(&(last_record as u32).to_le_bytes());
+    let free_space = chunk.len() as u32;
+    chunk[48..52].copy_from_slice(&free_space.to_le_bytes());
+    chunk.resize(65536, 0);
+
+    let mut file = vec![0u8; 4096];
+    file[..8].copy_from_slice(b"ElfFile\0");
+    file[24..32].copy_from_slice(&(last_id + 1).to_le_bytes());
+    file[32..36].copy_from_slice(&128u32.to_le_bytes());
+    file[36..38].copy_from_slice(&1u16.to_le_bytes());
+    file[38..40].copy_from_slice(&3u16.to_le_bytes());
+    file[40..42].copy_from_slice(&4096u16.to_le_bytes());
+    file[42..44].copy_from_slice(&1u16.to_le_bytes());
+    file.extend(chunk);
+    file
+}
+
+/// A base `FILE` record for [`mft_record`]
+#[derive(Debug, Clone)]
+pub struct MftRecordFixture<'a> {
+    pub record_number: u64,
+    pub in_use: bool,
+    pub directory: bool,
+    pub standard_information: Option<NtfsTimes>,
+    /// Parent record number and long name
+    pub file_name: Option<(u64, &'a str)>,
+    pub file_name_times: NtfsTimes,
+}
+
+impl Default for MftRecordFixture<'_> {
+    fn default() -> Self {
+        let times = NtfsTimes {
+            created: FIXTURE_FILETIME,
+            modified: FIXTURE_FILETIME,
+            mft_modified: FIXTURE_FILETIME,
+            accessed: FIXTURE_FILETIME,
+        };
+        Self {
+            record_number: 0,
+            in_use: true,
+            directory: false,
+            standard_information: Some(times),
+            file_name: None,
+            file_name_times: times,
+        }
+    }
+}
+
+fn mft_times(times: &NtfsTimes) -> Vec<u8> {
+    [
+        times.created,
+        times.modified,
+        times.mft_modified,
+        times.accessed,
+    ]
+    .iter()
+    .flat_map(|t| t.to_le_bytes())
+    .collect()
+}
+
+/// Append a resident attribute of `attr_type` holding `content`
+fn mft_attribute(record: &mut Vec<u8>, attr_type: u32, content: &[u8]) {
+    let length = (24 + content.len()).next_multiple_of(8);
+    let start = record.len();
+    record.extend_from_slice(&attr_type.to_le_bytes());
+    record.extend_from_slice(&(length as u32).to_le_bytes());
+    record.extend_from_slice(&[0; 8]);
+    record.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    record.extend_from_slice(&24u16.to_le_bytes());
+    record.extend_from_slice(&[0; 2]);
+    record.extend_from_slice(content);
+    record.resize(start + length, 0);
+}
+
+/// A 1 KiB `FILE` record with update sequence fixups applied, as on disk
+pub fn mft_record(fixture: &MftRecordFixture) -> Vec<u8> {
+    const USA_OFFSET: usize = 48;
+    const FIRST_ATTRIBUTE: usize = 56;
+
+    let mut record = vec![0u8; FIRST_ATTRIBUTE];
+    record[..4].copy_from_slice(b"FILE");
+    record[4..6].copy_from_slice(&(USA_OFFSET as u16).to_le_bytes());
+    record[6..8].copy_from_slice(&3u16.to_le_bytes());
+    record[20..22].copy_from_slice(&(FIRST_ATTRIBUTE as u16).to_le_bytes());
+    let flags = u16::from(fixture.in_use) | (u16::from(fixture.directory) << 1);
+    record[22..24].copy_from_slice(&flags.to_le_bytes());
+    record[28..32].copy_from_slice(&1024u32.to_le_bytes());
+    record[44..48].copy_from_slice(&(fixture.record_number as u32).to_le_bytes());
+
+    if let Some(times) = &fixture.standard_information {
+        let mut content = mft_times(times);
+        content.resize(72, 0);
+        mft_attribute(&mut record, 0x10, &content);
+    }
+    if let Some((parent, name)) = fixture.file_name {
+        let units: Vec<u8> = utf16_bytes(name);
+        let mut content = parent.to_le_bytes().to_vec();
+        content.extend(mft_times(&fixture.file_name_times));
+        content.resize(64, 0);
+        content.push((units.len() / 2) as u8);
+        content.push(1);
+        content.extend(units);
+        mft_attribute(&mut record, 0x30, &content);
+    }
+    record.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    let used = record.len() as u32;
+    record[24..28].copy_from_slice(&used.to_le_bytes());
+    record.resize(1024, 0);
+
+    // Update sequence number 1; the array keeps each stride's last two bytes
+    record[USA_OFFSET..USA_OFFSET + 2].copy_from_slice(&1u16.to_le_bytes());
+    for stride in 1..3 {
+        let end = stride * 512;
+        let original = [record[end - 2], record[end - 1]];
+        record[USA_OFFSET + stride * 2..USA_OFFSET + stride * 2 + 2].copy_from_slice(&original);
+        record[end - 2..end].copy_from_slice(&1u16.to_le_bytes());
+    }
+    record
+}
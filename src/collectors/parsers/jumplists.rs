@@ -0,0 +1,395 @@
+//! JumpList parser.
+//!
+//! Windows keeps a JumpList per application (named by its AppID) under the
+//! user's `Recent` folder:
+//!
+//! - `AutomaticDestinations\<appid>.automaticDestinations-ms`: a compound
+//!   file with one shell link stream per recently used item, named by the
+//!   item's entry number in hex, and a `DestList` stream recording for each
+//!   entry the path, last access time, access count, pin state and the
+//!   machine it was used on.
+//! - `CustomDestinations\<appid>.customDestinations-ms`: shell links the
+//!   application pinned itself, stored back to back between small headers.
+//!
+//! `DestList` starts with a 32-byte header (`u32` version, `u32` entry
+//! count, ...). Entry layout:
+//!
+//! ```text
+//! 0x00 u64 checksum         0x08 volume/file droids, birth droids (4 GUIDs)
+//! 0x48 NetBIOS name (16)    0x58 u32 entry number     0x60 f32 access count (v1)
+//! 0x64 u64 last access      0x6C i32 pin position (-1 when not pinned)
+//! v1:      0x70 u16 path length | UTF-16 path
+//! v2 and later: 0x74 u32 access count | 0x80 u16 path length | UTF-16 path | u32
+//! ```
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use cfb::CompoundFile;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::bytes::{u16_at, u32_at, u64_at};
+use super::lnk::{self, ShellLink};
+use super::ParsedReport;
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Report file name
+pub const JUMPLISTS_FILE: &str = "jumplists.json";
+
+/// Extension of automatic JumpLists
+pub const AUTOMATIC_EXTENSION: &str = "automaticDestinations-ms";
+
+/// Extension of custom JumpLists
+pub const CUSTOM_EXTENSION: &str = "customDestinations-ms";
+
+const DEST_LIST_STREAM: &str = "DestList";
+const DEST_LIST_HEADER_SIZE: usize = 32;
+
+/// Longest path accepted from a `DestList` entry, in UTF-16 units
+const MAX_PATH_UNITS: usize = 0x8000;
+
+/// An item in an application's JumpList
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JumpListEntry {
+    /// Application id the JumpList belongs to, from its file name
+    pub app_id: String,
+    /// automatic or custom
+    pub jump_list_type: String,
+    /// `DestList` entry number; also the name (in hex) of the link stream
+    pub entry_number: Option<u32>,
+    /// Path recorded in `DestList`, or the link target for custom entries
+    pub path: Option<String>,
+    /// NetBIOS name of the machine the item was used on
+    pub hostname: Option<String>,
+    pub last_accessed: Option<DateTime<Utc>>,
+    pub access_count: Option<u32>,
+    pub pinned: bool,
+    /// MAC address from the entry's file object id
+    pub mac_address: Option<String>,
+    /// The entry's shell link, when it could be parsed
+    pub link: Option<ShellLink>,
+    /// Collected JumpList file
+    pub source: String,
+}
+
+/// A `DestList` entry and the offset of the next one
+fn dest_list_entry(data: &[u8], offset: usize, version: u32) -> Option<(JumpListEntry, usize)> {
+    let (access_count, path_offset, trailer) = if version == 1 {
+        let count = f32::from_bits(u32_at(data, offset + 0x60).ok()?);
+        (count as u32, offset + 0x70, 0)
+    } else {
+        (u32_at(data, offset + 0x74).ok()?, offset + 0x80, 4)
+    };
+
+    let units = u16_at(data, path_offset).ok()? as usize;
+    if units > MAX_PATH_UNITS {
+        return None;
+    }
+    let path_bytes = data.get(path_offset + 2..path_offset + 2 + units * 2)?;
+    let path: Vec<u16> = path_bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let end = path_offset + 2 + units * 2 + trailer;
+    if end > data.len() {
+        return None;
+    }
+
+    let hostname: String = data
+        .get(offset + 0x48..offset + 0x58)?
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect();
+    let pin_position = u32_at(data, offset + 0x6C).ok()?;
+
+    let entry = JumpListEntry {
+        app_id: String::new(),
+        jump_list_type: "automatic".to_string(),
+        entry_number: Some(u32_at(data, offset + 0x58).ok()?),
+        path: Some(String::from_utf16_lossy(&path)).filter(|p| !p.is_empty()),
+        hostname: (!hostname.is_empty()).then_some(hostname),
+        last_accessed: match u64_at(data, offset + 0x64).ok()? {
+            0 => None,
+            ticks => filetime_ticks_to_utc(ticks),
+        },
+        access_count: Some(access_count),
+        pinned: pin_position != u32::MAX,
+        mac_address: lnk::droid_mac_address(data.get(offset + 0x18..offset + 0x28)?),
+        link: None,
+        source: String::new(),
+    };
+    Some((entry, end))
+}
+
+/// Parse a `DestList` stream. Returns the entries read and, if it ended
+/// early, a note saying where.
+pub fn parse_dest_list(data: &[u8]) -> Result<(Vec<JumpListEntry>, Option<String>)> {
+    let version =
+        u32_at(data, 0).map_err(|_| anyhow!("DestList header truncated ({} bytes)", data.len()))?;
+    let count = u32_at(data, 4).map_err(|_| anyhow!("DestList header truncated"))? as usize;
+    if !(1..=6).contains(&version) {
+        bail!("Unknown DestList version {}", version);
+    }
+
+    let mut entries = Vec::new();
+    let mut offset = DEST_LIST_HEADER_SIZE;
+    while entries.len() < count {
+        match dest_list_entry(data, offset, version) {
+            Some((entry, next)) => {
+                entries.push(entry);
+                offset = next;
+            }
+            None => {
+                let note = format!(
+                    "DestList truncated after {} of {} entries",
+                    entries.len(),
+                    count
+                );
+                return Ok((entries, Some(note)));
+            }
+        }
+    }
+    Ok((entries, None))
+}
+
+/// Entries of an automatic JumpList, with their link streams; problems are
+/// added to `notes`
+fn parse_automatic(
+    data: Vec<u8>,
+    notes: &mut Vec<String>,
+    name: &str,
+) -> Result<Vec<JumpListEntry>> {
+    let mut file = CompoundFile::open(Cursor::new(data)).context("Failed to open compound file")?;
+    let dest_list = read_stream(&mut file, DEST_LIST_STREAM)?;
+    let (mut entries, truncated) = parse_dest_list(&dest_list)?;
+    if let Some(note) = truncated {
+        notes.push(format!("{}: {}", name, note));
+    }
+
+    for entry in &mut entries {
+        let Some(number) = entry.entry_number else {
+            continue;
+        };
+        let stream = format!("{:x}", number);
+        match read_stream(&mut file, &stream).and_then(|data| lnk::parse_shell_link(&data)) {
+            Ok(link) => entry.link = Some(link),
+            Err(e) => notes.push(format!("{}: entry {}: {}", name, stream, e)),
+        }
+    }
+    Ok(entries)
+}
+
+/// Contents of the stream `name` in the root storage
+fn read_stream(file: &mut CompoundFile<Cursor<Vec<u8>>>, name: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    file.open_stream(format!("/{}", name))
+        .and_then(|mut stream| stream.read_to_end(&mut data))
+        .context(format!("Failed to read stream {}", name))?;
+    Ok(data)
+}
+
+/// Shell links stored in a custom JumpList; links that cannot be parsed are
+/// added to `notes`
+fn parse_custom(data: &[u8], notes: &mut Vec<String>, name: &str) -> Vec<JumpListEntry> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + lnk::HEADER_SIZE <= data.len() {
+        if !lnk::is_shell_link(&data[offset..]) {
+            offset += 1;
+            continue;
+        }
+        match lnk::parse_shell_link(&data[offset..]) {
+            Ok(link) => entries.push(JumpListEntry {
+                app_id: String::new(),
+                jump_list_type: "custom".to_string(),
+                entry_number: None,
+                path: link.target_path.clone(),
+                hostname: link.machine_id.clone(),
+                last_accessed: None,
+                access_count: None,
+                pinned: false,
+                mac_address: link.mac_address.clone(),
+                link: Some(link),
+                source: String::new(),
+            }),
+            Err(e) => notes.push(format!("{}: link at offset {:#x}: {}", name, offset, e)),
+        }
+        offset += lnk::HEADER_SIZE;
+    }
+    entries
+}
+
+/// Whether `path` has a JumpList extension
+pub fn is_jump_list(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ext.eq_ignore_ascii_case(AUTOMATIC_EXTENSION) || ext.eq_ignore_ascii_case(CUSTOM_EXTENSION)
+    })
+}
+
+/// Parse each collected JumpList; files that cannot be parsed are noted and
+/// skipped
+pub fn parse_jump_lists(files: &[PathBuf]) -> Result<ParsedReport<JumpListEntry>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let app_id = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let automatic = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(AUTOMATIC_EXTENSION));
+
+        let parsed = fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| {
+                if automatic {
+                    parse_automatic(data, &mut report.notes, &name)
+                } else {
+                    Ok(parse_custom(&data, &mut report.notes, &name))
+                }
+            });
+        match parsed {
+            Ok(entries) => {
+                for mut entry in entries {
+                    entry.app_id = app_id.clone();
+                    entry.source = file.to_string_lossy().to_string();
+                    report.records.push(entry);
+                }
+            }
+            Err(e) => report.notes.push(format!("Skipped {}: {}", name, e)),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{
+        compound_file, dest_list, lnk_file, DestListFixture, LnkFixture,
+    };
+    use tempfile::TempDir;
+
+    fn link(suffix: &'static str) -> Vec<u8> {
+        lnk_file(&LnkFixture {
+            local_base_path: r"C:\Users\alice\Downloads\",
+            path_suffix: suffix,
+            ..Default::default()
+        })
+    }
+
+    fn entries() -> Vec<DestListFixture<'static>> {
+        vec![
+            DestListFixture {
+                entry_number: 1,
+                path: r"C:\Users\alice\Downloads\invoice.pdf",
+                hostname: "workstation7",
+                access_count: 3,
+                pinned: true,
+                ..Default::default()
+            },
+            DestListFixture {
+                entry_number: 0x1a,
+                path: r"C:\Users\alice\Downloads\setup.exe",
+                hostname: "workstation7",
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_dest_list() {
+        let (parsed, note) = parse_dest_list(&dest_list(4, &entries())).unwrap();
+        assert_eq!(note, None);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].entry_number, Some(1));
+        assert_eq!(
+            parsed[0].path.as_deref(),
+            Some(r"C:\Users\alice\Downloads\invoice.pdf")
+        );
+        assert_eq!(parsed[0].hostname.as_deref(), Some("workstation7"));
+        assert_eq!(parsed[0].access_count, Some(3));
+        assert!(parsed[0].pinned);
+        assert!(!parsed[1].pinned);
+        assert_eq!(
+            parsed[0].last_accessed.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+        assert_eq!(parsed[0].mac_address.as_deref(), Some("00:0c:29:ab:cd:ef"));
+
+        let (parsed, _) = parse_dest_list(&dest_list(1, &entries())).unwrap();
+        assert_eq!(parsed[1].entry_number, Some(0x1a));
+        assert_eq!(parsed[1].access_count, Some(1));
+
+        let data = dest_list(4, &entries());
+        let (parsed, note) = parse_dest_list(&data[..data.len() - 10]).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(
+            note.as_deref(),
+            Some("DestList truncated after 1 of 2 entries")
+        );
+    }
+
+    #[test]
+    fn test_parse_jump_lists() {
+        let temp = TempDir::new().unwrap();
+        let automatic = temp
+            .path()
+            .join("5f7b5f1e01b83767.automaticDestinations-ms");
+        let custom = temp.path().join("9b9cdc69c1c24e2b.customDestinations-ms");
+        let broken = temp
+            .path()
+            .join("0000000000000000.automaticDestinations-ms");
+
+        fs::write(
+            &automatic,
+            compound_file(&[
+                ("1", link("invoice.pdf")),
+                ("1a", b"truncated".to_vec()),
+                ("DestList", dest_list(4, &entries())),
+            ]),
+        )
+        .unwrap();
+        let mut custom_data = vec![0u8; 24];
+        custom_data.extend(link("tool.exe"));
+        custom_data.extend([0xAB, 0xFB, 0xBF, 0xBA]);
+        fs::write(&custom, custom_data).unwrap();
+        fs::write(&broken, b"not a compound file").unwrap();
+
+        let report = parse_jump_lists(&[automatic.clone(), broken, custom]).unwrap();
+        assert_eq!(report.records.len(), 3);
+
+        let first = &report.records[0];
+        assert_eq!(first.app_id, "5f7b5f1e01b83767");
+        assert_eq!(first.jump_list_type, "automatic");
+        assert_eq!(first.source, automatic.to_string_lossy());
+        assert_eq!(
+            first.link.as_ref().unwrap().target_path.as_deref(),
+            Some(r"C:\Users\alice\Downloads\invoice.pdf")
+        );
+        assert!(report.records[1].link.is_none());
+
+        let custom_entry = &report.records[2];
+        assert_eq!(custom_entry.jump_list_type, "custom");
+        assert_eq!(
+            custom_entry.path.as_deref(),
+            Some(r"C:\Users\alice\Downloads\tool.exe")
+        );
+
+        assert_eq!(report.notes.len(), 2);
+        assert!(report.notes[0].starts_with("5f7b5f1e01b83767.automaticDestinations-ms: entry 1a:"));
+        assert!(report.notes[1].starts_with(
+            "Skipped 0000000000000000.automaticDestinations-ms: Failed to open compound file"
+        ));
+    }
+}
@@ -0,0 +1,584 @@
+//! Linux persistence analyzer.
+//!
+//! Reviews the collected `Linux:Persistence` locations and systemd units for
+//! entries that run code from attacker-friendly places:
+//!
+//! - `ld.so.preload` libraries
+//! - PAM service files (`pam_exec.so` and absolute module paths)
+//! - `rc.local`, init.d and profile.d scripts, and per-user shell profiles
+//! - udev rules' `RUN` keys
+//! - systemd units' `Exec*=` lines and systemd generators
+//!
+//! Every absolute path referenced by those entries is checked. An entry is
+//! reported in `persistence_findings.json` when the path is under `/tmp`,
+//! `/var/tmp` or `/dev/shm`, or when the path or its directory is
+//! world-writable on the live system.
+//!
+//! The collected files say which libraries are loaded but not what they
+//! contain, so `persistence_libraries.json` hashes the live copies of the
+//! preloaded libraries and every module in the PAM module directories.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::ParsedReport;
+use crate::utils::hash::calculate_sha256;
+
+/// Report file name for flagged entries
+pub const PERSISTENCE_FINDINGS_FILE: &str = "persistence_findings.json";
+
+/// Report file name for the preload and PAM library hashes
+pub const PERSISTENCE_LIBRARIES_FILE: &str = "persistence_libraries.json";
+
+/// Temporary directories any user can write to
+const TEMP_DIRS: &[&str] = &["/tmp", "/var/tmp", "/dev/shm"];
+
+/// Shell startup files looked for in home directories
+const SHELL_PROFILES: &[&str] = &[
+    ".bashrc",
+    ".bash_profile",
+    ".bash_login",
+    ".bash_logout",
+    ".profile",
+    ".zshrc",
+    ".zprofile",
+    ".zshenv",
+    ".zlogin",
+];
+
+/// PAM module directories relative to the root, besides the multiarch
+/// `lib/<triplet>/security` directories found at runtime
+const PAM_MODULE_DIRS: &[&str] = &[
+    "lib/security",
+    "lib64/security",
+    "usr/lib/security",
+    "usr/lib64/security",
+];
+
+/// Libraries larger than this are listed without a hash
+const MAX_LIBRARY_HASH_MB: u64 = 256;
+
+lazy_static! {
+    /// An absolute path following whitespace, a quote, `=`, `:`, `;` or `(`
+    static ref ABSOLUTE_PATH: Regex = Regex::new(r#"(?:^|[\s"'`=:;(])(/[^\s"'`;|&<>()]+)"#)
+        .expect("path pattern is valid");
+    /// A udev `RUN`, `RUN+=` or `RUN{program}+=` assignment and its value
+    static ref UDEV_RUN: Regex = Regex::new(r#"RUN(?:\{[^}]*\})?\s*\+?=\s*"([^"]*)""#)
+        .expect("udev pattern is valid");
+}
+
+/// Kind of persistence location a collected file comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceLocation {
+    LdSoPreload,
+    Pam,
+    RcLocal,
+    InitScript,
+    ProfileScript,
+    ShellProfile,
+    UdevRule,
+    SystemdUnit,
+    SystemdGenerator,
+}
+
+/// An entry referencing a suspicious path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistenceFinding {
+    pub location: PersistenceLocation,
+    /// Collected file the entry was found in
+    pub source: String,
+    /// 1-based line number of the entry
+    pub line: usize,
+    /// The entry as written
+    pub entry: String,
+    pub referenced_path: String,
+    /// Why the path was flagged
+    pub reason: String,
+}
+
+/// A preloaded library or PAM module on the live system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistenceLibrary {
+    /// `ld_so_preload` or `pam_module`
+    pub kind: String,
+    pub path: String,
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+    pub world_writable: bool,
+}
+
+fn parent_name(path: &Path) -> Option<String> {
+    path.parent()
+        .and_then(Path::file_name)
+        .map(|n| n.to_string_lossy().to_string())
+}
+
+/// Which persistence location a collected file belongs to, if any
+pub fn classify(path: &Path) -> Option<PersistenceLocation> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let parent = parent_name(path).unwrap_or_default();
+
+    if name == "ld.so.preload" {
+        Some(PersistenceLocation::LdSoPreload)
+    } else if name == "rc.local" {
+        Some(PersistenceLocation::RcLocal)
+    } else if SHELL_PROFILES.contains(&name.as_str()) {
+        Some(PersistenceLocation::ShellProfile)
+    } else if parent == "pam.d" {
+        Some(PersistenceLocation::Pam)
+    } else if parent == "init.d" {
+        Some(PersistenceLocation::InitScript)
+    } else if parent == "profile.d" {
+        Some(PersistenceLocation::ProfileScript)
+    } else if parent == "rules.d" && name.ends_with(".rules") {
+        Some(PersistenceLocation::UdevRule)
+    } else if parent.ends_with("-generators") {
+        Some(PersistenceLocation::SystemdGenerator)
+    } else if [".service", ".timer", ".socket", ".path"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+    {
+        Some(PersistenceLocation::SystemdUnit)
+    } else {
+        None
+    }
+}
+
+/// Whether `path` is a collected persistence location
+pub fn is_persistence_file(path: &Path) -> bool {
+    classify(path).is_some()
+}
+
+/// Map an absolute path on the examined system to a path under `root`
+fn under_root(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+#[cfg(unix)]
+fn is_world_writable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|m| m.permissions().mode() & 0o002 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_world_writable(_path: &Path) -> bool {
+    false
+}
+
+/// Why a referenced path is suspicious, if it is
+fn suspicious_reason(path: &str, root: &Path) -> Option<String> {
+    if let Some(dir) = TEMP_DIRS
+        .iter()
+        .find(|dir| path == **dir || path.starts_with(&format!("{}/", dir)))
+    {
+        return Some(format!("Path is under {}", dir));
+    }
+
+    let live = under_root(root, path);
+    if is_world_writable(&live) {
+        return Some("Path is world-writable".to_string());
+    }
+    let parent = Path::new(path).parent().filter(|p| *p != Path::new("/"))?;
+    if is_world_writable(&under_root(root, &parent.to_string_lossy())) {
+        return Some(format!("Directory {} is world-writable", parent.display()));
+    }
+    None
+}
+
+/// Absolute paths referenced by one line of a file at `location`
+fn referenced_paths(location: PersistenceLocation, line: &str) -> Vec<String> {
+    let candidates: Vec<&str> = match location {
+        PersistenceLocation::LdSoPreload => line
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .filter(|s| s.starts_with('/'))
+            .collect(),
+        PersistenceLocation::UdevRule => UDEV_RUN
+            .captures_iter(line)
+            .filter_map(|c| c.get(1))
+            .flat_map(|value| {
+                ABSOLUTE_PATH
+                    .captures_iter(value.as_str())
+                    .filter_map(|c| c.get(1).map(|m| m.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+        PersistenceLocation::SystemdUnit => match line.split_once('=') {
+            // Exec prefixes such as `-` (ignore failure) or `@` precede the path
+            Some((key, value)) if key.trim().starts_with("Exec") => ABSOLUTE_PATH
+                .captures_iter(value.trim_start_matches(['-', '@', '+', '!', ':']))
+                .filter_map(|c| c.get(1).map(|m| m.as_str()))
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => ABSOLUTE_PATH
+            .captures_iter(line)
+            .filter_map(|c| c.get(1).map(|m| m.as_str()))
+            .collect(),
+    };
+
+    // `/tmp/x,` and `/tmp/x` are the same reference
+    candidates
+        .into_iter()
+        .map(|p| p.trim_end_matches([',', '.']).to_string())
+        .filter(|p| p.len() > 1)
+        .collect()
+}
+
+/// Flag entries of the collected persistence files that reference paths in
+/// temporary or world-writable locations. `root` is where the examined
+/// system's file system is mounted (`/` for the live host).
+pub fn analyze_persistence(
+    files: &[PathBuf],
+    root: &Path,
+) -> Result<ParsedReport<PersistenceFinding>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files {
+        let location = match classify(file) {
+            Some(location) => location,
+            None => continue,
+        };
+        let name = file.display();
+        let data = match fs::read(file) {
+            Ok(data) => data,
+            Err(e) => {
+                report.notes.push(format!("Skipped {}: {}", name, e));
+                continue;
+            }
+        };
+        if data.contains(&0) {
+            // Compiled generators and the like have no lines to review
+            report.notes.push(format!("Skipped {}: binary file", name));
+            continue;
+        }
+
+        let text = String::from_utf8_lossy(&data);
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            let mut seen = HashSet::new();
+            for path in referenced_paths(location, line) {
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+                if let Some(reason) = suspicious_reason(&path, root) {
+                    report.records.push(PersistenceFinding {
+                        location,
+                        source: file.to_string_lossy().to_string(),
+                        line: index + 1,
+                        entry: line.to_string(),
+                        referenced_path: path,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn library_record(kind: &str, path: &str, root: &Path) -> PersistenceLibrary {
+    let live = under_root(root, path);
+    PersistenceLibrary {
+        kind: kind.to_string(),
+        path: path.to_string(),
+        size: fs::metadata(&live).ok().map(|m| m.len()),
+        sha256: calculate_sha256(&live, MAX_LIBRARY_HASH_MB).ok().flatten(),
+        world_writable: is_world_writable(&live),
+    }
+}
+
+/// PAM module directories present under `root`, as absolute paths
+fn pam_module_dirs(root: &Path) -> Vec<String> {
+    let mut dirs: Vec<String> = PAM_MODULE_DIRS.iter().map(|d| format!("/{}", d)).collect();
+    for lib in ["lib", "usr/lib"] {
+        if let Ok(entries) = fs::read_dir(root.join(lib)) {
+            let mut triplets: Vec<String> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|n| n.ends_with("-linux-gnu"))
+                .collect();
+            triplets.sort();
+            dirs.extend(triplets.iter().map(|t| format!("/{}/{}/security", lib, t)));
+        }
+    }
+    dirs.retain(|d| under_root(root, d).is_dir());
+    dirs
+}
+
+/// Hash the libraries listed in the collected `ld.so.preload` files and
+/// every module in the PAM module directories under `root`
+pub fn hash_persistence_libraries(
+    files: &[PathBuf],
+    root: &Path,
+) -> Result<ParsedReport<PersistenceLibrary>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files
+        .iter()
+        .filter(|f| classify(f) == Some(PersistenceLocation::LdSoPreload))
+    {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Skipped {}: {}", file.display(), e));
+                continue;
+            }
+        };
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('#') {
+                continue;
+            }
+            for library in referenced_paths(PersistenceLocation::LdSoPreload, line) {
+                report
+                    .records
+                    .push(library_record("ld_so_preload", &library, root));
+            }
+        }
+    }
+
+    for dir in pam_module_dirs(root) {
+        let entries = match fs::read_dir(under_root(root, &dir)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report.notes.push(format!("Skipped {}: {}", dir, e));
+                continue;
+            }
+        };
+        let mut modules: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.ends_with(".so"))
+            .collect();
+        modules.sort();
+        for module in modules {
+            report.records.push(library_record(
+                "pam_module",
+                &format!("{}/{}", dir, module),
+                root,
+            ));
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, rel: &str, contents: &str) -> PathBuf {
+        let path = dir.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            (
+                "fs/etc/ld.so.preload",
+                Some(PersistenceLocation::LdSoPreload),
+            ),
+            ("fs/etc/pam.d/sshd", Some(PersistenceLocation::Pam)),
+            ("fs/etc/rc.local", Some(PersistenceLocation::RcLocal)),
+            ("fs/etc/init.d/ssh", Some(PersistenceLocation::InitScript)),
+            (
+                "fs/etc/profile.d/z.sh",
+                Some(PersistenceLocation::ProfileScript),
+            ),
+            ("fs/alice/.bashrc", Some(PersistenceLocation::ShellProfile)),
+            (
+                "fs/etc/udev/rules.d/99-x.rules",
+                Some(PersistenceLocation::UdevRule),
+            ),
+            (
+                "fs/etc/systemd/system-generators/gen",
+                Some(PersistenceLocation::SystemdGenerator),
+            ),
+            (
+                "fs/usr/lib/systemd/apt.timer",
+                Some(PersistenceLocation::SystemdUnit),
+            ),
+            ("fs/var/log/syslog", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(classify(Path::new(path)), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_referenced_paths() {
+        assert_eq!(
+            referenced_paths(
+                PersistenceLocation::UdevRule,
+                r#"ACTION=="add", ENV{ID}="/opt/id", RUN+="/bin/sh -c '/dev/shm/x'""#
+            ),
+            vec!["/bin/sh", "/dev/shm/x"]
+        );
+        assert_eq!(
+            referenced_paths(
+                PersistenceLocation::SystemdUnit,
+                "ExecStartPre=-/tmp/.cache/run --flag"
+            ),
+            vec!["/tmp/.cache/run"]
+        );
+        assert!(
+            referenced_paths(PersistenceLocation::SystemdUnit, "WorkingDirectory=/tmp").is_empty()
+        );
+        assert_eq!(
+            referenced_paths(PersistenceLocation::LdSoPreload, "/lib/a.so:/tmp/b.so"),
+            vec!["/lib/a.so", "/tmp/b.so"]
+        );
+    }
+
+    #[test]
+    fn test_analyze_persistence_flags_temp_paths() {
+        let collected = TempDir::new().unwrap();
+        let root = TempDir::new().unwrap();
+        let dir = collected.path();
+
+        let files = vec![
+            write(
+                dir,
+                "fs/etc/ld.so.preload",
+                "# preload\n/dev/shm/libhook.so\n",
+            ),
+            write(
+                dir,
+                "fs/etc/pam.d/sshd",
+                "auth required pam_unix.so\nauth optional pam_exec.so /var/tmp/grab.sh\n",
+            ),
+            write(
+                dir,
+                "fs/etc/rc.local",
+                "#!/bin/sh\n/usr/sbin/ntpdate pool\nexit 0\n",
+            ),
+            write(
+                dir,
+                "fs/alice/.bashrc",
+                "alias ll='ls -l'\n(/tmp/.x/beacon &)\n",
+            ),
+            write(
+                dir,
+                "fs/etc/udev/rules.d/99-usb.rules",
+                "# /tmp/ignored\nACTION==\"add\", RUN+=\"/tmp/usb.sh\"\n",
+            ),
+            write(
+                dir,
+                "fs/etc/systemd/backdoor.service",
+                "[Service]\nWorkingDirectory=/tmp\nExecStart=/tmp/backdoor\n",
+            ),
+            write(dir, "fs/etc/systemd/system-generators/gen", "\x7fELF\0\0"),
+        ];
+
+        let report = analyze_persistence(&files, root.path()).unwrap();
+        let flagged: Vec<(PersistenceLocation, &str, usize)> = report
+            .records
+            .iter()
+            .map(|f| (f.location, f.referenced_path.as_str(), f.line))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (PersistenceLocation::LdSoPreload, "/dev/shm/libhook.so", 2),
+                (PersistenceLocation::Pam, "/var/tmp/grab.sh", 2),
+                (PersistenceLocation::ShellProfile, "/tmp/.x/beacon", 2),
+                (PersistenceLocation::UdevRule, "/tmp/usb.sh", 2),
+                (PersistenceLocation::SystemdUnit, "/tmp/backdoor", 3),
+            ]
+        );
+        assert_eq!(report.records[3].reason, "Path is under /tmp");
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].contains("binary file"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_analyze_persistence_flags_world_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let collected = TempDir::new().unwrap();
+        let root = TempDir::new().unwrap();
+        let drop_dir = root.path().join("opt/drop");
+        fs::create_dir_all(&drop_dir).unwrap();
+        fs::set_permissions(&drop_dir, fs::Permissions::from_mode(0o777)).unwrap();
+        let tool = write(root.path(), "opt/tools/update", "");
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o666)).unwrap();
+        write(root.path(), "usr/bin/safe", "");
+
+        let files = vec![write(
+            collected.path(),
+            "fs/etc/profile.d/site.sh",
+            "/opt/drop/run.sh\n/opt/tools/update\n/usr/bin/safe\n",
+        )];
+        let report = analyze_persistence(&files, root.path()).unwrap();
+
+        let reasons: Vec<&str> = report.records.iter().map(|f| f.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "Directory /opt/drop is world-writable",
+                "Path is world-writable"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_persistence_libraries() {
+        let collected = TempDir::new().unwrap();
+        let root = TempDir::new().unwrap();
+        write(root.path(), "lib/libhook.so", "hook");
+        write(
+            root.path(),
+            "lib/x86_64-linux-gnu/security/pam_unix.so",
+            "pam",
+        );
+        write(root.path(), "lib/x86_64-linux-gnu/security/README", "");
+
+        let files = vec![write(
+            collected.path(),
+            "fs/etc/ld.so.preload",
+            "/lib/libhook.so /lib/missing.so\n",
+        )];
+        let report = hash_persistence_libraries(&files, root.path()).unwrap();
+
+        let paths: Vec<(&str, &str)> = report
+            .records
+            .iter()
+            .map(|l| (l.kind.as_str(), l.path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("ld_so_preload", "/lib/libhook.so"),
+                ("ld_so_preload", "/lib/missing.so"),
+                ("pam_module", "/lib/x86_64-linux-gnu/security/pam_unix.so"),
+            ]
+        );
+        // SHA-256 of "hook"
+        assert_eq!(
+            report.records[0].sha256.as_deref(),
+            Some("0648298b48be031996277ae472115a46e7964d2ac3882e61b84351f3c3f8a547")
+        );
+        assert_eq!(report.records[0].size, Some(4));
+        assert!(report.records[1].sha256.is_none());
+    }
+}
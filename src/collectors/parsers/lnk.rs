@@ -0,0 +1,440 @@
+//! Windows shell link (`.lnk`) parser.
+//!
+//! Shortcuts in a user's `Recent` folder are created by Explorer when a file
+//! is opened, so they record files the user touched even after the target
+//! is gone. A link (MS-SHLLINK) is a fixed header followed by optional
+//! sections selected by the header's flags:
+//!
+//! ```text
+//! header (0x4C bytes) | LinkTargetIDList | LinkInfo | StringData | ExtraData
+//! ```
+//!
+//! The target path is rebuilt from `LinkInfo` (local base path or network
+//! share, plus the common path suffix). The shell item list is skipped. The
+//! header holds the target's timestamps and size as they were when the link
+//! was last updated, and the `TrackerDataBlock` in `ExtraData` names the
+//! machine the target was on and, through its version 1 object id, the MAC
+//! address of that machine.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::bytes::{u16_at, u32_at, u64_at};
+use super::ParsedReport;
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Report file name
+pub const LNK_REPORT_FILE: &str = "lnk_report.json";
+
+/// Size of the fixed header; also its first field
+pub const HEADER_SIZE: usize = 0x4C;
+
+/// `LinkCLSID` {00021401-0000-0000-C000-000000000046} as stored
+pub const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0001;
+const HAS_LINK_INFO: u32 = 0x0002;
+const HAS_NAME: u32 = 0x0004;
+const HAS_RELATIVE_PATH: u32 = 0x0008;
+const HAS_WORKING_DIR: u32 = 0x0010;
+const HAS_ARGUMENTS: u32 = 0x0020;
+const HAS_ICON_LOCATION: u32 = 0x0040;
+const IS_UNICODE: u32 = 0x0080;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x2;
+
+const TRACKER_DATA_SIGNATURE: u32 = 0xA000_0003;
+const TRACKER_DATA_SIZE: usize = 0x60;
+
+/// A parsed shell link
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellLink {
+    /// Target path from `LinkInfo`; absent for links to shell folders
+    pub target_path: Option<String>,
+    pub arguments: Option<String>,
+    pub working_directory: Option<String>,
+    pub relative_path: Option<String>,
+    pub description: Option<String>,
+    pub icon_location: Option<String>,
+    /// Target timestamps and size recorded in the link header (UTC)
+    pub target_created: Option<DateTime<Utc>>,
+    pub target_modified: Option<DateTime<Utc>>,
+    pub target_accessed: Option<DateTime<Utc>>,
+    pub target_size: u32,
+    pub target_attributes: u32,
+    /// fixed, removable, remote, cdrom, ramdisk, no_root_dir or unknown
+    pub drive_type: Option<String>,
+    /// Serial number of the target's volume, as `XXXX-XXXX`
+    pub volume_serial: Option<String>,
+    pub volume_label: Option<String>,
+    /// UNC share for targets on network drives
+    pub network_share: Option<String>,
+    /// NetBIOS name of the machine the target was on
+    pub machine_id: Option<String>,
+    /// MAC address from the tracker object id, when it is a version 1 UUID
+    pub mac_address: Option<String>,
+}
+
+/// A link file from the collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LnkRecord {
+    #[serde(flatten)]
+    pub link: ShellLink,
+    /// Collected link file
+    pub source: String,
+}
+
+fn filetime(ticks: u64) -> Option<DateTime<Utc>> {
+    match ticks {
+        0 => None,
+        ticks => filetime_ticks_to_utc(ticks),
+    }
+}
+
+/// NUL-terminated single-byte string at `offset`
+fn ansi_string(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    Some(bytes[..end].iter().map(|&b| b as char).collect())
+}
+
+/// NUL-terminated UTF-16 string at `offset`
+fn unicode_string(data: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = data
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+fn drive_type_name(value: u32) -> &'static str {
+    match value {
+        1 => "no_root_dir",
+        2 => "removable",
+        3 => "fixed",
+        4 => "remote",
+        5 => "cdrom",
+        6 => "ramdisk",
+        _ => "unknown",
+    }
+}
+
+/// Check for the link header size and CLSID
+pub fn is_shell_link(data: &[u8]) -> bool {
+    data.len() >= HEADER_SIZE
+        && data[..4] == (HEADER_SIZE as u32).to_le_bytes()
+        && data[4..20] == LINK_CLSID
+}
+
+/// Whether `path` has the `.lnk` extension
+pub fn is_lnk_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+}
+
+/// Parse a shell link.
+///
+/// Fails if the header is invalid or a section the flags announce is
+/// truncated; a truncated `ExtraData` section only loses the blocks past
+/// the cut.
+pub fn parse_shell_link(data: &[u8]) -> Result<ShellLink> {
+    if !is_shell_link(data) {
+        bail!("Not a shell link (bad header size or CLSID)");
+    }
+    let flags = u32_at(data, 0x14)?;
+    let mut link = ShellLink {
+        target_attributes: u32_at(data, 0x18)?,
+        target_created: filetime(u64_at(data, 0x1C)?),
+        target_accessed: filetime(u64_at(data, 0x24)?),
+        target_modified: filetime(u64_at(data, 0x2C)?),
+        target_size: u32_at(data, 0x34)?,
+        ..Default::default()
+    };
+
+    let mut offset = HEADER_SIZE;
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        offset += 2 + u16_at(data, offset)? as usize;
+    }
+
+    if flags & HAS_LINK_INFO != 0 {
+        let size = u32_at(data, offset)? as usize;
+        let info = data
+            .get(offset..offset + size)
+            .ok_or_else(|| anyhow!("LinkInfo truncated at offset {:#x}", offset))?;
+        parse_link_info(info, &mut link)?;
+        offset += size;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    for (flag, field) in [
+        (HAS_NAME, &mut link.description),
+        (HAS_RELATIVE_PATH, &mut link.relative_path),
+        (HAS_WORKING_DIR, &mut link.working_directory),
+        (HAS_ARGUMENTS, &mut link.arguments),
+        (HAS_ICON_LOCATION, &mut link.icon_location),
+    ] {
+        if flags & flag != 0 {
+            let (value, next) = string_data(data, offset, unicode)?;
+            *field = non_empty(Some(value));
+            offset = next;
+        }
+    }
+
+    parse_extra_data(data, offset, &mut link);
+    Ok(link)
+}
+
+/// A counted `StringData` string; returns it and the next offset
+fn string_data(data: &[u8], offset: usize, unicode: bool) -> Result<(String, usize)> {
+    let count = u16_at(data, offset)? as usize;
+    let start = offset + 2;
+    let len = if unicode { count * 2 } else { count };
+    let bytes = data
+        .get(start..start + len)
+        .ok_or_else(|| anyhow!("String data truncated at offset {:#x}", offset))?;
+    let value = if unicode {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    };
+    Ok((value, start + len))
+}
+
+fn parse_link_info(info: &[u8], link: &mut ShellLink) -> Result<()> {
+    let header_size = u32_at(info, 4)? as usize;
+    let info_flags = u32_at(info, 8)?;
+    let volume_id_offset = u32_at(info, 12)? as usize;
+    let local_base_offset = u32_at(info, 16)? as usize;
+    let network_offset = u32_at(info, 20)? as usize;
+    let suffix_offset = u32_at(info, 24)? as usize;
+    // Unicode copies of the paths follow in headers of 0x24 bytes or more
+    let (local_base_unicode, suffix_unicode) = if header_size >= 0x24 {
+        (
+            Some(u32_at(info, 28)? as usize),
+            Some(u32_at(info, 32)? as usize),
+        )
+    } else {
+        (None, None)
+    };
+
+    let suffix = match suffix_unicode {
+        Some(offset) if offset > 0 => unicode_string(info, offset),
+        _ => ansi_string(info, suffix_offset),
+    }
+    .unwrap_or_default();
+
+    if info_flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        parse_volume_id(info, volume_id_offset, link)?;
+        let base = match local_base_unicode {
+            Some(offset) if offset > 0 => unicode_string(info, offset),
+            _ => ansi_string(info, local_base_offset),
+        };
+        link.target_path = base.map(|base| join_path(&base, &suffix));
+    }
+
+    if info_flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+        let net_name_offset = u32_at(info, network_offset + 8)? as usize;
+        let share = non_empty(ansi_string(info, network_offset + net_name_offset));
+        if link.target_path.is_none() {
+            link.target_path = share.as_deref().map(|share| join_path(share, &suffix));
+        }
+        link.network_share = share;
+    }
+    Ok(())
+}
+
+fn join_path(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() || base.ends_with('\\') {
+        format!("{}{}", base, suffix)
+    } else {
+        format!("{}\\{}", base, suffix)
+    }
+}
+
+fn parse_volume_id(info: &[u8], offset: usize, link: &mut ShellLink) -> Result<()> {
+    let drive_type = u32_at(info, offset + 4)?;
+    let serial = u32_at(info, offset + 8)?;
+    let label_offset = u32_at(info, offset + 12)? as usize;
+    // An offset of 0x14 means the label is stored as Unicode instead
+    let label = if label_offset == 0x14 {
+        let unicode_offset = u32_at(info, offset + 16)? as usize;
+        unicode_string(info, offset + unicode_offset)
+    } else {
+        ansi_string(info, offset + label_offset)
+    };
+
+    link.drive_type = Some(drive_type_name(drive_type).to_string());
+    link.volume_serial = Some(format!("{:04X}-{:04X}", serial >> 16, serial & 0xFFFF));
+    link.volume_label = non_empty(label);
+    Ok(())
+}
+
+fn parse_extra_data(data: &[u8], mut offset: usize, link: &mut ShellLink) {
+    // Blocks end with a terminal block smaller than 4 bytes
+    while let (Ok(size), Ok(signature)) = (u32_at(data, offset), u32_at(data, offset + 4)) {
+        let size = size as usize;
+        if size < 8 {
+            break;
+        }
+        let Some(block) = data.get(offset..offset + size) else {
+            break;
+        };
+        if signature == TRACKER_DATA_SIGNATURE && size >= TRACKER_DATA_SIZE {
+            let machine_id: String = block[16..32]
+                .iter()
+                .take_while(|&&b| b != 0)
+                .map(|&b| b as char)
+                .collect();
+            link.machine_id = non_empty(Some(machine_id));
+            link.mac_address = droid_mac_address(&block[48..64]);
+        }
+        offset += size;
+    }
+}
+
+/// MAC address from the node field of a version 1 UUID, as stored in
+/// tracker object ids
+pub fn droid_mac_address(guid: &[u8]) -> Option<String> {
+    if guid.len() != 16 || guid[7] >> 4 != 1 {
+        return None;
+    }
+    Some(
+        guid[10..16]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// Parse each collected link file; files that cannot be parsed are noted
+/// and skipped
+pub fn parse_lnk_files(files: &[PathBuf]) -> Result<ParsedReport<LnkRecord>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files {
+        let parsed = fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| parse_shell_link(&data));
+        match parsed {
+            Ok(link) => report.records.push(LnkRecord {
+                link,
+                source: file.to_string_lossy().to_string(),
+            }),
+            Err(e) => report
+                .notes
+                .push(format!("Skipped {}: {}", display_name(file), e)),
+        }
+    }
+
+    Ok(report)
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{lnk_file, LnkFixture};
+    use tempfile::TempDir;
+
+    fn sample_link() -> LnkFixture<'static> {
+        LnkFixture {
+            local_base_path: r"C:\Users\alice\Documents\",
+            path_suffix: "report.docx",
+            arguments: "/safe",
+            working_directory: r"C:\Users\alice\Documents",
+            volume_serial: 0x1234_ABCD,
+            volume_label: "OS",
+            machine_id: "workstation7",
+            file_droid: [
+                0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x11, 0x80, 0x01, 0x00, 0x0c, 0x29, 0xab,
+                0xcd, 0xef,
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_shell_link() {
+        let link = parse_shell_link(&lnk_file(&sample_link())).unwrap();
+
+        assert_eq!(
+            link.target_path.as_deref(),
+            Some(r"C:\Users\alice\Documents\report.docx")
+        );
+        assert_eq!(link.arguments.as_deref(), Some("/safe"));
+        assert_eq!(
+            link.working_directory.as_deref(),
+            Some(r"C:\Users\alice\Documents")
+        );
+        assert_eq!(link.drive_type.as_deref(), Some("fixed"));
+        assert_eq!(link.volume_serial.as_deref(), Some("1234-ABCD"));
+        assert_eq!(link.volume_label.as_deref(), Some("OS"));
+        assert_eq!(link.machine_id.as_deref(), Some("workstation7"));
+        assert_eq!(link.mac_address.as_deref(), Some("00:0c:29:ab:cd:ef"));
+        assert_eq!(link.target_size, 4096);
+        assert_eq!(
+            link.target_modified.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_network_target_and_random_droid() {
+        let mut fixture = sample_link();
+        fixture.local_base_path = "";
+        fixture.network_share = r"\\fileserver\finance";
+        fixture.path_suffix = "q3.xlsx";
+        fixture.file_droid[7] = 0x41;
+
+        let link = parse_shell_link(&lnk_file(&fixture)).unwrap();
+        assert_eq!(
+            link.target_path.as_deref(),
+            Some(r"\\fileserver\finance\q3.xlsx")
+        );
+        assert_eq!(link.network_share.as_deref(), Some(r"\\fileserver\finance"));
+        assert_eq!(link.volume_serial, None);
+        assert_eq!(link.mac_address, None);
+    }
+
+    #[test]
+    fn test_malformed_links_are_noted() {
+        let temp = TempDir::new().unwrap();
+        let good = temp.path().join("report.docx.lnk");
+        let truncated = temp.path().join("truncated.lnk");
+        let garbage = temp.path().join("garbage.lnk");
+        let data = lnk_file(&sample_link());
+        fs::write(&good, &data).unwrap();
+        fs::write(&truncated, &data[..HEADER_SIZE + 40]).unwrap();
+        fs::write(&garbage, b"not a shortcut").unwrap();
+
+        let report = parse_lnk_files(&[garbage, good.clone(), truncated]).unwrap();
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].source, good.to_string_lossy());
+        assert_eq!(report.notes.len(), 2);
+        assert!(report.notes[0].starts_with("Skipped garbage.lnk: Not a shell link"));
+        assert!(report.notes[1].starts_with("Skipped truncated.lnk:"));
+    }
+}
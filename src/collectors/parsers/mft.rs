@@ -0,0 +1,399 @@
+//! Minimal reader for a collected NTFS master file table (`$MFT`).
+//!
+//! The table is an array of fixed-size `FILE` records (1 KiB on almost every
+//! volume; the size is read from the first record). Before a record is read,
+//! the update sequence fixups are applied: the last two bytes of every
+//! 512-byte stride were replaced by a check value when the record was
+//! written, and the originals are kept in the update sequence array.
+//!
+//! ```text
+//! header: "FILE" | u16 usa offset @4 | u16 usa count @6 | u16 flags @22
+//!         | u32 used size @24 | u32 allocated size @28 | u64 base record @32
+//!         | u16 first attribute @20 | u32 record number @44 (XP and later)
+//! ```
+//!
+//! Only the resident `$STANDARD_INFORMATION` and `$FILE_NAME` attributes of
+//! base records are read. Attributes that spilled into extension records are
+//! not followed, so a file with very many hard links may have no name.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::bytes::{u16_at, u32_at, u64_at};
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+const RECORD_SIGNATURE: &[u8] = b"FILE";
+const DEFAULT_RECORD_SIZE: usize = 1024;
+/// Stride of the update sequence fixups
+const FIXUP_STRIDE: usize = 512;
+
+const FLAG_IN_USE: u16 = 0x1;
+const FLAG_DIRECTORY: u16 = 0x2;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_END: u32 = 0xffff_ffff;
+
+/// `$FILE_NAME` namespace holding the 8.3 alias
+const NAMESPACE_DOS: u8 = 2;
+
+/// Record number of the root directory
+pub const ROOT_RECORD: u64 = 5;
+
+/// Low 48 bits of a file reference are the record number
+const RECORD_NUMBER_MASK: u64 = 0x0000_ffff_ffff_ffff;
+
+/// Deepest directory nesting followed when building a path
+const MAX_PATH_DEPTH: usize = 256;
+
+/// The four timestamps NTFS keeps in `$STANDARD_INFORMATION` and in each
+/// `$FILE_NAME`, as raw FILETIME ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NtfsTimes {
+    pub created: u64,
+    pub modified: u64,
+    pub mft_modified: u64,
+    pub accessed: u64,
+}
+
+impl NtfsTimes {
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            created: u64_at(data, 0).ok()?,
+            modified: u64_at(data, 8).ok()?,
+            mft_modified: u64_at(data, 16).ok()?,
+            accessed: u64_at(data, 24).ok()?,
+        })
+    }
+
+    pub fn created_utc(&self) -> Option<DateTime<Utc>> {
+        filetime_ticks_to_utc(self.created)
+    }
+
+    /// Whether every timestamp is a whole second, as tools that set times
+    /// from a seconds-resolution value leave them
+    pub fn whole_seconds(&self) -> bool {
+        [
+            self.created,
+            self.modified,
+            self.mft_modified,
+            self.accessed,
+        ]
+        .iter()
+        .all(|ticks| ticks % 10_000_000 == 0)
+    }
+}
+
+/// A `$FILE_NAME` attribute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileName {
+    pub parent_record: u64,
+    pub name: String,
+    pub times: NtfsTimes,
+}
+
+/// A base `FILE` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MftRecord {
+    pub record_number: u64,
+    pub in_use: bool,
+    pub is_dir: bool,
+    pub standard_information: Option<NtfsTimes>,
+    /// Long (Win32 or POSIX) name, or the 8.3 name if that is all there is
+    pub file_name: Option<FileName>,
+}
+
+/// Restore the bytes the update sequence array stands in for
+fn apply_fixups(record: &mut [u8]) -> Result<()> {
+    let offset = u16_at(record, 4).map_err(|_| anyhow!("Truncated record header"))? as usize;
+    let count = u16_at(record, 6).map_err(|_| anyhow!("Truncated record header"))? as usize;
+    if count < 2 || offset + count * 2 > record.len() {
+        bail!("Invalid update sequence array");
+    }
+    let check = [record[offset], record[offset + 1]];
+    for i in 1..count {
+        let end = i * FIXUP_STRIDE;
+        if end > record.len() {
+            break;
+        }
+        if record[end - 2..end] != check {
+            bail!("Update sequence mismatch; the record was torn while written");
+        }
+        let original = [record[offset + i * 2], record[offset + i * 2 + 1]];
+        record[end - 2..end].copy_from_slice(&original);
+    }
+    Ok(())
+}
+
+fn parse_file_name(content: &[u8]) -> Option<(u8, FileName)> {
+    let parent_record = u64_at(content, 0).ok()? & RECORD_NUMBER_MASK;
+    let times = NtfsTimes::from_bytes(content.get(8..40)?)?;
+    let length = *content.get(64)? as usize;
+    let namespace = *content.get(65)?;
+    let units: Vec<u16> = content
+        .get(66..66 + length * 2)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Some((
+        namespace,
+        FileName {
+            parent_record,
+            name: String::from_utf16_lossy(&units),
+            times,
+        },
+    ))
+}
+
+/// Decode one record; `None` for unused slots and extension records
+pub fn parse_record(record: &mut [u8], index: u64) -> Result<Option<MftRecord>> {
+    if record.iter().all(|&b| b == 0) {
+        return Ok(None);
+    }
+    if !record.starts_with(RECORD_SIGNATURE) {
+        bail!("Record {} has no FILE signature", index);
+    }
+    apply_fixups(record).with_context(|| format!("Record {}", index))?;
+
+    let base = u64_at(record, 32).unwrap_or(0) & RECORD_NUMBER_MASK;
+    if base != 0 {
+        return Ok(None);
+    }
+    let flags = u16_at(record, 22).unwrap_or(0);
+    let usa_offset = u16_at(record, 4).unwrap_or(0);
+    let record_number = match u32_at(record, 44) {
+        Ok(number) if usa_offset >= 48 => number as u64,
+        _ => index,
+    };
+    let used = (u32_at(record, 24).unwrap_or(0) as usize).min(record.len());
+
+    let mut parsed = MftRecord {
+        record_number,
+        in_use: flags & FLAG_IN_USE != 0,
+        is_dir: flags & FLAG_DIRECTORY != 0,
+        standard_information: None,
+        file_name: None,
+    };
+    let mut name_namespace = None;
+
+    let mut offset = u16_at(record, 20).unwrap_or(0) as usize;
+    while offset + 16 <= used {
+        let attr_type = u32_at(record, offset).unwrap_or(ATTR_END);
+        let length = u32_at(record, offset + 4).unwrap_or(0) as usize;
+        if attr_type == ATTR_END || length < 16 || offset + length > used {
+            break;
+        }
+        let attribute = &record[offset..offset + length];
+        let resident = attribute[8] == 0;
+        let content = resident
+            .then(|| {
+                let size = u32_at(attribute, 16).ok()? as usize;
+                let start = u16_at(attribute, 20).ok()? as usize;
+                attribute.get(start..start + size)
+            })
+            .flatten();
+
+        match (attr_type, content) {
+            (ATTR_STANDARD_INFORMATION, Some(content)) => {
+                parsed.standard_information = NtfsTimes::from_bytes(content);
+            }
+            (ATTR_FILE_NAME, Some(content)) => {
+                if let Some((namespace, name)) = parse_file_name(content) {
+                    if name_namespace.is_none() || name_namespace == Some(NAMESPACE_DOS) {
+                        name_namespace = Some(namespace);
+                        parsed.file_name = Some(name);
+                    }
+                }
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Size of the records in a table starting with `first`
+fn record_size(first: &[u8]) -> usize {
+    match u32_at(first, 28) {
+        Ok(size) if size.is_power_of_two() && (512..=65536).contains(&size) => size as usize,
+        _ => DEFAULT_RECORD_SIZE,
+    }
+}
+
+/// Call `visit` for every base record in the `$MFT` copy at `path`; returns
+/// the number of records that could not be decoded
+pub fn for_each_record<F>(path: &Path, mut visit: F) -> Result<usize>
+where
+    F: FnMut(MftRecord),
+{
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    );
+    let mut first = vec![0u8; DEFAULT_RECORD_SIZE];
+    reader
+        .read_exact(&mut first)
+        .with_context(|| format!("{} is too short for an MFT", path.display()))?;
+    if !first.starts_with(RECORD_SIGNATURE) {
+        bail!("{} does not start with a FILE record", path.display());
+    }
+    let size = record_size(&first);
+    let mut buf = first;
+    buf.resize(size, 0);
+    if size > DEFAULT_RECORD_SIZE {
+        reader.read_exact(&mut buf[DEFAULT_RECORD_SIZE..])?;
+    }
+
+    let mut index = 0u64;
+    let mut skipped = 0usize;
+    loop {
+        match parse_record(&mut buf, index) {
+            Ok(Some(record)) => visit(record),
+            Ok(None) => {}
+            Err(_) => skipped += 1,
+        }
+        index += 1;
+        match reader.read_exact(&mut buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(skipped)
+}
+
+/// Directory names by record number, for turning a parent reference into a
+/// full path
+#[derive(Debug, Default)]
+pub struct DirectoryIndex {
+    directories: HashMap<u64, (u64, String)>,
+}
+
+impl DirectoryIndex {
+    /// Remember `record` if it is a named directory
+    pub fn add(&mut self, record: &MftRecord) {
+        if let (true, Some(name)) = (record.is_dir, &record.file_name) {
+            self.directories.insert(
+                record.record_number,
+                (name.parent_record, name.name.clone()),
+            );
+        }
+    }
+
+    /// `\`-separated path of `name` in directory `parent`; a directory that
+    /// is missing from the index is shown as `?`
+    pub fn path(&self, parent: u64, name: &str) -> String {
+        let mut parts = vec![name.to_string()];
+        let mut current = parent;
+        while current != ROOT_RECORD && parts.len() <= MAX_PATH_DEPTH {
+            match self.directories.get(&current) {
+                Some((next, dir)) if *next != current => {
+                    parts.push(dir.clone());
+                    current = *next;
+                }
+                _ => {
+                    parts.push("?".to_string());
+                    break;
+                }
+            }
+        }
+        parts.reverse();
+        format!(r"\{}", parts.join(r"\"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{mft_record, MftRecordFixture};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_record() {
+        let si = NtfsTimes {
+            created: 130_000_000_000_000_000,
+            modified: 133_000_000_000_000_001,
+            mft_modified: 133_000_000_000_000_002,
+            accessed: 133_000_000_000_000_003,
+        };
+        let fixture = MftRecordFixture {
+            record_number: 42,
+            standard_information: Some(si),
+            file_name: Some((ROOT_RECORD, "report.docx")),
+            ..Default::default()
+        };
+        let mut data = mft_record(&fixture);
+
+        let record = parse_record(&mut data, 42).unwrap().unwrap();
+        assert_eq!(record.record_number, 42);
+        assert!(record.in_use);
+        assert!(!record.is_dir);
+        assert_eq!(record.standard_information, Some(si));
+        let name = record.file_name.unwrap();
+        assert_eq!(name.name, "report.docx");
+        assert_eq!(name.parent_record, ROOT_RECORD);
+        assert_eq!(name.times, fixture.file_name_times);
+
+        // A torn write leaves a stale check value at the end of a stride
+        let mut torn = mft_record(&fixture);
+        torn[FIXUP_STRIDE - 1] ^= 0xff;
+        assert!(parse_record(&mut torn, 42).is_err());
+        assert!(parse_record(&mut [0u8; 1024], 7).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_for_each_record_builds_paths() {
+        let temp = TempDir::new().unwrap();
+        let records = [
+            MftRecordFixture {
+                record_number: 0,
+                file_name: Some((ROOT_RECORD, "$MFT")),
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 64,
+                directory: true,
+                file_name: Some((ROOT_RECORD, "Users")),
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 65,
+                directory: true,
+                file_name: Some((64, "bob")),
+                ..Default::default()
+            },
+            MftRecordFixture {
+                record_number: 66,
+                file_name: Some((65, "notes.txt")),
+                ..Default::default()
+            },
+        ];
+        let mut data: Vec<u8> = records.iter().flat_map(mft_record).collect();
+        data.extend_from_slice(b"BAAD");
+        data.resize(data.len() + 1020, 0);
+        let path = temp.path().join("MFT");
+        std::fs::write(&path, data).unwrap();
+
+        let mut index = DirectoryIndex::default();
+        let mut files = Vec::new();
+        let skipped = for_each_record(&path, |record| {
+            index.add(&record);
+            files.push(record);
+        })
+        .unwrap();
+
+        assert_eq!(skipped, 1);
+        assert_eq!(files.len(), 4);
+        let notes = files[3].file_name.as_ref().unwrap();
+        assert_eq!(
+            index.path(notes.parent_record, &notes.name),
+            r"\Users\bob\notes.txt"
+        );
+        assert_eq!(index.path(99, "lost.txt"), r"\?\lost.txt");
+    }
+}
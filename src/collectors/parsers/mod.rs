@@ -0,0 +1,557 @@
+//! Offline parsers for collected artifacts.
+//!
+//! Parsers run after collection against the copies in the output directory,
+//! never against the live source, and write their reports under
+//! [`PARSED_DIR`]. The exceptions are [`linux_persistence`], which also
+//! inspects the live files the collected configuration points to, and
+//! [`antiforensics`], which checks whether collected shell history files are
+//! links to `/dev/null`. A parser failure is logged and leaves the raw artifact in
+//! place; it never fails the collection. Parsers that report through
+//! [`ParsedReport`] still write their report on failure, with the error in
+//! its notes, so the report's absence is never the only signal.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::collectors::volatile::user_sessions;
+use crate::collectors::windows::{
+    event_correlation, event_log_gaps, execution_evidence, iis_logs, office_mru,
+    powershell_history, shellbags,
+};
+use crate::utils::timezone::host_timezone;
+
+/// Anti-forensics indicator checks
+pub mod antiforensics;
+
+/// Unix login, sudo and last-login history parser
+pub mod auth_unix;
+
+/// BITS job database parser
+pub mod bits;
+
+/// Minimal binary property list reader
+pub mod bplist;
+
+/// Bounds-checked integer readers shared by the binary parsers
+pub mod bytes;
+
+/// Background Task Management database parser
+pub mod btm;
+
+/// Cloud CLI, kubeconfig and Docker credential file parser
+pub mod cloud_creds;
+
+/// Minimal ESE database reader
+pub mod ese;
+
+/// Minimal Windows event log (EVTX) reader
+pub mod evtx;
+
+/// JumpList DestList and link stream parser
+pub mod jumplists;
+
+/// Linux persistence location analyzer
+pub mod linux_persistence;
+
+/// Shell link (LNK) parser
+pub mod lnk;
+
+/// Minimal NTFS master file table reader
+pub mod mft;
+
+/// Recycle Bin `$I` index file parser
+pub mod recycle_bin;
+
+/// Minimal offline registry hive reader
+pub mod regf;
+
+/// Scheduled task XML and TaskCache parser
+pub mod scheduled_tasks;
+
+/// SRUM database parser
+pub mod srum;
+
+/// WMI repository event consumer parser
+pub mod wmi;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+/// Directory under the output directory that receives parser reports
+pub const PARSED_DIR: &str = "parsed";
+
+/// A parser report with provenance and parsing notes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedReport<T> {
+    /// Collected files the report was built from
+    pub sources: Vec<String>,
+    pub notes: Vec<String>,
+    pub records: Vec<T>,
+}
+
+impl<T> ParsedReport<T> {
+    pub fn new(sources: &[PathBuf]) -> Self {
+        Self {
+            sources: sources
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            notes: Vec::new(),
+            records: Vec::new(),
+        }
+    }
+}
+
+/// Run `parse` and write its report as `file_name` under `output_dir`.
+///
+/// A parse error is recorded as a note in an otherwise empty report.
+fn run_parser<T, F>(
+    name: &str,
+    sources: &[PathBuf],
+    output_dir: &Path,
+    file_name: &str,
+    parse: F,
+) -> Result<PathBuf>
+where
+    T: Serialize,
+    F: FnOnce() -> Result<ParsedReport<T>>,
+{
+    info!("Parsing {} from {} collected file(s)", name, sources.len());
+    let report = parse().unwrap_or_else(|e| {
+        warn!("{} parsing failed, raw files kept: {:#}", name, e);
+        let mut report = ParsedReport::new(sources);
+        report.notes.push(format!(
+            "Parsing failed, only the raw files were collected: {:#}",
+            e
+        ));
+        report
+    });
+
+    fs::create_dir_all(output_dir).context(format!("Failed to create {}", output_dir.display()))?;
+    let path = output_dir.join(file_name);
+    let json = serde_json::to_string_pretty(&report)
+        .context(format!("Failed to serialize {} report", name))?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+    info!("{}: {} records parsed", name, report.records.len());
+    Ok(path)
+}
+
+/// Find a collected copy of `file_name` (case-insensitive) under `artifact_dir`
+pub fn find_collected_file(artifact_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let parsed_dir = artifact_dir.join(PARSED_DIR);
+    WalkDir::new(artifact_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path() != parsed_dir)
+        .filter_map(|e| e.ok())
+        .find(|e| {
+            e.file_type().is_file()
+                && e.file_name()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(file_name)
+        })
+        .map(|e| e.into_path())
+}
+
+/// Collected files below any directory named `dir_name` (case-insensitive)
+pub fn find_collected_files_under(artifact_dir: &Path, dir_name: &str) -> Vec<PathBuf> {
+    let parsed_dir = artifact_dir.join(PARSED_DIR);
+    WalkDir::new(artifact_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path() != parsed_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .strip_prefix(artifact_dir)
+                .map(|rel| {
+                    rel.parent().is_some_and(|parent| {
+                        parent.components().any(|c| {
+                            c.as_os_str()
+                                .to_string_lossy()
+                                .eq_ignore_ascii_case(dir_name)
+                        })
+                    })
+                })
+                .unwrap_or(false)
+        })
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Collected files for which `matches` returns true
+pub fn find_collected_files_matching<F>(artifact_dir: &Path, matches: F) -> Vec<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    let parsed_dir = artifact_dir.join(PARSED_DIR);
+    WalkDir::new(artifact_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path() != parsed_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && matches(e.path()))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Run every parser whose input artifact was collected; returns written reports
+pub fn parse_collected_artifacts(artifact_dir: &Path) -> Vec<PathBuf> {
+    let output_dir = artifact_dir.join(PARSED_DIR);
+    let mut written = Vec::new();
+
+    if let Some(srudb) = find_collected_file(artifact_dir, "SRUDB.dat") {
+        let software = find_collected_file(artifact_dir, "SOFTWARE");
+        info!("Parsing SRUM database {}", srudb.display());
+        match srum::write_srum_reports(&srudb, software.as_deref(), &output_dir) {
+            Ok(paths) => written.extend(paths),
+            Err(e) => warn!("SRUM parsing failed, raw database kept: {:#}", e),
+        }
+    }
+
+    let mut outputs: Vec<Result<PathBuf>> = Vec::new();
+
+    let bits_files: Vec<PathBuf> = bits::BITS_DATABASE_FILES
+        .iter()
+        .filter_map(|name| find_collected_file(artifact_dir, name))
+        .collect();
+    if !bits_files.is_empty() {
+        outputs.push(run_parser(
+            "BITS jobs",
+            &bits_files,
+            &output_dir,
+            bits::BITS_JOBS_FILE,
+            || bits::parse_bits(&bits_files),
+        ));
+    }
+
+    let task_files = find_collected_files_under(artifact_dir, scheduled_tasks::TASKS_DIR);
+    if !task_files.is_empty() {
+        let software = find_collected_file(artifact_dir, "SOFTWARE");
+        let mut sources = task_files.clone();
+        sources.extend(software.clone());
+        outputs.push(run_parser(
+            "Scheduled tasks",
+            &sources,
+            &output_dir,
+            scheduled_tasks::SCHEDULED_TASKS_FILE,
+            || scheduled_tasks::parse_scheduled_tasks(&task_files, software.as_deref()),
+        ));
+    }
+
+    let shellbag_hives: Vec<PathBuf> = shellbags::SHELLBAG_HIVES
+        .iter()
+        .filter_map(|name| find_collected_file(artifact_dir, name))
+        .collect();
+    if !shellbag_hives.is_empty() {
+        outputs.push(run_parser(
+            "ShellBags",
+            &shellbag_hives,
+            &output_dir,
+            shellbags::SHELLBAGS_FILE,
+            || shellbags::shellbags_report(&shellbag_hives),
+        ));
+    }
+
+    let ntuser_hives = find_collected_files_matching(artifact_dir, |path| {
+        path.file_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case(office_mru::OFFICE_MRU_HIVE))
+    });
+    if !ntuser_hives.is_empty() {
+        outputs.push(run_parser(
+            "Office MRU",
+            &ntuser_hives,
+            &output_dir,
+            office_mru::OFFICE_MRU_FILE,
+            || office_mru::office_mru_report(&ntuser_hives),
+        ));
+    }
+
+    let user_hives = find_collected_files_matching(artifact_dir, execution_evidence::is_user_hive);
+    let system_hive = find_collected_file(artifact_dir, "SYSTEM");
+    if !user_hives.is_empty() || system_hive.is_some() {
+        let software = find_collected_file(artifact_dir, "SOFTWARE");
+        let mut sources = user_hives.clone();
+        sources.extend(system_hive.clone());
+        sources.extend(software.clone());
+        outputs.push(run_parser(
+            "Execution evidence",
+            &sources,
+            &output_dir,
+            execution_evidence::EXECUTION_EVIDENCE_FILE,
+            || {
+                execution_evidence::parse_execution_evidence(
+                    &user_hives,
+                    system_hive.as_deref(),
+                    software.as_deref(),
+                )
+            },
+        ));
+    }
+
+    if let Some(objects) = find_collected_file(artifact_dir, wmi::OBJECTS_FILE) {
+        let sources = [objects];
+        outputs.push(run_parser(
+            "WMI persistence",
+            &sources,
+            &output_dir,
+            wmi::WMI_PERSISTENCE_FILE,
+            || wmi::parse_wmi_persistence(&sources[0]),
+        ));
+    }
+
+    let index_files = find_collected_files_matching(artifact_dir, recycle_bin::is_index_file);
+    if !index_files.is_empty() {
+        outputs.push(run_parser(
+            "Recycle Bin",
+            &index_files,
+            &output_dir,
+            recycle_bin::RECYCLE_BIN_FILE,
+            || recycle_bin::parse_recycle_bin(&index_files),
+        ));
+    }
+
+    let lnk_files = find_collected_files_matching(artifact_dir, lnk::is_lnk_file);
+    if !lnk_files.is_empty() {
+        outputs.push(run_parser(
+            "LNK files",
+            &lnk_files,
+            &output_dir,
+            lnk::LNK_REPORT_FILE,
+            || lnk::parse_lnk_files(&lnk_files),
+        ));
+    }
+
+    let jump_lists = find_collected_files_matching(artifact_dir, jumplists::is_jump_list);
+    if !jump_lists.is_empty() {
+        outputs.push(run_parser(
+            "JumpLists",
+            &jump_lists,
+            &output_dir,
+            jumplists::JUMPLISTS_FILE,
+            || jumplists::parse_jump_lists(&jump_lists),
+        ));
+    }
+
+    let history_files =
+        find_collected_files_matching(artifact_dir, powershell_history::is_history_file);
+    if !history_files.is_empty() {
+        let software = find_collected_file(artifact_dir, "SOFTWARE");
+        let mut sources = history_files.clone();
+        sources.extend(software.clone());
+        outputs.push(run_parser(
+            "PowerShell history",
+            &sources,
+            &output_dir,
+            powershell_history::POWERSHELL_HISTORY_REPORT_FILE,
+            || powershell_history::parse_powershell_history(&history_files, software.as_deref()),
+        ));
+    }
+
+    let btm_files = find_collected_files_matching(artifact_dir, btm::is_btm_file);
+    if !btm_files.is_empty() {
+        outputs.push(run_parser(
+            "BTM items",
+            &btm_files,
+            &output_dir,
+            btm::BTM_ITEMS_FILE,
+            || btm::parse_btm_files(&btm_files),
+        ));
+    }
+
+    let persistence_files =
+        find_collected_files_matching(artifact_dir, linux_persistence::is_persistence_file);
+    if !persistence_files.is_empty() && cfg!(target_os = "linux") {
+        let root = Path::new("/");
+        outputs.push(run_parser(
+            "Linux persistence",
+            &persistence_files,
+            &output_dir,
+            linux_persistence::PERSISTENCE_FINDINGS_FILE,
+            || linux_persistence::analyze_persistence(&persistence_files, root),
+        ));
+        outputs.push(run_parser(
+            "Persistence libraries",
+            &persistence_files,
+            &output_dir,
+            linux_persistence::PERSISTENCE_LIBRARIES_FILE,
+            || linux_persistence::hash_persistence_libraries(&persistence_files, root),
+        ));
+    }
+
+    let auth_sources = auth_unix::find_auth_sources(artifact_dir);
+    if !auth_sources.is_empty() {
+        let time = auth_unix::TimeContext {
+            utc_offset_minutes: host_timezone().utc_offset_minutes,
+            reference: Utc::now(),
+        };
+        outputs.push(run_parser(
+            "Authentication history",
+            &auth_sources.all(),
+            &output_dir,
+            auth_unix::AUTH_REPORT_FILE,
+            || auth_unix::parse_auth_sources(&auth_sources, time),
+        ));
+    }
+
+    let antiforensics_sources = antiforensics::find_sources(artifact_dir);
+    if !antiforensics_sources.is_empty() {
+        let options = antiforensics::options();
+        let time = auth_unix::TimeContext {
+            utc_offset_minutes: host_timezone().utc_offset_minutes,
+            reference: Utc::now(),
+        };
+        let live_root = cfg!(unix).then(|| Path::new("/"));
+        outputs.push(run_parser(
+            "Anti-forensics indicators",
+            &antiforensics_sources.all(),
+            &output_dir,
+            antiforensics::ANTIFORENSICS_FINDINGS_FILE,
+            || {
+                antiforensics::detect_antiforensics(
+                    &antiforensics_sources,
+                    &options,
+                    live_root,
+                    time,
+                )
+            },
+        ));
+    }
+
+    if let Some(security_log) = find_collected_file(artifact_dir, event_correlation::SECURITY_LOG) {
+        info!("Building process tree from {}", security_log.display());
+        outputs.push(event_correlation::write_process_tree(
+            &security_log,
+            &output_dir,
+        ));
+    }
+
+    let event_logs = find_collected_files_matching(artifact_dir, |path| {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("evtx"))
+    });
+    if !event_logs.is_empty() {
+        info!(
+            "Checking {} event log(s) for timeline gaps",
+            event_logs.len()
+        );
+        outputs.push(event_log_gaps::write_event_log_gaps(
+            &event_logs,
+            &output_dir,
+        ));
+    }
+
+    let session_logs: Vec<PathBuf> = [
+        event_correlation::SECURITY_LOG,
+        user_sessions::LOCAL_SESSION_MANAGER_LOG,
+    ]
+    .into_iter()
+    .filter_map(|name| find_collected_file(artifact_dir, name))
+    .collect();
+    if !session_logs.is_empty() {
+        info!(
+            "Reading RDP and console sessions from {} event log(s)",
+            session_logs.len()
+        );
+        outputs.push(user_sessions::write_event_log_sessions(
+            &artifact_dir.join("volatile"),
+            &session_logs,
+        ));
+    }
+
+    let iis_logs = iis_logs::find_iis_logs(artifact_dir);
+    if !iis_logs.is_empty() {
+        info!("Parsing {} collected IIS log(s)", iis_logs.len());
+        outputs.push(iis_logs::write_iis_events(&iis_logs, &output_dir));
+    }
+
+    for output in outputs {
+        match output {
+            Ok(path) => written.push(path),
+            Err(e) => warn!("Failed to write parser report: {:#}", e),
+        }
+    }
+
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_collected_file_skips_parsed_dir() {
+        let temp = TempDir::new().unwrap();
+        let parsed = temp.path().join(PARSED_DIR);
+        let fs_dir = temp.path().join("Windows-SRUM/fs/Windows/System32/sru");
+        std::fs::create_dir_all(&parsed).unwrap();
+        std::fs::create_dir_all(&fs_dir).unwrap();
+        std::fs::write(parsed.join("srudb.dat"), "").unwrap();
+        std::fs::write(fs_dir.join("SRUDB.dat"), "").unwrap();
+
+        assert_eq!(
+            find_collected_file(temp.path(), "srudb.DAT"),
+            Some(fs_dir.join("SRUDB.dat"))
+        );
+        assert_eq!(find_collected_file(temp.path(), "SOFTWARE"), None);
+    }
+
+    #[test]
+    fn test_unparseable_artifact_is_not_fatal() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("SRUDB.dat"), "not a database").unwrap();
+
+        assert!(parse_collected_artifacts(temp.path()).is_empty());
+        assert!(temp.path().join("SRUDB.dat").exists());
+    }
+
+    #[test]
+    fn test_parser_failure_is_noted_in_report() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("OBJECTS.DATA");
+
+        let path = run_parser::<String, _>(
+            "Test",
+            std::slice::from_ref(&source),
+            temp.path(),
+            "test.json",
+            || Err(anyhow::anyhow!("truncated file")),
+        )
+        .unwrap();
+
+        let report: ParsedReport<String> =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(report.sources, vec![source.to_string_lossy().to_string()]);
+        assert!(report.records.is_empty());
+        assert_eq!(
+            report.notes,
+            vec!["Parsing failed, only the raw files were collected: truncated file"]
+        );
+    }
+
+    #[test]
+    fn test_find_collected_files_under() {
+        let temp = TempDir::new().unwrap();
+        let tasks = temp
+            .path()
+            .join("Windows-ScheduledTasks/fs/Windows/System32/Tasks");
+        std::fs::create_dir_all(tasks.join("Microsoft")).unwrap();
+        std::fs::write(tasks.join("Updater"), "").unwrap();
+        std::fs::write(tasks.join("Microsoft/Defrag"), "").unwrap();
+        std::fs::write(temp.path().join("Tasks"), "").unwrap();
+
+        assert_eq!(
+            find_collected_files_under(temp.path(), "tasks"),
+            vec![tasks.join("Microsoft/Defrag"), tasks.join("Updater")]
+        );
+    }
+}
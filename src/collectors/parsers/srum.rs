@@ -0,0 +1,631 @@
+//! SRUM (System Resource Usage Monitor) database parser.
+//!
+//! Reads a collected copy of `SRUDB.dat` and writes two reports:
+//!
+//! - `srum_network_usage.json`: bytes sent/received per application, user
+//!   and interface, from the Network Data Usage table
+//! - `srum_app_timeline.json`: per-application CPU time and I/O, from the
+//!   Application Resource Usage table
+//!
+//! Application and user identifiers are resolved through `SruDbIdMapTable`.
+//! The extension names for the table GUIDs come from the collected SOFTWARE
+//! hive (`Microsoft\Windows NT\CurrentVersion\SRUM\Extensions`) when present.
+//!
+//! SRUDB.dat copied from a live system is usually in the dirty shutdown
+//! state because its transaction logs have not been replayed. That is
+//! recorded in each report and parsing continues on a best-effort basis.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::ese::{self, EseDatabase, EseValue, Row};
+use super::regf::Hive;
+
+/// Report file names
+pub const NETWORK_USAGE_FILE: &str = "srum_network_usage.json";
+pub const APP_TIMELINE_FILE: &str = "srum_app_timeline.json";
+
+/// Table GUIDs of the SRUM extensions we parse
+pub const NETWORK_USAGE_TABLE: &str = "{973F5D5C-1D90-4944-BE8E-24B94231A174}";
+pub const APP_RESOURCE_USAGE_TABLE: &str = "{D10CA2FE-6FCF-4F6D-848E-B2E99266FA89}";
+const ID_MAP_TABLE: &str = "SruDbIdMapTable";
+
+/// SOFTWARE hive key listing the registered SRUM extensions
+const EXTENSIONS_KEY: &str = r"Microsoft\Windows NT\CurrentVersion\SRUM\Extensions";
+
+/// `SruDbIdMapTable.IdType` value for user SIDs
+const ID_TYPE_SID: i64 = 3;
+
+/// A parsed SRUM table with provenance and parsing notes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SrumReport<T> {
+    pub source: String,
+    /// ESE shutdown state of the database ("clean_shutdown", "dirty_shutdown", ...)
+    pub database_state: String,
+    pub dirty: bool,
+    /// SRUM extension table GUID
+    pub table: String,
+    /// Extension name registered in the SOFTWARE hive, if resolved
+    pub extension: Option<String>,
+    pub notes: Vec<String>,
+    pub records: Vec<T>,
+}
+
+/// One row of the Network Data Usage table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkUsageRecord {
+    pub timestamp: Option<String>,
+    pub app_id: Option<i64>,
+    pub app: Option<String>,
+    pub user_id: Option<i64>,
+    pub user: Option<String>,
+    pub interface_luid: Option<i64>,
+    pub l2_profile_id: Option<i64>,
+    pub bytes_sent: Option<i64>,
+    pub bytes_received: Option<i64>,
+}
+
+/// One row of the Application Resource Usage table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppUsageRecord {
+    pub timestamp: Option<String>,
+    pub app_id: Option<i64>,
+    pub app: Option<String>,
+    pub user_id: Option<i64>,
+    pub user: Option<String>,
+    pub foreground_cycle_time: Option<i64>,
+    pub background_cycle_time: Option<i64>,
+    pub face_time: Option<i64>,
+    pub foreground_bytes_read: Option<i64>,
+    pub foreground_bytes_written: Option<i64>,
+    pub background_bytes_read: Option<i64>,
+    pub background_bytes_written: Option<i64>,
+}
+
+/// Render a binary SID as S-1-...
+pub fn format_sid(data: &[u8]) -> Option<String> {
+    if data.len() < 8 {
+        return None;
+    }
+    let revision = data[0];
+    let count = data[1] as usize;
+    if data.len() < 8 + count * 4 {
+        return None;
+    }
+    let authority = data[2..8]
+        .iter()
+        .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+
+    let mut sid = format!("S-{}-{}", revision, authority);
+    for i in 0..count {
+        let start = 8 + i * 4;
+        let sub = u32::from_le_bytes(data[start..start + 4].try_into().ok()?);
+        sid.push_str(&format!("-{}", sub));
+    }
+    Some(sid)
+}
+
+fn utf16_string(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// Build the IdIndex -> name map from SruDbIdMapTable rows
+fn resolve_id_map(rows: &[Row]) -> (HashMap<i64, String>, usize) {
+    let mut map = HashMap::new();
+    let mut unresolved = 0;
+
+    for row in rows {
+        let index = match row.get("IdIndex").and_then(EseValue::as_i64) {
+            Some(index) => index,
+            None => continue,
+        };
+        let id_type = row.get("IdType").and_then(EseValue::as_i64);
+        let name = match row.get("IdBlob") {
+            Some(EseValue::Text(text)) => Some(text.clone()),
+            Some(EseValue::Binary(blob)) if id_type == Some(ID_TYPE_SID) => format_sid(blob),
+            Some(EseValue::Binary(blob)) => Some(utf16_string(blob)),
+            _ => None,
+        };
+        match name {
+            Some(name) => {
+                map.insert(index, name);
+            }
+            None => unresolved += 1,
+        }
+    }
+
+    (map, unresolved)
+}
+
+/// Extension table GUID -> registered name, from the SOFTWARE hive
+pub fn read_extension_names(software_hive: &Path) -> Result<HashMap<String, String>> {
+    let hive = Hive::open(software_hive)?;
+    let mut names = HashMap::new();
+
+    let key = match hive.open_key(EXTENSIONS_KEY)? {
+        Some(key) => key,
+        None => return Ok(names),
+    };
+    for extension in key.subkeys()? {
+        let name = extension
+            .value("")?
+            .and_then(|v| v.as_string())
+            .filter(|s| !s.is_empty())
+            .or_else(|| {
+                extension
+                    .value("DllName")
+                    .ok()
+                    .flatten()
+                    .and_then(|v| v.as_string())
+            });
+        if let Some(name) = name {
+            names.insert(extension.name().to_uppercase(), name);
+        }
+    }
+    Ok(names)
+}
+
+fn int(row: &Row, column: &str) -> Option<i64> {
+    row.get(column).and_then(EseValue::as_i64)
+}
+
+fn timestamp(row: &Row) -> Option<String> {
+    match row.get("TimeStamp") {
+        Some(EseValue::DateTime(days)) => ese::ole_date_to_rfc3339(*days),
+        _ => None,
+    }
+}
+
+fn lookup(ids: &HashMap<i64, String>, id: Option<i64>) -> Option<String> {
+    id.and_then(|id| ids.get(&id).cloned())
+}
+
+fn network_record(row: &Row, ids: &HashMap<i64, String>) -> NetworkUsageRecord {
+    let app_id = int(row, "AppId");
+    let user_id = int(row, "UserId");
+    NetworkUsageRecord {
+        timestamp: timestamp(row),
+        app_id,
+        app: lookup(ids, app_id),
+        user_id,
+        user: lookup(ids, user_id),
+        interface_luid: int(row, "InterfaceLuid"),
+        l2_profile_id: int(row, "L2ProfileId"),
+        bytes_sent: int(row, "BytesSent"),
+        bytes_received: int(row, "BytesRecvd"),
+    }
+}
+
+fn app_record(row: &Row, ids: &HashMap<i64, String>) -> AppUsageRecord {
+    let app_id = int(row, "AppId");
+    let user_id = int(row, "UserId");
+    AppUsageRecord {
+        timestamp: timestamp(row),
+        app_id,
+        app: lookup(ids, app_id),
+        user_id,
+        user: lookup(ids, user_id),
+        foreground_cycle_time: int(row, "ForegroundCycleTime"),
+        background_cycle_time: int(row, "BackgroundCycleTime"),
+        face_time: int(row, "FaceTime"),
+        foreground_bytes_read: int(row, "ForegroundBytesRead"),
+        foreground_bytes_written: int(row, "ForegroundBytesWritten"),
+        background_bytes_read: int(row, "BackgroundBytesRead"),
+        background_bytes_written: int(row, "BackgroundBytesWritten"),
+    }
+}
+
+fn empty_report<T>(
+    source: &Path,
+    header: &ese::EseHeader,
+    table: &str,
+    extensions: &HashMap<String, String>,
+    notes: &[String],
+) -> SrumReport<T> {
+    SrumReport {
+        source: source.to_string_lossy().to_string(),
+        database_state: header.state.as_str().to_string(),
+        dirty: header.state.is_dirty(),
+        table: table.to_string(),
+        extension: extensions.get(table).cloned(),
+        notes: notes.to_vec(),
+        records: Vec::new(),
+    }
+}
+
+/// Records that can be ordered on the timeline
+trait Timestamped {
+    fn timestamp(&self) -> Option<String>;
+}
+
+impl Timestamped for NetworkUsageRecord {
+    fn timestamp(&self) -> Option<String> {
+        self.timestamp.clone()
+    }
+}
+
+impl Timestamped for AppUsageRecord {
+    fn timestamp(&self) -> Option<String> {
+        self.timestamp.clone()
+    }
+}
+
+/// Read the report's table, sorted by timestamp, recording any failure as a note
+fn read_report<T: Timestamped>(
+    db: &EseDatabase,
+    mut report: SrumReport<T>,
+    convert: impl Fn(&Row) -> T,
+) -> SrumReport<T> {
+    match db.read_table(&report.table) {
+        Ok(scan) => {
+            if scan.skipped_pages > 0 {
+                report.notes.push(format!(
+                    "{} unreadable pages skipped in {}",
+                    scan.skipped_pages, report.table
+                ));
+            }
+            report.records = scan.rows.iter().map(convert).collect();
+            report.records.sort_by_key(Timestamped::timestamp);
+        }
+        Err(e) => report.notes.push(format!("{:#}", e)),
+    }
+    report
+}
+
+/// Parse a collected SRUDB.dat, resolving extension names via `software_hive`
+pub fn parse_srum(
+    srudb: &Path,
+    software_hive: Option<&Path>,
+) -> Result<(SrumReport<NetworkUsageRecord>, SrumReport<AppUsageRecord>)> {
+    let header = ese::read_header(srudb)?;
+    let mut notes = Vec::new();
+
+    if header.state.is_dirty() {
+        warn!(
+            "{} is in {} state; parsing without log replay",
+            srudb.display(),
+            header.state.as_str()
+        );
+        notes.push(format!(
+            "Database state is {}: transaction logs were not replayed, recent records may be missing",
+            header.state.as_str()
+        ));
+    }
+
+    let extensions = match software_hive {
+        Some(hive) => read_extension_names(hive).unwrap_or_else(|e| {
+            notes.push(format!("SRUM extension names unresolved: {:#}", e));
+            HashMap::new()
+        }),
+        None => {
+            notes.push("SOFTWARE hive not collected; SRUM extension names unresolved".to_string());
+            HashMap::new()
+        }
+    };
+
+    let db = match EseDatabase::open(srudb) {
+        Ok(db) => Some(db),
+        Err(e) => {
+            // Typically a damaged dirty database; report rather than fail
+            warn!("Failed to read {}: {:#}", srudb.display(), e);
+            notes.push(format!("Database could not be read: {:#}", e));
+            None
+        }
+    };
+
+    let mut ids = HashMap::new();
+    if let Some(db) = &db {
+        match db.read_table(ID_MAP_TABLE) {
+            Ok(scan) => {
+                let (resolved, unresolved) = resolve_id_map(&scan.rows);
+                ids = resolved;
+                if unresolved > 0 {
+                    notes.push(format!(
+                        "{} identifiers stored as long values or compressed were not resolved",
+                        unresolved
+                    ));
+                }
+            }
+            Err(e) => notes.push(format!("Identifiers unresolved: {:#}", e)),
+        }
+    }
+
+    let mut network = empty_report(srudb, &header, NETWORK_USAGE_TABLE, &extensions, &notes);
+    let mut apps = empty_report(
+        srudb,
+        &header,
+        APP_RESOURCE_USAGE_TABLE,
+        &extensions,
+        &notes,
+    );
+    if let Some(db) = &db {
+        network = read_report(db, network, |row| network_record(row, &ids));
+        apps = read_report(db, apps, |row| app_record(row, &ids));
+    }
+
+    Ok((network, apps))
+}
+
+/// Parse `srudb` and write both reports into `output_dir`
+pub fn write_srum_reports(
+    srudb: &Path,
+    software_hive: Option<&Path>,
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let (network, apps) = parse_srum(srudb, software_hive)?;
+    fs::create_dir_all(output_dir).context(format!("Failed to create {}", output_dir.display()))?;
+
+    let network_path = output_dir.join(NETWORK_USAGE_FILE);
+    fs::write(
+        &network_path,
+        serde_json::to_string_pretty(&network).context("Failed to serialize SRUM network usage")?,
+    )
+    .context(format!("Failed to write {}", network_path.display()))?;
+
+    let apps_path = output_dir.join(APP_TIMELINE_FILE);
+    fs::write(
+        &apps_path,
+        serde_json::to_string_pretty(&apps).context("Failed to serialize SRUM app timeline")?,
+    )
+    .context(format!("Failed to write {}", apps_path.display()))?;
+
+    info!(
+        "SRUM: {} network usage and {} application records parsed",
+        network.records.len(),
+        apps.records.len()
+    );
+    Ok(vec![network_path, apps_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::ese::coltyp;
+    use crate::collectors::parsers::fixtures::{
+        ese_record, fixed_i32, fixed_i64, fixed_ole_date, EseBuilder, FixtureColumn, HiveBuilder,
+        HiveKey,
+    };
+    use tempfile::TempDir;
+
+    const USER_SID: [u8; 28] = [
+        1, 5, 0, 0, 0, 0, 0, 5, 21, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0xe9, 0x03, 0, 0,
+    ];
+
+    fn id_map_row(id_type: u8, index: i32, blob: Vec<u8>) -> Vec<u8> {
+        ese_record(&[vec![id_type], fixed_i32(index)], &[], &[(256, blob)])
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn sample_srudb(builder: &mut EseBuilder) {
+        builder.add_table(
+            ID_MAP_TABLE,
+            &[
+                FixtureColumn::new(1, "IdType", coltyp::UNSIGNED_BYTE),
+                FixtureColumn::new(2, "IdIndex", coltyp::LONG),
+                FixtureColumn::new(256, "IdBlob", coltyp::LONG_BINARY),
+            ],
+            &[
+                id_map_row(0, 1, utf16(r"\Device\HarddiskVolume3\Tools\rclone.exe")),
+                id_map_row(3, 2, USER_SID.to_vec()),
+            ],
+        );
+
+        let common = [
+            FixtureColumn::new(1, "AutoIncId", coltyp::LONG),
+            FixtureColumn::new(2, "TimeStamp", coltyp::DATE_TIME),
+            FixtureColumn::new(3, "AppId", coltyp::LONG),
+            FixtureColumn::new(4, "UserId", coltyp::LONG),
+        ];
+
+        let mut network_columns = common.to_vec();
+        network_columns.extend([
+            FixtureColumn::new(5, "InterfaceLuid", coltyp::LONG_LONG),
+            FixtureColumn::new(6, "L2ProfileId", coltyp::LONG),
+            FixtureColumn::new(7, "BytesSent", coltyp::LONG_LONG),
+            FixtureColumn::new(8, "BytesRecvd", coltyp::LONG_LONG),
+        ]);
+        let network_row = |id: i32, days: f64, sent: i64| {
+            ese_record(
+                &[
+                    fixed_i32(id),
+                    fixed_ole_date(days),
+                    fixed_i32(1),
+                    fixed_i32(2),
+                    fixed_i64(1_689_399_419_994_112),
+                    fixed_i32(0),
+                    fixed_i64(sent),
+                    fixed_i64(4096),
+                ],
+                &[],
+                &[],
+            )
+        };
+        builder.add_table(
+            NETWORK_USAGE_TABLE,
+            &network_columns,
+            &[
+                network_row(2, 45292.75, 9_000_000_000),
+                network_row(1, 45292.5, 1024),
+            ],
+        );
+
+        let mut app_columns = common.to_vec();
+        app_columns.extend([
+            FixtureColumn::new(5, "ForegroundCycleTime", coltyp::LONG_LONG),
+            FixtureColumn::new(6, "BackgroundCycleTime", coltyp::LONG_LONG),
+            FixtureColumn::new(7, "FaceTime", coltyp::LONG_LONG),
+            FixtureColumn::new(8, "ForegroundBytesRead", coltyp::LONG_LONG),
+            FixtureColumn::new(9, "ForegroundBytesWritten", coltyp::LONG_LONG),
+        ]);
+        builder.add_table(
+            APP_RESOURCE_USAGE_TABLE,
+            &app_columns,
+            &[ese_record(
+                &[
+                    fixed_i32(1),
+                    fixed_ole_date(45292.5),
+                    fixed_i32(1),
+                    fixed_i32(2),
+                    fixed_i64(500),
+                    fixed_i64(700),
+                    fixed_i64(0),
+                    fixed_i64(123_456),
+                    fixed_i64(654_321),
+                ],
+                &[],
+                &[],
+            )],
+        );
+    }
+
+    fn sample_software_hive(path: &Path) {
+        let mut root = HiveKey::new("ROOT");
+        let extensions = root
+            .subkey("Microsoft")
+            .subkey("Windows NT")
+            .subkey("CurrentVersion")
+            .subkey("SRUM")
+            .subkey("Extensions");
+        extensions
+            .subkey(NETWORK_USAGE_TABLE)
+            .string_value("", "Network Data Usage Monitor");
+        extensions
+            .subkey(APP_RESOURCE_USAGE_TABLE)
+            .string_value("DllName", r"%SystemRoot%\System32\appsrv.dll");
+        fs::write(path, HiveBuilder::build(&root)).unwrap();
+    }
+
+    #[test]
+    fn test_format_sid() {
+        assert_eq!(format_sid(&USER_SID).unwrap(), "S-1-5-21-1-2-3-1001");
+        assert_eq!(format_sid(&[1, 5, 0]), None);
+    }
+
+    #[test]
+    fn test_parse_sample_database() {
+        let temp = TempDir::new().unwrap();
+        let srudb = temp.path().join("SRUDB.dat");
+        let software = temp.path().join("SOFTWARE");
+
+        let mut builder = EseBuilder::new();
+        sample_srudb(&mut builder);
+        builder.write(&srudb);
+        sample_software_hive(&software);
+
+        let (network, apps) = parse_srum(&srudb, Some(&software)).unwrap();
+
+        assert!(!network.dirty);
+        assert_eq!(network.database_state, "clean_shutdown");
+        assert_eq!(
+            network.extension.as_deref(),
+            Some("Network Data Usage Monitor")
+        );
+        assert!(network.notes.is_empty(), "{:?}", network.notes);
+
+        // Sorted by timestamp
+        assert_eq!(network.records.len(), 2);
+        let first = &network.records[0];
+        assert_eq!(
+            first.timestamp.as_deref(),
+            Some("2024-01-01T12:00:00+00:00")
+        );
+        assert_eq!(
+            first.app.as_deref(),
+            Some(r"\Device\HarddiskVolume3\Tools\rclone.exe")
+        );
+        assert_eq!(first.user.as_deref(), Some("S-1-5-21-1-2-3-1001"));
+        assert_eq!(first.bytes_sent, Some(1024));
+        assert_eq!(network.records[1].bytes_sent, Some(9_000_000_000));
+        assert_eq!(network.records[1].bytes_received, Some(4096));
+
+        assert_eq!(
+            apps.extension.as_deref(),
+            Some(r"%SystemRoot%\System32\appsrv.dll")
+        );
+        assert_eq!(apps.records.len(), 1);
+        assert_eq!(apps.records[0].foreground_bytes_written, Some(654_321));
+        assert_eq!(apps.records[0].background_bytes_read, None);
+    }
+
+    #[test]
+    fn test_dirty_database_is_reported() {
+        let temp = TempDir::new().unwrap();
+        let srudb = temp.path().join("SRUDB.dat");
+
+        let mut builder = EseBuilder::new();
+        sample_srudb(builder.dirty());
+        builder.write(&srudb);
+
+        let (network, apps) = parse_srum(&srudb, None).unwrap();
+        assert!(network.dirty);
+        assert_eq!(network.database_state, "dirty_shutdown");
+        assert!(network.notes.iter().any(|n| n.contains("dirty_shutdown")));
+        assert!(network.notes.iter().any(|n| n.contains("SOFTWARE hive")));
+        // Records are still parsed best-effort
+        assert_eq!(network.records.len(), 2);
+        assert_eq!(apps.records.len(), 1);
+    }
+
+    #[test]
+    fn test_damaged_dirty_database_does_not_fail() {
+        let temp = TempDir::new().unwrap();
+        let srudb = temp.path().join("SRUDB.dat");
+
+        let mut builder = EseBuilder::new();
+        sample_srudb(builder.dirty());
+        let mut bytes = builder.build();
+        // Wipe the catalog page
+        bytes.truncate(5 * 4096);
+        fs::write(&srudb, bytes).unwrap();
+
+        let (network, _) = parse_srum(&srudb, None).unwrap();
+        assert!(network.records.is_empty());
+        assert!(network
+            .notes
+            .iter()
+            .any(|n| n.contains("could not be read")));
+    }
+
+    #[test]
+    fn test_write_reports() {
+        let temp = TempDir::new().unwrap();
+        let srudb = temp.path().join("SRUDB.dat");
+        let mut builder = EseBuilder::new();
+        sample_srudb(&mut builder);
+        builder.write(&srudb);
+
+        let out = temp.path().join("parsed");
+        let written = write_srum_reports(&srudb, None, &out).unwrap();
+        assert_eq!(
+            written,
+            vec![out.join(NETWORK_USAGE_FILE), out.join(APP_TIMELINE_FILE)]
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&written[0]).unwrap()).unwrap();
+        assert_eq!(json["records"].as_array().unwrap().len(), 2);
+        assert_eq!(json["table"], NETWORK_USAGE_TABLE);
+    }
+
+    #[test]
+    fn test_rejects_non_ese_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("SRUDB.dat");
+        fs::write(&path, vec![0u8; 8192]).unwrap();
+        assert!(parse_srum(&path, None).is_err());
+    }
+}
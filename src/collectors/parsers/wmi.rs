@@ -0,0 +1,263 @@
+//! WMI repository parser for permanent event subscriptions.
+//!
+//! A permanent WMI event subscription is a common fileless persistence
+//! mechanism: an `__EventFilter` (a WQL query), an event consumer
+//! (`CommandLineEventConsumer`, `ActiveScriptEventConsumer`, ...) and a
+//! `__FilterToConsumerBinding` tying the two together.
+//!
+//! The CIM repository is not parsed structurally. Instead `OBJECTS.DATA`
+//! is scanned for the class signatures of those objects: each binding
+//! references its consumer and filter by name, and the filter query and the
+//! consumer's properties (command line, script text, ...) are carved as
+//! strings stored next to the filter or consumer name. Only ASCII-encoded
+//! instances are found, and instances on free pages that `INDEX.BTR` no
+//! longer references are reported too, which can expose removed
+//! subscriptions.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::ParsedReport;
+
+/// Report file name
+pub const WMI_PERSISTENCE_FILE: &str = "wmi_persistence.json";
+
+/// CIM repository file holding class instances
+pub const OBJECTS_FILE: &str = "OBJECTS.DATA";
+
+/// How far around a name the related strings are searched for
+const SEARCH_WINDOW: usize = 0x2000;
+
+/// Shortest carved string kept as consumer detail
+const MIN_DETAIL_LENGTH: usize = 4;
+
+lazy_static! {
+    static ref BINDING: Regex = Regex::new(
+        r#"(?s-u)([A-Za-z_]*EventConsumer)\.Name="([^"\x00]{1,256})".{0,256}?__EventFilter\.Name="([^"\x00]{1,256})""#
+    )
+    .expect("binding pattern is valid");
+    static ref QUERY: Regex =
+        Regex::new(r"(?i-u)select [\x20-\x7e]{1,4096}?\x00").expect("query pattern is valid");
+    static ref PRINTABLE: Regex =
+        Regex::new(r"(?-u)[\x09\x0a\x0d\x20-\x7e]+\x00").expect("string pattern is valid");
+}
+
+/// A filter-to-consumer binding with the filter and consumer it references
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WmiSubscription {
+    pub filter_name: String,
+    /// WQL query of the filter, if found next to its name
+    pub filter_query: Option<String>,
+    /// Consumer class, e.g. `CommandLineEventConsumer`
+    pub consumer_class: String,
+    pub consumer_name: String,
+    /// Strings stored with the consumer (command line, executable, script)
+    pub consumer_details: Vec<String>,
+    /// Offset of the binding in `OBJECTS.DATA`
+    pub offset: u64,
+}
+
+/// Offsets of `name` stored as a NUL-terminated string
+fn name_offsets(data: &[u8], name: &str) -> Vec<usize> {
+    let mut needle = name.as_bytes().to_vec();
+    needle.push(0);
+    data.windows(needle.len())
+        .enumerate()
+        .filter(|(i, window)| *window == needle.as_slice() && (*i == 0 || data[i - 1] == 0))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(bytes)).to_string()
+}
+
+/// The filter query stored closest to the filter name
+fn filter_query(data: &[u8], name: &str) -> Option<String> {
+    name_offsets(data, name)
+        .into_iter()
+        .filter_map(|offset| {
+            let start = offset.saturating_sub(SEARCH_WINDOW);
+            let end = (offset + SEARCH_WINDOW).min(data.len());
+            QUERY
+                .find_iter(&data[start..end])
+                .map(|m| ((start + m.start()).abs_diff(offset), text(m.as_bytes())))
+                .min_by_key(|(distance, _)| *distance)
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, query)| query)
+}
+
+/// Strings stored after the consumer name, up to the next object signature
+fn consumer_details(data: &[u8], class: &str, name: &str) -> Vec<String> {
+    let mut details = Vec::new();
+    for offset in name_offsets(data, name) {
+        let start = offset + name.len() + 1;
+        let end = (start + SEARCH_WINDOW).min(data.len());
+        for m in PRINTABLE.find_iter(&data[start..end]) {
+            let value = text(m.as_bytes());
+            if value.contains("EventConsumer")
+                || value.contains("__EventFilter")
+                || value.contains("__FilterToConsumerBinding")
+            {
+                break;
+            }
+            if value.trim().len() >= MIN_DETAIL_LENGTH
+                && value != class
+                && !details.contains(&value)
+            {
+                details.push(value);
+            }
+        }
+        if !details.is_empty() {
+            break;
+        }
+    }
+    details
+}
+
+/// Extract event subscriptions from the contents of `OBJECTS.DATA`
+pub fn carve_subscriptions(data: &[u8]) -> Vec<WmiSubscription> {
+    let mut seen = HashSet::new();
+    let mut subscriptions = Vec::new();
+
+    for captures in BINDING.captures_iter(data) {
+        let consumer_class = text(&captures[1]);
+        let consumer_name = text(&captures[2]);
+        let filter_name = text(&captures[3]);
+        if !seen.insert((
+            consumer_class.clone(),
+            consumer_name.clone(),
+            filter_name.clone(),
+        )) {
+            continue;
+        }
+
+        subscriptions.push(WmiSubscription {
+            filter_query: filter_query(data, &filter_name),
+            consumer_details: consumer_details(data, &consumer_class, &consumer_name),
+            filter_name,
+            consumer_class,
+            consumer_name,
+            offset: captures.get(0).map(|m| m.start() as u64).unwrap_or(0),
+        });
+    }
+    subscriptions
+}
+
+/// Parse event subscriptions from a collected `OBJECTS.DATA`
+pub fn parse_wmi_persistence(objects: &Path) -> Result<ParsedReport<WmiSubscription>> {
+    let data = fs::read(objects).context(format!("Failed to read {}", objects.display()))?;
+
+    let mut report = ParsedReport::new(&[objects.to_path_buf()]);
+    report.records = carve_subscriptions(&data);
+    if report
+        .records
+        .iter()
+        .any(|s| s.filter_query.is_none() || s.consumer_details.is_empty())
+    {
+        report.notes.push(
+            "Some filter queries or consumer properties were not found next to their names"
+                .to_string(),
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Simulated OBJECTS.DATA page with a filter, consumer and binding
+    fn sample_objects() -> Vec<u8> {
+        let mut data = vec![0u8; 512];
+        let mut push = |parts: &[&str]| {
+            for part in parts {
+                data.extend_from_slice(part.as_bytes());
+                data.push(0);
+            }
+            data.extend([0xFFu8, 0x01, 0x00, 0x00]);
+        };
+
+        push(&[
+            "__EventFilter",
+            "root\\cimv2",
+            "EvilFilter",
+            "SELECT * FROM __InstanceModificationEvent WITHIN 60 WHERE TargetInstance ISA 'Win32_PerfFormattedData_PerfOS_System'",
+            "WQL",
+        ]);
+        push(&[
+            "CommandLineEventConsumer",
+            "EvilConsumer",
+            "C:\\Windows\\System32\\cmd.exe /c powershell -enc AAAA",
+            "C:\\Windows\\System32",
+        ]);
+        push(&[
+            "__FilterToConsumerBinding",
+            "CommandLineEventConsumer.Name=\"EvilConsumer\"",
+            "__EventFilter.Name=\"EvilFilter\"",
+        ]);
+        // The same binding again on another page
+        push(&[
+            "__FilterToConsumerBinding",
+            "CommandLineEventConsumer.Name=\"EvilConsumer\"",
+            "__EventFilter.Name=\"EvilFilter\"",
+        ]);
+        data
+    }
+
+    #[test]
+    fn test_carve_subscription() {
+        let subscriptions = carve_subscriptions(&sample_objects());
+        assert_eq!(subscriptions.len(), 1);
+
+        let sub = &subscriptions[0];
+        assert_eq!(sub.filter_name, "EvilFilter");
+        assert_eq!(sub.consumer_class, "CommandLineEventConsumer");
+        assert_eq!(sub.consumer_name, "EvilConsumer");
+        assert!(sub
+            .filter_query
+            .as_deref()
+            .unwrap()
+            .starts_with("SELECT * FROM __InstanceModificationEvent"));
+        assert_eq!(
+            sub.consumer_details,
+            vec![
+                "C:\\Windows\\System32\\cmd.exe /c powershell -enc AAAA",
+                "C:\\Windows\\System32"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_subscriptions() {
+        assert!(carve_subscriptions(&[0u8; 4096]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_notes_incomplete_subscriptions() {
+        let temp = TempDir::new().unwrap();
+        let objects = temp.path().join(OBJECTS_FILE);
+        fs::write(
+            &objects,
+            b"\x00ActiveScriptEventConsumer.Name=\"Orphan\"\x00__EventFilter.Name=\"Gone\"\x00",
+        )
+        .unwrap();
+
+        let report = parse_wmi_persistence(&objects).unwrap();
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(
+            report.records[0].consumer_class,
+            "ActiveScriptEventConsumer"
+        );
+        assert_eq!(report.records[0].filter_query, None);
+        assert_eq!(report.notes.len(), 1);
+    }
+}
@@ -8,6 +8,8 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::errors::CollectorError;
+
 /// Tracks artifacts that failed due to permission errors
 #[derive(Debug, Clone, Default)]
 pub struct PermissionTracker {
@@ -36,6 +38,18 @@ impl PermissionTracker {
             || error_msg.contains("elevated privileges")
     }
 
+    /// Check if an error, or any error it wraps, is a permission problem
+    pub fn is_permission_failure(error: &anyhow::Error) -> bool {
+        match CollectorError::find(error) {
+            Some(collector_error) => {
+                matches!(collector_error, CollectorError::PermissionDenied { .. })
+            }
+            None => error
+                .chain()
+                .any(|cause| Self::is_permission_error(&cause.to_string())),
+        }
+    }
+
     /// Get the count of permission failures
     pub async fn failure_count(&self) -> usize {
         let failures = self.failed_artifacts.lock().await;
@@ -148,4 +162,23 @@ mod tests {
         assert!(!PermissionTracker::is_permission_error("File not found"));
         assert!(!PermissionTracker::is_permission_error("Network error"));
     }
+
+    #[test]
+    fn test_permission_failure_detection() {
+        let denied = anyhow::Error::from(CollectorError::PermissionDenied {
+            path: "/var/log/secure".into(),
+            artifact_name: None,
+        })
+        .context("Failed to collect secure");
+        assert!(PermissionTracker::is_permission_failure(&denied));
+
+        let missing = anyhow::Error::from(CollectorError::ArtifactNotFound {
+            name: "secure".to_string(),
+            path: "/var/log/secure".into(),
+        });
+        assert!(!PermissionTracker::is_permission_failure(&missing));
+
+        let wrapped = anyhow::anyhow!("Access is denied").context("Failed to open volume");
+        assert!(PermissionTracker::is_permission_failure(&wrapped));
+    }
 }
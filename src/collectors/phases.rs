@@ -0,0 +1,317 @@
+//! Collection phase tracking for order-of-volatility mode.
+//!
+//! RFC 3227 recommends capturing evidence from most to least volatile. When
+//! `--volatility-order` is used, the main collection flow is split into the
+//! phases below and every phase boundary is timestamped so the order can be
+//! attested in the collection summary. The default flow records the same
+//! phases so the summary can report how long each one took.
+//!
+//! Observers can subscribe to phase events, which is how tests (and any
+//! future progress reporting) follow the pipeline without inspecting output
+//! files.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::collectors::cancellation;
+
+/// Collection phases, declared from most to least volatile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionPhase {
+    /// Network, process and system snapshots buffered in memory
+    VolatileSnapshot,
+    /// Process memory for the selected processes
+    ProcessMemory,
+    /// File artifact copies
+    FileArtifacts,
+    /// Bodyfile timeline generation
+    Bodyfile,
+}
+
+impl CollectionPhase {
+    /// All phases in order of volatility
+    pub const ORDER: [CollectionPhase; 4] = [
+        CollectionPhase::VolatileSnapshot,
+        CollectionPhase::ProcessMemory,
+        CollectionPhase::FileArtifacts,
+        CollectionPhase::Bodyfile,
+    ];
+}
+
+impl fmt::Display for CollectionPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectionPhase::VolatileSnapshot => write!(f, "volatile_snapshot"),
+            CollectionPhase::ProcessMemory => write!(f, "process_memory"),
+            CollectionPhase::FileArtifacts => write!(f, "file_artifacts"),
+            CollectionPhase::Bodyfile => write!(f, "bodyfile"),
+        }
+    }
+}
+
+/// Phase boundary type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseEventKind {
+    Started,
+    Completed,
+}
+
+/// A timestamped phase boundary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseEvent {
+    pub phase: CollectionPhase,
+    pub kind: PhaseEventKind,
+    /// RFC3339 timestamp of the boundary
+    pub timestamp: String,
+}
+
+/// Start, end and duration of one completed or interrupted phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: CollectionPhase,
+    pub started: String,
+    pub completed: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// Receives phase events as they are recorded
+pub trait PhaseObserver: Send + Sync {
+    fn on_phase_event(&self, event: &PhaseEvent);
+}
+
+/// Ordered record of phase boundaries.
+///
+/// Phases must run in [`CollectionPhase::ORDER`]; starting a phase that is
+/// more volatile than one already started is rejected.
+#[derive(Default)]
+pub struct PhaseTimeline {
+    events: Mutex<Vec<PhaseEvent>>,
+    observers: Vec<Arc<dyn PhaseObserver>>,
+}
+
+impl PhaseTimeline {
+    /// Create an empty timeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an observer for phase events
+    pub fn with_observer(mut self, observer: Arc<dyn PhaseObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Run `f` as `phase`, recording start and completion boundaries.
+    ///
+    /// The completion boundary is recorded even if `f` fails. A cancelled
+    /// collection fails here without starting the phase.
+    pub fn run<T>(&self, phase: CollectionPhase, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if cancellation::collection_cancelled() {
+            return Err(anyhow!("Collection cancelled before phase {}", phase));
+        }
+        self.record(phase, PhaseEventKind::Started)?;
+        info!("Phase {} started", phase);
+        let result = f();
+        self.record(phase, PhaseEventKind::Completed)?;
+        info!("Phase {} completed", phase);
+        result
+    }
+
+    fn record(&self, phase: CollectionPhase, kind: PhaseEventKind) -> Result<()> {
+        let event = PhaseEvent {
+            phase,
+            kind,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+
+        {
+            let mut events = self
+                .events
+                .lock()
+                .map_err(|_| anyhow!("Phase timeline lock poisoned"))?;
+
+            if kind == PhaseEventKind::Started {
+                if let Some(last) = events
+                    .iter()
+                    .rev()
+                    .find(|e| e.kind == PhaseEventKind::Started)
+                {
+                    if last.phase >= phase {
+                        return Err(anyhow!(
+                            "Phase {} cannot start after phase {}",
+                            phase,
+                            last.phase
+                        ));
+                    }
+                }
+            }
+
+            events.push(event.clone());
+        }
+
+        for observer in &self.observers {
+            observer.on_phase_event(&event);
+        }
+        Ok(())
+    }
+
+    /// Snapshot of all recorded events
+    pub fn events(&self) -> Vec<PhaseEvent> {
+        self.events.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Timing of each phase that was started, in order
+    pub fn phase_timings(&self) -> Vec<PhaseTiming> {
+        let events = self.events();
+        CollectionPhase::ORDER
+            .iter()
+            .filter_map(|phase| {
+                let started = events
+                    .iter()
+                    .find(|e| e.phase == *phase && e.kind == PhaseEventKind::Started)?;
+                let completed = events
+                    .iter()
+                    .find(|e| e.phase == *phase && e.kind == PhaseEventKind::Completed);
+                let duration_ms = completed.and_then(|c| {
+                    let start = chrono::DateTime::parse_from_rfc3339(&started.timestamp).ok()?;
+                    let end = chrono::DateTime::parse_from_rfc3339(&c.timestamp).ok()?;
+                    Some((end - start).num_milliseconds())
+                });
+                Some(PhaseTiming {
+                    phase: *phase,
+                    started: started.timestamp.clone(),
+                    completed: completed.map(|e| e.timestamp.clone()),
+                    duration_ms,
+                })
+            })
+            .collect()
+    }
+
+    /// Summary representation: one entry per phase with its boundaries
+    pub fn to_summary_json(&self) -> Value {
+        let events = self.events();
+        let phases: Vec<Value> = CollectionPhase::ORDER
+            .iter()
+            .filter_map(|phase| {
+                let started = events
+                    .iter()
+                    .find(|e| e.phase == *phase && e.kind == PhaseEventKind::Started)?;
+                let completed = events
+                    .iter()
+                    .find(|e| e.phase == *phase && e.kind == PhaseEventKind::Completed);
+                Some(json!({
+                    "phase": phase.to_string(),
+                    "started": started.timestamp,
+                    "completed": completed.map(|e| e.timestamp.clone()),
+                }))
+            })
+            .collect();
+
+        json!({
+            "mode": "order_of_volatility",
+            "phases": phases,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        seen: Mutex<Vec<(CollectionPhase, PhaseEventKind)>>,
+    }
+
+    impl PhaseObserver for RecordingObserver {
+        fn on_phase_event(&self, event: &PhaseEvent) {
+            self.seen.lock().unwrap().push((event.phase, event.kind));
+        }
+    }
+
+    #[test]
+    fn test_phases_observed_in_order() {
+        let observer = Arc::new(RecordingObserver::default());
+        let timeline = PhaseTimeline::new().with_observer(observer.clone());
+
+        for phase in CollectionPhase::ORDER {
+            timeline.run(phase, || Ok(())).unwrap();
+        }
+
+        let seen = observer.seen.lock().unwrap().clone();
+        let expected: Vec<_> = CollectionPhase::ORDER
+            .iter()
+            .flat_map(|p| {
+                [
+                    (*p, PhaseEventKind::Started),
+                    (*p, PhaseEventKind::Completed),
+                ]
+            })
+            .collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_out_of_order_phase_rejected() {
+        let timeline = PhaseTimeline::new();
+        timeline
+            .run(CollectionPhase::FileArtifacts, || Ok(()))
+            .unwrap();
+
+        let result = timeline.run(CollectionPhase::VolatileSnapshot, || Ok(()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_failed_phase_still_completes() {
+        let timeline = PhaseTimeline::new();
+        let result: Result<()> =
+            timeline.run(CollectionPhase::ProcessMemory, || Err(anyhow!("boom")));
+
+        assert!(result.is_err());
+        let events = timeline.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].kind, PhaseEventKind::Completed);
+    }
+
+    #[test]
+    fn test_summary_json_timestamps_are_monotonic() {
+        let timeline = PhaseTimeline::new();
+        timeline
+            .run(CollectionPhase::VolatileSnapshot, || Ok(()))
+            .unwrap();
+        timeline.run(CollectionPhase::Bodyfile, || Ok(())).unwrap();
+
+        let summary = timeline.to_summary_json();
+        let phases = summary["phases"].as_array().unwrap();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[0]["phase"], "volatile_snapshot");
+        assert_eq!(phases[1]["phase"], "bodyfile");
+        let parse = |v: &Value| chrono::DateTime::parse_from_rfc3339(v.as_str().unwrap()).unwrap();
+        assert!(parse(&phases[0]["completed"]) <= parse(&phases[1]["started"]));
+    }
+
+    #[test]
+    fn test_phase_timings_skip_phases_not_run() {
+        let timeline = PhaseTimeline::new();
+        timeline
+            .run(CollectionPhase::ProcessMemory, || Ok(()))
+            .unwrap();
+        timeline
+            .run(CollectionPhase::FileArtifacts, || Ok(()))
+            .unwrap();
+
+        let timings = timeline.phase_timings();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].phase, CollectionPhase::ProcessMemory);
+        assert_eq!(timings[1].phase, CollectionPhase::FileArtifacts);
+        assert!(timings.iter().all(|t| t.duration_ms.unwrap() >= 0));
+    }
+}
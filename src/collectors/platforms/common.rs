@@ -1,20 +1,244 @@
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use anyhow::{Context, Result};
 use log::debug;
+use regex::Regex;
 
+use crate::collectors::cancellation;
 use crate::collectors::collector::ArtifactCollector;
+use crate::collectors::incremental;
+use crate::collectors::platforms::listing::MetadataListing;
+use crate::collectors::source::{self, block_on, ArtifactSource, SourceMetadata};
+use crate::collectors::streaming::stream_collected_file;
 use crate::config::{Artifact, ArtifactType};
+use crate::errors::CollectorError;
 use crate::models::ArtifactMetadata;
+use crate::utils::path_encoding::original_path_bytes;
+
+/// Per-artifact options for the directory walker, read from the artifact's
+/// `metadata`
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Write a listing of file metadata instead of copying contents
+    /// (`metadata_only`)
+    pub metadata_only: bool,
+    /// Hash listed files (`metadata_hashes`); listings only
+    pub hash_files: bool,
+    /// Skip entries whose path below the source matches (`exclude_pattern`)
+    pub exclude: Option<Regex>,
+    /// Skip files last modified before this time (`modified_after`)
+    pub modified_after: Option<SystemTime>,
+    /// Skip files last modified after this time (`modified_before`)
+    pub modified_before: Option<SystemTime>,
+}
+
+impl WalkOptions {
+    /// Options from `artifact.metadata`; invalid values are a config error,
+    /// so an exclusion is never silently ignored
+    pub fn from_artifact(artifact: &Artifact) -> Result<Self> {
+        let invalid = |key: &str, value: &str, reason: String| -> anyhow::Error {
+            CollectorError::ConfigError {
+                message: format!(
+                    "Invalid {} '{}' for artifact {}: {}",
+                    key, value, artifact.name, reason
+                ),
+            }
+            .into()
+        };
+        let flag = |key: &str| -> Result<bool> {
+            match artifact.metadata.get(key).map(|v| v.trim()) {
+                None => Ok(false),
+                Some(v) if v.eq_ignore_ascii_case("true") => Ok(true),
+                Some(v) if v.eq_ignore_ascii_case("false") => Ok(false),
+                Some(v) => Err(invalid(key, v, "expected true or false".to_string())),
+            }
+        };
+        let time = |key: &str| -> Result<Option<SystemTime>> {
+            artifact
+                .metadata
+                .get(key)
+                .map(|v| {
+                    chrono::DateTime::parse_from_rfc3339(v.trim())
+                        .map(SystemTime::from)
+                        .map_err(|e| invalid(key, v, e.to_string()))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            metadata_only: flag("metadata_only")?,
+            hash_files: flag("metadata_hashes")?,
+            exclude: artifact
+                .metadata
+                .get("exclude_pattern")
+                .filter(|pattern| !pattern.is_empty())
+                .map(|pattern| {
+                    Regex::new(pattern)
+                        .map_err(|e| invalid("exclude_pattern", pattern, e.to_string()))
+                })
+                .transpose()?,
+            modified_after: time("modified_after")?,
+            modified_before: time("modified_before")?,
+        })
+    }
+
+    /// Whether the walker copies everything, as platform collectors do
+    pub fn is_default(&self) -> bool {
+        !self.metadata_only
+            && self.exclude.is_none()
+            && self.modified_after.is_none()
+            && self.modified_before.is_none()
+    }
+
+    fn is_excluded(&self, relative: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(&relative.to_string_lossy().replace('\\', "/")))
+    }
+
+    /// Whether a file below the source passes the exclusion and time filters.
+    /// Files without a modification time are kept.
+    fn includes_file(&self, relative: &Path, metadata: &SourceMetadata) -> bool {
+        if self.is_excluded(relative) {
+            return false;
+        }
+        match metadata.modified {
+            Some(modified) => {
+                self.modified_after.is_none_or(|after| modified >= after)
+                    && self.modified_before.is_none_or(|before| modified <= before)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Receives the entries the directory walker selects below a source
+pub trait DirectorySink {
+    /// A directory `relative` to the source, before any of its entries
+    fn directory(&mut self, relative: &Path) -> Result<()>;
+
+    /// The file at `path`, `relative` to the source
+    fn file(&mut self, path: &Path, relative: &Path, metadata: &SourceMetadata) -> Result<()>;
+}
+
+/// Copies the selected files below `dest`, keeping the directory layout, and
+/// hands each copy to the active archive stream, if any
+struct CopySink<'a> {
+    source: &'a dyn ArtifactSource,
+    dest: &'a Path,
+}
+
+impl DirectorySink for CopySink<'_> {
+    fn directory(&mut self, relative: &Path) -> Result<()> {
+        let dest_path = self.dest.join(relative);
+        fs::create_dir_all(&dest_path).context(format!(
+            "Failed to create directory: {}",
+            dest_path.display()
+        ))
+    }
+
+    fn file(&mut self, path: &Path, relative: &Path, metadata: &SourceMetadata) -> Result<()> {
+        // Files unchanged since the --baseline collection are left there
+        let live = self.source.is_live_system();
+        let key = path.to_string_lossy();
+        if live
+            && incremental::is_unchanged(&key, metadata.len, metadata.modified, || {
+                incremental::source_sha256(self.source, path)
+            })
+        {
+            return Ok(());
+        }
+
+        let dest_path = self.dest.join(relative);
+        block_on(self.source.copy_to(path, &dest_path)).context(format!(
+            "Failed to copy {} to {}",
+            path.display(),
+            dest_path.display()
+        ))?;
+        if live {
+            incremental::record_collected(&key, metadata.len, rfc3339(metadata.modified));
+        }
+        // With a streaming upload the copy goes into the archive now rather
+        // than after the whole directory is done
+        stream_collected_file(&dest_path);
+        Ok(())
+    }
+}
 
 /// Fallback collector for platforms without specific implementations
-pub struct FallbackCollector;
+pub struct FallbackCollector {
+    source: Arc<dyn ArtifactSource>,
+    walk: WalkOptions,
+}
 
 impl FallbackCollector {
+    /// Collector reading from the run's default source
     pub fn new() -> Self {
-        FallbackCollector
+        Self::with_source(source::default_source())
+    }
+
+    /// Collector reading from `source`
+    pub fn with_source(source: Arc<dyn ArtifactSource>) -> Self {
+        FallbackCollector {
+            source,
+            walk: WalkOptions::default(),
+        }
+    }
+
+    /// Collector applying an artifact's exclusions, time filters and
+    /// metadata-only listing to directories
+    pub fn with_walk_options(mut self, walk: WalkOptions) -> Self {
+        self.walk = walk;
+        self
+    }
+
+    /// The source files are read from
+    pub fn source(&self) -> &Arc<dyn ArtifactSource> {
+        &self.source
+    }
+
+    /// Collector for an artifact `source_path`, switching to the source its
+    /// scheme prefix names, if any; also returns the path within the source
+    pub fn for_source_path<'a>(&self, source_path: &'a str) -> Result<(Self, &'a str)> {
+        match source::split_scheme(source_path) {
+            (None, path) => Ok((self.clone(), path)),
+            (Some(_), _) => {
+                let (source, path) = source::resolve(source_path)?;
+                Ok((
+                    Self::with_source(source).with_walk_options(self.walk.clone()),
+                    path,
+                ))
+            }
+        }
+    }
+
+    /// Metadata of `source`, with permission errors mapped to
+    /// [`CollectorError::PermissionDenied`]
+    fn stat(&self, source: &Path) -> Result<SourceMetadata> {
+        block_on(self.source.stat(source)).map_err(|e| {
+            if e.kind() == io::ErrorKind::PermissionDenied {
+                CollectorError::PermissionDenied {
+                    path: source.to_path_buf(),
+                    artifact_name: None,
+                }
+                .into()
+            } else {
+                anyhow::anyhow!("Failed to get metadata for {}: {}", source.display(), e)
+            }
+        })
+    }
+
+    /// Copy `source` as a directory or a single file, whichever it is
+    pub fn collect_path(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        if self.stat(source)?.is_dir {
+            self.collect_directory(source, dest)
+        } else {
+            self.collect_standard_file(source, dest)
+        }
     }
 
     /// Standard file collection method that works on all platforms
@@ -32,22 +256,16 @@ impl FallbackCollector {
         }
 
         // Get file metadata before copying
-        let metadata = fs::metadata(source)
-            .map_err(|e| {
-                if e.kind() == io::ErrorKind::PermissionDenied {
-                    anyhow::anyhow!("Permission denied reading metadata for {}. Try running with elevated privileges.", source.display())
-                } else {
-                    anyhow::anyhow!("Failed to get metadata for {}: {}", source.display(), e)
-                }
-            })?;
+        let metadata = self.stat(source)?;
 
         // Copy the file
-        fs::copy(source, dest).map_err(|e| {
+        block_on(self.source.copy_to(source, dest)).map_err(|e| {
             if e.kind() == io::ErrorKind::PermissionDenied {
-                anyhow::anyhow!(
-                    "Permission denied copying {}. Try running with elevated privileges.",
-                    source.display()
-                )
+                CollectorError::PermissionDenied {
+                    path: source.to_path_buf(),
+                    artifact_name: None,
+                }
+                .into()
             } else {
                 anyhow::anyhow!(
                     "Failed to copy {} to {}: {}",
@@ -58,40 +276,13 @@ impl FallbackCollector {
             }
         })?;
 
-        // Get current time for metadata
-        let collection_time = chrono::Utc::now().to_rfc3339();
-
-        // Convert file times to RFC3339 strings
-        let created_time = metadata
-            .created()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        let accessed_time = metadata
-            .accessed()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        let modified_time = metadata
-            .modified()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        // Create artifact metadata
-        let artifact_metadata = ArtifactMetadata {
-            original_path: source.to_string_lossy().to_string(),
-            collection_time,
-            file_size: metadata.len(),
-            created_time,
-            accessed_time,
-            modified_time,
-            is_locked: false,
-        };
-
-        Ok(artifact_metadata)
+        Ok(artifact_metadata(source, &metadata, metadata.len))
     }
 
-    /// Directory collection method that recursively copies directories
+    /// Directory collection method that recursively copies directories.
+    ///
+    /// With `metadata_only`, `dest` receives a listing of the files instead
+    /// of their contents, and `source` may also be a single file.
     pub fn collect_directory(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         debug!(
             "Collecting directory from {} to {}",
@@ -104,73 +295,69 @@ impl FallbackCollector {
             .context(format!("Failed to create directory: {}", dest.display()))?;
 
         // Get directory metadata
-        let metadata = fs::metadata(source)
-            .map_err(|e| {
-                if e.kind() == io::ErrorKind::PermissionDenied {
-                    anyhow::anyhow!("Permission denied reading metadata for directory {}. Try running with elevated privileges.", source.display())
-                } else {
-                    anyhow::anyhow!("Failed to get metadata for {}: {}", source.display(), e)
-                }
-            })?;
+        let metadata = self.stat(source)?;
+
+        // Will be updated with total size
+        let mut artifact_metadata = artifact_metadata(source, &metadata, 0);
+        artifact_metadata.allocated_size = None;
+
+        if self.walk.metadata_only {
+            let mut listing = MetadataListing::create(
+                dest,
+                source,
+                Arc::clone(&self.source),
+                self.walk.hash_files,
+            )?;
+            match source.file_name().filter(|_| !metadata.is_dir) {
+                Some(file_name) => listing.file(source, Path::new(file_name), &metadata)?,
+                None => self.walk_directory(source, Path::new(""), &mut listing)?,
+            }
+            artifact_metadata.listed_files = Some(listing.finish()?.files);
+            return Ok(artifact_metadata);
+        }
 
         // Recursively copy directory contents
-        self.copy_dir_contents(source, dest)?;
-
-        // Get current time for metadata
-        let collection_time = chrono::Utc::now().to_rfc3339();
-
-        // Convert file times to RFC3339 strings
-        let created_time = metadata
-            .created()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        let accessed_time = metadata
-            .accessed()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        let modified_time = metadata
-            .modified()
-            .ok()
-            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
-
-        // Create artifact metadata
-        let artifact_metadata = ArtifactMetadata {
-            original_path: source.to_string_lossy().to_string(),
-            collection_time,
-            file_size: 0, // Will be updated with total size
-            created_time,
-            accessed_time,
-            modified_time,
-            is_locked: false,
+        let mut sink = CopySink {
+            source: self.source.as_ref(),
+            dest,
         };
+        self.walk_directory(source, Path::new(""), &mut sink)?;
 
         Ok(artifact_metadata)
     }
 
-    /// Helper method to recursively copy directory contents
-    fn copy_dir_contents(&self, source: &Path, dest: &Path) -> Result<()> {
-        for entry in fs::read_dir(source)
-            .context(format!("Failed to read directory: {}", source.display()))?
+    /// Hand the entries below `dir`, which is `relative` to the artifact
+    /// source, to `sink`, applying the walk options
+    fn walk_directory(
+        &self,
+        dir: &Path,
+        relative: &Path,
+        sink: &mut dyn DirectorySink,
+    ) -> Result<()> {
+        for path in block_on(self.source.list_dir(dir))
+            .context(format!("Failed to read directory: {}", dir.display()))?
         {
-            let entry = entry.context("Failed to read directory entry")?;
-            let path = entry.path();
-            let file_name = entry.file_name();
-            let dest_path = dest.join(file_name);
-
-            if path.is_dir() {
-                fs::create_dir_all(&dest_path).context(format!(
-                    "Failed to create directory: {}",
-                    dest_path.display()
-                ))?;
-                self.copy_dir_contents(&path, &dest_path)?;
-            } else {
-                fs::copy(&path, &dest_path).context(format!(
-                    "Failed to copy {} to {}",
-                    path.display(),
-                    dest_path.display()
-                ))?;
+            let Some(file_name) = path.file_name() else {
+                continue;
+            };
+            let entry_relative = relative.join(file_name);
+
+            // Stop early if the artifact timed out
+            if cancellation::is_cancelled(&path) {
+                return Err(anyhow::anyhow!(
+                    "Copy of {} cancelled after timeout",
+                    dir.display()
+                ));
+            }
+
+            let metadata = block_on(self.source.stat(&path)).unwrap_or_default();
+            if metadata.is_dir {
+                if !self.walk.is_excluded(&entry_relative) {
+                    sink.directory(&entry_relative)?;
+                    self.walk_directory(&path, &entry_relative, sink)?;
+                }
+            } else if self.walk.includes_file(&entry_relative, &metadata) {
+                sink.file(&path, &entry_relative, &metadata)?;
             }
         }
 
@@ -178,14 +365,37 @@ impl FallbackCollector {
     }
 }
 
+/// RFC3339 string for a file time
+pub(crate) fn rfc3339(time: Option<SystemTime>) -> Option<String> {
+    time.map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+}
+
+/// Artifact metadata for `source`, with file times as RFC3339 strings
+fn artifact_metadata(source: &Path, metadata: &SourceMetadata, file_size: u64) -> ArtifactMetadata {
+    ArtifactMetadata {
+        original_path: source.to_string_lossy().to_string(),
+        collection_time: chrono::Utc::now().to_rfc3339(),
+        file_size,
+        created_time: rfc3339(metadata.created),
+        accessed_time: rfc3339(metadata.accessed),
+        modified_time: rfc3339(metadata.modified),
+        is_locked: false,
+        sha256: None,
+        allocated_size: metadata.allocated_size,
+        original_path_bytes: original_path_bytes(source),
+        ..Default::default()
+    }
+}
+
 #[async_trait::async_trait]
 impl ArtifactCollector for FallbackCollector {
-    async fn collect(&self, artifact: &Artifact, output_dir: &Path) -> Result<ArtifactMetadata> {
-        let source_path = PathBuf::from(&artifact.source_path);
+    async fn collect(&self, artifact: &Artifact, output_path: &Path) -> Result<ArtifactMetadata> {
+        let (collector, source_path) = self.for_source_path(&artifact.source_path)?;
+        let source_path = PathBuf::from(source_path);
 
         // Use the output directory directly instead of joining with destination name
         // The path structure is now handled by the main collector function
-        let output_path = output_dir.to_path_buf();
+        let output_path = output_path.to_path_buf();
 
         debug!(
             "Collecting {} from {} to {}",
@@ -195,30 +405,33 @@ impl ArtifactCollector for FallbackCollector {
         );
 
         // Check if source is a directory or file
-        let metadata = match fs::metadata(&source_path) {
+        let metadata = match collector.source.stat(&source_path).await {
             Ok(meta) => meta,
             Err(e) => {
                 if e.kind() == io::ErrorKind::NotFound {
-                    return Err(anyhow::anyhow!(
-                        "Source not found: {}",
-                        source_path.display()
-                    ));
+                    return Err(CollectorError::ArtifactNotFound {
+                        name: artifact.name.clone(),
+                        path: source_path,
+                    }
+                    .into());
                 } else if e.kind() == io::ErrorKind::PermissionDenied {
-                    return Err(anyhow::anyhow!("Permission denied accessing: {}. Try running with elevated privileges or check file permissions.", source_path.display()));
+                    return Err(CollectorError::PermissionDenied {
+                        path: source_path,
+                        artifact_name: Some(artifact.name.clone()),
+                    }
+                    .into());
                 } else {
                     return Err(anyhow::anyhow!("Failed to access source: {}", e));
                 }
             }
         };
 
-        // Clone self for the async block
-        let collector = self.clone();
         let source_path_clone = source_path.clone();
         let output_path_clone = output_path.clone();
 
         // Use tokio::task::spawn_blocking for file I/O operations
         let result = tokio::task::spawn_blocking(move || {
-            if metadata.is_dir() {
+            if metadata.is_dir || collector.walk.metadata_only {
                 collector.collect_directory(&source_path_clone, &output_path_clone)
             } else {
                 collector.collect_standard_file(&source_path_clone, &output_path_clone)
@@ -239,6 +452,9 @@ impl ArtifactCollector for FallbackCollector {
 // Make FallbackCollector cloneable for use in async blocks
 impl Clone for FallbackCollector {
     fn clone(&self) -> Self {
-        FallbackCollector
+        FallbackCollector {
+            source: Arc::clone(&self.source),
+            walk: self.walk.clone(),
+        }
     }
 }
@@ -0,0 +1,371 @@
+//! Encrypted source detection.
+//!
+//! Files under file-level encryption either fail to copy or copy as
+//! ciphertext that looks like any other file. Collected sources are checked
+//! so their metadata says so:
+//!
+//! - **Windows**: `FILE_ATTRIBUTE_ENCRYPTED` from `GetFileAttributesW` (EFS)
+//! - **Linux**: `FS_IOC_GET_ENCRYPTION_POLICY` (fscrypt, as used by ext4,
+//!   f2fs and Android), and eCryptfs either as the mounted file system or as
+//!   the header of a lower, still encrypted file
+//! - **macOS**: reads refused with `EPERM` although the file's metadata is
+//!   readable, which is how Data Protection refuses a protected file while
+//!   its class key is unavailable
+//!
+//! The platform queries go through [`EncryptionProbe`] so the decisions can
+//! be tested without encrypted volumes.
+
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::collectors::read_only;
+use crate::models::ArtifactMetadata;
+
+/// Windows Encrypting File System
+pub const EFS: &str = "efs";
+/// Linux native file-based encryption
+pub const FSCRYPT: &str = "fscrypt";
+/// eCryptfs stacked file system
+pub const ECRYPTFS: &str = "ecryptfs";
+/// macOS Data Protection classes
+pub const DATA_PROTECTION: &str = "data_protection";
+
+/// `FILE_ATTRIBUTE_ENCRYPTED`
+const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x4000;
+/// `INVALID_FILE_ATTRIBUTES`, returned when the attributes cannot be read
+const INVALID_FILE_ATTRIBUTES: u32 = u32::MAX;
+/// `ECRYPTFS_SUPER_MAGIC` reported by `statfs`
+const ECRYPTFS_SUPER_MAGIC: i64 = 0xf15f;
+/// `MAGIC_ECRYPTFS_MARKER`, the XOR of the two marker words at offset 8 of
+/// an eCryptfs lower file
+const ECRYPTFS_MARKER: u32 = 0x3c81_b7f5;
+
+/// `ENODATA`: the file has no fscrypt policy
+const ENODATA: i32 = 61;
+/// `EINVAL`: the file has a v2 policy, which the v1 ioctl cannot return
+const EINVAL: i32 = 22;
+/// `ENOKEY`: the file is encrypted and its key is not loaded
+const ENOKEY: i32 = 126;
+/// `EPERM`
+const EPERM: i32 = 1;
+
+/// Platform queries behind the detection
+pub trait EncryptionProbe {
+    /// Windows file attributes, or `None` elsewhere and on failure
+    fn file_attributes(&self, path: &Path) -> Option<u32>;
+    /// Result of `FS_IOC_GET_ENCRYPTION_POLICY` on `path`, as an OS error
+    /// number on failure; `None` where fscrypt does not exist
+    fn fscrypt_policy(&self, path: &Path) -> Option<Result<(), i32>>;
+    /// `statfs` file system magic, on Linux
+    fn filesystem_magic(&self, path: &Path) -> Option<i64>;
+    /// First 16 bytes of a regular file
+    fn header(&self, path: &Path) -> Option<[u8; 16]>;
+}
+
+/// Probe querying the running system
+pub struct LiveProbe;
+
+impl EncryptionProbe for LiveProbe {
+    fn file_attributes(&self, path: &Path) -> Option<u32> {
+        #[cfg(target_os = "windows")]
+        return windows_impl::file_attributes(path);
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = path;
+            None
+        }
+    }
+
+    fn fscrypt_policy(&self, path: &Path) -> Option<Result<(), i32>> {
+        #[cfg(target_os = "linux")]
+        return Some(linux_impl::fscrypt_policy(path));
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = path;
+            None
+        }
+    }
+
+    fn filesystem_magic(&self, path: &Path) -> Option<i64> {
+        #[cfg(target_os = "linux")]
+        return linux_impl::filesystem_magic(path);
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = path;
+            None
+        }
+    }
+
+    fn header(&self, path: &Path) -> Option<[u8; 16]> {
+        let mut header = [0; 16];
+        let mut file = read_only::open(path).ok()?;
+        file.metadata().ok()?.is_file().then_some(())?;
+        file.read_exact(&mut header).ok()?;
+        Some(header)
+    }
+}
+
+/// Encryption mechanism protecting `path` on this system, if any
+pub fn detect(path: &Path) -> Option<&'static str> {
+    detect_with(&LiveProbe, path)
+}
+
+/// Encryption mechanism protecting `path`, as `probe` reports it
+pub fn detect_with(probe: &dyn EncryptionProbe, path: &Path) -> Option<&'static str> {
+    if probe.file_attributes(path).is_some_and(is_efs_attribute) {
+        return Some(EFS);
+    }
+    if probe.fscrypt_policy(path).is_some_and(has_fscrypt_policy) {
+        return Some(FSCRYPT);
+    }
+    if probe.filesystem_magic(path) == Some(ECRYPTFS_SUPER_MAGIC)
+        || probe.header(path).is_some_and(|h| is_ecryptfs_header(&h))
+    {
+        return Some(ECRYPTFS);
+    }
+    None
+}
+
+/// Record the encryption of a collected live source in its metadata,
+/// keeping a mechanism the collector already set
+pub fn mark_encrypted(metadata: &mut ArtifactMetadata) {
+    if metadata.encrypted.is_none() {
+        metadata.encrypted = detect(Path::new(&metadata.original_path)).map(str::to_string);
+    }
+}
+
+/// Add the encryption mechanism to a failed read of `path`, when the
+/// failure is how that mechanism refuses access
+pub fn explain_failure(error: anyhow::Error, path: &Path) -> anyhow::Error {
+    let os_error = error.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .and_then(|e| e.raw_os_error())
+    });
+    let metadata_readable = path.symlink_metadata().is_ok();
+    let mechanism = match refusal_mechanism(os_error, metadata_readable) {
+        Some(mechanism) => Some(mechanism),
+        None => detect(path),
+    };
+    match mechanism {
+        Some(mechanism) => error.context(format!(
+            "{} is encrypted ({}) and could not be read without its key",
+            path.display(),
+            mechanism
+        )),
+        None => error,
+    }
+}
+
+/// Mechanism whose refusal produces `os_error` on this platform
+fn refusal_mechanism(os_error: Option<i32>, metadata_readable: bool) -> Option<&'static str> {
+    match os_error? {
+        ENOKEY if cfg!(target_os = "linux") => Some(FSCRYPT),
+        EPERM if cfg!(target_os = "macos") && metadata_readable => Some(DATA_PROTECTION),
+        _ => None,
+    }
+}
+
+fn is_efs_attribute(attributes: u32) -> bool {
+    attributes != INVALID_FILE_ATTRIBUTES && attributes & FILE_ATTRIBUTE_ENCRYPTED != 0
+}
+
+/// A policy was returned, or the file has one the v1 ioctl cannot express,
+/// or opening it failed because its key is missing
+fn has_fscrypt_policy(result: Result<(), i32>) -> bool {
+    match result {
+        Ok(()) | Err(EINVAL) | Err(ENOKEY) => true,
+        // No policy, or no fscrypt support on the file system
+        Err(ENODATA) | Err(_) => false,
+    }
+}
+
+/// Whether `header` starts an eCryptfs lower file: two big-endian marker
+/// words at offset 8 whose XOR is the eCryptfs magic
+fn is_ecryptfs_header(header: &[u8; 16]) -> bool {
+    let word = |offset: usize| {
+        u32::from_be_bytes([
+            header[offset],
+            header[offset + 1],
+            header[offset + 2],
+            header[offset + 3],
+        ])
+    };
+    word(8) ^ word(12) == ECRYPTFS_MARKER
+}
+
+#[cfg(target_os = "linux")]
+mod linux_impl {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    /// `FS_IOC_GET_ENCRYPTION_POLICY`: `_IOW('f', 21, struct fscrypt_policy_v1)`
+    const FS_IOC_GET_ENCRYPTION_POLICY: libc::c_ulong = 0x400c_6615;
+
+    pub fn fscrypt_policy(path: &Path) -> Result<(), i32> {
+        let file =
+            crate::collectors::read_only::open(path).map_err(|e| e.raw_os_error().unwrap_or(0))?;
+        // struct fscrypt_policy_v1 is 12 bytes
+        let mut policy = [0u8; 12];
+        // SAFETY: the descriptor is open and the buffer is the size the
+        // ioctl writes
+        let result = unsafe {
+            libc::ioctl(
+                file.as_raw_fd(),
+                FS_IOC_GET_ENCRYPTION_POLICY as _,
+                policy.as_mut_ptr(),
+            )
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error().raw_os_error().unwrap_or(0))
+        }
+    }
+
+    pub fn filesystem_magic(path: &Path) -> Option<i64> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: statfs fills the zeroed struct for a NUL-terminated path
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::statfs(path.as_ptr(), &mut stat) };
+        (result == 0).then_some(stat.f_type as i64)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::path::Path;
+
+    use widestring::U16CString;
+    use winapi::um::fileapi::GetFileAttributesW;
+
+    pub fn file_attributes(path: &Path) -> Option<u32> {
+        let wide = U16CString::from_os_str(path.as_os_str()).ok()?;
+        // SAFETY: the path is NUL-terminated
+        Some(unsafe { GetFileAttributesW(wide.as_ptr()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Probe returning fixed answers
+    #[derive(Default)]
+    struct MockProbe {
+        attributes: Option<u32>,
+        fscrypt: Option<Result<(), i32>>,
+        magic: Option<i64>,
+        header: Option<[u8; 16]>,
+    }
+
+    impl EncryptionProbe for MockProbe {
+        fn file_attributes(&self, _path: &Path) -> Option<u32> {
+            self.attributes
+        }
+        fn fscrypt_policy(&self, _path: &Path) -> Option<Result<(), i32>> {
+            self.fscrypt
+        }
+        fn filesystem_magic(&self, _path: &Path) -> Option<i64> {
+            self.magic
+        }
+        fn header(&self, _path: &Path) -> Option<[u8; 16]> {
+            self.header
+        }
+    }
+
+    fn detect_mock(probe: MockProbe) -> Option<&'static str> {
+        detect_with(&probe, Path::new("/evidence/file"))
+    }
+
+    #[test]
+    fn test_efs_attribute() {
+        // FILE_ATTRIBUTE_ARCHIVE | FILE_ATTRIBUTE_ENCRYPTED
+        let encrypted = MockProbe {
+            attributes: Some(0x4020),
+            ..MockProbe::default()
+        };
+        assert_eq!(detect_mock(encrypted), Some(EFS));
+
+        for attributes in [0x20, INVALID_FILE_ATTRIBUTES] {
+            let probe = MockProbe {
+                attributes: Some(attributes),
+                ..MockProbe::default()
+            };
+            assert_eq!(detect_mock(probe), None);
+        }
+    }
+
+    #[test]
+    fn test_fscrypt_policy_results() {
+        for (result, expected) in [
+            (Ok(()), Some(FSCRYPT)),
+            (Err(EINVAL), Some(FSCRYPT)),
+            (Err(ENOKEY), Some(FSCRYPT)),
+            (Err(ENODATA), None),
+            // ENOTTY: the file system has no fscrypt support
+            (Err(25), None),
+        ] {
+            let probe = MockProbe {
+                fscrypt: Some(result),
+                ..MockProbe::default()
+            };
+            assert_eq!(detect_mock(probe), expected, "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_ecryptfs_mount_and_lower_file() {
+        let mounted = MockProbe {
+            magic: Some(ECRYPTFS_SUPER_MAGIC),
+            ..MockProbe::default()
+        };
+        assert_eq!(detect_mock(mounted), Some(ECRYPTFS));
+
+        let mut header = [0u8; 16];
+        header[..8].copy_from_slice(&4096u64.to_be_bytes());
+        header[8..12].copy_from_slice(&0x1234_5678u32.to_be_bytes());
+        header[12..].copy_from_slice(&(0x1234_5678u32 ^ ECRYPTFS_MARKER).to_be_bytes());
+        let lower = MockProbe {
+            header: Some(header),
+            ..MockProbe::default()
+        };
+        assert_eq!(detect_mock(lower), Some(ECRYPTFS));
+
+        let plain = MockProbe {
+            magic: Some(0xef53),
+            header: Some(*b"plain text file\n"),
+            ..MockProbe::default()
+        };
+        assert_eq!(detect_mock(plain), None);
+    }
+
+    #[test]
+    fn test_refusal_mechanism() {
+        assert_eq!(
+            refusal_mechanism(Some(ENOKEY), true),
+            cfg!(target_os = "linux").then_some(FSCRYPT)
+        );
+        assert_eq!(
+            refusal_mechanism(Some(EPERM), true),
+            cfg!(target_os = "macos").then_some(DATA_PROTECTION)
+        );
+        assert_eq!(refusal_mechanism(Some(EPERM), false), None);
+        assert_eq!(refusal_mechanism(None, true), None);
+    }
+
+    #[test]
+    fn test_live_detection_of_plain_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, b"nothing to see here, just text").unwrap();
+        assert_eq!(detect(&path), None);
+
+        let error = anyhow::Error::new(io::Error::from_raw_os_error(2));
+        let explained = explain_failure(error, &path);
+        assert!(!explained.to_string().contains("encrypted"));
+    }
+}
@@ -0,0 +1,618 @@
+//! File copying for collected artifacts.
+//!
+//! Large evtx and journal files dominate collection time, so copies use a
+//! large buffer (`copy_buffer_kb` in `global_options`, 1 MiB by default) and
+//! the fastest mechanism the platform offers, falling back to the next one
+//! when the file system does not support it:
+//!
+//! 1. macOS: `clonefile`, an instant APFS clone when source and destination
+//!    share a volume
+//! 2. Sparse files (Linux and Windows): only the allocated ranges are copied
+//!    and the holes are recreated in the destination (see [`sparse`])
+//! 3. Windows: `CopyFileExW` with a progress routine
+//! 4. Linux: `copy_file_range`, an in-kernel copy
+//! 5. A buffered read/write loop
+//!
+//! The destination is sized with `set_len` before data is written, so the
+//! output volume does not fragment, and trimmed afterwards if the source
+//! shrank. Files over [`COPY_PROGRESS_THRESHOLD`] log their progress, and
+//! every path stops early if the artifact's collection timed out.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{debug, info, warn};
+
+use crate::collectors::cancellation;
+use crate::collectors::platforms::sparse;
+use crate::collectors::read_only;
+use crate::constants::{COPY_PROGRESS_THRESHOLD, DEFAULT_COPY_BUFFER_KB};
+use crate::security::audit_log::{self, AuditAction};
+use crate::utils::resource_limits;
+
+/// Copy buffer size in KiB, set from `copy_buffer_kb`
+static COPY_BUFFER_KB: AtomicUsize = AtomicUsize::new(DEFAULT_COPY_BUFFER_KB);
+
+/// Smallest buffer used for buffered copies
+const MIN_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Largest buffer used while the collector is near its memory cap
+const MEMORY_PRESSURE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// How a file was copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    /// Copy-on-write clone sharing the source's blocks (APFS)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Clone,
+    /// `CopyFileExW`
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    CopyFileEx,
+    /// In-kernel copy with `copy_file_range`
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    CopyFileRange,
+    /// Allocated ranges only, with holes recreated in the destination
+    #[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+    Sparse,
+    /// Read/write loop through the copy buffer
+    Buffered,
+}
+
+/// Outcome of a successful copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyResult {
+    pub bytes_copied: u64,
+    pub method: CopyMethod,
+}
+
+/// Set the copy buffer size for later copies
+pub fn set_copy_buffer_kb(kb: usize) {
+    COPY_BUFFER_KB.store(kb.max(MIN_BUFFER_SIZE / 1024), Ordering::Relaxed);
+}
+
+/// Current copy buffer size in bytes, smaller while the collector is near
+/// its `--max-memory-mb` cap
+pub fn copy_buffer_size() -> usize {
+    let size = COPY_BUFFER_KB.load(Ordering::Relaxed) * 1024;
+    if resource_limits::memory_pressure() {
+        return size.min(MEMORY_PRESSURE_BUFFER_SIZE);
+    }
+    size
+}
+
+/// Apply `copy_buffer_kb` from `global_options`, using
+/// [`DEFAULT_COPY_BUFFER_KB`] when it is missing or invalid
+pub fn configure(global_options: &HashMap<String, String>) {
+    let kb = match global_options.get("copy_buffer_kb") {
+        None => DEFAULT_COPY_BUFFER_KB,
+        Some(value) => match value.trim().parse::<usize>() {
+            Ok(kb) if kb > 0 => {
+                debug!("Using a {} KiB copy buffer", kb);
+                kb
+            }
+            _ => {
+                warn!(
+                    "Invalid copy_buffer_kb '{}'; using {} KiB",
+                    value, DEFAULT_COPY_BUFFER_KB
+                );
+                DEFAULT_COPY_BUFFER_KB
+            }
+        },
+    };
+    set_copy_buffer_kb(kb);
+}
+
+/// Copy `source` to `dest`, logging progress for large files.
+///
+/// The source open and destination write are recorded in the audit trail.
+pub fn copy_file(source: &Path, dest: &Path) -> io::Result<CopyResult> {
+    let mut progress_log = ProgressLog::new(source);
+    audit_log::record(AuditAction::FileOpen, &source.to_string_lossy());
+    let result =
+        copy_file_with_progress(source, dest, copy_buffer_size(), &mut |copied, total| {
+            progress_log.update(copied, total)
+        })?;
+    audit_log::record(
+        AuditAction::FileWrite,
+        &format!("{} ({} bytes)", dest.display(), result.bytes_copied),
+    );
+    debug!(
+        "Copied {} bytes from {} ({:?})",
+        result.bytes_copied,
+        source.display(),
+        result.method
+    );
+    Ok(result)
+}
+
+/// Copy `source` to `dest`, calling `progress(bytes_copied, total_bytes)`
+/// as data is written.
+///
+/// An existing destination is replaced. Errors opening the source keep
+/// their `io::ErrorKind`, so callers can tell permission problems apart.
+pub fn copy_file_with_progress(
+    source: &Path,
+    dest: &Path,
+    buffer_size: usize,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<CopyResult> {
+    check_cancelled(source)?;
+    let total = fs::metadata(source)?.len();
+    let mut source_file = read_only::open(source)?;
+
+    #[cfg(target_os = "macos")]
+    if let Some(bytes_copied) = try_clone(source, dest) {
+        progress(bytes_copied, bytes_copied);
+        return Ok(CopyResult {
+            bytes_copied,
+            method: CopyMethod::Clone,
+        });
+    }
+
+    let ranges = sparse::data_ranges(&source_file, total).unwrap_or_else(|e| {
+        debug!(
+            "Cannot query allocated ranges of {}: {}",
+            source.display(),
+            e
+        );
+        None
+    });
+    if let Some(ranges) = ranges {
+        let mut dest_file = File::create(dest)?;
+        let data_bytes = copy_sparse_file(
+            &mut source_file,
+            &mut dest_file,
+            source,
+            &ranges,
+            total,
+            buffer_size,
+            progress,
+        )?;
+        debug!(
+            "Copied sparse file {}: {} of {} bytes allocated",
+            source.display(),
+            data_bytes,
+            total
+        );
+        return Ok(CopyResult {
+            bytes_copied: total,
+            method: CopyMethod::Sparse,
+        });
+    }
+
+    #[cfg(target_os = "windows")]
+    if let Some(bytes_copied) = try_copy_file_ex(source, dest, progress)? {
+        return Ok(CopyResult {
+            bytes_copied,
+            method: CopyMethod::CopyFileEx,
+        });
+    }
+
+    let mut dest_file = File::create(dest)?;
+    if total > 0 {
+        dest_file.set_len(total)?;
+    }
+
+    #[cfg(target_os = "linux")]
+    let fast_copied = try_copy_file_range(
+        &source_file,
+        &dest_file,
+        source,
+        total,
+        buffer_size,
+        progress,
+    )?;
+    #[cfg(not(target_os = "linux"))]
+    let fast_copied: Option<u64> = None;
+
+    let (bytes_copied, method) = match fast_copied {
+        Some(copied) => (copied, CopyMethod::CopyFileRange),
+        None => {
+            let copied = buffered_copy(
+                &mut source_file,
+                &mut dest_file,
+                source,
+                total,
+                buffer_size,
+                progress,
+            )?;
+            (copied, CopyMethod::Buffered)
+        }
+    };
+
+    // Trim the preallocation if the source shrank while it was copied
+    if bytes_copied != total {
+        dest_file.set_len(bytes_copied)?;
+    }
+
+    Ok(CopyResult {
+        bytes_copied,
+        method,
+    })
+}
+
+/// Called between chunks: stops a cancelled copy, and paces copies while
+/// the collector is over its `--max-cpu-percent` target
+fn check_cancelled(source: &Path) -> io::Result<()> {
+    resource_limits::pace();
+    if cancellation::is_cancelled(source) {
+        return Err(io::Error::other(format!(
+            "Copy of {} cancelled after timeout",
+            source.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Copy only the allocated `ranges` of a sparse file, leaving holes in the
+/// destination.
+///
+/// The destination ends up `total` bytes long. Returns the number of data
+/// bytes copied, which is roughly the source's allocated size.
+pub fn copy_sparse_file(
+    source_file: &mut File,
+    dest_file: &mut File,
+    source: &Path,
+    ranges: &[Range<u64>],
+    total: u64,
+    buffer_size: usize,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<u64> {
+    if let Err(e) = sparse::set_sparse(dest_file) {
+        debug!("Cannot mark copy of {} sparse: {}", source.display(), e);
+    }
+    dest_file.set_len(total)?;
+
+    let data_total: u64 = ranges.iter().map(|r| r.end - r.start).sum();
+    let mut buffer = vec![0u8; buffer_size.max(MIN_BUFFER_SIZE)];
+    let mut copied = 0u64;
+
+    for range in ranges {
+        source_file.seek(SeekFrom::Start(range.start))?;
+        dest_file.seek(SeekFrom::Start(range.start))?;
+
+        let mut remaining = range.end - range.start;
+        while remaining > 0 {
+            check_cancelled(source)?;
+            let want = buffer
+                .len()
+                .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+            let read = match source_file.read(&mut buffer[..want]) {
+                // The source shrank after its ranges were queried
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            dest_file.write_all(&buffer[..read])?;
+            remaining -= read as u64;
+            copied += read as u64;
+            progress(copied, data_total.max(copied));
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Read/write loop; reads until EOF so files with a misleading size (procfs,
+/// sysfs) are copied completely
+fn buffered_copy(
+    source_file: &mut File,
+    dest_file: &mut File,
+    source: &Path,
+    total: u64,
+    buffer_size: usize,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<u64> {
+    let mut buffer = vec![0u8; buffer_size.max(MIN_BUFFER_SIZE)];
+    let mut copied = 0u64;
+
+    loop {
+        check_cancelled(source)?;
+        let read = match source_file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        dest_file.write_all(&buffer[..read])?;
+        copied += read as u64;
+        progress(copied, total.max(copied));
+    }
+
+    Ok(copied)
+}
+
+/// Clone on APFS; `None` if the volume or file system can't clone
+#[cfg(target_os = "macos")]
+fn try_clone(source: &Path, dest: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let source_c = CString::new(source.as_os_str().as_bytes()).ok()?;
+    let dest_c = CString::new(dest.as_os_str().as_bytes()).ok()?;
+
+    // clonefile refuses to replace an existing destination
+    if dest.exists() && fs::remove_file(dest).is_err() {
+        return None;
+    }
+
+    let result = unsafe { libc::clonefile(source_c.as_ptr(), dest_c.as_ptr(), 0) };
+    if result != 0 {
+        debug!(
+            "Cannot clone {}, copying instead: {}",
+            source.display(),
+            io::Error::last_os_error()
+        );
+        return None;
+    }
+    fs::metadata(dest).ok().map(|metadata| metadata.len())
+}
+
+/// Copy with `CopyFileExW`; `None` if it failed and a buffered copy should
+/// be tried
+#[cfg(target_os = "windows")]
+fn try_copy_file_ex(
+    source: &Path,
+    dest: &Path,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<Option<u64>> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use winapi::shared::minwindef::{DWORD, FALSE, LPVOID};
+    use winapi::shared::ntdef::LARGE_INTEGER;
+    use winapi::shared::winerror::ERROR_REQUEST_ABORTED;
+    use winapi::um::winbase::{CopyFileExW, PROGRESS_CANCEL, PROGRESS_CONTINUE};
+    use winapi::um::winnt::HANDLE;
+
+    struct RoutineData<'a> {
+        source: &'a Path,
+        progress: &'a mut dyn FnMut(u64, u64),
+    }
+
+    unsafe extern "system" fn routine(
+        total_size: LARGE_INTEGER,
+        transferred: LARGE_INTEGER,
+        _stream_size: LARGE_INTEGER,
+        _stream_transferred: LARGE_INTEGER,
+        _stream_number: DWORD,
+        _reason: DWORD,
+        _source_file: HANDLE,
+        _dest_file: HANDLE,
+        data: LPVOID,
+    ) -> DWORD {
+        let data = &mut *(data as *mut RoutineData);
+        if cancellation::is_cancelled(data.source) {
+            return PROGRESS_CANCEL;
+        }
+        (data.progress)(
+            *transferred.QuadPart() as u64,
+            *total_size.QuadPart() as u64,
+        );
+        PROGRESS_CONTINUE
+    }
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain(Some(0)).collect()
+    }
+
+    let source_w = wide(source);
+    let dest_w = wide(dest);
+    let mut data = RoutineData { source, progress };
+
+    let result = unsafe {
+        CopyFileExW(
+            source_w.as_ptr(),
+            dest_w.as_ptr(),
+            Some(routine),
+            &mut data as *mut RoutineData as LPVOID,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if result != FALSE {
+        return Ok(Some(fs::metadata(dest)?.len()));
+    }
+
+    let error = io::Error::last_os_error();
+    if error.raw_os_error() == Some(ERROR_REQUEST_ABORTED as i32) {
+        check_cancelled(source)?;
+    }
+    debug!(
+        "CopyFileExW failed for {}, copying instead: {}",
+        source.display(),
+        error
+    );
+    Ok(None)
+}
+
+/// Copy with `copy_file_range`; `None` if nothing could be copied this way
+/// (unsupported file system, cross-device on old kernels, virtual files)
+#[cfg(target_os = "linux")]
+fn try_copy_file_range(
+    source_file: &File,
+    dest_file: &File,
+    source: &Path,
+    total: u64,
+    chunk_size: usize,
+    progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<Option<u64>> {
+    use std::os::unix::io::AsRawFd;
+    use std::ptr;
+
+    let chunk_size = chunk_size.max(MIN_BUFFER_SIZE);
+    let mut copied = 0u64;
+
+    loop {
+        check_cancelled(source)?;
+        let result = unsafe {
+            libc::copy_file_range(
+                source_file.as_raw_fd(),
+                ptr::null_mut(),
+                dest_file.as_raw_fd(),
+                ptr::null_mut(),
+                chunk_size,
+                0,
+            )
+        };
+
+        if result < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            if copied == 0 {
+                debug!(
+                    "copy_file_range unavailable for {}: {}",
+                    source.display(),
+                    error
+                );
+                return Ok(None);
+            }
+            return Err(error);
+        }
+        if result == 0 {
+            // Virtual files report a size but copy nothing; read them instead
+            return Ok(if copied == 0 { None } else { Some(copied) });
+        }
+
+        copied += result as u64;
+        progress(copied, total.max(copied));
+    }
+}
+
+/// Logs copy progress in 10% steps for files over the progress threshold
+pub(crate) struct ProgressLog {
+    source: PathBuf,
+    next_percent: u64,
+}
+
+impl ProgressLog {
+    pub(crate) fn new(source: &Path) -> Self {
+        Self {
+            source: source.to_path_buf(),
+            next_percent: 10,
+        }
+    }
+
+    pub(crate) fn update(&mut self, copied: u64, total: u64) {
+        if total < COPY_PROGRESS_THRESHOLD {
+            return;
+        }
+        let percent = copied * 100 / total;
+        if percent >= self.next_percent {
+            info!(
+                "Copying {}: {}/{} bytes ({}%)",
+                self.source.display(),
+                copied,
+                total,
+                percent
+            );
+            self.next_percent = (percent / 10 + 1) * 10;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn patterned(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_copy_replaces_destination_and_reports_progress() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("Security.evtx");
+        let dest = temp.path().join("copy.evtx");
+        let data = patterned(300 * 1024 + 17);
+        fs::write(&source, &data).unwrap();
+        fs::write(&dest, vec![0xffu8; 1024 * 1024]).unwrap();
+
+        let mut last = (0, 0);
+        let result = copy_file_with_progress(&source, &dest, 64 * 1024, &mut |copied, total| {
+            last = (copied, total)
+        })
+        .unwrap();
+
+        assert_eq!(result.bytes_copied, data.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), data);
+        if result.method != CopyMethod::Clone {
+            assert_eq!(last, (data.len() as u64, data.len() as u64));
+        }
+
+        let empty = temp.path().join("empty.log");
+        fs::write(&empty, b"").unwrap();
+        let result = copy_file(&empty, &temp.path().join("empty_copy.log")).unwrap();
+        assert_eq!(result.bytes_copied, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_virtual_files_fall_back_to_buffered_copy() {
+        let temp = TempDir::new().unwrap();
+        let dest = temp.path().join("status");
+
+        let result = copy_file(Path::new("/proc/self/status"), &dest).unwrap();
+
+        assert_eq!(result.method, CopyMethod::Buffered);
+        assert!(result.bytes_copied > 0);
+        assert_eq!(fs::metadata(&dest).unwrap().len(), result.bytes_copied);
+    }
+
+    #[test]
+    fn test_cancelled_copy_stops() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("journal");
+        fs::write(&source, patterned(8192)).unwrap();
+        let source_str = source.to_string_lossy().to_string();
+
+        cancellation::cancel(&source_str);
+        let result = copy_file(&source, &temp.path().join("journal.copy"));
+        cancellation::resume(&source_str);
+
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+    }
+
+    #[test]
+    fn test_sparse_copy_preserves_holes() {
+        use crate::collectors::platforms::sparse::tests::{create_sparse_file, SPARSE_LEN};
+
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("UsnJrnl_J");
+        let dest = temp.path().join("UsnJrnl_J.copy");
+        create_sparse_file(&source);
+
+        let result = copy_file(&source, &dest).unwrap();
+
+        assert_eq!(result.bytes_copied, SPARSE_LEN);
+        assert_eq!(fs::read(&dest).unwrap(), fs::read(&source).unwrap());
+        if result.method == CopyMethod::Sparse {
+            let allocated = sparse::allocated_size(&dest).unwrap();
+            assert!(
+                allocated < SPARSE_LEN / 8,
+                "copy allocated {} bytes",
+                allocated
+            );
+        }
+    }
+
+    #[test]
+    fn test_progress_log_steps() {
+        let mut log = ProgressLog::new(Path::new("/var/log/journal/system.journal"));
+        log.update(COPY_PROGRESS_THRESHOLD / 4, COPY_PROGRESS_THRESHOLD);
+        assert_eq!(log.next_percent, 30);
+        log.update(COPY_PROGRESS_THRESHOLD / 4 + 1, COPY_PROGRESS_THRESHOLD);
+        assert_eq!(log.next_percent, 30);
+
+        let mut small = ProgressLog::new(Path::new("/etc/hosts"));
+        small.update(100, 100);
+        assert_eq!(small.next_percent, 10);
+    }
+}
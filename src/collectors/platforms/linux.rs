@@ -7,12 +7,21 @@ use log::{debug, info, warn};
 use tokio::task;
 
 use crate::collectors::collector::ArtifactCollector;
+use crate::collectors::footprint::run_command;
+use crate::collectors::linux::containers::{collect_containers, ContainerRuntime};
+use crate::collectors::linux::immutable_files::collect_immutable_files;
+use crate::collectors::linux::kernel_messages::collect_dmesg;
+use crate::collectors::linux::sshd_config::collect_ssh_configuration;
 use crate::collectors::platforms::common::FallbackCollector;
-use crate::config::parse_unix_env_vars;
-use crate::config::{Artifact, ArtifactType, LinuxArtifactType};
+use crate::collectors::streaming::stream_command_output;
+use crate::config::resolve_all_variables;
+use crate::config::{Artifact, ArtifactType, ContainerType, LinuxArtifactType};
 use crate::constants::PROC_PATH;
 use crate::models::ArtifactMetadata;
 use crate::privileges::is_elevated;
+use crate::security::credential_scrubber::{
+    scrub_volatile_command_line, scrub_volatile_text, volatile_scrubbing_enabled,
+};
 // Path validation is handled by the FallbackCollector
 
 /// Linux-specific artifact collector
@@ -31,9 +40,7 @@ impl LinuxCollector {
         }
 
         // Check for journalctl availability
-        let has_journalctl = Command::new("which")
-            .arg("journalctl")
-            .output()
+        let has_journalctl = run_command(Command::new("which").arg("journalctl"))
             .map(|output| output.status.success())
             .unwrap_or(false);
 
@@ -50,11 +57,7 @@ impl LinuxCollector {
     fn collect_syslogs(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting system logs");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect journal logs using journalctl
@@ -68,12 +71,28 @@ impl LinuxCollector {
         }
 
         // Use journalctl to export logs
-        let output = Command::new("journalctl")
+        let mut command = Command::new("journalctl");
+        command
             .arg("--no-pager")
             .arg("--output=json")
-            .arg("--since=yesterday")
-            .output()
-            .context("Failed to execute journalctl")?;
+            .arg("--since=yesterday");
+
+        // When streaming an upload, pipe the export straight into the
+        // archive. Scrubbing needs the whole export, so it still goes
+        // through memory.
+        if !volatile_scrubbing_enabled() {
+            if let Some(streamed) = stream_command_output(&mut command, dest) {
+                let streamed = streamed.context("Failed to execute journalctl")?;
+                if streamed.status.success() {
+                    return Ok(streamed.metadata(source));
+                }
+                let error = String::from_utf8_lossy(&streamed.stderr);
+                warn!("journalctl command failed: {}", error);
+                return self.fallback.collect_directory(source, dest);
+            }
+        }
+
+        let output = run_command(&mut command).context("Failed to execute journalctl")?;
 
         if !output.status.success() {
             let error = String::from_utf8_lossy(&output.stderr);
@@ -83,8 +102,14 @@ impl LinuxCollector {
             return self.fallback.collect_directory(source, dest);
         }
 
+        let journal = if volatile_scrubbing_enabled() {
+            scrub_journal_export(&output.stdout)
+        } else {
+            output.stdout
+        };
+
         // Write the output to the destination file
-        fs::write(dest, output.stdout).context(format!(
+        fs::write(dest, journal).context(format!(
             "Failed to write journal logs to {}",
             dest.display()
         ))?;
@@ -121,6 +146,8 @@ impl LinuxCollector {
             accessed_time,
             modified_time,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         Ok(artifact_metadata)
@@ -181,15 +208,13 @@ impl LinuxCollector {
                 accessed_time,
                 modified_time,
                 is_locked: false,
+                sha256: None,
+                ..Default::default()
             });
         }
 
         // For other proc entries, try standard collection
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect audit logs
@@ -202,11 +227,7 @@ impl LinuxCollector {
     fn collect_cron(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting cron jobs");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect bash history
@@ -219,33 +240,75 @@ impl LinuxCollector {
     fn collect_package_logs(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting package manager logs");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect systemd units
     fn collect_systemd(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting systemd units");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
+    }
+
+    /// Collect persistence locations (PAM, init scripts, profiles, udev rules)
+    fn collect_persistence(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        info!("Collecting persistence location {}", source.display());
+
+        self.fallback.collect_path(source, dest)
+    }
+
+    /// Collect sshd configuration and authorized keys; `source` is `/etc/ssh`
+    fn collect_sshd_config(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        let collected = collect_ssh_configuration(source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect every container of `runtime`; `source` is the runtime's
+    /// container storage directory
+    fn collect_containers(
+        &self,
+        runtime: ContainerRuntime,
+        source: &Path,
+        dest: &Path,
+    ) -> Result<ArtifactMetadata> {
+        let collected = collect_containers(runtime, source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
     }
 }
 
 #[async_trait::async_trait]
 impl ArtifactCollector for LinuxCollector {
-    async fn collect(&self, artifact: &Artifact, output_dir: &Path) -> Result<ArtifactMetadata> {
-        let source_path = PathBuf::from(parse_unix_env_vars(&artifact.source_path));
+    async fn collect(&self, artifact: &Artifact, output_path: &Path) -> Result<ArtifactMetadata> {
+        let (fallback, source_path) = self.fallback.for_source_path(&artifact.source_path)?;
+        let source_path = PathBuf::from(resolve_all_variables(source_path));
 
         // Use the output directory directly instead of joining with destination name
         // The path structure is now handled by the main collector function
-        let output_path = output_dir.to_path_buf();
+        let output_path = output_path.to_path_buf();
 
         debug!(
             "Collecting {} from {} to {}",
@@ -255,13 +318,22 @@ impl ArtifactCollector for LinuxCollector {
         );
 
         // Clone self and data for the async block
-        let collector = self.clone();
+        let mut collector = self.clone();
+        collector.fallback = fallback;
         let source_path_clone = source_path.clone();
         let output_path_clone = output_path.clone();
         let artifact_type = artifact.artifact_type.clone();
 
         // Choose appropriate collection method based on artifact type
         let result = task::spawn_blocking(move || {
+            // Type-specific collection needs the live system; other sources
+            // only hold plain files and directories
+            if !collector.fallback.source().is_live_system() {
+                return collector
+                    .fallback
+                    .collect_path(&source_path_clone, &output_path_clone);
+            }
+
             match &artifact_type {
                 ArtifactType::Linux(LinuxArtifactType::SysLogs) => {
                     collector.collect_syslogs(&source_path_clone, &output_path_clone)
@@ -289,17 +361,34 @@ impl ArtifactCollector for LinuxCollector {
                 ArtifactType::Linux(LinuxArtifactType::Systemd) => {
                     collector.collect_systemd(&source_path_clone, &output_path_clone)
                 }
+                ArtifactType::Linux(LinuxArtifactType::Persistence) => {
+                    collector.collect_persistence(&source_path_clone, &output_path_clone)
+                }
+                ArtifactType::Linux(LinuxArtifactType::SSHDConfig) => {
+                    collector.collect_sshd_config(&source_path_clone, &output_path_clone)
+                }
+                // Read with dmesg; the source path is only informational
+                ArtifactType::Linux(LinuxArtifactType::Dmesg) => collect_dmesg(&output_path_clone),
+                ArtifactType::Linux(LinuxArtifactType::ImmutableFiles) => {
+                    collect_immutable_files(&source_path_clone, &output_path_clone)
+                }
+                ArtifactType::Container(ContainerType::DockerContainerLogs) => collector
+                    .collect_containers(
+                        ContainerRuntime::Docker,
+                        &source_path_clone,
+                        &output_path_clone,
+                    ),
+                ArtifactType::Container(ContainerType::PodmanContainerLogs) => collector
+                    .collect_containers(
+                        ContainerRuntime::Podman,
+                        &source_path_clone,
+                        &output_path_clone,
+                    ),
                 _ => {
                     // For other artifact types, use standard file collection
-                    if source_path_clone.is_dir() {
-                        collector
-                            .fallback
-                            .collect_directory(&source_path_clone, &output_path_clone)
-                    } else {
-                        collector
-                            .fallback
-                            .collect_standard_file(&source_path_clone, &output_path_clone)
-                    }
+                    collector
+                        .fallback
+                        .collect_path(&source_path_clone, &output_path_clone)
                 }
             }
         })
@@ -313,12 +402,15 @@ impl ArtifactCollector for LinuxCollector {
         matches!(
             artifact_type,
             ArtifactType::Linux(_)
+                | ArtifactType::Container(_)
                 | ArtifactType::FileSystem
                 | ArtifactType::Logs
                 | ArtifactType::UserData
                 | ArtifactType::SystemInfo
                 | ArtifactType::Memory
                 | ArtifactType::Network
+                | ArtifactType::CloudCredentials
+                | ArtifactType::MailStores
                 | ArtifactType::Custom
         )
     }
@@ -333,6 +425,42 @@ impl Clone for LinuxCollector {
     }
 }
 
+/// Scrub secrets from the string fields of `journalctl --output=json` lines.
+///
+/// `_CMDLINE` is scrubbed as a process command line, split on whitespace.
+/// Lines that are not JSON objects, and fields journald exports as byte
+/// arrays, are kept as they are.
+fn scrub_journal_export(export: &[u8]) -> Vec<u8> {
+    let mut scrubbed = Vec::with_capacity(export.len());
+    for line in export.split_inclusive(|&b| b == b'\n') {
+        let (content, newline) = match line.strip_suffix(b"\n") {
+            Some(content) => (content, &b"\n"[..]),
+            None => (line, &b""[..]),
+        };
+        match serde_json::from_slice::<serde_json::Value>(content) {
+            Ok(serde_json::Value::Object(mut fields)) => {
+                for (key, value) in fields.iter_mut() {
+                    if let serde_json::Value::String(text) = value {
+                        *text = if key == "_CMDLINE" {
+                            let args = text.split_whitespace().map(str::to_string).collect();
+                            scrub_volatile_command_line(args).join(" ")
+                        } else {
+                            scrub_volatile_text(text)
+                        };
+                    }
+                }
+                match serde_json::to_vec(&fields) {
+                    Ok(json) => scrubbed.extend_from_slice(&json),
+                    Err(_) => scrubbed.extend_from_slice(content),
+                }
+            }
+            _ => scrubbed.extend_from_slice(content),
+        }
+        scrubbed.extend_from_slice(newline);
+    }
+    scrubbed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,6 +486,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_scrub_journal_export() {
+        let export = concat!(
+            r#"{"MESSAGE":"login with password=hunter2","_CMDLINE":"sshpass -p hunter2 ssh host","PRIORITY":"6"}"#,
+            "\n",
+            r#"{"MESSAGE":[104,105],"_PID":"42"}"#,
+            "\n",
+            "not json\n"
+        );
+        let scrubbed = String::from_utf8(scrub_journal_export(export.as_bytes())).unwrap();
+        let lines: Vec<&str> = scrubbed.lines().collect();
+        assert_eq!(lines.len(), 3);
+
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["MESSAGE"], "login with password=***SCRUBBED***");
+        assert_eq!(first["_CMDLINE"], "sshpass -p ***SCRUBBED*** ssh host");
+        assert_eq!(first["PRIORITY"], "6");
+        assert_eq!(lines[1], r#"{"MESSAGE":[104,105],"_PID":"42"}"#);
+        assert_eq!(lines[2], "not json");
+    }
+
     #[test]
     fn test_supports_artifact_type() {
         let collector = LinuxCollector::new();
@@ -382,6 +531,8 @@ mod tests {
         assert!(collector.supports_artifact_type(&ArtifactType::Memory));
         assert!(collector.supports_artifact_type(&ArtifactType::Network));
         assert!(collector.supports_artifact_type(&ArtifactType::Custom));
+        assert!(collector
+            .supports_artifact_type(&ArtifactType::Container(ContainerType::DockerContainerLogs)));
 
         // Unsupported types
         assert!(!collector.supports_artifact_type(&ArtifactType::Windows(
@@ -529,6 +680,10 @@ mod tests {
             (LinuxArtifactType::Dpkg, "Dpkg"),
             (LinuxArtifactType::Yum, "Yum"),
             (LinuxArtifactType::Systemd, "Systemd"),
+            (LinuxArtifactType::Persistence, "Persistence"),
+            (LinuxArtifactType::SSHDConfig, "SSHDConfig"),
+            (LinuxArtifactType::Dmesg, "Dmesg"),
+            (LinuxArtifactType::ImmutableFiles, "ImmutableFiles"),
         ];
 
         for (linux_type, name) in test_cases {
@@ -606,6 +761,33 @@ mod tests {
         assert!(output_path.join("job2").exists());
     }
 
+    #[tokio::test]
+    async fn test_collect_persistence_directory() {
+        let collector = LinuxCollector::new();
+        let temp_dir = TempDir::new().unwrap();
+
+        let pam_dir = temp_dir.path().join("etc").join("pam.d");
+        fs::create_dir_all(&pam_dir).unwrap();
+        fs::write(pam_dir.join("sshd"), "auth required pam_unix.so\n").unwrap();
+
+        let artifact = Artifact {
+            name: "pam.d".to_string(),
+            artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+            source_path: pam_dir.to_string_lossy().to_string(),
+            destination_name: "pam.d".to_string(),
+            description: Some("PAM configuration".to_string()),
+            required: false,
+            metadata: std::collections::HashMap::new(),
+            regex: None,
+        };
+
+        let output_path = temp_dir.path().join("output").join("pam.d");
+        let result = collector.collect(&artifact, &output_path).await;
+
+        assert!(result.is_ok());
+        assert!(output_path.join("sshd").exists());
+    }
+
     #[tokio::test]
     async fn test_collect_journal_fallback() {
         let collector = LinuxCollector::new();
@@ -0,0 +1,353 @@
+//! Metadata-only listings of directory artifacts.
+//!
+//! When an artifact sets `metadata_only: "true"`, the directory walker hands
+//! each selected file to a [`MetadataListing`] instead of copying it. The
+//! listing is written to the artifact's destination directory as JSON Lines,
+//! one [`ListingEntry`] per file, as the walk goes, so trees with millions
+//! of files are never held in memory. Per-extension counts and recursive
+//! per-directory size rollups are kept in a [`ListingSummary`] written next
+//! to it when the walk finishes.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::platforms::common::{rfc3339, DirectorySink};
+use crate::collectors::source::{block_on, ArtifactSource, SourceMetadata};
+use crate::utils::hash::sha256_reader;
+
+/// JSON Lines listing written to the artifact destination
+pub const METADATA_LISTING_FILE: &str = "metadata_listing.jsonl";
+
+/// Extension and directory rollups written next to the listing
+pub const METADATA_LISTING_SUMMARY_FILE: &str = "metadata_listing_summary.json";
+
+/// Extension key for files without one
+const NO_EXTENSION: &str = "(none)";
+
+/// Directory key for the artifact source itself
+const ROOT_DIRECTORY: &str = ".";
+
+/// One listed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingEntry {
+    pub path: String,
+    pub size: u64,
+    pub created_time: Option<String>,
+    pub accessed_time: Option<String>,
+    pub modified_time: Option<String>,
+    /// Owning user id, on Unix
+    pub owner_uid: Option<u32>,
+    /// Only with `metadata_hashes: "true"`, and only if the file could be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// File count and total size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeRollup {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl SizeRollup {
+    fn add(&mut self, size: u64) {
+        self.files += 1;
+        self.bytes += size;
+    }
+}
+
+/// Totals for a listing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingSummary {
+    pub source: String,
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    /// Keyed by lowercase extension, `(none)` for files without one
+    pub extensions: BTreeMap<String, SizeRollup>,
+    /// Files at any depth below each directory, keyed by its path relative
+    /// to the source (`.` for the source itself)
+    pub directory_sizes: BTreeMap<String, SizeRollup>,
+}
+
+/// Directory sink writing a listing instead of copying files
+pub struct MetadataListing {
+    source: Arc<dyn ArtifactSource>,
+    hash_files: bool,
+    writer: BufWriter<File>,
+    summary_path: PathBuf,
+    summary: ListingSummary,
+}
+
+impl MetadataListing {
+    /// Start a listing of `source_root` in the directory `dest`
+    pub fn create(
+        dest: &Path,
+        source_root: &Path,
+        source: Arc<dyn ArtifactSource>,
+        hash_files: bool,
+    ) -> Result<Self> {
+        let listing_path = dest.join(METADATA_LISTING_FILE);
+        let file = File::create(&listing_path)
+            .context(format!("Failed to create {}", listing_path.display()))?;
+
+        let mut summary = ListingSummary {
+            source: source_root.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        summary
+            .directory_sizes
+            .insert(ROOT_DIRECTORY.to_string(), SizeRollup::default());
+
+        Ok(Self {
+            source,
+            hash_files,
+            writer: BufWriter::new(file),
+            summary_path: dest.join(METADATA_LISTING_SUMMARY_FILE),
+            summary,
+        })
+    }
+
+    /// Flush the listing and write the summary
+    pub fn finish(mut self) -> Result<ListingSummary> {
+        self.writer
+            .flush()
+            .context("Failed to write metadata listing")?;
+
+        let json = serde_json::to_string_pretty(&self.summary)
+            .context("Failed to serialize metadata listing summary")?;
+        std::fs::write(&self.summary_path, json)
+            .context(format!("Failed to write {}", self.summary_path.display()))?;
+
+        info!(
+            "Listed {} files ({} bytes) under {} without copying them",
+            self.summary.files, self.summary.bytes, self.summary.source
+        );
+        Ok(self.summary)
+    }
+
+    fn sha256(&self, path: &Path) -> Option<String> {
+        let hashed = block_on(self.source.open(path)).and_then(sha256_reader);
+        hashed
+            .map_err(|e| debug!("Could not hash {}: {}", path.display(), e))
+            .ok()
+    }
+}
+
+/// `relative` with `/` separators
+fn directory_key(relative: &Path) -> String {
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+impl DirectorySink for MetadataListing {
+    fn directory(&mut self, relative: &Path) -> Result<()> {
+        self.summary.directories += 1;
+        self.summary
+            .directory_sizes
+            .insert(directory_key(relative), SizeRollup::default());
+        Ok(())
+    }
+
+    fn file(&mut self, path: &Path, relative: &Path, metadata: &SourceMetadata) -> Result<()> {
+        let entry = ListingEntry {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len,
+            created_time: rfc3339(metadata.created),
+            accessed_time: rfc3339(metadata.accessed),
+            modified_time: rfc3339(metadata.modified),
+            owner_uid: metadata.uid,
+            sha256: self.hash_files.then(|| self.sha256(path)).flatten(),
+        };
+        serde_json::to_writer(&mut self.writer, &entry)
+            .context("Failed to write metadata listing")?;
+        self.writer
+            .write_all(b"\n")
+            .context("Failed to write metadata listing")?;
+
+        self.summary.files += 1;
+        self.summary.bytes += metadata.len;
+
+        let extension = relative
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| NO_EXTENSION.to_string());
+        self.summary
+            .extensions
+            .entry(extension)
+            .or_default()
+            .add(metadata.len);
+
+        for ancestor in relative.ancestors().skip(1) {
+            let key = match directory_key(ancestor) {
+                key if key.is_empty() => ROOT_DIRECTORY.to_string(),
+                key => key,
+            };
+            self.summary
+                .directory_sizes
+                .entry(key)
+                .or_default()
+                .add(metadata.len);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::platforms::common::{FallbackCollector, WalkOptions};
+    use crate::config::{Artifact, ArtifactType};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn artifact(metadata: &[(&str, &str)]) -> Artifact {
+        Artifact {
+            name: "Documents".to_string(),
+            artifact_type: ArtifactType::FileSystem,
+            source_path: "/home/bob/Documents".to_string(),
+            destination_name: "documents".to_string(),
+            description: None,
+            required: false,
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            regex: None,
+        }
+    }
+
+    #[test]
+    fn test_walk_options_from_artifact() {
+        let walk = WalkOptions::from_artifact(&artifact(&[])).unwrap();
+        assert!(walk.is_default());
+
+        let walk = WalkOptions::from_artifact(&artifact(&[
+            ("metadata_only", "TRUE"),
+            ("exclude_pattern", r"^cache/"),
+            ("modified_after", "2024-01-01T00:00:00Z"),
+        ]))
+        .unwrap();
+        assert!(!walk.is_default());
+        assert!(walk.metadata_only);
+        assert!(!walk.hash_files);
+        assert!(walk.modified_after.is_some());
+
+        for bad in [
+            ("metadata_only", "yes"),
+            ("exclude_pattern", "("),
+            ("modified_before", "yesterday"),
+        ] {
+            assert!(WalkOptions::from_artifact(&artifact(&[bad])).is_err());
+        }
+    }
+
+    #[test]
+    fn test_metadata_listing_copies_nothing() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("Documents");
+        for (name, data) in [
+            ("budget.xlsx", &b"12345"[..]),
+            ("notes.TXT", b"abc"),
+            ("projects/plan.txt", b"plan"),
+            ("projects/2024/README", b"readme!"),
+            ("cache/blob.bin", b"skipped"),
+        ] {
+            let path = source.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, data).unwrap();
+        }
+        let dest = temp.path().join("out");
+
+        let walk = WalkOptions::from_artifact(&artifact(&[
+            ("metadata_only", "true"),
+            ("metadata_hashes", "true"),
+            ("exclude_pattern", "^cache$"),
+        ]))
+        .unwrap();
+        let metadata = FallbackCollector::new()
+            .with_walk_options(walk)
+            .collect_directory(&source, &dest)
+            .unwrap();
+        assert_eq!(metadata.file_size, 0);
+        assert_eq!(metadata.listed_files, Some(4));
+
+        let mut written: Vec<String> = fs::read_dir(&dest)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        written.sort();
+        assert_eq!(
+            written,
+            vec![METADATA_LISTING_FILE, METADATA_LISTING_SUMMARY_FILE]
+        );
+
+        let entries: Vec<ListingEntry> = fs::read_to_string(dest.join(METADATA_LISTING_FILE))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 4);
+        let budget = entries
+            .iter()
+            .find(|e| e.path.ends_with("budget.xlsx"))
+            .unwrap();
+        assert_eq!(budget.size, 5);
+        assert!(budget.modified_time.is_some());
+        assert_eq!(
+            budget.sha256.as_deref(),
+            Some("5994471abb01112afcc18159f6cc74b4f511b99806da59b3caf5a9c173cacfc5")
+        );
+
+        let summary: ListingSummary = serde_json::from_str(
+            &fs::read_to_string(dest.join(METADATA_LISTING_SUMMARY_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            (summary.files, summary.bytes, summary.directories),
+            (4, 19, 2)
+        );
+        assert_eq!(summary.extensions["txt"], SizeRollup { files: 2, bytes: 7 });
+        assert_eq!(summary.extensions[NO_EXTENSION].files, 1);
+        assert_eq!(
+            summary.directory_sizes["."],
+            SizeRollup {
+                files: 4,
+                bytes: 19
+            }
+        );
+        assert_eq!(
+            summary.directory_sizes["projects"],
+            SizeRollup {
+                files: 2,
+                bytes: 11
+            }
+        );
+        assert_eq!(summary.directory_sizes["projects/2024"].bytes, 7);
+        assert!(!summary.directory_sizes.contains_key("cache"));
+
+        // Time filters apply to copies too
+        let future =
+            chrono::DateTime::<chrono::Utc>::from(SystemTime::now() + Duration::from_secs(3600));
+        let walk = WalkOptions::from_artifact(&artifact(&[(
+            "modified_after",
+            future.to_rfc3339().as_str(),
+        )]))
+        .unwrap();
+        let copy_dest = temp.path().join("copy");
+        FallbackCollector::new()
+            .with_walk_options(walk)
+            .collect_directory(&source, &copy_dest)
+            .unwrap();
+        assert!(copy_dest.join("projects/2024").is_dir());
+        assert!(!copy_dest.join("projects/plan.txt").exists());
+    }
+}
@@ -7,8 +7,17 @@ use log::{debug, info, warn};
 use tokio::task;
 
 use crate::collectors::collector::ArtifactCollector;
+use crate::collectors::footprint::run_command;
+use crate::collectors::macos::airport_wifi::{collect_wifi_history, WIFI_HISTORY_FILE};
+use crate::collectors::macos::app_firewall::{collect_app_firewall_state, APP_FIREWALL_FILE};
+use crate::collectors::macos::keychains::collect_keychains;
+use crate::collectors::macos::login_records::collect_login_records;
+use crate::collectors::macos::unified_log_parser::{
+    parse_unified_logs, unified_log_hours, UNIFIED_LOG_FILE,
+};
 use crate::collectors::platforms::common::FallbackCollector;
-use crate::config::parse_unix_env_vars;
+use crate::collectors::streaming::stream_command_output;
+use crate::config::resolve_all_variables;
 use crate::config::{Artifact, ArtifactType, MacOSArtifactType};
 use crate::models::ArtifactMetadata;
 use crate::privileges::is_elevated;
@@ -29,9 +38,7 @@ impl MacOSCollector {
         }
 
         // Check for log command availability
-        let has_log_cmd = Command::new("which")
-            .arg("log")
-            .output()
+        let has_log_cmd = run_command(Command::new("which").arg("log"))
             .map(|output| output.status.success())
             .unwrap_or(false);
 
@@ -40,9 +47,7 @@ impl MacOSCollector {
         }
 
         // Check for plutil command availability
-        let has_plutil = Command::new("which")
-            .arg("plutil")
-            .output()
+        let has_plutil = run_command(Command::new("which").arg("plutil"))
             .map(|output| output.status.success())
             .unwrap_or(false);
 
@@ -59,9 +64,16 @@ impl MacOSCollector {
     fn collect_unified_logs(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting unified logs");
 
-        // If source is a directory, collect it normally
+        // If source is a directory, collect it normally and add a JSONL view
+        // of the recent events, which the raw tracev3 files cannot give alone
         if source.is_dir() {
-            return self.fallback.collect_directory(source, dest);
+            let mut metadata = self.fallback.collect_directory(source, dest)?;
+            let jsonl = dest.join(UNIFIED_LOG_FILE);
+            match parse_unified_logs(source, &jsonl, unified_log_hours()) {
+                Ok(_) => metadata.file_size += fs::metadata(&jsonl).map(|m| m.len()).unwrap_or(0),
+                Err(e) => warn!("Unified log conversion failed, raw logs kept: {:#}", e),
+            }
+            return Ok(metadata);
         }
 
         // If source is a file, collect it normally
@@ -77,12 +89,27 @@ impl MacOSCollector {
         }
 
         // Use log command to export logs
-        let output = Command::new("log")
+        let mut command = Command::new("log");
+        command
             .arg("show")
             .arg("--style=json")
-            .arg("--last=1d")
-            .output()
-            .context("Failed to execute log command")?;
+            .arg(format!("--last={}h", unified_log_hours()));
+
+        // When streaming an upload, pipe the export straight into the archive
+        if let Some(streamed) = stream_command_output(&mut command, dest) {
+            let streamed = streamed.context("Failed to execute log command")?;
+            if streamed.status.success() {
+                return Ok(streamed.metadata(source));
+            }
+            let error = String::from_utf8_lossy(&streamed.stderr);
+            warn!("log command failed: {}", error);
+            if source.exists() {
+                return self.fallback.collect_standard_file(source, dest);
+            }
+            return Err(anyhow::anyhow!("Failed to collect unified logs"));
+        }
+
+        let output = run_command(&mut command).context("Failed to execute log command")?;
 
         if !output.status.success() {
             let error = String::from_utf8_lossy(&output.stderr);
@@ -134,6 +161,8 @@ impl MacOSCollector {
             accessed_time,
             modified_time,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         Ok(artifact_metadata)
@@ -143,11 +172,7 @@ impl MacOSCollector {
     fn collect_fsevents(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting FSEvents");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect property list files
@@ -162,9 +187,7 @@ impl MacOSCollector {
         // If source is a file, try to convert binary plist to XML if needed
         if source.is_file() {
             // Check if it's a binary plist
-            let is_binary = Command::new("file")
-                .arg(source)
-                .output()
+            let is_binary = run_command(Command::new("file").arg(source))
                 .map(|output| {
                     let output_str = String::from_utf8_lossy(&output.stdout);
                     output_str.contains("binary property list")
@@ -181,14 +204,15 @@ impl MacOSCollector {
                 }
 
                 // Convert binary plist to XML
-                let output = Command::new("plutil")
-                    .arg("-convert")
-                    .arg("xml1")
-                    .arg("-o")
-                    .arg(dest)
-                    .arg(source)
-                    .output()
-                    .context("Failed to execute plutil command")?;
+                let output = run_command(
+                    Command::new("plutil")
+                        .arg("-convert")
+                        .arg("xml1")
+                        .arg("-o")
+                        .arg(dest)
+                        .arg(source),
+                )
+                .context("Failed to execute plutil command")?;
 
                 if !output.status.success() {
                     let error = String::from_utf8_lossy(&output.stderr);
@@ -230,6 +254,8 @@ impl MacOSCollector {
                     accessed_time,
                     modified_time,
                     is_locked: false,
+                    sha256: None,
+                    ..Default::default()
                 };
 
                 return Ok(artifact_metadata);
@@ -249,11 +275,7 @@ impl MacOSCollector {
     fn collect_spotlight(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting Spotlight metadata");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect Quarantine database
@@ -272,33 +294,103 @@ impl MacOSCollector {
     fn collect_launch_agents(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting Launch Agents");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
     }
 
     /// Collect Launch Daemons
     fn collect_launch_daemons(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         info!("Collecting Launch Daemons");
 
-        if source.is_dir() {
-            self.fallback.collect_directory(source, dest)
-        } else {
-            self.fallback.collect_standard_file(source, dest)
-        }
+        self.fallback.collect_path(source, dest)
+    }
+
+    /// Collect keychains; `source` is the home directory of the user
+    fn collect_keychains(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        let collected = collect_keychains(source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect the Application Firewall state; `source` is `socketfilterfw`
+    fn collect_app_firewall(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        collect_app_firewall_state(dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: collection_time.clone(),
+            file_size: fs::metadata(dest.join(APP_FIREWALL_FILE))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            created_time: Some(collection_time),
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect login records; `source` is the ASL store directory
+    fn collect_login_records(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        let collected = collect_login_records(source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect Wi-Fi connection history; `source` is the airport preferences
+    /// plist, and the known-networks plists are found from fixed locations
+    fn collect_wifi_history(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        collect_wifi_history(dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: collection_time.clone(),
+            file_size: fs::metadata(dest.join(WIFI_HISTORY_FILE))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            created_time: Some(collection_time),
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
     }
 }
 
 #[async_trait::async_trait]
 impl ArtifactCollector for MacOSCollector {
-    async fn collect(&self, artifact: &Artifact, output_dir: &Path) -> Result<ArtifactMetadata> {
-        let source_path = PathBuf::from(parse_unix_env_vars(&artifact.source_path));
+    async fn collect(&self, artifact: &Artifact, output_path: &Path) -> Result<ArtifactMetadata> {
+        let (fallback, source_path) = self.fallback.for_source_path(&artifact.source_path)?;
+        let source_path = PathBuf::from(resolve_all_variables(source_path));
 
         // Use the output directory directly instead of joining with destination name
         // The path structure is now handled by the main collector function
-        let output_path = output_dir.to_path_buf();
+        let output_path = output_path.to_path_buf();
 
         debug!(
             "Collecting {} from {} to {}",
@@ -308,13 +400,22 @@ impl ArtifactCollector for MacOSCollector {
         );
 
         // Clone self and data for the async block
-        let collector = self.clone();
+        let mut collector = self.clone();
+        collector.fallback = fallback;
         let source_path_clone = source_path.clone();
         let output_path_clone = output_path.clone();
         let artifact_type = artifact.artifact_type.clone();
 
         // Choose appropriate collection method based on artifact type
         let result = task::spawn_blocking(move || {
+            // Type-specific collection needs the live system; other sources
+            // only hold plain files and directories
+            if !collector.fallback.source().is_live_system() {
+                return collector
+                    .fallback
+                    .collect_path(&source_path_clone, &output_path_clone);
+            }
+
             match &artifact_type {
                 ArtifactType::MacOS(MacOSArtifactType::UnifiedLogs) => {
                     collector.collect_unified_logs(&source_path_clone, &output_path_clone)
@@ -340,17 +441,23 @@ impl ArtifactCollector for MacOSCollector {
                 ArtifactType::MacOS(MacOSArtifactType::LaunchDaemons) => {
                     collector.collect_launch_daemons(&source_path_clone, &output_path_clone)
                 }
+                ArtifactType::MacOS(MacOSArtifactType::Keychains) => {
+                    collector.collect_keychains(&source_path_clone, &output_path_clone)
+                }
+                ArtifactType::MacOS(MacOSArtifactType::AppFirewall) => {
+                    collector.collect_app_firewall(&source_path_clone, &output_path_clone)
+                }
+                ArtifactType::MacOS(MacOSArtifactType::LoginRecords) => {
+                    collector.collect_login_records(&source_path_clone, &output_path_clone)
+                }
+                ArtifactType::MacOS(MacOSArtifactType::WiFiHistory) => {
+                    collector.collect_wifi_history(&source_path_clone, &output_path_clone)
+                }
                 _ => {
                     // For other artifact types, use standard file collection
-                    if source_path_clone.is_dir() {
-                        collector
-                            .fallback
-                            .collect_directory(&source_path_clone, &output_path_clone)
-                    } else {
-                        collector
-                            .fallback
-                            .collect_standard_file(&source_path_clone, &output_path_clone)
-                    }
+                    collector
+                        .fallback
+                        .collect_path(&source_path_clone, &output_path_clone)
                 }
             }
         })
@@ -370,6 +477,8 @@ impl ArtifactCollector for MacOSCollector {
                 | ArtifactType::SystemInfo
                 | ArtifactType::Memory
                 | ArtifactType::Network
+                | ArtifactType::CloudCredentials
+                | ArtifactType::MailStores
                 | ArtifactType::Custom
         )
     }
@@ -569,6 +678,18 @@ mod tests {
             (MacOSArtifactType::KnowledgeC, "KnowledgeC"),
             (MacOSArtifactType::LaunchAgents, "LaunchAgents"),
             (MacOSArtifactType::LaunchDaemons, "LaunchDaemons"),
+            (MacOSArtifactType::Keychains, "Keychains"),
+            (
+                MacOSArtifactType::BackgroundTaskManagement,
+                "BackgroundTaskManagement",
+            ),
+            (
+                MacOSArtifactType::ConfigurationProfiles,
+                "ConfigurationProfiles",
+            ),
+            (MacOSArtifactType::AppFirewall, "AppFirewall"),
+            (MacOSArtifactType::LoginRecords, "LoginRecords"),
+            (MacOSArtifactType::WiFiHistory, "WiFiHistory"),
         ];
 
         for (macos_type, name) in test_cases {
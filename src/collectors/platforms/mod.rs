@@ -1,6 +1,11 @@
 pub mod common;
+pub mod encryption;
+pub mod fast_copy;
 pub mod linux;
+pub mod listing;
 pub mod macos;
+pub mod network_mounts;
+pub mod sparse;
 pub mod windows;
 
 use log::info;
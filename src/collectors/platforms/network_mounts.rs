@@ -0,0 +1,180 @@
+//! Remote path prefix to local mount point mapping.
+//!
+//! Artifacts on NFS or SMB shares are often referenced in configs by their
+//! remote path (`\\fileserver\logs`, `nfs01:/export/home`). When the share is
+//! mounted locally, `--network-mount-point <remote>=<local>` registers a
+//! mapping and [`FallbackCollector`](super::common::FallbackCollector)
+//! rewrites matching source paths before opening them, so configs do not
+//! need to be edited per environment.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use log::{debug, info};
+
+lazy_static! {
+    static ref NETWORK_MOUNTS: RwLock<Vec<NetworkMount>> = RwLock::new(Vec::new());
+}
+
+/// A single remote prefix to local mount mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkMount {
+    /// Remote path prefix as it appears in artifact source paths
+    pub remote_path: String,
+    /// Local directory where the remote path is mounted
+    pub local_mount: PathBuf,
+}
+
+impl NetworkMount {
+    /// Parse a `<remote_path>=<local_mount>` specification
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (remote, local) = spec.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "Invalid network mount '{}': expected <remote_path>=<local_mount>",
+                spec
+            )
+        })?;
+
+        let remote = remote.trim();
+        let local = local.trim();
+        if remote.is_empty() || local.is_empty() {
+            return Err(anyhow!(
+                "Invalid network mount '{}': remote path and local mount must not be empty",
+                spec
+            ));
+        }
+
+        Ok(Self {
+            remote_path: remote.to_string(),
+            local_mount: PathBuf::from(local),
+        })
+    }
+
+    /// Map `source` onto the local mount if it falls under the remote prefix
+    pub fn resolve(&self, source: &str) -> Option<PathBuf> {
+        let source = normalize(source);
+        let remote = normalize(&self.remote_path);
+        let remote = remote.trim_end_matches('/');
+
+        let rest = if source == remote {
+            ""
+        } else {
+            source.strip_prefix(remote)?.strip_prefix('/')?
+        };
+
+        let mut local = self.local_mount.clone();
+        for component in rest.split('/').filter(|c| !c.is_empty()) {
+            local.push(component);
+        }
+        Some(local)
+    }
+}
+
+/// Treat both separators alike so UNC and Unix-style remotes match either way
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Replace the registered mappings; an empty list clears them.
+///
+/// Longer remote prefixes take precedence over shorter ones.
+pub fn register_network_mounts(mut mounts: Vec<NetworkMount>) {
+    if let Ok(mut registry) = NETWORK_MOUNTS.write() {
+        for mount in &mounts {
+            info!(
+                "Mapping network path {} to local mount {}",
+                mount.remote_path,
+                mount.local_mount.display()
+            );
+        }
+        mounts.sort_by_key(|m| std::cmp::Reverse(m.remote_path.len()));
+        *registry = mounts;
+    }
+}
+
+/// Rewrite `source` to its local mount location if a mapping applies
+pub fn resolve_network_path(source: &Path) -> Option<PathBuf> {
+    let registry = NETWORK_MOUNTS.read().ok()?;
+    if registry.is_empty() {
+        return None;
+    }
+
+    let source_str = source.to_string_lossy();
+    let resolved = registry.iter().find_map(|m| m.resolve(&source_str))?;
+    debug!(
+        "Resolved network path {} to {}",
+        source.display(),
+        resolved.display()
+    );
+    Some(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mount_spec() {
+        let mount = NetworkMount::parse("//fileserver/logs=/mnt/logs").unwrap();
+        assert_eq!(mount.remote_path, "//fileserver/logs");
+        assert_eq!(mount.local_mount, PathBuf::from("/mnt/logs"));
+
+        assert!(NetworkMount::parse("no-separator").is_err());
+        assert!(NetworkMount::parse("=/mnt/logs").is_err());
+        assert!(NetworkMount::parse("//fileserver/logs=").is_err());
+    }
+
+    #[test]
+    fn test_resolve_unc_prefix() {
+        let mount = NetworkMount::parse(r"\\fileserver\logs=/mnt/logs").unwrap();
+
+        assert_eq!(
+            mount.resolve(r"\\fileserver\logs\app\today.log"),
+            Some(PathBuf::from("/mnt/logs").join("app").join("today.log"))
+        );
+        assert_eq!(
+            mount.resolve(r"\\fileserver\logs"),
+            Some(PathBuf::from("/mnt/logs"))
+        );
+    }
+
+    #[test]
+    fn test_fallback_collector_reads_through_mount() {
+        use crate::collectors::platforms::common::FallbackCollector;
+        use tempfile::TempDir;
+
+        let mount_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        std::fs::write(mount_dir.path().join("share.log"), "remote content").unwrap();
+
+        let remote = format!("//test-server-{}/share", uuid::Uuid::new_v4());
+        register_network_mounts(vec![NetworkMount {
+            remote_path: remote.clone(),
+            local_mount: mount_dir.path().to_path_buf(),
+        }]);
+
+        let source = PathBuf::from(format!("{}/share.log", remote));
+        let dest = output_dir.path().join("share.log");
+        let metadata = FallbackCollector::new()
+            .collect_standard_file(&source, &dest)
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "remote content");
+        // Provenance keeps the path as written in the config
+        assert_eq!(metadata.original_path, source.to_string_lossy());
+    }
+
+    #[test]
+    fn test_resolve_requires_component_boundary() {
+        let mount = NetworkMount::parse("/export/home=/mnt/home").unwrap();
+
+        assert_eq!(mount.resolve("/export/homework/file"), None);
+        assert_eq!(mount.resolve("/var/log/syslog"), None);
+        assert_eq!(
+            mount.resolve("/export/home/alice/.bash_history"),
+            Some(PathBuf::from("/mnt/home/alice/.bash_history"))
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Sparse file detection.
+//!
+//! The USN journal (`$UsnJrnl:$J`), pagefiles and VM disk images are mostly
+//! holes: a journal holding 200 MB of records can report a 30 GB logical
+//! size. Copying such files naively reads and writes every zero. This module
+//! finds the allocated extents so copies and archives can skip the holes:
+//!
+//! - **Linux**: `SEEK_DATA` / `SEEK_HOLE`
+//! - **Windows**: `FSCTL_QUERY_ALLOCATED_RANGES`, with `FSCTL_SET_SPARSE` to
+//!   keep the destination sparse
+//!
+//! Other platforms report every file as dense.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+/// Allocated extents of `file`, or `None` if the file has no holes or the
+/// file system cannot report them.
+///
+/// Ranges are sorted, non-overlapping and clamped to `len`. On Linux the
+/// file offset is rewound to the start afterwards.
+pub fn data_ranges(file: &File, len: u64) -> io::Result<Option<Vec<Range<u64>>>> {
+    if len == 0 {
+        return Ok(None);
+    }
+
+    #[cfg(target_os = "linux")]
+    let ranges = linux_impl::data_ranges(file, len)?;
+    #[cfg(target_os = "windows")]
+    let ranges = windows_impl::data_ranges(file, len)?;
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    let ranges: Option<Vec<Range<u64>>> = {
+        let _ = file;
+        None
+    };
+
+    Ok(ranges.and_then(|ranges| normalize_ranges(ranges, len)))
+}
+
+/// Merge adjacent ranges and clamp them to `len`.
+///
+/// Returns `None` when the ranges cover the whole file, so dense files take
+/// the normal copy path.
+pub fn normalize_ranges(mut ranges: Vec<Range<u64>>, len: u64) -> Option<Vec<Range<u64>>> {
+    ranges.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        let range = range.start.min(len)..range.end.min(len);
+        if range.is_empty() {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    let allocated: u64 = merged.iter().map(|r| r.end - r.start).sum();
+    if allocated >= len {
+        None
+    } else {
+        Some(merged)
+    }
+}
+
+/// Bytes the file system has allocated for `path`, if it can tell
+pub fn allocated_size(path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // st_blocks is always in 512-byte units
+        std::fs::metadata(path).ok().map(|m| m.blocks() * 512)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::allocated_size(path)
+    }
+    #[cfg(not(any(unix, target_os = "windows")))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Prepare `file` to hold holes. Unix file systems create holes on seek, so
+/// this only does work on Windows.
+pub fn set_sparse(file: &File) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::set_sparse(file)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = file;
+        Ok(())
+    }
+}
+
+/// Reader that yields zeros for holes without reading them from disk.
+///
+/// Dense files are read straight through.
+pub struct SparseReader {
+    file: File,
+    ranges: Option<Vec<Range<u64>>>,
+    len: u64,
+    pos: u64,
+    /// Whether the file offset matches `pos`
+    in_sync: bool,
+}
+
+impl SparseReader {
+    pub fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        let ranges = data_ranges(&file, len).unwrap_or(None);
+        Ok(Self {
+            file,
+            ranges,
+            len,
+            pos: 0,
+            in_sync: true,
+        })
+    }
+
+    /// Whether holes are being skipped
+    pub fn is_sparse(&self) -> bool {
+        self.ranges.is_some()
+    }
+}
+
+impl Read for SparseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(ranges) = &self.ranges else {
+            return self.file.read(buf);
+        };
+
+        // Length of the hole at `pos`, or the bytes left in its data range
+        let (hole, limit) = match ranges.iter().find(|r| r.end > self.pos) {
+            Some(range) if range.start > self.pos => (true, range.start - self.pos),
+            Some(range) => (false, range.end - self.pos),
+            None if self.pos < self.len => (true, self.len - self.pos),
+            None => (false, u64::MAX),
+        };
+        let want = buf.len().min(usize::try_from(limit).unwrap_or(usize::MAX));
+
+        if hole {
+            buf[..want].fill(0);
+            self.pos += want as u64;
+            self.in_sync = false;
+            return Ok(want);
+        }
+
+        if !self.in_sync {
+            self.file.seek(SeekFrom::Start(self.pos))?;
+            self.in_sync = true;
+        }
+        let read = self.file.read(&mut buf[..want])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux_impl {
+    use std::fs::File;
+    use std::io;
+    use std::ops::Range;
+    use std::os::unix::io::AsRawFd;
+
+    pub fn data_ranges(file: &File, len: u64) -> io::Result<Option<Vec<Range<u64>>>> {
+        let fd = file.as_raw_fd();
+        let mut ranges = Vec::new();
+        let mut pos: u64 = 0;
+
+        while pos < len {
+            // SAFETY: lseek on a file descriptor owned by `file`
+            let start = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+            if start < 0 {
+                let error = io::Error::last_os_error();
+                match error.raw_os_error() {
+                    // No data past `pos`: the rest of the file is a hole
+                    Some(libc::ENXIO) => break,
+                    // SEEK_DATA unsupported by this file system
+                    Some(libc::EINVAL) | Some(libc::EOPNOTSUPP) => {
+                        rewind(fd);
+                        return Ok(None);
+                    }
+                    _ => {
+                        rewind(fd);
+                        return Err(error);
+                    }
+                }
+            }
+
+            // SAFETY: as above
+            let end = unsafe { libc::lseek(fd, start, libc::SEEK_HOLE) };
+            if end < 0 {
+                let error = io::Error::last_os_error();
+                rewind(fd);
+                return Err(error);
+            }
+
+            if end <= start {
+                // Seek is a no-op on this file (virtual file systems)
+                rewind(fd);
+                return Ok(None);
+            }
+
+            ranges.push(start as u64..end as u64);
+            pos = end as u64;
+        }
+
+        rewind(fd);
+        Ok(Some(ranges))
+    }
+
+    fn rewind(fd: i32) {
+        // SAFETY: lseek on a valid descriptor; failure leaves the offset as is
+        unsafe { libc::lseek(fd, 0, libc::SEEK_SET) };
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::fs::File;
+    use std::io;
+    use std::mem;
+    use std::ops::Range;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+    use std::ptr;
+
+    use winapi::shared::minwindef::{DWORD, FALSE, LPVOID};
+    use winapi::shared::winerror::{ERROR_MORE_DATA, NO_ERROR};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::fileapi::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::{
+        FILE_ALLOCATED_RANGE_BUFFER, FSCTL_QUERY_ALLOCATED_RANGES, FSCTL_SET_SPARSE,
+    };
+    use winapi::um::winnt::HANDLE;
+
+    /// Ranges returned per `FSCTL_QUERY_ALLOCATED_RANGES` call
+    const RANGES_PER_QUERY: usize = 256;
+
+    pub fn data_ranges(file: &File, len: u64) -> io::Result<Option<Vec<Range<u64>>>> {
+        let handle = file.as_raw_handle() as HANDLE;
+        let mut ranges = Vec::new();
+        let mut offset = 0u64;
+
+        loop {
+            // SAFETY: FILE_ALLOCATED_RANGE_BUFFER is plain data
+            let mut query: FILE_ALLOCATED_RANGE_BUFFER = unsafe { mem::zeroed() };
+            unsafe {
+                *query.FileOffset.QuadPart_mut() = offset as i64;
+                *query.Length.QuadPart_mut() = (len - offset) as i64;
+            }
+            let mut output: Vec<FILE_ALLOCATED_RANGE_BUFFER> =
+                vec![unsafe { mem::zeroed() }; RANGES_PER_QUERY];
+            let mut returned: DWORD = 0;
+
+            // SAFETY: input and output buffers are sized as passed
+            let ok = unsafe {
+                DeviceIoControl(
+                    handle,
+                    FSCTL_QUERY_ALLOCATED_RANGES,
+                    &mut query as *mut _ as LPVOID,
+                    mem::size_of::<FILE_ALLOCATED_RANGE_BUFFER>() as DWORD,
+                    output.as_mut_ptr() as LPVOID,
+                    (output.len() * mem::size_of::<FILE_ALLOCATED_RANGE_BUFFER>()) as DWORD,
+                    &mut returned,
+                    ptr::null_mut(),
+                )
+            };
+            let status = if ok != FALSE {
+                NO_ERROR
+            } else {
+                unsafe { GetLastError() }
+            };
+            if status != NO_ERROR && status != ERROR_MORE_DATA {
+                // Not NTFS/ReFS, or the handle does not allow the query
+                return Ok(None);
+            }
+
+            let count = returned as usize / mem::size_of::<FILE_ALLOCATED_RANGE_BUFFER>();
+            for entry in &output[..count] {
+                // SAFETY: union fields written by the file system
+                let (start, length) =
+                    unsafe { (*entry.FileOffset.QuadPart(), *entry.Length.QuadPart()) };
+                ranges.push(start as u64..(start + length) as u64);
+            }
+
+            if status == NO_ERROR || count == 0 {
+                break;
+            }
+            offset = ranges.last().map(|r| r.end).unwrap_or(len);
+            if offset >= len {
+                break;
+            }
+        }
+
+        Ok(Some(ranges))
+    }
+
+    pub fn set_sparse(file: &File) -> io::Result<()> {
+        let mut returned: DWORD = 0;
+        // SAFETY: FSCTL_SET_SPARSE without an input buffer sets the flag
+        let ok = unsafe {
+            DeviceIoControl(
+                file.as_raw_handle() as HANDLE,
+                FSCTL_SET_SPARSE,
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                0,
+                &mut returned,
+                ptr::null_mut(),
+            )
+        };
+        if ok == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn allocated_size(path: &Path) -> Option<u64> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut high: DWORD = 0;
+        // SAFETY: NUL-terminated path and a valid out pointer
+        let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+        if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+            return None;
+        }
+        Some(((high as u64) << 32) | low as u64)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Logical size of the sparse test file
+    pub(crate) const SPARSE_LEN: u64 = 64 * 1024 * 1024;
+
+    /// Create a 64 MiB file with three 4 KiB data islands
+    pub(crate) fn create_sparse_file(path: &Path) -> Vec<(u64, Vec<u8>)> {
+        let islands: Vec<(u64, Vec<u8>)> = [0, 32 * 1024 * 1024, SPARSE_LEN - 4096]
+            .iter()
+            .enumerate()
+            .map(|(i, &offset)| (offset, vec![b'A' + i as u8; 4096]))
+            .collect();
+
+        let mut file = File::create(path).unwrap();
+        file.set_len(SPARSE_LEN).unwrap();
+        for (offset, data) in &islands {
+            file.seek(SeekFrom::Start(*offset)).unwrap();
+            file.write_all(data).unwrap();
+        }
+        file.sync_all().unwrap();
+        islands
+    }
+
+    #[test]
+    fn test_normalize_ranges() {
+        let whole_file = 0..100;
+        assert_eq!(normalize_ranges(vec![whole_file], 100), None);
+        assert_eq!(
+            normalize_ranges(vec![50..60, 0..10, 10..20, 90..200], 100),
+            Some(vec![0..20, 50..60, 90..100])
+        );
+        assert_eq!(normalize_ranges(vec![], 100), Some(vec![]));
+    }
+
+    #[test]
+    fn test_data_ranges_cover_islands() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("UsnJrnl_J");
+        let islands = create_sparse_file(&path);
+
+        let file = File::open(&path).unwrap();
+        let Some(ranges) = data_ranges(&file, SPARSE_LEN).unwrap() else {
+            // File system without hole support
+            return;
+        };
+        for (offset, data) in &islands {
+            let end = offset + data.len() as u64;
+            assert!(ranges.iter().any(|r| r.start <= *offset && r.end >= end));
+        }
+        let allocated: u64 = ranges.iter().map(|r| r.end - r.start).sum();
+        assert!(allocated < SPARSE_LEN / 2);
+    }
+
+    #[test]
+    fn test_sparse_reader_matches_contents() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("pagefile.sys");
+        create_sparse_file(&path);
+
+        let mut contents = Vec::new();
+        SparseReader::new(File::open(&path).unwrap())
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, fs::read(&path).unwrap());
+    }
+}
@@ -1,3 +1,4 @@
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
@@ -5,10 +6,19 @@ use log::{debug, info, warn};
 use tokio::task;
 
 use crate::collectors::collector::ArtifactCollector;
-use crate::config::parse_windows_env_vars;
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::collectors::windows::browser_extensions;
+use crate::collectors::windows::event_tracing::enumerate_etw_sessions;
+use crate::collectors::windows::network_shares::collect_network_shares;
+use crate::collectors::windows::office_macros::collect_from_appdata;
+use crate::collectors::windows::paging_files;
+use crate::collectors::windows::powershell_history::collect_powershell_history;
+use crate::config::resolve_all_variables;
 use crate::config::{Artifact, ArtifactType, WindowsArtifactType};
 use crate::models::ArtifactMetadata;
-use crate::windows::{check_backup_api_available, collect_with_raw_handle};
+use crate::windows::{
+    check_backup_api_available, collect_paging_file, collect_with_raw_handle, efs, vss,
+};
 
 /// Windows-specific artifact collector
 pub struct WindowsCollector {
@@ -39,6 +49,18 @@ impl WindowsCollector {
         collect_with_raw_handle(&source.to_string_lossy(), dest)
     }
 
+    /// Pass on the `attempt` to read `source` directly, or read the file
+    /// from a shadow copy if another process held it locked; `--disable-vss`
+    /// turns the fallback off
+    pub fn collect_with_shadow_copy(
+        &self,
+        attempt: Result<ArtifactMetadata>,
+        source: &Path,
+        dest: &Path,
+    ) -> Result<ArtifactMetadata> {
+        vss::fall_back_on_lock(attempt, &source.to_string_lossy(), dest)
+    }
+
     /// Collect registry hive using raw file access
     fn collect_registry(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
         if self.has_backup_api {
@@ -78,16 +100,220 @@ impl WindowsCollector {
         }
         collect_with_raw_handle(&source.to_string_lossy(), dest)
     }
+
+    /// Collect the SRUM database using raw file access (held open by the DPS service)
+    fn collect_srum(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        if self.has_backup_api {
+            info!("Collecting SRUM database using raw file access with Backup API");
+        } else {
+            info!("Collecting SRUM database using raw file access (Backup API unavailable)");
+        }
+        collect_with_raw_handle(&source.to_string_lossy(), dest)
+    }
+
+    /// Collect the BITS job database directory using raw file access (held open by BITS)
+    fn collect_bits(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        if self.has_backup_api {
+            info!("Collecting BITS job database using raw file access with Backup API");
+        } else {
+            info!("Collecting BITS job database using raw file access (Backup API unavailable)");
+        }
+        collect_with_raw_handle(&source.to_string_lossy(), dest)
+    }
+
+    /// Collect scheduled task XML files using raw file access
+    fn collect_scheduled_tasks(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        if self.has_backup_api {
+            info!("Collecting scheduled task files using raw file access with Backup API");
+        } else {
+            info!("Collecting scheduled task files using raw file access (Backup API unavailable)");
+        }
+        collect_with_raw_handle(&source.to_string_lossy(), dest)
+    }
+
+    /// Collect the WMI repository using raw file access (held open by Winmgmt)
+    fn collect_wmi_repository(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        if self.has_backup_api {
+            info!("Collecting WMI repository using raw file access with Backup API");
+        } else {
+            info!("Collecting WMI repository using raw file access (Backup API unavailable)");
+        }
+        collect_with_raw_handle(&source.to_string_lossy(), dest)
+    }
+
+    /// Collect a page, swap or hibernation file using raw file access,
+    /// capped at `size_cap` bytes
+    fn collect_paging_file(
+        &self,
+        source: &Path,
+        dest: &Path,
+        size_cap: Option<u64>,
+    ) -> Result<ArtifactMetadata> {
+        if self.has_backup_api {
+            info!("Collecting paging file using raw file access with Backup API");
+        } else {
+            info!("Collecting paging file using raw file access (Backup API unavailable)");
+        }
+        collect_paging_file(&source.to_string_lossy(), dest, size_cap)
+    }
+
+    /// Enumerate live ETW sessions and write them as JSON
+    fn collect_etw_sessions(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        info!("Enumerating ETW trace sessions");
+
+        let sessions = enumerate_etw_sessions()?;
+        info!("Found {} ETW sessions", sessions.len());
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .context(format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let json =
+            serde_json::to_string_pretty(&sessions).context("Failed to serialize ETW sessions")?;
+        fs::write(dest, json).context(format!(
+            "Failed to write ETW sessions to {}",
+            dest.display()
+        ))?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: collection_time.clone(),
+            file_size: fs::metadata(dest).map(|m| m.len()).unwrap_or(0),
+            created_time: Some(collection_time),
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Enumerate SMB shares and connected network resources and write them as JSON
+    fn collect_network_shares(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        info!("Enumerating SMB shares");
+
+        let report = collect_network_shares()?;
+        let permissive = report.shares.iter().filter(|s| s.overly_permissive).count();
+        info!(
+            "Found {} shares and {} connected network resources",
+            report.shares.len(),
+            report.connected_resources.len()
+        );
+        if permissive > 0 {
+            warn!("{} shares grant Everyone full control", permissive);
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .context(format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let json =
+            serde_json::to_string_pretty(&report).context("Failed to serialize network shares")?;
+        fs::write(dest, json).context(format!(
+            "Failed to write network shares to {}",
+            dest.display()
+        ))?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: collection_time.clone(),
+            file_size: fs::metadata(dest).map(|m| m.len()).unwrap_or(0),
+            created_time: Some(collection_time),
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect Office startup files; `source` is the user's roaming AppData folder
+    fn collect_office_startup(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        let collected = collect_from_appdata(source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Collect PowerShell history, profiles and transcripts of every user;
+    /// `source` is the Users folder
+    fn collect_powershell_history(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        let collected = collect_powershell_history(source, dest)?;
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: collected.iter().map(|m| m.file_size).sum(),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+
+    /// Enumerate browser extensions of every user; `source` is the Users folder
+    fn collect_browser_extensions(&self, source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+        browser_extensions::enumerate_from(source, dest)?;
+        let report = dest.join(browser_extensions::BROWSER_EXTENSIONS_FILE);
+
+        let collection_time = chrono::Utc::now().to_rfc3339();
+        Ok(ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time,
+            file_size: fs::metadata(&report).map(|m| m.len()).unwrap_or(0),
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        })
+    }
+}
+
+/// Whether an artifact type is collected by opening its source path, as
+/// opposed to live queries and per-user searches, and may fall back on a
+/// shadow copy or a raw EFS read. Shadow copies leave out page and
+/// hibernation files, so those have no fallback.
+fn reads_source_file(artifact_type: &ArtifactType) -> bool {
+    !matches!(
+        artifact_type,
+        ArtifactType::Windows(
+            WindowsArtifactType::ETWSessions
+                | WindowsArtifactType::NetworkShares
+                | WindowsArtifactType::OfficeStartup
+                | WindowsArtifactType::PowerShellHistory
+                | WindowsArtifactType::PagingFile
+                | WindowsArtifactType::BrowserExtensions
+        )
+    )
 }
 
 #[async_trait::async_trait]
 impl ArtifactCollector for WindowsCollector {
-    async fn collect(&self, artifact: &Artifact, output_dir: &Path) -> Result<ArtifactMetadata> {
-        let source_path = PathBuf::from(parse_windows_env_vars(&artifact.source_path));
+    async fn collect(&self, artifact: &Artifact, output_path: &Path) -> Result<ArtifactMetadata> {
+        let (fallback, source_path) =
+            FallbackCollector::new().for_source_path(&artifact.source_path)?;
+        let source_path = PathBuf::from(resolve_all_variables(source_path));
 
         // Use the output directory directly instead of joining with destination name
         // The path structure is now handled by the main collector function
-        let output_path = output_dir.to_path_buf();
+        let output_path = output_path.to_path_buf();
 
         debug!(
             "Collecting {} from {} to {}",
@@ -101,41 +327,92 @@ impl ArtifactCollector for WindowsCollector {
         let source_path_clone = source_path.clone();
         let output_path_clone = output_path.clone();
         let artifact_type = artifact.artifact_type.clone();
+        let size_cap = paging_files::size_cap(artifact);
 
         // Choose appropriate collection method based on artifact type
-        let result = task::spawn_blocking(move || {
-            match &artifact_type {
-                ArtifactType::Windows(WindowsArtifactType::MFT) => {
-                    collector.collect_mft(&source_path_clone, &output_path_clone)
-                }
-                ArtifactType::Windows(WindowsArtifactType::Registry) => {
-                    collector.collect_registry(&source_path_clone, &output_path_clone)
-                }
-                ArtifactType::Windows(WindowsArtifactType::EventLog) => {
-                    collector.collect_eventlog(&source_path_clone, &output_path_clone)
-                }
-                ArtifactType::Windows(WindowsArtifactType::Prefetch) => {
-                    collector.collect_prefetch(&source_path_clone, &output_path_clone)
+        let result =
+            task::spawn_blocking(move || {
+                // Raw and Backup API reads need the live system; other sources
+                // only hold plain files and directories
+                if !fallback.source().is_live_system() {
+                    return fallback.collect_path(&source_path_clone, &output_path_clone);
                 }
-                ArtifactType::Windows(WindowsArtifactType::USNJournal) => {
-                    collector.collect_usn_journal(&source_path_clone, &output_path_clone)
-                }
-                _ => {
-                    // For other artifact types, use raw file access
-                    if collector.has_backup_api {
-                        debug!("Using Backup API for generic file collection");
-                    } else {
-                        debug!("Using standard file access (Backup API unavailable)");
-                    }
-                    collect_with_raw_handle(
+
+                let result =
+                    match &artifact_type {
+                        ArtifactType::Windows(WindowsArtifactType::MFT) => {
+                            collector.collect_mft(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::Registry) => {
+                            collector.collect_registry(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::EventLog) => {
+                            collector.collect_eventlog(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::Prefetch) => {
+                            collector.collect_prefetch(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::USNJournal) => {
+                            collector.collect_usn_journal(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::ETWSessions) => {
+                            collector.collect_etw_sessions(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::NetworkShares) => {
+                            collector.collect_network_shares(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::SRUM) => {
+                            collector.collect_srum(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::BITS) => {
+                            collector.collect_bits(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::ScheduledTasks) => collector
+                            .collect_scheduled_tasks(&source_path_clone, &output_path_clone),
+                        ArtifactType::Windows(WindowsArtifactType::WMIRepository) => {
+                            collector.collect_wmi_repository(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::OfficeStartup) => {
+                            collector.collect_office_startup(&source_path_clone, &output_path_clone)
+                        }
+                        ArtifactType::Windows(WindowsArtifactType::PowerShellHistory) => collector
+                            .collect_powershell_history(&source_path_clone, &output_path_clone),
+                        ArtifactType::Windows(WindowsArtifactType::PagingFile) => collector
+                            .collect_paging_file(&source_path_clone, &output_path_clone, size_cap),
+                        ArtifactType::Windows(WindowsArtifactType::BrowserExtensions) => collector
+                            .collect_browser_extensions(&source_path_clone, &output_path_clone),
+                        _ => {
+                            // For other artifact types, use raw file access
+                            if collector.has_backup_api {
+                                debug!("Using Backup API for generic file collection");
+                            } else {
+                                debug!("Using standard file access (Backup API unavailable)");
+                            }
+                            collect_with_raw_handle(
+                                &source_path_clone.to_string_lossy(),
+                                &output_path_clone,
+                            )
+                        }
+                    };
+
+                // Files another process holds locked are read from a shadow
+                // copy, and EFS-encrypted files are captured raw
+                if reads_source_file(&artifact_type) {
+                    efs::fall_back_on_encrypted(
+                        collector.collect_with_shadow_copy(
+                            result,
+                            &source_path_clone,
+                            &output_path_clone,
+                        ),
                         &source_path_clone.to_string_lossy(),
                         &output_path_clone,
                     )
+                } else {
+                    result
                 }
-            }
-        })
-        .await
-        .context("Task join error")??;
+            })
+            .await
+            .context("Task join error")??;
 
         Ok(result)
     }
@@ -150,6 +427,8 @@ impl ArtifactCollector for WindowsCollector {
                 | ArtifactType::SystemInfo
                 | ArtifactType::Memory
                 | ArtifactType::Network
+                | ArtifactType::CloudCredentials
+                | ArtifactType::MailStores
                 | ArtifactType::Custom
         )
     }
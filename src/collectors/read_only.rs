@@ -0,0 +1,388 @@
+//! Read-only access to source files.
+//!
+//! Every evidence file the collector reads is opened through [`open`], so
+//! no collector can end up holding a writable handle on a source by
+//! accident. The open requests read access only:
+//!
+//! - Linux: `O_RDONLY | O_NOATIME`, so reads do not update the access time.
+//!   The kernel only allows `O_NOATIME` to the file's owner or with
+//!   `CAP_FOWNER`; otherwise the file is opened `O_RDONLY` alone. Times are
+//!   never restored afterwards, since that would itself be a write.
+//! - Windows: `GENERIC_READ` with `FILE_FLAG_BACKUP_SEMANTICS`, sharing
+//!   read, write and delete so other processes are not blocked.
+//! - Elsewhere: a plain read-only open.
+//!
+//! With `--verify-no-writes` ([`set_verification`]) each source's
+//! modification time, change time and size are recorded when it is first
+//! opened and again when its last handle closes. Sources whose stamp
+//! changed are listed in `no_writes_report.json` with the flags they were
+//! opened with; most such changes are concurrent system activity, which a
+//! read-only open cannot cause. Windows does not expose the change time
+//! through the standard library, so only the modification time and size
+//! are compared there.
+//!
+//! Like the footprint tracker, the verification state is process-global so
+//! collectors do not need it threaded through.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use serde::Serialize;
+
+use crate::collectors::footprint::record_created_path;
+
+/// Name of the `--verify-no-writes` report written into the output directory
+pub const NO_WRITES_REPORT_FILE: &str = "no_writes_report.json";
+
+#[cfg(target_os = "windows")]
+const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+
+lazy_static! {
+    /// Stamps of every source opened, when verification is enabled
+    static ref OBSERVED: Mutex<Option<BTreeMap<PathBuf, Observed>>> = Mutex::new(None);
+}
+
+/// Modification time, change time and size of a source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceStamp {
+    pub modified: Option<String>,
+    /// Inode change time; not available on Windows
+    pub changed: Option<String>,
+    pub size: u64,
+}
+
+impl SourceStamp {
+    fn from_metadata(metadata: &Metadata) -> Self {
+        SourceStamp {
+            modified: metadata.modified().ok().map(format_time),
+            changed: change_time(metadata),
+            size: metadata.len(),
+        }
+    }
+}
+
+/// A source whose stamp differed after collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedSource {
+    pub path: String,
+    /// Flags the source was first opened with, e.g. `O_RDONLY|O_NOATIME`
+    pub open_flags: String,
+    pub before: SourceStamp,
+    pub after: SourceStamp,
+}
+
+/// Result of `--verify-no-writes`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoWritesReport {
+    pub files_checked: usize,
+    pub changed: Vec<ChangedSource>,
+}
+
+#[derive(Debug, Clone)]
+struct Observed {
+    open_flags: &'static str,
+    before: SourceStamp,
+    after: Option<SourceStamp>,
+}
+
+/// A source file opened read-only.
+///
+/// Dereferences to the underlying [`File`] for APIs that need a handle
+/// (sparse range queries, `copy_file_range`); that handle is read-only.
+#[derive(Debug)]
+pub struct SourceFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl Deref for SourceFile {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl DerefMut for SourceFile {
+    fn deref_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+impl Read for SourceFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Seek for SourceFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for SourceFile {
+    fn drop(&mut self) {
+        let Ok(mut observed) = OBSERVED.lock() else {
+            return;
+        };
+        let Some(entry) = observed.as_mut().and_then(|o| o.get_mut(&self.path)) else {
+            return;
+        };
+        // Stat the handle rather than the path so a replaced file is not
+        // mistaken for this one
+        match self.file.metadata() {
+            Ok(metadata) => entry.after = Some(SourceStamp::from_metadata(&metadata)),
+            Err(e) => debug!("Cannot stat {} after reading: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Open `path` for reading only.
+///
+/// Errors keep their `io::ErrorKind`, so callers can tell permission
+/// problems apart.
+pub fn open(path: &Path) -> io::Result<SourceFile> {
+    let (file, open_flags) = open_read_only(path)?;
+    observe(path, &file, open_flags);
+    Ok(SourceFile {
+        file,
+        path: path.to_path_buf(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn open_read_only(path: &Path) -> io::Result<(File, &'static str)> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    match OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOATIME)
+        .open(path)
+    {
+        Ok(file) => Ok((file, "O_RDONLY|O_NOATIME")),
+        // Only the owner or CAP_FOWNER may suppress access time updates
+        Err(e) if e.raw_os_error() == Some(libc::EPERM) => OpenOptions::new()
+            .read(true)
+            .open(path)
+            .map(|file| (file, "O_RDONLY")),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn open_read_only(path: &Path) -> io::Result<(File, &'static str)> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+    const FILE_SHARE_DELETE: u32 = 0x4;
+
+    OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .map(|file| (file, "GENERIC_READ|FILE_FLAG_BACKUP_SEMANTICS"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn open_read_only(path: &Path) -> io::Result<(File, &'static str)> {
+    OpenOptions::new()
+        .read(true)
+        .open(path)
+        .map(|file| (file, "O_RDONLY"))
+}
+
+/// Record the stamp of a source the first time it is opened
+fn observe(path: &Path, file: &File, open_flags: &'static str) {
+    let Ok(mut observed) = OBSERVED.lock() else {
+        return;
+    };
+    let Some(observed) = observed.as_mut() else {
+        return;
+    };
+    if let Some(entry) = observed.get(path) {
+        if entry.open_flags != open_flags {
+            debug!(
+                "{} opened with {} after {}",
+                path.display(),
+                open_flags,
+                entry.open_flags
+            );
+        }
+        return;
+    }
+    match file.metadata() {
+        Ok(metadata) => {
+            observed.insert(
+                path.to_path_buf(),
+                Observed {
+                    open_flags,
+                    before: SourceStamp::from_metadata(&metadata),
+                    after: None,
+                },
+            );
+        }
+        Err(e) => debug!("Cannot stat {} before reading: {}", path.display(), e),
+    }
+}
+
+/// Record each source's stamp before and after it is read
+/// (`--verify-no-writes`). Enabling starts a new, empty record; disabling
+/// drops it.
+pub fn set_verification(enabled: bool) {
+    if let Ok(mut observed) = OBSERVED.lock() {
+        *observed = if enabled {
+            info!("Recording source timestamps and sizes to verify no writes");
+            Some(BTreeMap::new())
+        } else {
+            None
+        };
+    }
+}
+
+/// Sources whose stamp changed while they were collected, or `None` when
+/// verification is not enabled.
+///
+/// Sources still open are stamped by path.
+pub fn report() -> Option<NoWritesReport> {
+    let observed = OBSERVED.lock().ok()?.clone()?;
+    let files_checked = observed.len();
+    let changed = observed
+        .into_iter()
+        .filter_map(|(path, entry)| {
+            let after = entry.after.or_else(|| {
+                fs::metadata(&path)
+                    .ok()
+                    .map(|m| SourceStamp::from_metadata(&m))
+            })?;
+            (after != entry.before).then(|| ChangedSource {
+                path: path.to_string_lossy().to_string(),
+                open_flags: entry.open_flags.to_string(),
+                before: entry.before,
+                after,
+            })
+        })
+        .collect();
+    Some(NoWritesReport {
+        files_checked,
+        changed,
+    })
+}
+
+/// Write the `--verify-no-writes` report to `output_dir`, if verification
+/// is enabled
+pub fn write_report(output_dir: &Path) -> Result<Option<NoWritesReport>> {
+    let Some(report) = report() else {
+        return Ok(None);
+    };
+    let path = output_dir.join(NO_WRITES_REPORT_FILE);
+    record_created_path(&path);
+
+    let json =
+        serde_json::to_string_pretty(&report).context("Failed to serialize no-writes report")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+    if report.changed.is_empty() {
+        info!(
+            "No changes to {} source files read-only; report written to {}",
+            report.files_checked,
+            path.display()
+        );
+    } else {
+        warn!(
+            "{} of {} sources changed while being collected read-only; see {}",
+            report.changed.len(),
+            report.files_checked,
+            path.display()
+        );
+    }
+    Ok(Some(report))
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+#[cfg(unix)]
+fn change_time(metadata: &Metadata) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    DateTime::<Utc>::from_timestamp(metadata.ctime(), metadata.ctime_nsec() as u32)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Nanos, true))
+}
+
+#[cfg(not(unix))]
+fn change_time(_metadata: &Metadata) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_open_is_read_only() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("evidence.log");
+        fs::write(&path, b"original").unwrap();
+
+        let mut source = open(&path).unwrap();
+        let mut content = String::new();
+        source.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "original");
+        assert!(source.write_all(b"tampered").is_err());
+        assert!(source.set_len(0).is_err());
+        drop(source);
+
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(open(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_report_lists_changed_sources() {
+        set_verification(true);
+        let dir = TempDir::new().unwrap();
+        let unchanged = dir.path().join("unchanged.log");
+        let changed = dir.path().join("changed.log");
+        fs::write(&unchanged, b"quiet").unwrap();
+        fs::write(&changed, b"busy").unwrap();
+
+        drop(open(&unchanged).unwrap());
+        let source = open(&changed).unwrap();
+        // Concurrent activity while the source is being collected
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&changed)
+            .unwrap()
+            .write_all(b" appended")
+            .unwrap();
+        drop(source);
+
+        let written = write_report(dir.path()).unwrap().unwrap();
+        assert!(dir.path().join(NO_WRITES_REPORT_FILE).exists());
+        let changed_path = changed.to_string_lossy().to_string();
+        let entry = written
+            .changed
+            .iter()
+            .find(|c| c.path == changed_path)
+            .unwrap();
+        assert_eq!(entry.before.size, 4);
+        assert_eq!(entry.after.size, 13);
+        assert!(entry.open_flags.starts_with("O_RDONLY"));
+        let unchanged_path = unchanged.to_string_lossy().to_string();
+        assert!(!written.changed.iter().any(|c| c.path == unchanged_path));
+    }
+}
@@ -1,10 +1,12 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Result;
 use log::{debug, info};
 
 use crate::collectors::platforms::common::FallbackCollector;
 use crate::collectors::regex::walker::DirectoryWalker;
+use crate::collectors::source::ArtifactSource;
 use crate::config::Artifact;
 use crate::models::ArtifactMetadata;
 
@@ -21,6 +23,13 @@ impl RegexCollector {
         }
     }
 
+    /// Create a regex collector reading from `source`
+    pub fn with_source(source: Arc<dyn ArtifactSource>) -> Self {
+        RegexCollector {
+            fallback: FallbackCollector::with_source(source),
+        }
+    }
+
     /// Collect artifacts using regex patterns
     pub async fn collect_with_regex(
         &self,
@@ -319,6 +328,35 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_collect_with_regex_refuses_symlinked_output_dir() {
+        let collector = RegexCollector::new();
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+
+        let source_dir = temp_dir.path().join("source");
+        fs::create_dir_all(source_dir.join("subdir")).unwrap();
+        fs::write(source_dir.join("subdir/sub.log"), "sub log").unwrap();
+
+        // A directory in the output tree that points somewhere else
+        let output_dir = temp_dir.path().join("output");
+        fs::create_dir_all(&output_dir).unwrap();
+        std::os::unix::fs::symlink(outside.path(), output_dir.join("subdir")).unwrap();
+
+        let mut artifact = create_test_artifact(true);
+        artifact.source_path = source_dir.to_string_lossy().to_string();
+        if let Some(regex) = artifact.regex.as_mut() {
+            regex.include_pattern = r".*\.log$".to_string();
+        }
+
+        let result = collector
+            .collect_with_regex(&artifact, &source_dir, &output_dir)
+            .await;
+        assert!(result.is_err());
+        assert!(!outside.path().join("sub.log").exists());
+    }
+
     // Helper function to create test artifacts
     fn create_test_artifact(with_regex: bool) -> Artifact {
         Artifact {
@@ -2,6 +2,9 @@ use anyhow::{Context, Result};
 use regex::Regex;
 use std::path::{Path, PathBuf};
 
+use crate::security::config::{log_security_event, SecurityEvent};
+use crate::security::path_validator::validate_destination;
+
 /// Check if a path matches the given regex pattern
 pub fn path_matches_pattern(path: &Path, base_path: &Path, pattern: &Regex) -> bool {
     // Get path relative to base for regex matching
@@ -42,8 +45,15 @@ pub fn create_destination_path(
         source_path.display()
     ))?;
 
-    // Create destination path
-    let dest_path = output_base.join(relative_path);
+    // Create destination path, refusing anything that would land outside
+    // the output tree (e.g. through a symlinked directory already in it)
+    let dest_path = validate_destination(&output_base.join(relative_path), output_base)
+        .inspect_err(|e| {
+            log_security_event(SecurityEvent::PathValidationFailed {
+                path: source_path.display().to_string(),
+                reason: e.to_string(),
+            })
+        })?;
 
     // Create parent directories if they don't exist
     if let Some(parent) = dest_path.parent() {
@@ -1,4 +1,3 @@
-use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
@@ -9,6 +8,7 @@ use crate::collectors::platforms::common::FallbackCollector;
 use crate::collectors::regex::helpers::{
     create_destination_path, path_matches_pattern, should_exclude_path,
 };
+use crate::collectors::source::block_on;
 use crate::models::ArtifactMetadata;
 // Path validation is handled by the FallbackCollector
 
@@ -78,14 +78,11 @@ impl<'a> DirectoryWalker<'a> {
         }
 
         // Check if the path exists and is a directory
-        if !current_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Path does not exist: {}",
-                current_path.display()
-            ));
-        }
+        let source = self.fallback.source();
+        let metadata = block_on(source.stat(current_path))
+            .map_err(|_| anyhow::anyhow!("Path does not exist: {}", current_path.display()))?;
 
-        if !current_path.is_dir() {
+        if !metadata.is_dir {
             return Err(anyhow::anyhow!(
                 "Path is not a directory: {}",
                 current_path.display()
@@ -93,23 +90,23 @@ impl<'a> DirectoryWalker<'a> {
         }
 
         // Read directory entries
-        let entries = fs::read_dir(current_path).context(format!(
+        let entries = block_on(source.list_dir(current_path)).context(format!(
             "Failed to read directory: {}",
             current_path.display()
         ))?;
 
         // Process each entry
-        for entry in entries {
-            let entry = entry.context("Failed to read directory entry")?;
-            let path = entry.path();
-
+        for path in entries {
             // Skip if path should be excluded
             if should_exclude_path(&path, &self.base_path, &self.exclude_regex) {
                 debug!("Skipping excluded path: {}", path.display());
                 continue;
             }
 
-            if path.is_dir() {
+            let is_dir = block_on(source.stat(&path))
+                .map(|m| m.is_dir)
+                .unwrap_or(false);
+            if is_dir {
                 if self.recursive {
                     // Recursively process subdirectory
                     self.walk_directory_recursive(&path, current_depth + 1, results)?;
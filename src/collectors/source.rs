@@ -0,0 +1,340 @@
+//! Where artifact files are read from.
+//!
+//! Collectors read source files through an [`ArtifactSource`] instead of
+//! `std::fs`, so the same artifact definitions can be collected from
+//! something other than the live local filesystem (a shadow copy, a mounted
+//! image, a remote host). [`LocalSource`] is the default.
+//!
+//! The source is chosen per run with [`set_default_source`], or per artifact
+//! with a scheme prefix on `source_path` (`file:///var/log/syslog`,
+//! `vss://...`). `file://` always means the local filesystem; other schemes
+//! must be [`register_source`]d first.
+//!
+//! Like network mounts, the registry is process-global so collectors do not
+//! need it threaded through. Collectors run in blocking tasks and drive the
+//! async methods with [`block_on`].
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::info;
+
+use crate::collectors::footprint::record_bytes_read;
+use crate::collectors::platforms::fast_copy;
+use crate::collectors::platforms::network_mounts::resolve_network_path;
+use crate::collectors::platforms::sparse;
+use crate::collectors::read_only;
+use crate::models::ArtifactMetadata;
+
+/// Scheme of the local filesystem source
+pub const LOCAL_SCHEME: &str = "file";
+
+lazy_static! {
+    static ref SOURCES: RwLock<SourceRegistry> = RwLock::new(SourceRegistry::default());
+}
+
+/// File or directory metadata as reported by a source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMetadata {
+    pub len: u64,
+    pub is_dir: bool,
+    pub created: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    /// Bytes allocated on disk, when the source knows and it differs from `len`
+    pub allocated_size: Option<u64>,
+    /// Owning user id, on Unix
+    pub uid: Option<u32>,
+}
+
+impl From<fs::Metadata> for SourceMetadata {
+    fn from(metadata: fs::Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            is_dir: metadata.is_dir(),
+            created: metadata.created().ok(),
+            accessed: metadata.accessed().ok(),
+            modified: metadata.modified().ok(),
+            allocated_size: None,
+            #[cfg(unix)]
+            uid: Some(std::os::unix::fs::MetadataExt::uid(&metadata)),
+            #[cfg(not(unix))]
+            uid: None,
+        }
+    }
+}
+
+/// A place artifact files can be read from
+#[async_trait]
+pub trait ArtifactSource: Send + Sync {
+    /// Scheme selecting this source in artifact paths (`file`, `vss`, ...)
+    fn scheme(&self) -> &str;
+
+    /// Whether this is the running system, so type-specific collection
+    /// (journalctl, raw NTFS reads, keychain queries) applies
+    fn is_live_system(&self) -> bool {
+        false
+    }
+
+    /// Metadata of `path`, following symlinks
+    async fn stat(&self, path: &Path) -> io::Result<SourceMetadata>;
+
+    /// Full paths of the entries directly under `path`
+    async fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Open `path` for reading
+    async fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Add what this source knows about `path` to the metadata of the
+    /// artifact collected from it
+    fn annotate(&self, _path: &Path, _metadata: &mut ArtifactMetadata) {}
+
+    /// Read all of `path`
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.open(path).await?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Copy `path` to the local file `dest`; returns the bytes copied
+    async fn copy_to(&self, path: &Path, dest: &Path) -> io::Result<u64> {
+        let mut reader = self.open(path).await?;
+        io::copy(&mut reader, &mut File::create(dest)?)
+    }
+}
+
+/// The live local filesystem, honouring registered network mounts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalSource;
+
+impl LocalSource {
+    /// Path to actually read for `path`
+    fn read_path(path: &Path) -> PathBuf {
+        resolve_network_path(path).unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+#[async_trait]
+impl ArtifactSource for LocalSource {
+    fn scheme(&self) -> &str {
+        LOCAL_SCHEME
+    }
+
+    fn is_live_system(&self) -> bool {
+        true
+    }
+
+    async fn stat(&self, path: &Path) -> io::Result<SourceMetadata> {
+        let read_path = Self::read_path(path);
+        let mut metadata = SourceMetadata::from(fs::metadata(&read_path)?);
+        if !metadata.is_dir {
+            metadata.allocated_size = sparse::allocated_size(&read_path);
+        }
+        Ok(metadata)
+    }
+
+    async fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let read_path = Self::read_path(path);
+        fs::read_dir(&read_path)?
+            .map(|entry| entry.map(|e| path.join(e.file_name())))
+            .collect()
+    }
+
+    async fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(read_only::open(&Self::read_path(path))?))
+    }
+
+    async fn copy_to(&self, path: &Path, dest: &Path) -> io::Result<u64> {
+        let read_path = Self::read_path(path);
+        let bytes_copied = fast_copy::copy_file(&read_path, dest)?.bytes_copied;
+        record_bytes_read(&read_path, bytes_copied);
+        Ok(bytes_copied)
+    }
+}
+
+#[derive(Default)]
+struct SourceRegistry {
+    default: Option<Arc<dyn ArtifactSource>>,
+    by_scheme: HashMap<String, Arc<dyn ArtifactSource>>,
+}
+
+/// Read artifacts without a scheme prefix from `source` for the rest of the
+/// process lifetime
+pub fn set_default_source(source: Arc<dyn ArtifactSource>) {
+    if let Ok(mut registry) = SOURCES.write() {
+        info!("Reading artifacts from {}:// by default", source.scheme());
+        registry.default = Some(source);
+    }
+}
+
+/// Make `source` available to artifacts whose path starts with its scheme
+pub fn register_source(source: Arc<dyn ArtifactSource>) {
+    if let Ok(mut registry) = SOURCES.write() {
+        registry
+            .by_scheme
+            .insert(source.scheme().to_ascii_lowercase(), source);
+    }
+}
+
+/// The source for artifacts without a scheme prefix
+pub fn default_source() -> Arc<dyn ArtifactSource> {
+    SOURCES
+        .read()
+        .ok()
+        .and_then(|registry| registry.default.clone())
+        .unwrap_or_else(|| Arc::new(LocalSource))
+}
+
+/// Split a `scheme://` prefix off `source_path`.
+///
+/// Schemes are two or more ASCII letters, digits, `+`, `-` or `.`, starting
+/// with a letter, so drive letters (`C:\`) are never taken for one.
+pub fn split_scheme(source_path: &str) -> (Option<&str>, &str) {
+    if let Some((scheme, rest)) = source_path.split_once("://") {
+        let valid = scheme.len() >= 2
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if valid {
+            return (Some(scheme), rest);
+        }
+    }
+    (None, source_path)
+}
+
+/// `source_path` without any scheme prefix
+pub fn strip_scheme(source_path: &str) -> &str {
+    split_scheme(source_path).1
+}
+
+/// The source `source_path` names and the path within it
+pub fn resolve(source_path: &str) -> Result<(Arc<dyn ArtifactSource>, &str)> {
+    let (scheme, path) = split_scheme(source_path);
+    let source: Arc<dyn ArtifactSource> = match scheme.map(str::to_ascii_lowercase) {
+        None => default_source(),
+        Some(scheme) if scheme == LOCAL_SCHEME => Arc::new(LocalSource),
+        Some(scheme) => SOURCES
+            .read()
+            .ok()
+            .and_then(|registry| registry.by_scheme.get(&scheme).cloned())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No artifact source is registered for {}:// ({})",
+                    scheme,
+                    source_path
+                )
+            })?,
+    };
+    Ok((source, path))
+}
+
+/// Run a source operation to completion from synchronous collector code
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    futures::executor::block_on(future)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::platforms::common::FallbackCollector;
+    use crate::test_utils::mock_fs::MockFileSystem;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_split_scheme() {
+        assert_eq!(
+            split_scheme("file:///var/log/syslog"),
+            (Some("file"), "/var/log/syslog")
+        );
+        assert_eq!(
+            split_scheme(r"vss://HarddiskVolumeShadowCopy1\Windows"),
+            (Some("vss"), r"HarddiskVolumeShadowCopy1\Windows")
+        );
+        assert_eq!(split_scheme("/var/log/syslog"), (None, "/var/log/syslog"));
+        assert_eq!(split_scheme(r"C:\Windows"), (None, r"C:\Windows"));
+        assert_eq!(split_scheme("C://Windows"), (None, "C://Windows"));
+        assert_eq!(split_scheme(r"\\server\share"), (None, r"\\server\share"));
+
+        assert_eq!(resolve("file:///tmp").unwrap().1, "/tmp");
+        assert!(resolve("offline-test-unregistered:///etc").is_err());
+    }
+
+    /// Files and their contents under `root`, relative and sorted
+    fn tree(root: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let relative = path.strip_prefix(root).unwrap();
+                    files.push((
+                        relative.to_string_lossy().replace('\\', "/"),
+                        fs::read(&path).unwrap(),
+                    ));
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_local_and_in_memory_sources_collect_alike() {
+        let contents: &[(&str, &[u8])] = &[
+            ("auth.log", b"session opened"),
+            ("apt/history.log", b"Install: curl"),
+            ("apt/nested/term.log", b""),
+        ];
+
+        let local_root = TempDir::new().unwrap();
+        let mut mock = MockFileSystem::new();
+        for (name, data) in contents {
+            let path = local_root.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, data).unwrap();
+            mock.add_file(Path::new("/var/log").join(name), data.to_vec());
+        }
+
+        let output = TempDir::new().unwrap();
+        let local_dest = output.path().join("local");
+        let mock_dest = output.path().join("mock");
+
+        let local = FallbackCollector::new()
+            .collect_path(local_root.path(), &local_dest)
+            .unwrap();
+        let in_memory = FallbackCollector::with_source(Arc::new(mock))
+            .collect_path(Path::new("/var/log"), &mock_dest)
+            .unwrap();
+
+        assert_eq!(tree(&local_dest), tree(&mock_dest));
+        assert_eq!(tree(&local_dest).len(), contents.len());
+        assert_eq!(local.file_size, in_memory.file_size);
+        assert_eq!(in_memory.original_path, "/var/log");
+
+        // Single files report their size
+        let file = FallbackCollector::with_source(Arc::new({
+            let mut mock = MockFileSystem::new();
+            mock.add_file("/etc/hostname", b"host1\n".to_vec());
+            mock
+        }))
+        .collect_path(Path::new("/etc/hostname"), &output.path().join("hostname"))
+        .unwrap();
+        assert_eq!(file.file_size, 6);
+        assert_eq!(
+            fs::read(output.path().join("hostname")).unwrap(),
+            b"host1\n"
+        );
+    }
+}
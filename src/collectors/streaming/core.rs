@@ -6,7 +6,7 @@ use std::time::{Duration, Instant};
 use anyhow::{Context, Result};
 use log::{debug, info};
 use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::time::sleep;
 use walkdir::WalkDir;
 
@@ -15,6 +15,7 @@ use crate::constants::{
     COMPRESSED_EXTENSIONS, LARGE_FILE_COMPRESSION_THRESHOLD, PROGRESS_REPORT_INTERVAL_SECS,
     STREAMING_BUFFER_SIZE,
 };
+use crate::utils::path_encoding::archive_entry_name;
 use crate::utils::streaming_zip::{CompressionMethod, FileOptions, StreamingZipWriter};
 
 /// Progress tracker for streaming uploads
@@ -145,6 +146,50 @@ pub fn get_compression_options(path: &Path) -> FileOptions {
     options
 }
 
+/// Add the file at `path` to `zip_writer` as `name`.
+///
+/// The file is read in [`STREAMING_BUFFER_SIZE`] chunks, and `bytes_streamed`
+/// is advanced as each chunk is written. Returns the number of bytes read.
+pub async fn add_file_to_zip<W: AsyncWrite + Unpin>(
+    zip_writer: &mut StreamingZipWriter<W>,
+    path: &Path,
+    name: &str,
+    bytes_streamed: &AtomicU64,
+) -> Result<u64> {
+    // Determine compression options
+    let options = get_compression_options(path);
+
+    debug!("Adding {} to streaming ZIP", name);
+
+    // Open the file before starting the entry so a missing file leaves no
+    // partial entry behind
+    let mut file = File::open(path)
+        .await
+        .context(format!("Failed to open {}", path.display()))?;
+
+    // Start a new file entry
+    let mut file_writer = zip_writer.start_file(name, options).await?;
+
+    let mut buffer = vec![0u8; STREAMING_BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let bytes_read = tokio::io::AsyncReadExt::read(&mut file, &mut buffer).await?;
+        if bytes_read == 0 {
+            break;
+        }
+        file_writer.write_all(&buffer[..bytes_read]).await?;
+        total += bytes_read as u64;
+
+        // Update progress tracker
+        bytes_streamed.fetch_add(bytes_read as u64, Ordering::SeqCst);
+    }
+
+    // Finish the file entry
+    file_writer.finish().await?;
+    Ok(total)
+}
+
 /// Stream artifacts directly to a streaming target.
 ///
 /// This function:
@@ -201,11 +246,7 @@ pub async fn stream_directory_to_target<T: StreamingTarget>(
         let path = entry.path();
 
         // Get relative path
-        let rel_path = path
-            .strip_prefix(source_dir)
-            .unwrap_or(path)
-            .to_string_lossy()
-            .to_string();
+        let rel_path = archive_entry_name(path.strip_prefix(source_dir).unwrap_or(path));
 
         if rel_path.is_empty() {
             continue;
@@ -215,34 +256,7 @@ pub async fn stream_directory_to_target<T: StreamingTarget>(
             // Save directory for later addition
             dirs.push(format!("{}/", rel_path));
         } else {
-            // Determine compression options
-            let options = get_compression_options(path);
-
-            debug!("Adding {} to streaming ZIP", rel_path);
-
-            // Start a new file entry
-            let mut file_writer = zip_writer.start_file(&rel_path, options).await?;
-
-            // Open the file and stream its contents
-            let mut file = File::open(path)
-                .await
-                .context(format!("Failed to open {}", path.display()))?;
-
-            let mut buffer = vec![0u8; STREAMING_BUFFER_SIZE];
-
-            loop {
-                let bytes_read = tokio::io::AsyncReadExt::read(&mut file, &mut buffer).await?;
-                if bytes_read == 0 {
-                    break;
-                }
-                file_writer.write_all(&buffer[..bytes_read]).await?;
-
-                // Update progress tracker
-                bytes_uploaded_clone.fetch_add(bytes_read as u64, Ordering::SeqCst);
-            }
-
-            // Finish the file entry
-            file_writer.finish().await?;
+            add_file_to_zip(&mut zip_writer, path, &rel_path, &bytes_uploaded_clone).await?;
         }
     }
 
@@ -535,6 +549,25 @@ mod tests {
         assert!(bytes_ref.load(Ordering::SeqCst) > 0);
     }
 
+    #[tokio::test]
+    async fn test_add_file_to_zip_rejects_traversal_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+        fs::write(&test_file, "payload").unwrap();
+
+        for name in ["../evil", "fs/../../evil", "/etc/cron.d/evil", "C:evil"] {
+            let target = MockStreamingTarget::new("test-target");
+            let bytes_ref = target.bytes.clone();
+            let mut zip_writer = StreamingZipWriter::new(target);
+            let streamed = AtomicU64::new(0);
+
+            let result = add_file_to_zip(&mut zip_writer, &test_file, name, &streamed).await;
+
+            assert!(result.is_err(), "Accepted zip slip entry: {}", name);
+            assert_eq!(bytes_ref.load(Ordering::SeqCst), 0);
+        }
+    }
+
     #[tokio::test]
     async fn test_progress_tracker_zero_size() {
         let bytes_uploaded = Arc::new(AtomicU64::new(0));
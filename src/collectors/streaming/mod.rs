@@ -3,8 +3,13 @@
 //! This module handles streaming artifacts directly to remote storage
 
 mod core;
+pub(crate) mod pipeline;
 mod s3;
 mod sftp;
 
+pub use pipeline::{stream_collected_file, stream_command_output, ArchiveStream};
+// The collect-while-uploading pipeline is the archive stream
+#[allow(unused_imports)]
+pub use pipeline::ArchiveStream as StreamingArtifactPipeline;
 pub use s3::{stream_artifacts_to_s3, stream_file_to_s3};
 pub use sftp::{stream_artifacts_to_sftp, stream_file_to_sftp};
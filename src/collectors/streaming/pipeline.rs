@@ -0,0 +1,904 @@
+//! Streaming of artifacts into the upload archive while collection runs.
+//!
+//! Without this, `--stream` collects everything to the local artifact
+//! directory first and then walks the finished directory into the upload,
+//! so the whole collection has to fit on local disk. [`ArchiveStream`]
+//! instead runs a single archive-writer thread that owns the
+//! [`StreamingZipWriter`] and the upload target. Collection hands it each
+//! file as soon as the artifact it belongs to has been copied; the writer
+//! adds the file to the archive and deletes the local copy. Directory
+//! artifacts do not wait for the whole directory: the directory walker
+//! calls [`stream_collected_file`] after copying each file, so a large
+//! directory is uploaded while the rest of it is still being copied.
+//!
+//! The hand-off is a bounded channel of [`ARCHIVE_QUEUE_DEPTH`] files. When
+//! the upload is slower than collection the channel fills and collection
+//! blocks until the writer catches up, so local disk use stays at a few
+//! files rather than growing with the collection.
+//!
+//! Small files written after collection (volatile data, parsed reports, the
+//! footprint) stay on disk and are added by [`ArchiveStream::finish`],
+//! followed by the collection summary so it is the last entry. The central
+//! directory is written from the entries as they were added, so entry order
+//! does not matter.
+//!
+//! Collectors that export data with an external command (`journalctl`,
+//! `log show`) call [`stream_command_output`] while a stream is active, so
+//! the command's stdout is piped straight into an archive entry with
+//! [`StreamingZipWriter::add_file_from_reader`] and never written locally.
+//! [`stream_reader`] does the same for any reader, such as a raw read of a
+//! page file too large to stage on local disk.
+//! Like the artifact source registry, the active stream is process-global
+//! so collectors do not need it threaded through.
+//!
+//! Files that reach the archive before their artifact finishes stay there
+//! if the artifact later fails or times out; the summary still reports the
+//! artifact as failed.
+//!
+//! An upload is only completed by [`ArchiveStream::finish`]. If the stream
+//! is dropped before that, for example because collection failed, or if
+//! writing fails, the upload is aborted (for S3, the multipart upload is
+//! aborted).
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+use crate::cloud::streaming_target::StreamingTarget;
+use crate::collectors::footprint::record_process;
+use crate::collectors::streaming::core;
+use crate::constants::ARCHIVE_QUEUE_DEPTH;
+use crate::models::ArtifactMetadata;
+use crate::utils::path_encoding::{archive_entry_name, original_path_bytes};
+use crate::utils::streaming_zip::{FileOptions, StreamingZipWriter};
+
+/// Bytes buffered between a blocking reader and its archive entry
+const OUTPUT_PIPE_SIZE: usize = 1024 * 1024;
+
+lazy_static! {
+    /// Where [`stream_command_output`] and [`stream_collected_file`] send
+    /// data while a stream is active
+    static ref ACTIVE_STREAM: Mutex<Option<StreamSink>> = Mutex::new(None);
+}
+
+/// Handle on the active archive writer for collectors
+#[derive(Clone)]
+struct StreamSink {
+    artifact_dir: PathBuf,
+    sender: mpsc::Sender<ArchiveMessage>,
+    /// Files already handed over, shared with the [`ArchiveStream`]
+    sent: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl StreamSink {
+    fn mark_sent(&self, path: &Path) {
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf());
+    }
+}
+
+/// Work for the archive writer
+enum ArchiveMessage {
+    /// Add a local file to the archive, deleting it afterwards if `remove`
+    File {
+        path: PathBuf,
+        name: String,
+        remove: bool,
+    },
+    /// Add everything read from `reader` (a command's stdout or a source
+    /// file) to the archive, then report the bytes added on `done`
+    Output {
+        reader: Box<dyn Read + Send>,
+        name: String,
+        done: std_mpsc::Sender<Result<u64>>,
+    },
+    /// Everything has been sent; complete the upload
+    Finish,
+}
+
+/// Totals reported when the archive is complete
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveStats {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Archive upload fed with files while collection runs
+pub struct ArchiveStream {
+    artifact_dir: PathBuf,
+    target_name: String,
+    /// Files handed over during collection, which may still be queued
+    sent: Arc<Mutex<HashSet<PathBuf>>>,
+    sender: Option<mpsc::Sender<ArchiveMessage>>,
+    writer: Option<thread::JoinHandle<Result<ArchiveStats>>>,
+}
+
+impl ArchiveStream {
+    /// Start the archive writer for files under `artifact_dir`.
+    ///
+    /// `make_target` opens the upload; it runs on the writer thread, and an
+    /// error opening it is returned here so the caller can fall back to the
+    /// standard upload before anything has been collected.
+    pub fn start<T, F, Fut>(artifact_dir: &Path, make_target: F) -> Result<Self>
+    where
+        T: StreamingTarget,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>>,
+    {
+        let (sender, receiver) = mpsc::channel(ARCHIVE_QUEUE_DEPTH);
+        let (ready_tx, ready_rx) = std_mpsc::channel();
+
+        let writer = thread::Builder::new()
+            .name("archive-writer".to_string())
+            .spawn(move || -> Result<ArchiveStats> {
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .enable_all()
+                    .build()
+                    .context("Failed to create Tokio runtime for archive streaming")?;
+
+                runtime.block_on(async move {
+                    let target = match make_target().await {
+                        Ok(target) => {
+                            let _ = ready_tx.send(Ok(target.target_name()));
+                            target
+                        }
+                        Err(e) => {
+                            let message = format!("{:#}", e);
+                            let _ = ready_tx.send(Err(e));
+                            return Err(anyhow!(message));
+                        }
+                    };
+                    write_archive(target, receiver).await
+                })
+            })
+            .context("Failed to start archive writer thread")?;
+
+        let target_name = match ready_rx.recv() {
+            Ok(Ok(name)) => name,
+            Ok(Err(e)) => {
+                let _ = writer.join();
+                return Err(e.context("Failed to open streaming upload"));
+            }
+            Err(_) => {
+                // The thread ended without reporting, so the runtime failed
+                return match writer.join() {
+                    Ok(Err(e)) => Err(e),
+                    _ => Err(anyhow!("Archive writer thread stopped unexpectedly")),
+                };
+            }
+        };
+
+        info!("Streaming collected artifacts to {}", target_name);
+        let sent = Arc::new(Mutex::new(HashSet::new()));
+        set_active_stream(Some(StreamSink {
+            artifact_dir: artifact_dir.to_path_buf(),
+            sender: sender.clone(),
+            sent: Arc::clone(&sent),
+        }));
+        Ok(Self {
+            artifact_dir: artifact_dir.to_path_buf(),
+            target_name,
+            sent,
+            sender: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    /// Name of the upload target, for logs
+    pub fn target_name(&self) -> &str {
+        &self.target_name
+    }
+
+    /// Hand a collected file to the archive writer, which deletes it once it
+    /// is in the archive.
+    ///
+    /// Blocks while the writer is [`ARCHIVE_QUEUE_DEPTH`] files behind. Fails
+    /// if the writer has stopped, in which case the file is left in place.
+    pub fn send_file(&self, path: &Path) -> Result<()> {
+        self.send(path, true)?;
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Add the files still in the artifact directory, then `summary_path`
+    /// last, and complete the upload.
+    ///
+    /// These files are small outputs written after collection and are kept
+    /// on local disk.
+    pub fn finish(mut self, summary_path: &Path) -> Result<ArchiveStats> {
+        let sent = std::mem::take(&mut *self.sent.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut remaining: Vec<PathBuf> = WalkDir::new(&self.artifact_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.path() != summary_path)
+            .map(|e| e.into_path())
+            .filter(|path| !sent.contains(path))
+            .collect();
+        remaining.push(summary_path.to_path_buf());
+
+        // Later command output stays local; the writer must see the channel
+        // close once Finish is sent
+        set_active_stream(None);
+        for path in &remaining {
+            if self.send(path, false).is_err() {
+                // The writer has stopped; its error is returned below
+                break;
+            }
+        }
+
+        let sender = self
+            .sender
+            .take()
+            .context("Archive stream already finished")?;
+        // A closed channel means the writer failed; its error is reported below
+        let _ = sender.blocking_send(ArchiveMessage::Finish);
+        drop(sender);
+
+        self.join_writer()
+    }
+
+    fn send(&self, path: &Path, remove: bool) -> Result<()> {
+        let sender = self
+            .sender
+            .as_ref()
+            .context("Archive stream already finished")?;
+        let name = archive_name(&self.artifact_dir, path);
+        sender
+            .blocking_send(ArchiveMessage::File {
+                path: path.to_path_buf(),
+                name,
+                remove,
+            })
+            .map_err(|_| anyhow!("Archive writer for {} has stopped", self.target_name))
+    }
+
+    fn join_writer(&mut self) -> Result<ArchiveStats> {
+        match self.writer.take() {
+            Some(writer) => writer
+                .join()
+                .map_err(|_| anyhow!("Archive writer thread panicked"))?,
+            None => Err(anyhow!("Archive stream already finished")),
+        }
+    }
+}
+
+impl Drop for ArchiveStream {
+    /// Abort the upload if the stream was not finished
+    fn drop(&mut self) {
+        set_active_stream(None);
+        if self.sender.take().is_some() {
+            warn!(
+                "Archive stream to {} dropped before it was finished, aborting upload",
+                self.target_name
+            );
+            if let Err(e) = self.join_writer() {
+                debug!("Archive writer stopped: {}", e);
+            }
+        }
+    }
+}
+
+fn set_active_stream(sink: Option<StreamSink>) {
+    *ACTIVE_STREAM.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// Hand a file the collector has just written to the active archive
+/// writer, which deletes it once it is in the archive.
+///
+/// Returns `false`, leaving the file in place, when no stream is active,
+/// `path` is outside the streamed artifact directory or the writer has
+/// stopped. Blocks while the writer is [`ARCHIVE_QUEUE_DEPTH`] files
+/// behind, which pauses collection until the upload catches up; call it
+/// from blocking collector code, not from an async task.
+pub fn stream_collected_file(path: &Path) -> bool {
+    let Some(sink) = ACTIVE_STREAM
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    else {
+        return false;
+    };
+    if !path.starts_with(&sink.artifact_dir) || sink.sender.is_closed() {
+        return false;
+    }
+
+    let message = ArchiveMessage::File {
+        path: path.to_path_buf(),
+        name: archive_name(&sink.artifact_dir, path),
+        remove: true,
+    };
+    if sink.sender.blocking_send(message).is_err() {
+        return false;
+    }
+    sink.mark_sent(path);
+    true
+}
+
+/// Result of a command whose stdout was streamed into the archive
+#[derive(Debug)]
+pub struct StreamedOutput {
+    pub status: ExitStatus,
+    pub stderr: Vec<u8>,
+    /// Bytes of stdout added to the archive
+    pub bytes: u64,
+}
+
+impl StreamedOutput {
+    /// Metadata for the archive entry holding the output of a command that
+    /// exported `source`
+    pub fn metadata(&self, source: &Path) -> ArtifactMetadata {
+        ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: chrono::Utc::now().to_rfc3339(),
+            file_size: self.bytes,
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            original_path_bytes: original_path_bytes(source),
+            ..Default::default()
+        }
+    }
+}
+
+/// Run `command` with its stdout streamed into the active archive as the
+/// entry for the local path `dest`, which is never written.
+///
+/// Returns `None` without running anything when no archive stream is
+/// active or the writer has stopped, so the caller can run the command and
+/// write `dest` as usual. A failure of the command itself is reported in
+/// the returned status, like [`Command::output`]; whatever it printed
+/// before failing is already in the archive.
+pub fn stream_command_output(command: &mut Command, dest: &Path) -> Option<Result<StreamedOutput>> {
+    let sink = ACTIVE_STREAM
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()?;
+    if sink.sender.is_closed() {
+        return None;
+    }
+
+    let started = chrono::Utc::now().to_rfc3339();
+    let mut child = match command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            record_process(command, started, None, Some(e.to_string()));
+            return Some(Err(e.into()));
+        }
+    };
+    let stdout = child.stdout.take()?;
+
+    // Drain stderr alongside so a chatty command cannot block on it
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let streamed = match send_reader(&sink, Box::new(stdout), dest) {
+        Some(streamed) => streamed,
+        None => {
+            // Nothing has been read yet; let the caller run it locally
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+    };
+
+    let status = child.wait();
+    record_process(
+        command,
+        started,
+        status.as_ref().ok().and_then(|s| s.code()),
+        status.as_ref().err().map(|e| e.to_string()),
+    );
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    Some(streamed.and_then(|bytes| {
+        Ok(StreamedOutput {
+            status: status?,
+            stderr,
+            bytes,
+        })
+    }))
+}
+
+/// Add everything `reader` yields to the active archive as the entry for
+/// the local path `dest`, which is never written; returns the bytes added.
+///
+/// Returns `None` without reading when no archive stream is active or the
+/// writer has stopped, so the caller can write `dest` as usual. Blocks until
+/// the reader is exhausted; call it from blocking collector code.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn stream_reader(reader: impl Read + Send + 'static, dest: &Path) -> Option<Result<u64>> {
+    let sink = ACTIVE_STREAM
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()?;
+    if sink.sender.is_closed() {
+        return None;
+    }
+    send_reader(&sink, Box::new(reader), dest)
+}
+
+/// Hand `reader` to the writer and wait until it is in the archive; `None`
+/// if the writer stopped before taking it
+fn send_reader(
+    sink: &StreamSink,
+    reader: Box<dyn Read + Send>,
+    dest: &Path,
+) -> Option<Result<u64>> {
+    let (done_tx, done_rx) = std_mpsc::channel();
+    let message = ArchiveMessage::Output {
+        reader,
+        name: archive_name(&sink.artifact_dir, dest),
+        done: done_tx,
+    };
+    sink.sender.blocking_send(message).ok()?;
+    // The writer drops `done` without answering if it stops first
+    Some(
+        done_rx
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow!("Archive writer stopped"))),
+    )
+}
+
+/// Path of `path` inside the archive: relative to `artifact_dir`, with `/`
+/// separators
+fn archive_name(artifact_dir: &Path, path: &Path) -> String {
+    archive_entry_name(path.strip_prefix(artifact_dir).unwrap_or(path))
+}
+
+/// Writer loop: add files as they arrive and complete the upload on
+/// [`ArchiveMessage::Finish`]. If the channel closes first or a write fails,
+/// the upload is aborted instead.
+async fn write_archive<T: StreamingTarget>(
+    target: T,
+    mut receiver: mpsc::Receiver<ArchiveMessage>,
+) -> Result<ArchiveStats> {
+    let mut zip_writer = StreamingZipWriter::new(target);
+    let bytes_streamed = AtomicU64::new(0);
+    let mut stats = ArchiveStats::default();
+
+    let outcome: Result<bool> = async {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                ArchiveMessage::File { path, name, remove } => {
+                    core::add_file_to_zip(&mut zip_writer, &path, &name, &bytes_streamed)
+                        .await
+                        .context(format!("Failed to stream {}", path.display()))?;
+                    stats.files += 1;
+                    if remove {
+                        if let Err(e) = tokio::fs::remove_file(&path).await {
+                            warn!("Failed to remove streamed file {}: {}", path.display(), e);
+                        }
+                    }
+                }
+                ArchiveMessage::Output { reader, name, done } => {
+                    let added = add_output_to_zip(&mut zip_writer, reader, &name).await;
+                    let _ = done.send(match &added {
+                        Ok(bytes) => Ok(*bytes),
+                        Err(e) => Err(anyhow!("{:#}", e)),
+                    });
+                    let bytes = added.context(format!("Failed to stream {}", name))?;
+                    stats.files += 1;
+                    bytes_streamed.fetch_add(bytes, Ordering::SeqCst);
+                }
+                ArchiveMessage::Finish => return Ok(true),
+            }
+        }
+        Ok(false)
+    }
+    .await;
+    stats.bytes = bytes_streamed.load(Ordering::SeqCst);
+
+    match outcome {
+        Ok(true) => {
+            let target = zip_writer.finish().await?;
+            target.complete().await?;
+            info!(
+                "Streamed {} files ({} bytes) into the archive",
+                stats.files, stats.bytes
+            );
+            Ok(stats)
+        }
+        Ok(false) => {
+            zip_writer.writer.abort().await?;
+            Err(anyhow!("Archive stream was cancelled; upload aborted"))
+        }
+        Err(e) => {
+            // Refuse further files so collection keeps them locally
+            receiver.close();
+            if let Err(abort_err) = zip_writer.writer.abort().await {
+                warn!("Failed to abort upload: {}", abort_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Add everything `reader` yields to `zip_writer` as `name`; returns the
+/// bytes added.
+///
+/// The blocking reader is drained on a blocking thread into an in-memory
+/// pipe the archive entry is read from.
+async fn add_output_to_zip<W: tokio::io::AsyncWrite + Unpin>(
+    zip_writer: &mut StreamingZipWriter<W>,
+    mut reader: Box<dyn Read + Send>,
+    name: &str,
+) -> Result<u64> {
+    let (mut pipe_writer, pipe_reader) = tokio::io::duplex(OUTPUT_PIPE_SIZE);
+    let runtime = tokio::runtime::Handle::current();
+    let pump = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        let mut buffer = vec![0u8; OUTPUT_PIPE_SIZE];
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            runtime.block_on(pipe_writer.write_all(&buffer[..read]))?;
+        }
+    });
+    let added = zip_writer
+        .add_file_from_reader(name, FileOptions::default(), pipe_reader)
+        .await;
+    // A read error ends the entry early; the caller sees the short count
+    match pump.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Reading {} stopped early: {}", name, e),
+        Err(e) => warn!("Reading {} stopped early: {}", name, e),
+    }
+    added?;
+    Ok(zip_writer
+        .entries
+        .last()
+        .map_or(0, |entry| entry.uncompressed_size as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context as TaskContext, Poll};
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct Uploaded {
+        data: Vec<u8>,
+        completed: bool,
+        aborted: bool,
+    }
+
+    struct MemoryTarget {
+        uploaded: Arc<Mutex<Uploaded>>,
+    }
+
+    impl StreamingTarget for MemoryTarget {
+        fn target_name(&self) -> String {
+            "memory".to_string()
+        }
+
+        fn bytes_uploaded(&self) -> u64 {
+            self.uploaded.lock().unwrap().data.len() as u64
+        }
+
+        async fn complete(self) -> Result<()> {
+            self.uploaded.lock().unwrap().completed = true;
+            Ok(())
+        }
+
+        async fn abort(self) -> Result<()> {
+            self.uploaded.lock().unwrap().aborted = true;
+            Ok(())
+        }
+    }
+
+    impl tokio::io::AsyncWrite for MemoryTarget {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut TaskContext<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.uploaded.lock().unwrap().data.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Started streams register as the active stream, so tests take turns
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn serial() -> std::sync::MutexGuard<'static, ()> {
+        SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(dir: &Path) -> (ArchiveStream, Arc<Mutex<Uploaded>>) {
+        let uploaded = Arc::new(Mutex::new(Uploaded::default()));
+        let target = MemoryTarget {
+            uploaded: Arc::clone(&uploaded),
+        };
+        let stream = ArchiveStream::start(dir, move || async move { Ok(target) }).unwrap();
+        (stream, uploaded)
+    }
+
+    /// Entry names from the central directory of a complete archive
+    fn central_directory_names(zip: &[u8]) -> Vec<String> {
+        let eocd = zip.len() - 22;
+        assert_eq!(&zip[eocd..eocd + 4], b"PK\x05\x06");
+        let entries = u16::from_le_bytes([zip[eocd + 10], zip[eocd + 11]]) as usize;
+        let mut offset = u32::from_le_bytes(zip[eocd + 16..eocd + 20].try_into().unwrap()) as usize;
+
+        let mut names = Vec::new();
+        for _ in 0..entries {
+            assert_eq!(&zip[offset..offset + 4], b"PK\x01\x02");
+            let field = |at: usize| u16::from_le_bytes([zip[at], zip[at + 1]]) as usize;
+            let (name_len, extra_len, comment_len) =
+                (field(offset + 28), field(offset + 30), field(offset + 32));
+            names.push(String::from_utf8_lossy(&zip[offset + 46..offset + 46 + name_len]).into());
+            offset += 46 + name_len + extra_len + comment_len;
+        }
+        names
+    }
+
+    #[test]
+    fn test_streams_files_as_collected_and_summary_last() {
+        let _serial = serial();
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("Linux-SystemLogs")).unwrap();
+        fs::create_dir_all(dir.join("volatile")).unwrap();
+        let collected = dir.join("Linux-SystemLogs/syslog");
+        fs::write(&collected, "log line\n").unwrap();
+        fs::write(dir.join("volatile/processes.json"), "[]").unwrap();
+        let summary = dir.join("collection_summary.json");
+        fs::write(&summary, "{}").unwrap();
+
+        let (stream, uploaded) = start(dir);
+        stream.send_file(&collected).unwrap();
+        let stats = stream.finish(&summary).unwrap();
+
+        // Collected files are removed; metadata stays local
+        assert!(!collected.exists());
+        assert!(dir.join("volatile/processes.json").exists());
+        assert!(summary.exists());
+        assert_eq!(stats.files, 3);
+
+        let uploaded = uploaded.lock().unwrap();
+        assert!(uploaded.completed);
+        assert!(!uploaded.aborted);
+        assert_eq!(
+            central_directory_names(&uploaded.data),
+            vec![
+                "Linux-SystemLogs/syslog",
+                "volatile/processes.json",
+                "collection_summary.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dropped_stream_aborts_upload() {
+        let _serial = serial();
+        let temp = TempDir::new().unwrap();
+        let collected = temp.path().join("file.bin");
+        fs::write(&collected, vec![7u8; 1024]).unwrap();
+
+        let (stream, uploaded) = start(temp.path());
+        stream.send_file(&collected).unwrap();
+        drop(stream);
+
+        let uploaded = uploaded.lock().unwrap();
+        assert!(uploaded.aborted);
+        assert!(!uploaded.completed);
+    }
+
+    #[test]
+    fn test_write_failure_stops_stream() {
+        let _serial = serial();
+        let temp = TempDir::new().unwrap();
+        let (stream, uploaded) = start(temp.path());
+
+        // A missing file fails the writer and aborts the upload
+        let _ = stream.send_file(&temp.path().join("missing"));
+        let kept = temp.path().join("kept");
+        fs::write(&kept, "data").unwrap();
+        let mut refused = false;
+        for _ in 0..100 {
+            if stream.send_file(&kept).is_err() {
+                refused = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(refused);
+
+        let summary = temp.path().join("collection_summary.json");
+        fs::write(&summary, "{}").unwrap();
+        assert!(stream.finish(&summary).is_err());
+        assert!(uploaded.lock().unwrap().aborted);
+    }
+
+    #[test]
+    fn test_failed_target_reported_at_start() {
+        let temp = TempDir::new().unwrap();
+        let result = ArchiveStream::start(temp.path(), || async {
+            Err::<MemoryTarget, _>(anyhow!("bucket not found"))
+        });
+        let err = result.err().unwrap();
+        assert!(format!("{:#}", err).contains("bucket not found"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_output_streamed_without_local_file() {
+        let _serial = serial();
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let dest = dir.join("Linux-SystemLogs/journal.json");
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg("printf 'line1\\nline2\\n'; echo partial >&2; exit 3");
+
+        // Without an active stream the caller writes the file itself
+        assert!(stream_command_output(&mut command, &dest).is_none());
+
+        let (stream, uploaded) = start(dir);
+        let output = stream_command_output(&mut command, &dest).unwrap().unwrap();
+        assert_eq!(output.bytes, 12);
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stderr, b"partial\n");
+        assert!(!dest.exists());
+
+        let missing = stream_command_output(&mut Command::new("/nonexistent/command"), &dest);
+        assert!(missing.unwrap().is_err());
+
+        let summary = dir.join("collection_summary.json");
+        fs::write(&summary, "{}").unwrap();
+        let stats = stream.finish(&summary).unwrap();
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.bytes, 14);
+        assert!(stream_command_output(&mut command, &dest).is_none());
+
+        let uploaded = uploaded.lock().unwrap();
+        assert_eq!(
+            central_directory_names(&uploaded.data),
+            vec!["Linux-SystemLogs/journal.json", "collection_summary.json"]
+        );
+    }
+
+    #[test]
+    fn test_reader_streamed_without_local_file() {
+        /// Yields `data`, then fails like a raw read hitting a bad sector
+        struct FailingReader {
+            data: io::Cursor<Vec<u8>>,
+        }
+
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.data.read(buf)? {
+                    0 => Err(io::Error::other("device error")),
+                    read => Ok(read),
+                }
+            }
+        }
+
+        let _serial = serial();
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let dest = dir.join("fs/pagefile.sys");
+        assert!(stream_reader(io::empty(), &dest).is_none());
+
+        let (stream, uploaded) = start(dir);
+        let data = vec![7u8; 3 * OUTPUT_PIPE_SIZE + 5];
+        let bytes = stream_reader(io::Cursor::new(data.clone()), &dest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(bytes, data.len() as u64);
+        assert!(!dest.exists());
+
+        // A failed read keeps what was read and the stream going
+        let failing = FailingReader {
+            data: io::Cursor::new(b"partial".to_vec()),
+        };
+        let short = dir.join("fs/hiberfil.sys");
+        assert_eq!(stream_reader(failing, &short).unwrap().unwrap(), 7);
+
+        let summary = dir.join("collection_summary.json");
+        fs::write(&summary, "{}").unwrap();
+        let stats = stream.finish(&summary).unwrap();
+        assert_eq!(stats.files, 3);
+        assert_eq!(
+            central_directory_names(&uploaded.lock().unwrap().data),
+            vec![
+                "fs/pagefile.sys",
+                "fs/hiberfil.sys",
+                "collection_summary.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directory_files_streamed_as_copied() {
+        use crate::collectors::platforms::common::FallbackCollector;
+
+        let _serial = serial();
+        let source = TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("nested")).unwrap();
+        fs::write(source.path().join("a.log"), "a").unwrap();
+        fs::write(source.path().join("nested/b.log"), "bb").unwrap();
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let outside = source.path().join("a.log");
+
+        let (stream, uploaded) = start(dir);
+        let dest = dir.join("Linux-SystemLogs/logs");
+        FallbackCollector::new()
+            .collect_directory(source.path(), &dest)
+            .unwrap();
+        assert!(!stream_collected_file(&outside));
+
+        let summary = dir.join("collection_summary.json");
+        fs::write(&summary, "{}").unwrap();
+        let stats = stream.finish(&summary).unwrap();
+
+        // Each copy is added once and removed; the source is untouched
+        assert_eq!(stats.files, 3);
+        assert!(!dest.join("a.log").exists());
+        assert!(!dest.join("nested/b.log").exists());
+        assert!(outside.exists());
+
+        let mut names = central_directory_names(&uploaded.lock().unwrap().data);
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "Linux-SystemLogs/logs/a.log",
+                "Linux-SystemLogs/logs/nested/b.log",
+                "collection_summary.json"
+            ]
+        );
+    }
+}
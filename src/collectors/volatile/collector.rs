@@ -1,14 +1,63 @@
 use anyhow::{Context as AnyhowContext, Result};
-use log::{debug, info};
-use std::fs;
-use std::path::Path;
+use log::{debug, info, warn};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use sysinfo::{CpuExt, DiskExt, NetworkExt, PidExt, ProcessExt, ProcessStatus, System, SystemExt};
 
+use crate::collectors::linux::ld_preload;
+use crate::collectors::linux::proc_net;
+use crate::collectors::volatile::crypto_miners;
+use crate::collectors::volatile::csv;
+use crate::collectors::volatile::macos_persistence;
 use crate::collectors::volatile::models::*;
+use crate::collectors::volatile::process_ancestry;
+use crate::collectors::volatile::process_binaries::{self, ProcessBinaryStats};
+use crate::collectors::volatile::scheduled_tasks_live;
+use crate::collectors::volatile::services;
+use crate::collectors::volatile::tcp_sockets;
+use crate::collectors::volatile::user_sessions;
+use crate::collectors::volatile::windows_network_config;
+use crate::security::credential_scrubber::{scrub_volatile_command_line, scrub_volatile_text};
+use crate::utils::resource_limits;
+use crate::utils::timezone::HostTimeContext;
+
+/// Name of the consolidated report embedding every volatile section
+pub const VOLATILE_REPORT_FILE: &str = "volatile_report.json";
+
+/// Sorted, de-duplicated process names for the collection summary
+fn process_names(processes: &[ProcessInfo]) -> Vec<String> {
+    let mut names: Vec<String> = processes.iter().map(|p| p.name.clone()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// In-memory holding area for volatile output files.
+///
+/// Used in order-of-volatility mode so the snapshot phase does not touch the
+/// disk until the most volatile state has been captured. Pending writes are
+/// flushed once they exceed `cap_bytes` or when [`VolatileDataCollector::flush`]
+/// is called.
+struct WriteBuffer {
+    cap_bytes: usize,
+    pending: Vec<(PathBuf, Vec<u8>)>,
+    pending_bytes: usize,
+}
 
 /// Collector for volatile system data
 pub struct VolatileDataCollector {
     system: System,
+    format: VolatileOutputFormat,
+    write_buffer: Option<WriteBuffer>,
+    defer_external: bool,
+    time_context: Option<HostTimeContext>,
+    hash_binaries: bool,
+    /// Sockets from the last TCP enumeration, for the miner scan
+    tcp_sockets: Vec<tcp_sockets::TcpSocket>,
+    /// Process snapshot waiting for deferred TCP sockets before the miner
+    /// scan runs
+    pending_miner_scan: Option<Vec<ProcessInfo>>,
 }
 
 impl VolatileDataCollector {
@@ -17,7 +66,54 @@ impl VolatileDataCollector {
         info!("Initializing volatile data collector");
         let mut system = System::new_all();
         system.refresh_all();
-        Self { system }
+        Self {
+            system,
+            format: VolatileOutputFormat::default(),
+            write_buffer: None,
+            defer_external: false,
+            time_context: None,
+            hash_binaries: false,
+            tcp_sockets: Vec::new(),
+            pending_miner_scan: None,
+        }
+    }
+
+    /// Set the output format used by [`collect_all`](Self::collect_all)
+    pub fn with_format(mut self, format: VolatileOutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Include host timezone and clock context in the system info
+    pub fn with_time_context(mut self, time_context: HostTimeContext) -> Self {
+        self.time_context = Some(time_context);
+        self
+    }
+
+    /// Hold output in memory (up to `cap_bytes`) instead of writing each file
+    /// immediately. Call [`flush`](Self::flush) to write pending output.
+    pub fn with_write_buffer(mut self, cap_bytes: usize) -> Self {
+        self.write_buffer = Some(WriteBuffer {
+            cap_bytes,
+            pending: Vec::new(),
+            pending_bytes: 0,
+        });
+        self
+    }
+
+    /// Hash and check the signature of each process binary, written to
+    /// `process_binaries.json`
+    pub fn with_binary_hashing(mut self, enabled: bool) -> Self {
+        self.hash_binaries = enabled;
+        self
+    }
+
+    /// Skip sections that require spawning external commands during
+    /// [`collect_all`](Self::collect_all); run them later with
+    /// [`collect_deferred`](Self::collect_deferred).
+    pub fn defer_external_commands(mut self, defer: bool) -> Self {
+        self.defer_external = defer;
+        self
     }
 
     /// Collect all volatile data and save to the specified directory
@@ -36,41 +132,476 @@ impl VolatileDataCollector {
         // Refresh all system information
         self.system.refresh_all();
 
-        // Collect and save system information
-        let system_info = self.collect_system_info()?;
-        self.save_to_json(&system_info, output_dir.join("system-info.json"))?;
+        let mut output_files = Vec::new();
 
-        // Collect and save process information
+        // Collect all sections first so every format sees the same snapshot
+        let system_info = self.collect_system_info()?;
         let processes = self.collect_processes()?;
-        self.save_to_json(&processes, output_dir.join("processes.json"))?;
-
-        // Collect and save network information
         let network = self.collect_network()?;
-        self.save_to_json(&network, output_dir.join("network-connections.json"))?;
-
-        // Collect and save memory information
         let memory = self.collect_memory()?;
-        self.save_to_json(&memory, output_dir.join("memory.json"))?;
-
-        // Collect and save disk information
         let disks = self.collect_disks()?;
-        self.save_to_json(&disks, output_dir.join("disks.json"))?;
+
+        if self.format.writes_json() {
+            self.emit_json(&system_info, output_dir.join("system-info.json"))?;
+            self.emit_json(&processes, output_dir.join("processes.json"))?;
+            self.emit_json(&network, output_dir.join("network-connections.json"))?;
+            self.emit_json(&memory, output_dir.join("memory.json"))?;
+            self.emit_json(&disks, output_dir.join("disks.json"))?;
+            output_files.extend(
+                [
+                    "system-info.json",
+                    "processes.json",
+                    "network-connections.json",
+                    "memory.json",
+                    "disks.json",
+                ]
+                .iter()
+                .map(|f| f.to_string()),
+            );
+        }
+
+        if self.format.writes_csv() {
+            self.emit_csv(output_dir.join("processes.csv"), |w| {
+                csv::write_processes(w, &processes).map(|_| ())
+            })?;
+            self.emit_csv(output_dir.join("network-connections.csv"), |w| {
+                csv::write_connections(w, &network.connections).map(|_| ())
+            })?;
+            self.emit_csv(output_dir.join("network-interfaces.csv"), |w| {
+                csv::write_interfaces(w, &network.interfaces).map(|_| ())
+            })?;
+            self.emit_csv(output_dir.join("disks.csv"), |w| {
+                csv::write_disks(w, &disks).map(|_| ())
+            })?;
+            output_files.extend(
+                [
+                    "processes.csv",
+                    "network-connections.csv",
+                    "network-interfaces.csv",
+                    "disks.csv",
+                ]
+                .iter()
+                .map(|f| f.to_string()),
+            );
+        }
+
+        // After the snapshot above, as hashing reads every binary from disk
+        let binary_stats = if self.hash_binaries {
+            let binaries = process_binaries::describe_binaries(&processes, !self.defer_external);
+            let stats = ProcessBinaryStats::from_binaries(&binaries);
+            if stats.deleted_on_disk > 0 {
+                warn!(
+                    "{} running executables have been deleted from disk",
+                    stats.deleted_on_disk
+                );
+            }
+            self.emit_json(
+                &binaries,
+                output_dir.join(process_binaries::PROCESS_BINARIES_FILE),
+            )?;
+            output_files.push(process_binaries::PROCESS_BINARIES_FILE.to_string());
+            Some(stats)
+        } else {
+            None
+        };
+
+        if !tcp_sockets::USES_EXTERNAL_COMMAND {
+            output_files.extend(self.collect_tcp_sockets(output_dir)?);
+        }
+
+        if proc_net::SUPPORTED {
+            output_files.extend(self.collect_packet_sockets(output_dir)?);
+        }
+
+        if ld_preload::SUPPORTED {
+            output_files.extend(self.collect_ld_preload_audit(output_dir, &processes)?);
+        }
+
+        if services::SUPPORTED {
+            output_files.extend(self.collect_services(output_dir)?);
+        }
+
+        if !self.defer_external {
+            output_files.extend(self.collect_deferred(output_dir)?);
+        }
+
+        // The miner scan needs the TCP sockets, so it waits for them when
+        // they come from a deferred external command
+        if tcp_sockets::USES_EXTERNAL_COMMAND && self.defer_external {
+            self.pending_miner_scan = Some(processes.clone());
+        } else {
+            output_files.push(self.collect_crypto_miners(output_dir, &processes)?);
+        }
+
+        let ancestry = process_ancestry::build_ancestry_tree(&processes);
+        if ancestry.anomaly_count() > 0 {
+            warn!(
+                "{} orphaned processes (parent exited or PID reused)",
+                ancestry.anomaly_count()
+            );
+        }
+        self.emit_json(
+            &ancestry,
+            output_dir.join(process_ancestry::PROCESS_ANCESTRY_FILE),
+        )?;
+        output_files.push(process_ancestry::PROCESS_ANCESTRY_FILE.to_string());
 
         // Create a summary for the collection summary
-        let summary = VolatileDataSummary {
+        let mut summary = VolatileDataSummary {
             system_name: system_info.hostname.clone(),
             os_version: system_info.os_version.clone(),
             cpu_count: system_info.cpu_info.count,
             total_memory_mb: memory.total_memory / 1024, // Convert KB to MB
             process_count: processes.len(),
+            process_names: process_names(&processes),
             network_interface_count: network.interfaces.len(),
             disk_count: disks.len(),
+            output_files: Vec::new(),
+            process_binaries: binary_stats,
+            process_ancestry_anomalies: ancestry.anomaly_count(),
+        };
+
+        // Always write the consolidated report for easier downstream parsing
+        let report = VolatileData {
+            system_info,
+            processes,
+            network,
+            memory,
+            disks,
         };
+        self.emit_json(&report, output_dir.join(VOLATILE_REPORT_FILE))?;
+        output_files.push(VOLATILE_REPORT_FILE.to_string());
+
+        summary.output_files = output_files;
 
         info!("Volatile data collection completed successfully");
         Ok(summary)
     }
 
+    /// Collect sections that rely on external tools or services.
+    ///
+    /// Returns the names of the files written, relative to `output_dir`.
+    pub fn collect_deferred(&mut self, output_dir: impl AsRef<Path>) -> Result<Vec<String>> {
+        let output_dir = output_dir.as_ref();
+        let mut output_files = Vec::new();
+
+        // Live scheduler state complements the on-disk task definitions
+        match scheduled_tasks_live::enumerate_tasks_live() {
+            Ok(mut tasks) => {
+                for task in &mut tasks {
+                    task.command = task.command.as_deref().map(scrub_volatile_text);
+                }
+                self.emit_json(
+                    &tasks,
+                    output_dir.join(scheduled_tasks_live::SCHEDULED_TASKS_LIVE_FILE),
+                )?;
+                output_files.push(scheduled_tasks_live::SCHEDULED_TASKS_LIVE_FILE.to_string());
+            }
+            Err(e) => warn!("Failed to enumerate live scheduled tasks: {}", e),
+        }
+
+        if tcp_sockets::USES_EXTERNAL_COMMAND {
+            output_files.extend(self.collect_tcp_sockets(output_dir)?);
+        }
+
+        if let Some(processes) = self.pending_miner_scan.take() {
+            output_files.push(self.collect_crypto_miners(output_dir, &processes)?);
+        }
+
+        if windows_network_config::SUPPORTED {
+            output_files.extend(self.collect_windows_network_config(output_dir)?);
+        }
+
+        // Runs sfltool, profiles and systemextensionsctl
+        if macos_persistence::SUPPORTED {
+            output_files.extend(self.collect_macos_persistence(output_dir)?);
+        }
+
+        output_files.extend(self.collect_user_sessions(output_dir)?);
+
+        Ok(output_files)
+    }
+
+    /// Write the TCP socket listing with owning processes.
+    ///
+    /// Enumeration failures are logged and produce no file.
+    fn collect_tcp_sockets(&mut self, output_dir: &Path) -> Result<Option<String>> {
+        match tcp_sockets::enumerate_tcp_sockets() {
+            Ok(sockets) => {
+                debug!("Enumerated {} TCP sockets", sockets.len());
+                self.emit_json(&sockets, output_dir.join(tcp_sockets::TCP_SOCKETS_FILE))?;
+                self.tcp_sockets = sockets;
+                Ok(Some(tcp_sockets::TCP_SOCKETS_FILE.to_string()))
+            }
+            Err(e) => {
+                warn!("Failed to enumerate TCP sockets: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the processes showing signs of cryptocurrency mining, scored
+    /// against the last TCP socket enumeration.
+    fn collect_crypto_miners(
+        &mut self,
+        output_dir: &Path,
+        processes: &[ProcessInfo],
+    ) -> Result<String> {
+        let connections: Vec<NetworkConnection> = self
+            .tcp_sockets
+            .iter()
+            .map(NetworkConnection::from)
+            .collect();
+        let candidates = crypto_miners::detect_miners(processes, &connections);
+        let likely = candidates
+            .iter()
+            .filter(|candidate| candidate.confidence_score >= crypto_miners::LIKELY_MINER_SCORE)
+            .count();
+        if likely > 0 {
+            warn!("{} processes look like cryptocurrency miners", likely);
+        }
+        self.emit_json(
+            &candidates,
+            output_dir.join(crypto_miners::CRYPTO_MINER_CANDIDATES_FILE),
+        )?;
+        Ok(crypto_miners::CRYPTO_MINER_CANDIDATES_FILE.to_string())
+    }
+
+    /// Write live and on-host login session history.
+    ///
+    /// Failures are logged and produce no file.
+    fn collect_user_sessions(&mut self, output_dir: &Path) -> Result<Option<String>> {
+        match user_sessions::collect_user_sessions() {
+            Ok(sessions) => {
+                debug!("Found {} user sessions", sessions.len());
+                self.emit_json(
+                    &sessions,
+                    output_dir.join(user_sessions::USER_SESSIONS_FILE),
+                )?;
+                Ok(Some(user_sessions::USER_SESSIONS_FILE.to_string()))
+            }
+            Err(e) => {
+                warn!("Failed to collect user sessions: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the packet socket listing, warning about sockets opened by
+    /// non-root users.
+    ///
+    /// Enumeration failures are logged and produce no file.
+    fn collect_packet_sockets(&mut self, output_dir: &Path) -> Result<Option<String>> {
+        match proc_net::collect_packet_sockets() {
+            Ok(sockets) => {
+                debug!("Enumerated {} packet sockets", sockets.len());
+                for socket in sockets.iter().filter(|s| s.suspicious) {
+                    warn!(
+                        "Packet socket ({} {}) opened by uid {}, process {} ({})",
+                        socket.socket_type,
+                        socket.protocol,
+                        socket.uid,
+                        socket.pid.map_or("unknown".to_string(), |p| p.to_string()),
+                        socket.process_name.as_deref().unwrap_or("unknown")
+                    );
+                }
+                self.emit_json(&sockets, output_dir.join(proc_net::PACKET_SOCKETS_FILE))?;
+                Ok(Some(proc_net::PACKET_SOCKETS_FILE.to_string()))
+            }
+            Err(e) => {
+                warn!("Failed to enumerate packet sockets: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the `LD_PRELOAD` audit of the host and `processes`.
+    ///
+    /// Failures are logged and produce no file.
+    fn collect_ld_preload_audit(
+        &mut self,
+        output_dir: &Path,
+        processes: &[ProcessInfo],
+    ) -> Result<Option<String>> {
+        match ld_preload::audit(processes) {
+            Ok(report) => {
+                debug!(
+                    "{} processes have loader variables set",
+                    report.processes.len()
+                );
+                self.emit_json(&report, output_dir.join(ld_preload::LD_PRELOAD_AUDIT_FILE))?;
+                Ok(Some(ld_preload::LD_PRELOAD_AUDIT_FILE.to_string()))
+            }
+            Err(e) => {
+                warn!("Failed to audit LD_PRELOAD: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the installed services and their comparison with the default
+    /// installation, warning about new services running non-Windows binaries.
+    ///
+    /// Failures are logged and produce no files.
+    fn collect_services(&mut self, output_dir: &Path) -> Result<Vec<String>> {
+        match services::collect_services() {
+            Ok((installed, diff)) => {
+                for anomaly in &diff.anomalies {
+                    warn!(
+                        "New service {} runs {}",
+                        anomaly.name,
+                        scrub_volatile_text(&anomaly.binary_path)
+                    );
+                }
+                self.emit_json(&installed, output_dir.join(services::SERVICES_FILE))?;
+                self.emit_json(&diff, output_dir.join(services::SERVICES_DIFF_FILE))?;
+                Ok(vec![
+                    services::SERVICES_FILE.to_string(),
+                    services::SERVICES_DIFF_FILE.to_string(),
+                ])
+            }
+            Err(e) => {
+                warn!("Failed to enumerate services: {}", e);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Write the Windows network configuration, warning about each finding.
+    ///
+    /// Proxy settings are scrubbed, since proxy URLs can embed credentials.
+    fn collect_windows_network_config(&mut self, output_dir: &Path) -> Result<Option<String>> {
+        match windows_network_config::collect_network_config() {
+            Ok(mut config) => {
+                for proxy in config
+                    .winhttp_proxy
+                    .iter_mut()
+                    .chain(&mut config.user_proxies)
+                {
+                    proxy.server = proxy.server.as_deref().map(scrub_volatile_text);
+                    proxy.pac_url = proxy.pac_url.as_deref().map(scrub_volatile_text);
+                }
+                for finding in &mut config.findings {
+                    finding.detail = scrub_volatile_text(&finding.detail);
+                    warn!("Network configuration: {}", finding.detail);
+                }
+                self.emit_json(
+                    &config,
+                    output_dir.join(windows_network_config::WINDOWS_NETWORK_CONFIG_FILE),
+                )?;
+                Ok(Some(
+                    windows_network_config::WINDOWS_NETWORK_CONFIG_FILE.to_string(),
+                ))
+            }
+            Err(e) => {
+                warn!("Failed to collect Windows network configuration: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the live persistence inventory under
+    /// [`MACOS_PERSISTENCE_DIR`](macos_persistence::MACOS_PERSISTENCE_DIR).
+    ///
+    /// Every command gets a file, including those that were denied or failed.
+    fn collect_macos_persistence(&mut self, output_dir: &Path) -> Result<Vec<String>> {
+        let dir = output_dir.join(macos_persistence::MACOS_PERSISTENCE_DIR);
+        fs::create_dir_all(&dir)
+            .context(format!("Failed to create directory: {}", dir.display()))?;
+
+        let os_version = self.system.os_version();
+        let mut output_files = Vec::new();
+        for (file_name, capture) in
+            macos_persistence::collect_persistence_inventory(os_version.as_deref())
+        {
+            if let Some(note) = &capture.note {
+                warn!("{}", note);
+            }
+            self.emit_json(&capture, dir.join(file_name))?;
+            output_files.push(format!(
+                "{}/{}",
+                macos_persistence::MACOS_PERSISTENCE_DIR,
+                file_name
+            ));
+        }
+        Ok(output_files)
+    }
+
+    /// Write any output held by the write buffer to disk
+    pub fn flush(&mut self) -> Result<()> {
+        let pending = match self.write_buffer.as_mut() {
+            Some(buffer) => {
+                buffer.pending_bytes = 0;
+                std::mem::take(&mut buffer.pending)
+            }
+            None => return Ok(()),
+        };
+
+        for (path, bytes) in pending {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .context(format!("Failed to create directory: {}", parent.display()))?;
+            }
+            fs::write(&path, bytes)
+                .context(format!("Failed to write data to file: {}", path.display()))?;
+            debug!("Flushed buffered data to {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Queue or write a serialized output file
+    fn emit(&mut self, path: PathBuf, bytes: Vec<u8>) -> Result<()> {
+        let over_cap = match self.write_buffer.as_mut() {
+            Some(buffer) => {
+                buffer.pending_bytes += bytes.len();
+                buffer.pending.push((path, bytes));
+                // Near the --max-memory-mb cap, nothing is held back
+                buffer.pending_bytes > buffer.cap_bytes || resource_limits::memory_pressure()
+            }
+            None => {
+                fs::write(&path, bytes)
+                    .context(format!("Failed to write data to file: {}", path.display()))?;
+                return Ok(());
+            }
+        };
+
+        if over_cap {
+            debug!("Volatile write buffer cap exceeded, flushing");
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Save JSON either directly or through the write buffer
+    fn emit_json<T: serde::Serialize>(&mut self, data: &T, path: PathBuf) -> Result<()> {
+        if self.write_buffer.is_none() {
+            return self.save_to_json(data, path);
+        }
+        let bytes = serde_json::to_vec_pretty(data).context("Failed to serialize data to JSON")?;
+        self.emit(path, bytes)
+    }
+
+    /// Save CSV either streamed to a file or through the write buffer
+    fn emit_csv(
+        &mut self,
+        path: PathBuf,
+        write: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<()> {
+        if self.write_buffer.is_none() {
+            let mut writer = csv::create_csv_file(&path)?;
+            return write(&mut writer);
+        }
+        let mut bytes = Vec::new();
+        write(&mut bytes)?;
+        self.emit(path, bytes)
+    }
+
+    /// Refresh the process table for another snapshot without reloading
+    /// disks and other slow-changing state
+    pub fn refresh_processes(&mut self) {
+        self.system.refresh_processes();
+    }
+
     /// Collect system information
     pub fn collect_system_info(&self) -> Result<SystemInfo> {
         debug!("Collecting system information");
@@ -92,6 +623,7 @@ impl VolatileDataCollector {
             os_version: self.system.os_version(),
             kernel_version: self.system.kernel_version(),
             cpu_info,
+            time_context: self.time_context.clone(),
         };
 
         Ok(system_info)
@@ -116,7 +648,7 @@ impl VolatileDataCollector {
             let process_info = ProcessInfo {
                 pid: pid.as_u32(),
                 name: process.name().to_string(),
-                cmd: process.cmd().to_vec(),
+                cmd: scrub_volatile_command_line(process.cmd().to_vec()),
                 exe: Some(process.exe().to_string_lossy().to_string()),
                 status: status.to_string(),
                 start_time: process.start_time(),
@@ -223,12 +755,13 @@ impl VolatileDataCollector {
                 .context(format!("Failed to create directory: {}", parent.display()))?;
         }
 
-        // Serialize to JSON with pretty formatting
-        let json =
-            serde_json::to_string_pretty(data).context("Failed to serialize data to JSON")?;
-
-        // Write to file
-        fs::write(path, json)
+        // Stream pretty JSON straight to the file to avoid buffering large lists
+        let file = File::create(path)
+            .context(format!("Failed to write data to file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, data)
+            .context("Failed to serialize data to JSON")?;
+        std::io::Write::flush(&mut writer)
             .context(format!("Failed to write data to file: {}", path.display()))?;
 
         debug!("Saved data to {}", path.display());
@@ -266,6 +799,88 @@ mod tests {
         assert!(temp_dir.path().join("network-connections.json").exists());
         assert!(temp_dir.path().join("memory.json").exists());
         assert!(temp_dir.path().join("disks.json").exists());
+
+        #[cfg(target_os = "linux")]
+        assert!(temp_dir.path().join(tcp_sockets::TCP_SOCKETS_FILE).exists());
+        assert!(temp_dir
+            .path()
+            .join(crypto_miners::CRYPTO_MINER_CANDIDATES_FILE)
+            .exists());
+        #[cfg(target_os = "linux")]
+        assert!(temp_dir.path().join(proc_net::PACKET_SOCKETS_FILE).exists());
+        #[cfg(target_os = "linux")]
+        assert!(temp_dir
+            .path()
+            .join(ld_preload::LD_PRELOAD_AUDIT_FILE)
+            .exists());
+        #[cfg(target_os = "windows")]
+        assert!(temp_dir.path().join(services::SERVICES_DIFF_FILE).exists());
+    }
+
+    #[test]
+    fn test_collect_all_csv_format() {
+        let mut collector = VolatileDataCollector::new().with_format(VolatileOutputFormat::Csv);
+        let temp_dir = TempDir::new().unwrap();
+
+        let summary = collector.collect_all(temp_dir.path()).unwrap();
+
+        assert!(temp_dir.path().join("processes.csv").exists());
+        assert!(temp_dir.path().join("network-connections.csv").exists());
+        assert!(temp_dir.path().join("network-interfaces.csv").exists());
+        assert!(temp_dir.path().join("disks.csv").exists());
+        assert!(temp_dir.path().join(VOLATILE_REPORT_FILE).exists());
+        assert!(!temp_dir.path().join("processes.json").exists());
+        assert!(summary.output_files.contains(&"processes.csv".to_string()));
+
+        // The report and CSV must describe the same process snapshot
+        let report: VolatileData = serde_json::from_str(
+            &fs::read_to_string(temp_dir.path().join(VOLATILE_REPORT_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(report.processes.len(), summary.process_count);
+        assert_eq!(report.disks.len(), summary.disk_count);
+    }
+
+    #[test]
+    fn test_collect_all_both_formats() {
+        let mut collector = VolatileDataCollector::new().with_format(VolatileOutputFormat::Both);
+        let temp_dir = TempDir::new().unwrap();
+
+        let summary = collector.collect_all(temp_dir.path()).unwrap();
+
+        assert!(temp_dir.path().join("processes.json").exists());
+        assert!(temp_dir.path().join("processes.csv").exists());
+        assert!(summary.output_files.contains(&"processes.json".to_string()));
+        assert!(summary.output_files.contains(&"disks.csv".to_string()));
+    }
+
+    #[test]
+    fn test_buffered_collection_writes_nothing_until_flush() {
+        let mut collector = VolatileDataCollector::new()
+            .with_write_buffer(usize::MAX)
+            .defer_external_commands(true);
+        let temp_dir = TempDir::new().unwrap();
+
+        let summary = collector.collect_all(temp_dir.path()).unwrap();
+        assert!(!temp_dir.path().join("processes.json").exists());
+        assert!(!summary
+            .output_files
+            .contains(&scheduled_tasks_live::SCHEDULED_TASKS_LIVE_FILE.to_string()));
+
+        collector.flush().unwrap();
+        assert!(temp_dir.path().join("processes.json").exists());
+        assert!(temp_dir.path().join(VOLATILE_REPORT_FILE).exists());
+    }
+
+    #[test]
+    fn test_buffer_flushes_when_cap_exceeded() {
+        let mut collector = VolatileDataCollector::new().with_write_buffer(1);
+        let temp_dir = TempDir::new().unwrap();
+
+        collector
+            .emit_json(&vec!["data"], temp_dir.path().join("small.json"))
+            .unwrap();
+        assert!(temp_dir.path().join("small.json").exists());
     }
 
     #[test]
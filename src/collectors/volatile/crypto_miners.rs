@@ -0,0 +1,219 @@
+//! Heuristic detection of cryptocurrency mining processes.
+//!
+//! Miners dropped on compromised hosts are usually XMRig or a fork of it,
+//! run with a command line naming the pool, and keep the CPU busy. Each
+//! process is scored on three indicators:
+//!
+//! - a miner name or pool URL scheme in its name, path or command line
+//!   (`xmrig`, `cgminer`, `stratum+tcp://`, ...), worth 50
+//! - an outbound connection to a port mining pools listen on (3333, 4444,
+//!   14444, ...), worth 30
+//! - CPU usage above 80% of one core, worth 20
+//!
+//! Any process with an indicator is reported with the sum as its
+//! `confidence_score` (0-100). Busy compilers and databases score 20 on CPU
+//! alone, so low scores are leads rather than findings. Results are written
+//! to `volatile/crypto_miner_candidates.json`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::volatile::models::{NetworkConnection, ProcessInfo};
+
+/// File name used for the candidate listing
+pub const CRYPTO_MINER_CANDIDATES_FILE: &str = "crypto_miner_candidates.json";
+
+/// Lower-case names and URL schemes of common miners and mining protocols
+const MINER_KEYWORDS: &[&str] = &[
+    "xmrig",
+    "xmr-stak",
+    "cgminer",
+    "bfgminer",
+    "cpuminer",
+    "minerd",
+    "ethminer",
+    "nbminer",
+    "lolminer",
+    "phoenixminer",
+    "stratum+",
+];
+
+/// Ports mining pools commonly listen on
+const MINING_POOL_PORTS: &[u16] = &[3333, 4444, 5555, 7777, 14433, 14444, 45700];
+
+/// CPU usage, in percent of one core, above which a process counts as busy
+const HIGH_CPU_PERCENT: f32 = 80.0;
+
+/// Scores from this up need a miner keyword, or a pool connection from a
+/// busy process
+pub const LIKELY_MINER_SCORE: u8 = 50;
+
+const KEYWORD_SCORE: u8 = 50;
+const POOL_CONNECTION_SCORE: u8 = 30;
+const HIGH_CPU_SCORE: u8 = 20;
+
+/// A process with at least one mining indicator
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CryptoMinerCandidate {
+    pub pid: u32,
+    pub process_name: String,
+    pub exe: Option<String>,
+    pub command_line: String,
+    pub cpu_usage: f32,
+    /// Whether CPU usage was above 80%
+    pub high_cpu: bool,
+    /// Miner keywords found in the name, path or command line
+    pub keywords: Vec<String>,
+    /// Remote `address:port` of connections to mining pool ports
+    pub pool_connections: Vec<String>,
+    /// 0-100; 50 for a keyword, 30 for a pool port, 20 for high CPU
+    pub confidence_score: u8,
+}
+
+/// Score each of `processes`, returning those with any indicator, highest
+/// confidence first
+pub fn detect_miners(
+    processes: &[ProcessInfo],
+    network_connections: &[NetworkConnection],
+) -> Vec<CryptoMinerCandidate> {
+    let mut candidates: Vec<CryptoMinerCandidate> = processes
+        .iter()
+        .filter_map(|process| score_process(process, network_connections))
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.confidence_score
+            .cmp(&a.confidence_score)
+            .then(a.pid.cmp(&b.pid))
+    });
+    candidates
+}
+
+fn score_process(
+    process: &ProcessInfo,
+    network_connections: &[NetworkConnection],
+) -> Option<CryptoMinerCandidate> {
+    let command_line = process.cmd.join(" ");
+    let haystack = format!(
+        "{} {} {}",
+        process.name,
+        process.exe.as_deref().unwrap_or_default(),
+        command_line
+    )
+    .to_lowercase();
+    let keywords: Vec<String> = MINER_KEYWORDS
+        .iter()
+        .filter(|keyword| haystack.contains(*keyword))
+        .map(|keyword| keyword.to_string())
+        .collect();
+
+    let mut pool_connections: Vec<String> = network_connections
+        .iter()
+        .filter(|connection| connection.process_id == Some(process.pid))
+        .filter_map(|connection| {
+            let port = connection.remote_port?;
+            let address = connection.remote_address.as_deref()?;
+            MINING_POOL_PORTS
+                .contains(&port)
+                .then(|| format!("{}:{}", address, port))
+        })
+        .collect();
+    pool_connections.sort();
+    pool_connections.dedup();
+
+    let high_cpu = process.cpu_usage > HIGH_CPU_PERCENT;
+    let confidence_score = [
+        (!keywords.is_empty(), KEYWORD_SCORE),
+        (!pool_connections.is_empty(), POOL_CONNECTION_SCORE),
+        (high_cpu, HIGH_CPU_SCORE),
+    ]
+    .iter()
+    .filter(|(found, _)| *found)
+    .map(|(_, score)| score)
+    .sum();
+
+    (confidence_score > 0).then(|| CryptoMinerCandidate {
+        pid: process.pid,
+        process_name: process.name.clone(),
+        exe: process.exe.clone(),
+        command_line,
+        cpu_usage: process.cpu_usage,
+        high_cpu,
+        keywords,
+        pool_connections,
+        confidence_score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cmd: &[&str], cpu_usage: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cmd: cmd.iter().map(|arg| arg.to_string()).collect(),
+            exe: Some(format!("/usr/bin/{}", name)),
+            status: "Running".to_string(),
+            start_time: 0,
+            cpu_usage,
+            memory_usage: 0,
+            parent_pid: Some(1),
+        }
+    }
+
+    fn connection(pid: u32, remote: &str, port: u16) -> NetworkConnection {
+        NetworkConnection {
+            protocol: "tcp".to_string(),
+            local_address: "10.0.0.5".to_string(),
+            local_port: 50000,
+            remote_address: Some(remote.to_string()),
+            remote_port: Some(port),
+            state: Some("ESTABLISHED".to_string()),
+            process_id: Some(pid),
+        }
+    }
+
+    #[test]
+    fn test_scores_each_indicator() {
+        let processes = [
+            process(
+                10,
+                "kworkerds",
+                &["./kworkerds", "-o", "stratum+tcp://pool.example:3333"],
+                390.0,
+            ),
+            process(20, "xmrig", &["xmrig", "--donate-level", "1"], 5.0),
+            process(30, "updater", &["updater"], 1.0),
+            process(40, "cc1plus", &["cc1plus", "main.cpp"], 99.0),
+            process(50, "sshd", &["sshd"], 0.0),
+        ];
+        let connections = [
+            connection(10, "198.51.100.7", 3333),
+            connection(10, "198.51.100.7", 3333),
+            connection(30, "203.0.113.9", 14444),
+            connection(50, "192.0.2.1", 443),
+        ];
+
+        let candidates = detect_miners(&processes, &connections);
+        let scores: Vec<(u32, u8)> = candidates
+            .iter()
+            .map(|c| (c.pid, c.confidence_score))
+            .collect();
+        assert_eq!(scores, vec![(10, 100), (20, 50), (30, 30), (40, 20)]);
+
+        let disguised = &candidates[0];
+        assert_eq!(disguised.keywords, vec!["stratum+"]);
+        assert_eq!(disguised.pool_connections, vec!["198.51.100.7:3333"]);
+        assert!(disguised.high_cpu);
+        assert!(!candidates[1].high_cpu);
+    }
+
+    #[test]
+    fn test_keywords_match_path_case_insensitively() {
+        let mut miner = process(7, "svchost.exe", &["svchost.exe"], 0.0);
+        miner.exe = Some(r"C:\Users\Public\XMRig\svchost.exe".to_string());
+        let candidates = detect_miners(&[miner], &[]);
+        assert_eq!(candidates[0].keywords, vec!["xmrig"]);
+        assert_eq!(candidates[0].confidence_score, KEYWORD_SCORE);
+    }
+}
@@ -0,0 +1,253 @@
+//! Minimal CSV writer for volatile data exports.
+//!
+//! Rows are written straight to the underlying writer as they are produced, so
+//! large process lists never need to be materialized as a single string.
+//! Column order is fixed by the header constants below and must not change
+//! between releases, since downstream SOC pipelines ingest these files by
+//! position.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::collectors::volatile::models::{
+    DiskInfo, NetworkConnection, NetworkInterface, ProcessInfo,
+};
+
+/// Column order for `processes.csv`
+pub const PROCESS_COLUMNS: &[&str] = &[
+    "pid",
+    "parent_pid",
+    "name",
+    "exe",
+    "status",
+    "start_time",
+    "cpu_usage",
+    "memory_usage",
+    "cmd",
+];
+
+/// Column order for `network-connections.csv`
+pub const CONNECTION_COLUMNS: &[&str] = &[
+    "protocol",
+    "local_address",
+    "local_port",
+    "remote_address",
+    "remote_port",
+    "state",
+    "process_id",
+];
+
+/// Column order for `network-interfaces.csv`
+pub const INTERFACE_COLUMNS: &[&str] =
+    &["name", "mac", "ips", "received_bytes", "transmitted_bytes"];
+
+/// Column order for `disks.csv`
+pub const DISK_COLUMNS: &[&str] = &[
+    "name",
+    "mount_point",
+    "file_system",
+    "total_space",
+    "available_space",
+    "is_removable",
+];
+
+/// Escape a single CSV field according to RFC 4180.
+///
+/// Fields containing a comma, double quote, carriage return or line feed are
+/// wrapped in double quotes, with embedded quotes doubled. All other fields
+/// are written as-is.
+pub fn escape_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        std::borrow::Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        std::borrow::Cow::Borrowed(field)
+    }
+}
+
+/// Streaming CSV writer with a fixed header
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    columns: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a new writer and immediately emit the header row
+    pub fn new(writer: W, header: &[&str]) -> Result<Self> {
+        let mut csv = Self {
+            writer,
+            columns: header.len(),
+        };
+        csv.write_row(header)?;
+        Ok(csv)
+    }
+
+    /// Write a single row. The number of fields must match the header.
+    pub fn write_row<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<()> {
+        debug_assert_eq!(fields.len(), self.columns, "CSV row width mismatch");
+
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_all(b",")?;
+            }
+            self.writer
+                .write_all(escape_field(field.as_ref()).as_bytes())?;
+        }
+        self.writer.write_all(b"\r\n")?;
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush().context("Failed to flush CSV output")?;
+        Ok(self.writer)
+    }
+}
+
+fn opt_to_string<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Write process rows to any writer
+pub fn write_processes<W: Write>(writer: W, processes: &[ProcessInfo]) -> Result<W> {
+    let mut csv = CsvWriter::new(writer, PROCESS_COLUMNS)?;
+    for process in processes {
+        csv.write_row(&[
+            process.pid.to_string(),
+            opt_to_string(&process.parent_pid),
+            process.name.clone(),
+            opt_to_string(&process.exe),
+            process.status.clone(),
+            process.start_time.to_string(),
+            process.cpu_usage.to_string(),
+            process.memory_usage.to_string(),
+            process.cmd.join(" "),
+        ])?;
+    }
+    csv.finish()
+}
+
+/// Write network connection rows to any writer
+pub fn write_connections<W: Write>(writer: W, connections: &[NetworkConnection]) -> Result<W> {
+    let mut csv = CsvWriter::new(writer, CONNECTION_COLUMNS)?;
+    for conn in connections {
+        csv.write_row(&[
+            conn.protocol.clone(),
+            conn.local_address.clone(),
+            conn.local_port.to_string(),
+            opt_to_string(&conn.remote_address),
+            opt_to_string(&conn.remote_port),
+            opt_to_string(&conn.state),
+            opt_to_string(&conn.process_id),
+        ])?;
+    }
+    csv.finish()
+}
+
+/// Write network interface rows to any writer
+pub fn write_interfaces<W: Write>(writer: W, interfaces: &[NetworkInterface]) -> Result<W> {
+    let mut csv = CsvWriter::new(writer, INTERFACE_COLUMNS)?;
+    for iface in interfaces {
+        csv.write_row(&[
+            iface.name.clone(),
+            opt_to_string(&iface.mac),
+            iface.ips.join(";"),
+            iface.received_bytes.to_string(),
+            iface.transmitted_bytes.to_string(),
+        ])?;
+    }
+    csv.finish()
+}
+
+/// Write disk rows to any writer
+pub fn write_disks<W: Write>(writer: W, disks: &[DiskInfo]) -> Result<W> {
+    let mut csv = CsvWriter::new(writer, DISK_COLUMNS)?;
+    for disk in disks {
+        csv.write_row(&[
+            disk.name.clone(),
+            opt_to_string(&disk.mount_point),
+            opt_to_string(&disk.file_system),
+            disk.total_space.to_string(),
+            disk.available_space.to_string(),
+            disk.is_removable.to_string(),
+        ])?;
+    }
+    csv.finish()
+}
+
+/// Create a buffered file writer for a CSV output path
+pub fn create_csv_file(path: &Path) -> Result<BufWriter<File>> {
+    let file =
+        File::create(path).context(format!("Failed to create CSV file: {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN_PROCESSES: &str =
+        include_str!("../../../tests/fixtures/volatile/processes_escaping.csv");
+
+    fn tricky_process() -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            name: "powershell.exe".to_string(),
+            cmd: vec![
+                "powershell.exe".to_string(),
+                "-c".to_string(),
+                "Write-Host \"a,b\"\nexit".to_string(),
+            ],
+            exe: Some("C:\\Windows\\System32\\powershell.exe".to_string()),
+            status: "Running".to_string(),
+            start_time: 1700000000,
+            cpu_usage: 1.5,
+            memory_usage: 2048,
+            parent_pid: None,
+        }
+    }
+
+    #[test]
+    fn test_escape_field_plain() {
+        assert_eq!(escape_field("simple"), "simple");
+        assert_eq!(escape_field(""), "");
+    }
+
+    #[test]
+    fn test_escape_field_special_characters() {
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("line1\nline2"), "\"line1\nline2\"");
+        assert_eq!(escape_field("cr\rhere"), "\"cr\rhere\"");
+    }
+
+    #[test]
+    fn test_process_csv_matches_golden_file() {
+        let out = write_processes(Vec::new(), &[tricky_process()]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN_PROCESSES);
+    }
+
+    #[test]
+    fn test_header_only_when_empty() {
+        let out = write_disks(Vec::new(), &[]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "name,mount_point,file_system,total_space,available_space,is_removable\r\n"
+        );
+    }
+
+    #[test]
+    fn test_interfaces_join_ips() {
+        let iface = NetworkInterface {
+            name: "eth0".to_string(),
+            mac: None,
+            ips: vec!["10.0.0.1".to_string(), "fe80::1".to_string()],
+            received_bytes: 10,
+            transmitted_bytes: 20,
+        };
+        let out = String::from_utf8(write_interfaces(Vec::new(), &[iface]).unwrap()).unwrap();
+        assert!(out.ends_with("eth0,,10.0.0.1;fe80::1,10,20\r\n"));
+    }
+}
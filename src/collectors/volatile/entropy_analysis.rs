@@ -0,0 +1,236 @@
+//! Entropy of collected files, to spot packed or encrypted content
+//! (`--compute-entropy`).
+//!
+//! Packers and encryptors leave data close to 8 bits of entropy per byte,
+//! while scripts, documents and ordinary executables sit well below. With
+//! the flag, every collected file gets `entropy` in its metadata, and files
+//! above the threshold (`entropy_threshold` in `global_options`, default
+//! 7.2) are listed in `high_entropy_files.json` together with a histogram
+//! of all files by whole bits of entropy. Compressed formats (ZIP, JPEG,
+//! Office documents) are naturally high, so the list is a starting point
+//! for review rather than a verdict.
+//!
+//! Every byte of every file is read once more, which is slow for large
+//! collections, hence the opt-in flag.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ArtifactMetadata;
+use crate::utils::hash::compute_entropy;
+
+/// Report file name, in the artifact directory
+pub const HIGH_ENTROPY_FILES: &str = "high_entropy_files.json";
+
+/// `global_options` key for the threshold
+pub const ENTROPY_THRESHOLD_KEY: &str = "entropy_threshold";
+
+/// Files above this many bits per byte are reported by default
+pub const DEFAULT_ENTROPY_THRESHOLD: f64 = 7.2;
+
+static COMPUTE_ENTROPY: AtomicBool = AtomicBool::new(false);
+
+/// Turn entropy computation for collected files on or off
+pub fn set_compute_entropy(enabled: bool) {
+    COMPUTE_ENTROPY.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether collected files get their entropy computed
+pub fn compute_entropy_enabled() -> bool {
+    COMPUTE_ENTROPY.load(Ordering::Relaxed)
+}
+
+/// The threshold from `entropy_threshold`, or the default when it is
+/// missing or not a number from 0 to 8
+pub fn entropy_threshold(global_options: &HashMap<String, String>) -> f64 {
+    let Some(value) = global_options.get(ENTROPY_THRESHOLD_KEY) else {
+        return DEFAULT_ENTROPY_THRESHOLD;
+    };
+    match value.trim().parse::<f64>() {
+        Ok(threshold) if (0.0..=8.0).contains(&threshold) => threshold,
+        _ => {
+            warn!(
+                "Invalid {} '{}'; using {}",
+                ENTROPY_THRESHOLD_KEY, value, DEFAULT_ENTROPY_THRESHOLD
+            );
+            DEFAULT_ENTROPY_THRESHOLD
+        }
+    }
+}
+
+/// Set `entropy` in the metadata of a collected file.
+///
+/// Directories and files that cannot be read are left without one.
+pub fn analyze_collected_file(collected: &Path, metadata: &mut ArtifactMetadata) {
+    if !collected.is_file() {
+        return;
+    }
+    match compute_entropy(collected) {
+        Ok(entropy) => metadata.entropy = Some(entropy),
+        Err(e) => debug!("Cannot compute entropy of {}: {}", collected.display(), e),
+    }
+}
+
+/// Files whose entropy falls in `[min, max)`; the last bucket includes 8
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntropyBucket {
+    pub min: f64,
+    pub max: f64,
+    pub files: usize,
+}
+
+/// A file above the threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighEntropyFile {
+    /// Path of the copy, relative to its artifact type directory
+    pub path: String,
+    pub original_path: String,
+    pub entropy: f64,
+    pub file_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_type: Option<String>,
+}
+
+/// Contents of [`HIGH_ENTROPY_FILES`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighEntropyReport {
+    pub threshold: f64,
+    pub files_analyzed: usize,
+    /// One bucket per bit of entropy, 0 to 8
+    pub histogram: Vec<EntropyBucket>,
+    /// Highest entropy first
+    pub high_entropy_files: Vec<HighEntropyFile>,
+}
+
+impl HighEntropyReport {
+    /// Build the report from the metadata of every collected file
+    pub fn from_metadata(metadata: &[(String, ArtifactMetadata)], threshold: f64) -> Self {
+        let mut histogram: Vec<EntropyBucket> = (0..8)
+            .map(|bit| EntropyBucket {
+                min: bit as f64,
+                max: (bit + 1) as f64,
+                files: 0,
+            })
+            .collect();
+        let mut high_entropy_files = Vec::new();
+        let mut files_analyzed = 0;
+
+        for (path, meta) in metadata {
+            let Some(entropy) = meta.entropy else {
+                continue;
+            };
+            files_analyzed += 1;
+            let bucket = (entropy.floor().max(0.0) as usize).min(histogram.len() - 1);
+            histogram[bucket].files += 1;
+            if entropy > threshold {
+                high_entropy_files.push(HighEntropyFile {
+                    path: path.clone(),
+                    original_path: meta.original_path.clone(),
+                    entropy,
+                    file_size: meta.file_size,
+                    detected_type: meta.detected_type.clone(),
+                });
+            }
+        }
+        high_entropy_files.sort_by(|a, b| b.entropy.total_cmp(&a.entropy));
+
+        Self {
+            threshold,
+            files_analyzed,
+            histogram,
+            high_entropy_files,
+        }
+    }
+
+    /// Write the report to [`HIGH_ENTROPY_FILES`] in `artifact_dir`
+    pub fn write(&self, artifact_dir: &Path) -> Result<PathBuf> {
+        let path = artifact_dir.join(HIGH_ENTROPY_FILES);
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize entropy report")?;
+        fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::generators::test_artifact_metadata;
+    use tempfile::TempDir;
+
+    fn with_entropy(original_path: &str, entropy: Option<f64>) -> ArtifactMetadata {
+        let mut metadata = test_artifact_metadata(original_path);
+        metadata.entropy = entropy;
+        metadata
+    }
+
+    #[test]
+    fn test_high_entropy_report() {
+        let metadata = vec![
+            (
+                "a/notes.txt".to_string(),
+                with_entropy("/tmp/notes.txt", Some(4.5)),
+            ),
+            (
+                "a/packed.exe".to_string(),
+                with_entropy("/tmp/packed.exe", Some(7.6)),
+            ),
+            (
+                "a/random.bin".to_string(),
+                with_entropy("/tmp/random.bin", Some(8.0)),
+            ),
+            ("a/dir".to_string(), with_entropy("/tmp/dir", None)),
+        ];
+        let report = HighEntropyReport::from_metadata(&metadata, DEFAULT_ENTROPY_THRESHOLD);
+
+        assert_eq!(report.files_analyzed, 3);
+        assert_eq!(report.histogram.len(), 8);
+        assert_eq!(report.histogram[4].files, 1);
+        assert_eq!(report.histogram[7].files, 2);
+        let paths: Vec<&str> = report
+            .high_entropy_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["a/random.bin", "a/packed.exe"]);
+
+        let temp = TempDir::new().unwrap();
+        let path = report.write(temp.path()).unwrap();
+        let read: HighEntropyReport =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(read, report);
+    }
+
+    #[test]
+    fn test_entropy_threshold() {
+        let mut options = HashMap::new();
+        assert_eq!(entropy_threshold(&options), DEFAULT_ENTROPY_THRESHOLD);
+        options.insert(ENTROPY_THRESHOLD_KEY.to_string(), "7.5".to_string());
+        assert_eq!(entropy_threshold(&options), 7.5);
+        options.insert(ENTROPY_THRESHOLD_KEY.to_string(), "9".to_string());
+        assert_eq!(entropy_threshold(&options), DEFAULT_ENTROPY_THRESHOLD);
+        options.insert(ENTROPY_THRESHOLD_KEY.to_string(), "high".to_string());
+        assert_eq!(entropy_threshold(&options), DEFAULT_ENTROPY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_analyze_collected_file() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("zeros.bin");
+        fs::write(&file, vec![0u8; 512]).unwrap();
+
+        let mut metadata = with_entropy("/tmp/zeros.bin", None);
+        analyze_collected_file(&file, &mut metadata);
+        assert_eq!(metadata.entropy, Some(0.0));
+
+        let mut metadata = with_entropy("/tmp", None);
+        analyze_collected_file(temp.path(), &mut metadata);
+        assert_eq!(metadata.entropy, None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Executable memory with no file on disk behind it.
+//!
+//! Code loaded the normal way is mapped from an image file. Executable
+//! regions without one are left by shellcode injection, reflective DLL
+//! loading and process hollowing, so each is reported for review. JIT
+//! compilers (browsers, .NET, Java) also allocate anonymous executable
+//! memory, so a hit is a lead, not a verdict.
+//!
+//! A region counts as file-less when the platform reports no mapped file,
+//! or on Linux when the mapping name is a pseudo-path (`[heap]`,
+//! `[anon:...]`), a `memfd:` file or a deleted file. The kernel's own
+//! `[vdso]`, `[vsyscall]` and `[vectors]` pages are skipped.
+//!
+//! Only region maps are read, never region contents. Results are written to
+//! `volatile/suspicious_memory_regions.json`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::memory::models::{MemoryRegionInfo, MemoryRegionType};
+use crate::collectors::memory::platforms::MemoryCollectorImpl;
+use crate::collectors::volatile::models::ProcessInfo;
+
+/// File name used for the suspicious region listing
+pub const SUSPICIOUS_REGIONS_FILE: &str = "suspicious_memory_regions.json";
+
+/// Kernel-provided executable pages present in every Linux process
+const KERNEL_PSEUDO_MAPPINGS: &[&str] = &["[vdso]", "[vsyscall]", "[vectors]", "[uprobes]"];
+
+/// An executable region with no backing file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SuspiciousRegion {
+    pub pid: u32,
+    pub process_name: String,
+    pub base_address: u64,
+    pub size: u64,
+    pub region_type: MemoryRegionType,
+    /// Protection in `rwx` form
+    pub protection: String,
+    /// Mapping name, when the platform gave one (`[heap]`, `/memfd:x (deleted)`)
+    pub name: Option<String>,
+}
+
+/// Flag executable regions with no file on disk in each of `processes`.
+///
+/// Processes whose regions cannot be read (exited, access denied) are
+/// skipped.
+pub fn detect_suspicious_regions(
+    collector: &dyn MemoryCollectorImpl,
+    processes: &[ProcessInfo],
+) -> Result<Vec<SuspiciousRegion>> {
+    let mut suspicious = Vec::new();
+    for process in processes {
+        let regions = match collector.get_memory_regions(process) {
+            Ok(regions) => regions,
+            Err(e) => {
+                debug!("Skipping process {} ({}): {}", process.pid, process.name, e);
+                continue;
+            }
+        };
+        suspicious.extend(
+            regions
+                .iter()
+                .filter(|region| region.protection.execute && !is_file_backed(region))
+                .map(|region| SuspiciousRegion {
+                    pid: process.pid,
+                    process_name: process.name.clone(),
+                    base_address: region.base_address,
+                    size: region.size,
+                    region_type: region.region_type,
+                    protection: protection_string(region),
+                    name: region.mapped_file.clone().or_else(|| region.name.clone()),
+                }),
+        );
+    }
+    Ok(suspicious)
+}
+
+/// Whether `region` maps a file that exists on disk
+fn is_file_backed(region: &MemoryRegionInfo) -> bool {
+    let Some(path) = region.mapped_file.as_deref().map(str::trim) else {
+        return false;
+    };
+    if KERNEL_PSEUDO_MAPPINGS.contains(&path) {
+        // Not a file, but not injected either
+        return true;
+    }
+    !(path.is_empty()
+        || path.starts_with('[')
+        || path.starts_with("/memfd:")
+        || path.ends_with(" (deleted)"))
+}
+
+fn protection_string(region: &MemoryRegionInfo) -> String {
+    let flag = |set: bool, c: char| if set { c } else { '-' };
+    [
+        flag(region.protection.read, 'r'),
+        flag(region.protection.write, 'w'),
+        flag(region.protection.execute, 'x'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Write `regions` to [`SUSPICIOUS_REGIONS_FILE`] in `volatile_dir`
+pub fn write_suspicious_regions(
+    regions: &[SuspiciousRegion],
+    volatile_dir: &Path,
+) -> Result<PathBuf> {
+    let path = volatile_dir.join(SUSPICIOUS_REGIONS_FILE);
+    fs::write(&path, serde_json::to_string_pretty(regions)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    info!(
+        "Found {} executable memory regions without a backing file",
+        regions.len()
+    );
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::memory::models::{MemoryProtection, ModuleInfo};
+    use anyhow::bail;
+
+    struct FakeMaps;
+
+    fn region(base_address: u64, execute: bool, mapped_file: Option<&str>) -> MemoryRegionInfo {
+        MemoryRegionInfo {
+            base_address,
+            size: 0x1000,
+            region_type: MemoryRegionType::Code,
+            protection: MemoryProtection {
+                read: true,
+                write: !execute,
+                execute,
+            },
+            name: mapped_file.map(str::to_string),
+            mapped_file: mapped_file.map(str::to_string),
+            dumped: false,
+            dump_path: None,
+            gaps: Vec::new(),
+        }
+    }
+
+    impl MemoryCollectorImpl for FakeMaps {
+        fn new() -> Result<Self> {
+            Ok(Self)
+        }
+
+        fn get_memory_regions(&self, process: &ProcessInfo) -> Result<Vec<MemoryRegionInfo>> {
+            if process.pid == 2 {
+                bail!("access denied");
+            }
+            Ok(vec![
+                region(0x40_0000, true, Some("/usr/bin/target")),
+                region(0x7f00_0000, true, Some("/usr/lib/libc.so.6")),
+                region(0x7f10_0000, true, None),
+                region(0x7f20_0000, false, None),
+                region(0x7f30_0000, true, Some("/memfd:payload (deleted)")),
+                region(0x7f40_0000, true, Some("[vdso]")),
+                region(0x7f50_0000, true, Some("[heap]")),
+            ])
+        }
+
+        fn read_memory(&self, _pid: u32, _address: u64, _size: usize) -> Result<Vec<u8>> {
+            bail!("region contents are never read")
+        }
+
+        fn get_modules(&self, _process: &ProcessInfo) -> Result<Vec<ModuleInfo>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn process(pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: format!("proc{}", pid),
+            cmd: vec![],
+            exe: None,
+            status: "Running".into(),
+            start_time: 0,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_suspicious_regions() {
+        let regions = detect_suspicious_regions(&FakeMaps, &[process(1), process(2)]).unwrap();
+
+        let bases: Vec<u64> = regions.iter().map(|r| r.base_address).collect();
+        assert_eq!(bases, vec![0x7f10_0000, 0x7f30_0000, 0x7f50_0000]);
+        assert!(regions
+            .iter()
+            .all(|r| r.pid == 1 && r.process_name == "proc1"));
+        assert_eq!(regions[0].protection, "r-x");
+        assert_eq!(regions[0].name, None);
+        assert_eq!(regions[1].name.as_deref(), Some("/memfd:payload (deleted)"));
+    }
+}
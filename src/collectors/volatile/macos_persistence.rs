@@ -0,0 +1,502 @@
+//! Live macOS persistence inventory.
+//!
+//! Configuration profiles, system extensions and Background Task Management
+//! items are only partly visible in the files the collector copies. This
+//! module asks the system tools instead:
+//!
+//! - `profiles list` for installed configuration profiles
+//! - `systemextensionsctl list` for system extensions and their state
+//! - `sfltool dumpbtm` for login items and background tasks (macOS 13+)
+//!
+//! Each command is written to its own file under
+//! `volatile/macos_persistence/` with its status, exit code, raw output and
+//! the parsed records. Commands refused by TCC (missing Full Disk Access) are
+//! recorded as `tcc_denied` with a note instead of leaving an empty file.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::run_command;
+
+/// Directory under the volatile output that receives the command captures
+pub const MACOS_PERSISTENCE_DIR: &str = "macos_persistence";
+
+/// Whether the inventory is collected on this platform
+pub const SUPPORTED: bool = cfg!(target_os = "macos");
+
+/// First macOS major version with Background Task Management
+const BTM_MIN_MAJOR_VERSION: u32 = 13;
+
+/// Outcome of running an inventory command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStatus {
+    /// The command ran and its output was parsed
+    Ok,
+    /// The command was refused by TCC; grant Full Disk Access and rerun
+    TccDenied,
+    /// The command is missing or not supported by this macOS version
+    Unavailable,
+    /// The command ran and failed for another reason
+    Failed,
+}
+
+/// Exit code and output of a finished command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandResult {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandResult {
+    fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// A captured inventory command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandCapture {
+    /// Command line that was run
+    pub command: String,
+    pub status: CaptureStatus,
+    pub exit_code: Option<i32>,
+    /// Why the output is missing or incomplete
+    pub note: Option<String>,
+    /// Raw standard output
+    pub output: String,
+    /// Records parsed from the output
+    pub records: Vec<BTreeMap<String, String>>,
+}
+
+/// Whether command output reports a TCC (privacy) refusal
+pub fn is_tcc_denial(output: &str) -> bool {
+    let output = output.to_lowercase();
+    output.contains("operation not permitted") || output.contains("full disk access")
+}
+
+/// Major version from an `os_version` such as `13.4.1`
+fn major_version(os_version: &str) -> Option<u32> {
+    os_version.split('.').next()?.trim().parse().ok()
+}
+
+/// Parse `profiles list` output into `scope` and `identifier` records
+pub fn parse_profiles_list(output: &str) -> Vec<BTreeMap<String, String>> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (prefix, identifier) = line.split_once("attribute: profileIdentifier:")?;
+            let scope = prefix.trim().split('[').next().unwrap_or_default();
+            let mut record = BTreeMap::new();
+            record.insert("scope".to_string(), scope.to_string());
+            record.insert("identifier".to_string(), identifier.trim().to_string());
+            Some(record)
+        })
+        .collect()
+}
+
+/// Parse `systemextensionsctl list` output, one record per extension
+pub fn parse_system_extensions(output: &str) -> Vec<BTreeMap<String, String>> {
+    let mut records = Vec::new();
+    let mut category = String::new();
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("--- ") {
+            category = rest.trim().to_string();
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 6 || fields[0].trim() == "enabled" {
+            continue;
+        }
+
+        let (bundle_id, version) = match fields[3].trim().split_once(" (") {
+            Some((id, version)) => (id, version.trim_end_matches(')')),
+            None => (fields[3].trim(), ""),
+        };
+        let mut record = BTreeMap::new();
+        record.insert("category".to_string(), category.clone());
+        record.insert("enabled".to_string(), (fields[0].trim() == "*").to_string());
+        record.insert("active".to_string(), (fields[1].trim() == "*").to_string());
+        record.insert("team_id".to_string(), fields[2].trim().to_string());
+        record.insert("bundle_id".to_string(), bundle_id.to_string());
+        record.insert("version".to_string(), version.to_string());
+        record.insert("name".to_string(), fields[4].trim().to_string());
+        record.insert(
+            "state".to_string(),
+            fields[5]
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+        );
+        records.push(record);
+    }
+
+    records
+}
+
+/// `Developer Name` -> `developer_name`
+fn snake_case_key(key: &str) -> String {
+    key.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Parse `sfltool dumpbtm` output, one record per `#N:` item block.
+///
+/// Keys are snake_cased; nested `#N: value` lines (embedded item
+/// identifiers) are joined with `, ` under the key that introduces them.
+/// Each record carries the `uid` of the `Records for UID` section it is in.
+pub fn parse_dumpbtm(output: &str) -> Vec<BTreeMap<String, String>> {
+    let mut records = Vec::new();
+    let mut current: Option<BTreeMap<String, String>> = None;
+    let mut uid = String::new();
+    let mut list_key: Option<String> = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("Records for UID") {
+            records.extend(current.take());
+            uid = rest
+                .trim()
+                .split(|c: char| c == ':' || c.is_whitespace())
+                .find(|s| !s.is_empty())
+                .unwrap_or_default()
+                .to_string();
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        if key
+            .strip_prefix('#')
+            .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+        {
+            if value.is_empty() {
+                records.extend(current.take());
+                let mut record = BTreeMap::new();
+                record.insert("uid".to_string(), uid.clone());
+                current = Some(record);
+                list_key = None;
+            } else if let (Some(record), Some(list_key)) = (current.as_mut(), list_key.as_ref()) {
+                let entry = record.entry(list_key.clone()).or_default();
+                if !entry.is_empty() {
+                    entry.push_str(", ");
+                }
+                entry.push_str(value);
+            }
+            continue;
+        }
+
+        if let Some(record) = current.as_mut() {
+            let key = snake_case_key(key);
+            if value.is_empty() {
+                list_key = Some(key);
+            } else {
+                list_key = None;
+                record.insert(key, value.to_string());
+            }
+        }
+    }
+
+    records.extend(current);
+    records
+}
+
+/// Turn a command's result into a capture, parsing successful output
+pub fn capture(
+    command: &str,
+    result: io::Result<CommandResult>,
+    parse: fn(&str) -> Vec<BTreeMap<String, String>>,
+) -> CommandCapture {
+    let mut capture = CommandCapture {
+        command: command.to_string(),
+        status: CaptureStatus::Ok,
+        exit_code: None,
+        note: None,
+        output: String::new(),
+        records: Vec::new(),
+    };
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            capture.status = CaptureStatus::Unavailable;
+            capture.note = Some(format!("{} is not available on this system", command));
+            return capture;
+        }
+        Err(e) => {
+            capture.status = CaptureStatus::Failed;
+            capture.note = Some(format!("Failed to execute {}: {}", command, e));
+            return capture;
+        }
+    };
+
+    capture.exit_code = result.exit_code;
+    if is_tcc_denial(&result.stdout) || is_tcc_denial(&result.stderr) {
+        capture.status = CaptureStatus::TccDenied;
+        capture.note = Some(format!(
+            "{} was denied by TCC; grant the collector Full Disk Access and rerun",
+            command
+        ));
+    } else if !result.success() {
+        capture.status = CaptureStatus::Failed;
+        capture.note = Some(format!(
+            "{} exited with {}: {}",
+            command,
+            result
+                .exit_code
+                .map_or_else(|| "a signal".to_string(), |code| code.to_string()),
+            result.stderr.trim()
+        ));
+    } else {
+        capture.records = parse(&result.stdout);
+    }
+    capture.output = result.stdout;
+    capture
+}
+
+/// Run each inventory command through `run`.
+///
+/// Returns the file name and capture for each command. `sfltool dumpbtm` is
+/// recorded as unavailable when `os_version` is older than macOS 13.
+pub fn capture_all<F>(os_version: Option<&str>, run: F) -> Vec<(&'static str, CommandCapture)>
+where
+    F: Fn(&str, &[&str]) -> io::Result<CommandResult>,
+{
+    let mut captures = vec![
+        (
+            "profiles.json",
+            capture(
+                "profiles list",
+                run("profiles", &["list"]),
+                parse_profiles_list,
+            ),
+        ),
+        (
+            "system_extensions.json",
+            capture(
+                "systemextensionsctl list",
+                run("systemextensionsctl", &["list"]),
+                parse_system_extensions,
+            ),
+        ),
+    ];
+
+    let btm_supported = os_version
+        .and_then(major_version)
+        .is_none_or(|major| major >= BTM_MIN_MAJOR_VERSION);
+    let btm = if btm_supported {
+        capture(
+            "sfltool dumpbtm",
+            run("sfltool", &["dumpbtm"]),
+            parse_dumpbtm,
+        )
+    } else {
+        CommandCapture {
+            command: "sfltool dumpbtm".to_string(),
+            status: CaptureStatus::Unavailable,
+            exit_code: None,
+            note: Some(format!(
+                "Background Task Management requires macOS {} or later",
+                BTM_MIN_MAJOR_VERSION
+            )),
+            output: String::new(),
+            records: Vec::new(),
+        }
+    };
+    captures.push(("btm_dump.json", btm));
+
+    captures
+}
+
+/// Run an inventory command on the live system
+fn run_persistence_command(program: &str, args: &[&str]) -> io::Result<CommandResult> {
+    let output = run_command(Command::new(program).args(args))?;
+    Ok(CommandResult {
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+/// Capture the live persistence inventory of this host
+pub fn collect_persistence_inventory(
+    os_version: Option<&str>,
+) -> Vec<(&'static str, CommandCapture)> {
+    capture_all(os_version, run_persistence_command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &str = "\
+_computerlevel[1] attribute: profileIdentifier: com.example.mdm
+_computerlevel[2] attribute: profileIdentifier: com.example.wifi
+alice[1] attribute: profileIdentifier: com.example.vpn
+There are 3 configuration profiles installed
+";
+
+    const EXTENSIONS: &str = "\
+2 extension(s)
+--- com.apple.system_extension.network_extension
+enabled\tactive\tteamID\tbundleID (version)\tname\t[state]
+*\t*\tABCDE12345\tcom.example.filter (1.2/34)\tExample Filter\t[activated enabled]
+--- com.apple.system_extension.endpoint_security
+enabled\tactive\tteamID\tbundleID (version)\tname\t[state]
+\t*\tVWXYZ67890\tcom.other.es (2.0/1)\tOther ES\t[activated waiting for user]
+";
+
+    const DUMPBTM: &str = "\
+========================
+ Records for UID 501 : A1B2C3D4-0000-1111-2222-333344445555
+========================
+
+ ServiceManagement migrated: true
+
+ Items:
+
+ #1:
+                 UUID: 0F0E0D0C-0000-1111-2222-333344445555
+                 Name: Example
+       Developer Name: Example Inc
+                 Type: app (0x2)
+          Disposition: [enabled, allowed, visible, notified] (0xb)
+           Identifier: 2.com.example.app
+                  URL: file:///Applications/Example.app/
+    Bundle Identifier: com.example.app
+    Embedded Item Identifiers:
+        #1: 16.com.example.updater
+        #2: 16.com.example.helper
+
+ #2:
+                 UUID: 1F1E1D1C-0000-1111-2222-333344445555
+                 Name: com.example.updater
+                 Type: agent (0x10008)
+          Disposition: [enabled, allowed, visible, notified] (0xb)
+           Identifier: 16.com.example.updater
+      Executable Path: /Library/Application Support/Example/updater
+";
+
+    fn finished(exit_code: i32, stdout: &str, stderr: &str) -> io::Result<CommandResult> {
+        Ok(CommandResult {
+            exit_code: Some(exit_code),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parse_profiles_list() {
+        let records = parse_profiles_list(PROFILES);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["scope"], "_computerlevel");
+        assert_eq!(records[0]["identifier"], "com.example.mdm");
+        assert_eq!(records[2]["scope"], "alice");
+        assert!(parse_profiles_list("There are no configuration profiles installed").is_empty());
+    }
+
+    #[test]
+    fn test_parse_system_extensions() {
+        let records = parse_system_extensions(EXTENSIONS);
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0]["category"],
+            "com.apple.system_extension.network_extension"
+        );
+        assert_eq!(records[0]["enabled"], "true");
+        assert_eq!(records[0]["bundle_id"], "com.example.filter");
+        assert_eq!(records[0]["version"], "1.2/34");
+        assert_eq!(records[0]["state"], "activated enabled");
+        assert_eq!(records[1]["enabled"], "false");
+        assert_eq!(records[1]["active"], "true");
+        assert_eq!(records[1]["team_id"], "VWXYZ67890");
+        assert_eq!(records[1]["name"], "Other ES");
+    }
+
+    #[test]
+    fn test_parse_dumpbtm() {
+        let records = parse_dumpbtm(DUMPBTM);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["uid"], "501");
+        assert_eq!(records[0]["name"], "Example");
+        assert_eq!(records[0]["developer_name"], "Example Inc");
+        assert_eq!(records[0]["bundle_identifier"], "com.example.app");
+        assert_eq!(
+            records[0]["embedded_item_identifiers"],
+            "16.com.example.updater, 16.com.example.helper"
+        );
+        assert_eq!(
+            records[1]["executable_path"],
+            "/Library/Application Support/Example/updater"
+        );
+        assert_eq!(records[1]["type"], "agent (0x10008)");
+    }
+
+    #[test]
+    fn test_capture_status() {
+        let ok = capture(
+            "profiles list",
+            finished(0, PROFILES, ""),
+            parse_profiles_list,
+        );
+        assert_eq!(ok.status, CaptureStatus::Ok);
+        assert_eq!(ok.records.len(), 3);
+        assert_eq!(ok.note, None);
+
+        let denied = capture(
+            "sfltool dumpbtm",
+            finished(1, "", "sfltool: Operation not permitted"),
+            parse_dumpbtm,
+        );
+        assert_eq!(denied.status, CaptureStatus::TccDenied);
+        assert!(denied.records.is_empty());
+        assert!(denied.note.unwrap().contains("Full Disk Access"));
+
+        let missing = capture(
+            "systemextensionsctl list",
+            Err(io::Error::from(io::ErrorKind::NotFound)),
+            parse_system_extensions,
+        );
+        assert_eq!(missing.status, CaptureStatus::Unavailable);
+
+        let failed = capture(
+            "profiles list",
+            finished(2, "", "usage"),
+            parse_profiles_list,
+        );
+        assert_eq!(failed.status, CaptureStatus::Failed);
+        assert_eq!(failed.exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_capture_all_skips_btm_before_ventura() {
+        let run = |program: &str, _: &[&str]| match program {
+            "profiles" => finished(0, PROFILES, ""),
+            "systemextensionsctl" => finished(0, EXTENSIONS, ""),
+            _ => finished(0, DUMPBTM, ""),
+        };
+
+        let captures = capture_all(Some("12.6.1"), run);
+        let names: Vec<&str> = captures.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            vec!["profiles.json", "system_extensions.json", "btm_dump.json"]
+        );
+        assert_eq!(captures[2].1.status, CaptureStatus::Unavailable);
+
+        let captures = capture_all(Some("14.2"), run);
+        assert_eq!(captures[2].1.status, CaptureStatus::Ok);
+        assert_eq!(captures[2].1.records.len(), 2);
+    }
+}
@@ -4,18 +4,52 @@
 //! - System information
 //! - Running processes
 //! - Network connections
+//! - TCP sockets with their owning processes
+//! - Packet sockets on Linux (see [`crate::collectors::linux::proc_net`])
 //! - Memory usage
 //! - Disk information
+//! - Live scheduled task state
+//! - macOS configuration profiles, system extensions and background items
+//! - Windows firewall rules, hosts file, proxies, DNS servers and routes
+//! - Start/end state diffs across a collection run
+//! - Console, RDP and remote login session history
+//! - Executable memory regions with no backing file (`--detect-injected-code`)
+//! - Hashes and signature status of process binaries (`--hash-process-binaries`)
+//! - Processes that look like cryptocurrency miners
+//! - Parent-child process ancestry and orphaned processes
+//! - Windows services compared with a default installation
+//! - Entropy of collected files, for packed or encrypted content (`--compute-entropy`)
 //!
-//! The data is collected using the sysinfo crate and stored in JSON format.
+//! The data is collected using the sysinfo crate and stored in JSON format,
+//! optionally with CSV exports of the tabular sections (see
+//! [`models::VolatileOutputFormat`]).
 
 mod collector;
+pub mod crypto_miners;
+mod csv;
+pub mod entropy_analysis;
+pub mod hollowing_detector;
+pub mod macos_persistence;
 pub mod models;
+pub mod process_ancestry;
+pub mod process_binaries;
+pub mod scheduled_tasks_live;
+pub mod services;
+pub mod state_diff;
+pub mod tcp_sockets;
+pub mod user_sessions;
+pub mod windows_network_config;
 
-pub use collector::VolatileDataCollector;
+pub use collector::{VolatileDataCollector, VOLATILE_REPORT_FILE};
 // Used in main.rs
 #[allow(unused_imports)]
 pub use models::VolatileDataSummary;
+// Library entry point for the service baseline comparison
+#[allow(unused_imports)]
+pub use services::running_services_diff;
+// The miner heuristics under their detector name
+#[allow(unused_imports)]
+pub use crypto_miners as crypto_miners_detector;
 
 // Convenience functions for collecting specific volatile data
 use anyhow::Result;
@@ -1,5 +1,11 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::collectors::volatile::process_binaries::ProcessBinaryStats;
+use crate::utils::timezone::HostTimeContext;
+
 /// System information data structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SystemInfo {
@@ -8,6 +14,9 @@ pub struct SystemInfo {
     pub os_version: Option<String>,
     pub kernel_version: Option<String>,
     pub cpu_info: CpuInfo,
+    /// Host timezone, clock skew and recent clock changes
+    #[serde(default)]
+    pub time_context: Option<HostTimeContext>,
 }
 
 /// CPU information data structure
@@ -100,6 +109,72 @@ pub struct VolatileDataSummary {
     pub cpu_count: usize,
     pub total_memory_mb: u64,
     pub process_count: usize,
+    /// Sorted, de-duplicated names of the running processes
+    #[serde(default)]
+    pub process_names: Vec<String>,
     pub network_interface_count: usize,
     pub disk_count: usize,
+    /// Files written by the collector, relative to the volatile output directory
+    #[serde(default)]
+    pub output_files: Vec<String>,
+    /// Process binary counts, with `--hash-process-binaries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_binaries: Option<ProcessBinaryStats>,
+    /// Orphaned processes found while building `process_ancestry.json`
+    #[serde(default)]
+    pub process_ancestry_anomalies: usize,
+}
+
+/// Output format for volatile data files (`global_options.volatile_format`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolatileOutputFormat {
+    /// Separate JSON files per section (default)
+    #[default]
+    Json,
+    /// CSV files for tabular sections
+    Csv,
+    /// Both JSON and CSV files
+    Both,
+}
+
+impl VolatileOutputFormat {
+    /// Whether per-section JSON files should be written
+    pub fn writes_json(&self) -> bool {
+        matches!(
+            self,
+            VolatileOutputFormat::Json | VolatileOutputFormat::Both
+        )
+    }
+
+    /// Whether CSV files should be written
+    pub fn writes_csv(&self) -> bool {
+        matches!(self, VolatileOutputFormat::Csv | VolatileOutputFormat::Both)
+    }
+}
+
+impl FromStr for VolatileOutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(VolatileOutputFormat::Json),
+            "csv" => Ok(VolatileOutputFormat::Csv),
+            "both" => Ok(VolatileOutputFormat::Both),
+            other => Err(anyhow::anyhow!(
+                "Invalid volatile_format '{}': expected json, csv or both",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for VolatileOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VolatileOutputFormat::Json => write!(f, "json"),
+            VolatileOutputFormat::Csv => write!(f, "csv"),
+            VolatileOutputFormat::Both => write!(f, "both"),
+        }
+    }
 }
@@ -0,0 +1,247 @@
+//! Parent-child hierarchy of the running processes.
+//!
+//! Each process is linked to its parent through `parent_pid`, and its
+//! ancestry is recorded as the chain of process names from the top of its
+//! tree down to itself (`systemd→sshd→bash→python3→curl`), which is what an
+//! analyst reads to tell a shell spawned by a web server from one spawned
+//! by a login.
+//!
+//! A process is orphaned when it names a parent that is not in the list, or
+//! one that started after it, meaning the parent exited and its PID was
+//! reused. Injected or hollowed processes are often started by a loader
+//! that exits right away, so orphans are reported as anomalies. Windows
+//! does not reparent processes whose parent exits, so orphans are common
+//! there and are leads rather than findings. PID 0 as a parent is the
+//! kernel and is not an orphan. Results are written to
+//! `volatile/process_ancestry.json`.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::volatile::models::ProcessInfo;
+
+/// File name used for the ancestry tree
+pub const PROCESS_ANCESTRY_FILE: &str = "process_ancestry.json";
+
+/// Separator between the names of an ancestry chain
+pub const ANCESTRY_SEPARATOR: &str = "→";
+
+/// Why a process counts as orphaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// The parent PID is not in the process list
+    ParentNotRunning,
+    /// The process with the parent PID started after this one
+    ParentPidReused,
+}
+
+/// A process in the tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub name: String,
+    pub parent_pid: Option<u32>,
+    /// Names from the top of the tree down to this process
+    pub ancestry: String,
+    /// PIDs of `ancestry`, in the same order
+    pub ancestry_pids: Vec<u32>,
+    /// Ancestors above this process; 0 for a root
+    pub depth: usize,
+    pub children: Vec<u32>,
+    /// Set when the process is orphaned
+    pub orphan_reason: Option<OrphanReason>,
+}
+
+/// Contents of `process_ancestry.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessAncestryTree {
+    /// Processes without a parent in the tree, orphans included
+    pub roots: Vec<u32>,
+    /// PIDs of the orphaned processes
+    pub orphans: Vec<u32>,
+    /// Every process, by PID
+    pub processes: Vec<ProcessNode>,
+}
+
+impl ProcessAncestryTree {
+    /// Processes flagged as anomalies
+    pub fn anomaly_count(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// The node of `pid`
+    pub fn get(&self, pid: u32) -> Option<&ProcessNode> {
+        self.processes
+            .binary_search_by_key(&pid, |node| node.pid)
+            .ok()
+            .map(|index| &self.processes[index])
+    }
+}
+
+/// Build the parent-child hierarchy of `processes` from their `parent_pid`
+pub fn build_ancestry_tree(processes: &[ProcessInfo]) -> ProcessAncestryTree {
+    let by_pid: BTreeMap<u32, &ProcessInfo> = processes.iter().map(|p| (p.pid, p)).collect();
+
+    // The parent each process is linked to, if it is in the tree
+    let mut parents: BTreeMap<u32, u32> = BTreeMap::new();
+    let mut orphan_reasons: BTreeMap<u32, OrphanReason> = BTreeMap::new();
+    for process in by_pid.values() {
+        let Some(parent_pid) = process.parent_pid.filter(|&ppid| ppid != 0) else {
+            continue;
+        };
+        if parent_pid == process.pid {
+            continue;
+        }
+        match by_pid.get(&parent_pid) {
+            None => {
+                orphan_reasons.insert(process.pid, OrphanReason::ParentNotRunning);
+            }
+            // Start times of 0 are unknown
+            Some(parent) if parent.start_time > process.start_time && process.start_time != 0 => {
+                orphan_reasons.insert(process.pid, OrphanReason::ParentPidReused);
+            }
+            Some(_) => {
+                parents.insert(process.pid, parent_pid);
+            }
+        }
+    }
+
+    let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for (&child, &parent) in &parents {
+        children.entry(parent).or_default().push(child);
+    }
+
+    let nodes = by_pid
+        .values()
+        .map(|process| {
+            let ancestry_pids = chain(process.pid, &parents);
+            let ancestry = ancestry_pids
+                .iter()
+                .map(|pid| by_pid[pid].name.as_str())
+                .collect::<Vec<_>>()
+                .join(ANCESTRY_SEPARATOR);
+            ProcessNode {
+                pid: process.pid,
+                name: process.name.clone(),
+                parent_pid: process.parent_pid,
+                ancestry,
+                depth: ancestry_pids.len() - 1,
+                ancestry_pids,
+                children: children.remove(&process.pid).unwrap_or_default(),
+                orphan_reason: orphan_reasons.get(&process.pid).copied(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    ProcessAncestryTree {
+        roots: nodes
+            .iter()
+            .filter(|node| node.depth == 0)
+            .map(|node| node.pid)
+            .collect(),
+        orphans: orphan_reasons.keys().copied().collect(),
+        processes: nodes,
+    }
+}
+
+/// PIDs from the top of `pid`'s tree down to `pid`. A parent loop, which
+/// only a list read while PIDs were reused can contain, ends the chain.
+fn chain(pid: u32, parents: &BTreeMap<u32, u32>) -> Vec<u32> {
+    let mut pids = vec![pid];
+    let mut seen = HashSet::from([pid]);
+    let mut current = pid;
+    while let Some(&parent) = parents.get(&current) {
+        if !seen.insert(parent) {
+            break;
+        }
+        pids.push(parent);
+        current = parent;
+    }
+    pids.reverse();
+    pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, parent_pid: Option<u32>, start_time: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cmd: vec![name.to_string()],
+            exe: Some(format!("/usr/bin/{}", name)),
+            status: "Running".to_string(),
+            start_time,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid,
+        }
+    }
+
+    #[test]
+    fn test_ancestry_chains() {
+        let processes = [
+            process(1, "init", None, 100),
+            process(2, "kthreadd", Some(0), 100),
+            process(300, "sshd", Some(1), 110),
+            process(400, "bash", Some(300), 120),
+            process(500, "python3", Some(400), 130),
+            process(600, "curl", Some(500), 140),
+            process(601, "sleep", Some(500), 141),
+        ];
+        let tree = build_ancestry_tree(&processes);
+
+        assert_eq!(tree.roots, vec![1, 2]);
+        assert!(tree.orphans.is_empty());
+        assert_eq!(tree.anomaly_count(), 0);
+
+        let curl = tree.get(600).unwrap();
+        assert_eq!(curl.ancestry, "init→sshd→bash→python3→curl");
+        assert_eq!(curl.ancestry_pids, vec![1, 300, 400, 500, 600]);
+        assert_eq!(curl.depth, 4);
+        assert_eq!(tree.get(500).unwrap().children, vec![600, 601]);
+        assert_eq!(tree.get(1).unwrap().ancestry, "init");
+    }
+
+    #[test]
+    fn test_orphans() {
+        let processes = [
+            process(1, "init", None, 100),
+            // Parent exited without the child being reparented
+            process(700, "svchost.exe", Some(650), 200),
+            process(701, "cmd.exe", Some(700), 210),
+            // PID 800 was reused by a process newer than its "child"
+            process(800, "notepad", Some(1), 500),
+            process(900, "payload", Some(800), 300),
+        ];
+        let tree = build_ancestry_tree(&processes);
+
+        assert_eq!(tree.orphans, vec![700, 900]);
+        assert_eq!(tree.anomaly_count(), 2);
+        assert_eq!(tree.roots, vec![1, 700, 900]);
+        assert_eq!(
+            tree.get(700).unwrap().orphan_reason,
+            Some(OrphanReason::ParentNotRunning)
+        );
+        assert_eq!(
+            tree.get(900).unwrap().orphan_reason,
+            Some(OrphanReason::ParentPidReused)
+        );
+        assert_eq!(tree.get(900).unwrap().ancestry, "payload");
+        assert_eq!(tree.get(701).unwrap().ancestry, "svchost.exe→cmd.exe");
+        assert_eq!(tree.get(701).unwrap().orphan_reason, None);
+    }
+
+    #[test]
+    fn test_parent_loop_ends_chain() {
+        // Unknown start times cannot show which PID was reused
+        let processes = [process(10, "a", Some(11), 0), process(11, "b", Some(10), 0)];
+        let tree = build_ancestry_tree(&processes);
+        assert_eq!(tree.get(10).unwrap().ancestry, "b→a");
+        assert_eq!(tree.get(11).unwrap().ancestry, "a→b");
+        assert!(tree.roots.is_empty());
+    }
+}
@@ -0,0 +1,744 @@
+//! Hashes and signature status of running process binaries.
+//!
+//! `processes.json` names each process's executable but says nothing about
+//! the file. With `--hash-process-binaries`, every distinct executable is
+//! described once in `volatile/process_binaries.json`, keyed by path, with
+//! the PIDs running it: its SHA-256 (files over [`MAX_HASH_SIZE_MB`] are
+//! skipped), size and modification time, and its signature status.
+//!
+//! - **Windows**: Authenticode via `WinVerifyTrust`, falling back to the
+//!   system catalogs for catalog-signed binaries, and the signer's subject.
+//! - **macOS**: `SecStaticCodeCheckValidity`; the signing authority is read
+//!   with `codesign -dv` unless external commands are deferred.
+//! - **Linux**: no signature check.
+//!
+//! An executable deleted since it was started is flagged `deleted_on_disk`.
+//! On Linux it is still hashed through `/proc/<pid>/exe`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::platforms::common::rfc3339;
+use crate::collectors::read_only;
+use crate::collectors::volatile::models::ProcessInfo;
+use crate::utils::hash::sha256_reader;
+
+/// File written to the volatile data directory
+pub const PROCESS_BINARIES_FILE: &str = "process_binaries.json";
+
+/// Executables larger than this are not hashed
+pub const MAX_HASH_SIZE_MB: u64 = 256;
+
+/// Suffix Linux appends to `/proc/<pid>/exe` once the file is unlinked
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// Outcome of a code signature check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// Signed, and the signature verifies
+    Valid,
+    /// No signature (embedded or, on Windows, in a system catalog)
+    Unsigned,
+    /// Signed, but the signature does not verify (tampered, untrusted
+    /// root, revoked or expired certificate)
+    Invalid,
+    /// The check itself failed
+    Unknown,
+}
+
+/// Code signature of an executable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureInfo {
+    pub status: SignatureStatus,
+    /// Subject of the signing certificate (Windows) or the first signing
+    /// authority (macOS)
+    pub signer: Option<String>,
+    /// `authenticode`, `catalog` or `code_signature`
+    pub method: String,
+    /// Platform error code when the check did not return valid
+    pub error_code: Option<i64>,
+}
+
+/// One executable run by one or more processes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessBinary {
+    pub pids: Vec<u32>,
+    pub process_names: Vec<String>,
+    pub size: Option<u64>,
+    pub modified_time: Option<String>,
+    pub sha256: Option<String>,
+    /// Why no hash was computed
+    pub hash_error: Option<String>,
+    /// The executable no longer exists at its path (running from memory)
+    pub deleted_on_disk: bool,
+    pub signature: Option<SignatureInfo>,
+}
+
+/// Process binary counts for the collection summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessBinaryStats {
+    pub binaries: usize,
+    pub hashed: usize,
+    pub deleted_on_disk: usize,
+    pub unsigned: usize,
+    pub invalid_signature: usize,
+}
+
+impl ProcessBinaryStats {
+    pub fn from_binaries(binaries: &BTreeMap<String, ProcessBinary>) -> Self {
+        let with_status = |status| {
+            binaries
+                .values()
+                .filter(|b| b.signature.as_ref().map(|s| s.status) == Some(status))
+                .count()
+        };
+        ProcessBinaryStats {
+            binaries: binaries.len(),
+            hashed: binaries.values().filter(|b| b.sha256.is_some()).count(),
+            deleted_on_disk: binaries.values().filter(|b| b.deleted_on_disk).count(),
+            unsigned: with_status(SignatureStatus::Unsigned),
+            invalid_signature: with_status(SignatureStatus::Invalid),
+        }
+    }
+}
+
+/// Executable path without the Linux ` (deleted)` marker, and whether the
+/// marker was there
+fn split_deleted(exe: &str) -> (&str, bool) {
+    match exe.strip_suffix(DELETED_SUFFIX) {
+        Some(path) => (path, true),
+        None => (exe, false),
+    }
+}
+
+/// File to read an executable's contents from: the path itself, or on
+/// Linux the process's `/proc/<pid>/exe` link, which still opens the
+/// unlinked file
+fn readable_path(path: &Path, deleted: bool, pid: u32) -> PathBuf {
+    if cfg!(target_os = "linux") && deleted {
+        PathBuf::from(format!("/proc/{}/exe", pid))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// SHA-256 of `path` unless it is larger than `max_size` bytes
+fn hash_bounded(path: &Path, max_size: u64) -> Result<String, String> {
+    let file = read_only::open(path).map_err(|e| e.to_string())?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    if size > max_size {
+        return Err(format!(
+            "{} bytes, over the {} MB hashing limit",
+            size, MAX_HASH_SIZE_MB
+        ));
+    }
+    sha256_reader(BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+/// Describe the file behind one executable path
+fn describe(path: &str, deleted_marker: bool, pid: u32, allow_external: bool) -> ProcessBinary {
+    let path = Path::new(path);
+    let deleted = deleted_marker || !path.exists();
+    let source = readable_path(path, deleted, pid);
+    let metadata = fs::metadata(&source).ok();
+
+    let (sha256, hash_error) = match hash_bounded(&source, MAX_HASH_SIZE_MB * 1024 * 1024) {
+        Ok(hash) => (Some(hash), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    ProcessBinary {
+        pids: Vec::new(),
+        process_names: Vec::new(),
+        size: metadata.as_ref().map(|m| m.len()),
+        modified_time: rfc3339(metadata.and_then(|m| m.modified().ok())),
+        sha256,
+        hash_error,
+        deleted_on_disk: deleted,
+        signature: (!deleted)
+            .then(|| signature::check(path, allow_external))
+            .flatten(),
+    }
+}
+
+/// Describe each distinct executable of `processes` once, keyed by path.
+///
+/// `allow_external` permits external commands (`codesign`) for signer
+/// details.
+pub fn describe_binaries(
+    processes: &[ProcessInfo],
+    allow_external: bool,
+) -> BTreeMap<String, ProcessBinary> {
+    let mut binaries: BTreeMap<String, ProcessBinary> = BTreeMap::new();
+    for process in processes {
+        let Some(exe) = process.exe.as_deref().filter(|exe| !exe.is_empty()) else {
+            continue;
+        };
+        let (path, deleted) = split_deleted(exe);
+        let binary = binaries
+            .entry(path.to_string())
+            .or_insert_with(|| describe(path, deleted, process.pid, allow_external));
+        binary.pids.push(process.pid);
+        if !binary.process_names.contains(&process.name) {
+            binary.process_names.push(process.name.clone());
+        }
+    }
+    for binary in binaries.values_mut() {
+        binary.pids.sort_unstable();
+        binary.process_names.sort();
+    }
+    debug!("Described {} process binaries", binaries.len());
+    binaries
+}
+
+#[cfg(target_os = "windows")]
+mod signature {
+    //! Authenticode checks with `WinVerifyTrust`.
+    //!
+    //! Most Windows system binaries carry no embedded signature; their hash
+    //! is listed in a signed catalog instead, so a file without one is
+    //! looked up in the system catalogs before it is called unsigned.
+
+    use std::path::Path;
+    use std::ptr;
+
+    use widestring::U16CString;
+    use winapi::shared::guiddef::GUID;
+    use winapi::shared::minwindef::{BOOL, BYTE, DWORD};
+    use winapi::shared::ntdef::{HANDLE, LONG, LPCWSTR, WCHAR};
+    use winapi::um::softpub::WINTRUST_ACTION_GENERIC_VERIFY_V2;
+    use winapi::um::wincrypt::{
+        CertCloseStore, CertFindCertificateInStore, CertFreeCertificateContext, CertGetNameStringW,
+        CryptMsgClose, CryptMsgGetParam, CryptQueryObject, CERT_FIND_SUBJECT_CERT, CERT_INFO,
+        CERT_NAME_SIMPLE_DISPLAY_TYPE, CERT_QUERY_CONTENT_FLAG_ALL, CERT_QUERY_FORMAT_FLAG_BINARY,
+        CERT_QUERY_OBJECT_FILE, CMSG_SIGNER_INFO, CMSG_SIGNER_INFO_PARAM, HCERTSTORE, HCRYPTMSG,
+        PKCS_7_ASN_ENCODING, X509_ASN_ENCODING,
+    };
+    use winapi::um::wintrust::{
+        WinVerifyTrust, WINTRUST_DATA, WINTRUST_FILE_INFO, WTD_CHOICE_CATALOG, WTD_CHOICE_FILE,
+        WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+    };
+
+    use super::{SignatureInfo, SignatureStatus};
+
+    /// The file has no embedded signature
+    const TRUST_E_NOSIGNATURE: LONG = 0x800B0100_u32 as LONG;
+    /// The subject's form is not recognised (not a PE file)
+    const TRUST_E_SUBJECT_FORM_UNKNOWN: LONG = 0x800B0003_u32 as LONG;
+    /// Raised by the catalog path for files not listed in one
+    const TRUST_E_PROVIDER_UNKNOWN: LONG = 0x800B0001_u32 as LONG;
+
+    type HCatAdmin = HANDLE;
+    type HCatInfo = HANDLE;
+
+    /// `CATALOG_INFO`
+    #[repr(C)]
+    struct CatalogInfo {
+        cb_struct: DWORD,
+        catalog_file: [WCHAR; 260],
+    }
+
+    /// `WINTRUST_CATALOG_INFO`; winapi leaves it out of the union
+    #[repr(C)]
+    struct WintrustCatalogInfo {
+        cb_struct: DWORD,
+        catalog_version: DWORD,
+        catalog_file_path: LPCWSTR,
+        member_tag: LPCWSTR,
+        member_file_path: LPCWSTR,
+        member_file: HANDLE,
+        calculated_file_hash: *mut BYTE,
+        calculated_file_hash_len: DWORD,
+        catalog_context: *const std::ffi::c_void,
+        cat_admin: HCatAdmin,
+    }
+
+    #[link(name = "wintrust")]
+    extern "system" {
+        fn CryptCATAdminAcquireContext2(
+            ph_cat_admin: *mut HCatAdmin,
+            pg_subsystem: *const GUID,
+            pwsz_hash_algorithm: LPCWSTR,
+            p_strong_hash_policy: *const std::ffi::c_void,
+            dw_flags: DWORD,
+        ) -> BOOL;
+        fn CryptCATAdminCalcHashFromFileHandle2(
+            h_cat_admin: HCatAdmin,
+            h_file: HANDLE,
+            pcb_hash: *mut DWORD,
+            pb_hash: *mut BYTE,
+            dw_flags: DWORD,
+        ) -> BOOL;
+        fn CryptCATAdminEnumCatalogFromHash(
+            h_cat_admin: HCatAdmin,
+            pb_hash: *mut BYTE,
+            cb_hash: DWORD,
+            dw_flags: DWORD,
+            ph_prev_cat_info: *mut HCatInfo,
+        ) -> HCatInfo;
+        fn CryptCATCatalogInfoFromContext(
+            h_cat_info: HCatInfo,
+            ps_cat_info: *mut CatalogInfo,
+            dw_flags: DWORD,
+        ) -> BOOL;
+        fn CryptCATAdminReleaseCatalogContext(
+            h_cat_admin: HCatAdmin,
+            h_cat_info: HCatInfo,
+            dw_flags: DWORD,
+        ) -> BOOL;
+        fn CryptCATAdminReleaseContext(h_cat_admin: HCatAdmin, dw_flags: DWORD) -> BOOL;
+    }
+
+    fn status_for(code: LONG) -> SignatureStatus {
+        match code {
+            0 => SignatureStatus::Valid,
+            TRUST_E_NOSIGNATURE | TRUST_E_SUBJECT_FORM_UNKNOWN | TRUST_E_PROVIDER_UNKNOWN => {
+                SignatureStatus::Unsigned
+            }
+            _ => SignatureStatus::Invalid,
+        }
+    }
+
+    /// Run `WinVerifyTrust` on prepared trust data, closing its state
+    ///
+    /// # Safety
+    /// `data` must point at a fully initialised `WINTRUST_DATA` whose union
+    /// member matches `dwUnionChoice`.
+    unsafe fn verify(data: &mut WINTRUST_DATA) -> LONG {
+        let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+        data.dwStateAction = WTD_STATEACTION_VERIFY;
+        let result = WinVerifyTrust(
+            ptr::null_mut(),
+            &mut action,
+            data as *mut WINTRUST_DATA as *mut _,
+        );
+        data.dwStateAction = WTD_STATEACTION_CLOSE;
+        WinVerifyTrust(
+            ptr::null_mut(),
+            &mut action,
+            data as *mut WINTRUST_DATA as *mut _,
+        );
+        result
+    }
+
+    fn trust_data(choice: DWORD) -> WINTRUST_DATA {
+        // SAFETY: WINTRUST_DATA is plain data; all-zero is a valid start
+        let mut data: WINTRUST_DATA = unsafe { std::mem::zeroed() };
+        data.cbStruct = std::mem::size_of::<WINTRUST_DATA>() as DWORD;
+        data.dwUIChoice = WTD_UI_NONE;
+        // Revocation checks go to the network; the chain is still checked
+        data.fdwRevocationChecks = WTD_REVOKE_NONE;
+        data.dwUnionChoice = choice;
+        data
+    }
+
+    /// Verify the embedded Authenticode signature
+    fn verify_embedded(path: &U16CString) -> LONG {
+        let mut file_info = WINTRUST_FILE_INFO {
+            cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as DWORD,
+            pcwszFilePath: path.as_ptr(),
+            hFile: ptr::null_mut(),
+            pgKnownSubject: ptr::null(),
+        };
+        let mut data = trust_data(WTD_CHOICE_FILE);
+        // SAFETY: the union holds the file info, which outlives the call
+        unsafe {
+            *data.u.pFile_mut() = &mut file_info;
+            verify(&mut data)
+        }
+    }
+
+    /// Verify the file against the system catalog listing its hash, if
+    /// any; returns the result and the catalog path
+    fn verify_catalog(path: &Path, wide_path: &U16CString) -> Option<(LONG, String)> {
+        let file = crate::collectors::read_only::open(path).ok()?;
+        let handle = std::os::windows::io::AsRawHandle::as_raw_handle(&file) as HANDLE;
+        let sha256 = U16CString::from_str("SHA256").ok()?;
+
+        // SAFETY: handles are released on every path below; buffers are
+        // sized by the preceding calls
+        unsafe {
+            let mut admin: HCatAdmin = ptr::null_mut();
+            if CryptCATAdminAcquireContext2(
+                &mut admin,
+                ptr::null(),
+                sha256.as_ptr(),
+                ptr::null(),
+                0,
+            ) == 0
+            {
+                return None;
+            }
+            let mut hash_len: DWORD = 0;
+            CryptCATAdminCalcHashFromFileHandle2(admin, handle, &mut hash_len, ptr::null_mut(), 0);
+            let mut hash = vec![0u8; hash_len as usize];
+            let result = if hash_len > 0
+                && CryptCATAdminCalcHashFromFileHandle2(
+                    admin,
+                    handle,
+                    &mut hash_len,
+                    hash.as_mut_ptr(),
+                    0,
+                ) != 0
+            {
+                let cat_info = CryptCATAdminEnumCatalogFromHash(
+                    admin,
+                    hash.as_mut_ptr(),
+                    hash_len,
+                    0,
+                    ptr::null_mut(),
+                );
+                if cat_info.is_null() {
+                    None
+                } else {
+                    let mut info = CatalogInfo {
+                        cb_struct: std::mem::size_of::<CatalogInfo>() as DWORD,
+                        catalog_file: [0; 260],
+                    };
+                    let checked = if CryptCATCatalogInfoFromContext(cat_info, &mut info, 0) != 0 {
+                        let tag: String = hash.iter().map(|b| format!("{:02X}", b)).collect();
+                        let tag = U16CString::from_str(tag).ok();
+                        tag.map(|tag| {
+                            let mut catalog = WintrustCatalogInfo {
+                                cb_struct: std::mem::size_of::<WintrustCatalogInfo>() as DWORD,
+                                catalog_version: 0,
+                                catalog_file_path: info.catalog_file.as_ptr(),
+                                member_tag: tag.as_ptr(),
+                                member_file_path: wide_path.as_ptr(),
+                                member_file: ptr::null_mut(),
+                                calculated_file_hash: hash.as_mut_ptr(),
+                                calculated_file_hash_len: hash_len,
+                                catalog_context: ptr::null(),
+                                cat_admin: admin,
+                            };
+                            let mut data = trust_data(WTD_CHOICE_CATALOG);
+                            *data.u.pFile_mut() =
+                                &mut catalog as *mut WintrustCatalogInfo as *mut WINTRUST_FILE_INFO;
+                            let end = info
+                                .catalog_file
+                                .iter()
+                                .position(|&c| c == 0)
+                                .unwrap_or(info.catalog_file.len());
+                            (
+                                verify(&mut data),
+                                String::from_utf16_lossy(&info.catalog_file[..end]),
+                            )
+                        })
+                    } else {
+                        None
+                    };
+                    CryptCATAdminReleaseCatalogContext(admin, cat_info, 0);
+                    checked
+                }
+            } else {
+                None
+            };
+            CryptCATAdminReleaseContext(admin, 0);
+            result
+        }
+    }
+
+    /// Subject of the first signer of a signed file or catalog
+    fn signer_subject(path: &U16CString) -> Option<String> {
+        // SAFETY: the store, message and certificate are released below;
+        // the signer info buffer is sized by the first CryptMsgGetParam
+        unsafe {
+            let mut encoding: DWORD = 0;
+            let mut content_type: DWORD = 0;
+            let mut format_type: DWORD = 0;
+            let mut store: HCERTSTORE = ptr::null_mut();
+            let mut message: HCRYPTMSG = ptr::null_mut();
+            if CryptQueryObject(
+                CERT_QUERY_OBJECT_FILE,
+                path.as_ptr() as *const _,
+                CERT_QUERY_CONTENT_FLAG_ALL,
+                CERT_QUERY_FORMAT_FLAG_BINARY,
+                0,
+                &mut encoding,
+                &mut content_type,
+                &mut format_type,
+                &mut store,
+                &mut message,
+                ptr::null_mut(),
+            ) == 0
+            {
+                return None;
+            }
+
+            let mut subject = None;
+            let mut len: DWORD = 0;
+            if !message.is_null()
+                && CryptMsgGetParam(
+                    message,
+                    CMSG_SIGNER_INFO_PARAM,
+                    0,
+                    ptr::null_mut(),
+                    &mut len,
+                ) != 0
+            {
+                // u64 keeps the CMSG_SIGNER_INFO header aligned
+                let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+                if CryptMsgGetParam(
+                    message,
+                    CMSG_SIGNER_INFO_PARAM,
+                    0,
+                    buffer.as_mut_ptr() as *mut _,
+                    &mut len,
+                ) != 0
+                {
+                    let signer = &*(buffer.as_ptr() as *const CMSG_SIGNER_INFO);
+                    let mut cert_info: CERT_INFO = std::mem::zeroed();
+                    cert_info.Issuer = signer.Issuer;
+                    cert_info.SerialNumber = signer.SerialNumber;
+                    let cert = CertFindCertificateInStore(
+                        store,
+                        X509_ASN_ENCODING | PKCS_7_ASN_ENCODING,
+                        0,
+                        CERT_FIND_SUBJECT_CERT,
+                        &cert_info as *const CERT_INFO as *const _,
+                        ptr::null(),
+                    );
+                    if !cert.is_null() {
+                        let mut name = [0u16; 512];
+                        let written = CertGetNameStringW(
+                            cert,
+                            CERT_NAME_SIMPLE_DISPLAY_TYPE,
+                            0,
+                            ptr::null_mut(),
+                            name.as_mut_ptr(),
+                            name.len() as DWORD,
+                        );
+                        if written > 1 {
+                            subject = Some(String::from_utf16_lossy(&name[..written as usize - 1]));
+                        }
+                        CertFreeCertificateContext(cert);
+                    }
+                }
+            }
+            if !message.is_null() {
+                CryptMsgClose(message);
+            }
+            if !store.is_null() {
+                CertCloseStore(store, 0);
+            }
+            subject
+        }
+    }
+
+    pub(super) fn check(path: &Path, _allow_external: bool) -> Option<SignatureInfo> {
+        let wide_path = U16CString::from_os_str(path.as_os_str()).ok()?;
+
+        let embedded = verify_embedded(&wide_path);
+        if embedded != TRUST_E_NOSIGNATURE {
+            let status = status_for(embedded);
+            return Some(SignatureInfo {
+                status,
+                signer: (status != SignatureStatus::Unsigned)
+                    .then(|| signer_subject(&wide_path))
+                    .flatten(),
+                method: "authenticode".to_string(),
+                error_code: (embedded != 0).then_some(embedded as u32 as i64),
+            });
+        }
+
+        match verify_catalog(path, &wide_path) {
+            Some((result, catalog)) => Some(SignatureInfo {
+                status: status_for(result),
+                signer: U16CString::from_str(&catalog)
+                    .ok()
+                    .and_then(|c| signer_subject(&c)),
+                method: "catalog".to_string(),
+                error_code: (result != 0).then_some(result as u32 as i64),
+            }),
+            None => Some(SignatureInfo {
+                status: SignatureStatus::Unsigned,
+                signer: None,
+                method: "authenticode".to_string(),
+                error_code: Some(TRUST_E_NOSIGNATURE as u32 as i64),
+            }),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod signature {
+    //! Code signature checks with the Security framework.
+
+    use std::ffi::c_void;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::process::Command;
+    use std::ptr;
+
+    use super::{SignatureInfo, SignatureStatus};
+    use crate::collectors::footprint::run_command;
+
+    /// `errSecCSUnsigned`
+    const ERR_SEC_CS_UNSIGNED: i32 = -67062;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFURLCreateFromFileSystemRepresentation(
+            allocator: *const c_void,
+            buffer: *const u8,
+            length: isize,
+            is_directory: u8,
+        ) -> *const c_void;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        fn SecStaticCodeCreateWithPath(
+            path: *const c_void,
+            flags: u32,
+            static_code: *mut *const c_void,
+        ) -> i32;
+        fn SecStaticCodeCheckValidity(
+            static_code: *const c_void,
+            flags: u32,
+            requirement: *const c_void,
+        ) -> i32;
+    }
+
+    /// `SecStaticCodeCheckValidity` result for `path`, or the
+    /// `SecStaticCodeCreateWithPath` error if the file could not be opened
+    /// as code
+    fn check_validity(path: &Path) -> Option<Result<i32, i32>> {
+        let bytes = path.as_os_str().as_bytes();
+        // SAFETY: the URL and static code objects are released below
+        unsafe {
+            let url = CFURLCreateFromFileSystemRepresentation(
+                ptr::null(),
+                bytes.as_ptr(),
+                bytes.len() as isize,
+                0,
+            );
+            if url.is_null() {
+                return None;
+            }
+            let mut code: *const c_void = ptr::null();
+            let created = SecStaticCodeCreateWithPath(url, 0, &mut code);
+            CFRelease(url);
+            if created != 0 || code.is_null() {
+                return Some(Err(created));
+            }
+            let result = SecStaticCodeCheckValidity(code, 0, ptr::null());
+            CFRelease(code);
+            Some(Ok(result))
+        }
+    }
+
+    /// First `Authority=` line of `codesign -dv`, which writes to stderr
+    fn signing_authority(path: &Path) -> Option<String> {
+        let output = run_command(Command::new("codesign").arg("-dv").arg(path)).ok()?;
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find_map(|line| line.strip_prefix("Authority="))
+            .map(str::to_string)
+    }
+
+    pub(super) fn check(path: &Path, allow_external: bool) -> Option<SignatureInfo> {
+        let (status, result) = match check_validity(path)? {
+            Ok(0) => (SignatureStatus::Valid, 0),
+            Ok(ERR_SEC_CS_UNSIGNED) => (SignatureStatus::Unsigned, ERR_SEC_CS_UNSIGNED),
+            Ok(result) => (SignatureStatus::Invalid, result),
+            Err(result) => (SignatureStatus::Unknown, result),
+        };
+        Some(SignatureInfo {
+            status,
+            signer: (allow_external && status == SignatureStatus::Valid)
+                .then(|| signing_authority(path))
+                .flatten(),
+            method: "code_signature".to_string(),
+            error_code: (result != 0).then_some(result as i64),
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod signature {
+    use std::path::Path;
+
+    use super::SignatureInfo;
+
+    /// Executables are not signed on this platform
+    pub(super) fn check(_path: &Path, _allow_external: bool) -> Option<SignatureInfo> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn process(pid: u32, name: &str, exe: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cmd: Vec::new(),
+            exe: Some(exe.to_string()),
+            status: "Running".to_string(),
+            start_time: 0,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid: None,
+        }
+    }
+
+    #[test]
+    fn test_describe_binaries_hashes_each_path_once() {
+        let temp = TempDir::new().unwrap();
+        let exe = temp.path().join("chrome");
+        fs::write(&exe, b"abc").unwrap();
+        let exe = exe.to_string_lossy().to_string();
+        let gone = temp.path().join("dropper").to_string_lossy().to_string();
+
+        let processes = vec![
+            process(30, "chrome", &exe),
+            process(12, "chrome", &exe),
+            process(7, "dropper", &format!("{}{}", gone, DELETED_SUFFIX)),
+            process(2, "kthreadd", ""),
+        ];
+        let binaries = describe_binaries(&processes, false);
+        assert_eq!(binaries.len(), 2);
+
+        let chrome = &binaries[&exe];
+        assert_eq!(chrome.pids, vec![12, 30]);
+        assert_eq!(chrome.process_names, vec!["chrome"]);
+        assert_eq!(
+            chrome.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(chrome.size, Some(3));
+        assert!(!chrome.deleted_on_disk);
+
+        let dropper = &binaries[&gone];
+        assert!(dropper.deleted_on_disk);
+        assert_eq!(dropper.signature, None);
+
+        let stats = ProcessBinaryStats::from_binaries(&binaries);
+        assert_eq!(stats.binaries, 2);
+        assert_eq!(stats.deleted_on_disk, 1);
+    }
+
+    #[test]
+    fn test_hash_bounded() {
+        let temp = TempDir::new().unwrap();
+        let exe = temp.path().join("large");
+        fs::write(&exe, vec![0u8; 64]).unwrap();
+        assert!(hash_bounded(&exe, 64).is_ok());
+        assert!(hash_bounded(&exe, 63)
+            .unwrap_err()
+            .contains("hashing limit"));
+        assert_eq!(split_deleted("/tmp/x (deleted)"), ("/tmp/x", true));
+        assert_eq!(split_deleted("/usr/bin/x"), ("/usr/bin/x", false));
+    }
+}
@@ -0,0 +1,624 @@
+//! Runtime enumeration of scheduled tasks.
+//!
+//! Task definitions copied from disk do not always reflect the scheduler's
+//! live view (last/next run, last result, whether a task is executing right
+//! now). This module queries the scheduler itself:
+//!
+//! - **Windows**: the Task Scheduler 2.0 `ITaskService` COM interface
+//! - **Linux**: `atq` for pending `at` jobs, `crontab -l -u <user>` for
+//!   every account in `/etc/passwd`, and `systemctl list-timers --all` for
+//!   systemd timers
+//!
+//! Results are written to `volatile/scheduled_tasks_live.json`.
+
+use anyhow::Result;
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+
+/// File name used for the live scheduled task listing
+pub const SCHEDULED_TASKS_LIVE_FILE: &str = "scheduled_tasks_live.json";
+
+/// Runtime state of a scheduled task or job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledTaskRuntime {
+    /// Task or job name (at job number on Linux)
+    pub name: String,
+    /// Full task path (Windows) or source file/command (Linux)
+    pub path: String,
+    /// Scheduler that reported the task: `task_scheduler`, `at`, `crontab`,
+    /// or `systemd_timer`
+    pub source: String,
+    /// Owning user, when known
+    pub user: Option<String>,
+    /// Command line executed by the task, when known
+    pub command: Option<String>,
+    /// Raw schedule expression (cron spec or at queue time)
+    pub schedule: Option<String>,
+    /// Scheduler-reported state (Ready, Running, Disabled, ...)
+    pub state: Option<String>,
+    /// Whether the task is enabled
+    pub enabled: bool,
+    /// Last run time (RFC3339), if the task has run
+    pub last_run_time: Option<String>,
+    /// Next scheduled run time (RFC3339), if any
+    pub next_run_time: Option<String>,
+    /// Result code of the last run
+    pub last_result_code: Option<i32>,
+    /// Whether the task is executing at collection time
+    pub is_currently_running: bool,
+}
+
+/// Enumerate scheduled tasks from the live scheduler.
+///
+/// Missing scheduler tooling is not an error: an empty list is returned and
+/// the reason is logged at debug level.
+pub fn enumerate_tasks_live() -> Result<Vec<ScheduledTaskRuntime>> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::enumerate()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        linux_impl::enumerate()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        log::debug!("Live scheduled task enumeration is not supported on this platform");
+        Ok(Vec::new())
+    }
+}
+
+/// Convert an OLE Automation `DATE` (days since 1899-12-30) to RFC3339.
+///
+/// The Task Scheduler reports `0.0` for "never", which maps to `None`.
+pub fn ole_date_to_rfc3339(date: f64) -> Option<String> {
+    if date <= 0.0 || !date.is_finite() {
+        return None;
+    }
+
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let millis = (date * 86_400_000.0).round() as i64;
+    let naive = epoch.checked_add_signed(chrono::Duration::milliseconds(millis))?;
+
+    // Task Scheduler DATE values are expressed in local time
+    local_to_rfc3339(&naive)
+}
+
+fn local_to_rfc3339(naive: &chrono::NaiveDateTime) -> Option<String> {
+    chrono::Local
+        .from_local_datetime(naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+/// Parse the output of `atq`.
+///
+/// Each line looks like `12\tThu Jan 11 10:00:00 2024 a root`.
+pub fn parse_atq_output(output: &str) -> Vec<ScheduledTaskRuntime> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (job, rest) = line.trim().split_once(char::is_whitespace)?;
+            let mut fields: Vec<&str> = rest.split_whitespace().collect();
+            if fields.len() < 3 {
+                return None;
+            }
+
+            let user = fields.pop()?.to_string();
+            let queue = fields.pop()?.to_string();
+            let when = fields.join(" ");
+
+            Some(ScheduledTaskRuntime {
+                name: job.to_string(),
+                path: format!("at/{}", job),
+                source: "at".to_string(),
+                user: Some(user),
+                command: None,
+                schedule: Some(when.clone()),
+                // Queue "=" marks a job that is currently running
+                state: Some(if queue == "=" { "Running" } else { "Queued" }.to_string()),
+                enabled: true,
+                last_run_time: None,
+                // atq prints local time
+                next_run_time: chrono::NaiveDateTime::parse_from_str(&when, "%a %b %e %H:%M:%S %Y")
+                    .ok()
+                    .and_then(|dt| local_to_rfc3339(&dt)),
+                last_result_code: None,
+                is_currently_running: queue == "=",
+            })
+        })
+        .collect()
+}
+
+/// Parse the output of `crontab -l` for a single user.
+pub fn parse_crontab_output(user: &str, output: &str) -> Vec<ScheduledTaskRuntime> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (schedule, command) = if line.starts_with('@') {
+                let (spec, cmd) = line.split_once(char::is_whitespace)?;
+                (spec.to_string(), cmd.trim().to_string())
+            } else {
+                let fields: Vec<&str> = line.splitn(6, char::is_whitespace).collect();
+                if fields.len() < 6 || fields[0].contains('=') {
+                    // Environment assignments and malformed lines are not jobs
+                    return None;
+                }
+                (fields[..5].join(" "), fields[5].trim().to_string())
+            };
+
+            Some(ScheduledTaskRuntime {
+                name: command.split_whitespace().next().unwrap_or("").to_string(),
+                path: format!("crontab/{}", user),
+                source: "crontab".to_string(),
+                user: Some(user.to_string()),
+                command: Some(command),
+                schedule: Some(schedule),
+                state: Some("Ready".to_string()),
+                enabled: true,
+                last_run_time: None,
+                next_run_time: None,
+                last_result_code: None,
+                is_currently_running: false,
+            })
+        })
+        .collect()
+}
+
+/// Parse the output of `systemctl list-timers --all`.
+///
+/// The columns (`NEXT`, `LEFT`, `LAST`, `PASSED`, `UNIT`, `ACTIVATES`) are
+/// padded to fixed widths and the timestamps contain spaces, so each row is
+/// sliced at the offsets of the header labels. Timers that have never run or
+/// will not run again show `n/a`.
+pub fn parse_systemd_timers(output: &str) -> Vec<ScheduledTaskRuntime> {
+    let mut lines = output.lines();
+    let header = match lines.find(|line| line.trim_start().starts_with("NEXT")) {
+        Some(header) => header,
+        None => return Vec::new(),
+    };
+
+    let labels = ["NEXT", "LEFT", "LAST", "PASSED", "UNIT", "ACTIVATES"];
+    let offsets: Option<Vec<usize>> = labels.iter().map(|label| header.find(label)).collect();
+    let offsets = match offsets {
+        Some(offsets) if offsets.windows(2).all(|w| w[0] < w[1]) => offsets,
+        _ => return Vec::new(),
+    };
+
+    let column = |line: &str, index: usize| -> String {
+        let start = offsets[index];
+        let end = offsets.get(index + 1).copied().unwrap_or(line.len());
+        line.get(start.min(line.len())..end.min(line.len()))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+
+    lines
+        // The listing ends with a blank line and "N timers listed."
+        .take_while(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let unit = column(line, 4);
+            if unit.is_empty() {
+                return None;
+            }
+            let activates = column(line, 5);
+
+            Some(ScheduledTaskRuntime {
+                name: unit.clone(),
+                path: format!("systemd/{}", unit),
+                source: "systemd_timer".to_string(),
+                user: None,
+                command: (!activates.is_empty()).then_some(activates),
+                schedule: None,
+                state: None,
+                enabled: true,
+                last_run_time: systemd_timestamp_to_rfc3339(&column(line, 2)),
+                next_run_time: systemd_timestamp_to_rfc3339(&column(line, 0)),
+                last_result_code: None,
+                is_currently_running: false,
+            })
+        })
+        .collect()
+}
+
+/// Convert a systemd timestamp such as `Thu 2024-01-11 10:00:00 UTC` to
+/// RFC3339. Zones other than UTC are taken to be the local zone, which is
+/// what systemctl prints by default.
+fn systemd_timestamp_to_rfc3339(value: &str) -> Option<String> {
+    let mut fields: Vec<&str> = value.split_whitespace().collect();
+    if fields.len() < 3 {
+        return None;
+    }
+    let utc = fields.len() > 3 && fields.pop() == Some("UTC");
+    let naive =
+        chrono::NaiveDateTime::parse_from_str(&fields[1..3].join(" "), "%Y-%m-%d %H:%M:%S").ok()?;
+    if utc {
+        Some(chrono::Utc.from_utc_datetime(&naive).to_rfc3339())
+    } else {
+        local_to_rfc3339(&naive)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux_impl {
+    use std::fs;
+    use std::process::Command;
+
+    use anyhow::Result;
+    use log::debug;
+
+    use super::{
+        parse_atq_output, parse_crontab_output, parse_systemd_timers, ScheduledTaskRuntime,
+    };
+    use crate::collectors::footprint::run_command;
+
+    pub fn enumerate() -> Result<Vec<ScheduledTaskRuntime>> {
+        let mut tasks = Vec::new();
+
+        match run_command(&mut Command::new("atq")) {
+            Ok(output) if output.status.success() => {
+                tasks.extend(parse_atq_output(&String::from_utf8_lossy(&output.stdout)));
+            }
+            Ok(output) => debug!(
+                "atq exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => debug!("atq not available: {}", e),
+        }
+
+        let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+        for user in passwd
+            .lines()
+            .filter_map(|line| line.split(':').next())
+            .filter(|user| !user.is_empty() && !user.starts_with('#'))
+        {
+            match run_command(Command::new("crontab").args(["-l", "-u", user])) {
+                Ok(output) if output.status.success() => {
+                    tasks.extend(parse_crontab_output(
+                        user,
+                        &String::from_utf8_lossy(&output.stdout),
+                    ));
+                }
+                // "no crontab for <user>" is the common case
+                Ok(_) => {}
+                Err(e) => {
+                    debug!("crontab not available: {}", e);
+                    break;
+                }
+            }
+        }
+
+        match run_command(Command::new("systemctl").args(["list-timers", "--all", "--no-pager"])) {
+            Ok(output) if output.status.success() => {
+                tasks.extend(parse_systemd_timers(&String::from_utf8_lossy(
+                    &output.stdout,
+                )));
+            }
+            Ok(output) => debug!(
+                "systemctl list-timers exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => debug!("systemctl not available: {}", e),
+        }
+
+        Ok(tasks)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use log::warn;
+    use widestring::{U16CStr, U16CString};
+    use winapi::shared::guiddef::GUID;
+    use winapi::shared::winerror::{FAILED, RPC_E_CHANGED_MODE};
+    use winapi::shared::wtypes::{BSTR, DATE, VARIANT_BOOL, VT_I4};
+    use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+    use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize};
+    use winapi::um::oaidl::VARIANT;
+    use winapi::um::objbase::COINIT_MULTITHREADED;
+    use winapi::um::oleauto::{SysAllocString, SysFreeString};
+    use winapi::um::taskschd::{
+        IRegisteredTask, IRegisteredTaskCollection, ITaskFolder, ITaskFolderCollection,
+        ITaskService, TASK_ENUM_HIDDEN, TASK_STATE,
+    };
+    use winapi::Interface;
+
+    use super::{ole_date_to_rfc3339, ScheduledTaskRuntime};
+
+    /// CLSID_TaskScheduler {0F87369F-A4E5-4CFC-BD3E-73E6154572DD}
+    const CLSID_TASK_SCHEDULER: GUID = GUID {
+        Data1: 0x0f87369f,
+        Data2: 0xa4e5,
+        Data3: 0x4cfc,
+        Data4: [0xbd, 0x3e, 0x73, 0xe6, 0x15, 0x45, 0x72, 0xdd],
+    };
+
+    /// Releases a COM interface pointer when dropped
+    struct ComPtr<T: Interface>(*mut T);
+
+    impl<T: Interface> Drop for ComPtr<T> {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                // SAFETY: the pointer was returned by a COM method with an owned reference
+                unsafe { (*(self.0 as *mut winapi::um::unknwnbase::IUnknown)).Release() };
+            }
+        }
+    }
+
+    fn bstr_to_string(bstr: BSTR) -> String {
+        if bstr.is_null() {
+            return String::new();
+        }
+        // SAFETY: BSTR is a valid NUL-terminated UTF-16 string owned by us
+        let value = unsafe { U16CStr::from_ptr_str(bstr).to_string_lossy() };
+        unsafe { SysFreeString(bstr) };
+        value
+    }
+
+    fn index_variant(index: i32) -> VARIANT {
+        // SAFETY: a zeroed VARIANT is VT_EMPTY; we then set it to VT_I4
+        unsafe {
+            let mut variant: VARIANT = std::mem::zeroed();
+            let inner = variant.n1.n2_mut();
+            inner.vt = VT_I4 as u16;
+            *inner.n3.lVal_mut() = index;
+            variant
+        }
+    }
+
+    fn state_name(state: TASK_STATE) -> &'static str {
+        match state {
+            1 => "Disabled",
+            2 => "Queued",
+            3 => "Ready",
+            4 => "Running",
+            _ => "Unknown",
+        }
+    }
+
+    pub fn enumerate() -> Result<Vec<ScheduledTaskRuntime>> {
+        // SAFETY: COM initialisation for this thread; balanced by CoUninitialize below
+        let hr = unsafe { CoInitializeEx(ptr::null_mut(), COINIT_MULTITHREADED) };
+        let should_uninit = !FAILED(hr);
+        if FAILED(hr) && hr != RPC_E_CHANGED_MODE {
+            return Err(anyhow!("CoInitializeEx failed: 0x{:08x}", hr));
+        }
+
+        let result = enumerate_with_com();
+
+        if should_uninit {
+            // SAFETY: matches the successful CoInitializeEx above
+            unsafe { CoUninitialize() };
+        }
+        result
+    }
+
+    fn enumerate_with_com() -> Result<Vec<ScheduledTaskRuntime>> {
+        let mut service: *mut ITaskService = ptr::null_mut();
+        // SAFETY: standard CoCreateInstance call with valid CLSID/IID and out pointer
+        let hr = unsafe {
+            CoCreateInstance(
+                &CLSID_TASK_SCHEDULER,
+                ptr::null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &ITaskService::uuidof(),
+                &mut service as *mut *mut ITaskService as *mut _,
+            )
+        };
+        if FAILED(hr) {
+            return Err(anyhow!("Failed to create ITaskService: 0x{:08x}", hr));
+        }
+        let service = ComPtr(service);
+
+        // SAFETY: empty VARIANTs connect to the local machine as the current user
+        let hr = unsafe {
+            (*service.0).Connect(
+                std::mem::zeroed(),
+                std::mem::zeroed(),
+                std::mem::zeroed(),
+                std::mem::zeroed(),
+            )
+        };
+        if FAILED(hr) {
+            return Err(anyhow!("ITaskService::Connect failed: 0x{:08x}", hr));
+        }
+
+        let root_path = U16CString::from_str("\\").map_err(|e| anyhow!("{}", e))?;
+        let mut root: *mut ITaskFolder = ptr::null_mut();
+        // SAFETY: SysAllocString copies the path; the BSTR is freed after the call
+        let hr = unsafe {
+            let bstr = SysAllocString(root_path.as_ptr());
+            let hr = (*service.0).GetFolder(bstr, &mut root);
+            SysFreeString(bstr);
+            hr
+        };
+        if FAILED(hr) {
+            return Err(anyhow!("ITaskService::GetFolder failed: 0x{:08x}", hr));
+        }
+
+        let mut tasks = Vec::new();
+        walk_folder(ComPtr(root), &mut tasks);
+        Ok(tasks)
+    }
+
+    fn walk_folder(folder: ComPtr<ITaskFolder>, tasks: &mut Vec<ScheduledTaskRuntime>) {
+        // SAFETY: all calls below operate on live COM pointers held by ComPtr guards
+        unsafe {
+            let mut collection: *mut IRegisteredTaskCollection = ptr::null_mut();
+            if !FAILED((*folder.0).GetTasks(TASK_ENUM_HIDDEN as i32, &mut collection)) {
+                let collection = ComPtr(collection);
+                let mut count = 0;
+                (*collection.0).get_Count(&mut count);
+                // Collections are 1-based
+                for i in 1..=count {
+                    let mut task: *mut IRegisteredTask = ptr::null_mut();
+                    if FAILED((*collection.0).get_Item(index_variant(i), &mut task)) {
+                        continue;
+                    }
+                    tasks.push(read_task(&ComPtr(task)));
+                }
+            }
+
+            let mut subfolders: *mut ITaskFolderCollection = ptr::null_mut();
+            if FAILED((*folder.0).GetFolders(0, &mut subfolders)) {
+                return;
+            }
+            let subfolders = ComPtr(subfolders);
+            let mut count = 0;
+            (*subfolders.0).get_Count(&mut count);
+            for i in 1..=count {
+                let mut child: *mut ITaskFolder = ptr::null_mut();
+                if FAILED((*subfolders.0).get_Item(index_variant(i), &mut child)) {
+                    warn!("Failed to open task folder at index {}", i);
+                    continue;
+                }
+                walk_folder(ComPtr(child), tasks);
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `task` must hold a valid `IRegisteredTask` pointer.
+    unsafe fn read_task(task: &ComPtr<IRegisteredTask>) -> ScheduledTaskRuntime {
+        let mut name: BSTR = ptr::null_mut();
+        let mut path: BSTR = ptr::null_mut();
+        let mut state: TASK_STATE = 0;
+        let mut enabled: VARIANT_BOOL = 0;
+        let mut last_run: DATE = 0.0;
+        let mut next_run: DATE = 0.0;
+        let mut last_result: i32 = 0;
+
+        (*task.0).get_Name(&mut name);
+        (*task.0).get_Path(&mut path);
+        (*task.0).get_State(&mut state);
+        (*task.0).get_Enabled(&mut enabled);
+        (*task.0).get_LastRunTime(&mut last_run);
+        (*task.0).get_NextRunTime(&mut next_run);
+        let has_result = !FAILED((*task.0).get_LastTaskResult(&mut last_result));
+
+        let last_run_time = ole_date_to_rfc3339(last_run);
+        ScheduledTaskRuntime {
+            name: bstr_to_string(name),
+            path: bstr_to_string(path),
+            source: "task_scheduler".to_string(),
+            user: None,
+            command: None,
+            schedule: None,
+            state: Some(state_name(state).to_string()),
+            enabled: enabled != 0,
+            last_result_code: if has_result && last_run_time.is_some() {
+                Some(last_result)
+            } else {
+                None
+            },
+            last_run_time,
+            next_run_time: ole_date_to_rfc3339(next_run),
+            is_currently_running: state == 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_atq_output() {
+        let output = "12\tThu Jan 11 10:00:00 2024 a root\n13\tFri Jan 12 09:30:00 2024 = alice\n";
+        let jobs = parse_atq_output(output);
+
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "12");
+        assert_eq!(jobs[0].user.as_deref(), Some("root"));
+        assert_eq!(
+            jobs[0].schedule.as_deref(),
+            Some("Thu Jan 11 10:00:00 2024")
+        );
+        assert!(jobs[0].next_run_time.is_some());
+        assert!(!jobs[0].is_currently_running);
+        assert!(jobs[1].is_currently_running);
+    }
+
+    #[test]
+    fn test_parse_crontab_output() {
+        let output = "# m h dom mon dow command\nSHELL=/bin/bash\n\n*/5 * * * * /usr/bin/backup.sh --full\n@reboot /opt/agent/start\n";
+        let jobs = parse_crontab_output("alice", output);
+
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].schedule.as_deref(), Some("*/5 * * * *"));
+        assert_eq!(
+            jobs[0].command.as_deref(),
+            Some("/usr/bin/backup.sh --full")
+        );
+        assert_eq!(jobs[0].user.as_deref(), Some("alice"));
+        assert_eq!(jobs[1].schedule.as_deref(), Some("@reboot"));
+        assert_eq!(jobs[1].command.as_deref(), Some("/opt/agent/start"));
+    }
+
+    #[test]
+    fn test_parse_systemd_timers() {
+        let output = "\
+NEXT                        LEFT       LAST                        PASSED      UNIT                  ACTIVATES
+Thu 2024-01-11 10:00:00 UTC 50min left Thu 2024-01-11 09:00:00 UTC 10min ago   logrotate.timer       logrotate.service
+n/a                         n/a        n/a                         n/a         backdoor.timer        backdoor.service
+
+2 timers listed.
+Pass --all to see loaded but inactive timers, too.
+";
+        let tasks = parse_systemd_timers(output);
+        assert_eq!(tasks.len(), 2);
+
+        assert_eq!(tasks[0].name, "logrotate.timer");
+        assert_eq!(tasks[0].source, "systemd_timer");
+        assert_eq!(tasks[0].command.as_deref(), Some("logrotate.service"));
+        assert_eq!(
+            tasks[0].next_run_time.as_deref(),
+            Some("2024-01-11T10:00:00+00:00")
+        );
+        assert_eq!(
+            tasks[0].last_run_time.as_deref(),
+            Some("2024-01-11T09:00:00+00:00")
+        );
+
+        assert_eq!(tasks[1].name, "backdoor.timer");
+        assert!(tasks[1].next_run_time.is_none());
+        assert!(tasks[1].last_run_time.is_none());
+
+        assert!(parse_systemd_timers("0 timers listed.\n").is_empty());
+    }
+
+    #[test]
+    fn test_ole_date_never_run() {
+        assert_eq!(ole_date_to_rfc3339(0.0), None);
+        assert_eq!(ole_date_to_rfc3339(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_ole_date_conversion() {
+        // 45292.5 is 2024-01-01 12:00 local time
+        let converted = ole_date_to_rfc3339(45292.5).unwrap();
+        let parsed = chrono::DateTime::parse_from_rfc3339(&converted).unwrap();
+        let local = parsed.with_timezone(&chrono::Local);
+        assert_eq!(
+            local.naive_local(),
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_enumerate_does_not_fail_without_tooling() {
+        assert!(enumerate_tasks_live().is_ok());
+    }
+}
@@ -0,0 +1,585 @@
+//! Installed Windows services and their drift from a default installation.
+//!
+//! Services are enumerated from the Service Control Manager
+//! (`EnumServicesStatusEx` and `QueryServiceConfig`) and written to
+//! `volatile/services.json`. They are then compared with the services of a
+//! default Windows installation, embedded at compile time from
+//! `default_services_baseline.json` (see
+//! [`crate::constants::DEFAULT_SERVICES_BASELINE`]), and the difference is
+//! written to `volatile/services_diff.json`:
+//!
+//! - `new_services`: services the baseline does not list
+//! - `missing_services`: baseline services that are not installed, which is
+//!   normal for features and editions that leave them out
+//! - `modified_services`: baseline services whose binary path, start type or
+//!   account differ
+//! - `anomalies`: new services whose binary lies outside the directories
+//!   Windows installs its own services to. Services installed by malware
+//!   and remote administration tools are usually of this kind, so these are
+//!   reported with high confidence; a new service running a binary from
+//!   `System32` is listed in `new_services` only.
+//!
+//! Per-user service instances (`CDPUserSvc_3a4f1`) match their template in
+//! the baseline.
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether services are enumerated on this platform
+pub const SUPPORTED: bool = cfg!(target_os = "windows");
+
+/// File name used for the service listing
+pub const SERVICES_FILE: &str = "services.json";
+
+/// File name used for the comparison with the baseline
+pub const SERVICES_DIFF_FILE: &str = "services_diff.json";
+
+/// Directories, relative to the system drive, holding the binaries of
+/// services Windows ships
+const MICROSOFT_SERVICE_DIRS: &[&str] = &[
+    r"\windows\system32\",
+    r"\windows\syswow64\",
+    r"\windows\servicing\",
+    r"\windows\winsxs\",
+    r"\windows\microsoft.net\",
+    r"\program files\windows defender\",
+    r"\programdata\microsoft\windows defender\",
+];
+
+/// An installed service
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ServiceInfo {
+    /// Service (key) name
+    pub name: String,
+    pub display_name: String,
+    /// `running`, `stopped`, `start_pending`, ...
+    pub state: String,
+    /// `boot`, `system`, `auto`, `demand` or `disabled`
+    pub start_type: String,
+    /// Command line of the service binary (`ImagePath`)
+    pub binary_path: Option<String>,
+    /// Account the service runs as
+    pub account: Option<String>,
+    /// Process ID, while running
+    pub pid: Option<u32>,
+}
+
+/// A service of the default installation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BaselineService {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub start_type: String,
+    pub binary_path: String,
+    pub account: Option<String>,
+}
+
+/// A baseline service configured differently on the host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModifiedService {
+    pub name: String,
+    pub changes: Vec<ServiceChange>,
+}
+
+/// A setting that differs from the baseline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceChange {
+    /// `binary_path`, `start_type` or `account`
+    pub field: String,
+    pub baseline: String,
+    pub current: String,
+}
+
+/// A new service running a binary from outside the Windows directories
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceAnomaly {
+    pub name: String,
+    pub binary_path: String,
+    /// Always `high`
+    pub confidence: String,
+}
+
+/// Differences between the installed services and the baseline
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ServiceDiff {
+    pub new_services: Vec<String>,
+    pub missing_services: Vec<String>,
+    pub modified_services: Vec<ModifiedService>,
+    pub anomalies: Vec<ServiceAnomaly>,
+}
+
+/// Compare `services` with `baseline`. Names are case-insensitive, as the
+/// Service Control Manager treats them.
+pub fn diff_against_baseline(
+    services: &[ServiceInfo],
+    baseline: &[BaselineService],
+) -> ServiceDiff {
+    let expected: HashMap<String, &BaselineService> = baseline
+        .iter()
+        .map(|service| (service.name.to_lowercase(), service))
+        .collect();
+    let mut diff = ServiceDiff::default();
+    let mut seen = Vec::new();
+
+    for service in services {
+        let Some(base) = baseline_entry(&expected, &service.name) else {
+            diff.new_services.push(service.name.clone());
+            if let Some(path) = service
+                .binary_path
+                .as_deref()
+                .filter(|path| !is_microsoft_binary(path))
+            {
+                diff.anomalies.push(ServiceAnomaly {
+                    name: service.name.clone(),
+                    binary_path: path.to_string(),
+                    confidence: "high".to_string(),
+                });
+            }
+            continue;
+        };
+        seen.push(base.name.to_lowercase());
+
+        let mut changes = Vec::new();
+        let current_path = service.binary_path.as_deref().unwrap_or("");
+        if normalize_command_line(current_path) != normalize_command_line(&base.binary_path) {
+            changes.push(ServiceChange {
+                field: "binary_path".to_string(),
+                baseline: base.binary_path.clone(),
+                current: current_path.to_string(),
+            });
+        }
+        if !service.start_type.eq_ignore_ascii_case(&base.start_type) {
+            changes.push(ServiceChange {
+                field: "start_type".to_string(),
+                baseline: base.start_type.clone(),
+                current: service.start_type.clone(),
+            });
+        }
+        if let (Some(expected), Some(current)) = (&base.account, &service.account) {
+            if !expected.eq_ignore_ascii_case(current) {
+                changes.push(ServiceChange {
+                    field: "account".to_string(),
+                    baseline: expected.clone(),
+                    current: current.clone(),
+                });
+            }
+        }
+        if !changes.is_empty() {
+            diff.modified_services.push(ModifiedService {
+                name: service.name.clone(),
+                changes,
+            });
+        }
+    }
+
+    diff.missing_services = baseline
+        .iter()
+        .filter(|service| !seen.contains(&service.name.to_lowercase()))
+        .map(|service| service.name.clone())
+        .collect();
+    diff
+}
+
+/// The baseline entry for `name`, or for the template of a per-user service
+/// instance (`<template>_<hex LUID>`)
+fn baseline_entry<'a>(
+    expected: &HashMap<String, &'a BaselineService>,
+    name: &str,
+) -> Option<&'a BaselineService> {
+    let name = name.to_lowercase();
+    if let Some(service) = expected.get(&name) {
+        return Some(service);
+    }
+    let (template, suffix) = name.rsplit_once('_')?;
+    if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    expected.get(template).copied()
+}
+
+/// Whether the executable of service command line `binary_path` is in a
+/// directory Windows installs its own services to
+pub fn is_microsoft_binary(binary_path: &str) -> bool {
+    let path = normalize_command_line(executable(binary_path));
+    MICROSOFT_SERVICE_DIRS
+        .iter()
+        .any(|dir| path.starts_with(dir))
+}
+
+/// The executable of a service command line: the quoted part, or the text
+/// up to the first `.exe`
+fn executable(command_line: &str) -> &str {
+    let command_line = command_line.trim();
+    if let Some(quoted) = command_line.strip_prefix('"') {
+        return quoted.split('"').next().unwrap_or(quoted);
+    }
+    match command_line.to_ascii_lowercase().find(".exe") {
+        Some(end) => &command_line[..end + 4],
+        None => command_line
+            .split_whitespace()
+            .next()
+            .unwrap_or(command_line),
+    }
+}
+
+/// Lower-case `command_line` with quotes removed, runs of spaces collapsed,
+/// and the Windows directory written as `\windows` whichever of
+/// `%SystemRoot%`, `\SystemRoot`, `C:\Windows` or a path relative to it
+/// (`system32\drivers\...`) names it
+fn normalize_command_line(command_line: &str) -> String {
+    let lower = command_line
+        .replace('"', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let lower = lower.strip_prefix(r"\??\").unwrap_or(&lower);
+    for root in ["%systemroot%", "%windir%", r"\systemroot"] {
+        if let Some(rest) = lower.strip_prefix(root) {
+            return format!(r"\windows{}", rest);
+        }
+    }
+    if lower.starts_with(r"system32\") || lower.starts_with(r"syswow64\") {
+        return format!(r"\windows\{}", lower);
+    }
+    // Drop the drive letter
+    let bytes = lower.as_bytes();
+    if bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return lower[2..].to_string();
+    }
+    lower.to_string()
+}
+
+/// The embedded baseline of default services
+#[cfg(any(target_os = "windows", test))]
+pub fn default_baseline() -> Vec<BaselineService> {
+    serde_json::from_str(crate::constants::DEFAULT_SERVICES_BASELINE)
+        .expect("embedded service baseline is valid JSON")
+}
+
+/// Enumerate installed Win32 services (drivers are left out)
+pub fn enumerate_services() -> anyhow::Result<Vec<ServiceInfo>> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::enumerate()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        log::debug!("Service enumeration is only supported on Windows");
+        Ok(Vec::new())
+    }
+}
+
+/// Enumerate services and compare them with the embedded baseline
+#[cfg(target_os = "windows")]
+pub fn collect_services() -> anyhow::Result<(Vec<ServiceInfo>, ServiceDiff)> {
+    let services = enumerate_services()?;
+    let diff = diff_against_baseline(&services, &default_baseline());
+    Ok((services, diff))
+}
+
+/// Services are only enumerated on Windows
+#[cfg(not(target_os = "windows"))]
+pub fn collect_services() -> anyhow::Result<(Vec<ServiceInfo>, ServiceDiff)> {
+    Ok((Vec::new(), ServiceDiff::default()))
+}
+
+/// Compare the installed services with the embedded baseline; the diff is
+/// empty on other platforms
+pub fn running_services_diff() -> anyhow::Result<ServiceDiff> {
+    collect_services().map(|(_, diff)| diff)
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use log::debug;
+    use widestring::U16CStr;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_MORE_DATA};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::winnt::SERVICE_WIN32;
+    use winapi::um::winsvc::{
+        CloseServiceHandle, EnumServicesStatusExW, OpenSCManagerW, OpenServiceW,
+        QueryServiceConfigW, ENUM_SERVICE_STATUS_PROCESSW, QUERY_SERVICE_CONFIGW,
+        SC_ENUM_PROCESS_INFO, SC_HANDLE, SC_MANAGER_CONNECT, SC_MANAGER_ENUMERATE_SERVICE,
+        SERVICE_QUERY_CONFIG, SERVICE_STATE_ALL,
+    };
+
+    use super::ServiceInfo;
+
+    /// Closes a service or Service Control Manager handle on drop
+    struct Handle(SC_HANDLE);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            // SAFETY: the handle was opened by OpenSCManagerW/OpenServiceW
+            unsafe { CloseServiceHandle(self.0) };
+        }
+    }
+
+    /// Read a NUL-terminated wide string, `None` for a null pointer
+    unsafe fn wide(ptr: *const u16) -> Option<String> {
+        (!ptr.is_null()).then(|| U16CStr::from_ptr_str(ptr).to_string_lossy())
+    }
+
+    fn state_name(state: DWORD) -> &'static str {
+        match state {
+            1 => "stopped",
+            2 => "start_pending",
+            3 => "stop_pending",
+            4 => "running",
+            5 => "continue_pending",
+            6 => "pause_pending",
+            7 => "paused",
+            _ => "unknown",
+        }
+    }
+
+    fn start_type_name(start_type: DWORD) -> &'static str {
+        match start_type {
+            0 => "boot",
+            1 => "system",
+            2 => "auto",
+            3 => "demand",
+            4 => "disabled",
+            _ => "unknown",
+        }
+    }
+
+    pub fn enumerate() -> Result<Vec<ServiceInfo>> {
+        // SAFETY: null machine and database names open the local active database
+        let scm = unsafe {
+            OpenSCManagerW(
+                ptr::null(),
+                ptr::null(),
+                SC_MANAGER_CONNECT | SC_MANAGER_ENUMERATE_SERVICE,
+            )
+        };
+        if scm.is_null() {
+            return Err(anyhow!(
+                "OpenSCManager failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let scm = Handle(scm);
+
+        let mut services = Vec::new();
+        // u64 keeps the buffer aligned for ENUM_SERVICE_STATUS_PROCESSW
+        let mut buffer: Vec<u64> = vec![0; 8 * 1024];
+        let mut resume: DWORD = 0;
+        loop {
+            let mut needed: DWORD = 0;
+            let mut returned: DWORD = 0;
+            // SAFETY: the buffer and counters are valid for the call
+            let ok = unsafe {
+                EnumServicesStatusExW(
+                    scm.0,
+                    SC_ENUM_PROCESS_INFO,
+                    SERVICE_WIN32,
+                    SERVICE_STATE_ALL,
+                    buffer.as_mut_ptr() as *mut u8,
+                    (buffer.len() * 8) as DWORD,
+                    &mut needed,
+                    &mut returned,
+                    &mut resume,
+                    ptr::null(),
+                )
+            };
+            let more = ok == 0 && unsafe { GetLastError() } == ERROR_MORE_DATA;
+            if ok == 0 && !more {
+                return Err(anyhow!(
+                    "EnumServicesStatusEx failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            // SAFETY: the call wrote `returned` entries at the start of the
+            // buffer, their strings point into the buffer
+            let entries = unsafe {
+                std::slice::from_raw_parts(
+                    buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
+                    returned as usize,
+                )
+            };
+            for entry in entries {
+                let Some(name) = (unsafe { wide(entry.lpServiceName) }) else {
+                    continue;
+                };
+                let status = &entry.ServiceStatusProcess;
+                let mut service = ServiceInfo {
+                    display_name: unsafe { wide(entry.lpDisplayName) }.unwrap_or_default(),
+                    state: state_name(status.dwCurrentState).to_string(),
+                    pid: (status.dwProcessId != 0).then_some(status.dwProcessId),
+                    ..Default::default()
+                };
+                // SAFETY: the name is NUL-terminated and the SCM handle open
+                match unsafe { query_config(&scm, entry.lpServiceName) } {
+                    Ok((start_type, binary_path, account)) => {
+                        service.start_type = start_type_name(start_type).to_string();
+                        service.binary_path = binary_path;
+                        service.account = account;
+                    }
+                    Err(e) => {
+                        debug!("Failed to query configuration of service {}: {}", name, e);
+                        service.start_type = "unknown".to_string();
+                    }
+                }
+                service.name = name;
+                services.push(service);
+            }
+
+            if !more {
+                break;
+            }
+            if needed as usize > buffer.len() * 8 {
+                buffer.resize(needed as usize / 8 + 1, 0);
+            }
+        }
+        Ok(services)
+    }
+
+    /// Start type, binary path and account of the service `name`
+    unsafe fn query_config(
+        scm: &Handle,
+        name: *const u16,
+    ) -> Result<(DWORD, Option<String>, Option<String>)> {
+        let service = OpenServiceW(scm.0, name, SERVICE_QUERY_CONFIG);
+        if service.is_null() {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let service = Handle(service);
+
+        let mut needed: DWORD = 0;
+        if QueryServiceConfigW(service.0, ptr::null_mut(), 0, &mut needed) == 0
+            && GetLastError() != ERROR_INSUFFICIENT_BUFFER
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut buffer: Vec<u64> = vec![0; needed as usize / 8 + 1];
+        if QueryServiceConfigW(
+            service.0,
+            buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW,
+            (buffer.len() * 8) as DWORD,
+            &mut needed,
+        ) == 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let config = &*(buffer.as_ptr() as *const QUERY_SERVICE_CONFIGW);
+        Ok((
+            config.dwStartType,
+            wide(config.lpBinaryPathName).filter(|path| !path.is_empty()),
+            wide(config.lpServiceStartName).filter(|account| !account.is_empty()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, start_type: &str, binary_path: &str) -> ServiceInfo {
+        ServiceInfo {
+            name: name.to_string(),
+            display_name: name.to_string(),
+            state: "running".to_string(),
+            start_type: start_type.to_string(),
+            binary_path: Some(binary_path.to_string()),
+            account: None,
+            pid: Some(1234),
+        }
+    }
+
+    #[test]
+    fn test_running_services_diff() {
+        let diff = crate::collectors::volatile::running_services_diff().unwrap();
+        if !SUPPORTED {
+            assert_eq!(diff, ServiceDiff::default());
+        }
+    }
+
+    #[test]
+    fn test_diff_against_baseline() {
+        let baseline = default_baseline();
+        assert!(baseline.iter().any(|s| s.name == "Schedule"));
+
+        let services = vec![
+            // Same command line, written with C:\Windows
+            service(
+                "Schedule",
+                "auto",
+                r"C:\WINDOWS\system32\svchost.exe -k netsvcs -p",
+            ),
+            // Hijacked to another binary
+            service("Spooler", "auto", r"C:\Users\Public\spool.exe"),
+            service(
+                "RemoteRegistry",
+                "auto",
+                r"%SystemRoot%\system32\svchost.exe -k localService -p",
+            ),
+            // A per-user instance of a template
+            service(
+                "CDPSvc_3a4f1",
+                "auto",
+                r"%SystemRoot%\system32\svchost.exe -k LocalService -p",
+            ),
+            service("PSEXESVC", "demand", r"%SystemRoot%\PSEXESVC.exe"),
+            service(
+                "updater",
+                "auto",
+                r#""C:\ProgramData\svc\updater.exe" -run"#,
+            ),
+            service(
+                "MicrosoftEdgeHelper",
+                "demand",
+                r"C:\Windows\System32\edgehelper.exe",
+            ),
+        ];
+        let diff = diff_against_baseline(&services, &baseline);
+
+        assert_eq!(
+            diff.new_services,
+            vec!["PSEXESVC", "updater", "MicrosoftEdgeHelper"]
+        );
+        let anomalies: Vec<&str> = diff.anomalies.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(anomalies, vec!["PSEXESVC", "updater"]);
+        assert!(diff.anomalies.iter().all(|a| a.confidence == "high"));
+
+        assert_eq!(diff.modified_services.len(), 2);
+        assert_eq!(diff.modified_services[0].name, "Spooler");
+        assert_eq!(diff.modified_services[0].changes[0].field, "binary_path");
+        let registry = &diff.modified_services[1];
+        assert_eq!(registry.name, "RemoteRegistry");
+        assert_eq!(registry.changes.len(), 1);
+        assert_eq!(registry.changes[0].field, "start_type");
+        assert_eq!(registry.changes[0].baseline, "disabled");
+
+        assert!(!diff.missing_services.contains(&"Schedule".to_string()));
+        assert!(!diff.missing_services.contains(&"CDPSvc".to_string()));
+        assert!(diff.missing_services.contains(&"Winmgmt".to_string()));
+    }
+
+    #[test]
+    fn test_is_microsoft_binary() {
+        assert!(is_microsoft_binary(
+            r"%SystemRoot%\System32\svchost.exe -k netsvcs"
+        ));
+        assert!(is_microsoft_binary(
+            r"\SystemRoot\System32\drivers\tcpip.sys"
+        ));
+        assert!(is_microsoft_binary(r"system32\DRIVERS\disk.sys"));
+        assert!(is_microsoft_binary(
+            r#""D:\ProgramData\Microsoft\Windows Defender\platform\4.18.2301.6-0\MsMpEng.exe""#
+        ));
+        assert!(!is_microsoft_binary(r"C:\Windows\Temp\svchost.exe"));
+        assert!(!is_microsoft_binary(
+            r"C:\Program Files\Vendor\agent.exe --service"
+        ));
+    }
+}
@@ -0,0 +1,878 @@
+//! System state diff across a collection run (`--state-diff`).
+//!
+//! A lightweight snapshot is taken before collection starts and again once it
+//! finishes: running processes, TCP listeners, logged-in user sessions and the
+//! number of entries in a few directories that attackers and installers
+//! commonly write to. Both snapshots are taken with the volatile collector;
+//! the second pass skips disk and memory information to stay cheap.
+//!
+//! The differences are written to `state_diff.json`. Activity during
+//! collection shows the collector's own impact and can reveal anti-forensics
+//! (a process killed, a listener closed, a session started), so every change
+//! that can be traced back to the collector — its own process, its children,
+//! commands recorded in the [footprint](crate::collectors::footprint), or paths
+//! it created — is flagged with `caused_by_collector` for filtering.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::footprint::{self, CollectorFootprint};
+use crate::collectors::volatile::models::{
+    MemoryInfo, NetworkConnection, ProcessInfo, VolatileData,
+};
+use crate::collectors::volatile::tcp_sockets;
+use crate::collectors::volatile::VolatileDataCollector;
+
+/// Name of the diff report written into the output directory
+pub const STATE_DIFF_FILE: &str = "state_diff.json";
+
+/// How far a process start may be from a recorded spawn to be matched to it
+const SPAWN_WINDOW_SECS: i64 = 2;
+
+/// A logged-in user session
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct UserSession {
+    pub user: String,
+    /// Terminal or window station (`pts/0`, `console`, `RDP-Tcp#3`)
+    pub terminal: Option<String>,
+    /// Remote host the session was opened from
+    pub host: Option<String>,
+    /// Login time, where the platform records it
+    pub login_time: Option<String>,
+}
+
+/// System state at one point of the run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub taken_at: String,
+    /// Volatile data; TCP listeners are in `network.connections`
+    pub data: VolatileData,
+    pub sessions: Vec<UserSession>,
+    /// Number of entries in each watched directory
+    pub directory_counts: BTreeMap<String, usize>,
+}
+
+/// A process that appeared or disappeared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessChange {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<String>,
+    pub cmd: Vec<String>,
+    pub parent_pid: Option<u32>,
+    pub start_time: u64,
+    pub caused_by_collector: bool,
+}
+
+/// A TCP listener that was opened or closed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListenerChange {
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub process_id: Option<u32>,
+    pub process_name: Option<String>,
+    pub caused_by_collector: bool,
+}
+
+/// A watched directory whose entry count changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryChange {
+    pub path: String,
+    /// Entry count before collection, if the directory existed
+    pub before: Option<usize>,
+    /// Entry count after collection, if the directory still exists
+    pub after: Option<usize>,
+    /// The collector created files or directories inside it
+    pub caused_by_collector: bool,
+}
+
+/// Differences between the start and end snapshots
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub started: String,
+    pub finished: String,
+    /// PID of the collector, for filtering by hand
+    pub collector_pid: u32,
+    pub processes_started: Vec<ProcessChange>,
+    pub processes_exited: Vec<ProcessChange>,
+    pub listeners_opened: Vec<ListenerChange>,
+    pub listeners_closed: Vec<ListenerChange>,
+    pub sessions_started: Vec<UserSession>,
+    pub sessions_ended: Vec<UserSession>,
+    pub directory_changes: Vec<DirectoryChange>,
+}
+
+impl StateDiff {
+    /// Number of changes not attributed to the collector
+    pub fn external_change_count(&self) -> usize {
+        let processes = self
+            .processes_started
+            .iter()
+            .chain(&self.processes_exited)
+            .filter(|p| !p.caused_by_collector)
+            .count();
+        let listeners = self
+            .listeners_opened
+            .iter()
+            .chain(&self.listeners_closed)
+            .filter(|l| !l.caused_by_collector)
+            .count();
+        let directories = self
+            .directory_changes
+            .iter()
+            .filter(|d| !d.caused_by_collector)
+            .count();
+        processes
+            + listeners
+            + directories
+            + self.sessions_started.len()
+            + self.sessions_ended.len()
+    }
+}
+
+/// Holds the start snapshot until collection finishes
+pub struct StateDiffTracker {
+    collector: VolatileDataCollector,
+    before: StateSnapshot,
+}
+
+impl StateDiffTracker {
+    /// Take the start snapshot
+    pub fn start() -> Result<Self> {
+        info!("Taking start-of-collection state snapshot");
+        let mut collector = VolatileDataCollector::new();
+        let before = take_snapshot(&mut collector, true)?;
+        Ok(Self { collector, before })
+    }
+
+    /// Take the end snapshot and write [`STATE_DIFF_FILE`] to `output_dir`
+    pub fn finish(mut self, output_dir: &Path) -> Result<PathBuf> {
+        info!("Taking end-of-collection state snapshot");
+        self.collector.refresh_processes();
+        let after = take_snapshot(&mut self.collector, false)?;
+
+        let diff = diff_snapshots(
+            &self.before,
+            &after,
+            std::process::id(),
+            &footprint::snapshot(),
+        );
+        info!(
+            "State diff: {} processes started, {} exited, {} listeners opened, {} changes not caused by the collector",
+            diff.processes_started.len(),
+            diff.processes_exited.len(),
+            diff.listeners_opened.len(),
+            diff.external_change_count()
+        );
+
+        let path = output_dir.join(STATE_DIFF_FILE);
+        let json = serde_json::to_string_pretty(&diff).context("Failed to serialize state diff")?;
+        fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+        footprint::record_created_path(&path);
+        Ok(path)
+    }
+}
+
+/// Take a state snapshot with `collector`.
+///
+/// A full snapshot includes memory and disk information; the cheap pass
+/// leaves them empty since they are not diffed.
+pub fn take_snapshot(collector: &mut VolatileDataCollector, full: bool) -> Result<StateSnapshot> {
+    let taken_at = chrono::Utc::now().to_rfc3339();
+
+    let system_info = collector.collect_system_info()?;
+    let processes = collector.collect_processes()?;
+    let mut network = collector.collect_network()?;
+    network.connections = listening_sockets();
+
+    let (memory, disks) = if full {
+        (collector.collect_memory()?, collector.collect_disks()?)
+    } else {
+        (
+            MemoryInfo {
+                total_memory: 0,
+                used_memory: 0,
+                total_swap: 0,
+                used_swap: 0,
+            },
+            Vec::new(),
+        )
+    };
+
+    Ok(StateSnapshot {
+        taken_at,
+        data: VolatileData {
+            system_info,
+            processes,
+            network,
+            memory,
+            disks,
+        },
+        sessions: user_sessions(),
+        directory_counts: directory_counts(&watched_directories()),
+    })
+}
+
+/// TCP sockets in the LISTEN state
+fn listening_sockets() -> Vec<NetworkConnection> {
+    match tcp_sockets::enumerate_tcp_sockets() {
+        Ok(sockets) => sockets
+            .into_iter()
+            .filter(|s| s.state == "LISTEN")
+            .map(|s| NetworkConnection {
+                protocol: if s.local_addr.contains(':') {
+                    "TCP6".to_string()
+                } else {
+                    "TCP".to_string()
+                },
+                local_address: s.local_addr,
+                local_port: s.local_port,
+                remote_address: None,
+                remote_port: None,
+                state: Some(s.state),
+                process_id: s.pid,
+            })
+            .collect(),
+        Err(e) => {
+            warn!("Failed to enumerate TCP listeners for state diff: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Directories whose entry counts are compared
+fn watched_directories() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+        let mut dirs: Vec<PathBuf> = [r"Temp", r"Prefetch", r"System32\Tasks", r"System32\drivers"]
+            .iter()
+            .map(|d| Path::new(&root).join(d))
+            .collect();
+        if let Ok(public) = std::env::var("PUBLIC") {
+            dirs.push(PathBuf::from(public));
+        }
+        dirs
+    }
+    #[cfg(target_os = "macos")]
+    {
+        [
+            "/tmp",
+            "/var/tmp",
+            "/Library/LaunchDaemons",
+            "/Library/LaunchAgents",
+            "/Users/Shared",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        [
+            "/tmp",
+            "/var/tmp",
+            "/dev/shm",
+            "/etc/cron.d",
+            "/etc/systemd/system",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+}
+
+/// Count the entries directly inside each directory; unreadable ones are left out
+fn directory_counts(dirs: &[PathBuf]) -> BTreeMap<String, usize> {
+    dirs.iter()
+        .filter_map(|dir| match fs::read_dir(dir) {
+            Ok(entries) => Some((dir.to_string_lossy().to_string(), entries.count())),
+            Err(e) => {
+                debug!("Not counting {}: {}", dir.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Logged-in sessions from utmpx
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn user_sessions() -> Vec<UserSession> {
+    fn field(chars: &[libc::c_char]) -> Option<String> {
+        let bytes: Vec<u8> = chars
+            .iter()
+            .take_while(|c| **c != 0)
+            // c_char is signed on some targets
+            .map(|c| c.to_ne_bytes()[0])
+            .collect();
+        let value = String::from_utf8_lossy(&bytes).trim().to_string();
+        (!value.is_empty()).then_some(value)
+    }
+
+    let mut sessions = Vec::new();
+    // SAFETY: the utmpx database is iterated on this thread only and each
+    // record is copied before the next call overwrites it.
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            let entry = &*entry;
+            if entry.ut_type != libc::USER_PROCESS {
+                continue;
+            }
+            let Some(user) = field(&entry.ut_user) else {
+                continue;
+            };
+            let login_time =
+                chrono::DateTime::<chrono::Utc>::from_timestamp(i64::from(entry.ut_tv.tv_sec), 0)
+                    .map(|t| t.to_rfc3339());
+            sessions.push(UserSession {
+                user,
+                terminal: field(&entry.ut_line),
+                host: field(&entry.ut_host),
+                login_time,
+            });
+        }
+        libc::endutxent();
+    }
+    sessions
+}
+
+/// Logged-in sessions from the Remote Desktop Services API
+#[cfg(target_os = "windows")]
+pub(crate) fn user_sessions() -> Vec<UserSession> {
+    use std::ptr;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::winnt::LPWSTR;
+    use winapi::um::wtsapi32::{
+        WTSClientName, WTSDomainName, WTSEnumerateSessionsW, WTSFreeMemory,
+        WTSQuerySessionInformationW, WTSUserName, WTS_CURRENT_SERVER_HANDLE, WTS_INFO_CLASS,
+        WTS_SESSION_INFOW,
+    };
+
+    unsafe fn wide_to_string(ptr: *const u16) -> Option<String> {
+        if ptr.is_null() {
+            return None;
+        }
+        let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+        let value = String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len));
+        (!value.is_empty()).then_some(value)
+    }
+
+    unsafe fn query(session_id: DWORD, class: WTS_INFO_CLASS) -> Option<String> {
+        let mut buffer: LPWSTR = ptr::null_mut();
+        let mut bytes: DWORD = 0;
+        if WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            class,
+            &mut buffer,
+            &mut bytes,
+        ) == 0
+        {
+            return None;
+        }
+        let value = wide_to_string(buffer);
+        WTSFreeMemory(buffer as *mut _);
+        value
+    }
+
+    let mut sessions = Vec::new();
+    // SAFETY: the session array is only read within the count returned by
+    // WTSEnumerateSessionsW and freed once.
+    unsafe {
+        let mut info: *mut WTS_SESSION_INFOW = ptr::null_mut();
+        let mut count: DWORD = 0;
+        if WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut info, &mut count) == 0 {
+            warn!(
+                "Failed to enumerate user sessions: {}",
+                std::io::Error::last_os_error()
+            );
+            return sessions;
+        }
+
+        for session in std::slice::from_raw_parts(info, count as usize) {
+            let Some(user) = query(session.SessionId, WTSUserName) else {
+                continue;
+            };
+            let user = match query(session.SessionId, WTSDomainName) {
+                Some(domain) => format!(r"{}\{}", domain, user),
+                None => user,
+            };
+            sessions.push(UserSession {
+                user,
+                terminal: wide_to_string(session.pWinStationName),
+                host: query(session.SessionId, WTSClientName),
+                login_time: None,
+            });
+        }
+        WTSFreeMemory(info as *mut _);
+    }
+    sessions
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub(crate) fn user_sessions() -> Vec<UserSession> {
+    Vec::new()
+}
+
+/// Compare two snapshots.
+///
+/// Processes are matched on PID and start time so a reused PID shows up as
+/// one exit and one start. Changes are attributed to the collector when they
+/// involve `collector_pid` or its descendants, a process named like a
+/// command in `footprint` that started within [`SPAWN_WINDOW_SECS`] of it
+/// (children that outlived their parent lose the link to the collector), or
+/// a directory the collector created paths in.
+pub fn diff_snapshots(
+    before: &StateSnapshot,
+    after: &StateSnapshot,
+    collector_pid: u32,
+    footprint: &CollectorFootprint,
+) -> StateDiff {
+    // Parent links from both snapshots, so exited children can be traced too
+    let parents: HashMap<u32, Option<u32>> = before
+        .data
+        .processes
+        .iter()
+        .chain(&after.data.processes)
+        .map(|p| (p.pid, p.parent_pid))
+        .collect();
+    let spawned: Vec<(String, i64)> = footprint
+        .processes
+        .iter()
+        .filter_map(|p| {
+            let started = chrono::DateTime::parse_from_rfc3339(&p.started).ok()?;
+            Some((program_name(&p.program)?, started.timestamp()))
+        })
+        .collect();
+    let from_collector = |process: &ProcessInfo| {
+        descends_from(process.pid, collector_pid, &parents)
+            || program_name(&process.name).is_some_and(|name| {
+                let start = process.start_time as i64;
+                spawned.iter().any(|(program, started)| {
+                    *program == name && (start - started).abs() <= SPAWN_WINDOW_SECS
+                })
+            })
+    };
+    let to_change = |process: &ProcessInfo| ProcessChange {
+        pid: process.pid,
+        name: process.name.clone(),
+        exe: process.exe.clone(),
+        cmd: process.cmd.clone(),
+        parent_pid: process.parent_pid,
+        start_time: process.start_time,
+        caused_by_collector: from_collector(process),
+    };
+
+    let process_key = |p: &ProcessInfo| (p.pid, p.start_time);
+    let before_processes: HashSet<_> = before.data.processes.iter().map(process_key).collect();
+    let after_processes: HashSet<_> = after.data.processes.iter().map(process_key).collect();
+
+    let mut processes_started: Vec<ProcessChange> = after
+        .data
+        .processes
+        .iter()
+        .filter(|p| !before_processes.contains(&process_key(p)))
+        .map(to_change)
+        .collect();
+    let mut processes_exited: Vec<ProcessChange> = before
+        .data
+        .processes
+        .iter()
+        .filter(|p| !after_processes.contains(&process_key(p)))
+        .map(to_change)
+        .collect();
+    processes_started.sort_by_key(|p| p.pid);
+    processes_exited.sort_by_key(|p| p.pid);
+
+    // Listener owners are named from the snapshot they were seen in
+    let listener_changes = |from: &StateSnapshot, other: &StateSnapshot| {
+        let names: HashMap<u32, &ProcessInfo> =
+            from.data.processes.iter().map(|p| (p.pid, p)).collect();
+        let other_keys: HashSet<_> = other
+            .data
+            .network
+            .connections
+            .iter()
+            .map(listener_key)
+            .collect();
+        let mut changes: Vec<ListenerChange> = from
+            .data
+            .network
+            .connections
+            .iter()
+            .filter(|c| !other_keys.contains(&listener_key(c)))
+            .map(|c| {
+                let owner = c.process_id.and_then(|pid| names.get(&pid));
+                ListenerChange {
+                    protocol: c.protocol.clone(),
+                    local_address: c.local_address.clone(),
+                    local_port: c.local_port,
+                    process_id: c.process_id,
+                    process_name: owner.map(|p| p.name.clone()),
+                    caused_by_collector: c
+                        .process_id
+                        .is_some_and(|pid| descends_from(pid, collector_pid, &parents)),
+                }
+            })
+            .collect();
+        changes.sort_by(|a, b| {
+            (a.local_port, &a.local_address).cmp(&(b.local_port, &b.local_address))
+        });
+        changes
+    };
+
+    let before_sessions: HashSet<&UserSession> = before.sessions.iter().collect();
+    let after_sessions: HashSet<&UserSession> = after.sessions.iter().collect();
+    let mut sessions_started: Vec<UserSession> = after_sessions
+        .difference(&before_sessions)
+        .map(|s| (*s).clone())
+        .collect();
+    let mut sessions_ended: Vec<UserSession> = before_sessions
+        .difference(&after_sessions)
+        .map(|s| (*s).clone())
+        .collect();
+    sessions_started.sort();
+    sessions_ended.sort();
+
+    let directory_changes = before
+        .directory_counts
+        .keys()
+        .chain(after.directory_counts.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|dir| {
+            let (count_before, count_after) = (
+                before.directory_counts.get(dir).copied(),
+                after.directory_counts.get(dir).copied(),
+            );
+            if count_before == count_after {
+                return None;
+            }
+            Some(DirectoryChange {
+                path: dir.clone(),
+                before: count_before,
+                after: count_after,
+                caused_by_collector: footprint
+                    .created_paths
+                    .iter()
+                    .any(|created| Path::new(&created.path).starts_with(dir)),
+            })
+        })
+        .collect();
+
+    StateDiff {
+        started: before.taken_at.clone(),
+        finished: after.taken_at.clone(),
+        collector_pid,
+        processes_started,
+        processes_exited,
+        listeners_opened: listener_changes(after, before),
+        listeners_closed: listener_changes(before, after),
+        sessions_started,
+        sessions_ended,
+        directory_changes,
+    }
+}
+
+fn listener_key(connection: &NetworkConnection) -> (String, String, u16) {
+    (
+        connection.protocol.clone(),
+        connection.local_address.clone(),
+        connection.local_port,
+    )
+}
+
+/// Lower-case file name of a program, without a `.exe` extension
+fn program_name(program: &str) -> Option<String> {
+    let name = program.rsplit(['/', '\\']).next()?.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name).to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Whether `pid` is `ancestor` or one of its descendants
+fn descends_from(pid: u32, ancestor: u32, parents: &HashMap<u32, Option<u32>>) -> bool {
+    let mut current = pid;
+    // Bounded walk; PID reuse can create cycles in the parent map
+    for _ in 0..64 {
+        if current == ancestor {
+            return true;
+        }
+        match parents.get(&current).copied().flatten() {
+            Some(parent) if parent != current => current = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::footprint::{CreatedPath, SpawnedProcess};
+    use crate::collectors::volatile::models::{CpuInfo, NetworkInfo, SystemInfo};
+
+    const COLLECTOR_PID: u32 = 500;
+
+    fn process(pid: u32, parent_pid: Option<u32>, name: &str, start_time: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cmd: vec![name.to_string()],
+            exe: None,
+            status: "Running".to_string(),
+            start_time,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            parent_pid,
+        }
+    }
+
+    fn listener(port: u16, pid: u32) -> NetworkConnection {
+        NetworkConnection {
+            protocol: "TCP".to_string(),
+            local_address: "0.0.0.0".to_string(),
+            local_port: port,
+            remote_address: None,
+            remote_port: None,
+            state: Some("LISTEN".to_string()),
+            process_id: Some(pid),
+        }
+    }
+
+    fn snapshot(
+        processes: Vec<ProcessInfo>,
+        connections: Vec<NetworkConnection>,
+        sessions: Vec<UserSession>,
+        directory_counts: &[(&str, usize)],
+    ) -> StateSnapshot {
+        StateSnapshot {
+            taken_at: "2024-01-11T10:00:00+00:00".to_string(),
+            data: VolatileData {
+                system_info: SystemInfo {
+                    hostname: Some("host".to_string()),
+                    os_name: None,
+                    os_version: None,
+                    kernel_version: None,
+                    cpu_info: CpuInfo {
+                        count: 1,
+                        vendor: None,
+                        brand: None,
+                        frequency: 0,
+                    },
+                    time_context: None,
+                },
+                processes,
+                network: NetworkInfo {
+                    interfaces: Vec::new(),
+                    connections,
+                },
+                memory: MemoryInfo {
+                    total_memory: 0,
+                    used_memory: 0,
+                    total_swap: 0,
+                    used_swap: 0,
+                },
+                disks: Vec::new(),
+            },
+            sessions,
+            directory_counts: directory_counts
+                .iter()
+                .map(|(dir, count)| (dir.to_string(), *count))
+                .collect(),
+        }
+    }
+
+    fn session(user: &str, terminal: &str) -> UserSession {
+        UserSession {
+            user: user.to_string(),
+            terminal: Some(terminal.to_string()),
+            host: None,
+            login_time: None,
+        }
+    }
+
+    #[test]
+    fn test_process_changes() {
+        let before = snapshot(
+            vec![
+                process(1, None, "init", 10),
+                process(COLLECTOR_PID, Some(1), "rust_collector", 100),
+                process(200, Some(1), "edr_agent", 20),
+                process(300, Some(1), "old", 30),
+            ],
+            Vec::new(),
+            Vec::new(),
+            &[],
+        );
+        let after = snapshot(
+            vec![
+                process(1, None, "init", 10),
+                process(COLLECTOR_PID, Some(1), "rust_collector", 100),
+                // PID reused by a different process
+                process(300, Some(1), "new", 150),
+                process(600, Some(COLLECTOR_PID), "helper", 160),
+            ],
+            Vec::new(),
+            Vec::new(),
+            &[],
+        );
+
+        let diff = diff_snapshots(
+            &before,
+            &after,
+            COLLECTOR_PID,
+            &CollectorFootprint::default(),
+        );
+
+        let started: Vec<_> = diff
+            .processes_started
+            .iter()
+            .map(|p| (p.pid, p.name.as_str(), p.caused_by_collector))
+            .collect();
+        assert_eq!(started, vec![(300, "new", false), (600, "helper", true)]);
+
+        let exited: Vec<_> = diff
+            .processes_exited
+            .iter()
+            .map(|p| (p.pid, p.name.as_str(), p.caused_by_collector))
+            .collect();
+        assert_eq!(exited, vec![(200, "edr_agent", false), (300, "old", false)]);
+        assert_eq!(diff.collector_pid, COLLECTOR_PID);
+    }
+
+    #[test]
+    fn test_listener_changes_attributed() {
+        let processes = vec![
+            process(1, None, "init", 10),
+            process(COLLECTOR_PID, Some(1), "rust_collector", 100),
+            process(700, Some(1), "nc", 110),
+        ];
+        let before = snapshot(processes.clone(), vec![listener(22, 1)], Vec::new(), &[]);
+        let after = snapshot(
+            processes,
+            vec![listener(4444, 700), listener(9000, COLLECTOR_PID)],
+            Vec::new(),
+            &[],
+        );
+
+        let diff = diff_snapshots(
+            &before,
+            &after,
+            COLLECTOR_PID,
+            &CollectorFootprint::default(),
+        );
+
+        assert_eq!(diff.listeners_opened.len(), 2);
+        assert_eq!(diff.listeners_opened[0].local_port, 4444);
+        assert_eq!(diff.listeners_opened[0].process_name.as_deref(), Some("nc"));
+        assert!(!diff.listeners_opened[0].caused_by_collector);
+        assert!(diff.listeners_opened[1].caused_by_collector);
+
+        assert_eq!(diff.listeners_closed.len(), 1);
+        assert_eq!(diff.listeners_closed[0].local_port, 22);
+        assert_eq!(diff.external_change_count(), 2);
+    }
+
+    #[test]
+    fn test_sessions_and_directories() {
+        let before = snapshot(
+            Vec::new(),
+            Vec::new(),
+            vec![session("alice", "pts/0")],
+            &[("/tmp", 3), ("/var/tmp", 1), ("/dev/shm", 0)],
+        );
+        let after = snapshot(
+            Vec::new(),
+            Vec::new(),
+            vec![session("bob", "pts/1")],
+            &[("/tmp", 4), ("/var/tmp", 1), ("/dev/shm", 1)],
+        );
+        let footprint = CollectorFootprint {
+            created_paths: vec![CreatedPath {
+                path: "/tmp/rust_collector_scratch".to_string(),
+                kind: "directory".to_string(),
+                timestamp: "2024-01-11T10:00:01+00:00".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let diff = diff_snapshots(&before, &after, COLLECTOR_PID, &footprint);
+
+        assert_eq!(diff.sessions_started, vec![session("bob", "pts/1")]);
+        assert_eq!(diff.sessions_ended, vec![session("alice", "pts/0")]);
+
+        assert_eq!(diff.directory_changes.len(), 2);
+        let shm = &diff.directory_changes[0];
+        assert_eq!(
+            (shm.path.as_str(), shm.before, shm.after),
+            ("/dev/shm", Some(0), Some(1))
+        );
+        assert!(!shm.caused_by_collector);
+        assert!(diff.directory_changes[1].caused_by_collector);
+    }
+
+    #[test]
+    fn test_spawned_command_attributed() {
+        let before = snapshot(
+            vec![process(COLLECTOR_PID, Some(1), "rust_collector", 100)],
+            Vec::new(),
+            Vec::new(),
+            &[],
+        );
+        let after = snapshot(
+            vec![
+                process(COLLECTOR_PID, Some(1), "rust_collector", 100),
+                process(800, Some(COLLECTOR_PID), "sh", 165),
+                // Reparented after its parent exited
+                process(810, Some(1), "journalctl", 170),
+                // Same program, started independently later on
+                process(900, Some(1), "journalctl", 400),
+            ],
+            Vec::new(),
+            Vec::new(),
+            &[],
+        );
+        let footprint = CollectorFootprint {
+            processes: vec![SpawnedProcess {
+                program: "/usr/bin/journalctl".to_string(),
+                args: Vec::new(),
+                started: "1970-01-01T00:02:49+00:00".to_string(),
+                exit_code: Some(0),
+                error: None,
+            }],
+            ..Default::default()
+        };
+
+        let diff = diff_snapshots(&before, &after, COLLECTOR_PID, &footprint);
+        let attributed: Vec<_> = diff
+            .processes_started
+            .iter()
+            .map(|p| (p.pid, p.caused_by_collector))
+            .collect();
+        assert_eq!(attributed, vec![(800, true), (810, true), (900, false)]);
+    }
+
+    #[test]
+    fn test_program_name() {
+        assert_eq!(
+            program_name("/usr/bin/netstat"),
+            Some("netstat".to_string())
+        );
+        assert_eq!(
+            program_name(r"C:\Windows\System32\WEVTUTIL.EXE"),
+            Some("wevtutil".to_string())
+        );
+        assert_eq!(program_name(""), None);
+    }
+}
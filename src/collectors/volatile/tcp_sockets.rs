@@ -0,0 +1,500 @@
+//! TCP socket enumeration with owning process association.
+//!
+//! [`NetworkInfo`](super::models::NetworkInfo) lists interfaces but cannot
+//! say which process owns a connection. This module walks every TCP socket
+//! on the host and attributes it to a process:
+//!
+//! - **Linux**: `/proc/net/tcp` and `/proc/net/tcp6`, matched to processes
+//!   through the `socket:[inode]` links in `/proc/<pid>/fd/`
+//! - **Windows**: `GetExtendedTcpTable` for IPv4 and IPv6
+//! - **macOS**: `netstat -vanp tcp`
+//!
+//! Results are written to `volatile/tcp_sockets_extended.json`.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+
+use crate::collectors::volatile::models::NetworkConnection;
+
+/// File name used for the extended TCP socket listing
+pub const TCP_SOCKETS_FILE: &str = "tcp_sockets_extended.json";
+
+/// Whether enumeration spawns an external command on this platform.
+///
+/// Order-of-volatility mode defers such sections until after file collection.
+pub const USES_EXTERNAL_COMMAND: bool = cfg!(target_os = "macos");
+
+/// A TCP socket and the process that owns it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TcpSocket {
+    pub local_addr: String,
+    pub local_port: u16,
+    pub remote_addr: String,
+    pub remote_port: u16,
+    /// Connection state using netstat naming (ESTABLISHED, LISTEN, ...)
+    pub state: String,
+    /// Owning process, when it could be determined
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    /// Kernel socket inode (Linux only)
+    pub inode: Option<u64>,
+}
+
+impl From<&TcpSocket> for NetworkConnection {
+    fn from(socket: &TcpSocket) -> Self {
+        NetworkConnection {
+            protocol: "tcp".to_string(),
+            local_address: socket.local_addr.clone(),
+            local_port: socket.local_port,
+            remote_address: Some(socket.remote_addr.clone()),
+            remote_port: Some(socket.remote_port),
+            state: Some(socket.state.clone()),
+            process_id: socket.pid,
+        }
+    }
+}
+
+/// Enumerate every TCP socket on the host with its owning process.
+pub fn enumerate_tcp_sockets() -> Result<Vec<TcpSocket>> {
+    #[cfg(target_os = "linux")]
+    let mut sockets = linux_impl::enumerate()?;
+    #[cfg(target_os = "windows")]
+    let mut sockets = windows_impl::enumerate()?;
+    #[cfg(target_os = "macos")]
+    let mut sockets = macos_impl::enumerate()?;
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let mut sockets: Vec<TcpSocket> = {
+        log::debug!("TCP socket enumeration is not supported on this platform");
+        Vec::new()
+    };
+
+    fill_process_names(&mut sockets);
+    Ok(sockets)
+}
+
+/// Resolve process names for sockets whose owner is known by PID only
+fn fill_process_names(sockets: &mut [TcpSocket]) {
+    if sockets
+        .iter()
+        .all(|s| s.pid.is_none() || s.process_name.is_some())
+    {
+        return;
+    }
+
+    let mut system = System::new();
+    system.refresh_processes();
+    let names: HashMap<u32, String> = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.name().to_string()))
+        .collect();
+
+    for socket in sockets.iter_mut().filter(|s| s.process_name.is_none()) {
+        socket.process_name = socket.pid.and_then(|pid| names.get(&pid).cloned());
+    }
+}
+
+/// Map a `/proc/net/tcp` state code to its netstat name
+pub fn linux_state_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RCVD",
+        0x04 => "FIN_WAIT_1",
+        0x05 => "FIN_WAIT_2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSED",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "SYN_RCVD",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Decode a `/proc/net/tcp{,6}` address such as `0100007F:0035`.
+///
+/// The kernel prints the address as 32-bit words in host byte order and the
+/// port as a plain hex number.
+pub fn parse_proc_net_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in addr.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+/// Parse the contents of `/proc/net/tcp` or `/proc/net/tcp6`.
+///
+/// Owning processes are left unset; they are resolved from socket inodes.
+pub fn parse_proc_net_tcp(contents: &str) -> Vec<TcpSocket> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+
+            let (local_ip, local_port) = parse_proc_net_address(fields[1])?;
+            let (remote_ip, remote_port) = parse_proc_net_address(fields[2])?;
+            let state = u8::from_str_radix(fields[3], 16).ok()?;
+
+            Some(TcpSocket {
+                local_addr: local_ip.to_string(),
+                local_port,
+                remote_addr: remote_ip.to_string(),
+                remote_port,
+                state: linux_state_name(state).to_string(),
+                pid: None,
+                process_name: None,
+                inode: fields[9].parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Extract the inode from a `/proc/<pid>/fd` link target like `socket:[12345]`
+pub fn parse_socket_link(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Split a netstat endpoint such as `192.168.1.5.52345` or `*.22`.
+///
+/// netstat separates the port with the last `.`, including for IPv6.
+pub fn parse_netstat_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (addr, port) = endpoint.rsplit_once('.')?;
+    // Unconnected remote endpoints are printed as `*.*`
+    let port = if port == "*" { 0 } else { port.parse().ok()? };
+    Some((addr.to_string(), port))
+}
+
+/// Parse the output of `netstat -vanp tcp` on macOS.
+///
+/// The PID column follows the receive and send high-water marks. Recent
+/// macOS releases print it as `name:pid`, older ones as a bare PID.
+pub fn parse_netstat_output(output: &str) -> Vec<TcpSocket> {
+    output
+        .lines()
+        .filter(|line| line.starts_with("tcp"))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 9 {
+                return None;
+            }
+
+            let (local_addr, local_port) = parse_netstat_endpoint(fields[3])?;
+            let (remote_addr, remote_port) = parse_netstat_endpoint(fields[4])?;
+            let (process_name, pid) = match fields[8].rsplit_once(':') {
+                Some((name, pid)) => (Some(name.to_string()), pid.parse().ok()),
+                None => (None, fields[8].parse().ok()),
+            };
+
+            Some(TcpSocket {
+                local_addr,
+                local_port,
+                remote_addr,
+                remote_port,
+                state: fields[5].to_string(),
+                pid,
+                process_name,
+                inode: None,
+            })
+        })
+        .collect()
+}
+
+/// Socket inode to owning PID map, shared with the other `/proc/net` tables
+#[cfg(target_os = "linux")]
+pub(crate) use linux_impl::socket_owners;
+
+#[cfg(target_os = "linux")]
+mod linux_impl {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use anyhow::{bail, Result};
+    use log::debug;
+
+    use super::{parse_proc_net_tcp, parse_socket_link, TcpSocket};
+
+    pub fn enumerate() -> Result<Vec<TcpSocket>> {
+        let mut sockets = Vec::new();
+        let mut read_any = false;
+        for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            match fs::read_to_string(table) {
+                Ok(contents) => {
+                    read_any = true;
+                    sockets.extend(parse_proc_net_tcp(&contents));
+                }
+                // tcp6 is absent when IPv6 is disabled
+                Err(e) => debug!("Failed to read {}: {}", table, e),
+            }
+        }
+        if !read_any {
+            bail!("No TCP tables readable under /proc/net");
+        }
+
+        let owners = socket_owners();
+        for socket in &mut sockets {
+            socket.pid = socket.inode.and_then(|inode| owners.get(&inode).copied());
+        }
+        Ok(sockets)
+    }
+
+    /// Map socket inodes to the PID holding them open.
+    ///
+    /// Processes whose fd directory is unreadable (other users, without
+    /// privileges) are skipped.
+    pub fn socket_owners() -> HashMap<u64, u32> {
+        let mut owners = HashMap::new();
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return owners;
+        };
+
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                if let Some(inode) = fs::read_link(fd.path())
+                    .ok()
+                    .and_then(|target| parse_socket_link(&target.to_string_lossy()))
+                {
+                    owners.entry(inode).or_insert(pid);
+                }
+            }
+        }
+        owners
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use winapi::shared::iprtrmib::TCP_TABLE_OWNER_PID_ALL;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::tcpmib::{
+        MIB_TCP6ROW_OWNER_PID, MIB_TCP6TABLE_OWNER_PID, MIB_TCPROW_OWNER_PID,
+        MIB_TCPTABLE_OWNER_PID,
+    };
+    use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, NO_ERROR};
+    use winapi::shared::ws2def::{AF_INET, AF_INET6};
+    use winapi::um::iphlpapi::GetExtendedTcpTable;
+
+    use super::TcpSocket;
+
+    fn state_name(state: DWORD) -> &'static str {
+        match state {
+            1 => "CLOSED",
+            2 => "LISTEN",
+            3 => "SYN_SENT",
+            4 => "SYN_RCVD",
+            5 => "ESTABLISHED",
+            6 => "FIN_WAIT_1",
+            7 => "FIN_WAIT_2",
+            8 => "CLOSE_WAIT",
+            9 => "CLOSING",
+            10 => "LAST_ACK",
+            11 => "TIME_WAIT",
+            12 => "DELETE_TCB",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// Ports are stored in network byte order in the low 16 bits
+    fn port(value: DWORD) -> u16 {
+        u16::from_be(value as u16)
+    }
+
+    /// Fetch a raw owner-PID TCP table for one address family
+    fn fetch_table(family: i32) -> Result<Vec<u8>> {
+        let mut size: DWORD = 0;
+        let mut buffer: Vec<u8> = Vec::new();
+        // The table can grow between calls, so retry until it fits
+        loop {
+            // SAFETY: buffer holds `size` bytes (or is null when size is 0)
+            let result = unsafe {
+                GetExtendedTcpTable(
+                    if buffer.is_empty() {
+                        ptr::null_mut()
+                    } else {
+                        buffer.as_mut_ptr() as *mut _
+                    },
+                    &mut size,
+                    0,
+                    family as u32,
+                    TCP_TABLE_OWNER_PID_ALL,
+                    0,
+                )
+            };
+            match result {
+                NO_ERROR => return Ok(buffer),
+                ERROR_INSUFFICIENT_BUFFER => buffer = vec![0u8; size as usize],
+                code => return Err(anyhow!("GetExtendedTcpTable failed: {}", code)),
+            }
+        }
+    }
+
+    pub fn enumerate() -> Result<Vec<TcpSocket>> {
+        let mut sockets = Vec::new();
+
+        let table = fetch_table(AF_INET)?;
+        if !table.is_empty() {
+            // SAFETY: the API filled the buffer with a MIB_TCPTABLE_OWNER_PID
+            let rows = unsafe {
+                let header = &*(table.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+                std::slice::from_raw_parts::<MIB_TCPROW_OWNER_PID>(
+                    header.table.as_ptr(),
+                    header.dwNumEntries as usize,
+                )
+            };
+            sockets.extend(rows.iter().map(|row| TcpSocket {
+                local_addr: Ipv4Addr::from(row.dwLocalAddr.to_ne_bytes()).to_string(),
+                local_port: port(row.dwLocalPort),
+                remote_addr: Ipv4Addr::from(row.dwRemoteAddr.to_ne_bytes()).to_string(),
+                remote_port: port(row.dwRemotePort),
+                state: state_name(row.dwState).to_string(),
+                pid: Some(row.dwOwningPid),
+                process_name: None,
+                inode: None,
+            }));
+        }
+
+        let table = fetch_table(AF_INET6)?;
+        if !table.is_empty() {
+            // SAFETY: the API filled the buffer with a MIB_TCP6TABLE_OWNER_PID
+            let rows = unsafe {
+                let header = &*(table.as_ptr() as *const MIB_TCP6TABLE_OWNER_PID);
+                std::slice::from_raw_parts::<MIB_TCP6ROW_OWNER_PID>(
+                    header.table.as_ptr(),
+                    header.dwNumEntries as usize,
+                )
+            };
+            sockets.extend(rows.iter().map(|row| TcpSocket {
+                local_addr: Ipv6Addr::from(row.ucLocalAddr).to_string(),
+                local_port: port(row.dwLocalPort),
+                remote_addr: Ipv6Addr::from(row.ucRemoteAddr).to_string(),
+                remote_port: port(row.dwRemotePort),
+                state: state_name(row.dwState).to_string(),
+                pid: Some(row.dwOwningPid),
+                process_name: None,
+                inode: None,
+            }));
+        }
+
+        Ok(sockets)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_impl {
+    use std::process::Command;
+
+    use anyhow::{bail, Context, Result};
+
+    use super::{parse_netstat_output, TcpSocket};
+    use crate::collectors::footprint::run_command;
+
+    pub fn enumerate() -> Result<Vec<TcpSocket>> {
+        let output = run_command(Command::new("netstat").args(["-vanp", "tcp"]))
+            .context("Failed to run netstat")?;
+        if !output.status.success() {
+            bail!(
+                "netstat exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(parse_netstat_output(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net_tcp() {
+        let contents = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: 0100007F:0035 00000000:0000 0A 00000000:00000000 00:00000000 00000000   101        0 23456 1 0000000000000000 100 0 0 10 0\n   1: 0F02000A:A2C4 2E1A3A8E:01BB 01 00000000:00000000 02:000AFE7C 00000000  1000        0 98765 2 0000000000000000 20 4 30 10 -1\n";
+        let sockets = parse_proc_net_tcp(contents);
+
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].local_addr, "127.0.0.1");
+        assert_eq!(sockets[0].local_port, 53);
+        assert_eq!(sockets[0].state, "LISTEN");
+        assert_eq!(sockets[0].inode, Some(23456));
+        assert_eq!(sockets[1].local_addr, "10.0.2.15");
+        assert_eq!(sockets[1].remote_addr, "142.58.26.46");
+        assert_eq!(sockets[1].remote_port, 443);
+        assert_eq!(sockets[1].state, "ESTABLISHED");
+    }
+
+    #[test]
+    fn test_parse_proc_net_tcp6_address() {
+        let (ip, port) = parse_proc_net_address("00000000000000000000000001000000:0016").unwrap();
+        assert_eq!(ip, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(port, 22);
+    }
+
+    #[test]
+    fn test_parse_socket_link() {
+        assert_eq!(parse_socket_link("socket:[12345]"), Some(12345));
+        assert_eq!(parse_socket_link("pipe:[12345]"), None);
+        assert_eq!(parse_socket_link("/dev/null"), None);
+    }
+
+    #[test]
+    fn test_parse_netstat_output() {
+        let output = "Active Internet connections (including servers)\nProto Recv-Q Send-Q  Local Address          Foreign Address        (state)      rhiwat  shiwat    pid   epid state  options\ntcp4       0      0  192.168.1.5.52345      17.57.146.20.5223      ESTABLISHED  131072  131768    412      0 0x0102 0x00000020\ntcp6       0      0  *.22                   *.*                    LISTEN       131072  131072  sshd:88      0 00100 00000006\n";
+        let sockets = parse_netstat_output(output);
+
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].local_addr, "192.168.1.5");
+        assert_eq!(sockets[0].local_port, 52345);
+        assert_eq!(sockets[0].remote_port, 5223);
+        assert_eq!(sockets[0].pid, Some(412));
+        assert_eq!(sockets[0].process_name, None);
+        assert_eq!(sockets[1].local_addr, "*");
+        assert_eq!(sockets[1].remote_port, 0);
+        assert_eq!(sockets[1].state, "LISTEN");
+        assert_eq!(sockets[1].pid, Some(88));
+        assert_eq!(sockets[1].process_name.as_deref(), Some("sshd"));
+    }
+
+    #[test]
+    fn test_enumerate_tcp_sockets() {
+        assert!(enumerate_tcp_sockets().is_ok());
+    }
+}
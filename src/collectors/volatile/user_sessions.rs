@@ -0,0 +1,738 @@
+//! Console, RDP and remote login sessions, live and historic.
+//!
+//! Who was logged on, from where and when is the first thing checked for
+//! lateral movement. Sessions are written to `volatile/user_sessions.json`
+//! as [`SessionEvent`]s, sorted by login time:
+//!
+//! - **Windows**: live sessions from `WTSEnumerateSessions`, with the logon
+//!   time and client address of each. Once file artifacts are collected,
+//!   [`write_event_log_sessions`] adds the historic sessions in the
+//!   collected `Security.evtx` (4778 reconnected / 4779 disconnected) and
+//!   TerminalServices LocalSessionManager log (21 logon, 25 reconnect /
+//!   23 logoff, 24 disconnect).
+//! - **Linux**: `/var/log/wtmp`, pairing each login with the logout on the
+//!   same terminal. Sessions still open at a reboot or shutdown end there;
+//!   those still open at the end of the file are active.
+//! - **macOS**: live sessions from utmpx, and last logins from
+//!   `/var/log/lastlog` where it exists (read with the Linux record
+//!   layout). Current macOS releases keep login history in the Apple
+//!   System Log instead, which the login records collector copies.
+//!
+//! A session's `logout_time` is when it ended or was disconnected. Event log
+//! sessions with no logoff or disconnect after them are marked active, as
+//! nothing in the log says otherwise.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::auth_unix::AuthEvent;
+use crate::collectors::parsers::evtx::{self, EventRecord};
+
+/// File written to the volatile data directory
+pub const USER_SESSIONS_FILE: &str = "user_sessions.json";
+
+/// Remote Desktop session manager log read alongside `Security.evtx`
+pub const LOCAL_SESSION_MANAGER_LOG: &str =
+    "Microsoft-Windows-TerminalServices-LocalSessionManager%4Operational.evtx";
+
+/// A session was reconnected to a window station
+pub const SESSION_RECONNECTED_EVENT_ID: u32 = 4778;
+
+/// A session was disconnected from a window station
+pub const SESSION_DISCONNECTED_EVENT_ID: u32 = 4779;
+
+/// LocalSessionManager: logon, logoff, disconnect, reconnect
+const LSM_LOGON: u32 = 21;
+const LSM_LOGOFF: u32 = 23;
+const LSM_DISCONNECT: u32 = 24;
+const LSM_RECONNECT: u32 = 25;
+
+/// `source` of sessions read from event logs, replaced on each parse
+const EVENT_LOG_SOURCES: [&str; 2] = ["Security.evtx", "LocalSessionManager"];
+
+/// How the user was connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    /// Local console or graphical login
+    Console,
+    /// Remote Desktop
+    Rdp,
+    /// Login from a remote host (SSH, telnet)
+    Remote,
+    /// Local terminal or pseudo-terminal with no remote host
+    Terminal,
+}
+
+/// A login session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub session_type: SessionType,
+    pub username: String,
+    pub source_ip: Option<String>,
+    pub login_time: DateTime<Utc>,
+    /// When the session ended or was disconnected
+    pub logout_time: Option<DateTime<Utc>>,
+    /// Windows session id or window station, or the Unix terminal
+    pub session_id: String,
+    pub is_active: bool,
+    /// Where the session was found: `wts`, `utmpx`, `wtmp`, `lastlog`,
+    /// `Security.evtx` or `LocalSessionManager`
+    pub source: String,
+}
+
+/// Live and on-host session history for this platform
+pub fn collect_user_sessions() -> Result<Vec<SessionEvent>> {
+    #[cfg(target_os = "windows")]
+    let mut sessions = windows_impl::live_sessions()?;
+
+    #[cfg(target_os = "linux")]
+    let mut sessions = {
+        let wtmp = Path::new("/var/log/wtmp");
+        let data = fs::read(wtmp).context(format!("Failed to read {}", wtmp.display()))?;
+        crate::collectors::parsers::auth_unix::parse_wtmp_data(&data, "wtmp")
+            .map(|events| sessions_from_wtmp(&events))
+            .unwrap_or_default()
+    };
+
+    #[cfg(target_os = "macos")]
+    let mut sessions = {
+        let mut sessions = utmpx_sessions();
+        sessions.extend(lastlog_sessions(
+            Path::new("/var/log/lastlog"),
+            Path::new("/etc/passwd"),
+        ));
+        sessions
+    };
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let mut sessions: Vec<SessionEvent> = {
+        debug!("User session history is not supported on this platform");
+        Vec::new()
+    };
+
+    sessions.sort_by_key(|s| s.login_time);
+    Ok(sessions)
+}
+
+/// Unix session type from the terminal and remote host
+fn unix_session_type(tty: Option<&str>, remote: bool) -> SessionType {
+    match tty {
+        _ if remote => SessionType::Remote,
+        Some(tty) if tty.starts_with("tty") || tty.starts_with(':') || tty == "console" => {
+            SessionType::Console
+        }
+        _ => SessionType::Terminal,
+    }
+}
+
+/// Pair `wtmp` logins with the logout on the same terminal
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn sessions_from_wtmp(events: &[AuthEvent]) -> Vec<SessionEvent> {
+    let mut sessions: Vec<SessionEvent> = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+
+    for event in events {
+        let tty = event.tty.clone().unwrap_or_default();
+        match event.event.as_str() {
+            "login" => {
+                // A login on a terminal that never logged out ends the earlier session
+                if let Some(earlier) = open.insert(tty.clone(), sessions.len()) {
+                    sessions[earlier].logout_time = Some(event.timestamp);
+                }
+                let remote = event.source_ip.is_some() || event.host.is_some();
+                sessions.push(SessionEvent {
+                    session_type: unix_session_type(event.tty.as_deref(), remote),
+                    username: event.user.clone().unwrap_or_default(),
+                    source_ip: event.source_ip.clone(),
+                    login_time: event.timestamp,
+                    logout_time: None,
+                    session_id: tty,
+                    is_active: false,
+                    source: "wtmp".to_string(),
+                });
+            }
+            "logout" => {
+                if let Some(index) = open.remove(&tty) {
+                    sessions[index].logout_time = Some(event.timestamp);
+                }
+            }
+            "boot" | "shutdown" => {
+                for (_, index) in open.drain() {
+                    sessions[index].logout_time = Some(event.timestamp);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for index in open.into_values() {
+        sessions[index].is_active = true;
+    }
+    sessions
+}
+
+/// Last logins from a `lastlog` file, named from `passwd` where it can be read
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn lastlog_sessions(lastlog: &Path, passwd: &Path) -> Vec<SessionEvent> {
+    use crate::collectors::parsers::auth_unix::{parse_lastlog, parse_passwd};
+
+    if !lastlog.exists() {
+        return Vec::new();
+    }
+    let users = fs::read_to_string(passwd)
+        .map(|text| parse_passwd(&text))
+        .unwrap_or_default();
+    let events = match parse_lastlog(lastlog, &users) {
+        Ok(events) => events,
+        Err(e) => {
+            debug!("Cannot read {}: {:#}", lastlog.display(), e);
+            return Vec::new();
+        }
+    };
+    events
+        .into_iter()
+        .map(|event| SessionEvent {
+            session_type: unix_session_type(
+                event.tty.as_deref(),
+                event.source_ip.is_some() || event.host.is_some(),
+            ),
+            username: event.user.unwrap_or_default(),
+            source_ip: event.source_ip,
+            login_time: event.timestamp,
+            logout_time: None,
+            session_id: event.tty.unwrap_or_default(),
+            is_active: false,
+            source: "lastlog".to_string(),
+        })
+        .collect()
+}
+
+/// Logged-in sessions from utmpx
+#[cfg(target_os = "macos")]
+fn utmpx_sessions() -> Vec<SessionEvent> {
+    crate::collectors::volatile::state_diff::user_sessions()
+        .into_iter()
+        .filter_map(|session| {
+            let login_time = DateTime::parse_from_rfc3339(session.login_time.as_deref()?)
+                .ok()?
+                .with_timezone(&Utc);
+            let source_ip = session
+                .host
+                .as_deref()
+                .and_then(|h| h.parse::<IpAddr>().ok())
+                .map(|ip| ip.to_string());
+            Some(SessionEvent {
+                session_type: unix_session_type(
+                    session.terminal.as_deref(),
+                    session.host.is_some(),
+                ),
+                username: session.user,
+                source_ip,
+                login_time,
+                logout_time: None,
+                session_id: session.terminal.unwrap_or_default(),
+                is_active: true,
+                source: "utmpx".to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Windows session type from the window station name
+fn window_station_type(name: &str) -> SessionType {
+    if name.eq_ignore_ascii_case("console") {
+        SessionType::Console
+    } else {
+        SessionType::Rdp
+    }
+}
+
+/// A logged address, if it is one (`LOCAL`, `-` and `Unknown` are not)
+fn logged_ip(address: Option<&String>) -> Option<String> {
+    address
+        .and_then(|a| a.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_string())
+}
+
+/// `DOMAIN\user`, or `user` alone
+fn qualified_user(domain: Option<&String>, user: &str) -> String {
+    match domain.filter(|d| !d.is_empty() && *d != "-") {
+        Some(domain) => format!(r"{}\{}", domain, user),
+        None => user.to_string(),
+    }
+}
+
+/// Sessions from Security 4778/4779 records, paired by user and window
+/// station
+fn sessions_from_security_log(records: &[EventRecord]) -> Vec<SessionEvent> {
+    let mut sessions: Vec<SessionEvent> = Vec::new();
+    let mut open: HashMap<(String, String), usize> = HashMap::new();
+
+    for record in records {
+        let (Some(event_id), Some(time)) = (record.event_id(), record.written_time) else {
+            continue;
+        };
+        if event_id != SESSION_RECONNECTED_EVENT_ID && event_id != SESSION_DISCONNECTED_EVENT_ID {
+            continue;
+        }
+        let data = record.event_data();
+        let Some(user) = data.get("AccountName").filter(|u| !u.is_empty()) else {
+            continue;
+        };
+        let username = qualified_user(data.get("AccountDomain"), user);
+        let station = data.get("SessionName").cloned().unwrap_or_default();
+        let key = (username.to_lowercase(), station.clone());
+
+        if event_id == SESSION_DISCONNECTED_EVENT_ID {
+            // A disconnect with no reconnect in the log has no start time
+            if let Some(index) = open.remove(&key) {
+                sessions[index].logout_time = Some(time);
+            }
+            continue;
+        }
+        open.insert(key, sessions.len());
+        sessions.push(SessionEvent {
+            session_type: window_station_type(&station),
+            username,
+            source_ip: logged_ip(data.get("ClientAddress")),
+            login_time: time,
+            logout_time: None,
+            session_id: station,
+            is_active: false,
+            source: EVENT_LOG_SOURCES[0].to_string(),
+        });
+    }
+
+    for index in open.into_values() {
+        sessions[index].is_active = true;
+    }
+    sessions
+}
+
+/// Sessions from LocalSessionManager records, paired by session id
+fn sessions_from_session_manager_log(records: &[EventRecord]) -> Vec<SessionEvent> {
+    let mut sessions: Vec<SessionEvent> = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+
+    for record in records {
+        let (Some(event_id), Some(time)) = (record.event_id(), record.written_time) else {
+            continue;
+        };
+        if ![LSM_LOGON, LSM_LOGOFF, LSM_DISCONNECT, LSM_RECONNECT].contains(&event_id) {
+            continue;
+        }
+        let data = record.user_data();
+        let Some(session_id) = data.get("SessionID").cloned() else {
+            continue;
+        };
+
+        if event_id == LSM_LOGOFF || event_id == LSM_DISCONNECT {
+            if let Some(index) = open.remove(&session_id) {
+                sessions[index].logout_time = Some(time);
+            }
+            continue;
+        }
+        let source_ip = logged_ip(data.get("Address"));
+        let session_type = match (&source_ip, data.get("Address")) {
+            (None, Some(address)) if address.eq_ignore_ascii_case("LOCAL") => SessionType::Console,
+            _ => SessionType::Rdp,
+        };
+        if let Some(earlier) = open.insert(session_id.clone(), sessions.len()) {
+            sessions[earlier].logout_time = Some(time);
+        }
+        sessions.push(SessionEvent {
+            session_type,
+            username: data.get("User").cloned().unwrap_or_default(),
+            source_ip,
+            login_time: time,
+            logout_time: None,
+            session_id,
+            is_active: false,
+            source: EVENT_LOG_SOURCES[1].to_string(),
+        });
+    }
+
+    for index in open.into_values() {
+        sessions[index].is_active = true;
+    }
+    sessions
+}
+
+/// Sessions from collected Security and LocalSessionManager logs, told
+/// apart by file name
+pub fn sessions_from_event_logs(logs: &[PathBuf]) -> Result<Vec<SessionEvent>> {
+    let mut sessions = Vec::new();
+    for path in logs {
+        let data = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        let log = evtx::parse_evtx(&data)?;
+        let is_security = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case("Security.evtx"));
+        sessions.extend(match is_security {
+            true => sessions_from_security_log(&log.records),
+            false => sessions_from_session_manager_log(&log.records),
+        });
+    }
+    Ok(sessions)
+}
+
+/// Add the sessions in collected event `logs` to
+/// `<volatile_dir>/user_sessions.json`, keeping the live sessions already
+/// there. Event log sessions from an earlier run are replaced.
+pub fn write_event_log_sessions(volatile_dir: &Path, logs: &[PathBuf]) -> Result<PathBuf> {
+    let path = volatile_dir.join(USER_SESSIONS_FILE);
+    let mut sessions: Vec<SessionEvent> = match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .context(format!("Failed to read sessions from {}", path.display()))?,
+        Err(_) => Vec::new(),
+    };
+    sessions.retain(|s| !EVENT_LOG_SOURCES.contains(&s.source.as_str()));
+
+    let historic = sessions_from_event_logs(logs)?;
+    debug!("Read {} sessions from event logs", historic.len());
+    sessions.extend(historic);
+    sessions.sort_by_key(|s| s.login_time);
+
+    fs::create_dir_all(volatile_dir)
+        .context(format!("Failed to create {}", volatile_dir.display()))?;
+    fs::write(&path, serde_json::to_string_pretty(&sessions)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::ffi::c_void;
+    use std::net::Ipv4Addr;
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use log::debug;
+    use winapi::shared::minwindef::{BOOL, DWORD};
+    use winapi::um::winnt::{HANDLE, LPWSTR};
+
+    use super::{qualified_user, window_station_type, SessionEvent};
+    use crate::utils::timezone::filetime_ticks_to_utc;
+
+    const WTS_CURRENT_SERVER_HANDLE: HANDLE = ptr::null_mut();
+    const WTS_CLIENT_ADDRESS_CLASS: u32 = 14;
+    const WTS_SESSION_INFO_CLASS: u32 = 24;
+    const AF_INET: u32 = 2;
+
+    #[repr(C)]
+    struct WtsSessionInfo {
+        session_id: DWORD,
+        win_station_name: LPWSTR,
+        state: u32,
+    }
+
+    /// `WTSINFOW`
+    #[repr(C)]
+    struct WtsInfo {
+        state: u32,
+        session_id: DWORD,
+        counters: [DWORD; 6],
+        win_station_name: [u16; 32],
+        domain: [u16; 17],
+        user_name: [u16; 21],
+        connect_time: i64,
+        disconnect_time: i64,
+        last_input_time: i64,
+        logon_time: i64,
+        current_time: i64,
+    }
+
+    /// `WTS_CLIENT_ADDRESS`
+    #[repr(C)]
+    struct WtsClientAddress {
+        address_family: DWORD,
+        address: [u8; 20],
+    }
+
+    #[link(name = "wtsapi32")]
+    extern "system" {
+        fn WTSEnumerateSessionsW(
+            server: HANDLE,
+            reserved: DWORD,
+            version: DWORD,
+            sessions: *mut *mut WtsSessionInfo,
+            count: *mut DWORD,
+        ) -> BOOL;
+        fn WTSQuerySessionInformationW(
+            server: HANDLE,
+            session_id: DWORD,
+            class: u32,
+            buffer: *mut LPWSTR,
+            bytes: *mut DWORD,
+        ) -> BOOL;
+        fn WTSFreeMemory(memory: *mut c_void);
+    }
+
+    fn wide(chars: &[u16]) -> String {
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        String::from_utf16_lossy(&chars[..len])
+    }
+
+    /// Query a fixed-size session information structure
+    unsafe fn query<T>(session_id: DWORD, class: u32) -> Option<T> {
+        let mut buffer: LPWSTR = ptr::null_mut();
+        let mut bytes: DWORD = 0;
+        if WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            class,
+            &mut buffer,
+            &mut bytes,
+        ) == 0
+        {
+            return None;
+        }
+        let value = (bytes as usize >= std::mem::size_of::<T>())
+            .then(|| ptr::read_unaligned(buffer as *const T));
+        WTSFreeMemory(buffer as *mut c_void);
+        value
+    }
+
+    pub fn live_sessions() -> Result<Vec<SessionEvent>> {
+        let mut sessions = Vec::new();
+        // SAFETY: the session array is only read within the count returned
+        // by WTSEnumerateSessionsW and freed once.
+        unsafe {
+            let mut list: *mut WtsSessionInfo = ptr::null_mut();
+            let mut count: DWORD = 0;
+            if WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut list, &mut count) == 0 {
+                return Err(anyhow!(
+                    "WTSEnumerateSessions failed: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            for entry in std::slice::from_raw_parts(list, count as usize) {
+                let Some(info) = query::<WtsInfo>(entry.session_id, WTS_SESSION_INFO_CLASS) else {
+                    continue;
+                };
+                let user = wide(&info.user_name);
+                if user.is_empty() {
+                    continue;
+                }
+                let Some(login_time) = [info.logon_time, info.connect_time]
+                    .into_iter()
+                    .filter(|t| *t > 0)
+                    .find_map(|t| filetime_ticks_to_utc(t as u64))
+                else {
+                    debug!("No logon time for session {}", entry.session_id);
+                    continue;
+                };
+                let source_ip =
+                    query::<WtsClientAddress>(entry.session_id, WTS_CLIENT_ADDRESS_CLASS)
+                        .filter(|a| a.address_family == AF_INET)
+                        .map(|a| {
+                            Ipv4Addr::new(a.address[2], a.address[3], a.address[4], a.address[5])
+                                .to_string()
+                        });
+                let station = wide(&info.win_station_name);
+                let domain = wide(&info.domain);
+
+                sessions.push(SessionEvent {
+                    session_type: window_station_type(&station),
+                    username: qualified_user(Some(&domain), &user),
+                    source_ip,
+                    login_time,
+                    logout_time: None,
+                    session_id: entry.session_id.to_string(),
+                    is_active: true,
+                    source: "wts".to_string(),
+                });
+            }
+            WTSFreeMemory(list as *mut c_void);
+        }
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::evtx::XmlElement;
+    use crate::collectors::parsers::fixtures::{evtx_file, EvtxEvent, EvtxValue};
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    /// 2024-01-01T00:00:00Z as a FILETIME
+    const BASE_TIME: u64 = 133_485_408_000_000_000;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn wtmp(hour: u32, event: &str, user: Option<&str>, tty: &str, ip: Option<&str>) -> AuthEvent {
+        AuthEvent {
+            timestamp: at(hour),
+            event: event.to_string(),
+            user: user.map(str::to_string),
+            source_ip: ip.map(str::to_string),
+            host: None,
+            tty: Some(tty.to_string()),
+            success: true,
+            method: "wtmp".to_string(),
+            detail: None,
+            source: "wtmp".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sessions_from_wtmp() {
+        let sessions = sessions_from_wtmp(&[
+            wtmp(1, "login", Some("alice"), "tty1", None),
+            wtmp(2, "login", Some("mallory"), "pts/0", Some("203.0.113.9")),
+            wtmp(3, "logout", None, "pts/0", None),
+            wtmp(4, "boot", Some("reboot"), "~", None),
+            wtmp(5, "login", Some("bob"), "pts/1", None),
+        ]);
+
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].session_type, SessionType::Console);
+        // Still logged in at the reboot
+        assert_eq!(sessions[0].logout_time, Some(at(4)));
+        assert!(!sessions[0].is_active);
+
+        let mallory = &sessions[1];
+        assert_eq!(mallory.session_type, SessionType::Remote);
+        assert_eq!(mallory.source_ip.as_deref(), Some("203.0.113.9"));
+        assert_eq!(mallory.session_id, "pts/0");
+        assert_eq!(mallory.logout_time, Some(at(3)));
+
+        assert_eq!(sessions[2].session_type, SessionType::Terminal);
+        assert!(sessions[2].is_active);
+        assert!(sessions[2].logout_time.is_none());
+    }
+
+    fn session_manager_record(
+        hour: u64,
+        event_id: u32,
+        user: &str,
+        id: &str,
+        address: &str,
+    ) -> EventRecord {
+        let element = |name: &str, text: &str| XmlElement {
+            name: name.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        };
+        let mut system = element("System", "");
+        system
+            .children
+            .push(element("EventID", &event_id.to_string()));
+        let mut fields = element("EventXML", "");
+        fields.children = vec![
+            element("User", user),
+            element("SessionID", id),
+            element("Address", address),
+        ];
+        let mut user_data = element("UserData", "");
+        user_data.children.push(fields);
+        let mut event = element("Event", "");
+        event.children = vec![system, user_data];
+
+        EventRecord {
+            record_id: hour,
+            written_time: Some(at(hour as u32)),
+            event,
+        }
+    }
+
+    #[test]
+    fn test_sessions_from_event_logs() {
+        let temp = TempDir::new().unwrap();
+        fn security_event<'a>(
+            record_id: u64,
+            event_id: u16,
+            station: &'a str,
+            address: &'a str,
+        ) -> EvtxEvent<'a> {
+            EvtxEvent {
+                record_id,
+                written: BASE_TIME + record_id * 3600 * 10_000_000,
+                event_id,
+                data: vec![
+                    ("AccountName", EvtxValue::Text("admin")),
+                    ("AccountDomain", EvtxValue::Text("CORP")),
+                    ("SessionName", EvtxValue::Text(station)),
+                    ("ClientAddress", EvtxValue::Text(address)),
+                ],
+            }
+        }
+        let security = temp.path().join("Security.evtx");
+        fs::write(
+            &security,
+            evtx_file(&[
+                security_event(1, 4778, "RDP-Tcp#3", "198.51.100.7"),
+                security_event(2, 4779, "RDP-Tcp#3", "198.51.100.7"),
+                // Disconnected before the oldest record: no start time
+                security_event(3, 4779, "RDP-Tcp#1", "198.51.100.8"),
+                security_event(4, 4778, "Console", "LOCAL"),
+            ]),
+        )
+        .unwrap();
+
+        let sessions = sessions_from_event_logs(&[security]).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].username, r"CORP\admin");
+        assert_eq!(sessions[0].session_type, SessionType::Rdp);
+        assert_eq!(sessions[0].source_ip.as_deref(), Some("198.51.100.7"));
+        assert_eq!(sessions[0].login_time, at(1));
+        assert_eq!(sessions[0].logout_time, Some(at(2)));
+        assert_eq!(sessions[1].session_type, SessionType::Console);
+        assert!(sessions[1].source_ip.is_none());
+        assert!(sessions[1].is_active);
+
+        let sessions = sessions_from_session_manager_log(&[
+            session_manager_record(1, LSM_LOGON, r"CORP\admin", "2", "198.51.100.7"),
+            session_manager_record(2, LSM_DISCONNECT, r"CORP\admin", "2", "198.51.100.7"),
+            session_manager_record(3, LSM_RECONNECT, r"CORP\admin", "2", "198.51.100.9"),
+            session_manager_record(4, LSM_LOGON, r"CORP\user", "1", "LOCAL"),
+        ]);
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].logout_time, Some(at(2)));
+        assert_eq!(sessions[1].source_ip.as_deref(), Some("198.51.100.9"));
+        assert!(sessions[1].is_active);
+        assert_eq!(sessions[2].session_type, SessionType::Console);
+        assert_eq!(sessions[2].session_id, "1");
+
+        // Merged after the live sessions, replacing earlier event log sessions
+        let volatile_dir = temp.path().join("volatile");
+        fs::create_dir_all(&volatile_dir).unwrap();
+        let live = SessionEvent {
+            session_type: SessionType::Rdp,
+            username: r"CORP\admin".to_string(),
+            source_ip: None,
+            login_time: at(0),
+            logout_time: None,
+            session_id: "2".to_string(),
+            is_active: true,
+            source: "wts".to_string(),
+        };
+        fs::write(
+            volatile_dir.join(USER_SESSIONS_FILE),
+            serde_json::to_string(&[live]).unwrap(),
+        )
+        .unwrap();
+        let logs = [temp.path().join("Security.evtx")];
+        write_event_log_sessions(&volatile_dir, &logs).unwrap();
+        let path = write_event_log_sessions(&volatile_dir, &logs).unwrap();
+        let merged: Vec<SessionEvent> =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(
+            merged.iter().map(|s| s.source.as_str()).collect::<Vec<_>>(),
+            ["wts", "Security.evtx", "Security.evtx"]
+        );
+    }
+}
@@ -0,0 +1,868 @@
+//! Windows network configuration.
+//!
+//! Interface statistics alone say little about where a host's traffic is
+//! sent. This module captures the settings an attacker changes to intercept
+//! or block it:
+//!
+//! - firewall rules, from `netsh advfirewall firewall show rule name=all verbose`
+//! - the hosts file, with its non-default entries
+//! - the WinHTTP proxy (`netsh winhttp show proxy`) and every loaded user's
+//!   WinINET proxy and PAC URL (`Internet Settings` under `HKEY_USERS`)
+//! - DNS servers per interface and persistent routes, from the `Tcpip`
+//!   service parameters
+//!
+//! Registry keys are read with `reg query` so that every source is parsed
+//! from text. `netsh` output is localized; rules are only parsed from an
+//! English-language system, the raw output is not kept.
+//!
+//! `findings` flags hosts entries for security vendor or Windows Update
+//! domains, proxies and PAC files served from the local host, and, when
+//! `expected_dns_servers` is set in `global_options` (a comma-separated list
+//! of addresses or CIDR ranges), DNS servers outside those ranges.
+//!
+//! Results are written to `volatile/windows_network_config.json`.
+
+// The parsers are only reachable from the Windows collector
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::RwLock;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// File name used for the network configuration
+pub const WINDOWS_NETWORK_CONFIG_FILE: &str = "windows_network_config.json";
+
+/// Whether the network configuration is collected on this platform
+pub const SUPPORTED: bool = cfg!(target_os = "windows");
+
+/// Domain fragments of security vendors and Windows Update. A hosts entry
+/// for one of them redirects or blocks updates and telemetry.
+const SECURITY_VENDOR_DOMAINS: &[&str] = &[
+    "windowsupdate",
+    "update.microsoft.com",
+    "definitionupdates.microsoft.com",
+    "wdcp.microsoft.com",
+    "smartscreen",
+    "defender",
+    "symantec",
+    "norton",
+    "mcafee",
+    "trellix",
+    "kaspersky",
+    "eset.com",
+    "sophos",
+    "crowdstrike",
+    "sentinelone",
+    "trendmicro",
+    "bitdefender",
+    "malwarebytes",
+    "avast",
+    "avg.com",
+    "virustotal",
+    "carbonblack",
+    "cylance",
+    "f-secure",
+    "paloaltonetworks",
+];
+
+lazy_static! {
+    /// DNS server ranges from `expected_dns_servers`; empty disables the check
+    static ref EXPECTED_DNS_SERVERS: RwLock<Vec<IpRange>> = RwLock::new(Vec::new());
+}
+
+/// An address range in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Parse `10.0.0.0/8`, `fd00::/8` or a bare address
+    pub fn parse(value: &str) -> Option<Self> {
+        let (address, prefix) = match value.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+            None => (value.trim(), None),
+        };
+        let network: IpAddr = address.parse().ok()?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix.unwrap_or(max);
+        (prefix_len <= max).then_some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Apply `expected_dns_servers` from `global_options`, clearing the baseline
+/// when it is missing
+pub fn configure(global_options: &HashMap<String, String>) {
+    let value = global_options
+        .get("expected_dns_servers")
+        .map(String::as_str)
+        .unwrap_or_default();
+    let mut ranges = Vec::new();
+    for entry in value.split(',').filter(|e| !e.trim().is_empty()) {
+        match IpRange::parse(entry) {
+            Some(range) => ranges.push(range),
+            None => warn!("Invalid expected_dns_servers entry '{}'; ignored", entry),
+        }
+    }
+    if let Ok(mut expected) = EXPECTED_DNS_SERVERS.write() {
+        *expected = ranges;
+    }
+}
+
+/// A Windows Firewall rule
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FirewallRule {
+    pub name: String,
+    pub enabled: bool,
+    /// `In` or `Out`
+    pub direction: String,
+    pub profiles: String,
+    pub grouping: Option<String>,
+    pub local_ip: String,
+    pub remote_ip: String,
+    pub protocol: String,
+    pub local_port: Option<String>,
+    pub remote_port: Option<String>,
+    pub program: Option<String>,
+    pub service: Option<String>,
+    /// `Allow`, `Block` or `Bypass`
+    pub action: String,
+}
+
+/// A hosts file mapping
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostsEntry {
+    pub line: usize,
+    pub address: String,
+    pub hostnames: Vec<String>,
+    /// A `localhost` mapping shipped with Windows
+    pub default: bool,
+}
+
+/// WinHTTP or per-user WinINET proxy settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProxySettings {
+    /// `winhttp` or the SID of the user the settings belong to
+    pub scope: String,
+    pub enabled: bool,
+    /// Proxy server, possibly per scheme (`http=host:port;https=...`)
+    pub server: Option<String>,
+    pub bypass: Option<String>,
+    /// Proxy auto-config URL (`AutoConfigURL`)
+    pub pac_url: Option<String>,
+}
+
+/// DNS servers configured for an interface
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InterfaceDns {
+    /// Interface GUID
+    pub interface: String,
+    /// Statically configured servers (`NameServer`)
+    pub name_servers: Vec<String>,
+    /// Servers assigned by DHCP (`DhcpNameServer`)
+    pub dhcp_name_servers: Vec<String>,
+}
+
+/// A route added with `route -p`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PersistentRoute {
+    pub destination: String,
+    pub netmask: String,
+    pub gateway: String,
+    /// `None` when the route uses the automatic metric
+    pub metric: Option<u32>,
+}
+
+/// A suspicious network setting
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NetworkFinding {
+    /// `hosts`, `proxy` or `dns`
+    pub category: String,
+    pub detail: String,
+}
+
+/// Contents of `windows_network_config.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct WindowsNetworkConfig {
+    pub firewall_rules: Vec<FirewallRule>,
+    pub hosts_entries: Vec<HostsEntry>,
+    pub winhttp_proxy: Option<ProxySettings>,
+    pub user_proxies: Vec<ProxySettings>,
+    pub dns_servers: Vec<InterfaceDns>,
+    pub persistent_routes: Vec<PersistentRoute>,
+    pub findings: Vec<NetworkFinding>,
+    /// Sources that could not be read or parsed
+    pub notes: Vec<String>,
+}
+
+/// A registry key and its values from `reg query` output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegistryKey {
+    pub path: String,
+    /// Value name to data; `REG_DWORD` data is kept as printed (`0x1`)
+    pub values: BTreeMap<String, String>,
+}
+
+/// Parse `netsh advfirewall firewall show rule name=all verbose` output
+pub fn parse_firewall_rules(output: &str) -> Vec<FirewallRule> {
+    let mut rules = Vec::new();
+    let mut current: Option<FirewallRule> = None;
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let optional = || (!value.is_empty()).then(|| value.to_string());
+
+        if key.trim() == "Rule Name" {
+            rules.extend(current.take());
+            current = Some(FirewallRule {
+                name: value.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(rule) = current.as_mut() else {
+            continue;
+        };
+        match key.trim() {
+            "Enabled" => rule.enabled = value.eq_ignore_ascii_case("Yes"),
+            "Direction" => rule.direction = value.to_string(),
+            "Profiles" => rule.profiles = value.to_string(),
+            "Grouping" => rule.grouping = optional(),
+            "LocalIP" => rule.local_ip = value.to_string(),
+            "RemoteIP" => rule.remote_ip = value.to_string(),
+            "Protocol" => rule.protocol = value.to_string(),
+            "LocalPort" => rule.local_port = optional(),
+            "RemotePort" => rule.remote_port = optional(),
+            "Program" => rule.program = optional(),
+            "Service" => rule.service = optional(),
+            "Action" => rule.action = value.to_string(),
+            _ => {}
+        }
+    }
+    rules.extend(current);
+    rules
+}
+
+/// Parse a hosts file; comments and blank lines are skipped
+pub fn parse_hosts(contents: &str) -> Vec<HostsEntry> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let address = fields.next()?.to_string();
+            let hostnames: Vec<String> = fields.map(str::to_string).collect();
+            if hostnames.is_empty() {
+                return None;
+            }
+            let default = hostnames
+                .iter()
+                .all(|h| h.eq_ignore_ascii_case("localhost"))
+                && (address == "127.0.0.1" || address == "::1");
+            Some(HostsEntry {
+                line: index + 1,
+                address,
+                hostnames,
+                default,
+            })
+        })
+        .collect()
+}
+
+/// Parse `netsh winhttp show proxy` output
+pub fn parse_winhttp_proxy(output: &str) -> ProxySettings {
+    let mut settings = ProxySettings {
+        scope: "winhttp".to_string(),
+        ..Default::default()
+    };
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(" :") else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() || value == "(none)" {
+            continue;
+        }
+        match key.trim() {
+            "Proxy Server(s)" => {
+                settings.enabled = true;
+                settings.server = Some(value.to_string());
+            }
+            "Bypass List" => settings.bypass = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    settings
+}
+
+/// Parse `reg query` output into keys and their values
+pub fn parse_reg_query(output: &str) -> Vec<RegistryKey> {
+    let mut keys = Vec::new();
+    let mut current: Option<RegistryKey> = None;
+
+    for line in output.lines() {
+        if line.starts_with("HKEY_") {
+            keys.extend(current.take());
+            current = Some(RegistryKey {
+                path: line.trim().to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(key) = current.as_mut() else {
+            continue;
+        };
+        // `    <name>    REG_<type>    <data>`; names may contain spaces
+        let Some((name, rest)) = line.trim_start().split_once("    REG_") else {
+            continue;
+        };
+        let data = rest
+            .split_once("    ")
+            .map(|(_, data)| data.trim_end())
+            .unwrap_or_default();
+        key.values.insert(name.to_string(), data.to_string());
+    }
+    keys.extend(current);
+    keys
+}
+
+/// Whether a `REG_DWORD` value as printed by `reg query` is non-zero
+fn dword_set(value: Option<&String>) -> bool {
+    value
+        .and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        .is_some_and(|v| v != 0)
+}
+
+/// WinINET proxy settings from a user's `Internet Settings` key
+pub fn user_proxy_settings(sid: &str, key: &RegistryKey) -> ProxySettings {
+    let value = |name: &str| key.values.get(name).filter(|v| !v.is_empty()).cloned();
+    ProxySettings {
+        scope: sid.to_string(),
+        enabled: dword_set(key.values.get("ProxyEnable")),
+        server: value("ProxyServer"),
+        bypass: value("ProxyOverride"),
+        pac_url: value("AutoConfigURL"),
+    }
+}
+
+/// Split a `NameServer` value, which uses commas or spaces
+fn split_name_servers(value: Option<&String>) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split([',', ' '])
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// DNS servers from the `Tcpip\Parameters\Interfaces\{GUID}` keys
+pub fn interface_dns_servers(keys: &[RegistryKey]) -> Vec<InterfaceDns> {
+    keys.iter()
+        .filter_map(|key| {
+            let (parent, interface) = key.path.rsplit_once('\\')?;
+            if !parent.ends_with("\\Interfaces") {
+                return None;
+            }
+            let dns = InterfaceDns {
+                interface: interface.to_string(),
+                name_servers: split_name_servers(key.values.get("NameServer")),
+                dhcp_name_servers: split_name_servers(key.values.get("DhcpNameServer")),
+            };
+            (!dns.name_servers.is_empty() || !dns.dhcp_name_servers.is_empty()).then_some(dns)
+        })
+        .collect()
+}
+
+/// Routes from the `Tcpip\Parameters\PersistentRoutes` key, whose value
+/// names are `destination,netmask,gateway,metric`
+pub fn persistent_routes(keys: &[RegistryKey]) -> Vec<PersistentRoute> {
+    keys.iter()
+        .filter(|key| key.path.ends_with("\\PersistentRoutes"))
+        .flat_map(|key| key.values.keys())
+        .filter_map(|name| {
+            let fields: Vec<&str> = name.split(',').collect();
+            if fields.len() != 4 {
+                return None;
+            }
+            Some(PersistentRoute {
+                destination: fields[0].to_string(),
+                netmask: fields[1].to_string(),
+                gateway: fields[2].to_string(),
+                metric: fields[3].parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Whether `host` names the local machine
+fn is_local_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback() || ip.is_unspecified())
+}
+
+/// Host part of a URL or `host:port`, e.g. `[::1]` from `http://[::1]:3128/`
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    if let Some(bracketed) = authority.strip_prefix('[') {
+        return bracketed.split(']').next().unwrap_or(bracketed);
+    }
+    authority.split(':').next().unwrap_or(authority)
+}
+
+/// Host part of a proxy entry such as `http=127.0.0.1:8888`
+fn proxy_host(entry: &str) -> &str {
+    url_host(entry.split_once('=').map_or(entry, |(_, server)| server))
+}
+
+/// Flag hijacked hosts entries, local proxies and unexpected DNS servers
+pub fn network_findings(
+    config: &WindowsNetworkConfig,
+    expected_dns: &[IpRange],
+) -> Vec<NetworkFinding> {
+    let mut findings = Vec::new();
+    let mut push = |category: &str, detail: String| {
+        findings.push(NetworkFinding {
+            category: category.to_string(),
+            detail,
+        })
+    };
+
+    for entry in config.hosts_entries.iter().filter(|e| !e.default) {
+        for hostname in &entry.hostnames {
+            let lower = hostname.to_ascii_lowercase();
+            if SECURITY_VENDOR_DOMAINS.iter().any(|d| lower.contains(d)) {
+                push(
+                    "hosts",
+                    format!(
+                        "Line {}: {} is redirected to {}",
+                        entry.line, hostname, entry.address
+                    ),
+                );
+            }
+        }
+    }
+
+    for proxy in config.winhttp_proxy.iter().chain(&config.user_proxies) {
+        if let Some(server) = proxy.server.as_deref().filter(|_| proxy.enabled) {
+            if server
+                .split(';')
+                .any(|entry| is_local_host(proxy_host(entry)))
+            {
+                push(
+                    "proxy",
+                    format!("{} proxy {} points at the local host", proxy.scope, server),
+                );
+            }
+        }
+        if let Some(pac_url) = &proxy.pac_url {
+            if pac_url.to_ascii_lowercase().starts_with("file:") {
+                push(
+                    "proxy",
+                    format!("{} PAC file is read from disk: {}", proxy.scope, pac_url),
+                );
+            } else if is_local_host(url_host(pac_url)) {
+                push(
+                    "proxy",
+                    format!("{} PAC URL {} is served locally", proxy.scope, pac_url),
+                );
+            }
+        }
+    }
+
+    if !expected_dns.is_empty() {
+        for interface in &config.dns_servers {
+            for server in interface
+                .name_servers
+                .iter()
+                .chain(&interface.dhcp_name_servers)
+            {
+                let expected = server
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| expected_dns.iter().any(|range| range.contains(ip)));
+                if !expected {
+                    push(
+                        "dns",
+                        format!(
+                            "Interface {} uses unexpected DNS server {}",
+                            interface.interface, server
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+/// Collect the network configuration of this host.
+///
+/// Sources that cannot be queried are recorded in `notes`; the rest of the
+/// configuration is still returned.
+pub fn collect_network_config() -> Result<WindowsNetworkConfig> {
+    #[cfg(target_os = "windows")]
+    let mut config = windows_impl::collect();
+    #[cfg(not(target_os = "windows"))]
+    let mut config = {
+        log::debug!("Windows network configuration is only collected on Windows");
+        WindowsNetworkConfig::default()
+    };
+
+    let expected = EXPECTED_DNS_SERVERS
+        .read()
+        .map(|ranges| ranges.clone())
+        .unwrap_or_default();
+    config.findings = network_findings(&config, &expected);
+    Ok(config)
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::*;
+    use crate::collectors::footprint::run_command;
+
+    const TCPIP_PARAMETERS: &str = r"HKLM\SYSTEM\CurrentControlSet\Services\Tcpip\Parameters";
+    const INTERNET_SETTINGS: &str = r"Software\Microsoft\Windows\CurrentVersion\Internet Settings";
+
+    /// Run a command and return its standard output, noting failures
+    fn capture(program: &str, args: &[&str], notes: &mut Vec<String>) -> Option<String> {
+        let command_line = format!("{} {}", program, args.join(" "));
+        match run_command(Command::new(program).args(args)) {
+            Ok(output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).to_string())
+            }
+            Ok(output) => {
+                notes.push(format!(
+                    "{} exited with {}: {}",
+                    command_line,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+                None
+            }
+            Err(e) => {
+                notes.push(format!("Failed to run {}: {}", command_line, e));
+                None
+            }
+        }
+    }
+
+    pub fn collect() -> WindowsNetworkConfig {
+        let mut config = WindowsNetworkConfig::default();
+        let mut notes = Vec::new();
+
+        if let Some(output) = capture(
+            "netsh",
+            &[
+                "advfirewall",
+                "firewall",
+                "show",
+                "rule",
+                "name=all",
+                "verbose",
+            ],
+            &mut notes,
+        ) {
+            config.firewall_rules = parse_firewall_rules(&output);
+            if config.firewall_rules.is_empty() && !output.trim().is_empty() {
+                notes.push("No firewall rules parsed; netsh output may be localized".into());
+            }
+        }
+
+        let hosts_path = PathBuf::from(env::var("SystemRoot").unwrap_or("C:\\Windows".into()))
+            .join(r"System32\drivers\etc\hosts");
+        match fs::read(&hosts_path) {
+            Ok(bytes) => config.hosts_entries = parse_hosts(&String::from_utf8_lossy(&bytes)),
+            Err(e) => notes.push(format!("Failed to read {}: {}", hosts_path.display(), e)),
+        }
+
+        if let Some(output) = capture("netsh", &["winhttp", "show", "proxy"], &mut notes) {
+            config.winhttp_proxy = Some(parse_winhttp_proxy(&output));
+        }
+
+        // Only the hives of logged-on users are loaded under HKEY_USERS
+        if let Some(output) = capture("reg", &["query", "HKU"], &mut notes) {
+            let sids: Vec<String> = output
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix(r"HKEY_USERS\"))
+                .filter(|sid| sid.starts_with("S-") && !sid.ends_with("_Classes"))
+                .map(str::to_string)
+                .collect();
+            for sid in sids {
+                let key = format!(r"HKU\{}\{}", sid, INTERNET_SETTINGS);
+                let mut ignored = Vec::new();
+                if let Some(output) = capture("reg", &["query", &key], &mut ignored) {
+                    if let Some(settings) = parse_reg_query(&output).first() {
+                        config
+                            .user_proxies
+                            .push(user_proxy_settings(&sid, settings));
+                    }
+                }
+            }
+        }
+
+        let interfaces = format!(r"{}\Interfaces", TCPIP_PARAMETERS);
+        if let Some(output) = capture("reg", &["query", &interfaces, "/s"], &mut notes) {
+            config.dns_servers = interface_dns_servers(&parse_reg_query(&output));
+        }
+
+        // The key is absent until a persistent route is added
+        let routes = format!(r"{}\PersistentRoutes", TCPIP_PARAMETERS);
+        let mut ignored = Vec::new();
+        if let Some(output) = capture("reg", &["query", &routes], &mut ignored) {
+            config.persistent_routes = persistent_routes(&parse_reg_query(&output));
+        }
+
+        config.notes = notes;
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREWALL_RULES: &str =
+        include_str!("../../../tests/fixtures/volatile/netsh_firewall_rules.txt");
+    const WINHTTP_PROXY: &str =
+        include_str!("../../../tests/fixtures/volatile/netsh_winhttp_proxy.txt");
+    const HOSTS: &str = include_str!("../../../tests/fixtures/volatile/hosts");
+    const INTERNET_SETTINGS: &str =
+        include_str!("../../../tests/fixtures/volatile/reg_query_internet_settings.txt");
+    const TCPIP: &str = include_str!("../../../tests/fixtures/volatile/reg_query_tcpip.txt");
+
+    #[test]
+    fn test_parse_firewall_rules() {
+        let rules = parse_firewall_rules(FIREWALL_RULES);
+        assert_eq!(rules.len(), 3);
+
+        assert_eq!(rules[0].name, "Core Networking - Teredo (UDP-In)");
+        assert!(rules[0].enabled);
+        assert_eq!(rules[0].direction, "In");
+        assert_eq!(rules[0].grouping.as_deref(), Some("Core Networking"));
+        assert_eq!(rules[0].local_port.as_deref(), Some("Teredo"));
+        assert_eq!(
+            rules[0].program.as_deref(),
+            Some(r"%SystemRoot%\system32\svchost.exe")
+        );
+        assert_eq!(rules[0].service.as_deref(), Some("iphlpsvc"));
+        assert_eq!(rules[0].action, "Allow");
+
+        assert_eq!(rules[1].grouping, None);
+        assert_eq!(rules[1].remote_ip, "203.0.113.7");
+        assert_eq!(rules[1].remote_port.as_deref(), Some("443"));
+        assert_eq!(
+            rules[1].program.as_deref(),
+            Some(r"C:\Users\Public\updater.exe")
+        );
+
+        assert!(!rules[2].enabled);
+        assert_eq!(rules[2].action, "Block");
+        assert_eq!(rules[2].program, None);
+    }
+
+    #[test]
+    fn test_parse_hosts() {
+        let entries = parse_hosts(HOSTS);
+        assert_eq!(entries.len(), 5);
+        assert!(entries[0].default && entries[1].default);
+
+        assert_eq!(entries[2].line, 11);
+        assert_eq!(entries[2].address, "10.0.0.5");
+        assert_eq!(
+            entries[2].hostnames,
+            vec!["intranet.corp.example", "intranet"]
+        );
+        assert!(!entries[2].default);
+
+        assert_eq!(
+            entries[4].hostnames,
+            vec!["definitionupdates.microsoft.com", "wdcp.microsoft.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_winhttp_proxy() {
+        let proxy = parse_winhttp_proxy(WINHTTP_PROXY);
+        assert_eq!(proxy.scope, "winhttp");
+        assert!(proxy.enabled);
+        assert_eq!(proxy.server.as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(proxy.bypass.as_deref(), Some("<local>;*.corp.example"));
+
+        let direct = parse_winhttp_proxy(
+            "\r\nCurrent WinHTTP proxy settings:\r\n\r\n    Direct access (no proxy server).\r\n",
+        );
+        assert!(!direct.enabled);
+        assert_eq!(direct.server, None);
+    }
+
+    #[test]
+    fn test_parse_reg_query_user_proxy() {
+        let keys = parse_reg_query(INTERNET_SETTINGS);
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].path.ends_with(r"\Internet Settings"));
+        assert_eq!(keys[0].values.get("ProxyEnable").unwrap(), "0x1");
+
+        let sid = "S-1-5-21-1111111111-2222222222-3333333333-1001";
+        let proxy = user_proxy_settings(sid, &keys[0]);
+        assert_eq!(proxy.scope, sid);
+        assert!(proxy.enabled);
+        assert_eq!(
+            proxy.server.as_deref(),
+            Some("http=127.0.0.1:8888;https=127.0.0.1:8888")
+        );
+        assert_eq!(proxy.bypass.as_deref(), Some("<local>"));
+        assert_eq!(
+            proxy.pac_url.as_deref(),
+            Some("file://C:/Users/Public/proxy.pac")
+        );
+    }
+
+    #[test]
+    fn test_parse_reg_query_tcpip() {
+        let keys = parse_reg_query(TCPIP);
+        let dns = interface_dns_servers(&keys);
+        assert_eq!(dns.len(), 2);
+        assert_eq!(dns[0].interface, "{0a1b2c3d-0000-1111-2222-333344445555}");
+        assert!(dns[0].name_servers.is_empty());
+        assert_eq!(dns[0].dhcp_name_servers, vec!["10.0.0.2", "10.0.0.3"]);
+        assert_eq!(dns[1].name_servers, vec!["198.51.100.53", "10.0.0.2"]);
+
+        let routes = persistent_routes(&keys);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].destination, "0.0.0.0");
+        assert_eq!(routes[0].gateway, "192.0.2.1");
+        assert_eq!(routes[0].metric, Some(1));
+        assert_eq!(routes[1].netmask, "255.255.0.0");
+        assert_eq!(routes[1].metric, None);
+    }
+
+    #[test]
+    fn test_ip_range() {
+        let range = IpRange::parse("10.0.0.0/8").unwrap();
+        assert!(range.contains("10.20.30.40".parse().unwrap()));
+        assert!(!range.contains("11.0.0.1".parse().unwrap()));
+        assert!(!range.contains("::1".parse().unwrap()));
+
+        let single = IpRange::parse("192.0.2.53").unwrap();
+        assert!(single.contains("192.0.2.53".parse().unwrap()));
+        assert!(!single.contains("192.0.2.54".parse().unwrap()));
+
+        assert!(IpRange::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("203.0.113.1".parse().unwrap()));
+        assert!(IpRange::parse("fd00::/8")
+            .unwrap()
+            .contains("fd12::1".parse().unwrap()));
+        assert!(IpRange::parse("10.0.0.0/33").is_none());
+        assert!(IpRange::parse("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn test_network_findings() {
+        let keys = parse_reg_query(TCPIP);
+        let config = WindowsNetworkConfig {
+            hosts_entries: parse_hosts(HOSTS),
+            winhttp_proxy: Some(parse_winhttp_proxy(WINHTTP_PROXY)),
+            user_proxies: vec![user_proxy_settings(
+                "S-1-5-21-1001",
+                &parse_reg_query(INTERNET_SETTINGS)[0],
+            )],
+            dns_servers: interface_dns_servers(&keys),
+            ..Default::default()
+        };
+
+        let expected = [IpRange::parse("10.0.0.0/8").unwrap()];
+        let findings = network_findings(&config, &expected);
+        let details: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|f| (f.category.as_str(), f.detail.as_str()))
+            .collect();
+        assert_eq!(
+            details,
+            vec![
+                ("hosts", "Line 12: update.crowdstrike.com is redirected to 0.0.0.0"),
+                (
+                    "hosts",
+                    "Line 13: definitionupdates.microsoft.com is redirected to 127.0.0.1"
+                ),
+                ("hosts", "Line 13: wdcp.microsoft.com is redirected to 127.0.0.1"),
+                ("proxy", "winhttp proxy 127.0.0.1:8080 points at the local host"),
+                (
+                    "proxy",
+                    "S-1-5-21-1001 proxy http=127.0.0.1:8888;https=127.0.0.1:8888 points at the local host"
+                ),
+                (
+                    "proxy",
+                    "S-1-5-21-1001 PAC file is read from disk: file://C:/Users/Public/proxy.pac"
+                ),
+                (
+                    "dns",
+                    "Interface {9f8e7d6c-aaaa-bbbb-cccc-ddddeeeeffff} uses unexpected DNS server 198.51.100.53"
+                ),
+            ]
+        );
+
+        // Without expected ranges, DNS servers are not judged
+        assert!(network_findings(&config, &[])
+            .iter()
+            .all(|f| f.category != "dns"));
+    }
+
+    #[test]
+    fn test_local_proxy_hosts() {
+        assert!(is_local_host(proxy_host("localhost:8080")));
+        assert!(is_local_host(proxy_host("https=[::1]:3128")));
+        assert!(is_local_host(proxy_host("http://127.0.0.2:80")));
+        assert!(!is_local_host(proxy_host("proxy.corp.example:8080")));
+        assert!(is_local_host(url_host(
+            "http://127.0.0.1:9000/proxy.pac?a=b"
+        )));
+        assert!(is_local_host(url_host("http://localhost/proxy.pac")));
+        assert!(!is_local_host(url_host(
+            "https://pac.corp.example/wpad.dat"
+        )));
+    }
+}
@@ -0,0 +1,440 @@
+//! Continuous re-collection of changed artifacts (`--watch`).
+//!
+//! After the initial collection, every artifact source path is watched with
+//! `notify::RecommendedWatcher`. When a change event fires, only the affected
+//! artifacts are collected again into the same output directory (earlier
+//! copies are kept, the new copy gets a numeric suffix), and one JSONL record
+//! per re-collection is appended to [`WATCH_EVENTS_FILE`].
+//!
+//! Events are debounced so a burst of writes to one file produces a single
+//! re-collection. Changes under the output directory itself are ignored so
+//! the collector never re-triggers on its own writes.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::collector::{self, is_special_artifact};
+use crate::collectors::footprint;
+use crate::collectors::platforms::network_mounts::resolve_network_path;
+use crate::config::Artifact;
+use crate::models::ArtifactMetadata;
+
+/// Name of the JSONL event log written into the output directory
+pub const WATCH_EVENTS_FILE: &str = "watch_events.jsonl";
+
+/// Quiet period after the first event before re-collecting
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// One re-collection, written as a single line of JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchEvent {
+    pub timestamp: String,
+    pub artifact: String,
+    /// Change kinds seen in the batch ("create", "modify", "remove")
+    pub event_kinds: Vec<String>,
+    pub changed_paths: Vec<String>,
+    /// "collected", "failed" or "source_removed"
+    pub status: String,
+    /// Collected files, relative to the output directory
+    pub collected_files: Vec<String>,
+    pub error: Option<String>,
+    /// Whether the collected files were uploaded (`--stream`)
+    pub uploaded: bool,
+}
+
+/// A path handed to the watcher
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WatchTarget {
+    pub path: PathBuf,
+    pub recursive: bool,
+}
+
+/// Result of re-collecting one artifact, passed to the caller's callback
+pub struct Recollection<'a> {
+    pub artifact: &'a Artifact,
+    /// Metadata keyed the same way as the initial collection
+    pub metadata: Vec<(String, ArtifactMetadata)>,
+    /// Absolute paths of the collected files
+    pub files: Vec<PathBuf>,
+}
+
+/// Changes accumulated for one artifact during a debounce window
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PendingChange {
+    kinds: Vec<String>,
+    paths: Vec<PathBuf>,
+}
+
+/// Source path as it will be opened, after network mount mapping
+fn local_source(artifact: &Artifact) -> Option<PathBuf> {
+    if artifact.source_path.is_empty() || is_special_artifact(&artifact.artifact_type) {
+        return None;
+    }
+    let source = PathBuf::from(&artifact.source_path);
+    Some(resolve_network_path(&source).unwrap_or(source))
+}
+
+/// What to watch for `artifact`, if anything.
+///
+/// Directories are watched recursively. Files are watched through their
+/// parent directory so replace-by-rename saves are still seen.
+pub fn watch_target(artifact: &Artifact) -> Option<WatchTarget> {
+    let source = local_source(artifact)?;
+
+    if source.is_dir() {
+        return Some(WatchTarget {
+            path: source,
+            recursive: true,
+        });
+    }
+
+    let parent = source.parent()?;
+    if parent.as_os_str().is_empty() || !parent.is_dir() {
+        return None;
+    }
+    Some(WatchTarget {
+        path: parent.to_path_buf(),
+        recursive: false,
+    })
+}
+
+/// Indices of the artifacts whose source covers `changed`
+pub fn affected_artifacts(artifacts: &[Artifact], changed: &Path) -> Vec<usize> {
+    artifacts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, artifact)| {
+            let source = local_source(artifact)?;
+            changed.starts_with(&source).then_some(i)
+        })
+        .collect()
+}
+
+fn event_kind_name(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("create"),
+        EventKind::Modify(_) => Some("modify"),
+        EventKind::Remove(_) => Some("remove"),
+        // Access events include our own reads during collection
+        _ => None,
+    }
+}
+
+/// Group a batch of events by affected artifact, skipping the output directory
+fn group_changes(
+    artifacts: &[Artifact],
+    events: &[Event],
+    output_dir: &Path,
+) -> BTreeMap<usize, PendingChange> {
+    let mut pending: BTreeMap<usize, PendingChange> = BTreeMap::new();
+
+    for event in events {
+        let kind = match event_kind_name(&event.kind) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        for path in event.paths.iter().filter(|p| !p.starts_with(output_dir)) {
+            for index in affected_artifacts(artifacts, path) {
+                let change = pending.entry(index).or_default();
+                if !change.kinds.iter().any(|k| k == kind) {
+                    change.kinds.push(kind.to_string());
+                }
+                if !change.paths.contains(path) {
+                    change.paths.push(path.clone());
+                }
+            }
+        }
+    }
+
+    pending
+}
+
+/// Append `event` as one line to the JSONL log at `path`
+pub fn append_event(path: &Path, event: &WatchEvent) -> Result<()> {
+    let existed = path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open {}", path.display()))?;
+    if !existed {
+        footprint::record_created_path(path);
+    }
+
+    let line = serde_json::to_string(event).context("Failed to serialize watch event")?;
+    writeln!(file, "{}", line).context(format!("Failed to write {}", path.display()))?;
+    file.flush()?;
+    Ok(())
+}
+
+fn relative_to(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Collect `artifact` again into its type directory under `artifact_dir`
+fn recollect<'a>(artifact: &'a Artifact, artifact_dir: &Path) -> Result<Recollection<'a>> {
+    let type_dir = artifact_dir.join(artifact.artifact_type.to_string());
+    footprint::create_dir_all_tracked(&type_dir)
+        .context("Failed to create artifact type directory")?;
+
+    let collected = collector::collect_artifacts(std::slice::from_ref(artifact), &type_dir)?;
+    if collected.is_empty() {
+        return Err(anyhow!(
+            "Artifact '{}' could not be collected",
+            artifact.name
+        ));
+    }
+
+    let mut metadata: Vec<(String, ArtifactMetadata)> = collected.into_iter().collect();
+    metadata.sort_by(|a, b| a.0.cmp(&b.0));
+    let files = metadata.iter().map(|(rel, _)| type_dir.join(rel)).collect();
+
+    Ok(Recollection {
+        artifact,
+        metadata,
+        files,
+    })
+}
+
+/// Watch the source paths of `artifacts` and re-collect them on change.
+///
+/// Runs until the process is killed. `on_recollected` is called after each
+/// successful re-collection (to refresh the summary, upload, ...) and
+/// returns whether the files were uploaded.
+pub fn watch_and_recollect<F>(
+    artifacts: &[Artifact],
+    artifact_dir: &Path,
+    mut on_recollected: F,
+) -> Result<()>
+where
+    F: FnMut(&Recollection) -> Result<bool>,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+        .context("Failed to create file watcher")?;
+
+    let mut targets: Vec<WatchTarget> = artifacts.iter().filter_map(watch_target).collect();
+    targets.sort();
+    targets.dedup();
+
+    let mut watched = 0;
+    for target in &targets {
+        let mode = if target.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        match watcher.watch(&target.path, mode) {
+            Ok(_) => watched += 1,
+            Err(e) => warn!("Cannot watch {}: {}", target.path.display(), e),
+        }
+    }
+    if watched == 0 {
+        return Err(anyhow!("None of the artifact source paths can be watched"));
+    }
+
+    let events_path = artifact_dir.join(WATCH_EVENTS_FILE);
+    info!(
+        "Watching {} path(s) for changes; re-collection events are written to {}",
+        watched,
+        events_path.display()
+    );
+
+    loop {
+        // Block for the first event, then gather the rest of the burst
+        let first = rx
+            .recv()
+            .map_err(|_| anyhow!("File watcher stopped unexpectedly"))?;
+        let mut batch = Vec::new();
+        let deadline = Instant::now() + DEBOUNCE;
+        let mut next = Some(first);
+        while let Some(result) = next.take() {
+            match result {
+                Ok(event) => batch.push(event),
+                Err(e) => warn!("File watcher error: {}", e),
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            next = rx.recv_timeout(remaining).ok();
+        }
+
+        for (index, change) in group_changes(artifacts, &batch, artifact_dir) {
+            let artifact = &artifacts[index];
+            debug!("Change detected for {}: {:?}", artifact.name, change.paths);
+
+            let mut event = WatchEvent {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                artifact: artifact.name.clone(),
+                event_kinds: change.kinds,
+                changed_paths: change
+                    .paths
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+                status: "collected".to_string(),
+                collected_files: Vec::new(),
+                error: None,
+                uploaded: false,
+            };
+
+            let source_gone = local_source(artifact).is_some_and(|s| !s.exists());
+            if source_gone {
+                info!("Source of {} was removed", artifact.name);
+                event.status = "source_removed".to_string();
+            } else {
+                match recollect(artifact, artifact_dir) {
+                    Ok(recollection) => {
+                        info!("Re-collected {}", artifact.name);
+                        event.collected_files = recollection
+                            .files
+                            .iter()
+                            .map(|f| relative_to(f, artifact_dir))
+                            .collect();
+                        event.uploaded = on_recollected(&recollection).unwrap_or_else(|e| {
+                            warn!("Post-collection step failed for {}: {}", artifact.name, e);
+                            false
+                        });
+                    }
+                    Err(e) => {
+                        warn!("Failed to re-collect {}: {}", artifact.name, e);
+                        event.status = "failed".to_string();
+                        event.error = Some(e.to_string());
+                    }
+                }
+            }
+
+            if let Err(e) = append_event(&events_path, &event) {
+                warn!("Failed to record watch event: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ArtifactType, LinuxArtifactType, WindowsArtifactType};
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn artifact(name: &str, source: &Path) -> Artifact {
+        Artifact {
+            name: name.to_string(),
+            artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+            source_path: source.to_string_lossy().to_string(),
+            destination_name: name.to_string(),
+            description: None,
+            required: false,
+            metadata: HashMap::new(),
+            regex: None,
+        }
+    }
+
+    #[test]
+    fn test_watch_target_for_file_and_dir() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("auth.log");
+        std::fs::write(&file, "x").unwrap();
+
+        assert_eq!(
+            watch_target(&artifact("file", &file)),
+            Some(WatchTarget {
+                path: temp.path().to_path_buf(),
+                recursive: false
+            })
+        );
+        assert_eq!(
+            watch_target(&artifact("dir", temp.path())),
+            Some(WatchTarget {
+                path: temp.path().to_path_buf(),
+                recursive: true
+            })
+        );
+
+        let mut mft = artifact("mft", Path::new(r"C:\$MFT"));
+        mft.artifact_type = ArtifactType::Windows(WindowsArtifactType::MFT);
+        assert_eq!(watch_target(&mft), None);
+    }
+
+    #[test]
+    fn test_affected_artifacts_matches_components() {
+        let artifacts = vec![
+            artifact("syslog", Path::new("/var/log/syslog")),
+            artifact("logs", Path::new("/var/log")),
+            artifact("passwd", Path::new("/etc/passwd")),
+        ];
+
+        assert_eq!(
+            affected_artifacts(&artifacts, Path::new("/var/log/syslog")),
+            vec![0, 1]
+        );
+        assert_eq!(
+            affected_artifacts(&artifacts, Path::new("/var/log/syslog.1")),
+            vec![1]
+        );
+        assert!(affected_artifacts(&artifacts, Path::new("/etc/passwd-")).is_empty());
+    }
+
+    #[test]
+    fn test_group_changes_debounces_and_skips_output() {
+        let artifacts = vec![artifact("logs", Path::new("/var/log"))];
+        let output = Path::new("/var/log/collection");
+        let events = vec![
+            Event::new(EventKind::Modify(ModifyKind::Any)).add_path("/var/log/auth.log".into()),
+            Event::new(EventKind::Modify(ModifyKind::Any)).add_path("/var/log/auth.log".into()),
+            Event::new(EventKind::Create(CreateKind::File)).add_path("/var/log/new.log".into()),
+            Event::new(EventKind::Access(AccessKind::Any)).add_path("/var/log/read.log".into()),
+            Event::new(EventKind::Create(CreateKind::File))
+                .add_path("/var/log/collection/fs/copy.log".into()),
+        ];
+
+        let pending = group_changes(&artifacts, &events, output);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[&0].kinds, vec!["modify", "create"]);
+        assert_eq!(
+            pending[&0].paths,
+            vec![
+                PathBuf::from("/var/log/auth.log"),
+                PathBuf::from("/var/log/new.log")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_event_writes_jsonl() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(WATCH_EVENTS_FILE);
+        let event = WatchEvent {
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+            artifact: "syslog".to_string(),
+            event_kinds: vec!["modify".to_string()],
+            changed_paths: vec!["/var/log/syslog".to_string()],
+            status: "collected".to_string(),
+            collected_files: vec!["logs/fs/var/log/syslog_1".to_string()],
+            error: None,
+            uploaded: false,
+        };
+
+        append_event(&path, &event).unwrap();
+        append_event(&path, &event).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed: WatchEvent = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed, event);
+    }
+}
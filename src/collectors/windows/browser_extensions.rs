@@ -0,0 +1,539 @@
+//! Installed Chrome, Edge and Firefox extensions of every user.
+//!
+//! Chromium browsers unpack each extension to
+//! `%LOCALAPPDATA%\<vendor>\User Data\<profile>\Extensions\<id>\<version>\`,
+//! Firefox keeps them as `.xpi` archives (or unpacked folders) in
+//! `%APPDATA%\Mozilla\Firefox\Profiles\<profile>\extensions\`. The
+//! `manifest.json` of each one is read for its name, version, permissions,
+//! content scripts and background scripts; `__MSG_*__` names are looked up
+//! in the extension's default locale.
+//!
+//! Extensions that can read every page (`<all_urls>`, `http://*/*` and
+//! similar host patterns, in permissions or content script matches) or
+//! watch browsing (`tabs`, `webRequest`) are flagged in `broad_permissions`,
+//! as credential-stealing extensions need exactly those.
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::collectors::footprint::create_dir_all_tracked;
+use crate::collectors::windows::powershell_history::user_profiles;
+use crate::config::resolve_all_variables;
+
+/// Report file name, in the output directory
+pub const BROWSER_EXTENSIONS_FILE: &str = "browser_extensions.json";
+
+/// Chromium browsers and their `User Data` folder relative to a user profile
+const CHROMIUM_BROWSERS: &[(&str, &[&str])] = &[
+    (
+        "Chrome",
+        &["AppData", "Local", "Google", "Chrome", "User Data"],
+    ),
+    (
+        "Edge",
+        &["AppData", "Local", "Microsoft", "Edge", "User Data"],
+    ),
+];
+
+/// Firefox profiles folder relative to a user profile
+const FIREFOX_PROFILES: &[&str] = &["AppData", "Roaming", "Mozilla", "Firefox", "Profiles"];
+
+/// Permissions and match patterns that give access to every site or to
+/// the user's browsing
+const BROAD_PERMISSIONS: &[&str] = &[
+    "<all_urls>",
+    "http://*/*",
+    "https://*/*",
+    "*://*/*",
+    "tabs",
+    "webRequest",
+];
+
+/// Largest manifest or locale file read from an extension
+const MAX_MANIFEST_BYTES: u64 = 4 * 1024 * 1024;
+
+/// A `content_scripts` entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContentScript {
+    pub matches: Vec<String>,
+    pub js: Vec<String>,
+}
+
+/// An installed extension
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BrowserExtension {
+    /// `Chrome`, `Edge` or `Firefox`
+    pub browser: String,
+    pub user: String,
+    /// Browser profile folder (`Default`, `Profile 1`, `xxxxxxxx.default-release`)
+    pub profile: String,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// `permissions` and `host_permissions`
+    pub permissions: Vec<String>,
+    pub content_scripts: Vec<ContentScript>,
+    /// Background scripts, page or service worker
+    pub background_scripts: Vec<String>,
+    /// Broad permissions and content script matches; empty when none
+    pub broad_permissions: Vec<String>,
+    /// Version folder, extension folder or `.xpi` the manifest was read from
+    pub path: String,
+}
+
+/// Enumerate the extensions of every user under `%SystemDrive%\Users` and
+/// write them to [`BROWSER_EXTENSIONS_FILE`] in `output_dir`
+pub fn enumerate_extensions(output_dir: &Path) -> Result<Vec<BrowserExtension>> {
+    let users_dir = PathBuf::from(resolve_all_variables(r"%SystemDrive%\Users"));
+    enumerate_from(&users_dir, output_dir)
+}
+
+/// Enumerate the extensions of every profile under `users_dir` and write
+/// them to [`BROWSER_EXTENSIONS_FILE`] in `output_dir`
+pub fn enumerate_from(users_dir: &Path, output_dir: &Path) -> Result<Vec<BrowserExtension>> {
+    info!(
+        "Enumerating browser extensions under {}",
+        users_dir.display()
+    );
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut extensions = Vec::new();
+    for profile in user_profiles(users_dir)? {
+        let user = profile
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for (browser, user_data) in CHROMIUM_BROWSERS {
+            let user_data = user_data
+                .iter()
+                .fold(profile.clone(), |path, part| path.join(part));
+            extensions.extend(chromium_extensions(browser, &user, &user_data));
+        }
+        let firefox = FIREFOX_PROFILES
+            .iter()
+            .fold(profile.clone(), |path, part| path.join(part));
+        extensions.extend(firefox_extensions(&user, &firefox));
+    }
+
+    let flagged = extensions
+        .iter()
+        .filter(|e| !e.broad_permissions.is_empty())
+        .count();
+    info!("Found {} browser extensions", extensions.len());
+    if flagged > 0 {
+        warn!("{} browser extensions have broad permissions", flagged);
+    }
+
+    let path = output_dir.join(BROWSER_EXTENSIONS_FILE);
+    let json = serde_json::to_string_pretty(&extensions)
+        .context("Failed to serialize browser extensions")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+    Ok(extensions)
+}
+
+/// Extensions of every profile in a Chromium `User Data` folder; of the
+/// version folders an update leaves behind, the highest version is used
+fn chromium_extensions(browser: &str, user: &str, user_data: &Path) -> Vec<BrowserExtension> {
+    let mut extensions = Vec::new();
+    for profile in subdirectories(user_data) {
+        let profile_name = file_name(&profile);
+        for extension_dir in subdirectories(&profile.join("Extensions")) {
+            let id = file_name(&extension_dir);
+            let Some(version_dir) = subdirectories(&extension_dir)
+                .into_iter()
+                .filter(|dir| dir.join("manifest.json").is_file())
+                .max_by_key(|dir| version_key(&file_name(dir)))
+            else {
+                continue;
+            };
+            let read = |name: &str| read_limited(&version_dir.join(name));
+            match parse_extension(read, &id) {
+                Ok(manifest) => extensions.push(manifest.into_extension(
+                    browser,
+                    user,
+                    &profile_name,
+                    &version_dir,
+                )),
+                Err(e) => debug!("Skipping {}: {:#}", version_dir.display(), e),
+            }
+        }
+    }
+    extensions
+}
+
+/// Extensions in every Firefox profile, packed (`.xpi`) or unpacked
+fn firefox_extensions(user: &str, profiles: &Path) -> Vec<BrowserExtension> {
+    let mut extensions = Vec::new();
+    for profile in subdirectories(profiles) {
+        let profile_name = file_name(&profile);
+        let Ok(entries) = fs::read_dir(profile.join("extensions")) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        paths.sort();
+
+        for path in paths {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let parsed = if path.is_dir() {
+                parse_extension(|name| read_limited(&path.join(name)), &file_name(&path))
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xpi"))
+            {
+                fs::read(&path)
+                    .context("Failed to read XPI")
+                    .and_then(|data| {
+                        zip::ZipArchive::new(Cursor::new(data)).context("XPI is not a ZIP archive")
+                    })
+                    .and_then(|archive| {
+                        let archive = std::cell::RefCell::new(archive);
+                        parse_extension(
+                            |name| read_zip_entry(&mut archive.borrow_mut(), name),
+                            &stem,
+                        )
+                    })
+            } else {
+                continue;
+            };
+            match parsed {
+                Ok(manifest) => {
+                    extensions.push(manifest.into_extension("Firefox", user, &profile_name, &path))
+                }
+                Err(e) => debug!("Skipping {}: {:#}", path.display(), e),
+            }
+        }
+    }
+    extensions
+}
+
+/// Fields read from `manifest.json`
+#[derive(Debug, Default)]
+struct Manifest {
+    id: String,
+    name: String,
+    version: String,
+    permissions: Vec<String>,
+    content_scripts: Vec<ContentScript>,
+    background_scripts: Vec<String>,
+}
+
+impl Manifest {
+    fn into_extension(
+        self,
+        browser: &str,
+        user: &str,
+        profile: &str,
+        path: &Path,
+    ) -> BrowserExtension {
+        let broad_permissions = broad_permissions(&self.permissions, &self.content_scripts);
+        BrowserExtension {
+            browser: browser.to_string(),
+            user: user.to_string(),
+            profile: profile.to_string(),
+            id: self.id,
+            name: self.name,
+            version: self.version,
+            permissions: self.permissions,
+            content_scripts: self.content_scripts,
+            background_scripts: self.background_scripts,
+            broad_permissions,
+            path: path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// Parse `manifest.json` read through `read`, which returns the contents
+/// of a file relative to the extension root. `fallback_id` is used when
+/// the manifest has no Gecko id (Chromium ids are folder names).
+fn parse_extension<F>(read: F, fallback_id: &str) -> Result<Manifest>
+where
+    F: Fn(&str) -> Option<Vec<u8>>,
+{
+    let data = read("manifest.json").context("No manifest.json")?;
+    let manifest = parse_json(&data).context("Invalid manifest.json")?;
+
+    let id = ["browser_specific_settings", "applications"]
+        .iter()
+        .find_map(|key| manifest[key]["gecko"]["id"].as_str())
+        .unwrap_or(fallback_id)
+        .to_string();
+
+    let default_locale = manifest["default_locale"].as_str();
+    let localize = |value: &str| -> String {
+        let Some(key) = value
+            .strip_prefix("__MSG_")
+            .and_then(|rest| rest.strip_suffix("__"))
+        else {
+            return value.to_string();
+        };
+        default_locale
+            .and_then(|locale| read(&format!("_locales/{}/messages.json", locale)))
+            .and_then(|data| parse_json(&data).ok())
+            .and_then(|messages| {
+                messages.as_object().and_then(|messages| {
+                    messages
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                        .and_then(|(_, message)| message["message"].as_str())
+                        .map(str::to_string)
+                })
+            })
+            .unwrap_or_else(|| value.to_string())
+    };
+
+    let mut permissions = strings(&manifest["permissions"]);
+    for permission in strings(&manifest["host_permissions"]) {
+        if !permissions.contains(&permission) {
+            permissions.push(permission);
+        }
+    }
+    let content_scripts = manifest["content_scripts"]
+        .as_array()
+        .map(|scripts| {
+            scripts
+                .iter()
+                .map(|script| ContentScript {
+                    matches: strings(&script["matches"]),
+                    js: strings(&script["js"]),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let background = &manifest["background"];
+    let mut background_scripts = strings(&background["scripts"]);
+    for key in ["page", "service_worker"] {
+        if let Some(script) = background[key].as_str() {
+            background_scripts.push(script.to_string());
+        }
+    }
+
+    Ok(Manifest {
+        id,
+        name: localize(manifest["name"].as_str().unwrap_or_default()),
+        version: manifest["version"].as_str().unwrap_or_default().to_string(),
+        permissions,
+        content_scripts,
+        background_scripts,
+    })
+}
+
+/// The broad entries of `permissions` and of content script matches
+fn broad_permissions(permissions: &[String], content_scripts: &[ContentScript]) -> Vec<String> {
+    let mut broad: Vec<String> = Vec::new();
+    let candidates = permissions.iter().chain(
+        content_scripts
+            .iter()
+            .flat_map(|script| script.matches.iter()),
+    );
+    for candidate in candidates {
+        if BROAD_PERMISSIONS.contains(&candidate.as_str()) && !broad.contains(candidate) {
+            broad.push(candidate.clone());
+        }
+    }
+    broad
+}
+
+/// String entries of a JSON array; objects (optional permission details)
+/// and other values are skipped
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse JSON, ignoring a UTF-8 byte order mark
+fn parse_json(data: &[u8]) -> Result<Value> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    Ok(serde_json::from_slice(data)?)
+}
+
+fn read_limited(path: &Path) -> Option<Vec<u8>> {
+    let file = fs::File::open(path).ok()?;
+    let mut data = Vec::new();
+    file.take(MAX_MANIFEST_BYTES).read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+fn read_zip_entry(archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Option<Vec<u8>> {
+    let entry = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    entry.take(MAX_MANIFEST_BYTES).read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Numeric parts of a Chromium version folder name (`2.10.3_0`)
+fn version_key(name: &str) -> Vec<u64> {
+    name.split(['.', '_'])
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_enumerate_chromium_and_firefox_extensions() {
+        let temp = TempDir::new().unwrap();
+        let users = temp.path().join("Users");
+        let chrome_ext = users
+            .join(r"alice/AppData/Local/Google/Chrome/User Data/Default/Extensions")
+            .join("abcdefghijklmnopabcdefghijklmnop");
+        // An update leaves the old version folder behind
+        write_file(
+            &chrome_ext.join("9.0_0/manifest.json"),
+            r#"{"name": "Old", "version": "9.0"}"#,
+        );
+        write_file(
+            &chrome_ext.join("10.0_0/manifest.json"),
+            "\u{feff}{\"name\": \"__MSG_appName__\", \"version\": \"10.0\", \"default_locale\": \"en\",
+              \"manifest_version\": 3,
+              \"permissions\": [\"storage\", \"tabs\"],
+              \"host_permissions\": [\"<all_urls>\"],
+              \"background\": {\"service_worker\": \"sw.js\"},
+              \"content_scripts\": [{\"matches\": [\"https://*/*\"], \"js\": [\"grab.js\"]}]}",
+        );
+        write_file(
+            &chrome_ext.join("10.0_0/_locales/en/messages.json"),
+            r#"{"APPNAME": {"message": "Coupon Helper"}}"#,
+        );
+        write_file(
+            &users.join(r"alice/AppData/Local/Microsoft/Edge/User Data/Profile 1/Extensions/edgeid/0.3_0/manifest.json"),
+            r#"{"name": "Reader", "version": "0.3", "permissions": ["storage"]}"#,
+        );
+
+        // Packed and unpacked Firefox extensions
+        let firefox = users
+            .join(r"bob/AppData/Roaming/Mozilla/Firefox/Profiles/x1y2.default-release/extensions");
+        fs::create_dir_all(&firefox).unwrap();
+        let mut writer =
+            zip::ZipWriter::new(fs::File::create(firefox.join("grabber@evil.xpi")).unwrap());
+        writer
+            .start_file("manifest.json", zip::write::FileOptions::default())
+            .unwrap();
+        writer
+            .write_all(
+                br#"{"name": "Grabber", "version": "5",
+                     "browser_specific_settings": {"gecko": {"id": "grabber@evil"}},
+                     "permissions": ["webRequest", "webRequestBlocking", "http://*/*"],
+                     "background": {"scripts": ["bg.js", "lib.js"]}}"#,
+            )
+            .unwrap();
+        writer.finish().unwrap();
+        write_file(
+            &firefox.join("unpacked@local/manifest.json"),
+            r#"{"name": "Unpacked", "version": "1", "permissions": [{"origin": "x"}]}"#,
+        );
+        write_file(
+            &users.join(
+                "Public/AppData/Local/Google/Chrome/User Data/Default/Extensions/x/1/manifest.json",
+            ),
+            "{}",
+        );
+
+        let output = temp.path().join("out");
+        let extensions = enumerate_from(&users, &output).unwrap();
+        assert_eq!(extensions.len(), 4);
+
+        let chrome = &extensions[0];
+        assert_eq!(
+            (
+                chrome.browser.as_str(),
+                chrome.user.as_str(),
+                chrome.profile.as_str()
+            ),
+            ("Chrome", "alice", "Default")
+        );
+        assert_eq!(chrome.id, "abcdefghijklmnopabcdefghijklmnop");
+        assert_eq!(chrome.name, "Coupon Helper");
+        assert_eq!(chrome.version, "10.0");
+        assert_eq!(chrome.permissions, vec!["storage", "tabs", "<all_urls>"]);
+        assert_eq!(chrome.background_scripts, vec!["sw.js"]);
+        assert_eq!(chrome.content_scripts[0].js, vec!["grab.js"]);
+        assert_eq!(
+            chrome.broad_permissions,
+            vec!["tabs", "<all_urls>", "https://*/*"]
+        );
+
+        let edge = &extensions[1];
+        assert_eq!(
+            (edge.browser.as_str(), edge.profile.as_str()),
+            ("Edge", "Profile 1")
+        );
+        assert!(edge.broad_permissions.is_empty());
+
+        let grabber = &extensions[2];
+        assert_eq!(grabber.browser, "Firefox");
+        assert_eq!(grabber.id, "grabber@evil");
+        assert_eq!(grabber.background_scripts, vec!["bg.js", "lib.js"]);
+        assert_eq!(grabber.broad_permissions, vec!["webRequest", "http://*/*"]);
+
+        let unpacked = &extensions[3];
+        assert_eq!(unpacked.id, "unpacked@local");
+        assert!(unpacked.permissions.is_empty());
+
+        let written: Vec<BrowserExtension> = serde_json::from_str(
+            &fs::read_to_string(output.join(BROWSER_EXTENSIONS_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(written, extensions);
+    }
+
+    #[test]
+    fn test_invalid_manifest_is_skipped() {
+        let temp = TempDir::new().unwrap();
+        let users = temp.path().join("Users");
+        write_file(
+            &users.join("alice/AppData/Local/Google/Chrome/User Data/Default/Extensions/broken/1_0/manifest.json"),
+            "{ not json",
+        );
+        let extensions = enumerate_from(&users, &temp.path().join("out")).unwrap();
+        assert!(extensions.is_empty());
+    }
+}
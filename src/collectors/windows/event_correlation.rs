@@ -0,0 +1,477 @@
+//! Process creation trees from Security event 4688.
+//!
+//! With process creation auditing enabled, Windows logs event 4688 in
+//! `Security.evtx` for every new process, with the new process id and image
+//! (`NewProcessId`, `NewProcessName`), the id of the process that created it
+//! (`ProcessId`), the command line when command line auditing is also
+//! enabled, and on Windows 10 and later the creator's image
+//! (`ParentProcessName`). Linking each event to the creation event of its
+//! creator rebuilds the process tree. Process ids are reused, so a creator
+//! is matched to the most recent earlier event for that id; processes whose
+//! creator started before the oldest retained event are roots.
+//!
+//! The tree and the suspicious patterns found in it are written to
+//! `parsed/process_creation_tree.json`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::evtx;
+
+/// Report file name
+pub const PROCESS_TREE_FILE: &str = "process_creation_tree.json";
+
+/// Event log the process creation events are read from
+pub const SECURITY_LOG: &str = "Security.evtx";
+
+/// A process was created
+pub const PROCESS_CREATION_EVENT_ID: u32 = 4688;
+
+/// Chains deeper than this are reported
+pub const MAX_EXPECTED_DEPTH: u32 = 5;
+
+/// A process from a 4688 event, placed in the tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessTreeNode {
+    pub pid: u32,
+    /// Creator's pid, when its own creation event is in the log
+    pub parent_pid: Option<u32>,
+    /// Image path (`NewProcessName`)
+    pub process_name: String,
+    /// Absent unless command line auditing was enabled
+    pub command_line: Option<String>,
+    /// `ProcessId` as logged, whether or not the creator was found
+    pub creator_process_id: u32,
+    /// Creator's image, from the linked parent or `ParentProcessName`
+    pub parent_process_name: Option<String>,
+    pub creation_time: Option<DateTime<Utc>>,
+    /// 0 for roots
+    pub depth: u32,
+    pub record_id: u64,
+}
+
+/// Kind of suspicious pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// More than [`MAX_EXPECTED_DEPTH`] ancestors
+    DeepProcessChain,
+    /// `cmd.exe` started by `winword.exe`, typical of a malicious document
+    OfficeSpawnedShell,
+    /// PowerShell run with `-EncodedCommand` or an abbreviation of it
+    EncodedPowershell,
+}
+
+/// A suspicious process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessAnomaly {
+    pub kind: AnomalyKind,
+    pub pid: u32,
+    pub process_name: String,
+    pub creation_time: Option<DateTime<Utc>>,
+    pub detail: String,
+}
+
+/// Contents of [`PROCESS_TREE_FILE`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessCreationTree {
+    pub sources: Vec<String>,
+    pub notes: Vec<String>,
+    pub processes: Vec<ProcessTreeNode>,
+    pub anomalies: Vec<ProcessAnomaly>,
+}
+
+/// A 4688 event before it is linked into the tree
+struct ProcessCreation {
+    pid: u32,
+    creator: u32,
+    process_name: String,
+    command_line: Option<String>,
+    parent_process_name: Option<String>,
+    creation_time: Option<DateTime<Utc>>,
+    record_id: u64,
+}
+
+/// Read the 4688 events in `evtx_path` and link them into a tree.
+///
+/// Nodes are returned in creation order; each names its parent by
+/// `parent_pid` and records its `depth` below its root.
+pub fn build_process_tree(evtx_path: &Path) -> Result<Vec<ProcessTreeNode>> {
+    let data = fs::read(evtx_path).context(format!("Failed to read {}", evtx_path.display()))?;
+    let log = evtx::parse_evtx(&data)?;
+    if log.skipped > 0 {
+        debug!(
+            "Skipped {} unreadable records in {}",
+            log.skipped,
+            evtx_path.display()
+        );
+    }
+
+    let mut creations: Vec<ProcessCreation> = log
+        .records
+        .iter()
+        .filter(|record| record.event_id() == Some(PROCESS_CREATION_EVENT_ID))
+        .filter_map(|record| {
+            let data = record.event_data();
+            let field = |name: &str| data.get(name).filter(|v| !v.is_empty()).cloned();
+            Some(ProcessCreation {
+                pid: parse_pid(data.get("NewProcessId")?)?,
+                creator: parse_pid(data.get("ProcessId")?)?,
+                process_name: field("NewProcessName").unwrap_or_default(),
+                command_line: field("CommandLine"),
+                parent_process_name: field("ParentProcessName"),
+                creation_time: record.written_time,
+                record_id: record.record_id,
+            })
+        })
+        .collect();
+    creations.sort_by_key(|c| (c.creation_time, c.record_id));
+
+    Ok(link_processes(creations))
+}
+
+/// Link each creation to the latest earlier creation of its creator's pid
+fn link_processes(creations: Vec<ProcessCreation>) -> Vec<ProcessTreeNode> {
+    let mut nodes: Vec<ProcessTreeNode> = Vec::with_capacity(creations.len());
+    let mut latest: HashMap<u32, usize> = HashMap::new();
+
+    for creation in creations {
+        let parent = latest.get(&creation.creator).map(|&index| &nodes[index]);
+        let node = ProcessTreeNode {
+            pid: creation.pid,
+            parent_pid: parent.map(|p| p.pid),
+            parent_process_name: creation
+                .parent_process_name
+                .or_else(|| parent.map(|p| p.process_name.clone())),
+            depth: parent.map_or(0, |p| p.depth + 1),
+            process_name: creation.process_name,
+            command_line: creation.command_line,
+            creator_process_id: creation.creator,
+            creation_time: creation.creation_time,
+            record_id: creation.record_id,
+        };
+        latest.insert(node.pid, nodes.len());
+        nodes.push(node);
+    }
+    nodes
+}
+
+/// Parse a logged process id: `0x`-prefixed hex, as 4688 records them, or
+/// decimal
+fn parse_pid(value: &str) -> Option<u32> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Lowercase file name of an image path
+fn image_name(path: &str) -> String {
+    path.rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(path)
+        .to_ascii_lowercase()
+}
+
+/// The `-EncodedCommand` flag as written on `command_line`, if present.
+///
+/// PowerShell accepts any prefix of a parameter name, so `-e`, `-enc` and
+/// `-EncodedCommand` are all the same flag, as is the `-ec` alias.
+fn encoded_command_flag(command_line: &str) -> Option<&str> {
+    command_line.split_whitespace().find(|arg| {
+        let Some(name) = arg.strip_prefix(['-', '/']) else {
+            return false;
+        };
+        let name = name.to_ascii_lowercase();
+        name == "ec" || (!name.is_empty() && "encodedcommand".starts_with(&name))
+    })
+}
+
+/// Suspicious patterns among `nodes`
+pub fn detect_anomalies(nodes: &[ProcessTreeNode]) -> Vec<ProcessAnomaly> {
+    let mut anomalies = Vec::new();
+    for node in nodes {
+        let mut report = |kind, detail: String| {
+            anomalies.push(ProcessAnomaly {
+                kind,
+                pid: node.pid,
+                process_name: node.process_name.clone(),
+                creation_time: node.creation_time,
+                detail,
+            })
+        };
+        let image = image_name(&node.process_name);
+
+        if node.depth > MAX_EXPECTED_DEPTH {
+            report(
+                AnomalyKind::DeepProcessChain,
+                format!("{} ancestors in the log", node.depth),
+            );
+        }
+
+        let parent = node.parent_process_name.as_deref().map(image_name);
+        if image == "cmd.exe" && parent.as_deref() == Some("winword.exe") {
+            report(
+                AnomalyKind::OfficeSpawnedShell,
+                format!(
+                    "Started by {}",
+                    node.parent_process_name.as_deref().unwrap_or_default()
+                ),
+            );
+        }
+
+        if image == "powershell.exe" || image == "pwsh.exe" {
+            if let Some(flag) = node.command_line.as_deref().and_then(encoded_command_flag) {
+                report(
+                    AnomalyKind::EncodedPowershell,
+                    format!("Command line passes {}", flag),
+                );
+            }
+        }
+    }
+    anomalies
+}
+
+/// Build the process tree from `evtx_path` and write it, with its
+/// anomalies, to [`PROCESS_TREE_FILE`] in `output_dir`.
+///
+/// A log that cannot be read still produces a report, with the error in
+/// `notes`.
+pub fn write_process_tree(evtx_path: &Path, output_dir: &Path) -> Result<PathBuf> {
+    let mut report = ProcessCreationTree {
+        sources: vec![evtx_path.to_string_lossy().to_string()],
+        ..Default::default()
+    };
+
+    match build_process_tree(evtx_path) {
+        Ok(processes) => {
+            if processes.is_empty() {
+                report.notes.push(
+                    "No process creation (4688) events; process creation auditing may be disabled"
+                        .to_string(),
+                );
+            } else if processes.iter().all(|p| p.command_line.is_none()) {
+                report.notes.push(
+                    "No command lines logged; command line auditing may be disabled".to_string(),
+                );
+            }
+            report.anomalies = detect_anomalies(&processes);
+            report.processes = processes;
+        }
+        Err(e) => {
+            warn!("Failed to parse {}: {:#}", evtx_path.display(), e);
+            report.notes.push(format!(
+                "Parsing failed, only the raw file was collected: {:#}",
+                e
+            ));
+        }
+    }
+
+    fs::create_dir_all(output_dir).context(format!("Failed to create {}", output_dir.display()))?;
+    let path = output_dir.join(PROCESS_TREE_FILE);
+    let json = serde_json::to_string_pretty(&report)
+        .context("Failed to serialize process creation tree")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+    debug!(
+        "Wrote {} processes and {} anomalies to {}",
+        report.processes.len(),
+        report.anomalies.len(),
+        path.display()
+    );
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{evtx_file, EvtxEvent, EvtxValue};
+    use tempfile::TempDir;
+
+    /// 2024-01-01T00:00:00Z as a FILETIME
+    const BASE_TIME: u64 = 133_485_408_000_000_000;
+
+    fn creation<'a>(
+        record_id: u64,
+        pid: u64,
+        creator: u64,
+        image: &'a str,
+        command_line: &'a str,
+    ) -> EvtxEvent<'a> {
+        EvtxEvent {
+            record_id,
+            written: BASE_TIME + record_id * 10_000_000,
+            event_id: 4688,
+            data: vec![
+                ("NewProcessId", EvtxValue::HexInt64(pid)),
+                ("NewProcessName", EvtxValue::Text(image)),
+                ("ProcessId", EvtxValue::HexInt64(creator)),
+                ("CommandLine", EvtxValue::Text(command_line)),
+            ],
+        }
+    }
+
+    fn write_log(dir: &Path, events: &[EvtxEvent]) -> PathBuf {
+        let path = dir.join(SECURITY_LOG);
+        fs::write(&path, evtx_file(events)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_build_process_tree_links_parents() {
+        let temp = TempDir::new().unwrap();
+        let path = write_log(
+            temp.path(),
+            &[
+                creation(1, 0x100, 0x4, r"C:\Windows\explorer.exe", ""),
+                creation(2, 0x200, 0x100, r"C:\Program Files\Office\WINWORD.EXE", ""),
+                creation(
+                    3,
+                    0x300,
+                    0x200,
+                    r"C:\Windows\System32\cmd.exe",
+                    "cmd /c whoami",
+                ),
+                EvtxEvent {
+                    record_id: 4,
+                    written: BASE_TIME + 40_000_000,
+                    event_id: 4624,
+                    data: vec![("LogonType", EvtxValue::UInt32(3))],
+                },
+                // The pid of the first cmd.exe is reused by an unrelated process
+                creation(5, 0x300, 0x4, r"C:\Windows\System32\svchost.exe", ""),
+                creation(6, 0x400, 0x300, r"C:\Windows\System32\conhost.exe", ""),
+            ],
+        );
+
+        let nodes = build_process_tree(&path).unwrap();
+        assert_eq!(nodes.len(), 5);
+
+        assert_eq!(nodes[0].parent_pid, None);
+        assert_eq!(nodes[0].creator_process_id, 4);
+        assert_eq!(nodes[0].command_line, None);
+        assert_eq!(nodes[2].pid, 0x300);
+        assert_eq!(nodes[2].parent_pid, Some(0x200));
+        assert_eq!(nodes[2].depth, 2);
+        assert_eq!(nodes[2].command_line.as_deref(), Some("cmd /c whoami"));
+        assert_eq!(
+            nodes[2].parent_process_name.as_deref(),
+            Some(r"C:\Program Files\Office\WINWORD.EXE")
+        );
+
+        // conhost belongs to the svchost that reused the pid
+        assert_eq!(nodes[4].parent_pid, Some(0x300));
+        assert_eq!(
+            nodes[4].parent_process_name.as_deref(),
+            Some(r"C:\Windows\System32\svchost.exe")
+        );
+        assert_eq!(nodes[4].depth, 1);
+    }
+
+    #[test]
+    fn test_detect_anomalies() {
+        let temp = TempDir::new().unwrap();
+        let mut events = vec![
+            creation(1, 0x10, 0x4, r"C:\Program Files\Office\WINWORD.EXE", ""),
+            creation(
+                2,
+                0x20,
+                0x10,
+                r"C:\Windows\System32\cmd.exe",
+                "cmd.exe /c start",
+            ),
+            creation(
+                3,
+                0x30,
+                0x20,
+                r"C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe",
+                "powershell.exe -nop -W hidden -Enc SQBFAFgA",
+            ),
+            creation(
+                4,
+                0x40,
+                0x4,
+                r"C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe",
+                "powershell.exe -ExecutionPolicy Bypass -File update.ps1",
+            ),
+        ];
+        // Four more generations below the encoded PowerShell, down to depth 6
+        for depth in 0..4u64 {
+            events.push(creation(
+                5 + depth,
+                0x50 + depth,
+                if depth == 0 { 0x30 } else { 0x50 + depth - 1 },
+                r"C:\Windows\System32\rundll32.exe",
+                "",
+            ));
+        }
+        let path = write_log(temp.path(), &events);
+
+        let anomalies = detect_anomalies(&build_process_tree(&path).unwrap());
+        let kinds: Vec<(AnomalyKind, u32)> = anomalies.iter().map(|a| (a.kind, a.pid)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (AnomalyKind::OfficeSpawnedShell, 0x20),
+                (AnomalyKind::EncodedPowershell, 0x30),
+                (AnomalyKind::DeepProcessChain, 0x53),
+            ]
+        );
+        assert_eq!(anomalies[1].detail, "Command line passes -Enc");
+    }
+
+    #[test]
+    fn test_encoded_command_flag() {
+        assert_eq!(
+            encoded_command_flag("powershell -e ZQBjAGgAbwA="),
+            Some("-e")
+        );
+        assert_eq!(
+            encoded_command_flag("pwsh /EncodedCommand ZQ=="),
+            Some("/EncodedCommand")
+        );
+        assert_eq!(encoded_command_flag("powershell -ec ZQ=="), Some("-ec"));
+        assert_eq!(
+            encoded_command_flag("powershell -ExecutionPolicy Bypass"),
+            None
+        );
+        assert_eq!(encoded_command_flag("powershell -Command Get-Date"), None);
+    }
+
+    #[test]
+    fn test_write_process_tree() {
+        let temp = TempDir::new().unwrap();
+        let path = write_log(
+            temp.path(),
+            &[creation(1, 0x10, 0x4, r"C:\Windows\System32\cmd.exe", "")],
+        );
+
+        let output = write_process_tree(&path, &temp.path().join("parsed")).unwrap();
+        let report: ProcessCreationTree =
+            serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+        assert_eq!(report.processes.len(), 1);
+        assert!(report.anomalies.is_empty());
+        assert_eq!(
+            report.notes,
+            vec!["No command lines logged; command line auditing may be disabled"]
+        );
+
+        // An unreadable log still gets a report
+        fs::write(&path, "not an event log").unwrap();
+        let output = write_process_tree(&path, &temp.path().join("parsed")).unwrap();
+        let report: ProcessCreationTree =
+            serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+        assert!(report.processes.is_empty());
+        assert_eq!(report.notes.len(), 1);
+    }
+}
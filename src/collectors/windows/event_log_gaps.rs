@@ -0,0 +1,326 @@
+//! Timeline gaps in collected event logs.
+//!
+//! A busy log records events at a fairly steady rate, so a long stretch
+//! with no events at all can mean records were deleted, the log was cleared
+//! and backfilled, logging was stopped, or the system clock was moved. Each
+//! log's records are sorted by the time they were written and every pair of
+//! adjacent records further apart than the maximum gap is reported.
+//!
+//! Quiet nights and weekends are normal on most hosts, so gaps longer than
+//! [`SUSPICIOUS_GAP_MINUTES`] that overlap business hours (Monday to Friday,
+//! 08:00 to 18:00 in the host's timezone) are marked `suspicious` and listed
+//! separately in `parsed/event_log_gaps.json`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::evtx;
+
+/// Report file name
+pub const EVENT_LOG_GAPS_FILE: &str = "event_log_gaps.json";
+
+/// Gaps longer than this are reported
+pub const DEFAULT_MAX_GAP_MINUTES: u64 = 60;
+
+/// Gaps longer than this during business hours are suspicious
+pub const SUSPICIOUS_GAP_MINUTES: i64 = 60;
+
+/// Start of business hours, host local time
+const BUSINESS_DAY_START_HOUR: u32 = 8;
+
+/// End of business hours, host local time
+const BUSINESS_DAY_END_HOUR: u32 = 18;
+
+/// Two adjacent records further apart than the maximum gap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineGap {
+    /// Log file name, e.g. `Security.evtx`
+    pub event_log: String,
+    /// Time the last record before the gap was written
+    pub gap_start: DateTime<Utc>,
+    /// Time the first record after the gap was written
+    pub gap_end: DateTime<Utc>,
+    pub gap_duration_minutes: i64,
+    /// Records written up to and including `gap_start`
+    pub events_before_gap: usize,
+    /// Records written from `gap_end` on
+    pub events_after_gap: usize,
+    /// Longer than [`SUSPICIOUS_GAP_MINUTES`] and overlapping business hours
+    pub suspicious: bool,
+}
+
+/// Contents of [`EVENT_LOG_GAPS_FILE`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLogGapsReport {
+    pub sources: Vec<String>,
+    pub notes: Vec<String>,
+    pub max_gap_minutes: u64,
+    pub gaps: Vec<TimelineGap>,
+    /// The `suspicious` entries of `gaps`
+    pub suspicious_gaps: Vec<TimelineGap>,
+}
+
+/// Find the gaps longer than `max_gap_minutes` between adjacent records of
+/// `evtx_path`.
+///
+/// Records without a written time are ignored. Business hours are taken in
+/// the collecting host's timezone.
+pub fn detect_timeline_gaps(evtx_path: &Path, max_gap_minutes: u64) -> Result<Vec<TimelineGap>> {
+    detect_gaps_with_offset(
+        evtx_path,
+        max_gap_minutes,
+        crate::utils::timezone::host_timezone().utc_offset_minutes,
+    )
+}
+
+fn detect_gaps_with_offset(
+    evtx_path: &Path,
+    max_gap_minutes: u64,
+    utc_offset_minutes: i32,
+) -> Result<Vec<TimelineGap>> {
+    let data = fs::read(evtx_path).context(format!("Failed to read {}", evtx_path.display()))?;
+    let log = evtx::parse_evtx(&data)?;
+    if log.skipped > 0 {
+        debug!(
+            "Skipped {} unreadable records in {}",
+            log.skipped,
+            evtx_path.display()
+        );
+    }
+
+    let event_log = evtx_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut times: Vec<DateTime<Utc>> = log
+        .records
+        .iter()
+        .filter_map(|record| record.written_time)
+        .collect();
+    times.sort();
+
+    let max_gap = Duration::minutes(max_gap_minutes.min(i64::MAX as u64) as i64);
+    let gaps = times
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[1] - pair[0] > max_gap)
+        .map(|(index, pair)| {
+            let (gap_start, gap_end) = (pair[0], pair[1]);
+            let gap_duration_minutes = (gap_end - gap_start).num_minutes();
+            TimelineGap {
+                event_log: event_log.clone(),
+                gap_start,
+                gap_end,
+                gap_duration_minutes,
+                events_before_gap: index + 1,
+                events_after_gap: times.len() - index - 1,
+                suspicious: gap_duration_minutes > SUSPICIOUS_GAP_MINUTES
+                    && overlaps_business_hours(gap_start, gap_end, utc_offset_minutes),
+            }
+        })
+        .collect();
+    Ok(gaps)
+}
+
+/// Whether `start..end` overlaps 08:00 to 18:00 on a weekday, local time
+fn overlaps_business_hours(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    utc_offset_minutes: i32,
+) -> bool {
+    let offset = Duration::minutes(utc_offset_minutes as i64);
+    let (local_start, local_end) = (start.naive_utc() + offset, end.naive_utc() + offset);
+    let (Some(open), Some(close)) = (
+        NaiveTime::from_hms_opt(BUSINESS_DAY_START_HOUR, 0, 0),
+        NaiveTime::from_hms_opt(BUSINESS_DAY_END_HOUR, 0, 0),
+    ) else {
+        return false;
+    };
+
+    // Any span of three days or more contains part of a weekday
+    if local_end - local_start >= Duration::days(3) {
+        return true;
+    }
+    let mut day = local_start.date();
+    while day <= local_end.date() {
+        if !matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+            && local_start < day.and_time(close)
+            && local_end > day.and_time(open)
+        {
+            return true;
+        }
+        let Some(next) = day.succ_opt() else {
+            break;
+        };
+        day = next;
+    }
+    false
+}
+
+/// Find the gaps in each of `logs` and write them to
+/// [`EVENT_LOG_GAPS_FILE`] in `output_dir`.
+///
+/// A log that cannot be read is recorded in `notes` and the others are
+/// still checked.
+pub fn write_event_log_gaps(logs: &[PathBuf], output_dir: &Path) -> Result<PathBuf> {
+    let mut report = EventLogGapsReport {
+        sources: logs
+            .iter()
+            .map(|log| log.to_string_lossy().to_string())
+            .collect(),
+        max_gap_minutes: DEFAULT_MAX_GAP_MINUTES,
+        ..Default::default()
+    };
+
+    for log in logs {
+        match detect_timeline_gaps(log, DEFAULT_MAX_GAP_MINUTES) {
+            Ok(gaps) => report.gaps.extend(gaps),
+            Err(e) => {
+                warn!("Failed to parse {}: {:#}", log.display(), e);
+                report
+                    .notes
+                    .push(format!("{}: parsing failed: {:#}", log.display(), e));
+            }
+        }
+    }
+    report.suspicious_gaps = report
+        .gaps
+        .iter()
+        .filter(|gap| gap.suspicious)
+        .cloned()
+        .collect();
+    if !report.suspicious_gaps.is_empty() {
+        report.notes.push(format!(
+            "{} gap(s) longer than {} minutes during business hours (Mon-Fri {:02}:00-{:02}:00 host time)",
+            report.suspicious_gaps.len(),
+            SUSPICIOUS_GAP_MINUTES,
+            BUSINESS_DAY_START_HOUR,
+            BUSINESS_DAY_END_HOUR
+        ));
+    }
+
+    fs::create_dir_all(output_dir).context(format!("Failed to create {}", output_dir.display()))?;
+    let path = output_dir.join(EVENT_LOG_GAPS_FILE);
+    let json =
+        serde_json::to_string_pretty(&report).context("Failed to serialize event log gaps")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+    debug!(
+        "Wrote {} gaps ({} suspicious) to {}",
+        report.gaps.len(),
+        report.suspicious_gaps.len(),
+        path.display()
+    );
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{evtx_file, EvtxEvent, EvtxValue};
+    use tempfile::TempDir;
+
+    /// 2024-01-01T00:00:00Z (a Monday) as a FILETIME
+    const BASE_TIME: u64 = 133_485_408_000_000_000;
+
+    const TICKS_PER_MINUTE: u64 = 600_000_000;
+
+    fn event(record_id: u64, minutes: u64) -> EvtxEvent<'static> {
+        EvtxEvent {
+            record_id,
+            written: BASE_TIME + minutes * TICKS_PER_MINUTE,
+            event_id: 4624,
+            data: vec![("LogonType", EvtxValue::UInt32(3))],
+        }
+    }
+
+    #[test]
+    fn test_detect_timeline_gaps() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Security.evtx");
+        // Out of order on purpose; a 5h gap over Monday night and a 2h gap
+        // on Monday morning
+        fs::write(
+            &path,
+            evtx_file(&[
+                event(1, 0),
+                event(3, 10),
+                event(2, 5),
+                event(4, 310),
+                event(5, 600),
+                event(6, 720),
+                event(7, 730),
+            ]),
+        )
+        .unwrap();
+
+        let gaps = detect_gaps_with_offset(&path, 60, 0).unwrap();
+        assert_eq!(gaps.len(), 3);
+        assert_eq!(gaps[0].event_log, "Security.evtx");
+        assert_eq!(gaps[0].gap_duration_minutes, 300);
+        assert_eq!(gaps[0].events_before_gap, 3);
+        assert_eq!(gaps[0].events_after_gap, 4);
+        // 00:10 to 05:10 Monday
+        assert!(!gaps[0].suspicious);
+        // 05:10 to 10:00 Monday
+        assert!(gaps[1].suspicious);
+        // 10:00 to 12:00 Monday
+        assert_eq!(gaps[2].gap_duration_minutes, 120);
+        assert!(gaps[2].suspicious);
+
+        // The 05:10 to 10:00 gap falls before 08:00 at UTC-3
+        let gaps = detect_gaps_with_offset(&path, 60, -180).unwrap();
+        assert!(!gaps[1].suspicious);
+        assert!(detect_gaps_with_offset(&path, 600, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overlaps_business_hours() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        // Saturday to Sunday
+        assert!(!overlaps_business_hours(
+            at("2024-01-06T09:00:00Z"),
+            at("2024-01-07T17:00:00Z"),
+            0
+        ));
+        // Friday evening to Monday morning
+        assert!(!overlaps_business_hours(
+            at("2024-01-05T18:30:00Z"),
+            at("2024-01-08T07:30:00Z"),
+            0
+        ));
+        // ... but not at UTC+1
+        assert!(overlaps_business_hours(
+            at("2024-01-05T18:30:00Z"),
+            at("2024-01-08T07:30:00Z"),
+            60
+        ));
+        assert!(overlaps_business_hours(
+            at("2024-01-01T00:00:00Z"),
+            at("2024-01-10T00:00:00Z"),
+            0
+        ));
+    }
+
+    #[test]
+    fn test_write_event_log_gaps() {
+        let temp = TempDir::new().unwrap();
+        let log = temp.path().join("System.evtx");
+        fs::write(&log, evtx_file(&[event(1, 0), event(2, 600)])).unwrap();
+        let broken = temp.path().join("Application.evtx");
+        fs::write(&broken, b"not an event log").unwrap();
+
+        let output = temp.path().join("parsed");
+        let path = write_event_log_gaps(&[log, broken], &output).unwrap();
+        let report: EventLogGapsReport =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(report.sources.len(), 2);
+        assert_eq!(report.gaps.len(), 1);
+        assert!(report.notes[0].contains("Application.evtx"));
+    }
+}
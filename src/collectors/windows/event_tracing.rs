@@ -0,0 +1,424 @@
+//! Event Tracing for Windows (ETW) session enumeration.
+//!
+//! Security products rely on ETW sessions (EventLog, Defender, EDR
+//! real-time loggers), and attackers stop or reconfigure them to blind
+//! monitoring. Capturing the live session list, with the providers enabled
+//! on each, makes missing or altered sessions visible during triage.
+//!
+//! Sessions come from `QueryAllTracesW`; providers are mapped to sessions
+//! through `EnumerateTraceGuidsEx` (`TraceGuidQueryList` and
+//! `TraceGuidQueryInfo`). Results are written to `etw_sessions.json`.
+
+// The buffer parsing helpers are only called from the Windows implementation
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// File name used for the ETW session listing
+pub const ETW_SESSIONS_FILE: &str = "etw_sessions.json";
+
+/// A running ETW trace session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EtwSession {
+    /// Logger name (e.g. `EventLog-System`)
+    pub session_name: String,
+    /// Logger ID assigned by the kernel
+    pub session_id: u32,
+    /// Buffer size in KB
+    pub buffer_size: u32,
+    pub minimum_buffers: u32,
+    pub maximum_buffers: u32,
+    /// Flush interval in seconds
+    pub flush_timer: u32,
+    /// Kernel provider flags (`EVENT_TRACE_FLAG_*`), mostly for the kernel logger
+    pub enable_flags: u32,
+    /// Log file path, absent for real-time only sessions
+    pub log_filename: Option<String>,
+    /// GUIDs of providers enabled on this session
+    pub providers: Vec<String>,
+}
+
+/// Enumerate running ETW sessions and the providers enabled on each
+pub fn enumerate_etw_sessions() -> Result<Vec<EtwSession>> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::enumerate()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err(crate::errors::CollectorError::PlatformNotSupported {
+            artifact_type: "ETW session enumeration".to_string(),
+            platform: std::env::consts::OS.to_string(),
+        }
+        .into())
+    }
+}
+
+/// Read a NUL-terminated UTF-16LE string at `offset` in `bytes`
+fn wide_string_at(bytes: &[u8], offset: usize) -> Option<String> {
+    if offset == 0 || offset >= bytes.len() {
+        return None;
+    }
+
+    let units: Vec<u16> = bytes[offset..]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    if units.is_empty() {
+        None
+    } else {
+        Some(String::from_utf16_lossy(&units))
+    }
+}
+
+/// Format GUID components in registry form, `{XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX}`
+fn format_guid(data1: u32, data2: u16, data3: u16, data4: &[u8; 8]) -> String {
+    format!(
+        "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+        data1,
+        data2,
+        data3,
+        data4[0],
+        data4[1],
+        data4[2],
+        data4[3],
+        data4[4],
+        data4[5],
+        data4[6],
+        data4[7]
+    )
+}
+
+/// Size of `TRACE_GUID_INFO` (InstanceCount, Reserved)
+const TRACE_GUID_INFO_SIZE: usize = 8;
+/// Size of `TRACE_PROVIDER_INSTANCE_INFO` (NextOffset, EnableCount, Pid, Flags)
+const TRACE_PROVIDER_INSTANCE_INFO_SIZE: usize = 16;
+/// Size of `TRACE_ENABLE_INFO`
+const TRACE_ENABLE_INFO_SIZE: usize = 32;
+/// Offset of `LoggerId` within `TRACE_ENABLE_INFO`
+const TRACE_ENABLE_INFO_LOGGER_ID_OFFSET: usize = 6;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+/// Logger IDs a provider is enabled on, from a `TraceGuidQueryInfo` buffer.
+///
+/// The buffer is a `TRACE_GUID_INFO` header followed by `InstanceCount`
+/// `TRACE_PROVIDER_INSTANCE_INFO` blocks, each trailed by `EnableCount`
+/// `TRACE_ENABLE_INFO` entries. `NextOffset` is relative to the current
+/// instance and zero on the last one. Truncated buffers yield what could
+/// be read.
+fn parse_guid_info_logger_ids(bytes: &[u8]) -> Vec<u16> {
+    let mut logger_ids = Vec::new();
+    let instance_count = match read_u32(bytes, 0) {
+        Some(count) => count,
+        None => return logger_ids,
+    };
+
+    let mut instance_offset = TRACE_GUID_INFO_SIZE;
+    for _ in 0..instance_count {
+        let (next_offset, enable_count) = match (
+            read_u32(bytes, instance_offset),
+            read_u32(bytes, instance_offset + 4),
+        ) {
+            (Some(next), Some(count)) => (next as usize, count as usize),
+            _ => break,
+        };
+
+        let first_enable = instance_offset + TRACE_PROVIDER_INSTANCE_INFO_SIZE;
+        for i in 0..enable_count {
+            let offset =
+                first_enable + i * TRACE_ENABLE_INFO_SIZE + TRACE_ENABLE_INFO_LOGGER_ID_OFFSET;
+            match read_u16(bytes, offset) {
+                Some(id) if !logger_ids.contains(&id) => logger_ids.push(id),
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        if next_offset == 0 {
+            break;
+        }
+        instance_offset += next_offset;
+    }
+
+    logger_ids
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::collections::HashMap;
+    use std::mem;
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use log::{debug, warn};
+    use winapi::shared::guiddef::GUID;
+    use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_MORE_DATA, ERROR_SUCCESS};
+    use winapi::um::evntrace::{
+        EnumerateTraceGuidsEx, QueryAllTracesW, TraceGuidQueryInfo, TraceGuidQueryList,
+        EVENT_TRACE_PROPERTIES, PEVENT_TRACE_PROPERTIES,
+    };
+
+    use super::{format_guid, parse_guid_info_logger_ids, wide_string_at, EtwSession};
+
+    /// Upper bound on concurrent sessions (the kernel limit is 64)
+    const MAX_SESSIONS: usize = 64;
+    /// Room reserved for each of the logger name and log file name
+    const MAX_NAME_CHARS: usize = 1024;
+
+    pub fn enumerate() -> Result<Vec<EtwSession>> {
+        let props_size = mem::size_of::<EVENT_TRACE_PROPERTIES>();
+        let name_bytes = MAX_NAME_CHARS * mem::size_of::<u16>();
+        let buffer_size = props_size + 2 * name_bytes;
+
+        // u64 storage keeps each EVENT_TRACE_PROPERTIES suitably aligned
+        let mut buffers: Vec<Vec<u64>> = (0..MAX_SESSIONS)
+            .map(|_| vec![0u64; (buffer_size + 7) / 8])
+            .collect();
+        let mut properties: Vec<PEVENT_TRACE_PROPERTIES> = buffers
+            .iter_mut()
+            .map(|buffer| {
+                let props = buffer.as_mut_ptr() as PEVENT_TRACE_PROPERTIES;
+                // SAFETY: the buffer is zeroed and large enough for the header
+                unsafe {
+                    (*props).Wnode.BufferSize = buffer_size as u32;
+                    (*props).LoggerNameOffset = props_size as u32;
+                    (*props).LogFileNameOffset = (props_size + name_bytes) as u32;
+                }
+                props
+            })
+            .collect();
+
+        let mut session_count: u32 = 0;
+        // SAFETY: every pointer refers to a buffer sized as declared in its header
+        let status = unsafe {
+            QueryAllTracesW(
+                properties.as_mut_ptr(),
+                MAX_SESSIONS as u32,
+                &mut session_count,
+            )
+        };
+        if status == ERROR_MORE_DATA {
+            warn!(
+                "More than {} ETW sessions running; listing truncated",
+                MAX_SESSIONS
+            );
+        } else if status != ERROR_SUCCESS {
+            return Err(anyhow!("QueryAllTracesW failed with error {}", status));
+        }
+
+        let providers = providers_by_logger();
+
+        let sessions = buffers
+            .iter()
+            .take(session_count as usize)
+            .map(|buffer| {
+                // SAFETY: the buffer holds an EVENT_TRACE_PROPERTIES filled by QueryAllTracesW
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer_size)
+                };
+                let props = unsafe { &*(buffer.as_ptr() as *const EVENT_TRACE_PROPERTIES) };
+                // The low 16 bits of the session handle are the logger ID
+                let session_id = unsafe { *props.Wnode.u1.HistoricalContext() } as u32 & 0xFFFF;
+
+                EtwSession {
+                    session_name: wide_string_at(bytes, props.LoggerNameOffset as usize)
+                        .unwrap_or_default(),
+                    session_id,
+                    buffer_size: props.BufferSize,
+                    minimum_buffers: props.MinimumBuffers,
+                    maximum_buffers: props.MaximumBuffers,
+                    flush_timer: props.FlushTimer,
+                    enable_flags: props.EnableFlags,
+                    log_filename: wide_string_at(bytes, props.LogFileNameOffset as usize),
+                    providers: providers
+                        .get(&(session_id as u16))
+                        .cloned()
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Map logger ID to the GUIDs of providers enabled on it
+    fn providers_by_logger() -> HashMap<u16, Vec<String>> {
+        let mut map: HashMap<u16, Vec<String>> = HashMap::new();
+
+        let guids = match registered_provider_guids() {
+            Ok(guids) => guids,
+            Err(e) => {
+                warn!("Failed to list ETW providers: {}", e);
+                return map;
+            }
+        };
+
+        for guid in guids {
+            let info = match query_with_retry(|buffer, size, needed| unsafe {
+                // SAFETY: the input is a single GUID; the output buffer is `size` bytes
+                EnumerateTraceGuidsEx(
+                    TraceGuidQueryInfo,
+                    &guid as *const GUID as *mut _,
+                    mem::size_of::<GUID>() as u32,
+                    buffer,
+                    size,
+                    needed,
+                )
+            }) {
+                Ok(info) => info,
+                Err(e) => {
+                    debug!("Skipping provider info query: {}", e);
+                    continue;
+                }
+            };
+
+            let name = format_guid(guid.Data1, guid.Data2, guid.Data3, &guid.Data4);
+            for logger_id in parse_guid_info_logger_ids(&info) {
+                map.entry(logger_id).or_default().push(name.clone());
+            }
+        }
+
+        map
+    }
+
+    /// GUIDs of all registered providers
+    fn registered_provider_guids() -> Result<Vec<GUID>> {
+        let bytes = query_with_retry(|buffer, size, needed| unsafe {
+            // SAFETY: TraceGuidQueryList takes no input; the output buffer is `size` bytes
+            EnumerateTraceGuidsEx(TraceGuidQueryList, ptr::null_mut(), 0, buffer, size, needed)
+        })?;
+
+        Ok(bytes
+            .chunks_exact(mem::size_of::<GUID>())
+            // SAFETY: the buffer is a packed array of GUIDs
+            .map(|chunk| unsafe { ptr::read_unaligned(chunk.as_ptr() as *const GUID) })
+            .collect())
+    }
+
+    /// Call a sizing-style API until the buffer is large enough
+    fn query_with_retry(
+        mut call: impl FnMut(*mut winapi::ctypes::c_void, u32, &mut u32) -> u32,
+    ) -> Result<Vec<u8>> {
+        let mut needed: u32 = 0;
+        let mut buffer: Vec<u8> = Vec::new();
+
+        for _ in 0..4 {
+            let status = call(
+                buffer.as_mut_ptr() as *mut _,
+                buffer.len() as u32,
+                &mut needed,
+            );
+            match status {
+                ERROR_SUCCESS => {
+                    buffer.truncate(needed as usize);
+                    return Ok(buffer);
+                }
+                ERROR_INSUFFICIENT_BUFFER | ERROR_MORE_DATA => {
+                    buffer.resize(needed as usize, 0);
+                }
+                other => return Err(anyhow!("EnumerateTraceGuidsEx failed with error {}", other)),
+            }
+        }
+
+        Err(anyhow!("EnumerateTraceGuidsEx buffer size kept changing"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|u| u.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_wide_string_at() {
+        let mut bytes = vec![0u8; 4];
+        bytes.extend(utf16le("EventLog-System"));
+
+        assert_eq!(
+            wide_string_at(&bytes, 4),
+            Some("EventLog-System".to_string())
+        );
+        // Offset zero means the field is absent
+        assert_eq!(wide_string_at(&bytes, 0), None);
+        assert_eq!(wide_string_at(&bytes, bytes.len()), None);
+        // Empty string at the offset
+        assert_eq!(wide_string_at(&[0, 0, 0, 0], 2), None);
+    }
+
+    #[test]
+    fn test_format_guid() {
+        // Microsoft-Windows-Kernel-Process
+        let guid = format_guid(
+            0x22fb2cd6,
+            0x0e7b,
+            0x422b,
+            &[0xa0, 0xc7, 0x2f, 0xad, 0x1f, 0xd0, 0xe7, 0x16],
+        );
+        assert_eq!(guid, "{22FB2CD6-0E7B-422B-A0C7-2FAD1FD0E716}");
+    }
+
+    fn enable_info(logger_id: u16) -> Vec<u8> {
+        let mut entry = vec![0u8; TRACE_ENABLE_INFO_SIZE];
+        entry[0] = 1; // IsEnabled
+        entry[TRACE_ENABLE_INFO_LOGGER_ID_OFFSET..TRACE_ENABLE_INFO_LOGGER_ID_OFFSET + 2]
+            .copy_from_slice(&logger_id.to_le_bytes());
+        entry
+    }
+
+    fn instance(next_offset: u32, logger_ids: &[u16]) -> Vec<u8> {
+        let mut block = Vec::new();
+        block.extend(next_offset.to_le_bytes());
+        block.extend((logger_ids.len() as u32).to_le_bytes());
+        block.extend(1234u32.to_le_bytes()); // Pid
+        block.extend(0u32.to_le_bytes()); // Flags
+        for id in logger_ids {
+            block.extend(enable_info(*id));
+        }
+        block
+    }
+
+    #[test]
+    fn test_parse_guid_info_multiple_instances() {
+        let first = instance(0, &[3, 7]);
+        let first_len = first.len() as u32;
+        let first = instance(first_len, &[3, 7]);
+        let second = instance(0, &[7, 12]);
+
+        let mut bytes = Vec::new();
+        bytes.extend(2u32.to_le_bytes()); // InstanceCount
+        bytes.extend(0u32.to_le_bytes()); // Reserved
+        bytes.extend(first);
+        bytes.extend(second);
+
+        assert_eq!(parse_guid_info_logger_ids(&bytes), vec![3, 7, 12]);
+    }
+
+    #[test]
+    fn test_parse_guid_info_truncated() {
+        let mut bytes = Vec::new();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(instance(0, &[5, 6]));
+        bytes.truncate(bytes.len() - TRACE_ENABLE_INFO_SIZE);
+
+        assert_eq!(parse_guid_info_logger_ids(&bytes), vec![5]);
+        assert!(parse_guid_info_logger_ids(&[]).is_empty());
+    }
+}
@@ -0,0 +1,693 @@
+//! Program execution evidence from collected registry hives.
+//!
+//! Several registry keys record programs a user ran, independently of
+//! Prefetch and Amcache:
+//!
+//! - `UserAssist` (`NTUSER.DAT`, `Software\Microsoft\Windows\CurrentVersion\Explorer\UserAssist\{GUID}\Count`):
+//!   programs and shortcuts started from Explorer, with ROT13-encoded value
+//!   names and a run count, focus count, focus time and last run time
+//! - `MUICache` (`UsrClass.dat`, `Local Settings\Software\Microsoft\Windows\Shell\MuiCache`;
+//!   `NTUSER.DAT`, `Software\Microsoft\Windows\ShellNoRoam\MUICache` before Vista):
+//!   the display name of every executable whose name Explorer looked up
+//! - `RecentApps` (`NTUSER.DAT`, `Software\Microsoft\Windows\CurrentVersion\Search\RecentApps`,
+//!   Windows 10 1607 to 1709): launch count and last launch per application
+//! - BAM/DAM (`SYSTEM`, `<control set>\Services\bam\State\UserSettings\<SID>`,
+//!   or `bam\UserSettings` before 1809): last execution time per user SID
+//!
+//! Hives are read offline from the collected copies with their `.LOG1` and
+//! `.LOG2` transaction logs replayed, so entries a running system had not
+//! flushed yet are included. Users are named from `ProfileList` in the
+//! collected SOFTWARE hive: BAM keys by SID, user hives by the profile
+//! directory they were collected from.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::bytes::u32_at;
+use crate::collectors::parsers::regf::{Hive, Key, RegValue};
+use crate::collectors::parsers::ParsedReport;
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Report file name
+pub const EXECUTION_EVIDENCE_FILE: &str = "execution_evidence.json";
+
+/// User hive holding UserAssist, RecentApps and the pre-Vista MUICache
+pub const NTUSER_HIVE: &str = "NTUSER.DAT";
+
+/// User class hive holding MUICache
+pub const USRCLASS_HIVE: &str = "UsrClass.dat";
+
+const USERASSIST_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\UserAssist";
+const RECENT_APPS_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Search\RecentApps";
+const MUICACHE_KEYS: [&str; 2] = [
+    r"Local Settings\Software\Microsoft\Windows\Shell\MuiCache",
+    r"Software\Microsoft\Windows\ShellNoRoam\MUICache",
+];
+const PROFILE_LIST_KEY: &str = r"Microsoft\Windows NT\CurrentVersion\ProfileList";
+
+/// Per-SID BAM/DAM keys below `<control set>\Services`, newest layout first
+const BAM_KEYS: [(&str, EvidenceKind); 4] = [
+    (r"bam\State\UserSettings", EvidenceKind::Bam),
+    (r"bam\UserSettings", EvidenceKind::Bam),
+    (r"dam\State\UserSettings", EvidenceKind::Dam),
+    (r"dam\UserSettings", EvidenceKind::Dam),
+];
+
+/// MUICache value name suffixes; only the display name is reported
+const FRIENDLY_APP_NAME: &str = ".FriendlyAppName";
+const APPLICATION_COMPANY: &str = ".ApplicationCompany";
+
+/// Registry key an execution record was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    UserAssist,
+    MuiCache,
+    RecentApps,
+    /// Background Activity Moderator
+    Bam,
+    /// Desktop Activity Moderator
+    Dam,
+}
+
+/// A program recorded as run by one of the execution evidence keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionEvidence {
+    pub kind: EvidenceKind,
+    /// Program path or identifier as recorded; UserAssist names are decoded,
+    /// and BAM/DAM paths use `\Device\HarddiskVolume<n>`
+    pub program: String,
+    pub user_sid: Option<String>,
+    pub username: Option<String>,
+    pub last_executed: Option<DateTime<Utc>>,
+    /// UserAssist run count or RecentApps launch count
+    pub run_count: Option<u32>,
+    /// UserAssist focus count and time, Windows 7 and later
+    pub focus_count: Option<u32>,
+    pub focus_time_ms: Option<u32>,
+    /// MUICache display name of the program
+    pub description: Option<String>,
+    /// Key the record was read from, relative to the hive root
+    pub key: String,
+    /// Collected hive the record was read from
+    pub source: String,
+}
+
+/// Whether `path` is a collected user hive (`NTUSER.DAT` or `UsrClass.dat`)
+pub fn is_user_hive(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        name.eq_ignore_ascii_case(NTUSER_HIVE) || name.eq_ignore_ascii_case(USRCLASS_HIVE)
+    })
+}
+
+/// Decode a ROT13-encoded UserAssist value name
+fn rot13(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+            'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+            _ => c,
+        })
+        .collect()
+}
+
+fn filetime_at(data: &[u8], offset: usize) -> Option<DateTime<Utc>> {
+    let ticks = u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?);
+    match ticks {
+        0 => None,
+        ticks => filetime_ticks_to_utc(ticks),
+    }
+}
+
+/// Counters of a UserAssist value
+#[derive(Debug, PartialEq, Eq)]
+struct UserAssistCounts {
+    run_count: u32,
+    focus_count: Option<u32>,
+    focus_time_ms: Option<u32>,
+    last_executed: Option<DateTime<Utc>>,
+}
+
+/// Decode UserAssist value data: 72 bytes from Windows 7, 16 bytes before
+fn user_assist_counts(data: &[u8]) -> Option<UserAssistCounts> {
+    if data.len() >= 68 {
+        Some(UserAssistCounts {
+            run_count: u32_at(data, 4).ok()?,
+            focus_count: u32_at(data, 8).ok(),
+            focus_time_ms: u32_at(data, 12).ok(),
+            last_executed: filetime_at(data, 60),
+        })
+    } else if data.len() == 16 {
+        // XP and Vista count from 5
+        Some(UserAssistCounts {
+            run_count: u32_at(data, 4).ok()?.saturating_sub(5),
+            focus_count: None,
+            focus_time_ms: None,
+            last_executed: filetime_at(data, 8),
+        })
+    } else {
+        None
+    }
+}
+
+/// The user a hive belongs to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Owner {
+    sid: Option<String>,
+    username: Option<String>,
+}
+
+impl ExecutionEvidence {
+    fn new(kind: EvidenceKind, program: String, owner: &Owner, key: &str, source: &str) -> Self {
+        Self {
+            kind,
+            program,
+            user_sid: owner.sid.clone(),
+            username: owner.username.clone(),
+            last_executed: None,
+            run_count: None,
+            focus_count: None,
+            focus_time_ms: None,
+            description: None,
+            key: key.to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+/// User names by SID from `ProfileList`: the last component of each
+/// profile's `ProfileImagePath`
+fn read_profiles(software: &Hive) -> Result<BTreeMap<String, String>> {
+    let mut profiles = BTreeMap::new();
+    let Some(list) = software.open_key(PROFILE_LIST_KEY)? else {
+        return Ok(profiles);
+    };
+    for profile in list.subkeys()? {
+        let image_path = profile
+            .value("ProfileImagePath")?
+            .and_then(|v| v.as_string());
+        if let Some(username) = image_path
+            .as_deref()
+            .and_then(|path| path.trim_end_matches('\\').rsplit('\\').next())
+            .filter(|name| !name.is_empty())
+        {
+            profiles.insert(profile.name(), username.to_string());
+        }
+    }
+    Ok(profiles)
+}
+
+/// Owner of a user hive, from the directory following `Users` in the path
+/// it was collected to, with the SID looked up in `profiles`
+fn hive_owner(hive: &Path, profiles: &BTreeMap<String, String>) -> Owner {
+    let names: Vec<String> = hive
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    let Some(username) = names
+        .windows(2)
+        .rev()
+        .find(|pair| pair[0].eq_ignore_ascii_case("Users"))
+        .map(|pair| pair[1].clone())
+        .filter(|name| !is_user_hive(Path::new(name)))
+    else {
+        return Owner::default();
+    };
+    let sid = profiles
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(&username))
+        .map(|(sid, _)| sid.clone());
+    Owner {
+        sid,
+        username: Some(username),
+    }
+}
+
+fn user_assist(hive: &Hive, owner: &Owner, source: &str) -> Result<Vec<ExecutionEvidence>> {
+    let mut records = Vec::new();
+    let Some(user_assist) = hive.open_key(USERASSIST_KEY)? else {
+        return Ok(records);
+    };
+    for guid in user_assist.subkeys()? {
+        let Some(count) = guid.subkey("Count")? else {
+            continue;
+        };
+        let key = format!(r"{}\{}\Count", USERASSIST_KEY, guid.name());
+        for value in count.values()? {
+            let program = rot13(&value.name);
+            // Session bookkeeping, not a program
+            if program.starts_with("UEME_CTL") {
+                continue;
+            }
+            let Some(counts) = user_assist_counts(&value.data) else {
+                continue;
+            };
+            let mut record =
+                ExecutionEvidence::new(EvidenceKind::UserAssist, program, owner, &key, source);
+            record.run_count = Some(counts.run_count);
+            record.focus_count = counts.focus_count;
+            record.focus_time_ms = counts.focus_time_ms;
+            record.last_executed = counts.last_executed;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// MUICache display names; resource strings (`@...`) and company names are
+/// skipped
+fn mui_cache(hive: &Hive, owner: &Owner, source: &str) -> Result<Vec<ExecutionEvidence>> {
+    let mut records = Vec::new();
+    for key_path in MUICACHE_KEYS {
+        let Some(key) = hive.open_key(key_path)? else {
+            continue;
+        };
+        for value in key.values()? {
+            if value.name.starts_with('@')
+                || value.name.eq_ignore_ascii_case("LangID")
+                || value.name.ends_with(APPLICATION_COMPANY)
+            {
+                continue;
+            }
+            let program = value
+                .name
+                .strip_suffix(FRIENDLY_APP_NAME)
+                .unwrap_or(&value.name)
+                .to_string();
+            let mut record =
+                ExecutionEvidence::new(EvidenceKind::MuiCache, program, owner, key_path, source);
+            record.description = value.as_string();
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+fn recent_apps(hive: &Hive, owner: &Owner, source: &str) -> Result<Vec<ExecutionEvidence>> {
+    let mut records = Vec::new();
+    let Some(recent) = hive.open_key(RECENT_APPS_KEY)? else {
+        return Ok(records);
+    };
+    for app in recent.subkeys()? {
+        let string = |name: &str| -> Result<Option<String>> {
+            Ok(app.value(name)?.and_then(|v| v.as_string()))
+        };
+        let Some(program) = string("AppPath")?.or(string("AppId")?) else {
+            continue;
+        };
+        let key = format!(r"{}\{}", RECENT_APPS_KEY, app.name());
+        let mut record =
+            ExecutionEvidence::new(EvidenceKind::RecentApps, program, owner, &key, source);
+        record.run_count = app.value("LaunchCount")?.and_then(|v| v.as_dword());
+        record.last_executed = app
+            .value("LastAccessedTime")?
+            .and_then(|v| v.as_qword())
+            .filter(|&ticks| ticks != 0)
+            .and_then(filetime_ticks_to_utc);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Control set the system booted with, from `Select\Current`
+fn current_control_set(system: &Hive) -> Result<String> {
+    let current = match system.open_key("Select")? {
+        Some(select) => select
+            .value("Current")?
+            .and_then(|v| v.as_dword())
+            .unwrap_or(1),
+        None => 1,
+    };
+    Ok(format!("ControlSet{:03}", current))
+}
+
+/// A BAM/DAM program value: the FILETIME of the last execution followed by
+/// padding; `Version` and `SequenceNumber` are DWORDs and skipped
+fn bam_last_executed(value: &RegValue) -> Option<DateTime<Utc>> {
+    (value.data.len() >= 8 && value.as_dword().is_none())
+        .then(|| filetime_at(&value.data, 0))
+        .flatten()
+}
+
+fn bam(
+    system: &Hive,
+    profiles: &BTreeMap<String, String>,
+    source: &str,
+) -> Result<Vec<ExecutionEvidence>> {
+    let mut records = Vec::new();
+    let control_set = current_control_set(system)?;
+    for (path, kind) in BAM_KEYS {
+        let key_path = format!(r"{}\Services\{}", control_set, path);
+        let Some(user_settings) = system.open_key(&key_path)? else {
+            continue;
+        };
+        for user in user_settings.subkeys()? {
+            let sid = user.name();
+            let owner = Owner {
+                username: profiles.get(&sid).cloned(),
+                sid: Some(sid),
+            };
+            records.extend(bam_user(&user, kind, &owner, &key_path, source)?);
+        }
+    }
+    Ok(records)
+}
+
+fn bam_user(
+    user: &Key,
+    kind: EvidenceKind,
+    owner: &Owner,
+    parent: &str,
+    source: &str,
+) -> Result<Vec<ExecutionEvidence>> {
+    let key = format!(r"{}\{}", parent, user.name());
+    Ok(user
+        .values()?
+        .into_iter()
+        .filter_map(|value| {
+            let last_executed = bam_last_executed(&value)?;
+            let mut record = ExecutionEvidence::new(kind, value.name, owner, &key, source);
+            record.last_executed = Some(last_executed);
+            Some(record)
+        })
+        .collect())
+}
+
+/// Open a hive with its transaction logs, noting replayed entries
+fn open_hive(path: &Path, notes: &mut Vec<String>) -> Result<Hive> {
+    let hive = Hive::open_with_logs(path)?;
+    if hive.replayed_entries() > 0 {
+        notes.push(format!(
+            "{}: replayed {} transaction log entries",
+            path.display(),
+            hive.replayed_entries()
+        ));
+    }
+    Ok(hive)
+}
+
+/// Build the `execution_evidence.json` report from the collected user hives
+/// (`NTUSER.DAT`, `UsrClass.dat`) and the SYSTEM and SOFTWARE hives.
+///
+/// A hive that cannot be parsed is noted; the report fails only if none can.
+pub fn parse_execution_evidence(
+    user_hives: &[PathBuf],
+    system: Option<&Path>,
+    software: Option<&Path>,
+) -> Result<ParsedReport<ExecutionEvidence>> {
+    let mut sources = user_hives.to_vec();
+    sources.extend(system.map(Path::to_path_buf));
+    sources.extend(software.map(Path::to_path_buf));
+    let mut report = ParsedReport::new(&sources);
+    let mut failures = Vec::new();
+
+    let profiles = match software.map(|path| (path, open_hive(path, &mut report.notes))) {
+        Some((_, Ok(hive))) => read_profiles(&hive)?,
+        Some((path, Err(e))) => {
+            failures.push(format!("{}: {:#}", path.display(), e));
+            BTreeMap::new()
+        }
+        None => {
+            report
+                .notes
+                .push("SOFTWARE hive not collected; user names are not resolved".to_string());
+            BTreeMap::new()
+        }
+    };
+
+    for path in user_hives {
+        let source = path.to_string_lossy().to_string();
+        let parsed = open_hive(path, &mut report.notes).and_then(|hive| {
+            let owner = hive_owner(path, &profiles);
+            let mut records = user_assist(&hive, &owner, &source)?;
+            records.extend(recent_apps(&hive, &owner, &source)?);
+            records.extend(mui_cache(&hive, &owner, &source)?);
+            Ok(records)
+        });
+        match parsed {
+            Ok(records) => report.records.extend(records),
+            Err(e) => failures.push(format!("{}: {:#}", path.display(), e)),
+        }
+    }
+
+    if let Some(path) = system {
+        let source = path.to_string_lossy().to_string();
+        match open_hive(path, &mut report.notes).and_then(|hive| bam(&hive, &profiles, &source)) {
+            Ok(records) => report.records.extend(records),
+            Err(e) => failures.push(format!("{}: {:#}", path.display(), e)),
+        }
+    }
+
+    if failures.len() == sources.len() {
+        return Err(anyhow!("No hive could be parsed: {}", failures.join("; ")));
+    }
+    report.notes.extend(
+        failures
+            .into_iter()
+            .map(|failure| format!("Hive not parsed: {}", failure)),
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{HiveBuilder, HiveKey};
+    use tempfile::TempDir;
+
+    const ALICE_SID: &str = "S-1-5-21-1111111111-2222222222-3333333333-1001";
+    const BOB_SID: &str = "S-1-5-21-1111111111-2222222222-3333333333-1002";
+
+    /// 2024-01-17T21:20:00Z
+    const TICKS: u64 = 133_500_000_000_000_000;
+
+    fn user_assist_data(run_count: u32, focus_count: u32, focus_ms: u32, ticks: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 72];
+        data[4..8].copy_from_slice(&run_count.to_le_bytes());
+        data[8..12].copy_from_slice(&focus_count.to_le_bytes());
+        data[12..16].copy_from_slice(&focus_ms.to_le_bytes());
+        data[60..68].copy_from_slice(&ticks.to_le_bytes());
+        data
+    }
+
+    fn bam_data(ticks: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 24];
+        data[0..8].copy_from_slice(&ticks.to_le_bytes());
+        data
+    }
+
+    fn write_hive(path: &Path, root: &HiveKey) -> PathBuf {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, HiveBuilder::build(root)).unwrap();
+        path.to_path_buf()
+    }
+
+    fn ntuser(path: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        let count = root
+            .subkey("Software")
+            .subkey("Microsoft")
+            .subkey("Windows")
+            .subkey("CurrentVersion")
+            .subkey("Explorer")
+            .subkey("UserAssist")
+            .subkey("{CEBFF5CD-ACE2-4F4F-9178-9926F41749EA}")
+            .subkey("Count");
+        count
+            .binary_value(
+                &rot13(r"C:\Users\alice\Downloads\mimikatz.exe"),
+                &user_assist_data(3, 2, 65_000, TICKS),
+            )
+            .binary_value(&rot13("UEME_CTLSESSION"), &[0u8; 1612]);
+        root.subkey("Software")
+            .subkey("Microsoft")
+            .subkey("Windows")
+            .subkey("CurrentVersion")
+            .subkey("Search")
+            .subkey("RecentApps")
+            .subkey("{0A1B2C3D-0000-1111-2222-333344445555}")
+            .string_value("AppId", "Microsoft.Windows.Explorer")
+            .string_value("AppPath", r"C:\Windows\explorer.exe")
+            .dword_value("LaunchCount", 12)
+            .qword_value("LastAccessedTime", TICKS);
+        write_hive(path, &root)
+    }
+
+    fn usrclass(path: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        root.subkey("Local Settings")
+            .subkey("Software")
+            .subkey("Microsoft")
+            .subkey("Windows")
+            .subkey("Shell")
+            .subkey("MuiCache")
+            .string_value(r"C:\Tools\psexec.exe.FriendlyAppName", "PsExec")
+            .string_value(r"C:\Tools\psexec.exe.ApplicationCompany", "Sysinternals")
+            .string_value("@C:\\Windows\\system32\\shell32.dll,-8964", "Control Panel")
+            .dword_value("LangID", 0x0409);
+        write_hive(path, &root)
+    }
+
+    fn system(path: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        root.subkey("Select").dword_value("Current", 2);
+        let services = root.subkey("ControlSet002").subkey("Services");
+        services
+            .subkey("bam")
+            .subkey("State")
+            .subkey("UserSettings")
+            .subkey(BOB_SID)
+            .binary_value(
+                r"\Device\HarddiskVolume3\Windows\System32\cmd.exe",
+                &bam_data(TICKS),
+            )
+            .dword_value("Version", 1)
+            .dword_value("SequenceNumber", 9);
+        services
+            .subkey("dam")
+            .subkey("UserSettings")
+            .subkey(ALICE_SID)
+            .binary_value(
+                r"\Device\HarddiskVolume3\Tools\rclone.exe",
+                &bam_data(TICKS),
+            );
+        // Not the current control set
+        root.subkey("ControlSet001")
+            .subkey("Services")
+            .subkey("bam")
+            .subkey("State")
+            .subkey("UserSettings")
+            .subkey(BOB_SID)
+            .binary_value(r"\Device\HarddiskVolume3\stale.exe", &bam_data(TICKS));
+        write_hive(path, &root)
+    }
+
+    fn software(path: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        let list = root
+            .subkey("Microsoft")
+            .subkey("Windows NT")
+            .subkey("CurrentVersion")
+            .subkey("ProfileList");
+        list.subkey(ALICE_SID)
+            .string_value("ProfileImagePath", r"C:\Users\alice");
+        list.subkey(BOB_SID)
+            .string_value("ProfileImagePath", r"C:\Users\bob");
+        write_hive(path, &root)
+    }
+
+    #[test]
+    fn test_rot13() {
+        assert_eq!(
+            rot13("P:\\Jvaqbjf\\flfgrz32\\pzq.rkr"),
+            r"C:\Windows\system32\cmd.exe"
+        );
+        assert_eq!(rot13(&rot13("{6D809377}\\App.exe")), "{6D809377}\\App.exe");
+    }
+
+    #[test]
+    fn test_user_assist_counts() {
+        let counts = user_assist_counts(&user_assist_data(4, 7, 1500, TICKS)).unwrap();
+        assert_eq!(counts.run_count, 4);
+        assert_eq!(counts.focus_count, Some(7));
+        assert_eq!(counts.focus_time_ms, Some(1500));
+        assert_eq!(counts.last_executed, filetime_ticks_to_utc(TICKS));
+
+        let mut xp = vec![0u8; 16];
+        xp[4..8].copy_from_slice(&8u32.to_le_bytes());
+        xp[8..16].copy_from_slice(&TICKS.to_le_bytes());
+        let counts = user_assist_counts(&xp).unwrap();
+        assert_eq!(counts.run_count, 3);
+        assert_eq!(counts.focus_count, None);
+
+        assert_eq!(user_assist_counts(&[0u8; 20]), None);
+    }
+
+    #[test]
+    fn test_parse_execution_evidence() {
+        let temp = TempDir::new().unwrap();
+        let users = temp.path().join("C/Users");
+        let user_hives = vec![
+            ntuser(&users.join("alice/NTUSER.DAT")),
+            usrclass(&users.join("alice/AppData/Local/Microsoft/Windows/UsrClass.dat")),
+        ];
+        let config = temp.path().join("C/Windows/System32/config");
+        let system = system(&config.join("SYSTEM"));
+        let software = software(&config.join("SOFTWARE"));
+
+        let report = parse_execution_evidence(&user_hives, Some(&system), Some(&software)).unwrap();
+        assert!(report.notes.is_empty(), "{:?}", report.notes);
+        let find = |kind: EvidenceKind| -> Vec<&ExecutionEvidence> {
+            report.records.iter().filter(|r| r.kind == kind).collect()
+        };
+
+        let user_assist = find(EvidenceKind::UserAssist);
+        assert_eq!(user_assist.len(), 1);
+        assert_eq!(
+            user_assist[0].program,
+            r"C:\Users\alice\Downloads\mimikatz.exe"
+        );
+        assert_eq!(user_assist[0].run_count, Some(3));
+        assert_eq!(user_assist[0].focus_count, Some(2));
+        assert_eq!(user_assist[0].focus_time_ms, Some(65_000));
+        assert_eq!(user_assist[0].last_executed, filetime_ticks_to_utc(TICKS));
+        assert_eq!(user_assist[0].user_sid.as_deref(), Some(ALICE_SID));
+        assert_eq!(user_assist[0].username.as_deref(), Some("alice"));
+        assert!(user_assist[0].key.ends_with(r"\Count"));
+
+        let recent = find(EvidenceKind::RecentApps);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].program, r"C:\Windows\explorer.exe");
+        assert_eq!(recent[0].run_count, Some(12));
+        assert_eq!(recent[0].last_executed, filetime_ticks_to_utc(TICKS));
+
+        let mui = find(EvidenceKind::MuiCache);
+        assert_eq!(mui.len(), 1);
+        assert_eq!(mui[0].program, r"C:\Tools\psexec.exe");
+        assert_eq!(mui[0].description.as_deref(), Some("PsExec"));
+        assert_eq!(mui[0].user_sid.as_deref(), Some(ALICE_SID));
+
+        let bam = find(EvidenceKind::Bam);
+        assert_eq!(bam.len(), 1);
+        assert_eq!(
+            bam[0].program,
+            r"\Device\HarddiskVolume3\Windows\System32\cmd.exe"
+        );
+        assert_eq!(bam[0].user_sid.as_deref(), Some(BOB_SID));
+        assert_eq!(bam[0].username.as_deref(), Some("bob"));
+        assert!(bam[0].key.starts_with(r"ControlSet002\Services\bam"));
+
+        let dam = find(EvidenceKind::Dam);
+        assert_eq!(dam.len(), 1);
+        assert_eq!(dam[0].username.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_unparseable_hive_is_noted() {
+        let temp = TempDir::new().unwrap();
+        let good = ntuser(&temp.path().join("Users/alice/NTUSER.DAT"));
+        let bad = temp.path().join("Users/bob/NTUSER.DAT");
+        std::fs::create_dir_all(bad.parent().unwrap()).unwrap();
+        std::fs::write(&bad, b"not a hive").unwrap();
+
+        let report = parse_execution_evidence(&[good, bad.clone()], None, None).unwrap();
+        assert_eq!(report.records.len(), 2);
+        // No SOFTWARE hive: the user is named from the path only
+        assert_eq!(report.records[0].username.as_deref(), Some("alice"));
+        assert_eq!(report.records[0].user_sid, None);
+        assert!(report.notes.iter().any(|n| n.contains("SOFTWARE hive")));
+        assert!(report
+            .notes
+            .iter()
+            .any(|n| n.starts_with("Hive not parsed") && n.contains("bob")));
+
+        assert!(parse_execution_evidence(&[bad], None, None).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! IIS web server logs in the W3C Extended Log File Format.
+//!
+//! IIS writes one directory per site (`W3SVC<site id>`) of daily `u_ex*.log`
+//! files. Each file starts with `#` directives; `#Fields:` names the
+//! space-separated columns of the lines that follow and can change part-way
+//! through a file when logging settings change, so it is tracked per line.
+//! `-` marks an empty column, and spaces inside values (the user agent) are
+//! written as `+`. Times are UTC.
+//!
+//! Entries from every collected log are written as JSON lines to
+//! `parsed/iis_events.jsonl`.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::collectors::parsers::PARSED_DIR;
+
+/// Output file name
+pub const IIS_EVENTS_FILE: &str = "iis_events.jsonl";
+
+/// Prefix of the per-site log directories
+pub const SITE_DIR_PREFIX: &str = "W3SVC";
+
+/// A request from an IIS W3C log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IISLogEntry {
+    pub date: String,
+    pub time: String,
+    /// `c-ip`
+    pub client_ip: String,
+    /// `cs-username`, for authenticated requests
+    pub username: Option<String>,
+    /// `s-ip`
+    pub server_ip: String,
+    pub method: String,
+    pub uri_stem: String,
+    pub uri_query: Option<String>,
+    pub status_code: u16,
+    /// `sc-bytes`; 0 when not logged
+    pub bytes_sent: u64,
+    /// `cs-bytes`; 0 when not logged
+    pub bytes_received: u64,
+    pub time_taken_ms: u64,
+    pub user_agent: Option<String>,
+    /// Collected log file the entry was read from
+    pub source: String,
+}
+
+/// Parse a W3C extended log file.
+///
+/// Lines that do not match the current `#Fields:` directive are skipped.
+pub fn parse_iis_w3c(log_path: &Path) -> Result<Vec<IISLogEntry>> {
+    let file = File::open(log_path).context(format!("Failed to open {}", log_path.display()))?;
+    let source = log_path.to_string_lossy().to_string();
+
+    let mut entries = Vec::new();
+    let mut fields: Vec<String> = Vec::new();
+    let mut skipped = 0usize;
+
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line.context(format!("Failed to read {}", log_path.display()))?;
+        // Logs are ASCII in practice; tolerate stray bytes in URIs
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+
+        if let Some(directive) = line.strip_prefix('#') {
+            if let Some(names) = directive.strip_prefix("Fields:") {
+                fields = names.split_whitespace().map(str::to_string).collect();
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_line(&fields, line, &source) {
+            Some(entry) => entries.push(entry),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        debug!(
+            "Skipped {} malformed lines in {}",
+            skipped,
+            log_path.display()
+        );
+    }
+    Ok(entries)
+}
+
+/// Parse one log line against the active field list
+fn parse_line(fields: &[String], line: &str, source: &str) -> Option<IISLogEntry> {
+    let values: Vec<&str> = line.split(' ').collect();
+    if fields.is_empty() || values.len() != fields.len() {
+        return None;
+    }
+
+    let row: HashMap<&str, &str> = fields
+        .iter()
+        .map(String::as_str)
+        .zip(values)
+        .filter(|(_, value)| *value != "-")
+        .collect();
+    let text = |name: &str| row.get(name).map(|v| v.to_string());
+    let number = |name: &str| row.get(name).and_then(|v| v.parse::<u64>().ok());
+
+    Some(IISLogEntry {
+        date: text("date")?,
+        time: text("time")?,
+        client_ip: text("c-ip").unwrap_or_default(),
+        username: text("cs-username"),
+        server_ip: text("s-ip").unwrap_or_default(),
+        method: text("cs-method").unwrap_or_default(),
+        uri_stem: text("cs-uri-stem").unwrap_or_default(),
+        uri_query: text("cs-uri-query"),
+        status_code: row.get("sc-status").and_then(|v| v.parse().ok())?,
+        bytes_sent: number("sc-bytes").unwrap_or(0),
+        bytes_received: number("cs-bytes").unwrap_or(0),
+        time_taken_ms: number("time-taken").unwrap_or(0),
+        user_agent: text("cs(User-Agent)").map(|ua| ua.replace('+', " ")),
+        source: source.to_string(),
+    })
+}
+
+/// Collected `.log` files inside `W3SVC*` site directories
+pub fn find_iis_logs(artifact_dir: &Path) -> Vec<PathBuf> {
+    let parsed_dir = artifact_dir.join(PARSED_DIR);
+    WalkDir::new(artifact_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path() != parsed_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let path = e.path();
+            let is_log = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("log"));
+            let in_site_dir = path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_ascii_uppercase())
+                .is_some_and(|name| name.starts_with(SITE_DIR_PREFIX));
+            is_log && in_site_dir
+        })
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Parse `logs` and write every entry to [`IIS_EVENTS_FILE`] in `output_dir`.
+///
+/// A log that cannot be parsed is logged and skipped.
+pub fn write_iis_events(logs: &[PathBuf], output_dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(output_dir).context(format!("Failed to create {}", output_dir.display()))?;
+    let path = output_dir.join(IIS_EVENTS_FILE);
+    let mut writer = BufWriter::new(
+        File::create(&path).context(format!("Failed to create {}", path.display()))?,
+    );
+
+    let mut total = 0usize;
+    for log in logs {
+        let entries = match parse_iis_w3c(log) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to parse IIS log {}: {:#}", log.display(), e);
+                continue;
+            }
+        };
+        for entry in &entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        total += entries.len();
+    }
+    writer
+        .flush()
+        .context(format!("Failed to write {}", path.display()))?;
+
+    debug!("Wrote {} IIS log entries to {}", total, path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SAMPLE_LOG: &str = "#Software: Microsoft Internet Information Services 10.0\r
+#Version: 1.0\r
+#Date: 2024-01-11 10:00:00\r
+#Fields: date time s-ip cs-method cs-uri-stem cs-uri-query s-port cs-username c-ip cs(User-Agent) cs(Referer) sc-status sc-substatus sc-win32-status time-taken\r
+2024-01-11 10:00:01 10.0.0.5 GET /index.html - 80 - 203.0.113.9 Mozilla/5.0+(Windows+NT+10.0) - 200 0 0 15\r
+2024-01-11 10:00:02 10.0.0.5 POST /upload.aspx cmd=whoami 443 CORP\\alice 198.51.100.7 curl/8.0 - 500 0 0 230\r
+truncated line\r
+#Fields: date time s-ip cs-method cs-uri-stem c-ip sc-status sc-bytes cs-bytes time-taken\r
+2024-01-11 11:00:00 10.0.0.5 GET /shell.aspx 198.51.100.7 200 5120 340 8\r
+";
+
+    fn write_sample(dir: &Path) -> PathBuf {
+        let site = dir.join("Windows-IISLogs/W3SVC1");
+        fs::create_dir_all(&site).unwrap();
+        let path = site.join("u_ex240111.log");
+        fs::write(&path, SAMPLE_LOG).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_iis_w3c() {
+        let temp = TempDir::new().unwrap();
+        let entries = parse_iis_w3c(&write_sample(temp.path())).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].uri_stem, "/index.html");
+        assert_eq!(entries[0].uri_query, None);
+        assert_eq!(entries[0].username, None);
+        assert_eq!(
+            entries[0].user_agent.as_deref(),
+            Some("Mozilla/5.0 (Windows NT 10.0)")
+        );
+        assert_eq!(entries[0].time_taken_ms, 15);
+
+        assert_eq!(entries[1].method, "POST");
+        assert_eq!(entries[1].uri_query.as_deref(), Some("cmd=whoami"));
+        assert_eq!(entries[1].username.as_deref(), Some("CORP\\alice"));
+        assert_eq!(entries[1].client_ip, "198.51.100.7");
+        assert_eq!(entries[1].status_code, 500);
+
+        // Fields redefined part-way through the file
+        assert_eq!(entries[2].bytes_sent, 5120);
+        assert_eq!(entries[2].bytes_received, 340);
+        assert_eq!(entries[2].user_agent, None);
+    }
+
+    #[test]
+    fn test_write_iis_events() {
+        let temp = TempDir::new().unwrap();
+        write_sample(temp.path());
+        fs::write(temp.path().join("unrelated.log"), "not iis").unwrap();
+
+        let logs = find_iis_logs(temp.path());
+        assert_eq!(logs.len(), 1);
+
+        let path = write_iis_events(&logs, &temp.path().join(PARSED_DIR)).unwrap();
+        let lines: Vec<IISLogEntry> = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2].uri_stem, "/shell.aspx");
+    }
+}
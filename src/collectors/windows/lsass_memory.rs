@@ -0,0 +1,138 @@
+//! LSASS process memory for offline credential analysis.
+//!
+//! The Local Security Authority Subsystem (`lsass.exe`) holds the NTLM
+//! hashes and Kerberos tickets of logged-on users, and clear-text passwords
+//! where WDigest or a similar provider is enabled. A full-memory minidump
+//! written with `MiniDumpWriteDump` can be examined offline with the usual
+//! credential analysis tools, so investigators can tell which accounts were
+//! exposed on a compromised host.
+//!
+//! The dump is as sensitive as the credentials in it. It is only written
+//! when the collector is built with the `memory_collection` feature and run
+//! with both `--dump-lsass` and `--force`. Opening LSASS needs
+//! `SeDebugPrivilege`, and fails when LSASS runs as a protected process
+//! (`RunAsPPL`) or under Credential Guard; nothing is written then.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::models::ArtifactMetadata;
+
+/// `dump_method` recorded in the dump's metadata
+pub const LSASS_DUMP_METHOD: &str = "minidump";
+
+/// Image name of the LSASS process
+pub const LSASS_PROCESS_NAME: &str = "lsass.exe";
+
+/// Warning logged before LSASS is opened
+pub const LSASS_DUMP_WARNING: &str = "Dumping LSASS memory: the dump contains NTLM hashes, \
+     Kerberos tickets and possibly clear-text passwords; store and transfer it as you would \
+     the credentials themselves";
+
+/// Pid of the LSASS process among `(pid, image name)` pairs
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn lsass_pid<'a>(processes: impl IntoIterator<Item = (u32, &'a str)>) -> Option<u32> {
+    processes
+        .into_iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(LSASS_PROCESS_NAME))
+        .map(|(pid, _)| pid)
+}
+
+/// Write a full-memory minidump of LSASS to `output_dir/lsass_<pid>.dmp`
+pub fn dump_lsass(output_dir: &Path) -> Result<ArtifactMetadata> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::dump_lsass(output_dir)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = output_dir;
+        Err(anyhow::anyhow!(
+            "LSASS memory can only be dumped on Windows"
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::fs;
+    use std::path::Path;
+
+    use anyhow::{anyhow, Context, Result};
+    use log::{info, warn};
+    use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+
+    use super::{lsass_pid, LSASS_DUMP_METHOD, LSASS_DUMP_WARNING, LSASS_PROCESS_NAME};
+    use crate::collectors::memory::minidump_export::{write_windows_minidump, MiniDumpType};
+    use crate::models::ArtifactMetadata;
+    use crate::windows::enable_named_privilege;
+
+    pub fn dump_lsass(output_dir: &Path) -> Result<ArtifactMetadata> {
+        warn!("{}", LSASS_DUMP_WARNING);
+
+        enable_named_privilege("SeDebugPrivilege")
+            .context("Failed to enable SeDebugPrivilege for the LSASS dump")?;
+
+        let mut system = System::new();
+        system.refresh_processes();
+        let pid = lsass_pid(
+            system
+                .processes()
+                .values()
+                .map(|process| (process.pid().as_u32(), process.name())),
+        )
+        .ok_or_else(|| anyhow!("{} is not running", LSASS_PROCESS_NAME))?;
+
+        fs::create_dir_all(output_dir)
+            .context(format!("Failed to create {}", output_dir.display()))?;
+        let dump_path = output_dir.join(format!("lsass_{}.dmp", pid));
+
+        write_windows_minidump(pid, &dump_path, MiniDumpType::MiniDumpWithFullMemory).context(
+            "Failed to dump LSASS; it may be running as a protected process (RunAsPPL) or \
+             under Credential Guard",
+        )?;
+
+        let file_size = fs::metadata(&dump_path)
+            .context(format!(
+                "Failed to get metadata for {}",
+                dump_path.display()
+            ))?
+            .len();
+        info!(
+            "Dumped LSASS (pid {}) to {} ({} bytes)",
+            pid,
+            dump_path.display(),
+            file_size
+        );
+
+        Ok(ArtifactMetadata {
+            original_path: format!("{} (pid {})", LSASS_PROCESS_NAME, pid),
+            collection_time: chrono::Utc::now().to_rfc3339(),
+            file_size,
+            created_time: None,
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            dump_method: Some(LSASS_DUMP_METHOD.to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsass_pid() {
+        let processes = [(4, "System"), (712, "LSASS.EXE"), (800, "lsaiso.exe")];
+        assert_eq!(lsass_pid(processes), Some(712));
+        assert_eq!(lsass_pid([(4, "System")]), None);
+
+        #[cfg(not(target_os = "windows"))]
+        assert!(dump_lsass(Path::new("/tmp")).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! Windows-specific collectors that query live system state rather than
+//! copying files, and parsers for Windows user activity artifacts.
+
+/// Installed Chrome, Edge and Firefox extensions, with broad permissions flagged
+pub mod browser_extensions;
+
+/// Process creation trees from Security event 4688
+pub mod event_correlation;
+
+/// Timeline gaps between adjacent records of collected event logs
+pub mod event_log_gaps;
+
+/// ETW trace session enumeration
+pub mod event_tracing;
+
+/// UserAssist, MUICache, RecentApps and BAM/DAM execution evidence from
+/// collected hives
+pub mod execution_evidence;
+
+/// IIS W3C web server log parsing
+pub mod iis_logs;
+
+/// LSASS memory minidumps for offline credential analysis
+#[cfg(feature = "memory_collection")]
+pub mod lsass_memory;
+
+/// SMB shares with their permissions, and connected network resources
+pub mod network_shares;
+
+/// Office File MRU lists from user hives
+pub mod office_mru;
+
+/// [`office_mru`] spelled out
+#[allow(unused_imports)]
+pub use office_mru as office_most_recent_files;
+
+/// Office startup files (XLSTART, Word STARTUP, Normal.dotm) and their macros
+pub mod office_macros;
+
+/// Alias of [`office_macros`] named for the persistence it detects
+#[allow(unused_imports)]
+pub use office_macros as autorun_via_office_macros;
+
+/// Page, swap and hibernation files, with the hibernation header parsed
+pub mod paging_files;
+
+/// PowerShell history, profiles and transcripts of every user
+pub mod powershell_history;
+
+/// ShellBags folder access history from user hives
+pub mod shellbags;
@@ -0,0 +1,666 @@
+//! SMB share and mapped drive enumeration.
+//!
+//! Shares are a common path for lateral movement and staging data for
+//! exfiltration, and attackers create them (or loosen their permissions)
+//! rather than leaving files on disk. Shares come from `NetShareEnum` at
+//! level 502, which carries the share's security descriptor; without
+//! administrator rights only level 1 (name, type, comment) is available.
+//! Share permissions are read from the self-relative security descriptor,
+//! and a share granting Everyone full control is flagged.
+//!
+//! Network resources the collecting user is connected to (mapped drives
+//! and UNC connections) come from `WNetOpenEnum`. Both lists are written
+//! to `network_shares.json`.
+
+// The descriptor parsing helpers are only called from the Windows implementation
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// File name used for the share listing
+pub const NETWORK_SHARES_FILE: &str = "network_shares.json";
+
+/// SID of the Everyone group
+const EVERYONE_SID: &str = "S-1-1-0";
+/// Share permission "Full Control" (`FILE_ALL_ACCESS`)
+const SHARE_FULL_CONTROL: u32 = 0x001F_01FF;
+/// Share permission "Change"
+const SHARE_CHANGE: u32 = 0x0013_01BF;
+/// Share permission "Read"
+const SHARE_READ: u32 = 0x0012_00A9;
+const GENERIC_ALL: u32 = 0x1000_0000;
+
+/// `SECURITY_DESCRIPTOR_CONTROL` flags
+const SE_DACL_PRESENT: u16 = 0x0004;
+const SE_SELF_RELATIVE: u16 = 0x8000;
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+
+/// Share type values (`STYPE_*`) in the low byte of `shi_type`
+const STYPE_MASK: u32 = 0xFF;
+const STYPE_SPECIAL: u32 = 0x8000_0000;
+const STYPE_TEMPORARY: u32 = 0x4000_0000;
+/// `max_uses` value for shares without a connection limit
+const SHI_USES_UNLIMITED: u32 = u32::MAX;
+
+/// A share published by this host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShareInfo {
+    pub name: String,
+    /// Local path being shared; absent without administrator rights
+    pub path: Option<String>,
+    /// `disk`, `print_queue`, `device` or `ipc`, with a `special_` prefix for
+    /// administrative shares (`C$`, `ADMIN$`, `IPC$`)
+    pub share_type: String,
+    pub comment: Option<String>,
+    pub current_connections: Option<u32>,
+    /// Connection limit; absent when unlimited or unknown
+    pub max_connections: Option<u32>,
+    /// Share-level ACEs, in DACL order
+    pub permissions: Vec<ShareAce>,
+    /// Everyone is granted full control
+    pub overly_permissive: bool,
+}
+
+/// One entry of a share's DACL
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShareAce {
+    /// `DOMAIN\name` when the SID resolves, the SID string otherwise
+    pub trustee: String,
+    pub sid: String,
+    pub access_mask: u32,
+    /// `full_control`, `change` or `read` when the mask is exactly one of
+    /// the share permission sets
+    pub rights: Option<String>,
+    /// `allow`, `deny` or the numeric ACE type
+    pub ace_type: String,
+}
+
+/// A network resource the collecting user is connected to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConnectedResource {
+    /// Drive letter (`Z:`) for mapped drives
+    pub local_name: Option<String>,
+    /// UNC path (`\\server\share`)
+    pub remote_name: Option<String>,
+    pub provider: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Contents of `network_shares.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkSharesReport {
+    pub shares: Vec<ShareInfo>,
+    pub connected_resources: Vec<ConnectedResource>,
+}
+
+/// Enumerate the shares published by this host, with their permissions
+pub fn enumerate_shares() -> Result<Vec<ShareInfo>> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::enumerate_shares()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err(crate::errors::CollectorError::PlatformNotSupported {
+            artifact_type: "SMB share enumeration".to_string(),
+            platform: std::env::consts::OS.to_string(),
+        }
+        .into())
+    }
+}
+
+/// Enumerate the network resources the collecting user is connected to
+pub fn enumerate_connected_resources() -> Result<Vec<ConnectedResource>> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_impl::enumerate_connected_resources()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err(crate::errors::CollectorError::PlatformNotSupported {
+            artifact_type: "network connection enumeration".to_string(),
+            platform: std::env::consts::OS.to_string(),
+        }
+        .into())
+    }
+}
+
+/// Shares and connected resources; a failure to list connections is logged
+/// and leaves that list empty
+pub fn collect_network_shares() -> Result<NetworkSharesReport> {
+    let shares = enumerate_shares()?;
+    let connected_resources = enumerate_connected_resources().unwrap_or_else(|e| {
+        log::warn!("Failed to enumerate network connections: {}", e);
+        Vec::new()
+    });
+    Ok(NetworkSharesReport {
+        shares,
+        connected_resources,
+    })
+}
+
+/// Name for a `shi_type` value
+fn share_type_name(share_type: u32) -> String {
+    let mut name = match share_type & STYPE_MASK {
+        0 => "disk".to_string(),
+        1 => "print_queue".to_string(),
+        2 => "device".to_string(),
+        3 => "ipc".to_string(),
+        other => other.to_string(),
+    };
+    if share_type & STYPE_TEMPORARY != 0 {
+        name = format!("temporary_{}", name);
+    }
+    if share_type & STYPE_SPECIAL != 0 {
+        name = format!("special_{}", name);
+    }
+    name
+}
+
+/// Connection limit, or `None` for unlimited
+fn max_connections(max_uses: u32) -> Option<u32> {
+    (max_uses != SHI_USES_UNLIMITED).then_some(max_uses)
+}
+
+/// Share permission set named by `mask`, if it is exactly one
+fn share_rights(mask: u32) -> Option<String> {
+    let rights = match mask {
+        SHARE_FULL_CONTROL | GENERIC_ALL => "full_control",
+        SHARE_CHANGE => "change",
+        SHARE_READ => "read",
+        _ => return None,
+    };
+    Some(rights.to_string())
+}
+
+/// Whether `aces` grant Everyone full control
+fn is_overly_permissive(aces: &[ShareAce]) -> bool {
+    aces.iter().any(|ace| {
+        ace.ace_type == "allow"
+            && ace.sid == EVERYONE_SID
+            && (ace.access_mask & SHARE_FULL_CONTROL == SHARE_FULL_CONTROL
+                || ace.access_mask & GENERIC_ALL != 0)
+    })
+}
+
+/// An ACE read from a security descriptor, before its SID is resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawAce {
+    ace_type: u8,
+    access_mask: u32,
+    /// Binary SID, as passed to `LookupAccountSid`
+    sid: Vec<u8>,
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+/// Length of the binary SID at the start of `bytes`
+fn sid_len(bytes: &[u8]) -> Option<usize> {
+    let count = *bytes.get(1)? as usize;
+    let len = 8 + 4 * count;
+    (bytes.len() >= len).then_some(len)
+}
+
+/// `S-1-5-21-...` form of a binary SID
+fn sid_to_string(sid: &[u8]) -> Option<String> {
+    let len = sid_len(sid)?;
+    let authority = sid[2..8]
+        .iter()
+        .fold(0u64, |value, &b| (value << 8) | b as u64);
+    let mut text = format!("S-{}-{}", sid[0], authority);
+    for offset in (8..len).step_by(4) {
+        text.push_str(&format!("-{}", read_u32(sid, offset)?));
+    }
+    Some(text)
+}
+
+/// DACL entries of a self-relative security descriptor.
+///
+/// Returns `None` for a NULL DACL (or none at all), which places no
+/// restriction on the share. Only allow and deny ACEs carry a SID at a
+/// fixed offset; other ACE types are skipped.
+fn parse_dacl(descriptor: &[u8]) -> Result<Option<Vec<RawAce>>> {
+    let control = match read_u16(descriptor, 2) {
+        Some(control) => control,
+        None => bail!("Security descriptor is truncated"),
+    };
+    if control & SE_SELF_RELATIVE == 0 {
+        bail!("Security descriptor is not self-relative");
+    }
+    let dacl_offset = read_u32(descriptor, 16).unwrap_or(0) as usize;
+    if control & SE_DACL_PRESENT == 0 || dacl_offset == 0 {
+        return Ok(None);
+    }
+
+    let (Some(acl_size), Some(ace_count)) = (
+        read_u16(descriptor, dacl_offset + 2),
+        read_u16(descriptor, dacl_offset + 4),
+    ) else {
+        bail!("DACL header is truncated");
+    };
+    let acl_end = (dacl_offset + acl_size as usize).min(descriptor.len());
+
+    let mut aces = Vec::new();
+    let mut offset = dacl_offset + 8;
+    for _ in 0..ace_count {
+        let (Some(&ace_type), Some(ace_size)) =
+            (descriptor.get(offset), read_u16(descriptor, offset + 2))
+        else {
+            break;
+        };
+        let ace_end = offset + ace_size as usize;
+        if ace_size < 8 || ace_end > acl_end {
+            break;
+        }
+        if ace_type == ACCESS_ALLOWED_ACE_TYPE || ace_type == ACCESS_DENIED_ACE_TYPE {
+            let sid = &descriptor[offset + 8..ace_end];
+            if let (Some(access_mask), Some(len)) = (read_u32(descriptor, offset + 4), sid_len(sid))
+            {
+                aces.push(RawAce {
+                    ace_type,
+                    access_mask,
+                    sid: sid[..len].to_vec(),
+                });
+            }
+        }
+        offset = ace_end;
+    }
+    Ok(Some(aces))
+}
+
+/// Share ACEs for a security descriptor, naming trustees with `resolve`
+/// (SID bytes to `DOMAIN\name`). A NULL DACL is reported as Everyone with
+/// full control, which is what it grants.
+fn share_aces(
+    descriptor: &[u8],
+    resolve: impl Fn(&[u8]) -> Option<String>,
+) -> Result<Vec<ShareAce>> {
+    let Some(raw) = parse_dacl(descriptor)? else {
+        return Ok(vec![ShareAce {
+            trustee: "Everyone".to_string(),
+            sid: EVERYONE_SID.to_string(),
+            access_mask: SHARE_FULL_CONTROL,
+            rights: share_rights(SHARE_FULL_CONTROL),
+            ace_type: "allow".to_string(),
+        }]);
+    };
+
+    Ok(raw
+        .into_iter()
+        .map(|ace| {
+            let sid = sid_to_string(&ace.sid).unwrap_or_default();
+            ShareAce {
+                trustee: resolve(&ace.sid).unwrap_or_else(|| sid.clone()),
+                sid,
+                access_mask: ace.access_mask,
+                rights: share_rights(ace.access_mask),
+                ace_type: match ace.ace_type {
+                    ACCESS_ALLOWED_ACE_TYPE => "allow".to_string(),
+                    ACCESS_DENIED_ACE_TYPE => "deny".to_string(),
+                    other => other.to_string(),
+                },
+            }
+        })
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+mod windows_impl {
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+    use log::{debug, warn};
+    use winapi::shared::lmcons::MAX_PREFERRED_LENGTH;
+    use winapi::shared::minwindef::{DWORD, LPBYTE};
+    use winapi::shared::winerror::{
+        ERROR_ACCESS_DENIED, ERROR_MORE_DATA, ERROR_NO_MORE_ITEMS, NO_ERROR,
+    };
+    use winapi::um::lmapibuf::NetApiBufferFree;
+    use winapi::um::lmshare::{NetShareEnum, SHARE_INFO_1, SHARE_INFO_502};
+    use winapi::um::securitybaseapi::{GetSecurityDescriptorLength, IsValidSecurityDescriptor};
+    use winapi::um::winbase::LookupAccountSidW;
+    use winapi::um::winnetwk::{
+        WNetCloseEnum, WNetEnumResourceW, WNetOpenEnumW, NETRESOURCEW, RESOURCETYPE_ANY,
+        RESOURCE_CONNECTED,
+    };
+    use winapi::um::winnt::{HANDLE, LPWSTR, SID_NAME_USE};
+
+    use super::{
+        is_overly_permissive, max_connections, share_aces, share_type_name, ConnectedResource,
+        ShareInfo,
+    };
+
+    /// Size of each `WNetEnumResourceW` buffer
+    const ENUM_BUFFER_SIZE: usize = 16 * 1024;
+
+    fn wide_to_string(ptr: LPWSTR) -> Option<String> {
+        if ptr.is_null() {
+            return None;
+        }
+        // SAFETY: the API returned a NUL-terminated UTF-16 string
+        let len = unsafe { (0..).take_while(|&i| *ptr.add(i) != 0).count() };
+        let units = unsafe { std::slice::from_raw_parts(ptr, len) };
+        Some(String::from_utf16_lossy(units)).filter(|s| !s.is_empty())
+    }
+
+    pub fn enumerate_shares() -> Result<Vec<ShareInfo>> {
+        match net_share_enum(502) {
+            Ok(shares) => Ok(shares),
+            Err(status) if status == ERROR_ACCESS_DENIED => {
+                warn!("Listing share paths and permissions needs administrator rights");
+                net_share_enum(1)
+                    .map_err(|status| anyhow!("NetShareEnum failed with error {}", status))
+            }
+            Err(status) => Err(anyhow!("NetShareEnum failed with error {}", status)),
+        }
+    }
+
+    /// Shares at `level` (1 or 502), or the failing status
+    fn net_share_enum(level: DWORD) -> std::result::Result<Vec<ShareInfo>, DWORD> {
+        let mut buffer: LPBYTE = ptr::null_mut();
+        let mut entries_read: DWORD = 0;
+        let mut total_entries: DWORD = 0;
+        // SAFETY: the output pointers are valid; the buffer is freed below
+        let status = unsafe {
+            NetShareEnum(
+                ptr::null_mut(),
+                level,
+                &mut buffer,
+                MAX_PREFERRED_LENGTH,
+                &mut entries_read,
+                &mut total_entries,
+                ptr::null_mut(),
+            )
+        };
+        if status != NO_ERROR && status != ERROR_MORE_DATA {
+            return Err(status);
+        }
+        if status == ERROR_MORE_DATA {
+            warn!("Only {} of {} shares returned", entries_read, total_entries);
+        }
+
+        let mut shares = Vec::with_capacity(entries_read as usize);
+        for i in 0..entries_read as usize {
+            // SAFETY: the buffer holds `entries_read` structures of `level`
+            let share = unsafe {
+                if level == 502 {
+                    share_from_502(&*(buffer as *const SHARE_INFO_502).add(i))
+                } else {
+                    share_from_1(&*(buffer as *const SHARE_INFO_1).add(i))
+                }
+            };
+            shares.push(share);
+        }
+        if !buffer.is_null() {
+            // SAFETY: allocated by NetShareEnum
+            unsafe { NetApiBufferFree(buffer as *mut _) };
+        }
+        Ok(shares)
+    }
+
+    fn share_from_1(info: &SHARE_INFO_1) -> ShareInfo {
+        ShareInfo {
+            name: wide_to_string(info.shi1_netname).unwrap_or_default(),
+            path: None,
+            share_type: share_type_name(info.shi1_type),
+            comment: wide_to_string(info.shi1_remark),
+            current_connections: None,
+            max_connections: None,
+            permissions: Vec::new(),
+            overly_permissive: false,
+        }
+    }
+
+    fn share_from_502(info: &SHARE_INFO_502) -> ShareInfo {
+        let name = wide_to_string(info.shi502_netname).unwrap_or_default();
+        let descriptor = info.shi502_security_descriptor;
+        // Administrative shares have no descriptor and are admin-only
+        let permissions = if descriptor.is_null()
+            // SAFETY: non-null descriptor returned by NetShareEnum
+            || unsafe { IsValidSecurityDescriptor(descriptor) } == 0
+        {
+            Vec::new()
+        } else {
+            // SAFETY: a valid self-relative descriptor of the reported length
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    descriptor as *const u8,
+                    GetSecurityDescriptorLength(descriptor) as usize,
+                )
+            };
+            share_aces(bytes, lookup_account).unwrap_or_else(|e| {
+                debug!("Cannot read permissions of share {}: {}", name, e);
+                Vec::new()
+            })
+        };
+
+        ShareInfo {
+            path: wide_to_string(info.shi502_path),
+            share_type: share_type_name(info.shi502_type),
+            comment: wide_to_string(info.shi502_remark),
+            current_connections: Some(info.shi502_current_uses),
+            max_connections: max_connections(info.shi502_max_uses),
+            overly_permissive: is_overly_permissive(&permissions),
+            permissions,
+            name,
+        }
+    }
+
+    /// `DOMAIN\name` for a binary SID
+    fn lookup_account(sid: &[u8]) -> Option<String> {
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let mut name_len = name.len() as DWORD;
+        let mut domain_len = domain.len() as DWORD;
+        let mut sid_use: SID_NAME_USE = 0;
+        // SAFETY: `sid` is a complete binary SID and the buffers match their lengths
+        let ok = unsafe {
+            LookupAccountSidW(
+                ptr::null(),
+                sid.as_ptr() as *mut _,
+                name.as_mut_ptr(),
+                &mut name_len,
+                domain.as_mut_ptr(),
+                &mut domain_len,
+                &mut sid_use,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let name = String::from_utf16_lossy(&name[..name_len as usize]);
+        let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+        Some(if domain.is_empty() {
+            name
+        } else {
+            format!("{}\\{}", domain, name)
+        })
+    }
+
+    pub fn enumerate_connected_resources() -> Result<Vec<ConnectedResource>> {
+        let mut handle: HANDLE = ptr::null_mut();
+        // SAFETY: a null resource enumerates from the root; handle is closed below
+        let status = unsafe {
+            WNetOpenEnumW(
+                RESOURCE_CONNECTED,
+                RESOURCETYPE_ANY,
+                0,
+                ptr::null_mut(),
+                &mut handle,
+            )
+        };
+        if status != NO_ERROR {
+            return Err(anyhow!("WNetOpenEnum failed with error {}", status));
+        }
+
+        // u64 storage keeps the NETRESOURCEW array aligned
+        let mut buffer = vec![0u64; ENUM_BUFFER_SIZE / 8];
+        let mut resources = Vec::new();
+        let result = loop {
+            let mut count: DWORD = DWORD::MAX;
+            let mut size = ENUM_BUFFER_SIZE as DWORD;
+            // SAFETY: the buffer is `size` bytes
+            let status = unsafe {
+                WNetEnumResourceW(handle, &mut count, buffer.as_mut_ptr() as *mut _, &mut size)
+            };
+            match status {
+                NO_ERROR => {
+                    let entries = buffer.as_ptr() as *const NETRESOURCEW;
+                    for i in 0..count as usize {
+                        // SAFETY: WNetEnumResourceW wrote `count` entries
+                        let entry = unsafe { &*entries.add(i) };
+                        resources.push(ConnectedResource {
+                            local_name: wide_to_string(entry.lpLocalName),
+                            remote_name: wide_to_string(entry.lpRemoteName),
+                            provider: wide_to_string(entry.lpProvider),
+                            comment: wide_to_string(entry.lpComment),
+                        });
+                    }
+                }
+                ERROR_NO_MORE_ITEMS => break Ok(resources),
+                other => break Err(anyhow!("WNetEnumResource failed with error {}", other)),
+            }
+        };
+
+        // SAFETY: opened by WNetOpenEnumW above
+        unsafe { WNetCloseEnum(handle) };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Binary SID from authority and sub-authorities
+    fn sid(authority: u8, subs: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![1, subs.len() as u8, 0, 0, 0, 0, 0, authority];
+        for sub in subs {
+            bytes.extend(sub.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn ace(ace_type: u8, mask: u32, sid: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![ace_type, 0];
+        bytes.extend(((8 + sid.len()) as u16).to_le_bytes());
+        bytes.extend(mask.to_le_bytes());
+        bytes.extend(sid);
+        bytes
+    }
+
+    /// Self-relative descriptor with a DACL of `aces`, or a NULL DACL
+    fn descriptor(aces: Option<&[Vec<u8>]>) -> Vec<u8> {
+        let mut control = SE_SELF_RELATIVE;
+        let mut bytes = vec![1, 0, 0, 0];
+        bytes.extend([0u8; 16]);
+        if let Some(aces) = aces {
+            control |= SE_DACL_PRESENT;
+            let body: Vec<u8> = aces.concat();
+            bytes[16..20].copy_from_slice(&20u32.to_le_bytes());
+            bytes.extend([2, 0]);
+            bytes.extend(((8 + body.len()) as u16).to_le_bytes());
+            bytes.extend((aces.len() as u16).to_le_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(body);
+        }
+        bytes[2..4].copy_from_slice(&control.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_sid_to_string() {
+        assert_eq!(sid_to_string(&sid(1, &[0])).unwrap(), "S-1-1-0");
+        assert_eq!(
+            sid_to_string(&sid(5, &[21, 1004336348, 1177238915, 682003330, 512])).unwrap(),
+            "S-1-5-21-1004336348-1177238915-682003330-512"
+        );
+        assert_eq!(sid_to_string(&[1, 2, 0, 0, 0, 0, 0, 5, 32, 0]), None);
+    }
+
+    #[test]
+    fn test_share_aces_flags_everyone_full_control() {
+        let everyone = sid(1, &[0]);
+        let admins = sid(5, &[32, 544]);
+        let bytes = descriptor(Some(&[
+            ace(ACCESS_ALLOWED_ACE_TYPE, SHARE_FULL_CONTROL, &everyone),
+            ace(ACCESS_DENIED_ACE_TYPE, SHARE_CHANGE, &admins),
+        ]));
+        let resolve = |sid: &[u8]| {
+            (sid_to_string(sid).as_deref() == Some("S-1-5-32-544"))
+                .then(|| "BUILTIN\\Administrators".to_string())
+        };
+
+        let aces = share_aces(&bytes, resolve).unwrap();
+        assert_eq!(
+            aces,
+            vec![
+                ShareAce {
+                    trustee: "S-1-1-0".to_string(),
+                    sid: "S-1-1-0".to_string(),
+                    access_mask: SHARE_FULL_CONTROL,
+                    rights: Some("full_control".to_string()),
+                    ace_type: "allow".to_string(),
+                },
+                ShareAce {
+                    trustee: "BUILTIN\\Administrators".to_string(),
+                    sid: "S-1-5-32-544".to_string(),
+                    access_mask: SHARE_CHANGE,
+                    rights: Some("change".to_string()),
+                    ace_type: "deny".to_string(),
+                },
+            ]
+        );
+        assert!(is_overly_permissive(&aces));
+    }
+
+    #[test]
+    fn test_share_aces_read_only_and_null_dacl() {
+        let everyone = sid(1, &[0]);
+        let read_only = descriptor(Some(&[ace(ACCESS_ALLOWED_ACE_TYPE, SHARE_READ, &everyone)]));
+        let aces = share_aces(&read_only, |_| None).unwrap();
+        assert_eq!(aces[0].rights.as_deref(), Some("read"));
+        assert!(!is_overly_permissive(&aces));
+
+        // A NULL DACL grants everyone everything
+        let aces = share_aces(&descriptor(None), |_| None).unwrap();
+        assert!(is_overly_permissive(&aces));
+
+        // Absolute descriptors hold pointers, not offsets
+        assert!(share_aces(&[1, 0, 0, 0], |_| None).is_err());
+    }
+
+    #[test]
+    fn test_truncated_dacl_keeps_complete_aces() {
+        let everyone = sid(1, &[0]);
+        let mut bytes = descriptor(Some(&[
+            ace(ACCESS_ALLOWED_ACE_TYPE, SHARE_READ, &everyone),
+            ace(ACCESS_ALLOWED_ACE_TYPE, SHARE_FULL_CONTROL, &everyone),
+        ]));
+        bytes.truncate(bytes.len() - 4);
+        let aces = share_aces(&bytes, |_| None).unwrap();
+        assert_eq!(aces.len(), 1);
+        assert!(!is_overly_permissive(&aces));
+    }
+
+    #[test]
+    fn test_share_type_name() {
+        assert_eq!(share_type_name(0), "disk");
+        assert_eq!(share_type_name(STYPE_SPECIAL), "special_disk");
+        assert_eq!(share_type_name(STYPE_SPECIAL | 3), "special_ipc");
+        assert_eq!(share_type_name(1), "print_queue");
+        assert_eq!(max_connections(SHI_USES_UNLIMITED), None);
+        assert_eq!(max_connections(10), Some(10));
+    }
+}
@@ -0,0 +1,546 @@
+//! Office startup files used for macro persistence.
+//!
+//! Word loads every template in `%APPDATA%\Microsoft\Word\STARTUP` and the
+//! global `Normal.dotm` on start, and Excel opens every workbook in
+//! `%APPDATA%\Microsoft\Excel\XLSTART` (including the personal macro
+//! workbook `PERSONAL.XLSB`). A macro planted in any of them runs each time
+//! the application starts.
+//!
+//! The files are copied raw. For macro-capable documents the VBA project is
+//! also extracted: OOXML documents (`.dotm`, `.xlsb`, `.xlsm`, ...) are ZIP
+//! archives holding a `vbaProject.bin` compound file, legacy documents
+//! (`.dot`, `.xls`, ...) are compound files themselves. The module source
+//! is decompressed (MS-OVBA) and written next to the copy as
+//! `<file>.macro_content.txt`. Extraction failures are written to the
+//! sidecar instead of failing the collection; the raw copy is the evidence.
+
+use std::fs;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::collectors::footprint::create_dir_all_tracked;
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+
+/// Suffix appended to a collected file name for its extracted macros
+pub const MACRO_SIDECAR_SUFFIX: &str = ".macro_content.txt";
+
+/// Startup locations relative to `%APPDATA%\Microsoft`, and where they are
+/// written in the output directory. `PERSONAL.XLSB` lives in `XLSTART`.
+const STARTUP_SOURCES: &[(&str, &str)] = &[
+    (r"Excel\XLSTART", "Excel/XLSTART"),
+    (r"Word\STARTUP", "Word/STARTUP"),
+    (r"Templates\Normal.dotm", "Templates/Normal.dotm"),
+];
+
+/// Extensions of documents that can carry a VBA project
+const MACRO_EXTENSIONS: &[&str] = &[
+    "dotm", "docm", "xlsb", "xlsm", "xltm", "xlam", "dot", "doc", "xls", "xlt", "xla",
+];
+
+/// Compound file header signature
+const CFB_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Compressed container signature byte (MS-OVBA 2.4.1.1.1)
+const CONTAINER_SIGNATURE: u8 = 0x01;
+
+// `dir` stream record ids (MS-OVBA 2.3.4.2)
+const RECORD_PROJECT_VERSION: u16 = 0x0009;
+const RECORD_MODULE_NAME: u16 = 0x0019;
+const RECORD_MODULE_STREAM_NAME: u16 = 0x001A;
+const RECORD_MODULE_TERMINATOR: u16 = 0x002B;
+const RECORD_MODULE_OFFSET: u16 = 0x0031;
+const RECORD_MODULE_STREAM_NAME_UNICODE: u16 = 0x0032;
+const RECORD_MODULE_NAME_UNICODE: u16 = 0x0047;
+
+/// A VBA module and its decompressed source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VbaModule {
+    pub name: String,
+    pub stream_name: String,
+    pub source: String,
+}
+
+/// Copy the Office startup files under `appdata` (a roaming AppData folder)
+///
+/// Returns metadata for every copied file and every macro sidecar written.
+/// Missing locations are skipped, as most users have none of them.
+pub fn collect_from_appdata(appdata: &Path, output_dir: &Path) -> Result<Vec<ArtifactMetadata>> {
+    info!("Collecting Office startup files from {}", appdata.display());
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let fallback = FallbackCollector::new();
+    let mut collected = Vec::new();
+
+    for (relative, dest_name) in STARTUP_SOURCES {
+        let source = relative
+            .split('\\')
+            .fold(appdata.join("Microsoft"), |path, part| path.join(part));
+        if !source.exists() {
+            debug!("{} does not exist", source.display());
+            continue;
+        }
+
+        for (file, dest) in startup_files(&source, &output_dir.join(dest_name)) {
+            match fallback.collect_standard_file(&file, &dest) {
+                Ok(metadata) => collected.push(metadata),
+                Err(e) => {
+                    warn!("Failed to copy {}: {:#}", file.display(), e);
+                    continue;
+                }
+            }
+            if is_macro_document(&file) {
+                match write_macro_sidecar(&file, &dest) {
+                    Ok(sidecar) => collected.push(sidecar),
+                    Err(e) => warn!(
+                        "Failed to write macro content for {}: {:#}",
+                        file.display(),
+                        e
+                    ),
+                }
+            }
+        }
+    }
+
+    info!("Collected {} Office startup files", collected.len());
+    Ok(collected)
+}
+
+/// Files under `source` (a file or directory) with their destinations
+fn startup_files(source: &Path, dest: &Path) -> Vec<(PathBuf, PathBuf)> {
+    if !source.is_dir() {
+        return vec![(source.to_path_buf(), dest.to_path_buf())];
+    }
+    WalkDir::new(source)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let relative = e.path().strip_prefix(source).ok()?.to_path_buf();
+            Some((e.into_path(), dest.join(relative)))
+        })
+        .collect()
+}
+
+/// Whether `path` has the extension of a macro-capable Office document
+pub fn is_macro_document(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .is_some_and(|ext| MACRO_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Extract the macros of `source` into a sidecar next to `dest`
+fn write_macro_sidecar(source: &Path, dest: &Path) -> Result<ArtifactMetadata> {
+    let mut sidecar_name = dest.file_name().unwrap_or_default().to_os_string();
+    sidecar_name.push(MACRO_SIDECAR_SUFFIX);
+    let sidecar = dest.with_file_name(sidecar_name);
+
+    let content = match fs::read(dest)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| extract_macros(&data))
+    {
+        Ok(modules) if modules.is_empty() => "' No VBA project found\n".to_string(),
+        Ok(modules) => format_modules(&modules),
+        Err(e) => format!("' Macro extraction failed: {:#}\n", e),
+    };
+    fs::write(&sidecar, content).context(format!("Failed to write {}", sidecar.display()))?;
+
+    let collection_time = chrono::Utc::now().to_rfc3339();
+    Ok(ArtifactMetadata {
+        original_path: source.to_string_lossy().to_string(),
+        collection_time: collection_time.clone(),
+        file_size: fs::metadata(&sidecar).map(|m| m.len()).unwrap_or(0),
+        created_time: Some(collection_time),
+        accessed_time: None,
+        modified_time: None,
+        is_locked: false,
+        sha256: None,
+        ..Default::default()
+    })
+}
+
+fn format_modules(modules: &[VbaModule]) -> String {
+    let mut out = String::new();
+    for module in modules {
+        out.push_str(&format!(
+            "' ==== Module {} (stream {}) ====\n",
+            module.name, module.stream_name
+        ));
+        out.push_str(module.source.trim_end());
+        out.push_str("\n\n");
+    }
+    out
+}
+
+/// VBA modules of an Office document; empty if it has no VBA project
+///
+/// `data` is either a compound file (legacy formats) or an OOXML archive
+/// whose VBA project is stored in a `vbaProject.bin` part.
+pub fn extract_macros(data: &[u8]) -> Result<Vec<VbaModule>> {
+    if data.starts_with(&CFB_SIGNATURE) {
+        return vba_modules(Cursor::new(data));
+    }
+
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).context("Not a compound file or ZIP archive")?;
+    let mut modules = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if !entry
+            .name()
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| name.eq_ignore_ascii_case("vbaProject.bin"))
+        {
+            continue;
+        }
+        let mut project = Vec::new();
+        entry
+            .read_to_end(&mut project)
+            .context(format!("Failed to read {}", entry.name()))?;
+        modules.extend(vba_modules(Cursor::new(project))?);
+    }
+    Ok(modules)
+}
+
+/// Modules listed in the `dir` stream of the VBA project in compound file
+/// `data`; the module streams are stored next to `dir`
+fn vba_modules<F: Read + Seek>(data: F) -> Result<Vec<VbaModule>> {
+    let mut file = cfb::CompoundFile::open(data).context("Failed to open compound file")?;
+    let Some(dir_path) = file
+        .walk()
+        .find(|entry| entry.is_stream() && entry.name().eq_ignore_ascii_case("dir"))
+        .map(|entry| entry.path().to_path_buf())
+    else {
+        return Ok(Vec::new());
+    };
+    let storage = dir_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+    let dir = decompress(&read_stream(&mut file, &dir_path)?)
+        .context("Failed to decompress dir stream")?;
+
+    let mut modules = Vec::new();
+    for entry in parse_dir(&dir)? {
+        let stream = read_stream(&mut file, &storage.join(&entry.stream_name))?;
+        let compressed = stream.get(entry.offset..).ok_or_else(|| {
+            anyhow!(
+                "Module {} source offset {} is past the end of its stream",
+                entry.name,
+                entry.offset
+            )
+        })?;
+        let source = decompress(compressed)
+            .context(format!("Failed to decompress module {}", entry.name))?;
+        modules.push(VbaModule {
+            name: entry.name,
+            stream_name: entry.stream_name,
+            source: decode_text(source),
+        });
+    }
+    Ok(modules)
+}
+
+fn read_stream<F: Read + Seek>(file: &mut cfb::CompoundFile<F>, path: &Path) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    file.open_stream(path)
+        .and_then(|mut stream| stream.read_to_end(&mut data))
+        .context(format!("Failed to read stream {}", path.display()))?;
+    Ok(data)
+}
+
+/// A module record of the `dir` stream
+#[derive(Debug, Default)]
+struct DirModule {
+    name: String,
+    stream_name: String,
+    offset: usize,
+}
+
+/// Module records of a decompressed `dir` stream
+fn parse_dir(dir: &[u8]) -> Result<Vec<DirModule>> {
+    let mut modules = Vec::new();
+    let mut current = DirModule::default();
+    let mut pos = 0;
+    while pos + 6 <= dir.len() {
+        let id = u16::from_le_bytes([dir[pos], dir[pos + 1]]);
+        let mut size =
+            u32::from_le_bytes([dir[pos + 2], dir[pos + 3], dir[pos + 4], dir[pos + 5]]) as usize;
+        // The version record's size field says 4 but 6 bytes follow
+        if id == RECORD_PROJECT_VERSION {
+            size = 6;
+        }
+        let data = dir
+            .get(pos + 6..pos + 6 + size)
+            .ok_or_else(|| anyhow!("dir record {:#06x} at {} is truncated", id, pos))?;
+        pos += 6 + size;
+
+        match id {
+            RECORD_MODULE_NAME if current.name.is_empty() => {
+                current.name = decode_text(data.to_vec());
+            }
+            RECORD_MODULE_NAME_UNICODE => current.name = decode_utf16(data),
+            RECORD_MODULE_STREAM_NAME if current.stream_name.is_empty() => {
+                current.stream_name = decode_text(data.to_vec());
+            }
+            RECORD_MODULE_STREAM_NAME_UNICODE => current.stream_name = decode_utf16(data),
+            RECORD_MODULE_OFFSET => {
+                let bytes: [u8; 4] = data
+                    .try_into()
+                    .map_err(|_| anyhow!("Module offset record has {} bytes", data.len()))?;
+                current.offset = u32::from_le_bytes(bytes) as usize;
+            }
+            RECORD_MODULE_TERMINATOR => {
+                let module = std::mem::take(&mut current);
+                if !module.stream_name.is_empty() {
+                    modules.push(module);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(modules)
+}
+
+/// Decompress an MS-OVBA compressed container (2.4.1)
+pub fn decompress(container: &[u8]) -> Result<Vec<u8>> {
+    if container.first() != Some(&CONTAINER_SIGNATURE) {
+        bail!("Not a compressed container (bad signature)");
+    }
+
+    let mut out = Vec::new();
+    let mut pos = 1;
+    while pos + 2 <= container.len() {
+        let header = u16::from_le_bytes([container[pos], container[pos + 1]]);
+        let chunk_size = (header & 0x0FFF) as usize + 3;
+        let end = (pos + chunk_size).min(container.len());
+        let chunk = &container[pos + 2..end];
+        pos = end;
+
+        // Uncompressed chunks hold 4096 raw bytes
+        if header & 0x8000 == 0 {
+            out.extend_from_slice(chunk);
+            continue;
+        }
+
+        let chunk_start = out.len();
+        let mut i = 0;
+        while i < chunk.len() {
+            let flags = chunk[i];
+            i += 1;
+            for bit in 0..8 {
+                if i >= chunk.len() {
+                    break;
+                }
+                if flags & (1 << bit) == 0 {
+                    out.push(chunk[i]);
+                    i += 1;
+                    continue;
+                }
+
+                let token = chunk
+                    .get(i..i + 2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                    .ok_or_else(|| anyhow!("Truncated copy token"))?;
+                i += 2;
+
+                // The offset/length split depends on the position in the chunk
+                let decompressed = out.len() - chunk_start;
+                let mut bit_count = 4;
+                while (1usize << bit_count) < decompressed {
+                    bit_count += 1;
+                }
+                let length = (token & (0xFFFF >> bit_count)) as usize + 3;
+                let offset = (token >> (16 - bit_count)) as usize + 1;
+                if offset > decompressed {
+                    bail!("Copy token points before the start of the chunk");
+                }
+                for _ in 0..length {
+                    out.push(out[out.len() - offset]);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Decode MBCS text: UTF-8 when valid, otherwise Windows-1252 as Latin-1
+fn decode_text(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| e.into_bytes().iter().map(|&b| b as char).collect())
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Compressed container using literal tokens only
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![CONTAINER_SIGNATURE];
+        for chunk in data.chunks(3000) {
+            let mut body = Vec::new();
+            for group in chunk.chunks(8) {
+                body.push(0);
+                body.extend_from_slice(group);
+            }
+            let header = (body.len() + 2 - 3) as u16 | 0x3000 | 0x8000;
+            out.extend_from_slice(&header.to_le_bytes());
+            out.extend(body);
+        }
+        out
+    }
+
+    fn record(id: u16, data: &[u8]) -> Vec<u8> {
+        let mut out = id.to_le_bytes().to_vec();
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    /// A vbaProject.bin with one module whose source follows 4 bytes of p-code
+    fn vba_project(module: &str, source: &str) -> Vec<u8> {
+        // Code page record
+        let mut dir = record(0x0003, &1252u16.to_le_bytes());
+        // Version record: size field 4, six bytes of data
+        dir.extend(record(RECORD_PROJECT_VERSION, &[0; 4]));
+        dir.extend([0, 0]);
+        dir.extend(record(RECORD_MODULE_NAME, module.as_bytes()));
+        dir.extend(record(RECORD_MODULE_STREAM_NAME, module.as_bytes()));
+        dir.extend(record(RECORD_MODULE_OFFSET, &4u32.to_le_bytes()));
+        dir.extend(record(RECORD_MODULE_TERMINATOR, &[]));
+
+        let mut stream = vec![0xAA; 4];
+        stream.extend(compress(source.as_bytes()));
+
+        let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        file.create_storage("/VBA").unwrap();
+        file.create_stream("/VBA/dir")
+            .unwrap()
+            .write_all(&compress(&dir))
+            .unwrap();
+        file.create_stream(format!("/VBA/{}", module))
+            .unwrap()
+            .write_all(&stream)
+            .unwrap();
+        file.flush().unwrap();
+        file.into_inner().into_inner()
+    }
+
+    fn ooxml(part: &str, project: &[u8]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        writer.start_file("[Content_Types].xml", options).unwrap();
+        writer.write_all(b"<Types/>").unwrap();
+        writer.start_file(part, options).unwrap();
+        writer.write_all(project).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_decompress_spec_example() {
+        // MS-OVBA 3.2.3, a chunk mixing literals and copy tokens
+        let compressed = [
+            0x01, 0x2F, 0xB0, 0x00, 0x23, 0x61, 0x61, 0x61, 0x62, 0x63, 0x64, 0x65, 0x82, 0x66,
+            0x00, 0x70, 0x61, 0x67, 0x68, 0x69, 0x6A, 0x01, 0x38, 0x08, 0x61, 0x6B, 0x6C, 0x00,
+            0x30, 0x6D, 0x6E, 0x6F, 0x70, 0x06, 0x71, 0x02, 0x70, 0x04, 0x10, 0x72, 0x73, 0x74,
+            0x75, 0x76, 0x10, 0x77, 0x78, 0x79, 0x7A, 0x00, 0x3C,
+        ];
+        assert_eq!(
+            decompress(&compressed).unwrap(),
+            b"#aaabcdefaaaaghijaaaaaklaaamnopqaaaaaaaaaaaarstuvwxyzaaa"
+        );
+        assert!(decompress(b"\x02abc").is_err());
+    }
+
+    #[test]
+    fn test_extract_macros() {
+        let source = "Attribute VB_Name = \"ThisWorkbook\"\r\nSub Workbook_Open()\r\n    Shell \"calc.exe\"\r\nEnd Sub\r\n";
+        let project = vba_project("ThisWorkbook", source);
+
+        // Legacy documents are the compound file itself
+        let modules = extract_macros(&project).unwrap();
+        assert_eq!(
+            modules,
+            vec![VbaModule {
+                name: "ThisWorkbook".into(),
+                stream_name: "ThisWorkbook".into(),
+                source: source.into(),
+            }]
+        );
+
+        // OOXML documents carry it as a part
+        let xlsb = ooxml("xl/vbaProject.bin", &project);
+        assert_eq!(extract_macros(&xlsb).unwrap(), modules);
+
+        let without_macros = ooxml("xl/workbook.bin", b"workbook");
+        assert!(extract_macros(&without_macros).unwrap().is_empty());
+        assert!(extract_macros(b"plain text").is_err());
+    }
+
+    #[test]
+    fn test_collect_from_appdata() {
+        let appdata = TempDir::new().unwrap();
+        let output = TempDir::new().unwrap();
+        let microsoft = appdata.path().join("Microsoft");
+
+        let xlstart = microsoft.join("Excel").join("XLSTART");
+        fs::create_dir_all(&xlstart).unwrap();
+        let project = vba_project("Module1", "Sub Auto_Open()\r\nEnd Sub\r\n");
+        fs::write(
+            xlstart.join("PERSONAL.XLSB"),
+            ooxml("xl/vbaProject.bin", &project),
+        )
+        .unwrap();
+        fs::write(xlstart.join("notes.txt"), "not a document").unwrap();
+
+        let templates = microsoft.join("Templates");
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(templates.join("Normal.dotm"), "corrupt").unwrap();
+        // Word\STARTUP is missing
+
+        let collected = collect_from_appdata(appdata.path(), output.path()).unwrap();
+        // Three copies and two sidecars
+        assert_eq!(collected.len(), 5);
+
+        let personal = output.path().join("Excel/XLSTART/PERSONAL.XLSB");
+        assert!(personal.exists());
+        assert!(output.path().join("Excel/XLSTART/notes.txt").exists());
+        assert!(!output
+            .path()
+            .join("Excel/XLSTART/notes.txt.macro_content.txt")
+            .exists());
+
+        let macros = fs::read_to_string(
+            output
+                .path()
+                .join("Excel/XLSTART/PERSONAL.XLSB.macro_content.txt"),
+        )
+        .unwrap();
+        assert!(macros.contains("' ==== Module Module1 (stream Module1) ===="));
+        assert!(macros.contains("Sub Auto_Open()"));
+
+        let normal = fs::read_to_string(
+            output
+                .path()
+                .join("Templates/Normal.dotm.macro_content.txt"),
+        )
+        .unwrap();
+        assert!(normal.starts_with("' Macro extraction failed"));
+        assert!(!output.path().join("Word").exists());
+    }
+}
@@ -0,0 +1,301 @@
+//! Office most recently used (MRU) file lists from user hives.
+//!
+//! Each Office application keeps the documents it opened under
+//! `Software\Microsoft\Office\<version>\<App>\File MRU` in `NTUSER.DAT`.
+//! Office 2013 and later keep one list per signed-in identity, under
+//! `<App>\User MRU\<identity>\File MRU`. Every `Item <n>` value (1 is the most
+//! recent) has the form
+//!
+//! ```text
+//! [F00000000][T01D9A1B2C3D4E5F6][O00000000]*C:\Users\bob\Documents\budget.xlsx
+//! ```
+//!
+//! where `F` holds flags (bit 0 marks a pinned item) and `T` is the FILETIME
+//! of the last access in hexadecimal. Office 2007 wrote the bare path. The
+//! lists survive the files, so deleted documents and documents on removable
+//! or network drives are reported too. Hives are read offline from the
+//! collected copies.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::regf::{Hive, Key};
+use crate::collectors::parsers::ParsedReport;
+use crate::utils::timezone::filetime_ticks_to_utc;
+
+/// Report file name
+pub const OFFICE_MRU_FILE: &str = "office_mru.json";
+
+/// Hive file name the MRU lists are read from
+pub const OFFICE_MRU_HIVE: &str = "NTUSER.DAT";
+
+/// Key holding one subkey per installed Office version
+const OFFICE_KEY: &str = r"Software\Microsoft\Office";
+
+/// Flag bit marking a pinned item
+const PINNED_FLAG: u32 = 0x1;
+
+/// A document in an Office application's File MRU list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfficeMruEntry {
+    /// Application key name (`Word`, `Excel`, `PowerPoint`, ...)
+    pub app: String,
+    /// Office version key (`16.0` for Office 2016 and later)
+    pub version: String,
+    pub file_path: String,
+    pub last_accessed_time: Option<DateTime<Utc>>,
+    pub pinned: bool,
+    /// Number of the `Item <n>` value; 1 is the most recently used
+    pub item: u32,
+    /// Identity key under `User MRU` (`LiveId_...`, `AD_...`), Office 2013 and later
+    pub user_identity: Option<String>,
+    /// Collected hive the entry was read from
+    pub source: String,
+}
+
+/// Flags, last access time and path decoded from one `Item <n>` value
+#[derive(Debug, PartialEq, Eq)]
+struct MruItem {
+    flags: u32,
+    last_accessed: Option<DateTime<Utc>>,
+    path: String,
+}
+
+/// Decode an `Item <n>` value
+fn parse_mru_item(value: &str) -> Option<MruItem> {
+    let mut item = MruItem {
+        flags: 0,
+        last_accessed: None,
+        path: String::new(),
+    };
+
+    let Some((fields, path)) = value.split_once('*') else {
+        // Office 2007: the bare path
+        item.path = value.trim().to_string();
+        return (!item.path.is_empty()).then_some(item);
+    };
+
+    for field in fields.split('[').filter_map(|f| f.strip_suffix(']')) {
+        let (tag, hex) = field.split_at(field.len().min(1));
+        match tag {
+            "F" => item.flags = u32::from_str_radix(hex, 16).ok()?,
+            "T" => {
+                item.last_accessed = u64::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|&ticks| ticks != 0)
+                    .and_then(filetime_ticks_to_utc)
+            }
+            _ => {}
+        }
+    }
+    item.path = path.trim().to_string();
+    (!item.path.is_empty()).then_some(item)
+}
+
+/// `Item <n>` values of a `File MRU` key
+fn file_mru_entries(
+    file_mru: &Key,
+    app: &str,
+    version: &str,
+    user_identity: Option<&str>,
+    source: &str,
+) -> Result<Vec<OfficeMruEntry>> {
+    let mut entries: Vec<OfficeMruEntry> = file_mru
+        .values()?
+        .into_iter()
+        .filter_map(|value| {
+            let number = value.name.strip_prefix("Item ")?.parse::<u32>().ok()?;
+            let item = parse_mru_item(&value.as_string()?)?;
+            Some(OfficeMruEntry {
+                app: app.to_string(),
+                version: version.to_string(),
+                file_path: item.path,
+                last_accessed_time: item.last_accessed,
+                pinned: item.flags & PINNED_FLAG != 0,
+                item: number,
+                user_identity: user_identity.map(str::to_string),
+                source: source.to_string(),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.item);
+    Ok(entries)
+}
+
+/// Office version key names look like `16.0`
+fn is_version_key(name: &str) -> bool {
+    name.split_once('.').is_some_and(|(major, minor)| {
+        !major.is_empty()
+            && !minor.is_empty()
+            && major.chars().all(|c| c.is_ascii_digit())
+            && minor.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Parse the File MRU lists of every Office version and application in a
+/// collected `NTUSER.DAT`, pinned items included
+pub fn parse_office_mru(ntuser_hive: &Path) -> Result<Vec<OfficeMruEntry>> {
+    let hive = Hive::open(ntuser_hive)?;
+    let source = ntuser_hive.to_string_lossy().to_string();
+    let mut entries = Vec::new();
+
+    let Some(office) = hive.open_key(OFFICE_KEY)? else {
+        return Ok(entries);
+    };
+
+    for version_key in office.subkeys()? {
+        let version = version_key.name();
+        if !is_version_key(&version) {
+            continue;
+        }
+        for app_key in version_key.subkeys()? {
+            let app = app_key.name();
+            if let Some(file_mru) = app_key.subkey("File MRU")? {
+                entries.extend(file_mru_entries(&file_mru, &app, &version, None, &source)?);
+            }
+            let Some(user_mru) = app_key.subkey("User MRU")? else {
+                continue;
+            };
+            for identity in user_mru.subkeys()? {
+                if let Some(file_mru) = identity.subkey("File MRU")? {
+                    entries.extend(file_mru_entries(
+                        &file_mru,
+                        &app,
+                        &version,
+                        Some(&identity.name()),
+                        &source,
+                    )?);
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Build the `office_mru.json` report from every collected user hive.
+///
+/// A hive that cannot be parsed is noted; the report fails only if none can.
+pub fn office_mru_report(hives: &[PathBuf]) -> Result<ParsedReport<OfficeMruEntry>> {
+    let mut report = ParsedReport::new(hives);
+    let mut failures = Vec::new();
+
+    for hive in hives {
+        match parse_office_mru(hive) {
+            Ok(entries) => report.records.extend(entries),
+            Err(e) => failures.push(format!("{}: {:#}", hive.display(), e)),
+        }
+    }
+
+    if failures.len() == hives.len() {
+        return Err(anyhow!("No hive could be parsed: {}", failures.join("; ")));
+    }
+    report.notes.extend(
+        failures
+            .into_iter()
+            .map(|failure| format!("Hive not parsed: {}", failure)),
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{HiveBuilder, HiveKey};
+    use tempfile::TempDir;
+
+    fn ntuser_hive(dir: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        let office = root.subkey("Software").subkey("Microsoft").subkey("Office");
+
+        office
+            .subkey("14.0")
+            .subkey("Word")
+            .subkey("File MRU")
+            .string_value(
+                "Item 1",
+                r"[F00000000][T01CC5D7E2B1A6000]*C:\Users\bob\Documents\old.docx",
+            )
+            .string_value("Max Display", "25");
+
+        let excel = office.subkey("16.0").subkey("Excel");
+        excel
+            .subkey("User MRU")
+            .subkey("AD_4F2A")
+            .subkey("File MRU")
+            .string_value(
+                "Item 2",
+                r"[F00000000][T01DA6F1B4E8C7000][O00000000]*\\fileserver\finance\q1.xlsx",
+            )
+            .string_value(
+                "Item 1",
+                r"[F00000001][T01DA6F3C2A1B0000][O00000000]*E:\payroll.xlsx",
+            );
+        excel
+            .subkey("Place MRU")
+            .string_value("Item 1", r"[F00000000][T01DA6F3C2A1B0000][O00000000]*E:\");
+        office.subkey("Common").subkey("Open Find");
+
+        let path = dir.join("NTUSER.DAT");
+        std::fs::write(&path, HiveBuilder::build(&root)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_mru_item() {
+        let item =
+            parse_mru_item(r"[F00000001][T01DA6F3C2A1B0000][O00000000]*E:\payroll.xlsx").unwrap();
+        assert_eq!(item.flags, 1);
+        assert_eq!(item.path, r"E:\payroll.xlsx");
+        assert_eq!(
+            item.last_accessed,
+            filetime_ticks_to_utc(0x01DA6F3C2A1B0000)
+        );
+
+        let bare = parse_mru_item(r"C:\Users\bob\report.doc").unwrap();
+        assert_eq!(bare.flags, 0);
+        assert_eq!(bare.last_accessed, None);
+        assert_eq!(bare.path, r"C:\Users\bob\report.doc");
+
+        assert!(parse_mru_item("[F00000000][T01DA6F3C2A1B0000]*").is_none());
+        assert!(parse_mru_item("[Fzz][T0]*C:\\x").is_none());
+    }
+
+    #[test]
+    fn test_parse_office_mru() {
+        let temp = TempDir::new().unwrap();
+        let hive = ntuser_hive(temp.path());
+
+        let entries = parse_office_mru(&hive).unwrap();
+        let summary: Vec<(&str, &str, u32, bool)> = entries
+            .iter()
+            .map(|e| (e.version.as_str(), e.app.as_str(), e.item, e.pinned))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("14.0", "Word", 1, false),
+                ("16.0", "Excel", 1, true),
+                ("16.0", "Excel", 2, false),
+            ]
+        );
+
+        assert_eq!(entries[0].file_path, r"C:\Users\bob\Documents\old.docx");
+        assert_eq!(entries[0].user_identity, None);
+        assert!(entries[0].last_accessed_time.is_some());
+        assert_eq!(entries[1].file_path, r"E:\payroll.xlsx");
+        assert_eq!(entries[1].user_identity.as_deref(), Some("AD_4F2A"));
+        assert_eq!(entries[2].file_path, r"\\fileserver\finance\q1.xlsx");
+
+        let unparseable = temp.path().join("other").join("NTUSER.DAT");
+        std::fs::create_dir_all(unparseable.parent().unwrap()).unwrap();
+        std::fs::write(&unparseable, "not a hive").unwrap();
+        let report = office_mru_report(&[hive, unparseable.clone()]).unwrap();
+        assert_eq!(report.records.len(), 3);
+        assert_eq!(report.notes.len(), 1);
+        assert!(office_mru_report(&[unparseable]).is_err());
+    }
+}
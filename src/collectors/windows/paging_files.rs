@@ -0,0 +1,189 @@
+//! Page, swap and hibernation files.
+//!
+//! `pagefile.sys`, `swapfile.sys` (the modern app swap file) and
+//! `hiberfil.sys` hold memory pages written to disk, so they often contain
+//! cleartext credentials and injected code that no longer exist in live
+//! memory. Windows keeps them open for its own exclusive use, so they are
+//! read through the raw-access path with backup semantics, copying only
+//! their allocated ranges. They can be tens of GB: the default artifacts
+//! are not required, `max_size_mb` in an artifact's metadata caps the copy,
+//! progress is logged during the read, and while an archive stream is
+//! active (`--stream`) the read goes straight into the upload without a
+//! local copy.
+//!
+//! The first MiB of `hiberfil.sys` is checked before it is copied to record
+//! whether it holds an image and in which compression format; the copy
+//! itself is not decompressed.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::warn;
+
+use crate::config::{Artifact, ArtifactType, WindowsArtifactType};
+use crate::models::HibernationInfo;
+
+/// Artifact metadata key capping the copy, in MiB
+pub const MAX_SIZE_MB_KEY: &str = "max_size_mb";
+
+/// Bytes at the start of `hiberfil.sys` examined by [`parse_hibernation_header`]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub const HIBERNATION_HEADER_SCAN_BYTES: usize = 1024 * 1024;
+
+/// Start of each Xpress LZ77 block in Windows 7 and earlier images
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const XPRESS_BLOCK_SIGNATURE: &[u8] = b"\x81\x81xpress";
+
+/// Page, swap and hibernation files in the default Windows configuration
+const DEFAULT_PAGING_FILES: [(&str, &str, &str); 3] = [
+    ("pagefile.sys", r"\\?\C:\pagefile.sys", "Page file"),
+    (
+        "swapfile.sys",
+        r"\\?\C:\swapfile.sys",
+        "Swap file of modern (UWP) apps",
+    ),
+    ("hiberfil.sys", r"\\?\C:\hiberfil.sys", "Hibernation file"),
+];
+
+/// The page, swap and hibernation files of the system drive, not required
+pub fn default_artifacts() -> Vec<Artifact> {
+    DEFAULT_PAGING_FILES
+        .iter()
+        .map(|(name, source_path, description)| Artifact {
+            name: name.to_string(),
+            artifact_type: ArtifactType::Windows(WindowsArtifactType::PagingFile),
+            source_path: source_path.to_string(),
+            destination_name: name.to_string(),
+            description: Some(description.to_string()),
+            required: false,
+            metadata: HashMap::new(),
+            regex: None,
+        })
+        .collect()
+}
+
+/// Byte cap from the artifact's `max_size_mb`; `None` for no cap.
+///
+/// An invalid value is logged and ignored.
+pub fn size_cap(artifact: &Artifact) -> Option<u64> {
+    let value = artifact.metadata.get(MAX_SIZE_MB_KEY)?;
+    match value.trim().parse::<u64>() {
+        Ok(mb) if mb > 0 => Some(mb.saturating_mul(1024 * 1024)),
+        _ => {
+            warn!(
+                "Invalid {} '{}' for artifact {}; copying the whole file",
+                MAX_SIZE_MB_KEY, value, artifact.name
+            );
+            None
+        }
+    }
+}
+
+/// Whether `path` names a hibernation file
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn is_hibernation_file(path: &Path) -> bool {
+    path.to_string_lossy()
+        .rsplit(['\\', '/'])
+        .next()
+        .is_some_and(|name| name.eq_ignore_ascii_case("hiberfil.sys"))
+}
+
+/// Read the image state and compression format from the start of a
+/// hibernation file.
+///
+/// The signature of the `PO_MEMORY_IMAGE` header gives the state: `HIBR`
+/// while the system is hibernated, `WAKE` after it resumed (the pages are
+/// stale but mostly intact), `RSTR` while resuming. Windows 8 and later
+/// zero the header on resume. Windows 7 and earlier compress the image in
+/// Xpress LZ77 blocks, each starting with `\x81\x81xpress`; later versions
+/// use Xpress Huffman without block markers.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_hibernation_header(data: &[u8]) -> HibernationInfo {
+    let signature_bytes = data.get(..4).unwrap_or(data);
+    let signature: String = signature_bytes
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+        .collect();
+    let state = match signature_bytes {
+        b"hibr" | b"HIBR" => "hibernated",
+        b"wake" | b"WAKE" => "resumed",
+        b"rstr" | b"RSTR" => "resuming",
+        [0, 0, 0, 0] => "cleared",
+        _ => "unknown",
+    };
+
+    let legacy_blocks = data
+        .windows(XPRESS_BLOCK_SIGNATURE.len())
+        .any(|window| window == XPRESS_BLOCK_SIGNATURE);
+    let format = if legacy_blocks {
+        Some("xpress_lz77")
+    } else if matches!(state, "hibernated" | "resumed" | "resuming") {
+        Some("xpress_huffman")
+    } else {
+        None
+    };
+    let tools: &[&str] = match format {
+        Some("xpress_lz77") => &["Hibr2Bin", "Volatility 2 imagecopy"],
+        Some(_) => &["Hibr2Bin", "Volatility 3 windows.hibernation.Dump"],
+        None => &[],
+    };
+
+    HibernationInfo {
+        signature,
+        state: state.to_string(),
+        format: format.map(str::to_string),
+        tools: tools.iter().map(|tool| tool.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hibernation_header() {
+        let mut legacy = b"hibr".to_vec();
+        legacy.resize(0x3000, 0);
+        legacy.extend_from_slice(XPRESS_BLOCK_SIGNATURE);
+        let info = parse_hibernation_header(&legacy);
+        assert_eq!(info.signature, "hibr");
+        assert_eq!(info.state, "hibernated");
+        assert_eq!(info.format.as_deref(), Some("xpress_lz77"));
+        assert!(info.tools.contains(&"Volatility 2 imagecopy".to_string()));
+
+        let mut modern = b"WAKE".to_vec();
+        modern.resize(0x3000, 0x5a);
+        let info = parse_hibernation_header(&modern);
+        assert_eq!(info.state, "resumed");
+        assert_eq!(info.format.as_deref(), Some("xpress_huffman"));
+        assert!(info.tools.contains(&"Hibr2Bin".to_string()));
+
+        let cleared = parse_hibernation_header(&[0u8; 4096]);
+        assert_eq!(cleared.signature, "....");
+        assert_eq!(cleared.state, "cleared");
+        assert_eq!(cleared.format, None);
+        assert!(cleared.tools.is_empty());
+
+        assert_eq!(parse_hibernation_header(b"MZ").state, "unknown");
+    }
+
+    #[test]
+    fn test_size_cap_and_defaults() {
+        let mut artifacts = default_artifacts();
+        assert_eq!(artifacts.len(), 3);
+        assert!(artifacts.iter().all(|a| !a.required));
+        assert!(is_hibernation_file(Path::new(&artifacts[2].source_path)));
+        assert!(!is_hibernation_file(Path::new(&artifacts[0].source_path)));
+
+        let pagefile = &mut artifacts[0];
+        assert_eq!(size_cap(pagefile), None);
+        pagefile
+            .metadata
+            .insert(MAX_SIZE_MB_KEY.to_string(), "2048".to_string());
+        assert_eq!(size_cap(pagefile), Some(2048 * 1024 * 1024));
+        pagefile
+            .metadata
+            .insert(MAX_SIZE_MB_KEY.to_string(), "lots".to_string());
+        assert_eq!(size_cap(pagefile), None);
+    }
+}
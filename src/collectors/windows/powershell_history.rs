@@ -0,0 +1,744 @@
+//! PowerShell command history, profiles and transcripts of every user.
+//!
+//! PSReadLine saves interactive commands to
+//! `%APPDATA%\Microsoft\Windows\PowerShell\PSReadLine\<host>_history.txt`
+//! (`ConsoleHost_history.txt` for the console host). Profile scripts in
+//! `Documents\WindowsPowerShell` and `Documents\PowerShell` run at every
+//! start and hold the user's PSReadLine options, including
+//! `Set-PSReadLineOption -HistorySaveStyle SaveNothing` to stop history
+//! being written. Transcripts go to the directory set by the Transcription
+//! policy, or to `Documents\<yyyyMMdd>\` of each user when none is set.
+//!
+//! Every profile under the Users folder is visited, not just the collecting
+//! user's. After collection, [`parse_powershell_history`] joins the histories
+//! into `powershell_history_report.json` with the owning user of each command
+//! and flags encoded commands (`-EncodedCommand` and its abbreviations,
+//! `FromBase64String`).
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::collectors::footprint::create_dir_all_tracked;
+use crate::collectors::parsers::regf::Hive;
+use crate::collectors::parsers::ParsedReport;
+use crate::collectors::platforms::common::FallbackCollector;
+use crate::models::ArtifactMetadata;
+
+/// File name of the history report under `parsed/`
+pub const POWERSHELL_HISTORY_REPORT_FILE: &str = "powershell_history_report.json";
+
+/// File name of the Transcription policy written next to the copies
+pub const TRANSCRIPTION_POLICY_FILE: &str = "transcription_policy.json";
+
+/// Transcription policy key, relative to `HKLM\SOFTWARE` (and the SOFTWARE hive)
+pub const TRANSCRIPTION_POLICY_KEY: &str = r"Policies\Microsoft\Windows\PowerShell\Transcription";
+
+/// Directory holding PSReadLine history files
+const PSREADLINE_DIR: &str = "PSReadLine";
+
+/// PSReadLine directory relative to a user profile
+const PSREADLINE_PATH: &[&str] = &[
+    "AppData",
+    "Roaming",
+    "Microsoft",
+    "Windows",
+    "PowerShell",
+    PSREADLINE_DIR,
+];
+
+/// Profile script directories under `Documents` (Windows PowerShell, PowerShell 7)
+const PROFILE_DIRS: &[&str] = &["WindowsPowerShell", "PowerShell"];
+
+/// Folders under `Users` that are not user profiles
+const SKIPPED_PROFILES: &[&str] = &["All Users", "Default", "Default User", "Public"];
+
+/// Transcript file name prefix
+const TRANSCRIPT_PREFIX: &str = "powershell_transcript";
+
+/// PowerShell Transcription policy (`EnableTranscripting`, `OutputDirectory`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TranscriptionPolicy {
+    pub enabled: bool,
+    /// Transcripts go to each user's Documents folder when unset
+    pub output_directory: Option<String>,
+    pub invocation_header: bool,
+    /// `live registry` or the SOFTWARE hive path the policy was read from
+    pub source: String,
+}
+
+/// One command from a PSReadLine history file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PowerShellHistoryEntry {
+    pub user: String,
+    /// History file name, one per PowerShell host (`ConsoleHost_history.txt`, ...)
+    pub history_file: String,
+    /// Line the command starts on (1-based)
+    pub line: usize,
+    /// Command text; continuation lines are joined with newlines
+    pub command: String,
+    /// `encoded_command` and/or `from_base64`
+    pub flags: Vec<String>,
+    /// Decoded payload of an `-EncodedCommand` argument
+    pub decoded_command: Option<String>,
+}
+
+/// Copy PowerShell histories, profiles and transcripts of every user under
+/// `users_dir` into `output_dir`
+///
+/// The Transcription policy is read from the live registry.
+pub fn collect_powershell_history(
+    users_dir: &Path,
+    output_dir: &Path,
+) -> Result<Vec<ArtifactMetadata>> {
+    let policy = live_transcription_policy().unwrap_or_else(|e| {
+        warn!(
+            "Failed to read the PowerShell Transcription policy: {:#}",
+            e
+        );
+        None
+    });
+    collect_from(users_dir, output_dir, policy.as_ref())
+}
+
+/// Copy the files of every profile under `users_dir`, plus transcripts from
+/// the policy's output directory, into `output_dir`.
+///
+/// Each user's files go to `<user>/PSReadLine`, `<user>/<profile dir>` and
+/// `<user>/Transcripts/<date>`; transcripts from the policy directory go to
+/// `Transcripts/`. The policy itself is written to `transcription_policy.json`.
+pub fn collect_from(
+    users_dir: &Path,
+    output_dir: &Path,
+    policy: Option<&TranscriptionPolicy>,
+) -> Result<Vec<ArtifactMetadata>> {
+    info!("Collecting PowerShell history from {}", users_dir.display());
+    create_dir_all_tracked(output_dir).context(format!(
+        "Failed to create directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut files = Vec::new();
+    for profile in user_profiles(users_dir)? {
+        let user = profile
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let user_output = output_dir.join(&user);
+
+        let psreadline = PSREADLINE_PATH
+            .iter()
+            .fold(profile.clone(), |path, part| path.join(part));
+        for file in files_in(&psreadline, |name| name.ends_with("_history.txt")) {
+            files.push((
+                file.clone(),
+                user_output.join(PSREADLINE_DIR).join(file_name(&file)),
+            ));
+        }
+
+        let documents = profile.join("Documents");
+        for dir in PROFILE_DIRS {
+            for file in files_in(&documents.join(dir), |name| name.ends_with("profile.ps1")) {
+                files.push((file.clone(), user_output.join(dir).join(file_name(&file))));
+            }
+        }
+
+        // Default transcript location: Documents\<yyyyMMdd>\PowerShell_transcript.*.txt
+        for date_dir in subdirectories(&documents) {
+            let date = file_name(&date_dir);
+            if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+                continue;
+            }
+            for file in files_in(&date_dir, is_transcript_name) {
+                let dest = user_output
+                    .join("Transcripts")
+                    .join(&date)
+                    .join(file_name(&file));
+                files.push((file, dest));
+            }
+        }
+    }
+
+    if let Some(directory) = policy.and_then(|p| p.output_directory.as_deref()) {
+        let directory = Path::new(directory);
+        if directory.is_dir() {
+            for entry in WalkDir::new(directory)
+                .max_depth(2)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter(|e| is_transcript_name(&e.file_name().to_string_lossy().to_lowercase()))
+            {
+                let relative = entry.path().strip_prefix(directory).unwrap_or(entry.path());
+                files.push((
+                    entry.path().to_path_buf(),
+                    output_dir.join("Transcripts").join(relative),
+                ));
+            }
+        } else {
+            debug!(
+                "Transcript directory {} is not reachable",
+                directory.display()
+            );
+        }
+    }
+
+    let fallback = FallbackCollector::new();
+    let mut collected = Vec::new();
+    for (source, dest) in files {
+        match fallback.collect_standard_file(&source, &dest) {
+            Ok(metadata) => collected.push(metadata),
+            Err(e) => warn!("Failed to copy {}: {:#}", source.display(), e),
+        }
+    }
+
+    if let Some(policy) = policy {
+        let path = output_dir.join(TRANSCRIPTION_POLICY_FILE);
+        let json = serde_json::to_string_pretty(policy)
+            .context("Failed to serialize Transcription policy")?;
+        fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+
+        let collection_time = Utc::now().to_rfc3339();
+        collected.push(ArtifactMetadata {
+            original_path: policy.source.clone(),
+            collection_time: collection_time.clone(),
+            file_size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+            created_time: Some(collection_time),
+            accessed_time: None,
+            modified_time: None,
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        });
+    }
+
+    info!("Collected {} PowerShell history files", collected.len());
+    Ok(collected)
+}
+
+/// Read the Transcription policy from `HKLM\SOFTWARE`; `None` if not configured
+#[cfg(target_os = "windows")]
+fn live_transcription_policy() -> Result<Option<TranscriptionPolicy>> {
+    use crate::collectors::footprint::run_command;
+    use crate::collectors::volatile::windows_network_config::parse_reg_query;
+    use std::process::Command;
+
+    let key = format!(r"HKLM\SOFTWARE\{}", TRANSCRIPTION_POLICY_KEY);
+    let output = run_command(Command::new("reg").args(["query", &key]))
+        .context("Failed to execute reg query")?;
+    // reg query fails when the key does not exist
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let keys = parse_reg_query(&String::from_utf8_lossy(&output.stdout));
+    Ok(keys.first().map(|key| {
+        let dword = |name: &str| {
+            key.values
+                .get(name)
+                .and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        };
+        policy_from(
+            dword("EnableTranscripting"),
+            key.values.get("OutputDirectory").cloned(),
+            dword("EnableInvocationHeader"),
+            "live registry",
+        )
+    }))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn live_transcription_policy() -> Result<Option<TranscriptionPolicy>> {
+    Ok(None)
+}
+
+/// Read the Transcription policy from a SOFTWARE hive; `None` if not configured
+pub fn transcription_policy_from_hive(software: &Path) -> Result<Option<TranscriptionPolicy>> {
+    let hive = Hive::open(software)?;
+    let Some(key) = hive.open_key(TRANSCRIPTION_POLICY_KEY)? else {
+        return Ok(None);
+    };
+    let dword =
+        |name: &str| -> Result<Option<u32>> { Ok(key.value(name)?.and_then(|v| v.as_dword())) };
+    Ok(Some(policy_from(
+        dword("EnableTranscripting")?,
+        key.value("OutputDirectory")?.and_then(|v| v.as_string()),
+        dword("EnableInvocationHeader")?,
+        &software.to_string_lossy(),
+    )))
+}
+
+fn policy_from(
+    enabled: Option<u32>,
+    output_directory: Option<String>,
+    invocation_header: Option<u32>,
+    source: &str,
+) -> TranscriptionPolicy {
+    TranscriptionPolicy {
+        enabled: enabled.is_some_and(|v| v != 0),
+        output_directory: output_directory.filter(|d| !d.trim().is_empty()),
+        invocation_header: invocation_header.is_some_and(|v| v != 0),
+        source: source.to_string(),
+    }
+}
+
+/// Whether `path` is a collected PSReadLine history file
+pub fn is_history_file(path: &Path) -> bool {
+    let in_psreadline = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(PSREADLINE_DIR));
+    in_psreadline
+        && path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().to_lowercase().ends_with("_history.txt"))
+}
+
+/// Join collected history files into one report, one record per command.
+///
+/// The Transcription policy from the collected SOFTWARE hive, if any, is
+/// recorded in the notes.
+pub fn parse_powershell_history(
+    files: &[PathBuf],
+    software: Option<&Path>,
+) -> Result<ParsedReport<PowerShellHistoryEntry>> {
+    let mut sources = files.to_vec();
+    sources.extend(software.map(Path::to_path_buf));
+    let mut report = ParsedReport::new(&sources);
+
+    if let Some(software) = software {
+        match transcription_policy_from_hive(software) {
+            Ok(Some(policy)) => report.notes.push(format!(
+                "Transcription policy: {}, output directory {}",
+                if policy.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                },
+                policy
+                    .output_directory
+                    .as_deref()
+                    .unwrap_or("<user Documents folder>")
+            )),
+            Ok(None) => report
+                .notes
+                .push("Transcription policy is not configured".to_string()),
+            Err(e) => report
+                .notes
+                .push(format!("Failed to read Transcription policy: {:#}", e)),
+        }
+    }
+
+    for file in files {
+        let data = match fs::read(file) {
+            Ok(data) => data,
+            Err(e) => {
+                report
+                    .notes
+                    .push(format!("Failed to read {}: {}", file.display(), e));
+                continue;
+            }
+        };
+        let user = history_user(file);
+        let history_file = file_name(file);
+        for (line, command) in history_commands(&String::from_utf8_lossy(&data)) {
+            let (flags, decoded_command) = command_flags(&command);
+            report.records.push(PowerShellHistoryEntry {
+                user: user.clone(),
+                history_file: history_file.clone(),
+                line,
+                command,
+                flags,
+                decoded_command,
+            });
+        }
+    }
+
+    let flagged = report
+        .records
+        .iter()
+        .filter(|r| !r.flags.is_empty())
+        .count();
+    if flagged > 0 {
+        report.notes.push(format!(
+            "{} command(s) flagged for encoded content",
+            flagged
+        ));
+    }
+    Ok(report)
+}
+
+/// Owner of a collected history file: the folder before `AppData` when the
+/// profile layout was kept, else the folder holding `PSReadLine`
+fn history_user(path: &Path) -> String {
+    let components: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if let Some(index) = components
+        .iter()
+        .rposition(|c| c.eq_ignore_ascii_case("AppData"))
+    {
+        if index > 0 {
+            return components[index - 1].clone();
+        }
+    }
+    path.parent()
+        .and_then(|p| p.parent())
+        .map(file_name)
+        .unwrap_or_default()
+}
+
+/// Commands with their starting line; PSReadLine writes each line of a
+/// multi-line command ending in a backtick
+fn history_commands(text: &str) -> Vec<(usize, String)> {
+    let mut commands = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let (start, mut command) = current.take().unwrap_or((index + 1, String::new()));
+        match line.strip_suffix('`') {
+            Some(continued) => {
+                command.push_str(continued);
+                command.push('\n');
+                current = Some((start, command));
+            }
+            None => {
+                command.push_str(line);
+                if !command.trim().is_empty() {
+                    commands.push((start, command));
+                }
+            }
+        }
+    }
+    commands.extend(current.filter(|(_, c)| !c.trim().is_empty()));
+    commands
+}
+
+/// Flags for `command` and the decoded `-EncodedCommand` payload, if any
+fn command_flags(command: &str) -> (Vec<String>, Option<String>) {
+    let mut flags = Vec::new();
+    let mut decoded = None;
+
+    let mut tokens = command.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let Some(name) = token.strip_prefix('-').or_else(|| token.strip_prefix('/')) else {
+            continue;
+        };
+        let name = name.to_ascii_lowercase();
+        // powershell.exe accepts -ec and any prefix of -EncodedCommand
+        if name != "ec" && (name.is_empty() || !"encodedcommand".starts_with(&name)) {
+            continue;
+        }
+        let payload = tokens
+            .next()
+            .map(|p| p.trim_matches(|c| c == '\'' || c == '"'))
+            .unwrap_or_default();
+        decoded = decode_base64(payload)
+            .and_then(|bytes| utf16le_text(&bytes))
+            .filter(|text| !text.is_empty());
+        // A bare -e is too common an abbreviation to flag without a payload
+        if decoded.is_some() || name.len() >= 3 {
+            flags.push("encoded_command".to_string());
+        }
+        break;
+    }
+
+    if command.to_ascii_lowercase().contains("frombase64string") {
+        flags.push("from_base64".to_string());
+    }
+    (flags, decoded)
+}
+
+/// Decode standard base64; `None` on any other character
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    if text.is_empty() {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// UTF-16LE text, as `-EncodedCommand` payloads are encoded
+fn utf16le_text(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+fn is_transcript_name(name: &str) -> bool {
+    name.starts_with(TRANSCRIPT_PREFIX) && name.ends_with(".txt")
+}
+
+/// User profile folders under `users_dir`, without `Public` and the
+/// default profiles
+pub(crate) fn user_profiles(users_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut profiles: Vec<PathBuf> = fs::read_dir(users_dir)
+        .context(format!("Failed to read {}", users_dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            !SKIPPED_PROFILES
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(&name))
+        })
+        .map(|e| e.path())
+        .collect();
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// Files directly in `dir` whose lowercase name satisfies `matches`
+fn files_in<F>(dir: &Path, matches: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> bool,
+{
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|e| matches(&e.file_name().to_string_lossy().to_lowercase()))
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{HiveBuilder, HiveKey};
+    use tempfile::TempDir;
+
+    /// `whoami` as UTF-16LE base64
+    const ENCODED_WHOAMI: &str = "dwBoAG8AYQBtAGkA";
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn history_path(users: &Path, user: &str) -> PathBuf {
+        PSREADLINE_PATH
+            .iter()
+            .fold(users.join(user), |path, part| path.join(part))
+            .join("ConsoleHost_history.txt")
+    }
+
+    #[test]
+    fn test_parse_history_flags_encoded_commands() {
+        let temp = TempDir::new().unwrap();
+        let alice = history_path(temp.path(), "alice");
+        write_file(
+            &alice,
+            &format!(
+                "Get-Process\n\
+                 Get-ChildItem `\n  -Recurse\n\
+                 \n\
+                 powershell -nop -enc {}\n\
+                 [Text.Encoding]::UTF8.GetString([Convert]::FromBase64String('aGk='))\n\
+                 powershell -ExecutionPolicy Bypass -File run.ps1\n",
+                ENCODED_WHOAMI
+            ),
+        );
+        // Collected copy layout: <user>/PSReadLine/<file>
+        let bob = temp
+            .path()
+            .join("bob")
+            .join(PSREADLINE_DIR)
+            .join("ConsoleHost_history.txt");
+        write_file(
+            &bob,
+            "powershell -e notbase64!\npowershell /EncodedCommand x\n",
+        );
+
+        assert!(is_history_file(&alice));
+        assert!(!is_history_file(
+            &temp.path().join("ConsoleHost_history.txt")
+        ));
+
+        let report = parse_powershell_history(&[alice, bob], None).unwrap();
+        let commands: Vec<(&str, usize, &str)> = report
+            .records
+            .iter()
+            .map(|r| (r.user.as_str(), r.line, r.command.as_str()))
+            .collect();
+        assert_eq!(commands[0], ("alice", 1, "Get-Process"));
+        assert_eq!(commands[1], ("alice", 2, "Get-ChildItem \n  -Recurse"));
+        assert_eq!(commands[2].1, 5);
+        assert_eq!(commands[5].0, "bob");
+        assert_eq!(report.records.len(), 7);
+
+        let encoded = &report.records[2];
+        assert_eq!(encoded.flags, vec!["encoded_command"]);
+        assert_eq!(encoded.decoded_command.as_deref(), Some("whoami"));
+        assert_eq!(report.records[3].flags, vec!["from_base64"]);
+        assert!(report.records[4].flags.is_empty());
+        assert!(report.records[0].flags.is_empty());
+        // A bare -e without a decodable payload is not flagged
+        assert!(report.records[5].flags.is_empty());
+        assert_eq!(report.records[6].flags, vec!["encoded_command"]);
+        assert_eq!(report.records[6].decoded_command, None);
+        assert!(report
+            .notes
+            .contains(&"3 command(s) flagged for encoded content".to_string()));
+    }
+
+    #[test]
+    fn test_collect_from_users_dir() {
+        let temp = TempDir::new().unwrap();
+        let users = temp.path().join("Users");
+        write_file(&history_path(&users, "alice"), "whoami\n");
+        write_file(&history_path(&users, "Public"), "ignored\n");
+        write_file(
+            &users
+                .join("alice")
+                .join("Documents")
+                .join("WindowsPowerShell")
+                .join("Microsoft.PowerShell_profile.ps1"),
+            "Set-PSReadLineOption -HistorySaveStyle SaveNothing\n",
+        );
+        write_file(
+            &users
+                .join("bob")
+                .join("Documents")
+                .join("20240305")
+                .join("PowerShell_transcript.HOST.abc.20240305143010.txt"),
+            "transcript\n",
+        );
+        write_file(
+            &users.join("bob").join("Documents").join("notes.txt"),
+            "not collected\n",
+        );
+        let transcripts = temp.path().join("Transcripts");
+        write_file(
+            &transcripts
+                .join("20240306")
+                .join("PowerShell_transcript.HOST.def.20240306090000.txt"),
+            "central transcript\n",
+        );
+        let policy = TranscriptionPolicy {
+            enabled: true,
+            output_directory: Some(transcripts.to_string_lossy().to_string()),
+            invocation_header: false,
+            source: "live registry".to_string(),
+        };
+
+        let output = temp.path().join("out");
+        let collected = collect_from(&users, &output, Some(&policy)).unwrap();
+        assert_eq!(collected.len(), 5);
+
+        assert!(output
+            .join("alice/PSReadLine/ConsoleHost_history.txt")
+            .is_file());
+        assert!(output
+            .join("alice/WindowsPowerShell/Microsoft.PowerShell_profile.ps1")
+            .is_file());
+        assert!(output
+            .join("bob/Transcripts/20240305/PowerShell_transcript.HOST.abc.20240305143010.txt")
+            .is_file());
+        assert!(output
+            .join("Transcripts/20240306/PowerShell_transcript.HOST.def.20240306090000.txt")
+            .is_file());
+        assert!(!output.join("Public").exists());
+        let written: TranscriptionPolicy = serde_json::from_str(
+            &fs::read_to_string(output.join(TRANSCRIPTION_POLICY_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(written, policy);
+
+        // The collected layout is recognised by the analyzer
+        let report = parse_powershell_history(
+            &[output.join("alice/PSReadLine/ConsoleHost_history.txt")],
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.records[0].user, "alice");
+    }
+
+    #[test]
+    fn test_transcription_policy_from_hive() {
+        let temp = TempDir::new().unwrap();
+        let mut root = HiveKey::new("ROOT");
+        let mut key = &mut root;
+        for part in TRANSCRIPTION_POLICY_KEY.split('\\') {
+            key = key.subkey(part);
+        }
+        key.dword_value("EnableTranscripting", 1)
+            .string_value("OutputDirectory", r"\\server\transcripts");
+        let software = temp.path().join("SOFTWARE");
+        fs::write(&software, HiveBuilder::build(&root)).unwrap();
+
+        let policy = transcription_policy_from_hive(&software).unwrap().unwrap();
+        assert!(policy.enabled);
+        assert!(!policy.invocation_header);
+        assert_eq!(
+            policy.output_directory.as_deref(),
+            Some(r"\\server\transcripts")
+        );
+
+        let report = parse_powershell_history(&[], Some(&software)).unwrap();
+        assert_eq!(
+            report.notes,
+            vec![r"Transcription policy: enabled, output directory \\server\transcripts"]
+        );
+
+        let empty = temp.path().join("EMPTY");
+        fs::write(&empty, HiveBuilder::build(&HiveKey::new("ROOT"))).unwrap();
+        assert_eq!(transcription_policy_from_hive(&empty).unwrap(), None);
+    }
+}
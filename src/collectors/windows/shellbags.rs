@@ -0,0 +1,546 @@
+//! ShellBags: the folders a user has browsed in Explorer.
+//!
+//! Explorer records every folder it displays under `BagMRU`, in `NTUSER.DAT`
+//! (`Software\Microsoft\Windows\Shell`, `ShellNoRoam` before Vista) and in
+//! `UsrClass.dat` (`Local Settings\Software\Microsoft\Windows\Shell`). Each
+//! `BagMRU` key holds numbered values, one shell item per child folder, and a
+//! subkey with the same number for that child's own children, so walking the
+//! tree and joining the item names rebuilds the folder path. `NodeSlot` links
+//! a folder to its view settings under `Bags\<slot>`; that key's last write
+//! time approximates when the folder window was last opened.
+//!
+//! Folder timestamps come from the file entry shell items (DOS date and time
+//! in UTC, two-second resolution) and describe the folder as it was when it
+//! was browsed. The entries outlive the folders, so deleted folders and
+//! folders on removable or network drives are reported too. The hives are
+//! read offline from the collected copies.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::collectors::parsers::bytes::{u16_at, u32_at};
+use crate::collectors::parsers::regf::{Hive, Key, RegValue};
+use crate::collectors::parsers::ParsedReport;
+use crate::utils::timezone::fat_datetime_to_naive;
+
+/// Report file name
+pub const SHELLBAGS_FILE: &str = "shellbags.json";
+
+/// Hive file names ShellBags are read from
+pub const SHELLBAG_HIVES: [&str; 2] = ["NTUSER.DAT", "UsrClass.dat"];
+
+/// Keys holding `BagMRU` and `Bags`, in `NTUSER.DAT` and `UsrClass.dat`
+const SHELL_KEYS: [&str; 3] = [
+    r"Software\Microsoft\Windows\Shell",
+    r"Software\Microsoft\Windows\ShellNoRoam",
+    r"Local Settings\Software\Microsoft\Windows\Shell",
+];
+
+/// Deepest `BagMRU` nesting followed
+const MAX_DEPTH: usize = 64;
+
+/// Signature of the file entry extension block that holds the long name
+const FILE_ENTRY_EXTENSION: [u8; 4] = [0x04, 0x00, 0xef, 0xbe];
+
+/// Class types of the shell items understood here
+const ROOT_FOLDER: u8 = 0x1f;
+const CONTROL_PANEL: u8 = 0x71;
+
+/// A folder recorded in the ShellBags
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellBagEntry {
+    /// Path rebuilt from the shell items along the `BagMRU` branch
+    pub folder_path: String,
+    pub created_time: Option<DateTime<Utc>>,
+    pub modified_time: Option<DateTime<Utc>>,
+    pub accessed_time: Option<DateTime<Utc>>,
+    /// Number of the value in the parent `BagMRU` key holding the folder
+    pub slot_index: u32,
+    /// Position in the parent's `MRUListEx`; 0 is the most recently used
+    pub mru_position: Option<u32>,
+    /// `NodeSlot` value: the folder's key under `Bags`
+    pub node_slot: Option<u32>,
+    /// Last write time of the folder's `Bags\<NodeSlot>` key
+    pub bag_last_written: Option<DateTime<Utc>>,
+    /// The folder's key, e.g. `BagMRU\0\2`
+    pub bag_mru_key: String,
+    /// Collected hive the entry was read from
+    pub source: String,
+}
+
+/// Name and timestamps decoded from one shell item
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ShellItem {
+    name: String,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+}
+
+/// NUL-terminated single-byte string
+fn ascii_at(data: &[u8], offset: usize) -> Option<String> {
+    let rest = data.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Some(rest[..end].iter().map(|&b| b as char).collect())
+}
+
+/// NUL-terminated UTF-16LE string
+fn utf16_at(data: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = data
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Packed DOS date and time (date first), as stored in shell items
+fn dos_datetime(data: &[u8], offset: usize) -> Option<DateTime<Utc>> {
+    let date = u16_at(data, offset).ok()?;
+    let time = u16_at(data, offset + 2).ok()?;
+    if date == 0 {
+        return None;
+    }
+    fat_datetime_to_naive(date, time).map(|naive| naive.and_utc())
+}
+
+fn guid_at(data: &[u8], offset: usize) -> Option<String> {
+    let bytes: [u8; 16] = data.get(offset..offset + 16)?.try_into().ok()?;
+    Some(
+        Uuid::from_bytes_le(bytes)
+            .hyphenated()
+            .to_string()
+            .to_uppercase(),
+    )
+}
+
+/// Display names of shell folders that appear as ShellBag roots
+fn known_folder_name(guid: &str) -> Option<&'static str> {
+    Some(match guid {
+        "20D04FE0-3AEA-1069-A2D8-08002B30309D" => "My Computer",
+        "59031A47-3F72-44A7-89C5-5595FE6B30EE" => "Users Files",
+        "450D8FBA-AD25-11D0-98A8-0800361B1103" => "My Documents",
+        "208D2C60-3AEA-1069-A2D7-08002B30309D" => "My Network Places",
+        "F02C1A0D-BE21-4350-88B0-7367FC96EF3C" => "Network",
+        "26EE0668-A00A-44D7-9371-BEB064C98683" => "Control Panel",
+        "21EC2020-3AEA-1069-A2DD-08002B30309D" => "Control Panel",
+        "031E4825-7B94-4DC3-B131-E946B44C8DD5" => "Libraries",
+        "645FF040-5081-101B-9F08-00AA002F954E" => "Recycle Bin",
+        "679F85CB-0220-4080-B29B-5540CC05AAB6" => "Quick Access",
+        "B4BFCC3A-DB2C-424C-B029-7FE99A87C641" => "Desktop",
+        "D3162B92-9365-467A-956B-92703ACA08AF" => "Documents",
+        "088E3905-0323-4B02-9826-5D99428E115F" => "Downloads",
+        "374DE290-123F-4565-9164-39C4925E467B" => "Downloads",
+        "24AD3AD4-A569-4530-98E1-AB02F9417AA8" => "Pictures",
+        "3DFDF296-DBEC-4FB4-81D1-6A3438BCF4DE" => "Music",
+        "F86FA3AB-70D2-4FC7-9C99-FCBF05467F3A" => "Videos",
+        _ => return None,
+    })
+}
+
+fn guid_name(data: &[u8], offset: usize) -> Option<String> {
+    let guid = guid_at(data, offset)?;
+    Some(
+        known_folder_name(&guid)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{{{}}}", guid)),
+    )
+}
+
+/// Decode the first shell item of an item ID list
+fn parse_shell_item(data: &[u8]) -> Option<ShellItem> {
+    let size = u16_at(data, 0).ok()? as usize;
+    let item = data.get(..size)?;
+    let class = *item.get(2)?;
+
+    let name = match class {
+        ROOT_FOLDER => guid_name(item, 4)?,
+        0x20..=0x2f => match ascii_at(item, 3).filter(|n| !n.is_empty()) {
+            Some(drive) => drive,
+            None => guid_name(item, 4)?,
+        },
+        0x30..=0x3f => return parse_file_entry(item, class),
+        0x40..=0x4f => ascii_at(item, 5)?,
+        CONTROL_PANEL => guid_name(item, 14)?,
+        _ => format!("<unknown shell item 0x{:02x}>", class),
+    };
+
+    Some(ShellItem {
+        name,
+        ..Default::default()
+    })
+}
+
+/// Folder or file shell item, with the long name and creation and access
+/// times from its `0xbeef0004` extension block
+fn parse_file_entry(item: &[u8], class: u8) -> Option<ShellItem> {
+    let modified = dos_datetime(item, 8);
+    let primary_name = if class & 0x04 != 0 {
+        utf16_at(item, 14)?
+    } else {
+        ascii_at(item, 14)?
+    };
+
+    let mut entry = ShellItem {
+        name: primary_name,
+        modified,
+        ..Default::default()
+    };
+
+    let Some(signature) = item
+        .windows(4)
+        .skip(14)
+        .position(|w| w == FILE_ENTRY_EXTENSION)
+    else {
+        return Some(entry);
+    };
+    let start = signature + 14 - 4;
+    let extension = item.get(start..)?;
+    let extension = extension.get(..u16_at(extension, 0).ok()? as usize)?;
+
+    entry.created = dos_datetime(extension, 8);
+    entry.accessed = dos_datetime(extension, 12);
+
+    let name_offset = match u16_at(extension, 2).ok()? {
+        9.. => 46,
+        8 => 42,
+        7 => 38,
+        3..=6 => 20,
+        _ => return Some(entry),
+    };
+    if let Some(long_name) = utf16_at(extension, name_offset).filter(|n| !n.is_empty()) {
+        entry.name = long_name;
+    }
+    Some(entry)
+}
+
+fn dword(value: Option<RegValue>) -> Option<u32> {
+    value.and_then(|v| u32_at(&v.data, 0).ok())
+}
+
+/// Slots in `MRUListEx` order
+fn mru_order(key: &Key) -> Vec<u32> {
+    key.value("MRUListEx")
+        .ok()
+        .flatten()
+        .map(|v| {
+            v.data
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .take_while(|&slot| slot != u32::MAX)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else if parent.ends_with('\\') {
+        format!("{}{}", parent, name)
+    } else {
+        format!("{}\\{}", parent, name)
+    }
+}
+
+/// Walks one `BagMRU` tree
+struct BagMruWalker<'a> {
+    bags: Option<Key<'a>>,
+    source: String,
+    entries: Vec<ShellBagEntry>,
+}
+
+impl<'a> BagMruWalker<'a> {
+    fn walk(
+        &mut self,
+        key: Key<'a>,
+        parent_path: &str,
+        key_path: &str,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(anyhow!("BagMRU nested too deeply at {}", key_path));
+        }
+
+        let order = mru_order(&key);
+        let mut slots: Vec<(u32, RegValue)> = key
+            .values()?
+            .into_iter()
+            .filter_map(|v| v.name.parse::<u32>().ok().map(|slot| (slot, v)))
+            .collect();
+        slots.sort_by_key(|(slot, _)| *slot);
+
+        for (slot, value) in slots {
+            let child_path = format!("{}\\{}", key_path, slot);
+            let Some(item) = parse_shell_item(&value.data) else {
+                debug!("Unreadable shell item at {}", child_path);
+                continue;
+            };
+            let folder_path = join_path(parent_path, &item.name);
+
+            let child = key.subkey(&value.name)?;
+            let node_slot = child.and_then(|c| dword(c.value("NodeSlot").ok().flatten()));
+            let bag_last_written = node_slot
+                .zip(self.bags)
+                .and_then(|(node_slot, bags)| bags.subkey(&node_slot.to_string()).ok().flatten())
+                .and_then(|bag| bag.last_written());
+
+            self.entries.push(ShellBagEntry {
+                folder_path: folder_path.clone(),
+                created_time: item.created,
+                modified_time: item.modified,
+                accessed_time: item.accessed,
+                slot_index: slot,
+                mru_position: order.iter().position(|&s| s == slot).map(|p| p as u32),
+                node_slot,
+                bag_last_written,
+                bag_mru_key: child_path.clone(),
+                source: self.source.clone(),
+            });
+
+            if let Some(child) = child {
+                self.walk(child, &folder_path, &child_path, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the ShellBags of one collected hive (`NTUSER.DAT` or `UsrClass.dat`)
+pub fn parse_hive_shellbags(hive_path: &Path) -> Result<Vec<ShellBagEntry>> {
+    let hive = Hive::open(hive_path)?;
+    let source = hive_path.to_string_lossy().to_string();
+    let mut entries = Vec::new();
+
+    for shell_key in SHELL_KEYS {
+        let Some(shell) = hive.open_key(shell_key)? else {
+            continue;
+        };
+        let Some(bag_mru) = shell.subkey("BagMRU")? else {
+            continue;
+        };
+        let mut walker = BagMruWalker {
+            bags: shell.subkey("Bags")?,
+            source: source.clone(),
+            entries: Vec::new(),
+        };
+        walker.walk(bag_mru, "", "BagMRU", 0)?;
+        entries.extend(walker.entries);
+    }
+
+    Ok(entries)
+}
+
+/// Build the `shellbags.json` report from whichever hives were collected.
+///
+/// A hive that cannot be parsed is noted; the report fails only if none can.
+pub fn shellbags_report(hives: &[PathBuf]) -> Result<ParsedReport<ShellBagEntry>> {
+    let mut report = ParsedReport::new(hives);
+    let mut failures = Vec::new();
+
+    for hive in hives {
+        match parse_hive_shellbags(hive) {
+            Ok(entries) => report.records.extend(entries),
+            Err(e) => failures.push(format!("{}: {:#}", hive.display(), e)),
+        }
+    }
+
+    if failures.len() == hives.len() {
+        return Err(anyhow!("No hive could be parsed: {}", failures.join("; ")));
+    }
+    report.notes.extend(
+        failures
+            .into_iter()
+            .map(|failure| format!("Hive not parsed: {}", failure)),
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::parsers::fixtures::{HiveBuilder, HiveKey};
+    use tempfile::TempDir;
+
+    /// 2024-03-05 14:30:10 as DOS date and time
+    const DOS_DATE: u16 = ((2024 - 1980) << 9) | (3 << 5) | 5;
+    const DOS_TIME: u16 = (14 << 11) | (30 << 5) | 5;
+
+    fn with_size(mut body: Vec<u8>) -> Vec<u8> {
+        let size = (body.len() + 2) as u16;
+        body.splice(0..0, size.to_le_bytes());
+        // Terminating empty item
+        body.extend_from_slice(&[0, 0]);
+        body
+    }
+
+    fn root_item(guid: [u8; 16]) -> Vec<u8> {
+        let mut body = vec![ROOT_FOLDER, 0x50];
+        body.extend_from_slice(&guid);
+        with_size(body)
+    }
+
+    fn volume_item(drive: &str) -> Vec<u8> {
+        let mut body = vec![0x2f];
+        body.extend_from_slice(drive.as_bytes());
+        body.resize(23, 0);
+        with_size(body)
+    }
+
+    fn folder_item(short_name: &str, long_name: &str) -> Vec<u8> {
+        let mut body = vec![0x31, 0];
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&DOS_DATE.to_le_bytes());
+        body.extend_from_slice(&DOS_TIME.to_le_bytes());
+        body.extend_from_slice(&0x10u16.to_le_bytes());
+        body.extend_from_slice(short_name.as_bytes());
+        body.push(0);
+        if body.len() % 2 != 0 {
+            body.push(0);
+        }
+
+        // Version 9 extension block: long name at offset 46
+        let mut extension = vec![0u8; 46];
+        extension[2..4].copy_from_slice(&9u16.to_le_bytes());
+        extension[4..8].copy_from_slice(&FILE_ENTRY_EXTENSION);
+        extension[8..10].copy_from_slice(&(DOS_DATE - 1).to_le_bytes());
+        extension[10..12].copy_from_slice(&DOS_TIME.to_le_bytes());
+        extension[12..14].copy_from_slice(&(DOS_DATE + 1).to_le_bytes());
+        extension[14..16].copy_from_slice(&DOS_TIME.to_le_bytes());
+        extension.extend(long_name.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        extension.extend_from_slice(&[0, 0, 0, 0]);
+        let size = extension.len() as u16;
+        extension[0..2].copy_from_slice(&size.to_le_bytes());
+
+        body.extend_from_slice(&extension);
+        with_size(body)
+    }
+
+    fn mru_list(slots: &[u32]) -> Vec<u8> {
+        slots
+            .iter()
+            .chain(Some(&u32::MAX))
+            .flat_map(|s| s.to_le_bytes())
+            .collect()
+    }
+
+    const MY_COMPUTER: [u8; 16] = [
+        0xe0, 0x4f, 0xd0, 0x20, 0xea, 0x3a, 0x69, 0x10, 0xa2, 0xd8, 0x08, 0x00, 0x2b, 0x30, 0x30,
+        0x9d,
+    ];
+
+    fn usrclass_hive(dir: &Path) -> PathBuf {
+        let mut root = HiveKey::new("ROOT");
+        let shell = root
+            .subkey("Local Settings")
+            .subkey("Software")
+            .subkey("Microsoft")
+            .subkey("Windows")
+            .subkey("Shell");
+        shell.subkey("Bags").subkey("3").subkey("Shell");
+
+        let bag_mru = shell.subkey("BagMRU");
+        bag_mru
+            .binary_value("0", &root_item(MY_COMPUTER))
+            .binary_value("MRUListEx", &mru_list(&[0]));
+        let computer = bag_mru.subkey("0");
+        computer
+            .dword_value("NodeSlot", 1)
+            .binary_value("0", &volume_item(r"C:\"))
+            .binary_value("MRUListEx", &mru_list(&[0]));
+        let drive = computer.subkey("0");
+        drive
+            .dword_value("NodeSlot", 2)
+            .binary_value("0", &folder_item("PROGRA~1", "Program Files"))
+            .binary_value("1", &folder_item("Tools", ""))
+            .binary_value("MRUListEx", &mru_list(&[1, 0]));
+        drive.subkey("1").dword_value("NodeSlot", 3);
+
+        let path = dir.join("UsrClass.dat");
+        std::fs::write(&path, HiveBuilder::build(&root)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_shell_items() {
+        let item = parse_shell_item(&root_item(MY_COMPUTER)).unwrap();
+        assert_eq!(item.name, "My Computer");
+        assert_eq!(parse_shell_item(&volume_item("E:\\")).unwrap().name, "E:\\");
+
+        let folder = parse_shell_item(&folder_item("PROGRA~1", "Program Files")).unwrap();
+        assert_eq!(folder.name, "Program Files");
+        assert_eq!(
+            folder.modified.unwrap().to_rfc3339(),
+            "2024-03-05T14:30:10+00:00"
+        );
+        assert_eq!(
+            folder.created.unwrap().to_rfc3339(),
+            "2024-03-04T14:30:10+00:00"
+        );
+        assert_eq!(
+            folder.accessed.unwrap().to_rfc3339(),
+            "2024-03-06T14:30:10+00:00"
+        );
+
+        // No long name: the primary name is kept
+        assert_eq!(
+            parse_shell_item(&folder_item("Tools", "")).unwrap().name,
+            "Tools"
+        );
+        assert!(parse_shell_item(&[0x40, 0x00]).is_none());
+    }
+
+    #[test]
+    fn test_parse_bag_mru_tree() {
+        let temp = TempDir::new().unwrap();
+        let hive = usrclass_hive(temp.path());
+
+        let entries = parse_hive_shellbags(&hive).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.folder_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "My Computer",
+                r"My Computer\C:\",
+                r"My Computer\C:\Program Files",
+                r"My Computer\C:\Tools",
+            ]
+        );
+
+        let tools = &entries[3];
+        assert_eq!(tools.bag_mru_key, r"BagMRU\0\0\1");
+        assert_eq!(tools.slot_index, 1);
+        assert_eq!(tools.mru_position, Some(0));
+        assert_eq!(tools.node_slot, Some(3));
+        assert!(tools.bag_last_written.is_some());
+        assert!(tools.created_time.is_some());
+
+        let program_files = &entries[2];
+        assert_eq!(program_files.mru_position, Some(1));
+        assert_eq!(program_files.node_slot, None);
+        assert_eq!(program_files.bag_last_written, None);
+        assert_eq!(entries[0].node_slot, Some(1));
+        assert_eq!(entries[0].modified_time, None);
+    }
+
+    #[test]
+    fn test_report_notes_unparseable_hive() {
+        let temp = TempDir::new().unwrap();
+        let usrclass = usrclass_hive(temp.path());
+        let ntuser = temp.path().join("NTUSER.DAT");
+        std::fs::write(&ntuser, "not a hive").unwrap();
+
+        let report = shellbags_report(&[ntuser.clone(), usrclass]).unwrap();
+        assert_eq!(report.records.len(), 4);
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].contains("NTUSER.DAT"));
+
+        assert!(shellbags_report(&[ntuser]).is_err());
+    }
+}
@@ -11,12 +11,21 @@ pub enum ArtifactType {
     SystemInfo,
     Memory,
     Network,
+    /// Cloud CLI, kubeconfig and Docker credential files across user homes,
+    /// inventoried with secrets scrubbed
+    CloudCredentials,
+    /// Outlook, Windows Mail and Apple Mail stores across user homes,
+    /// inventoried and copied only on request
+    MailStores,
 
     // OS-specific types
     Windows(WindowsArtifactType),
     Linux(LinuxArtifactType),
     MacOS(MacOSArtifactType),
 
+    // Container runtimes and orchestration
+    Container(ContainerType),
+
     // Volatile data collection
     VolatileData(VolatileDataType),
 
@@ -34,6 +43,16 @@ pub enum VolatileDataType {
     Disks,
 }
 
+/// Container artifact types (Docker, Podman, Kubernetes)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub enum ContainerType {
+    DockerDaemonJson,
+    DockerContainerLogs,
+    PodmanContainerLogs,
+    KubernetesAuditLog,
+    ContainerImage,
+}
+
 /// Windows-specific artifact types
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum WindowsArtifactType {
@@ -44,6 +63,21 @@ pub enum WindowsArtifactType {
     USNJournal,
     ShimCache,
     AmCache,
+    ETWSessions,
+    SRUM,
+    BITS,
+    ScheduledTasks,
+    WMIRepository,
+    ShellBags,
+    IISLogs,
+    RecycleBin,
+    RecentItems,
+    OfficeStartup,
+    PowerShellHistory,
+    OfficeMRU,
+    NetworkShares,
+    PagingFile,
+    BrowserExtensions,
 }
 
 /// Linux-specific artifact types
@@ -59,6 +93,10 @@ pub enum LinuxArtifactType {
     Dpkg,
     Yum,
     Systemd,
+    Persistence,
+    SSHDConfig,
+    Dmesg,
+    ImmutableFiles,
 }
 
 /// macOS-specific artifact types
@@ -72,6 +110,12 @@ pub enum MacOSArtifactType {
     KnowledgeC,
     LaunchAgents,
     LaunchDaemons,
+    Keychains,
+    BackgroundTaskManagement,
+    ConfigurationProfiles,
+    AppFirewall,
+    LoginRecords,
+    WiFiHistory,
 }
 
 impl fmt::Display for ArtifactType {
@@ -83,9 +127,12 @@ impl fmt::Display for ArtifactType {
             ArtifactType::SystemInfo => write!(f, "SystemInfo"),
             ArtifactType::Memory => write!(f, "Memory"),
             ArtifactType::Network => write!(f, "Network"),
+            ArtifactType::CloudCredentials => write!(f, "CloudCredentials"),
+            ArtifactType::MailStores => write!(f, "MailStores"),
             ArtifactType::Windows(wtype) => write!(f, "Windows-{:?}", wtype),
             ArtifactType::Linux(ltype) => write!(f, "Linux-{:?}", ltype),
             ArtifactType::MacOS(mtype) => write!(f, "MacOS-{:?}", mtype),
+            ArtifactType::Container(ctype) => write!(f, "Container-{:?}", ctype),
             ArtifactType::VolatileData(vtype) => write!(f, "VolatileData-{:?}", vtype),
             ArtifactType::Custom => write!(f, "Custom"),
         }
@@ -125,6 +172,13 @@ mod tests {
         let deserialized: ArtifactType = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, win_type);
 
+        // Test container types
+        let container_type = ArtifactType::Container(ContainerType::KubernetesAuditLog);
+        let serialized = serde_json::to_string(&container_type).unwrap();
+        assert_eq!(serialized, r#"{"Container":"KubernetesAuditLog"}"#);
+        let deserialized: ArtifactType = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, container_type);
+
         // Test volatile data types
         let volatile_type = ArtifactType::VolatileData(VolatileDataType::Processes);
         let serialized = serde_json::to_string(&volatile_type).unwrap();
@@ -140,6 +194,11 @@ mod tests {
         assert_eq!(ArtifactType::FileSystem.to_string(), "FileSystem");
         assert_eq!(ArtifactType::Logs.to_string(), "Logs");
         assert_eq!(ArtifactType::Custom.to_string(), "Custom");
+        assert_eq!(
+            ArtifactType::CloudCredentials.to_string(),
+            "CloudCredentials"
+        );
+        assert_eq!(ArtifactType::MailStores.to_string(), "MailStores");
 
         // OS-specific
         assert_eq!(
@@ -155,6 +214,12 @@ mod tests {
             "MacOS-UnifiedLogs"
         );
 
+        // Containers
+        assert_eq!(
+            ArtifactType::Container(ContainerType::DockerContainerLogs).to_string(),
+            "Container-DockerContainerLogs"
+        );
+
         // Volatile data
         assert_eq!(
             ArtifactType::VolatileData(VolatileDataType::SystemInfo).to_string(),
@@ -188,6 +253,46 @@ mod tests {
             ArtifactType::Windows(WindowsArtifactType::MFT),
             ArtifactType::Windows(WindowsArtifactType::Registry)
         );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::ETWSessions).to_string(),
+            "Windows-ETWSessions"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::SRUM).to_string(),
+            "Windows-SRUM"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::WMIRepository).to_string(),
+            "Windows-WMIRepository"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::ShellBags).to_string(),
+            "Windows-ShellBags"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::IISLogs).to_string(),
+            "Windows-IISLogs"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::RecycleBin).to_string(),
+            "Windows-RecycleBin"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::RecentItems).to_string(),
+            "Windows-RecentItems"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::OfficeStartup).to_string(),
+            "Windows-OfficeStartup"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::PowerShellHistory).to_string(),
+            "Windows-PowerShellHistory"
+        );
+        assert_eq!(
+            ArtifactType::Windows(WindowsArtifactType::OfficeMRU).to_string(),
+            "Windows-OfficeMRU"
+        );
     }
 
     #[test]
@@ -240,6 +345,10 @@ mod tests {
             LinuxArtifactType::Dpkg,
             LinuxArtifactType::Yum,
             LinuxArtifactType::Systemd,
+            LinuxArtifactType::Persistence,
+            LinuxArtifactType::SSHDConfig,
+            LinuxArtifactType::Dmesg,
+            LinuxArtifactType::ImmutableFiles,
         ];
 
         for linux_type in types {
@@ -262,6 +371,12 @@ mod tests {
             MacOSArtifactType::KnowledgeC,
             MacOSArtifactType::LaunchAgents,
             MacOSArtifactType::LaunchDaemons,
+            MacOSArtifactType::Keychains,
+            MacOSArtifactType::BackgroundTaskManagement,
+            MacOSArtifactType::ConfigurationProfiles,
+            MacOSArtifactType::AppFirewall,
+            MacOSArtifactType::LoginRecords,
+            MacOSArtifactType::WiFiHistory,
         ];
 
         for macos_type in types {
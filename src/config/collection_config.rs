@@ -7,8 +7,10 @@ use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
 use crate::config::artifact_types::ArtifactType;
-use crate::config::env_vars::{normalize_path_for_os, parse_unix_env_vars, parse_windows_env_vars};
+use crate::config::env_vars::{normalize_path_for_os, resolve_all_variables};
+use crate::config::migrations::migrate_config;
 use crate::config::regex_config::RegexConfig;
+use crate::errors::CollectorError;
 
 // Include default config at compile time
 #[cfg(feature = "embed_config")]
@@ -52,21 +54,31 @@ impl Default for CollectionConfig {
 }
 
 impl CollectionConfig {
-    /// Load configuration from a YAML file
+    /// Load configuration from a YAML file, migrating older schema versions
     pub fn from_yaml_file(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
             .context(format!("Failed to read config file: {}", path.display()))?;
+        let content = migrate_config(&content).map_err(|e| CollectorError::ConfigError {
+            message: format!("{}: {}", path.display(), e),
+        })?;
 
         let config: CollectionConfig =
-            serde_yaml::from_str(&content).context("Failed to parse YAML config")?;
+            serde_yaml::from_str(&content).map_err(|e| CollectorError::ConfigError {
+                message: format!("Failed to parse YAML config {}: {}", path.display(), e),
+            })?;
 
         debug!("Loaded configuration from {}", path.display());
         Ok(config)
     }
 
+    /// Serialize the configuration to YAML
+    pub fn to_yaml_string(&self) -> Result<String> {
+        serde_yaml::to_string(self).context("Failed to serialize config to YAML")
+    }
+
     /// Save configuration to a YAML file
     pub fn save_to_yaml_file(&self, path: &Path) -> Result<()> {
-        let yaml = serde_yaml::to_string(self).context("Failed to serialize config to YAML")?;
+        let yaml = self.to_yaml_string()?;
 
         fs::write(path, yaml).context(format!("Failed to write config to {}", path.display()))?;
 
@@ -83,11 +95,13 @@ impl CollectionConfig {
         let os_config_path = format!("default_{}_config.yaml", os_name);
 
         if let Some(file) = CONFIG_DIR.get_file(&os_config_path) {
-            let content = file.contents_utf8().ok_or_else(|| {
-                anyhow::anyhow!("Failed to read embedded OS-specific config as UTF-8")
-            })?;
+            let content = file
+                .contents_utf8()
+                .ok_or_else(|| CollectorError::ConfigError {
+                    message: format!("Embedded config {} is not valid UTF-8", os_config_path),
+                })?;
 
-            let config: CollectionConfig = serde_yaml::from_str(content)
+            let config: CollectionConfig = serde_yaml::from_str(&migrate_config(content)?)
                 .context("Failed to parse embedded OS-specific YAML config")?;
 
             info!("Using embedded OS-specific configuration for {}", os_name);
@@ -96,10 +110,12 @@ impl CollectionConfig {
             // Fall back to generic config
             let content = file
                 .contents_utf8()
-                .ok_or_else(|| anyhow::anyhow!("Failed to read embedded config as UTF-8"))?;
+                .ok_or_else(|| CollectorError::ConfigError {
+                    message: "Embedded config default_config.yaml is not valid UTF-8".to_string(),
+                })?;
 
-            let config: CollectionConfig =
-                serde_yaml::from_str(content).context("Failed to parse embedded YAML config")?;
+            let config: CollectionConfig = serde_yaml::from_str(&migrate_config(content)?)
+                .context("Failed to parse embedded YAML config")?;
 
             info!("Using generic embedded configuration");
             Ok(config)
@@ -113,17 +129,8 @@ impl CollectionConfig {
     /// Handles both Windows (%VAR%) and Unix ($VAR) style variables
     pub fn process_environment_variables(&mut self) -> Result<()> {
         for artifact in &mut self.artifacts {
-            // Process Windows-style %VARIABLE% environment variables
-            if artifact.source_path.contains('%') {
-                let processed_path = parse_windows_env_vars(&artifact.source_path);
-                artifact.source_path = processed_path;
-            }
-
-            // Process Unix-style $VARIABLE and ${VARIABLE} environment variables
-            if artifact.source_path.contains('$') {
-                let processed_path = parse_unix_env_vars(&artifact.source_path);
-                artifact.source_path = processed_path;
-            }
+            // Resolve %VARIABLE%, $VARIABLE, ${VARIABLE} and ~ in any combination
+            artifact.source_path = resolve_all_variables(&artifact.source_path);
 
             // Normalize path separators for the current OS
             artifact.source_path = normalize_path_for_os(&artifact.source_path);
@@ -138,6 +145,22 @@ impl CollectionConfig {
         default_config.save_to_yaml_file(path)
     }
 
+    /// Default configuration for a `--config-template` name (`windows`,
+    /// `linux`, `macos`, `container` or `minimal`)
+    pub fn default_for_template(name: &str) -> Result<Self> {
+        match name {
+            "windows" => Ok(Self::default_windows()),
+            "linux" => Ok(Self::default_linux()),
+            "macos" => Ok(Self::default_macos()),
+            "container" => Ok(Self::default_container()),
+            "minimal" => Ok(Self::default_minimal()),
+            other => Err(CollectorError::ConfigError {
+                message: format!("Unknown config template: {}", other),
+            }
+            .into()),
+        }
+    }
+
     /// Create an OS-specific default configuration file
     pub fn create_os_specific_config_file(path: &Path, target_os: &str) -> Result<()> {
         let config = match target_os {
@@ -225,6 +248,8 @@ pub fn load_or_create_config(config_path: Option<&Path>) -> Result<CollectionCon
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::migrations::CURRENT_SCHEMA_VERSION;
+    use crate::config::WindowsArtifactType;
     use std::fs;
     use tempfile::{NamedTempFile, TempDir};
 
@@ -243,7 +268,7 @@ mod tests {
 
     fn create_test_config() -> CollectionConfig {
         CollectionConfig {
-            version: "1.0".to_string(),
+            version: CURRENT_SCHEMA_VERSION.to_string(),
             description: "Test configuration".to_string(),
             artifacts: vec![create_test_artifact()],
             global_options: HashMap::new(),
@@ -256,7 +281,7 @@ mod tests {
 
         // Serialize to YAML
         let yaml = serde_yaml::to_string(&config).unwrap();
-        assert!(yaml.contains("version: '1.0'"));
+        assert!(yaml.contains("version: '1.1'"));
         assert!(yaml.contains("test_artifact"));
 
         // Deserialize back
@@ -286,7 +311,7 @@ mod tests {
     fn test_default_configs() {
         // Test Windows default
         let windows_config = CollectionConfig::default_windows();
-        assert!(windows_config.version == "1.0");
+        assert!(windows_config.version == CURRENT_SCHEMA_VERSION);
         assert!(windows_config.artifacts.iter().any(|a| a.name == "MFT"));
         assert!(windows_config.artifacts.iter().any(|a| a.name == "SYSTEM"));
 
@@ -311,7 +336,7 @@ mod tests {
     #[test]
     fn test_process_environment_variables() {
         let mut config = CollectionConfig {
-            version: "1.0".to_string(),
+            version: CURRENT_SCHEMA_VERSION.to_string(),
             description: "Test".to_string(),
             artifacts: vec![
                 Artifact {
@@ -371,14 +396,33 @@ mod tests {
         // Load non-existent file (should create default)
         let loaded = load_or_create_config(Some(&config_path)).unwrap();
         assert!(config_path.exists());
-        assert_eq!(loaded.version, "1.0");
+        assert_eq!(loaded.version, CURRENT_SCHEMA_VERSION);
     }
 
     #[test]
     fn test_load_or_create_config_no_path() {
         // Load with no path (should use default)
         let loaded = load_or_create_config(None).unwrap();
-        assert_eq!(loaded.version, "1.0");
+        assert_eq!(loaded.version, CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_tagged_artifact_type_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("tagged.yaml");
+
+        let mut config = create_test_config();
+        config.artifacts[0].artifact_type = ArtifactType::Windows(WindowsArtifactType::MFT);
+        config.save_to_yaml_file(&config_path).unwrap();
+        assert!(fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("!Windows MFT"));
+
+        let loaded = CollectionConfig::from_yaml_file(&config_path).unwrap();
+        assert_eq!(
+            loaded.artifacts[0].artifact_type,
+            ArtifactType::Windows(WindowsArtifactType::MFT)
+        );
     }
 
     #[test]
@@ -400,6 +444,18 @@ mod tests {
         assert!(linux_config.artifacts.iter().any(|a| a.name == "syslog"));
     }
 
+    #[test]
+    fn test_config_templates_round_trip() {
+        for name in ["windows", "linux", "macos", "container", "minimal"] {
+            let config = CollectionConfig::default_for_template(name).unwrap();
+            let yaml = config.to_yaml_string().unwrap();
+            let parsed: CollectionConfig = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(parsed.artifacts.len(), config.artifacts.len(), "{}", name);
+        }
+
+        assert!(CollectionConfig::default_for_template("solaris").is_err());
+    }
+
     #[test]
     fn test_artifact_with_regex() {
         let artifact = Artifact {
@@ -448,7 +504,7 @@ mod tests {
     #[test]
     fn test_path_normalization() {
         let mut config = CollectionConfig {
-            version: "1.0".to_string(),
+            version: CURRENT_SCHEMA_VERSION.to_string(),
             description: "Test".to_string(),
             artifacts: vec![Artifact {
                 name: "mixed_separators".to_string(),
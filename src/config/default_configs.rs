@@ -1,14 +1,119 @@
+use crate::collectors::windows::paging_files;
 use crate::config::artifact_types::{
-    ArtifactType, LinuxArtifactType, MacOSArtifactType, WindowsArtifactType,
+    ArtifactType, ContainerType, LinuxArtifactType, MacOSArtifactType, WindowsArtifactType,
 };
 use crate::config::collection_config::{Artifact, CollectionConfig};
+use crate::config::migrations::CURRENT_SCHEMA_VERSION;
+use crate::config::regex_config::RegexConfig;
 use std::collections::HashMap;
 
+/// Log files inside the `W3SVC<site id>` directories of an IIS log folder
+fn iis_log_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: r"(?i)^W3SVC[0-9]*[\\/][^\\/]+\.log$".into(),
+        exclude_pattern: String::new(),
+        max_depth: Some(2),
+    }
+}
+
+/// `$I` index and `$R` content files in the per-SID Recycle Bin folders
+fn recycle_bin_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: r"(?i)^S-1-[0-9-]+[\\/]\$[IR][^\\/]*$".into(),
+        exclude_pattern: String::new(),
+        max_depth: Some(2),
+    }
+}
+
+/// Shortcuts in Recent and JumpLists in its *Destinations folders
+fn recent_items_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: r"(?i)\.(lnk|automaticDestinations-ms|customDestinations-ms)$".into(),
+        exclude_pattern: String::new(),
+        max_depth: Some(2),
+    }
+}
+
+/// Shortcuts Office creates for each document it opens
+fn office_recent_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: false,
+        include_pattern: r"(?i)^[^\\/]+\.lnk$".into(),
+        exclude_pattern: String::new(),
+        max_depth: None,
+    }
+}
+
+/// Timer units shipped by packages alongside the services they start
+fn systemd_timers_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: r"^[^\\/]+\.timer$".into(),
+        exclude_pattern: String::new(),
+        max_depth: Some(1),
+    }
+}
+
+/// sudo I/O log session `log` and `timing` files; the recorded terminal
+/// streams (`ttyin`, `ttyout`, ...) may hold typed passwords and are skipped
+fn sudo_io_regex() -> RegexConfig {
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: r"(^|[\\/])(log|log\.json|timing)$".into(),
+        exclude_pattern: String::new(),
+        max_depth: Some(4),
+    }
+}
+
+/// Shell startup files in `/root` or, with `per_user`, in each home
+/// directory under `/home`
+fn shell_profiles_regex(per_user: bool) -> RegexConfig {
+    let user_prefix = if per_user { r"[^\\/]+[\\/]" } else { "" };
+    RegexConfig {
+        enabled: true,
+        recursive: true,
+        include_pattern: format!(
+            r"^{}\.(bashrc|bash_profile|bash_login|bash_logout|profile|zshrc|zprofile|zshenv|zlogin)$",
+            user_prefix
+        ),
+        exclude_pattern: String::new(),
+        max_depth: Some(if per_user { 2 } else { 1 }),
+    }
+}
+
+/// The `.LOG1` and `.LOG2` transaction logs of a hive artifact, collected
+/// next to the hive so changes not yet flushed to it can be replayed when it
+/// is parsed
+fn registry_transaction_logs(hive: &Artifact) -> Vec<Artifact> {
+    ["LOG1", "LOG2"]
+        .iter()
+        .map(|extension| Artifact {
+            name: format!("{}.{}", hive.name, extension),
+            artifact_type: ArtifactType::Windows(WindowsArtifactType::Registry),
+            source_path: format!("{}.{}", hive.source_path, extension),
+            destination_name: format!("{}.{}", hive.destination_name, extension),
+            description: Some(format!("{} transaction log", hive.name)),
+            required: false,
+            metadata: HashMap::new(),
+            regex: None,
+        })
+        .collect()
+}
+
 impl CollectionConfig {
     /// Default configuration for Windows
     pub fn default_windows() -> Self {
-        CollectionConfig {
-            version: "1.0".into(),
+        let mut config = CollectionConfig {
+            version: CURRENT_SCHEMA_VERSION.into(),
             description: "Default Windows DFIR triage configuration".into(),
             artifacts: vec![
                 // MFT
@@ -114,6 +219,16 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                Artifact {
+                    name: "Windows PowerShell.evtx".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::EventLog),
+                    source_path: r"\\?\C:\Windows\System32\winevt\Logs\Windows PowerShell.evtx".into(),
+                    destination_name: "Windows-PowerShell.evtx".into(),
+                    description: Some("Windows PowerShell engine and module logging".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
                 Artifact {
                     name: "Sysmon.evtx".into(),
                     artifact_type: ArtifactType::Windows(WindowsArtifactType::EventLog),
@@ -146,15 +261,228 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                // ETW trace sessions
+                Artifact {
+                    name: "ETW Sessions".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::ETWSessions),
+                    source_path: "ETW".into(),
+                    destination_name: "etw_sessions.json".into(),
+                    description: Some("Running ETW trace sessions and their providers".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // SMB shares and connected network resources
+                Artifact {
+                    name: "Network Shares".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::NetworkShares),
+                    source_path: "SMB".into(),
+                    destination_name: "network_shares.json".into(),
+                    description: Some("SMB shares, share permissions and mapped drives".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // SRUM database (locked; parsed with the SOFTWARE hive above)
+                Artifact {
+                    name: "SRUM".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::SRUM),
+                    source_path: r"\\?\C:\Windows\System32\sru\SRUDB.dat".into(),
+                    destination_name: "SRUDB.dat".into(),
+                    description: Some("System Resource Usage Monitor database".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // BITS job database (qmgr.db, or qmgr0.dat/qmgr1.dat before Windows 10)
+                Artifact {
+                    name: "BITS".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::BITS),
+                    source_path: r"\\?\C:\ProgramData\Microsoft\Network\Downloader".into(),
+                    destination_name: "Downloader".into(),
+                    description: Some("BITS job database".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Scheduled task definitions (parsed with the TaskCache of the SOFTWARE hive)
+                Artifact {
+                    name: "Scheduled Tasks".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::ScheduledTasks),
+                    source_path: r"\\?\C:\Windows\System32\Tasks".into(),
+                    destination_name: "Tasks".into(),
+                    description: Some("Scheduled task XML files".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // WMI repository (event filters, consumers and bindings)
+                Artifact {
+                    name: "WMI Repository".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::WMIRepository),
+                    source_path: r"\\?\C:\Windows\System32\wbem\Repository".into(),
+                    destination_name: "Repository".into(),
+                    description: Some("WMI CIM repository".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // UsrClass.dat (ShellBags; parsed together with NTUSER.DAT)
+                Artifact {
+                    name: "UsrClass.dat".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::ShellBags),
+                    source_path: r"\\?\%USERPROFILE%\AppData\Local\Microsoft\Windows\UsrClass.dat"
+                        .into(),
+                    destination_name: "UsrClass.dat".into(),
+                    description: Some("User class registry hive (ShellBags)".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // IIS W3C logs, one W3SVC<site id> directory per site (parsed)
+                Artifact {
+                    name: "IIS Logs".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::IISLogs),
+                    source_path: r"%SystemRoot%\System32\LogFiles".into(),
+                    destination_name: "IISLogs".into(),
+                    description: Some("IIS web server logs (W3SVC*)".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(iis_log_regex()),
+                },
+                // Default log location since IIS 7
+                Artifact {
+                    name: "IIS Logs (inetpub)".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::IISLogs),
+                    source_path: r"%SystemDrive%\inetpub\logs\LogFiles".into(),
+                    destination_name: "IISLogs-inetpub".into(),
+                    description: Some("IIS web server logs (W3SVC*)".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(iis_log_regex()),
+                },
+                // Deleted files: $I metadata and $R content per user SID (parsed)
+                Artifact {
+                    name: "Recycle Bin".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::RecycleBin),
+                    source_path: r"%SystemDrive%\$Recycle.Bin".into(),
+                    destination_name: "RecycleBin".into(),
+                    description: Some("Recycle Bin index and content files".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(recycle_bin_regex()),
+                },
+                // LNK files and JumpLists of the collecting user (parsed)
+                Artifact {
+                    name: "Recent Items".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::RecentItems),
+                    source_path: r"%USERPROFILE%\AppData\Roaming\Microsoft\Windows\Recent"
+                        .into(),
+                    destination_name: "Recent".into(),
+                    description: Some("Recent LNK files and JumpLists".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(recent_items_regex()),
+                },
+                // Office startup files of the collecting user, with extracted macros
+                Artifact {
+                    name: "Office Startup".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::OfficeStartup),
+                    source_path: "%APPDATA%".into(),
+                    destination_name: "OfficeStartup".into(),
+                    description: Some("XLSTART, Word STARTUP and Normal.dotm".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // PowerShell history, profiles and transcripts of every user (parsed)
+                Artifact {
+                    name: "PowerShell History".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::PowerShellHistory),
+                    source_path: r"%SystemDrive%\Users".into(),
+                    destination_name: "PowerShell".into(),
+                    description: Some("PSReadLine history, profiles and transcripts".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Chrome, Edge and Firefox extensions of every user
+                Artifact {
+                    name: "Browser Extensions".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::BrowserExtensions),
+                    source_path: r"%SystemDrive%\Users".into(),
+                    destination_name: "BrowserExtensions".into(),
+                    description: Some("Installed browser extensions and their permissions".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Office Recent shortcuts; File MRU lists are parsed from NTUSER.DAT
+                Artifact {
+                    name: "Office Recent".into(),
+                    artifact_type: ArtifactType::Windows(WindowsArtifactType::OfficeMRU),
+                    source_path: r"%APPDATA%\Microsoft\Office\Recent".into(),
+                    destination_name: "OfficeRecent".into(),
+                    description: Some("Office recent document shortcuts".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(office_recent_regex()),
+                },
+                // Cloud CLI credential files of every user, inventoried not copied
+                Artifact {
+                    name: "cloud_credentials".into(),
+                    artifact_type: ArtifactType::CloudCredentials,
+                    source_path: r"%SystemDrive%\Users".into(),
+                    destination_name: "CloudCredentials".into(),
+                    description: Some(
+                        "AWS, Azure, gcloud, kubeconfig and Docker credential inventory (secrets scrubbed)"
+                            .into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Mail client stores of every user, listed unless opted in
+                Artifact {
+                    name: "mail_stores".into(),
+                    artifact_type: ArtifactType::MailStores,
+                    source_path: r"%SystemDrive%\Users".into(),
+                    destination_name: "MailStores".into(),
+                    description: Some(
+                        "Outlook, new Outlook and Windows Mail stores (listed unless metadata_only is false)".into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
             ],
             global_options: HashMap::new(),
-        }
+        };
+
+        let hives = [
+            "SYSTEM",
+            "SOFTWARE",
+            "SECURITY",
+            "SAM",
+            "NTUSER.DAT",
+            "UsrClass.dat",
+        ];
+        let logs: Vec<Artifact> = config
+            .artifacts
+            .iter()
+            .filter(|a| hives.contains(&a.name.as_str()))
+            .flat_map(registry_transaction_logs)
+            .collect();
+        config.artifacts.extend(logs);
+        // Not required, since they can be tens of GB
+        config.artifacts.extend(paging_files::default_artifacts());
+        config
     }
 
     /// Default configuration for Linux
     pub fn default_linux() -> Self {
         CollectionConfig {
-            version: "1.0".into(),
+            version: CURRENT_SCHEMA_VERSION.into(),
             description: "Default Linux DFIR triage configuration".into(),
             artifacts: vec![
                 // System logs
@@ -178,6 +506,66 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                Artifact {
+                    name: "secure".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/secure".into(),
+                    destination_name: "secure".into(),
+                    description: Some("Authentication logs (Red Hat family)".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "wtmp".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/wtmp".into(),
+                    destination_name: "wtmp".into(),
+                    description: Some("Login, logout and boot records".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "btmp".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/btmp".into(),
+                    destination_name: "btmp".into(),
+                    description: Some("Failed login records".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "lastlog".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/lastlog".into(),
+                    destination_name: "lastlog".into(),
+                    description: Some("Last login per user".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "passwd".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/passwd".into(),
+                    destination_name: "passwd".into(),
+                    description: Some("Local accounts".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "sudo-io".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/sudo-io".into(),
+                    destination_name: "sudo-io".into(),
+                    description: Some("sudo I/O log session metadata and timing".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(sudo_io_regex()),
+                },
                 // Journal logs
                 Artifact {
                     name: "journal".into(),
@@ -221,6 +609,16 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                Artifact {
+                    name: "audit.rules".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Audit),
+                    source_path: "/etc/audit/audit.rules".into(),
+                    destination_name: "audit.rules".into(),
+                    description: Some("Audit rules loaded at boot".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
                 // Cron
                 Artifact {
                     name: "crontab".into(),
@@ -275,6 +673,218 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                // Persistence locations
+                Artifact {
+                    name: "systemd-timers".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/usr/lib/systemd/system".into(),
+                    destination_name: "systemd_timers".into(),
+                    description: Some("Package-provided systemd timer units".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(systemd_timers_regex()),
+                },
+                Artifact {
+                    name: "systemd-generators".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/systemd/system-generators".into(),
+                    destination_name: "system-generators".into(),
+                    description: Some("Administrator-installed systemd generators".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "ld.so.preload".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/ld.so.preload".into(),
+                    destination_name: "ld.so.preload".into(),
+                    description: Some("Libraries preloaded into every process".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "pam.d".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/pam.d".into(),
+                    destination_name: "pam.d".into(),
+                    description: Some("PAM service configuration".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "rc.local".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/rc.local".into(),
+                    destination_name: "rc.local".into(),
+                    description: Some("Legacy boot script".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "init.d".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/init.d".into(),
+                    destination_name: "init.d".into(),
+                    description: Some("SysV init scripts".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "profile.d".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/profile.d".into(),
+                    destination_name: "profile.d".into(),
+                    description: Some("System-wide login shell scripts".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "udev-rules".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/etc/udev/rules.d".into(),
+                    destination_name: "rules.d".into(),
+                    description: Some("Local udev rules, including RUN keys".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "user-shell-profiles".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/home".into(),
+                    destination_name: "home".into(),
+                    description: Some("Per-user .bashrc, .profile and .zshrc files".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(shell_profiles_regex(true)),
+                },
+                Artifact {
+                    name: "root-shell-profiles".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Persistence),
+                    source_path: "/root".into(),
+                    destination_name: "root".into(),
+                    description: Some("Shell startup files of the root account".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: Some(shell_profiles_regex(false)),
+                },
+                // sshd configuration and every account's authorized keys
+                Artifact {
+                    name: "sshd_config".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SSHDConfig),
+                    source_path: "/etc/ssh".into(),
+                    destination_name: "ssh".into(),
+                    description: Some("sshd_config, its includes and authorized_keys files".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Kernel ring buffer
+                Artifact {
+                    name: "dmesg".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::Dmesg),
+                    source_path: "/dev/kmsg".into(),
+                    destination_name: "dmesg".into(),
+                    description: Some("Kernel ring buffer messages".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Files protected with chattr +i or +a
+                Artifact {
+                    name: "immutable_files".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::ImmutableFiles),
+                    source_path: "/".into(),
+                    destination_name: "immutable_files".into(),
+                    description: Some("Files with the immutable or append-only attribute".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Container runtimes
+                Artifact {
+                    name: "docker_daemon_json".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::DockerDaemonJson),
+                    source_path: "/etc/docker/daemon.json".into(),
+                    destination_name: "daemon.json".into(),
+                    description: Some("Docker daemon configuration".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "docker_containers".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::DockerContainerLogs),
+                    source_path: "/var/lib/docker/containers".into(),
+                    destination_name: "docker_containers".into(),
+                    description: Some("Docker container configuration, logs and summary".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "docker_images".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::ContainerImage),
+                    source_path: "/var/lib/docker/image".into(),
+                    destination_name: "image".into(),
+                    description: Some("Docker image and layer metadata".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "podman_containers".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::PodmanContainerLogs),
+                    source_path: "/var/lib/containers/storage/overlay-containers".into(),
+                    destination_name: "podman_containers".into(),
+                    description: Some("Podman container configuration, logs and summary".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "kubernetes_audit_log".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::KubernetesAuditLog),
+                    source_path: "/var/log/kubernetes/audit".into(),
+                    destination_name: "audit".into(),
+                    description: Some("Kubernetes API server audit log".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Cloud CLI credential files of every user, inventoried not copied
+                Artifact {
+                    name: "cloud_credentials".into(),
+                    artifact_type: ArtifactType::CloudCredentials,
+                    source_path: "/home".into(),
+                    destination_name: "CloudCredentials".into(),
+                    description: Some(
+                        "AWS, Azure, gcloud, kubeconfig and Docker credential inventory (secrets scrubbed)"
+                            .into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "root_cloud_credentials".into(),
+                    artifact_type: ArtifactType::CloudCredentials,
+                    source_path: "/root".into(),
+                    destination_name: "RootCloudCredentials".into(),
+                    description: Some(
+                        "AWS, Azure, gcloud, kubeconfig and Docker credential inventory (secrets scrubbed)"
+                            .into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
             ],
             global_options: HashMap::new(),
         }
@@ -283,7 +893,7 @@ impl CollectionConfig {
     /// Default configuration for macOS
     pub fn default_macos() -> Self {
         CollectionConfig {
-            version: "1.0".into(),
+            version: CURRENT_SCHEMA_VERSION.into(),
             description: "Default macOS DFIR triage configuration".into(),
             artifacts: vec![
                 // System logs
@@ -397,6 +1007,224 @@ impl CollectionConfig {
                     metadata: HashMap::new(),
                     regex: None,
                 },
+                // Keychains (user, system and SystemKey) plus inventory
+                Artifact {
+                    name: "keychains".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::Keychains),
+                    source_path: "$HOME".into(),
+                    destination_name: "Keychains".into(),
+                    description: Some("User and system keychain databases".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Background Task Management database (macOS 13+)
+                Artifact {
+                    name: "background_task_management".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::BackgroundTaskManagement),
+                    source_path: "/private/var/db/com.apple.backgroundtaskmanagement".into(),
+                    destination_name: "BackgroundTaskManagement".into(),
+                    description: Some("Login items and background task database".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "configuration_profiles".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::ConfigurationProfiles),
+                    source_path: "/private/var/db/ConfigurationProfiles".into(),
+                    destination_name: "ConfigurationProfiles".into(),
+                    description: Some("Installed configuration profiles".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "app_firewall".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::AppFirewall),
+                    source_path: "/usr/libexec/ApplicationFirewall/socketfilterfw".into(),
+                    destination_name: "AppFirewall".into(),
+                    description: Some("Application Firewall state and per-app rules".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "login_records".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::LoginRecords),
+                    source_path: "/private/var/log/asl".into(),
+                    destination_name: "LoginRecords".into(),
+                    description: Some("ASL login records, last and loginwindow events".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "wifi_history".into(),
+                    artifact_type: ArtifactType::MacOS(MacOSArtifactType::WiFiHistory),
+                    source_path:
+                        "/Library/Preferences/SystemConfiguration/com.apple.airport.preferences.plist"
+                            .into(),
+                    destination_name: "WiFiHistory".into(),
+                    description: Some("Remembered Wi-Fi networks and last connection times".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Cloud CLI credential files of every user, inventoried not copied
+                Artifact {
+                    name: "cloud_credentials".into(),
+                    artifact_type: ArtifactType::CloudCredentials,
+                    source_path: "/Users".into(),
+                    destination_name: "CloudCredentials".into(),
+                    description: Some(
+                        "AWS, Azure, gcloud, kubeconfig and Docker credential inventory (secrets scrubbed)"
+                            .into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Mail client stores of every user, listed unless opted in
+                Artifact {
+                    name: "mail_stores".into(),
+                    artifact_type: ArtifactType::MailStores,
+                    source_path: "/Users".into(),
+                    destination_name: "MailStores".into(),
+                    description: Some(
+                        "Apple Mail envelope index and mailboxes (listed unless metadata_only is false)".into(),
+                    ),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+            ],
+            global_options: HashMap::new(),
+        }
+    }
+
+    /// Default configuration for container hosts (Docker, containerd and
+    /// Kubernetes nodes)
+    pub fn default_container() -> Self {
+        CollectionConfig {
+            version: CURRENT_SCHEMA_VERSION.into(),
+            description: "Default container host DFIR triage configuration".into(),
+            artifacts: vec![
+                // Host logs
+                Artifact {
+                    name: "syslog".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/syslog".into(),
+                    destination_name: "syslog".into(),
+                    description: Some("System logs".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "auth.log".into(),
+                    artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    source_path: "/var/log/auth.log".into(),
+                    destination_name: "auth.log".into(),
+                    description: Some("Authentication logs".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Docker
+                Artifact {
+                    name: "docker-daemon.json".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::DockerDaemonJson),
+                    source_path: "/etc/docker/daemon.json".into(),
+                    destination_name: "daemon.json".into(),
+                    description: Some("Docker daemon configuration".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "docker-containers".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::DockerContainerLogs),
+                    source_path: "/var/lib/docker/containers".into(),
+                    destination_name: "containers".into(),
+                    description: Some("Container configuration, json-file logs and summary".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "docker-images".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::ContainerImage),
+                    source_path: "/var/lib/docker/image".into(),
+                    destination_name: "image".into(),
+                    description: Some("Docker image and layer metadata".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Podman
+                Artifact {
+                    name: "podman-containers".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::PodmanContainerLogs),
+                    source_path: "/var/lib/containers/storage/overlay-containers".into(),
+                    destination_name: "podman_containers".into(),
+                    description: Some("Podman container configuration, logs and summary".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // containerd
+                Artifact {
+                    name: "containerd-config".into(),
+                    artifact_type: ArtifactType::SystemInfo,
+                    source_path: "/etc/containerd/config.toml".into(),
+                    destination_name: "config.toml".into(),
+                    description: Some("containerd configuration".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                // Kubernetes
+                Artifact {
+                    name: "pod-logs".into(),
+                    artifact_type: ArtifactType::Logs,
+                    source_path: "/var/log/pods".into(),
+                    destination_name: "pods".into(),
+                    description: Some("Kubelet pod logs".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "kubelet-config".into(),
+                    artifact_type: ArtifactType::SystemInfo,
+                    source_path: "/var/lib/kubelet/config.yaml".into(),
+                    destination_name: "kubelet_config.yaml".into(),
+                    description: Some("Kubelet configuration".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "static-pod-manifests".into(),
+                    artifact_type: ArtifactType::SystemInfo,
+                    source_path: "/etc/kubernetes/manifests".into(),
+                    destination_name: "manifests".into(),
+                    description: Some("Static pod manifests started by the kubelet".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
+                Artifact {
+                    name: "kubernetes-audit-log".into(),
+                    artifact_type: ArtifactType::Container(ContainerType::KubernetesAuditLog),
+                    source_path: "/var/log/kubernetes/audit".into(),
+                    destination_name: "audit".into(),
+                    description: Some("Kubernetes API server audit log".into()),
+                    required: false,
+                    metadata: HashMap::new(),
+                    regex: None,
+                },
             ],
             global_options: HashMap::new(),
         }
@@ -405,7 +1233,7 @@ impl CollectionConfig {
     /// Minimal configuration for unsupported OS
     pub fn default_minimal() -> Self {
         CollectionConfig {
-            version: "1.0".into(),
+            version: CURRENT_SCHEMA_VERSION.into(),
             description: "Minimal DFIR triage configuration".into(),
             artifacts: vec![
                 // Basic system info
@@ -445,7 +1273,7 @@ mod tests {
         let config = CollectionConfig::default_windows();
 
         // Test basic properties
-        assert_eq!(config.version, "1.0");
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
         assert_eq!(
             config.description,
             "Default Windows DFIR triage configuration"
@@ -499,6 +1327,17 @@ mod tests {
             r"\\?\C:\Windows\System32\config\SYSTEM"
         );
         assert!(system_hive.required);
+
+        // Transaction logs are collected next to each hive
+        let log = config
+            .artifacts
+            .iter()
+            .find(|a| a.name == "NTUSER.DAT.LOG2")
+            .expect("NTUSER.DAT.LOG2 artifact should exist");
+        assert_eq!(log.source_path, r"\\?\%USERPROFILE%\NTUSER.DAT.LOG2");
+        assert!(!log.required);
+        assert!(artifact_names.contains(&"SYSTEM.LOG1"));
+        assert!(artifact_names.contains(&"UsrClass.dat.LOG1"));
     }
 
     #[test]
@@ -506,7 +1345,7 @@ mod tests {
         let config = CollectionConfig::default_linux();
 
         // Test basic properties
-        assert_eq!(config.version, "1.0");
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
         assert_eq!(
             config.description,
             "Default Linux DFIR triage configuration"
@@ -522,10 +1361,52 @@ mod tests {
         assert!(artifact_names.contains(&"proc-cmdline"));
         assert!(artifact_names.contains(&"proc-modules"));
         assert!(artifact_names.contains(&"audit.log"));
+        assert!(artifact_names.contains(&"audit.rules"));
         assert!(artifact_names.contains(&"crontab"));
         assert!(artifact_names.contains(&"bash_history"));
         assert!(artifact_names.contains(&"dpkg.log"));
         assert!(artifact_names.contains(&"systemd-units"));
+        for name in [
+            "systemd-timers",
+            "systemd-generators",
+            "ld.so.preload",
+            "pam.d",
+            "rc.local",
+            "init.d",
+            "profile.d",
+            "udev-rules",
+            "user-shell-profiles",
+            "root-shell-profiles",
+        ] {
+            let artifact = config
+                .artifacts
+                .iter()
+                .find(|a| a.name == name)
+                .unwrap_or_else(|| panic!("{} artifact should exist", name));
+            assert!(matches!(
+                artifact.artifact_type,
+                ArtifactType::Linux(LinuxArtifactType::Persistence)
+            ));
+            assert!(!artifact.required);
+        }
+        assert!(config.artifacts.iter().any(|a| a.name == "sshd_config"
+            && a.artifact_type == ArtifactType::Linux(LinuxArtifactType::SSHDConfig)));
+        assert!(config.artifacts.iter().any(|a| a.name == "dmesg"
+            && a.artifact_type == ArtifactType::Linux(LinuxArtifactType::Dmesg)));
+        assert!(config.artifacts.iter().any(|a| a.name == "immutable_files"
+            && a.artifact_type == ArtifactType::Linux(LinuxArtifactType::ImmutableFiles)));
+        for name in ["secure", "wtmp", "btmp", "lastlog", "sudo-io"] {
+            let artifact = config
+                .artifacts
+                .iter()
+                .find(|a| a.name == name)
+                .unwrap_or_else(|| panic!("{} artifact should exist", name));
+            assert_eq!(
+                artifact.artifact_type,
+                ArtifactType::Linux(LinuxArtifactType::SysLogs)
+            );
+            assert!(!artifact.required);
+        }
 
         // Test syslog artifact specifically
         let syslog = config
@@ -562,7 +1443,7 @@ mod tests {
         let config = CollectionConfig::default_macos();
 
         // Test basic properties
-        assert_eq!(config.version, "1.0");
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
         assert_eq!(
             config.description,
             "Default macOS DFIR triage configuration"
@@ -612,12 +1493,40 @@ mod tests {
         assert!(quarantine.source_path.contains("QuarantineEventsV2"));
     }
 
+    #[test]
+    fn test_default_container_config() {
+        let config = CollectionConfig::default_container();
+
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            config.description,
+            "Default container host DFIR triage configuration"
+        );
+        assert!(config.artifacts.iter().all(|a| !a.required));
+
+        let containers = config
+            .artifacts
+            .iter()
+            .find(|a| a.name == "docker-containers")
+            .expect("docker-containers artifact should exist");
+        assert_eq!(containers.source_path, "/var/lib/docker/containers");
+        // The container collector picks the files itself
+        assert_eq!(
+            containers.artifact_type,
+            ArtifactType::Container(ContainerType::DockerContainerLogs)
+        );
+        assert!(containers.regex.is_none());
+        assert!(config.artifacts.iter().any(
+            |a| a.artifact_type == ArtifactType::Container(ContainerType::PodmanContainerLogs)
+        ));
+    }
+
     #[test]
     fn test_default_minimal_config() {
         let config = CollectionConfig::default_minimal();
 
         // Test basic properties
-        assert_eq!(config.version, "1.0");
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
         assert_eq!(config.description, "Minimal DFIR triage configuration");
         assert_eq!(config.artifacts.len(), 2);
 
@@ -652,6 +1561,7 @@ mod tests {
             CollectionConfig::default_windows(),
             CollectionConfig::default_linux(),
             CollectionConfig::default_macos(),
+            CollectionConfig::default_container(),
             CollectionConfig::default_minimal(),
         ];
 
@@ -667,6 +1577,7 @@ mod tests {
             CollectionConfig::default_windows(),
             CollectionConfig::default_linux(),
             CollectionConfig::default_macos(),
+            CollectionConfig::default_container(),
             CollectionConfig::default_minimal(),
         ];
 
@@ -682,14 +1593,19 @@ mod tests {
             CollectionConfig::default_windows(),
             CollectionConfig::default_linux(),
             CollectionConfig::default_macos(),
+            CollectionConfig::default_container(),
             CollectionConfig::default_minimal(),
         ];
 
-        // All default artifacts should have empty metadata and no regex
+        // All default artifacts should have empty metadata and any regex
+        // they carry should be enabled and compile
         for config in configs {
             for artifact in &config.artifacts {
                 assert!(artifact.metadata.is_empty());
-                assert!(artifact.regex.is_none());
+                if let Some(regex) = &artifact.regex {
+                    assert!(regex.enabled);
+                    assert!(regex::Regex::new(&regex.include_pattern).is_ok());
+                }
             }
         }
     }
@@ -700,6 +1616,7 @@ mod tests {
             CollectionConfig::default_windows(),
             CollectionConfig::default_linux(),
             CollectionConfig::default_macos(),
+            CollectionConfig::default_container(),
             CollectionConfig::default_minimal(),
         ];
 
@@ -722,6 +1639,7 @@ mod tests {
                     let type_name = format!("{:?}", win_type);
                     *type_counts.entry(type_name).or_insert(0) += 1;
                 }
+                ArtifactType::CloudCredentials | ArtifactType::MailStores => {}
                 _ => panic!("Non-Windows artifact type in Windows config"),
             }
         }
@@ -732,16 +1650,44 @@ mod tests {
         assert_eq!(type_counts.get("MFT").unwrap_or(&0), &1);
         assert_eq!(type_counts.get("Prefetch").unwrap_or(&0), &1);
         assert_eq!(type_counts.get("USNJournal").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("ETWSessions").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("NetworkShares").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("SRUM").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("BITS").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("ScheduledTasks").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("WMIRepository").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("ShellBags").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("IISLogs").unwrap_or(&0), &2);
+        assert_eq!(type_counts.get("RecycleBin").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("RecentItems").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("OfficeStartup").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("PowerShellHistory").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("OfficeMRU").unwrap_or(&0), &1);
+        assert_eq!(type_counts.get("PagingFile").unwrap_or(&0), &3);
+        assert_eq!(type_counts.get("BrowserExtensions").unwrap_or(&0), &1);
     }
 
     #[test]
     fn test_linux_artifact_types() {
         let config = CollectionConfig::default_linux();
 
-        // Verify all artifacts are Linux type
+        // Verify all artifacts are Linux, container or credential types
         for artifact in &config.artifacts {
-            assert!(matches!(artifact.artifact_type, ArtifactType::Linux(_)));
+            assert!(matches!(
+                artifact.artifact_type,
+                ArtifactType::Linux(_)
+                    | ArtifactType::Container(_)
+                    | ArtifactType::CloudCredentials
+            ));
         }
+        assert_eq!(
+            config
+                .artifacts
+                .iter()
+                .filter(|a| matches!(a.artifact_type, ArtifactType::Container(_)))
+                .count(),
+            5
+        );
 
         // Count required vs optional
         let required_count = config.artifacts.iter().filter(|a| a.required).count();
@@ -755,9 +1701,12 @@ mod tests {
     fn test_macos_artifact_types() {
         let config = CollectionConfig::default_macos();
 
-        // Verify all artifacts are macOS type
+        // Verify all artifacts are macOS or credential types
         for artifact in &config.artifacts {
-            assert!(matches!(artifact.artifact_type, ArtifactType::MacOS(_)));
+            assert!(matches!(
+                artifact.artifact_type,
+                ArtifactType::MacOS(_) | ArtifactType::CloudCredentials | ArtifactType::MailStores
+            ));
         }
 
         // Only system.log should be required
@@ -769,6 +1718,32 @@ mod tests {
             .collect();
 
         assert_eq!(required_artifacts, vec!["system.log"]);
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type == ArtifactType::MacOS(MacOSArtifactType::Keychains)));
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type
+                == ArtifactType::MacOS(MacOSArtifactType::BackgroundTaskManagement)));
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type
+                == ArtifactType::MacOS(MacOSArtifactType::ConfigurationProfiles)));
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type == ArtifactType::MacOS(MacOSArtifactType::AppFirewall)));
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type == ArtifactType::MacOS(MacOSArtifactType::LoginRecords)));
+        assert!(config
+            .artifacts
+            .iter()
+            .any(|a| a.artifact_type == ArtifactType::MacOS(MacOSArtifactType::WiFiHistory)));
     }
 
     #[test]
@@ -0,0 +1,1157 @@
+//! Artifacts added at startup for the roles a host turns out to have.
+//!
+//! A configuration written for every host cannot know which of them run a
+//! web server, a database or a build server. Before collection, each
+//! [`RoleDetector`] in [`DETECTORS`] runs cheap checks against the host:
+//! whether a service is installed, a well-known path exists or a registry
+//! value is set. A detector matches when any of its signals does, and its
+//! catalog artifacts are added to the collection unless the configuration
+//! already collects the same source path. The roles, the signals that
+//! matched and the artifacts added are recorded in the `dynamic_artifacts`
+//! section of the collection summary. `--no-dynamic-artifacts` skips the
+//! phase.
+//!
+//! Detection goes through [`HostEnvironment`] so that each detector can be
+//! tested against a mocked host.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::artifact_types::{ArtifactType, LinuxArtifactType, WindowsArtifactType};
+use crate::config::collection_config::Artifact;
+use crate::config::env_vars::{normalize_path_for_os, resolve_all_variables};
+use crate::config::regex_config::RegexConfig;
+
+/// `metadata` key naming the role that added an artifact
+pub const DYNAMIC_ROLE_KEY: &str = "dynamic_role";
+
+/// A server role or product detected on the host
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HostRole {
+    Iis,
+    Apache,
+    Nginx,
+    Mssql,
+    Mysql,
+    Postgresql,
+    Exchange,
+    Jenkins,
+    Gitlab,
+    /// Remote Desktop connections are allowed
+    Rdp,
+    HyperV,
+    Vmware,
+    Postfix,
+    Exim,
+}
+
+impl HostRole {
+    /// Name used in the summary and in artifact metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostRole::Iis => "iis",
+            HostRole::Apache => "apache",
+            HostRole::Nginx => "nginx",
+            HostRole::Mssql => "mssql",
+            HostRole::Mysql => "mysql",
+            HostRole::Postgresql => "postgresql",
+            HostRole::Exchange => "exchange",
+            HostRole::Jenkins => "jenkins",
+            HostRole::Gitlab => "gitlab",
+            HostRole::Rdp => "rdp",
+            HostRole::HyperV => "hyper_v",
+            HostRole::Vmware => "vmware",
+            HostRole::Postfix => "postfix",
+            HostRole::Exim => "exim",
+        }
+    }
+}
+
+/// A cheap check for a role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// A service (Windows) or systemd unit / init script (Linux) is installed
+    Service(&'static str),
+    /// A file or directory exists; variables are expanded first
+    Path(&'static str),
+    /// A `REG_DWORD` under `HKLM` has the given value
+    RegistryDword {
+        key: &'static str,
+        value: &'static str,
+        equals: u32,
+    },
+}
+
+impl Signal {
+    /// Description recorded as evidence when the signal matches
+    fn describe(&self) -> String {
+        match self {
+            Signal::Service(name) => format!("service {}", name),
+            Signal::Path(path) => format!("path {}", path),
+            Signal::RegistryDword { key, value, equals } => {
+                format!(r"registry HKLM\{}\{} = {}", key, value, equals)
+            }
+        }
+    }
+}
+
+/// An artifact a detector adds, in configuration form
+#[derive(Debug, Clone)]
+pub struct CatalogArtifact {
+    pub name: &'static str,
+    pub artifact_type: ArtifactType,
+    /// Source path, with variables as in a configuration file
+    pub source_path: &'static str,
+    pub destination_name: &'static str,
+    pub description: &'static str,
+    /// Regex of the files to take from a source directory, relative to it
+    pub include_pattern: Option<&'static str>,
+    pub max_depth: Option<usize>,
+}
+
+/// Checks for a role and the artifacts collected when it is found
+#[derive(Debug, Clone)]
+pub struct RoleDetector {
+    pub role: HostRole,
+    /// Operating systems (`std::env::consts::OS`) the detector runs on
+    pub os: &'static [&'static str],
+    pub signals: &'static [Signal],
+    pub artifacts: &'static [CatalogArtifact],
+}
+
+const WINDOWS: &[&str] = &["windows"];
+const LINUX: &[&str] = &["linux"];
+
+/// Log files inside the `W3SVC<site id>` directories of an IIS log folder
+const IIS_LOG_PATTERN: &str = r"(?i)^W3SVC[0-9]*[\\/][^\\/]+\.log$";
+
+/// Exchange message tracking logs (`MSGTRK`, `MSGTRKMA`, `MSGTRKMD`, ...)
+const MESSAGE_TRACKING_PATTERN: &str = r"(?i)^MSGTRK[^\\/]*\.log$";
+
+const fn artifact(
+    name: &'static str,
+    artifact_type: ArtifactType,
+    source_path: &'static str,
+    destination_name: &'static str,
+    description: &'static str,
+) -> CatalogArtifact {
+    CatalogArtifact {
+        name,
+        artifact_type,
+        source_path,
+        destination_name,
+        description,
+        include_pattern: None,
+        max_depth: None,
+    }
+}
+
+const fn matching(
+    artifact: CatalogArtifact,
+    include_pattern: &'static str,
+    max_depth: usize,
+) -> CatalogArtifact {
+    CatalogArtifact {
+        include_pattern: Some(include_pattern),
+        max_depth: Some(max_depth),
+        ..artifact
+    }
+}
+
+/// The built-in catalog of role detectors
+pub const DETECTORS: &[RoleDetector] = &[
+    RoleDetector {
+        role: HostRole::Iis,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("W3SVC"),
+            Signal::Path(r"%SystemRoot%\System32\inetsrv\w3wp.exe"),
+        ],
+        artifacts: &[
+            matching(
+                artifact(
+                    "IIS Logs (inetpub)",
+                    ArtifactType::Windows(WindowsArtifactType::IISLogs),
+                    r"%SystemDrive%\inetpub\logs\LogFiles",
+                    "IISLogs-inetpub",
+                    "IIS web server logs (W3SVC*)",
+                ),
+                IIS_LOG_PATTERN,
+                2,
+            ),
+            artifact(
+                "IIS Configuration",
+                ArtifactType::Custom,
+                r"%SystemRoot%\System32\inetsrv\config",
+                "IIS-Config",
+                "applicationHost.config and the IIS configuration store",
+            ),
+            artifact(
+                "HTTP.sys Error Logs",
+                ArtifactType::Logs,
+                r"%SystemRoot%\System32\LogFiles\HTTPERR",
+                "IIS-HTTPERR",
+                "Requests rejected by HTTP.sys before reaching IIS",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Apache,
+        os: LINUX,
+        signals: &[
+            Signal::Service("apache2"),
+            Signal::Service("httpd"),
+            Signal::Path("/etc/apache2"),
+            Signal::Path("/etc/httpd/conf"),
+        ],
+        artifacts: &[
+            artifact(
+                "Apache Logs",
+                ArtifactType::Logs,
+                "/var/log/apache2",
+                "apache2-logs",
+                "Apache access and error logs (Debian layout)",
+            ),
+            artifact(
+                "Apache Logs (httpd)",
+                ArtifactType::Logs,
+                "/var/log/httpd",
+                "httpd-logs",
+                "Apache access and error logs (Red Hat layout)",
+            ),
+            artifact(
+                "Apache Configuration",
+                ArtifactType::Custom,
+                "/etc/apache2",
+                "apache2-config",
+                "Apache configuration and enabled sites (Debian layout)",
+            ),
+            artifact(
+                "Apache Configuration (httpd)",
+                ArtifactType::Custom,
+                "/etc/httpd",
+                "httpd-config",
+                "Apache configuration (Red Hat layout)",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Nginx,
+        os: LINUX,
+        signals: &[Signal::Service("nginx"), Signal::Path("/etc/nginx")],
+        artifacts: &[
+            artifact(
+                "nginx Logs",
+                ArtifactType::Logs,
+                "/var/log/nginx",
+                "nginx-logs",
+                "nginx access and error logs",
+            ),
+            artifact(
+                "nginx Configuration",
+                ArtifactType::Custom,
+                "/etc/nginx",
+                "nginx-config",
+                "nginx configuration and server blocks",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Mssql,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("MSSQLSERVER"),
+            Signal::Path(r"%ProgramFiles%\Microsoft SQL Server"),
+        ],
+        artifacts: &[matching(
+            artifact(
+                "SQL Server Logs",
+                ArtifactType::Logs,
+                r"%ProgramFiles%\Microsoft SQL Server",
+                "MSSQL-Logs",
+                "ERRORLOG, default trace and SQL Agent logs of every instance",
+            ),
+            r"(?i)^MSSQL[^\\/]*[\\/]MSSQL[\\/]Log[\\/](ERRORLOG(\.[0-9]+)?|log(_[0-9]+)?\.trc|SQLAGENT\.(OUT|[0-9]+))$",
+            4,
+        )],
+    },
+    RoleDetector {
+        role: HostRole::Mysql,
+        os: LINUX,
+        signals: &[
+            Signal::Service("mysql"),
+            Signal::Service("mysqld"),
+            Signal::Service("mariadb"),
+            Signal::Path("/etc/mysql"),
+            Signal::Path("/etc/my.cnf"),
+        ],
+        artifacts: &[
+            artifact(
+                "MySQL Logs",
+                ArtifactType::Logs,
+                "/var/log/mysql",
+                "mysql-logs",
+                "MySQL/MariaDB error, general and slow query logs",
+            ),
+            artifact(
+                "MySQL Configuration",
+                ArtifactType::Custom,
+                "/etc/mysql",
+                "mysql-config",
+                "MySQL/MariaDB configuration (Debian layout)",
+            ),
+            artifact(
+                "MySQL Configuration (my.cnf)",
+                ArtifactType::Custom,
+                "/etc/my.cnf",
+                "my.cnf",
+                "MySQL/MariaDB configuration (Red Hat layout)",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Mysql,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("MySQL"),
+            Signal::Service("MySQL80"),
+            Signal::Path(r"%ProgramData%\MySQL"),
+        ],
+        artifacts: &[matching(
+            artifact(
+                "MySQL Data Logs",
+                ArtifactType::Logs,
+                r"%ProgramData%\MySQL",
+                "MySQL",
+                "MySQL error logs and my.ini",
+            ),
+            r"(?i)\.(err|log|ini)$",
+            4,
+        )],
+    },
+    RoleDetector {
+        role: HostRole::Postgresql,
+        os: LINUX,
+        signals: &[
+            Signal::Service("postgresql"),
+            Signal::Path("/var/lib/postgresql"),
+            Signal::Path("/var/lib/pgsql"),
+        ],
+        artifacts: &[
+            artifact(
+                "PostgreSQL Logs",
+                ArtifactType::Logs,
+                "/var/log/postgresql",
+                "postgresql-logs",
+                "PostgreSQL server logs (Debian layout)",
+            ),
+            matching(
+                artifact(
+                    "PostgreSQL pg_log",
+                    ArtifactType::Logs,
+                    "/var/lib/pgsql",
+                    "pgsql",
+                    "Server logs (pg_log, log) and pg_hba.conf under the data directory (Red Hat layout)",
+                ),
+                r"(^|/)(data/(pg_log|log)/[^/]+|data/(pg_hba|postgresql)\.conf)$",
+                4,
+            ),
+            artifact(
+                "PostgreSQL Configuration",
+                ArtifactType::Custom,
+                "/etc/postgresql",
+                "postgresql-config",
+                "postgresql.conf and pg_hba.conf of each cluster (Debian layout)",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Postgresql,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("postgresql-x64-16"),
+            Signal::Service("postgresql-x64-15"),
+            Signal::Path(r"%ProgramFiles%\PostgreSQL"),
+        ],
+        artifacts: &[matching(
+            artifact(
+                "PostgreSQL Logs",
+                ArtifactType::Logs,
+                r"%ProgramFiles%\PostgreSQL",
+                "PostgreSQL",
+                "Server logs (pg_log, log) and pg_hba.conf of each installed version",
+            ),
+            r"(?i)^[^\\/]+[\\/]data[\\/]((pg_log|log)[\\/][^\\/]+|(pg_hba|postgresql)\.conf)$",
+            4,
+        )],
+    },
+    RoleDetector {
+        role: HostRole::Exchange,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("MSExchangeTransport"),
+            Signal::Path(r"%ProgramFiles%\Microsoft\Exchange Server"),
+        ],
+        artifacts: &[
+            matching(
+                artifact(
+                    "Exchange Logging",
+                    ArtifactType::Logs,
+                    r"%ProgramFiles%\Microsoft\Exchange Server\V15\Logging",
+                    "Exchange-Logging",
+                    "HttpProxy, ECP, OWA, message tracking and cmdlet logs",
+                ),
+                r"(?i)^(HttpProxy|ECP|OWA|MessageTracking|CmdletInfra)[\\/].*\.log$",
+                5,
+            ),
+            artifact(
+                "Exchange Management Log",
+                ArtifactType::Windows(WindowsArtifactType::EventLog),
+                r"%SystemRoot%\System32\winevt\Logs\MSExchange Management.evtx",
+                "Exchange-Management.evtx",
+                "Admin audit log of Exchange cmdlets run on this server",
+            ),
+            matching(
+                artifact(
+                    "Exchange Message Tracking Logs",
+                    ArtifactType::Logs,
+                    r"%ProgramFiles%\Microsoft\Exchange Server\V15\TransportRoles\Logs\MessageTracking",
+                    "Exchange-MessageTracking",
+                    "Sender, recipients and subject of every message the transport handled",
+                ),
+                MESSAGE_TRACKING_PATTERN,
+                1,
+            ),
+            matching(
+                artifact(
+                    "IIS Logs (inetpub)",
+                    ArtifactType::Windows(WindowsArtifactType::IISLogs),
+                    r"%SystemDrive%\inetpub\logs\LogFiles",
+                    "IISLogs-inetpub",
+                    "IIS logs of the Exchange front end and back end sites",
+                ),
+                IIS_LOG_PATTERN,
+                2,
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Jenkins,
+        os: LINUX,
+        signals: &[Signal::Service("jenkins"), Signal::Path("/var/lib/jenkins")],
+        artifacts: &[
+            artifact(
+                "Jenkins Configuration",
+                ArtifactType::Custom,
+                "/var/lib/jenkins/config.xml",
+                "jenkins-config.xml",
+                "Jenkins global configuration (security realm, agents)",
+            ),
+            matching(
+                artifact(
+                    "Jenkins Job Configurations",
+                    ArtifactType::Custom,
+                    "/var/lib/jenkins/jobs",
+                    "jenkins-jobs",
+                    "config.xml of every job and folder",
+                ),
+                r"(^|/)config\.xml$",
+                6,
+            ),
+            artifact(
+                "Jenkins Users",
+                ArtifactType::Custom,
+                "/var/lib/jenkins/users",
+                "jenkins-users",
+                "Jenkins user accounts and API token metadata",
+            ),
+            artifact(
+                "Jenkins Logs",
+                ArtifactType::Logs,
+                "/var/log/jenkins",
+                "jenkins-logs",
+                "Jenkins controller logs",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Jenkins,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("jenkins"),
+            Signal::Path(r"%ProgramData%\Jenkins\.jenkins"),
+        ],
+        artifacts: &[
+            artifact(
+                "Jenkins Configuration",
+                ArtifactType::Custom,
+                r"%ProgramData%\Jenkins\.jenkins\config.xml",
+                "jenkins-config.xml",
+                "Jenkins global configuration (security realm, agents)",
+            ),
+            matching(
+                artifact(
+                    "Jenkins Job Configurations",
+                    ArtifactType::Custom,
+                    r"%ProgramData%\Jenkins\.jenkins\jobs",
+                    "jenkins-jobs",
+                    "config.xml of every job and folder",
+                ),
+                r"(?i)(^|[\\/])config\.xml$",
+                6,
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Gitlab,
+        os: LINUX,
+        signals: &[
+            Signal::Service("gitlab-runsvdir"),
+            Signal::Path("/etc/gitlab/gitlab.rb"),
+            Signal::Path("/opt/gitlab"),
+        ],
+        artifacts: &[
+            artifact(
+                "GitLab Logs",
+                ArtifactType::Logs,
+                "/var/log/gitlab",
+                "gitlab-logs",
+                "Rails, API, audit and nginx logs of the Omnibus install",
+            ),
+            artifact(
+                "GitLab Configuration",
+                ArtifactType::Custom,
+                "/etc/gitlab/gitlab.rb",
+                "gitlab.rb",
+                "Omnibus GitLab configuration",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Rdp,
+        os: WINDOWS,
+        signals: &[Signal::RegistryDword {
+            key: r"SYSTEM\CurrentControlSet\Control\Terminal Server",
+            value: "fDenyTSConnections",
+            equals: 0,
+        }],
+        artifacts: &[
+            artifact(
+                "RDP Local Session Manager Log",
+                ArtifactType::Windows(WindowsArtifactType::EventLog),
+                r"%SystemRoot%\System32\winevt\Logs\Microsoft-Windows-TerminalServices-LocalSessionManager%4Operational.evtx",
+                "RDP-LocalSessionManager.evtx",
+                "Session logon, logoff, disconnect and reconnect events",
+            ),
+            artifact(
+                "RDP Remote Connection Manager Log",
+                ArtifactType::Windows(WindowsArtifactType::EventLog),
+                r"%SystemRoot%\System32\winevt\Logs\Microsoft-Windows-TerminalServices-RemoteConnectionManager%4Operational.evtx",
+                "RDP-RemoteConnectionManager.evtx",
+                "Incoming RDP connection attempts (1149)",
+            ),
+            artifact(
+                "RDP Core Log",
+                ArtifactType::Windows(WindowsArtifactType::EventLog),
+                r"%SystemRoot%\System32\winevt\Logs\Microsoft-Windows-RemoteDesktopServices-RdpCoreTS%4Operational.evtx",
+                "RDP-RdpCoreTS.evtx",
+                "RDP transport events with client addresses",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::HyperV,
+        os: WINDOWS,
+        signals: &[Signal::Service("vmms")],
+        artifacts: &[
+            matching(
+                artifact(
+                    "Hyper-V Virtual Machine Configurations",
+                    ArtifactType::Custom,
+                    r"%ProgramData%\Microsoft\Windows\Hyper-V",
+                    "Hyper-V",
+                    "Virtual machine configuration and state files (disks are not copied)",
+                ),
+                r"(?i)\.(vmcx|vmrs|xml)$",
+                4,
+            ),
+            artifact(
+                "Hyper-V VMMS Log",
+                ArtifactType::Windows(WindowsArtifactType::EventLog),
+                r"%SystemRoot%\System32\winevt\Logs\Microsoft-Windows-Hyper-V-VMMS-Admin.evtx",
+                "Hyper-V-VMMS-Admin.evtx",
+                "Virtual machine management service events",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Vmware,
+        os: WINDOWS,
+        signals: &[
+            Signal::Service("VMAuthdService"),
+            Signal::Path(r"%ProgramData%\VMware"),
+        ],
+        artifacts: &[matching(
+            artifact(
+                "VMware Workstation Logs",
+                ArtifactType::Logs,
+                r"%ProgramData%\VMware",
+                "VMware",
+                "VMware host service logs and configuration",
+            ),
+            r"(?i)\.(log|ini|xml)$",
+            3,
+        )],
+    },
+    RoleDetector {
+        role: HostRole::Vmware,
+        os: LINUX,
+        signals: &[Signal::Path("/etc/vmware")],
+        artifacts: &[
+            artifact(
+                "VMware Configuration",
+                ArtifactType::Custom,
+                "/etc/vmware",
+                "vmware-config",
+                "VMware host configuration",
+            ),
+            matching(
+                artifact(
+                    "VMware Logs",
+                    ArtifactType::Linux(LinuxArtifactType::SysLogs),
+                    "/var/log",
+                    "vmware-logs",
+                    "VMware host service logs",
+                ),
+                r"^vmware[^/]*\.log$",
+                1,
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Postfix,
+        os: LINUX,
+        signals: &[
+            Signal::Service("postfix"),
+            Signal::Path("/etc/postfix/main.cf"),
+        ],
+        artifacts: &[
+            artifact(
+                "Mail Log",
+                ArtifactType::Logs,
+                "/var/log/mail.log",
+                "mail.log",
+                "Postfix delivery log (Debian layout)",
+            ),
+            artifact(
+                "Mail Log (maillog)",
+                ArtifactType::Logs,
+                "/var/log/maillog",
+                "maillog",
+                "Postfix delivery log (Red Hat layout)",
+            ),
+            artifact(
+                "Postfix Configuration",
+                ArtifactType::Custom,
+                "/etc/postfix",
+                "postfix-config",
+                "Postfix configuration, transport maps and sender rewrites",
+            ),
+        ],
+    },
+    RoleDetector {
+        role: HostRole::Exim,
+        os: LINUX,
+        signals: &[
+            Signal::Service("exim4"),
+            Signal::Service("exim"),
+            Signal::Path("/etc/exim4"),
+            Signal::Path("/etc/exim"),
+        ],
+        artifacts: &[
+            artifact(
+                "Exim Logs",
+                ArtifactType::Logs,
+                "/var/log/exim4",
+                "exim4-logs",
+                "Exim main, reject and panic logs (Debian layout)",
+            ),
+            artifact(
+                "Exim Logs (exim)",
+                ArtifactType::Logs,
+                "/var/log/exim",
+                "exim-logs",
+                "Exim main, reject and panic logs (Red Hat layout)",
+            ),
+            artifact(
+                "Exim Configuration",
+                ArtifactType::Custom,
+                "/etc/exim4",
+                "exim4-config",
+                "Exim configuration and routers (Debian layout)",
+            ),
+            artifact(
+                "Exim Configuration (exim)",
+                ArtifactType::Custom,
+                "/etc/exim",
+                "exim-config",
+                "Exim configuration (Red Hat layout)",
+            ),
+        ],
+    },
+];
+
+/// The host as seen by the detectors
+pub trait HostEnvironment {
+    /// Operating system, as in `std::env::consts::OS`
+    fn os(&self) -> &str;
+
+    /// Whether the service is installed
+    fn has_service(&self, name: &str) -> bool;
+
+    /// Whether `path`, as written in the catalog, exists
+    fn path_exists(&self, path: &str) -> bool;
+
+    /// A `REG_DWORD` under `HKLM`
+    fn registry_dword(&self, key: &str, value: &str) -> Option<u32>;
+
+    /// Expand the variables of a catalog path as configuration paths are
+    fn expand(&self, path: &str) -> String {
+        normalize_path_for_os(&resolve_all_variables(path))
+    }
+}
+
+/// The host the collector runs on. Installed services are listed once.
+pub struct LiveEnvironment {
+    services: HashSet<String>,
+}
+
+impl LiveEnvironment {
+    pub fn new() -> Self {
+        Self {
+            services: live_services(),
+        }
+    }
+}
+
+impl Default for LiveEnvironment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostEnvironment for LiveEnvironment {
+    fn os(&self) -> &str {
+        std::env::consts::OS
+    }
+
+    fn has_service(&self, name: &str) -> bool {
+        if cfg!(target_os = "linux") {
+            return [
+                "/etc/systemd/system",
+                "/lib/systemd/system",
+                "/usr/lib/systemd/system",
+            ]
+            .iter()
+            .any(|dir| Path::new(dir).join(format!("{}.service", name)).exists())
+                || Path::new("/etc/init.d").join(name).exists();
+        }
+        self.services.contains(&name.to_lowercase())
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        Path::new(&self.expand(path)).exists()
+    }
+
+    #[cfg(target_os = "windows")]
+    fn registry_dword(&self, key: &str, value: &str) -> Option<u32> {
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        use winreg::RegKey;
+
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(key)
+            .ok()?
+            .get_value::<u32, _>(value)
+            .ok()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn registry_dword(&self, _key: &str, _value: &str) -> Option<u32> {
+        None
+    }
+}
+
+/// Lower-case names of the installed Windows services
+fn live_services() -> HashSet<String> {
+    if !crate::collectors::volatile::services::SUPPORTED {
+        return HashSet::new();
+    }
+    match crate::collectors::volatile::services::enumerate_services() {
+        Ok(services) => services
+            .into_iter()
+            .map(|service| service.name.to_lowercase())
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to list services for role detection: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+/// A detected role, why it was detected and what it added
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectedRole {
+    pub role: HostRole,
+    /// Signals that matched, such as `service W3SVC`
+    pub evidence: Vec<String>,
+    /// Names of the artifacts added for the role
+    pub artifacts_added: Vec<String>,
+    /// Names of catalog artifacts skipped because the configuration, or an
+    /// earlier role, already collects their source path
+    pub artifacts_already_collected: Vec<String>,
+}
+
+/// Roles detected on the host and the artifacts added for them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DynamicArtifacts {
+    pub roles: Vec<DetectedRole>,
+    #[serde(skip)]
+    pub artifacts: Vec<Artifact>,
+}
+
+/// Run the detectors for the host's OS and select the catalog artifacts of
+/// every role found that `configured` does not already collect
+pub fn select_artifacts(env: &dyn HostEnvironment, configured: &[Artifact]) -> DynamicArtifacts {
+    let key = |path: &str| {
+        if env.os() == "windows" {
+            path.to_lowercase()
+        } else {
+            path.to_string()
+        }
+    };
+    let mut collected: HashSet<String> = configured
+        .iter()
+        .map(|artifact| key(&artifact.source_path))
+        .collect();
+    let mut selection = DynamicArtifacts::default();
+
+    for detector in DETECTORS.iter().filter(|d| d.os.contains(&env.os())) {
+        let evidence: Vec<String> = detector
+            .signals
+            .iter()
+            .filter(|signal| matches(env, signal))
+            .map(Signal::describe)
+            .collect();
+        if evidence.is_empty() {
+            continue;
+        }
+
+        let mut detected = DetectedRole {
+            role: detector.role,
+            evidence,
+            artifacts_added: Vec::new(),
+            artifacts_already_collected: Vec::new(),
+        };
+        for entry in detector.artifacts {
+            let artifact = entry.to_artifact(env, detector.role);
+            if collected.insert(key(&artifact.source_path)) {
+                detected.artifacts_added.push(artifact.name.clone());
+                selection.artifacts.push(artifact);
+            } else {
+                detected
+                    .artifacts_already_collected
+                    .push(entry.name.to_string());
+            }
+        }
+        selection.roles.push(detected);
+    }
+    selection
+}
+
+fn matches(env: &dyn HostEnvironment, signal: &Signal) -> bool {
+    match signal {
+        Signal::Service(name) => env.has_service(name),
+        Signal::Path(path) => env.path_exists(path),
+        Signal::RegistryDword { key, value, equals } => {
+            env.registry_dword(key, value) == Some(*equals)
+        }
+    }
+}
+
+impl CatalogArtifact {
+    /// The artifact as it would appear in a processed configuration
+    pub fn to_artifact(&self, env: &dyn HostEnvironment, role: HostRole) -> Artifact {
+        Artifact {
+            name: self.name.to_string(),
+            artifact_type: self.artifact_type.clone(),
+            source_path: env.expand(self.source_path),
+            destination_name: self.destination_name.to_string(),
+            description: Some(self.description.to_string()),
+            required: false,
+            metadata: HashMap::from([(DYNAMIC_ROLE_KEY.to_string(), role.as_str().to_string())]),
+            regex: self.include_pattern.map(|pattern| RegexConfig {
+                enabled: true,
+                recursive: true,
+                include_pattern: pattern.to_string(),
+                exclude_pattern: String::new(),
+                max_depth: self.max_depth,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A host with the given OS, services, paths and registry values
+    #[derive(Default)]
+    struct MockEnvironment {
+        os: &'static str,
+        services: Vec<&'static str>,
+        paths: Vec<&'static str>,
+        registry: Vec<(&'static str, &'static str, u32)>,
+    }
+
+    impl HostEnvironment for MockEnvironment {
+        fn os(&self) -> &str {
+            self.os
+        }
+
+        fn has_service(&self, name: &str) -> bool {
+            self.services.iter().any(|s| s.eq_ignore_ascii_case(name))
+        }
+
+        fn path_exists(&self, path: &str) -> bool {
+            self.paths.contains(&path)
+        }
+
+        fn registry_dword(&self, key: &str, value: &str) -> Option<u32> {
+            self.registry
+                .iter()
+                .find(|(k, v, _)| *k == key && *v == value)
+                .map(|(_, _, data)| *data)
+        }
+
+        // Catalog paths are kept as written so tests do not depend on the
+        // collecting host
+        fn expand(&self, path: &str) -> String {
+            path.to_string()
+        }
+    }
+
+    fn roles(selection: &DynamicArtifacts) -> Vec<HostRole> {
+        selection.roles.iter().map(|r| r.role).collect()
+    }
+
+    fn windows(services: Vec<&'static str>, paths: Vec<&'static str>) -> MockEnvironment {
+        MockEnvironment {
+            os: "windows",
+            services,
+            paths,
+            ..Default::default()
+        }
+    }
+
+    fn linux(services: Vec<&'static str>, paths: Vec<&'static str>) -> MockEnvironment {
+        MockEnvironment {
+            os: "linux",
+            services,
+            paths,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clean_host_adds_nothing() {
+        assert!(select_artifacts(&windows(vec![], vec![]), &[])
+            .roles
+            .is_empty());
+        assert!(select_artifacts(&linux(vec!["sshd"], vec!["/etc"]), &[])
+            .roles
+            .is_empty());
+    }
+
+    #[test]
+    fn test_every_detector_matches_its_own_signals() {
+        for detector in DETECTORS {
+            for signal in detector.signals {
+                let mut env = MockEnvironment {
+                    os: detector.os[0],
+                    ..Default::default()
+                };
+                match *signal {
+                    Signal::Service(name) => env.services.push(name),
+                    Signal::Path(path) => env.paths.push(path),
+                    Signal::RegistryDword { key, value, equals } => {
+                        env.registry.push((key, value, equals))
+                    }
+                }
+                let selection = select_artifacts(&env, &[]);
+                assert!(
+                    roles(&selection).contains(&detector.role),
+                    "{:?} not detected from {:?}",
+                    detector.role,
+                    signal
+                );
+                assert!(selection
+                    .artifacts
+                    .iter()
+                    .all(|a| { !a.required && a.metadata.contains_key(DYNAMIC_ROLE_KEY) }));
+            }
+        }
+    }
+
+    #[test]
+    fn test_catalog_patterns_compile() {
+        for detector in DETECTORS {
+            for entry in detector.artifacts {
+                if let Some(pattern) = entry.include_pattern {
+                    assert!(regex::Regex::new(pattern).is_ok(), "{}", entry.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_iis_detector() {
+        let selection = select_artifacts(&windows(vec!["w3svc"], vec![]), &[]);
+        assert_eq!(roles(&selection), vec![HostRole::Iis]);
+        assert_eq!(selection.roles[0].evidence, vec!["service W3SVC"]);
+        assert!(selection
+            .artifacts
+            .iter()
+            .any(|a| a.name == "IIS Configuration"));
+    }
+
+    #[test]
+    fn test_mssql_detector() {
+        let selection = select_artifacts(&windows(vec!["MSSQLSERVER"], vec![]), &[]);
+        assert_eq!(roles(&selection), vec![HostRole::Mssql]);
+        let regex = selection.artifacts[0].regex.as_ref().unwrap();
+        let pattern = regex::Regex::new(&regex.include_pattern).unwrap();
+        assert!(pattern.is_match(r"MSSQL15.MSSQLSERVER\MSSQL\Log\ERRORLOG"));
+        assert!(pattern.is_match(r"MSSQL15.MSSQLSERVER\MSSQL\Log\ERRORLOG.3"));
+        assert!(pattern.is_match(r"MSSQL15.MSSQLSERVER\MSSQL\Log\log_12.trc"));
+        assert!(!pattern.is_match(r"MSSQL15.MSSQLSERVER\MSSQL\DATA\master.mdf"));
+    }
+
+    #[test]
+    fn test_exchange_shares_iis_logs_with_iis() {
+        let env = windows(
+            vec!["W3SVC", "MSExchangeTransport"],
+            vec![r"%ProgramFiles%\Microsoft\Exchange Server"],
+        );
+        let selection = select_artifacts(&env, &[]);
+        assert_eq!(roles(&selection), vec![HostRole::Iis, HostRole::Exchange]);
+
+        let exchange = &selection.roles[1];
+        assert_eq!(exchange.evidence.len(), 2);
+        assert!(exchange
+            .artifacts_added
+            .contains(&"Exchange Management Log".to_string()));
+        assert_eq!(
+            exchange.artifacts_already_collected,
+            vec!["IIS Logs (inetpub)"]
+        );
+    }
+
+    #[test]
+    fn test_configured_sources_are_not_added_twice() {
+        let configured = Artifact {
+            name: "IIS".into(),
+            artifact_type: ArtifactType::Windows(WindowsArtifactType::IISLogs),
+            source_path: r"%SYSTEMDRIVE%\inetpub\logs\LogFiles".into(),
+            destination_name: "IIS".into(),
+            description: None,
+            required: false,
+            metadata: HashMap::new(),
+            regex: None,
+        };
+        let selection = select_artifacts(&windows(vec!["W3SVC"], vec![]), &[configured]);
+        assert_eq!(
+            selection.roles[0].artifacts_already_collected,
+            vec!["IIS Logs (inetpub)"]
+        );
+        assert_eq!(selection.artifacts.len(), 2);
+    }
+
+    #[test]
+    fn test_rdp_detector() {
+        let key = r"SYSTEM\CurrentControlSet\Control\Terminal Server";
+        let mut env = windows(vec![], vec![]);
+        env.registry.push((key, "fDenyTSConnections", 1));
+        assert!(select_artifacts(&env, &[]).roles.is_empty());
+
+        env.registry[0].2 = 0;
+        let selection = select_artifacts(&env, &[]);
+        assert_eq!(roles(&selection), vec![HostRole::Rdp]);
+        assert_eq!(selection.artifacts.len(), 3);
+    }
+
+    #[test]
+    fn test_linux_web_and_database_detectors() {
+        let env = linux(
+            vec!["nginx", "postgresql"],
+            vec!["/var/lib/jenkins", "/etc/gitlab/gitlab.rb"],
+        );
+        let selection = select_artifacts(&env, &[]);
+        assert_eq!(
+            roles(&selection),
+            vec![
+                HostRole::Nginx,
+                HostRole::Postgresql,
+                HostRole::Jenkins,
+                HostRole::Gitlab
+            ]
+        );
+        // Windows detectors of the same roles do not run
+        assert!(selection
+            .artifacts
+            .iter()
+            .all(|a| a.source_path.starts_with('/')));
+
+        let jobs = selection
+            .artifacts
+            .iter()
+            .find(|a| a.name == "Jenkins Job Configurations")
+            .unwrap();
+        let pattern = regex::Regex::new(&jobs.regex.as_ref().unwrap().include_pattern).unwrap();
+        assert!(pattern.is_match("deploy/config.xml"));
+        assert!(pattern.is_match("folder/jobs/build/config.xml"));
+        assert!(!pattern.is_match("deploy/builds/12/log"));
+    }
+
+    #[test]
+    fn test_mail_server_detectors() {
+        let selection = select_artifacts(&linux(vec!["postfix", "exim4"], vec![]), &[]);
+        assert_eq!(roles(&selection), vec![HostRole::Postfix, HostRole::Exim]);
+        assert!(selection
+            .artifacts
+            .iter()
+            .any(|a| a.source_path == "/var/log/mail.log"));
+
+        let env = windows(vec!["MSExchangeTransport"], vec![]);
+        let tracking = select_artifacts(&env, &[])
+            .artifacts
+            .into_iter()
+            .find(|a| a.name == "Exchange Message Tracking Logs")
+            .unwrap();
+        let pattern = regex::Regex::new(&tracking.regex.unwrap().include_pattern).unwrap();
+        assert!(pattern.is_match("MSGTRK2024061512-1.LOG"));
+        assert!(pattern.is_match("MSGTRKMD2024061512-1.LOG"));
+        assert!(!pattern.is_match("CONNECTLOG2024061512-1.LOG"));
+    }
+
+    #[test]
+    fn test_summary_serialization() {
+        let selection = select_artifacts(&linux(vec!["mysql"], vec![]), &[]);
+        let json = serde_json::to_value(&selection).unwrap();
+        assert_eq!(json["roles"][0]["role"], "mysql");
+        assert_eq!(json["roles"][0]["evidence"][0], "service mysql");
+        assert!(json.get("artifacts").is_none());
+    }
+}
@@ -104,6 +104,32 @@ pub fn parse_unix_env_vars(path: &str) -> String {
     result
 }
 
+/// Resolve `%VAR%`, `$VAR` and `${VAR}` in the same path, on any platform.
+///
+/// Windows-style variables are expanded first, then Unix-style ones, so a
+/// config written from a template works on every host. A leading `~` is
+/// replaced with the home directory (`HOME`, or `USERPROFILE` on Windows);
+/// a `~` elsewhere, as in 8.3 short names like `PROGRA~1`, is left alone.
+pub fn resolve_all_variables(input: &str) -> String {
+    let resolved = parse_unix_env_vars(&parse_windows_env_vars(input));
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"));
+    expand_home(&resolved, home.ok().as_deref())
+}
+
+/// Replace a leading `~` (alone or before a separator) with the home directory
+fn expand_home(path: &str, home: Option<&str>) -> String {
+    let Some(rest) = path.strip_prefix('~') else {
+        return path.to_string();
+    };
+    if !(rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\')) {
+        return path.to_string();
+    }
+    match home {
+        Some(home) => format!("{}{}", home.trim_end_matches(['/', '\\']), rest),
+        None => path.to_string(),
+    }
+}
+
 /// Normalize path separators for the current OS
 pub fn normalize_path_for_os(path: &str) -> String {
     if cfg!(windows) {
@@ -324,6 +350,46 @@ mod tests {
         env::remove_var("HOME");
     }
 
+    #[test]
+    fn test_resolve_all_variables() {
+        env::set_var("RESOLVE_PROFILE", r"C:\Users\analyst");
+        env::set_var("RESOLVE_APP", "Tool");
+
+        // Both styles in one path
+        assert_eq!(
+            resolve_all_variables(r"%RESOLVE_PROFILE%\AppData\$RESOLVE_APP\${RESOLVE_APP}.log"),
+            r"C:\Users\analyst\AppData\Tool\Tool.log"
+        );
+        assert_eq!(
+            resolve_all_variables(r"C:\PROGRA~1\$RESOLVE_APP"),
+            r"C:\PROGRA~1\Tool"
+        );
+        // Unset variables are left in place
+        assert_eq!(
+            resolve_all_variables(r"C:\$Recycle.Bin\%NOT_SET_ANYWHERE%"),
+            r"C:\$Recycle.Bin\%NOT_SET_ANYWHERE%"
+        );
+
+        env::remove_var("RESOLVE_PROFILE");
+        env::remove_var("RESOLVE_APP");
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = Some("/home/analyst/");
+        assert_eq!(expand_home("~/.ssh", home), "/home/analyst/.ssh");
+        assert_eq!(expand_home("~", home), "/home/analyst");
+        assert_eq!(
+            expand_home(r"~\AppData", Some(r"C:\Users\analyst")),
+            r"C:\Users\analyst\AppData"
+        );
+
+        // Only a leading ~ is the home directory
+        assert_eq!(expand_home("~root/.ssh", home), "~root/.ssh");
+        assert_eq!(expand_home(r"C:\PROGRA~1", home), r"C:\PROGRA~1");
+        assert_eq!(expand_home("~/.ssh", None), "~/.ssh");
+    }
+
     #[test]
     fn test_special_characters_in_env_values() {
         // Test environment variables containing special characters
@@ -0,0 +1,205 @@
+//! Schema migrations for collection configs.
+//!
+//! Configs record the schema they were written against in `version: "x.y"`.
+//! [`migrate_config`] upgrades an older document one version at a time until
+//! it matches [`CURRENT_SCHEMA_VERSION`], so configs written for earlier
+//! releases keep loading after the schema changes.
+//!
+//! To add a migration, write a `migrate_x_y_to_x_z` function that edits the
+//! document in place and append it to [`MIGRATIONS`].
+
+use anyhow::Result;
+use log::info;
+use serde_yaml::Value;
+
+use crate::errors::CollectorError;
+
+/// Schema version written by this release
+pub const CURRENT_SCHEMA_VERSION: &str = "1.1";
+
+/// Version assumed for configs without a `version` field
+const UNVERSIONED_SCHEMA_VERSION: &str = "1.0";
+
+type MigrationFn = fn(&mut Value) -> Result<()>;
+
+/// Sequential migrations as `(from, to, migration)`
+const MIGRATIONS: &[(&str, &str, MigrationFn)] = &[("1.0", "1.1", migrate_1_0_to_1_1)];
+
+/// Upgrade a YAML config to the current schema version.
+///
+/// Configs already at the current version are returned unchanged, including
+/// comments and formatting. Migrated configs are re-serialized.
+pub fn migrate_config(yaml: &str) -> Result<String> {
+    let mut config: Value =
+        serde_yaml::from_str(yaml).map_err(|e| CollectorError::ConfigError {
+            message: format!("Failed to parse YAML config: {}", e),
+        })?;
+
+    let mut version = schema_version(&config)?;
+    if version == CURRENT_SCHEMA_VERSION {
+        return Ok(yaml.to_string());
+    }
+
+    if parse_version(&version) > parse_version(CURRENT_SCHEMA_VERSION) {
+        return Err(CollectorError::ConfigError {
+            message: format!(
+                "Config schema version {} is newer than the supported version {}",
+                version, CURRENT_SCHEMA_VERSION
+            ),
+        }
+        .into());
+    }
+
+    while version != CURRENT_SCHEMA_VERSION {
+        let (_, to, migrate) = MIGRATIONS
+            .iter()
+            .find(|(from, _, _)| *from == version)
+            .ok_or_else(|| CollectorError::ConfigError {
+                message: format!(
+                    "No migration available from config schema version {}",
+                    version
+                ),
+            })?;
+
+        info!("Migrating config schema from {} to {}", version, to);
+        migrate(&mut config)?;
+        if let Some(map) = config.as_mapping_mut() {
+            map.insert("version".into(), Value::String(to.to_string()));
+        }
+        version = to.to_string();
+    }
+
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+/// Read the `version` field, accepting both `"1.0"` and an unquoted `1.0`
+fn schema_version(config: &Value) -> Result<String> {
+    match config.get("version") {
+        None | Some(Value::Null) => Ok(UNVERSIONED_SCHEMA_VERSION.to_string()),
+        Some(Value::String(version)) => Ok(version.trim().to_string()),
+        // An unquoted 1.0 is read as a float; Debug keeps the trailing ".0"
+        Some(Value::Number(number)) => Ok(number
+            .as_f64()
+            .map(|n| format!("{:?}", n))
+            .unwrap_or_else(|| number.to_string())),
+        Some(other) => Err(CollectorError::ConfigError {
+            message: format!("Invalid config version: {:?}", other),
+        }
+        .into()),
+    }
+}
+
+/// Parse `major.minor` for ordering; unparsable parts sort as zero
+fn parse_version(version: &str) -> (u32, u32) {
+    let mut parts = version.split('.').map(|p| p.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
+
+/// 1.0 -> 1.1: the Windows `EventLogs` artifact type was renamed `EventLog`
+fn migrate_1_0_to_1_1(config: &mut Value) -> Result<()> {
+    let Some(artifacts) = config.get_mut("artifacts").and_then(Value::as_sequence_mut) else {
+        return Ok(());
+    };
+
+    for artifact in artifacts {
+        if let Some(windows) = artifact.get_mut("artifact_type").and_then(windows_type_mut) {
+            if *windows == "EventLogs" {
+                *windows = Value::String("EventLog".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The variant of a `Windows` artifact type, written either as the tag
+/// `!Windows MFT` or as the map `Windows: MFT`
+fn windows_type_mut(artifact_type: &mut Value) -> Option<&mut Value> {
+    if let Value::Tagged(tagged) = artifact_type {
+        return (tagged.tag == "Windows").then_some(&mut tagged.value);
+    }
+    artifact_type.get_mut("Windows")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ArtifactType, CollectionConfig, WindowsArtifactType};
+
+    #[test]
+    fn test_migrate_1_0_renames_event_logs() {
+        let yaml = r#"
+version: "1.0"
+description: "Old config"
+artifacts:
+  - name: "Security"
+    artifact_type:
+      Windows: EventLogs
+    source_path: "%SystemRoot%\\System32\\winevt\\Logs\\Security.evtx"
+    destination_name: "Security.evtx"
+    required: true
+  - name: "MFT"
+    artifact_type:
+      Windows: MFT
+    source_path: "\\\\?\\C:\\$MFT"
+    destination_name: "MFT"
+    required: true
+"#;
+        let migrated: Value = serde_yaml::from_str(&migrate_config(yaml).unwrap()).unwrap();
+
+        assert_eq!(migrated["version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            migrated["artifacts"][0]["artifact_type"]["Windows"],
+            "EventLog"
+        );
+        assert_eq!(migrated["artifacts"][1]["artifact_type"]["Windows"], "MFT");
+    }
+
+    #[test]
+    fn test_migrate_1_0_renames_tagged_event_logs() {
+        let yaml = r#"
+version: "1.0"
+description: "Old config"
+artifacts:
+  - name: "Security"
+    artifact_type: !Windows EventLogs
+    source_path: "%SystemRoot%\\System32\\winevt\\Logs\\Security.evtx"
+    destination_name: "Security.evtx"
+    required: true
+"#;
+        let config: CollectionConfig =
+            serde_yaml::from_str(&migrate_config(yaml).unwrap()).unwrap();
+
+        assert_eq!(config.version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(
+            config.artifacts[0].artifact_type,
+            ArtifactType::Windows(WindowsArtifactType::EventLog)
+        );
+    }
+
+    #[test]
+    fn test_current_version_is_unchanged() {
+        let yaml = "# keep me\nversion: \"1.1\"\ndescription: \"Current\"\nartifacts: []\n";
+        assert_eq!(migrate_config(yaml).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_unquoted_and_missing_versions() {
+        let migrated = migrate_config("version: 1.0\ndescription: x\nartifacts: []\n").unwrap();
+        assert!(migrated.contains("version: '1.1'"));
+
+        let migrated = migrate_config("description: x\nartifacts: []\n").unwrap();
+        assert!(migrated.contains("version: '1.1'"));
+    }
+
+    #[test]
+    fn test_unsupported_versions_rejected() {
+        let newer = migrate_config("version: \"9.0\"\ndescription: x\nartifacts: []\n");
+        assert!(newer.unwrap_err().to_string().contains("newer"));
+
+        let unknown = migrate_config("version: \"0.5\"\ndescription: x\nartifacts: []\n");
+        assert!(unknown
+            .unwrap_err()
+            .to_string()
+            .contains("No migration available"));
+    }
+}
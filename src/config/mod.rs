@@ -10,18 +10,21 @@
 //! - Environment variable expansion
 //! - Regular expression-based file matching
 //! - Default configurations for common use cases
+//! - Migration of configs written against older schema versions
+//! - Validation of configs before they are embedded in a build
+//! - Artifacts added for the server roles detected on the host
 //!
 //! ## Configuration Format
 //!
 //! Configurations are typically stored in YAML format:
 //!
 //! ```yaml
-//! version: "1.0"
+//! version: "1.1"
 //! description: "Windows DFIR collection"
 //! artifacts:
 //!   - name: "Windows Event Logs"
 //!     artifact_type:
-//!       Windows: EventLogs
+//!       Windows: EventLog
 //!     source_path: "%SystemRoot%\\System32\\winevt\\Logs"
 //!     destination_name: "EventLogs"
 //!     required: true
@@ -49,8 +52,11 @@
 mod artifact_types;
 mod collection_config;
 mod default_configs;
+pub mod dynamic_catalog;
 mod env_vars;
+mod migrations;
 mod regex_config;
+pub mod validator;
 
 /// Artifact type definitions for different platforms
 ///
@@ -58,8 +64,11 @@ mod regex_config;
 /// on Windows, Linux, and macOS systems. Each platform has specific artifact
 /// types that correspond to forensically relevant data sources.
 pub use artifact_types::{
-    ArtifactType, LinuxArtifactType, MacOSArtifactType, VolatileDataType, WindowsArtifactType,
+    ArtifactType, ContainerType, LinuxArtifactType, MacOSArtifactType, WindowsArtifactType,
 };
+// Part of the library API; the binary builds volatile artifacts elsewhere
+#[allow(unused_imports)]
+pub use artifact_types::VolatileDataType;
 
 /// Main configuration structures
 ///
@@ -71,7 +80,10 @@ pub use collection_config::{load_or_create_config, Artifact, CollectionConfig};
 ///
 /// These functions handle platform-specific environment variable expansion,
 /// allowing artifact paths to use variables like %SystemRoot% on Windows
-/// or $HOME on Unix systems.
+/// or $HOME on Unix systems, or both in the same path.
+pub use env_vars::resolve_all_variables;
+// Part of the library API; the collectors use resolve_all_variables
+#[allow(unused_imports)]
 pub use env_vars::{parse_unix_env_vars, parse_windows_env_vars};
 
 /// Regular expression configuration for file matching
@@ -0,0 +1,171 @@
+//! Semantic checks on a parsed configuration
+//!
+//! Deserialization only proves a config has the right shape. These checks
+//! catch configs that parse but cannot be collected as written, such as an
+//! artifact with no source or a regex that does not compile, so they fail
+//! before a binary embedding them is built rather than at collection time.
+
+use std::collections::HashSet;
+use std::path::{Component, Path};
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::config::collection_config::{Artifact, CollectionConfig};
+use crate::errors::CollectorError;
+
+/// Check a configuration, reporting every problem found in one error
+pub fn validate_config(config: &CollectionConfig) -> Result<()> {
+    let mut problems = Vec::new();
+
+    if config.version.trim().is_empty() {
+        problems.push("version is empty".to_string());
+    }
+    if config.artifacts.is_empty() {
+        problems.push("no artifacts are defined".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for (index, artifact) in config.artifacts.iter().enumerate() {
+        let label = if artifact.name.trim().is_empty() {
+            problems.push(format!("artifact #{} has an empty name", index + 1));
+            format!("artifact #{}", index + 1)
+        } else {
+            if !names.insert(artifact.name.as_str()) {
+                problems.push(format!("artifact name '{}' is not unique", artifact.name));
+            }
+            format!("artifact '{}'", artifact.name)
+        };
+        problems.extend(
+            artifact_problems(artifact)
+                .into_iter()
+                .map(|problem| format!("{} {}", label, problem)),
+        );
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CollectorError::ConfigError {
+            message: format!("Invalid configuration: {}", problems.join("; ")),
+        }
+        .into())
+    }
+}
+
+fn artifact_problems(artifact: &Artifact) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if artifact.source_path.trim().is_empty() {
+        problems.push("has an empty source_path".to_string());
+    }
+
+    let destination = Path::new(&artifact.destination_name);
+    if artifact.destination_name.trim().is_empty() {
+        problems.push("has an empty destination_name".to_string());
+    } else if !destination
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        problems.push(format!(
+            "destination_name '{}' must be a relative path inside the output directory",
+            artifact.destination_name
+        ));
+    }
+
+    if let Some(regex) = artifact.regex.as_ref().filter(|r| r.enabled) {
+        for (field, pattern) in [
+            ("include_pattern", &regex.include_pattern),
+            ("exclude_pattern", &regex.exclude_pattern),
+        ] {
+            if let Err(e) = Regex::new(pattern) {
+                problems.push(format!("{} does not compile: {}", field, e));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ArtifactType, LinuxArtifactType, RegexConfig};
+    use std::collections::HashMap;
+
+    fn artifact(name: &str, destination: &str) -> Artifact {
+        Artifact {
+            name: name.to_string(),
+            artifact_type: ArtifactType::Linux(LinuxArtifactType::SysLogs),
+            source_path: "/var/log/syslog".to_string(),
+            destination_name: destination.to_string(),
+            description: None,
+            required: false,
+            metadata: HashMap::new(),
+            regex: None,
+        }
+    }
+
+    fn config(artifacts: Vec<Artifact>) -> CollectionConfig {
+        CollectionConfig {
+            version: "1.1".to_string(),
+            description: "test".to_string(),
+            artifacts,
+            global_options: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_default_templates_are_valid() {
+        for template in ["windows", "linux", "macos", "container", "minimal"] {
+            let config = CollectionConfig::default_for_template(template).unwrap();
+            validate_config(&config).unwrap_or_else(|e| panic!("{}: {}", template, e));
+        }
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut bad_regex = artifact("logs", "logs");
+        bad_regex.regex = Some(RegexConfig {
+            enabled: true,
+            include_pattern: "([".to_string(),
+            ..RegexConfig::default()
+        });
+        let mut no_source = artifact("syslog", "../escape");
+        no_source.source_path = String::new();
+
+        let err = validate_config(&config(vec![
+            bad_regex,
+            no_source,
+            artifact("logs", "/etc/passwd"),
+        ]))
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("artifact 'logs' include_pattern does not compile"));
+        assert!(err.contains("artifact 'syslog' has an empty source_path"));
+        assert!(err.contains("destination_name '../escape'"));
+        assert!(err.contains("artifact name 'logs' is not unique"));
+        assert!(err.contains("destination_name '/etc/passwd'"));
+    }
+
+    #[test]
+    fn test_disabled_regex_is_not_compiled() {
+        let mut logs = artifact("logs", "logs/syslog");
+        logs.regex = Some(RegexConfig {
+            enabled: false,
+            include_pattern: "([".to_string(),
+            ..RegexConfig::default()
+        });
+        assert!(validate_config(&config(vec![logs])).is_ok());
+    }
+
+    #[test]
+    fn test_empty_config_is_invalid() {
+        let mut empty = config(Vec::new());
+        empty.version = " ".to_string();
+        let err = validate_config(&empty).unwrap_err().to_string();
+        assert!(err.contains("version is empty"));
+        assert!(err.contains("no artifacts are defined"));
+    }
+}
@@ -16,6 +16,9 @@ pub const MEMORY_CHUNK_SIZE: usize = 1024 * 1024;
 /// Small buffer size for streaming operations (64KB)
 pub const STREAMING_BUFFER_SIZE: usize = 64 * 1024;
 
+/// Collected files that may wait for the archive writer before collection blocks
+pub const ARCHIVE_QUEUE_DEPTH: usize = 4;
+
 /// Maximum memory region size to collect (1GB)
 pub const MAX_MEMORY_REGION_SIZE: u64 = 1024 * 1024 * 1024;
 
@@ -38,6 +41,15 @@ pub const S3_MAX_PARTS: usize = 10000;
 /// Large file threshold for multipart uploads (50MB)
 pub const LARGE_FILE_THRESHOLD: u64 = 50 * 1024 * 1024;
 
+/// Default buffer size for copying collected files, in KiB
+pub const DEFAULT_COPY_BUFFER_KB: usize = 1024;
+
+/// Files at least this large log copy progress (256MB)
+pub const COPY_PROGRESS_THRESHOLD: u64 = 256 * 1024 * 1024;
+
+/// Free space below which preflight warns about the output volume (1GB)
+pub const PREFLIGHT_MIN_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
 /// Default SFTP port
 pub const SFTP_DEFAULT_PORT: u16 = 22;
 
@@ -51,6 +63,12 @@ pub const SFTP_BUFFER_SIZE: usize = 8 * 1024 * 1024;
 /// Default connection timeout in seconds
 pub const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 30;
 
+/// Default per-artifact collection timeout in seconds
+pub const DEFAULT_ARTIFACT_TIMEOUT_SECS: u64 = 600;
+
+/// Timeout for each network check run by `check` and `--preflight`
+pub const PREFLIGHT_TIMEOUT_SECS: u64 = 15;
+
 /// Maximum upload retry attempts
 pub const MAX_UPLOAD_RETRIES: usize = 3;
 
@@ -91,6 +109,9 @@ pub const ZIP_COMPRESSION_METHOD_STORE: u16 = 0;
 /// ZIP default bit flag
 pub const ZIP_DEFAULT_BIT_FLAG: u16 = 0;
 
+/// ZIP general purpose flag bit 11 (EFS): the entry name is UTF-8
+pub const ZIP_UTF8_NAME_FLAG: u16 = 1 << 11;
+
 // File size thresholds
 /// Large file threshold for compression decisions (100MB)
 pub const LARGE_FILE_COMPRESSION_THRESHOLD: u64 = 100 * 1024 * 1024;
@@ -134,6 +155,11 @@ pub const DEFAULT_OUTPUT_NAME: &str = "collected_artifacts.zip";
 pub const DEFAULT_MEMORY_DUMP_NAME: &str = "memory_dump.bin";
 pub const DEFAULT_VOLATILE_DATA_NAME: &str = "volatile_data.json";
 
+// Services of a default Windows installation, compared against the live
+// service list by `collectors::volatile::services`
+#[cfg(any(target_os = "windows", test))]
+pub const DEFAULT_SERVICES_BASELINE: &str = include_str!("default_services_baseline.json");
+
 // Test constants
 #[cfg(test)]
 pub mod test {
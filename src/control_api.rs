@@ -0,0 +1,697 @@
+//! HTTP control API for fleet orchestration (`--serve`, `control_api` feature).
+//!
+//! An orchestration agent starts and follows collections over HTTP instead of
+//! running the binary once per collection:
+//!
+//! - `POST /collections` starts a collection. The body is a JSON object of
+//!   command-line options, e.g. `{"config": "triage.yaml", "skip_upload": true}`,
+//!   parsed by the same `clap` definition and configuration loading as a
+//!   normal run
+//! - `GET /collections/{id}` returns the collection's status, updated from
+//!   its phase events
+//! - `GET /collections/{id}/summary` returns `collection_summary.json` once
+//!   the collection has completed
+//! - `POST /collections/{id}/cancel` cancels a running collection
+//!
+//! Every request needs `Authorization: Bearer <token>`, with the token read
+//! from `--serve-token-file`. With `--serve-tls-cert` and `--serve-tls-key`
+//! connections are served over TLS. Only one collection runs at a time; a
+//! second `POST /collections` gets `409 Conflict`. Connections are handled one
+//! at a time, since this is a control channel and every request is small.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use log::{info, warn};
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::cli::Args;
+use crate::collectors::cancellation;
+use crate::collectors::phases::{CollectionPhase, PhaseEvent, PhaseEventKind, PhaseObserver};
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Longest request line or header line accepted
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+/// Most header lines read before a request is rejected
+const MAX_HEADERS: usize = 64;
+
+/// Read and write timeout for a client connection
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options that cannot be set through `POST /collections`
+const REJECTED_OPTIONS: &[&str] = &[
+    "serve",
+    "auto-elevate",
+    "check-update",
+    "proxy-url",
+    "proxy-auth-env",
+    "no-proxy",
+    "serve-token-file",
+    "serve-tls-cert",
+    "serve-tls-key",
+    "watch",
+    "config-template",
+    "help",
+    "version",
+];
+
+/// Lifecycle of a collection started through the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Status returned by `GET /collections/{id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectionStatus {
+    pub id: String,
+    pub state: CollectionState,
+    pub started: String,
+    pub finished: Option<String>,
+    /// Phase currently running
+    pub phase: Option<CollectionPhase>,
+    pub completed_phases: Vec<CollectionPhase>,
+    /// Whether cancellation was requested
+    pub cancel_requested: bool,
+    pub error: Option<String>,
+    #[serde(skip)]
+    summary_path: Option<PathBuf>,
+}
+
+impl CollectionStatus {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            state: CollectionState::Running,
+            started: chrono::Utc::now().to_rfc3339(),
+            finished: None,
+            phase: None,
+            completed_phases: Vec::new(),
+            cancel_requested: false,
+            error: None,
+            summary_path: None,
+        }
+    }
+}
+
+type SharedStatus = Arc<Mutex<CollectionStatus>>;
+
+fn lock(status: &SharedStatus) -> MutexGuard<'_, CollectionStatus> {
+    status.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Updates a collection's status from its phase events
+struct StatusObserver(SharedStatus);
+
+impl PhaseObserver for StatusObserver {
+    fn on_phase_event(&self, event: &PhaseEvent) {
+        let mut status = lock(&self.0);
+        match event.kind {
+            PhaseEventKind::Started => status.phase = Some(event.phase),
+            PhaseEventKind::Completed => {
+                status.phase = None;
+                status.completed_phases.push(event.phase);
+            }
+        }
+    }
+}
+
+/// A parsed HTTP request
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+/// A JSON response
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Serve the control API on `addr` until the process is killed
+pub fn serve(addr: &str, args: &Args) -> Result<()> {
+    let token_file = args
+        .serve_token_file
+        .as_deref()
+        .ok_or_else(|| anyhow!("--serve requires --serve-token-file"))?;
+    let token = read_token(token_file)?;
+
+    let tls = match (&args.serve_tls_cert, &args.serve_tls_key) {
+        (Some(cert), Some(key)) => Some(tls_acceptor(cert, key)?),
+        (None, None) => None,
+        _ => bail!("--serve-tls-cert and --serve-tls-key must be used together"),
+    };
+
+    let listener =
+        TcpListener::bind(addr).context(format!("Failed to bind control API to {}", addr))?;
+    info!(
+        "Control API listening on {}{}",
+        listener.local_addr()?,
+        if tls.is_some() { " (TLS)" } else { "" }
+    );
+
+    let mut server = ControlServer::new(token);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept control API connection: {}", e);
+                continue;
+            }
+        };
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+
+        let result = match &tls {
+            Some(acceptor) => acceptor
+                .accept(stream)
+                .map_err(|e| anyhow!("TLS handshake failed: {}", e))
+                .and_then(|mut stream| server.handle_connection(&mut stream)),
+            None => {
+                let mut stream = stream;
+                server.handle_connection(&mut stream)
+            }
+        };
+        if let Err(e) = result {
+            warn!("Control API connection failed: {:#}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Read the pre-shared bearer token
+fn read_token(path: &Path) -> Result<String> {
+    let token = fs::read_to_string(path)
+        .context(format!("Failed to read token file {}", path.display()))?
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        bail!("Token file {} is empty", path.display());
+    }
+    Ok(token)
+}
+
+fn tls_acceptor(cert: &Path, key: &Path) -> Result<SslAcceptor> {
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())
+        .context("Failed to create TLS acceptor")?;
+    builder
+        .set_private_key_file(key, SslFiletype::PEM)
+        .context(format!("Failed to load TLS key {}", key.display()))?;
+    builder
+        .set_certificate_chain_file(cert)
+        .context(format!("Failed to load TLS certificate {}", cert.display()))?;
+    builder
+        .check_private_key()
+        .context("TLS key does not match the certificate")?;
+    Ok(builder.build())
+}
+
+/// Collections started by this server, keyed by ID
+struct ControlServer {
+    token: String,
+    collections: BTreeMap<String, SharedStatus>,
+}
+
+impl ControlServer {
+    fn new(token: String) -> Self {
+        Self {
+            token,
+            collections: BTreeMap::new(),
+        }
+    }
+
+    fn handle_connection<S: Read + Write>(&mut self, stream: &mut S) -> Result<()> {
+        let response = match read_request(&mut *stream) {
+            Ok(request) => self.route(&request),
+            Err(e) if e.is::<RequestTooLarge>() => Response::error(413, format!("{:#}", e)),
+            Err(e) => Response::error(400, format!("{:#}", e)),
+        };
+        write_response(stream, &response)
+    }
+
+    fn route(&mut self, request: &Request) -> Response {
+        if !self.authorized(request) {
+            return Response::error(401, "Missing or invalid bearer token");
+        }
+
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["collections"]) => self.start_collection(&request.body),
+            ("GET", ["collections", id]) => self.status(id),
+            ("GET", ["collections", id, "summary"]) => self.summary(id),
+            ("POST", ["collections", id, "cancel"]) => self.cancel(id),
+            _ => Response::error(404, format!("No route for {} {}", request.method, path)),
+        }
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        request
+            .authorization
+            .as_deref()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), self.token.as_bytes()))
+    }
+
+    fn running(&self) -> Option<String> {
+        self.collections
+            .values()
+            .map(lock)
+            .find(|status| status.state == CollectionState::Running)
+            .map(|status| status.id.clone())
+    }
+
+    fn start_collection(&mut self, body: &[u8]) -> Response {
+        if let Some(id) = self.running() {
+            return Response::error(409, format!("Collection {} is still running", id));
+        }
+
+        let args = match serde_json::from_slice::<Value>(body)
+            .context("Request body is not JSON")
+            .and_then(|options| args_from_options(&options))
+            .and_then(|args| crate::load_and_process_config(&args).map(|_| args))
+        {
+            Ok(args) => args,
+            Err(e) => return Response::error(400, format!("{:#}", e)),
+        };
+
+        let id = Uuid::new_v4().to_string();
+        let status = Arc::new(Mutex::new(CollectionStatus::new(id.clone())));
+        self.collections.insert(id.clone(), Arc::clone(&status));
+        info!("Starting collection {} from the control API", id);
+
+        cancellation::reset_collection();
+        let observer: Arc<dyn PhaseObserver> = Arc::new(StatusObserver(Arc::clone(&status)));
+        thread::spawn(move || {
+            let result = crate::run_collection(&args, Some(observer));
+            let mut status = lock(&status);
+            status.finished = Some(chrono::Utc::now().to_rfc3339());
+            status.phase = None;
+            match result {
+                Ok(summary_path) => {
+                    status.state = CollectionState::Completed;
+                    status.summary_path = Some(summary_path);
+                }
+                Err(e) => {
+                    status.state = if cancellation::collection_cancelled() {
+                        CollectionState::Cancelled
+                    } else {
+                        CollectionState::Failed
+                    };
+                    status.error = Some(format!("{:#}", e));
+                }
+            }
+            info!("Collection {} finished: {:?}", status.id, status.state);
+        });
+
+        Response {
+            status: 202,
+            body: serde_json::to_value(&*lock(&self.collections[&id])).unwrap_or_default(),
+        }
+    }
+
+    fn find(&self, id: &str) -> Result<&SharedStatus, Response> {
+        self.collections
+            .get(id)
+            .ok_or_else(|| Response::error(404, format!("No collection {}", id)))
+    }
+
+    fn status(&self, id: &str) -> Response {
+        match self.find(id) {
+            Ok(status) => Response::ok(serde_json::to_value(&*lock(status)).unwrap_or_default()),
+            Err(response) => response,
+        }
+    }
+
+    fn summary(&self, id: &str) -> Response {
+        let status = match self.find(id) {
+            Ok(status) => lock(status).clone(),
+            Err(response) => return response,
+        };
+        let Some(summary_path) = status.summary_path else {
+            return Response::error(
+                409,
+                format!("Collection {} has no summary ({:?})", id, status.state),
+            );
+        };
+        match fs::read_to_string(&summary_path)
+            .context(format!("Failed to read {}", summary_path.display()))
+            .and_then(|summary| serde_json::from_str(&summary).context("Invalid summary JSON"))
+        {
+            Ok(summary) => Response::ok(summary),
+            Err(e) => Response::error(500, format!("{:#}", e)),
+        }
+    }
+
+    fn cancel(&self, id: &str) -> Response {
+        let status = match self.find(id) {
+            Ok(status) => status,
+            Err(response) => return response,
+        };
+        let mut status = lock(status);
+        if status.state != CollectionState::Running {
+            return Response::error(409, format!("Collection {} is not running", id));
+        }
+        info!("Cancelling collection {}", id);
+        cancellation::cancel_collection();
+        status.cancel_requested = true;
+        Response {
+            status: 202,
+            body: serde_json::to_value(&*status).unwrap_or_default(),
+        }
+    }
+}
+
+/// Build collection options from a JSON object of long option names.
+///
+/// `true` adds a flag, `false` and `null` leave it out, and arrays repeat the
+/// option. Names may use `_` or `-`.
+fn args_from_options(options: &Value) -> Result<Args> {
+    let options = options
+        .as_object()
+        .ok_or_else(|| anyhow!("Request body must be a JSON object of options"))?;
+
+    let mut argv = vec![env!("CARGO_PKG_NAME").to_string()];
+    for (name, value) in options {
+        let name = name.replace('_', "-");
+        if REJECTED_OPTIONS.contains(&name.as_str()) {
+            bail!("Option {} cannot be set through the control API", name);
+        }
+        let values = match value {
+            Value::Bool(true) => {
+                argv.push(format!("--{}", name));
+                continue;
+            }
+            Value::Bool(false) | Value::Null => continue,
+            Value::Array(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => bail!("Option {} must be a string, number or boolean", name),
+            };
+            argv.push(format!("--{}={}", name, value));
+        }
+    }
+
+    Args::try_parse_from(argv).map_err(|e| anyhow!("{}", e.to_string().trim()))
+}
+
+/// A request over one of the size limits, answered with `413`
+#[derive(Debug)]
+struct RequestTooLarge(String);
+
+impl fmt::Display for RequestTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RequestTooLarge {}
+
+/// Read one HTTP/1.1 request with an optional `Content-Length` body.
+///
+/// This runs before the bearer token is checked, so every read is capped and
+/// nothing a client sends is buffered past the limits.
+fn read_request<R: Read>(stream: R) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let request_line = read_line_capped(&mut reader, "Request line")?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    for _ in 0..=MAX_HEADERS {
+        let line = read_line_capped(&mut reader, "Header")?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            let mut body = Vec::with_capacity(content_length);
+            reader
+                .take(content_length as u64)
+                .read_to_end(&mut body)
+                .context("Failed to read request body")?;
+            if body.len() < content_length {
+                bail!("Request body shorter than its Content-Length");
+            }
+            return Ok(Request {
+                method: method.to_string(),
+                path: path.to_string(),
+                authorization,
+                body,
+            });
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            bail!("Malformed header");
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().context("Invalid Content-Length")?;
+            if content_length > MAX_BODY_BYTES {
+                return Err(
+                    RequestTooLarge(format!("Request body over {} bytes", MAX_BODY_BYTES)).into(),
+                );
+            }
+        }
+    }
+    Err(RequestTooLarge(format!("More than {} headers", MAX_HEADERS)).into())
+}
+
+/// Read one line of at most [`MAX_LINE_BYTES`], failing instead of reading on
+fn read_line_capped<R: BufRead>(reader: &mut R, what: &str) -> Result<String> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_line(&mut line)
+        .with_context(|| format!("Failed to read {}", what.to_lowercase()))?;
+    if line.len() > MAX_LINE_BYTES {
+        return Err(RequestTooLarge(format!("{} over {} bytes", what, MAX_LINE_BYTES)).into());
+    }
+    Ok(line)
+}
+
+fn write_response<W: Write>(stream: &mut W, response: &Response) -> Result<()> {
+    let body = serde_json::to_vec_pretty(&response.body)?;
+    let reason = match response.status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Compare secrets without returning early on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, token: Option<&str>, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            authorization: token.map(|t| format!("Bearer {}", t)),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /collections HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer s3cret\r\nContent-Length: 17\r\n\r\n{\"force\": true}\r\n";
+        let request = read_request(raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/collections");
+        assert_eq!(request.authorization.as_deref(), Some("Bearer s3cret"));
+        assert_eq!(request.body.len(), 17);
+
+        let oversized = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert!(read_request(oversized.as_bytes())
+            .unwrap_err()
+            .is::<RequestTooLarge>());
+        assert!(read_request("garbage\r\n\r\n".as_bytes()).is_err());
+        assert!(read_request("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nab".as_bytes()).is_err());
+
+        // An endless request line or header is cut off at the line limit
+        let endless = std::io::repeat(b'a');
+        assert!(read_request(endless).unwrap_err().is::<RequestTooLarge>());
+        let endless_header = "GET / HTTP/1.1\r\nX-Pad: "
+            .as_bytes()
+            .chain(std::io::repeat(b'a'));
+        assert!(read_request(endless_header)
+            .unwrap_err()
+            .is::<RequestTooLarge>());
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Pad: a\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(read_request(many_headers.as_bytes())
+            .unwrap_err()
+            .is::<RequestTooLarge>());
+    }
+
+    #[test]
+    fn test_args_from_options() {
+        let args = args_from_options(&json!({
+            "output": "/evidence",
+            "skip_upload": true,
+            "force": false,
+            "unified-log-hours": 6,
+            "nsrl_db": null,
+        }))
+        .unwrap();
+        assert_eq!(args.output.as_deref(), Some("/evidence"));
+        assert!(args.skip_upload);
+        assert!(!args.force);
+        assert_eq!(args.unified_log_hours, 6);
+        assert!(args.nsrl_db.is_none());
+
+        // Values are passed with `=`, so they may start with a dash
+        let args = args_from_options(&json!({ "output": "-odd-dir" })).unwrap();
+        assert_eq!(args.output.as_deref(), Some("-odd-dir"));
+
+        assert!(args_from_options(&json!({ "no_such_option": true })).is_err());
+        assert!(args_from_options(&json!({ "unified_log_hours": 0 })).is_err());
+        assert!(args_from_options(&json!({ "watch": true })).is_err());
+        assert!(args_from_options(&json!({ "serve": "0.0.0.0:80" })).is_err());
+        assert!(args_from_options(&json!({ "output": { "nested": 1 } })).is_err());
+        assert!(args_from_options(&json!(["--force"])).is_err());
+    }
+
+    #[test]
+    fn test_requests_need_the_bearer_token() {
+        let mut server = ControlServer::new("s3cret".to_string());
+        assert_eq!(
+            server
+                .route(&request("GET", "/collections/x", None, ""))
+                .status,
+            401
+        );
+        assert_eq!(
+            server
+                .route(&request("GET", "/collections/x", Some("wrong"), ""))
+                .status,
+            401
+        );
+        assert_eq!(
+            server
+                .route(&request("GET", "/collections/x", Some("s3cret"), ""))
+                .status,
+            404
+        );
+        assert_eq!(
+            server
+                .route(&request("DELETE", "/collections", Some("s3cret"), ""))
+                .status,
+            404
+        );
+    }
+
+    #[test]
+    fn test_one_collection_at_a_time() {
+        let mut server = ControlServer::new("s3cret".to_string());
+        let running = Arc::new(Mutex::new(CollectionStatus::new("first".to_string())));
+        server
+            .collections
+            .insert("first".to_string(), Arc::clone(&running));
+
+        let response = server.route(&request("POST", "/collections", Some("s3cret"), "{}"));
+        assert_eq!(response.status, 409);
+
+        let response = server.route(&request(
+            "GET",
+            "/collections/first/summary",
+            Some("s3cret"),
+            "",
+        ));
+        assert_eq!(response.status, 409);
+
+        let observer = StatusObserver(Arc::clone(&running));
+        observer.on_phase_event(&PhaseEvent {
+            phase: CollectionPhase::VolatileSnapshot,
+            kind: PhaseEventKind::Started,
+            timestamp: String::new(),
+        });
+        let status = server.route(&request("GET", "/collections/first", Some("s3cret"), ""));
+        assert_eq!(status.status, 200);
+        assert_eq!(status.body["state"], "running");
+        assert_eq!(status.body["phase"], "volatile_snapshot");
+
+        lock(&running).state = CollectionState::Completed;
+        let response = server.route(&request(
+            "POST",
+            "/collections/first/cancel",
+            Some("s3cret"),
+            "",
+        ));
+        assert_eq!(response.status, 409);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Structured error types for the collector's critical error paths.
+//!
+//! Most of the code base reports errors through `anyhow`, which is kept as the
+//! transport. `CollectorError` gives the failures callers need to react to
+//! (missing sources, permission problems, failed uploads, ...) a type that can
+//! be recovered with `anyhow::Error::downcast_ref` instead of matching on
+//! message text. Messages keep the wording of the strings they replace, so
+//! log output and existing message checks are unchanged.
+//!
+//! `CollectorError` implements `std::error::Error + Send + Sync`, so anyhow's
+//! blanket `From` implementation converts it into `anyhow::Error` and it can
+//! be returned with `?` or `.into()` from any `anyhow::Result` function.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors raised on the collector's critical paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectorError {
+    /// The source of an artifact does not exist
+    ArtifactNotFound { name: String, path: PathBuf },
+    /// The source of an artifact could not be read with the current privileges
+    PermissionDenied {
+        path: PathBuf,
+        artifact_name: Option<String>,
+    },
+    /// An upload to remote storage failed
+    UploadFailed { target: String, cause: String },
+    /// The collection configuration is invalid or unreadable
+    ConfigError { message: String },
+    /// An artifact type cannot be collected on this platform
+    PlatformNotSupported {
+        artifact_type: String,
+        platform: String,
+    },
+    /// Memory of a process could not be collected
+    #[cfg_attr(
+        not(any(target_os = "linux", feature = "memory_collection")),
+        allow(dead_code)
+    )]
+    MemoryCollectionFailed { pid: u32, cause: String },
+    /// Collecting an artifact took longer than its timeout
+    Timeout { artifact_name: String, seconds: u64 },
+}
+
+impl fmt::Display for CollectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectorError::ArtifactNotFound { name, path } => {
+                write!(f, "Source not found for {}: {}", name, path.display())
+            }
+            CollectorError::PermissionDenied {
+                path,
+                artifact_name,
+            } => {
+                write!(f, "Permission denied accessing {}", path.display())?;
+                if let Some(name) = artifact_name {
+                    write!(f, " ({})", name)?;
+                }
+                write!(f, ". Try running with elevated privileges.")
+            }
+            CollectorError::UploadFailed { target, cause } => {
+                write!(f, "Failed to upload to {}: {}", target, cause)
+            }
+            CollectorError::ConfigError { message } => {
+                write!(f, "Configuration error: {}", message)
+            }
+            CollectorError::PlatformNotSupported {
+                artifact_type,
+                platform,
+            } => write!(f, "{} is not supported on {}", artifact_type, platform),
+            CollectorError::MemoryCollectionFailed { pid, cause } => {
+                write!(f, "Memory collection failed for process {}: {}", pid, cause)
+            }
+            CollectorError::Timeout {
+                artifact_name,
+                seconds,
+            } => write!(
+                f,
+                "Collection of {} timed out after {} seconds",
+                artifact_name, seconds
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CollectorError {}
+
+impl CollectorError {
+    /// Find a `CollectorError` anywhere in the chain of an `anyhow::Error`
+    pub fn find(error: &anyhow::Error) -> Option<&CollectorError> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<CollectorError>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+
+    #[test]
+    fn test_display_keeps_existing_wording() {
+        let error = CollectorError::PermissionDenied {
+            path: PathBuf::from("/etc/shadow"),
+            artifact_name: Some("shadow".to_string()),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Permission denied accessing /etc/shadow (shadow). Try running with elevated privileges."
+        );
+
+        let error = CollectorError::UploadFailed {
+            target: "S3 bucket evidence".to_string(),
+            cause: "timeout".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Failed to upload to S3 bucket evidence: timeout"
+        );
+    }
+
+    #[test]
+    fn test_recovered_through_anyhow_context() {
+        fn collect() -> Result<()> {
+            Err(CollectorError::MemoryCollectionFailed {
+                pid: 42,
+                cause: "process exited".to_string(),
+            })
+            .context("Failed to dump process")?;
+            Ok(())
+        }
+
+        let error = collect().unwrap_err();
+        assert_eq!(
+            CollectorError::find(&error),
+            Some(&CollectorError::MemoryCollectionFailed {
+                pid: 42,
+                cause: "process exited".to_string(),
+            })
+        );
+        assert!(CollectorError::find(&anyhow::anyhow!("plain error")).is_none());
+    }
+}
@@ -73,6 +73,7 @@
 //! - [`cloud`]: Cloud storage upload functionality (S3, SFTP)
 //! - [`utils`]: Utility functions for compression, hashing, etc.
 //! - [`security`]: Security utilities including path validation and credential scrubbing
+//! - [`preflight`]: Environment checks run before a collection
 //! - [`privileges`]: Platform-specific privilege escalation
 //! - [`constants`]: Application-wide constants
 //!
@@ -124,9 +125,15 @@ pub mod cloud;
 /// Configuration management and artifact definitions
 pub mod config;
 
+/// Structured error types for critical error paths
+pub mod errors;
+
 /// Build script generation for custom collection workflows
 pub mod build;
 
+/// Environment checks for the `check` subcommand and `--preflight`
+pub mod preflight;
+
 /// Platform-specific privilege management
 pub mod privileges;
 
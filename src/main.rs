@@ -1,10 +1,14 @@
+use std::collections::BTreeMap;
 use std::env;
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
-use log::{info, warn, LevelFilter};
+use log::{debug, info, warn, LevelFilter};
 use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
 use tokio::runtime::Runtime;
 
@@ -14,73 +18,501 @@ mod cloud;
 mod collectors;
 mod config;
 mod constants;
+#[cfg(feature = "control_api")]
+mod control_api;
+mod errors;
 mod models;
+mod preflight;
 mod privileges;
+#[cfg(feature = "remote_windows")]
+mod remote_windows;
 mod utils;
 mod windows;
 
+// The binary uses the audit trail and destination validation; the other
+// security utilities are library API
+mod security {
+    pub mod audit_log;
+    #[allow(dead_code)]
+    pub mod config;
+    #[allow(dead_code)]
+    pub mod credential_scrubber;
+    #[allow(dead_code)]
+    pub mod path_validator;
+}
+
 #[cfg(test)]
 mod test_utils;
 
-use cli::{Args, Commands};
-use collectors::collector;
+use cli::{Args, Commands, SummaryFormat};
+use collectors::collector::{self, ArtifactFailure, FailureReason};
+use collectors::footprint;
+use collectors::incremental;
+use collectors::macos::extended_attributes;
+use collectors::phases::{CollectionPhase, PhaseObserver, PhaseTimeline};
+use collectors::streaming::ArchiveStream;
+use collectors::volatile::entropy_analysis::{self, HighEntropyReport};
+use config::dynamic_catalog::{self, DynamicArtifacts};
 use config::{load_or_create_config, Artifact, CollectionConfig};
 use models::ArtifactMetadata;
 use privileges::enable_required_privileges;
-use utils::{compress, summary};
+use security::{audit_log, credential_scrubber};
+use utils::atomic_output::{self, StagedOutput};
+use utils::deadline;
+use utils::file_type::{self, FileTypeStats};
+use utils::metrics::{self, MetricsPhaseObserver, SnapshotWriter};
+use utils::nsrl::{NsrlDatabase, NsrlStats};
+use utils::resource_limits::{self, AppliedLimits, ResourceLimits};
+use utils::summary::{ArtifactTypeRollup, CollectionSummary, HostInfo, UploadDestination};
+use utils::timezone::HostTimeContext;
+use utils::verification::{self, SampleCandidate, SampleOptions, VerificationReport};
+use utils::{compress, proxy, self_update, siem_export, summary, timezone};
 
 fn main() -> Result<()> {
     // Parse arguments
     let args = Args::parse();
 
+    // Templates go to stdout before the logger, which also writes there
+    if let Some(template) = args.config_template {
+        let config = CollectionConfig::default_for_template(&template.to_string())?;
+        print!("{}", config.to_yaml_string()?);
+        return Ok(());
+    }
+
     // Initialize logging
     initialize_logging(args.verbose)?;
 
+    // Every outbound connection, subcommands included, uses the proxy
+    if let Some(proxy_url) = &args.proxy_url {
+        proxy::configure(
+            proxy_url,
+            args.proxy_auth_env.as_deref(),
+            args.no_proxy.as_deref(),
+        )?;
+    }
+
     // Handle subcommands
     if let Some(cmd) = &args.command {
-        return handle_subcommand(cmd);
+        return handle_subcommand(cmd, &args);
+    }
+
+    if let Some(manifest_url) = &args.check_update {
+        let check = self_update::check_update(manifest_url)?;
+        println!("{}", check.describe());
+        return Ok(());
     }
 
+    if let Some(addr) = &args.serve {
+        return serve_control_api(addr, &args);
+    }
+
+    // Re-launch elevated before anything is written
+    if args.auto_elevate && !args.force && !privileges::is_elevated() {
+        privileges::request_elevation().context("Automatic elevation failed")?;
+    }
+
+    run_collection(&args, None).map(|_| ())
+}
+
+/// Run one collection with the given options; returns the summary path.
+///
+/// `observer` receives the phase events, which is how the control API
+/// reports live status.
+fn run_collection(args: &Args, observer: Option<Arc<dyn PhaseObserver>>) -> Result<PathBuf> {
+    let result = run_collection_inner(args, observer);
+    // Stop the governor on failure too, so a later --serve run is not
+    // throttled by this one
+    resource_limits::release();
+    result
+}
+
+/// Body of [`run_collection`]
+fn run_collection_inner(args: &Args, observer: Option<Arc<dyn PhaseObserver>>) -> Result<PathBuf> {
     info!("Starting DFIR triage collection");
 
+    // The --max-runtime window starts with the collector; cleared first, as
+    // --serve collects repeatedly in one process
+    deadline::clear();
+    if let Some(max_runtime) = args.max_runtime {
+        deadline::start(max_runtime, args.max_runtime_margin);
+    }
+
+    // Limit the collector's own CPU and memory before worker threads start
+    resource_limits::apply(ResourceLimits {
+        max_cpu_percent: args.max_cpu_percent,
+        max_memory_mb: args.max_memory_mb,
+    });
+
+    // Each run reports only what it touched; reset first, as --serve collects
+    // repeatedly in one process
+    footprint::reset();
+
+    // Point scratch space away from the system temp directory if requested
+    configure_temp_dir(args)?;
+
+    // Register network share mappings before any source paths are opened
+    register_network_mounts(args)?;
+
+    collectors::read_only::set_verification(args.verify_no_writes);
+
+    extended_attributes::set_collect_xattrs(
+        args.collect_xattrs && extended_attributes::supported(),
+    );
+    if args.collect_xattrs && !extended_attributes::supported() {
+        warn!("--collect-xattrs is only supported on Linux and macOS; no sidecars will be written");
+    }
+
+    entropy_analysis::set_compute_entropy(args.compute_entropy);
+
+    if args.fuzzy_hash && !cfg!(feature = "fuzzy_hash") {
+        bail!("--fuzzy-hash needs a build with the fuzzy_hash feature (cargo build --features fuzzy_hash)");
+    }
+
     // Load and process configuration
-    let config = load_and_process_config(&args)?;
-    let artifacts_to_collect = filter_artifacts_by_type(&config, &args);
+    let mut config = load_and_process_config(args)?;
+    let verify_sample = args
+        .verify_sample
+        .map(|percent| SampleOptions::new(percent, args.verify_seed));
+    let config_sha256 = summary::config_sha256(&config)?;
+    let dynamic_artifacts = add_dynamic_artifacts(&mut config, args);
+    let artifacts_to_collect = filter_artifacts_by_type(&config, args);
+    collectors::platforms::fast_copy::configure(&config.global_options);
+    credential_scrubber::configure(&config.global_options);
+    credential_scrubber::reset_redaction_count();
+    collectors::volatile::windows_network_config::configure(&config.global_options);
+    collectors::linux::ld_preload::configure(&config.global_options);
+    collectors::source::register_source(Arc::new(
+        collectors::linux::container_fs::ContainerSource::new(),
+    ));
+    collectors::parsers::antiforensics::configure(&config.global_options);
+    collectors::macos::unified_log_parser::set_unified_log_hours(args.unified_log_hours);
+    windows::vss::set_enabled(!args.disable_vss);
+
+    // Load the baseline up front so a bad path fails before collection;
+    // cleared first, as --serve collects repeatedly in one process
+    incremental::clear_baseline();
+    if let Some(baseline) = &args.baseline {
+        if args.force_full {
+            info!("--force-full: collecting every file despite --baseline");
+        } else {
+            incremental::set_baseline(incremental::Baseline::load(baseline)?);
+        }
+    }
+
+    // Open the NSRL database up front so a bad path fails before collection
+    let nsrl_db = args
+        .nsrl_db
+        .as_deref()
+        .map(|path| NsrlDatabase::open(Path::new(path)))
+        .transpose()?;
+
+    // Validate the environment before anything is collected
+    if args.preflight {
+        let report = preflight::run_preflight(&preflight_options(args, &artifacts_to_collect)?)?;
+        info!("Preflight: {} checks passed", report.checks.len());
+    }
 
     // Check privileges
-    check_and_enable_privileges(&args)?;
+    check_and_enable_privileges(args)?;
+
+    // Record system state before anything is collected
+    let state_diff_tracker = if args.state_diff {
+        match collectors::volatile::state_diff::StateDiffTracker::start() {
+            Ok(tracker) => Some(tracker),
+            Err(e) => {
+                warn!("Failed to take start-of-collection state snapshot: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     // Setup collection directories
-    let (hostname, timestamp, artifact_dir) = setup_collection_directories(&args)?;
+    let (hostname, timestamp, artifact_dir, staged_output) = setup_collection_directories(args)?;
+
+    // Audit the collector's own file access and uploads from here on
+    if let Err(e) = audit_log::start(&artifact_dir, &hostname, &timestamp) {
+        warn!("Failed to start audit trail: {:#}", e);
+    }
+
+    // Raw credential files are only copied on explicit request; set on every
+    // run, as --serve collects repeatedly in one process
+    collectors::cloud_credentials::set_collect_raw_files(args.collect_credential_files);
+    if args.collect_credential_files {
+        warn!(
+            "--collect-credential-files: raw cloud credential files will be copied into the output"
+        );
+        audit_log::record(
+            audit_log::AuditAction::CredentialCollection,
+            "raw cloud credential file collection enabled by --collect-credential-files",
+        );
+    }
+
+    // Record timezone and clock skew before any timestamps are collected
+    let time_context = timezone::collect_time_context(args.ntp_check.as_deref());
+
+    // With --stream, collected files go into the upload archive as they are copied
+    let archive_stream = start_archive_stream(&artifact_dir, &hostname, &timestamp, args);
+
+    let mut phase_timeline = PhaseTimeline::new();
+    if let Some(observer) = observer {
+        phase_timeline = phase_timeline.with_observer(observer);
+    }
+
+    // Local performance metrics, written at the end and optionally as
+    // snapshots; set on every run, as --serve collects repeatedly in one process
+    metrics::set_enabled(args.metrics);
+    let metrics_snapshots = if args.metrics {
+        phase_timeline = phase_timeline.with_observer(Arc::new(MetricsPhaseObserver));
+        args.metrics_interval
+            .filter(|secs| *secs > 0)
+            .and_then(|secs| {
+                SnapshotWriter::start(&artifact_dir, Duration::from_secs(secs))
+                    .map_err(|e| warn!("Metrics snapshots disabled: {:#}", e))
+                    .ok()
+            })
+    } else {
+        None
+    };
 
-    // Collect volatile data
-    let volatile_data_summary = collect_volatile_data(&artifact_dir, &args)?;
+    // Read artifacts from filesystem snapshots until collection is done;
+    // the snapshots are removed when the session is dropped
+    let snapshot_session = if args.use_snapshots {
+        start_snapshot_session(args, &artifacts_to_collect)
+    } else {
+        None
+    };
+
+    let (volatile_data_summary, memory_collection_summary, collected) = if args.volatility_order {
+        collect_in_volatility_order(
+            &artifact_dir,
+            &artifacts_to_collect,
+            &config,
+            &hostname,
+            args,
+            &time_context,
+            &phase_timeline,
+            nsrl_db.as_ref(),
+            archive_stream.as_ref(),
+        )?
+    } else {
+        // Collect volatile data
+        let volatile_data_summary = phase_timeline
+            .run(CollectionPhase::VolatileSnapshot, || {
+                collect_volatile_data(&artifact_dir, &config, args, &time_context)
+            })?;
+
+        // Collect process memory if requested
+        let memory_collection_summary = phase_timeline
+            .run(CollectionPhase::ProcessMemory, || {
+                handle_memory_operations(&artifact_dir, args, &volatile_data_summary)
+            })?;
+
+        // Collect artifacts
+        let collected = phase_timeline.run(CollectionPhase::FileArtifacts, || {
+            collect_artifacts(
+                &artifact_dir,
+                &artifacts_to_collect,
+                &config,
+                nsrl_db.as_ref(),
+                archive_stream.as_ref(),
+                args.fuzzy_hash,
+                verify_sample,
+            )
+        })?;
+
+        // Generate bodyfile if requested
+        phase_timeline.run(CollectionPhase::Bodyfile, || {
+            generate_bodyfile_if_requested(&artifact_dir, &config, &hostname);
+            Ok(())
+        })?;
 
-    // Collect process memory if requested
-    let memory_collection_summary =
-        handle_memory_operations(&artifact_dir, &args, &volatile_data_summary)?;
+        (volatile_data_summary, memory_collection_summary, collected)
+    };
+    drop(snapshot_session);
 
-    // Collect artifacts
-    let all_metadata = collect_artifacts(&artifact_dir, &artifacts_to_collect, &config)?;
+    // Parse collected copies of artifacts that have offline parsers
+    if archive_stream.is_some() {
+        info!("Collected files were streamed to the upload, skipping offline parsing");
+    } else if deadline::allows_phase("offline_parsing") {
+        collectors::parsers::parse_collected_artifacts(&artifact_dir);
+    }
 
-    // Generate bodyfile if requested
-    generate_bodyfile_if_requested(&artifact_dir, &config, &hostname);
+    // Compare against the start snapshot once collection is done
+    if let Some(tracker) = state_diff_tracker {
+        if let Err(e) = tracker.finish(&artifact_dir) {
+            warn!("Failed to write state diff: {}", e);
+        }
+    }
+
+    let nsrl_stats = nsrl_db
+        .as_ref()
+        .map(|db| NsrlStats::from_metadata(db.path(), &collected.metadata));
+    if let Some(stats) = &nsrl_stats {
+        info!(
+            "NSRL: {} of {} hashed files known-good, {} for review",
+            stats.known_good, stats.files_checked, stats.unknown
+        );
+    }
+
+    let file_type_stats = file_type::identify_types_enabled(&config.global_options)
+        .then(|| FileTypeStats::from_metadata(&collected.metadata));
+    if let Some(stats) = file_type_stats
+        .as_ref()
+        .filter(|s| s.extension_mismatches > 0)
+    {
+        warn!(
+            "{} collected files have an extension that does not match their content",
+            stats.extension_mismatches
+        );
+    }
+
+    let summary_context = SummaryContext {
+        hostname: &hostname,
+        timestamp: &timestamp,
+        format: args.summary_format,
+        config_sha256,
+        host: HostInfo::collect(),
+        uploads: upload_destinations(args, &hostname, &timestamp),
+        time_context: &time_context,
+        phase_timeline: &phase_timeline,
+        volatility_order: args.volatility_order,
+        nsrl_stats,
+        file_type_stats,
+        volatile_data_summary: volatile_data_summary.as_ref(),
+        memory_collection_summary: memory_collection_summary.as_ref(),
+        resource_limits: resource_limits::report(),
+        dynamic_artifacts,
+    };
+
+    // Every source has been read by now
+    collectors::read_only::write_report(&artifact_dir)?;
 
     // Write collection summary
-    write_collection_summary(
-        &artifact_dir,
-        &hostname,
-        &timestamp,
-        &all_metadata,
-        &volatile_data_summary,
-        &memory_collection_summary,
-    )?;
+    let summary_path = write_collection_summary(&artifact_dir, &summary_context, &collected)?;
+
+    // Delete shadow copies made for locked files before recording the footprint
+    windows::vss::release_shadow_copies();
+
+    // Record what the collector changed on the system
+    footprint::write_footprint(&artifact_dir)?;
 
     // Handle upload
-    handle_upload(&artifact_dir, &hostname, &timestamp, &args)?;
+    handle_upload(&artifact_dir, &hostname, &timestamp, args, archive_stream)?;
+    if let Some(snapshots) = metrics_snapshots {
+        snapshots.stop();
+    }
+
+    // The summary and archive are complete, so the staged output can take its final name
+    let (artifact_dir, summary_path) = match staged_output {
+        Some(staged) => {
+            let final_dir = audit_log::relocate(|| staged.finalize(args.output_manifest))?;
+            let summary_path = final_dir.join(summary_path.file_name().unwrap_or_default());
+            (final_dir, summary_path)
+        }
+        None => (artifact_dir, summary_path),
+    };
+
+    // Written last so compression and upload are covered; not in the archive
+    if args.metrics {
+        match metrics::write_metrics(&artifact_dir, true) {
+            Ok(path) => info!("Wrote performance metrics to {}", path.display()),
+            Err(e) => warn!("Failed to write metrics: {:#}", e),
+        }
+    }
+
+    info!("{}", footprint::snapshot().summary_line());
+    log_audit_verification();
+
+    if args.watch {
+        // Runs until the process is killed
+        let mut collected = collected;
+        let runtime = Runtime::new().context("Failed to create Tokio runtime")?;
+        let stream_uploads = args.stream && !args.skip_upload;
+
+        collectors::watch::watch_and_recollect(
+            &artifacts_to_collect,
+            &artifact_dir,
+            |recollection| {
+                let rollup = collected
+                    .rollups
+                    .entry(recollection.artifact.artifact_type.to_string())
+                    .or_default();
+                for (_, metadata) in &recollection.metadata {
+                    rollup.add_file(metadata);
+                }
+                collected
+                    .metadata
+                    .extend(recollection.metadata.iter().cloned());
+                write_collection_summary(&artifact_dir, &summary_context, &collected)?;
+                windows::vss::release_shadow_copies();
+                footprint::write_footprint(&artifact_dir)?;
+
+                if !stream_uploads {
+                    return Ok(false);
+                }
+                runtime.block_on(upload_recollected_files(
+                    &artifact_dir,
+                    &recollection.files,
+                    &hostname,
+                    &timestamp,
+                    args,
+                ))?;
+                Ok(true)
+            },
+        )?;
+    }
 
     info!("DFIR triage completed successfully");
-    Ok(())
+    Ok(summary_path)
+}
+
+/// Serve the control API (`--serve`)
+#[cfg(feature = "control_api")]
+fn serve_control_api(addr: &str, args: &Args) -> Result<()> {
+    control_api::serve(addr, args)
+}
+
+#[cfg(not(feature = "control_api"))]
+fn serve_control_api(_addr: &str, _args: &Args) -> Result<()> {
+    Err(anyhow!(
+        "--serve needs a build with the control_api feature (cargo build --features control_api)"
+    ))
+}
+
+/// Record the SSDEEP hash of a collected executable (`--fuzzy-hash`)
+#[cfg(feature = "fuzzy_hash")]
+fn fuzzy_hash_collected_file(path: &Path, metadata: &mut ArtifactMetadata) {
+    use utils::hash::{calculate_ssdeep, is_fuzzy_hash_candidate, FUZZY_HASH_MAX_SIZE_MB};
+
+    if !is_fuzzy_hash_candidate(path) {
+        return;
+    }
+    match calculate_ssdeep(path, FUZZY_HASH_MAX_SIZE_MB) {
+        Ok(hash) => metadata.ssdeep = hash,
+        Err(e) => warn!("Could not fuzzy hash {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(not(feature = "fuzzy_hash"))]
+fn fuzzy_hash_collected_file(_path: &Path, _metadata: &mut ArtifactMetadata) {}
+
+/// Write the extended attributes of a live source next to its copy
+/// (`--collect-xattrs`)
+fn write_xattr_sidecar(original_path: &str, collected_path: &Path) {
+    // Sources in images and containers are not on this host's filesystem
+    let Ok((source, path)) = collectors::source::resolve(original_path) else {
+        return;
+    };
+    if !source.is_live_system() || !collected_path.is_file() {
+        return;
+    }
+    match extended_attributes::write_sidecar(Path::new(path), collected_path) {
+        Ok(Some(sidecar)) => debug!("Wrote {}", sidecar.display()),
+        Ok(None) => {}
+        Err(e) => debug!("{:#}", e),
+    }
 }
 
 /// Initialize logging with the specified verbosity level
@@ -100,8 +532,8 @@ fn initialize_logging(verbose: bool) -> Result<()> {
     Ok(())
 }
 
-/// Handle subcommands (init-config and build)
-fn handle_subcommand(cmd: &Commands) -> Result<()> {
+/// Handle subcommands (init-config, build, check, diff, remote-windows, generate-url and download)
+fn handle_subcommand(cmd: &Commands, args: &Args) -> Result<()> {
     match cmd {
         Commands::InitConfig { path, target_os } => {
             if let Some(os) = target_os {
@@ -134,7 +566,285 @@ fn handle_subcommand(cmd: &Commands) -> Result<()> {
             info!("Standalone binary created at: {}", output_file.display());
             Ok(())
         }
+        Commands::Check { json } => {
+            let config = load_and_process_config(args)?;
+            let artifacts = filter_artifacts_by_type(&config, args);
+            let report = preflight::run_checks(&preflight_options(args, &artifacts)?);
+
+            if *json {
+                println!("{}", report.to_json()?);
+            } else {
+                print!("{}", report.to_table());
+            }
+
+            if !report.passed() {
+                return Err(anyhow!("One or more required checks failed"));
+            }
+            Ok(())
+        }
+        Commands::Diff {
+            baseline,
+            incident,
+            json,
+        } => {
+            let read = |path: &PathBuf| {
+                fs::read_to_string(path).context(format!("Failed to read {}", path.display()))
+            };
+            let diff = summary::diff_summaries(&read(baseline)?, &read(incident)?)?;
+
+            if *json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&diff).context("Failed to serialize diff")?
+                );
+            } else {
+                print!("{}", diff.to_text());
+            }
+            Ok(())
+        }
+        Commands::RemoteWindows(opts) => run_remote_windows(opts),
+        Commands::GenerateUrl { key, expiry_hours } => generate_url(args, key, *expiry_hours),
+        Commands::Download {
+            bucket,
+            key,
+            output_path,
+            region,
+            profile,
+        } => download(
+            args,
+            bucket,
+            key,
+            output_path,
+            region.as_deref(),
+            profile.as_deref(),
+        ),
+        Commands::Export(opts) => run_export(opts),
+        Commands::SelfUpdate {
+            manifest_url,
+            allow_downgrade,
+        } => run_self_update(manifest_url, *allow_downgrade),
+    }
+}
+
+/// Install the release in a signed manifest over this executable (`self-update`)
+fn run_self_update(manifest_url: &str, allow_downgrade: bool) -> Result<()> {
+    let check = self_update::check_update(manifest_url)?;
+    println!("{}", check.describe());
+    match check.status {
+        self_update::UpdateStatus::UpToDate => return Ok(()),
+        self_update::UpdateStatus::Ahead if !allow_downgrade => {
+            bail!("Not downgrading without --allow-downgrade")
+        }
+        _ => {}
+    }
+    let release = check
+        .release()
+        .ok_or_else(|| anyhow!("The manifest has no {} build", self_update::platform()))?;
+
+    let exe = std::env::current_exe().context("Failed to locate the running executable")?;
+    self_update::install_release(release, &exe)?;
+    println!(
+        "Installed {} {} at {}; it is used from the next run",
+        env!("CARGO_PKG_NAME"),
+        check.manifest.version,
+        exe.display()
+    );
+    Ok(())
+}
+
+/// Export a finished collection to files or a SIEM endpoint (`export`)
+fn run_export(opts: &cli::ExportOpts) -> Result<()> {
+    let destination = match &opts.url {
+        Some(url) => {
+            let authorization = match &opts.token_file {
+                Some(path) => {
+                    let token = fs::read_to_string(path)
+                        .context(format!("Failed to read {}", path.display()))?;
+                    let token = token.trim();
+                    if token.is_empty() {
+                        bail!("{} is empty", path.display());
+                    }
+                    let scheme = opts.auth_scheme.as_deref().unwrap_or(match opts.format {
+                        cli::SiemFormat::Opensearch => "ApiKey",
+                        cli::SiemFormat::Splunk => "Splunk",
+                    });
+                    Some(format!("{} {}", scheme, token))
+                }
+                None => None,
+            };
+            siem_export::ExportDestination::Http {
+                url: url.clone(),
+                authorization,
+            }
+        }
+        None => siem_export::ExportDestination::Files(
+            opts.output
+                .clone()
+                .unwrap_or_else(|| opts.collection_dir.join(siem_export::EXPORT_DIR)),
+        ),
+    };
+    let options = siem_export::ExportOptions {
+        format: opts.format,
+        index_template: opts.index.clone(),
+        sourcetype_template: opts.sourcetype.clone(),
+        batch_size: opts.batch_size,
+        retries: opts.retries,
+        destination: destination.clone(),
+    };
+
+    let stats = siem_export::export_collection(&opts.collection_dir, &options)?;
+    for (doc_type, count) in &stats.documents {
+        info!("Exported {} {} documents", count, doc_type);
+    }
+    match destination {
+        siem_export::ExportDestination::Files(dir) => {
+            println!("Wrote {} files to {}", stats.batches, dir.display())
+        }
+        siem_export::ExportDestination::Http { url, .. } => println!(
+            "Sent {} batches to {} ({} documents rejected)",
+            stats.batches, url, stats.rejected
+        ),
+    }
+    Ok(())
+}
+
+/// Print a presigned URL for `key` in `--bucket` and audit it (`generate-url`).
+///
+/// The trail goes to `generate-url-<timestamp>` under the output directory,
+/// so a collection's own trail is never replaced. The URL itself is not
+/// recorded, since it grants access until it expires.
+fn generate_url(args: &Args, key: &str, expiry_hours: u64) -> Result<()> {
+    let bucket = args
+        .bucket
+        .as_deref()
+        .ok_or_else(|| anyhow!("generate-url requires --bucket"))?;
+    let expiry_secs = expiry_hours.saturating_mul(3600);
+
+    let runtime = Runtime::new().context("Failed to create Tokio runtime")?;
+    let url = runtime.block_on(cloud::s3::generate_presigned_url(
+        bucket,
+        key,
+        args.region.as_deref(),
+        args.profile.as_deref(),
+        expiry_secs,
+    ))?;
+
+    let hostname = get_hostname()?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let trail_dir = output_dir_from_args(args).join(format!("generate-url-{}", timestamp));
+    match audit_log::start(&trail_dir, &hostname, &timestamp) {
+        Ok(path) => {
+            let expires = chrono::Utc::now() + chrono::Duration::seconds(expiry_secs as i64);
+            audit_log::record(
+                audit_log::AuditAction::PresignedUrl,
+                &format!("s3://{}/{} expires={}", bucket, key, expires.to_rfc3339()),
+            );
+            info!("Recorded presigned URL in {}", path.display());
+        }
+        Err(e) => warn!("Failed to start audit trail: {:#}", e),
+    }
+
+    println!("{}", url);
+    Ok(())
+}
+
+/// Download one uploaded object and audit it (`download`).
+///
+/// Like `generate-url`, the trail goes to `download-<timestamp>` under the
+/// output directory.
+fn download(
+    args: &Args,
+    bucket: &str,
+    key: &str,
+    output_path: &Path,
+    region: Option<&str>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let hostname = get_hostname()?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let trail_dir = output_dir_from_args(args).join(format!("download-{}", timestamp));
+    match audit_log::start(&trail_dir, &hostname, &timestamp) {
+        Ok(path) => info!("Recording the download in {}", path.display()),
+        Err(e) => warn!("Failed to start audit trail: {:#}", e),
+    }
+
+    let runtime = Runtime::new().context("Failed to create Tokio runtime")?;
+    runtime.block_on(cloud::s3::download_artifact(
+        bucket,
+        key,
+        output_path,
+        region,
+        profile,
+    ))
+}
+
+/// Deploy and run the collector on a remote Windows host (`remote-windows`)
+#[cfg(feature = "remote_windows")]
+fn run_remote_windows(opts: &cli::RemoteWindowsOpts) -> Result<()> {
+    remote_windows::run(opts)
+}
+
+#[cfg(not(feature = "remote_windows"))]
+fn run_remote_windows(_opts: &cli::RemoteWindowsOpts) -> Result<()> {
+    Err(anyhow!(
+        "remote-windows needs a build with the remote_windows feature (cargo build --features remote_windows)"
+    ))
+}
+
+/// Environment variables that name the scratch directory
+const TEMP_VARS: [&str; 3] = ["TMPDIR", "TMP", "TEMP"];
+
+/// Values of [`TEMP_VARS`] before the first `--temp-dir` override
+static ORIGINAL_TEMP_VARS: OnceLock<Vec<(&str, Option<OsString>)>> = OnceLock::new();
+
+/// Redirect scratch space to `--temp-dir`.
+///
+/// The temp environment variables are overridden so the archive, any
+/// temporary files and spawned tools all use the same directory. They are
+/// restored first, so a `--serve` run without `--temp-dir` does not inherit
+/// an earlier run's directory.
+fn configure_temp_dir(args: &Args) -> Result<()> {
+    let originals = ORIGINAL_TEMP_VARS.get_or_init(|| {
+        TEMP_VARS
+            .iter()
+            .map(|var| (*var, env::var_os(var)))
+            .collect()
+    });
+    for (var, value) in originals {
+        match value {
+            Some(value) => env::set_var(var, value),
+            None => env::remove_var(var),
+        }
+    }
+
+    let temp_dir = match &args.temp_dir {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(()),
+    };
+
+    footprint::create_dir_all_tracked(&temp_dir).context(format!(
+        "Failed to create temp directory: {}",
+        temp_dir.display()
+    ))?;
+
+    for var in TEMP_VARS {
+        env::set_var(var, &temp_dir);
     }
+    info!("Using {} for scratch space", temp_dir.display());
+    Ok(())
+}
+
+/// Register `--network-mount-point` mappings with the fallback collector
+fn register_network_mounts(args: &Args) -> Result<()> {
+    let mounts = args
+        .network_mount_point
+        .iter()
+        .map(|spec| collectors::platforms::network_mounts::NetworkMount::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Replaced on every run, as --serve collects repeatedly in one process
+    collectors::platforms::network_mounts::register_network_mounts(mounts);
+    Ok(())
 }
 
 /// Load configuration and process environment variables
@@ -144,6 +854,29 @@ fn load_and_process_config(args: &Args) -> Result<CollectionConfig> {
     Ok(config)
 }
 
+/// Add the catalog artifacts of the server roles detected on the host,
+/// unless `--no-dynamic-artifacts` is set. They are added before the
+/// `--artifact-types` filter, which applies to them too.
+fn add_dynamic_artifacts(config: &mut CollectionConfig, args: &Args) -> Option<DynamicArtifacts> {
+    if args.no_dynamic_artifacts {
+        return None;
+    }
+    let selection = dynamic_catalog::select_artifacts(
+        &dynamic_catalog::LiveEnvironment::new(),
+        &config.artifacts,
+    );
+    for role in &selection.roles {
+        info!(
+            "Detected {} ({}), adding {} artifacts",
+            role.role.as_str(),
+            role.evidence.join(", "),
+            role.artifacts_added.len()
+        );
+    }
+    config.artifacts.extend(selection.artifacts.iter().cloned());
+    Some(selection)
+}
+
 /// Filter artifacts by type if specified
 fn filter_artifacts_by_type(config: &CollectionConfig, args: &Args) -> Vec<Artifact> {
     if let Some(types_str) = &args.artifact_types {
@@ -180,10 +913,29 @@ fn compress_and_upload(
     summary_path: &PathBuf,
     args: &Args,
 ) -> Result<()> {
-    // Compress artifacts
-    let zip_path = compress::compress_artifacts(artifact_dir, hostname, timestamp)?;
-
-    info!("Artifact archive: {}", zip_path.display());
+    // Compress artifacts, in parts if a FAT volume cannot hold the whole archive
+    check_previous_archive(args, artifact_dir)?;
+    let archive_paths = match (archive_part_size(artifact_dir), &args.previous_archive) {
+        (Some(part_size), _) => {
+            compress::compress_artifacts_split(artifact_dir, hostname, timestamp, part_size)?
+        }
+        (None, Some(previous)) => vec![compress::compress_artifacts_incremental(
+            artifact_dir,
+            Some(previous),
+            hostname,
+            timestamp,
+            None,
+        )?],
+        (None, None) => vec![compress::compress_artifacts(
+            artifact_dir,
+            hostname,
+            timestamp,
+        )?],
+    };
+    for path in &archive_paths {
+        footprint::record_created_path(path);
+        info!("Artifact archive: {}", path.display());
+    }
 
     // Skip upload if requested
     if args.skip_upload {
@@ -195,7 +947,7 @@ fn compress_and_upload(
     // Upload to S3 if configured
     if args.bucket.is_some() {
         // Prepare artifact paths to upload
-        let mut files_to_upload = vec![zip_path.clone()];
+        let mut files_to_upload = archive_paths.clone();
 
         // Also upload the summary JSON separately for easy access
         files_to_upload.push(summary_path.clone());
@@ -234,32 +986,11 @@ fn compress_and_upload(
     // Upload to SFTP if configured
     if args.sftp_host.is_some() && args.sftp_user.is_some() && args.sftp_key.is_some() {
         // Create SFTP config
-        let sftp_config = cloud::sftp::SFTPConfig {
-            host: args
-                .sftp_host
-                .as_ref()
-                .ok_or_else(|| anyhow!("SFTP host not provided"))?
-                .clone(),
-            port: args.sftp_port,
-            username: args
-                .sftp_user
-                .as_ref()
-                .ok_or_else(|| anyhow!("SFTP user not provided"))?
-                .clone(),
-            private_key_path: args
-                .sftp_key
-                .as_ref()
-                .ok_or_else(|| anyhow!("SFTP key not provided"))?
-                .clone(),
-            remote_path: args.sftp_path.clone().unwrap_or_else(|| "/".to_string()),
-            concurrent_connections: args.sftp_connections,
-            buffer_size_mb: args.buffer_size,
-            connection_timeout_sec: 30, // Default timeout
-            max_retries: 3,             // Default retries
-        };
+        let sftp_config = sftp_config_from_args(args)?;
 
         // Prepare artifact paths to upload
-        let files_to_upload = vec![zip_path.clone(), summary_path.clone()];
+        let mut files_to_upload = archive_paths.clone();
+        files_to_upload.push(summary_path.clone());
 
         info!(
             "Starting upload of {} files to SFTP server: {}",
@@ -280,6 +1011,79 @@ fn compress_and_upload(
 
     Ok(())
 }
+/// Build the SFTP configuration from command line arguments
+fn sftp_config_from_args(args: &Args) -> Result<cloud::sftp::SFTPConfig> {
+    Ok(cloud::sftp::SFTPConfig {
+        host: args
+            .sftp_host
+            .as_ref()
+            .ok_or_else(|| anyhow!("SFTP host not provided"))?
+            .clone(),
+        port: args.sftp_port,
+        username: args
+            .sftp_user
+            .as_ref()
+            .ok_or_else(|| anyhow!("SFTP user not provided"))?
+            .clone(),
+        private_key_path: args
+            .sftp_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("SFTP key not provided"))?
+            .clone(),
+        remote_path: args.sftp_path.clone().unwrap_or_else(|| "/".to_string()),
+        concurrent_connections: args.sftp_connections,
+        buffer_size_mb: args.buffer_size,
+        connection_timeout_sec: 30, // Default timeout
+        max_retries: 3,             // Default retries
+    })
+}
+
+/// Build the environment checks for `check` and `--preflight` from the arguments.
+///
+/// Upload targets are only checked when uploading, and the S3 probe uses the
+/// prefix the upload would use.
+fn preflight_options(args: &Args, artifacts: &[Artifact]) -> Result<preflight::PreflightOptions> {
+    let uploading = !args.skip_upload;
+
+    let s3 = match (&args.bucket, uploading) {
+        (Some(bucket), true) => {
+            let prefix = match &args.prefix {
+                Some(prefix) => prefix.clone(),
+                None => format!(
+                    "triage-{}-{}",
+                    chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+                    get_hostname()?
+                ),
+            };
+            Some(preflight::S3Target {
+                bucket: bucket.clone(),
+                prefix,
+                region: args.region.clone(),
+                profile: args.profile.clone(),
+            })
+        }
+        _ => None,
+    };
+
+    let sftp_configured =
+        args.sftp_host.is_some() && args.sftp_user.is_some() && args.sftp_key.is_some();
+    let sftp = if sftp_configured && uploading {
+        Some(sftp_config_from_args(args)?)
+    } else {
+        None
+    };
+
+    Ok(preflight::PreflightOptions {
+        output_dir: output_dir_from_args(args),
+        require_elevation: !args.force,
+        s3,
+        sftp,
+        write_probe: !args.no_write_probe,
+        memory_required: args.dump_process_memory,
+        artifacts: artifacts.to_vec(),
+    })
+}
+
 /// Check and enable privileges
 fn check_and_enable_privileges(args: &Args) -> Result<()> {
     // Check if we have sufficient privileges
@@ -288,7 +1092,7 @@ fn check_and_enable_privileges(args: &Args) -> Result<()> {
 
         if !args.force {
             return Err(anyhow!(
-                "Elevated privileges required. {} or use --force to continue anyway",
+                "Elevated privileges required. {}, pass --auto-elevate, or use --force to continue anyway",
                 privileges::get_elevation_instructions()
             ));
         }
@@ -302,57 +1106,276 @@ fn check_and_enable_privileges(args: &Args) -> Result<()> {
     Ok(())
 }
 
-/// Setup collection directories and return hostname, timestamp, and artifact directory
-fn setup_collection_directories(args: &Args) -> Result<(String, String, PathBuf)> {
-    let hostname = hostname::get()
-        .map_err(|e| anyhow!("Failed to get hostname: {}", e))?
-        .to_string_lossy()
-        .to_string();
+/// Verify the audit trail's HMAC chain and log the outcome
+fn log_audit_verification() {
+    match audit_log::verify_trail() {
+        Ok(Some(result)) if result.valid => {
+            info!("Audit trail verified: {} entries", result.entries)
+        }
+        Ok(Some(result)) => warn!(
+            "Audit trail verification failed at entry {}: {}",
+            result.first_invalid_seq.unwrap_or_default(),
+            result.reason.unwrap_or_default()
+        ),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to verify audit trail: {:#}", e),
+    }
+}
+
+/// Snapshot the filesystems holding `artifacts` for `--use-snapshots`
+#[cfg(target_os = "linux")]
+fn start_snapshot_session(
+    args: &Args,
+    artifacts: &[Artifact],
+) -> Option<collectors::linux::snapshots::SnapshotSession> {
+    use collectors::linux::snapshots::{SnapshotOptions, SnapshotSession};
+
+    let mut options = SnapshotOptions {
+        exclude: args.no_snapshot.clone(),
+        ..Default::default()
+    };
+    if let Some(size) = &args.snapshot_size {
+        options.lvm_size = size.clone();
+    }
+    let source_paths: Vec<String> = artifacts.iter().map(|a| a.source_path.clone()).collect();
+    SnapshotSession::start(&source_paths, &options)
+        .map_err(|e| warn!("Snapshots unavailable, collecting live: {:#}", e))
+        .ok()
+}
+
+/// Snapshot the filesystems holding `artifacts` for `--use-snapshots`
+#[cfg(not(target_os = "linux"))]
+fn start_snapshot_session(_args: &Args, _artifacts: &[Artifact]) -> Option<()> {
+    warn!("--use-snapshots is only supported on Linux, collecting live");
+    None
+}
+
+/// Setup collection directories and return hostname, timestamp, and artifact directory.
+///
+/// With `--atomic-output` the artifact directory is a staging directory,
+/// returned with the [`StagedOutput`] that finalizes it.
+fn setup_collection_directories(
+    args: &Args,
+) -> Result<(String, String, PathBuf, Option<StagedOutput>)> {
+    let hostname = get_hostname()?;
 
     let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
 
-    let output_dir = match &args.output {
-        Some(path) => PathBuf::from(path),
-        None => {
-            let temp_dir = env::temp_dir();
-            temp_dir.join("dfir-triage")
+    let output_dir = output_dir_from_args(args);
+    footprint::create_dir_all_tracked(&output_dir).context("Failed to create output directory")?;
+    warn_if_fat_output(&output_dir);
+    // Refuse before collecting rather than quietly writing a full archive
+    check_previous_archive(args, &output_dir)?;
+
+    if args.atomic_output {
+        if let Err(e) = atomic_output::quarantine_interrupted(&output_dir, &hostname) {
+            warn!("Failed to check for interrupted collections: {:#}", e);
         }
-    };
+        let staged = StagedOutput::create(&output_dir, &hostname, &timestamp)?;
+        footprint::record_created_path(staged.dir());
+        let artifact_dir = staged.dir().to_path_buf();
+        return Ok((hostname, timestamp, artifact_dir, Some(staged)));
+    }
 
     let artifact_dir = output_dir.join(&hostname);
-    fs::create_dir_all(&artifact_dir).context("Failed to create output directory")?;
+    footprint::create_dir_all_tracked(&artifact_dir)
+        .context("Failed to create output directory")?;
 
     info!("Output directory created at {}", artifact_dir.display());
 
-    Ok((hostname, timestamp, artifact_dir))
+    Ok((hostname, timestamp, artifact_dir, None))
+}
+
+/// Warn when the output is on a FAT volume, where files of 4 GiB or more
+/// (large memory dumps, disk images) cannot be written
+fn warn_if_fat_output(output_dir: &Path) {
+    match atomic_output::filesystem_type(output_dir) {
+        Some(fs_type) if atomic_output::is_fat_filesystem(&fs_type) => warn!(
+            "Output {} is on a {} volume: files of 4 GiB or more cannot be written there, \
+             and the archive will be split into {} MB parts",
+            output_dir.display(),
+            fs_type,
+            atomic_output::FAT_ARCHIVE_PART_SIZE / (1024 * 1024)
+        ),
+        Some(fs_type) => info!("Output volume filesystem: {}", fs_type),
+        None => {}
+    }
+}
+
+/// Part size to split the archive into when it or the output is on a FAT
+/// volume, `None` to write a single archive
+fn archive_part_size(output_dir: &Path) -> Option<u64> {
+    [output_dir.to_path_buf(), env::temp_dir()]
+        .iter()
+        .filter_map(|dir| atomic_output::filesystem_type(dir))
+        .any(|fs_type| atomic_output::is_fat_filesystem(&fs_type))
+        .then_some(atomic_output::FAT_ARCHIVE_PART_SIZE)
+}
+
+/// Fail if `--previous-archive` is given but the archive would be split for
+/// a FAT volume, as split archives are always written in full
+fn check_previous_archive(args: &Args, output_dir: &Path) -> Result<()> {
+    if args.previous_archive.is_some() && archive_part_size(output_dir).is_some() {
+        bail!(
+            "--previous-archive is not supported when the archive is split into parts for a FAT \
+             volume; write the output and temporary directory to a non-FAT volume, or drop \
+             --previous-archive"
+        );
+    }
+    Ok(())
+}
+
+/// Local machine hostname
+fn get_hostname() -> Result<String> {
+    Ok(hostname::get()
+        .map_err(|e| anyhow!("Failed to get hostname: {}", e))?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Output directory from `--output`, defaulting to `dfir-triage` in the temp directory
+fn output_dir_from_args(args: &Args) -> PathBuf {
+    match &args.output {
+        Some(path) => PathBuf::from(path),
+        None => env::temp_dir().join("dfir-triage"),
+    }
+}
+
+/// Read `global_options.volatile_format`, falling back to JSON on bad values
+fn volatile_format_from_config(
+    config: &CollectionConfig,
+) -> collectors::volatile::models::VolatileOutputFormat {
+    match config.global_options.get("volatile_format") {
+        Some(value) => value.parse().unwrap_or_else(|e| {
+            warn!("{}; using json", e);
+            collectors::volatile::models::VolatileOutputFormat::default()
+        }),
+        None => collectors::volatile::models::VolatileOutputFormat::default(),
+    }
 }
 
 /// Collect volatile data if not disabled
 fn collect_volatile_data(
     artifact_dir: &PathBuf,
+    config: &CollectionConfig,
     args: &Args,
+    time_context: &HostTimeContext,
 ) -> Result<Option<collectors::volatile::models::VolatileDataSummary>> {
     if args.no_volatile_data {
         info!("Volatile data collection disabled, skipping...");
         return Ok(None);
     }
 
-    info!("Starting volatile data collection...");
+    info!("Starting volatile data collection...");
+
+    let volatile_dir = artifact_dir.join("volatile");
+    let mut collector = collectors::volatile::VolatileDataCollector::new()
+        .with_format(volatile_format_from_config(config))
+        .with_time_context(time_context.clone())
+        .with_binary_hashing(args.hash_process_binaries);
+
+    match collector.collect_all(&volatile_dir) {
+        Ok(summary) => {
+            info!("Volatile data collection completed successfully");
+            Ok(Some(summary))
+        }
+        Err(e) => {
+            warn!("Volatile data collection failed: {}", e);
+            warn!("Continuing with regular artifact collection");
+            Ok(None)
+        }
+    }
+}
+
+/// Run the collection phases in RFC 3227 order of volatility.
+///
+/// Phase 1 captures the volatile snapshot using native APIs only and holds it
+/// in memory (up to `--volatility-buffer-mb`) until the phase completes.
+/// Sections that need external commands are deferred until after the file
+/// artifacts have been copied.
+#[allow(clippy::too_many_arguments)]
+fn collect_in_volatility_order(
+    artifact_dir: &PathBuf,
+    artifacts_to_collect: &[Artifact],
+    config: &CollectionConfig,
+    hostname: &str,
+    args: &Args,
+    time_context: &HostTimeContext,
+    timeline: &PhaseTimeline,
+    nsrl_db: Option<&NsrlDatabase>,
+    archive_stream: Option<&ArchiveStream>,
+) -> Result<(
+    Option<collectors::volatile::models::VolatileDataSummary>,
+    Option<collectors::memory::models::MemoryCollectionSummary>,
+    CollectedArtifacts,
+)> {
+    info!("Collecting in order of volatility");
+    let volatile_dir = artifact_dir.join("volatile");
+
+    // Phase 1: volatile snapshot, buffered in memory
+    let (mut volatile_collector, mut volatile_data_summary) =
+        timeline.run(CollectionPhase::VolatileSnapshot, || {
+            if args.no_volatile_data {
+                info!("Volatile data collection disabled, skipping...");
+                return Ok((None, None));
+            }
+
+            let mut collector = collectors::volatile::VolatileDataCollector::new()
+                .with_format(volatile_format_from_config(config))
+                .with_time_context(time_context.clone())
+                .with_binary_hashing(args.hash_process_binaries)
+                .with_write_buffer(args.volatility_buffer_mb * 1024 * 1024)
+                .defer_external_commands(true);
+
+            let summary = match collector.collect_all(&volatile_dir) {
+                Ok(summary) => Some(summary),
+                Err(e) => {
+                    warn!("Volatile data collection failed: {}", e);
+                    None
+                }
+            };
+            collector.flush()?;
+            Ok((Some(collector), summary))
+        })?;
+
+    // Phase 2: process memory for the selected processes
+    let memory_collection_summary = timeline.run(CollectionPhase::ProcessMemory, || {
+        handle_memory_operations(artifact_dir, args, &volatile_data_summary)
+    })?;
+
+    // Phase 3: file artifacts, followed by deferred volatile sections
+    let collected = timeline.run(CollectionPhase::FileArtifacts, || {
+        let collected = collect_artifacts(
+            artifact_dir,
+            artifacts_to_collect,
+            config,
+            nsrl_db,
+            archive_stream,
+            args.fuzzy_hash,
+            args.verify_sample
+                .map(|percent| SampleOptions::new(percent, args.verify_seed)),
+        )?;
+
+        if let (Some(collector), Some(summary)) =
+            (volatile_collector.as_mut(), volatile_data_summary.as_mut())
+        {
+            match collector.collect_deferred(&volatile_dir) {
+                Ok(files) => summary.output_files.extend(files),
+                Err(e) => warn!("Deferred volatile collection failed: {}", e),
+            }
+            collector.flush()?;
+        }
+
+        Ok(collected)
+    })?;
 
-    let volatile_dir = artifact_dir.join("volatile");
-    let mut collector = collectors::volatile::VolatileDataCollector::new();
+    // Phase 4: bodyfile
+    timeline.run(CollectionPhase::Bodyfile, || {
+        generate_bodyfile_if_requested(artifact_dir, config, hostname);
+        Ok(())
+    })?;
 
-    match collector.collect_all(&volatile_dir) {
-        Ok(summary) => {
-            info!("Volatile data collection completed successfully");
-            Ok(Some(summary))
-        }
-        Err(e) => {
-            warn!("Volatile data collection failed: {}", e);
-            warn!("Continuing with regular artifact collection");
-            Ok(None)
-        }
-    }
+    Ok((volatile_data_summary, memory_collection_summary, collected))
 }
 
 /// Handle memory operations (collection, search, YARA scanning)
@@ -361,37 +1384,74 @@ fn handle_memory_operations(
     args: &Args,
     volatile_data_summary: &Option<collectors::volatile::models::VolatileDataSummary>,
 ) -> Result<Option<collectors::memory::models::MemoryCollectionSummary>> {
-    if !args.dump_process_memory
-        && args.memory_search.is_none()
-        && args.memory_yara.is_none()
-        && args.dump_memory_region.is_none()
-    {
+    let reads_memory = args.dump_process_memory
+        || args.memory_search.is_some()
+        || args.memory_yara.is_some()
+        || args.dump_memory_region.is_some()
+        || args.detect_injected_code;
+    if !reads_memory && !args.dump_lsass && !args.process_context_only {
+        return Ok(None);
+    }
+
+    if !deadline::allows_phase("process_memory") {
         return Ok(None);
     }
 
     info!("Starting process memory operations...");
 
+    // LSASS is dumped only on explicit request; clap requires --force with it
+    if args.dump_lsass {
+        dump_lsass_if_available(artifact_dir);
+    }
+
+    // Process context comes from /proc and needs no memory reader
+    if args.process_context_only {
+        write_process_context(artifact_dir, args, volatile_data_summary);
+    }
+    if !reads_memory {
+        return Ok(None);
+    }
+
     // Check if memory collection is available
     if !collectors::memory::is_memory_collection_available() {
         warn!("Memory collection is not available on this system");
         return Ok(None);
     }
 
+    // Dump a specific memory region if requested; needs no process list
+    if let Some(spec) = &args.dump_memory_region {
+        if let Err(e) = dump_requested_memory_region(artifact_dir, spec, args.max_memory_size) {
+            warn!("Memory region dump failed: {}", e);
+        }
+    }
+
     // Use volatile data if available
     if volatile_data_summary.is_none() {
         warn!("Process memory operations require volatile data collection. Run without --no-volatile-data flag.");
         return Ok(None);
     }
 
-    // Read the processes from the file
-    let processes_path = artifact_dir.join("volatile").join("processes.json");
-    let processes_json =
-        fs::read_to_string(&processes_path).context("Failed to read processes file")?;
-    let processes: Vec<collectors::volatile::models::ProcessInfo> =
-        serde_json::from_str(&processes_json).context("Failed to parse processes JSON")?;
+    let volatile_dir = artifact_dir.join("volatile");
+    let processes = read_collected_processes(artifact_dir)?;
 
     let mut memory_summary = None;
 
+    // Flag executable regions with no file behind them before any dumping
+    if args.detect_injected_code {
+        use collectors::volatile::hollowing_detector;
+
+        match collectors::memory::platforms::get_memory_collector()
+            .and_then(|collector| {
+                hollowing_detector::detect_suspicious_regions(collector.as_ref(), &processes)
+            })
+            .and_then(|regions| {
+                hollowing_detector::write_suspicious_regions(&regions, &volatile_dir)
+            }) {
+            Ok(path) => info!("Wrote {}", path.display()),
+            Err(e) => warn!("Injected code detection failed: {}", e),
+        }
+    }
+
     // Handle memory collection if requested
     if args.dump_process_memory {
         match collectors::memory::collect_process_memory(
@@ -414,6 +1474,21 @@ fn handle_memory_operations(
         }
     }
 
+    // Write minidumps alongside the raw region dumps if requested
+    if let (true, Some(dump_type)) = (args.dump_process_memory, args.minidump_type) {
+        match collectors::memory::write_process_minidumps(
+            &processes,
+            artifact_dir,
+            args.process.as_deref(),
+            args.pid.as_deref(),
+            args.include_system_processes,
+            dump_type,
+        ) {
+            Ok(paths) => info!("Wrote {} process minidumps", paths.len()),
+            Err(e) => warn!("Minidump export failed: {}", e),
+        }
+    }
+
     // Handle memory search if requested
     if let Some(_pattern) = &args.memory_search {
         warn!("Memory pattern search is not yet implemented in the refactored code");
@@ -424,23 +1499,163 @@ fn handle_memory_operations(
         warn!("YARA memory scanning is not yet implemented in the refactored code");
     }
 
-    // Handle specific memory region dump if requested
-    if let Some(_spec) = &args.dump_memory_region {
-        warn!("Memory region dump is not yet implemented in the refactored code");
+    Ok(memory_summary)
+}
+
+/// Read the processes from the volatile data, falling back to the
+/// consolidated report when only CSV section files were requested
+fn read_collected_processes(
+    artifact_dir: &Path,
+) -> Result<Vec<collectors::volatile::models::ProcessInfo>> {
+    let volatile_dir = artifact_dir.join("volatile");
+    let processes_path = volatile_dir.join("processes.json");
+    if processes_path.exists() {
+        let processes_json =
+            fs::read_to_string(&processes_path).context("Failed to read processes file")?;
+        return serde_json::from_str(&processes_json).context("Failed to parse processes JSON");
     }
+    let report_json =
+        fs::read_to_string(volatile_dir.join(collectors::volatile::VOLATILE_REPORT_FILE))
+            .context("Failed to read volatile report")?;
+    let report: collectors::volatile::models::VolatileData =
+        serde_json::from_str(&report_json).context("Failed to parse volatile report")?;
+    Ok(report.processes)
+}
 
-    Ok(memory_summary)
+/// Write `context.json` for each selected process for `--process-context-only`
+fn write_process_context(
+    artifact_dir: &Path,
+    args: &Args,
+    volatile_data_summary: &Option<collectors::volatile::models::VolatileDataSummary>,
+) {
+    if volatile_data_summary.is_none() {
+        warn!("--process-context-only requires volatile data collection. Run without --no-volatile-data flag.");
+        return;
+    }
+    let result = read_collected_processes(artifact_dir).and_then(|processes| {
+        collectors::memory::collect_process_context(
+            &processes,
+            artifact_dir,
+            args.process.as_deref(),
+            args.pid.as_deref(),
+            args.include_system_processes,
+        )
+    });
+    match result {
+        Ok(paths) => info!("Wrote context of {} processes", paths.len()),
+        Err(e) => warn!("Process context collection failed: {}", e),
+    }
+}
+
+/// Dump LSASS into `process_memory/`, with its metadata in a JSON file next
+/// to the dump
+#[cfg(feature = "memory_collection")]
+fn dump_lsass_if_available(artifact_dir: &Path) {
+    use collectors::windows::lsass_memory;
+
+    let memory_dir = artifact_dir.join("process_memory");
+    let result = footprint::create_dir_all_tracked(&memory_dir)
+        .context(format!("Failed to create {}", memory_dir.display()))
+        .and_then(|_| lsass_memory::dump_lsass(&memory_dir))
+        .and_then(|metadata| {
+            let metadata_path = memory_dir.join("lsass.json");
+            fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)
+                .context(format!("Failed to write {}", metadata_path.display()))
+        });
+    if let Err(e) = result {
+        warn!("LSASS dump failed: {:#}", e);
+    }
+}
+
+#[cfg(not(feature = "memory_collection"))]
+fn dump_lsass_if_available(_artifact_dir: &Path) {
+    warn!("--dump-lsass needs a collector built with the memory_collection feature; LSASS was not dumped");
 }
 
-/// Collect configured artifacts
+/// Dump the `--dump-memory-region` range into `process_memory/`, with its
+/// metadata in a JSON file next to the dump
+fn dump_requested_memory_region(
+    artifact_dir: &Path,
+    spec: &str,
+    max_memory_size_mb: usize,
+) -> Result<()> {
+    use collectors::memory::region_dump;
+
+    let (pid, start, end) = region_dump::parse_region_spec(spec)?;
+    let max_size = (max_memory_size_mb as u64) * 1024 * 1024;
+    if end - start > max_size {
+        return Err(anyhow!(
+            "Memory region {:#x}-{:#x} is larger than --max-memory-size ({} MB)",
+            start,
+            end,
+            max_memory_size_mb
+        ));
+    }
+
+    let memory_dir = artifact_dir.join("process_memory");
+    footprint::create_dir_all_tracked(&memory_dir)
+        .context(format!("Failed to create {}", memory_dir.display()))?;
+    let dump_path = memory_dir.join(format!("region_{}_{:x}_{:x}.dmp", pid, start, end));
+
+    let collector = collectors::memory::platforms::get_memory_collector()?;
+    let metadata =
+        region_dump::dump_memory_region(collector.as_ref(), pid, start, end, &dump_path)?;
+
+    let metadata_path = dump_path.with_extension("json");
+    fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)
+        .context(format!("Failed to write {}", metadata_path.display()))?;
+
+    info!(
+        "Dumped {} of {} bytes from process {} to {}",
+        metadata.memory_region.as_ref().map_or(0, |r| r.dump_size),
+        metadata.file_size,
+        pid,
+        dump_path.display()
+    );
+    Ok(())
+}
+
+/// File artifacts collected in a run
+struct CollectedArtifacts {
+    metadata: Vec<(String, ArtifactMetadata)>,
+    failures: Vec<ArtifactFailure>,
+    /// Counts keyed by artifact type, for the summary
+    rollups: BTreeMap<String, ArtifactTypeRollup>,
+    /// Sampled re-verification of the copies (`--verify-sample`)
+    verification: Option<VerificationReport>,
+}
+
+/// Collect configured artifacts, returning their metadata and any failures.
+///
+/// With an NSRL database, each collected file is flagged as known-good or
+/// for review. With an archive stream, each file is handed to the upload once
+/// its artifact is done, and files below a collected directory as soon as
+/// they are copied; if the stream stops, the remaining files are kept
+/// locally. With `fuzzy_hash`, collected executables get an SSDEEP hash.
+/// With `--collect-xattrs`, files copied from the live system get a sidecar
+/// with the source's extended attributes. With `--compute-entropy`, each
+/// collected file gets its entropy and the high-entropy files are reported.
+/// With `verify_sample`, a sample of the copies is checked against the
+/// sources once every artifact is collected.
 fn collect_artifacts(
     artifact_dir: &PathBuf,
     artifacts_to_collect: &[Artifact],
-    _config: &CollectionConfig,
-) -> Result<Vec<(String, ArtifactMetadata)>> {
+    config: &CollectionConfig,
+    nsrl_db: Option<&NsrlDatabase>,
+    archive_stream: Option<&ArchiveStream>,
+    fuzzy_hash: bool,
+    verify_sample: Option<SampleOptions>,
+) -> Result<CollectedArtifacts> {
     info!("Starting artifact collection...");
 
+    let options = collector::CollectionOptions::from_config(config);
+    let identify_types = file_type::identify_types_enabled(&config.global_options);
+    let collect_xattrs = extended_attributes::collect_xattrs_enabled();
+    let compute_entropy = entropy_analysis::compute_entropy_enabled();
     let mut all_metadata: Vec<(String, ArtifactMetadata)> = Vec::new();
+    let mut failures: Vec<ArtifactFailure> = Vec::new();
+    let mut rollups: BTreeMap<String, ArtifactTypeRollup> = BTreeMap::new();
+    let mut sample_candidates: Vec<SampleCandidate> = Vec::new();
     let required_artifacts: Vec<&Artifact> =
         artifacts_to_collect.iter().filter(|a| a.required).collect();
 
@@ -450,7 +1665,23 @@ fn collect_artifacts(
         required_artifacts.len()
     );
 
+    let deadline = deadline::current();
+    let mut archive_stream = archive_stream;
     for artifact in artifacts_to_collect {
+        // With --max-runtime, each artifact gets what is left above the
+        // compression and upload reserve, and none once that is used up
+        let mut options = options.clone();
+        if let Some(deadline) = &deadline {
+            match deadline.artifact_budget() {
+                Some(budget) => options.cutoff = Some(Instant::now() + budget),
+                None => {
+                    info!("Skipping {}: --max-runtime reached", artifact.name);
+                    deadline.skip_artifact(&artifact.name);
+                    continue;
+                }
+            }
+        }
+
         let artifact_type_str = format!("{}", artifact.artifact_type);
         let type_dir = artifact_dir.join(&artifact_type_str);
 
@@ -458,13 +1689,107 @@ fn collect_artifacts(
             fs::create_dir_all(&type_dir).context("Failed to create artifact type directory")?;
         }
 
-        let metadata = collector::collect_artifacts(&[artifact.clone()], &type_dir)?;
+        let started = Instant::now();
+        let outcome = collector::collect_artifacts_with_options(
+            std::slice::from_ref(artifact),
+            &type_dir,
+            &options,
+        )?;
+        if let Some(deadline) = &deadline {
+            let bytes = outcome.collected.values().map(|m| m.file_size).sum();
+            deadline.record_copy(bytes, started.elapsed());
+            for name in &outcome.deadline_aborted {
+                deadline.abort_artifact(name);
+            }
+        }
 
-        all_metadata.extend(metadata.into_iter());
+        let rollup = rollups.entry(artifact_type_str).or_default();
+        for (path, mut metadata) in outcome.collected {
+            let collected_path = type_dir.join(&path);
+            if let Some(db) = nsrl_db {
+                db.check_collected_file(&collected_path, &mut metadata);
+            }
+            if identify_types {
+                file_type::identify_collected_file(&collected_path, &mut metadata);
+            }
+            if fuzzy_hash {
+                fuzzy_hash_collected_file(&collected_path, &mut metadata);
+            }
+            if compute_entropy {
+                entropy_analysis::analyze_collected_file(&collected_path, &mut metadata);
+            }
+            if collect_xattrs {
+                write_xattr_sidecar(&metadata.original_path, &collected_path);
+            }
+            if verify_sample.is_some() {
+                sample_candidates.extend(SampleCandidate::new(
+                    &artifact.name,
+                    artifact.required,
+                    collected_path.clone(),
+                    &metadata,
+                ));
+            }
+            if let Some(stream) = archive_stream.filter(|_| collected_path.is_file()) {
+                if let Err(e) = stream.send_file(&collected_path) {
+                    warn!("Streaming upload stopped: {}", e);
+                    warn!("Keeping the remaining collected files locally");
+                    archive_stream = None;
+                }
+            }
+            rollup.add_file(&metadata);
+            all_metadata.push((path, metadata));
+        }
+        rollup.failures += outcome.failures.len();
+        rollup.skipped += outcome.skipped.len();
+        failures.extend(outcome.failures);
     }
 
     info!("Successfully collected {} artifacts", all_metadata.len());
-    Ok(all_metadata)
+    if compute_entropy {
+        let threshold = entropy_analysis::entropy_threshold(&config.global_options);
+        let report = HighEntropyReport::from_metadata(&all_metadata, threshold);
+        if !report.high_entropy_files.is_empty() {
+            warn!(
+                "{} of {} collected files have entropy above {}",
+                report.high_entropy_files.len(),
+                report.files_analyzed,
+                threshold
+            );
+        }
+        if let Err(e) = report.write(artifact_dir) {
+            warn!("Failed to write entropy report: {:#}", e);
+        }
+    }
+    let timeouts = failures
+        .iter()
+        .filter(|f| f.reason == FailureReason::Timeout)
+        .count();
+    if timeouts > 0 {
+        warn!("{} artifacts timed out", timeouts);
+    }
+
+    // Re-read a sample while the sources are still close to what was copied
+    let verification = verify_sample.map(|options| {
+        let report = verification::verify_sample(artifact_dir, &sample_candidates, options);
+        if let Err(e) = verification::write_report(artifact_dir, &report) {
+            warn!("Failed to write verification report: {:#}", e);
+        }
+        if report.mismatched > 0 {
+            warn!(
+                "{} of {} sampled files did not match their unchanged source; {} re-collected",
+                report.mismatched, report.sampled_files, report.recollected
+            );
+        }
+        failures.extend(report.failures());
+        report
+    });
+
+    Ok(CollectedArtifacts {
+        metadata: all_metadata,
+        failures,
+        rollups,
+        verification,
+    })
 }
 
 /// Generate bodyfile if requested
@@ -480,7 +1805,7 @@ fn generate_bodyfile_if_requested(
         .map(|v| v == "true")
         .unwrap_or(true);
 
-    if generate_bodyfile {
+    if generate_bodyfile && deadline::allows_phase("bodyfile") {
         #[cfg(not(target_os = "windows"))]
         {
             let bodyfile_path = artifact_dir
@@ -490,33 +1815,154 @@ fn generate_bodyfile_if_requested(
 
             info!("Generating bodyfile at {}", bodyfile_path.display());
 
-            if let Err(e) =
-                utils::bodyfile::generate_bodyfile(&bodyfile_path, &config.global_options)
-            {
-                warn!("Failed to generate bodyfile: {}", e);
-            } else {
-                info!("Bodyfile generation completed successfully");
+            // A walk that reaches the --max-runtime cutoff is abandoned
+            let deadline = deadline::current();
+            let past_cutoff = || deadline.as_ref().is_some_and(|d| d.past_cutoff());
+            match utils::bodyfile::generate_bodyfile_until(
+                &bodyfile_path,
+                &config.global_options,
+                Some(&past_cutoff),
+            ) {
+                Ok(true) => {
+                    footprint::record_created_path(&bodyfile_path);
+                    info!("Bodyfile generation completed successfully");
+                }
+                Ok(false) => {
+                    let _ = fs::remove_file(&bodyfile_path);
+                    if let Some(deadline) = &deadline {
+                        deadline.skip_phase("bodyfile");
+                    }
+                }
+                Err(e) => warn!("Failed to generate bodyfile: {}", e),
             }
         }
     }
 }
 
+/// Run-wide inputs to the collection summary, fixed once collection finishes
+struct SummaryContext<'a> {
+    hostname: &'a str,
+    timestamp: &'a str,
+    format: SummaryFormat,
+    config_sha256: String,
+    host: HostInfo,
+    uploads: Vec<UploadDestination>,
+    time_context: &'a HostTimeContext,
+    phase_timeline: &'a PhaseTimeline,
+    volatility_order: bool,
+    nsrl_stats: Option<NsrlStats>,
+    file_type_stats: Option<FileTypeStats>,
+    volatile_data_summary: Option<&'a collectors::volatile::models::VolatileDataSummary>,
+    memory_collection_summary: Option<&'a collectors::memory::models::MemoryCollectionSummary>,
+    resource_limits: Option<AppliedLimits>,
+    dynamic_artifacts: Option<DynamicArtifacts>,
+}
+
 /// Write collection summary
 fn write_collection_summary(
     artifact_dir: &PathBuf,
-    hostname: &str,
-    timestamp: &str,
-    all_metadata: &[(String, ArtifactMetadata)],
-    volatile_data_summary: &Option<collectors::volatile::models::VolatileDataSummary>,
-    memory_collection_summary: &Option<collectors::memory::models::MemoryCollectionSummary>,
+    context: &SummaryContext,
+    collected: &CollectedArtifacts,
 ) -> Result<PathBuf> {
-    let summary_json = summary::create_collection_summary(
-        hostname,
-        timestamp,
-        all_metadata,
-        volatile_data_summary.as_ref(),
-        memory_collection_summary.as_ref(),
-    )?;
+    let mut collection_summary = CollectionSummary::new(
+        context.hostname,
+        context.timestamp,
+        &collected.metadata,
+        context.volatile_data_summary,
+        context.memory_collection_summary,
+    )
+    .with_host(context.host.clone())
+    .with_config_hash(context.config_sha256.clone())
+    .with_artifact_types(collected.rollups.clone())
+    .with_phase_timings(context.phase_timeline.phase_timings())
+    .with_uploads(context.uploads.clone())
+    .with_section(
+        "time_context",
+        serde_json::to_value(context.time_context).context("Failed to serialize time context")?,
+    );
+
+    if !collected.failures.is_empty() {
+        collection_summary = collection_summary.with_section(
+            "artifact_failures",
+            summary::artifact_failures_section(&collected.failures),
+        );
+    }
+
+    if let Some(report) = collectors::read_only::report() {
+        collection_summary = collection_summary.with_section(
+            "no_writes",
+            serde_json::to_value(report).context("Failed to serialize no-writes report")?,
+        );
+    }
+
+    if let Some(deadline) = deadline::current() {
+        collection_summary = collection_summary.with_section(
+            "deadline",
+            serde_json::to_value(deadline.report())
+                .context("Failed to serialize deadline report")?,
+        );
+    }
+
+    if credential_scrubber::volatile_scrubbing_enabled() {
+        collection_summary = collection_summary.with_section(
+            "credential_scrubbing",
+            summary::credential_scrubbing_section(credential_scrubber::redaction_count()),
+        );
+    }
+
+    if let Some(report) = &collected.verification {
+        collection_summary =
+            collection_summary.with_section("verification", report.summary_section());
+    }
+
+    if let Some(stats) = &context.nsrl_stats {
+        collection_summary = collection_summary.with_section(
+            "nsrl",
+            serde_json::to_value(stats).context("Failed to serialize NSRL results")?,
+        );
+    }
+
+    if let Some(stats) = &context.file_type_stats {
+        collection_summary = collection_summary.with_section(
+            "file_types",
+            serde_json::to_value(stats).context("Failed to serialize file type results")?,
+        );
+    }
+
+    if let Some(dynamic) = &context.dynamic_artifacts {
+        collection_summary = collection_summary.with_section(
+            "dynamic_artifacts",
+            serde_json::to_value(dynamic).context("Failed to serialize dynamic artifacts")?,
+        );
+    }
+
+    if let Some(limits) = &context.resource_limits {
+        collection_summary = collection_summary.with_section(
+            "resource_limits",
+            serde_json::to_value(limits).context("Failed to serialize resource limits")?,
+        );
+    }
+
+    if context.volatility_order {
+        collection_summary = collection_summary.with_section(
+            "collection_phases",
+            context.phase_timeline.to_summary_json(),
+        );
+    }
+
+    // Every collection can be the next one's --baseline
+    let manifest = incremental::manifest(
+        &collection_summary.collection_id,
+        context.hostname,
+        &collection_summary.collection_time,
+        &collected.metadata,
+    );
+    manifest.write(artifact_dir)?;
+    if let Some(section) = manifest.summary_section() {
+        collection_summary = collection_summary.with_section("incremental", section);
+    }
+
+    let summary_json = collection_summary.to_json(context.format)?;
     let summary_path = artifact_dir.join("collection_summary.json");
 
     fs::write(&summary_path, &summary_json).context("Failed to write collection summary")?;
@@ -526,15 +1972,83 @@ fn write_collection_summary(
     Ok(summary_path)
 }
 
+/// Destinations the archive and summary are uploaded to.
+///
+/// Recorded in the summary before the upload runs, so keys are those of the
+/// selected upload method rather than of any fallback. An archive split for
+/// a FAT volume is recorded as `<archive>.*`, since the part count is not
+/// known yet.
+fn upload_destinations(args: &Args, hostname: &str, timestamp: &str) -> Vec<UploadDestination> {
+    let mut destinations = Vec::new();
+    if args.skip_upload {
+        return destinations;
+    }
+
+    let archive_name = if args.stream {
+        format!("{}-{}.zip", hostname, timestamp)
+    } else if archive_part_size(&output_dir_from_args(args)).is_some() {
+        format!("{}.*", compress::archive_file_name(hostname, timestamp))
+    } else {
+        compress::archive_file_name(hostname, timestamp)
+    };
+    let sftp_configured =
+        args.sftp_host.is_some() && args.sftp_user.is_some() && args.sftp_key.is_some();
+
+    if let Some(bucket) = &args.bucket {
+        let prefix = args
+            .prefix
+            .clone()
+            .unwrap_or_else(|| format!("triage-{}-{}", timestamp, hostname));
+        for name in [archive_name.as_str(), "collection_summary.json"] {
+            destinations.push(UploadDestination {
+                target: "s3".to_string(),
+                location: bucket.clone(),
+                key: format!("{}/{}", prefix, name),
+            });
+        }
+    }
+
+    // Streaming uploads go to S3 only when both are configured
+    if sftp_configured && !(args.stream && args.bucket.is_some()) {
+        let remote_dir = args
+            .sftp_path
+            .as_deref()
+            .unwrap_or("/")
+            .trim_end_matches('/');
+        let host = args.sftp_host.clone().unwrap_or_default();
+        for name in [archive_name.as_str(), "collection_summary.json"] {
+            destinations.push(UploadDestination {
+                target: "sftp".to_string(),
+                location: host.clone(),
+                key: format!("{}/{}", remote_dir, name),
+            });
+        }
+    }
+
+    destinations
+}
+
 /// Handle artifact upload (streaming or standard)
 fn handle_upload(
     artifact_dir: &PathBuf,
     hostname: &str,
     timestamp: &str,
     args: &Args,
+    archive_stream: Option<ArchiveStream>,
 ) -> Result<()> {
     let summary_path = artifact_dir.join("collection_summary.json");
 
+    if let Some(stream) = archive_stream {
+        return finish_archive_stream(
+            stream,
+            artifact_dir,
+            hostname,
+            timestamp,
+            &summary_path,
+            args,
+        );
+    }
+
     // Check if streaming to cloud storage is enabled
     if !args.skip_upload && args.stream {
         handle_streaming_upload(artifact_dir, hostname, timestamp, &summary_path, args)?;
@@ -610,6 +2124,209 @@ fn handle_streaming_upload(
     Ok(())
 }
 
+/// Start streaming collected files into the upload archive.
+///
+/// Only used with `--stream`. Returns `None` when no streaming destination is
+/// configured or the upload cannot be opened, in which case the artifacts are
+/// uploaded after collection as before.
+fn start_archive_stream(
+    artifact_dir: &Path,
+    hostname: &str,
+    timestamp: &str,
+    args: &Args,
+) -> Option<ArchiveStream> {
+    if args.skip_upload || !args.stream {
+        return None;
+    }
+
+    let buffer_size = args.buffer_size;
+    let result = if let Some(bucket) = args.bucket.clone() {
+        let default_prefix = format!("triage-{}-{}", timestamp, hostname);
+        let prefix = args.prefix.clone().unwrap_or(default_prefix);
+        let key = format!("{}/{}-{}.zip", prefix, hostname, timestamp);
+        let region = args.region.clone();
+        let profile = args.profile.clone();
+
+        ArchiveStream::start(artifact_dir, move || async move {
+            let s3_client = cloud::client::create_s3_client(region.as_deref(), profile.as_deref())?;
+            cloud::streaming::S3UploadStream::new(s3_client, &bucket, &key, buffer_size).await
+        })
+    } else if args.sftp_host.is_some() && args.sftp_user.is_some() && args.sftp_key.is_some() {
+        let sftp_config = match sftp_config_from_args(args) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Failed to start streaming upload: {}", e);
+                return None;
+            }
+        };
+        let remote_path = format!(
+            "{}/{}-{}.zip",
+            sftp_config.remote_path.trim_end_matches('/'),
+            hostname,
+            timestamp
+        );
+
+        ArchiveStream::start(artifact_dir, move || async move {
+            cloud::sftp_streaming::create_sftp_upload_stream(sftp_config, &remote_path, buffer_size)
+                .await
+        })
+    } else {
+        // handle_streaming_upload reports the missing destination
+        return None;
+    };
+
+    match result {
+        Ok(stream) => Some(stream),
+        Err(e) => {
+            warn!("Failed to start streaming upload: {:#}", e);
+            warn!("Artifacts will be uploaded after collection instead");
+            None
+        }
+    }
+}
+
+/// Complete an archive streamed during collection, then upload the summary
+/// on its own.
+///
+/// If the stream failed, the upload has been aborted and whatever is still
+/// in the artifact directory is compressed and uploaded instead.
+fn finish_archive_stream(
+    stream: ArchiveStream,
+    artifact_dir: &PathBuf,
+    hostname: &str,
+    timestamp: &str,
+    summary_path: &PathBuf,
+    args: &Args,
+) -> Result<()> {
+    let target_name = stream.target_name().to_string();
+    match stream.finish(summary_path) {
+        Ok(stats) => {
+            info!(
+                "Successfully streamed {} files ({} bytes) to {}",
+                stats.files, stats.bytes, target_name
+            );
+            let runtime = Runtime::new().context("Failed to create Tokio runtime")?;
+            runtime.block_on(upload_streamed_summary(
+                summary_path,
+                hostname,
+                timestamp,
+                args,
+            ))
+        }
+        Err(e) => {
+            warn!("Streaming upload to {} failed: {:#}", target_name, e);
+            warn!("Falling back to standard upload of the files still on disk");
+            compress_and_upload(artifact_dir, hostname, timestamp, summary_path, args)
+        }
+    }
+}
+
+/// Upload the collection summary next to a streamed archive
+async fn upload_streamed_summary(
+    summary_path: &PathBuf,
+    hostname: &str,
+    timestamp: &str,
+    args: &Args,
+) -> Result<()> {
+    if let Some(bucket) = args.bucket.as_ref() {
+        let s3_client =
+            cloud::client::create_s3_client(args.region.as_deref(), args.profile.as_deref())?;
+        let default_prefix = format!("triage-{}-{}", timestamp, hostname);
+        let prefix = args.prefix.as_deref().unwrap_or(default_prefix.as_str());
+
+        collectors::streaming::stream_file_to_s3(
+            summary_path,
+            s3_client,
+            bucket,
+            &format!("{}/collection_summary.json", prefix),
+            args.buffer_size,
+        )
+        .await
+    } else {
+        let sftp_config = sftp_config_from_args(args)?;
+        let summary_remote_path = format!(
+            "{}/collection_summary.json",
+            sftp_config.remote_path.trim_end_matches('/')
+        );
+
+        collectors::streaming::stream_file_to_sftp(
+            summary_path,
+            sftp_config,
+            &summary_remote_path,
+            args.buffer_size,
+        )
+        .await
+    }
+}
+
+/// Upload files re-collected in watch mode, followed by the refreshed summary
+async fn upload_recollected_files(
+    artifact_dir: &PathBuf,
+    files: &[PathBuf],
+    hostname: &str,
+    timestamp: &str,
+    args: &Args,
+) -> Result<()> {
+    let summary_path = artifact_dir.join("collection_summary.json");
+    let mut uploads: Vec<(PathBuf, String)> = files
+        .iter()
+        .map(|f| {
+            let relative = f
+                .strip_prefix(artifact_dir)
+                .unwrap_or(f)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (f.clone(), format!("watch/{}", relative))
+        })
+        .collect();
+    uploads.push((summary_path, "collection_summary.json".to_string()));
+
+    if let Some(bucket) = args.bucket.as_ref() {
+        let s3_client =
+            cloud::client::create_s3_client(args.region.as_deref(), args.profile.as_deref())?;
+        let default_prefix = format!("triage-{}-{}", timestamp, hostname);
+        let prefix = args.prefix.as_deref().unwrap_or(default_prefix.as_str());
+
+        for (path, name) in uploads {
+            let key = format!("{}/{}", prefix, name);
+            collectors::streaming::stream_file_to_s3(
+                &path,
+                s3_client.clone(),
+                bucket,
+                &key,
+                args.buffer_size,
+            )
+            .await?;
+        }
+        info!("Uploaded re-collected files to S3 bucket {}", bucket);
+    } else if args.sftp_host.is_some() && args.sftp_user.is_some() && args.sftp_key.is_some() {
+        let sftp_config = sftp_config_from_args(args)?;
+        let remote_dir = sftp_config.remote_path.trim_end_matches('/').to_string();
+
+        for (path, name) in uploads {
+            // Remote subdirectories may not exist, so keep SFTP uploads flat
+            let remote_path = format!("{}/{}", remote_dir, name.replace('/', "_"));
+            collectors::streaming::stream_file_to_sftp(
+                &path,
+                sftp_config.clone(),
+                &remote_path,
+                args.buffer_size,
+            )
+            .await?;
+        }
+        info!(
+            "Uploaded re-collected files to SFTP server {}",
+            sftp_config.host
+        );
+    } else {
+        return Err(anyhow!(
+            "Streaming enabled but no valid cloud storage options provided"
+        ));
+    }
+
+    Ok(())
+}
+
 /// Stream artifacts to S3
 async fn stream_to_s3(
     artifact_dir: &PathBuf,
@@ -669,29 +2386,7 @@ async fn stream_to_sftp(
     args: &Args,
 ) -> Result<()> {
     // Create SFTP config
-    let sftp_config = cloud::sftp::SFTPConfig {
-        host: args
-            .sftp_host
-            .as_ref()
-            .ok_or_else(|| anyhow!("SFTP host not provided"))?
-            .clone(),
-        port: args.sftp_port,
-        username: args
-            .sftp_user
-            .as_ref()
-            .ok_or_else(|| anyhow!("SFTP user not provided"))?
-            .clone(),
-        private_key_path: args
-            .sftp_key
-            .as_ref()
-            .ok_or_else(|| anyhow!("SFTP key not provided"))?
-            .clone(),
-        remote_path: args.sftp_path.clone().unwrap_or_else(|| "/".to_string()),
-        concurrent_connections: args.sftp_connections,
-        buffer_size_mb: args.buffer_size,
-        connection_timeout_sec: 30, // Default timeout
-        max_retries: 3,             // Default retries
-    };
+    let sftp_config = sftp_config_from_args(args)?;
 
     // Create remote path for ZIP file
     let remote_path = format!(
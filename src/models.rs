@@ -15,12 +15,37 @@ use serde::{Deserialize, Serialize};
 /// * `accessed_time` - Optional last access timestamp (ISO 8601 format)
 /// * `modified_time` - Optional last modification timestamp (ISO 8601 format)
 /// * `is_locked` - Whether the file was locked/in-use during collection
+/// * `nsrl_known_good` - Whether the file's SHA-256 is in the NSRL database
+///   (`None` unless `--nsrl-db` was given, or the file could not be hashed)
+/// * `allocated_size` - Bytes allocated on disk for the source, when known;
+///   smaller than `file_size` for sparse files
+/// * `memory_region` - Process and address range, for dumps of process memory
+/// * `listed_files` - Files recorded by a metadata-only listing, whose
+///   contents were not copied
+/// * `dump_method` - How a process memory dump was written (`minidump`)
+/// * `detected_type` - File type identified from the content's magic bytes
+///   (only with `identify_types`)
+/// * `binary_info` - Header facts for PE, ELF and Mach-O executables
+/// * `fallback_method` - How a locked file was read when opening it failed
+///   (`vss` for a Volume Shadow Copy)
+/// * `original_path_bytes` - Base64 of the source path's raw bytes (UTF-16LE
+///   on Windows), present only when `original_path` is a lossy conversion
+/// * `ssdeep` - SSDEEP fuzzy hash of a collected executable (only with
+///   `--fuzzy-hash`)
+/// * `encrypted` - Encryption found on the source (`efs`, `fscrypt`,
+///   `ecryptfs`); the copy may be ciphertext
+/// * `container` - Container, image and layers, for files read from a
+///   container filesystem (`container://`)
+/// * `paging_file` - Size cap and hibernation header of a collected
+///   `pagefile.sys`, `swapfile.sys` or `hiberfil.sys`
+/// * `entropy` - Shannon entropy of a collected file's bytes, 0 to 8 bits per
+///   byte (only with `--compute-entropy`)
 ///
 /// # Serialization
 ///
 /// This struct supports JSON and other serde-compatible formats for easy
 /// integration with analysis tools and long-term storage.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ArtifactMetadata {
     pub original_path: String,
     pub collection_time: String,
@@ -29,6 +54,110 @@ pub struct ArtifactMetadata {
     pub accessed_time: Option<String>,
     pub modified_time: Option<String>,
     pub is_locked: bool,
+    /// SHA-256 of the collected copy, when it was hashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nsrl_known_good: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_region: Option<MemoryRegionSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listed_files: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dump_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary_info: Option<BinaryInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_path_bytes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssdeep: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<ContainerProvenance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging_file: Option<PagingFileInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<f64>,
+}
+
+/// Header facts of a collected executable
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BinaryInfo {
+    /// `pe`, `elf` or `macho`
+    pub format: String,
+    /// CPU architecture (`x86`, `x86_64`, `arm`, `arm64`, ...), comma-separated
+    /// for universal Mach-O binaries
+    pub arch: Option<String>,
+    /// PE link timestamp; ELF and Mach-O headers carry none
+    pub compile_time: Option<String>,
+    /// Entry point: relative virtual address for PE, virtual address for
+    /// ELF, file offset (`LC_MAIN`) for Mach-O
+    pub entry_point: Option<u64>,
+    /// Whether the PE security directory or a Mach-O code signature is
+    /// present; not checked for validity
+    pub signed: Option<bool>,
+}
+
+/// Source of a process memory region dump
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegionSource {
+    pub pid: u32,
+    /// First address dumped
+    pub start_address: u64,
+    /// End of the range (exclusive)
+    pub end_address: u64,
+    /// Bytes actually read; unreadable pages are zero-filled in the dump
+    pub dump_size: u64,
+}
+
+/// Container a file was read from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContainerProvenance {
+    /// `docker` or `podman`
+    pub runtime: String,
+    pub container_id: String,
+    pub container_name: Option<String>,
+    /// Image reference the container was created from, e.g. `nginx:1.25`
+    pub image: Option<String>,
+    /// Image ID (`sha256:` digest of the image configuration)
+    pub image_digest: Option<String>,
+    /// Layers holding the path, topmost first: `container` for the
+    /// container's writable layer, else the layer's diff digest, or its
+    /// storage directory when the digest is unknown
+    pub layers: Vec<String>,
+}
+
+/// Collection facts of a page, swap or hibernation file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PagingFileInfo {
+    /// Size of the source; `file_size` is smaller when the copy was capped
+    pub logical_size: u64,
+    /// `max_size_mb` of the artifact, in bytes
+    pub size_cap: Option<u64>,
+    pub truncated: bool,
+    /// Header of `hiberfil.sys`
+    pub hibernation: Option<HibernationInfo>,
+}
+
+/// What the `hiberfil.sys` header says about the image
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HibernationInfo {
+    /// Header signature as found, e.g. `HIBR` or `wake`
+    pub signature: String,
+    /// `hibernated`, `resumed`, `resuming` or `cleared`
+    pub state: String,
+    /// `xpress_lz77` (Windows 7 and earlier) or `xpress_huffman` (Windows 8
+    /// and later); `None` when the header is cleared or unrecognised
+    pub format: Option<String>,
+    /// Tools that can decompress this format into a raw memory image
+    pub tools: Vec<String>,
 }
 
 #[cfg(test)]
@@ -45,6 +174,8 @@ mod tests {
             accessed_time: Some("2024-01-01T00:00:00Z".to_string()),
             modified_time: Some("2024-01-01T00:00:00Z".to_string()),
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         // Test JSON serialization
@@ -70,6 +201,8 @@ mod tests {
             accessed_time: None,
             modified_time: None,
             is_locked: true,
+            sha256: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -91,6 +224,8 @@ mod tests {
             accessed_time: None,
             modified_time: Some("2023-12-15T00:00:00Z".to_string()),
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         let cloned = original.clone();
@@ -113,6 +248,8 @@ mod tests {
             accessed_time: None,
             modified_time: None,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         let debug_str = format!("{:?}", metadata);
@@ -131,6 +268,8 @@ mod tests {
             accessed_time: None,
             modified_time: None,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -148,6 +287,8 @@ mod tests {
             accessed_time: None,
             modified_time: None,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -168,6 +309,8 @@ mod tests {
             accessed_time: Some("2024-01-01T01:00:00Z".to_string()),
             modified_time: Some("2024-01-01T00:30:00Z".to_string()),
             is_locked: true,
+            sha256: None,
+            ..Default::default()
         };
 
         let yaml = serde_yaml::to_string(&metadata).unwrap();
@@ -189,6 +332,8 @@ mod tests {
             accessed_time: None,
             modified_time: None,
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -215,5 +360,6 @@ mod tests {
         assert_eq!(metadata.original_path, "/test");
         assert_eq!(metadata.file_size, 100);
         assert!(!metadata.is_locked);
+        assert_eq!(metadata.nsrl_known_good, None);
     }
 }
@@ -0,0 +1,664 @@
+//! Environment checks run before a collection.
+//!
+//! The `check` subcommand runs these checks on their own, and `--preflight`
+//! runs the same checks before a collection starts. Checks do not change the
+//! system beyond 1-byte probe files, which are removed again; with
+//! `--no-write-probe` the S3 and SFTP checks only confirm access.
+//!
+//! A check marked required fails the run. Optional checks that fail are
+//! reported as warnings.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, error, warn};
+use rusoto_core::ByteStream;
+use rusoto_s3::{DeleteObjectRequest, HeadBucketRequest, PutObjectRequest, S3};
+use serde::Serialize;
+use serde_json::json;
+use sysinfo::{DiskExt, System, SystemExt};
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+use crate::cloud::client::create_s3_client;
+use crate::cloud::sftp::{SFTPClient, SFTPConfig};
+use crate::collectors::memory;
+use crate::config::{Artifact, ArtifactType, LinuxArtifactType, MacOSArtifactType};
+use crate::constants::{PREFLIGHT_MIN_FREE_SPACE, PREFLIGHT_TIMEOUT_SECS};
+use crate::privileges;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skip => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// Result of a single check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub required: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn pass(name: &str, required: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            required,
+            detail: detail.into(),
+        }
+    }
+
+    /// A failed check; only required checks are reported as failures
+    fn failed(name: &str, required: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: if required {
+                CheckStatus::Fail
+            } else {
+                CheckStatus::Warn
+            },
+            required,
+            detail: detail.into(),
+        }
+    }
+
+    fn skip(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Skip,
+            required: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Results of all checks, in the order they ran
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    /// True when no required check failed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    /// Checks that failed the run
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Plain-text table, one row per check
+    pub fn to_table(&self) -> String {
+        let name_width = self
+            .checks
+            .iter()
+            .map(|c| c.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("CHECK".len());
+
+        let mut table = format!(
+            "{:<name_width$}  {:<6}  {:<8}  DETAIL\n",
+            "CHECK", "STATUS", "REQUIRED"
+        );
+        for check in &self.checks {
+            table.push_str(&format!(
+                "{:<name_width$}  {:<6}  {:<8}  {}\n",
+                check.name,
+                check.status.to_string(),
+                if check.required { "yes" } else { "no" },
+                check.detail
+            ));
+        }
+        table.push_str(if self.passed() {
+            "\nAll required checks passed\n"
+        } else {
+            "\nOne or more required checks failed\n"
+        });
+        table
+    }
+
+    /// JSON document with the overall result and every check
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&json!({
+            "passed": self.passed(),
+            "checks": self.checks,
+        }))
+        .context("Failed to serialize preflight report")
+    }
+}
+
+/// S3 destination to probe
+#[derive(Debug, Clone)]
+pub struct S3Target {
+    pub bucket: String,
+    /// Key prefix uploads will use
+    pub prefix: String,
+    pub region: Option<String>,
+    pub profile: Option<String>,
+}
+
+/// What to check, derived from the command line and configuration
+#[derive(Debug, Clone)]
+pub struct PreflightOptions {
+    pub output_dir: PathBuf,
+    /// Whether running without elevation fails the run (no `--force`)
+    pub require_elevation: bool,
+    pub s3: Option<S3Target>,
+    pub sftp: Option<SFTPConfig>,
+    /// Write and delete a probe object/file rather than only checking access
+    pub write_probe: bool,
+    /// Whether process memory collection was requested
+    pub memory_required: bool,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// Run every check that applies to `options`
+pub fn run_checks(options: &PreflightOptions) -> PreflightReport {
+    let mut checks = vec![check_elevation(options.require_elevation)];
+    checks.extend(check_privileges());
+    checks.push(check_output_dir(&options.output_dir));
+    checks.push(check_free_space(&options.output_dir));
+
+    match &options.s3 {
+        Some(target) => checks.push(check_s3(target, options.write_probe)),
+        None => checks.push(CheckResult::skip("s3", "no bucket configured")),
+    }
+    match &options.sftp {
+        Some(config) => checks.push(check_sftp(config, options.write_probe)),
+        None => checks.push(CheckResult::skip("sftp", "no SFTP server configured")),
+    }
+
+    checks.push(check_memory_collection(options.memory_required));
+    checks.extend(check_tools(&options.artifacts));
+
+    PreflightReport { checks }
+}
+
+fn check_elevation(required: bool) -> CheckResult {
+    if privileges::is_elevated() {
+        CheckResult::pass("elevation", required, "running with elevated privileges")
+    } else {
+        CheckResult::failed(
+            "elevation",
+            required,
+            format!("not elevated; {}", privileges::get_elevation_instructions()),
+        )
+    }
+}
+
+fn check_privileges() -> Vec<CheckResult> {
+    privileges::privilege_status()
+        .into_iter()
+        .map(|privilege| {
+            let name = format!("privilege:{}", privilege.name);
+            if privilege.available {
+                CheckResult::pass(&name, false, "available")
+            } else {
+                CheckResult::failed(&name, false, "not available")
+            }
+        })
+        .collect()
+}
+
+/// Closest existing directory at or above `path`
+fn nearest_existing_dir(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .map(|p| {
+            if p.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                p
+            }
+        })
+        .find(|p| p.is_dir())
+}
+
+fn check_output_dir(output_dir: &Path) -> CheckResult {
+    let name = "output_dir";
+    let Some(existing) = nearest_existing_dir(output_dir) else {
+        return CheckResult::failed(
+            name,
+            true,
+            format!("no existing parent for {}", output_dir.display()),
+        );
+    };
+
+    let probe = existing.join(format!(".rs-collector-preflight-{}", Uuid::new_v4()));
+    match fs::write(&probe, [0u8]).and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) if existing == output_dir => {
+            CheckResult::pass(name, true, format!("{} is writable", output_dir.display()))
+        }
+        Ok(()) => CheckResult::pass(
+            name,
+            true,
+            format!(
+                "{} will be created under writable {}",
+                output_dir.display(),
+                existing.display()
+            ),
+        ),
+        Err(e) => CheckResult::failed(
+            name,
+            true,
+            format!("{} is not writable: {}", existing.display(), e),
+        ),
+    }
+}
+
+fn check_free_space(output_dir: &Path) -> CheckResult {
+    let name = "free_space";
+    let existing = nearest_existing_dir(output_dir)
+        .and_then(|p| p.canonicalize().ok())
+        .unwrap_or_else(|| output_dir.to_path_buf());
+
+    let mut system = System::new();
+    system.refresh_disks_list();
+    let disk = system
+        .disks()
+        .iter()
+        .filter(|d| existing.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len());
+
+    match disk {
+        Some(disk) => {
+            let available = disk.available_space();
+            let detail = format!(
+                "{} MB free on {}",
+                available / (1024 * 1024),
+                disk.mount_point().display()
+            );
+            if available >= PREFLIGHT_MIN_FREE_SPACE {
+                CheckResult::pass(name, false, detail)
+            } else {
+                CheckResult::failed(
+                    name,
+                    false,
+                    format!(
+                        "{} (below {} MB)",
+                        detail,
+                        PREFLIGHT_MIN_FREE_SPACE / (1024 * 1024)
+                    ),
+                )
+            }
+        }
+        None => CheckResult::failed(
+            name,
+            false,
+            format!("no volume found for {}", existing.display()),
+        ),
+    }
+}
+
+fn check_s3(target: &S3Target, write_probe: bool) -> CheckResult {
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => return CheckResult::failed("s3", true, format!("no async runtime: {}", e)),
+    };
+
+    let result = runtime.block_on(async {
+        tokio::time::timeout(
+            Duration::from_secs(PREFLIGHT_TIMEOUT_SECS),
+            probe_s3(target, write_probe),
+        )
+        .await
+    });
+
+    match result {
+        Ok(Ok(check)) => check,
+        Ok(Err(e)) => CheckResult::failed("s3", true, format!("s3://{}: {:#}", target.bucket, e)),
+        Err(_) => CheckResult::failed(
+            "s3",
+            true,
+            format!(
+                "s3://{}: no response within {} seconds",
+                target.bucket, PREFLIGHT_TIMEOUT_SECS
+            ),
+        ),
+    }
+}
+
+/// Write and delete a probe object under the prefix, or send HeadBucket
+/// when writes are not allowed.
+///
+/// A probe that was written but could not be deleted is a warning: uploads
+/// only need PutObject.
+async fn probe_s3(target: &S3Target, write_probe: bool) -> Result<CheckResult> {
+    let client = create_s3_client(target.region.as_deref(), target.profile.as_deref())?;
+
+    if !write_probe {
+        client
+            .head_bucket(HeadBucketRequest {
+                bucket: target.bucket.clone(),
+                ..Default::default()
+            })
+            .await
+            .context("HeadBucket failed")?;
+        return Ok(CheckResult::pass(
+            "s3",
+            true,
+            format!("s3://{} is accessible (write not probed)", target.bucket),
+        ));
+    }
+
+    let prefix = target.prefix.trim_end_matches('/');
+    let probe_name = format!(".rs-collector-preflight-{}", Uuid::new_v4());
+    let key = if prefix.is_empty() {
+        probe_name
+    } else {
+        format!("{}/{}", prefix, probe_name)
+    };
+
+    client
+        .put_object(PutObjectRequest {
+            bucket: target.bucket.clone(),
+            key: key.clone(),
+            body: Some(ByteStream::from(vec![0u8])),
+            ..Default::default()
+        })
+        .await
+        .context(format!("PutObject to {} failed", key))?;
+
+    let deleted = client
+        .delete_object(DeleteObjectRequest {
+            bucket: target.bucket.clone(),
+            key: key.clone(),
+            ..Default::default()
+        })
+        .await;
+
+    Ok(match deleted {
+        Ok(_) => CheckResult::pass(
+            "s3",
+            true,
+            format!("s3://{}/{}/ is writable", target.bucket, prefix),
+        ),
+        Err(e) => CheckResult {
+            name: "s3".to_string(),
+            status: CheckStatus::Warn,
+            required: true,
+            detail: format!(
+                "s3://{}/{} written but not deleted: {}",
+                target.bucket, key, e
+            ),
+        },
+    })
+}
+
+fn check_sftp(config: &SFTPConfig, write_probe: bool) -> CheckResult {
+    let target = format!(
+        "{}@{}:{}{}",
+        config.username, config.host, config.port, config.remote_path
+    );
+    match SFTPClient::new(config.clone()).check_access(write_probe) {
+        Ok(()) if write_probe => CheckResult::pass("sftp", true, format!("{} is writable", target)),
+        Ok(()) => CheckResult::pass(
+            "sftp",
+            true,
+            format!("{} is accessible (write not probed)", target),
+        ),
+        Err(e) => CheckResult::failed("sftp", true, format!("{}: {:#}", target, e)),
+    }
+}
+
+fn check_memory_collection(required: bool) -> CheckResult {
+    let name = "memory_collection";
+    if memory::is_memory_collection_available() {
+        CheckResult::pass(name, required, "available")
+    } else if required {
+        CheckResult::failed(name, true, "not available on this system or build")
+    } else {
+        CheckResult::failed(
+            name,
+            false,
+            "not available; only needed for --dump-process-memory",
+        )
+    }
+}
+
+/// External tool an artifact type is collected with
+fn tool_for(artifact_type: &ArtifactType) -> Option<&'static str> {
+    match artifact_type {
+        ArtifactType::Linux(LinuxArtifactType::Journal) => Some("journalctl"),
+        ArtifactType::Linux(LinuxArtifactType::Dmesg) => Some("dmesg"),
+        ArtifactType::MacOS(MacOSArtifactType::UnifiedLogs) => Some("log"),
+        ArtifactType::MacOS(MacOSArtifactType::Plist) => Some("plutil"),
+        _ => None,
+    }
+}
+
+/// Whether an artifact type is collected on the current platform
+fn applies_to_current_os(artifact_type: &ArtifactType) -> bool {
+    match artifact_type {
+        ArtifactType::Windows(_) => cfg!(target_os = "windows"),
+        ArtifactType::Linux(_) | ArtifactType::Container(_) => cfg!(target_os = "linux"),
+        ArtifactType::MacOS(_) => cfg!(target_os = "macos"),
+        _ => true,
+    }
+}
+
+/// Find an executable in a `PATH`-style list without running it
+fn find_in_path(tool: &str, search_path: &OsStr) -> Option<PathBuf> {
+    env::split_paths(search_path)
+        .map(|dir| dir.join(tool))
+        .find(|candidate| candidate.is_file())
+}
+
+fn check_tools(artifacts: &[Artifact]) -> Vec<CheckResult> {
+    // Tool name, artifacts using it, and whether any of them is required
+    let mut tools: Vec<(&str, Vec<&str>, bool)> = Vec::new();
+    for artifact in artifacts
+        .iter()
+        .filter(|a| applies_to_current_os(&a.artifact_type))
+    {
+        let Some(tool) = tool_for(&artifact.artifact_type) else {
+            continue;
+        };
+        match tools.iter_mut().find(|(name, _, _)| *name == tool) {
+            Some((_, users, required)) => {
+                users.push(artifact.name.as_str());
+                *required |= artifact.required;
+            }
+            None => tools.push((tool, vec![artifact.name.as_str()], artifact.required)),
+        }
+    }
+
+    let search_path = env::var_os("PATH").unwrap_or_default();
+    tools
+        .into_iter()
+        .map(|(tool, users, required)| {
+            let name = format!("tool:{}", tool);
+            let users = users.join(", ");
+            match find_in_path(tool, &search_path) {
+                Some(path) => CheckResult::pass(
+                    &name,
+                    required,
+                    format!("{} (used by {})", path.display(), users),
+                ),
+                None => CheckResult::failed(
+                    &name,
+                    required,
+                    format!("not found in PATH (used by {})", users),
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Run the checks and log the result, failing if a required check failed
+pub fn run_preflight(options: &PreflightOptions) -> Result<PreflightReport> {
+    let report = run_checks(options);
+    for check in &report.checks {
+        match check.status {
+            CheckStatus::Pass | CheckStatus::Skip => {
+                debug!(
+                    "Preflight {}: {} ({})",
+                    check.name, check.status, check.detail
+                )
+            }
+            CheckStatus::Warn => {
+                warn!("Preflight {}: {}", check.name, check.detail)
+            }
+            CheckStatus::Fail => {
+                error!("Preflight {}: {}", check.name, check.detail)
+            }
+        }
+    }
+
+    if !report.passed() {
+        let failed: Vec<&str> = report.failures().map(|c| c.name.as_str()).collect();
+        return Err(anyhow!("Preflight checks failed: {}", failed.join(", ")));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn artifact(name: &str, artifact_type: ArtifactType, required: bool) -> Artifact {
+        Artifact {
+            name: name.to_string(),
+            artifact_type,
+            source_path: "/var/log/journal".to_string(),
+            destination_name: name.to_string(),
+            description: None,
+            required,
+            metadata: HashMap::new(),
+            regex: None,
+        }
+    }
+
+    #[test]
+    fn test_failed_optional_check_is_warning() {
+        let report = PreflightReport {
+            checks: vec![
+                CheckResult::pass("output_dir", true, "ok"),
+                CheckResult::failed("free_space", false, "low"),
+                CheckResult::skip("s3", "no bucket configured"),
+            ],
+        };
+        assert_eq!(report.checks[1].status, CheckStatus::Warn);
+        assert!(report.passed());
+
+        let mut failing = report.clone();
+        failing
+            .checks
+            .push(CheckResult::failed("sftp", true, "auth failed"));
+        assert!(!failing.passed());
+        assert_eq!(failing.failures().count(), 1);
+    }
+
+    #[test]
+    fn test_report_table_and_json() {
+        let report = PreflightReport {
+            checks: vec![
+                CheckResult::pass("elevation", true, "running with elevated privileges"),
+                CheckResult::failed("tool:journalctl", true, "not found in PATH"),
+            ],
+        };
+
+        let table = report.to_table();
+        assert!(table.starts_with("CHECK"));
+        assert!(table.contains("tool:journalctl  FAIL    yes"));
+        assert!(table.contains("required checks failed"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["checks"][1]["status"], "fail");
+        assert_eq!(json["checks"][0]["required"], true);
+    }
+
+    #[test]
+    fn test_output_dir_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let check = check_output_dir(temp_dir.path());
+        assert_eq!(check.status, CheckStatus::Pass);
+
+        let nested = temp_dir.path().join("case-42").join("host");
+        let check = check_output_dir(&nested);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert!(check.detail.contains("will be created"));
+        assert!(!nested.exists());
+
+        // Probe files are removed
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_tool_checks_follow_config() {
+        let artifacts = vec![
+            artifact(
+                "journal",
+                ArtifactType::Linux(LinuxArtifactType::Journal),
+                false,
+            ),
+            artifact(
+                "journal-required",
+                ArtifactType::Linux(LinuxArtifactType::Journal),
+                true,
+            ),
+            artifact("syslog", ArtifactType::Logs, true),
+            artifact(
+                "unified-logs",
+                ArtifactType::MacOS(MacOSArtifactType::UnifiedLogs),
+                true,
+            ),
+        ];
+
+        let checks = check_tools(&artifacts);
+        if cfg!(target_os = "linux") {
+            assert_eq!(checks.len(), 1);
+            assert_eq!(checks[0].name, "tool:journalctl");
+            assert!(checks[0].required);
+            assert!(checks[0].detail.contains("journal, journal-required"));
+        } else if cfg!(target_os = "macos") {
+            assert_eq!(checks.len(), 1);
+            assert_eq!(checks[0].name, "tool:log");
+        } else {
+            assert!(checks.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("fake-tool"), "").unwrap();
+
+        let search_path = env::join_paths([Path::new("/nonexistent"), temp_dir.path()]).unwrap();
+
+        assert_eq!(
+            find_in_path("fake-tool", &search_path),
+            Some(temp_dir.path().join("fake-tool"))
+        );
+        assert_eq!(find_in_path("missing-tool", &search_path), None);
+    }
+}
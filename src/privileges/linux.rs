@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
 use log::{debug, info, warn};
+use std::fs;
 use std::process::Command;
 
+use crate::collectors::footprint::run_command;
+
 /// Enable necessary Linux privileges for artifact collection
 #[allow(dead_code)]
 pub fn enable_privileges() -> Result<()> {
@@ -29,13 +32,40 @@ pub fn is_root() -> bool {
     unsafe { libc::geteuid() == 0 }
 }
 
+/// Capabilities that let a non-root collector read protected files and
+/// process memory, as (name, bit) pairs
+const COLLECTION_CAPABILITIES: [(&str, u32); 2] =
+    [("CAP_DAC_READ_SEARCH", 2), ("CAP_SYS_PTRACE", 19)];
+
+/// Which collection capabilities the process has in its effective set
+pub fn privilege_status() -> Vec<super::PrivilegeStatus> {
+    let effective = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| effective_capabilities(&status))
+        .unwrap_or(0);
+
+    COLLECTION_CAPABILITIES
+        .iter()
+        .map(|(name, bit)| super::PrivilegeStatus {
+            name: name.to_string(),
+            available: effective & (1 << bit) != 0,
+        })
+        .collect()
+}
+
+/// Parse the `CapEff` mask from `/proc/<pid>/status`
+fn effective_capabilities(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+}
+
 /// Set capabilities to allow access to certain files without root
 #[allow(dead_code)]
 fn set_capabilities() -> Result<()> {
     // Check if we have the capability to set capabilities
-    let output = Command::new("capsh")
-        .arg("--print")
-        .output()
+    let output = run_command(Command::new("capsh").arg("--print"))
         .context("Failed to execute capsh command")?;
 
     if !output.status.success() {
@@ -56,3 +86,19 @@ fn set_capabilities() -> Result<()> {
     // For now, just return Ok since this is a placeholder
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_capabilities() {
+        let status = "Name:\tcat\nCapInh:\t0000000000000000\nCapEff:\t0000000000080004\n";
+        let effective = effective_capabilities(status).unwrap();
+        assert_ne!(effective & (1 << 2), 0);
+        assert_ne!(effective & (1 << 19), 0);
+        assert_eq!(effective & (1 << 21), 0);
+
+        assert_eq!(effective_capabilities("Name:\tcat\n"), None);
+    }
+}
@@ -2,6 +2,8 @@ use anyhow::{Context, Result};
 use log::{debug, info, warn};
 use std::process::Command;
 
+use crate::collectors::footprint::run_command;
+
 /// Enable necessary macOS privileges for artifact collection
 pub fn enable_privileges() -> Result<()> {
     // Check if running as root
@@ -27,16 +29,21 @@ pub fn is_root() -> bool {
     unsafe { libc::geteuid() == 0 }
 }
 
+/// Whether the process has Full Disk Access
+pub fn privilege_status() -> Vec<super::PrivilegeStatus> {
+    vec![super::PrivilegeStatus {
+        name: "FullDiskAccess".to_string(),
+        available: check_full_disk_access().is_ok(),
+    }]
+}
+
 /// Check if the process has Full Disk Access
 fn check_full_disk_access() -> Result<()> {
     // Try to access a protected file that requires Full Disk Access
     // For example, try to read a file in /Library/Application Support/com.apple.TCC
     let test_path = "/Library/Application Support/com.apple.TCC/TCC.db";
 
-    let output = Command::new("ls")
-        .arg("-la")
-        .arg(test_path)
-        .output()
+    let output = run_command(Command::new("ls").arg("-la").arg(test_path))
         .context("Failed to execute ls command")?;
 
     if !output.status.success() {
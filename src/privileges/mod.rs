@@ -7,11 +7,34 @@ pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::info;
+use serde::Serialize;
 
-/// Enable required privileges for the current platform
+use crate::security::audit_log::{self, AuditAction};
+
+/// A platform privilege or capability used during collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrivilegeStatus {
+    pub name: String,
+    /// Whether the process holds it or could enable it
+    pub available: bool,
+}
+
+/// Enable required privileges for the current platform.
+///
+/// The attempt and its outcome are recorded in the audit trail.
 pub fn enable_required_privileges() -> Result<()> {
+    let result = enable_platform_privileges();
+    let detail = match &result {
+        Ok(()) => format!("enabled (elevated: {})", is_elevated()),
+        Err(e) => format!("failed: {:#}", e),
+    };
+    audit_log::record(AuditAction::PrivilegeEscalation, &detail);
+    result
+}
+
+fn enable_platform_privileges() -> Result<()> {
     #[cfg(target_os = "windows")]
     {
         info!("Enabling Windows privileges");
@@ -54,6 +77,67 @@ pub fn is_elevated() -> bool {
     }
 }
 
+/// Re-launch the collector with elevated privileges and the same arguments.
+///
+/// On Linux and macOS the process is replaced with `sudo <exe> <args>`, which
+/// may prompt for a password on the terminal. On Windows the executable is
+/// started with the `runas` verb, which shows the UAC prompt; Windows cannot
+/// replace a process, so this one waits for the elevated copy and exits with
+/// its exit code. Does nothing when already elevated, and otherwise returns
+/// only if the re-launch failed.
+pub fn request_elevation() -> Result<()> {
+    if is_elevated() {
+        return Ok(());
+    }
+    let exe = std::env::current_exe().context("Failed to locate the running executable")?;
+    let args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+
+    #[cfg(target_os = "windows")]
+    {
+        let code = windows::run_elevated(&exe, &args)?;
+        std::process::exit(code)
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        info!("Re-launching with sudo");
+        let error = std::process::Command::new("sudo")
+            .arg("--")
+            .arg(&exe)
+            .args(&args)
+            .exec();
+        Err(error).context("Failed to run sudo")
+    }
+    #[cfg(not(any(target_os = "windows", unix)))]
+    {
+        let _ = (exe, args);
+        Err(anyhow::anyhow!(
+            "Automatic elevation is not supported on this platform"
+        ))
+    }
+}
+
+/// Check which collection privileges are available to this process
+pub fn privilege_status() -> Vec<PrivilegeStatus> {
+    #[cfg(target_os = "windows")]
+    {
+        windows::privilege_status()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        linux::privilege_status()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::privilege_status()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        Vec::new()
+    }
+}
+
 /// Get instructions for elevating privileges on the current platform
 pub fn get_elevation_instructions() -> &'static str {
     #[cfg(target_os = "windows")]
@@ -1,4 +1,7 @@
-use anyhow::Result;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
 use log::{info, warn};
 
 /// Enable necessary Windows privileges for artifact collection
@@ -19,6 +22,23 @@ pub fn enable_privileges() -> Result<()> {
     Ok(())
 }
 
+/// Which collection privileges the process token can enable
+pub fn privilege_status() -> Vec<super::PrivilegeStatus> {
+    match crate::windows::privilege_status() {
+        Ok(status) => status
+            .into_iter()
+            .map(|(name, available)| super::PrivilegeStatus {
+                name: name.to_string(),
+                available,
+            })
+            .collect(),
+        Err(e) => {
+            warn!("Failed to query privileges: {}", e);
+            Vec::new()
+        }
+    }
+}
+
 /// Check if the process is running as administrator
 #[cfg(target_os = "windows")]
 #[allow(dead_code)]
@@ -33,3 +53,121 @@ pub fn is_admin() -> bool {
     // On non-Windows platforms, this is just a mock
     false
 }
+
+/// Start `exe` with `args` through the `runas` verb, which shows the UAC
+/// prompt, wait for it and return its exit code
+#[cfg(target_os = "windows")]
+pub fn run_elevated(exe: &Path, args: &[OsString]) -> Result<i32> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::GetExitCodeProcess;
+    use winapi::um::shellapi::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+    use winapi::um::synchapi::WaitForSingleObject;
+    use winapi::um::winbase::INFINITE;
+    use winapi::um::winuser::SW_SHOWNORMAL;
+
+    let wide = |s: &OsStr| -> Vec<u16> { s.encode_wide().chain(Some(0)).collect() };
+    let verb = wide(OsStr::new("runas"));
+    let file = wide(exe.as_os_str());
+    let parameters = wide(OsStr::new(&command_line(args)));
+    let directory = std::env::current_dir()
+        .map(|dir| wide(dir.as_os_str()))
+        .unwrap_or_else(|_| vec![0]);
+
+    let mut info: SHELLEXECUTEINFOW = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as DWORD;
+    info.fMask = SEE_MASK_NOCLOSEPROCESS;
+    info.lpVerb = verb.as_ptr();
+    info.lpFile = file.as_ptr();
+    info.lpParameters = parameters.as_ptr();
+    info.lpDirectory = if directory.len() > 1 {
+        directory.as_ptr()
+    } else {
+        ptr::null()
+    };
+    info.nShow = SW_SHOWNORMAL;
+
+    info!("Re-launching {} with the runas verb", exe.display());
+    if unsafe { ShellExecuteExW(&mut info) } == 0 {
+        // ERROR_CANCELLED when the UAC prompt is declined
+        return Err(std::io::Error::last_os_error()).context("Failed to start elevated process");
+    }
+    if info.hProcess.is_null() {
+        return Err(anyhow!("Elevated process started without a process handle"));
+    }
+
+    let mut code: DWORD = 0;
+    let ok = unsafe {
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let ok = GetExitCodeProcess(info.hProcess, &mut code);
+        CloseHandle(info.hProcess);
+        ok
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to read the elevated process's exit code");
+    }
+    Ok(code as i32)
+}
+
+/// Join `args` into a command line that `CommandLineToArgvW` splits back
+/// into the same arguments
+fn command_line(args: &[OsString]) -> String {
+    args.iter()
+        .map(|arg| quote_argument(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote one argument: backslashes are literal unless they precede a quote,
+/// so those and the ones before the closing quote are doubled
+fn quote_argument(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\x0b', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.extend(std::iter::repeat('\\').take(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat('\\').take(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.extend(std::iter::repeat('\\').take(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_quoting() {
+        let args: Vec<OsString> = [
+            "--output",
+            r"C:\Evidence Dir\",
+            r#"say "hi""#,
+            "",
+            r"C:\plain\path",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(
+            command_line(&args),
+            r#"--output "C:\Evidence Dir\\" "say \"hi\"" "" C:\plain\path"#
+        );
+    }
+}
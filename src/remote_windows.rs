@@ -0,0 +1,1011 @@
+//! Remote Windows triage over SMB (`remote-windows`, `remote_windows` feature).
+//!
+//! Runs a collection on another Windows host without an interactive logon:
+//!
+//! 1. **Copy**: the collector binary, an optional configuration and a small
+//!    `run.cmd` wrapper are copied to `ADMIN$\rs-collector-<id>`
+//! 2. **Exec**: a one-off scheduled task `rs-collector-<id>` runs the
+//!    wrapper as SYSTEM. The wrapper writes the collector's exit code to a
+//!    `status` file once the collection has finished, which is polled for
+//! 3. **Retrieve**: the archive and the collector's console log are copied
+//!    back over SMB
+//! 4. **Cleanup**: the task and the remote directory are deleted, whether or
+//!    not the earlier steps succeeded
+//!
+//! `<id>` is a new collection ID for every run, so concurrent runs against the
+//! same host do not collide. A failed step is reported as a
+//! [`RemoteStepError`] naming the step.
+//!
+//! Credentials come from `RS_COLLECTOR_REMOTE_USER` and
+//! `RS_COLLECTOR_REMOTE_PASSWORD`, with a password prompt when only the user
+//! is set; never from the command line. Without them the current logon
+//! (Kerberos) context is used. The SMB and task operations sit behind
+//! [`RemoteHost`]; the implementation using the Windows networking APIs only
+//! exists on Windows, so the operator's machine must be a Windows host.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use log::{info, warn};
+use uuid::Uuid;
+
+use crate::cli::RemoteWindowsOpts;
+
+/// Environment variable holding the remote user name (`DOMAIN\user`)
+pub const USER_ENV: &str = "RS_COLLECTOR_REMOTE_USER";
+
+/// Environment variable holding the remote user's password
+pub const PASSWORD_ENV: &str = "RS_COLLECTOR_REMOTE_PASSWORD";
+
+/// Name of the collector binary on the remote host
+const REMOTE_BINARY: &str = "rust_collector.exe";
+
+/// Collector options the wrapper sets itself, and options that would keep
+/// the collection from producing an archive or finishing
+const REJECTED_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "--temp-dir",
+    "--stream",
+    "--watch",
+    "--serve",
+];
+
+/// Steps of a remote collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteStep {
+    Copy,
+    Exec,
+    Retrieve,
+    Cleanup,
+}
+
+impl fmt::Display for RemoteStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RemoteStep::Copy => "copy",
+            RemoteStep::Exec => "exec",
+            RemoteStep::Retrieve => "retrieve",
+            RemoteStep::Cleanup => "cleanup",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A remote collection step that failed
+#[derive(Debug)]
+pub struct RemoteStepError {
+    pub step: RemoteStep,
+    pub host: String,
+    pub cause: String,
+}
+
+impl fmt::Display for RemoteStepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Remote {} step failed on {}: {}",
+            self.step, self.host, self.cause
+        )
+    }
+}
+
+impl std::error::Error for RemoteStepError {}
+
+/// Credentials for the remote host
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// File and task operations on a remote host.
+///
+/// Paths are relative to the host's `ADMIN$` share (`%SystemRoot%`) and use
+/// `\` separators.
+pub trait RemoteHost {
+    /// Host name, for messages
+    fn host(&self) -> &str;
+
+    /// Copy a local file to the host, creating parent directories
+    fn upload(&self, local: &Path, remote: &str) -> Result<()>;
+
+    /// Write `contents` to a file on the host, creating parent directories
+    fn write_file(&self, remote: &str, contents: &[u8]) -> Result<()>;
+
+    /// Read a file from the host; `None` if it does not exist
+    fn read_file(&self, remote: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Names of the entries of a directory on the host
+    fn list_dir(&self, remote: &str) -> Result<Vec<String>>;
+
+    /// Copy a file from the host to a local path
+    fn download(&self, remote: &str, local: &Path) -> Result<()>;
+
+    /// Delete a directory on the host and everything in it
+    fn remove_dir_all(&self, remote: &str) -> Result<()>;
+
+    /// Create a scheduled task running `command` as SYSTEM and start it
+    fn start_task(&self, name: &str, command: &str) -> Result<()>;
+
+    /// Delete a scheduled task
+    fn delete_task(&self, name: &str) -> Result<()>;
+}
+
+/// What to run on the remote host and where to put the results
+#[derive(Debug, Clone)]
+pub struct RemotePlan {
+    /// Collection ID used in the remote directory and task names
+    pub collection_id: String,
+    /// Local collector binary to deploy
+    pub binary: PathBuf,
+    /// Local configuration file to deploy
+    pub config: Option<PathBuf>,
+    /// Extra collector arguments
+    pub collector_args: Vec<String>,
+    /// Local directory for the retrieved archive and log
+    pub output: PathBuf,
+    /// Time between checks for the status file
+    pub poll_interval: Duration,
+    /// How long the collection may run
+    pub timeout: Duration,
+}
+
+impl RemotePlan {
+    /// Directory under `ADMIN$` holding everything deployed for this run
+    pub fn remote_dir(&self) -> String {
+        format!("rs-collector-{}", self.collection_id)
+    }
+
+    /// Name of the scheduled task
+    pub fn task_name(&self) -> String {
+        format!("rs-collector-{}", self.collection_id)
+    }
+
+    fn remote_path(&self, name: &str) -> String {
+        format!("{}\\{}", self.remote_dir(), name)
+    }
+}
+
+/// Files retrieved from a remote collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteResult {
+    pub archive: PathBuf,
+    pub log: Option<PathBuf>,
+}
+
+/// Run the `remote-windows` subcommand
+pub fn run(opts: &RemoteWindowsOpts) -> Result<()> {
+    let binary = match &opts.binary {
+        Some(path) => path.clone(),
+        None => std::env::current_exe()?,
+    };
+    let plan = RemotePlan {
+        collection_id: Uuid::new_v4().to_string(),
+        binary,
+        config: opts.config.clone(),
+        collector_args: opts.collector_args.clone(),
+        output: opts.output.clone(),
+        poll_interval: Duration::from_secs(opts.poll_seconds.max(1)),
+        timeout: Duration::from_secs(opts.timeout_minutes * 60),
+    };
+    // Catch bad arguments before anything is copied
+    run_script(&plan)?;
+
+    let credentials = resolve_credentials(|name| std::env::var(name).ok(), prompt_password)?;
+    let host = connect(&opts.host, credentials.as_ref())?;
+    let result = run_remote_collection(host.as_ref(), &plan)?;
+
+    info!("Remote archive: {}", result.archive.display());
+    if let Some(log) = &result.log {
+        info!("Collector log: {}", log.display());
+    }
+    Ok(())
+}
+
+/// Credentials from the environment, prompting for a missing password.
+///
+/// `None` when no user is set, meaning the current logon context is used.
+pub fn resolve_credentials(
+    env: impl Fn(&str) -> Option<String>,
+    prompt: impl FnOnce(&str) -> Result<String>,
+) -> Result<Option<Credentials>> {
+    let username = match env(USER_ENV).filter(|u| !u.is_empty()) {
+        Some(username) => username,
+        None => {
+            if env(PASSWORD_ENV).is_some() {
+                bail!("{} is set but {} is not", PASSWORD_ENV, USER_ENV);
+            }
+            return Ok(None);
+        }
+    };
+    let password = match env(PASSWORD_ENV) {
+        Some(password) => password,
+        None => prompt(&format!("Password for {}: ", username))?,
+    };
+    Ok(Some(Credentials { username, password }))
+}
+
+/// Deploy, run and retrieve a collection, then clean up.
+///
+/// Cleanup runs after every other step, successful or not. When an earlier
+/// step failed its error is returned and a cleanup failure is only logged.
+pub fn run_remote_collection(host: &dyn RemoteHost, plan: &RemotePlan) -> Result<RemoteResult> {
+    info!(
+        "Starting remote collection {} on {}",
+        plan.collection_id,
+        host.host()
+    );
+    let result = deploy_and_collect(host, plan);
+    let cleanup = cleanup(host, plan);
+
+    match (result, cleanup) {
+        (Ok(result), Ok(())) => Ok(result),
+        (Ok(result), Err(e)) => {
+            info!("Remote archive: {}", result.archive.display());
+            Err(e.into())
+        }
+        (Err(e), Ok(())) => Err(e.into()),
+        (Err(e), Err(cleanup_error)) => {
+            warn!("{}", cleanup_error);
+            Err(e.into())
+        }
+    }
+}
+
+fn deploy_and_collect(
+    host: &dyn RemoteHost,
+    plan: &RemotePlan,
+) -> std::result::Result<RemoteResult, RemoteStepError> {
+    let step_error = |step, e: anyhow::Error| RemoteStepError {
+        step,
+        host: host.host().to_string(),
+        cause: format!("{:#}", e),
+    };
+
+    copy_files(host, plan).map_err(|e| step_error(RemoteStep::Copy, e))?;
+
+    let exit_code = execute(host, plan).map_err(|e| step_error(RemoteStep::Exec, e))?;
+    if exit_code != 0 {
+        // Keep the log of the failed run for troubleshooting
+        let cause = match retrieve_log(host, plan) {
+            Some(path) => anyhow!(
+                "collector exited with code {}, see {}",
+                exit_code,
+                path.display()
+            ),
+            None => anyhow!("collector exited with code {}", exit_code),
+        };
+        return Err(step_error(RemoteStep::Exec, cause));
+    }
+
+    let archive = retrieve_archive(host, plan).map_err(|e| step_error(RemoteStep::Retrieve, e))?;
+    Ok(RemoteResult {
+        archive,
+        log: retrieve_log(host, plan),
+    })
+}
+
+fn copy_files(host: &dyn RemoteHost, plan: &RemotePlan) -> Result<()> {
+    info!(
+        "Copying collector to \\\\{}\\ADMIN$\\{}",
+        host.host(),
+        plan.remote_dir()
+    );
+    host.upload(&plan.binary, &plan.remote_path(REMOTE_BINARY))?;
+    if let Some(config) = &plan.config {
+        host.upload(config, &plan.remote_path("config.yaml"))?;
+    }
+    host.write_file(&plan.remote_path("run.cmd"), run_script(plan)?.as_bytes())
+}
+
+/// Start the task and wait for the status file; returns the exit code
+fn execute(host: &dyn RemoteHost, plan: &RemotePlan) -> Result<i32> {
+    let command = format!("%SystemRoot%\\{}", plan.remote_path("run.cmd"));
+    host.start_task(&plan.task_name(), &command)?;
+    info!("Started task {} on {}", plan.task_name(), host.host());
+
+    let status_path = plan.remote_path("status");
+    let deadline = Instant::now() + plan.timeout;
+    loop {
+        if let Some(status) = host.read_file(&status_path)? {
+            let status = String::from_utf8_lossy(&status);
+            return status
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("unexpected status file contents: {:?}", status.trim()));
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "collector did not finish within {} minutes",
+                plan.timeout.as_secs() / 60
+            );
+        }
+        thread::sleep(plan.poll_interval);
+    }
+}
+
+fn retrieve_archive(host: &dyn RemoteHost, plan: &RemotePlan) -> Result<PathBuf> {
+    let work_dir = plan.remote_path("work");
+    let archive = host
+        .list_dir(&work_dir)?
+        .into_iter()
+        .find(|name| name.to_lowercase().ends_with(".zip"))
+        .ok_or_else(|| anyhow!("no archive in {}", work_dir))?;
+
+    fs::create_dir_all(&plan.output)?;
+    let local = plan.output.join(&archive);
+    host.download(&format!("{}\\{}", work_dir, archive), &local)?;
+    info!("Retrieved {}", local.display());
+    Ok(local)
+}
+
+/// Copy the collector's console log, if there is one
+fn retrieve_log(host: &dyn RemoteHost, plan: &RemotePlan) -> Option<PathBuf> {
+    let local = plan.output.join(format!(
+        "{}-{}-collector.log",
+        host.host(),
+        plan.collection_id
+    ));
+    let result = fs::create_dir_all(&plan.output)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| host.download(&plan.remote_path("collector.log"), &local));
+    match result {
+        Ok(()) => Some(local),
+        Err(e) => {
+            warn!(
+                "Failed to retrieve collector log from {}: {}",
+                host.host(),
+                e
+            );
+            None
+        }
+    }
+}
+
+fn cleanup(host: &dyn RemoteHost, plan: &RemotePlan) -> std::result::Result<(), RemoteStepError> {
+    let mut failures = Vec::new();
+    if let Err(e) = host.delete_task(&plan.task_name()) {
+        failures.push(format!("task {}: {:#}", plan.task_name(), e));
+    }
+    if let Err(e) = host.remove_dir_all(&plan.remote_dir()) {
+        failures.push(format!("ADMIN$\\{}: {:#}", plan.remote_dir(), e));
+    }
+
+    if failures.is_empty() {
+        info!("Removed collector and task from {}", host.host());
+        Ok(())
+    } else {
+        Err(RemoteStepError {
+            step: RemoteStep::Cleanup,
+            host: host.host().to_string(),
+            cause: format!("could not remove {}", failures.join("; ")),
+        })
+    }
+}
+
+/// The `run.cmd` wrapper run by the scheduled task.
+///
+/// Output and scratch space are kept in the remote directory, so the archive
+/// ends up in `work`. The status file is renamed into place so it is never
+/// read half-written.
+pub fn run_script(plan: &RemotePlan) -> Result<String> {
+    let mut args = vec![
+        "--output \"%~dp0output\"".to_string(),
+        "--temp-dir \"%~dp0work\"".to_string(),
+    ];
+    if plan.config.is_some() {
+        args.push("--config \"%~dp0config.yaml\"".to_string());
+    }
+    for arg in &plan.collector_args {
+        let option = arg.split('=').next().unwrap_or(arg);
+        let config_option = option == "-c" || option == "--config";
+        if REJECTED_OPTIONS.contains(&option) || (config_option && plan.config.is_some()) {
+            bail!("{} cannot be passed to a remote collection", option);
+        }
+        args.push(cmd_quote(arg)?);
+    }
+
+    Ok(format!(
+        "@echo off\r\n\
+         \"%~dp0{}\" {} > \"%~dp0collector.log\" 2>&1\r\n\
+         > \"%~dp0status.tmp\" echo %ERRORLEVEL%\r\n\
+         move /y \"%~dp0status.tmp\" \"%~dp0status\" > nul\r\n",
+        REMOTE_BINARY,
+        args.join(" ")
+    ))
+}
+
+/// Quote an argument for a `cmd.exe` batch file line
+fn cmd_quote(arg: &str) -> Result<String> {
+    if arg.contains(['"', '\r', '\n']) {
+        bail!(
+            "Collector argument cannot contain quotes or line breaks: {}",
+            arg
+        );
+    }
+    let escaped = arg.replace('%', "%%");
+    let needs_quotes = arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c.is_whitespace() || "&|<>^(),;=".contains(c));
+    Ok(if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    })
+}
+
+/// Connect to `host`'s `ADMIN$` and `IPC$` shares
+#[cfg(target_os = "windows")]
+fn connect(host: &str, credentials: Option<&Credentials>) -> Result<Box<dyn RemoteHost>> {
+    Ok(Box::new(smb::SmbHost::connect(host, credentials)?))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn connect(_host: &str, _credentials: Option<&Credentials>) -> Result<Box<dyn RemoteHost>> {
+    bail!("remote-windows uses the Windows SMB client and must be run from a Windows host")
+}
+
+/// Read a password from the console without echoing it
+#[cfg(target_os = "windows")]
+fn prompt_password(prompt: &str) -> Result<String> {
+    use std::io::{self, BufRead, Write};
+    use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::STD_INPUT_HANDLE;
+    use winapi::um::wincon::ENABLE_ECHO_INPUT;
+
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    // SAFETY: the standard input handle is owned by the process; the mode is
+    // restored before returning
+    let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+    let mut mode = 0;
+    let console = unsafe { GetConsoleMode(handle, &mut mode) } != 0;
+    if console {
+        unsafe { SetConsoleMode(handle, mode & !ENABLE_ECHO_INPUT) };
+    }
+    let mut password = String::new();
+    let read = io::stdin().lock().read_line(&mut password);
+    if console {
+        unsafe { SetConsoleMode(handle, mode) };
+    }
+    eprintln!();
+
+    read?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn prompt_password(_prompt: &str) -> Result<String> {
+    bail!("{} must be set when {} is", PASSWORD_ENV, USER_ENV)
+}
+
+#[cfg(target_os = "windows")]
+mod smb {
+    //! [`RemoteHost`] over the Windows SMB client and `schtasks.exe`
+
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::ptr::null;
+
+    use anyhow::{bail, Context, Result};
+    use log::warn;
+    use widestring::U16CString;
+    use winapi::shared::winerror::NO_ERROR;
+    use winapi::um::winnetwk::{
+        WNetAddConnection2W, WNetCancelConnection2W, NETRESOURCEW, RESOURCETYPE_ANY,
+        RESOURCETYPE_DISK,
+    };
+
+    use super::{Credentials, RemoteHost};
+
+    /// Connections to a host's `ADMIN$` and `IPC$` shares, closed on drop.
+    ///
+    /// `schtasks.exe` reaches the Task Scheduler over `IPC$`, so it runs
+    /// with the credentials of the connection made here.
+    pub struct SmbHost {
+        host: String,
+        admin_share: PathBuf,
+        connections: Vec<U16CString>,
+    }
+
+    impl SmbHost {
+        pub fn connect(host: &str, credentials: Option<&Credentials>) -> Result<Self> {
+            let mut smb = Self {
+                host: host.to_string(),
+                admin_share: PathBuf::from(format!("\\\\{}\\ADMIN$", host)),
+                connections: Vec::new(),
+            };
+            smb.add_connection(
+                &format!("\\\\{}\\IPC$", host),
+                RESOURCETYPE_ANY,
+                credentials,
+            )?;
+            smb.add_connection(
+                &format!("\\\\{}\\ADMIN$", host),
+                RESOURCETYPE_DISK,
+                credentials,
+            )?;
+            Ok(smb)
+        }
+
+        fn add_connection(
+            &mut self,
+            share: &str,
+            resource_type: u32,
+            credentials: Option<&Credentials>,
+        ) -> Result<()> {
+            let mut remote_name = U16CString::from_str(share)?;
+            let username = credentials
+                .map(|c| U16CString::from_str(&c.username))
+                .transpose()?;
+            let password = credentials
+                .map(|c| U16CString::from_str(&c.password))
+                .transpose()?;
+
+            // SAFETY: NETRESOURCEW is plain data; zero is "unset" for every field
+            let mut resource: NETRESOURCEW = unsafe { std::mem::zeroed() };
+            resource.dwType = resource_type;
+            resource.lpRemoteName = remote_name.as_mut_ptr();
+
+            // SAFETY: the strings outlive the call; null user and password
+            // select the current logon context
+            let status = unsafe {
+                WNetAddConnection2W(
+                    &mut resource,
+                    password.as_ref().map_or(null(), |p| p.as_ptr()),
+                    username.as_ref().map_or(null(), |u| u.as_ptr()),
+                    0,
+                )
+            };
+            if status != NO_ERROR {
+                bail!(
+                    "Failed to connect to {}: {}",
+                    share,
+                    io::Error::from_raw_os_error(status as i32)
+                );
+            }
+            self.connections.push(remote_name);
+            Ok(())
+        }
+
+        fn path(&self, remote: &str) -> PathBuf {
+            self.admin_share.join(remote)
+        }
+
+        fn schtasks(&self, args: &[&str]) -> Result<()> {
+            let output = Command::new("schtasks.exe")
+                .arg("/s")
+                .arg(&self.host)
+                .args(args)
+                .output()
+                .context("Failed to run schtasks.exe")?;
+            if !output.status.success() {
+                bail!(
+                    "schtasks.exe failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            Ok(())
+        }
+    }
+
+    impl RemoteHost for SmbHost {
+        fn host(&self) -> &str {
+            &self.host
+        }
+
+        fn upload(&self, local: &Path, remote: &str) -> Result<()> {
+            let target = self.path(remote);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(local, &target).context(format!(
+                "Failed to copy {} to {}",
+                local.display(),
+                target.display()
+            ))?;
+            Ok(())
+        }
+
+        fn write_file(&self, remote: &str, contents: &[u8]) -> Result<()> {
+            let target = self.path(remote);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, contents).context(format!("Failed to write {}", target.display()))
+        }
+
+        fn read_file(&self, remote: &str) -> Result<Option<Vec<u8>>> {
+            match fs::read(self.path(remote)) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        fn list_dir(&self, remote: &str) -> Result<Vec<String>> {
+            let mut names = Vec::new();
+            for entry in fs::read_dir(self.path(remote))? {
+                names.push(entry?.file_name().to_string_lossy().to_string());
+            }
+            Ok(names)
+        }
+
+        fn download(&self, remote: &str, local: &Path) -> Result<()> {
+            let source = self.path(remote);
+            fs::copy(&source, local).context(format!(
+                "Failed to copy {} to {}",
+                source.display(),
+                local.display()
+            ))?;
+            Ok(())
+        }
+
+        fn remove_dir_all(&self, remote: &str) -> Result<()> {
+            match fs::remove_dir_all(self.path(remote)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+
+        fn start_task(&self, name: &str, command: &str) -> Result<()> {
+            // A one-off task in the past never fires on its own; /run starts it
+            self.schtasks(&[
+                "/create", "/tn", name, "/tr", command, "/sc", "once", "/st", "00:00", "/ru",
+                "SYSTEM", "/rl", "HIGHEST", "/f",
+            ])?;
+            self.schtasks(&["/run", "/tn", name])
+        }
+
+        fn delete_task(&self, name: &str) -> Result<()> {
+            self.schtasks(&["/delete", "/tn", name, "/f"])
+        }
+    }
+
+    impl Drop for SmbHost {
+        fn drop(&mut self) {
+            for name in self.connections.iter().rev() {
+                // SAFETY: `name` is a valid null-terminated string
+                let status = unsafe { WNetCancelConnection2W(name.as_ptr(), 0, 1) };
+                if status != NO_ERROR {
+                    warn!(
+                        "Failed to disconnect {}: {}",
+                        name.to_string_lossy(),
+                        io::Error::from_raw_os_error(status as i32)
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    /// In-memory host: the task "runs" by writing the status file, an
+    /// archive and a log after a number of polls
+    #[derive(Default)]
+    struct MockHost {
+        files: RefCell<BTreeMap<String, Vec<u8>>>,
+        tasks: RefCell<Vec<String>>,
+        calls: RefCell<Vec<String>>,
+        polls_until_done: RefCell<u32>,
+        exit_code: i32,
+        produce_archive: bool,
+        fail_upload: bool,
+        fail_delete_task: bool,
+    }
+
+    impl MockHost {
+        fn new() -> Self {
+            Self {
+                produce_archive: true,
+                polls_until_done: RefCell::new(2),
+                ..Default::default()
+            }
+        }
+
+        fn call(&self, call: String) {
+            self.calls.borrow_mut().push(call);
+        }
+    }
+
+    impl RemoteHost for MockHost {
+        fn host(&self) -> &str {
+            "ws01"
+        }
+
+        fn upload(&self, local: &Path, remote: &str) -> Result<()> {
+            self.call(format!("upload {}", remote));
+            if self.fail_upload {
+                bail!("access denied");
+            }
+            self.files
+                .borrow_mut()
+                .insert(remote.to_string(), fs::read(local)?);
+            Ok(())
+        }
+
+        fn write_file(&self, remote: &str, contents: &[u8]) -> Result<()> {
+            self.call(format!("write {}", remote));
+            self.files
+                .borrow_mut()
+                .insert(remote.to_string(), contents.to_vec());
+            Ok(())
+        }
+
+        fn read_file(&self, remote: &str) -> Result<Option<Vec<u8>>> {
+            let mut polls = self.polls_until_done.borrow_mut();
+            if remote.ends_with("\\status") && *polls > 0 {
+                *polls -= 1;
+                if *polls == 0 {
+                    let dir = remote.trim_end_matches("\\status");
+                    let mut files = self.files.borrow_mut();
+                    files.insert(remote.to_string(), format!("{}\r\n", self.exit_code).into());
+                    files.insert(format!("{}\\collector.log", dir), b"log".to_vec());
+                    if self.produce_archive {
+                        files.insert(format!("{}\\work\\ws01_20240101.zip", dir), b"PK".to_vec());
+                    }
+                }
+            }
+            Ok(self.files.borrow().get(remote).cloned())
+        }
+
+        fn list_dir(&self, remote: &str) -> Result<Vec<String>> {
+            let prefix = format!("{}\\", remote);
+            Ok(self
+                .files
+                .borrow()
+                .keys()
+                .filter_map(|k| k.strip_prefix(&prefix))
+                .map(String::from)
+                .collect())
+        }
+
+        fn download(&self, remote: &str, local: &Path) -> Result<()> {
+            self.call(format!("download {}", remote));
+            let files = self.files.borrow();
+            let data = files
+                .get(remote)
+                .ok_or_else(|| anyhow!("{} not found", remote))?;
+            fs::write(local, data)?;
+            Ok(())
+        }
+
+        fn remove_dir_all(&self, remote: &str) -> Result<()> {
+            self.call(format!("remove {}", remote));
+            let prefix = format!("{}\\", remote);
+            self.files
+                .borrow_mut()
+                .retain(|k, _| !k.starts_with(&prefix));
+            Ok(())
+        }
+
+        fn start_task(&self, name: &str, command: &str) -> Result<()> {
+            self.call(format!("start {} {}", name, command));
+            self.tasks.borrow_mut().push(name.to_string());
+            Ok(())
+        }
+
+        fn delete_task(&self, name: &str) -> Result<()> {
+            self.call(format!("delete {}", name));
+            if self.fail_delete_task {
+                bail!("task scheduler unavailable");
+            }
+            self.tasks.borrow_mut().retain(|t| t != name);
+            Ok(())
+        }
+    }
+
+    fn plan(temp: &TempDir) -> RemotePlan {
+        let binary = temp.path().join("rust_collector.exe");
+        fs::write(&binary, b"MZ").unwrap();
+        RemotePlan {
+            collection_id: "1234".to_string(),
+            binary,
+            config: None,
+            collector_args: vec!["--artifact-types".into(), "Registry,EventLog".into()],
+            output: temp.path().join("results"),
+            poll_interval: Duration::ZERO,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    fn step_of(error: &anyhow::Error) -> RemoteStep {
+        error.downcast_ref::<RemoteStepError>().unwrap().step
+    }
+
+    #[test]
+    fn test_remote_collection() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = plan(&temp);
+        let config = temp.path().join("triage.yaml");
+        fs::write(&config, "artifacts: []").unwrap();
+        plan.config = Some(config);
+        let host = MockHost::new();
+
+        let result = run_remote_collection(&host, &plan).unwrap();
+
+        assert_eq!(result.archive, plan.output.join("ws01_20240101.zip"));
+        assert_eq!(fs::read(&result.archive).unwrap(), b"PK");
+        assert_eq!(fs::read(result.log.unwrap()).unwrap(), b"log");
+        assert_eq!(
+            host.calls.borrow()[..4],
+            [
+                "upload rs-collector-1234\\rust_collector.exe",
+                "upload rs-collector-1234\\config.yaml",
+                "write rs-collector-1234\\run.cmd",
+                "start rs-collector-1234 %SystemRoot%\\rs-collector-1234\\run.cmd",
+            ]
+        );
+        // Nothing is left behind
+        assert!(host.files.borrow().is_empty());
+        assert!(host.tasks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_copy_failure_still_cleans_up() {
+        let temp = TempDir::new().unwrap();
+        let host = MockHost {
+            fail_upload: true,
+            ..MockHost::new()
+        };
+
+        let error = run_remote_collection(&host, &plan(&temp)).unwrap_err();
+        assert_eq!(step_of(&error), RemoteStep::Copy);
+        assert!(error.to_string().contains("access denied"));
+        let calls = host.calls.borrow();
+        assert!(!calls.iter().any(|c| c.starts_with("start")));
+        assert!(calls.contains(&"remove rs-collector-1234".to_string()));
+    }
+
+    #[test]
+    fn test_collector_failure_is_exec_error() {
+        let temp = TempDir::new().unwrap();
+        let plan = plan(&temp);
+        let host = MockHost {
+            exit_code: 1,
+            ..MockHost::new()
+        };
+
+        let error = run_remote_collection(&host, &plan).unwrap_err();
+        assert_eq!(step_of(&error), RemoteStep::Exec);
+        assert!(error.to_string().contains("exited with code 1"));
+        // The log of the failed run is kept
+        assert!(plan.output.join("ws01-1234-collector.log").exists());
+    }
+
+    #[test]
+    fn test_exec_timeout() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = plan(&temp);
+        plan.timeout = Duration::ZERO;
+        let host = MockHost {
+            polls_until_done: RefCell::new(u32::MAX),
+            ..MockHost::new()
+        };
+
+        let error = run_remote_collection(&host, &plan).unwrap_err();
+        assert_eq!(step_of(&error), RemoteStep::Exec);
+        assert!(error.to_string().contains("did not finish"));
+        assert!(host.tasks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_missing_archive_is_retrieve_error() {
+        let temp = TempDir::new().unwrap();
+        let host = MockHost {
+            produce_archive: false,
+            ..MockHost::new()
+        };
+
+        let error = run_remote_collection(&host, &plan(&temp)).unwrap_err();
+        assert_eq!(step_of(&error), RemoteStep::Retrieve);
+    }
+
+    #[test]
+    fn test_cleanup_failure_is_reported() {
+        let temp = TempDir::new().unwrap();
+        let host = MockHost {
+            fail_delete_task: true,
+            ..MockHost::new()
+        };
+
+        let error = run_remote_collection(&host, &plan(&temp)).unwrap_err();
+        assert_eq!(step_of(&error), RemoteStep::Cleanup);
+        assert!(error.to_string().contains("rs-collector-1234"));
+        // The directory is still removed
+        assert!(host.files.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_run_script() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = plan(&temp);
+        plan.collector_args = vec![
+            "--artifact-types".into(),
+            "Registry,EventLog".into(),
+            "--process".into(),
+            "100%".into(),
+            "-v".into(),
+        ];
+        let script = run_script(&plan).unwrap();
+        assert!(script.contains(
+            "\"%~dp0rust_collector.exe\" --output \"%~dp0output\" --temp-dir \"%~dp0work\" \
+             --artifact-types \"Registry,EventLog\" --process 100%% -v > \"%~dp0collector.log\" 2>&1"
+        ));
+        assert!(script.contains("> \"%~dp0status.tmp\" echo %ERRORLEVEL%"));
+
+        for rejected in ["--output=C:\\out", "--stream", "--temp-dir", "bad\"quote"] {
+            plan.collector_args = vec![rejected.to_string()];
+            assert!(run_script(&plan).is_err(), "{}", rejected);
+        }
+        // The deployed config replaces --config
+        plan.collector_args = vec!["-c".into(), "other.yaml".into()];
+        assert!(run_script(&plan).is_ok());
+        plan.config = Some(temp.path().join("triage.yaml"));
+        assert!(run_script(&plan).is_err());
+    }
+
+    #[test]
+    fn test_resolve_credentials() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        let no_prompt = |_: &str| -> Result<String> { panic!("unexpected prompt") };
+
+        assert_eq!(resolve_credentials(env(&[]), no_prompt).unwrap(), None);
+        assert_eq!(
+            resolve_credentials(
+                env(&[(USER_ENV, "CORP\\ir"), (PASSWORD_ENV, "secret")]),
+                no_prompt
+            )
+            .unwrap(),
+            Some(Credentials {
+                username: "CORP\\ir".into(),
+                password: "secret".into()
+            })
+        );
+        let prompted = resolve_credentials(env(&[(USER_ENV, "CORP\\ir")]), |prompt| {
+            assert_eq!(prompt, "Password for CORP\\ir: ");
+            Ok("typed".into())
+        })
+        .unwrap();
+        assert_eq!(prompted.unwrap().password, "typed");
+        assert!(resolve_credentials(env(&[(PASSWORD_ENV, "secret")]), no_prompt).is_err());
+
+        let credentials = Credentials {
+            username: "ir".into(),
+            password: "secret".into(),
+        };
+        assert!(!format!("{:?}", credentials).contains("secret"));
+    }
+}
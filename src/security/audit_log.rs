@@ -0,0 +1,585 @@
+//! Tamper-evident audit trail of the collector's own actions.
+//!
+//! Every privilege escalation, source file opened, destination file written,
+//! upload attempted, presigned URL generated and object downloaded is
+//! appended to `audit_trail.log` as one JSON line.
+//! Each entry carries an HMAC-SHA256 over the previous entry's HMAC and its
+//! own fields, so editing, reordering or deleting any entry breaks the chain
+//! from that point on; [`AuditLog::verify`] reports where.
+//!
+//! The HMAC key is derived with HKDF-SHA256 from the hostname and the
+//! collection timestamp. Both appear in the collection summary, so the trail
+//! can be re-verified after the fact; it shows that entries were not altered
+//! by accident or naive editing, not that they were written by this tool.
+//! Truncation after the last entry cannot be detected from the trail alone.
+//!
+//! The trail is process-global, like the collector footprint, so file copies
+//! and uploads can [`record`] without threading state through every call.
+//! Entries recorded before [`start`] are held and written right after the
+//! trail's first (`trail_started`) entry.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use openssl::hash::MessageDigest;
+use openssl::md::Md;
+use openssl::pkey::{Id, PKey};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::sign::Signer;
+use serde::{Deserialize, Serialize};
+
+/// Name of the audit trail written into the output directory
+pub const AUDIT_LOG_FILE: &str = "audit_trail.log";
+
+/// HKDF salt and info binding the derived key to this use
+const HKDF_SALT: &[u8] = b"rust-collector audit trail";
+const HKDF_INFO: &[u8] = b"audit_trail.log hmac-sha256";
+
+/// Chain value preceding the first entry
+const GENESIS_HMAC: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Entries held before the trail is started; later ones are counted and dropped
+const MAX_PENDING_ENTRIES: usize = 4096;
+
+lazy_static! {
+    static ref AUDIT_TRAIL: Mutex<AuditTrail> = Mutex::new(AuditTrail::default());
+}
+
+/// Audited collector actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// The audit trail was opened
+    TrailStarted,
+    /// Platform privileges were enabled
+    PrivilegeEscalation,
+    /// A source file was opened for reading
+    FileOpen,
+    /// A destination file was written
+    FileWrite,
+    /// An upload to remote storage was attempted
+    UploadAttempt,
+    /// A presigned URL was generated for an uploaded object
+    PresignedUrl,
+    /// An uploaded object was downloaded (`download`)
+    Download,
+    /// Raw credential files were collected (`--collect-credential-files`)
+    CredentialCollection,
+    /// A filesystem snapshot was created or removed (`--use-snapshots`)
+    Snapshot,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditAction::TrailStarted => "trail_started",
+            AuditAction::PrivilegeEscalation => "privilege_escalation",
+            AuditAction::FileOpen => "file_open",
+            AuditAction::FileWrite => "file_write",
+            AuditAction::UploadAttempt => "upload_attempt",
+            AuditAction::PresignedUrl => "presigned_url",
+            AuditAction::Download => "download",
+            AuditAction::CredentialCollection => "credential_collection",
+            AuditAction::Snapshot => "snapshot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One line of the audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the trail, starting at 0
+    pub seq: u64,
+    /// RFC3339 time the action was recorded
+    pub timestamp: String,
+    pub action: String,
+    pub detail: String,
+    /// Hex HMAC-SHA256 over the previous entry's HMAC and this entry's fields
+    pub hmac_sha256_of_prev: String,
+}
+
+/// Outcome of verifying an audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    /// Entries read, including any after the first invalid one
+    pub entries: u64,
+    pub valid: bool,
+    /// Sequence number at which the chain first breaks
+    pub first_invalid_seq: Option<u64>,
+    pub reason: Option<String>,
+}
+
+/// HMAC-SHA256 (RFC 2104)
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Result<[u8; 32]> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(message)?;
+    let mut mac = [0u8; 32];
+    signer.sign(&mut mac)?;
+    Ok(mac)
+}
+
+/// HKDF-SHA256 (RFC 5869) producing a single 32-byte output block
+fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8]) -> Result<[u8; 32]> {
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_salt(salt)?;
+    ctx.set_hkdf_key(ikm)?;
+    ctx.add_hkdf_info(info)?;
+    let mut okm = [0u8; 32];
+    ctx.derive(Some(&mut okm))?;
+    Ok(okm)
+}
+
+/// Derive the trail's HMAC key from the hostname and collection timestamp
+fn derive_key(hostname: &str, timestamp: &str) -> Result<[u8; 32]> {
+    let ikm = format!("{}\n{}", hostname, timestamp);
+    hkdf_sha256(HKDF_SALT, ikm.as_bytes(), HKDF_INFO)
+        .context("Failed to derive the audit trail key")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Chain HMAC for an entry following `prev_hmac`
+fn entry_hmac(
+    key: &[u8],
+    prev_hmac: &str,
+    seq: u64,
+    timestamp: &str,
+    action: &str,
+    detail: &str,
+) -> Result<String> {
+    let message = format!(
+        "{}\n{}\n{}\n{}\n{}",
+        prev_hmac, seq, timestamp, action, detail
+    );
+    Ok(to_hex(&hmac_sha256(key, message.as_bytes())?))
+}
+
+/// An open audit trail file
+pub struct AuditLog {
+    path: PathBuf,
+    key: [u8; 32],
+    file: File,
+    next_seq: u64,
+    last_hmac: String,
+}
+
+impl AuditLog {
+    /// Create (or replace) the trail at `path`, keyed to this collection
+    pub fn create(path: &Path, hostname: &str, timestamp: &str) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context(format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let file = File::create(path).context(format!("Failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            key: derive_key(hostname, timestamp)?,
+            file,
+            next_seq: 0,
+            last_hmac: GENESIS_HMAC.to_string(),
+        })
+    }
+
+    /// Close the trail file, keeping the chain state to [`reopen`](ClosedAuditLog::reopen) it
+    pub fn close(self) -> ClosedAuditLog {
+        ClosedAuditLog {
+            path: self.path,
+            key: self.key,
+            next_seq: self.next_seq,
+            last_hmac: self.last_hmac,
+        }
+    }
+
+    /// Append an entry stamped with the current time
+    pub fn append(&mut self, action: &str, detail: &str) -> Result<AuditEntry> {
+        self.append_at(&chrono::Utc::now().to_rfc3339(), action, detail)
+    }
+
+    /// Append an entry with an explicit timestamp
+    fn append_at(&mut self, timestamp: &str, action: &str, detail: &str) -> Result<AuditEntry> {
+        let entry = AuditEntry {
+            seq: self.next_seq,
+            timestamp: timestamp.to_string(),
+            action: action.to_string(),
+            detail: detail.to_string(),
+            hmac_sha256_of_prev: entry_hmac(
+                &self.key,
+                &self.last_hmac,
+                self.next_seq,
+                timestamp,
+                action,
+                detail,
+            )?,
+        };
+
+        let mut line = serde_json::to_string(&entry).context("Failed to serialize audit entry")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .context(format!("Failed to write {}", self.path.display()))?;
+
+        self.next_seq += 1;
+        self.last_hmac = entry.hmac_sha256_of_prev.clone();
+        Ok(entry)
+    }
+
+    /// Verify this trail as currently written
+    pub fn verify(&self) -> Result<VerificationResult> {
+        verify_with_key(&self.path, &self.key)
+    }
+
+    /// Verify a trail written for `hostname` and `timestamp`.
+    ///
+    /// For reviewing a collected trail; the collector itself verifies its
+    /// open trail with [`verify`](Self::verify).
+    #[allow(dead_code)]
+    pub fn verify_file(path: &Path, hostname: &str, timestamp: &str) -> Result<VerificationResult> {
+        verify_with_key(path, &derive_key(hostname, timestamp)?)
+    }
+}
+
+/// A trail whose file is closed, e.g. while its directory is moved
+pub struct ClosedAuditLog {
+    path: PathBuf,
+    key: [u8; 32],
+    next_seq: u64,
+    last_hmac: String,
+}
+
+impl ClosedAuditLog {
+    /// Path the trail was at when it was closed
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reopen the trail, now at `path`, and continue its chain
+    pub fn reopen(self, path: &Path) -> Result<AuditLog> {
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .context(format!("Failed to reopen {}", path.display()))?;
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            key: self.key,
+            file,
+            next_seq: self.next_seq,
+            last_hmac: self.last_hmac,
+        })
+    }
+}
+
+fn verify_with_key(path: &Path, key: &[u8]) -> Result<VerificationResult> {
+    let file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let mut result = VerificationResult {
+        entries: 0,
+        valid: true,
+        first_invalid_seq: None,
+        reason: None,
+    };
+    let mut prev_hmac = GENESIS_HMAC.to_string();
+
+    for line in BufReader::new(file).lines() {
+        let line = line.context(format!("Failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let expected_seq = result.entries;
+        result.entries += 1;
+        if !result.valid {
+            continue;
+        }
+
+        let failure = match serde_json::from_str::<AuditEntry>(&line) {
+            Err(e) => Some(format!("entry {} is not valid JSON: {}", expected_seq, e)),
+            Ok(entry) if entry.seq != expected_seq => Some(format!(
+                "expected entry {}, found entry {}",
+                expected_seq, entry.seq
+            )),
+            Ok(entry) => {
+                let hmac = entry_hmac(
+                    key,
+                    &prev_hmac,
+                    entry.seq,
+                    &entry.timestamp,
+                    &entry.action,
+                    &entry.detail,
+                )?;
+                if hmac == entry.hmac_sha256_of_prev {
+                    prev_hmac = hmac;
+                    None
+                } else {
+                    Some(format!("HMAC mismatch at entry {}", expected_seq))
+                }
+            }
+        };
+
+        if let Some(reason) = failure {
+            result.valid = false;
+            result.first_invalid_seq = Some(expected_seq);
+            result.reason = Some(reason);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Process-global trail state
+#[derive(Default)]
+struct AuditTrail {
+    log: Option<AuditLog>,
+    /// (timestamp, action, detail) recorded before the trail was started
+    pending: Vec<(String, String, String)>,
+    dropped: usize,
+}
+
+/// Start the process-global trail in `output_dir`.
+///
+/// Entries recorded before this call follow the `trail_started` entry, with
+/// the times they were recorded. Returns the trail path.
+pub fn start(output_dir: &Path, hostname: &str, timestamp: &str) -> Result<PathBuf> {
+    let path = output_dir.join(AUDIT_LOG_FILE);
+    let mut log = AuditLog::create(&path, hostname, timestamp)?;
+
+    let mut trail = AUDIT_TRAIL
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Audit trail lock poisoned"))?;
+    let mut detail = format!("hostname={} timestamp={}", hostname, timestamp);
+    if trail.dropped > 0 {
+        detail.push_str(&format!(
+            " ({} earlier entries dropped before the trail started)",
+            trail.dropped
+        ));
+    }
+    log.append(&AuditAction::TrailStarted.to_string(), &detail)?;
+    trail.dropped = 0;
+    resume_with(&mut trail, log)?;
+
+    info!("Audit trail started at {}", path.display());
+    Ok(path)
+}
+
+/// Move the directory holding the process-global trail.
+///
+/// The trail file is closed while `move_dir` runs, since Windows cannot
+/// rename a directory with open files, and reopened in the directory
+/// `move_dir` returns (or where it was, if the move fails). Entries recorded
+/// meanwhile are held as before [`start`].
+pub fn relocate<F>(move_dir: F) -> Result<PathBuf>
+where
+    F: FnOnce() -> Result<PathBuf>,
+{
+    let closed = AUDIT_TRAIL
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Audit trail lock poisoned"))?
+        .log
+        .take()
+        .map(AuditLog::close);
+    let moved = move_dir();
+
+    if let Some(closed) = closed {
+        let file_name = closed
+            .path()
+            .file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(AUDIT_LOG_FILE));
+        let path = match &moved {
+            Ok(dir) => dir.join(file_name),
+            Err(_) => closed.path().to_path_buf(),
+        };
+        let log = closed.reopen(&path)?;
+        let mut trail = AUDIT_TRAIL
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Audit trail lock poisoned"))?;
+        resume_with(&mut trail, log)?;
+    }
+    moved
+}
+
+/// Write the held entries to `log` and make it the trail's log
+fn resume_with(trail: &mut AuditTrail, mut log: AuditLog) -> Result<()> {
+    for (timestamp, action, detail) in std::mem::take(&mut trail.pending) {
+        log.append_at(&timestamp, &action, &detail)?;
+    }
+    trail.log = Some(log);
+    Ok(())
+}
+
+/// Record an action in the process-global trail.
+///
+/// Write failures are logged; auditing never fails the collection.
+pub fn record(action: AuditAction, detail: &str) {
+    let Ok(mut guard) = AUDIT_TRAIL.lock() else {
+        return;
+    };
+    let trail = &mut *guard;
+    let action = action.to_string();
+    match trail.log.as_mut() {
+        Some(log) => {
+            if let Err(e) = log.append(&action, detail) {
+                warn!("Failed to write audit entry: {:#}", e);
+            }
+        }
+        None if trail.pending.len() < MAX_PENDING_ENTRIES => {
+            let timestamp = chrono::Utc::now().to_rfc3339();
+            trail.pending.push((timestamp, action, detail.to_string()));
+        }
+        None => trail.dropped += 1,
+    }
+}
+
+/// Verify the process-global trail; `None` if it was never started
+pub fn verify_trail() -> Result<Option<VerificationResult>> {
+    let trail = AUDIT_TRAIL
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Audit trail lock poisoned"))?;
+    trail.log.as_ref().map(AuditLog::verify).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_hmac_and_hkdf_vectors() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 4231 test case 6 (key longer than the block size)
+        assert_eq!(
+            to_hex(
+                &hmac_sha256(
+                    &[0xaa; 131],
+                    b"Test Using Larger Than Block-Size Key - Hash Key First"
+                )
+                .unwrap()
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        // RFC 5869 test case 1, first output block
+        assert_eq!(
+            to_hex(
+                &hkdf_sha256(
+                    &hex("000102030405060708090a0b0c"),
+                    &[0x0b; 22],
+                    &hex("f0f1f2f3f4f5f6f7f8f9")
+                )
+                .unwrap()
+            ),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf"
+        );
+    }
+
+    fn write_trail(path: &Path) -> AuditLog {
+        let mut log = AuditLog::create(path, "host1", "20240102-030405").unwrap();
+        log.append("file_open", "/etc/passwd").unwrap();
+        log.append("file_write", "/out/etc/passwd (1234 bytes)")
+            .unwrap();
+        log.append("upload_attempt", "s3://bucket/prefix/host1.zip")
+            .unwrap();
+        log
+    }
+
+    #[test]
+    fn test_verify_intact_trail() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(AUDIT_LOG_FILE);
+        let log = write_trail(&path);
+
+        let result = log.verify().unwrap();
+        assert!(result.valid);
+        assert_eq!(result.entries, 3);
+        assert_eq!(result.first_invalid_seq, None);
+
+        let offline = AuditLog::verify_file(&path, "host1", "20240102-030405").unwrap();
+        assert_eq!(offline, result);
+
+        let wrong_key = AuditLog::verify_file(&path, "host2", "20240102-030405").unwrap();
+        assert!(!wrong_key.valid);
+        assert_eq!(wrong_key.first_invalid_seq, Some(0));
+    }
+
+    #[test]
+    fn test_reopen_after_move_continues_chain() {
+        let temp = TempDir::new().unwrap();
+        let staging = temp.path().join(".inprogress-1");
+        let log = write_trail(&staging.join(AUDIT_LOG_FILE));
+
+        let closed = log.close();
+        let done = temp.path().join("host1");
+        fs::rename(&staging, &done).unwrap();
+        let mut log = closed.reopen(&done.join(AUDIT_LOG_FILE)).unwrap();
+        log.append("output_finalized", "host1").unwrap();
+
+        let result = log.verify().unwrap();
+        assert!(result.valid);
+        assert_eq!(result.entries, 4);
+    }
+
+    #[test]
+    fn test_verify_detects_modified_entry() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(AUDIT_LOG_FILE);
+        let log = write_trail(&path);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("1234 bytes", "12 bytes")).unwrap();
+
+        let result = log.verify().unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.entries, 3);
+        assert_eq!(result.first_invalid_seq, Some(1));
+        assert!(result.reason.unwrap().contains("HMAC mismatch"));
+    }
+
+    #[test]
+    fn test_verify_detects_deleted_entry() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(AUDIT_LOG_FILE);
+        let log = write_trail(&path);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let kept: Vec<&str> = content
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, line)| line)
+            .collect();
+        fs::write(&path, kept.join("\n")).unwrap();
+
+        let result = log.verify().unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.first_invalid_seq, Some(1));
+    }
+
+    #[test]
+    fn test_action_names() {
+        assert_eq!(
+            AuditAction::PrivilegeEscalation.to_string(),
+            "privilege_escalation"
+        );
+        assert_eq!(AuditAction::FileOpen.to_string(), "file_open");
+        assert_eq!(AuditAction::FileWrite.to_string(), "file_write");
+        assert_eq!(AuditAction::UploadAttempt.to_string(), "upload_attempt");
+    }
+}
@@ -6,11 +6,16 @@
 //! - Privilege management helpers
 //! - Security configuration and policies
 //! - Credential scrubbing to prevent sensitive data exposure
+//! - A tamper-evident audit trail of the collector's own actions
 
+pub mod audit_log;
 pub mod config;
 pub mod credential_scrubber;
 pub mod path_validator;
 
 pub use config::{log_security_event, SecurityConfig, SecurityEvent};
 pub use credential_scrubber::{safe_error_message, scrub_credentials, scrub_path};
-pub use path_validator::{sanitize_filename, validate_output_path, validate_path};
+pub use path_validator::{
+    sanitize_filename, validate_archive_entry_name, validate_destination, validate_output_path,
+    validate_path,
+};
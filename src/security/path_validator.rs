@@ -5,7 +5,7 @@
 //! access to unauthorized locations.
 
 use anyhow::{anyhow, bail, Context, Result};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 /// Validates that a path is safe and doesn't contain directory traversal attempts.
 ///
@@ -208,6 +208,124 @@ pub fn validate_output_path(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Validates that a computed destination stays inside `base_dir`.
+///
+/// Unlike [`validate_path`], which rejects every `..`, this accepts a path
+/// built by joining untrusted configuration onto `base_dir` as long as it
+/// still lands inside `base_dir` once `.` and `..` are resolved. The deepest
+/// part of the result that already exists is then canonicalized, so a
+/// symlinked intermediate directory cannot redirect the write elsewhere.
+///
+/// # Arguments
+///
+/// * `path` - The destination path, normally `base_dir` joined with a
+///   relative path
+/// * `base_dir` - The directory the destination must stay within
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The normalized destination
+/// * `Err` - If the destination escapes `base_dir` or is unsafe for output
+///
+/// # Security
+///
+/// This function rejects:
+/// - `..` sequences that climb above `base_dir`
+/// - Absolute paths, which replace `base_dir` when joined onto it
+/// - Windows drive-relative paths such as `C:evil`
+/// - Symbolic links, including dangling ones, that resolve outside `base_dir`
+pub fn validate_destination(path: &Path, base_dir: &Path) -> Result<PathBuf> {
+    if path.to_string_lossy().contains('\0') {
+        bail!("Path contains null bytes");
+    }
+
+    let relative = path
+        .strip_prefix(base_dir)
+        .map_err(|_| anyhow!("Path escapes base directory: {}", path.display()))?;
+
+    let mut normalized = base_dir.to_path_buf();
+    let mut depth = 0usize;
+    for (index, component) in relative.components().enumerate() {
+        match component {
+            Component::Normal(name) => {
+                if index == 0 && is_drive_relative(&name.to_string_lossy()) {
+                    bail!("Drive-relative path not allowed: {}", path.display());
+                }
+                normalized.push(name);
+                depth += 1;
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    bail!("Path traversal attempt detected: {}", path.display());
+                }
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                bail!("Absolute path not allowed: {}", path.display());
+            }
+        }
+    }
+
+    validate_output_path(&normalized)?;
+
+    // Resolve the deepest existing ancestor; symlink_metadata also stops at
+    // dangling links, which canonicalize then rejects
+    let base_canonical = base_dir
+        .canonicalize()
+        .context("Failed to canonicalize base directory")?;
+    let mut existing = normalized.as_path();
+    while existing.symlink_metadata().is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break,
+        }
+    }
+    let resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", existing.display()))?;
+    if !resolved.starts_with(&base_canonical) {
+        bail!(
+            "Path escapes base directory through a symbolic link: {}",
+            path.display()
+        );
+    }
+
+    Ok(normalized)
+}
+
+/// Validates an archive entry name before it is written.
+///
+/// Entry names are joined onto the extraction directory by whoever unpacks
+/// the archive, so names that are absolute, carry a drive letter, or contain
+/// `..` segments would let the archive write outside it ("zip slip").
+/// Both `/` and `\` are treated as separators because extractors differ.
+pub fn validate_archive_entry_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Archive entry name is empty");
+    }
+    if name.contains('\0') {
+        bail!("Archive entry name contains null bytes: {:?}", name);
+    }
+    if name.starts_with('/') || name.starts_with('\\') {
+        bail!("Archive entry name is absolute: {}", name);
+    }
+    if is_drive_relative(name) {
+        bail!("Archive entry name has a drive prefix: {}", name);
+    }
+    if name.split(['/', '\\']).any(|segment| segment == "..") {
+        bail!("Archive entry name contains '..': {}", name);
+    }
+    Ok(())
+}
+
+/// Whether `name` starts with a Windows drive prefix such as `C:`
+fn is_drive_relative(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,4 +421,104 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_validate_destination_inside_base() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        let dest = validate_destination(&base.join("etc/./passwd"), base).unwrap();
+        assert_eq!(dest, base.join("etc/passwd"));
+
+        // `..` that stays inside the base is normalized away
+        let dest = validate_destination(&base.join("etc/../var/log"), base).unwrap();
+        assert_eq!(dest, base.join("var/log"));
+    }
+
+    #[test]
+    fn test_validate_destination_rejects_parent_traversal() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        for attempt in [
+            "../../../../etc/cron.d/evil",
+            "etc/../../escape",
+            "./a/b/../../../escape",
+        ] {
+            assert!(
+                validate_destination(&base.join(attempt), base).is_err(),
+                "Failed to catch escape attempt: {}",
+                attempt
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_destination_rejects_absolute_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        // Joining an absolute name replaces the base entirely
+        #[allow(clippy::join_absolute_paths)]
+        let joined = base.join("/etc/cron.d/evil");
+        assert!(validate_destination(&joined, base).is_err());
+        assert!(validate_destination(Path::new("/tmp/elsewhere"), base).is_err());
+    }
+
+    #[test]
+    fn test_validate_destination_rejects_drive_relative() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        assert!(validate_destination(&base.join("C:evil.dll"), base).is_err());
+        assert!(validate_destination(&base.join("d:\\Windows\\evil"), base).is_err());
+        // A colon later in the name is not a drive prefix
+        assert!(validate_destination(&base.join("logs/10:30.txt"), base).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_destination_rejects_symlinked_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        std::os::unix::fs::symlink(outside.path(), base.join("link")).unwrap();
+        assert!(validate_destination(&base.join("link/evil"), base).is_err());
+        assert!(validate_destination(&base.join("link/new/dir/evil"), base).is_err());
+
+        // Dangling links would be followed when the file is created
+        std::os::unix::fs::symlink(outside.path().join("missing"), base.join("dangling")).unwrap();
+        assert!(validate_destination(&base.join("dangling"), base).is_err());
+
+        // Links that stay inside the base are fine
+        std::fs::create_dir(base.join("real")).unwrap();
+        std::os::unix::fs::symlink(base.join("real"), base.join("inner")).unwrap();
+        assert!(validate_destination(&base.join("inner/file"), base).is_ok());
+    }
+
+    #[test]
+    fn test_validate_archive_entry_name() {
+        assert!(validate_archive_entry_name("fs/etc/passwd").is_ok());
+        assert!(validate_archive_entry_name("fs/logs/").is_ok());
+        assert!(validate_archive_entry_name("fs/..hidden").is_ok());
+
+        for attempt in [
+            "",
+            "../evil",
+            "fs/../../evil",
+            "fs\\..\\..\\evil",
+            "/etc/cron.d/evil",
+            "\\Windows\\evil",
+            "C:evil",
+            "c:\\Windows\\evil",
+            "fs/evil\0",
+        ] {
+            assert!(
+                validate_archive_entry_name(attempt).is_err(),
+                "Failed to catch zip slip attempt: {:?}",
+                attempt
+            );
+        }
+    }
 }
@@ -48,7 +48,7 @@ pub fn create_test_file_structure() -> Result<TempDir> {
 /// Creates a test YAML configuration file
 pub fn create_test_config() -> Result<NamedTempFile> {
     let config_content = r#"
-version: "1.0"
+version: "1.1"
 description: "Test configuration"
 global_options:
   max_file_size_mb: "100"
@@ -92,6 +92,8 @@ pub mod generators {
             accessed_time: Some(Utc::now().to_rfc3339()),
             modified_time: Some(Utc::now().to_rfc3339()),
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         }
     }
 
@@ -108,6 +110,7 @@ pub mod generators {
                 brand: Some("Test CPU".to_string()),
                 frequency: 3600,
             },
+            time_context: None,
         }
     }
 
@@ -178,9 +181,13 @@ pub mod assertions {
 /// Mock filesystem utilities
 pub mod mock_fs {
     use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
     use std::collections::HashMap;
+    use std::io::{self, Cursor, Read};
     use std::path::{Path, PathBuf};
 
+    use crate::collectors::source::{ArtifactSource, SourceMetadata};
+
     /// Mock filesystem for testing
     pub struct MockFileSystem {
         files: HashMap<PathBuf, Vec<u8>>,
@@ -271,6 +278,45 @@ pub mod mock_fs {
         }
     }
 
+    /// In-memory artifact source; every path is a file or a directory
+    #[async_trait]
+    impl ArtifactSource for MockFileSystem {
+        fn scheme(&self) -> &str {
+            "mock"
+        }
+
+        async fn stat(&self, path: &Path) -> io::Result<SourceMetadata> {
+            if let Some(content) = self.files.get(path) {
+                Ok(SourceMetadata {
+                    len: content.len() as u64,
+                    ..Default::default()
+                })
+            } else if self.directories.iter().any(|d| d == path) {
+                Ok(SourceMetadata {
+                    is_dir: true,
+                    ..Default::default()
+                })
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} not found", path.display()),
+                ))
+            }
+        }
+
+        async fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+            MockFileSystem::list_dir(self, path)
+                .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))
+        }
+
+        async fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+            let content = self
+                .read_file(path)
+                .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+            Ok(Box::new(Cursor::new(content)))
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -0,0 +1,483 @@
+//! Atomic finalization of the collection output directory.
+//!
+//! When writing straight to an attached drive or a network share, a pulled
+//! cable or a killed process can leave a half-written collection that looks
+//! like a finished one. With `--atomic-output` the collection is written to
+//! `<output>/.inprogress-<collection_id>/` and only renamed to its final name
+//! once the summary and archive are complete, so every directory without the
+//! prefix is a finished collection.
+//!
+//! On the next start, staging directories left behind by an interrupted run
+//! of this host are renamed to `.failed-<collection_id>` with a note saying
+//! what happened. Directories still being written by a live collector, or by
+//! another host sharing the output, are left alone.
+//!
+//! The output volume's filesystem type is also detected here, so the archive
+//! can be split into parts on FAT volumes, which cannot hold files of 4 GiB
+//! or more.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sysinfo::{DiskExt, Pid, PidExt, ProcessExt, System, SystemExt};
+use walkdir::WalkDir;
+
+use crate::utils::hash::calculate_sha256;
+
+/// Name prefix of a collection that is still being written
+pub const INPROGRESS_PREFIX: &str = ".inprogress-";
+
+/// Name prefix of a quarantined, interrupted collection
+pub const FAILED_PREFIX: &str = ".failed-";
+
+/// Note written into a quarantined collection
+pub const QUARANTINE_NOTE_FILE: &str = "QUARANTINED.txt";
+
+/// SHA-256 manifest written into the collection before it is finalized
+pub const OUTPUT_MANIFEST_FILE: &str = "output_manifest.sha256";
+
+/// Archive part size used on FAT volumes, safely below the FAT32 limit of
+/// 4 GiB - 1 byte per file
+pub const FAT_ARCHIVE_PART_SIZE: u64 = 4000 * 1024 * 1024;
+
+/// Ownership marker written next to a staging directory as
+/// `.inprogress-<collection_id>.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct StagingMarker {
+    collection_id: String,
+    hostname: String,
+    /// Directory name the collection is renamed to when finalized
+    final_name: String,
+    pid: u32,
+    /// Start time of the collector process (seconds since the epoch), so a
+    /// reused PID is not mistaken for the collector
+    process_start_time: u64,
+    started: String,
+}
+
+/// A collection being written to a staging directory
+#[derive(Debug)]
+pub struct StagedOutput {
+    output_root: PathBuf,
+    dir: PathBuf,
+    marker_path: PathBuf,
+    final_name: String,
+    fallback_name: String,
+}
+
+impl StagedOutput {
+    /// Create `<output_root>/.inprogress-<collection_id>/` for a collection
+    /// of `hostname`.
+    ///
+    /// It is finalized as `<output_root>/<hostname>`, or as
+    /// `<hostname>-<timestamp>` if that already exists.
+    pub fn create(output_root: &Path, hostname: &str, timestamp: &str) -> Result<Self> {
+        let collection_id = uuid::Uuid::new_v4().simple().to_string();
+        let dir = output_root.join(format!("{}{}", INPROGRESS_PREFIX, collection_id));
+        let marker_path = marker_path_for(&dir);
+
+        let marker = StagingMarker {
+            collection_id,
+            hostname: hostname.to_string(),
+            final_name: hostname.to_string(),
+            pid: std::process::id(),
+            process_start_time: process_start_time(std::process::id()).unwrap_or_default(),
+            started: chrono::Utc::now().to_rfc3339(),
+        };
+        fs::create_dir_all(&dir).context(format!(
+            "Failed to create staging directory {}",
+            dir.display()
+        ))?;
+        fs::write(&marker_path, serde_json::to_string_pretty(&marker)?)
+            .context(format!("Failed to write {}", marker_path.display()))?;
+
+        info!("Writing collection to staging directory {}", dir.display());
+        Ok(Self {
+            output_root: output_root.to_path_buf(),
+            dir,
+            marker_path,
+            final_name: hostname.to_string(),
+            fallback_name: format!("{}-{}", hostname, timestamp),
+        })
+    }
+
+    /// Staging directory the collection is written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory the collection will be renamed to
+    pub fn final_dir(&self) -> PathBuf {
+        let preferred = self.output_root.join(&self.final_name);
+        if preferred.exists() {
+            self.output_root.join(&self.fallback_name)
+        } else {
+            preferred
+        }
+    }
+
+    /// Write the SHA-256 manifest into the staging directory if requested,
+    /// then rename it to [`final_dir`](Self::final_dir).
+    ///
+    /// Returns the final directory.
+    pub fn finalize(self, write_manifest: bool) -> Result<PathBuf> {
+        if write_manifest {
+            let manifest = write_output_manifest(&self.dir)?;
+            info!("Output manifest written to {}", manifest.display());
+        }
+
+        let final_dir = self.final_dir();
+        if final_dir.exists() {
+            anyhow::bail!(
+                "Cannot finalize {}: {} already exists",
+                self.dir.display(),
+                final_dir.display()
+            );
+        }
+        fs::rename(&self.dir, &final_dir).context(format!(
+            "Failed to rename {} to {}",
+            self.dir.display(),
+            final_dir.display()
+        ))?;
+        if let Err(e) = fs::remove_file(&self.marker_path) {
+            warn!(
+                "Failed to remove staging marker {}: {}",
+                self.marker_path.display(),
+                e
+            );
+        }
+
+        info!("Collection finalized at {}", final_dir.display());
+        Ok(final_dir)
+    }
+}
+
+/// An interrupted collection moved aside by [`quarantine_interrupted`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedOutput {
+    pub collection_id: String,
+    /// The `.failed-<collection_id>` directory
+    pub path: PathBuf,
+}
+
+/// Quarantine staging directories in `output_root` left behind by an
+/// interrupted collection of `hostname`.
+///
+/// Each is renamed to `.failed-<collection_id>` with a [`QUARANTINE_NOTE_FILE`]
+/// describing the run. Staging directories owned by a collector process that
+/// is still running, by another host, or without a readable marker are left
+/// in place.
+pub fn quarantine_interrupted(
+    output_root: &Path,
+    hostname: &str,
+) -> Result<Vec<QuarantinedOutput>> {
+    let mut quarantined = Vec::new();
+    if !output_root.is_dir() {
+        return Ok(quarantined);
+    }
+
+    let entries =
+        fs::read_dir(output_root).context(format!("Failed to read {}", output_root.display()))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(collection_id) = name.strip_prefix(INPROGRESS_PREFIX) else {
+            continue;
+        };
+        if !path.is_dir() {
+            continue;
+        }
+
+        let marker_path = marker_path_for(&path);
+        let marker = match read_marker(&marker_path) {
+            Ok(marker) => marker,
+            Err(e) => {
+                warn!(
+                    "Leaving {} in place: no readable staging marker ({:#})",
+                    path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+        if marker.hostname != hostname {
+            debug!(
+                "Leaving {}: staged by host {}",
+                path.display(),
+                marker.hostname
+            );
+            continue;
+        }
+        if process_start_time(marker.pid) == Some(marker.process_start_time) {
+            debug!(
+                "Leaving {}: collector PID {} is still running",
+                path.display(),
+                marker.pid
+            );
+            continue;
+        }
+
+        let failed = output_root.join(format!("{}{}", FAILED_PREFIX, collection_id));
+        if failed.exists() {
+            warn!(
+                "Leaving {} in place: {} already exists",
+                path.display(),
+                failed.display()
+            );
+            continue;
+        }
+        fs::rename(&path, &failed).context(format!(
+            "Failed to quarantine {} as {}",
+            path.display(),
+            failed.display()
+        ))?;
+        fs::write(failed.join(QUARANTINE_NOTE_FILE), quarantine_note(&marker)).context(format!(
+            "Failed to write quarantine note in {}",
+            failed.display()
+        ))?;
+        if let Err(e) = fs::remove_file(&marker_path) {
+            warn!(
+                "Failed to remove staging marker {}: {}",
+                marker_path.display(),
+                e
+            );
+        }
+
+        warn!(
+            "Quarantined interrupted collection {} (started {}) as {}",
+            collection_id,
+            marker.started,
+            failed.display()
+        );
+        quarantined.push(QuarantinedOutput {
+            collection_id: collection_id.to_string(),
+            path: failed,
+        });
+    }
+
+    Ok(quarantined)
+}
+
+/// Write `output_manifest.sha256` in `sha256sum` format covering every file
+/// under `dir`, with paths relative to `dir`
+pub fn write_output_manifest(dir: &Path) -> Result<PathBuf> {
+    let manifest_path = dir.join(OUTPUT_MANIFEST_FILE);
+    let mut lines = Vec::new();
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.context(format!("Failed to walk {}", dir.display()))?;
+        if !entry.file_type().is_file() || entry.path() == manifest_path {
+            continue;
+        }
+        let hash = calculate_sha256(entry.path(), u64::MAX / (1024 * 1024))
+            .context(format!("Failed to hash {}", entry.path().display()))?;
+        if let Some(hash) = hash {
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            lines.push(format!("{}  {}\n", hash, relative));
+        }
+    }
+
+    fs::write(&manifest_path, lines.concat())
+        .context(format!("Failed to write {}", manifest_path.display()))?;
+    Ok(manifest_path)
+}
+
+/// Filesystem type of the volume holding `path` as the OS reports it
+/// (e.g. "vfat", "msdos", "FAT32", "ntfs"), if it can be determined
+pub fn filesystem_type(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mut system = System::new();
+    system.refresh_disks_list();
+    system
+        .disks()
+        .iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| String::from_utf8_lossy(d.file_system()).to_string())
+}
+
+/// Whether `fs_type` names a FAT filesystem limited to files below 4 GiB.
+///
+/// exFAT has no such limit.
+pub fn is_fat_filesystem(fs_type: &str) -> bool {
+    matches!(
+        fs_type.to_ascii_lowercase().as_str(),
+        "vfat" | "msdos" | "fat" | "fat12" | "fat16" | "fat32"
+    )
+}
+
+/// Marker path for the staging directory `dir`
+fn marker_path_for(dir: &Path) -> PathBuf {
+    let mut name = dir.as_os_str().to_os_string();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+fn read_marker(path: &Path) -> Result<StagingMarker> {
+    let data = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&data).context(format!("Failed to parse {}", path.display()))
+}
+
+/// Start time of process `pid`, `None` if it is not running
+fn process_start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    if !system.refresh_process(pid) {
+        return None;
+    }
+    system.process(pid).map(|p| p.start_time())
+}
+
+fn quarantine_note(marker: &StagingMarker) -> String {
+    format!(
+        "This collection did not finish and is incomplete.\n\
+         \n\
+         Collection ID: {}\n\
+         Host: {}\n\
+         Intended output: {}\n\
+         Started: {} (PID {})\n\
+         Quarantined: {} (PID {})\n\
+         \n\
+         The collector stopped before the summary and archive were complete,\n\
+         e.g. because it was killed or the output volume was removed. Files\n\
+         here may be truncated. Re-run the collection rather than relying on it.\n",
+        marker.collection_id,
+        marker.hostname,
+        marker.final_name,
+        marker.started,
+        marker.pid,
+        chrono::Utc::now().to_rfc3339(),
+        std::process::id()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Leave a staging directory behind as a killed collector would
+    fn interrupted_run(root: &Path, hostname: &str, pid: u32) -> PathBuf {
+        let dir = root.join(format!("{}dead0001", INPROGRESS_PREFIX));
+        fs::create_dir_all(dir.join("fs")).unwrap();
+        fs::write(dir.join("fs/partial.log"), b"truncated").unwrap();
+        let marker = StagingMarker {
+            collection_id: "dead0001".to_string(),
+            hostname: hostname.to_string(),
+            final_name: hostname.to_string(),
+            pid,
+            process_start_time: 1,
+            started: "2026-01-02T03:04:05+00:00".to_string(),
+        };
+        fs::write(
+            marker_path_for(&dir),
+            serde_json::to_string(&marker).unwrap(),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_stage_and_finalize() {
+        let temp = TempDir::new().unwrap();
+        let staged = StagedOutput::create(temp.path(), "host1", "20260102-030405").unwrap();
+        let staging_dir = staged.dir().to_path_buf();
+        assert!(staging_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(INPROGRESS_PREFIX));
+        fs::create_dir_all(staging_dir.join("logs")).unwrap();
+        fs::write(staging_dir.join("logs/syslog"), b"hello").unwrap();
+
+        let final_dir = staged.finalize(true).unwrap();
+        assert_eq!(final_dir, temp.path().join("host1"));
+        assert!(!staging_dir.exists());
+        assert!(!marker_path_for(&staging_dir).exists());
+        assert_eq!(fs::read(final_dir.join("logs/syslog")).unwrap(), b"hello");
+
+        let manifest = fs::read_to_string(final_dir.join(OUTPUT_MANIFEST_FILE)).unwrap();
+        assert_eq!(
+            manifest,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  logs/syslog\n"
+        );
+
+        // A second collection of the same host does not replace the first
+        let staged = StagedOutput::create(temp.path(), "host1", "20260102-040000").unwrap();
+        let final_dir = staged.finalize(false).unwrap();
+        assert_eq!(final_dir, temp.path().join("host1-20260102-040000"));
+        assert!(!final_dir.join(OUTPUT_MANIFEST_FILE).exists());
+    }
+
+    #[test]
+    fn test_quarantine_interrupted_run() {
+        let temp = TempDir::new().unwrap();
+        // PID 0 is never a collector, so the run is known to be dead
+        let staging_dir = interrupted_run(temp.path(), "host1", 0);
+
+        let quarantined = quarantine_interrupted(temp.path(), "host1").unwrap();
+        assert_eq!(
+            quarantined,
+            vec![QuarantinedOutput {
+                collection_id: "dead0001".to_string(),
+                path: temp.path().join(".failed-dead0001"),
+            }]
+        );
+        assert!(!staging_dir.exists());
+        assert!(!marker_path_for(&staging_dir).exists());
+
+        let failed = temp.path().join(".failed-dead0001");
+        assert_eq!(
+            fs::read(failed.join("fs/partial.log")).unwrap(),
+            b"truncated"
+        );
+        let note = fs::read_to_string(failed.join(QUARANTINE_NOTE_FILE)).unwrap();
+        assert!(note.contains("Collection ID: dead0001"));
+        assert!(note.contains("Started: 2026-01-02T03:04:05+00:00 (PID 0)"));
+
+        // Nothing left to quarantine on the following start
+        assert!(quarantine_interrupted(temp.path(), "host1")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_quarantine_skips_other_hosts_and_live_runs() {
+        let temp = TempDir::new().unwrap();
+        let other = interrupted_run(temp.path(), "host2", 0);
+        assert!(quarantine_interrupted(temp.path(), "host1")
+            .unwrap()
+            .is_empty());
+        assert!(other.exists());
+        fs::remove_dir_all(&other).unwrap();
+
+        // A staging directory of this very process is still being written
+        let live = StagedOutput::create(temp.path(), "host1", "20260102-030405").unwrap();
+        assert!(quarantine_interrupted(temp.path(), "host1")
+            .unwrap()
+            .is_empty());
+        assert!(live.dir().exists());
+
+        // No marker: ownership unknown, so leave it
+        let unmarked = temp.path().join(format!("{}unmarked", INPROGRESS_PREFIX));
+        fs::create_dir_all(&unmarked).unwrap();
+        assert!(quarantine_interrupted(temp.path(), "host1")
+            .unwrap()
+            .is_empty());
+        assert!(unmarked.exists());
+    }
+
+    #[test]
+    fn test_is_fat_filesystem() {
+        for fs_type in ["vfat", "msdos", "FAT32", "FAT", "fat16"] {
+            assert!(is_fat_filesystem(fs_type), "{}", fs_type);
+        }
+        for fs_type in ["exfat", "exFAT", "ntfs", "NTFS", "ext4", "apfs", ""] {
+            assert!(!is_fat_filesystem(fs_type), "{}", fs_type);
+        }
+    }
+}
@@ -3,7 +3,7 @@ use std::fs::{self, File};
 use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Mutex,
 };
 use std::time::UNIX_EPOCH;
@@ -16,6 +16,7 @@ use rayon::prelude::*;
 use walkdir::WalkDir;
 
 use crate::utils::hash::calculate_sha256;
+use crate::utils::path_encoding::bodyfile_path;
 
 /// Get inode number for the file
 #[cfg(unix)]
@@ -92,6 +93,18 @@ fn unix_to_iso8601(timestamp: u64) -> String {
 /// Each line follows the format:
 /// `MD5|name|inode|mode|UID|GID|size|atime|mtime|ctime|crtime`
 pub fn generate_bodyfile(output_path: &Path, options: &HashMap<String, String>) -> Result<()> {
+    generate_bodyfile_until(output_path, options, None).map(|_| ())
+}
+
+/// [`generate_bodyfile`], stopping the walk once `stop`, if given, returns
+/// true.
+///
+/// Returns `Ok(false)` when stopped early, leaving a partial bodyfile.
+pub fn generate_bodyfile_until(
+    output_path: &Path,
+    options: &HashMap<String, String>,
+    stop: Option<&(dyn Fn() -> bool + Sync)>,
+) -> Result<bool> {
     info!("Generating bodyfile at {}", output_path.display());
 
     // Parse configuration options
@@ -150,8 +163,14 @@ pub fn generate_bodyfile(output_path: &Path, options: &HashMap<String, String>)
 
     // Process files in parallel
     let count = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
     walker
         .into_iter()
+        .take_while(|_| {
+            let stop = stop.is_some_and(|stop| stop());
+            stopped.store(stop, Ordering::Relaxed);
+            !stop
+        })
         .filter_map(Result::ok)
         .filter(|entry| {
             let path = entry.path();
@@ -176,11 +195,19 @@ pub fn generate_bodyfile(output_path: &Path, options: &HashMap<String, String>)
             }
         });
 
+    if stopped.load(Ordering::Relaxed) {
+        info!(
+            "Bodyfile generation stopped after {} entries",
+            count.load(Ordering::SeqCst)
+        );
+        return Ok(false);
+    }
+
     info!(
         "Bodyfile generation complete: {} entries",
         count.load(Ordering::SeqCst)
     );
-    Ok(())
+    Ok(true)
 }
 
 /// Create a bodyfile line for a single file with advanced options
@@ -249,7 +276,7 @@ fn create_bodyfile_line_advanced(
     let line = format!(
         "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
         hash,
-        path.to_string_lossy(),
+        bodyfile_path(path),
         get_inode(&metadata),
         get_mode_string(&metadata),
         get_uid(&metadata),
@@ -297,7 +324,7 @@ fn create_bodyfile_line(path: &Path) -> Option<Bodyfile3Line> {
     // Create the bodyfile line using string format
     let line_str = format!(
         "0|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
-        path.to_string_lossy(),
+        bodyfile_path(path),
         get_inode(&metadata),
         get_mode_string(&metadata),
         get_uid(&metadata),
@@ -608,6 +635,23 @@ mod tests {
         assert!(parts[9].contains("T"));
     }
 
+    #[test]
+    fn test_create_bodyfile_line_escapes_name() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join(std::ffi::OsStr::from_bytes(b"a|b\xFF\n.txt"));
+        fs::write(&path, b"x").unwrap();
+
+        let line = create_bodyfile_line_advanced(&path, false, 100, false).unwrap();
+        let parts: Vec<&str> = line.split('|').collect();
+        assert_eq!(parts.len(), 11);
+        assert!(parts[1].ends_with("a\\x7cb\\xff\\x0a.txt"));
+        assert_eq!(parts[6], "1");
+    }
+
     #[test]
     fn test_create_bodyfile_line_nonexistent_file() {
         let path = Path::new("/nonexistent/file.txt");
@@ -724,6 +768,20 @@ mod tests {
         assert_eq!(parts[0], "0"); // Hash should be skipped
     }
 
+    #[test]
+    fn test_generate_bodyfile_until_stops() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("stopped.body");
+
+        let completed =
+            generate_bodyfile_until(&output_path, &HashMap::new(), Some(&|| true)).unwrap();
+        assert!(!completed);
+
+        // Only the header was written before the walk stopped
+        let content = fs::read_to_string(&output_path).unwrap();
+        assert_eq!(content.lines().count(), 1);
+    }
+
     #[test]
     fn test_platform_specific_times() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -1,18 +1,25 @@
+use std::collections::HashSet;
 use std::env;
+use std::ffi::OsString;
 use std::fs;
-use std::io::{BufReader, Read, Write};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
 use crossbeam::channel::{bounded, Receiver, Sender};
 use log::{debug, info};
-use zip::{write::FileOptions, ZipWriter};
+use walkdir::WalkDir;
+use zip::{read::ZipArchive, write::FileOptions, ZipWriter};
 
+use crate::collectors::platforms::sparse::SparseReader;
 use crate::constants::{
     COMPRESSED_EXTENSIONS, COMPRESSION_CHUNK_SIZE as CHUNK_SIZE, LARGE_FILE_COMPRESSION_THRESHOLD,
 };
+use crate::utils::metrics::{self, Measurement};
+use crate::utils::path_encoding::archive_entry_name;
 
 /// File entry with its compression options
 struct FileEntry {
@@ -63,9 +70,9 @@ pub fn get_compression_options(path: &Path) -> FileOptions {
 }
 
 /// Worker function for compression threads
-fn compression_worker(
+fn compression_worker<W: Write + Seek>(
     receiver: Receiver<Option<FileEntry>>,
-    zip: Arc<Mutex<ZipWriter<fs::File>>>,
+    zip: Arc<Mutex<ZipWriter<W>>>,
 ) -> Result<()> {
     // Thread-local buffer to avoid repeated allocations
     let mut buffer = vec![0u8; CHUNK_SIZE];
@@ -79,7 +86,12 @@ fn compression_worker(
                 let file = fs::File::open(&entry.abs_path)
                     .context(format!("Failed to open {}", entry.abs_path.display()))?;
                 let file_size = file.metadata()?.len();
-                let mut reader = BufReader::new(file);
+                // Holes in sparse copies are zero-filled without reading them
+                let sparse_reader = SparseReader::new(file)?;
+                if sparse_reader.is_sparse() {
+                    debug!("Skipping holes while compressing {}", entry.rel_path);
+                }
+                let mut reader = BufReader::new(sparse_reader);
 
                 // Acquire lock only when ready to write to the zip
                 {
@@ -123,6 +135,11 @@ fn compression_worker(
     Ok(())
 }
 
+/// File name of the archive created by [`compress_artifacts`]
+pub fn archive_file_name(hostname: &str, timestamp: &str) -> String {
+    format!("{}-triage-{}.zip", hostname, timestamp)
+}
+
 /// Compress all collected artifacts into a zip file with multithreading.
 ///
 /// This function creates a ZIP archive containing all files from the source directory,
@@ -156,14 +173,73 @@ pub fn compress_artifacts(source_dir: &Path, hostname: &str, timestamp: &str) ->
     let start = Instant::now();
     info!("Compressing artifacts with multithreading...");
 
-    let zip_filename = format!("{}-triage-{}.zip", hostname, timestamp);
-    let zip_path = env::temp_dir().join(zip_filename);
+    let zip_path = env::temp_dir().join(archive_file_name(hostname, timestamp));
 
     // Create zip file
     let zip_file = fs::File::create(&zip_path).context("Failed to create zip file")?;
+    write_archive(source_dir, zip_file)?;
+    if metrics::is_enabled() {
+        record_compression(fs::File::open(&zip_path)?)?;
+    }
+
+    info!(
+        "Compressed artifacts to {} in {:?}",
+        zip_path.display(),
+        start.elapsed()
+    );
+    Ok(zip_path)
+}
+
+/// Compress all collected artifacts into an archive split into numbered parts.
+///
+/// Same as [`compress_artifacts`], but the archive is written as
+/// `<name>.zip.001`, `<name>.zip.002`, ... with no part larger than
+/// `part_size` bytes, for volumes with a per-file size limit such as FAT32.
+/// Concatenating the parts in order restores the ZIP file.
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - Paths of the parts, in order
+/// * `Err` - If compression fails or `part_size` is zero
+pub fn compress_artifacts_split(
+    source_dir: &Path,
+    hostname: &str,
+    timestamp: &str,
+    part_size: u64,
+) -> Result<Vec<PathBuf>> {
+    let start = Instant::now();
+    info!(
+        "Compressing artifacts into parts of at most {} MB...",
+        part_size / (1024 * 1024)
+    );
 
+    let base_path = env::temp_dir().join(archive_file_name(hostname, timestamp));
+    let writer = SplitWriter::new(&base_path, part_size)?;
+    let mut writer = write_archive(source_dir, writer)?;
+    writer.flush().context("Failed to flush archive parts")?;
+    if metrics::is_enabled() {
+        writer.seek(SeekFrom::Start(0))?;
+        record_compression(&mut writer)?;
+    }
+    let parts = writer.part_paths();
+
+    info!(
+        "Compressed artifacts to {} parts at {} in {:?}",
+        parts.len(),
+        base_path.display(),
+        start.elapsed()
+    );
+    Ok(parts)
+}
+
+/// Write a ZIP archive of `source_dir` to `writer` with one compression
+/// worker per CPU (max 8), returning the writer once the archive is finished
+fn write_archive<W>(source_dir: &Path, writer: W) -> Result<W>
+where
+    W: Write + Seek + Send + 'static,
+{
     // Create zip writer and wrap in Arc<Mutex> for thread sharing
-    let zip = Arc::new(Mutex::new(ZipWriter::new(zip_file)));
+    let zip = Arc::new(Mutex::new(ZipWriter::new(writer)));
 
     // Set up crossbeam channels for work distribution
     let (sender, receiver) = bounded::<Option<FileEntry>>(1000);
@@ -210,26 +286,153 @@ pub fn compress_artifacts(source_dir: &Path, hostname: &str, timestamp: &str) ->
     }
 
     // Finalize the zip file
-    {
-        let mut zip = Arc::try_unwrap(zip)
-            .map_err(|_| anyhow::anyhow!("Failed to unwrap Arc"))?
-            .into_inner()
-            .map_err(|e| anyhow::anyhow!("Failed to get inner zip writer: {:?}", e))?;
+    let mut zip = Arc::try_unwrap(zip)
+        .map_err(|_| anyhow::anyhow!("Failed to unwrap Arc"))?
+        .into_inner()
+        .map_err(|e| anyhow::anyhow!("Failed to get inner zip writer: {:?}", e))?;
+
+    // Add all directory entries (after files to avoid conflicts)
+    for dir in dirs {
+        zip.add_directory(dir, FileOptions::default())?;
+    }
 
-        // Add all directory entries (after files to avoid conflicts)
-        for dir in dirs {
-            zip.add_directory(dir, FileOptions::default())?;
+    zip.finish().context("Failed to finalize zip file")
+}
+
+/// Record the original and compressed size of each file in a finished
+/// archive; the sizes are final only once the central directory is written
+fn record_compression<R: Read + Seek>(reader: R) -> Result<()> {
+    let mut archive = ZipArchive::new(reader).context("Failed to read back archive")?;
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        if entry.is_file() {
+            metrics::record_with(|| Measurement::Compression {
+                original_bytes: entry.size(),
+                compressed_bytes: entry.compressed_size(),
+            });
         }
+    }
+    Ok(())
+}
 
-        zip.finish().context("Failed to finalize zip file")?;
+/// Seekable writer spreading one stream across numbered part files.
+///
+/// Part `n` holds bytes `[n * part_size, (n + 1) * part_size)` and is named
+/// `<base>.<n + 1>` zero-padded to three digits. Seeking back into an earlier
+/// part (as the ZIP writer does to patch local headers) rewrites it in place.
+pub struct SplitWriter {
+    base_path: PathBuf,
+    part_size: u64,
+    parts: Vec<fs::File>,
+    position: u64,
+}
+
+impl SplitWriter {
+    /// Writer for parts named after `base_path`; nothing is created until the
+    /// first write
+    pub fn new(base_path: &Path, part_size: u64) -> Result<Self> {
+        if part_size == 0 {
+            anyhow::bail!("Archive part size must be greater than zero");
+        }
+        Ok(Self {
+            base_path: base_path.to_path_buf(),
+            part_size,
+            parts: Vec::new(),
+            position: 0,
+        })
     }
 
-    info!(
-        "Compressed artifacts to {} in {:?}",
-        zip_path.display(),
-        start.elapsed()
-    );
-    Ok(zip_path)
+    /// Paths of the parts created so far, in order
+    pub fn part_paths(&self) -> Vec<PathBuf> {
+        (0..self.parts.len())
+            .map(|index| split_part_path(&self.base_path, index))
+            .collect()
+    }
+
+    /// Part `index`, creating it and any parts before it
+    fn part(&mut self, index: usize) -> io::Result<&mut fs::File> {
+        while self.parts.len() <= index {
+            let path = split_part_path(&self.base_path, self.parts.len());
+            let file = fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?;
+            self.parts.push(file);
+        }
+        Ok(&mut self.parts[index])
+    }
+
+    /// Total length written across all parts
+    fn len(&self) -> io::Result<u64> {
+        match self.parts.last() {
+            Some(last) => {
+                Ok((self.parts.len() as u64 - 1) * self.part_size + last.metadata()?.len())
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+impl Write for SplitWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let index = usize::try_from(self.position / self.part_size)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Archive too large"))?;
+        let offset = self.position % self.part_size;
+        let len = buf.len().min((self.part_size - offset) as usize);
+
+        let part = self.part(index)?;
+        part.seek(SeekFrom::Start(offset))?;
+        let written = part.write(&buf[..len])?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.parts.iter_mut().try_for_each(|part| part.flush())
+    }
+}
+
+impl Read for SplitWriter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Ok(index) = usize::try_from(self.position / self.part_size) else {
+            return Ok(0);
+        };
+        let offset = self.position % self.part_size;
+        let len = buf.len().min((self.part_size - offset) as usize);
+        let Some(part) = self.parts.get_mut(index) else {
+            return Ok(0);
+        };
+        part.seek(SeekFrom::Start(offset))?;
+        let read = part.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SplitWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.len()?.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of archive")
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// Path of part `index` (zero-based) of a split archive
+fn split_part_path(base_path: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(base_path.as_os_str());
+    name.push(format!(".{:03}", index + 1));
+    PathBuf::from(name)
 }
 
 /// Scan directory and queue files for compression
@@ -243,11 +446,7 @@ fn scan_directory(
         let entry = entry?;
         let path = entry.path();
 
-        let rel_path = path
-            .strip_prefix(base_path)
-            .unwrap_or(&path)
-            .to_string_lossy()
-            .to_string();
+        let rel_path = archive_entry_name(path.strip_prefix(base_path).unwrap_or(&path));
 
         if path.is_dir() {
             // Save directory for later addition
@@ -271,6 +470,193 @@ fn scan_directory(
     Ok(())
 }
 
+/// Convert a UTC time to a ZIP (MS-DOS) timestamp, which has two-second
+/// resolution and no zone; `None` outside 1980-2107
+fn to_zip_time(time: DateTime<Utc>) -> Option<zip::DateTime> {
+    zip::DateTime::from_date_and_time(
+        u16::try_from(time.year()).ok()?,
+        time.month() as u8,
+        time.day() as u8,
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+    )
+    .ok()
+}
+
+/// Read a ZIP timestamp as UTC, the zone [`compress_artifacts_incremental`]
+/// writes them in
+fn from_zip_time(time: zip::DateTime) -> Option<DateTime<Utc>> {
+    NaiveDate::from_ymd_opt(
+        i32::from(time.year()),
+        u32::from(time.month()),
+        u32::from(time.day()),
+    )?
+    .and_hms_opt(
+        u32::from(time.hour()),
+        u32::from(time.minute()),
+        u32::from(time.second()),
+    )
+    .map(|t| t.and_utc())
+}
+
+/// Modification time of `path` rounded down to ZIP timestamp resolution
+fn zip_resolution_mtime(path: &Path) -> Result<DateTime<Utc>> {
+    let modified: DateTime<Utc> = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .context(format!(
+            "Failed to read modification time of {}",
+            path.display()
+        ))?
+        .into();
+    let seconds = modified.timestamp();
+    Ok(DateTime::from_timestamp(seconds - seconds.rem_euclid(2), 0).unwrap_or(modified))
+}
+
+/// Update an archive of `artifact_dir` without recompressing unchanged files.
+///
+/// Entries of `existing_zip` are copied into the new archive still
+/// compressed, except for files under `artifact_dir` that changed: those
+/// modified after `since`, or, without `since`, after the entry's timestamp
+/// in the existing archive's central directory. Changed and new files are
+/// compressed and stamped with their modification time in UTC. Entries for
+/// files no longer in `artifact_dir` are kept, so an ongoing collection can
+/// prune its local copies between runs.
+///
+/// # Arguments
+///
+/// * `artifact_dir` - Directory containing artifacts to compress
+/// * `existing_zip` - Archive from the previous run, if there was one
+/// * `hostname` - Hostname to include in the output filename
+/// * `timestamp` - Timestamp string to include in the output filename
+/// * `since` - Treat files modified after this time as changed
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - Path to the new ZIP file, named like [`compress_artifacts`]'s
+/// * `Err` - If the existing archive cannot be read or the new one written
+pub fn compress_artifacts_incremental(
+    artifact_dir: &Path,
+    existing_zip: Option<&Path>,
+    hostname: &str,
+    timestamp: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<PathBuf> {
+    let start = Instant::now();
+    info!("Compressing artifacts incrementally...");
+
+    let zip_path = env::temp_dir().join(archive_file_name(hostname, timestamp));
+    // Written under another name first, as the existing archive may be at `zip_path`
+    let partial_path = zip_path.with_extension("zip.partial");
+    let mut zip =
+        ZipWriter::new(fs::File::create(&partial_path).context("Failed to create zip file")?);
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(artifact_dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let rel_path = archive_entry_name(
+            entry
+                .path()
+                .strip_prefix(artifact_dir)
+                .unwrap_or(entry.path()),
+        );
+        if entry.file_type().is_dir() {
+            dirs.push(format!("{}/", rel_path));
+        } else if entry.file_type().is_file() {
+            files.push((rel_path, entry.into_path()));
+        }
+    }
+
+    let mut written = HashSet::new();
+    let (mut copied, mut replaced) = (0usize, 0usize);
+    if let Some(existing_zip) = existing_zip {
+        let mut archive = ZipArchive::new(
+            fs::File::open(existing_zip)
+                .context(format!("Failed to open {}", existing_zip.display()))?,
+        )
+        .context(format!("Failed to read {}", existing_zip.display()))?;
+
+        let on_disk: std::collections::HashMap<&str, &Path> = files
+            .iter()
+            .map(|(rel, abs)| (rel.as_str(), abs.as_path()))
+            .collect();
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i)?;
+            let name = entry.name().to_string();
+            if let Some(path) = on_disk.get(name.as_str()) {
+                let threshold = since.or_else(|| from_zip_time(entry.last_modified()));
+                let changed = match threshold {
+                    Some(threshold) => zip_resolution_mtime(path)? > threshold,
+                    None => true,
+                };
+                if changed {
+                    replaced += 1;
+                    continue;
+                }
+            }
+            zip.raw_copy_file(entry)
+                .context(format!("Failed to copy {} from the existing archive", name))?;
+            written.insert(name);
+            copied += 1;
+        }
+    }
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut added = 0usize;
+    for (rel_path, abs_path) in &files {
+        if written.contains(rel_path) {
+            continue;
+        }
+        let mut options = get_compression_options(abs_path);
+        if let Some(time) = to_zip_time(zip_resolution_mtime(abs_path)?) {
+            options = options.last_modified_time(time);
+        }
+        let file =
+            fs::File::open(abs_path).context(format!("Failed to open {}", abs_path.display()))?;
+        let mut reader = BufReader::new(SparseReader::new(file)?);
+        zip.start_file(rel_path.clone(), options)
+            .context(format!("Failed to start file entry for {}", rel_path))?;
+        loop {
+            let bytes_read = reader
+                .read(&mut buffer)
+                .context(format!("Failed to read from {}", abs_path.display()))?;
+            if bytes_read == 0 {
+                break;
+            }
+            zip.write_all(&buffer[..bytes_read])
+                .context(format!("Failed to write to zip for {}", rel_path))?;
+        }
+        added += 1;
+    }
+
+    for dir in dirs {
+        if !written.contains(&dir) {
+            zip.add_directory(dir, FileOptions::default())?;
+        }
+    }
+    zip.finish().context("Failed to finalize zip file")?;
+    fs::rename(&partial_path, &zip_path).context(format!(
+        "Failed to move {} to {}",
+        partial_path.display(),
+        zip_path.display()
+    ))?;
+
+    info!(
+        "Incremental archive {}: {} entries kept, {} files compressed ({} replaced) in {:?}",
+        zip_path.display(),
+        copied,
+        added,
+        replaced,
+        start.elapsed()
+    );
+    Ok(zip_path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -403,6 +789,29 @@ mod tests {
         fs::remove_file(zip_path).ok();
     }
 
+    #[test]
+    fn test_compress_sparse_file() {
+        use crate::collectors::platforms::sparse::tests::create_sparse_file;
+
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("UsnJrnl_J");
+        create_sparse_file(&source);
+
+        let timestamp = format!("sparse-test-{}", std::process::id());
+        let zip_path = compress_artifacts(temp_dir.path(), "test-host", &timestamp).unwrap();
+
+        let mut archive = ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let mut contents = Vec::new();
+        archive
+            .by_name("UsnJrnl_J")
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, fs::read(&source).unwrap());
+
+        fs::remove_file(zip_path).ok();
+    }
+
     #[test]
     fn test_compress_artifacts_empty_directory() {
         // Create an empty directory
@@ -633,6 +1042,156 @@ mod tests {
         // Clean up
         fs::remove_file(result).ok();
     }
+
+    #[test]
+    fn test_split_writer_reads_back_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut writer = SplitWriter::new(&temp_dir.path().join("read.zip"), 100).unwrap();
+        {
+            let mut zip = ZipWriter::new(&mut writer);
+            zip.start_file("a.txt", FileOptions::default()).unwrap();
+            zip.write_all(&[b'a'; 500]).unwrap();
+            zip.finish().unwrap();
+        }
+        assert!(writer.part_paths().len() > 1);
+
+        writer.seek(SeekFrom::Start(0)).unwrap();
+        let mut archive = ZipArchive::new(&mut writer).unwrap();
+        let entry = archive.by_index_raw(0).unwrap();
+        assert_eq!(entry.size(), 500);
+        assert!(entry.compressed_size() < 500);
+    }
+
+    #[test]
+    fn test_compress_artifacts_split() {
+        let temp_dir = TempDir::new().unwrap();
+        // Incompressible content so the archive spans several parts
+        let mut state = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..6000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        fs::create_dir_all(temp_dir.path().join("logs")).unwrap();
+        fs::write(temp_dir.path().join("noise.bin"), &noise).unwrap();
+        fs::write(temp_dir.path().join("logs/app.log"), b"split me").unwrap();
+
+        let timestamp = format!("split-{}", std::process::id());
+        let parts =
+            compress_artifacts_split(temp_dir.path(), "split-host", &timestamp, 1024).unwrap();
+
+        assert!(parts.len() > 5);
+        let first = parts[0].file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(first, format!("split-host-triage-{}.zip.001", timestamp));
+
+        let mut joined = Vec::new();
+        for part in &parts {
+            let data = fs::read(part).unwrap();
+            assert!(data.len() <= 1024);
+            joined.extend(data);
+        }
+        let mut archive = ZipArchive::new(std::io::Cursor::new(joined)).unwrap();
+        let mut content = Vec::new();
+        archive
+            .by_name("noise.bin")
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, noise);
+        let mut log = String::new();
+        archive
+            .by_name("logs/app.log")
+            .unwrap()
+            .read_to_string(&mut log)
+            .unwrap();
+        assert_eq!(log, "split me");
+
+        for part in parts {
+            fs::remove_file(part).ok();
+        }
+        assert!(SplitWriter::new(temp_dir.path(), 0).is_err());
+    }
+
+    fn set_mtime(path: &Path, rfc3339: &str) {
+        let time: DateTime<Utc> = DateTime::parse_from_rfc3339(rfc3339).unwrap().into();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time.into())
+            .unwrap();
+    }
+
+    fn read_entry(zip_path: &Path, name: &str) -> String {
+        let mut archive = ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_compress_artifacts_incremental() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("logs")).unwrap();
+        fs::write(base.join("kept.txt"), b"original").unwrap();
+        fs::write(base.join("logs/changed.log"), b"old").unwrap();
+        set_mtime(&base.join("kept.txt"), "2020-01-01T00:00:00Z");
+        set_mtime(&base.join("logs/changed.log"), "2020-01-01T00:00:00Z");
+
+        let hostname = "incremental-host";
+        let first_ts = format!("inc1-{}", std::process::id());
+        let first = compress_artifacts_incremental(base, None, hostname, &first_ts, None).unwrap();
+        assert_eq!(read_entry(&first, "kept.txt"), "original");
+
+        // Same mtime, different content: must come from the old archive
+        fs::write(base.join("kept.txt"), b"rewritten").unwrap();
+        set_mtime(&base.join("kept.txt"), "2020-01-01T00:00:00Z");
+        fs::write(base.join("logs/changed.log"), b"new").unwrap();
+        set_mtime(&base.join("logs/changed.log"), "2099-01-01T00:00:00Z");
+        fs::write(base.join("logs/added.log"), b"added").unwrap();
+        let removed_later = base.join("removed.txt");
+        fs::write(&removed_later, b"gone").unwrap();
+
+        let second_ts = format!("inc2-{}", std::process::id());
+        let second =
+            compress_artifacts_incremental(base, Some(&first), hostname, &second_ts, None).unwrap();
+        assert_eq!(read_entry(&second, "kept.txt"), "original");
+        assert_eq!(read_entry(&second, "logs/changed.log"), "new");
+        assert_eq!(read_entry(&second, "logs/added.log"), "added");
+
+        // Entries for deleted files are kept; `since` overrides the archive times
+        fs::remove_file(&removed_later).unwrap();
+        set_mtime(&base.join("kept.txt"), "2021-06-01T00:00:00Z");
+        let since = DateTime::parse_from_rfc3339("2021-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+        let third_ts = format!("inc3-{}", std::process::id());
+        let third =
+            compress_artifacts_incremental(base, Some(&second), hostname, &third_ts, Some(since))
+                .unwrap();
+        assert_eq!(read_entry(&third, "kept.txt"), "rewritten");
+        assert_eq!(read_entry(&third, "removed.txt"), "gone");
+
+        let archive = ZipArchive::new(fs::File::open(&third).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(
+            names.iter().filter(|n| **n == "logs/changed.log").count(),
+            1
+        );
+        assert!(names.contains(&"logs/"));
+
+        for path in [first, second, third] {
+            fs::remove_file(path).ok();
+        }
+    }
 }
 
 /// Wrapper function for backward compatibility with tests and benchmarks.
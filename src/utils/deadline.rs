@@ -0,0 +1,366 @@
+//! Overall time limit on a collection (`--max-runtime`).
+//!
+//! Some engagements allow a fixed window on a host. With a deadline set, the
+//! collection keeps enough of the window back to compress and upload what it
+//! has collected, and spends the rest collecting:
+//!
+//! - Compression and upload re-read every collected byte. Until they run,
+//!   the only throughput known is that of the copies, so the time reserved
+//!   for them (the tail) is the collected bytes at the measured copy
+//!   throughput, plus a fixed safety margin.
+//! - An artifact copied for `t` seconds grows the tail by about `t` as well,
+//!   so an artifact may use at most half of the time left above the tail.
+//!   That half is its budget; one still running when it runs out is aborted.
+//! - No artifact is dispatched once the budget is under a second. Process
+//!   memory, offline parsing and the bodyfile are skipped the same way, and
+//!   a bodyfile that runs into the cutoff is abandoned.
+//!
+//! Compression and upload always run. What was skipped or aborted because of
+//! the deadline is recorded in the `deadline` section of the collection
+//! summary, apart from artifacts that failed.
+//!
+//! Like the resource governor, the deadline is process-global so each phase
+//! can check it without threading it through every call.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Smallest budget worth dispatching an artifact for
+const MIN_ARTIFACT_BUDGET: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref ACTIVE: Mutex<Option<Arc<Deadline>>> = Mutex::new(None);
+}
+
+/// A collection deadline and what it cut short
+#[derive(Debug)]
+pub struct Deadline {
+    started: Instant,
+    max_runtime: Duration,
+    safety_margin: Duration,
+    /// Bytes copied so far
+    bytes_collected: AtomicU64,
+    /// Time spent copying them, in milliseconds
+    copy_millis: AtomicU64,
+    cut_short: Mutex<DeadlineReport>,
+}
+
+/// What a deadline cut short, for the collection summary
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeadlineReport {
+    pub max_runtime_secs: u64,
+    pub safety_margin_secs: u64,
+    /// Bytes copied before the deadline
+    pub collected_bytes: u64,
+    /// Tail reserved for compression and upload when collection ended
+    pub reserved_tail_secs: f64,
+    /// Seconds into the run at which the first artifact or phase was skipped
+    pub stopped_dispatching_at_secs: Option<f64>,
+    /// Artifacts never started
+    pub skipped_artifacts: Vec<String>,
+    /// Artifacts started but aborted at their budget
+    pub aborted_artifacts: Vec<String>,
+    /// Phases not run, or abandoned (`process_memory`, `offline_parsing`, `bodyfile`)
+    pub skipped_phases: Vec<String>,
+}
+
+impl Deadline {
+    /// A deadline `max_runtime` from now
+    pub fn new(max_runtime: Duration, safety_margin: Duration) -> Self {
+        Self::starting_at(Instant::now(), max_runtime, safety_margin)
+    }
+
+    fn starting_at(started: Instant, max_runtime: Duration, safety_margin: Duration) -> Self {
+        Self {
+            started,
+            max_runtime,
+            safety_margin,
+            bytes_collected: AtomicU64::new(0),
+            copy_millis: AtomicU64::new(0),
+            cut_short: Mutex::new(DeadlineReport {
+                max_runtime_secs: max_runtime.as_secs(),
+                safety_margin_secs: safety_margin.as_secs(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Account for `bytes` copied in `elapsed`; time that produced nothing
+    /// adds nothing to compress or upload
+    pub fn record_copy(&self, bytes: u64, elapsed: Duration) {
+        if bytes == 0 {
+            return;
+        }
+        self.bytes_collected.fetch_add(bytes, Ordering::Relaxed);
+        self.copy_millis
+            .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Time reserved for compression and upload: the collected bytes at
+    /// the measured copy throughput, plus the safety margin
+    pub fn tail_reserve(&self) -> Duration {
+        // At the copy throughput, re-reading the collected bytes takes as
+        // long as copying them did
+        Duration::from_millis(self.copy_millis.load(Ordering::Relaxed)) + self.safety_margin
+    }
+
+    /// Time left before the tail must start, as of `now`
+    fn time_above_tail(&self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.started);
+        self.max_runtime
+            .saturating_sub(elapsed)
+            .saturating_sub(self.tail_reserve())
+    }
+
+    fn budget_at(&self, now: Instant) -> Option<Duration> {
+        let budget = self.time_above_tail(now) / 2;
+        (budget >= MIN_ARTIFACT_BUDGET).then_some(budget)
+    }
+
+    /// How long the next artifact may run, or `None` when there is no time
+    /// left to start one
+    pub fn artifact_budget(&self) -> Option<Duration> {
+        self.budget_at(Instant::now())
+    }
+
+    /// Whether the tail reserve has been reached
+    pub fn past_cutoff(&self) -> bool {
+        self.time_above_tail(Instant::now()).is_zero()
+    }
+
+    /// Record an artifact that was not started
+    pub fn skip_artifact(&self, name: &str) {
+        self.with_report(|report| report.skipped_artifacts.push(name.to_string()));
+    }
+
+    /// Record an artifact aborted at its budget
+    pub fn abort_artifact(&self, name: &str) {
+        self.with_report(|report| report.aborted_artifacts.push(name.to_string()));
+    }
+
+    /// Record a phase that was not run or was abandoned
+    pub fn skip_phase(&self, phase: &str) {
+        warn!("Skipping {} to stay within --max-runtime", phase);
+        self.with_report(|report| report.skipped_phases.push(phase.to_string()));
+    }
+
+    fn with_report(&self, update: impl FnOnce(&mut DeadlineReport)) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if let Ok(mut report) = self.cut_short.lock() {
+            report.stopped_dispatching_at_secs.get_or_insert(elapsed);
+            update(&mut report);
+        }
+    }
+
+    /// What the deadline cut short so far
+    pub fn report(&self) -> DeadlineReport {
+        let mut report = self
+            .cut_short
+            .lock()
+            .map(|report| report.clone())
+            .unwrap_or_default();
+        report.collected_bytes = self.bytes_collected.load(Ordering::Relaxed);
+        report.reserved_tail_secs = self.tail_reserve().as_secs_f64();
+        report
+    }
+}
+
+/// Start the run-wide deadline
+pub fn start(max_runtime: Duration, safety_margin: Duration) {
+    if safety_margin >= max_runtime {
+        warn!(
+            "--max-runtime-margin ({}s) leaves no time of --max-runtime ({}s) for collection",
+            safety_margin.as_secs(),
+            max_runtime.as_secs()
+        );
+    }
+    info!(
+        "Collection deadline in {}s, keeping {}s plus the compression and upload estimate in reserve",
+        max_runtime.as_secs(),
+        safety_margin.as_secs()
+    );
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(Arc::new(Deadline::new(max_runtime, safety_margin)));
+    }
+}
+
+/// Drop the run-wide deadline of an earlier collection
+pub fn clear() {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = None;
+    }
+}
+
+/// The run-wide deadline, if one was started
+pub fn current() -> Option<Arc<Deadline>> {
+    ACTIVE.lock().ok().and_then(|active| active.clone())
+}
+
+/// Whether a phase may start; records it as skipped when it may not
+pub fn allows_phase(phase: &str) -> bool {
+    match current() {
+        Some(deadline) if deadline.artifact_budget().is_none() => {
+            deadline.skip_phase(phase);
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Parse a duration such as `90`, `45s`, `30m`, `2h` or `1h30m`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => {
+                return Err(format!(
+                    "invalid duration '{}': unknown unit '{}'",
+                    value, c
+                ))
+            }
+        };
+        let amount: u64 = digits.parse().map_err(|_| {
+            format!(
+                "invalid duration '{}': expected a number before '{}'",
+                value, c
+            )
+        })?;
+        total += amount * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() || value.is_empty() {
+        return Err(format!(
+            "invalid duration '{}': use e.g. 90s, 30m, 2h or 1h30m",
+            value
+        ));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("30x").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_budget_is_half_the_time_above_the_tail() {
+        let start = Instant::now();
+        let deadline =
+            Deadline::starting_at(start, Duration::from_secs(600), Duration::from_secs(60));
+
+        // Nothing copied yet: only the safety margin is reserved
+        assert_eq!(deadline.tail_reserve(), Duration::from_secs(60));
+        assert_eq!(deadline.budget_at(start), Some(Duration::from_secs(270)));
+
+        // 100 seconds of copying reserves 100 seconds for the tail
+        deadline.record_copy(1000 * MIB, Duration::from_secs(100));
+        // A failed artifact copied nothing and reserves nothing
+        deadline.record_copy(0, Duration::from_secs(30));
+        assert_eq!(deadline.tail_reserve(), Duration::from_secs(160));
+        let now = start + Duration::from_secs(120);
+        assert_eq!(deadline.budget_at(now), Some(Duration::from_secs(160)));
+    }
+
+    #[test]
+    fn test_no_budget_once_the_tail_is_reached() {
+        let start = Instant::now();
+        let deadline =
+            Deadline::starting_at(start, Duration::from_secs(300), Duration::from_secs(30));
+        deadline.record_copy(50 * MIB, Duration::from_secs(100));
+
+        // 300 - 130 reserved leaves 170; at 169s only 1s is left above the tail
+        assert_eq!(deadline.budget_at(start + Duration::from_secs(169)), None);
+        assert!(deadline
+            .budget_at(start + Duration::from_secs(160))
+            .is_some());
+        // Past the end of the window entirely
+        assert_eq!(deadline.budget_at(start + Duration::from_secs(400)), None);
+    }
+
+    #[test]
+    fn test_clear_drops_an_expired_deadline() {
+        start(Duration::ZERO, Duration::ZERO);
+        assert!(!allows_phase("bodyfile"));
+        clear();
+        assert!(current().is_none());
+        assert!(allows_phase("bodyfile"));
+    }
+
+    /// Artifacts copied at a steady throughput, each using its whole budget
+    /// when it is slower than that
+    #[test]
+    fn test_simulated_slow_collection_leaves_the_tail() {
+        let start = Instant::now();
+        let max_runtime = Duration::from_secs(1800);
+        let margin = Duration::from_secs(60);
+        let deadline = Deadline::starting_at(start, max_runtime, margin);
+
+        // 20 MiB/s copies; every artifact would take 5 minutes
+        let throughput = 20 * MIB;
+        let artifact_time = Duration::from_secs(300);
+        let mut now = start;
+        let mut dispatched = 0;
+        while let Some(budget) = deadline.budget_at(now) {
+            let ran = artifact_time.min(budget);
+            deadline.record_copy(throughput * ran.as_secs(), ran);
+            now += ran;
+            dispatched += 1;
+            assert!(dispatched < 100, "dispatching never stopped");
+        }
+
+        // Whatever was collected can still be compressed and uploaded at
+        // the same throughput within the window
+        let tail = deadline.tail_reserve();
+        assert!(now + tail <= start + max_runtime);
+        // and little of the window was left unused
+        assert!(start + max_runtime - (now + tail) <= Duration::from_secs(2));
+        assert!(dispatched >= 3);
+    }
+
+    #[test]
+    fn test_report_lists_what_was_cut_short() {
+        let deadline = Deadline::new(Duration::from_secs(60), Duration::from_secs(10));
+        deadline.skip_artifact("Browser History");
+        deadline.abort_artifact("Event Logs");
+        deadline.skip_phase("bodyfile");
+
+        let report = deadline.report();
+        assert_eq!(report.max_runtime_secs, 60);
+        assert_eq!(report.skipped_artifacts, vec!["Browser History"]);
+        assert_eq!(report.aborted_artifacts, vec!["Event Logs"]);
+        assert_eq!(report.skipped_phases, vec!["bodyfile"]);
+        assert!(report.stopped_dispatching_at_secs.is_some());
+        assert_eq!(report.reserved_tail_secs, 10.0);
+    }
+}
@@ -0,0 +1,698 @@
+//! File type identification from magic bytes.
+//!
+//! With `identify_types: "true"` in `global_options`, the start of each
+//! collected file is matched against a small built-in signature table and
+//! the result recorded as `detected_type` in its metadata. PE, ELF and
+//! Mach-O executables also get a [`BinaryInfo`] read from their headers.
+//!
+//! A file whose extension belongs to a different type than its content is
+//! counted in the summary's `file_types.extension_mismatches`: a "log" that
+//! is a PE, or a `.jpg` that is a ZIP, is a classic sign of a renamed
+//! payload. Extensions the table does not know (such as `libc.so.6`) are
+//! never counted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::collectors::parsers::bytes::{u16_at, u16_be_at, u32_at, u32_be_at, u64_at, u64_be_at};
+use crate::models::{ArtifactMetadata, BinaryInfo};
+
+/// Bytes read from the start of each file; enough for PE and Mach-O headers
+const HEADER_READ_SIZE: u64 = 64 * 1024;
+
+/// A magic number at a fixed offset, and the extensions its type uses
+struct Signature {
+    name: &'static str,
+    offset: usize,
+    magic: &'static [u8],
+    extensions: &'static [&'static str],
+}
+
+const PE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "sys", "scr", "cpl", "ocx", "drv", "efi", "mui", "com", "node", "winmd",
+];
+const ELF_EXTENSIONS: &[&str] = &["so", "ko", "o", "elf", "bin", "axf", "out"];
+const MACHO_EXTENSIONS: &[&str] = &["dylib", "bundle", "so", "o"];
+const JAVA_CLASS_EXTENSIONS: &[&str] = &["class"];
+
+/// Extensions of plain-text files, which no signature matches
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "log", "csv", "json", "jsonl", "xml", "ini", "cfg", "conf", "md", "yml", "yaml", "html",
+    "htm",
+];
+
+/// Checked in order; the first match wins
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        name: "elf",
+        offset: 0,
+        magic: b"\x7fELF",
+        extensions: ELF_EXTENSIONS,
+    },
+    Signature {
+        name: "zip",
+        offset: 0,
+        magic: b"PK\x03\x04",
+        extensions: &[
+            "zip", "jar", "war", "apk", "ipa", "docx", "docm", "xlsx", "xlsm", "pptx", "pptm",
+            "odt", "ods", "odp", "epub", "xpi", "nupkg", "whl", "appx", "msix", "vsix", "kmz",
+        ],
+    },
+    Signature {
+        name: "pdf",
+        offset: 0,
+        magic: b"%PDF-",
+        extensions: &["pdf"],
+    },
+    Signature {
+        name: "gzip",
+        offset: 0,
+        magic: b"\x1f\x8b",
+        extensions: &["gz", "tgz", "gzip"],
+    },
+    Signature {
+        name: "bzip2",
+        offset: 0,
+        magic: b"BZh",
+        extensions: &["bz2", "tbz2"],
+    },
+    Signature {
+        name: "xz",
+        offset: 0,
+        magic: b"\xfd7zXZ\x00",
+        extensions: &["xz", "txz"],
+    },
+    Signature {
+        name: "7z",
+        offset: 0,
+        magic: b"7z\xbc\xaf\x27\x1c",
+        extensions: &["7z"],
+    },
+    Signature {
+        name: "rar",
+        offset: 0,
+        magic: b"Rar!\x1a\x07",
+        extensions: &["rar"],
+    },
+    Signature {
+        name: "png",
+        offset: 0,
+        magic: b"\x89PNG\r\n\x1a\n",
+        extensions: &["png"],
+    },
+    Signature {
+        name: "jpeg",
+        offset: 0,
+        magic: b"\xff\xd8\xff",
+        extensions: &["jpg", "jpeg", "jpe", "jfif"],
+    },
+    Signature {
+        name: "gif",
+        offset: 0,
+        magic: b"GIF8",
+        extensions: &["gif"],
+    },
+    Signature {
+        name: "ole",
+        offset: 0,
+        magic: b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        extensions: &[
+            "doc",
+            "xls",
+            "ppt",
+            "msi",
+            "msp",
+            "mst",
+            "msg",
+            "pub",
+            "vsd",
+            "db",
+            "automaticdestinations-ms",
+        ],
+    },
+    Signature {
+        name: "sqlite",
+        offset: 0,
+        magic: b"SQLite format 3\x00",
+        extensions: &["sqlite", "sqlite3", "db", "db3", "sqlitedb"],
+    },
+    Signature {
+        name: "evtx",
+        offset: 0,
+        magic: b"ElfFile\x00",
+        extensions: &["evtx"],
+    },
+    Signature {
+        name: "evt",
+        offset: 4,
+        magic: b"LfLe",
+        extensions: &["evt"],
+    },
+    Signature {
+        name: "registry_hive",
+        offset: 0,
+        magic: b"regf",
+        extensions: &["dat", "hve", "hiv", "sav"],
+    },
+    Signature {
+        name: "ese_database",
+        offset: 4,
+        magic: b"\xef\xcd\xab\x89",
+        extensions: &["edb", "dat", "sdb"],
+    },
+    Signature {
+        name: "lnk",
+        offset: 0,
+        magic: b"L\x00\x00\x00\x01\x14\x02\x00",
+        extensions: &["lnk"],
+    },
+    Signature {
+        name: "prefetch",
+        offset: 4,
+        magic: b"SCCA",
+        extensions: &["pf"],
+    },
+    Signature {
+        name: "prefetch",
+        offset: 0,
+        magic: b"MAM\x04",
+        extensions: &["pf"],
+    },
+    Signature {
+        name: "pcap",
+        offset: 0,
+        magic: b"\xd4\xc3\xb2\xa1",
+        extensions: &["pcap", "cap"],
+    },
+    Signature {
+        name: "pcap",
+        offset: 0,
+        magic: b"\xa1\xb2\xc3\xd4",
+        extensions: &["pcap", "cap"],
+    },
+    Signature {
+        name: "pcapng",
+        offset: 0,
+        magic: b"\x0a\x0d\x0d\x0a",
+        extensions: &["pcapng"],
+    },
+    Signature {
+        name: "script",
+        offset: 0,
+        magic: b"#!",
+        extensions: &["sh", "bash", "zsh", "py", "pl", "rb", "command"],
+    },
+];
+
+/// Whether `identify_types` is set in `global_options`
+pub fn identify_types_enabled(global_options: &HashMap<String, String>) -> bool {
+    match global_options.get("identify_types") {
+        Some(value) => value.trim().parse::<bool>().unwrap_or_else(|_| {
+            warn!(
+                "Invalid identify_types '{}'; not identifying file types",
+                value
+            );
+            false
+        }),
+        None => false,
+    }
+}
+
+fn u16_endian(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    match big_endian {
+        true => u16_be_at(data, offset),
+        false => u16_at(data, offset),
+    }
+    .ok()
+}
+
+fn u32_endian(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    match big_endian {
+        true => u32_be_at(data, offset),
+        false => u32_at(data, offset),
+    }
+    .ok()
+}
+
+fn u64_endian(data: &[u8], offset: usize, big_endian: bool) -> Option<u64> {
+    match big_endian {
+        true => u64_be_at(data, offset),
+        false => u64_at(data, offset),
+    }
+    .ok()
+}
+
+/// Offset of the `PE\0\0` signature, if `data` is a PE image
+fn pe_header_offset(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(b"MZ") {
+        return None;
+    }
+    let offset = u32_at(data, 0x3c).ok()? as usize;
+    (data.get(offset..offset + 4)? == b"PE\0\0").then_some(offset)
+}
+
+/// Mach-O byte order and word size from the magic number
+fn macho_layout(data: &[u8]) -> Option<(bool, bool)> {
+    match data.get(..4)? {
+        [0xce, 0xfa, 0xed, 0xfe] => Some((false, false)),
+        [0xcf, 0xfa, 0xed, 0xfe] => Some((false, true)),
+        [0xfe, 0xed, 0xfa, 0xce] => Some((true, false)),
+        [0xfe, 0xed, 0xfa, 0xcf] => Some((true, true)),
+        _ => None,
+    }
+}
+
+/// Universal Mach-O and Java class files share `CAFEBABE`; a universal
+/// binary holds a handful of architectures where a class file has its
+/// version (45 or later)
+fn is_universal_macho(data: &[u8]) -> bool {
+    data.starts_with(&[0xca, 0xfe, 0xba, 0xbe])
+        && u32_be_at(data, 4).is_ok_and(|count| (1..20).contains(&count))
+}
+
+/// Type name of the content in `data`, from its first bytes
+pub fn detect_type(data: &[u8]) -> Option<&'static str> {
+    if pe_header_offset(data).is_some() {
+        return Some("pe");
+    }
+    if macho_layout(data).is_some() || is_universal_macho(data) {
+        return Some("macho");
+    }
+    if data.starts_with(&[0xca, 0xfe, 0xba, 0xbe]) {
+        return Some("java_class");
+    }
+    SIGNATURES
+        .iter()
+        .find(|s| data.get(s.offset..s.offset + s.magic.len()) == Some(s.magic))
+        .map(|s| s.name)
+}
+
+/// Extensions used by a detected type
+fn type_extensions(detected: &str) -> impl Iterator<Item = &'static str> + '_ {
+    let executable = match detected {
+        "pe" => PE_EXTENSIONS,
+        "macho" => MACHO_EXTENSIONS,
+        "java_class" => JAVA_CLASS_EXTENSIONS,
+        _ => &[],
+    };
+    SIGNATURES
+        .iter()
+        .filter(move |s| s.name == detected)
+        .flat_map(|s| s.extensions.iter().copied())
+        .chain(executable.iter().copied())
+}
+
+/// Whether `path`'s extension belongs to another type than `detected`.
+///
+/// Files without an extension, or with one the table does not know, never
+/// mismatch.
+pub fn extension_mismatch(path: &str, detected: &str) -> bool {
+    let Some(extension) = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+    else {
+        return false;
+    };
+    let known = TEXT_EXTENSIONS
+        .iter()
+        .chain(PE_EXTENSIONS)
+        .chain(MACHO_EXTENSIONS)
+        .chain(JAVA_CLASS_EXTENSIONS)
+        .chain(SIGNATURES.iter().flat_map(|s| s.extensions))
+        .any(|e| *e == extension);
+    known && !type_extensions(detected).any(|e| e == extension)
+}
+
+fn pe_arch(machine: u16) -> Option<&'static str> {
+    match machine {
+        0x014c => Some("x86"),
+        0x8664 => Some("x86_64"),
+        0x01c0 | 0x01c4 => Some("arm"),
+        0xaa64 => Some("arm64"),
+        0x0200 => Some("ia64"),
+        _ => None,
+    }
+}
+
+fn elf_arch(machine: u16) -> Option<&'static str> {
+    match machine {
+        0x03 => Some("x86"),
+        0x3e => Some("x86_64"),
+        0x28 => Some("arm"),
+        0xb7 => Some("arm64"),
+        0x08 => Some("mips"),
+        0x14 => Some("powerpc"),
+        0x15 => Some("powerpc64"),
+        0x16 => Some("s390"),
+        0xf3 => Some("riscv"),
+        _ => None,
+    }
+}
+
+fn macho_arch(cpu_type: u32) -> Option<&'static str> {
+    match cpu_type {
+        0x0000_0007 => Some("x86"),
+        0x0100_0007 => Some("x86_64"),
+        0x0000_000c => Some("arm"),
+        0x0100_000c => Some("arm64"),
+        0x0200_000c => Some("arm64_32"),
+        0x0000_0012 => Some("powerpc"),
+        0x0100_0012 => Some("powerpc64"),
+        _ => None,
+    }
+}
+
+/// COFF header facts, and the security directory of the optional header
+fn pe_info(data: &[u8]) -> Option<BinaryInfo> {
+    let coff = pe_header_offset(data)? + 4;
+    let machine = u16_at(data, coff).ok()?;
+    let timestamp = u32_at(data, coff + 4).ok()?;
+
+    let optional = coff + 20;
+    let (entry_point, signed) = match u16_at(data, optional).ok() {
+        Some(magic @ (0x10b | 0x20b)) => {
+            // Data directories follow the PE32 or PE32+ fields; the
+            // security directory is the fifth
+            let (count_offset, directories) = match magic {
+                0x10b => (optional + 92, optional + 96),
+                _ => (optional + 108, optional + 112),
+            };
+            let signed = u32_at(data, count_offset)
+                .ok()
+                .filter(|count| *count > 4)
+                .and_then(|_| u32_at(data, directories + 4 * 8 + 4).ok())
+                .map(|size| size > 0);
+            (u32_at(data, optional + 16).ok().map(u64::from), signed)
+        }
+        _ => (None, None),
+    };
+
+    Some(BinaryInfo {
+        format: "pe".to_string(),
+        arch: pe_arch(machine).map(str::to_string),
+        // Reproducible builds store a hash here instead of a time
+        compile_time: (timestamp > 0)
+            .then(|| Utc.timestamp_opt(timestamp as i64, 0).single())
+            .flatten()
+            .map(|t| t.to_rfc3339()),
+        entry_point,
+        signed,
+    })
+}
+
+fn elf_info(data: &[u8]) -> Option<BinaryInfo> {
+    let is_64 = *data.get(4)? == 2;
+    let big_endian = *data.get(5)? == 2;
+    let machine = u16_endian(data, 18, big_endian)?;
+    let entry_point = match is_64 {
+        true => u64_endian(data, 24, big_endian),
+        false => u32_endian(data, 24, big_endian).map(u64::from),
+    };
+    Some(BinaryInfo {
+        format: "elf".to_string(),
+        arch: elf_arch(machine).map(str::to_string),
+        compile_time: None,
+        entry_point: entry_point.filter(|e| *e > 0),
+        signed: None,
+    })
+}
+
+/// Load commands with the entry point and code signature
+const LC_MAIN: u32 = 0x8000_0028;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+fn macho_info(data: &[u8]) -> Option<BinaryInfo> {
+    if is_universal_macho(data) {
+        let count = u32_be_at(data, 4).ok()? as usize;
+        let arches: Vec<&str> = (0..count)
+            .filter_map(|i| u32_be_at(data, 8 + i * 20).ok().and_then(macho_arch))
+            .collect();
+        return Some(BinaryInfo {
+            format: "macho".to_string(),
+            arch: (!arches.is_empty()).then(|| arches.join(",")),
+            compile_time: None,
+            entry_point: None,
+            signed: None,
+        });
+    }
+
+    let (big_endian, is_64) = macho_layout(data)?;
+    let cpu_type = u32_endian(data, 4, big_endian)?;
+    let command_count = u32_endian(data, 16, big_endian)?;
+    let mut offset = if is_64 { 32 } else { 28 };
+
+    let mut entry_point = None;
+    let mut signed = false;
+    for _ in 0..command_count {
+        let (Some(command), Some(size)) = (
+            u32_endian(data, offset, big_endian),
+            u32_endian(data, offset + 4, big_endian),
+        ) else {
+            break;
+        };
+        match command {
+            LC_MAIN => entry_point = u64_endian(data, offset + 8, big_endian),
+            LC_CODE_SIGNATURE => signed = true,
+            _ => {}
+        }
+        if size < 8 {
+            break;
+        }
+        offset += size as usize;
+    }
+
+    Some(BinaryInfo {
+        format: "macho".to_string(),
+        arch: macho_arch(cpu_type).map(str::to_string),
+        compile_time: None,
+        entry_point,
+        signed: Some(signed),
+    })
+}
+
+/// Header facts of a PE, ELF or Mach-O file
+pub fn binary_info(data: &[u8]) -> Option<BinaryInfo> {
+    match detect_type(data)? {
+        "pe" => pe_info(data),
+        "elf" => elf_info(data),
+        "macho" => macho_info(data),
+        _ => None,
+    }
+}
+
+/// Record the detected type and binary header facts of a collected copy.
+///
+/// Directories and unreadable files are left unchanged.
+pub fn identify_collected_file(collected: &Path, metadata: &mut ArtifactMetadata) {
+    if !collected.is_file() {
+        return;
+    }
+    let mut header = Vec::new();
+    let read =
+        File::open(collected).and_then(|file| file.take(HEADER_READ_SIZE).read_to_end(&mut header));
+    if let Err(e) = read {
+        debug!("Cannot identify {}: {}", collected.display(), e);
+        return;
+    }
+    metadata.detected_type = detect_type(&header).map(str::to_string);
+    metadata.binary_info = binary_info(&header);
+}
+
+/// File type results for the collection summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTypeStats {
+    /// Files with a recognised type
+    pub files_identified: usize,
+    /// File counts keyed by detected type
+    pub types: BTreeMap<String, usize>,
+    /// Files whose extension belongs to another type than their content
+    pub extension_mismatches: usize,
+    /// Original paths of those files
+    pub mismatched_files: Vec<String>,
+}
+
+impl FileTypeStats {
+    /// Count detected types and extension mismatches across collected artifacts
+    pub fn from_metadata(artifacts: &[(String, ArtifactMetadata)]) -> Self {
+        let mut stats = FileTypeStats::default();
+        for (_, metadata) in artifacts {
+            let Some(detected) = metadata.detected_type.as_deref() else {
+                continue;
+            };
+            stats.files_identified += 1;
+            *stats.types.entry(detected.to_string()).or_default() += 1;
+            if extension_mismatch(&metadata.original_path, detected) {
+                stats.extension_mismatches += 1;
+                stats.mismatched_files.push(metadata.original_path.clone());
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::generators::test_artifact_metadata;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 2021-01-01T00:00:00Z
+    const LINK_TIME: u32 = 1_609_459_200;
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// PE32+ x86_64 image with a security directory
+    fn pe_fixture() -> Vec<u8> {
+        let mut data = vec![0u8; 0x200];
+        put(&mut data, 0, b"MZ");
+        put(&mut data, 0x3c, &0x80u32.to_le_bytes());
+        put(&mut data, 0x80, b"PE\0\0");
+        let coff = 0x84;
+        put(&mut data, coff, &0x8664u16.to_le_bytes());
+        put(&mut data, coff + 4, &LINK_TIME.to_le_bytes());
+        let optional = coff + 20;
+        put(&mut data, optional, &0x20bu16.to_le_bytes());
+        put(&mut data, optional + 16, &0x1400u32.to_le_bytes());
+        put(&mut data, optional + 108, &16u32.to_le_bytes());
+        put(&mut data, optional + 112 + 32, &0x1e00u32.to_le_bytes());
+        put(&mut data, optional + 112 + 36, &0x2000u32.to_le_bytes());
+        data
+    }
+
+    /// ELF64 little-endian aarch64 executable
+    fn elf_fixture() -> Vec<u8> {
+        let mut data = vec![0u8; 64];
+        put(&mut data, 0, b"\x7fELF\x02\x01\x01");
+        put(&mut data, 16, &2u16.to_le_bytes());
+        put(&mut data, 18, &0xb7u16.to_le_bytes());
+        put(&mut data, 24, &0x40_0580u64.to_le_bytes());
+        data
+    }
+
+    /// 64-bit arm64 Mach-O with `LC_MAIN` and a code signature
+    fn macho_fixture() -> Vec<u8> {
+        let mut data = vec![0u8; 32 + 24 + 16];
+        put(&mut data, 0, &[0xcf, 0xfa, 0xed, 0xfe]);
+        put(&mut data, 4, &0x0100_000cu32.to_le_bytes());
+        put(&mut data, 12, &2u32.to_le_bytes());
+        put(&mut data, 16, &2u32.to_le_bytes());
+        put(&mut data, 20, &40u32.to_le_bytes());
+        put(&mut data, 32, &LC_MAIN.to_le_bytes());
+        put(&mut data, 36, &24u32.to_le_bytes());
+        put(&mut data, 40, &0x3f20u64.to_le_bytes());
+        put(&mut data, 56, &LC_CODE_SIGNATURE.to_le_bytes());
+        put(&mut data, 60, &16u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_binary_info() {
+        let pe = binary_info(&pe_fixture()).unwrap();
+        assert_eq!(pe.format, "pe");
+        assert_eq!(pe.arch.as_deref(), Some("x86_64"));
+        assert_eq!(
+            pe.compile_time.as_deref(),
+            Some("2021-01-01T00:00:00+00:00")
+        );
+        assert_eq!(pe.entry_point, Some(0x1400));
+        assert_eq!(pe.signed, Some(true));
+
+        // An MZ stub with no PE header is not an executable we can read
+        let mut stub = pe_fixture();
+        put(&mut stub, 0x80, b"NE\0\0");
+        assert_eq!(detect_type(&stub), None);
+
+        let elf = binary_info(&elf_fixture()).unwrap();
+        assert_eq!(elf.format, "elf");
+        assert_eq!(elf.arch.as_deref(), Some("arm64"));
+        assert_eq!(elf.entry_point, Some(0x40_0580));
+        assert_eq!(elf.compile_time, None);
+
+        let macho = binary_info(&macho_fixture()).unwrap();
+        assert_eq!(macho.format, "macho");
+        assert_eq!(macho.arch.as_deref(), Some("arm64"));
+        assert_eq!(macho.entry_point, Some(0x3f20));
+        assert_eq!(macho.signed, Some(true));
+
+        let mut universal = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2];
+        universal.resize(48, 0);
+        put(&mut universal, 8, &0x0100_0007u32.to_be_bytes());
+        put(&mut universal, 28, &0x0100_000cu32.to_be_bytes());
+        assert_eq!(
+            binary_info(&universal).unwrap().arch.as_deref(),
+            Some("x86_64,arm64")
+        );
+        // Java class version 52
+        assert_eq!(
+            detect_type(&[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52]),
+            Some("java_class")
+        );
+    }
+
+    #[test]
+    fn test_detect_type() {
+        assert_eq!(detect_type(b"ElfFile\0rest"), Some("evtx"));
+        assert_eq!(detect_type(b"\x11\x00\x00\x00SCCA"), Some("prefetch"));
+        assert_eq!(detect_type(b"PK\x03\x04\x14\x00"), Some("zip"));
+        assert_eq!(detect_type(b"#!/bin/sh\n"), Some("script"));
+        assert_eq!(detect_type(b"Jan 1 00:00:00 host sshd[1]: ..."), None);
+        assert_eq!(detect_type(b""), None);
+
+        assert!(extension_mismatch("/var/log/syslog.log", "elf"));
+        assert!(extension_mismatch(r"C:\Users\bob\Pictures\cat.jpg", "zip"));
+        assert!(extension_mismatch(r"C:\Windows\Temp\svchost.txt", "pe"));
+        assert!(!extension_mismatch(
+            r"C:\Windows\System32\kernel32.DLL",
+            "pe"
+        ));
+        assert!(!extension_mismatch("/usr/lib/libc.so.6", "elf"));
+        assert!(!extension_mismatch("/usr/bin/ls", "elf"));
+        assert!(!extension_mismatch("Thumbs.db", "ole"));
+    }
+
+    #[test]
+    fn test_identify_collected_files() {
+        let temp = TempDir::new().unwrap();
+        let files = [
+            ("update.log", pe_fixture()),
+            ("sshd", elf_fixture()),
+            ("notes.txt", b"meeting at 10".to_vec()),
+        ];
+        let mut artifacts = Vec::new();
+        for (name, data) in &files {
+            let path = temp.path().join(name);
+            fs::write(&path, data).unwrap();
+            let mut metadata = test_artifact_metadata(name);
+            metadata.original_path = format!("/collected/{}", name);
+            identify_collected_file(&path, &mut metadata);
+            artifacts.push((name.to_string(), metadata));
+        }
+        let mut directory = test_artifact_metadata("dir");
+        identify_collected_file(temp.path(), &mut directory);
+        assert_eq!(directory.detected_type, None);
+
+        assert_eq!(artifacts[0].1.detected_type.as_deref(), Some("pe"));
+        assert!(artifacts[0].1.binary_info.is_some());
+        assert_eq!(artifacts[2].1.detected_type, None);
+        assert_eq!(artifacts[2].1.binary_info, None);
+
+        let stats = FileTypeStats::from_metadata(&artifacts);
+        assert_eq!(stats.files_identified, 2);
+        assert_eq!(stats.types["elf"], 1);
+        assert_eq!(stats.extension_mismatches, 1);
+        assert_eq!(stats.mismatched_files, vec!["/collected/update.log"]);
+
+        let options = HashMap::from([("identify_types".to_string(), "true".to_string())]);
+        assert!(identify_types_enabled(&options));
+        assert!(!identify_types_enabled(&HashMap::new()));
+    }
+}
@@ -1,8 +1,8 @@
 use sha2::{Digest, Sha256};
-use std::fs::File;
 use std::io::{self, BufReader, Read};
 use std::path::Path;
 
+use crate::collectors::read_only;
 use crate::constants::DEFAULT_BUFFER_SIZE as BUFFER_SIZE;
 
 /// Calculate SHA-256 hash of a file
@@ -24,8 +24,11 @@ pub fn calculate_sha256(path: &Path, max_size_mb: u64) -> io::Result<Option<Stri
         return Ok(None);
     }
 
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
+    sha256_reader(BufReader::new(read_only::open(path)?)).map(Some)
+}
+
+/// SHA-256 of everything read from `reader`, as lowercase hex
+pub fn sha256_reader<R: Read>(mut reader: R) -> io::Result<String> {
     let mut hasher = Sha256::new();
     let mut buffer = [0; BUFFER_SIZE];
 
@@ -37,10 +40,313 @@ pub fn calculate_sha256(path: &Path, max_size_mb: u64) -> io::Result<Option<Stri
         hasher.update(&buffer[..bytes_read]);
     }
 
-    let hash = hasher.finalize();
-    let hash_string = format!("{:x}", hash);
+    Ok(format!("{:x}", hasher.finalize()))
+}
 
-    Ok(Some(hash_string))
+/// Shannon entropy of a file's bytes, in bits per byte (0 to 8).
+///
+/// Compressed, encrypted and packed data is close to 8; text and most
+/// unpacked executables are well below. An empty file has entropy 0.
+pub fn compute_entropy(path: &Path) -> io::Result<f64> {
+    let mut reader = BufReader::new(std::fs::File::open(path)?);
+    let mut counts = [0u64; 256];
+    let mut buffer = [0; BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        for &byte in &buffer[..bytes_read] {
+            counts[byte as usize] += 1;
+        }
+        total += bytes_read as u64;
+    }
+
+    if total == 0 {
+        return Ok(0.0);
+    }
+    let entropy = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>();
+    Ok(entropy.max(0.0))
+}
+
+#[cfg(feature = "fuzzy_hash")]
+pub use fuzzy::{calculate_ssdeep, is_fuzzy_hash_candidate, FUZZY_HASH_MAX_SIZE_MB};
+
+/// SSDEEP context-triggered piecewise hashes (`fuzzy_hash` feature)
+///
+/// A rolling hash over a 7-byte window marks piece boundaries wherever it
+/// hits `block_size - 1` modulo the block size, and each piece contributes
+/// one base64 character, so an edit to a file only changes the characters
+/// of the pieces it touches. The output is `block_size:sig1:sig2`, where
+/// `sig2` uses twice the block size, in the format `ssdeep` prints and
+/// compares. TLSH is not computed; it needs a crate this build does not
+/// carry.
+#[cfg(feature = "fuzzy_hash")]
+mod fuzzy {
+    use anyhow::{Context, Result};
+    use std::path::Path;
+
+    /// Executables larger than this are not fuzzy hashed (100MB)
+    pub const FUZZY_HASH_MAX_SIZE_MB: u64 = 100;
+
+    /// Extensions of the executables that get a fuzzy hash
+    const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "dll", "sys", "so", "dylib"];
+
+    const ROLLING_WINDOW: usize = 7;
+    const MIN_BLOCK_SIZE: u32 = 3;
+    const SPAMSUM_LENGTH: usize = 64;
+    const HASH_PRIME: u32 = 0x0100_0193;
+    const HASH_INIT: u32 = 0x2802_1967;
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /// Whether `path` has an executable extension (`.exe`, `.dll`, `.sys`,
+    /// `.so`, `.dylib`)
+    pub fn is_fuzzy_hash_candidate(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                EXECUTABLE_EXTENSIONS
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            })
+    }
+
+    /// Calculate the SSDEEP hash of a file
+    ///
+    /// Returns None if the file is larger than `max_size_mb` or is not a
+    /// regular file.
+    pub fn calculate_ssdeep(path: &Path, max_size_mb: u64) -> Result<Option<String>> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        if metadata.len() > max_size_mb * 1024 * 1024 || !metadata.is_file() {
+            return Ok(None);
+        }
+
+        // The block size depends on the total length and may be halved
+        // after a first pass, so the whole file is needed up front
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(ssdeep(&data)))
+    }
+
+    /// SSDEEP hash of `data`
+    pub(super) fn ssdeep(data: &[u8]) -> String {
+        let mut block_size = MIN_BLOCK_SIZE;
+        while (block_size as usize) * SPAMSUM_LENGTH < data.len() {
+            block_size *= 2;
+        }
+
+        loop {
+            let (sig1, sig2) = signatures(data, block_size);
+            // Too few pieces for a useful comparison: try a smaller block
+            if block_size > MIN_BLOCK_SIZE && sig1.len() < SPAMSUM_LENGTH / 2 {
+                block_size /= 2;
+                continue;
+            }
+            return format!("{}:{}:{}", block_size, sig1, sig2);
+        }
+    }
+
+    fn signatures(data: &[u8], block_size: u32) -> (String, String) {
+        let mut roll = RollingHash::default();
+        let mut sig1 = Signature::new(SPAMSUM_LENGTH);
+        let mut sig2 = Signature::new(SPAMSUM_LENGTH / 2);
+
+        for &byte in data {
+            sig1.update(byte);
+            sig2.update(byte);
+            let rolling = roll.update(byte);
+            if rolling % block_size == block_size - 1 {
+                sig1.boundary();
+            }
+            if rolling % (block_size * 2) == block_size * 2 - 1 {
+                sig2.boundary();
+            }
+        }
+
+        let trailing = roll.sum() != 0;
+        (sig1.finish(trailing), sig2.finish(trailing))
+    }
+
+    /// One signature: a character per piece, up to `limit` characters
+    struct Signature {
+        chars: Vec<u8>,
+        limit: usize,
+        hash: u32,
+        /// Character of the latest piece once the signature is full; the
+        /// last character covers the rest of the input
+        last: Option<u8>,
+    }
+
+    impl Signature {
+        fn new(limit: usize) -> Self {
+            Signature {
+                chars: Vec::with_capacity(limit),
+                limit,
+                hash: HASH_INIT,
+                last: None,
+            }
+        }
+
+        fn update(&mut self, byte: u8) {
+            self.hash = self.hash.wrapping_mul(HASH_PRIME) ^ u32::from(byte);
+        }
+
+        fn boundary(&mut self) {
+            let char = BASE64[(self.hash % 64) as usize];
+            if self.chars.len() < self.limit - 1 {
+                self.chars.push(char);
+                self.hash = HASH_INIT;
+            } else {
+                self.last = Some(char);
+            }
+        }
+
+        /// `trailing` is whether input remains after the last boundary
+        fn finish(mut self, trailing: bool) -> String {
+            if trailing {
+                self.chars.push(BASE64[(self.hash % 64) as usize]);
+            } else if let Some(last) = self.last {
+                self.chars.push(last);
+            }
+            // Every character comes from the base64 alphabet
+            self.chars.into_iter().map(char::from).collect()
+        }
+    }
+
+    #[derive(Default)]
+    struct RollingHash {
+        window: [u8; ROLLING_WINDOW],
+        h1: u32,
+        h2: u32,
+        h3: u32,
+        n: usize,
+    }
+
+    impl RollingHash {
+        fn update(&mut self, byte: u8) -> u32 {
+            let c = u32::from(byte);
+            self.h2 = self
+                .h2
+                .wrapping_sub(self.h1)
+                .wrapping_add(ROLLING_WINDOW as u32 * c);
+            self.h1 = self
+                .h1
+                .wrapping_add(c)
+                .wrapping_sub(u32::from(self.window[self.n]));
+            self.window[self.n] = byte;
+            self.n = (self.n + 1) % ROLLING_WINDOW;
+            self.h3 = (self.h3 << 5) ^ c;
+            self.sum()
+        }
+
+        fn sum(&self) -> u32 {
+            self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::fs;
+        use tempfile::TempDir;
+
+        /// Deterministic, incompressible-looking test data
+        fn pseudo_random(len: usize, seed: u32) -> Vec<u8> {
+            let mut state = seed;
+            (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (state >> 16) as u8
+                })
+                .collect()
+        }
+
+        fn parts(hash: &str) -> (u32, String, String) {
+            let mut fields = hash.splitn(3, ':');
+            let block_size = fields.next().unwrap().parse().unwrap();
+            (
+                block_size,
+                fields.next().unwrap().to_string(),
+                fields.next().unwrap().to_string(),
+            )
+        }
+
+        #[test]
+        fn test_ssdeep_format() {
+            assert_eq!(ssdeep(b""), "3::");
+
+            let (block_size, sig1, sig2) = parts(&ssdeep(&pseudo_random(200_000, 1)));
+            assert!(block_size % MIN_BLOCK_SIZE == 0);
+            assert!((block_size / MIN_BLOCK_SIZE).is_power_of_two());
+            assert!(sig1.len() >= SPAMSUM_LENGTH / 2 && sig1.len() <= SPAMSUM_LENGTH);
+            assert!(sig2.len() <= SPAMSUM_LENGTH / 2);
+            assert!(sig1
+                .bytes()
+                .chain(sig2.bytes())
+                .all(|b| BASE64.contains(&b)));
+        }
+
+        #[test]
+        fn test_ssdeep_similar_inputs_share_pieces() {
+            let original = pseudo_random(64 * 1024, 7);
+            let mut edited = original.clone();
+            edited[40_000..40_016].copy_from_slice(b"patched section!");
+
+            let (bs_a, sig_a, _) = parts(&ssdeep(&original));
+            let (bs_b, sig_b, _) = parts(&ssdeep(&edited));
+            assert_eq!(bs_a, bs_b);
+            assert_ne!(sig_a, sig_b);
+            // Only the pieces around the edit change
+            let common_prefix = sig_a
+                .bytes()
+                .zip(sig_b.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            assert!(common_prefix >= sig_a.len() / 2, "{} vs {}", sig_a, sig_b);
+
+            let unrelated = parts(&ssdeep(&pseudo_random(64 * 1024, 8))).1;
+            assert_ne!(sig_a, unrelated);
+        }
+
+        #[test]
+        fn test_calculate_ssdeep_file() {
+            let dir = TempDir::new().unwrap();
+            let path = dir.path().join("tool.exe");
+            let data = pseudo_random(2 * 1024 * 1024, 3);
+            fs::write(&path, &data).unwrap();
+
+            assert_eq!(calculate_ssdeep(&path, 1).unwrap(), None);
+            assert_eq!(calculate_ssdeep(&path, 3).unwrap(), Some(ssdeep(&data)));
+            assert_eq!(calculate_ssdeep(dir.path(), 3).unwrap(), None);
+            assert!(calculate_ssdeep(&dir.path().join("missing.dll"), 3).is_err());
+        }
+
+        #[test]
+        fn test_fuzzy_hash_candidates() {
+            for name in [
+                "cmd.exe",
+                "KERNEL32.DLL",
+                "ntfs.sys",
+                "libc.so",
+                "libz.dylib",
+            ] {
+                assert!(is_fuzzy_hash_candidate(Path::new(name)), "{}", name);
+            }
+            for name in ["notes.txt", "libc.so.6", "exe", "archive.exe.zip"] {
+                assert!(!is_fuzzy_hash_candidate(Path::new(name)), "{}", name);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -68,6 +374,27 @@ mod tests {
         assert_eq!(result, Some(expected.to_string()));
     }
 
+    #[test]
+    fn test_compute_entropy() {
+        let temp = TempDir::new().unwrap();
+        let uniform = temp.path().join("uniform.bin");
+        let all_bytes: Vec<u8> = (0..=255u8).cycle().take(256 * 64).collect();
+        fs::write(&uniform, all_bytes).unwrap();
+        assert!((compute_entropy(&uniform).unwrap() - 8.0).abs() < 1e-9);
+
+        let two_values = temp.path().join("two.bin");
+        fs::write(&two_values, b"abababab").unwrap();
+        assert!((compute_entropy(&two_values).unwrap() - 1.0).abs() < 1e-9);
+
+        let constant = temp.path().join("zeros.bin");
+        fs::write(&constant, vec![0u8; 4096]).unwrap();
+        assert_eq!(compute_entropy(&constant).unwrap(), 0.0);
+
+        let empty = temp.path().join("empty.bin");
+        fs::write(&empty, b"").unwrap();
+        assert_eq!(compute_entropy(&empty).unwrap(), 0.0);
+    }
+
     #[test]
     fn test_calculate_sha256_empty_file() {
         // Create an empty file
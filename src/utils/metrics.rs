@@ -0,0 +1,654 @@
+//! Local performance metrics for tuning collection configs.
+//!
+//! With `--metrics`, the collection pipeline records raw measurements here
+//! and `metrics.json` is written into the output directory at the end:
+//!
+//! - per-phase durations (through the [`PhaseObserver`] callbacks)
+//! - per-artifact elapsed time, bytes, throughput and queue wait (time from
+//!   dispatch to acquiring a concurrency permit)
+//! - per-file compression ratios, read back from the finished archive
+//! - upload progress sampled while archives upload, as throughput over time
+//! - peak resident memory of the collector
+//!
+//! Distributions are summarised as p50/p95/max. Nothing is sent anywhere.
+//!
+//! Like the footprint tracker, the recorder is process-global so the
+//! compressor and uploaders can record without threading state through.
+//! When metrics are off, [`record_with`] is a single relaxed atomic load and
+//! the measurement is never built.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sysinfo::{ProcessExt, System, SystemExt};
+
+use crate::collectors::phases::{PhaseEvent, PhaseEventKind, PhaseObserver};
+
+/// Name of the metrics file written into the output directory
+pub const METRICS_FILE: &str = "metrics.json";
+
+/// Per-artifact records listed in the report, slowest first
+const MAX_LISTED_ARTIFACTS: usize = 50;
+
+static RECORDER: MetricsRecorder = MetricsRecorder::new();
+
+/// Highest resident set size seen when sampling, in bytes
+static SAMPLED_PEAK_RSS: AtomicU64 = AtomicU64::new(0);
+
+/// A raw measurement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Measurement {
+    /// A collection phase boundary
+    Phase {
+        phase: String,
+        kind: PhaseEventKind,
+        at: Instant,
+    },
+    /// One artifact collected (or failed)
+    Artifact {
+        name: String,
+        elapsed: Duration,
+        queue_wait: Duration,
+        bytes: u64,
+        succeeded: bool,
+    },
+    /// One archive entry, read back from the finished archive
+    Compression {
+        original_bytes: u64,
+        compressed_bytes: u64,
+    },
+    /// Cumulative bytes an upload has sent so far
+    UploadProgress {
+        target: String,
+        bytes_uploaded: u64,
+        at: Instant,
+    },
+}
+
+/// Collects measurements while enabled
+pub struct MetricsRecorder {
+    enabled: AtomicBool,
+    started: Mutex<Option<Instant>>,
+    measurements: Mutex<Vec<Measurement>>,
+}
+
+impl Default for MetricsRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsRecorder {
+    /// A disabled recorder
+    pub const fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            started: Mutex::new(None),
+            measurements: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Start a fresh recording, or stop recording. Either way the samples of
+    /// an earlier run are dropped; the report's elapsed time counts from here.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if let Ok(mut measurements) = self.measurements.lock() {
+            measurements.clear();
+        }
+        if let Ok(mut started) = self.started.lock() {
+            *started = enabled.then(Instant::now);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Record the measurement built by `measure`, which is only called when
+    /// recording is enabled
+    pub fn record_with(&self, measure: impl FnOnce() -> Measurement) {
+        if !self.is_enabled() {
+            return;
+        }
+        let measurement = measure();
+        if let Ok(mut measurements) = self.measurements.lock() {
+            measurements.push(measurement);
+        }
+    }
+
+    /// Aggregate everything recorded so far
+    pub fn report(&self, complete: bool) -> MetricsReport {
+        let now = Instant::now();
+        let started = self.started.lock().ok().and_then(|s| *s).unwrap_or(now);
+        let measurements = self
+            .measurements
+            .lock()
+            .map(|m| m.clone())
+            .unwrap_or_default();
+        MetricsReport::aggregate(&measurements, started, now, peak_rss_bytes(), complete)
+    }
+}
+
+/// Turn process-wide metrics recording on with an empty record, or off
+pub fn set_enabled(enabled: bool) {
+    RECORDER.set_enabled(enabled);
+}
+
+/// Whether process-wide metrics recording is on
+pub fn is_enabled() -> bool {
+    RECORDER.is_enabled()
+}
+
+/// Record a measurement into the process-wide recorder when enabled
+pub fn record_with(measure: impl FnOnce() -> Measurement) {
+    RECORDER.record_with(measure);
+}
+
+/// Forwards phase events into the process-wide recorder
+pub struct MetricsPhaseObserver;
+
+impl PhaseObserver for MetricsPhaseObserver {
+    fn on_phase_event(&self, event: &PhaseEvent) {
+        record_with(|| Measurement::Phase {
+            phase: event.phase.to_string(),
+            kind: event.kind,
+            at: Instant::now(),
+        });
+    }
+}
+
+/// p50/p95/max of a distribution (nearest-rank percentiles)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub count: usize,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Self {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+        sorted.sort_by(f64::total_cmp);
+        let rank = |percentile: f64| {
+            let index = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[index.clamp(1, sorted.len()) - 1]
+        };
+        Self {
+            count: sorted.len(),
+            p50: rank(50.0),
+            p95: rank(95.0),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Duration of one phase; `None` while it is running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseMetrics {
+    pub phase: String,
+    pub duration_ms: Option<u64>,
+}
+
+/// Measurements of one artifact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactSample {
+    pub name: String,
+    pub elapsed_ms: u64,
+    pub queue_wait_ms: u64,
+    pub bytes: u64,
+    pub throughput_bytes_per_sec: f64,
+    pub succeeded: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactMetrics {
+    pub count: usize,
+    pub failed: usize,
+    pub total_bytes: u64,
+    pub elapsed_ms: Summary,
+    pub queue_wait_ms: Summary,
+    /// Throughput of artifacts that copied at least one byte
+    pub throughput_bytes_per_sec: Summary,
+    /// The slowest artifacts, slowest first
+    pub slowest: Vec<ArtifactSample>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompressionMetrics {
+    pub files: usize,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    /// Compressed size over original size across all files
+    pub overall_ratio: Option<f64>,
+    /// Per-file compressed size over original size, for non-empty files
+    pub ratio: Summary,
+}
+
+/// Upload progress at one sampling point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadSample {
+    /// Time since metrics recording started
+    pub elapsed_ms: u64,
+    pub bytes_uploaded: u64,
+    /// Throughput since the previous sample
+    pub throughput_bytes_per_sec: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadMetrics {
+    pub target: String,
+    pub bytes_uploaded: u64,
+    pub throughput_bytes_per_sec: Summary,
+    pub samples: Vec<UploadSample>,
+}
+
+/// Contents of `metrics.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub generated: String,
+    /// False for the periodic snapshots written while collection runs
+    pub complete: bool,
+    pub elapsed_ms: u64,
+    pub peak_rss_bytes: Option<u64>,
+    pub phases: Vec<PhaseMetrics>,
+    pub artifacts: ArtifactMetrics,
+    pub compression: CompressionMetrics,
+    pub uploads: Vec<UploadMetrics>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
+fn throughput(bytes: u64, duration: Duration) -> f64 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        bytes as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+impl MetricsReport {
+    /// Aggregate `measurements` recorded between `started` and `now`
+    pub fn aggregate(
+        measurements: &[Measurement],
+        started: Instant,
+        now: Instant,
+        peak_rss_bytes: Option<u64>,
+        complete: bool,
+    ) -> Self {
+        let mut phases: Vec<(String, Instant, Option<Instant>)> = Vec::new();
+        let mut artifacts: Vec<ArtifactSample> = Vec::new();
+        let mut compression = CompressionMetrics::default();
+        let mut ratios = Vec::new();
+        let mut uploads: Vec<(String, Vec<(Instant, u64)>)> = Vec::new();
+
+        for measurement in measurements {
+            match measurement {
+                Measurement::Phase { phase, kind, at } => match kind {
+                    PhaseEventKind::Started => phases.push((phase.clone(), *at, None)),
+                    PhaseEventKind::Completed => {
+                        if let Some(entry) = phases
+                            .iter_mut()
+                            .rev()
+                            .find(|(name, _, end)| name == phase && end.is_none())
+                        {
+                            entry.2 = Some(*at);
+                        }
+                    }
+                },
+                Measurement::Artifact {
+                    name,
+                    elapsed,
+                    queue_wait,
+                    bytes,
+                    succeeded,
+                } => artifacts.push(ArtifactSample {
+                    name: name.clone(),
+                    elapsed_ms: millis(*elapsed),
+                    queue_wait_ms: millis(*queue_wait),
+                    bytes: *bytes,
+                    throughput_bytes_per_sec: throughput(*bytes, *elapsed),
+                    succeeded: *succeeded,
+                }),
+                Measurement::Compression {
+                    original_bytes,
+                    compressed_bytes,
+                } => {
+                    compression.files += 1;
+                    compression.original_bytes += original_bytes;
+                    compression.compressed_bytes += compressed_bytes;
+                    if *original_bytes > 0 {
+                        ratios.push(*compressed_bytes as f64 / *original_bytes as f64);
+                    }
+                }
+                Measurement::UploadProgress {
+                    target,
+                    bytes_uploaded,
+                    at,
+                } => match uploads.iter_mut().find(|(name, _)| name == target) {
+                    Some((_, samples)) => samples.push((*at, *bytes_uploaded)),
+                    None => uploads.push((target.clone(), vec![(*at, *bytes_uploaded)])),
+                },
+            }
+        }
+
+        compression.ratio = Summary::of(&ratios);
+        compression.overall_ratio = (compression.original_bytes > 0)
+            .then(|| compression.compressed_bytes as f64 / compression.original_bytes as f64);
+
+        let elapsed: Vec<f64> = artifacts.iter().map(|a| a.elapsed_ms as f64).collect();
+        let queue_wait: Vec<f64> = artifacts.iter().map(|a| a.queue_wait_ms as f64).collect();
+        let rates: Vec<f64> = artifacts
+            .iter()
+            .filter(|a| a.bytes > 0)
+            .map(|a| a.throughput_bytes_per_sec)
+            .collect();
+        let mut slowest = artifacts.clone();
+        slowest.sort_by_key(|a| std::cmp::Reverse(a.elapsed_ms));
+        slowest.truncate(MAX_LISTED_ARTIFACTS);
+
+        Self {
+            generated: chrono::Utc::now().to_rfc3339(),
+            complete,
+            elapsed_ms: millis(now.saturating_duration_since(started)),
+            peak_rss_bytes,
+            phases: phases
+                .into_iter()
+                .map(|(phase, start, end)| PhaseMetrics {
+                    phase,
+                    duration_ms: end.map(|end| millis(end.saturating_duration_since(start))),
+                })
+                .collect(),
+            artifacts: ArtifactMetrics {
+                count: artifacts.len(),
+                failed: artifacts.iter().filter(|a| !a.succeeded).count(),
+                total_bytes: artifacts.iter().map(|a| a.bytes).sum(),
+                elapsed_ms: Summary::of(&elapsed),
+                queue_wait_ms: Summary::of(&queue_wait),
+                throughput_bytes_per_sec: Summary::of(&rates),
+                slowest,
+            },
+            compression,
+            uploads: uploads
+                .into_iter()
+                .map(|(target, samples)| upload_metrics(target, &samples, started))
+                .collect(),
+        }
+    }
+}
+
+/// Throughput between consecutive progress samples of one upload
+fn upload_metrics(target: String, samples: &[(Instant, u64)], started: Instant) -> UploadMetrics {
+    let mut previous: Option<(Instant, u64)> = None;
+    let samples: Vec<UploadSample> = samples
+        .iter()
+        .map(|&(at, bytes)| {
+            let rate = previous.map_or(0.0, |(prev_at, prev_bytes)| {
+                throughput(
+                    bytes.saturating_sub(prev_bytes),
+                    at.saturating_duration_since(prev_at),
+                )
+            });
+            previous = Some((at, bytes));
+            UploadSample {
+                elapsed_ms: millis(at.saturating_duration_since(started)),
+                bytes_uploaded: bytes,
+                throughput_bytes_per_sec: rate,
+            }
+        })
+        .collect();
+    let rates: Vec<f64> = samples
+        .iter()
+        .skip(1)
+        .map(|s| s.throughput_bytes_per_sec)
+        .collect();
+    UploadMetrics {
+        target,
+        bytes_uploaded: samples.last().map_or(0, |s| s.bytes_uploaded),
+        throughput_bytes_per_sec: Summary::of(&rates),
+        samples,
+    }
+}
+
+/// Record the collector's current resident set size
+fn sample_rss() {
+    let pid = match sysinfo::get_current_pid() {
+        Ok(pid) => pid,
+        Err(e) => {
+            debug!("Cannot sample resident memory: {}", e);
+            return;
+        }
+    };
+    let mut system = System::new();
+    if system.refresh_process(pid) {
+        if let Some(process) = system.process(pid) {
+            SAMPLED_PEAK_RSS.fetch_max(process.memory(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Peak resident set size: the kernel's high-water mark on Linux, otherwise
+/// the highest sample taken
+fn peak_rss_bytes() -> Option<u64> {
+    sample_rss();
+    #[cfg(target_os = "linux")]
+    if let Some(peak) = fs::read_to_string("/proc/self/status")
+        .ok()
+        .as_deref()
+        .and_then(parse_vm_hwm)
+    {
+        return Some(peak);
+    }
+    Some(SAMPLED_PEAK_RSS.load(Ordering::Relaxed)).filter(|peak| *peak > 0)
+}
+
+/// `VmHWM:    123456 kB` from `/proc/self/status`, in bytes
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Write the process-wide report to [`METRICS_FILE`] in `dir`
+pub fn write_metrics(dir: &Path, complete: bool) -> Result<PathBuf> {
+    let report = RECORDER.report(complete);
+    let path = dir.join(METRICS_FILE);
+    // Snapshots replace the file while readers may be looking at it
+    let temp = dir.join(format!("{}.tmp", METRICS_FILE));
+    fs::write(&temp, serde_json::to_string_pretty(&report)?)
+        .context(format!("Failed to write {}", temp.display()))?;
+    fs::rename(&temp, &path).context(format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Background writer of periodic metrics snapshots
+pub struct SnapshotWriter {
+    stop: mpsc::Sender<()>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl SnapshotWriter {
+    /// Write a snapshot into `dir` every `interval` until stopped
+    pub fn start(dir: &Path, interval: Duration) -> Result<Self> {
+        let (stop, stopped) = mpsc::channel();
+        let dir = dir.to_path_buf();
+        let handle = thread::Builder::new()
+            .name("metrics-snapshots".into())
+            .spawn(move || {
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if let Err(e) = write_metrics(&dir, false) {
+                        warn!("Failed to write metrics snapshot: {:#}", e);
+                    }
+                }
+            })
+            .context("Failed to start metrics snapshot thread")?;
+        info!("Writing metrics snapshots every {:?}", interval);
+        Ok(Self { stop, handle })
+    }
+
+    /// Stop writing snapshots and wait for an in-progress write
+    pub fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_percentiles() {
+        let values: Vec<f64> = (1..=100).map(f64::from).collect();
+        let summary = Summary::of(&values);
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50, 50.0);
+        assert_eq!(summary.p95, 95.0);
+        assert_eq!(summary.max, 100.0);
+
+        let single = Summary::of(&[7.0]);
+        assert_eq!((single.p50, single.p95, single.max), (7.0, 7.0, 7.0));
+        assert_eq!(Summary::of(&[3.0, 1.0, 2.0]).p50, 2.0);
+        assert_eq!(Summary::of(&[]), Summary::default());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let artifact =
+            |name: &str, elapsed_ms: u64, wait_ms: u64, bytes: u64| Measurement::Artifact {
+                name: name.into(),
+                elapsed: Duration::from_millis(elapsed_ms),
+                queue_wait: Duration::from_millis(wait_ms),
+                bytes,
+                succeeded: bytes > 0,
+            };
+        let measurements = vec![
+            Measurement::Phase {
+                phase: "file_artifacts".into(),
+                kind: PhaseEventKind::Started,
+                at: at(0),
+            },
+            artifact("mft", 2000, 0, 4_000_000),
+            artifact("registry", 500, 100, 1_000_000),
+            artifact("missing", 10, 300, 0),
+            Measurement::Phase {
+                phase: "file_artifacts".into(),
+                kind: PhaseEventKind::Completed,
+                at: at(2500),
+            },
+            Measurement::Phase {
+                phase: "bodyfile".into(),
+                kind: PhaseEventKind::Started,
+                at: at(2500),
+            },
+            Measurement::Compression {
+                original_bytes: 1000,
+                compressed_bytes: 250,
+            },
+            Measurement::Compression {
+                original_bytes: 3000,
+                compressed_bytes: 3000,
+            },
+            Measurement::Compression {
+                original_bytes: 0,
+                compressed_bytes: 2,
+            },
+            Measurement::UploadProgress {
+                target: "s3".into(),
+                bytes_uploaded: 0,
+                at: at(3000),
+            },
+            Measurement::UploadProgress {
+                target: "s3".into(),
+                bytes_uploaded: 5_000_000,
+                at: at(8000),
+            },
+        ];
+
+        let report = MetricsReport::aggregate(&measurements, start, at(9000), Some(1), true);
+        assert_eq!(report.elapsed_ms, 9000);
+        assert_eq!(
+            report.phases,
+            vec![
+                PhaseMetrics {
+                    phase: "file_artifacts".into(),
+                    duration_ms: Some(2500)
+                },
+                PhaseMetrics {
+                    phase: "bodyfile".into(),
+                    duration_ms: None
+                },
+            ]
+        );
+
+        let artifacts = &report.artifacts;
+        assert_eq!((artifacts.count, artifacts.failed), (3, 1));
+        assert_eq!(artifacts.total_bytes, 5_000_000);
+        assert_eq!(artifacts.elapsed_ms.max, 2000.0);
+        assert_eq!(artifacts.queue_wait_ms.p50, 100.0);
+        assert_eq!(artifacts.throughput_bytes_per_sec.count, 2);
+        assert_eq!(artifacts.throughput_bytes_per_sec.max, 2_000_000.0);
+        assert_eq!(artifacts.slowest[0].name, "mft");
+
+        let compression = &report.compression;
+        assert_eq!(compression.files, 3);
+        assert_eq!(compression.overall_ratio, Some(3252.0 / 4000.0));
+        assert_eq!(compression.ratio.count, 2);
+        assert_eq!(compression.ratio.p50, 0.25);
+        assert_eq!(compression.ratio.max, 1.0);
+
+        let upload = &report.uploads[0];
+        assert_eq!(upload.bytes_uploaded, 5_000_000);
+        assert_eq!(upload.samples[1].elapsed_ms, 8000);
+        assert_eq!(upload.throughput_bytes_per_sec.max, 1_000_000.0);
+    }
+
+    #[test]
+    fn test_disabled_recorder_does_nothing() {
+        let recorder = MetricsRecorder::new();
+        let start = Instant::now();
+        for _ in 0..1_000_000 {
+            recorder.record_with(|| unreachable!("measurement built while disabled"));
+        }
+        // A million skipped records is a million relaxed loads; even an
+        // unoptimised build stays far below this bound
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(recorder.report(true).artifacts.count, 0);
+
+        recorder.set_enabled(true);
+        recorder.record_with(|| Measurement::Compression {
+            original_bytes: 10,
+            compressed_bytes: 5,
+        });
+        assert_eq!(recorder.report(true).compression.files, 1);
+
+        // Turning recording off drops the earlier run's samples
+        recorder.set_enabled(false);
+        assert!(!recorder.is_enabled());
+        assert_eq!(recorder.report(true).compression.files, 0);
+    }
+
+    #[test]
+    fn test_parse_vm_hwm() {
+        let status = "Name:\trust_collector\nVmPeak:\t  20000 kB\nVmHWM:\t    1536 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(1536 * 1024));
+        assert_eq!(parse_vm_hwm("Name:\tx\n"), None);
+    }
+}
@@ -7,8 +7,20 @@
 //!
 //! - **Compression**: ZIP archive creation with streaming support
 //! - **Hashing**: SHA-256 calculation for file integrity
+//! - **NSRL**: Known-good filtering against an NSRL hash database
+//! - **File types**: Magic-byte type identification and executable headers
 //! - **Bodyfile**: Timeline generation in Sleuthkit bodyfile format
 //! - **Summary**: Collection summary and reporting
+//! - **Timezone**: Host timezone and clock skew context
+//! - **Atomic output**: Staged output directories finalized by rename
+//! - **Metrics**: Local performance counters written to `metrics.json`
+//! - **Resource limits**: CPU and memory caps on the collector itself
+//! - **Deadline**: Overall time limit with a reserve for compression and upload
+//! - **SIEM export**: OpenSearch bulk and Splunk HEC documents from a collection
+//! - **HTTP**: Blocking HTTP client on hyper with proxy support
+//! - **Self-update**: Signed manifest checks and in-place binary replacement
+//! - **Path encoding**: Non-Unicode filenames kept distinct in archives and timelines
+//! - **Verification**: Sampled re-reads of collected files against their sources
 //!
 //! ## Common Use Cases
 //!
@@ -77,5 +89,47 @@ pub mod bodyfile;
 /// Cryptographic hash calculation utilities
 pub mod hash;
 
+/// NSRL known-good hash lookups
+pub mod nsrl;
+
+/// File type identification from magic bytes
+pub mod file_type;
+
 /// Streaming ZIP archive creation for large collections
 pub mod streaming_zip;
+
+/// Host timezone, clock skew and FAT local-time conversion
+pub mod timezone;
+
+/// SNTP probe for documenting clock skew
+pub mod ntp;
+
+/// Staged collection output finalized by an atomic rename
+pub mod atomic_output;
+
+/// Local per-phase, per-artifact, compression and upload metrics
+pub mod metrics;
+
+/// CPU and memory limits on the collector process, with internal throttling
+pub mod resource_limits;
+
+/// `--max-runtime` deadline, reserving time for compression and upload
+pub mod deadline;
+
+/// OpenSearch/Elasticsearch bulk and Splunk HEC export of a finished collection
+pub mod siem_export;
+
+/// Blocking HTTP/1.1 requests for exports and update checks
+pub mod http;
+
+/// Explicit and environment proxies for outbound connections (`--proxy-url`)
+pub mod proxy;
+
+/// `--check-update` and `self-update` against a signed release manifest
+pub mod self_update;
+
+/// Escaped and raw-byte forms of paths that are not valid Unicode
+pub mod path_encoding;
+
+/// Re-hash a sample of collected files against their sources (`--verify-sample`)
+pub mod verification;
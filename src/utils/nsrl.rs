@@ -0,0 +1,202 @@
+//! NSRL known-good file filtering.
+//!
+//! The NIST National Software Reference Library (NSRL) Reference Data Set
+//! lists hashes of files shipped with known software. With `--nsrl-db` the
+//! SHA-256 of each collected file is looked up in an RDS v3 SQLite database
+//! (the `FILE` table and its `sha256` column). Matches are flagged
+//! `nsrl_known_good: true` and can be filtered out; everything else is
+//! flagged `false` for analyst review. Directories and files that could not
+//! be hashed are left as `None`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ArtifactMetadata;
+use crate::utils::hash::calculate_sha256;
+
+/// Lookup of a SHA-256 in the RDS `FILE` table; RDS stores upper-case hex
+const LOOKUP_QUERY: &str = "SELECT 1 FROM FILE WHERE sha256 = ?1 OR sha256 = ?2 LIMIT 1";
+
+/// Hash every collected file regardless of size
+const NO_SIZE_LIMIT_MB: u64 = u64::MAX / (1024 * 1024);
+
+/// An open NSRL RDS database
+pub struct NsrlDatabase {
+    path: PathBuf,
+    connection: Connection,
+}
+
+impl NsrlDatabase {
+    /// Open an RDS SQLite database read-only and check it has a `FILE` table
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .context(format!("Failed to open NSRL database {}", path.display()))?;
+
+        connection.prepare(LOOKUP_QUERY).context(format!(
+            "{} is not an NSRL RDS database (no FILE table with a sha256 column)",
+            path.display()
+        ))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            connection,
+        })
+    }
+
+    /// Path the database was opened from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a SHA-256 (hex, any case) is in the database
+    pub fn contains_sha256(&self, sha256: &str) -> Result<bool> {
+        let mut statement = self
+            .connection
+            .prepare_cached(LOOKUP_QUERY)
+            .context("Failed to prepare NSRL lookup")?;
+        let found = statement
+            .query_row([sha256.to_uppercase(), sha256.to_lowercase()], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()
+            .context("NSRL lookup failed")?;
+        Ok(found.is_some())
+    }
+
+    /// Hash a collected copy and record the hash and whether it is known-good
+    pub fn check_collected_file(&self, collected: &Path, metadata: &mut ArtifactMetadata) {
+        let sha256 = match calculate_sha256(collected, NO_SIZE_LIMIT_MB) {
+            Ok(Some(sha256)) => sha256,
+            Ok(None) => return,
+            Err(e) => {
+                debug!("Not checking {} against NSRL: {}", collected.display(), e);
+                return;
+            }
+        };
+
+        let lookup = self.contains_sha256(&sha256);
+        metadata.sha256 = Some(sha256);
+        match lookup {
+            Ok(known) => metadata.nsrl_known_good = Some(known),
+            Err(e) => warn!("NSRL lookup for {} failed: {}", collected.display(), e),
+        }
+    }
+}
+
+/// NSRL results for the collection summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NsrlStats {
+    pub database: String,
+    /// Files whose hash was looked up
+    pub files_checked: usize,
+    /// Files found in the NSRL (known-good)
+    pub known_good: usize,
+    /// Files not in the NSRL, to be reviewed
+    pub unknown: usize,
+    /// Directories and files that could not be hashed
+    pub not_checked: usize,
+}
+
+impl NsrlStats {
+    /// Count NSRL results across collected artifacts
+    pub fn from_metadata(database: &Path, artifacts: &[(String, ArtifactMetadata)]) -> Self {
+        let mut stats = NsrlStats {
+            database: database.display().to_string(),
+            ..Default::default()
+        };
+        for (_, metadata) in artifacts {
+            match metadata.nsrl_known_good {
+                Some(true) => stats.known_good += 1,
+                Some(false) => stats.unknown += 1,
+                None => stats.not_checked += 1,
+            }
+        }
+        stats.files_checked = stats.known_good + stats.unknown;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::generators::test_artifact_metadata;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_rds(dir: &Path, hashes: &[String]) -> PathBuf {
+        let path = dir.join("RDS_modern_minimal.db");
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute(
+                "CREATE TABLE FILE (sha256 TEXT, sha1 TEXT, md5 TEXT, file_name TEXT, file_size INTEGER)",
+                [],
+            )
+            .unwrap();
+        for hash in hashes {
+            connection
+                .execute(
+                    "INSERT INTO FILE (sha256, file_name) VALUES (?1, 'known.dll')",
+                    [hash],
+                )
+                .unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_collected_files_are_flagged() {
+        let temp = TempDir::new().unwrap();
+        let known = temp.path().join("known.dll");
+        let unknown = temp.path().join("dropper.exe");
+        fs::write(&known, "known file contents").unwrap();
+        fs::write(&unknown, "something new").unwrap();
+
+        let known_hash = calculate_sha256(&known, 1).unwrap().unwrap();
+        let db =
+            NsrlDatabase::open(&create_rds(temp.path(), &[known_hash.to_uppercase()])).unwrap();
+
+        let mut artifacts = vec![
+            ("known.dll".to_string(), test_artifact_metadata("known.dll")),
+            (
+                "dropper.exe".to_string(),
+                test_artifact_metadata("dropper.exe"),
+            ),
+            ("dir".to_string(), test_artifact_metadata("dir")),
+        ];
+        db.check_collected_file(&known, &mut artifacts[0].1);
+        db.check_collected_file(&unknown, &mut artifacts[1].1);
+        db.check_collected_file(temp.path(), &mut artifacts[2].1);
+
+        assert_eq!(artifacts[0].1.nsrl_known_good, Some(true));
+        assert_eq!(artifacts[1].1.nsrl_known_good, Some(false));
+        assert_eq!(artifacts[2].1.nsrl_known_good, None);
+        assert_eq!(artifacts[0].1.sha256.as_deref(), Some(known_hash.as_str()));
+        assert_eq!(artifacts[2].1.sha256, None);
+
+        let stats = NsrlStats::from_metadata(db.path(), &artifacts);
+        assert_eq!(stats.files_checked, 2);
+        assert_eq!(stats.known_good, 1);
+        assert_eq!(stats.unknown, 1);
+        assert_eq!(stats.not_checked, 1);
+    }
+
+    #[test]
+    fn test_open_rejects_non_rds_database() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("other.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute("CREATE TABLE hashes (value TEXT)", [])
+            .unwrap();
+
+        assert!(NsrlDatabase::open(&path).is_err());
+        assert!(NsrlDatabase::open(&temp.path().join("missing.db")).is_err());
+    }
+}
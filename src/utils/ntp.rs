@@ -0,0 +1,199 @@
+//! Minimal SNTP client for documenting host clock skew.
+//!
+//! A single request is sent with a bounded timeout; the result is the clock
+//! offset and round-trip delay as defined in RFC 4330. It is used only to
+//! document skew in the collection summary, never to adjust timestamps.
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// Default probe timeout, applied to both send and receive
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_EPOCH_DIFF: i64 = 2_208_988_800;
+
+/// Size of an NTP packet without extensions
+const NTP_PACKET_SIZE: usize = 48;
+
+/// Result of an NTP probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NtpProbe {
+    pub server: String,
+    /// Server clock minus local clock, in milliseconds
+    pub offset_ms: i64,
+    /// Round-trip delay excluding server processing, in milliseconds
+    pub round_trip_ms: i64,
+    /// Server stratum
+    pub stratum: u8,
+    /// Local time the probe was sent (RFC3339)
+    pub probed_at: String,
+}
+
+/// Build a client request (LI 0, version 3, mode 3)
+fn build_request() -> [u8; NTP_PACKET_SIZE] {
+    let mut packet = [0u8; NTP_PACKET_SIZE];
+    packet[0] = 0x1b;
+    packet
+}
+
+/// Convert a 64-bit NTP timestamp to Unix milliseconds (fraction rounded)
+fn ntp_timestamp_to_unix_ms(bytes: &[u8]) -> i64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    (secs - NTP_UNIX_EPOCH_DIFF) * 1000 + ((frac * 1000 + (1 << 31)) >> 32)
+}
+
+/// Compute offset and delay from the four timestamps (RFC 4330 section 5)
+fn offset_and_delay(t0: i64, t1: i64, t2: i64, t3: i64) -> (i64, i64) {
+    let offset = ((t1 - t0) + (t2 - t3)) / 2;
+    let delay = (t3 - t0) - (t2 - t1);
+    (offset, delay)
+}
+
+/// Parse a server response given local send (`t0`) and receive (`t3`) times
+fn parse_response(packet: &[u8], t0: i64, t3: i64) -> Result<(i64, i64, u8)> {
+    if packet.len() < NTP_PACKET_SIZE {
+        return Err(anyhow!("NTP response too short: {} bytes", packet.len()));
+    }
+
+    let mode = packet[0] & 0x07;
+    if mode != 4 {
+        return Err(anyhow!("Unexpected NTP mode {}", mode));
+    }
+    let stratum = packet[1];
+    if stratum == 0 {
+        return Err(anyhow!("NTP server sent kiss-of-death response"));
+    }
+
+    let t1 = ntp_timestamp_to_unix_ms(&packet[32..40]);
+    let t2 = ntp_timestamp_to_unix_ms(&packet[40..48]);
+    let (offset, delay) = offset_and_delay(t0, t1, t2, t3);
+    Ok((offset, delay, stratum))
+}
+
+fn unix_ms_now() -> Result<i64> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System clock is before the Unix epoch")?;
+    Ok(now.as_millis() as i64)
+}
+
+/// Probe `server` (host or host:port) once, bounded by `timeout`
+pub fn probe(server: &str, timeout: Duration) -> Result<NtpProbe> {
+    let address = match server.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, 123).to_string(),
+        Err(_) if server.contains(':') => server.to_string(),
+        Err(_) => format!("{}:123", server),
+    };
+
+    let target = address
+        .to_socket_addrs()
+        .context(format!("Failed to resolve NTP server {}", server))?
+        .next()
+        .ok_or_else(|| anyhow!("No addresses for NTP server {}", server))?;
+
+    let bind_addr = if target.is_ipv6() {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let socket = UdpSocket::bind(bind_addr).context("Failed to bind UDP socket")?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+
+    let probed_at = chrono::Utc::now().to_rfc3339();
+    let t0 = unix_ms_now()?;
+    socket
+        .send_to(&build_request(), target)
+        .context("Failed to send NTP request")?;
+
+    let mut buffer = [0u8; 512];
+    let (len, _) = socket
+        .recv_from(&mut buffer)
+        .context("No NTP response before timeout")?;
+    let t3 = unix_ms_now()?;
+
+    let (offset_ms, round_trip_ms, stratum) = parse_response(&buffer[..len], t0, t3)?;
+    info!(
+        "Clock offset versus {}: {} ms (round trip {} ms)",
+        server, offset_ms, round_trip_ms
+    );
+
+    Ok(NtpProbe {
+        server: server.to_string(),
+        offset_ms,
+        round_trip_ms,
+        stratum,
+        probed_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntp_bytes(unix_ms: i64) -> [u8; 8] {
+        let secs = (unix_ms / 1000 + NTP_UNIX_EPOCH_DIFF) as u32;
+        let frac = (((unix_ms % 1000) << 32) / 1000) as u32;
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&secs.to_be_bytes());
+        bytes[4..].copy_from_slice(&frac.to_be_bytes());
+        bytes
+    }
+
+    fn response(t1: i64, t2: i64) -> [u8; NTP_PACKET_SIZE] {
+        let mut packet = [0u8; NTP_PACKET_SIZE];
+        packet[0] = 0x1c; // version 3, server mode
+        packet[1] = 2;
+        packet[32..40].copy_from_slice(&ntp_bytes(t1));
+        packet[40..48].copy_from_slice(&ntp_bytes(t2));
+        packet
+    }
+
+    #[test]
+    fn test_request_header() {
+        let request = build_request();
+        assert_eq!(request[0] >> 6, 0); // leap indicator
+        assert_eq!((request[0] >> 3) & 0x07, 3); // version
+        assert_eq!(request[0] & 0x07, 3); // client mode
+    }
+
+    #[test]
+    fn test_ntp_timestamp_round_trip() {
+        let ms = 1_700_000_000_250;
+        assert_eq!(ntp_timestamp_to_unix_ms(&ntp_bytes(ms)), ms);
+    }
+
+    #[test]
+    fn test_offset_when_local_clock_is_behind() {
+        // Local clock is 5s behind the server, 40ms each way, 10ms processing
+        let t0 = 1_700_000_000_000;
+        let t1 = t0 + 5_000 + 40;
+        let t2 = t1 + 10;
+        let t3 = t0 + 90;
+
+        let (offset, delay, stratum) = parse_response(&response(t1, t2), t0, t3).unwrap();
+        assert_eq!(offset, 5_000);
+        assert_eq!(delay, 80);
+        assert_eq!(stratum, 2);
+    }
+
+    #[test]
+    fn test_rejects_bad_responses() {
+        let t0 = 1_700_000_000_000;
+        assert!(parse_response(&[0u8; 10], t0, t0).is_err());
+
+        let mut client_mode = response(t0, t0);
+        client_mode[0] = 0x1b;
+        assert!(parse_response(&client_mode, t0, t0).is_err());
+
+        let mut kiss_of_death = response(t0, t0);
+        kiss_of_death[1] = 0;
+        assert!(parse_response(&kiss_of_death, t0, t0).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Text forms of paths that are not valid Unicode.
+//!
+//! Linux filenames are arbitrary bytes and Windows filenames are arbitrary
+//! UTF-16 code units, so a path read from disk may hold a byte such as `0xFF`
+//! or an unpaired surrogate. `to_string_lossy` turns both into U+FFFD, which
+//! loses the original name and lets two different files map to the same
+//! string. The helpers here keep every name distinct and recoverable:
+//!
+//! - [`escaped_path`] / [`archive_entry_name`] - readable text with invalid
+//!   bytes as `%XX` and unpaired surrogates as `%uXXXX`
+//! - [`bodyfile_path`] - text safe for a pipe-separated bodyfile field
+//! - [`original_path_bytes`] - base64 of the raw bytes, stored in
+//!   `ArtifactMetadata` when the lossy string is not the real name
+
+use std::ffi::OsStr;
+use std::path::{Component, Path};
+
+/// A run of a path decoded as far as Unicode allows
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    /// A byte that is not part of valid UTF-8 (Unix)
+    Byte(u8),
+    /// An unpaired UTF-16 surrogate (Windows)
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Surrogate(u16),
+}
+
+#[cfg(unix)]
+fn segments(name: &OsStr) -> Vec<Segment> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut segments = Vec::new();
+    let mut bytes = name.as_bytes();
+    while !bytes.is_empty() {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => {
+                segments.push(Segment::Text(text.to_string()));
+                break;
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                if !valid.is_empty() {
+                    // Checked by from_utf8 above
+                    let text = std::str::from_utf8(valid).unwrap_or_default();
+                    segments.push(Segment::Text(text.to_string()));
+                }
+                let invalid = e.error_len().unwrap_or(rest.len());
+                segments.extend(rest[..invalid].iter().map(|&b| Segment::Byte(b)));
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+    segments
+}
+
+#[cfg(windows)]
+fn segments(name: &OsStr) -> Vec<Segment> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    for unit in char::decode_utf16(name.encode_wide()) {
+        match unit {
+            Ok(c) => text.push(c),
+            Err(e) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Surrogate(e.unpaired_surrogate()));
+            }
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+#[cfg(not(any(unix, windows)))]
+fn segments(name: &OsStr) -> Vec<Segment> {
+    vec![Segment::Text(name.to_string_lossy().into_owned())]
+}
+
+/// Raw bytes of a path: the bytes themselves on Unix, UTF-16LE on Windows
+pub fn raw_path_bytes(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        path.as_os_str()
+            .encode_wide()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        path.to_string_lossy().into_owned().into_bytes()
+    }
+}
+
+/// Base64 of [`raw_path_bytes`], or `None` when the path is valid Unicode and
+/// its string form already is the real name
+pub fn original_path_bytes(path: &Path) -> Option<String> {
+    match path.to_str() {
+        Some(_) => None,
+        None => Some(encode_base64(&raw_path_bytes(path))),
+    }
+}
+
+/// `path` as text, with invalid bytes written as `%XX` and unpaired
+/// surrogates as `%uXXXX`
+pub fn escaped_path(path: &Path) -> String {
+    escaped_os_str(path.as_os_str())
+}
+
+fn escaped_os_str(name: &OsStr) -> String {
+    let mut escaped = String::new();
+    for segment in segments(name) {
+        match segment {
+            Segment::Text(text) => escaped.push_str(&text),
+            Segment::Byte(b) => escaped.push_str(&format!("%{:02X}", b)),
+            Segment::Surrogate(unit) => escaped.push_str(&format!("%u{:04X}", unit)),
+        }
+    }
+    escaped
+}
+
+/// Archive entry name for a path relative to the archive root: escaped
+/// components joined with `/`
+pub fn archive_entry_name(relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(escaped_os_str(name)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `path` for a bodyfile name field: `|`, control characters and invalid
+/// bytes are written as `\xNN`, unpaired surrogates as `\uXXXX`
+pub fn bodyfile_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for segment in segments(path.as_os_str()) {
+        match segment {
+            Segment::Text(text) => {
+                for c in text.chars() {
+                    if c == '|' || c.is_control() {
+                        let mut buf = [0; 4];
+                        for b in c.encode_utf8(&mut buf).bytes() {
+                            escaped.push_str(&format!("\\x{:02x}", b));
+                        }
+                    } else {
+                        escaped.push(c);
+                    }
+                }
+            }
+            Segment::Byte(b) => escaped.push_str(&format!("\\x{:02x}", b)),
+            Segment::Surrogate(unit) => escaped.push_str(&format!("\\u{:04x}", unit)),
+        }
+    }
+    escaped
+}
+
+/// Standard base64 with padding
+fn encode_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_encode_base64() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(&[0xFF, 0xFE, 0x00, 0x01]), "//4AAQ==");
+    }
+
+    #[test]
+    fn test_valid_unicode_is_unchanged() {
+        let path = Path::new("dir/документ 日本.txt");
+        assert_eq!(original_path_bytes(path), None);
+        assert_eq!(escaped_path(path), "dir/документ 日本.txt");
+        assert_eq!(archive_entry_name(path), "dir/документ 日本.txt");
+    }
+
+    #[test]
+    fn test_bodyfile_path_escapes_pipes_and_controls() {
+        let path = Path::new("/tmp/a|b\nc\u{85}é");
+        assert_eq!(bodyfile_path(path), "/tmp/a\\x7cb\\x0ac\\xc2\\x85é");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_utf8_byte() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"logs/bad\xFFname.txt"));
+        assert_eq!(
+            original_path_bytes(&path),
+            Some(encode_base64(b"logs/bad\xFFname.txt"))
+        );
+        assert_eq!(escaped_path(&path), "logs/bad%FFname.txt");
+        assert_eq!(archive_entry_name(&path), "logs/bad%FFname.txt");
+        assert_eq!(bodyfile_path(&path), "logs/bad\\xffname.txt");
+
+        // Different invalid bytes stay distinct, unlike U+FFFD replacement
+        let other = PathBuf::from(OsStr::from_bytes(b"logs/bad\xFEname.txt"));
+        assert_ne!(archive_entry_name(&path), archive_entry_name(&other));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_unpaired_surrogate() {
+        use std::ffi::OsString;
+        use std::os::windows::ffi::OsStringExt;
+
+        let mut wide: Vec<u16> = "logs\\bad".encode_utf16().collect();
+        wide.push(0xD800);
+        wide.extend("name.txt".encode_utf16());
+        let path = PathBuf::from(OsString::from_wide(&wide));
+
+        let bytes: Vec<u8> = wide.iter().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(original_path_bytes(&path), Some(encode_base64(&bytes)));
+        assert_eq!(escaped_path(&path), "logs\\bad%uD800name.txt");
+        assert_eq!(archive_entry_name(&path), "logs/bad%uD800name.txt");
+        assert_eq!(bodyfile_path(&path), "logs\\bad\\ud800name.txt");
+    }
+}
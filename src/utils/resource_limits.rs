@@ -0,0 +1,834 @@
+//! Limits on the collector's own CPU and memory use.
+//!
+//! On shared production servers `--max-cpu-percent` and `--max-memory-mb`
+//! keep the collector from competing with the workloads being triaged.
+//! [`apply`] has the operating system enforce them where it can:
+//!
+//! - Linux: a transient systemd scope (started through `busctl`) or, without
+//!   systemd, a cgroup v2 group written directly, with `cpu.max` and
+//!   `memory.max` set. When neither works the collector drops to nice 10
+//!   and the lowest best-effort I/O priority instead.
+//! - Windows: a Job Object with a hard CPU rate cap and a process memory
+//!   limit.
+//! - macOS: background QoS (`PRIO_DARWIN_BG`, as set by `taskpolicy -b`),
+//!   which lowers CPU and I/O priority. There is no per-process memory cap.
+//!
+//! CPU percentages are of the whole machine, all cores together.
+//!
+//! A governor thread also samples the collector's CPU and resident memory
+//! every second. Above the CPU target it withholds permits from the parallel
+//! collector's semaphore and paces copy loops between chunks, relaxing both
+//! once usage falls. Above 80% of the memory cap it collects one artifact at
+//! a time, shrinks copy buffers and makes buffered volatile output flush, so
+//! the collector degrades before the OS kills it.
+//!
+//! Like the footprint tracker, the governor is process-global so copy loops
+//! can check it without threading state through every collector. OS limits
+//! last for the life of the process; what was applied, and how often the
+//! governor throttled, is recorded in the collection summary.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sysinfo::{ProcessExt, System, SystemExt};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How often the governor samples the collector's usage
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pause added between copy chunks when first over the CPU target
+const PACE_STEP_MS: u64 = 5;
+
+/// Longest pause between copy chunks
+const MAX_PACE_MS: u64 = 250;
+
+/// Share of the memory cap at which the collector starts degrading
+const MEMORY_PRESSURE_PERCENT: u64 = 80;
+
+/// Share of the memory cap below which it recovers
+const MEMORY_RECOVERY_PERCENT: u64 = 60;
+
+/// Share of the CPU target below which throttling is relaxed
+const CPU_RECOVERY_FRACTION: f32 = 0.8;
+
+/// Pause between copy chunks, set by the governor
+static PACE_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Set while resident memory is near the cap
+static MEMORY_PRESSURE: AtomicBool = AtomicBool::new(false);
+
+/// OS enforcement, applied once per process
+static ENFORCEMENT: OnceLock<Enforcement> = OnceLock::new();
+
+lazy_static! {
+    static ref GOVERNOR: Mutex<Option<Governor>> = Mutex::new(None);
+    static ref SAMPLER: Mutex<Option<Sampler>> = Mutex::new(None);
+}
+
+/// Requested limits; `None` leaves a resource unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Percent of total CPU capacity, all cores together
+    pub max_cpu_percent: Option<u32>,
+    pub max_memory_mb: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_cpu_percent.is_none() && self.max_memory_mb.is_none()
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        self.max_memory_mb.map(|mb| mb * 1024 * 1024)
+    }
+}
+
+/// Limits as applied, recorded in the collection summary
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AppliedLimits {
+    pub max_cpu_percent: Option<u32>,
+    pub max_memory_mb: Option<u64>,
+    /// OS mechanisms enforcing the limits: `systemd_scope`, `cgroup_v2`,
+    /// `nice`, `ionice`, `job_object` or `darwin_background`
+    pub enforcement: Vec<String>,
+    /// Systemd scope or cgroup directory the collector was moved into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<String>,
+    /// Why stronger mechanisms could not be used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+    /// Samples taken by the governor
+    pub samples: u64,
+    /// Samples above the CPU target
+    pub cpu_throttled_samples: u64,
+    /// Samples above the memory pressure threshold
+    pub memory_pressure_samples: u64,
+    /// Fewest artifacts the governor allowed to run at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lowest_concurrency: Option<usize>,
+    pub peak_cpu_percent: f32,
+    pub peak_memory_mb: u64,
+}
+
+/// What the OS was asked to enforce, and how
+#[derive(Debug, Clone, Default)]
+struct Enforcement {
+    limits: ResourceLimits,
+    mechanisms: Vec<String>,
+    cgroup: Option<String>,
+    fallbacks: Vec<String>,
+}
+
+/// One reading of the collector's own usage
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// Percent of total CPU capacity
+    cpu_percent: f32,
+    memory_bytes: u64,
+}
+
+/// Throttling decisions from successive samples
+#[derive(Debug)]
+struct Throttle {
+    limits: ResourceLimits,
+    max_concurrency: usize,
+    concurrency: usize,
+    pace_ms: u64,
+    memory_pressure: bool,
+}
+
+impl Throttle {
+    fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            max_concurrency: usize::MAX,
+            concurrency: usize::MAX,
+            pace_ms: 0,
+            memory_pressure: false,
+        }
+    }
+
+    /// Start governing a collector allowing `max` artifacts at once
+    fn set_max_concurrency(&mut self, max: usize) {
+        self.max_concurrency = max.max(1);
+        self.concurrency = if self.memory_pressure {
+            1
+        } else {
+            self.max_concurrency
+        };
+    }
+
+    /// Adjust to `sample`; returns whether it was over the CPU target
+    fn observe(&mut self, sample: Sample) -> bool {
+        if let Some(cap) = self.limits.memory_bytes() {
+            if sample.memory_bytes >= cap / 100 * MEMORY_PRESSURE_PERCENT {
+                self.memory_pressure = true;
+            } else if sample.memory_bytes < cap / 100 * MEMORY_RECOVERY_PERCENT {
+                self.memory_pressure = false;
+            }
+        }
+
+        let target = self.limits.max_cpu_percent.map(|p| p as f32);
+        let over_cpu = target.is_some_and(|t| sample.cpu_percent > t);
+        if over_cpu {
+            self.pace_ms = (self.pace_ms * 2).clamp(PACE_STEP_MS, MAX_PACE_MS);
+            self.concurrency = (self.concurrency.min(self.max_concurrency) / 2).max(1);
+        } else if target.is_none_or(|t| sample.cpu_percent < t * CPU_RECOVERY_FRACTION) {
+            self.pace_ms = match self.pace_ms / 2 {
+                pace if pace < PACE_STEP_MS => 0,
+                pace => pace,
+            };
+            self.concurrency = self.concurrency.saturating_add(1).min(self.max_concurrency);
+        }
+
+        if self.memory_pressure {
+            self.concurrency = 1;
+        }
+        over_cpu
+    }
+}
+
+/// The collector semaphore and the permits held back from it
+struct GovernedSemaphore {
+    semaphore: Arc<Semaphore>,
+    withheld: Vec<OwnedSemaphorePermit>,
+}
+
+struct Governor {
+    applied: AppliedLimits,
+    throttle: Throttle,
+    semaphore: Option<GovernedSemaphore>,
+}
+
+impl Governor {
+    fn observe(&mut self, sample: Sample) {
+        let over_cpu = self.throttle.observe(sample);
+
+        let applied = &mut self.applied;
+        applied.samples += 1;
+        applied.cpu_throttled_samples += over_cpu as u64;
+        applied.memory_pressure_samples += self.throttle.memory_pressure as u64;
+        applied.peak_cpu_percent = applied.peak_cpu_percent.max(sample.cpu_percent);
+        applied.peak_memory_mb = applied
+            .peak_memory_mb
+            .max(sample.memory_bytes / (1024 * 1024));
+        self.rebalance();
+    }
+
+    /// Make the throttling state visible to copy loops and buffers
+    fn publish(&self) {
+        let pressure = self.throttle.memory_pressure;
+        if pressure != MEMORY_PRESSURE.swap(pressure, Ordering::Relaxed) {
+            if pressure {
+                warn!(
+                    "Collector memory is near the {} MB cap, reducing concurrency and flushing buffers",
+                    self.throttle.limits.max_memory_mb.unwrap_or_default()
+                );
+            } else {
+                info!("Collector memory back below the cap, resuming normal collection");
+            }
+        }
+        PACE_MS.store(self.throttle.pace_ms, Ordering::Relaxed);
+    }
+
+    /// Withhold or return semaphore permits to match the throttled concurrency
+    fn rebalance(&mut self) {
+        let Some(governed) = self.semaphore.as_mut() else {
+            return;
+        };
+        let withhold = self
+            .throttle
+            .max_concurrency
+            .saturating_sub(self.throttle.concurrency);
+        governed.withheld.truncate(withhold);
+        while governed.withheld.len() < withhold {
+            // Busy permits are taken as their artifacts finish
+            match Arc::clone(&governed.semaphore).try_acquire_owned() {
+                Ok(permit) => governed.withheld.push(permit),
+                Err(_) => break,
+            }
+        }
+
+        let running = self.throttle.max_concurrency - governed.withheld.len();
+        self.applied.lowest_concurrency = Some(
+            self.applied
+                .lowest_concurrency
+                .map_or(running, |lowest| lowest.min(running)),
+        );
+    }
+}
+
+/// Background sampling thread
+struct Sampler {
+    stop: mpsc::Sender<()>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl Sampler {
+    fn start() -> Option<Self> {
+        let pid = sysinfo::get_current_pid()
+            .map_err(|e| warn!("Cannot sample collector usage: {}", e))
+            .ok()?;
+        let cpus = num_cpus::get().max(1) as f32;
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("resource-governor".into())
+            .spawn(move || {
+                let mut system = System::new();
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stopped.recv_timeout(SAMPLE_INTERVAL)
+                {
+                    system.refresh_cpu();
+                    if !system.refresh_process(pid) {
+                        continue;
+                    }
+                    let Some(process) = system.process(pid) else {
+                        continue;
+                    };
+                    let sample = Sample {
+                        cpu_percent: process.cpu_usage() / cpus,
+                        memory_bytes: process.memory(),
+                    };
+                    if let Some(governor) = lock_governor().as_mut() {
+                        governor.observe(sample);
+                        governor.publish();
+                    }
+                }
+            })
+            .map_err(|e| warn!("Failed to start resource governor: {}", e))
+            .ok()?;
+        Some(Self { stop, handle })
+    }
+
+    fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.handle.join();
+    }
+}
+
+fn lock_governor() -> std::sync::MutexGuard<'static, Option<Governor>> {
+    GOVERNOR.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Enforce `limits` and start the governor; `None` if nothing is limited.
+///
+/// Call before worker threads start, so on Linux they inherit the fallback
+/// nice and I/O priority. OS limits are applied once per process: a later
+/// call with different limits only changes the governor's targets.
+pub fn apply(limits: ResourceLimits) -> Option<AppliedLimits> {
+    // Drop any governor left by an earlier collection in this process
+    release();
+    if limits.is_unlimited() {
+        return None;
+    }
+
+    let enforcement = ENFORCEMENT.get_or_init(|| {
+        let mut enforcement = Enforcement {
+            limits,
+            ..Default::default()
+        };
+        os::enforce(&limits, &mut enforcement);
+        enforcement
+    });
+    if enforcement.limits != limits {
+        warn!(
+            "Resource limits are already enforced by the OS as {:?}; only the governor uses the new limits",
+            enforcement.limits
+        );
+    }
+    for fallback in &enforcement.fallbacks {
+        warn!("Resource limits: {}", fallback);
+    }
+
+    let applied = AppliedLimits {
+        max_cpu_percent: limits.max_cpu_percent,
+        max_memory_mb: limits.max_memory_mb,
+        enforcement: enforcement.mechanisms.clone(),
+        cgroup: enforcement.cgroup.clone(),
+        fallbacks: enforcement.fallbacks.clone(),
+        ..Default::default()
+    };
+    info!(
+        "Limiting the collector to {} CPU and {} memory (enforced by: {}, plus internal throttling)",
+        limits
+            .max_cpu_percent
+            .map_or("unlimited".to_string(), |p| format!("{}%", p)),
+        limits
+            .max_memory_mb
+            .map_or("unlimited".to_string(), |mb| format!("{} MB", mb)),
+        match applied.enforcement.is_empty() {
+            true => "nothing".to_string(),
+            false => applied.enforcement.join(", "),
+        }
+    );
+
+    *lock_governor() = Some(Governor {
+        applied: applied.clone(),
+        throttle: Throttle::new(limits),
+        semaphore: None,
+    });
+    *SAMPLER.lock().unwrap_or_else(|e| e.into_inner()) = Sampler::start();
+    Some(applied)
+}
+
+/// Let the governor shrink `semaphore`, which allows `max` artifacts at once
+pub fn govern(semaphore: &Arc<Semaphore>, max: usize) {
+    if let Some(governor) = lock_governor().as_mut() {
+        governor.throttle.set_max_concurrency(max);
+        governor.semaphore = Some(GovernedSemaphore {
+            semaphore: Arc::clone(semaphore),
+            withheld: Vec::new(),
+        });
+        governor.rebalance();
+    }
+}
+
+/// Pause between copy chunks while the collector is over its CPU target
+pub fn pace() {
+    let pace = PACE_MS.load(Ordering::Relaxed);
+    if pace > 0 {
+        thread::sleep(Duration::from_millis(pace));
+    }
+}
+
+/// Whether resident memory is near the cap, so buffers should be flushed
+/// and kept small
+pub fn memory_pressure() -> bool {
+    MEMORY_PRESSURE.load(Ordering::Relaxed)
+}
+
+/// Limits and throttling so far, for the collection summary
+pub fn report() -> Option<AppliedLimits> {
+    lock_governor()
+        .as_ref()
+        .map(|governor| governor.applied.clone())
+}
+
+/// Stop the governor and release throttling; OS limits stay in place
+pub fn release() {
+    if let Some(sampler) = SAMPLER.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        sampler.stop();
+    }
+    if lock_governor().take().is_some() {
+        debug!("Resource governor stopped");
+    }
+    PACE_MS.store(0, Ordering::Relaxed);
+    MEMORY_PRESSURE.store(false, Ordering::Relaxed);
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use log::{debug, info};
+
+    use super::{Enforcement, ResourceLimits};
+    use crate::collectors::footprint;
+
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+    /// `cpu.max` period in microseconds
+    const CPU_PERIOD_USEC: u64 = 100_000;
+
+    /// Nice value used when cgroups are not writable
+    const FALLBACK_NICE: libc::c_int = 10;
+
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_LOWEST_LEVEL: libc::c_int = 7;
+
+    pub(super) fn enforce(limits: &ResourceLimits, enforcement: &mut Enforcement) {
+        let pid = std::process::id();
+
+        if Path::new("/run/systemd/system").exists() {
+            let unit = format!("rust-collector-{}.scope", pid);
+            match start_scope(&unit, pid, limits) {
+                Ok(()) => {
+                    info!("Moved the collector into systemd scope {}", unit);
+                    enforcement.mechanisms.push("systemd_scope".to_string());
+                    enforcement.cgroup = Some(unit);
+                    return;
+                }
+                Err(e) => enforcement
+                    .fallbacks
+                    .push(format!("systemd scope unavailable: {}", e)),
+            }
+        }
+
+        match create_cgroup(limits, pid) {
+            Ok(dir) => {
+                info!("Moved the collector into cgroup {}", dir.display());
+                enforcement.mechanisms.push("cgroup_v2".to_string());
+                enforcement.cgroup = Some(dir.to_string_lossy().to_string());
+                return;
+            }
+            Err(e) => enforcement
+                .fallbacks
+                .push(format!("cgroup v2 not writable: {}", e)),
+        }
+
+        // No hard limits: lower priority and rely on the governor
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, FALLBACK_NICE) } == 0 {
+            enforcement.mechanisms.push("nice".to_string());
+        } else {
+            debug!("setpriority failed: {}", io::Error::last_os_error());
+        }
+        let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | IOPRIO_LOWEST_LEVEL;
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } == 0 {
+            enforcement.mechanisms.push("ionice".to_string());
+        } else {
+            debug!("ioprio_set failed: {}", io::Error::last_os_error());
+        }
+    }
+
+    /// CPU quota in microseconds per `period` for `percent` of all `cpus`
+    pub(super) fn cpu_quota_usec(percent: u32, cpus: usize, period: u64) -> u64 {
+        (period * percent as u64 * cpus.max(1) as u64 / 100).max(1000)
+    }
+
+    /// `busctl` arguments starting a transient scope holding `pid`
+    pub(super) fn scope_arguments(unit: &str, pid: u32, limits: &ResourceLimits) -> Vec<String> {
+        let mut properties = vec![vec![
+            "PIDs".to_string(),
+            "au".to_string(),
+            "1".to_string(),
+            pid.to_string(),
+        ]];
+        if let Some(percent) = limits.max_cpu_percent {
+            let quota = cpu_quota_usec(percent, num_cpus::get(), 1_000_000);
+            properties.push(vec![
+                "CPUQuotaPerSecUSec".to_string(),
+                "t".to_string(),
+                quota.to_string(),
+            ]);
+        }
+        if let Some(bytes) = limits.memory_bytes() {
+            properties.push(vec![
+                "MemoryHigh".to_string(),
+                "t".to_string(),
+                (bytes / 10 * 9).to_string(),
+            ]);
+            properties.push(vec![
+                "MemoryMax".to_string(),
+                "t".to_string(),
+                bytes.to_string(),
+            ]);
+        }
+
+        let mut arguments: Vec<String> = [
+            "call",
+            "org.freedesktop.systemd1",
+            "/org/freedesktop/systemd1",
+            "org.freedesktop.systemd1.Manager",
+            "StartTransientUnit",
+            "ssa(sv)a(sa(sv))",
+            unit,
+            "fail",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        arguments.push(properties.len().to_string());
+        arguments.extend(properties.into_iter().flatten());
+        // No auxiliary units
+        arguments.push("0".to_string());
+        arguments
+    }
+
+    fn start_scope(unit: &str, pid: u32, limits: &ResourceLimits) -> io::Result<()> {
+        let output = footprint::run_command(
+            Command::new("busctl").args(scope_arguments(unit, pid, limits)),
+        )?;
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Create a cgroup under the root with the limits and move `pid` into it
+    fn create_cgroup(limits: &ResourceLimits, pid: u32) -> io::Result<PathBuf> {
+        let root = Path::new(CGROUP_ROOT);
+        if !root.join("cgroup.controllers").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "cgroup v2 is not mounted",
+            ));
+        }
+
+        let mut controllers = Vec::new();
+        if limits.max_cpu_percent.is_some() {
+            controllers.push("+cpu");
+        }
+        if limits.max_memory_mb.is_some() {
+            controllers.push("+memory");
+        }
+        fs::write(root.join("cgroup.subtree_control"), controllers.join(" "))?;
+
+        let dir = root.join(format!("rust_collector-{}", pid));
+        fs::create_dir(&dir)?;
+        footprint::record_created_path(&dir);
+
+        let configured = configure_cgroup(&dir, limits, pid);
+        if configured.is_err() {
+            let _ = fs::remove_dir(&dir);
+        }
+        configured.map(|()| dir)
+    }
+
+    fn configure_cgroup(dir: &Path, limits: &ResourceLimits, pid: u32) -> io::Result<()> {
+        if let Some(percent) = limits.max_cpu_percent {
+            let quota = cpu_quota_usec(percent, num_cpus::get(), CPU_PERIOD_USEC);
+            fs::write(
+                dir.join("cpu.max"),
+                format!("{} {}", quota, CPU_PERIOD_USEC),
+            )?;
+        }
+        if let Some(bytes) = limits.memory_bytes() {
+            // Reclaim starts at memory.high, well before the OOM killer
+            fs::write(dir.join("memory.high"), (bytes / 10 * 9).to_string())?;
+            fs::write(dir.join("memory.max"), bytes.to_string())?;
+        }
+        fs::write(dir.join("cgroup.procs"), pid.to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod os {
+    use std::io;
+    use std::mem;
+    use std::ptr;
+
+    use log::info;
+    use winapi::shared::minwindef::{DWORD, LPVOID};
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::jobapi2::{
+        AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject,
+    };
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::winnt::{
+        JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation,
+        JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+
+    use super::{Enforcement, ResourceLimits};
+
+    pub(super) fn enforce(limits: &ResourceLimits, enforcement: &mut Enforcement) {
+        match assign_job_object(limits) {
+            Ok(()) => {
+                info!("Assigned the collector to a Job Object");
+                enforcement.mechanisms.push("job_object".to_string());
+            }
+            Err(e) => enforcement
+                .fallbacks
+                .push(format!("Job Object unavailable: {}", e)),
+        }
+    }
+
+    fn assign_job_object(limits: &ResourceLimits) -> io::Result<()> {
+        unsafe {
+            let job = CreateJobObjectW(ptr::null_mut(), ptr::null());
+            if job.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let result = set_job_limits(job, limits).and_then(|()| {
+                if AssignProcessToJobObject(job, GetCurrentProcess()) == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+            // The process keeps the job alive once assigned
+            CloseHandle(job);
+            result
+        }
+    }
+
+    unsafe fn set_job_limits(
+        job: winapi::um::winnt::HANDLE,
+        limits: &ResourceLimits,
+    ) -> io::Result<()> {
+        if let Some(percent) = limits.max_cpu_percent {
+            let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = mem::zeroed();
+            info.ControlFlags =
+                JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+            // In hundredths of a percent of all processors
+            *info.u.CpuRate_mut() = percent * 100;
+            if SetInformationJobObject(
+                job,
+                JobObjectCpuRateControlInformation,
+                &mut info as *mut _ as LPVOID,
+                mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as DWORD,
+            ) == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        if let Some(bytes) = limits.memory_bytes() {
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+            info.ProcessMemoryLimit = bytes as usize;
+            if SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as LPVOID,
+                mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as DWORD,
+            ) == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod os {
+    use std::io;
+
+    use super::{Enforcement, ResourceLimits};
+
+    pub(super) fn enforce(limits: &ResourceLimits, enforcement: &mut Enforcement) {
+        // What `taskpolicy -b` applies: low CPU priority and throttled I/O
+        if unsafe { libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) } == 0 {
+            enforcement.mechanisms.push("darwin_background".to_string());
+        } else {
+            enforcement.fallbacks.push(format!(
+                "background QoS unavailable: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        if limits.max_memory_mb.is_some() {
+            enforcement.fallbacks.push(
+                "macOS has no per-process memory cap; only the governor enforces it".to_string(),
+            );
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+mod os {
+    use super::{Enforcement, ResourceLimits};
+
+    pub(super) fn enforce(_limits: &ResourceLimits, enforcement: &mut Enforcement) {
+        enforcement
+            .fallbacks
+            .push("no OS limits on this platform; only the governor enforces them".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cpu_percent: f32, memory_mb: u64) -> Sample {
+        Sample {
+            cpu_percent,
+            memory_bytes: memory_mb * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::new(ResourceLimits {
+            max_cpu_percent: Some(20),
+            max_memory_mb: Some(100),
+        });
+        throttle.set_max_concurrency(8);
+
+        // Over the CPU target: halve concurrency and pace copies
+        assert!(throttle.observe(sample(35.0, 10)));
+        assert_eq!((throttle.concurrency, throttle.pace_ms), (4, PACE_STEP_MS));
+        assert!(throttle.observe(sample(30.0, 10)));
+        assert_eq!(
+            (throttle.concurrency, throttle.pace_ms),
+            (2, 2 * PACE_STEP_MS)
+        );
+
+        // Just under the target holds steady, well under relaxes
+        assert!(!throttle.observe(sample(19.0, 10)));
+        assert_eq!(
+            (throttle.concurrency, throttle.pace_ms),
+            (2, 2 * PACE_STEP_MS)
+        );
+        assert!(!throttle.observe(sample(5.0, 10)));
+        assert_eq!((throttle.concurrency, throttle.pace_ms), (3, PACE_STEP_MS));
+        throttle.observe(sample(5.0, 10));
+        assert_eq!(throttle.pace_ms, 0);
+
+        // Near the memory cap: one artifact at a time until well below it
+        throttle.observe(sample(5.0, 85));
+        assert!(throttle.memory_pressure);
+        assert_eq!(throttle.concurrency, 1);
+        throttle.observe(sample(5.0, 70));
+        assert!(throttle.memory_pressure);
+        throttle.observe(sample(5.0, 50));
+        assert!(!throttle.memory_pressure);
+        assert_eq!(throttle.concurrency, 2);
+    }
+
+    #[test]
+    fn test_governor_withholds_permits() {
+        let semaphore = Arc::new(Semaphore::new(4));
+        let mut governor = Governor {
+            applied: AppliedLimits::default(),
+            throttle: Throttle::new(ResourceLimits {
+                max_cpu_percent: Some(10),
+                max_memory_mb: None,
+            }),
+            semaphore: Some(GovernedSemaphore {
+                semaphore: Arc::clone(&semaphore),
+                withheld: Vec::new(),
+            }),
+        };
+        governor.throttle.set_max_concurrency(4);
+
+        let busy = Arc::clone(&semaphore).try_acquire_many_owned(3).unwrap();
+        governor.observe(sample(50.0, 10));
+        // Only the free permit can be withheld until the busy ones return
+        assert_eq!(semaphore.available_permits(), 0);
+        drop(busy);
+        governor.observe(sample(50.0, 10));
+        assert_eq!(semaphore.available_permits(), 1);
+        assert_eq!(governor.applied.lowest_concurrency, Some(1));
+        assert_eq!(governor.applied.cpu_throttled_samples, 2);
+        assert_eq!(governor.applied.peak_cpu_percent, 50.0);
+
+        for _ in 0..3 {
+            governor.observe(sample(1.0, 10));
+        }
+        assert_eq!(semaphore.available_permits(), 4);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_limit_values() {
+        assert_eq!(os::cpu_quota_usec(25, 4, 100_000), 100_000);
+        assert_eq!(os::cpu_quota_usec(1, 1, 100_000), 1000);
+
+        let limits = ResourceLimits {
+            max_cpu_percent: Some(50),
+            max_memory_mb: Some(512),
+        };
+        let arguments = os::scope_arguments("rust-collector-42.scope", 42, &limits);
+        assert_eq!(arguments[6..9], ["rust-collector-42.scope", "fail", "4"]);
+        assert_eq!(arguments[9..13], ["PIDs", "au", "1", "42"]);
+        assert!(arguments.ends_with(&[
+            "MemoryMax".to_string(),
+            "t".to_string(),
+            (512u64 * 1024 * 1024).to_string(),
+            "0".to_string()
+        ]));
+    }
+}
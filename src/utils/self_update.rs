@@ -0,0 +1,427 @@
+//! Update checks and self-update against a signed manifest
+//! (`--check-update` and the `self-update` subcommand).
+//!
+//! The manifest is a JSON object with the release `version`, a `platforms`
+//! map from `<os>-<arch>` (e.g. `linux-x86_64`, `windows-x86_64`,
+//! `macos-aarch64`) to the binary's `url` and `sha256`, and a `signature`:
+//! the base64 Ed25519 signature of the rest of the manifest serialized as
+//! compact JSON with sorted keys (`jq -jcS 'del(.signature)'`). The public
+//! key is compiled in from `RS_COLLECTOR_UPDATE_PUBLIC_KEY` (hex) at build
+//! time; builds without one refuse to check or update.
+//!
+//! Nothing here runs during a collection; updating is always an explicit
+//! operator action. A binary is installed only when the manifest signature
+//! verifies and the download matches the signed SHA-256. It is staged next
+//! to the running executable and swapped in by rename: on Unix over the old
+//! file, which running processes keep open; on Windows the running file is
+//! renamed to `<name>.old` first and deleted at the next reboot.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info};
+use openssl::pkey::{Id, PKey};
+use openssl::sign::Verifier;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::utils::http;
+
+/// Build-time environment variable holding the hex Ed25519 public key
+pub const PUBLIC_KEY_ENV: &str = "RS_COLLECTOR_UPDATE_PUBLIC_KEY";
+
+/// Manifest signing key compiled into this build
+const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("RS_COLLECTOR_UPDATE_PUBLIC_KEY");
+
+/// Connect, read and write timeout for the manifest and binary downloads
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest manifest accepted
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+
+/// Largest binary accepted
+const MAX_BINARY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// A signed release manifest, without its signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateManifest {
+    pub version: String,
+    /// Download per `<os>-<arch>`
+    pub platforms: BTreeMap<String, PlatformRelease>,
+}
+
+/// The binary for one platform
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlatformRelease {
+    pub url: String,
+    /// Lowercase hex SHA-256 of the binary
+    pub sha256: String,
+}
+
+/// How the running version compares with the manifest's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStatus {
+    UpToDate,
+    /// The manifest has a newer version
+    Available,
+    /// The running version is newer than the manifest's, which pins an
+    /// older release
+    Ahead,
+}
+
+/// Result of comparing the running collector with a verified manifest
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub current_version: String,
+    pub manifest: UpdateManifest,
+    pub status: UpdateStatus,
+}
+
+impl UpdateCheck {
+    /// The manifest's binary for this platform
+    pub fn release(&self) -> Option<&PlatformRelease> {
+        self.manifest.platforms.get(&platform())
+    }
+
+    /// One-line description for the operator
+    pub fn describe(&self) -> String {
+        let state = match self.status {
+            UpdateStatus::UpToDate => "up to date".to_string(),
+            UpdateStatus::Available => format!("update available: {}", self.manifest.version),
+            UpdateStatus::Ahead => format!(
+                "newer than the manifest's pinned version {}",
+                self.manifest.version
+            ),
+        };
+        let missing = if self.status != UpdateStatus::UpToDate && self.release().is_none() {
+            format!(" (no {} build in the manifest)", platform())
+        } else {
+            String::new()
+        };
+        format!(
+            "{} {}: {}{}",
+            env!("CARGO_PKG_NAME"),
+            self.current_version,
+            state,
+            missing
+        )
+    }
+}
+
+/// `<os>-<arch>` key of this build in the manifest's `platforms`
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Fetch the manifest at `manifest_url`, verify it with the compiled-in key
+/// and compare it with the running version
+pub fn check_update(manifest_url: &str) -> Result<UpdateCheck> {
+    let public_key = compiled_public_key()?;
+    let data =
+        fetch(manifest_url, MAX_MANIFEST_BYTES).context("Failed to fetch update manifest")?;
+    let manifest = verify_manifest(&data, &public_key)?;
+    let current_version = env!("CARGO_PKG_VERSION").to_string();
+    let status = update_status(&current_version, &manifest.version);
+    Ok(UpdateCheck {
+        current_version,
+        manifest,
+        status,
+    })
+}
+
+/// Download `release` and install it over `target`
+pub fn install_release(release: &PlatformRelease, target: &Path) -> Result<()> {
+    info!("Downloading {}", release.url);
+    let binary = fetch(&release.url, MAX_BINARY_BYTES).context("Failed to download update")?;
+    install_binary(&binary, &release.sha256, target)
+}
+
+/// Parse a manifest and check its signature against `public_key`, the raw
+/// 32-byte Ed25519 key.
+///
+/// Manifests without a signature, or whose signature does not verify, are
+/// rejected.
+pub fn verify_manifest(data: &[u8], public_key: &[u8]) -> Result<UpdateManifest> {
+    let mut value: Value = serde_json::from_slice(data).context("Invalid update manifest")?;
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Update manifest is not a JSON object"))?;
+    let signature = match object.remove("signature") {
+        Some(Value::String(signature)) => signature,
+        Some(_) => bail!("Update manifest signature is not a string"),
+        None => bail!("Update manifest is not signed"),
+    };
+    let signature = openssl::base64::decode_block(signature.trim())
+        .map_err(|e| anyhow!("Update manifest signature is not base64: {}", e))?;
+
+    // serde_json maps are sorted, so this is the sorted compact form
+    let payload = serde_json::to_vec(&value).context("Failed to serialize update manifest")?;
+    let key = PKey::public_key_from_raw_bytes(public_key, Id::ED25519)
+        .context("Invalid update public key")?;
+    let valid = Verifier::new_without_digest(&key)
+        .and_then(|mut verifier| verifier.verify_oneshot(&signature, &payload))
+        .unwrap_or(false);
+    if !valid {
+        bail!("Update manifest signature does not verify");
+    }
+
+    serde_json::from_value(value).context("Invalid update manifest")
+}
+
+/// Compare the running version with the manifest's
+pub fn update_status(current: &str, manifest_version: &str) -> UpdateStatus {
+    match compare_versions(current, manifest_version) {
+        Ordering::Less => UpdateStatus::Available,
+        Ordering::Equal => UpdateStatus::UpToDate,
+        Ordering::Greater => UpdateStatus::Ahead,
+    }
+}
+
+/// Compare dotted versions numerically, with a `-pre` release before the
+/// release itself; `+build` metadata is ignored
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split('+').next().unwrap_or_default();
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let mut numbers: Vec<u64> = core
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        while numbers.len() > 1 && numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        (numbers, pre)
+    }
+    let (a_core, a_pre) = split(a);
+    let (b_core, b_pre) = split(b);
+    a_core.cmp(&b_core).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(b),
+    })
+}
+
+/// Check `binary` against the signed `expected_sha256` and put it in place
+/// of `target`.
+///
+/// A mismatch is refused before anything is written.
+pub fn install_binary(binary: &[u8], expected_sha256: &str, target: &Path) -> Result<()> {
+    let sha256 = format!("{:x}", Sha256::digest(binary));
+    if !sha256.eq_ignore_ascii_case(expected_sha256.trim()) {
+        bail!(
+            "Refusing to install update: its SHA-256 is {} but the signed manifest lists {}",
+            sha256,
+            expected_sha256.trim()
+        );
+    }
+
+    let staged = sibling(target, |name| format!(".{}.update", name))?;
+    let result = write_staged(binary, target, &staged).and_then(|()| replace(&staged, target));
+    if result.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    result
+}
+
+/// A path next to `target` whose file name is `name(target's name)`
+fn sibling(target: &Path, name: impl Fn(&str) -> String) -> Result<PathBuf> {
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no file name", target.display()))?;
+    Ok(target.with_file_name(name(&file_name.to_string_lossy())))
+}
+
+/// Write the new binary next to `target`, with `target`'s permissions
+fn write_staged(binary: &[u8], target: &Path, staged: &Path) -> Result<()> {
+    let mut file =
+        fs::File::create(staged).context(format!("Failed to create {}", staged.display()))?;
+    file.write_all(binary)
+        .context(format!("Failed to write {}", staged.display()))?;
+    file.sync_all()
+        .context(format!("Failed to flush {}", staged.display()))?;
+    drop(file);
+
+    let permissions = fs::metadata(target)
+        .context(format!("Failed to read {}", target.display()))?
+        .permissions();
+    fs::set_permissions(staged, permissions)
+        .context(format!("Failed to set permissions on {}", staged.display()))
+}
+
+/// Rename `staged` over `target`, which may be running
+#[cfg(not(target_os = "windows"))]
+fn replace(staged: &Path, target: &Path) -> Result<()> {
+    fs::rename(staged, target).context(format!("Failed to replace {}", target.display()))
+}
+
+/// Move the running `target` aside and rename `staged` into its place; the
+/// old binary is deleted at the next reboot
+#[cfg(target_os = "windows")]
+fn replace(staged: &Path, target: &Path) -> Result<()> {
+    let old = sibling(target, |name| format!("{}.old", name))?;
+    // Left by an earlier update whose reboot has not happened yet
+    let _ = fs::remove_file(&old);
+    fs::rename(target, &old).context(format!("Failed to move {} aside", target.display()))?;
+    if let Err(e) = fs::rename(staged, target) {
+        let _ = fs::rename(&old, target);
+        return Err(e).context(format!("Failed to replace {}", target.display()));
+    }
+    if let Err(e) = delete_at_reboot(&old) {
+        log::warn!(
+            "Could not schedule {} for deletion at reboot, remove it manually: {}",
+            old.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn delete_at_reboot(path: &Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::winbase::{MoveFileExW, MOVEFILE_DELAY_UNTIL_REBOOT};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    if unsafe { MoveFileExW(wide.as_ptr(), std::ptr::null(), MOVEFILE_DELAY_UNTIL_REBOOT) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The raw Ed25519 key from [`PUBLIC_KEY_ENV`] at build time
+fn compiled_public_key() -> Result<Vec<u8>> {
+    let hex = UPDATE_PUBLIC_KEY.ok_or_else(|| {
+        anyhow!(
+            "This build has no update signing key; rebuild with {} set to the hex Ed25519 public key",
+            PUBLIC_KEY_ENV
+        )
+    })?;
+    from_hex(hex.trim())
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| anyhow!("{} is not a 32-byte hex key", PUBLIC_KEY_ENV))
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// GET `url` and return the body of a `200` response
+fn fetch(url: &str, max_bytes: u64) -> Result<Vec<u8>> {
+    // One byte over the limit tells a full response from a truncated one
+    let response = http::get(url, HTTP_TIMEOUT, max_bytes + 1)?;
+    if response.status != 200 {
+        bail!("{} returned HTTP {}", url, response.status);
+    }
+    if response.body.len() as u64 > max_bytes {
+        bail!("{} is larger than {} bytes", url, max_bytes);
+    }
+    debug!("Fetched {} bytes from {}", response.body.len(), url);
+    Ok(response.body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn signed_manifest(key: &PKey<Private>, manifest: &Value) -> Vec<u8> {
+        let payload = serde_json::to_vec(manifest).unwrap();
+        let signature = Signer::new_without_digest(key)
+            .unwrap()
+            .sign_oneshot_to_vec(&payload)
+            .unwrap();
+        let mut signed = manifest.clone();
+        signed["signature"] = json!(openssl::base64::encode_block(&signature));
+        serde_json::to_vec_pretty(&signed).unwrap()
+    }
+
+    fn manifest() -> Value {
+        json!({
+            "version": "0.4.0",
+            "platforms": {
+                "linux-x86_64": {
+                    "url": "https://updates.corp/rust_collector-linux",
+                    "sha256": format!("{:x}", Sha256::digest(b"new binary")),
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn test_verify_manifest() {
+        let key = PKey::generate_ed25519().unwrap();
+        let public_key = key.raw_public_key().unwrap();
+        let signed = signed_manifest(&key, &manifest());
+
+        let verified = verify_manifest(&signed, &public_key).unwrap();
+        assert_eq!(verified.version, "0.4.0");
+        assert!(verified.platforms.contains_key("linux-x86_64"));
+
+        // Tampered after signing
+        let mut tampered: Value = serde_json::from_slice(&signed).unwrap();
+        tampered["version"] = json!("9.9.9");
+        let tampered = serde_json::to_vec(&tampered).unwrap();
+        assert!(verify_manifest(&tampered, &public_key).is_err());
+
+        // Signed by another key
+        let other = PKey::generate_ed25519().unwrap();
+        let err = verify_manifest(&signed, &other.raw_public_key().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("does not verify"));
+
+        let unsigned = serde_json::to_vec(&manifest()).unwrap();
+        let err = verify_manifest(&unsigned, &public_key).unwrap_err();
+        assert!(err.to_string().contains("not signed"));
+    }
+
+    #[test]
+    fn test_install_binary_refuses_mismatch() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("rust_collector");
+        fs::write(&target, b"old binary").unwrap();
+        let expected = format!("{:x}", Sha256::digest(b"new binary"));
+
+        let err = install_binary(b"tampered binary", &expected, &target).unwrap_err();
+        assert!(err.to_string().contains("Refusing to install"));
+        assert_eq!(fs::read(&target).unwrap(), b"old binary");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+        install_binary(b"new binary", &expected.to_uppercase(), &target).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new binary");
+    }
+
+    #[test]
+    fn test_update_status() {
+        assert_eq!(update_status("0.3.0", "0.4.0"), UpdateStatus::Available);
+        assert_eq!(update_status("0.3.0", "v0.3"), UpdateStatus::UpToDate);
+        assert_eq!(update_status("0.10.0", "0.9.1"), UpdateStatus::Ahead);
+        assert_eq!(update_status("1.0.0-rc1", "1.0.0"), UpdateStatus::Available);
+        assert_eq!(
+            update_status("1.0.0+build5", "1.0.0"),
+            UpdateStatus::UpToDate
+        );
+
+        assert_eq!(from_hex("00ff10"), Some(vec![0, 255, 16]));
+        assert_eq!(from_hex("0g"), None);
+        assert_eq!(from_hex("abc"), None);
+    }
+}
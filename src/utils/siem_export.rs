@@ -0,0 +1,1055 @@
+//! Export of a finished collection to a SIEM (`export` subcommand).
+//!
+//! Processes, TCP sockets, collected artifacts, parsed event logs and
+//! parser findings are mapped to documents with one field schema, loosely
+//! following the Elastic Common Schema. Every document has `@timestamp`,
+//! `host.name`, `collection_id` and `event.kind`, `event.category` and
+//! `event.dataset` (`rs_collector.<type>`); the type-specific fields are
+//! under the ECS names where one exists and `rs_collector` otherwise.
+//!
+//! Documents are written as OpenSearch/Elasticsearch `_bulk` bodies or
+//! Splunk HEC events, either to one `.ndjson` file per document type or
+//! POSTed to the endpoint in batches. Failed batches are retried with
+//! backoff on connection errors, `429` and `5xx` responses.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use log::{debug, info, warn};
+use serde_json::{json, Map, Value};
+use walkdir::WalkDir;
+
+use crate::cli::SiemFormat;
+use crate::collectors::linux::kernel_messages::DMESG_FILE;
+use crate::collectors::macos::login_records::LOGINWINDOW_FILE;
+use crate::collectors::macos::unified_log_parser::UNIFIED_LOG_FILE;
+use crate::collectors::parsers::PARSED_DIR;
+use crate::collectors::volatile::models::ProcessInfo;
+use crate::collectors::volatile::tcp_sockets::{TcpSocket, TCP_SOCKETS_FILE};
+use crate::collectors::windows::iis_logs::IIS_EVENTS_FILE;
+use crate::utils::http::{self, Url};
+use crate::utils::summary::{CollectionSummary, SummaryArtifact};
+
+/// Directory under the collection directory that receives exported files
+pub const EXPORT_DIR: &str = "siem_export";
+
+/// OpenSearch index name when `--index` is not given
+pub const DEFAULT_INDEX_TEMPLATE: &str = "rs-collector-{type}-{date}";
+
+/// Splunk sourcetype when `--sourcetype` is not given
+pub const DEFAULT_SOURCETYPE_TEMPLATE: &str = "rs_collector:{type}";
+
+/// Prefix of `event.dataset` and namespace of fields with no ECS name
+const DATASET_PREFIX: &str = "rs_collector";
+
+/// Parsed event logs exported as `event` documents: file name, dataset
+/// and `event.category`
+const EVENT_SOURCES: &[(&str, &str, &str)] = &[
+    ("journal", "journal", "host"),
+    (DMESG_FILE, "dmesg", "host"),
+    (UNIFIED_LOG_FILE, "unified_log", "host"),
+    (IIS_EVENTS_FILE, "iis", "web"),
+    (LOGINWINDOW_FILE, "loginwindow", "authentication"),
+];
+
+/// Parser reports under [`PARSED_DIR`] exported as `finding` documents
+const FINDINGS_SUFFIX: &str = "_findings.json";
+
+/// Connect, read and write timeout for one request
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest response read from the endpoint
+const MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Longest wait between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// What a document describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DocumentType {
+    Process,
+    Connection,
+    Artifact,
+    Event,
+    Finding,
+}
+
+impl DocumentType {
+    /// Name used for `{type}` in templates, file names and `event.dataset`
+    pub fn name(self) -> &'static str {
+        match self {
+            DocumentType::Process => "process",
+            DocumentType::Connection => "connection",
+            DocumentType::Artifact => "artifact",
+            DocumentType::Event => "event",
+            DocumentType::Finding => "finding",
+        }
+    }
+}
+
+/// Collection-wide fields shared by every document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportContext {
+    pub hostname: String,
+    pub collection_id: String,
+    /// Start of the collection, used when a record has no time of its own
+    pub collection_time: DateTime<Utc>,
+}
+
+impl ExportContext {
+    /// Context from a collection summary
+    pub fn from_summary(summary: &CollectionSummary) -> Self {
+        Self {
+            hostname: summary.hostname.clone(),
+            collection_id: summary.collection_id.clone(),
+            collection_time: parse_collection_time(&summary.collection_time)
+                .unwrap_or_else(Utc::now),
+        }
+    }
+
+    /// Common fields of a document
+    fn document(
+        &self,
+        doc_type: DocumentType,
+        dataset: &str,
+        timestamp: DateTime<Utc>,
+        kind: &str,
+        category: &str,
+        fields: Value,
+    ) -> SiemDocument {
+        let mut body = json!({
+            "@timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "collection_id": self.collection_id,
+            "host": { "name": self.hostname },
+            "event": {
+                "kind": kind,
+                "category": [category],
+                "dataset": format!("{}.{}", DATASET_PREFIX, dataset),
+                "module": DATASET_PREFIX,
+            },
+        });
+        merge(&mut body, fields);
+        strip_nulls(&mut body);
+        SiemDocument {
+            doc_type,
+            timestamp,
+            body,
+        }
+    }
+}
+
+/// One exported document
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiemDocument {
+    pub doc_type: DocumentType,
+    pub timestamp: DateTime<Utc>,
+    pub body: Value,
+}
+
+/// A running process from `volatile/processes.json`, stamped with its start time
+pub fn process_document(ctx: &ExportContext, process: &ProcessInfo) -> SiemDocument {
+    let start = Utc.timestamp_opt(process.start_time as i64, 0).single();
+    ctx.document(
+        DocumentType::Process,
+        "process",
+        start.unwrap_or(ctx.collection_time),
+        "state",
+        "process",
+        json!({
+            "process": {
+                "pid": process.pid,
+                "name": process.name,
+                "executable": process.exe,
+                "args": process.cmd,
+                "command_line": process.cmd.join(" "),
+                "parent": { "pid": process.parent_pid },
+                "start": start.map(|s| s.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            },
+            DATASET_PREFIX: {
+                "status": process.status,
+                "cpu_usage": process.cpu_usage,
+                "memory_usage": process.memory_usage,
+            },
+        }),
+    )
+}
+
+/// A TCP socket from `volatile/tcp_sockets_extended.json`
+pub fn connection_document(ctx: &ExportContext, socket: &TcpSocket) -> SiemDocument {
+    ctx.document(
+        DocumentType::Connection,
+        "connection",
+        ctx.collection_time,
+        "state",
+        "network",
+        json!({
+            "network": { "transport": "tcp" },
+            "source": { "ip": socket.local_addr, "port": socket.local_port },
+            "destination": { "ip": socket.remote_addr, "port": socket.remote_port },
+            "process": { "pid": socket.pid, "name": socket.process_name },
+            DATASET_PREFIX: { "state": socket.state, "inode": socket.inode },
+        }),
+    )
+}
+
+/// A collected artifact from `collection_summary.json`
+pub fn artifact_document(ctx: &ExportContext, artifact: &SummaryArtifact) -> SiemDocument {
+    let metadata = &artifact.metadata;
+    ctx.document(
+        DocumentType::Artifact,
+        "artifact",
+        parse_time(&metadata.collection_time).unwrap_or(ctx.collection_time),
+        "state",
+        "file",
+        json!({
+            "file": {
+                "path": metadata.original_path,
+                "size": metadata.file_size,
+                "created": metadata.created_time,
+                "accessed": metadata.accessed_time,
+                "mtime": metadata.modified_time,
+                "hash": { "sha256": metadata.sha256 },
+                "mime_type": metadata.detected_type,
+            },
+            DATASET_PREFIX: {
+                "collected_as": artifact.path,
+                "is_locked": metadata.is_locked,
+                "nsrl_known_good": metadata.nsrl_known_good,
+                "fallback_method": metadata.fallback_method,
+            },
+        }),
+    )
+}
+
+/// One line of a parsed event log.
+///
+/// The time is read from `__REALTIME_TIMESTAMP` (journal), `timestamp` or
+/// `date` and `time` (IIS, UTC). The line itself is kept as
+/// `event.original`. Lines that are not JSON objects are skipped.
+pub fn event_document(
+    ctx: &ExportContext,
+    dataset: &str,
+    category: &str,
+    source_file: &str,
+    line: &str,
+) -> Option<SiemDocument> {
+    let record = match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(record)) => record,
+        _ => return None,
+    };
+    let mut document = ctx.document(
+        DocumentType::Event,
+        dataset,
+        event_time(&record).unwrap_or(ctx.collection_time),
+        "event",
+        category,
+        json!({
+            "message": event_message(&record),
+            "log": { "file": { "path": source_file } },
+        }),
+    );
+    document.body["event"]["original"] = Value::String(line.to_string());
+    Some(document)
+}
+
+/// A record of a parser findings report (`parsed/*_findings.json`)
+pub fn finding_document(ctx: &ExportContext, ruleset: &str, record: &Value) -> SiemDocument {
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| record.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+    };
+    let timestamp = text(&["timestamp"])
+        .and_then(|t| parse_time(&t))
+        .unwrap_or(ctx.collection_time);
+    ctx.document(
+        DocumentType::Finding,
+        "finding",
+        timestamp,
+        "alert",
+        "intrusion_detection",
+        json!({
+            "message": text(&["description", "reason"]),
+            "rule": { "name": text(&["check", "location"]), "ruleset": ruleset },
+            "file": { "path": text(&["evidence", "source"]) },
+            DATASET_PREFIX: {
+                "severity": text(&["severity"]),
+                "finding": record,
+            },
+        }),
+    )
+}
+
+/// Read every exportable record of the collection in `collection_dir`,
+/// which holds `collection_summary.json`
+pub fn load_collection(collection_dir: &Path) -> Result<(ExportContext, Vec<SiemDocument>)> {
+    let summary_path = collection_dir.join("collection_summary.json");
+    let summary = CollectionSummary::from_json(
+        &fs::read_to_string(&summary_path)
+            .context(format!("Failed to read {}", summary_path.display()))?,
+    )?;
+    let ctx = ExportContext::from_summary(&summary);
+    let mut documents = Vec::new();
+
+    let volatile_dir = collection_dir.join("volatile");
+    if let Some(processes) = read_json::<Vec<ProcessInfo>>(&volatile_dir.join("processes.json")) {
+        documents.extend(processes.iter().map(|p| process_document(&ctx, p)));
+    }
+    if let Some(sockets) = read_json::<Vec<TcpSocket>>(&volatile_dir.join(TCP_SOCKETS_FILE)) {
+        documents.extend(sockets.iter().map(|s| connection_document(&ctx, s)));
+    }
+
+    documents.extend(summary.artifacts.iter().map(|a| artifact_document(&ctx, a)));
+
+    let export_dir = collection_dir.join(EXPORT_DIR);
+    for entry in WalkDir::new(collection_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.path() != export_dir)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        let relative = entry
+            .path()
+            .strip_prefix(collection_dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .to_string();
+
+        if let Some((_, dataset, category)) =
+            EVENT_SOURCES.iter().find(|(file, _, _)| *file == name)
+        {
+            let file = match fs::File::open(entry.path()) {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("Failed to open {}: {}", entry.path().display(), e);
+                    continue;
+                }
+            };
+            let before = documents.len();
+            documents.extend(
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| event_document(&ctx, dataset, category, &relative, &line)),
+            );
+            debug!("{} events from {}", documents.len() - before, relative);
+        } else if let Some(ruleset) = name.strip_suffix(FINDINGS_SUFFIX) {
+            let in_parsed_dir = entry
+                .path()
+                .parent()
+                .is_some_and(|parent| parent.ends_with(PARSED_DIR));
+            if !in_parsed_dir {
+                continue;
+            }
+            if let Some(report) = read_json::<Value>(entry.path()) {
+                let records = report["records"].as_array().cloned().unwrap_or_default();
+                documents.extend(records.iter().map(|r| finding_document(&ctx, ruleset, r)));
+            }
+        }
+    }
+
+    Ok((ctx, documents))
+}
+
+/// Render an index or sourcetype template.
+///
+/// `{host}`, `{type}`, `{collection_id}` and `{date}` (the collection
+/// date as `YYYY.MM.DD`) are replaced.
+pub fn render_template(template: &str, ctx: &ExportContext, doc_type: DocumentType) -> String {
+    template
+        .replace("{host}", &ctx.hostname)
+        .replace("{type}", doc_type.name())
+        .replace("{collection_id}", &ctx.collection_id)
+        .replace(
+            "{date}",
+            &ctx.collection_time.format("%Y.%m.%d").to_string(),
+        )
+}
+
+/// Where the documents go
+#[derive(Debug, Clone)]
+pub enum ExportDestination {
+    /// One `<type>.ndjson` file per document type in this directory
+    Files(PathBuf),
+    /// POSTed to a `_bulk` or HEC endpoint
+    Http {
+        url: String,
+        /// `Authorization` header value
+        authorization: Option<String>,
+    },
+}
+
+/// Options of the `export` subcommand
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: SiemFormat,
+    /// OpenSearch index (default [`DEFAULT_INDEX_TEMPLATE`]) or Splunk index
+    pub index_template: Option<String>,
+    pub sourcetype_template: String,
+    /// Documents per request
+    pub batch_size: usize,
+    /// Retries of a failed request
+    pub retries: u32,
+    pub destination: ExportDestination,
+}
+
+/// Counts of an export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// Documents by type name
+    pub documents: BTreeMap<String, usize>,
+    /// Requests sent, or files written
+    pub batches: usize,
+    /// Documents the endpoint reported as failed
+    pub rejected: usize,
+}
+
+/// Format `document` as newline-terminated lines for `format`: an action
+/// line and the document for `_bulk`, or one HEC event
+pub fn format_document(
+    document: &SiemDocument,
+    ctx: &ExportContext,
+    options: &ExportOptions,
+) -> Result<String> {
+    let lines = match options.format {
+        SiemFormat::Opensearch => {
+            let index = render_template(
+                options
+                    .index_template
+                    .as_deref()
+                    .unwrap_or(DEFAULT_INDEX_TEMPLATE),
+                ctx,
+                document.doc_type,
+            )
+            .to_lowercase();
+            format!(
+                "{}\n{}\n",
+                json!({ "index": { "_index": index } }),
+                serde_json::to_string(&document.body)?
+            )
+        }
+        SiemFormat::Splunk => {
+            let mut event = json!({
+                "time": document.timestamp.timestamp_millis() as f64 / 1000.0,
+                "host": ctx.hostname,
+                "source": DATASET_PREFIX,
+                "sourcetype": render_template(&options.sourcetype_template, ctx, document.doc_type),
+                "event": document.body,
+            });
+            if let Some(index) = &options.index_template {
+                event["index"] = Value::String(render_template(index, ctx, document.doc_type));
+            }
+            format!("{}\n", serde_json::to_string(&event)?)
+        }
+    };
+    Ok(lines)
+}
+
+/// Export the collection in `collection_dir` as `options` describe
+pub fn export_collection(collection_dir: &Path, options: &ExportOptions) -> Result<ExportStats> {
+    let (ctx, documents) = load_collection(collection_dir)?;
+    info!(
+        "Exporting {} documents from collection {} of {}",
+        documents.len(),
+        ctx.collection_id,
+        ctx.hostname
+    );
+
+    let mut stats = ExportStats::default();
+    for document in &documents {
+        *stats
+            .documents
+            .entry(document.doc_type.name().to_string())
+            .or_default() += 1;
+    }
+
+    match &options.destination {
+        ExportDestination::Files(dir) => {
+            fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+            let mut files: BTreeMap<DocumentType, String> = BTreeMap::new();
+            for document in &documents {
+                files
+                    .entry(document.doc_type)
+                    .or_default()
+                    .push_str(&format_document(document, &ctx, options)?);
+            }
+            for (doc_type, contents) in files {
+                let path = dir.join(format!("{}.ndjson", doc_type.name()));
+                fs::write(&path, contents)
+                    .context(format!("Failed to write {}", path.display()))?;
+                stats.batches += 1;
+            }
+        }
+        ExportDestination::Http { url, authorization } => {
+            let endpoint = export_endpoint(url, options.format)?;
+            if !endpoint.tls && authorization.is_some() {
+                warn!("Sending credentials to {} without TLS", url);
+            }
+            for batch in documents.chunks(options.batch_size.max(1)) {
+                let mut body = String::new();
+                for document in batch {
+                    body.push_str(&format_document(document, &ctx, options)?);
+                }
+                let response = send_with_retry(
+                    &endpoint,
+                    options,
+                    authorization.as_deref(),
+                    body.as_bytes(),
+                )?;
+                stats.batches += 1;
+                if options.format == SiemFormat::Opensearch {
+                    stats.rejected += bulk_item_errors(&response);
+                }
+            }
+            if stats.rejected > 0 {
+                warn!("{} documents were rejected by {}", stats.rejected, url);
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// `YYYYMMDD-HHMMSS` as written to the summary, or RFC 3339
+fn parse_collection_time(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, "%Y%m%d-%H%M%S")
+        .map(|t| t.and_utc())
+        .ok()
+        .or_else(|| parse_time(text))
+}
+
+/// RFC 3339, or `YYYY-MM-DD HH:MM:SS[.f]±zzzz` as printed by `log show`
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%z"))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn event_time(record: &Map<String, Value>) -> Option<DateTime<Utc>> {
+    if let Some(micros) = record
+        .get("__REALTIME_TIMESTAMP")
+        .and_then(Value::as_str)
+        .and_then(|us| us.parse::<i64>().ok())
+    {
+        return Utc.timestamp_micros(micros).single();
+    }
+    if let Some(timestamp) = record.get("timestamp").and_then(Value::as_str) {
+        return parse_time(timestamp);
+    }
+    let date = record.get("date").and_then(Value::as_str)?;
+    let time = record.get("time").and_then(Value::as_str)?;
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+fn event_message(record: &Map<String, Value>) -> Option<String> {
+    if let Some(message) = ["message", "MESSAGE"]
+        .iter()
+        .find_map(|key| record.get(*key).and_then(Value::as_str))
+    {
+        return Some(message.to_string());
+    }
+    // IIS requests have no message field
+    let method = record.get("method").and_then(Value::as_str)?;
+    let uri = record.get("uri_stem").and_then(Value::as_str)?;
+    let status = record.get("status_code").and_then(Value::as_u64)?;
+    Some(format!("{} {} {}", method, uri, status))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Skipping {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Merge the object `fields` into the object `target`, recursively
+fn merge(target: &mut Value, fields: Value) {
+    match (target, fields) {
+        (Value::Object(target), Value::Object(fields)) => {
+            for (key, value) in fields {
+                match target.get_mut(&key) {
+                    Some(existing) if existing.is_object() && value.is_object() => {
+                        merge(existing, value)
+                    }
+                    _ => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, fields) => *target = fields,
+    }
+}
+
+/// Drop null fields and objects left empty, so absent values are not
+/// indexed as `null`
+fn strip_nulls(value: &mut Value) {
+    if let Value::Object(map) = value {
+        for child in map.values_mut() {
+            strip_nulls(child);
+        }
+        map.retain(|_, child| match child {
+            Value::Null => false,
+            Value::Object(child) => !child.is_empty(),
+            _ => true,
+        });
+    }
+}
+
+/// Number of items an OpenSearch `_bulk` response reports as failed
+fn bulk_item_errors(response: &[u8]) -> usize {
+    let Ok(response) = serde_json::from_slice::<Value>(response) else {
+        return 0;
+    };
+    if response["errors"] != Value::Bool(true) {
+        return 0;
+    }
+    response["items"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|item| {
+                    item.as_object()
+                        .and_then(|actions| actions.values().next())
+                        .is_some_and(|result| result.get("error").is_some())
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Parse the export URL, adding the `_bulk` or HEC event path when it only
+/// names the server
+fn export_endpoint(url: &str, format: SiemFormat) -> Result<Url> {
+    let mut endpoint = Url::parse(url).context("Invalid export URL")?;
+    let trimmed = endpoint.path.trim_end_matches('/');
+    endpoint.path = match format {
+        SiemFormat::Opensearch if !trimmed.ends_with("/_bulk") => format!("{}/_bulk", trimmed),
+        SiemFormat::Splunk if trimmed.is_empty() => "/services/collector/event".to_string(),
+        _ => endpoint.path,
+    };
+    Ok(endpoint)
+}
+
+/// POST `body`, retrying connection errors, `429` and `5xx` responses
+fn send_with_retry(
+    endpoint: &Url,
+    options: &ExportOptions,
+    authorization: Option<&str>,
+    body: &[u8],
+) -> Result<Vec<u8>> {
+    let content_type = match options.format {
+        SiemFormat::Opensearch => "application/x-ndjson",
+        SiemFormat::Splunk => "application/json",
+    };
+    let mut attempt = 0;
+    loop {
+        let error = match post(endpoint, content_type, authorization, body) {
+            Ok((status, response)) if (200..300).contains(&status) => return Ok(response),
+            Ok((status, response)) => {
+                let error = anyhow!(
+                    "{} returned HTTP {}: {}",
+                    endpoint.authority(),
+                    status,
+                    String::from_utf8_lossy(&response).trim()
+                );
+                if status != 429 && status < 500 {
+                    return Err(error);
+                }
+                error
+            }
+            Err(e) => e,
+        };
+        if attempt >= options.retries {
+            return Err(error.context(format!("Export failed after {} attempts", attempt + 1)));
+        }
+        let backoff = Duration::from_secs(1 << attempt.min(5)).min(MAX_BACKOFF);
+        warn!("{:#}; retrying in {}s", error, backoff.as_secs());
+        thread::sleep(backoff);
+        attempt += 1;
+    }
+}
+
+/// Send one HTTP/1.1 POST and return the status and body of the response
+fn post(
+    endpoint: &Url,
+    content_type: &str,
+    authorization: Option<&str>,
+    body: &[u8],
+) -> Result<(u16, Vec<u8>)> {
+    let mut headers = vec![("Content-Type", content_type)];
+    if let Some(authorization) = authorization {
+        headers.push(("Authorization", authorization));
+    }
+    let response = http::send(
+        "POST",
+        endpoint,
+        &headers,
+        body,
+        HTTP_TIMEOUT,
+        MAX_RESPONSE_BYTES,
+    )?;
+    Ok((response.status, response.body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ArtifactMetadata;
+    use std::io::{Read, Write};
+    use tempfile::TempDir;
+
+    const PROCESS_GOLDEN: &str = include_str!("../../tests/fixtures/siem_export/process.json");
+    const CONNECTION_GOLDEN: &str =
+        include_str!("../../tests/fixtures/siem_export/connection.json");
+    const ARTIFACT_GOLDEN: &str = include_str!("../../tests/fixtures/siem_export/artifact.json");
+    const EVENT_GOLDEN: &str = include_str!("../../tests/fixtures/siem_export/event.json");
+    const FINDING_GOLDEN: &str = include_str!("../../tests/fixtures/siem_export/finding.json");
+
+    fn context() -> ExportContext {
+        ExportContext {
+            hostname: "WS01".to_string(),
+            collection_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            collection_time: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
+    fn assert_golden(document: &SiemDocument, golden: &str) {
+        let actual = serde_json::to_string_pretty(&document.body).unwrap();
+        assert_eq!(actual.trim(), golden.trim());
+    }
+
+    fn options(format: SiemFormat, index_template: Option<&str>) -> ExportOptions {
+        ExportOptions {
+            format,
+            index_template: index_template.map(str::to_string),
+            sourcetype_template: DEFAULT_SOURCETYPE_TEMPLATE.to_string(),
+            batch_size: 500,
+            retries: 0,
+            destination: ExportDestination::Files(PathBuf::from(EXPORT_DIR)),
+        }
+    }
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            name: "powershell.exe".to_string(),
+            cmd: vec![
+                "powershell.exe".to_string(),
+                "-nop".to_string(),
+                "-w".to_string(),
+                "hidden".to_string(),
+            ],
+            exe: Some(r"C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe".to_string()),
+            status: "Run".to_string(),
+            start_time: 1709290800,
+            cpu_usage: 1.5,
+            memory_usage: 52428800,
+            parent_pid: Some(1200),
+        }
+    }
+
+    #[test]
+    fn test_process_document_golden() {
+        assert_golden(&process_document(&context(), &process()), PROCESS_GOLDEN);
+    }
+
+    #[test]
+    fn test_connection_document_golden() {
+        let socket = TcpSocket {
+            local_addr: "10.0.0.5".to_string(),
+            local_port: 49811,
+            remote_addr: "203.0.113.7".to_string(),
+            remote_port: 443,
+            state: "ESTABLISHED".to_string(),
+            pid: Some(4242),
+            process_name: Some("powershell.exe".to_string()),
+            inode: None,
+        };
+        assert_golden(&connection_document(&context(), &socket), CONNECTION_GOLDEN);
+    }
+
+    #[test]
+    fn test_artifact_document_golden() {
+        let artifact = SummaryArtifact {
+            path: "fs/Windows/System32/config/SYSTEM".to_string(),
+            metadata: ArtifactMetadata {
+                original_path: r"C:\Windows\System32\config\SYSTEM".to_string(),
+                collection_time: "2024-03-01T12:01:30+00:00".to_string(),
+                file_size: 12582912,
+                created_time: Some("2023-06-01T08:00:00+00:00".to_string()),
+                accessed_time: None,
+                modified_time: Some("2024-03-01T11:58:00+00:00".to_string()),
+                is_locked: true,
+                sha256: Some("ab".repeat(32)),
+                detected_type: Some("registry_hive".to_string()),
+                fallback_method: Some("vss".to_string()),
+                ..Default::default()
+            },
+        };
+        assert_golden(&artifact_document(&context(), &artifact), ARTIFACT_GOLDEN);
+    }
+
+    #[test]
+    fn test_event_document_golden() {
+        let line = r#"{"__REALTIME_TIMESTAMP":"1709290800123456","MESSAGE":"Accepted publickey for root from 203.0.113.7","_COMM":"sshd"}"#;
+        let document =
+            event_document(&context(), "journal", "host", "fs/var/log/journal", line).unwrap();
+        assert_golden(&document, EVENT_GOLDEN);
+
+        assert!(event_document(&context(), "journal", "host", "journal", "not json").is_none());
+
+        let iis = r#"{"date":"2024-03-01","time":"10:00:00","method":"GET","uri_stem":"/owa/auth.aspx","status_code":200}"#;
+        let document = event_document(&context(), "iis", "web", "iis_events.jsonl", iis).unwrap();
+        assert_eq!(document.body["message"], "GET /owa/auth.aspx 200");
+        assert_eq!(document.body["@timestamp"], "2024-03-01T10:00:00.000Z");
+
+        let unified = r#"{"timestamp":"2024-03-01 02:00:00.500000-0800","message":"hello"}"#;
+        let document = event_document(
+            &context(),
+            "unified_log",
+            "host",
+            "unified_logs.jsonl",
+            unified,
+        )
+        .unwrap();
+        assert_eq!(document.body["@timestamp"], "2024-03-01T10:00:00.500Z");
+    }
+
+    #[test]
+    fn test_finding_document_golden() {
+        let record = json!({
+            "check": "log_cleared",
+            "severity": "high",
+            "evidence": "fs/Windows/System32/winevt/Logs/Security.evtx",
+            "description": "Security log cleared (event 1102) by CORP\\admin",
+            "timestamp": "2024-03-01T09:15:00Z"
+        });
+        assert_golden(
+            &finding_document(&context(), "antiforensics", &record),
+            FINDING_GOLDEN,
+        );
+    }
+
+    #[test]
+    fn test_format_document() {
+        let ctx = context();
+        let document = process_document(&ctx, &process());
+
+        let bulk =
+            format_document(&document, &ctx, &options(SiemFormat::Opensearch, None)).unwrap();
+        let lines: Vec<&str> = bulk.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"index":{"_index":"rs-collector-process-2024.03.01"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(lines[1]).unwrap(),
+            document.body
+        );
+
+        let hec = format_document(
+            &document,
+            &ctx,
+            &options(SiemFormat::Splunk, Some("dfir_{host}")),
+        )
+        .unwrap();
+        let event: Value = serde_json::from_str(hec.trim()).unwrap();
+        assert_eq!(event["time"], 1709290800.0);
+        assert_eq!(event["host"], "WS01");
+        assert_eq!(event["sourcetype"], "rs_collector:process");
+        assert_eq!(event["index"], "dfir_WS01");
+        assert_eq!(event["event"], document.body);
+    }
+
+    #[test]
+    fn test_load_and_export_collection() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let summary = CollectionSummary::new("WS01", "20240301-120000", &[], None, None);
+        fs::write(
+            dir.join("collection_summary.json"),
+            serde_json::to_string(&summary).unwrap(),
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("volatile")).unwrap();
+        fs::write(
+            dir.join("volatile/processes.json"),
+            serde_json::to_string(&vec![process()]).unwrap(),
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("fs/var/log")).unwrap();
+        fs::write(
+            dir.join("fs/var/log/journal"),
+            "{\"__REALTIME_TIMESTAMP\":\"1709290800000000\",\"MESSAGE\":\"a\"}\nnot json\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join(PARSED_DIR)).unwrap();
+        fs::write(
+            dir.join(PARSED_DIR).join("persistence_findings.json"),
+            r#"{"sources":[],"notes":[],"records":[{"location":"ld_so_preload","reason":"in /tmp"}]}"#,
+        )
+        .unwrap();
+
+        let (ctx, documents) = load_collection(dir).unwrap();
+        assert_eq!(ctx.collection_id, summary.collection_id);
+        assert_eq!(ctx.collection_time, context().collection_time);
+        let types: Vec<DocumentType> = documents.iter().map(|d| d.doc_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                DocumentType::Process,
+                DocumentType::Event,
+                DocumentType::Finding
+            ]
+        );
+        assert_eq!(documents[2].body["rule"]["ruleset"], "persistence");
+
+        let out = dir.join(EXPORT_DIR);
+        let mut opts = options(SiemFormat::Opensearch, None);
+        opts.destination = ExportDestination::Files(out.clone());
+        let stats = export_collection(dir, &opts).unwrap();
+        assert_eq!(stats.batches, 3);
+        assert_eq!(stats.documents["event"], 1);
+        assert_eq!(
+            fs::read_to_string(out.join("event.ndjson"))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+
+        // A second export does not read its own output
+        let stats = export_collection(dir, &opts).unwrap();
+        assert_eq!(stats.documents.values().sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn test_export_over_http_retries() {
+        let temp = TempDir::new().unwrap();
+        let summary = CollectionSummary::new("WS01", "20240301-120000", &[], None, None);
+        fs::write(
+            temp.path().join("collection_summary.json"),
+            serde_json::to_string(&summary).unwrap(),
+        )
+        .unwrap();
+        fs::create_dir_all(temp.path().join("volatile")).unwrap();
+        fs::write(
+            temp.path().join("volatile/processes.json"),
+            serde_json::to_string(&vec![process(), process(), process()]).unwrap(),
+        )
+        .unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            let responses = [
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 21\r\n\r\n{\"text\":\"Success\"...}",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length: ")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.push((head, String::from_utf8(body).unwrap()));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let mut opts = options(SiemFormat::Splunk, None);
+        opts.batch_size = 2;
+        opts.retries = 1;
+        opts.destination = ExportDestination::Http {
+            url: format!("http://127.0.0.1:{}", port),
+            authorization: Some("Splunk token".to_string()),
+        };
+        let stats = export_collection(temp.path(), &opts).unwrap();
+        assert_eq!(stats.batches, 2);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0]
+            .0
+            .starts_with("POST /services/collector/event HTTP/1.1\r\n"));
+        assert!(requests[0]
+            .0
+            .to_ascii_lowercase()
+            .contains("authorization: splunk token\r\n"));
+        assert_eq!(requests[0].1, requests[1].1);
+        assert_eq!(requests[1].1.lines().count(), 2);
+        assert_eq!(requests[2].1.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_endpoint_parse() {
+        let endpoint = export_endpoint("https://search.corp:9200", SiemFormat::Opensearch).unwrap();
+        assert_eq!(
+            endpoint,
+            Url {
+                tls: true,
+                host: "search.corp".to_string(),
+                port: 9200,
+                path: "/_bulk".to_string(),
+            }
+        );
+        assert_eq!(endpoint.authority(), "search.corp:9200");
+
+        let endpoint =
+            export_endpoint("http://[::1]/cluster/_bulk", SiemFormat::Opensearch).unwrap();
+        assert_eq!(endpoint.host, "::1");
+        assert_eq!(endpoint.port, 80);
+        assert_eq!(endpoint.path, "/cluster/_bulk");
+        assert_eq!(endpoint.authority(), "[::1]");
+
+        let endpoint = export_endpoint("https://splunk:8088/", SiemFormat::Splunk).unwrap();
+        assert_eq!(endpoint.path, "/services/collector/event");
+        let endpoint =
+            export_endpoint("https://splunk:8088/services/collector", SiemFormat::Splunk).unwrap();
+        assert_eq!(endpoint.path, "/services/collector");
+
+        assert!(export_endpoint("ftp://host", SiemFormat::Splunk).is_err());
+        assert!(export_endpoint("https://host:port", SiemFormat::Splunk).is_err());
+    }
+
+    #[test]
+    fn test_bulk_item_errors() {
+        let response = br#"{"errors":true,"items":[{"index":{"status":201}},{"index":{"status":400,"error":{"type":"mapper_parsing_exception"}}}]}"#;
+        assert_eq!(bulk_item_errors(response), 1);
+        assert_eq!(bulk_item_errors(br#"{"errors":false,"items":[]}"#), 0);
+        assert_eq!(bulk_item_errors(b"not json"), 0);
+    }
+}
@@ -13,8 +13,8 @@ use crate::constants::{
 pub use crate::constants::{
     ZIP_COMPRESSION_METHOD_DEFLATE as COMPRESSION_METHOD_DEFLATE,
     ZIP_COMPRESSION_METHOD_STORE as COMPRESSION_METHOD_STORE,
-    ZIP_DEFAULT_BIT_FLAG as DEFAULT_BIT_FLAG, ZIP_VERSION_MADE_BY as VERSION_MADE_BY,
-    ZIP_VERSION_NEEDED as VERSION_NEEDED,
+    ZIP_DEFAULT_BIT_FLAG as DEFAULT_BIT_FLAG, ZIP_UTF8_NAME_FLAG as UTF8_NAME_FLAG,
+    ZIP_VERSION_MADE_BY as VERSION_MADE_BY, ZIP_VERSION_NEEDED as VERSION_NEEDED,
 };
 
 /// ZIP file entry information
@@ -2,12 +2,14 @@ use anyhow::Result;
 use bytes::BytesMut;
 use crc32fast::Hasher;
 use log::debug;
-use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+use crate::constants::STREAMING_BUFFER_SIZE;
+use crate::security::path_validator::validate_archive_entry_name;
 use crate::utils::streaming_zip::formats::{
     CentralDirectoryHeader, CompressionMethod, EndOfCentralDirectoryRecord, FileOptions,
     LocalFileHeader, ZipEntry, COMPRESSION_METHOD_DEFLATE, COMPRESSION_METHOD_STORE,
-    DEFAULT_BIT_FLAG, VERSION_MADE_BY, VERSION_NEEDED,
+    DEFAULT_BIT_FLAG, UTF8_NAME_FLAG, VERSION_MADE_BY, VERSION_NEEDED,
 };
 use crate::utils::streaming_zip::helpers::dos_time;
 
@@ -40,11 +42,17 @@ impl<'a, W: AsyncWrite + Unpin> StreamingZipWriter<W> {
     }
 
     /// Start a new file entry in the ZIP
+    ///
+    /// Names that would extract outside the destination directory (absolute,
+    /// drive-prefixed or containing `..`) are rejected before anything is
+    /// written.
     pub async fn start_file<'b>(
         &'b mut self,
         name: &str,
         options: FileOptions,
     ) -> Result<StreamingFileWriter<'b, W>> {
+        validate_archive_entry_name(name)?;
+
         let compression_method = match options.compression_method {
             CompressionMethod::Stored => COMPRESSION_METHOD_STORE,
             CompressionMethod::Deflated => COMPRESSION_METHOD_DEFLATE,
@@ -56,7 +64,7 @@ impl<'a, W: AsyncWrite + Unpin> StreamingZipWriter<W> {
         // Write local file header
         let header = LocalFileHeader {
             version_needed: VERSION_NEEDED,
-            bit_flag: DEFAULT_BIT_FLAG,
+            bit_flag: bit_flag(name),
             compression_method,
             last_mod_time,
             last_mod_date,
@@ -88,6 +96,32 @@ impl<'a, W: AsyncWrite + Unpin> StreamingZipWriter<W> {
         Ok(file_writer)
     }
 
+    /// Add a file entry whose contents are read from `reader` until it ends.
+    ///
+    /// For data that never exists as a local file, such as the stdout of an
+    /// external command. The entry is only added to the central directory
+    /// once the reader is exhausted; if reading fails part of the data has
+    /// already been written, so the archive should be abandoned.
+    pub async fn add_file_from_reader<R: AsyncRead + Unpin>(
+        &mut self,
+        name: &str,
+        options: FileOptions,
+        mut reader: R,
+    ) -> Result<()> {
+        let mut file_writer = self.start_file(name, options).await?;
+        let mut buffer = vec![0u8; STREAMING_BUFFER_SIZE];
+
+        loop {
+            let bytes_read = reader.read(&mut buffer).await?;
+            if bytes_read == 0 {
+                break;
+            }
+            file_writer.write_all(&buffer[..bytes_read]).await?;
+        }
+
+        file_writer.finish().await
+    }
+
     /// Finish the ZIP file
     pub async fn finish(mut self) -> Result<W> {
         // Write central directory
@@ -97,7 +131,7 @@ impl<'a, W: AsyncWrite + Unpin> StreamingZipWriter<W> {
             let header = CentralDirectoryHeader {
                 version_made_by: VERSION_MADE_BY,
                 version_needed: VERSION_NEEDED,
-                bit_flag: DEFAULT_BIT_FLAG,
+                bit_flag: bit_flag(&entry.name),
                 compression_method: entry.compression_method,
                 last_mod_time: entry.last_mod_time,
                 last_mod_date: entry.last_mod_date,
@@ -152,6 +186,17 @@ impl<'a, W: AsyncWrite + Unpin> StreamingZipWriter<W> {
     }
 }
 
+/// General purpose flags for an entry: names are always written as UTF-8,
+/// and flagged as such when they are not plain ASCII (which every reader
+/// decodes the same way)
+fn bit_flag(name: &str) -> u16 {
+    if name.is_ascii() {
+        DEFAULT_BIT_FLAG
+    } else {
+        DEFAULT_BIT_FLAG | UTF8_NAME_FLAG
+    }
+}
+
 /// Writer for a single file entry in the ZIP archive.
 ///
 /// This struct is created by the `start_file` method of `StreamingZipWriter` and
@@ -274,3 +319,71 @@ impl<'a, W: AsyncWrite + Unpin> StreamingFileWriter<'a, W> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_file_from_reader() {
+        let data = vec![b'x'; STREAMING_BUFFER_SIZE * 2 + 17];
+        let mut zip_writer = StreamingZipWriter::new(Vec::new());
+        let options = FileOptions {
+            compression_method: CompressionMethod::Stored,
+            last_modified: None,
+        };
+        zip_writer
+            .add_file_from_reader("logs/journal.json", options, data.as_slice())
+            .await
+            .unwrap();
+
+        let entry = &zip_writer.entries[0];
+        assert_eq!(entry.name, "logs/journal.json");
+        assert_eq!(entry.uncompressed_size as usize, data.len());
+        assert_eq!(entry.crc32, crc32fast::hash(&data));
+
+        assert!(zip_writer
+            .add_file_from_reader("../escape", FileOptions::default(), &b"x"[..])
+            .await
+            .is_err());
+        assert_eq!(zip_writer.entries.len(), 1);
+
+        let archive = zip_writer.finish().await.unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(
+            &mut zip.by_name("logs/journal.json").unwrap(),
+            &mut contents,
+        )
+        .unwrap();
+        assert_eq!(contents, data);
+    }
+
+    #[tokio::test]
+    async fn test_non_ascii_names_are_flagged_utf8() {
+        let mut zip_writer = StreamingZipWriter::new(Vec::new());
+        for name in ["plain.txt", "документ/日本.txt", "logs/bad%FFname.txt"] {
+            zip_writer
+                .add_file_from_reader(name, FileOptions::default(), &b"x"[..])
+                .await
+                .unwrap();
+        }
+        let archive = zip_writer.finish().await.unwrap();
+
+        // Bit flag follows the signature and version in the local header
+        assert_eq!(
+            u16::from_le_bytes([archive[6], archive[7]]),
+            DEFAULT_BIT_FLAG
+        );
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let names: Vec<String> = (0..zip.len())
+            .map(|i| zip.by_index(i).unwrap().name().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            ["plain.txt", "документ/日本.txt", "logs/bad%FFname.txt"]
+        );
+        assert!(zip.by_name("документ/日本.txt").is_ok());
+    }
+}
@@ -1,127 +1,512 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use anyhow::{Context, Result};
-use serde_json::json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sysinfo::{System, SystemExt};
 use uuid::Uuid;
 
+use crate::cli::SummaryFormat;
+use crate::collectors::collector::{ArtifactFailure, FailureReason};
 use crate::collectors::memory::models::MemoryCollectionSummary;
+use crate::collectors::phases::PhaseTiming;
 use crate::collectors::volatile::models::VolatileDataSummary;
+use crate::config::CollectionConfig;
 use crate::models::ArtifactMetadata;
+use crate::privileges;
+use crate::security::credential_scrubber::SCRUBBED;
 
-/// Create a JSON summary of the collection.
-///
-/// Generates a comprehensive JSON report containing metadata about all collected
-/// artifacts, system information, and collection statistics. This summary is
-/// crucial for chain of custody and forensic analysis documentation.
-///
-/// # Arguments
-///
-/// * `hostname` - The hostname of the system where collection occurred
-/// * `timestamp` - ISO 8601 formatted timestamp of when collection started
-/// * `artifacts` - Vector of tuples containing (path, metadata) for each collected artifact
-/// * `volatile_data_summary` - Optional summary of volatile data collection
-/// * `memory_collection_summary` - Optional summary of memory collection
-///
-/// # Returns
+/// Schema version written by [`CollectionSummary`]
+pub const SUMMARY_VERSION: u32 = 2;
+
+/// Top-level fields that only exist in the v2 schema
+const V2_FIELDS: [&str; 8] = [
+    "summary_version",
+    "collector",
+    "config_sha256",
+    "host",
+    "artifact_types",
+    "totals",
+    "phase_timings",
+    "uploads",
+];
+
+/// Collection summary written to `collection_summary.json`.
 ///
-/// * `Ok(String)` - JSON formatted summary as a string
-/// * `Err` - If JSON serialization fails
+/// The v1 fields are kept at the top level so older consumers keep working;
+/// the v2 fields are optional so v1 documents deserialize into the same
+/// struct. Sections produced elsewhere (time context, failures, NSRL, phase
+/// timeline) are kept in `sections` and flattened into the document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionSummary {
+    #[serde(default = "default_summary_version")]
+    pub summary_version: u32,
+    pub collection_id: String,
+    pub hostname: String,
+    pub collection_time: String,
+    pub os_version: String,
+    pub collector_version: String,
+    pub artifacts: Vec<SummaryArtifact>,
+    pub organization: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volatile_data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_memory: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collector: Option<CollectorInfo>,
+    /// SHA-256 of the effective configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<HostInfo>,
+    /// Counts and byte totals keyed by artifact type
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub artifact_types: BTreeMap<String, ArtifactTypeRollup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totals: Option<ArtifactTypeRollup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phase_timings: Vec<PhaseTiming>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uploads: Vec<UploadDestination>,
+    #[serde(flatten)]
+    pub sections: BTreeMap<String, Value>,
+}
+
+fn default_summary_version() -> u32 {
+    1
+}
+
+/// A collected file, keyed by its path relative to the artifact type directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryArtifact {
+    pub path: String,
+    #[serde(flatten)]
+    pub metadata: ArtifactMetadata,
+}
+
+/// Version of the collector binary that produced the summary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorInfo {
+    pub version: String,
+    /// Short commit hash, when the binary was built from a git checkout
+    pub git_hash: Option<String>,
+}
+
+impl CollectorInfo {
+    /// Version information for the running binary
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: option_env!("RS_COLLECTOR_GIT_HASH").map(str::to_string),
+        }
+    }
+}
+
+/// Minimal description of the collected host.
 ///
-/// # Example Output
+/// Gathered without a full volatile snapshot, so it is present even with
+/// `--no-volatile-data`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: Option<String>,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub architecture: String,
+    /// Whether the collector ran with administrator or root privileges
+    pub elevated: bool,
+}
+
+impl HostInfo {
+    /// Describe the current host
+    pub fn collect() -> Self {
+        let system = System::new();
+        Self {
+            hostname: system.host_name(),
+            os_name: system.name(),
+            os_version: system.long_os_version(),
+            kernel_version: system.kernel_version(),
+            architecture: std::env::consts::ARCH.to_string(),
+            elevated: privileges::is_elevated(),
+        }
+    }
+}
+
+/// File counts, bytes and failures for one artifact type (or the whole run)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactTypeRollup {
+    pub files: usize,
+    pub bytes: u64,
+    pub failures: usize,
+    /// Artifacts not attempted because the platform does not support them
+    pub skipped: usize,
+    /// Files recorded by metadata-only listings; not counted in `bytes`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub listed_files: u64,
+    /// Files whose source was under file-level encryption
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub encrypted: usize,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl ArtifactTypeRollup {
+    /// Count a collected file
+    pub fn add_file(&mut self, metadata: &ArtifactMetadata) {
+        self.files += 1;
+        self.bytes += metadata.file_size;
+        self.listed_files += metadata.listed_files.unwrap_or(0);
+        if metadata.encrypted.is_some() {
+            self.encrypted += 1;
+        }
+    }
+
+    fn add(&mut self, other: &ArtifactTypeRollup) {
+        self.files += other.files;
+        self.bytes += other.bytes;
+        self.failures += other.failures;
+        self.skipped += other.skipped;
+        self.listed_files += other.listed_files;
+        self.encrypted += other.encrypted;
+    }
+}
+
+/// Where the archive or summary is uploaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadDestination {
+    /// `s3` or `sftp`
+    pub target: String,
+    /// Bucket name or SFTP host
+    pub location: String,
+    /// Object key or remote path
+    pub key: String,
+}
+
+impl CollectionSummary {
+    /// Build a summary with the v1 fields populated
+    pub fn new(
+        hostname: &str,
+        timestamp: &str,
+        artifacts: &[(String, ArtifactMetadata)],
+        volatile_data_summary: Option<&VolatileDataSummary>,
+        memory_collection_summary: Option<&MemoryCollectionSummary>,
+    ) -> Self {
+        Self {
+            summary_version: SUMMARY_VERSION,
+            collection_id: Uuid::new_v4().to_string(),
+            hostname: hostname.to_string(),
+            collection_time: timestamp.to_string(),
+            os_version: std::env::consts::OS.to_string(),
+            collector_version: env!("CARGO_PKG_VERSION").to_string(),
+            artifacts: artifacts
+                .iter()
+                .map(|(path, meta)| SummaryArtifact {
+                    path: path.clone(),
+                    metadata: meta.clone(),
+                })
+                .collect(),
+            organization: "file_system_based".to_string(), // Indicate the new organization method
+            volatile_data: volatile_data_summary.map(volatile_data_section),
+            process_memory: memory_collection_summary.map(process_memory_section),
+            collector: Some(CollectorInfo::current()),
+            config_sha256: None,
+            host: None,
+            artifact_types: BTreeMap::new(),
+            totals: None,
+            phase_timings: Vec::new(),
+            uploads: Vec::new(),
+            sections: BTreeMap::new(),
+        }
+    }
+
+    /// Record the host description
+    pub fn with_host(mut self, host: HostInfo) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    /// Record the hash of the effective configuration
+    pub fn with_config_hash(mut self, config_sha256: String) -> Self {
+        self.config_sha256 = Some(config_sha256);
+        self
+    }
+
+    /// Record per-type rollups; the run totals are derived from them
+    pub fn with_artifact_types(mut self, rollups: BTreeMap<String, ArtifactTypeRollup>) -> Self {
+        let mut totals = ArtifactTypeRollup::default();
+        for rollup in rollups.values() {
+            totals.add(rollup);
+        }
+        self.totals = Some(totals);
+        self.artifact_types = rollups;
+        self
+    }
+
+    /// Record how long each collection phase took
+    pub fn with_phase_timings(mut self, timings: Vec<PhaseTiming>) -> Self {
+        self.phase_timings = timings;
+        self
+    }
+
+    /// Record where the collection is uploaded
+    pub fn with_uploads(mut self, uploads: Vec<UploadDestination>) -> Self {
+        self.uploads = uploads;
+        self
+    }
+
+    /// Add or replace a top-level section such as `time_context`
+    pub fn with_section(mut self, key: &str, value: Value) -> Self {
+        self.sections.insert(key.to_string(), value);
+        self
+    }
+
+    /// Serialize as pretty JSON in the requested schema.
+    ///
+    /// v1 drops the v2-only fields, leaving the layout older consumers expect.
+    pub fn to_json(&self, format: SummaryFormat) -> Result<String> {
+        let mut summary =
+            serde_json::to_value(self).context("Failed to serialize collection summary")?;
+
+        if format == SummaryFormat::V1 {
+            if let Some(obj) = summary.as_object_mut() {
+                for field in V2_FIELDS {
+                    obj.remove(field);
+                }
+            }
+        }
+
+        serde_json::to_string_pretty(&summary)
+            .context("Failed to serialize collection summary to JSON")
+    }
+
+    /// Parse a v1 or v2 summary document
+    pub fn from_json(summary_json: &str) -> Result<Self> {
+        serde_json::from_str(summary_json).context("Failed to parse collection summary JSON")
+    }
+}
+
+/// SHA-256 of the effective configuration, after merging and environment
+/// variable expansion.
 ///
-/// ```json
-/// {
-///   "collection_id": "550e8400-e29b-41d4-a716-446655440000",
-///   "hostname": "workstation-01",
-///   "collection_timestamp": "2024-01-15T14:30:52Z",
-///   "artifact_count": 42,
-///   "artifacts": [...],
-///   "volatile_data": {...},
-///   "process_memory": {...}
-/// }
-/// ```
-pub fn create_collection_summary(
-    hostname: &str,
-    timestamp: &str,
-    artifacts: &[(String, ArtifactMetadata)],
-    volatile_data_summary: Option<&VolatileDataSummary>,
-    memory_collection_summary: Option<&MemoryCollectionSummary>,
-) -> Result<String> {
-    let artifact_list: Vec<_> = artifacts
-        .iter()
-        .map(|(path, meta)| {
-            json!({
-                "path": path,
-                "original_path": meta.original_path,
-                "collection_time": meta.collection_time,
-                "file_size": meta.file_size,
-                "created_time": meta.created_time,
-                "accessed_time": meta.accessed_time,
-                "modified_time": meta.modified_time,
-                "is_locked": meta.is_locked
-            })
-        })
-        .collect();
+/// The config is hashed as JSON via [`Value`], whose maps are sorted, so
+/// `HashMap` iteration order does not change the hash.
+pub fn config_sha256(config: &CollectionConfig) -> Result<String> {
+    let value = serde_json::to_value(config).context("Failed to serialize configuration")?;
+    let canonical = serde_json::to_vec(&value).context("Failed to serialize configuration")?;
+    Ok(format!("{:x}", Sha256::digest(&canonical)))
+}
 
-    let mut summary = json!({
-        "collection_id": Uuid::new_v4().to_string(),
-        "hostname": hostname,
-        "collection_time": timestamp,
-        "os_version": std::env::consts::OS,
-        "collector_version": env!("CARGO_PKG_VERSION"),
-        "artifacts": artifact_list,
-        "organization": "file_system_based" // Indicate the new organization method
-    });
-
-    // Add volatile data summary if available
-    if let Some(vd_summary) = volatile_data_summary {
-        let volatile_data = json!({
-            "system_name": vd_summary.system_name,
-            "os_version": vd_summary.os_version,
-            "cpu_count": vd_summary.cpu_count,
-            "total_memory_mb": vd_summary.total_memory_mb,
-            "process_count": vd_summary.process_count,
-            "network_interface_count": vd_summary.network_interface_count,
-            "disk_count": vd_summary.disk_count,
-            "files": [
-                "volatile/system-info.json",
-                "volatile/processes.json",
-                "volatile/network-connections.json",
-                "volatile/memory.json",
-                "volatile/disks.json"
-            ]
-        });
+/// Summary section describing the volatile data collection
+fn volatile_data_section(vd_summary: &VolatileDataSummary) -> Value {
+    let files: Vec<String> = if vd_summary.output_files.is_empty() {
+        vec![
+            "volatile/system-info.json".to_string(),
+            "volatile/processes.json".to_string(),
+            "volatile/network-connections.json".to_string(),
+            "volatile/memory.json".to_string(),
+            "volatile/disks.json".to_string(),
+        ]
+    } else {
+        vd_summary
+            .output_files
+            .iter()
+            .map(|f| format!("volatile/{}", f))
+            .collect()
+    };
 
-        if let Some(obj) = summary.as_object_mut() {
-            obj.insert("volatile_data".to_string(), volatile_data);
+    json!({
+        "system_name": vd_summary.system_name,
+        "os_version": vd_summary.os_version,
+        "cpu_count": vd_summary.cpu_count,
+        "total_memory_mb": vd_summary.total_memory_mb,
+        "process_count": vd_summary.process_count,
+        "processes": vd_summary.process_names,
+        "network_interface_count": vd_summary.network_interface_count,
+        "disk_count": vd_summary.disk_count,
+        "files": files
+    })
+}
+
+/// Summary section describing the process memory collection
+fn process_memory_section(mem_summary: &MemoryCollectionSummary) -> Value {
+    json!({
+        "processes_examined": mem_summary.processes_examined,
+        "processes_collected": mem_summary.processes_collected,
+        "processes_skipped": mem_summary.processes_skipped,
+        "processes_failed": mem_summary.processes_failed,
+        "total_memory_collected": mem_summary.total_memory_collected,
+        "collection_start_time": mem_summary.start_time,
+        "collection_end_time": mem_summary.end_time,
+        "duration_seconds": mem_summary.duration_seconds,
+        "summary_file": "process_memory/memory_collection_summary.json"
+    })
+}
+
+/// Summary section for artifacts that failed to collect, with timeouts
+/// counted separately from permission problems, rejected destinations and
+/// other errors.
+pub fn artifact_failures_section(failures: &[ArtifactFailure]) -> Value {
+    let count = |reason: FailureReason| failures.iter().filter(|f| f.reason == reason).count();
+
+    json!({
+        "total": failures.len(),
+        "timeouts": count(FailureReason::Timeout),
+        "permission_denied": count(FailureReason::PermissionDenied),
+        "unsafe_paths": count(FailureReason::UnsafePath),
+        "errors": count(FailureReason::Error),
+        "failures": failures,
+    })
+}
+
+/// An artifact collected in both runs whose contents differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifiedArtifact {
+    pub name: String,
+    pub baseline_hash: Option<String>,
+    pub incident_hash: Option<String>,
+    /// Incident size minus baseline size
+    pub size_delta_bytes: i64,
+}
+
+/// Differences between a baseline and an incident collection summary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionDiff {
+    pub new_artifacts: Vec<String>,
+    pub removed_artifacts: Vec<String>,
+    pub modified_artifacts: Vec<ModifiedArtifact>,
+    pub new_processes: Vec<String>,
+    pub terminated_processes: Vec<String>,
+}
+
+impl CollectionDiff {
+    /// Whether the two runs collected the same artifacts and processes
+    pub fn is_empty(&self) -> bool {
+        self.new_artifacts.is_empty()
+            && self.removed_artifacts.is_empty()
+            && self.modified_artifacts.is_empty()
+            && self.new_processes.is_empty()
+            && self.terminated_processes.is_empty()
+    }
+
+    /// One line per change: `+` added, `-` removed, `~` modified
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for name in &self.new_artifacts {
+            text.push_str(&format!("+ artifact  {}\n", name));
+        }
+        for name in &self.removed_artifacts {
+            text.push_str(&format!("- artifact  {}\n", name));
+        }
+        for artifact in &self.modified_artifacts {
+            text.push_str(&format!(
+                "~ artifact  {} ({} -> {}, {:+} bytes)\n",
+                artifact.name,
+                artifact.baseline_hash.as_deref().unwrap_or("unhashed"),
+                artifact.incident_hash.as_deref().unwrap_or("unhashed"),
+                artifact.size_delta_bytes
+            ));
+        }
+        for name in &self.new_processes {
+            text.push_str(&format!("+ process   {}\n", name));
         }
+        for name in &self.terminated_processes {
+            text.push_str(&format!("- process   {}\n", name));
+        }
+        if self.is_empty() {
+            text.push_str("No differences\n");
+        }
+        text
     }
+}
+
+/// Compare two `collection_summary.json` documents.
+///
+/// Artifacts are matched on their `path`. A matched artifact is modified when
+/// both runs hashed it and the hashes differ; when either run has no hash
+/// (hashes are only recorded with `--nsrl-db`), a change in size or
+/// modification time counts instead. Processes are compared by name, from
+/// the `volatile_data.processes` list; they are not compared when either
+/// summary lacks one (older summaries, or `--no-volatile-data`).
+pub fn diff_summaries(baseline: &str, incident: &str) -> Result<CollectionDiff> {
+    let baseline = CollectionSummary::from_json(baseline).context("Invalid baseline summary")?;
+    let incident = CollectionSummary::from_json(incident).context("Invalid incident summary")?;
 
-    // Add memory collection summary if available
-    if let Some(mem_summary) = memory_collection_summary {
-        let memory_data = json!({
-            "processes_examined": mem_summary.processes_examined,
-            "processes_collected": mem_summary.processes_collected,
-            "processes_skipped": mem_summary.processes_skipped,
-            "processes_failed": mem_summary.processes_failed,
-            "total_memory_collected": mem_summary.total_memory_collected,
-            "collection_start_time": mem_summary.start_time,
-            "collection_end_time": mem_summary.end_time,
-            "duration_seconds": mem_summary.duration_seconds,
-            "summary_file": "process_memory/memory_collection_summary.json"
-        });
-
-        if let Some(obj) = summary.as_object_mut() {
-            obj.insert("process_memory".to_string(), memory_data);
+    let index = |summary: &CollectionSummary| -> BTreeMap<String, ArtifactMetadata> {
+        summary
+            .artifacts
+            .iter()
+            .map(|a| (a.path.clone(), a.metadata.clone()))
+            .collect()
+    };
+    let before = index(&baseline);
+    let after = index(&incident);
+
+    let mut diff = CollectionDiff::default();
+    for (name, new) in &after {
+        let Some(old) = before.get(name) else {
+            diff.new_artifacts.push(name.clone());
+            continue;
+        };
+        let changed = match (&old.sha256, &new.sha256) {
+            (Some(old_hash), Some(new_hash)) => old_hash != new_hash,
+            _ => old.file_size != new.file_size || old.modified_time != new.modified_time,
+        };
+        if changed {
+            diff.modified_artifacts.push(ModifiedArtifact {
+                name: name.clone(),
+                baseline_hash: old.sha256.clone(),
+                incident_hash: new.sha256.clone(),
+                size_delta_bytes: new.file_size as i64 - old.file_size as i64,
+            });
         }
     }
+    diff.removed_artifacts = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .cloned()
+        .collect();
+
+    if let (Some(before), Some(after)) =
+        (summary_processes(&baseline), summary_processes(&incident))
+    {
+        diff.new_processes = after.difference(&before).cloned().collect();
+        diff.terminated_processes = before.difference(&after).cloned().collect();
+    }
 
-    serde_json::to_string_pretty(&summary).context("Failed to serialize collection summary to JSON")
+    Ok(diff)
+}
+
+/// Process names listed in the volatile data section
+fn summary_processes(summary: &CollectionSummary) -> Option<BTreeSet<String>> {
+    summary
+        .volatile_data
+        .as_ref()
+        .and_then(|volatile| volatile.get("processes"))
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+}
+
+/// Summary section for the scrubbing of secrets from volatile data
+pub fn credential_scrubbing_section(redactions: u64) -> Value {
+    json!({
+        "redactions": redactions,
+        "placeholder": SCRUBBED,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
-    use serde_json::Value;
 
     fn create_test_artifact_metadata() -> ArtifactMetadata {
         ArtifactMetadata {
@@ -132,6 +517,8 @@ mod tests {
             accessed_time: Some(Utc::now().to_rfc3339()),
             modified_time: Some(Utc::now().to_rfc3339()),
             is_locked: false,
+            sha256: None,
+            ..Default::default()
         }
     }
 
@@ -142,8 +529,12 @@ mod tests {
             cpu_count: 4,
             total_memory_mb: 8192,
             process_count: 100,
+            process_names: vec!["init".to_string(), "sshd".to_string()],
             network_interface_count: 3,
             disk_count: 2,
+            output_files: Vec::new(),
+            process_binaries: None,
+            process_ancestry_anomalies: 0,
         }
     }
 
@@ -162,6 +553,24 @@ mod tests {
         }
     }
 
+    /// The summary in the v1 layout
+    fn v1_summary(
+        hostname: &str,
+        timestamp: &str,
+        artifacts: &[(String, ArtifactMetadata)],
+        volatile_data_summary: Option<&VolatileDataSummary>,
+        memory_collection_summary: Option<&MemoryCollectionSummary>,
+    ) -> Result<String> {
+        CollectionSummary::new(
+            hostname,
+            timestamp,
+            artifacts,
+            volatile_data_summary,
+            memory_collection_summary,
+        )
+        .to_json(SummaryFormat::V1)
+    }
+
     #[test]
     fn test_basic_summary_creation() {
         let artifacts = vec![
@@ -169,8 +578,7 @@ mod tests {
             ("artifact2.log".to_string(), create_test_artifact_metadata()),
         ];
 
-        let result =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
+        let result = v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
 
         assert!(result.is_ok());
         let json_str = result.unwrap();
@@ -199,7 +607,7 @@ mod tests {
         let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
         let volatile_summary = create_test_volatile_summary();
 
-        let result = create_collection_summary(
+        let result = v1_summary(
             "test-host",
             "2024-01-01T00:00:00Z",
             &artifacts,
@@ -227,12 +635,37 @@ mod tests {
         assert!(files.contains(&json!("volatile/system-info.json")));
     }
 
+    #[test]
+    fn test_summary_lists_reported_volatile_files() {
+        let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
+        let mut volatile_summary = create_test_volatile_summary();
+        volatile_summary.output_files = vec![
+            "processes.csv".to_string(),
+            "volatile_report.json".to_string(),
+        ];
+
+        let json_str = v1_summary(
+            "test-host",
+            "2024-01-01T00:00:00Z",
+            &artifacts,
+            Some(&volatile_summary),
+            None,
+        )
+        .unwrap();
+        let json: Value = serde_json::from_str(&json_str).unwrap();
+
+        let files = json["volatile_data"]["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&json!("volatile/processes.csv")));
+        assert_eq!(json["volatile_data"]["process_count"], 100);
+    }
+
     #[test]
     fn test_summary_with_memory_data() {
         let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
         let memory_summary = create_test_memory_summary();
 
-        let result = create_collection_summary(
+        let result = v1_summary(
             "test-host",
             "2024-01-01T00:00:00Z",
             &artifacts,
@@ -270,7 +703,7 @@ mod tests {
         let volatile_summary = create_test_volatile_summary();
         let memory_summary = create_test_memory_summary();
 
-        let result = create_collection_summary(
+        let result = v1_summary(
             "test-host",
             "2024-01-01T00:00:00Z",
             &artifacts,
@@ -293,8 +726,7 @@ mod tests {
     fn test_empty_artifacts_list() {
         let artifacts = vec![];
 
-        let result =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
+        let result = v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
 
         assert!(result.is_ok());
         let json_str = result.unwrap();
@@ -309,12 +741,10 @@ mod tests {
 
         // Create two summaries
         let result1 =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None)
-                .unwrap();
+            v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None).unwrap();
 
         let result2 =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None)
-                .unwrap();
+            v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None).unwrap();
 
         let json1: Value = serde_json::from_str(&result1).unwrap();
         let json2: Value = serde_json::from_str(&result2).unwrap();
@@ -330,7 +760,7 @@ mod tests {
 
         let artifacts = vec![("artifact with spaces.txt".to_string(), metadata)];
 
-        let result = create_collection_summary(
+        let result = v1_summary(
             "host-name-123",
             "2024-01-01T00:00:00Z",
             &artifacts,
@@ -354,8 +784,7 @@ mod tests {
         let metadata = create_test_artifact_metadata();
         let artifacts = vec![("test.txt".to_string(), metadata.clone())];
 
-        let result =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
+        let result = v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
 
         assert!(result.is_ok());
         let json_str = result.unwrap();
@@ -375,8 +804,7 @@ mod tests {
     fn test_json_pretty_formatting() {
         let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
 
-        let result =
-            create_collection_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
+        let result = v1_summary("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None);
 
         assert!(result.is_ok());
         let json_str = result.unwrap();
@@ -385,4 +813,258 @@ mod tests {
         assert!(json_str.contains('\n'));
         assert!(json_str.contains("  ")); // Indentation
     }
+
+    #[test]
+    fn test_artifact_failures_section() {
+        let failure = |name: &str, reason: FailureReason| ArtifactFailure {
+            artifact: name.to_string(),
+            source_path: format!("/mnt/{}", name),
+            required: false,
+            reason,
+            message: "failed".to_string(),
+        };
+        let failures = vec![
+            failure("nfs", FailureReason::Timeout),
+            failure("shadow", FailureReason::PermissionDenied),
+            failure("missing", FailureReason::Error),
+            failure("device", FailureReason::Timeout),
+            failure("escape", FailureReason::UnsafePath),
+        ];
+
+        let section = artifact_failures_section(&failures);
+        assert_eq!(section["total"], 5);
+        assert_eq!(section["timeouts"], 2);
+        assert_eq!(section["permission_denied"], 1);
+        assert_eq!(section["unsafe_paths"], 1);
+        assert_eq!(section["errors"], 1);
+        assert_eq!(section["failures"][0]["reason"], "timeout");
+        assert_eq!(section["failures"][1]["reason"], "permission_denied");
+    }
+
+    #[test]
+    fn test_v2_summary_fields() {
+        let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
+        let mut rollups = BTreeMap::new();
+        rollups.insert(
+            "Logs".to_string(),
+            ArtifactTypeRollup {
+                files: 2,
+                bytes: 2048,
+                failures: 1,
+                skipped: 0,
+                listed_files: 0,
+                encrypted: 2,
+            },
+        );
+        rollups.insert(
+            "Registry".to_string(),
+            ArtifactTypeRollup {
+                files: 1,
+                bytes: 512,
+                failures: 0,
+                skipped: 3,
+                listed_files: 0,
+                encrypted: 0,
+            },
+        );
+
+        let summary =
+            CollectionSummary::new("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None)
+                .with_host(HostInfo::collect())
+                .with_config_hash("ab".repeat(32))
+                .with_artifact_types(rollups)
+                .with_uploads(vec![UploadDestination {
+                    target: "s3".to_string(),
+                    location: "evidence".to_string(),
+                    key: "triage/collection_summary.json".to_string(),
+                }])
+                .with_section("time_context", json!({"utc_offset": "+00:00"}));
+        let json: Value =
+            serde_json::from_str(&summary.to_json(SummaryFormat::V2).unwrap()).unwrap();
+
+        assert_eq!(json["summary_version"], 2);
+        assert_eq!(json["collector"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["config_sha256"], "ab".repeat(32));
+        assert!(json["host"]["elevated"].is_boolean());
+        assert_eq!(json["artifact_types"]["Logs"]["bytes"], 2048);
+        assert_eq!(json["totals"]["files"], 3);
+        assert_eq!(json["totals"]["failures"], 1);
+        assert_eq!(json["totals"]["skipped"], 3);
+        assert_eq!(json["totals"]["encrypted"], 2);
+        assert!(json["artifact_types"]["Registry"]
+            .get("encrypted")
+            .is_none());
+        assert_eq!(json["uploads"][0]["key"], "triage/collection_summary.json");
+        assert_eq!(json["time_context"]["utc_offset"], "+00:00");
+
+        // v1 fields are kept for older consumers
+        assert_eq!(json["hostname"], "test-host");
+        assert_eq!(json["organization"], "file_system_based");
+        assert_eq!(json["artifacts"][0]["path"], "test.txt");
+        assert_eq!(json["artifacts"][0]["file_size"], 1024);
+    }
+
+    #[test]
+    fn test_v1_format_omits_v2_fields() {
+        let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
+        let summary =
+            CollectionSummary::new("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None)
+                .with_host(HostInfo::collect())
+                .with_config_hash("00".repeat(32))
+                .with_artifact_types(BTreeMap::new())
+                .with_section("time_context", json!({}));
+        let json: Value =
+            serde_json::from_str(&summary.to_json(SummaryFormat::V1).unwrap()).unwrap();
+
+        for field in V2_FIELDS {
+            assert!(json.get(field).is_none(), "{} present in v1", field);
+        }
+        assert!(json["time_context"].is_object());
+        assert_eq!(json["artifacts"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_summary_round_trip() {
+        let artifacts = vec![("test.txt".to_string(), create_test_artifact_metadata())];
+        let summary =
+            CollectionSummary::new("test-host", "2024-01-01T00:00:00Z", &artifacts, None, None)
+                .with_config_hash("cd".repeat(32))
+                .with_section("nsrl", json!({"known_good": 1}));
+
+        let v2 =
+            CollectionSummary::from_json(&summary.to_json(SummaryFormat::V2).unwrap()).unwrap();
+        assert_eq!(v2.summary_version, SUMMARY_VERSION);
+        assert_eq!(v2.config_sha256, summary.config_sha256);
+        assert_eq!(v2.artifacts[0].metadata.file_size, 1024);
+        assert_eq!(v2.sections["nsrl"]["known_good"], 1);
+
+        // v1 documents load into the same struct
+        let v1 =
+            CollectionSummary::from_json(&summary.to_json(SummaryFormat::V1).unwrap()).unwrap();
+        assert_eq!(v1.summary_version, 1);
+        assert!(v1.config_sha256.is_none());
+        assert_eq!(v1.collection_id, summary.collection_id);
+    }
+
+    #[test]
+    fn test_config_sha256_tracks_effective_config() {
+        let original = config_sha256(&CollectionConfig::default()).unwrap();
+        assert_eq!(original.len(), 64);
+
+        let options = [("artifact_timeout_sec", "30"), ("copy_buffer_kb", "4096")];
+        let mut forward = CollectionConfig::default();
+        for (key, value) in options {
+            forward
+                .global_options
+                .insert(key.to_string(), value.to_string());
+        }
+        let mut reverse = CollectionConfig::default();
+        for (key, value) in options.iter().rev() {
+            reverse
+                .global_options
+                .insert(key.to_string(), value.to_string());
+        }
+
+        let hash = config_sha256(&forward).unwrap();
+        assert_ne!(hash, original);
+        assert_eq!(hash, config_sha256(&reverse).unwrap());
+    }
+
+    #[test]
+    fn test_credential_scrubbing_section() {
+        let section = credential_scrubbing_section(3);
+        assert_eq!(section["redactions"], 3);
+        assert_eq!(section["placeholder"], "***SCRUBBED***");
+    }
+
+    fn summary_json(artifacts: &[(&str, u64, Option<&str>)], processes: &[&str]) -> String {
+        let artifacts: Vec<(String, ArtifactMetadata)> = artifacts
+            .iter()
+            .map(|(path, size, hash)| {
+                let mut metadata = create_test_artifact_metadata();
+                metadata.file_size = *size;
+                metadata.sha256 = hash.map(str::to_string);
+                (path.to_string(), metadata)
+            })
+            .collect();
+        let mut volatile = create_test_volatile_summary();
+        volatile.process_names = processes.iter().map(|p| p.to_string()).collect();
+        CollectionSummary::new(
+            "host",
+            "2024-01-01T00:00:00Z",
+            &artifacts,
+            Some(&volatile),
+            None,
+        )
+        .to_json(SummaryFormat::V2)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_summaries() {
+        let baseline = summary_json(
+            &[
+                ("Logs/syslog", 100, None),
+                ("Registry/SYSTEM", 4096, Some("aaaa")),
+                ("Registry/SAM", 2048, Some("bbbb")),
+                ("Users/alice/.bash_history", 50, None),
+            ],
+            &["cron", "sshd"],
+        );
+        let incident = summary_json(
+            &[
+                ("Logs/syslog", 180, None),
+                ("Registry/SYSTEM", 4096, Some("cccc")),
+                ("Registry/SAM", 2048, Some("bbbb")),
+                ("Users/alice/.ssh/authorized_keys", 400, None),
+            ],
+            &["nc", "sshd"],
+        );
+
+        let diff = diff_summaries(&baseline, &incident).unwrap();
+        assert_eq!(diff.new_artifacts, vec!["Users/alice/.ssh/authorized_keys"]);
+        assert_eq!(diff.removed_artifacts, vec!["Users/alice/.bash_history"]);
+        assert_eq!(
+            diff.modified_artifacts,
+            vec![
+                ModifiedArtifact {
+                    name: "Logs/syslog".to_string(),
+                    baseline_hash: None,
+                    incident_hash: None,
+                    size_delta_bytes: 80,
+                },
+                ModifiedArtifact {
+                    name: "Registry/SYSTEM".to_string(),
+                    baseline_hash: Some("aaaa".to_string()),
+                    incident_hash: Some("cccc".to_string()),
+                    size_delta_bytes: 0,
+                },
+            ]
+        );
+        assert_eq!(diff.new_processes, vec!["nc"]);
+        assert_eq!(diff.terminated_processes, vec!["cron"]);
+
+        let text = diff.to_text();
+        assert!(text.contains("+ artifact  Users/alice/.ssh/authorized_keys\n"));
+        assert!(text.contains("~ artifact  Logs/syslog (unhashed -> unhashed, +80 bytes)\n"));
+        assert!(text.contains("- process   cron\n"));
+
+        let same = diff_summaries(&baseline, &baseline).unwrap();
+        assert!(same.is_empty());
+        assert_eq!(same.to_text(), "No differences\n");
+    }
+
+    #[test]
+    fn test_diff_summaries_without_volatile_data() {
+        let mut v1 = serde_json::from_str::<Value>(&summary_json(&[], &["sshd"])).unwrap();
+        v1.as_object_mut().unwrap().remove("volatile_data");
+        let incident = summary_json(&[("Logs/syslog", 1, None)], &["sshd"]);
+
+        let diff = diff_summaries(&v1.to_string(), &incident).unwrap();
+        assert_eq!(diff.new_artifacts, vec!["Logs/syslog"]);
+        assert!(diff.new_processes.is_empty());
+        assert!(diff.terminated_processes.is_empty());
+
+        assert!(diff_summaries("not json", &incident).is_err());
+    }
 }
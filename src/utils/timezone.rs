@@ -0,0 +1,459 @@
+//! Host timezone and clock context.
+//!
+//! Collected timestamps are emitted as UTC, but an analyst also needs the
+//! host's local timezone, whether its clock was skewed at collection time and
+//! whether the clock was recently changed. This module gathers that context
+//! for the collection summary and the volatile system info.
+//!
+//! It also holds the conversion math for FAT-family file systems, which store
+//! timestamps as local wall-clock time rather than UTC.
+
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::ntp::{self, NtpProbe};
+
+lazy_static! {
+    static ref HOST_TIMEZONE: HostTimezone = detect_host_timezone();
+}
+
+/// Maximum number of clock change events to include
+#[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+const MAX_CLOCK_CHANGES: usize = 10;
+
+/// Host timezone as configured at collection time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostTimezone {
+    /// Zone name (IANA name on Unix, Windows time zone key name on Windows)
+    pub name: Option<String>,
+    /// Where the zone name was read from
+    pub source: String,
+    /// Current offset from UTC in minutes, east positive
+    pub utc_offset_minutes: i32,
+    /// Current offset from UTC as `+HH:MM`
+    pub utc_offset: String,
+}
+
+/// Timezone, clock skew and clock change context for the host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostTimeContext {
+    pub timezone: HostTimezone,
+    /// Clock offset versus an NTP server, when `--ntp-check` was given
+    pub ntp_check: Option<NtpProbe>,
+    /// Error from the NTP probe, if it was attempted and failed
+    pub ntp_error: Option<String>,
+    /// Most recent clock change events, newest first, where cheaply available
+    pub recent_clock_changes: Vec<String>,
+}
+
+/// Timezone detected once per process
+pub fn host_timezone() -> &'static HostTimezone {
+    &HOST_TIMEZONE
+}
+
+/// Gather timezone, optional NTP skew and recent clock changes
+pub fn collect_time_context(ntp_server: Option<&str>) -> HostTimeContext {
+    let (ntp_check, ntp_error) = match ntp_server {
+        Some(server) => match ntp::probe(server, ntp::DEFAULT_TIMEOUT) {
+            Ok(probe) => (Some(probe), None),
+            Err(e) => {
+                warn!("NTP check against {} failed: {}", server, e);
+                (None, Some(e.to_string()))
+            }
+        },
+        None => (None, None),
+    };
+
+    HostTimeContext {
+        timezone: host_timezone().clone(),
+        ntp_check,
+        ntp_error,
+        recent_clock_changes: recent_clock_changes(),
+    }
+}
+
+/// Format an offset in minutes as `+HH:MM`
+pub fn format_utc_offset(offset_minutes: i32) -> String {
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+    let abs = offset_minutes.abs();
+    format!("{}{:02}:{:02}", sign, abs / 60, abs % 60)
+}
+
+fn local_offset_minutes() -> i32 {
+    chrono::Local::now().offset().local_minus_utc() / 60
+}
+
+/// Zone name from an `/etc/localtime` symlink target
+#[cfg_attr(not(unix), allow(dead_code))]
+fn zone_from_localtime_link(target: &Path) -> Option<String> {
+    let target = target.to_string_lossy();
+    target
+        .split_once("zoneinfo/")
+        .map(|(_, zone)| zone.trim_matches('/').to_string())
+        .filter(|zone| !zone.is_empty())
+}
+
+/// Zone name from `systemsetup -gettimezone` output (`Time Zone: Europe/Paris`)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_systemsetup_timezone(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Time Zone:"))
+        .map(|zone| zone.trim().to_string())
+        .filter(|zone| !zone.is_empty())
+}
+
+#[cfg(unix)]
+fn detect_unix_zone() -> (Option<String>, String) {
+    if let Ok(target) = std::fs::read_link("/etc/localtime") {
+        if let Some(zone) = zone_from_localtime_link(&target) {
+            return (Some(zone), "/etc/localtime".to_string());
+        }
+    }
+    if let Ok(contents) = std::fs::read_to_string("/etc/timezone") {
+        let zone = contents.trim();
+        if !zone.is_empty() {
+            return (Some(zone.to_string()), "/etc/timezone".to_string());
+        }
+    }
+    (None, "unknown".to_string())
+}
+
+/// Detect the host timezone from the platform's authoritative source
+pub fn detect_host_timezone() -> HostTimezone {
+    #[cfg(target_os = "windows")]
+    let (name, source, offset) = {
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        use winreg::RegKey;
+
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey("SYSTEM\\CurrentControlSet\\Control\\TimeZoneInformation");
+        match key {
+            Ok(key) => {
+                let name: Option<String> = key.get_value("TimeZoneKeyName").ok();
+                // ActiveTimeBias is minutes to add to local time to get UTC
+                let offset = key
+                    .get_value::<u32, _>("ActiveTimeBias")
+                    .map(|bias| -(bias as i32))
+                    .unwrap_or_else(|_| local_offset_minutes());
+                (name, "registry:TimeZoneInformation".to_string(), offset)
+            }
+            Err(e) => {
+                warn!("Failed to open TimeZoneInformation key: {}", e);
+                (None, "unknown".to_string(), local_offset_minutes())
+            }
+        }
+    };
+
+    #[cfg(target_os = "macos")]
+    let (name, source, offset) = {
+        use std::process::Command;
+
+        use crate::collectors::footprint::run_command;
+
+        let from_systemsetup = run_command(Command::new("systemsetup").arg("-gettimezone"))
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                parse_systemsetup_timezone(&String::from_utf8_lossy(&output.stdout))
+            });
+        match from_systemsetup {
+            Some(zone) => (
+                Some(zone),
+                "systemsetup".to_string(),
+                local_offset_minutes(),
+            ),
+            None => {
+                let (name, source) = detect_unix_zone();
+                (name, source, local_offset_minutes())
+            }
+        }
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let (name, source, offset) = {
+        let (name, source) = detect_unix_zone();
+        (name, source, local_offset_minutes())
+    };
+
+    #[cfg(not(any(unix, target_os = "windows")))]
+    let (name, source, offset) = (None, "unknown".to_string(), local_offset_minutes());
+
+    debug!("Host timezone: {:?} ({}), offset {}", name, source, offset);
+    HostTimezone {
+        name,
+        source,
+        utc_offset_minutes: offset,
+        utc_offset: format_utc_offset(offset),
+    }
+}
+
+/// Recent clock change events from the system log, newest first.
+///
+/// - **Linux**: systemd's "Time has been changed" journal message
+/// - **Windows**: `Microsoft-Windows-Kernel-General` event 1 in the System log
+///
+/// Other platforms, or hosts without the tooling, return an empty list.
+pub fn recent_clock_changes() -> Vec<String> {
+    #[cfg(target_os = "linux")]
+    let output = {
+        use std::process::Command;
+
+        use crate::collectors::footprint::run_command;
+
+        // SD_MESSAGE_TIME_CHANGE
+        run_command(Command::new("journalctl").args([
+            "--no-pager",
+            "--quiet",
+            "--reverse",
+            "--output=short-iso",
+            "-n",
+            &MAX_CLOCK_CHANGES.to_string(),
+            "MESSAGE_ID=c7a787079b354eaaa9e77b371893cd27",
+        ]))
+    };
+
+    #[cfg(target_os = "windows")]
+    let output = {
+        use std::process::Command;
+
+        use crate::collectors::footprint::run_command;
+
+        run_command(Command::new("wevtutil").args([
+            "qe",
+            "System",
+            "/q:*[System[Provider[@Name='Microsoft-Windows-Kernel-General'] and (EventID=1)]]",
+            &format!("/c:{}", MAX_CLOCK_CHANGES),
+            "/rd:true",
+            "/f:text",
+        ]))
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    {
+        match output {
+            Ok(output) if output.status.success() => {
+                parse_clock_change_output(&String::from_utf8_lossy(&output.stdout))
+            }
+            Ok(output) => {
+                debug!(
+                    "Clock change query failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                Vec::new()
+            }
+            Err(e) => {
+                debug!("Clock change query unavailable: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        Vec::new()
+    }
+}
+
+/// Split clock change query output into one entry per event.
+///
+/// `wevtutil /f:text` separates events with `Event[n]:` headers and each
+/// event is collapsed onto a single line. Journal output is already one
+/// event per line.
+#[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+fn parse_clock_change_output(output: &str) -> Vec<String> {
+    let lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    let mut events: Vec<String> = if output.contains("Event[") {
+        let mut events = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        for line in lines {
+            if line.starts_with("Event[") {
+                if !current.is_empty() {
+                    events.push(current.join(" "));
+                    current.clear();
+                }
+            } else {
+                current.push(line);
+            }
+        }
+        if !current.is_empty() {
+            events.push(current.join(" "));
+        }
+        events
+    } else {
+        lines.map(str::to_string).collect()
+    };
+
+    events.truncate(MAX_CLOCK_CHANGES);
+    events
+}
+
+/// Whether `fs_name` stores timestamps as local time (FAT family)
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn is_local_time_filesystem(fs_name: &str) -> bool {
+    matches!(
+        fs_name.to_ascii_lowercase().as_str(),
+        "fat" | "fat12" | "fat16" | "fat32" | "exfat" | "vfat" | "msdos"
+    )
+}
+
+/// Decode a packed FAT date/time pair into a naive local timestamp.
+///
+/// Date: bits 15-9 year since 1980, 8-5 month, 4-0 day.
+/// Time: bits 15-11 hour, 10-5 minute, 4-0 seconds / 2.
+pub fn fat_datetime_to_naive(date: u16, time: u16) -> Option<NaiveDateTime> {
+    let year = 1980 + (date >> 9) as i32;
+    let month = ((date >> 5) & 0x0f) as u32;
+    let day = (date & 0x1f) as u32;
+    let hour = (time >> 11) as u32;
+    let minute = ((time >> 5) & 0x3f) as u32;
+    let second = ((time & 0x1f) * 2) as u32;
+
+    NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, second)
+}
+
+/// Interpret a local wall-clock timestamp using the recorded UTC offset
+pub fn local_to_utc(local: NaiveDateTime, offset_minutes: i32) -> Option<DateTime<Utc>> {
+    let offset = FixedOffset::east_opt(offset_minutes * 60)?;
+    offset
+        .from_local_datetime(&local)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Convert a FILETIME tick count (100ns intervals since 1601-01-01 UTC)
+pub fn filetime_ticks_to_utc(ticks: u64) -> Option<DateTime<Utc>> {
+    // Seconds between 1601-01-01 and 1970-01-01
+    const EPOCH_DIFFERENCE_SECS: i64 = 11_644_473_600;
+
+    let secs = (ticks / 10_000_000) as i64 - EPOCH_DIFFERENCE_SECS;
+    let nanos = ((ticks % 10_000_000) * 100) as u32;
+    Utc.timestamp_opt(secs, nanos).single()
+}
+
+/// Render a FAT-volume timestamp in the recorded local timezone.
+///
+/// When Windows reads a FAT or exFAT volume it turns the stored local time
+/// into UTC using the host's current bias. Rendering the result with the
+/// recorded offset restores the original wall-clock value and keeps the
+/// offset explicit, instead of presenting it as a plain UTC time.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn fat_utc_to_recorded_local(utc: DateTime<Utc>, offset_minutes: i32) -> String {
+    match FixedOffset::east_opt(offset_minutes * 60) {
+        Some(offset) => utc.with_timezone(&offset).to_rfc3339(),
+        None => utc.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_utc_offset() {
+        assert_eq!(format_utc_offset(0), "+00:00");
+        assert_eq!(format_utc_offset(330), "+05:30");
+        assert_eq!(format_utc_offset(-480), "-08:00");
+        assert_eq!(format_utc_offset(-210), "-03:30");
+    }
+
+    #[test]
+    fn test_zone_from_localtime_link() {
+        assert_eq!(
+            zone_from_localtime_link(Path::new("/usr/share/zoneinfo/Europe/Berlin")),
+            Some("Europe/Berlin".to_string())
+        );
+        assert_eq!(
+            zone_from_localtime_link(Path::new("../usr/share/zoneinfo/UTC")),
+            Some("UTC".to_string())
+        );
+        assert_eq!(zone_from_localtime_link(Path::new("/etc/custom")), None);
+    }
+
+    #[test]
+    fn test_parse_systemsetup_timezone() {
+        assert_eq!(
+            parse_systemsetup_timezone("Time Zone: America/New_York\n"),
+            Some("America/New_York".to_string())
+        );
+        assert_eq!(
+            parse_systemsetup_timezone("You need administrator access"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_clock_change_output() {
+        let journal = "2024-03-10T02:00:01+0000 host systemd[1]: Time has been changed\n\
+                       2024-01-01T00:00:05+0000 host systemd[1]: Time has been changed\n";
+        assert_eq!(parse_clock_change_output(journal).len(), 2);
+
+        let wevtutil = "Event[0]:\n  Log Name: System\n  Event ID: 1\n\nEvent[1]:\n  Log Name: System\n  Event ID: 1\n";
+        let events = parse_clock_change_output(wevtutil);
+        assert_eq!(events, vec!["Log Name: System Event ID: 1"; 2]);
+    }
+
+    #[test]
+    fn test_is_local_time_filesystem() {
+        assert!(is_local_time_filesystem("FAT32"));
+        assert!(is_local_time_filesystem("exFAT"));
+        assert!(is_local_time_filesystem("vfat"));
+        assert!(!is_local_time_filesystem("NTFS"));
+        assert!(!is_local_time_filesystem("ext4"));
+    }
+
+    #[test]
+    fn test_fat_datetime_decoding() {
+        // 2023-07-15 14:30:58 -> date (43 << 9 | 7 << 5 | 15), time (14 << 11 | 30 << 5 | 29)
+        let date = (43 << 9) | (7 << 5) | 15;
+        let time = (14 << 11) | (30 << 5) | 29;
+        let naive = fat_datetime_to_naive(date, time).unwrap();
+        assert_eq!(naive.to_string(), "2023-07-15 14:30:58");
+
+        // Month 0 is invalid
+        assert!(fat_datetime_to_naive(43 << 9, 0).is_none());
+    }
+
+    #[test]
+    fn test_fat_local_time_converted_with_recorded_offset() {
+        let local = NaiveDate::from_ymd_opt(2023, 7, 15)
+            .unwrap()
+            .and_hms_opt(14, 30, 58)
+            .unwrap();
+
+        // UTC+02:00: 14:30 local is 12:30 UTC, not 14:30 UTC
+        let utc = local_to_utc(local, 120).unwrap();
+        assert_eq!(utc.to_rfc3339(), "2023-07-15T12:30:58+00:00");
+
+        // UTC-05:00 crossing midnight
+        let late = NaiveDate::from_ymd_opt(2023, 12, 31)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+            .unwrap();
+        let utc = local_to_utc(late, -300).unwrap();
+        assert_eq!(utc.to_rfc3339(), "2024-01-01T03:00:00+00:00");
+
+        // Rendering restores the original wall-clock time with its offset
+        assert_eq!(
+            fat_utc_to_recorded_local(utc, -300),
+            "2023-12-31T22:00:00-05:00"
+        );
+    }
+
+    #[test]
+    fn test_filetime_ticks_to_utc() {
+        // 1970-01-01T00:00:00Z
+        let epoch = filetime_ticks_to_utc(116_444_736_000_000_000).unwrap();
+        assert_eq!(epoch.timestamp(), 0);
+
+        // 2023-07-15T12:30:58.5Z
+        let ticks = 116_444_736_000_000_000u64 + 1_689_424_258 * 10_000_000 + 5_000_000;
+        let dt = filetime_ticks_to_utc(ticks).unwrap();
+        assert_eq!(dt.to_rfc3339(), "2023-07-15T12:30:58.500+00:00");
+    }
+}
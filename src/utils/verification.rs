@@ -0,0 +1,590 @@
+//! Sampled double-collection verification (`--verify-sample`).
+//!
+//! Once the file artifacts are copied, a percentage of the collected files
+//! is read again from the source and both the source and the copy are
+//! hashed. A file whose source is unchanged but whose copy differs was
+//! corrupted during collection: it is copied again and reported as a
+//! collection failure. A source whose modification time moved since it was
+//! copied is reported as changed during collection rather than as a
+//! mismatch. Locked files read from a shadow copy are re-read from the same
+//! shadow copy, so shadow copies must still exist when this runs.
+//!
+//! The sample is chosen by ranking files on a hash of the seed and their
+//! path, so the same seed picks the same files from the same collection.
+//! The seed is recorded in `verification_report.json`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::collectors::collector::{ArtifactFailure, FailureReason};
+use crate::models::ArtifactMetadata;
+use crate::utils::hash::calculate_sha256;
+use crate::windows::vss;
+
+/// Report written to the collection directory
+pub const VERIFICATION_REPORT_FILE: &str = "verification_report.json";
+
+/// Hash files of any size
+const NO_SIZE_LIMIT_MB: u64 = u64::MAX / (1024 * 1024);
+
+/// Mismatches listed in the collection summary
+const SUMMARY_MISMATCH_LIMIT: usize = 50;
+
+/// Percentage of files to verify and the seed picking them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleOptions {
+    /// 1-100
+    pub percent: u32,
+    pub seed: u64,
+}
+
+impl SampleOptions {
+    /// Options for `percent`, with `seed` or one taken from the clock
+    pub fn new(percent: u32, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Self { percent, seed }
+    }
+}
+
+/// A collected artifact whose files may be sampled
+#[derive(Debug, Clone)]
+pub struct SampleCandidate {
+    pub artifact: String,
+    pub required: bool,
+    /// Collected copy, a file or a directory of copied files
+    pub copy: PathBuf,
+    pub metadata: ArtifactMetadata,
+}
+
+impl SampleCandidate {
+    /// Candidate for a collected copy, or `None` when there is nothing to
+    /// re-read: listings, memory dumps and sources that are not local paths
+    pub fn new(
+        artifact: &str,
+        required: bool,
+        copy: PathBuf,
+        metadata: &ArtifactMetadata,
+    ) -> Option<Self> {
+        let source = Path::new(&metadata.original_path);
+        let copyable = metadata.listed_files.is_none()
+            && metadata.memory_region.is_none()
+            && metadata.dump_method.is_none();
+        (copyable && source.is_absolute() && source.exists() && copy.exists()).then(|| Self {
+            artifact: artifact.to_string(),
+            required,
+            copy,
+            metadata: metadata.clone(),
+        })
+    }
+}
+
+/// Outcome of verifying one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// Source and copy have the same hash
+    Match,
+    /// The source is unchanged but the copy differs
+    Mismatch,
+    /// The source's modification time moved after it was copied
+    SourceChanged,
+    /// The source or the copy could not be read again
+    Unverifiable,
+}
+
+/// One sampled file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedFile {
+    pub artifact: String,
+    pub source_path: String,
+    /// Path the source was re-read from, when not `source_path` (a shadow
+    /// copy for locked files)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_path: Option<String>,
+    /// Collected copy, relative to the collection directory
+    pub collected_path: String,
+    pub status: VerificationStatus,
+    pub source_sha256: Option<String>,
+    pub collected_sha256: Option<String>,
+    /// Modification time when the file was collected, if recorded
+    pub collected_modified_time: Option<String>,
+    /// Modification time when the file was verified
+    pub current_modified_time: Option<String>,
+    /// For mismatches, whether copying again produced a matching copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recollected: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    required: bool,
+}
+
+/// Result of a sampled verification, written to `verification_report.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub seed: u64,
+    pub sample_percent: u32,
+    /// Collected files the sample was drawn from
+    pub candidate_files: usize,
+    pub sampled_files: usize,
+    pub matched: usize,
+    pub mismatched: usize,
+    pub source_changed: usize,
+    pub unverifiable: usize,
+    /// Mismatched files copied again and verified
+    pub recollected: usize,
+    pub files: Vec<VerifiedFile>,
+}
+
+impl VerificationReport {
+    /// Mismatched files, which are collection failures
+    pub fn mismatches(&self) -> impl Iterator<Item = &VerifiedFile> {
+        self.files
+            .iter()
+            .filter(|file| file.status == VerificationStatus::Mismatch)
+    }
+
+    /// Failures to report for the mismatched files
+    pub fn failures(&self) -> Vec<ArtifactFailure> {
+        self.mismatches()
+            .map(|file| ArtifactFailure {
+                artifact: file.artifact.clone(),
+                source_path: file.source_path.clone(),
+                required: file.required,
+                reason: FailureReason::Error,
+                message: format!(
+                    "Collected copy {} did not match the unchanged source; {}",
+                    file.collected_path,
+                    match file.recollected {
+                        Some(true) => "copied again and verified",
+                        _ => "copying it again did not produce a matching copy",
+                    }
+                ),
+            })
+            .collect()
+    }
+
+    /// `verification` section of the collection summary: the counts and the
+    /// mismatched files
+    pub fn summary_section(&self) -> Value {
+        let mismatches: Vec<&VerifiedFile> =
+            self.mismatches().take(SUMMARY_MISMATCH_LIMIT).collect();
+        json!({
+            "report": VERIFICATION_REPORT_FILE,
+            "seed": self.seed,
+            "sample_percent": self.sample_percent,
+            "sampled_files": self.sampled_files,
+            "matched": self.matched,
+            "mismatched": self.mismatched,
+            "source_changed": self.source_changed,
+            "unverifiable": self.unverifiable,
+            "recollected": self.recollected,
+            "mismatches": mismatches,
+        })
+    }
+}
+
+/// Re-read and re-hash a sample of the collected files below `output_dir`
+pub fn verify_sample(
+    output_dir: &Path,
+    candidates: &[SampleCandidate],
+    options: SampleOptions,
+) -> VerificationReport {
+    let files = sample_files(candidates);
+    let sampled = select_sample(files.len(), options, |index| &files[index].copy);
+    info!(
+        "Verifying {} of {} collected files (seed {})",
+        sampled.len(),
+        files.len(),
+        options.seed
+    );
+
+    let mut report = VerificationReport {
+        seed: options.seed,
+        sample_percent: options.percent,
+        candidate_files: files.len(),
+        sampled_files: sampled.len(),
+        matched: 0,
+        mismatched: 0,
+        source_changed: 0,
+        unverifiable: 0,
+        recollected: 0,
+        files: Vec::with_capacity(sampled.len()),
+    };
+    for index in sampled {
+        let verified = verify_file(output_dir, &files[index]);
+        match verified.status {
+            VerificationStatus::Match => report.matched += 1,
+            VerificationStatus::Mismatch => {
+                report.mismatched += 1;
+                if verified.recollected == Some(true) {
+                    report.recollected += 1;
+                }
+                warn!(
+                    "Collected copy of {} does not match its unchanged source",
+                    verified.source_path
+                );
+            }
+            VerificationStatus::SourceChanged => report.source_changed += 1,
+            VerificationStatus::Unverifiable => report.unverifiable += 1,
+        }
+        report.files.push(verified);
+    }
+    report
+}
+
+/// Write `verification_report.json` to `output_dir`
+pub fn write_report(output_dir: &Path, report: &VerificationReport) -> Result<PathBuf> {
+    let path = output_dir.join(VERIFICATION_REPORT_FILE);
+    let json =
+        serde_json::to_string_pretty(report).context("Failed to serialize verification report")?;
+    fs::write(&path, json).context(format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// A single collected file and where its source is
+struct SampleFile<'a> {
+    candidate: &'a SampleCandidate,
+    copy: PathBuf,
+    source: PathBuf,
+    /// Whether the file is below a collected directory, whose copy does not
+    /// record each file's modification time
+    in_directory: bool,
+}
+
+/// Expand collected directories into their files, in a fixed order
+fn sample_files(candidates: &[SampleCandidate]) -> Vec<SampleFile<'_>> {
+    let mut files = Vec::new();
+    for candidate in candidates {
+        if candidate.copy.is_file() {
+            files.push(SampleFile {
+                candidate,
+                copy: candidate.copy.clone(),
+                source: PathBuf::from(&candidate.metadata.original_path),
+                in_directory: false,
+            });
+            continue;
+        }
+        for entry in WalkDir::new(&candidate.copy)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+        {
+            let Ok(relative) = entry.path().strip_prefix(&candidate.copy) else {
+                continue;
+            };
+            files.push(SampleFile {
+                candidate,
+                copy: entry.path().to_path_buf(),
+                source: Path::new(&candidate.metadata.original_path).join(relative),
+                in_directory: true,
+            });
+        }
+    }
+    files
+}
+
+/// Indices of the sampled files: the `percent` of them ranking lowest by a
+/// hash of the seed and their path, at least one when there are any
+fn select_sample<'a>(
+    count: usize,
+    options: SampleOptions,
+    path_of: impl Fn(usize) -> &'a PathBuf,
+) -> Vec<usize> {
+    let wanted = (count * options.percent.min(100) as usize).div_ceil(100);
+    let mut ranked: Vec<(u64, usize)> = (0..count)
+        .map(|index| (sample_rank(options.seed, path_of(index)), index))
+        .collect();
+    ranked.sort_unstable();
+    let mut sampled: Vec<usize> = ranked.into_iter().take(wanted).map(|(_, i)| i).collect();
+    sampled.sort_unstable();
+    sampled
+}
+
+fn sample_rank(seed: u64, path: &Path) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    hasher.update(path.to_string_lossy().as_bytes());
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 is 32 bytes"))
+}
+
+fn verify_file(output_dir: &Path, file: &SampleFile) -> VerifiedFile {
+    let metadata = &file.candidate.metadata;
+    let mut verified = VerifiedFile {
+        artifact: file.candidate.artifact.clone(),
+        source_path: file.source.to_string_lossy().to_string(),
+        read_path: None,
+        collected_path: file
+            .copy
+            .strip_prefix(output_dir)
+            .unwrap_or(&file.copy)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        status: VerificationStatus::Unverifiable,
+        source_sha256: None,
+        collected_sha256: None,
+        collected_modified_time: (!file.in_directory)
+            .then(|| metadata.modified_time.clone())
+            .flatten(),
+        current_modified_time: None,
+        recollected: None,
+        error: None,
+        required: file.candidate.required,
+    };
+
+    // Locked files are read again from the shadow copy they came from
+    let read_path = if metadata.fallback_method.as_deref() == Some(vss::FALLBACK_METHOD) {
+        match vss::existing_shadow_path(&verified.source_path) {
+            Some(path) => {
+                verified.read_path = Some(path.clone());
+                PathBuf::from(path)
+            }
+            None => {
+                verified.error = Some("the shadow copy it was read from is gone".to_string());
+                return verified;
+            }
+        }
+    } else {
+        file.source.clone()
+    };
+
+    let current_modified = fs::metadata(&read_path).and_then(|m| m.modified());
+    verified.current_modified_time = current_modified.as_ref().ok().map(|time| rfc3339(*time));
+    let source_hash = calculate_sha256(&read_path, NO_SIZE_LIMIT_MB);
+    let collected_hash = calculate_sha256(&file.copy, NO_SIZE_LIMIT_MB);
+    let (source_hash, collected_hash) = match (source_hash, collected_hash) {
+        (Ok(Some(source)), Ok(Some(collected))) => (source, collected),
+        (Err(e), _) => {
+            verified.error = Some(format!("source could not be read: {}", e));
+            return verified;
+        }
+        (_, Err(e)) => {
+            verified.error = Some(format!("collected copy could not be read: {}", e));
+            return verified;
+        }
+        _ => {
+            verified.error = Some("not a regular file".to_string());
+            return verified;
+        }
+    };
+    verified.source_sha256 = Some(source_hash.clone());
+    verified.collected_sha256 = Some(collected_hash.clone());
+
+    let changed = match &current_modified {
+        Ok(modified) if file.in_directory => modified_after(*modified, &metadata.collection_time),
+        Ok(modified) => modified_since(*modified, metadata.modified_time.as_deref()),
+        Err(_) => false,
+    };
+    verified.status = if changed {
+        VerificationStatus::SourceChanged
+    } else if source_hash == collected_hash {
+        VerificationStatus::Match
+    } else {
+        verified.recollected = Some(recollect(&read_path, &file.copy, &source_hash));
+        VerificationStatus::Mismatch
+    };
+    verified
+}
+
+/// Copy `source` over the corrupted `copy` again; whether the new copy
+/// has the source's hash
+fn recollect(source: &Path, copy: &Path, source_hash: &str) -> bool {
+    if let Err(e) = fs::copy(source, copy) {
+        warn!("Failed to collect {} again: {}", source.display(), e);
+        return false;
+    }
+    matches!(
+        calculate_sha256(copy, NO_SIZE_LIMIT_MB),
+        Ok(Some(hash)) if hash == source_hash
+    )
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+/// Whether `modified` differs from the RFC3339 time recorded at
+/// collection; unknown when none was recorded
+fn modified_since(modified: SystemTime, recorded: Option<&str>) -> bool {
+    match recorded.map(DateTime::parse_from_rfc3339) {
+        Some(Ok(recorded)) => DateTime::<Utc>::from(modified) != recorded,
+        Some(Err(_)) => recorded != Some(rfc3339(modified).as_str()),
+        None => false,
+    }
+}
+
+/// Whether `modified` is later than the RFC3339 `collected` time
+fn modified_after(modified: SystemTime, collected: &str) -> bool {
+    DateTime::parse_from_rfc3339(collected)
+        .is_ok_and(|collected| DateTime::<Utc>::from(modified) > collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn metadata(source: &Path) -> ArtifactMetadata {
+        let modified = fs::metadata(source).unwrap().modified().unwrap();
+        ArtifactMetadata {
+            original_path: source.to_string_lossy().to_string(),
+            collection_time: Utc::now().to_rfc3339(),
+            file_size: 0,
+            created_time: None,
+            accessed_time: None,
+            modified_time: Some(rfc3339(modified)),
+            is_locked: false,
+            sha256: None,
+            ..Default::default()
+        }
+    }
+
+    /// A source file and its collected copy
+    fn collected(dir: &TempDir, name: &str, contents: &[u8]) -> SampleCandidate {
+        let source = dir.path().join("source").join(name);
+        let copy = dir.path().join("output").join(name);
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&source, contents).unwrap();
+        fs::write(&copy, contents).unwrap();
+        SampleCandidate::new(name, false, copy, &metadata(&source)).unwrap()
+    }
+
+    fn full_sample(seed: u64) -> SampleOptions {
+        SampleOptions { percent: 100, seed }
+    }
+
+    #[test]
+    fn test_verify_matching_and_corrupted_copies() {
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("output");
+        let good = collected(&dir, "good.log", b"intact");
+        let bad = collected(&dir, "bad.log", b"original contents");
+        fs::write(&bad.copy, b"original c0ntents").unwrap();
+
+        let report = verify_sample(&output, &[good, bad.clone()], full_sample(7));
+        assert_eq!(report.sampled_files, 2);
+        assert_eq!(report.matched, 1);
+        assert_eq!(report.mismatched, 1);
+        assert_eq!(report.recollected, 1);
+
+        let mismatch = report.mismatches().next().unwrap();
+        assert_eq!(mismatch.collected_path, "bad.log");
+        assert_eq!(mismatch.recollected, Some(true));
+        assert_ne!(mismatch.source_sha256, mismatch.collected_sha256);
+        assert_eq!(fs::read(&bad.copy).unwrap(), b"original contents");
+
+        let failures = report.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].artifact, "bad.log");
+        assert_eq!(report.summary_section()["mismatched"], 1);
+
+        let path = write_report(&output, &report).unwrap();
+        let written: VerificationReport =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written.seed, 7);
+        assert_eq!(written.files, report.files);
+    }
+
+    #[test]
+    fn test_changed_source_is_not_a_mismatch() {
+        let dir = TempDir::new().unwrap();
+        let candidate = collected(&dir, "app.log", b"first");
+        let source = PathBuf::from(&candidate.metadata.original_path);
+        fs::write(&source, b"second, longer").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let report = verify_sample(dir.path(), std::slice::from_ref(&candidate), full_sample(1));
+        assert_eq!(report.source_changed, 1);
+        assert_eq!(report.mismatched, 0);
+        assert!(report.failures().is_empty());
+        assert_eq!(fs::read(&candidate.copy).unwrap(), b"first");
+    }
+
+    #[test]
+    fn test_collected_directories_are_sampled_per_file() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source/logs");
+        let copy = dir.path().join("output/logs");
+        for root in [&source, &copy] {
+            fs::create_dir_all(root.join("nested")).unwrap();
+            fs::write(root.join("a.log"), b"a").unwrap();
+            fs::write(root.join("nested/b.log"), b"b").unwrap();
+        }
+        fs::write(copy.join("nested/b.log"), b"x").unwrap();
+        let candidate = SampleCandidate::new("logs", true, copy.clone(), &metadata(&source));
+
+        let report = verify_sample(dir.path(), &[candidate.unwrap()], full_sample(3));
+        assert_eq!(report.candidate_files, 2);
+        assert_eq!(report.matched, 1);
+        assert_eq!(report.mismatched, 1);
+        assert!(report.failures()[0].required);
+        assert_eq!(fs::read(copy.join("nested/b.log")).unwrap(), b"b");
+    }
+
+    #[test]
+    fn test_sample_is_deterministic_for_a_seed() {
+        let paths: Vec<PathBuf> = (0..200)
+            .map(|i| PathBuf::from(format!("/out/file{}.bin", i)))
+            .collect();
+        let pick = |seed| {
+            select_sample(paths.len(), SampleOptions { percent: 10, seed }, |i| {
+                &paths[i]
+            })
+        };
+
+        let first = pick(42);
+        assert_eq!(first.len(), 20);
+        assert_eq!(first, pick(42));
+        assert_ne!(first, pick(43));
+        assert_eq!(
+            select_sample(
+                3,
+                SampleOptions {
+                    percent: 1,
+                    seed: 0
+                },
+                |i| &paths[i]
+            )
+            .len(),
+            1
+        );
+        assert!(select_sample(0, SampleOptions::new(50, None), |i| &paths[i]).is_empty());
+    }
+
+    #[test]
+    fn test_candidates_skip_listings_and_missing_sources() {
+        let dir = TempDir::new().unwrap();
+        let candidate = collected(&dir, "x.log", b"x");
+        let mut listing = candidate.metadata.clone();
+        listing.listed_files = Some(3);
+        assert!(SampleCandidate::new("x", false, candidate.copy.clone(), &listing).is_none());
+
+        let mut gone = candidate.metadata.clone();
+        gone.original_path = dir.path().join("missing").to_string_lossy().to_string();
+        assert!(SampleCandidate::new("x", false, candidate.copy.clone(), &gone).is_none());
+    }
+}
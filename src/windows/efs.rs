@@ -0,0 +1,210 @@
+//! Raw capture of EFS-encrypted files.
+//!
+//! A file encrypted with the Encrypting File System can only be read as
+//! plaintext by a user holding its key, so collection as SYSTEM or as
+//! another user fails with `ERROR_ACCESS_DENIED`, from the live volume and
+//! from a shadow copy alike. `ReadEncryptedFileRaw` still exports the file
+//! in the EFS backup format: the ciphertext together with the `$EFS`
+//! metadata holding its encrypted file key. That export is written to
+//! `<name>.efsraw`, with a note beside it explaining that the owner's EFS
+//! certificate and private key are needed to decrypt it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::collectors::platforms::encryption;
+use crate::models::ArtifactMetadata;
+
+/// `fallback_method` of files captured in the EFS backup format
+pub const FALLBACK_METHOD: &str = "efs_raw";
+
+/// Extension appended to the destination of a raw EFS capture
+pub const RAW_EXTENSION: &str = "efsraw";
+
+/// Note written next to each raw capture
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const DECRYPTION_NOTE: &str = "\
+This file was encrypted with the Windows Encrypting File System (EFS) and
+could not be read as plaintext during collection. The .efsraw file beside
+this note is its raw EFS backup stream (ReadEncryptedFileRaw format): the
+ciphertext and the $EFS metadata holding the encrypted file key.
+
+Decrypting it needs the owning user's EFS certificate and private key, or a
+data recovery agent's. Restore it on Windows with WriteEncryptedFileRaw (or
+a tool built on it) as a user holding that key, then open it normally.
+";
+
+/// Destination of the raw capture of a file collected to `dest`
+pub fn raw_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".");
+    name.push(RAW_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Destination of the note accompanying the raw capture at `raw`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn note_path(raw: &Path) -> PathBuf {
+    let mut name = raw.as_os_str().to_os_string();
+    name.push(".txt");
+    PathBuf::from(name)
+}
+
+/// Export `source_path` in the EFS backup format to `<dest>.efsraw`.
+///
+/// The metadata names the live path and has `encrypted: "efs"` and
+/// `fallback_method: "efs_raw"`; its size and hash are of the raw stream.
+#[cfg(target_os = "windows")]
+pub fn read_encrypted_file_raw(source_path: &str, dest: &Path) -> Result<ArtifactMetadata> {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use anyhow::{anyhow, Context};
+    use log::info;
+    use widestring::U16CString;
+    use winapi::ctypes::c_void;
+    use winapi::shared::minwindef::{DWORD, PBYTE, ULONG};
+    use winapi::shared::winerror::ERROR_SUCCESS;
+    use winapi::um::winbase::{CloseEncryptedFileRaw, OpenEncryptedFileRawW, ReadEncryptedFileRaw};
+
+    /// Append each exported chunk to the `File` passed as context
+    unsafe extern "system" fn export(data: PBYTE, context: *mut c_void, length: ULONG) -> DWORD {
+        let file = &mut *(context as *mut File);
+        let chunk = std::slice::from_raw_parts(data, length as usize);
+        match file.write_all(chunk) {
+            Ok(()) => ERROR_SUCCESS,
+            Err(e) => e.raw_os_error().unwrap_or(1) as DWORD,
+        }
+    }
+
+    let raw = raw_path(dest);
+    if let Some(parent) = raw.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let mut output = File::create(&raw)
+        .with_context(|| format!("Failed to create output file: {}", raw.display()))?;
+
+    let wide = U16CString::from_str(source_path)
+        .with_context(|| format!("Invalid path: {}", source_path))?;
+    let mut context: *mut c_void = std::ptr::null_mut();
+    // SAFETY: the path is NUL-terminated; flags 0 opens for export
+    let status = unsafe { OpenEncryptedFileRawW(wide.as_ptr(), 0, &mut context) };
+    if status != ERROR_SUCCESS {
+        let _ = fs::remove_file(&raw);
+        return Err(anyhow::Error::new(std::io::Error::from_raw_os_error(
+            status as i32,
+        )))
+        .context(format!("OpenEncryptedFileRaw failed for {}", source_path));
+    }
+    // SAFETY: the context is open and the callback context is the output
+    // file, which outlives the call
+    let status = unsafe {
+        let status = ReadEncryptedFileRaw(
+            Some(export),
+            &mut output as *mut File as *mut c_void,
+            context,
+        );
+        CloseEncryptedFileRaw(context);
+        status
+    };
+    if status != ERROR_SUCCESS {
+        drop(output);
+        let _ = fs::remove_file(&raw);
+        return Err(anyhow!(
+            "ReadEncryptedFileRaw failed for {}: {}",
+            source_path,
+            std::io::Error::from_raw_os_error(status as i32)
+        ));
+    }
+    output.flush()?;
+    drop(output);
+    fs::write(note_path(&raw), DECRYPTION_NOTE)
+        .with_context(|| format!("Failed to write EFS note next to {}", raw.display()))?;
+
+    let source = fs::metadata(source_path).ok();
+    let time = |t: std::io::Result<std::time::SystemTime>| {
+        t.ok()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+    };
+    info!(
+        "Captured EFS-encrypted {} as raw stream {}",
+        source_path,
+        raw.display()
+    );
+    Ok(ArtifactMetadata {
+        original_path: source_path.to_string(),
+        collection_time: chrono::Utc::now().to_rfc3339(),
+        file_size: fs::metadata(&raw)?.len(),
+        created_time: source.as_ref().and_then(|m| time(m.created())),
+        accessed_time: source.as_ref().and_then(|m| time(m.accessed())),
+        modified_time: source.as_ref().and_then(|m| time(m.modified())),
+        is_locked: false,
+        sha256: None,
+        fallback_method: Some(FALLBACK_METHOD.to_string()),
+        encrypted: Some(encryption::EFS.to_string()),
+        ..Default::default()
+    })
+}
+
+/// EFS only exists on Windows
+#[cfg(not(target_os = "windows"))]
+pub fn read_encrypted_file_raw(source_path: &str, _dest: &Path) -> Result<ArtifactMetadata> {
+    Err(anyhow::anyhow!(
+        "Cannot export {} in EFS raw format: EFS is only available on Windows",
+        source_path
+    ))
+}
+
+/// Capture `source_path` raw when reading it failed and it is EFS
+/// encrypted; other results are returned as they are
+pub fn fall_back_on_encrypted(
+    result: Result<ArtifactMetadata>,
+    source_path: &str,
+    dest: &Path,
+) -> Result<ArtifactMetadata> {
+    match result {
+        Err(e) if encryption::detect(Path::new(source_path)) == Some(encryption::EFS) => {
+            read_encrypted_file_raw(source_path, dest)
+                .map_err(|efs_error| e.context(format!("Raw EFS capture failed: {:#}", efs_error)))
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_raw_and_note_paths() {
+        let raw = raw_path(Path::new("out/fs/C/Users/alice/secret.docx"));
+        assert_eq!(
+            raw,
+            PathBuf::from("out/fs/C/Users/alice/secret.docx.efsraw")
+        );
+        assert_eq!(
+            note_path(&raw),
+            PathBuf::from("out/fs/C/Users/alice/secret.docx.efsraw.txt")
+        );
+        assert!(DECRYPTION_NOTE.contains("private key"));
+    }
+
+    #[test]
+    fn test_fallback_only_for_encrypted_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("plain.txt");
+        std::fs::write(&source, b"plain").unwrap();
+        let dest = dir.path().join("out");
+
+        let result = fall_back_on_encrypted(
+            Err(anyhow::Error::new(io::Error::from_raw_os_error(5))),
+            &source.to_string_lossy(),
+            &dest,
+        );
+        assert!(!format!("{:#}", result.unwrap_err()).contains("Raw EFS"));
+        assert!(!raw_path(&dest).exists());
+    }
+}
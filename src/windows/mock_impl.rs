@@ -47,8 +47,19 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
         accessed_time: Some(collection_time.clone()),
         modified_time: Some(collection_time),
         is_locked: false,
+        sha256: None,
+        ..Default::default()
     };
 
     info!("Mock implementation: File would be collected with backup semantics on Windows");
     Ok(metadata)
 }
+
+/// Mock implementation of paging file collection for non-Windows platforms
+pub fn collect_paging_file(
+    source_path: &str,
+    dest_path: &Path,
+    _size_cap: Option<u64>,
+) -> Result<ArtifactMetadata> {
+    collect_with_raw_handle(source_path, dest_path)
+}
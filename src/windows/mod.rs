@@ -6,15 +6,20 @@ pub mod raw_access;
 #[cfg(not(target_os = "windows"))]
 mod mock_impl;
 
+/// Raw capture of EFS-encrypted files
+pub mod efs;
+/// Volume Shadow Copy fallback for files locked by another process
+pub mod vss;
+
 #[cfg(target_os = "windows")]
-pub use privileges::enable_privileges;
+pub use privileges::{enable_named_privilege, enable_privileges, privilege_status};
 #[cfg(target_os = "windows")]
 pub use raw_access::check_backup_api_available;
 #[cfg(target_os = "windows")]
-pub use raw_access::collect_with_raw_handle;
+pub use raw_access::{collect_paging_file, collect_with_raw_handle};
 
 #[cfg(not(target_os = "windows"))]
-pub use mock_impl::{collect_with_raw_handle, enable_privileges};
+pub use mock_impl::{collect_paging_file, collect_with_raw_handle, enable_privileges};
 
 #[cfg(not(target_os = "windows"))]
 #[allow(dead_code)]
@@ -6,6 +6,7 @@ use anyhow::{anyhow, Result};
 use log::{debug, info, warn};
 use widestring::U16CString;
 use winapi::shared::minwindef::{DWORD, FALSE};
+use winapi::um::handleapi::CloseHandle;
 use winapi::um::processthreadsapi::GetCurrentProcess;
 use winapi::um::processthreadsapi::OpenProcessToken;
 use winapi::um::securitybaseapi::{AdjustTokenPrivileges, GetTokenInformation};
@@ -16,42 +17,23 @@ use winapi::um::winnt::{
     TOKEN_QUERY,
 };
 
+/// Privileges the collector enables for locked files and process memory
+const COLLECTION_PRIVILEGES: [&str; 5] = [
+    "SeBackupPrivilege",
+    "SeRestorePrivilege",
+    "SeSecurityPrivilege",
+    "SeTakeOwnershipPrivilege",
+    "SeDebugPrivilege",
+];
+
 /// Enable backup and restore privileges for accessing locked files
 pub fn enable_privileges() -> Result<()> {
     info!("Enabling backup and restore privileges");
 
-    let privileges = [
-        "SeBackupPrivilege",
-        "SeRestorePrivilege",
-        "SeSecurityPrivilege",
-        "SeTakeOwnershipPrivilege",
-        "SeDebugPrivilege",
-    ];
-
-    // SAFETY: GetCurrentProcess() returns a pseudo-handle to the current process.
-    // This handle does not need to be closed and is always valid.
-    let h_process = unsafe { GetCurrentProcess() };
-    let mut h_token: HANDLE = ptr::null_mut();
-
-    // Open the process token
-    // SAFETY: OpenProcessToken is safe to call with a valid process handle and
-    // a mutable pointer to receive the token handle. The h_process is valid
-    // (from GetCurrentProcess) and h_token is properly initialized to null.
-    let token_result = unsafe {
-        OpenProcessToken(
-            h_process,
-            TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
-            &mut h_token,
-        )
-    };
-
-    if token_result == 0 {
-        let err = io::Error::last_os_error();
-        return Err(anyhow!("Failed to open process token: {}", err));
-    }
+    let h_token = open_process_token()?;
 
     let mut success_count = 0;
-    for privilege in privileges.iter() {
+    for privilege in COLLECTION_PRIVILEGES.iter() {
         let privilege_result = enable_privilege(h_token, privilege);
         match privilege_result {
             Ok(enabled) => {
@@ -77,6 +59,61 @@ pub fn enable_privileges() -> Result<()> {
     }
 }
 
+/// Report which collection privileges can be enabled in this process.
+///
+/// A privilege the token does not hold fails to enable and is reported as
+/// unavailable; the others are left enabled, as a collection run would.
+pub fn privilege_status() -> Result<Vec<(&'static str, bool)>> {
+    let h_token = open_process_token()?;
+
+    let status = COLLECTION_PRIVILEGES
+        .iter()
+        .map(|privilege| (*privilege, enable_privilege(h_token, privilege).is_ok()))
+        .collect();
+
+    // SAFETY: h_token is a valid handle returned by OpenProcessToken
+    unsafe { CloseHandle(h_token) };
+    Ok(status)
+}
+
+/// Enable one privilege, such as `SeDebugPrivilege`, in the current process
+/// token. Fails if the token does not hold it.
+pub fn enable_named_privilege(privilege_name: &str) -> Result<()> {
+    let h_token = open_process_token()?;
+    let result = enable_privilege(h_token, privilege_name);
+
+    // SAFETY: h_token is a valid handle returned by OpenProcessToken
+    unsafe { CloseHandle(h_token) };
+    result.map(|_| ())
+}
+
+/// Open the current process token for querying and adjusting privileges
+fn open_process_token() -> Result<HANDLE> {
+    // SAFETY: GetCurrentProcess() returns a pseudo-handle to the current process.
+    // This handle does not need to be closed and is always valid.
+    let h_process = unsafe { GetCurrentProcess() };
+    let mut h_token: HANDLE = ptr::null_mut();
+
+    // Open the process token
+    // SAFETY: OpenProcessToken is safe to call with a valid process handle and
+    // a mutable pointer to receive the token handle. The h_process is valid
+    // (from GetCurrentProcess) and h_token is properly initialized to null.
+    let token_result = unsafe {
+        OpenProcessToken(
+            h_process,
+            TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+            &mut h_token,
+        )
+    };
+
+    if token_result == 0 {
+        let err = io::Error::last_os_error();
+        return Err(anyhow!("Failed to open process token: {}", err));
+    }
+
+    Ok(h_token)
+}
+
 /// Enable a specific privilege
 fn enable_privilege(h_token: HANDLE, privilege_name: &str) -> Result<bool> {
     let mut luid = LUID {
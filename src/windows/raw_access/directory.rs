@@ -13,8 +13,15 @@ use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
 use winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS;
 use winapi::um::winnt::{FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE};
 
+use crate::collectors::cancellation;
+use crate::collectors::incremental;
+use crate::collectors::platforms::common::rfc3339;
+use crate::collectors::platforms::encryption;
+use crate::collectors::read_only;
 use crate::models::ArtifactMetadata;
+use crate::utils::hash::sha256_reader;
 use crate::windows::raw_access::file_access::collect_with_raw_handle;
+use crate::windows::{efs, vss};
 
 /// Check if a path is a directory
 pub fn is_directory(path: &str) -> Result<bool> {
@@ -39,7 +46,8 @@ pub fn is_directory(path: &str) -> Result<bool> {
 
     if handle == INVALID_HANDLE_VALUE {
         let err = std::io::Error::last_os_error();
-        return Err(anyhow!("Failed to open path to check attributes: {}", err));
+        let message = format!("Failed to open path to check attributes: {}", err);
+        return Err(anyhow::Error::new(err).context(message));
     }
 
     // Get file information
@@ -80,6 +88,37 @@ pub fn is_directory(path: &str) -> Result<bool> {
     Ok((file_info.dwFileAttributes & winapi::um::winnt::FILE_ATTRIBUTE_DIRECTORY) != 0)
 }
 
+/// Collect one file of a directory, reading it from a shadow copy if it
+/// is locked and capturing it raw if it is EFS encrypted
+fn collect_file(file_src: &str, file_dest: &Path) -> Result<ArtifactMetadata> {
+    let source = std::fs::metadata(file_src).ok();
+    let metadata = efs::fall_back_on_encrypted(
+        vss::fall_back_on_lock(
+            collect_with_raw_handle(file_src, file_dest),
+            file_src,
+            file_dest,
+        ),
+        file_src,
+        file_dest,
+    )?;
+    if let Some(source) = source {
+        incremental::record_collected(file_src, source.len(), rfc3339(source.modified().ok()));
+    }
+    Ok(metadata)
+}
+
+/// Whether a file of a directory is unchanged since the `--baseline`
+/// collection, so it is left there
+fn unchanged_since_baseline(file_src: &str) -> bool {
+    let Ok(source) = std::fs::metadata(file_src) else {
+        return false;
+    };
+    incremental::is_unchanged(file_src, source.len(), source.modified().ok(), || {
+        let file = read_only::open(Path::new(file_src)).ok()?;
+        sha256_reader(file).ok()
+    })
+}
+
 /// Parallel collector for directory traversal
 /// Uses a thread pool to collect files in parallel
 pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<ArtifactMetadata> {
@@ -89,6 +128,11 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
         dest_path.display()
     );
 
+    // Stop early if the artifact timed out
+    if cancellation::is_cancelled(Path::new(source_path)) {
+        return Err(anyhow!("Copy of {} cancelled after timeout", source_path));
+    }
+
     // Create the destination directory
     std::fs::create_dir_all(dest_path).map_err(|e| {
         anyhow!(
@@ -191,10 +235,14 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
     // Close the find handle
     unsafe { winapi::um::fileapi::FindClose(find_handle) };
 
+    files.retain(|(file_src, _)| !unchanged_since_baseline(file_src));
+
     // Create atomic counters for parallel processing
     let total_files = Arc::new(std::sync::atomic::AtomicUsize::new(0));
     let total_bytes = Arc::new(std::sync::atomic::AtomicU64::new(0));
     let is_locked = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let from_shadow_copy = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let any_encrypted = Arc::new(std::sync::atomic::AtomicBool::new(false));
 
     // Process directories sequentially to create structure first
     for (dir_src, dir_dest) in directories {
@@ -205,6 +253,12 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
                 if metadata.is_locked {
                     is_locked.store(true, std::sync::atomic::Ordering::SeqCst);
                 }
+                if metadata.fallback_method.as_deref() == Some(vss::FALLBACK_METHOD) {
+                    from_shadow_copy.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+                if metadata.encrypted.is_some() {
+                    any_encrypted.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
             }
             Err(e) => {
                 warn!("Failed to collect directory {}: {}", dir_src, e);
@@ -234,10 +288,12 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
                     let total_files = total_files.clone();
                     let total_bytes = total_bytes.clone();
                     let is_locked_flag = is_locked.clone();
+                    let from_shadow_copy = from_shadow_copy.clone();
+                    let any_encrypted = any_encrypted.clone();
 
                     scope.spawn(move |_| {
                         for (file_src, file_dest) in chunk_files {
-                            match collect_with_raw_handle(&file_src, &file_dest) {
+                            match collect_file(&file_src, &file_dest) {
                                 Ok(metadata) => {
                                     total_files.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                                     total_bytes.fetch_add(
@@ -248,6 +304,16 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
                                         is_locked_flag
                                             .store(true, std::sync::atomic::Ordering::SeqCst);
                                     }
+                                    if metadata.fallback_method.as_deref()
+                                        == Some(vss::FALLBACK_METHOD)
+                                    {
+                                        from_shadow_copy
+                                            .store(true, std::sync::atomic::Ordering::SeqCst);
+                                    }
+                                    if metadata.encrypted.is_some() {
+                                        any_encrypted
+                                            .store(true, std::sync::atomic::Ordering::SeqCst);
+                                    }
                                 }
                                 Err(e) => {
                                     warn!("Failed to collect file {}: {}", file_src, e);
@@ -272,13 +338,19 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
     } else {
         // Process files sequentially for small sets
         for (file_src, file_dest) in files {
-            match collect_with_raw_handle(&file_src, &file_dest) {
+            match collect_file(&file_src, &file_dest) {
                 Ok(metadata) => {
                     total_files.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                     total_bytes.fetch_add(metadata.file_size, std::sync::atomic::Ordering::SeqCst);
                     if metadata.is_locked {
                         is_locked.store(true, std::sync::atomic::Ordering::SeqCst);
                     }
+                    if metadata.fallback_method.as_deref() == Some(vss::FALLBACK_METHOD) {
+                        from_shadow_copy.store(true, std::sync::atomic::Ordering::SeqCst);
+                    }
+                    if metadata.encrypted.is_some() {
+                        any_encrypted.store(true, std::sync::atomic::Ordering::SeqCst);
+                    }
                 }
                 Err(e) => {
                     warn!("Failed to collect file {}: {}", file_src, e);
@@ -292,6 +364,12 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
     let files_count = total_files.load(std::sync::atomic::Ordering::SeqCst);
     let bytes_count = total_bytes.load(std::sync::atomic::Ordering::SeqCst);
     let locked_status = is_locked.load(std::sync::atomic::Ordering::SeqCst);
+    let fallback_method = from_shadow_copy
+        .load(std::sync::atomic::Ordering::SeqCst)
+        .then(|| vss::FALLBACK_METHOD.to_string());
+    let encrypted = any_encrypted
+        .load(std::sync::atomic::Ordering::SeqCst)
+        .then(|| encryption::EFS.to_string());
 
     // Get current time for collection timestamp
     let collection_time = chrono::Utc::now().to_rfc3339();
@@ -308,6 +386,10 @@ pub fn collect_directory(source_path: &str, dest_path: &Path) -> Result<Artifact
         accessed_time: Some(now.clone()),
         modified_time: Some(now),
         is_locked: locked_status,
+        sha256: None,
+        fallback_method,
+        encrypted,
+        ..Default::default()
     };
 
     debug!(
@@ -1,21 +1,31 @@
 use std::cell::RefCell;
 use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::os::windows::io::AsRawHandle;
+use std::path::{Path, PathBuf};
 use std::ptr;
+use std::time::SystemTime;
 
-use anyhow::{anyhow, Context, Result};
-use log::{debug, warn};
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
 use widestring::U16CString;
 use winapi::shared::minwindef::{DWORD, FILETIME, LPVOID};
 use winapi::um::fileapi::{CreateFileW, ReadFile, OPEN_EXISTING};
 use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
-use winapi::um::winbase::{FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_SEQUENTIAL_SCAN};
-use winapi::um::winnt::{FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ};
-
-use crate::models::ArtifactMetadata;
+use winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS;
+use winapi::um::winnt::{
+    FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, HANDLE,
+};
+
+use crate::collectors::cancellation;
+use crate::collectors::platforms::fast_copy::{self, ProgressLog};
+use crate::collectors::platforms::sparse;
+use crate::collectors::read_only;
+use crate::collectors::streaming::pipeline::stream_reader;
+use crate::collectors::windows::paging_files;
+use crate::models::{ArtifactMetadata, HibernationInfo, PagingFileInfo};
 use crate::windows::raw_access::directory::is_directory;
-use crate::windows::raw_access::utils::filetime_to_iso8601;
+use crate::windows::raw_access::utils::{filetime_to_iso8601_on_volume, local_time_offset_for};
 
 /// Thread-local buffer for file operations to avoid repeated allocations
 thread_local! {
@@ -61,6 +71,18 @@ pub fn check_backup_api_available() -> bool {
     true
 }
 
+/// Logical size of the file or stream behind `handle`, or 0 if unknown
+fn file_size(handle: winapi::um::winnt::HANDLE) -> u64 {
+    // SAFETY: LARGE_INTEGER is plain data; GetFileSizeEx writes it on success
+    unsafe {
+        let mut size: winapi::shared::ntdef::LARGE_INTEGER = std::mem::zeroed();
+        if winapi::um::fileapi::GetFileSizeEx(handle, &mut size) == 0 {
+            return 0;
+        }
+        *size.QuadPart() as u64
+    }
+}
+
 /// Determine optimal buffer size based on file type and size
 fn get_optimal_buffer_size(file_path: &str) -> usize {
     // For large files like memory dumps, use larger buffers
@@ -97,38 +119,167 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
         return crate::windows::raw_access::directory::collect_directory(source_path, dest_path);
     }
 
-    // Convert the path to a wide string for Windows API
-    let wide_path = match U16CString::from_str(source_path) {
-        Ok(path) => path,
-        Err(e) => return Err(anyhow!("Failed to convert path to wide string: {}", e)),
-    };
+    copy_with_raw_handle(source_path, dest_path, None)
+}
 
-    // Add file access hints for Windows to optimize IO
-    #[cfg(target_os = "windows")]
-    let file_flags = FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_SEQUENTIAL_SCAN;
-    #[cfg(not(target_os = "windows"))]
-    let file_flags = FILE_FLAG_BACKUP_SEMANTICS;
+/// Collect a page, swap or hibernation file, copying at most `size_cap`
+/// bytes.
+///
+/// While an archive stream is active the file is read straight into the
+/// upload and `dest_path` is never written. The header of `hiberfil.sys` is
+/// parsed first and recorded in `paging_file`.
+pub fn collect_paging_file(
+    source_path: &str,
+    dest_path: &Path,
+    size_cap: Option<u64>,
+) -> Result<ArtifactMetadata> {
+    debug!("Collecting {} to {}", source_path, dest_path.display());
 
-    // Open the source file with backup semantics
-    let handle = unsafe {
-        CreateFileW(
-            wide_path.as_ptr(),
-            GENERIC_READ,
-            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
-            ptr::null_mut(),
-            OPEN_EXISTING,
-            file_flags,
-            ptr::null_mut(),
-        )
+    let hibernation = if paging_files::is_hibernation_file(Path::new(source_path)) {
+        read_hibernation_header(source_path)
+    } else {
+        None
     };
+    let logical_size = fs::metadata(source_path).map(|m| m.len()).ok();
 
-    if handle == INVALID_HANDLE_VALUE {
-        let err = io::Error::last_os_error();
-        return Err(anyhow!(
-            "Failed to open file with backup semantics: {}",
-            err
-        ));
+    let mut metadata = match stream_with_raw_handle(source_path, dest_path, size_cap) {
+        Some(streamed) => streamed?,
+        None => copy_with_raw_handle(source_path, dest_path, size_cap)?,
+    };
+    let logical_size = logical_size.unwrap_or(metadata.file_size);
+    if metadata.file_size < logical_size {
+        warn!(
+            "Collected {} of {} bytes of {}",
+            metadata.file_size, logical_size, source_path
+        );
+    }
+    metadata.paging_file = Some(PagingFileInfo {
+        logical_size,
+        size_cap,
+        truncated: metadata.file_size < logical_size,
+        hibernation,
+    });
+    Ok(metadata)
+}
+
+/// Parse the start of a hibernation file; `None` if it cannot be read
+fn read_hibernation_header(source_path: &str) -> Option<HibernationInfo> {
+    let mut header = Vec::with_capacity(paging_files::HIBERNATION_HEADER_SCAN_BYTES);
+    let read = read_only::open(Path::new(source_path)).and_then(|file| {
+        file.take(paging_files::HIBERNATION_HEADER_SCAN_BYTES as u64)
+            .read_to_end(&mut header)
+    });
+    match read {
+        Ok(_) => {
+            let info = paging_files::parse_hibernation_header(&header);
+            info!(
+                "{}: hibernation image {} ({})",
+                source_path,
+                info.state,
+                info.format.as_deref().unwrap_or("no image")
+            );
+            Some(info)
+        }
+        Err(e) => {
+            debug!("Cannot read hibernation header of {}: {}", source_path, e);
+            None
+        }
     }
+}
+
+/// Source reader that logs progress and stops once the artifact timed out
+struct ProgressReader {
+    inner: io::Take<read_only::SourceFile>,
+    source: PathBuf,
+    copied: u64,
+    total: u64,
+    progress_log: ProgressLog,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if cancellation::is_cancelled(&self.source) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "copy cancelled after timeout",
+            ));
+        }
+        let read = self.inner.read(buf)?;
+        self.copied += read as u64;
+        self.progress_log
+            .update(self.copied, self.total.max(self.copied));
+        Ok(read)
+    }
+}
+
+/// Read the file straight into the active archive stream; `None` when no
+/// stream is active
+fn stream_with_raw_handle(
+    source_path: &str,
+    dest_path: &Path,
+    size_cap: Option<u64>,
+) -> Option<Result<ArtifactMetadata>> {
+    let source = Path::new(source_path);
+    let source_file = match read_only::open(source) {
+        Ok(file) => file,
+        Err(err) => {
+            let message = format!("Failed to open file with backup semantics: {}", err);
+            return Some(Err(anyhow::Error::new(err).context(message)));
+        }
+    };
+    let times = source_file.metadata().ok();
+    let total = file_size(source_file.as_raw_handle() as HANDLE).min(size_cap.unwrap_or(u64::MAX));
+    let reader = ProgressReader {
+        inner: source_file.take(size_cap.unwrap_or(u64::MAX)),
+        source: source.to_path_buf(),
+        copied: 0,
+        total,
+        progress_log: ProgressLog::new(source),
+    };
+
+    let bytes = match stream_reader(reader, dest_path)? {
+        Ok(bytes) => bytes,
+        Err(e) => return Some(Err(e.context(format!("Failed to stream {}", source_path)))),
+    };
+    info!(
+        "Streamed {} bytes of {} into the archive",
+        bytes, source_path
+    );
+
+    let time = |t: io::Result<SystemTime>| {
+        t.ok()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+    };
+    Some(Ok(ArtifactMetadata {
+        original_path: source_path.to_string(),
+        collection_time: chrono::Utc::now().to_rfc3339(),
+        file_size: bytes,
+        created_time: times.as_ref().and_then(|m| time(m.created())),
+        accessed_time: times.as_ref().and_then(|m| time(m.accessed())),
+        modified_time: times.as_ref().and_then(|m| time(m.modified())),
+        is_locked: bytes < total,
+        sha256: None,
+        allocated_size: sparse::allocated_size(source),
+        ..Default::default()
+    }))
+}
+
+/// Copy a file with backup semantics, stopping after `size_cap` bytes
+fn copy_with_raw_handle(
+    source_path: &str,
+    dest_path: &Path,
+    size_cap: Option<u64>,
+) -> Result<ArtifactMetadata> {
+    // GENERIC_READ with backup semantics only; see collectors::read_only
+    let mut source_file = match read_only::open(Path::new(source_path)) {
+        Ok(file) => file,
+        Err(err) => {
+            // Keep the OS error in the chain so locked files can be recognised
+            let message = format!("Failed to open file with backup semantics: {}", err);
+            return Err(anyhow::Error::new(err).context(message));
+        }
+    };
+    let handle = source_file.as_raw_handle() as HANDLE;
 
     // Initialize file time structures
     let mut creation_time = FILETIME {
@@ -154,8 +305,14 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
         )
     };
 
+    // FAT-family volumes store local time; render it with the recorded offset
+    let local_time_offset = local_time_offset_for(source_path);
+
     let created_time_str = if times_result != 0 {
-        Some(filetime_to_iso8601(&creation_time))
+        Some(filetime_to_iso8601_on_volume(
+            &creation_time,
+            local_time_offset,
+        ))
     } else {
         warn!(
             "Failed to get creation time: {}",
@@ -165,14 +322,20 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
     };
 
     let accessed_time_str = if times_result != 0 {
-        Some(filetime_to_iso8601(&last_access_time))
+        Some(filetime_to_iso8601_on_volume(
+            &last_access_time,
+            local_time_offset,
+        ))
     } else {
         warn!("Failed to get access time: {}", io::Error::last_os_error());
         None
     };
 
     let modified_time_str = if times_result != 0 {
-        Some(filetime_to_iso8601(&last_write_time))
+        Some(filetime_to_iso8601_on_volume(
+            &last_write_time,
+            local_time_offset,
+        ))
     } else {
         warn!("Failed to get write time: {}", io::Error::last_os_error());
         None
@@ -208,40 +371,90 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
         let mut total_bytes: u64 = 0;
         let mut is_locked = false;
 
-        // Read from source and write to destination in chunks
-        loop {
-            let read_result = unsafe {
-                ReadFile(
-                    handle,
-                    buffer.as_mut_ptr() as LPVOID,
-                    optimal_size as DWORD,
-                    &mut bytes_read,
-                    ptr::null_mut(),
-                )
-            };
-
-            if read_result == 0 {
-                let err = io::Error::last_os_error();
-                warn!("Error reading file {}: {}", source_path, err);
-                is_locked = true;
-                break;
-            }
-
-            if bytes_read == 0 {
-                break; // End of file
+        let mut progress_log = ProgressLog::new(Path::new(source_path));
+        let limit = size_cap.unwrap_or(u64::MAX);
+
+        // Sparse streams such as $UsnJrnl:$J: copy only the allocated ranges
+        let logical_size = file_size(handle).min(limit);
+        let ranges = sparse::data_ranges(&source_file, logical_size).unwrap_or_else(|e| {
+            debug!("Cannot query allocated ranges of {}: {}", source_path, e);
+            None
+        });
+        if let Some(ranges) = &ranges {
+            let ranges: Vec<_> = ranges
+                .iter()
+                .filter(|range| range.start < limit)
+                .map(|range| range.start..range.end.min(limit))
+                .collect();
+            match fast_copy::copy_sparse_file(
+                &mut source_file,
+                &mut dest_file,
+                Path::new(source_path),
+                &ranges,
+                logical_size,
+                optimal_size,
+                &mut |copied, total| progress_log.update(copied, total),
+            ) {
+                Ok(data_bytes) => {
+                    debug!(
+                        "Copied sparse file {}: {} of {} bytes allocated",
+                        source_path, data_bytes, logical_size
+                    );
+                    total_bytes = logical_size;
+                }
+                Err(e) => {
+                    warn!("Error copying sparse file {}: {}", source_path, e);
+                    is_locked = true;
+                }
             }
-
-            if let Err(e) = dest_file.write_all(&buffer[0..bytes_read as usize]) {
-                warn!("Error writing to {}: {}", dest_path.display(), e);
-                is_locked = true;
-                break;
+        } else {
+            // Read from source and write to destination in chunks
+            loop {
+                // Stop early if the artifact timed out
+                if cancellation::is_cancelled(Path::new(source_path)) {
+                    warn!("Copy of {} cancelled after timeout", source_path);
+                    is_locked = true;
+                    break;
+                }
+
+                if total_bytes >= limit {
+                    break;
+                }
+                let want = (optimal_size as u64).min(limit - total_bytes) as DWORD;
+                let read_result = unsafe {
+                    ReadFile(
+                        handle,
+                        buffer.as_mut_ptr() as LPVOID,
+                        want,
+                        &mut bytes_read,
+                        ptr::null_mut(),
+                    )
+                };
+
+                if read_result == 0 {
+                    let err = io::Error::last_os_error();
+                    warn!("Error reading file {}: {}", source_path, err);
+                    is_locked = true;
+                    break;
+                }
+
+                if bytes_read == 0 {
+                    break; // End of file
+                }
+
+                if let Err(e) = dest_file.write_all(&buffer[0..bytes_read as usize]) {
+                    warn!("Error writing to {}: {}", dest_path.display(), e);
+                    is_locked = true;
+                    break;
+                }
+
+                total_bytes += bytes_read as u64;
+                progress_log.update(total_bytes, logical_size.max(total_bytes));
             }
-
-            total_bytes += bytes_read as u64;
         }
 
         // Close the handle before returning
-        unsafe { CloseHandle(handle) };
+        drop(source_file);
 
         // Get current time for collection timestamp
         let collection_time = chrono::Utc::now().to_rfc3339();
@@ -255,6 +468,9 @@ pub fn collect_with_raw_handle(source_path: &str, dest_path: &Path) -> Result<Ar
             accessed_time: accessed_time_str,
             modified_time: modified_time_str,
             is_locked,
+            sha256: None,
+            allocated_size: sparse::allocated_size(Path::new(source_path)),
+            ..Default::default()
         };
 
         Ok(metadata)
@@ -5,5 +5,8 @@ mod utils;
 
 // Re-export the main functions and types
 pub use directory::is_directory;
-pub use file_access::{check_backup_api_available, collect_with_raw_handle};
-pub use utils::{filetime_to_iso8601, get_current_filetime};
+pub use file_access::{check_backup_api_available, collect_paging_file, collect_with_raw_handle};
+pub use utils::{
+    filetime_to_iso8601, filetime_to_iso8601_on_volume, get_current_filetime,
+    local_time_offset_for, volume_filesystem,
+};
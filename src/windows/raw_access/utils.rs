@@ -1,8 +1,25 @@
-use winapi::shared::minwindef::FILETIME;
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use widestring::{U16CStr, U16CString};
+use winapi::shared::minwindef::{DWORD, FILETIME, MAX_PATH};
+use winapi::um::fileapi::{GetVolumeInformationW, GetVolumePathNameW};
 use winapi::um::minwinbase::SYSTEMTIME;
 use winapi::um::sysinfoapi::GetSystemTimeAsFileTime;
 use winapi::um::timezoneapi::FileTimeToSystemTime;
 
+use crate::utils::timezone::{
+    fat_utc_to_recorded_local, filetime_ticks_to_utc, host_timezone, is_local_time_filesystem,
+};
+
+lazy_static! {
+    /// File system name per volume root, so each volume is queried once
+    static ref VOLUME_FILESYSTEMS: Mutex<HashMap<String, Option<String>>> =
+        Mutex::new(HashMap::new());
+}
+
 /// Convert Windows FILETIME to ISO-8601 string
 pub fn filetime_to_iso8601(ft: &FILETIME) -> String {
     let mut system_time = SYSTEMTIME {
@@ -53,6 +70,87 @@ pub fn filetime_to_iso8601(ft: &FILETIME) -> String {
     dt.to_rfc3339()
 }
 
+/// Convert a FILETIME read from a file on a volume.
+///
+/// FAT and exFAT store local time; Windows converts it to UTC with the
+/// host's current bias when the file is read. For such volumes
+/// `local_time_offset` is the recorded host offset in minutes, and the
+/// timestamp is rendered in that offset so the original wall-clock value is
+/// preserved instead of being presented as UTC.
+pub fn filetime_to_iso8601_on_volume(ft: &FILETIME, local_time_offset: Option<i32>) -> String {
+    let offset = match local_time_offset {
+        Some(offset) => offset,
+        None => return filetime_to_iso8601(ft),
+    };
+
+    let ticks = ((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64;
+    match filetime_ticks_to_utc(ticks) {
+        Some(utc) => fat_utc_to_recorded_local(utc, offset),
+        None => filetime_to_iso8601(ft),
+    }
+}
+
+/// Recorded host UTC offset if `path` is on a local-time (FAT family) volume
+pub fn local_time_offset_for(path: &str) -> Option<i32> {
+    volume_filesystem(path)
+        .filter(|fs_name| is_local_time_filesystem(fs_name))
+        .map(|_| host_timezone().utc_offset_minutes)
+}
+
+/// File system name (`NTFS`, `FAT32`, `exFAT`, ...) of the volume holding `path`
+pub fn volume_filesystem(path: &str) -> Option<String> {
+    let wide_path = U16CString::from_str(path).ok()?;
+    let mut volume_root = [0u16; MAX_PATH + 1];
+
+    // SAFETY: both buffers are valid for the lengths passed
+    let ok = unsafe {
+        GetVolumePathNameW(
+            wide_path.as_ptr(),
+            volume_root.as_mut_ptr(),
+            volume_root.len() as DWORD,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    let root = U16CStr::from_slice_truncate(&volume_root)
+        .ok()?
+        .to_string_lossy();
+
+    if let Ok(cache) = VOLUME_FILESYSTEMS.lock() {
+        if let Some(fs_name) = cache.get(&root) {
+            return fs_name.clone();
+        }
+    }
+
+    let mut fs_name_buffer = [0u16; MAX_PATH + 1];
+    // SAFETY: the root is NUL-terminated and the name buffer length is passed
+    let ok = unsafe {
+        GetVolumeInformationW(
+            volume_root.as_ptr(),
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            fs_name_buffer.as_mut_ptr(),
+            fs_name_buffer.len() as DWORD,
+        )
+    };
+    let fs_name = if ok != 0 {
+        U16CStr::from_slice_truncate(&fs_name_buffer)
+            .ok()
+            .map(|name| name.to_string_lossy())
+    } else {
+        None
+    };
+
+    if let Ok(mut cache) = VOLUME_FILESYSTEMS.lock() {
+        cache.insert(root, fs_name.clone());
+    }
+    fs_name
+}
+
 /// Get current time as FILETIME
 pub fn get_current_filetime() -> FILETIME {
     let mut ft = FILETIME {
@@ -0,0 +1,332 @@
+//! Volume Shadow Copy fallback for locked files.
+//!
+//! Files another process holds open without read sharing (registry hives,
+//! `NTDS.dit`, some databases) fail to open with `ERROR_SHARING_VIOLATION`
+//! or `ERROR_ACCESS_DENIED` even with backup semantics. Their contents can
+//! still be read from a shadow copy of the volume. The first locked file on
+//! a volume creates one client-accessible shadow copy of it, which later
+//! locked files on that volume reuse; [`release_shadow_copies`] deletes the
+//! copies this run created once collection is done.
+//!
+//! Creating a shadow copy is visible to EDR products, so the fallback can be
+//! turned off with `--disable-vss`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+#[cfg(target_os = "windows")]
+use anyhow::{anyhow, Context};
+#[cfg(target_os = "windows")]
+use log::{info, warn};
+use std::path::Path;
+
+use crate::models::ArtifactMetadata;
+
+/// `fallback_method` of files read from a shadow copy
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub const FALLBACK_METHOD: &str = "vss";
+
+/// `ERROR_ACCESS_DENIED`
+const ERROR_ACCESS_DENIED: i32 = 5;
+/// `ERROR_SHARING_VIOLATION`
+const ERROR_SHARING_VIOLATION: i32 = 32;
+/// `ERROR_LOCK_VIOLATION`
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+/// Whether locked files may be read from a shadow copy (`--disable-vss`)
+static VSS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid the shadow copy fallback
+pub fn set_enabled(enabled: bool) {
+    VSS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the shadow copy fallback is allowed
+pub fn is_enabled() -> bool {
+    VSS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// A shadow copy created by this run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowCopy {
+    /// Shadow copy ID, `{GUID}`
+    pub id: String,
+    /// Device path, `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopyN`
+    pub device: String,
+}
+
+/// Whether an open failed because another process holds the file
+pub fn is_lock_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.raw_os_error())
+            .is_some_and(|code| {
+                matches!(
+                    code,
+                    ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION
+                )
+            })
+    })
+}
+
+/// Drive of a local path (`C:`), the volume a shadow copy is taken of
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn volume_of(source_path: &str) -> Option<String> {
+    let path = source_path.strip_prefix(r"\\?\").unwrap_or(source_path);
+    let bytes = path.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        .then(|| path[..2].to_ascii_uppercase())
+}
+
+/// `source_path` inside the shadow copy `device`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn shadow_path(device: &str, source_path: &str) -> Option<String> {
+    volume_of(source_path)?;
+    let path = source_path.strip_prefix(r"\\?\").unwrap_or(source_path);
+    let rest = path[2..].trim_start_matches(['\\', '/']).replace('/', "\\");
+    Some(format!(r"{}\{}", device.trim_end_matches('\\'), rest))
+}
+
+/// Parse the `ID|DeviceObject` line written by the creation script
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_created_shadow(output: &str) -> Option<ShadowCopy> {
+    output.lines().find_map(|line| {
+        let (id, device) = line.trim().split_once('|')?;
+        (id.starts_with('{') && device.starts_with(r"\\?\GLOBALROOT\")).then(|| ShadowCopy {
+            id: id.to_string(),
+            device: device.to_string(),
+        })
+    })
+}
+
+#[cfg(target_os = "windows")]
+lazy_static::lazy_static! {
+    /// Shadow copies created by this run, or why creation failed, by volume
+    static ref SHADOW_COPIES: std::sync::Mutex<std::collections::HashMap<String, std::result::Result<ShadowCopy, String>>> =
+        std::sync::Mutex::new(std::collections::HashMap::new());
+}
+
+/// Create a client-accessible shadow copy of `volume` (`C:`)
+#[cfg(target_os = "windows")]
+fn create_shadow_copy(volume: &str) -> Result<ShadowCopy> {
+    use std::process::Command;
+
+    use crate::collectors::footprint::run_command;
+
+    let script = format!(
+        "$r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+         -Arguments @{{Volume='{}\\'; Context='ClientAccessible'}}; \
+         if ($r.ReturnValue -ne 0) {{ Write-Error \"Win32_ShadowCopy.Create returned $($r.ReturnValue)\"; exit 1 }}; \
+         $s = Get-CimInstance Win32_ShadowCopy | Where-Object ID -eq $r.ShadowID; \
+         \"$($s.ID)|$($s.DeviceObject)\"",
+        volume
+    );
+    let output = run_command(Command::new("powershell.exe").args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        &script,
+    ]))
+    .context("Failed to run powershell.exe")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "shadow copy creation failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    parse_created_shadow(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("shadow copy was created but its device was not reported"))
+}
+
+/// The shadow copy of `volume`, created on first use
+#[cfg(target_os = "windows")]
+fn shadow_copy_for(volume: &str) -> Result<ShadowCopy> {
+    let mut copies = SHADOW_COPIES
+        .lock()
+        .map_err(|_| anyhow!("shadow copy registry is poisoned"))?;
+    let entry = copies.entry(volume.to_string()).or_insert_with(|| {
+        info!("Creating a shadow copy of {} to read locked files", volume);
+        create_shadow_copy(volume).map_err(|e| format!("{:#}", e))
+    });
+    entry
+        .clone()
+        .map_err(|reason| anyhow!("No shadow copy of {}: {}", volume, reason))
+}
+
+/// Copy `source_path` from a shadow copy of its volume to `dest`.
+///
+/// The metadata names the live path and has `fallback_method: "vss"`.
+#[cfg(target_os = "windows")]
+pub fn read_file_from_shadow_copy(source_path: &str, dest: &Path) -> Result<ArtifactMetadata> {
+    let volume = volume_of(source_path)
+        .ok_or_else(|| anyhow!("{} is not on a lettered volume", source_path))?;
+    let shadow = shadow_copy_for(&volume)?;
+    let path = shadow_path(&shadow.device, source_path)
+        .ok_or_else(|| anyhow!("{} cannot be mapped into {}", source_path, shadow.device))?;
+
+    info!("Reading locked {} from {}", source_path, shadow.device);
+    let mut metadata = crate::windows::collect_with_raw_handle(&path, dest)?;
+    metadata.original_path = source_path.to_string();
+    metadata.is_locked = true;
+    metadata.fallback_method = Some(FALLBACK_METHOD.to_string());
+    Ok(metadata)
+}
+
+/// Shadow copies only exist on Windows
+#[cfg(not(target_os = "windows"))]
+pub fn read_file_from_shadow_copy(source_path: &str, _dest: &Path) -> Result<ArtifactMetadata> {
+    Err(anyhow::anyhow!(
+        "Cannot read {} from a shadow copy: Volume Shadow Copy is only available on Windows",
+        source_path
+    ))
+}
+
+/// `source_path` inside the shadow copy this run already made of its
+/// volume, for reading a locked file again
+#[cfg(target_os = "windows")]
+pub fn existing_shadow_path(source_path: &str) -> Option<String> {
+    let volume = volume_of(source_path)?;
+    let copies = SHADOW_COPIES.lock().ok()?;
+    let shadow = copies.get(&volume)?.as_ref().ok()?;
+    shadow_path(&shadow.device, source_path)
+}
+
+/// Shadow copies only exist on Windows
+#[cfg(not(target_os = "windows"))]
+pub fn existing_shadow_path(_source_path: &str) -> Option<String> {
+    None
+}
+
+/// Retry a failed open of `source_path` from a shadow copy when the file
+/// was locked and the fallback is enabled; other results are returned as
+/// they are
+pub fn fall_back_on_lock(
+    result: Result<ArtifactMetadata>,
+    source_path: &str,
+    dest: &Path,
+) -> Result<ArtifactMetadata> {
+    match result {
+        Err(e) if is_enabled() && is_lock_error(&e) => {
+            read_file_from_shadow_copy(source_path, dest).map_err(|vss_error| {
+                e.context(format!("Shadow copy fallback failed: {:#}", vss_error))
+            })
+        }
+        result => result,
+    }
+}
+
+/// Delete the shadow copies this run created
+#[cfg(target_os = "windows")]
+pub fn release_shadow_copies() {
+    use std::process::Command;
+
+    use crate::collectors::footprint::run_command;
+
+    let Ok(mut copies) = SHADOW_COPIES.lock() else {
+        return;
+    };
+    for (volume, copy) in copies.drain() {
+        let Ok(copy) = copy else {
+            continue;
+        };
+        let shadow_arg = format!("/Shadow={}", copy.id);
+        match run_command(Command::new("vssadmin.exe").args([
+            "delete",
+            "shadows",
+            &shadow_arg,
+            "/Quiet",
+        ])) {
+            Ok(output) if output.status.success() => {
+                info!("Deleted shadow copy {} of {}", copy.id, volume)
+            }
+            Ok(output) => warn!(
+                "Failed to delete shadow copy {} of {}: {}",
+                copy.id,
+                volume,
+                String::from_utf8_lossy(&output.stdout).trim()
+            ),
+            Err(e) => warn!(
+                "Failed to run vssadmin to delete shadow copy {}: {}",
+                copy.id, e
+            ),
+        }
+    }
+}
+
+/// Shadow copies only exist on Windows
+#[cfg(not(target_os = "windows"))]
+pub fn release_shadow_copies() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_is_lock_error() {
+        let sharing = anyhow::Error::new(io::Error::from_raw_os_error(ERROR_SHARING_VIOLATION))
+            .context("Failed to open file with backup semantics");
+        assert!(is_lock_error(&sharing));
+        assert!(is_lock_error(&anyhow::Error::new(
+            io::Error::from_raw_os_error(ERROR_ACCESS_DENIED)
+        )));
+
+        // ERROR_FILE_NOT_FOUND, and errors without an OS code
+        assert!(!is_lock_error(&anyhow::Error::new(
+            io::Error::from_raw_os_error(2)
+        )));
+        assert!(!is_lock_error(&anyhow::anyhow!("sharing violation")));
+    }
+
+    #[test]
+    fn test_shadow_path() {
+        let device = r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3";
+        assert_eq!(volume_of(r"c:\Windows"), Some("C:".to_string()));
+        assert_eq!(
+            shadow_path(device, r"C:\Windows\System32\config\SAM").as_deref(),
+            Some(r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3\Windows\System32\config\SAM")
+        );
+        assert_eq!(
+            shadow_path(device, r"\\?\D:\Data/ntds.dit").as_deref(),
+            Some(r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3\Data\ntds.dit")
+        );
+        assert_eq!(shadow_path(device, r"\\server\share\file"), None);
+        assert_eq!(shadow_path(device, "/etc/shadow"), None);
+    }
+
+    #[test]
+    fn test_parse_created_shadow() {
+        let output = "\r\n{3E9B1E2A-6C1D-4F57-9A0B-2C7D8E9F0A1B}|\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy7\r\n";
+        assert_eq!(
+            parse_created_shadow(output),
+            Some(ShadowCopy {
+                id: "{3E9B1E2A-6C1D-4F57-9A0B-2C7D8E9F0A1B}".to_string(),
+                device: r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy7".to_string(),
+            })
+        );
+        assert_eq!(parse_created_shadow("|"), None);
+    }
+
+    #[test]
+    fn test_fallback_only_for_lock_errors() {
+        let dest = Path::new("unused");
+        let not_found = fall_back_on_lock(
+            Err(anyhow::Error::new(io::Error::from_raw_os_error(2))),
+            r"C:\missing",
+            dest,
+        );
+        assert!(!format!("{:#}", not_found.unwrap_err()).contains("Shadow copy"));
+
+        set_enabled(false);
+        let disabled = fall_back_on_lock(
+            Err(anyhow::Error::new(io::Error::from_raw_os_error(
+                ERROR_SHARING_VIOLATION,
+            ))),
+            r"C:\pagefile.sys",
+            dest,
+        );
+        set_enabled(true);
+        assert!(!format!("{:#}", disabled.unwrap_err()).contains("Shadow copy"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Integration tests for the HTTP control API (`--serve`).
+//!
+//! These start the collector binary in serve mode and drive a small
+//! collection through the API against temporary directories.
+
+#![cfg(feature = "control_api")]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+const TOKEN: &str = "integration-test-token";
+
+/// Collector running in serve mode, killed on drop
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start(work_dir: &Path) -> Self {
+        let token_file = work_dir.join("token");
+        fs::write(&token_file, format!("{}\n", TOKEN)).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust_collector"))
+            .args(["--serve", "127.0.0.1:0", "--serve-token-file"])
+            .arg(&token_file)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start collector");
+
+        // The bound address is only known from the startup log line
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let addr = loop {
+            let line = lines
+                .next()
+                .expect("collector exited before listening")
+                .unwrap();
+            if let Some((_, addr)) = line.split_once("Control API listening on ") {
+                break addr.trim().to_string();
+            }
+        };
+        // Keep draining the log so the collector never blocks on a full pipe
+        thread::spawn(move || lines.for_each(drop));
+
+        Self { child, addr }
+    }
+
+    fn request(&self, method: &str, path: &str, token: Option<&str>, body: &Value) -> (u16, Value) {
+        let body = body.to_string();
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            self.addr,
+            body.len()
+        );
+        if let Some(token) = token {
+            request.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        request.push_str("\r\n");
+        request.push_str(&body);
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn get(&self, path: &str) -> (u16, Value) {
+        self.request("GET", path, Some(TOKEN), &Value::Null)
+    }
+
+    fn post(&self, path: &str, body: &Value) -> (u16, Value) {
+        self.request("POST", path, Some(TOKEN), body)
+    }
+
+    /// Poll a collection until it leaves the running state
+    fn wait_for(&self, id: &str) -> Value {
+        let deadline = Instant::now() + Duration::from_secs(120);
+        loop {
+            let (status, body) = self.get(&format!("/collections/{}", id));
+            assert_eq!(status, 200, "{}", body);
+            if body["state"] != "running" {
+                return body;
+            }
+            assert!(Instant::now() < deadline, "collection did not finish");
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Config with one file artifact, and the options for collecting it
+fn collection_options(work_dir: &Path) -> Value {
+    let source = work_dir.join("evidence.txt");
+    fs::write(&source, "collected through the control API").unwrap();
+
+    let config = work_dir.join("config.yaml");
+    fs::write(
+        &config,
+        format!(
+            r#"version: "1.1"
+description: "Control API test"
+artifacts:
+  - name: "evidence"
+    artifact_type: FileSystem
+    source_path: "{}"
+    destination_name: "evidence.txt"
+    required: true
+"#,
+            source.display()
+        ),
+    )
+    .unwrap();
+
+    json!({
+        "config": config,
+        "output": work_dir.join("output"),
+        "skip_upload": true,
+        "no_volatile_data": true,
+        "force": true,
+    })
+}
+
+#[test]
+fn test_collection_through_control_api() {
+    let work_dir = TempDir::new().unwrap();
+    let server = Server::start(work_dir.path());
+    let options = collection_options(work_dir.path());
+
+    // Unauthenticated and invalid requests are rejected
+    let (status, _) = server.request("POST", "/collections", None, &options);
+    assert_eq!(status, 401);
+    let (status, _) = server.request("POST", "/collections", Some("wrong"), &options);
+    assert_eq!(status, 401);
+    let (status, body) = server.post("/collections", &json!({ "watch": true }));
+    assert_eq!(status, 400, "{}", body);
+    let bad_config = work_dir.path().join("bad.yaml");
+    fs::write(&bad_config, "artifacts: [not: valid").unwrap();
+    let (status, _) = server.post("/collections", &json!({ "config": bad_config }));
+    assert_eq!(status, 400);
+
+    let (status, started) = server.post("/collections", &options);
+    assert_eq!(status, 202, "{}", started);
+    let id = started["id"].as_str().unwrap().to_string();
+
+    let finished = server.wait_for(&id);
+    assert_eq!(finished["state"], "completed", "{}", finished);
+    assert!(finished["completed_phases"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("file_artifacts")));
+
+    let (status, summary) = server.get(&format!("/collections/{}/summary", id));
+    assert_eq!(status, 200, "{}", summary);
+    let artifacts = summary["artifacts"].as_array().unwrap();
+    assert!(artifacts.iter().any(|a| a["original_path"]
+        .as_str()
+        .unwrap()
+        .ends_with("evidence.txt")));
+
+    // Finished collections can't be cancelled; unknown IDs are 404
+    let (status, _) = server.post(&format!("/collections/{}/cancel", id), &Value::Null);
+    assert_eq!(status, 409);
+    let (status, _) = server.get("/collections/no-such-id");
+    assert_eq!(status, 404);
+}
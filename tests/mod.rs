@@ -6,6 +6,7 @@
 mod basic_collection;
 mod cloud_upload_tests;
 mod compression_tests;
+mod control_api_tests;
 mod memory_collection_tests;
 mod streaming_tests;
 // Temporarily disabled due to API changes